@@ -672,6 +672,14 @@ impl fmt::Display for DerivationPath {
     }
 }
 
+/// Derivation paths are public: they say which key, not what it is.
+#[cfg(feature = "tracing")]
+impl khodpay_tracing::LogSafe for DerivationPath {
+    fn log_value(&self) -> impl tracing::Value + '_ {
+        tracing::field::display(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
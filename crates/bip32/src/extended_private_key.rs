@@ -180,6 +180,9 @@ impl ExtendedPrivateKey {
     /// # Ok::<(), khodpay_bip32::Error>(())
     /// ```
     pub fn from_seed(seed: &[u8], network: Network) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!("master_key", network = network);
+
         // Validate seed length (BIP-32 recommends 128-512 bits = 16-64 bytes)
         if seed.len() < 16 || seed.len() > 64 {
             return Err(Error::InvalidSeedLength { length: seed.len() });
@@ -626,6 +629,10 @@ impl ExtendedPrivateKey {
     /// # Ok::<(), khodpay_bip32::Error>(())
     /// ```
     pub fn derive_path(&self, path: &crate::DerivationPath) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _operation =
+            khodpay_tracing::operation!("derive_path", path = path, network = self.network);
+
         // Start with current key
         let mut current = self.clone();
 
@@ -390,6 +390,10 @@ impl ExtendedPublicKey {
     /// # Ok::<(), khodpay_bip32::Error>(())
     /// ```
     pub fn derive_path(&self, path: &crate::DerivationPath) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _operation =
+            khodpay_tracing::operation!("derive_public_path", path = path, network = self.network);
+
         // Start with current key
         let mut current = self.clone();
 
@@ -329,6 +329,13 @@ impl std::fmt::Display for Network {
     }
 }
 
+#[cfg(feature = "tracing")]
+impl khodpay_tracing::LogSafe for Network {
+    fn log_value(&self) -> impl tracing::Value + '_ {
+        self.name()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
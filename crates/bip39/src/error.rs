@@ -209,6 +209,7 @@ impl PartialEq for Error {
 impl Eq for Error {}
 
 /// Convert from `rand::Error` to our `Error` type.
+#[cfg(feature = "rand")]
 impl From<rand::Error> for Error {
     fn from(_error: rand::Error) -> Self {
         Error::RandomGeneration
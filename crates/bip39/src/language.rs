@@ -157,6 +157,62 @@ impl Language {
         }
     }
 
+    /// Returns the 2048-word BIP39 word list for this language.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use khodpay_bip39::Language;
+    /// let words = Language::English.word_list();
+    /// assert_eq!(words[0], "abandon");
+    /// assert_eq!(words[2047], "zoo");
+    /// ```
+    pub fn word_list(self) -> &'static [&'static str; 2048] {
+        self.to_upstream().word_list()
+    }
+
+    /// Returns the index of `word` in this language's word list, or `None` if it is
+    /// not a BIP39 word.
+    ///
+    /// This allows a recovery screen to validate each word as it is typed; the
+    /// checksum can only be checked once the full phrase is entered, with
+    /// [`validate_phrase_in_language`](crate::validate_phrase_in_language).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use khodpay_bip39::Language;
+    /// assert_eq!(Language::English.find_word("abandon"), Some(0));
+    /// assert_eq!(Language::English.find_word("zoo"), Some(2047));
+    /// assert_eq!(Language::English.find_word("bitcoin"), None);
+    /// ```
+    pub fn find_word(self, word: &str) -> Option<u16> {
+        self.to_upstream().find_word(word)
+    }
+
+    /// Returns the words of this language's word list starting with `prefix`, in
+    /// word list order.
+    ///
+    /// An empty prefix returns the whole list. Since BIP39 words are unique in their
+    /// first four letters, a four-letter prefix matches at most one English word.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use khodpay_bip39::Language;
+    /// assert_eq!(Language::English.words_by_prefix("abs"), ["absent", "absorb", "abstract", "absurd"]);
+    /// assert_eq!(Language::English.words_by_prefix("zoo"), ["zoo"]);
+    /// assert!(Language::English.words_by_prefix("xyz").is_empty());
+    /// ```
+    pub fn words_by_prefix(self, prefix: &str) -> Vec<&'static str> {
+        // Not every word list is sorted, so matches are not always contiguous
+        self.word_list()
+            .iter()
+            .copied()
+            .filter(|w| w.starts_with(prefix))
+            .collect()
+    }
+
     /// Converts our Language enum to the upstream crate's Language type.
     ///
     /// This is an internal conversion method used to interface with the
@@ -281,4 +337,34 @@ mod tests {
         let debug_output = format!("{:?}", Language::English);
         assert!(debug_output.contains("English"));
     }
+
+    #[test]
+    fn test_word_list_and_find_word() {
+        for &language in Language::all_variants() {
+            let words = language.word_list();
+            assert_eq!(words.len(), 2048);
+            for index in [0usize, 1, 1024, 2047] {
+                assert_eq!(language.find_word(words[index]), Some(index as u16));
+            }
+        }
+        assert_eq!(Language::English.find_word("Abandon"), None);
+        assert_eq!(Language::English.find_word(""), None);
+        assert_eq!(Language::Japanese.find_word("abandon"), None);
+    }
+
+    #[test]
+    fn test_words_by_prefix() {
+        assert_eq!(Language::English.words_by_prefix("").len(), 2048);
+        assert_eq!(Language::English.words_by_prefix("zo"), ["zone", "zoo"]);
+        assert_eq!(Language::English.words_by_prefix("aban"), ["abandon"]);
+        assert!(Language::English.words_by_prefix("abandonx").is_empty());
+
+        for &language in Language::all_variants() {
+            let words = language.word_list();
+            let prefix: String = words[100].chars().take(2).collect();
+            let matches = language.words_by_prefix(&prefix);
+            assert!(matches.contains(&words[100]));
+            assert!(matches.iter().all(|w| w.starts_with(&prefix)));
+        }
+    }
 }
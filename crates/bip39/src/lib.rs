@@ -49,6 +49,7 @@
 //! - [`phrase()`](Mnemonic::phrase) - Get the mnemonic phrase as a string
 //! - [`entropy()`](Mnemonic::entropy) - Get the entropy bytes
 //! - [`word_count()`](Mnemonic::word_count) - Get the word count
+//! - [`language()`](Mnemonic::language) - Get the word list language
 //! - [`to_seed(passphrase)`](Mnemonic::to_seed) - Generate cryptographic seed
 //!
 //! ### [`WordCount`]
@@ -59,6 +60,17 @@
 //!
 //! Enum for supported languages (English, Japanese, Korean, Spanish, French, Italian, Czech, Portuguese, Chinese Simplified).
 //!
+//! **Word lists:**
+//! - [`word_list()`](Language::word_list) - The 2048-word list
+//! - [`find_word(word)`](Language::find_word) - Validate a single word and get its index
+//! - [`words_by_prefix(prefix)`](Language::words_by_prefix) - Autocomplete candidates
+//!
+//! ### [`seedqr`]
+//!
+//! SeedQR payloads for SeedSigner-compatible devices:
+//! [`encode_standard`](seedqr::encode_standard) / [`encode_compact`](seedqr::encode_compact)
+//! to export, [`decode`](seedqr::decode) to import a scanned QR code of either format.
+//!
 //! ### [`Error`]
 //!
 //! Comprehensive error type for all BIP39 operations.
@@ -136,7 +148,7 @@
 //! 1. **Entropy Generation**: Uses system CSPRNG (`rand::thread_rng()`)
 //! 2. **Mnemonic Storage**: Never store mnemonics in plain text
 //! 3. **Passphrase Security**: Passphrases add a "25th word" for additional security
-//! 4. **Memory Safety**: Consider zeroing sensitive data after use
+//! 4. **Memory Safety**: `Mnemonic` zeroizes its phrase and entropy on drop; zero seeds after use
 //!
 //! ### Best Practices
 //!
@@ -176,6 +188,7 @@
 mod error;
 mod language;
 mod mnemonic;
+pub mod seedqr;
 mod utils;
 mod word_count;
 
@@ -183,8 +196,9 @@ mod word_count;
 pub use error::{Error, Result};
 pub use language::Language;
 pub use mnemonic::Mnemonic;
+#[cfg(feature = "rand")]
+pub use utils::{generate_mnemonic, generate_mnemonic_in_language};
 pub use utils::{
-    generate_mnemonic, generate_mnemonic_in_language, phrase_to_seed, phrase_to_seed_in_language,
-    validate_phrase, validate_phrase_in_language,
+    phrase_to_seed, phrase_to_seed_in_language, validate_phrase, validate_phrase_in_language,
 };
 pub use word_count::WordCount;
@@ -25,6 +25,7 @@
 //! ```
 
 use crate::{Language, WordCount};
+use zeroize::Zeroize;
 
 /// A BIP39 mnemonic phrase with associated metadata.
 ///
@@ -47,6 +48,11 @@ use crate::{Language, WordCount};
 /// - The word count corresponds to the entropy length
 /// - All words are from the specified language's wordlist
 ///
+/// # Memory Safety
+///
+/// The phrase and entropy are zeroized when a `Mnemonic` is dropped, so
+/// callers that need deterministic cleanup can simply `drop()` it.
+///
 /// # Construction
 ///
 /// Mnemonics can be created through several constructors:
@@ -64,7 +70,7 @@ use crate::{Language, WordCount};
 /// // let mnemonic = Mnemonic::new(&entropy, Language::English).unwrap();
 /// // assert_eq!(mnemonic.word_count(), WordCount::Twelve);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Mnemonic {
     /// The mnemonic phrase as a space-separated string.
     /// Contains 12, 15, 18, 21, or 24 words from the specified language's wordlist.
@@ -172,6 +178,20 @@ impl Mnemonic {
         self.word_count
     }
 
+    /// Returns the language of this mnemonic's word list.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_bip39::{Mnemonic, Language};
+    ///
+    /// let mnemonic = Mnemonic::new(&[0u8; 16], Language::Spanish).unwrap();
+    /// assert_eq!(mnemonic.language(), Language::Spanish);
+    /// ```
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
     /// Returns the mnemonic phrase as a string slice.
     ///
     /// # Examples
@@ -368,6 +388,7 @@ impl Mnemonic {
     /// let mnemonic_24 = Mnemonic::generate(WordCount::TwentyFour, Language::Japanese).unwrap();
     /// assert_eq!(mnemonic_24.word_count(), WordCount::TwentyFour);
     /// ```
+    #[cfg(feature = "rand")]
     pub fn generate(word_count: WordCount, language: Language) -> crate::Result<Self> {
         use rand::rngs::OsRng;
         use rand::RngCore;
@@ -382,7 +403,34 @@ impl Mnemonic {
 
         // Step 3: Use the `new()` constructor to create the Mnemonic
         // This handles entropy validation, checksum calculation, and phrase generation
-        Self::new(&entropy, language)
+        let mnemonic = Self::new(&entropy, language);
+        entropy.zeroize();
+        mnemonic
+    }
+}
+
+impl std::fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mnemonic")
+            .field("language", &self.language)
+            .field("word_count", &self.word_count)
+            .field("phrase", &"[REDACTED]")
+            .field("entropy", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl Mnemonic {
+    /// Overwrites the phrase and entropy with zeros and empties them.
+    fn wipe(&mut self) {
+        self.phrase.zeroize();
+        self.entropy.zeroize();
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.wipe();
     }
 }
 
@@ -1233,4 +1281,49 @@ mod tests {
         assert_ne!(seed1, seed3);
         assert_ne!(seed2, seed3);
     }
+
+    #[test]
+    fn test_mnemonic_wipe_zeroizes() {
+        let mut mnemonic = Mnemonic::new(&[0x42u8; 16], Language::English).unwrap();
+        let clone = mnemonic.clone();
+
+        mnemonic.wipe();
+        assert!(mnemonic.phrase.is_empty());
+        assert!(mnemonic.entropy.is_empty());
+
+        // The allocations themselves are overwritten, not just truncated
+        let phrase = mnemonic.phrase.as_ptr();
+        let phrase_capacity = mnemonic.phrase.capacity();
+        let entropy = mnemonic.entropy.as_ptr();
+        let entropy_capacity = mnemonic.entropy.capacity();
+        assert!(phrase_capacity > 0 && entropy_capacity >= 16);
+        // SAFETY: both buffers are still allocated (`mnemonic` is alive) and `zeroize`
+        // initialized their whole capacity.
+        let (phrase, entropy) = unsafe {
+            (
+                std::slice::from_raw_parts(phrase, phrase_capacity),
+                std::slice::from_raw_parts(entropy, entropy_capacity),
+            )
+        };
+        assert!(phrase.iter().all(|&b| b == 0));
+        assert!(entropy.iter().all(|&b| b == 0));
+
+        // Wiping one copy leaves the other intact
+        assert_eq!(clone.entropy(), &[0x42u8; 16]);
+        assert_eq!(clone.phrase().split_whitespace().count(), 12);
+    }
+
+    #[test]
+    fn test_mnemonic_debug_redacted() {
+        let mnemonic = Mnemonic::new(&[0x42u8; 16], Language::English).unwrap();
+        let debug_output = format!("{:?}", mnemonic);
+
+        assert!(debug_output.contains("Mnemonic"));
+        assert!(debug_output.contains("English"));
+        assert!(debug_output.contains("[REDACTED]"));
+        for word in mnemonic.phrase().split_whitespace() {
+            assert!(!debug_output.contains(word));
+        }
+        assert!(!debug_output.contains("66"));
+    }
 }
@@ -0,0 +1,289 @@
+//! SeedQR: mnemonics as QR codes, compatible with SeedSigner.
+//!
+//! [SeedQR](https://github.com/SeedSigner/seedsigner/blob/dev/docs/seed_qr/README.md)
+//! has two formats for 12 and 24-word English mnemonics:
+//!
+//! - **Standard**: each word's index as four decimal digits, 48 or 96 digits in
+//!   all, for a numeric-mode QR code. Readable by eye, if need be.
+//! - **Compact**: the raw entropy, 16 or 32 bytes, for a smaller binary-mode QR
+//!   code. The checksum word is recomputed when decoding.
+//!
+//! This module builds and parses the payloads; drawing the QR code is left to the
+//! caller's QR library. Payloads are seed material, so they are returned in
+//! [`Zeroizing`] buffers, and intermediate copies are wiped as well.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_bip39::{seedqr, Language, Mnemonic};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mnemonic = Mnemonic::from_phrase(phrase, Language::English)?;
+//!
+//! let digits = seedqr::encode_standard(&mnemonic)?;
+//! assert_eq!(digits.as_str(), "000000000000000000000000000000000000000000000003");
+//! assert_eq!(*seedqr::encode_compact(&mnemonic)?, [0u8; 16]);
+//!
+//! // Whatever the scanner returns, text or bytes
+//! assert_eq!(seedqr::decode(digits.as_bytes())?, mnemonic);
+//! assert_eq!(seedqr::decode(&[0u8; 16])?, mnemonic);
+//! # Ok::<(), khodpay_bip39::Error>(())
+//! ```
+
+use std::fmt::Write;
+
+use zeroize::Zeroizing;
+
+use crate::{Error, Language, Mnemonic, Result, WordCount};
+
+/// Digits per word in a standard SeedQR.
+const DIGITS_PER_WORD: usize = 4;
+
+/// The SeedQR payload formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeedQrFormat {
+    /// Four decimal digits per word index, for a numeric-mode QR code.
+    Standard,
+    /// The raw entropy, for a binary-mode QR code.
+    Compact,
+}
+
+/// Checks that `mnemonic` can be written as a SeedQR.
+fn check_mnemonic(mnemonic: &Mnemonic) -> Result<()> {
+    if mnemonic.language() != Language::English {
+        return Err(Error::InvalidMnemonic {
+            reason: "SeedQR only encodes English mnemonics".to_string(),
+        });
+    }
+    check_word_count(mnemonic.word_count().word_count())
+}
+
+/// SeedQR defines 12 and 24-word mnemonics only.
+fn check_word_count(count: usize) -> Result<()> {
+    match count {
+        12 | 24 => Ok(()),
+        count => Err(Error::InvalidWordCount { count }),
+    }
+}
+
+/// Returns the standard SeedQR payload: four digits per word index.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMnemonic`] for non-English mnemonics and
+/// [`Error::InvalidWordCount`] for word counts other than 12 and 24.
+pub fn encode_standard(mnemonic: &Mnemonic) -> Result<Zeroizing<String>> {
+    check_mnemonic(mnemonic)?;
+    // Sized up front so the buffer never reallocates and leaves a copy behind
+    let mut digits = Zeroizing::new(String::with_capacity(
+        mnemonic.word_count().word_count() * DIGITS_PER_WORD,
+    ));
+    for (position, word) in mnemonic.phrase().split(' ').enumerate() {
+        let index = Language::English
+            .find_word(word)
+            .ok_or_else(|| Error::InvalidWord {
+                word: word.to_string(),
+                position,
+            })?;
+        write!(digits, "{:04}", index).expect("writing to a String cannot fail");
+    }
+    Ok(digits)
+}
+
+/// Returns the compact SeedQR payload: the mnemonic's entropy.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMnemonic`] for non-English mnemonics and
+/// [`Error::InvalidWordCount`] for word counts other than 12 and 24.
+pub fn encode_compact(mnemonic: &Mnemonic) -> Result<Zeroizing<Vec<u8>>> {
+    check_mnemonic(mnemonic)?;
+    Ok(Zeroizing::new(mnemonic.entropy().to_vec()))
+}
+
+/// Returns the SeedQR payload in `format`, as QR code bytes.
+///
+/// # Errors
+///
+/// As [`encode_standard`] and [`encode_compact`].
+pub fn encode(mnemonic: &Mnemonic, format: SeedQrFormat) -> Result<Zeroizing<Vec<u8>>> {
+    match format {
+        SeedQrFormat::Standard => {
+            let mut digits = encode_standard(mnemonic)?;
+            Ok(Zeroizing::new(std::mem::take(&mut *digits).into_bytes()))
+        }
+        SeedQrFormat::Compact => encode_compact(mnemonic),
+    }
+}
+
+/// Parses a standard SeedQR payload.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMnemonic`] if the payload is not all digits or not a
+/// whole number of words, [`Error::InvalidWordCount`] for word counts other than 12
+/// and 24, [`Error::InvalidWord`] for indexes above 2047, and
+/// [`Error::InvalidChecksum`] if the last word's checksum does not match.
+pub fn decode_standard(digits: &str) -> Result<Mnemonic> {
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || digits.len() % DIGITS_PER_WORD != 0 {
+        return Err(Error::InvalidMnemonic {
+            reason: "standard SeedQR is four digits per word".to_string(),
+        });
+    }
+    check_word_count(digits.len() / DIGITS_PER_WORD)?;
+    let word_list = Language::English.word_list();
+    // The longest English word has eight letters; sized so the phrase never reallocates
+    let word_count = digits.len() / DIGITS_PER_WORD;
+    let mut phrase = Zeroizing::new(String::with_capacity(word_count * 9));
+    for (position, chunk) in digits.as_bytes().chunks(DIGITS_PER_WORD).enumerate() {
+        let index = chunk
+            .iter()
+            .fold(0usize, |index, digit| index * 10 + (digit - b'0') as usize);
+        let word = word_list.get(index).ok_or_else(|| Error::InvalidWord {
+            word: String::from_utf8_lossy(chunk).into_owned(),
+            position,
+        })?;
+        if position > 0 {
+            phrase.push(' ');
+        }
+        phrase.push_str(word);
+    }
+    Mnemonic::from_phrase(&phrase, Language::English)
+}
+
+/// Parses a compact SeedQR payload.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidEntropyLength`] unless the payload is 16 or 32 bytes.
+pub fn decode_compact(entropy: &[u8]) -> Result<Mnemonic> {
+    let word_count = WordCount::from_entropy_length(entropy.len())?;
+    if check_word_count(word_count.word_count()).is_err() {
+        return Err(Error::InvalidEntropyLength {
+            length: entropy.len(),
+        });
+    }
+    Mnemonic::new(entropy, Language::English)
+}
+
+/// Returns the format of a scanned SeedQR payload, or `None` if it is neither.
+///
+/// 48 or 96 ASCII digits are a standard SeedQR, 16 or 32 bytes a compact one; the
+/// lengths never overlap.
+pub fn detect(payload: &[u8]) -> Option<SeedQrFormat> {
+    match payload.len() {
+        48 | 96 if payload.iter().all(u8::is_ascii_digit) => Some(SeedQrFormat::Standard),
+        16 | 32 => Some(SeedQrFormat::Compact),
+        _ => None,
+    }
+}
+
+/// Parses a scanned SeedQR payload of either format.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMnemonic`] if the payload is neither format, and the
+/// errors of [`decode_standard`] for standard payloads.
+pub fn decode(payload: &[u8]) -> Result<Mnemonic> {
+    match detect(payload) {
+        Some(SeedQrFormat::Standard) => {
+            decode_standard(std::str::from_utf8(payload).expect("ASCII digits"))
+        }
+        Some(SeedQrFormat::Compact) => decode_compact(payload),
+        None => Err(Error::InvalidMnemonic {
+            reason: format!("{} bytes is not a SeedQR payload", payload.len()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 24-word example from the SeedQR specification.
+    const PHRASE_24: &str = "attack pizza motion avocado network gather crop fresh patrol unusual wild holiday candy pony ranch winter theme error hybrid van cereal salon goddess expire";
+    const STANDARD_24: &str = "011513251154012711900771041507421289190620080870026613431420201617920614089619290300152408010643";
+
+    #[test]
+    fn test_standard() {
+        let mnemonic = Mnemonic::from_phrase(PHRASE_24, Language::English).unwrap();
+        assert_eq!(encode_standard(&mnemonic).unwrap().as_str(), STANDARD_24);
+        assert_eq!(decode_standard(STANDARD_24).unwrap(), mnemonic);
+        assert_eq!(
+            *encode(&mnemonic, SeedQrFormat::Standard).unwrap(),
+            STANDARD_24.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        let mnemonic = Mnemonic::from_phrase(PHRASE_24, Language::English).unwrap();
+        let compact = encode_compact(&mnemonic).unwrap();
+        assert_eq!(compact.len(), 32);
+        assert_eq!(*compact, mnemonic.entropy());
+        assert_eq!(decode_compact(&compact).unwrap(), mnemonic);
+        assert_eq!(encode(&mnemonic, SeedQrFormat::Compact).unwrap(), compact);
+    }
+
+    #[test]
+    fn test_decode_detects_format() {
+        let mnemonic = Mnemonic::generate(WordCount::Twelve, Language::English).unwrap();
+        for format in [SeedQrFormat::Standard, SeedQrFormat::Compact] {
+            let payload = encode(&mnemonic, format).unwrap();
+            assert_eq!(detect(&payload), Some(format));
+            assert_eq!(decode(&payload).unwrap(), mnemonic);
+        }
+        assert_eq!(detect(b"not a seed"), None);
+        assert!(matches!(
+            decode(b"not a seed"),
+            Err(Error::InvalidMnemonic { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_mnemonics() {
+        let mnemonic = Mnemonic::generate(WordCount::Eighteen, Language::English).unwrap();
+        assert_eq!(
+            encode_standard(&mnemonic).unwrap_err(),
+            Error::InvalidWordCount { count: 18 }
+        );
+        assert_eq!(
+            decode_compact(mnemonic.entropy()),
+            Err(Error::InvalidEntropyLength { length: 24 })
+        );
+
+        let mnemonic = Mnemonic::generate(WordCount::Twelve, Language::Spanish).unwrap();
+        assert!(matches!(
+            encode_compact(&mnemonic),
+            Err(Error::InvalidMnemonic { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_standard() {
+        // Index 2048
+        let digits = format!("2048{}", &STANDARD_24[4..]);
+        assert_eq!(
+            decode_standard(&digits),
+            Err(Error::InvalidWord {
+                word: "2048".to_string(),
+                position: 0
+            })
+        );
+        // Last word swapped for another
+        let digits = format!("{}0000", &STANDARD_24[..92]);
+        assert_eq!(decode_standard(&digits), Err(Error::InvalidChecksum));
+        assert!(matches!(
+            decode_standard(&STANDARD_24[1..]),
+            Err(Error::InvalidMnemonic { .. })
+        ));
+        assert!(matches!(
+            decode_standard(&STANDARD_24.replace('0', "o")),
+            Err(Error::InvalidMnemonic { .. })
+        ));
+        assert_eq!(
+            decode_standard(&STANDARD_24[..64]),
+            Err(Error::InvalidWordCount { count: 16 })
+        );
+    }
+}
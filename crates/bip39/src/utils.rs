@@ -129,10 +129,7 @@ pub fn validate_phrase_in_language(phrase: &str, language: Language) -> Result<(
         let word_lower = word.to_lowercase();
 
         // Check if word is in the BIP39 word list for the specified language
-        let word_list = upstream_language.word_list();
-        let is_valid_word = word_list.iter().any(|&w| w == word_lower);
-
-        if !is_valid_word {
+        if language.find_word(&word_lower).is_none() {
             return Err(Error::InvalidWord {
                 word: word.to_string(),
                 position: index,
@@ -312,6 +309,7 @@ pub fn phrase_to_seed_in_language(
 /// let mnemonic_24 = generate_mnemonic(WordCount::TwentyFour).unwrap();
 /// assert_eq!(mnemonic_24.split_whitespace().count(), 24);
 /// ```
+#[cfg(feature = "rand")]
 pub fn generate_mnemonic(word_count: WordCount) -> Result<String> {
     generate_mnemonic_in_language(word_count, Language::English)
 }
@@ -350,6 +348,7 @@ pub fn generate_mnemonic(word_count: WordCount) -> Result<String> {
 /// let mnemonic_ja = generate_mnemonic_in_language(WordCount::TwentyFour, Language::Japanese).unwrap();
 /// assert_eq!(mnemonic_ja.split_whitespace().count(), 24);
 /// ```
+#[cfg(feature = "rand")]
 pub fn generate_mnemonic_in_language(word_count: WordCount, language: Language) -> Result<String> {
     use rand::rngs::OsRng;
     use rand::RngCore;
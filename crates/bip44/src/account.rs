@@ -43,7 +43,7 @@ use crate::{CoinType, Purpose, Result};
 use khodpay_bip32::ExtendedPrivateKey;
 
 #[cfg(feature = "serde")]
-mod network_serde {
+pub(crate) mod network_serde {
     use khodpay_bip32::Network;
     use serde::{Deserialize, Deserializer, Serializer};
 
@@ -0,0 +1,498 @@
+//! Encrypted wallet backup files.
+//!
+//! A [`Backup`] holds everything needed to restore a wallet: the mnemonic, the accounts
+//! in use and user labels. [`Backup::encrypt`] seals it into a single versioned file:
+//!
+//! ```text
+//! magic "KPWB" | version | Argon2id m_cost, t_cost, p_cost | salt (16) | nonce (24) | ciphertext + tag
+//! ```
+//!
+//! The key is derived from the password with Argon2id and the JSON payload is sealed
+//! with XChaCha20-Poly1305. The header is authenticated as associated data, so a
+//! changed version or KDF parameter fails decryption just like a changed payload.
+//!
+//! Requires the `backup` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_bip44::{AccountMetadata, Backup, BackupKdf, CoinType, Language, Purpose};
+//! use khodpay_bip32::Network;
+//!
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let backup = Backup::new(mnemonic, Language::English, Network::BitcoinMainnet)
+//!     .unwrap()
+//!     .with_account(AccountMetadata::new(Purpose::BIP84, CoinType::Bitcoin, 0, Network::BitcoinMainnet))
+//!     .with_label("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", "Savings");
+//!
+//! // Light parameters keep the example fast; use `Backup::encrypt` for real backups.
+//! let file = backup.encrypt_with("correct horse", BackupKdf::new(64, 1, 1)).unwrap();
+//!
+//! let restored = Backup::decrypt(&file, "correct horse").unwrap();
+//! assert_eq!(restored, backup);
+//! assert!(Backup::decrypt(&file, "wrong").is_err());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use khodpay_bip32::Network;
+use khodpay_bip39::{Language, Mnemonic};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroizing;
+
+use crate::account::network_serde;
+use crate::{AccountMetadata, Error, Result, Wallet};
+
+/// Magic bytes at the start of every backup file.
+pub const MAGIC: [u8; 4] = *b"KPWB";
+
+/// Current backup format version.
+pub const VERSION: u8 = 1;
+
+/// Largest accepted Argon2 memory cost in KiB (1 GiB).
+const MAX_M_COST: u32 = 1 << 20;
+
+/// Largest accepted Argon2 iteration count.
+const MAX_T_COST: u32 = 64;
+
+/// Largest accepted Argon2 parallelism.
+const MAX_P_COST: u32 = 16;
+
+pub(crate) const SALT_LEN: usize = 16;
+pub(crate) const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 12 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id parameters used to derive a backup's encryption key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupKdf {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl BackupKdf {
+    /// 64 MiB, 3 iterations, 1 lane.
+    pub const DEFAULT: Self = Self {
+        m_cost: 64 * 1024,
+        t_cost: 3,
+        p_cost: 1,
+    };
+
+    /// Creates parameters with memory cost `m_cost` in KiB, `t_cost` iterations and
+    /// `p_cost` lanes.
+    pub const fn new(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        Self {
+            m_cost,
+            t_cost,
+            p_cost,
+        }
+    }
+
+    /// Returns the memory cost in KiB.
+    pub const fn m_cost(&self) -> u32 {
+        self.m_cost
+    }
+
+    /// Returns the number of iterations.
+    pub const fn t_cost(&self) -> u32 {
+        self.t_cost
+    }
+
+    /// Returns the number of lanes.
+    pub const fn p_cost(&self) -> u32 {
+        self.p_cost
+    }
+
+    /// Derives the 32-byte encryption key.
+    pub(crate) fn derive(&self, password: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        if self.m_cost > MAX_M_COST || self.t_cost > MAX_T_COST || self.p_cost > MAX_P_COST {
+            return Err(Error::Backup(format!(
+                "Argon2 parameters exceed the limits (m_cost {}, t_cost {}, p_cost {})",
+                self.m_cost, self.t_cost, self.p_cost
+            )));
+        }
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| Error::Backup(format!("invalid Argon2 parameters: {}", e)))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), salt, key.as_mut())
+            .map_err(|e| Error::Backup(format!("key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+impl Default for BackupKdf {
+    /// Returns [`BackupKdf::DEFAULT`].
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The contents of an encrypted wallet backup.
+///
+/// The mnemonic is zeroized when the backup is dropped and redacted from `Debug` output.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    #[serde(
+        serialize_with = "serialize_mnemonic",
+        deserialize_with = "deserialize_mnemonic"
+    )]
+    mnemonic: Zeroizing<String>,
+    #[serde(
+        serialize_with = "serialize_language",
+        deserialize_with = "deserialize_language"
+    )]
+    language: Language,
+    #[serde(with = "network_serde")]
+    network: Network,
+    #[serde(default)]
+    accounts: Vec<AccountMetadata>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+impl Backup {
+    /// Creates a backup of a wallet's mnemonic.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidMnemonic`] if the phrase is not a valid mnemonic in
+    /// `language`.
+    pub fn new(mnemonic: &str, language: Language, network: Network) -> Result<Self> {
+        let parsed = Mnemonic::from_phrase(mnemonic, language)
+            .map_err(|e| Error::InvalidMnemonic(format!("Failed to parse mnemonic: {}", e)))?;
+        Ok(Self {
+            mnemonic: Zeroizing::new(parsed.phrase().to_string()),
+            language,
+            network,
+            accounts: Vec::new(),
+            labels: BTreeMap::new(),
+        })
+    }
+
+    /// Records an account in use, so a restore can re-create it without discovery.
+    pub fn with_account(mut self, account: AccountMetadata) -> Self {
+        if !self.accounts.contains(&account) {
+            self.accounts.push(account);
+        }
+        self
+    }
+
+    /// Sets a user label, keyed by address, transaction id or any other string.
+    pub fn with_label(mut self, key: impl Into<String>, label: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), label.into());
+        self
+    }
+
+    /// Returns the mnemonic phrase.
+    pub fn mnemonic(&self) -> &str {
+        &self.mnemonic
+    }
+
+    /// Returns the mnemonic language.
+    pub const fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Returns the wallet network.
+    pub const fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the recorded accounts.
+    pub fn accounts(&self) -> &[AccountMetadata] {
+        &self.accounts
+    }
+
+    /// Returns the labels.
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.labels
+    }
+
+    /// Restores the wallet with the BIP-39 `passphrase`, which is never stored in a
+    /// backup, and derives the recorded accounts into its cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the wallet or one of its accounts cannot be derived.
+    pub fn to_wallet(&self, passphrase: &str) -> Result<Wallet> {
+        let mut wallet =
+            Wallet::from_mnemonic(&self.mnemonic, passphrase, self.language, self.network)?;
+        for account in &self.accounts {
+            wallet.get_account(
+                account.purpose(),
+                account.coin_type(),
+                account.account_index(),
+            )?;
+        }
+        Ok(wallet)
+    }
+
+    /// Encrypts the backup under `password` with [`BackupKdf::DEFAULT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Backup`] if encryption fails.
+    pub fn encrypt(&self, password: &str) -> Result<Vec<u8>> {
+        self.encrypt_with(password, BackupKdf::DEFAULT)
+    }
+
+    /// Encrypts the backup under `password` with custom Argon2id parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Backup`] if the parameters are invalid or encryption fails.
+    pub fn encrypt_with(&self, password: &str, kdf: BackupKdf) -> Result<Vec<u8>> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut file = Vec::with_capacity(HEADER_LEN + 256);
+        file.extend_from_slice(&MAGIC);
+        file.push(VERSION);
+        file.extend_from_slice(&kdf.m_cost.to_be_bytes());
+        file.extend_from_slice(&kdf.t_cost.to_be_bytes());
+        file.extend_from_slice(&kdf.p_cost.to_be_bytes());
+        file.extend_from_slice(&salt);
+        file.extend_from_slice(&nonce);
+
+        let key = kdf.derive(password, &salt)?;
+        let plaintext =
+            Zeroizing::new(serde_json::to_vec(self).map_err(|e| Error::Backup(e.to_string()))?);
+        let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &file,
+                },
+            )
+            .map_err(|_| Error::Backup("encryption failed".to_string()))?;
+        file.extend_from_slice(&ciphertext);
+        Ok(file)
+    }
+
+    /// Decrypts a backup file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongPassword`] if authentication fails, and [`Error::Backup`] if
+    /// the file is not a backup, has an unsupported version or unacceptable parameters.
+    pub fn decrypt(data: &[u8], password: &str) -> Result<Self> {
+        if data.len() < HEADER_LEN || data[..MAGIC.len()] != MAGIC {
+            return Err(Error::Backup("not a wallet backup file".to_string()));
+        }
+        let version = data[MAGIC.len()];
+        if version != VERSION {
+            return Err(Error::Backup(format!(
+                "unsupported backup version: {}",
+                version
+            )));
+        }
+        let (header, ciphertext) = data.split_at(HEADER_LEN);
+        let word = |i: usize| {
+            let start = MAGIC.len() + 1 + 4 * i;
+            u32::from_be_bytes(header[start..start + 4].try_into().expect("four bytes"))
+        };
+        let kdf = BackupKdf::new(word(0), word(1), word(2));
+        let salt = &header[MAGIC.len() + 13..MAGIC.len() + 13 + SALT_LEN];
+        let nonce = &header[HEADER_LEN - NONCE_LEN..];
+
+        let key = kdf.derive(password, salt)?;
+        let plaintext = XChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| Error::WrongPassword)?;
+        serde_json::from_slice(&plaintext).map_err(|e| Error::Backup(e.to_string()))
+    }
+}
+
+impl fmt::Debug for Backup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backup")
+            .field("mnemonic", &"<redacted>")
+            .field("language", &self.language)
+            .field("network", &self.network)
+            .field("accounts", &self.accounts)
+            .field("labels", &self.labels)
+            .finish()
+    }
+}
+
+pub(crate) fn serialize_mnemonic<S: Serializer>(
+    mnemonic: &Zeroizing<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(mnemonic)
+}
+
+pub(crate) fn deserialize_mnemonic<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Zeroizing<String>, D::Error> {
+    String::deserialize(deserializer).map(Zeroizing::new)
+}
+
+pub(crate) fn serialize_language<S: Serializer>(
+    language: &Language,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(language.name())
+}
+
+pub(crate) fn deserialize_language<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Language, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Language::all_variants()
+        .iter()
+        .copied()
+        .find(|language| language.name() == name)
+        .ok_or_else(|| serde::de::Error::custom(format!("Unknown language: {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CoinType, Purpose};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const LIGHT: BackupKdf = BackupKdf::new(64, 1, 1);
+
+    fn backup() -> Backup {
+        Backup::new(MNEMONIC, Language::English, Network::BitcoinMainnet)
+            .unwrap()
+            .with_account(AccountMetadata::new(
+                Purpose::BIP84,
+                CoinType::Bitcoin,
+                0,
+                Network::BitcoinMainnet,
+            ))
+            .with_account(AccountMetadata::new(
+                Purpose::BIP44,
+                CoinType::Ethereum,
+                1,
+                Network::BitcoinMainnet,
+            ))
+            .with_label("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", "Savings")
+    }
+
+    #[test]
+    fn test_round_trip_and_restore() {
+        let backup = backup();
+        let file = backup.encrypt_with("pw", LIGHT).unwrap();
+        assert_eq!(&file[..4], b"KPWB");
+        assert_eq!(file[4], VERSION);
+        // Nothing readable outside the header
+        assert!(!file.windows(7).any(|w| w == b"abandon"));
+
+        let restored = Backup::decrypt(&file, "pw").unwrap();
+        assert_eq!(restored, backup);
+        assert_eq!(restored.accounts().len(), 2);
+        assert_eq!(
+            restored.labels()["bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"],
+            "Savings"
+        );
+
+        let wallet = restored.to_wallet("").unwrap();
+        assert_eq!(wallet.cached_account_count(), 2);
+        let expected =
+            Wallet::from_english_mnemonic(MNEMONIC, "", Network::BitcoinMainnet).unwrap();
+        assert_eq!(wallet.master_key(), expected.master_key());
+
+        // Fresh salt and nonce every time
+        assert_ne!(backup.encrypt_with("pw", LIGHT).unwrap(), file);
+    }
+
+    #[test]
+    fn test_wrong_password_and_tampering() {
+        let file = backup().encrypt_with("pw", LIGHT).unwrap();
+        assert_eq!(Backup::decrypt(&file, "pw2"), Err(Error::WrongPassword));
+
+        // Changed KDF parameters fail authentication too
+        let mut header = file.clone();
+        header[12] = 2;
+        assert_eq!(Backup::decrypt(&header, "pw"), Err(Error::WrongPassword));
+
+        let mut payload = file.clone();
+        let last = payload.len() - 1;
+        payload[last] ^= 0x01;
+        assert_eq!(Backup::decrypt(&payload, "pw"), Err(Error::WrongPassword));
+    }
+
+    #[test]
+    fn test_rejects_malformed_files() {
+        let file = backup().encrypt_with("pw", LIGHT).unwrap();
+
+        let mut version = file.clone();
+        version[4] = 2;
+        assert!(matches!(
+            Backup::decrypt(&version, "pw"),
+            Err(Error::Backup(_))
+        ));
+
+        let mut magic = file.clone();
+        magic[0] = b'X';
+        assert!(matches!(
+            Backup::decrypt(&magic, "pw"),
+            Err(Error::Backup(_))
+        ));
+        assert!(matches!(
+            Backup::decrypt(&file[..HEADER_LEN - 1], "pw"),
+            Err(Error::Backup(_))
+        ));
+
+        // A hostile memory cost is refused before any key derivation
+        let mut hostile = file.clone();
+        hostile[5..9].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            Backup::decrypt(&hostile, "pw"),
+            Err(Error::Backup(_))
+        ));
+
+        assert!(matches!(
+            Backup::new(
+                "abandon abandon",
+                Language::English,
+                Network::BitcoinMainnet
+            ),
+            Err(Error::InvalidMnemonic(_))
+        ));
+    }
+
+    #[test]
+    fn test_debug_redacts_mnemonic() {
+        let debug = format!("{:?}", backup());
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("abandon"));
+    }
+
+    #[test]
+    fn test_non_english_mnemonic() {
+        let entropy = [7u8; 16];
+        let mnemonic = Mnemonic::new(&entropy, Language::Japanese).unwrap();
+        let backup = Backup::new(
+            mnemonic.phrase(),
+            Language::Japanese,
+            Network::BitcoinTestnet,
+        )
+        .unwrap();
+        let file = backup.encrypt_with("パスワード", LIGHT).unwrap();
+        let restored = Backup::decrypt(&file, "パスワード").unwrap();
+        assert_eq!(restored.language(), Language::Japanese);
+        assert_eq!(restored.network(), Network::BitcoinTestnet);
+        assert_eq!(restored.mnemonic(), mnemonic.phrase());
+    }
+}
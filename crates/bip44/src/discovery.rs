@@ -37,6 +37,14 @@
 //! assert_eq!(last_used, Some(5));
 //! ```
 
+use std::time::Duration;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+// `std::time::Instant::now` panics on wasm32-unknown-unknown
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use web_time::Instant;
+
 /// Default gap limit as specified by BIP-44.
 ///
 /// BIP-44 recommends stopping the scan after finding 20 consecutive unused addresses.
@@ -200,38 +208,8 @@ impl GapLimitChecker {
         discovery: &D,
         start_index: u32,
     ) -> std::result::Result<Option<u32>, Box<dyn std::error::Error>> {
-        let mut last_used_index: Option<u32> = None;
-        let mut consecutive_unused = 0u32;
-        let mut current_index = start_index;
-
-        loop {
-            // Check if address is used
-            let is_used = discovery.is_address_used(current_index)?;
-
-            if is_used {
-                // Found a used address, reset gap counter and update last used
-                last_used_index = Some(current_index);
-                consecutive_unused = 0;
-            } else {
-                // Found an unused address, increment gap counter
-                consecutive_unused += 1;
-
-                // Stop if we've found enough consecutive unused addresses
-                if consecutive_unused >= self.gap_limit {
-                    break;
-                }
-            }
-
-            // Move to next address, stop if we'd overflow
-            if let Some(next) = current_index.checked_add(1) {
-                current_index = next;
-            } else {
-                // Reached u32::MAX
-                break;
-            }
-        }
-
-        Ok(last_used_index)
+        let (used_indices, _) = self.scan(discovery, start_index)?;
+        Ok(used_indices.last().copied())
     }
 
     /// Finds all used address indices on a chain up to the gap limit.
@@ -280,34 +258,50 @@ impl GapLimitChecker {
         discovery: &D,
         start_index: u32,
     ) -> std::result::Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let (used_indices, _) = self.scan(discovery, start_index)?;
+        Ok(used_indices)
+    }
+
+    /// Scans a chain once, returning the used indices and the number of addresses checked.
+    fn scan<D: AccountDiscovery>(
+        &self,
+        discovery: &D,
+        start_index: u32,
+    ) -> std::result::Result<(Vec<u32>, u32), Box<dyn std::error::Error>> {
         let mut used_indices = Vec::new();
         let mut consecutive_unused = 0u32;
+        let mut addresses_checked = 0u32;
         let mut current_index = start_index;
 
         loop {
             // Check if address is used
             let is_used = discovery.is_address_used(current_index)?;
+            addresses_checked = addresses_checked.saturating_add(1);
 
             if is_used {
+                // Found a used address, reset gap counter
                 used_indices.push(current_index);
                 consecutive_unused = 0;
             } else {
+                // Found an unused address, increment gap counter
                 consecutive_unused += 1;
 
+                // Stop if we've found enough consecutive unused addresses
                 if consecutive_unused >= self.gap_limit {
                     break;
                 }
             }
 
-            // Move to next address
+            // Move to next address, stop if we'd overflow
             if let Some(next) = current_index.checked_add(1) {
                 current_index = next;
             } else {
+                // Reached u32::MAX
                 break;
             }
         }
 
-        Ok(used_indices)
+        Ok((used_indices, addresses_checked))
     }
 }
 
@@ -329,8 +323,10 @@ impl Default for GapLimitChecker {
 
 /// Result of scanning a single chain (external or internal).
 ///
-/// Contains information about used addresses found during scanning.
+/// Contains information about used addresses found during scanning, along with
+/// statistics about the scan itself.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChainScanResult {
     /// The chain that was scanned
     pub chain: crate::Chain,
@@ -338,12 +334,41 @@ pub struct ChainScanResult {
     pub used_indices: Vec<u32>,
     /// The highest used address index, if any
     pub last_used_index: Option<u32>,
+    /// Number of addresses queried before the gap limit was reached
+    pub addresses_checked: u32,
+    /// Wall-clock time spent scanning the chain
+    pub elapsed: Duration,
+}
+
+impl ChainScanResult {
+    /// Returns the number of used addresses found on this chain.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_bip44::{ChainScanResult, Chain};
+    /// use std::time::Duration;
+    ///
+    /// let result = ChainScanResult {
+    ///     chain: Chain::External,
+    ///     used_indices: vec![0, 3],
+    ///     last_used_index: Some(3),
+    ///     addresses_checked: 23,
+    ///     elapsed: Duration::ZERO,
+    /// };
+    ///
+    /// assert_eq!(result.total_found(), 2);
+    /// ```
+    pub fn total_found(&self) -> usize {
+        self.used_indices.len()
+    }
 }
 
 /// Result of scanning both chains of an account.
 ///
 /// Contains scan results for external (receiving) and internal (change) chains.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccountScanResult {
     /// Account index that was scanned
     pub account_index: u32,
@@ -360,6 +385,7 @@ impl AccountScanResult {
     ///
     /// ```rust
     /// use khodpay_bip44::{AccountScanResult, ChainScanResult, Chain};
+    /// use std::time::Duration;
     ///
     /// let result = AccountScanResult {
     ///     account_index: 0,
@@ -367,11 +393,15 @@ impl AccountScanResult {
     ///         chain: Chain::External,
     ///         used_indices: vec![0, 1],
     ///         last_used_index: Some(1),
+    ///         addresses_checked: 22,
+    ///         elapsed: Duration::ZERO,
     ///     },
     ///     internal: ChainScanResult {
     ///         chain: Chain::Internal,
     ///         used_indices: vec![],
     ///         last_used_index: None,
+    ///         addresses_checked: 20,
+    ///         elapsed: Duration::ZERO,
     ///     },
     /// };
     ///
@@ -387,6 +417,7 @@ impl AccountScanResult {
     ///
     /// ```rust
     /// use khodpay_bip44::{AccountScanResult, ChainScanResult, Chain};
+    /// use std::time::Duration;
     ///
     /// let result = AccountScanResult {
     ///     account_index: 0,
@@ -394,11 +425,15 @@ impl AccountScanResult {
     ///         chain: Chain::External,
     ///         used_indices: vec![0, 1, 2],
     ///         last_used_index: Some(2),
+    ///         addresses_checked: 23,
+    ///         elapsed: Duration::ZERO,
     ///     },
     ///     internal: ChainScanResult {
     ///         chain: Chain::Internal,
     ///         used_indices: vec![0],
     ///         last_used_index: Some(0),
+    ///         addresses_checked: 21,
+    ///         elapsed: Duration::ZERO,
     ///     },
     /// };
     ///
@@ -407,6 +442,99 @@ impl AccountScanResult {
     pub fn total_used_count(&self) -> usize {
         self.external.used_indices.len() + self.internal.used_indices.len()
     }
+
+    /// Returns the number of addresses checked across both chains.
+    pub fn addresses_checked(&self) -> u64 {
+        u64::from(self.external.addresses_checked) + u64::from(self.internal.addresses_checked)
+    }
+
+    /// Returns the time spent scanning both chains.
+    pub fn elapsed(&self) -> Duration {
+        self.external.elapsed + self.internal.elapsed
+    }
+}
+
+/// Aggregated report of an account discovery run.
+///
+/// Summarizes every account found along with totals, suitable for diagnostics
+/// and restore-summary screens. With the `serde` feature enabled the report can
+/// be serialized (e.g. to JSON) as-is.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip44::{AccountScanner, GapLimitChecker, MockBlockchain};
+///
+/// let external = MockBlockchain::with_used_addresses(&[0, 1, 4]);
+/// let internal = MockBlockchain::with_used_addresses(&[0]);
+///
+/// let scanner = AccountScanner::new(GapLimitChecker::new(5));
+/// let report = scanner.discover_report(&external, &internal, 2).unwrap();
+///
+/// assert_eq!(report.used_account_count(), 2);
+/// assert_eq!(report.total_found(), 8);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiscoveryReport {
+    /// Gap limit used during the scan
+    pub gap_limit: u32,
+    /// Used accounts found, in ascending account index order
+    pub accounts: Vec<AccountScanResult>,
+    /// Number of accounts scanned, including the trailing unused account (if any)
+    pub accounts_scanned: u32,
+    /// Number of addresses checked across all scanned accounts and chains
+    pub addresses_checked: u64,
+    /// Wall-clock time spent on the whole discovery run
+    pub elapsed: Duration,
+}
+
+impl DiscoveryReport {
+    /// Returns the number of used accounts found.
+    pub fn used_account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns the total number of used addresses across all accounts.
+    pub fn total_found(&self) -> usize {
+        self.accounts
+            .iter()
+            .map(AccountScanResult::total_used_count)
+            .sum()
+    }
+
+    /// Returns the scan result for the given account index, if it was found.
+    pub fn account(&self, account_index: u32) -> Option<&AccountScanResult> {
+        self.accounts
+            .iter()
+            .find(|account| account.account_index == account_index)
+    }
+}
+
+/// Progress event emitted while [`AccountScanner::discover_report_with_progress`] runs.
+///
+/// A `ChainScanned` event follows each chain scan and an `AccountScanned` event
+/// follows each account, so restore screens can show live progress and partial
+/// results before the full [`DiscoveryReport`] is available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiscoveryProgress {
+    /// One chain of an account finished scanning.
+    ChainScanned {
+        /// Account index being scanned
+        account_index: u32,
+        /// Result of the chain scan
+        result: ChainScanResult,
+        /// Addresses checked so far across the whole run
+        addresses_checked: u64,
+    },
+    /// Both chains of an account finished scanning.
+    AccountScanned {
+        /// Result of the account scan; unused accounts end discovery
+        result: AccountScanResult,
+        /// Addresses checked so far across the whole run
+        addresses_checked: u64,
+    },
 }
 
 /// Scanner for discovering used accounts and addresses according to BIP-44.
@@ -505,13 +633,27 @@ impl AccountScanner {
         discovery: &D,
         chain: crate::Chain,
     ) -> std::result::Result<ChainScanResult, Box<dyn std::error::Error>> {
-        let used_indices = self.checker.find_used_indices(discovery, 0)?;
-        let last_used_index = self.checker.find_last_used_index(discovery, 0)?;
+        #[cfg(feature = "tracing")]
+        let operation = khodpay_tracing::operation!(
+            "scan_chain",
+            chain = chain,
+            gap_limit = self.gap_limit(),
+            addresses_checked = _
+        );
+
+        let started = Instant::now();
+        let (used_indices, addresses_checked) = self.checker.scan(discovery, 0)?;
+        let last_used_index = used_indices.last().copied();
+
+        #[cfg(feature = "tracing")]
+        operation.record("addresses_checked", &addresses_checked);
 
         Ok(ChainScanResult {
             chain,
             used_indices,
             last_used_index,
+            addresses_checked,
+            elapsed: started.elapsed(),
         })
     }
 
@@ -571,29 +713,159 @@ impl AccountScanner {
         internal_discovery: &D2,
         max_accounts: u32,
     ) -> std::result::Result<Vec<AccountScanResult>, Box<dyn std::error::Error>> {
-        let mut results = Vec::new();
+        let report = self.discover_report(external_discovery, internal_discovery, max_accounts)?;
+        Ok(report.accounts)
+    }
+
+    /// Discovers all used accounts for a given coin and returns an aggregated report.
+    ///
+    /// Performs the same scan as [`discover_accounts`](Self::discover_accounts), but also
+    /// records how many accounts and addresses were checked and how long the run took.
+    ///
+    /// # Arguments
+    ///
+    /// * `external_discovery` - Blockchain query for external chain
+    /// * `internal_discovery` - Blockchain query for internal chain
+    /// * `max_accounts` - Maximum number of accounts to scan (prevents infinite loops)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any blockchain query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_bip44::{AccountScanner, GapLimitChecker, MockBlockchain};
+    ///
+    /// let external = MockBlockchain::with_used_addresses(&[0, 2]);
+    /// let internal = MockBlockchain::new();
+    ///
+    /// let scanner = AccountScanner::new(GapLimitChecker::new(20));
+    /// let report = scanner.discover_report(&external, &internal, 1).unwrap();
+    ///
+    /// assert_eq!(report.accounts_scanned, 1);
+    /// // 23 external checks (indices 0..=22) + 20 internal checks
+    /// assert_eq!(report.addresses_checked, 43);
+    /// ```
+    pub fn discover_report<D1: AccountDiscovery, D2: AccountDiscovery>(
+        &self,
+        external_discovery: &D1,
+        internal_discovery: &D2,
+        max_accounts: u32,
+    ) -> std::result::Result<DiscoveryReport, Box<dyn std::error::Error>> {
+        self.discover_report_with_progress(
+            external_discovery,
+            internal_discovery,
+            max_accounts,
+            |_| {},
+        )
+    }
+
+    /// Discovers all used accounts like [`discover_report`](Self::discover_report),
+    /// calling `on_progress` after every chain and account scan.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any blockchain query fails. Events already emitted
+    /// describe the accounts scanned before the failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_bip44::{AccountScanner, DiscoveryProgress, GapLimitChecker, MockBlockchain};
+    ///
+    /// let external = MockBlockchain::with_used_addresses(&[0, 2]);
+    /// let internal = MockBlockchain::new();
+    ///
+    /// let scanner = AccountScanner::new(GapLimitChecker::new(20));
+    /// let mut accounts_done = Vec::new();
+    /// scanner
+    ///     .discover_report_with_progress(&external, &internal, 2, |event| {
+    ///         if let DiscoveryProgress::AccountScanned { result, .. } = event {
+    ///             accounts_done.push(result.account_index);
+    ///         }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(accounts_done, vec![0, 1]);
+    /// ```
+    pub fn discover_report_with_progress<D1, D2, F>(
+        &self,
+        external_discovery: &D1,
+        internal_discovery: &D2,
+        max_accounts: u32,
+        mut on_progress: F,
+    ) -> std::result::Result<DiscoveryReport, Box<dyn std::error::Error>>
+    where
+        D1: AccountDiscovery,
+        D2: AccountDiscovery,
+        F: FnMut(&DiscoveryProgress),
+    {
+        #[cfg(feature = "tracing")]
+        let operation = khodpay_tracing::operation!(
+            "discover_accounts",
+            gap_limit = self.gap_limit(),
+            max_accounts = max_accounts,
+            accounts_scanned = _,
+            addresses_checked = _
+        );
+
+        let started = Instant::now();
+        let mut accounts = Vec::new();
+        let mut accounts_scanned = 0u32;
+        let mut addresses_checked = 0u64;
 
         for account_index in 0..max_accounts {
             // Scan both chains
             let external = self.scan_chain(external_discovery, crate::Chain::External)?;
+            addresses_checked += u64::from(external.addresses_checked);
+            on_progress(&DiscoveryProgress::ChainScanned {
+                account_index,
+                result: external.clone(),
+                addresses_checked,
+            });
+
             let internal = self.scan_chain(internal_discovery, crate::Chain::Internal)?;
+            addresses_checked += u64::from(internal.addresses_checked);
+            on_progress(&DiscoveryProgress::ChainScanned {
+                account_index,
+                result: internal.clone(),
+                addresses_checked,
+            });
 
             let account_result = AccountScanResult {
                 account_index,
                 external,
                 internal,
             };
+            accounts_scanned += 1;
+            on_progress(&DiscoveryProgress::AccountScanned {
+                result: account_result.clone(),
+                addresses_checked,
+            });
 
             // If account has any used addresses, add it to results
             if account_result.is_used() {
-                results.push(account_result);
+                accounts.push(account_result);
             } else {
                 // Stop if we find an unused account (BIP-44 account gap)
                 break;
             }
         }
 
-        Ok(results)
+        #[cfg(feature = "tracing")]
+        {
+            operation.record("accounts_scanned", &accounts_scanned);
+            operation.record("addresses_checked", &addresses_checked);
+        }
+
+        Ok(DiscoveryReport {
+            gap_limit: self.gap_limit(),
+            accounts,
+            accounts_scanned,
+            addresses_checked,
+            elapsed: started.elapsed(),
+        })
     }
 }
 
@@ -1101,11 +1373,15 @@ mod tests {
                 chain: Chain::External,
                 used_indices: vec![0],
                 last_used_index: Some(0),
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
             internal: ChainScanResult {
                 chain: Chain::Internal,
                 used_indices: vec![],
                 last_used_index: None,
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
         };
 
@@ -1122,11 +1398,15 @@ mod tests {
                 chain: Chain::External,
                 used_indices: vec![],
                 last_used_index: None,
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
             internal: ChainScanResult {
                 chain: Chain::Internal,
                 used_indices: vec![],
                 last_used_index: None,
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
         };
 
@@ -1143,11 +1423,15 @@ mod tests {
                 chain: Chain::External,
                 used_indices: vec![0, 1, 2],
                 last_used_index: Some(2),
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
             internal: ChainScanResult {
                 chain: Chain::Internal,
                 used_indices: vec![0, 5],
                 last_used_index: Some(5),
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
         };
 
@@ -1230,12 +1514,16 @@ mod tests {
             chain: Chain::External,
             used_indices: vec![0, 1],
             last_used_index: Some(1),
+            addresses_checked: 0,
+            elapsed: Duration::ZERO,
         };
 
         let result2 = ChainScanResult {
             chain: Chain::External,
             used_indices: vec![0, 1],
             last_used_index: Some(1),
+            addresses_checked: 0,
+            elapsed: Duration::ZERO,
         };
 
         assert_eq!(result1, result2);
@@ -1251,11 +1539,15 @@ mod tests {
                 chain: Chain::External,
                 used_indices: vec![0],
                 last_used_index: Some(0),
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
             internal: ChainScanResult {
                 chain: Chain::Internal,
                 used_indices: vec![],
                 last_used_index: None,
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
         };
 
@@ -1281,11 +1573,15 @@ mod tests {
                 chain: Chain::External,
                 used_indices: vec![0],
                 last_used_index: Some(0),
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
             internal: ChainScanResult {
                 chain: Chain::Internal,
                 used_indices: vec![],
                 last_used_index: None,
+                addresses_checked: 0,
+                elapsed: Duration::ZERO,
             },
         };
 
@@ -1293,6 +1589,217 @@ mod tests {
         assert!(debug_str.contains("AccountScanResult"));
     }
 
+    #[test]
+    fn test_scan_chain_addresses_checked() {
+        use crate::Chain;
+
+        let blockchain = MockBlockchain::with_used_addresses(&[0, 2, 5]);
+        let scanner = AccountScanner::new(GapLimitChecker::new(5));
+        let result = scanner.scan_chain(&blockchain, Chain::External).unwrap();
+
+        // Indices 0..=10 are checked: the last used is 5, followed by 5 unused
+        assert_eq!(result.addresses_checked, 11);
+        assert_eq!(result.total_found(), 3);
+    }
+
+    #[test]
+    fn test_scan_chain_addresses_checked_empty() {
+        use crate::Chain;
+
+        let scanner = AccountScanner::new(GapLimitChecker::new(20));
+        let result = scanner
+            .scan_chain(&MockBlockchain::new(), Chain::Internal)
+            .unwrap();
+
+        assert_eq!(result.addresses_checked, 20);
+        assert_eq!(result.total_found(), 0);
+    }
+
+    #[test]
+    fn test_account_scan_result_aggregates() {
+        use crate::Chain;
+
+        let result = AccountScanResult {
+            account_index: 0,
+            external: ChainScanResult {
+                chain: Chain::External,
+                used_indices: vec![0],
+                last_used_index: Some(0),
+                addresses_checked: 21,
+                elapsed: Duration::from_millis(30),
+            },
+            internal: ChainScanResult {
+                chain: Chain::Internal,
+                used_indices: vec![],
+                last_used_index: None,
+                addresses_checked: 20,
+                elapsed: Duration::from_millis(12),
+            },
+        };
+
+        assert_eq!(result.addresses_checked(), 41);
+        assert_eq!(result.elapsed(), Duration::from_millis(42));
+    }
+
+    #[test]
+    fn test_discover_report_matches_discover_accounts() {
+        let external = MockBlockchain::with_used_addresses(&[0, 1]);
+        let internal = MockBlockchain::with_used_addresses(&[0]);
+
+        let scanner = AccountScanner::new(GapLimitChecker::new(20));
+        let accounts = scanner.discover_accounts(&external, &internal, 3).unwrap();
+        let report = scanner.discover_report(&external, &internal, 3).unwrap();
+
+        assert_eq!(report.used_account_count(), accounts.len());
+        for (reported, discovered) in report.accounts.iter().zip(&accounts) {
+            assert_eq!(reported.account_index, discovered.account_index);
+            assert_eq!(
+                reported.external.used_indices,
+                discovered.external.used_indices
+            );
+            assert_eq!(
+                reported.internal.used_indices,
+                discovered.internal.used_indices
+            );
+        }
+    }
+
+    #[test]
+    fn test_discover_report_totals() {
+        let external = MockBlockchain::with_used_addresses(&[0, 3]);
+        let internal = MockBlockchain::with_used_addresses(&[1]);
+
+        let scanner = AccountScanner::new(GapLimitChecker::new(4));
+        let report = scanner.discover_report(&external, &internal, 2).unwrap();
+
+        assert_eq!(report.gap_limit, 4);
+        assert_eq!(report.accounts_scanned, 2);
+        assert_eq!(report.used_account_count(), 2);
+        assert_eq!(report.total_found(), 6);
+        // External checks 0..=7 (8), internal checks 0..=5 (6), per account
+        assert_eq!(report.addresses_checked, 2 * (8 + 6));
+        assert!(report.elapsed >= report.accounts.iter().map(|a| a.elapsed()).sum());
+    }
+
+    #[test]
+    fn test_discover_report_empty_wallet() {
+        let scanner = AccountScanner::new(GapLimitChecker::new(20));
+        let report = scanner
+            .discover_report(&MockBlockchain::new(), &MockBlockchain::new(), 10)
+            .unwrap();
+
+        // The first account is scanned and found unused, which ends discovery
+        assert_eq!(report.accounts_scanned, 1);
+        assert_eq!(report.addresses_checked, 40);
+        assert_eq!(report.used_account_count(), 0);
+        assert_eq!(report.total_found(), 0);
+    }
+
+    #[test]
+    fn test_discover_report_with_progress_events() {
+        let external = MockBlockchain::with_used_addresses(&[0, 3]);
+        let internal = MockBlockchain::with_used_addresses(&[1]);
+
+        let scanner = AccountScanner::new(GapLimitChecker::new(4));
+        let mut events = Vec::new();
+        let report = scanner
+            .discover_report_with_progress(&external, &internal, 2, |event| {
+                events.push(event.clone())
+            })
+            .unwrap();
+
+        // Two chain events and one account event per scanned account
+        assert_eq!(events.len(), 6);
+        match &events[0] {
+            DiscoveryProgress::ChainScanned {
+                account_index,
+                result,
+                addresses_checked,
+            } => {
+                assert_eq!(*account_index, 0);
+                assert_eq!(result.chain, crate::Chain::External);
+                assert_eq!(*addresses_checked, 8);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match &events[2] {
+            DiscoveryProgress::AccountScanned {
+                result,
+                addresses_checked,
+            } => {
+                assert_eq!(result, &report.accounts[0]);
+                assert_eq!(*addresses_checked, 14);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match events.last().unwrap() {
+            DiscoveryProgress::AccountScanned {
+                addresses_checked, ..
+            } => assert_eq!(*addresses_checked, report.addresses_checked),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_discover_report_with_progress_stops_at_unused_account() {
+        let scanner = AccountScanner::new(GapLimitChecker::new(20));
+        let mut accounts = Vec::new();
+        scanner
+            .discover_report_with_progress(
+                &MockBlockchain::new(),
+                &MockBlockchain::new(),
+                10,
+                |event| {
+                    if let DiscoveryProgress::AccountScanned { result, .. } = event {
+                        accounts.push((result.account_index, result.is_used()));
+                    }
+                },
+            )
+            .unwrap();
+
+        assert_eq!(accounts, vec![(0, false)]);
+    }
+
+    #[test]
+    fn test_discover_report_account_lookup() {
+        let external = MockBlockchain::with_used_addresses(&[0]);
+        let scanner = AccountScanner::new(GapLimitChecker::new(20));
+        let report = scanner
+            .discover_report(&external, &MockBlockchain::new(), 2)
+            .unwrap();
+
+        assert_eq!(report.account(1).unwrap().account_index, 1);
+        assert!(report.account(2).is_none());
+    }
+
+    #[test]
+    fn test_discover_report_zero_max_accounts() {
+        let scanner = AccountScanner::default();
+        let report = scanner
+            .discover_report(&MockBlockchain::new(), &MockBlockchain::new(), 0)
+            .unwrap();
+
+        assert_eq!(report.accounts_scanned, 0);
+        assert_eq!(report.addresses_checked, 0);
+        assert!(report.accounts.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_discovery_report_serde_roundtrip() {
+        let external = MockBlockchain::with_used_addresses(&[0, 2]);
+        let scanner = AccountScanner::new(GapLimitChecker::new(5));
+        let report = scanner
+            .discover_report(&external, &MockBlockchain::new(), 1)
+            .unwrap();
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"addresses_checked\""));
+
+        let deserialized: DiscoveryReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, report);
+    }
+
     // MockBlockchain tests
     #[test]
     fn test_mock_blockchain_new() {
@@ -233,6 +233,59 @@ pub enum Error {
     /// ```
     #[error("Key derivation error: {0}")]
     KeyDerivation(String),
+
+    /// Invalid master extended private key provided for wallet creation.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use khodpay_bip44::Error;
+    /// let error = Error::InvalidMasterKey("expected depth 0, got 3".to_string());
+    /// ```
+    #[error("Invalid master key: {0}")]
+    InvalidMasterKey(String),
+
+    /// BIP-352 silent payment error.
+    ///
+    /// This occurs when a silent payment address cannot be parsed or when
+    /// sender-side output derivation fails (e.g. the input keys sum to zero).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use khodpay_bip44::Error;
+    /// let error = Error::SilentPayment("No eligible inputs".to_string());
+    /// ```
+    #[error("Silent payment error: {0}")]
+    SilentPayment(String),
+
+    /// Malformed, unsupported or unacceptable backup file.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use khodpay_bip44::Error;
+    /// let error = Error::Backup("unsupported backup version: 2".to_string());
+    /// ```
+    #[error("Backup error: {0}")]
+    Backup(String),
+
+    /// Malformed, unsupported or inconsistent wallet export.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use khodpay_bip44::Error;
+    /// let error = Error::Interchange("unsupported wallet export version: 2".to_string());
+    /// ```
+    #[error("Wallet export error: {0}")]
+    Interchange(String),
+
+    /// A backup failed authentication: wrong password or corrupted file.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use khodpay_bip44::Error;
+    /// let error = Error::WrongPassword;
+    /// ```
+    #[error("Wrong password or corrupted backup")]
+    WrongPassword,
 }
 
 /// Custom equality implementation for [`Error`].
@@ -265,6 +318,11 @@ impl PartialEq for Error {
             (Error::InvalidSeed(s1), Error::InvalidSeed(s2)) => s1 == s2,
             (Error::InvalidMnemonic(m1), Error::InvalidMnemonic(m2)) => m1 == m2,
             (Error::KeyDerivation(k1), Error::KeyDerivation(k2)) => k1 == k2,
+            (Error::InvalidMasterKey(m1), Error::InvalidMasterKey(m2)) => m1 == m2,
+            (Error::SilentPayment(s1), Error::SilentPayment(s2)) => s1 == s2,
+            (Error::Backup(b1), Error::Backup(b2)) => b1 == b2,
+            (Error::Interchange(i1), Error::Interchange(i2)) => i1 == i2,
+            (Error::WrongPassword, Error::WrongPassword) => true,
             _ => false,
         }
     }
@@ -357,4 +415,31 @@ mod tests {
             "Invalid hardened level: Purpose must be hardened"
         );
     }
+
+    #[test]
+    fn test_backup_errors() {
+        let error = Error::Backup("unsupported backup version: 2".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Backup error: unsupported backup version: 2"
+        );
+        assert_eq!(
+            Error::WrongPassword.to_string(),
+            "Wrong password or corrupted backup"
+        );
+        assert_eq!(Error::WrongPassword, Error::WrongPassword);
+    }
+
+    #[test]
+    fn test_interchange_error() {
+        let error = Error::Interchange("unsupported wallet export version: 2".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Wallet export error: unsupported wallet export version: 2"
+        );
+        assert_ne!(
+            error,
+            Error::Backup("unsupported wallet export version: 2".to_string())
+        );
+    }
 }
@@ -0,0 +1,1040 @@
+//! Versioned JSON wallet exports for moving a wallet between installations.
+//!
+//! A [`WalletExport`] describes a wallet without tying it to this library: the
+//! wallet's network and master key fingerprint, its accounts as SLIP-132
+//! extended public keys with their derivation paths, the derivation profiles to
+//! scan for more accounts, and BIP-329 labels. The mnemonic is optional and, when
+//! present, encrypted with Argon2id and XChaCha20-Poly1305 under its own password.
+//!
+//! ```json
+//! {
+//!   "format": "khodpay-wallet",
+//!   "version": 1,
+//!   "wallet": { "name": "Main", "network": "mainnet", "fingerprint": "73c5da0a" },
+//!   "profiles": [
+//!     { "name": "native-segwit", "purpose": 84, "coin_type": 0, "gap_limit": 20 }
+//!   ],
+//!   "accounts": [
+//!     {
+//!       "name": "Savings", "purpose": 84, "coin_type": 0, "account": 0,
+//!       "path": "m/84'/0'/0'", "xpub": "zpub6rFR7y4Q2Aij…", "profile": "native-segwit"
+//!     }
+//!   ],
+//!   "labels": [ { "type": "addr", "ref": "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", "label": "Savings" } ],
+//!   "secrets": {
+//!     "kdf": "argon2id", "m_cost": 65536, "t_cost": 3, "p_cost": 1, "salt": "…",
+//!     "cipher": "xchacha20poly1305", "nonce": "…", "ciphertext": "…"
+//!   }
+//! }
+//! ```
+//!
+//! Imports refuse other formats and newer versions, and check that every account
+//! key matches its path and the wallet's network. Unknown fields are ignored, so
+//! other wallets may add their own. The full format is specified in
+//! `docs/wallet-interchange-format.md`.
+//!
+//! Requires the `backup` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_bip44::{
+//!     BackupKdf, CoinType, DerivationProfile, ExportedAccount, ExportedLabel, Language,
+//!     LabelType, Purpose, Wallet, WalletDescriptor, WalletExport,
+//! };
+//! use khodpay_bip32::Network;
+//!
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet)?;
+//! let account = ExportedAccount::from(wallet.get_account(Purpose::BIP84, CoinType::Bitcoin, 0)?);
+//!
+//! let export = WalletExport::new(WalletDescriptor::from_wallet("Main", &wallet))
+//!     .with_profile(DerivationProfile::new("native-segwit", Purpose::BIP84, CoinType::Bitcoin))
+//!     .with_account(account.with_profile("native-segwit"))
+//!     .with_label(ExportedLabel::new(
+//!         LabelType::Addr,
+//!         "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+//!         "Savings",
+//!     ))
+//!     // Light parameters keep the example fast; use `with_secrets` for real exports.
+//!     .with_secrets_kdf(mnemonic, Language::English, "correct horse", BackupKdf::new(64, 1, 1))?;
+//! let json = export.to_json()?;
+//!
+//! // On the other installation
+//! let imported = WalletExport::from_json(&json)?;
+//! assert_eq!(imported.accounts()[0].path(), "m/84'/0'/0'");
+//! let restored = imported.to_wallet("correct horse", "")?;
+//! assert_eq!(restored.master_key(), wallet.master_key());
+//! # Ok::<(), khodpay_bip44::Error>(())
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use khodpay_bip32::{ChildNumber, Network};
+use khodpay_bip39::{Language, Mnemonic};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroizing;
+
+use crate::backup::{
+    deserialize_language, deserialize_mnemonic, serialize_language, serialize_mnemonic, NONCE_LEN,
+    SALT_LEN,
+};
+use crate::{
+    Account, BackupKdf, CoinType, Error, Purpose, Result, Wallet, WatchOnlyAccount,
+    DEFAULT_GAP_LIMIT,
+};
+
+/// Value of the `format` field of every wallet export.
+pub const EXPORT_FORMAT: &str = "khodpay-wallet";
+
+/// Current wallet export version.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Key derivation function named in the `secrets` object.
+const KDF_NAME: &str = "argon2id";
+
+/// Cipher named in the `secrets` object.
+const CIPHER_NAME: &str = "xchacha20poly1305";
+
+/// A wallet in the interchange format.
+///
+/// Build one with [`WalletExport::new`] and the `with_*` methods, write it with
+/// [`WalletExport::to_json`] and read it back with [`WalletExport::from_json`].
+/// The format is specified in `docs/wallet-interchange-format.md` in the
+/// repository.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletExport {
+    format: String,
+    version: u32,
+    wallet: WalletDescriptor,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    profiles: Vec<DerivationProfile>,
+    #[serde(default)]
+    accounts: Vec<ExportedAccount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<ExportedLabel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secrets: Option<EncryptedSecrets>,
+}
+
+impl WalletExport {
+    /// Creates an export of the wallet described by `wallet`, with no accounts,
+    /// labels or secrets yet.
+    pub fn new(wallet: WalletDescriptor) -> Self {
+        Self {
+            format: EXPORT_FORMAT.to_string(),
+            version: EXPORT_VERSION,
+            wallet,
+            profiles: Vec::new(),
+            accounts: Vec::new(),
+            labels: Vec::new(),
+            secrets: None,
+        }
+    }
+
+    /// Adds a derivation profile, replacing any profile with the same name.
+    pub fn with_profile(mut self, profile: DerivationProfile) -> Self {
+        self.profiles.retain(|p| p.name != profile.name);
+        self.profiles.push(profile);
+        self
+    }
+
+    /// Adds an account, replacing any account at the same path.
+    pub fn with_account(mut self, account: ExportedAccount) -> Self {
+        self.accounts.retain(|a| a.path() != account.path());
+        self.accounts.push(account);
+        self
+    }
+
+    /// Adds a label.
+    pub fn with_label(mut self, label: ExportedLabel) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    /// Includes the mnemonic, encrypted under `password` with
+    /// [`BackupKdf::DEFAULT`].
+    ///
+    /// The BIP-39 passphrase is never exported; [`WalletExport::to_wallet`] asks
+    /// for it and checks it against the wallet fingerprint.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidMnemonic`] if the phrase is not a valid mnemonic in
+    /// `language`, and [`Error::Backup`] if encryption fails.
+    pub fn with_secrets(self, mnemonic: &str, language: Language, password: &str) -> Result<Self> {
+        self.with_secrets_kdf(mnemonic, language, password, BackupKdf::DEFAULT)
+    }
+
+    /// Includes the mnemonic, encrypted under `password` with custom Argon2id
+    /// parameters.
+    ///
+    /// # Errors
+    ///
+    /// As [`WalletExport::with_secrets`], and [`Error::Backup`] if the parameters
+    /// are invalid.
+    pub fn with_secrets_kdf(
+        mut self,
+        mnemonic: &str,
+        language: Language,
+        password: &str,
+        kdf: BackupKdf,
+    ) -> Result<Self> {
+        let parsed = Mnemonic::from_phrase(mnemonic, language)
+            .map_err(|e| Error::InvalidMnemonic(format!("Failed to parse mnemonic: {}", e)))?;
+        let secrets = Secrets {
+            mnemonic: Zeroizing::new(parsed.phrase().to_string()),
+            language,
+        };
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = kdf.derive(password, &salt)?;
+        let plaintext =
+            Zeroizing::new(serde_json::to_vec(&secrets).map_err(|e| Error::Backup(e.to_string()))?);
+        let aad = self.wallet.associated_data(&kdf);
+        let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::Backup("encryption failed".to_string()))?;
+
+        self.secrets = Some(EncryptedSecrets {
+            kdf: KDF_NAME.to_string(),
+            m_cost: kdf.m_cost(),
+            t_cost: kdf.t_cost(),
+            p_cost: kdf.p_cost(),
+            salt: hex::encode(salt),
+            cipher: CIPHER_NAME.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        });
+        Ok(self)
+    }
+
+    /// Returns the format version.
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the wallet descriptor.
+    pub const fn wallet(&self) -> &WalletDescriptor {
+        &self.wallet
+    }
+
+    /// Returns the derivation profiles.
+    pub fn profiles(&self) -> &[DerivationProfile] {
+        &self.profiles
+    }
+
+    /// Returns the accounts.
+    pub fn accounts(&self) -> &[ExportedAccount] {
+        &self.accounts
+    }
+
+    /// Returns the labels.
+    pub fn labels(&self) -> &[ExportedLabel] {
+        &self.labels
+    }
+
+    /// Returns `true` if the export includes the encrypted mnemonic.
+    pub const fn has_secrets(&self) -> bool {
+        self.secrets.is_some()
+    }
+
+    /// Writes the export as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Interchange`] if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Interchange(e.to_string()))
+    }
+
+    /// Reads an export.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Interchange`] if the JSON is malformed or of another
+    /// format or a newer version, if an account key does not match its path or the
+    /// wallet's network, or if an account names a profile the export lacks.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let export: Self =
+            serde_json::from_str(json).map_err(|e| Error::Interchange(e.to_string()))?;
+        if export.format != EXPORT_FORMAT {
+            return Err(Error::Interchange(format!(
+                "not a wallet export: format {:?}",
+                export.format
+            )));
+        }
+        if export.version == 0 || export.version > EXPORT_VERSION {
+            return Err(Error::Interchange(format!(
+                "unsupported wallet export version: {}",
+                export.version
+            )));
+        }
+
+        let mut names = HashSet::new();
+        for profile in &export.profiles {
+            if !names.insert(profile.name.as_str()) {
+                return Err(Error::Interchange(format!(
+                    "duplicate profile {:?}",
+                    profile.name
+                )));
+            }
+        }
+        for account in &export.accounts {
+            if account.key.network() != export.wallet.network {
+                return Err(Error::Interchange(format!(
+                    "account {} is not on {}",
+                    account.path(),
+                    network_name(export.wallet.network)
+                )));
+            }
+            if let Some(profile) = &account.profile {
+                if !names.contains(profile.as_str()) {
+                    return Err(Error::Interchange(format!(
+                        "account {} uses unknown profile {:?}",
+                        account.path(),
+                        profile
+                    )));
+                }
+            }
+        }
+        Ok(export)
+    }
+
+    /// Restores the wallet from the encrypted mnemonic with the BIP-39
+    /// `passphrase`, and derives every exported account into its cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Interchange`] if the export has no secrets, or if the
+    /// restored wallet does not match the exported fingerprint (usually a wrong
+    /// passphrase) or account keys; [`Error::WrongPassword`] if decryption fails;
+    /// and [`Error::Backup`] for unsupported or unacceptable encryption
+    /// parameters.
+    pub fn to_wallet(&self, password: &str, passphrase: &str) -> Result<Wallet> {
+        let secrets = self
+            .secrets
+            .as_ref()
+            .ok_or_else(|| Error::Interchange("export has no secrets".to_string()))?
+            .decrypt(password, &self.wallet)?;
+
+        let mut wallet = Wallet::from_mnemonic(
+            &secrets.mnemonic,
+            passphrase,
+            secrets.language,
+            self.wallet.network,
+        )?;
+        if wallet.master_key().fingerprint() != self.wallet.fingerprint {
+            return Err(Error::Interchange(format!(
+                "restored wallet has fingerprint {}, not {}; check the passphrase",
+                hex::encode(wallet.master_key().fingerprint()),
+                hex::encode(self.wallet.fingerprint)
+            )));
+        }
+        for exported in &self.accounts {
+            let account = wallet.get_account(
+                exported.purpose(),
+                exported.coin_type,
+                exported.account_index,
+            )?;
+            if account.to_watch_only() != exported.key {
+                return Err(Error::Interchange(format!(
+                    "account {} does not match the exported key",
+                    exported.path()
+                )));
+            }
+        }
+        Ok(wallet)
+    }
+}
+
+impl fmt::Debug for WalletExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalletExport")
+            .field("version", &self.version)
+            .field("wallet", &self.wallet)
+            .field("profiles", &self.profiles)
+            .field("accounts", &self.accounts)
+            .field("labels", &self.labels)
+            .field("has_secrets", &self.has_secrets())
+            .finish()
+    }
+}
+
+/// The wallet an export describes.
+///
+/// The fingerprint is that of the master key, so it depends on the BIP-39
+/// passphrase as well as the mnemonic; an importing wallet can use it to tell
+/// wallets apart and to check a restored passphrase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletDescriptor {
+    name: String,
+    #[serde(with = "network_serde")]
+    network: Network,
+    #[serde(with = "fingerprint_serde")]
+    fingerprint: [u8; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<u64>,
+}
+
+impl WalletDescriptor {
+    /// Describes a wallet by name, network and master key fingerprint.
+    pub fn new(name: impl Into<String>, network: Network, fingerprint: [u8; 4]) -> Self {
+        Self {
+            name: name.into(),
+            network,
+            fingerprint,
+            created_at: None,
+        }
+    }
+
+    /// Describes `wallet` under `name`.
+    pub fn from_wallet(name: impl Into<String>, wallet: &Wallet) -> Self {
+        Self::new(name, wallet.network(), wallet.master_key().fingerprint())
+    }
+
+    /// Records when the wallet was created, in seconds since the Unix epoch, so an
+    /// importing wallet can start scanning the chain there.
+    pub fn with_created_at(mut self, created_at: u64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Returns the wallet name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the network.
+    pub const fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the master key fingerprint.
+    pub const fn fingerprint(&self) -> [u8; 4] {
+        self.fingerprint
+    }
+
+    /// Returns the creation time in seconds since the Unix epoch, if recorded.
+    pub const fn created_at(&self) -> Option<u64> {
+        self.created_at
+    }
+
+    /// Binds the encrypted secrets to this wallet and the key derivation
+    /// parameters.
+    fn associated_data(&self, kdf: &BackupKdf) -> Vec<u8> {
+        format!(
+            "{}/{}/{}/{}/{}/{}/{}/{}",
+            EXPORT_FORMAT,
+            EXPORT_VERSION,
+            network_name(self.network),
+            hex::encode(self.fingerprint),
+            KDF_NAME,
+            kdf.m_cost(),
+            kdf.t_cost(),
+            kdf.p_cost()
+        )
+        .into_bytes()
+    }
+}
+
+/// A purpose and coin type to scan for accounts, such as native SegWit Bitcoin.
+///
+/// Accounts name the profile they were found with, so an importing wallet knows
+/// which account indexes to look past and how many unused addresses to scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivationProfile {
+    name: String,
+    #[serde(with = "purpose_serde")]
+    purpose: Purpose,
+    #[serde(with = "coin_type_serde")]
+    coin_type: CoinType,
+    gap_limit: u32,
+}
+
+impl DerivationProfile {
+    /// Creates a profile with the [`DEFAULT_GAP_LIMIT`].
+    pub fn new(name: impl Into<String>, purpose: Purpose, coin_type: CoinType) -> Self {
+        Self {
+            name: name.into(),
+            purpose,
+            coin_type,
+            gap_limit: DEFAULT_GAP_LIMIT,
+        }
+    }
+
+    /// Sets the number of consecutive unused addresses to scan.
+    pub fn with_gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    /// Returns the profile name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the purpose.
+    pub const fn purpose(&self) -> Purpose {
+        self.purpose
+    }
+
+    /// Returns the coin type.
+    pub const fn coin_type(&self) -> CoinType {
+        self.coin_type
+    }
+
+    /// Returns the gap limit.
+    pub const fn gap_limit(&self) -> u32 {
+        self.gap_limit
+    }
+}
+
+/// An account in an export: its path and account-level extended public key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "RawAccount", try_from = "RawAccount")]
+pub struct ExportedAccount {
+    coin_type: CoinType,
+    account_index: u32,
+    key: WatchOnlyAccount,
+    name: Option<String>,
+    profile: Option<String>,
+}
+
+impl ExportedAccount {
+    /// Creates an account entry from a watch-only account key of `coin_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAccount`] if the key is not a hardened account-level
+    /// key.
+    pub fn new(key: WatchOnlyAccount, coin_type: CoinType) -> Result<Self> {
+        let account_index = match key.extended_key().child_number() {
+            ChildNumber::Hardened(index) if key.extended_key().depth() == 3 => index,
+            _ => {
+                return Err(Error::InvalidAccount {
+                    reason: "expected a hardened account-level key".to_string(),
+                })
+            }
+        };
+        Ok(Self {
+            coin_type,
+            account_index,
+            key,
+            name: None,
+            profile: None,
+        })
+    }
+
+    /// Sets the account's display name.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Names the derivation profile the account belongs to.
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Returns the purpose.
+    pub const fn purpose(&self) -> Purpose {
+        self.key.purpose()
+    }
+
+    /// Returns the coin type.
+    pub const fn coin_type(&self) -> CoinType {
+        self.coin_type
+    }
+
+    /// Returns the account index.
+    pub const fn account_index(&self) -> u32 {
+        self.account_index
+    }
+
+    /// Returns the account's derivation path, such as `m/84'/0'/0'`.
+    pub fn path(&self) -> String {
+        account_path(self.purpose(), self.coin_type, self.account_index)
+    }
+
+    /// Returns the watch-only account.
+    pub const fn watch_only(&self) -> &WatchOnlyAccount {
+        &self.key
+    }
+
+    /// Returns the display name, if set.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the derivation profile name, if set.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+}
+
+impl From<&Account> for ExportedAccount {
+    fn from(account: &Account) -> Self {
+        Self {
+            coin_type: account.coin_type(),
+            account_index: account.account_index(),
+            key: account.to_watch_only(),
+            name: None,
+            profile: None,
+        }
+    }
+}
+
+/// An account as written in JSON.
+#[derive(Serialize, Deserialize)]
+struct RawAccount {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    purpose: u32,
+    coin_type: u32,
+    account: u32,
+    path: String,
+    xpub: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+}
+
+impl From<ExportedAccount> for RawAccount {
+    fn from(account: ExportedAccount) -> Self {
+        Self {
+            path: account.path(),
+            purpose: account.purpose().value(),
+            coin_type: account.coin_type.index(),
+            account: account.account_index,
+            xpub: account.key.to_string(),
+            name: account.name,
+            profile: account.profile,
+        }
+    }
+}
+
+impl TryFrom<RawAccount> for ExportedAccount {
+    type Error = Error;
+
+    fn try_from(raw: RawAccount) -> Result<Self> {
+        let purpose = Purpose::try_from(raw.purpose)?;
+        let coin_type = CoinType::try_from(raw.coin_type)?;
+        let path = account_path(purpose, coin_type, raw.account);
+        if raw.path != path {
+            return Err(Error::Interchange(format!(
+                "account path {} does not match {}",
+                raw.path, path
+            )));
+        }
+
+        // Plain xpub/tpub keys parse as BIP-44 and take the stated purpose
+        let parsed: WatchOnlyAccount = raw.xpub.parse()?;
+        let key = if parsed.purpose() == purpose {
+            parsed
+        } else if parsed.purpose() == Purpose::BIP44 {
+            WatchOnlyAccount::new(parsed.extended_key().clone(), purpose)
+        } else {
+            return Err(Error::Interchange(format!(
+                "account {} has a {} key",
+                path,
+                parsed.purpose().name()
+            )));
+        };
+
+        let account = Self::new(key, coin_type)?;
+        if account.account_index != raw.account {
+            return Err(Error::Interchange(format!(
+                "account {} has the key of account {}",
+                path, account.account_index
+            )));
+        }
+        Ok(Self {
+            name: raw.name,
+            profile: raw.profile,
+            ..account
+        })
+    }
+}
+
+/// BIP-329 label types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelType {
+    /// A transaction, referenced by txid.
+    Tx,
+    /// An address.
+    Addr,
+    /// A public key, hex-encoded.
+    Pubkey,
+    /// A transaction input, referenced as `txid:vout` of the spent output.
+    Input,
+    /// A transaction output, referenced as `txid:vout`.
+    Output,
+    /// An extended public key.
+    Xpub,
+}
+
+/// A user label, in BIP-329 form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedLabel {
+    #[serde(rename = "type")]
+    kind: LabelType,
+    #[serde(rename = "ref")]
+    reference: String,
+    label: String,
+}
+
+impl ExportedLabel {
+    /// Labels the item of type `kind` referenced by `reference`.
+    pub fn new(kind: LabelType, reference: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            kind,
+            reference: reference.into(),
+            label: label.into(),
+        }
+    }
+
+    /// Returns the type of the labelled item.
+    pub const fn kind(&self) -> LabelType {
+        self.kind
+    }
+
+    /// Returns the reference of the labelled item.
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    /// Returns the label.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// The encrypted mnemonic as written in JSON.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct EncryptedSecrets {
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedSecrets {
+    /// Decrypts the secrets of `wallet`.
+    fn decrypt(&self, password: &str, wallet: &WalletDescriptor) -> Result<Secrets> {
+        if self.kdf != KDF_NAME || self.cipher != CIPHER_NAME {
+            return Err(Error::Backup(format!(
+                "unsupported encryption: {} with {}",
+                self.kdf, self.cipher
+            )));
+        }
+        let field = |name: &str, value: &str, len: Option<usize>| {
+            hex::decode(value)
+                .ok()
+                .filter(|bytes| len.is_none() || len == Some(bytes.len()))
+                .ok_or_else(|| Error::Backup(format!("invalid secrets {}", name)))
+        };
+        let salt = field("salt", &self.salt, Some(SALT_LEN))?;
+        let nonce = field("nonce", &self.nonce, Some(NONCE_LEN))?;
+        let ciphertext = field("ciphertext", &self.ciphertext, None)?;
+
+        let kdf = BackupKdf::new(self.m_cost, self.t_cost, self.p_cost);
+        let key = kdf.derive(password, &salt)?;
+        let plaintext = XChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &wallet.associated_data(&kdf),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| Error::WrongPassword)?;
+        serde_json::from_slice(&plaintext).map_err(|e| Error::Backup(e.to_string()))
+    }
+}
+
+/// The secrets an export may carry.
+#[derive(Serialize, Deserialize)]
+struct Secrets {
+    #[serde(
+        serialize_with = "serialize_mnemonic",
+        deserialize_with = "deserialize_mnemonic"
+    )]
+    mnemonic: Zeroizing<String>,
+    #[serde(
+        serialize_with = "serialize_language",
+        deserialize_with = "deserialize_language"
+    )]
+    language: Language,
+}
+
+/// Formats the hardened account path of `purpose`, `coin_type` and `account`.
+fn account_path(purpose: Purpose, coin_type: CoinType, account: u32) -> String {
+    format!("m/{}'/{}'/{}'", purpose.value(), coin_type.index(), account)
+}
+
+/// Returns the export name of `network`.
+fn network_name(network: Network) -> &'static str {
+    match network {
+        Network::BitcoinMainnet => "mainnet",
+        Network::BitcoinTestnet => "testnet",
+    }
+}
+
+mod network_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        network: &Network,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(network_name(*network))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Network, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.as_str() {
+            "mainnet" => Ok(Network::BitcoinMainnet),
+            "testnet" => Ok(Network::BitcoinTestnet),
+            _ => Err(serde::de::Error::custom(format!(
+                "Unknown network: {}",
+                name
+            ))),
+        }
+    }
+}
+
+mod fingerprint_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        fingerprint: &[u8; 4],
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(fingerprint))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<[u8; 4], D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let mut fingerprint = [0u8; 4];
+        hex::decode_to_slice(&value, &mut fingerprint)
+            .map_err(|_| serde::de::Error::custom(format!("Invalid fingerprint: {}", value)))?;
+        Ok(fingerprint)
+    }
+}
+
+mod purpose_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        purpose: &Purpose,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u32(purpose.value())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Purpose, D::Error> {
+        Purpose::try_from(u32::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+mod coin_type_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        coin_type: &CoinType,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u32(coin_type.index())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<CoinType, D::Error> {
+        CoinType::try_from(u32::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const LIGHT: BackupKdf = BackupKdf::new(64, 1, 1);
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    fn wallet() -> Wallet {
+        Wallet::from_english_mnemonic(MNEMONIC, "", Network::BitcoinMainnet).unwrap()
+    }
+
+    fn export() -> WalletExport {
+        let mut wallet = wallet();
+        let segwit = ExportedAccount::from(
+            wallet
+                .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+                .unwrap(),
+        )
+        .with_name("Savings")
+        .with_profile("native-segwit");
+        let taproot = ExportedAccount::from(
+            wallet
+                .get_account(Purpose::BIP86, CoinType::Bitcoin, 1)
+                .unwrap(),
+        );
+        let ethereum = ExportedAccount::from(
+            wallet
+                .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+                .unwrap(),
+        );
+        WalletExport::new(
+            WalletDescriptor::from_wallet("Main", &wallet).with_created_at(1_700_000_000),
+        )
+        .with_profile(
+            DerivationProfile::new("native-segwit", Purpose::BIP84, CoinType::Bitcoin)
+                .with_gap_limit(50),
+        )
+        .with_account(segwit)
+        .with_account(taproot)
+        .with_account(ethereum)
+        .with_label(ExportedLabel::new(
+            LabelType::Addr,
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            "Savings",
+        ))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let export = export();
+        let json = export.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["format"], "khodpay-wallet");
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["wallet"]["network"], "mainnet");
+        assert_eq!(value["wallet"]["fingerprint"], "73c5da0a");
+        assert_eq!(value["accounts"][0]["path"], "m/84'/0'/0'");
+        assert_eq!(value["accounts"][0]["xpub"], ZPUB);
+        assert_eq!(value["accounts"][1]["path"], "m/86'/0'/1'");
+        assert!(value["accounts"][1]["xpub"]
+            .as_str()
+            .unwrap()
+            .starts_with("xpub"));
+        assert_eq!(value["accounts"][2]["path"], "m/44'/60'/0'");
+        assert_eq!(value["labels"][0]["type"], "addr");
+        assert!(value.get("secrets").is_none());
+
+        let imported = WalletExport::from_json(&json).unwrap();
+        assert_eq!(imported, export);
+        assert_eq!(imported.accounts()[1].purpose(), Purpose::BIP86);
+        assert_eq!(imported.profiles()[0].gap_limit(), 50);
+        assert_eq!(imported.wallet().created_at(), Some(1_700_000_000));
+        assert!(!imported.has_secrets());
+        assert!(matches!(
+            imported.to_wallet("pw", ""),
+            Err(Error::Interchange(_))
+        ));
+    }
+
+    #[test]
+    fn test_secrets() {
+        let export = export()
+            .with_secrets_kdf(MNEMONIC, Language::English, "pw", LIGHT)
+            .unwrap();
+        let json = export.to_json().unwrap();
+        assert!(!json.contains("abandon"));
+        assert!(!format!("{:?}", export).contains("ciphertext"));
+
+        let imported = WalletExport::from_json(&json).unwrap();
+        let restored = imported.to_wallet("pw", "").unwrap();
+        assert_eq!(restored.master_key(), wallet().master_key());
+        assert_eq!(restored.cached_account_count(), 3);
+
+        assert!(matches!(
+            imported.to_wallet("pw2", ""),
+            Err(Error::WrongPassword)
+        ));
+        // A passphrase gives another wallet
+        assert!(matches!(
+            imported.to_wallet("pw", "TREZOR"),
+            Err(Error::Interchange(_))
+        ));
+
+        // The secrets are bound to the wallet they were exported with
+        let moved = json.replace("73c5da0a", "00000000");
+        let moved = WalletExport::from_json(&moved).unwrap();
+        assert!(matches!(
+            moved.to_wallet("pw", ""),
+            Err(Error::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_rejects() {
+        let json = export().to_json().unwrap();
+        for (from, to) in [
+            ("\"khodpay-wallet\"", "\"other-wallet\""),
+            ("\"version\": 1", "\"version\": 2"),
+            ("m/84'/0'/0'", "m/84'/0'/1'"),
+            ("\"mainnet\"", "\"testnet\""),
+            ("\"profile\": \"native-segwit\"", "\"profile\": \"legacy\""),
+            ("\"purpose\": 84", "\"purpose\": 49"),
+            ("\"73c5da0a\"", "\"73c5da\""),
+        ] {
+            let changed = json.replace(from, to);
+            assert_ne!(changed, json, "{}", from);
+            assert!(
+                matches!(
+                    WalletExport::from_json(&changed),
+                    Err(Error::Interchange(_))
+                ),
+                "{}",
+                to
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_fields_ignored() {
+        let json = format!(
+            r#"{{
+                "format": "khodpay-wallet",
+                "version": 1,
+                "wallet": {{ "name": "Imported", "network": "mainnet", "fingerprint": "73c5da0a", "color": "blue" }},
+                "accounts": [{{ "purpose": 84, "coin_type": 0, "account": 0, "path": "m/84'/0'/0'", "xpub": "{}", "balance": 5 }}],
+                "extension": {{}}
+            }}"#,
+            ZPUB
+        );
+        let export = WalletExport::from_json(&json).unwrap();
+        assert_eq!(export.wallet().name(), "Imported");
+        assert_eq!(export.accounts()[0].watch_only().to_string(), ZPUB);
+        assert_eq!(export.accounts()[0].name(), None);
+    }
+}
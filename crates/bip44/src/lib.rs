@@ -12,6 +12,11 @@
 //! - **Builder Pattern**: Fluent API for wallet construction
 //! - **Serialization**: Optional serde support for persistence
 //! - **Type Safety**: Strong typing for paths, chains, and coin types
+//! - **Silent Payments**: BIP-352 static addresses and sender-side output derivation
+//! - **Encrypted Backups**: Versioned backup files for the mnemonic, accounts and labels (`backup` feature)
+//! - **Wallet Export**: Versioned JSON interchange format for accounts, derivation profiles and labels,
+//!   with the mnemonic optional and encrypted (`backup` feature)
+//! - **Watch-Only Accounts**: Import `xpub`/`ypub`/`zpub` account keys and derive public keys
 //!
 //! ## Quick Start
 //!
@@ -160,34 +165,53 @@
 //!
 //! ## Optional Features
 //!
-//! - `serde`: Enable serialization support for paths and metadata
+//! - `serde`: Enable serialization support for paths, metadata and discovery reports
+//! - `backup`: Encrypted wallet backups ([`Backup`]) with Argon2id and XChaCha20-Poly1305
 
 #![warn(missing_docs)]
 #![warn(rustdoc::broken_intra_doc_links)]
 #![deny(unsafe_code)]
 
 mod account;
+#[cfg(feature = "backup")]
+mod backup;
 mod builder;
 mod derived;
 mod discovery;
 mod error;
+#[cfg(feature = "backup")]
+mod interchange;
 mod iterator;
 mod path;
+mod silent_payment;
 mod types;
 mod wallet;
+mod watch_only;
 
 pub use account::{Account, AccountMetadata};
+#[cfg(feature = "backup")]
+pub use backup::{Backup, BackupKdf};
 pub use builder::WalletBuilder;
 pub use derived::DerivedAddress;
 pub use discovery::{
-    AccountDiscovery, AccountScanResult, AccountScanner, ChainScanResult, GapLimitChecker,
-    MockBlockchain, DEFAULT_GAP_LIMIT,
+    AccountDiscovery, AccountScanResult, AccountScanner, ChainScanResult, DiscoveryProgress,
+    DiscoveryReport, GapLimitChecker, MockBlockchain, DEFAULT_GAP_LIMIT,
 };
 pub use error::Error;
+#[cfg(feature = "backup")]
+pub use interchange::{
+    DerivationProfile, ExportedAccount, ExportedLabel, LabelType, WalletDescriptor, WalletExport,
+    EXPORT_FORMAT, EXPORT_VERSION,
+};
 pub use iterator::AddressIterator;
 pub use path::{Bip44Path, Bip44PathBuilder};
+pub use silent_payment::{
+    derive_outputs, OutPoint, SilentPaymentAddress, SilentPaymentInput, SilentPaymentKeys,
+    SilentPaymentOutput,
+};
 pub use types::{Chain, CoinType, Purpose};
 pub use wallet::Wallet;
+pub use watch_only::WatchOnlyAccount;
 
 // Re-export Language from BIP39 for convenience
 pub use khodpay_bip39::Language;
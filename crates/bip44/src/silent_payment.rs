@@ -0,0 +1,1136 @@
+//! BIP-352 silent payments.
+//!
+//! This module implements [BIP-352](https://github.com/bitcoin/bips/blob/master/bip-0352.mediawiki)
+//! silent payment address generation and sender-side output derivation.
+//!
+//! A silent payment address is a static, reusable address made of two public
+//! keys: a *scan* key and a *spend* key. Senders combine the recipient's keys
+//! with the private keys of their own transaction inputs to derive a fresh
+//! taproot output key, so payments to the same address are not linkable
+//! on-chain.
+//!
+//! # Key Derivation
+//!
+//! Receiver keys are derived from the wallet master key as specified by BIP-352:
+//!
+//! ```text
+//! scan:  m / 352' / coin_type' / account' / 1' / 0
+//! spend: m / 352' / coin_type' / account' / 0' / 0
+//! ```
+//!
+//! where `coin_type` is `0'` for mainnet and `1'` for testnet.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_bip44::{SilentPaymentAddress, SilentPaymentKeys, Wallet};
+//! use khodpay_bip32::Network;
+//!
+//! let seed = [0u8; 64];
+//! let wallet = Wallet::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+//!
+//! let keys = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+//! let address = keys.address();
+//!
+//! let encoded = address.to_string();
+//! assert!(encoded.starts_with("sp1q"));
+//!
+//! let parsed: SilentPaymentAddress = encoded.parse().unwrap();
+//! assert_eq!(parsed, address);
+//! ```
+
+use crate::{Error, Result, Wallet};
+use bech32::primitives::decode::CheckedHrpstring;
+use bech32::{Bech32m, ByteIterExt, Fe32, Fe32IterExt, Hrp};
+use khodpay_bip32::{ChildNumber, Network, PrivateKey, PublicKey};
+use secp256k1::{Parity, Scalar, SecretKey, SECP256K1};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// BIP-44 style purpose used for silent payment key derivation.
+const SILENT_PAYMENT_PURPOSE: u32 = 352;
+
+/// Human-readable part for mainnet silent payment addresses.
+const HRP_MAINNET: &str = "sp";
+
+/// Human-readable part for testnet silent payment addresses.
+const HRP_TESTNET: &str = "tsp";
+
+/// Length of the version 0 address payload (`B_scan || B_spend`).
+const PAYLOAD_LENGTH: usize = 66;
+
+/// Computes a BIP-340 style tagged hash: `SHA256(SHA256(tag) || SHA256(tag) || msg)`.
+fn tagged_hash(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(msg);
+    hasher.finalize().into()
+}
+
+/// Interprets a 32-byte hash as a secp256k1 scalar.
+fn hash_to_scalar(hash: [u8; 32]) -> Result<Scalar> {
+    Scalar::from_be_bytes(hash)
+        .map_err(|_| Error::SilentPayment("Hash is not a valid curve scalar".to_string()))
+}
+
+/// Maps a secp256k1 error into a silent payment error with context.
+fn secp_error(context: &str, e: secp256k1::Error) -> Error {
+    Error::SilentPayment(format!("{}: {}", context, e))
+}
+
+/// A BIP-352 silent payment address.
+///
+/// The address encodes the recipient's scan public key and (possibly labeled)
+/// spend public key as a bech32m string with the `sp` (mainnet) or `tsp`
+/// (testnet) human-readable part.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip44::{SilentPaymentKeys, Wallet};
+/// use khodpay_bip32::Network;
+///
+/// let seed = [1u8; 64];
+/// let wallet = Wallet::from_seed(&seed, Network::BitcoinTestnet).unwrap();
+/// let address = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap().address();
+///
+/// assert!(address.to_string().starts_with("tsp1q"));
+/// assert_eq!(address.network(), Network::BitcoinTestnet);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SilentPaymentAddress {
+    /// The recipient's scan public key (`B_scan`)
+    scan_key: PublicKey,
+    /// The recipient's spend public key (`B_spend` or labeled `B_m`)
+    spend_key: PublicKey,
+    /// The network this address is valid on
+    network: Network,
+}
+
+impl SilentPaymentAddress {
+    /// Creates a silent payment address from its scan and spend public keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `scan_key` - The recipient's scan public key
+    /// * `spend_key` - The recipient's spend public key
+    /// * `network` - The network the address is intended for
+    pub fn new(scan_key: PublicKey, spend_key: PublicKey, network: Network) -> Self {
+        Self {
+            scan_key,
+            spend_key,
+            network,
+        }
+    }
+
+    /// Returns the scan public key.
+    pub fn scan_key(&self) -> &PublicKey {
+        &self.scan_key
+    }
+
+    /// Returns the spend public key.
+    pub fn spend_key(&self) -> &PublicKey {
+        &self.spend_key
+    }
+
+    /// Returns the network this address is valid on.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the human-readable part used for this address's network.
+    fn hrp(&self) -> &'static str {
+        match self.network {
+            Network::BitcoinMainnet => HRP_MAINNET,
+            Network::BitcoinTestnet => HRP_TESTNET,
+        }
+    }
+}
+
+impl fmt::Display for SilentPaymentAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hrp = Hrp::parse(self.hrp()).map_err(|_| fmt::Error)?;
+
+        let mut payload = Vec::with_capacity(PAYLOAD_LENGTH);
+        payload.extend_from_slice(&self.scan_key.to_bytes());
+        payload.extend_from_slice(&self.spend_key.to_bytes());
+
+        for c in payload
+            .iter()
+            .copied()
+            .bytes_to_fes()
+            .with_checksum::<Bech32m>(&hrp)
+            .with_witness_version(Fe32::Q)
+            .chars()
+        {
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SilentPaymentAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut checked = CheckedHrpstring::new::<Bech32m>(s)
+            .map_err(|e| Error::SilentPayment(format!("Invalid bech32m encoding: {}", e)))?;
+
+        let network = match checked.hrp().to_lowercase().as_str() {
+            HRP_MAINNET => Network::BitcoinMainnet,
+            HRP_TESTNET => Network::BitcoinTestnet,
+            other => {
+                return Err(Error::SilentPayment(format!(
+                    "Unknown human-readable part: {}",
+                    other
+                )))
+            }
+        };
+
+        let version = checked
+            .remove_witness_version()
+            .ok_or_else(|| Error::SilentPayment("Missing address version".to_string()))?;
+        if version != Fe32::Q {
+            return Err(Error::SilentPayment(format!(
+                "Unsupported address version: {}",
+                version.to_u8()
+            )));
+        }
+
+        let payload: Vec<u8> = checked.byte_iter().collect();
+        if payload.len() != PAYLOAD_LENGTH {
+            return Err(Error::SilentPayment(format!(
+                "Invalid payload length: expected {}, got {}",
+                PAYLOAD_LENGTH,
+                payload.len()
+            )));
+        }
+
+        let scan_key = PublicKey::from_bytes(&payload[..33])?;
+        let spend_key = PublicKey::from_bytes(&payload[33..])?;
+
+        Ok(Self::new(scan_key, spend_key, network))
+    }
+}
+
+/// Receiver key pair for BIP-352 silent payments.
+///
+/// Holds the scan and spend private keys for one account and produces the
+/// corresponding [`SilentPaymentAddress`].
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip44::{SilentPaymentKeys, Wallet};
+/// use khodpay_bip32::Network;
+///
+/// let seed = [0u8; 64];
+/// let wallet = Wallet::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+/// let keys = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+///
+/// // A labeled address shares the scan key but has a different spend key
+/// let labeled = keys.labeled_address(1).unwrap();
+/// assert_eq!(labeled.scan_key(), keys.address().scan_key());
+/// assert_ne!(labeled.spend_key(), keys.address().spend_key());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SilentPaymentKeys {
+    /// The scan private key (`b_scan`)
+    scan_key: PrivateKey,
+    /// The spend private key (`b_spend`)
+    spend_key: PrivateKey,
+    /// The network these keys belong to
+    network: Network,
+}
+
+impl SilentPaymentKeys {
+    /// Creates silent payment keys from raw scan and spend private keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `scan_key` - The scan private key
+    /// * `spend_key` - The spend private key
+    /// * `network` - The network the keys are used on
+    pub fn new(scan_key: PrivateKey, spend_key: PrivateKey, network: Network) -> Self {
+        Self {
+            scan_key,
+            spend_key,
+            network,
+        }
+    }
+
+    /// Derives silent payment keys for an account from a wallet.
+    ///
+    /// Uses `m/352'/coin_type'/account'/1'/0` for the scan key and
+    /// `m/352'/coin_type'/account'/0'/0` for the spend key.
+    ///
+    /// # Arguments
+    ///
+    /// * `wallet` - The wallet holding the master key
+    /// * `account_index` - The account index (hardened)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key derivation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_bip44::{SilentPaymentKeys, Wallet};
+    /// use khodpay_bip32::Network;
+    ///
+    /// let seed = [0u8; 64];
+    /// let wallet = Wallet::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+    ///
+    /// let account0 = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+    /// let account1 = SilentPaymentKeys::from_wallet(&wallet, 1).unwrap();
+    /// assert_ne!(account0.address(), account1.address());
+    /// ```
+    pub fn from_wallet(wallet: &Wallet, account_index: u32) -> Result<Self> {
+        let network = wallet.network();
+        let coin_type = match network {
+            Network::BitcoinMainnet => 0,
+            Network::BitcoinTestnet => 1,
+        };
+
+        let account_key = wallet
+            .master_key()
+            .derive_child(ChildNumber::Hardened(SILENT_PAYMENT_PURPOSE))
+            .and_then(|k| k.derive_child(ChildNumber::Hardened(coin_type)))
+            .and_then(|k| k.derive_child(ChildNumber::Hardened(account_index)))
+            .map_err(|e| Error::KeyDerivation(format!("Failed to derive account key: {}", e)))?;
+
+        let scan_key = account_key
+            .derive_child(ChildNumber::Hardened(1))
+            .and_then(|k| k.derive_child(ChildNumber::Normal(0)))
+            .map_err(|e| Error::KeyDerivation(format!("Failed to derive scan key: {}", e)))?;
+
+        let spend_key = account_key
+            .derive_child(ChildNumber::Hardened(0))
+            .and_then(|k| k.derive_child(ChildNumber::Normal(0)))
+            .map_err(|e| Error::KeyDerivation(format!("Failed to derive spend key: {}", e)))?;
+
+        Ok(Self::new(
+            scan_key.private_key().clone(),
+            spend_key.private_key().clone(),
+            network,
+        ))
+    }
+
+    /// Returns the scan private key.
+    ///
+    /// The scan key can be handed to a watch-only scanner; it is sufficient
+    /// to detect incoming payments but not to spend them.
+    pub fn scan_private_key(&self) -> &PrivateKey {
+        &self.scan_key
+    }
+
+    /// Returns the spend private key.
+    pub fn spend_private_key(&self) -> &PrivateKey {
+        &self.spend_key
+    }
+
+    /// Returns the network these keys belong to.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the unlabeled silent payment address for these keys.
+    pub fn address(&self) -> SilentPaymentAddress {
+        SilentPaymentAddress::new(
+            PublicKey::from_private_key(&self.scan_key),
+            PublicKey::from_private_key(&self.spend_key),
+            self.network,
+        )
+    }
+
+    /// Returns the labeled silent payment address for label `m`.
+    ///
+    /// The labeled spend key is `B_m = B_spend + hash_BIP0352/Label(b_scan || m)·G`.
+    /// Label `0` is reserved by BIP-352 for change outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tweak produces an invalid key (negligible probability).
+    pub fn labeled_address(&self, m: u32) -> Result<SilentPaymentAddress> {
+        let mut msg = Vec::with_capacity(36);
+        msg.extend_from_slice(&self.scan_key.to_bytes());
+        msg.extend_from_slice(&m.to_be_bytes());
+        let label = tagged_hash("BIP0352/Label", &msg);
+
+        let spend_key = PublicKey::from_private_key(&self.spend_key).tweak_add(&label)?;
+
+        Ok(SilentPaymentAddress::new(
+            PublicKey::from_private_key(&self.scan_key),
+            spend_key,
+            self.network,
+        ))
+    }
+}
+
+/// A reference to a transaction output being spent.
+///
+/// `txid` is stored in its serialized (internal, little-endian) byte order,
+/// which is the order BIP-352 uses when selecting the smallest outpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutPoint {
+    /// The transaction id in serialized byte order
+    pub txid: [u8; 32],
+    /// The output index within the transaction
+    pub vout: u32,
+}
+
+impl OutPoint {
+    /// Creates a new outpoint.
+    pub fn new(txid: [u8; 32], vout: u32) -> Self {
+        Self { txid, vout }
+    }
+
+    /// Serializes the outpoint as `txid || vout` (little-endian).
+    fn to_bytes(self) -> [u8; 36] {
+        let mut bytes = [0u8; 36];
+        bytes[..32].copy_from_slice(&self.txid);
+        bytes[32..].copy_from_slice(&self.vout.to_le_bytes());
+        bytes
+    }
+}
+
+/// A transaction input contributing to silent payment output derivation.
+///
+/// Only inputs eligible under BIP-352 (P2TR, P2WPKH, P2SH-P2WPKH and P2PKH)
+/// should be passed to [`derive_outputs`]; callers are responsible for
+/// filtering out other script types.
+#[derive(Debug, Clone)]
+pub struct SilentPaymentInput {
+    /// The outpoint being spent
+    outpoint: OutPoint,
+    /// The private key unlocking the input
+    private_key: PrivateKey,
+    /// Whether the input is a taproot (P2TR) key-path spend
+    is_taproot: bool,
+}
+
+impl SilentPaymentInput {
+    /// Creates a new silent payment input.
+    ///
+    /// # Arguments
+    ///
+    /// * `outpoint` - The outpoint being spent
+    /// * `private_key` - The private key unlocking the input
+    /// * `is_taproot` - `true` for P2TR inputs, whose keys are normalized to even y
+    pub fn new(outpoint: OutPoint, private_key: PrivateKey, is_taproot: bool) -> Self {
+        Self {
+            outpoint,
+            private_key,
+            is_taproot,
+        }
+    }
+
+    /// Returns the outpoint being spent.
+    pub fn outpoint(&self) -> OutPoint {
+        self.outpoint
+    }
+
+    /// Returns `true` if this is a taproot input.
+    pub fn is_taproot(&self) -> bool {
+        self.is_taproot
+    }
+
+    /// Returns the private key, negated if required by BIP-340 even-y normalization.
+    fn effective_secret_key(&self) -> SecretKey {
+        let secret_key = *self.private_key.secret_key();
+        if self.is_taproot {
+            let (_, parity) = secret_key.x_only_public_key(SECP256K1);
+            if parity == Parity::Odd {
+                return secret_key.negate();
+            }
+        }
+        secret_key
+    }
+}
+
+/// A taproot output derived for a silent payment recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SilentPaymentOutput {
+    /// The recipient this output pays
+    pub recipient: SilentPaymentAddress,
+    /// The x-only taproot output key (`P_k`)
+    pub output_key: [u8; 32],
+}
+
+/// Derives the taproot output keys a sender must create to pay silent payment recipients.
+///
+/// Implements the BIP-352 sender algorithm: the input private keys are summed
+/// into `a`, an input hash is committed to the smallest outpoint and `A = a·G`,
+/// and for each recipient the shared secret `input_hash·a·B_scan` is tweaked
+/// into `P_k = B_spend + hash_BIP0352/SharedSecret(ecdh || k)·G`. Recipients
+/// sharing a scan key receive consecutive `k` values.
+///
+/// Outputs are returned in the same order as `recipients`.
+///
+/// # Arguments
+///
+/// * `inputs` - The eligible inputs of the transaction being built
+/// * `recipients` - The silent payment addresses to pay
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - `inputs` is empty
+/// - The input private keys sum to zero
+/// - A tweak produces an invalid key (negligible probability)
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip44::{derive_outputs, OutPoint, SilentPaymentInput, SilentPaymentKeys, Wallet};
+/// use khodpay_bip32::{Network, PrivateKey};
+///
+/// let wallet = Wallet::from_seed(&[0u8; 64], Network::BitcoinMainnet).unwrap();
+/// let recipient = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap().address();
+///
+/// let input = SilentPaymentInput::new(
+///     OutPoint::new([7u8; 32], 0),
+///     PrivateKey::from_array([3u8; 32]).unwrap(),
+///     false,
+/// );
+///
+/// let outputs = derive_outputs(&[input], &[recipient]).unwrap();
+/// assert_eq!(outputs.len(), 1);
+/// ```
+pub fn derive_outputs(
+    inputs: &[SilentPaymentInput],
+    recipients: &[SilentPaymentAddress],
+) -> Result<Vec<SilentPaymentOutput>> {
+    let (first, rest) = inputs
+        .split_first()
+        .ok_or_else(|| Error::SilentPayment("No eligible inputs".to_string()))?;
+
+    // a = sum of (normalized) input private keys
+    let mut input_sum = first.effective_secret_key();
+    for input in rest {
+        let tweak = Scalar::from(input.effective_secret_key());
+        input_sum = input_sum
+            .add_tweak(&tweak)
+            .map_err(|e| secp_error("Input keys sum to zero", e))?;
+    }
+    let input_sum_point = input_sum.public_key(SECP256K1);
+
+    // input_hash = hash_BIP0352/Inputs(outpoint_L || A)
+    let smallest_outpoint = inputs
+        .iter()
+        .map(|input| input.outpoint.to_bytes())
+        .min()
+        .expect("inputs is non-empty");
+    let mut msg = Vec::with_capacity(36 + 33);
+    msg.extend_from_slice(&smallest_outpoint);
+    msg.extend_from_slice(&input_sum_point.serialize());
+    let input_hash = hash_to_scalar(tagged_hash("BIP0352/Inputs", &msg))?;
+
+    let tweaked_sum = input_sum
+        .mul_tweak(&input_hash)
+        .map_err(|e| secp_error("Invalid input hash", e))?;
+    let tweaked_scalar = Scalar::from(tweaked_sum);
+
+    let mut counters: HashMap<[u8; 33], u32> = HashMap::new();
+    let mut outputs = Vec::with_capacity(recipients.len());
+
+    for recipient in recipients {
+        let scan_bytes = recipient.scan_key.to_bytes();
+        let k = counters.entry(scan_bytes).or_insert(0);
+
+        let shared_secret = recipient
+            .scan_key
+            .public_key()
+            .mul_tweak(SECP256K1, &tweaked_scalar)
+            .map_err(|e| secp_error("Failed to compute shared secret", e))?;
+
+        let mut msg = Vec::with_capacity(33 + 4);
+        msg.extend_from_slice(&shared_secret.serialize());
+        msg.extend_from_slice(&k.to_be_bytes());
+        let t_k = hash_to_scalar(tagged_hash("BIP0352/SharedSecret", &msg))?;
+
+        let output_point = recipient
+            .spend_key
+            .public_key()
+            .add_exp_tweak(SECP256K1, &t_k)
+            .map_err(|e| secp_error("Failed to tweak spend key", e))?;
+
+        outputs.push(SilentPaymentOutput {
+            recipient: recipient.clone(),
+            output_key: output_point.x_only_public_key().0.serialize(),
+        });
+        *k += 1;
+    }
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_wallet(network: Network) -> Wallet {
+        Wallet::from_english_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "",
+            network,
+        )
+        .unwrap()
+    }
+
+    fn test_input(seed_byte: u8, vout: u32, is_taproot: bool) -> SilentPaymentInput {
+        SilentPaymentInput::new(
+            OutPoint::new([seed_byte; 32], vout),
+            PrivateKey::from_array([seed_byte; 32]).unwrap(),
+            is_taproot,
+        )
+    }
+
+    /// Receiver-side computation of `P_k` for verifying sender derivation.
+    fn receiver_output(
+        keys: &SilentPaymentKeys,
+        inputs: &[SilentPaymentInput],
+        spend_tweak: Option<[u8; 32]>,
+        k: u32,
+    ) -> [u8; 32] {
+        // A = sum of input public keys (taproot keys normalized to even y)
+        let points: Vec<secp256k1::PublicKey> = inputs
+            .iter()
+            .map(|i| i.effective_secret_key().public_key(SECP256K1))
+            .collect();
+        let refs: Vec<&secp256k1::PublicKey> = points.iter().collect();
+        let input_sum_point = secp256k1::PublicKey::combine_keys(&refs).unwrap();
+
+        let smallest = inputs.iter().map(|i| i.outpoint.to_bytes()).min().unwrap();
+        let mut msg = smallest.to_vec();
+        msg.extend_from_slice(&input_sum_point.serialize());
+        let input_hash = hash_to_scalar(tagged_hash("BIP0352/Inputs", &msg)).unwrap();
+
+        // ecdh = input_hash·b_scan·A
+        let scan_scalar = Scalar::from(
+            keys.scan_private_key()
+                .secret_key()
+                .mul_tweak(&input_hash)
+                .unwrap(),
+        );
+        let shared = input_sum_point.mul_tweak(SECP256K1, &scan_scalar).unwrap();
+
+        let mut msg = shared.serialize().to_vec();
+        msg.extend_from_slice(&k.to_be_bytes());
+        let t_k = hash_to_scalar(tagged_hash("BIP0352/SharedSecret", &msg)).unwrap();
+
+        let mut spend = *keys.spend_private_key().secret_key();
+        if let Some(tweak) = spend_tweak {
+            spend = spend
+                .add_tweak(&Scalar::from_be_bytes(tweak).unwrap())
+                .unwrap();
+        }
+        let output_secret = spend.add_tweak(&t_k).unwrap();
+        output_secret.x_only_public_key(SECP256K1).0.serialize()
+    }
+
+    // ==================== Tagged Hash Tests ====================
+
+    #[test]
+    fn test_tagged_hash_differs_by_tag() {
+        let a = tagged_hash("BIP0352/Inputs", b"data");
+        let b = tagged_hash("BIP0352/SharedSecret", b"data");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_tagged_hash_matches_manual_construction() {
+        let tag = Sha256::digest(b"BIP0352/Label");
+        let mut preimage = tag.to_vec();
+        preimage.extend_from_slice(&tag);
+        preimage.extend_from_slice(b"msg");
+        let expected: [u8; 32] = Sha256::digest(&preimage).into();
+
+        assert_eq!(tagged_hash("BIP0352/Label", b"msg"), expected);
+    }
+
+    // ==================== Address Tests ====================
+
+    #[test]
+    fn test_address_mainnet_prefix() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let encoded = keys.address().to_string();
+
+        assert!(encoded.starts_with("sp1q"));
+        // hrp (2) + separator (1) + version (1) + 66 bytes (106 chars) + checksum (6)
+        assert_eq!(encoded.len(), 116);
+    }
+
+    #[test]
+    fn test_address_testnet_prefix() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinTestnet), 0).unwrap();
+        let encoded = keys.address().to_string();
+
+        assert!(encoded.starts_with("tsp1q"));
+        assert_eq!(keys.network(), Network::BitcoinTestnet);
+    }
+
+    #[test]
+    fn test_address_roundtrip() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let address = keys.address();
+
+        let parsed: SilentPaymentAddress = address.to_string().parse().unwrap();
+        assert_eq!(parsed, address);
+        assert_eq!(parsed.network(), Network::BitcoinMainnet);
+    }
+
+    #[test]
+    fn test_address_roundtrip_testnet() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinTestnet), 3).unwrap();
+        let address = keys.labeled_address(7).unwrap();
+
+        let parsed: SilentPaymentAddress = address.to_string().parse().unwrap();
+        assert_eq!(parsed, address);
+    }
+
+    #[test]
+    fn test_address_parse_case_insensitive() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let encoded = keys.address().to_string().to_uppercase();
+
+        let parsed: SilentPaymentAddress = encoded.parse().unwrap();
+        assert_eq!(parsed, keys.address());
+    }
+
+    #[test]
+    fn test_address_parse_bad_checksum() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let mut encoded = keys.address().to_string();
+        let last = encoded.pop().unwrap();
+        encoded.push(if last == 'q' { 'p' } else { 'q' });
+
+        assert!(matches!(
+            encoded.parse::<SilentPaymentAddress>(),
+            Err(Error::SilentPayment(_))
+        ));
+    }
+
+    #[test]
+    fn test_address_parse_wrong_hrp() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let mut payload = keys.address().scan_key().to_bytes().to_vec();
+        payload.extend_from_slice(&keys.address().spend_key().to_bytes());
+
+        let hrp = Hrp::parse("bc").unwrap();
+        let encoded: String = payload
+            .iter()
+            .copied()
+            .bytes_to_fes()
+            .with_checksum::<Bech32m>(&hrp)
+            .with_witness_version(Fe32::Q)
+            .chars()
+            .collect();
+
+        let result = encoded.parse::<SilentPaymentAddress>();
+        assert_eq!(
+            result.unwrap_err(),
+            Error::SilentPayment("Unknown human-readable part: bc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_address_parse_unsupported_version() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let mut payload = keys.address().scan_key().to_bytes().to_vec();
+        payload.extend_from_slice(&keys.address().spend_key().to_bytes());
+
+        let hrp = Hrp::parse("sp").unwrap();
+        let encoded: String = payload
+            .iter()
+            .copied()
+            .bytes_to_fes()
+            .with_checksum::<Bech32m>(&hrp)
+            .with_witness_version(Fe32::P)
+            .chars()
+            .collect();
+
+        let result = encoded.parse::<SilentPaymentAddress>();
+        assert_eq!(
+            result.unwrap_err(),
+            Error::SilentPayment("Unsupported address version: 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_address_parse_invalid_payload_length() {
+        let hrp = Hrp::parse("sp").unwrap();
+        let encoded: String = [2u8; 33]
+            .iter()
+            .copied()
+            .bytes_to_fes()
+            .with_checksum::<Bech32m>(&hrp)
+            .with_witness_version(Fe32::Q)
+            .chars()
+            .collect();
+
+        let result = encoded.parse::<SilentPaymentAddress>();
+        assert_eq!(
+            result.unwrap_err(),
+            Error::SilentPayment("Invalid payload length: expected 66, got 33".to_string())
+        );
+    }
+
+    // ==================== Key Derivation Tests ====================
+
+    #[test]
+    fn test_keys_deterministic() {
+        let wallet = test_wallet(Network::BitcoinMainnet);
+        let keys1 = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+        let keys2 = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+
+        assert_eq!(keys1.address(), keys2.address());
+    }
+
+    #[test]
+    fn test_keys_scan_and_spend_differ() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let address = keys.address();
+
+        assert_ne!(address.scan_key(), address.spend_key());
+    }
+
+    #[test]
+    fn test_keys_derivation_paths() {
+        let wallet = test_wallet(Network::BitcoinMainnet);
+        let keys = SilentPaymentKeys::from_wallet(&wallet, 2).unwrap();
+
+        let scan_path = "m/352'/0'/2'/1'/0".parse().unwrap();
+        let spend_path = "m/352'/0'/2'/0'/0".parse().unwrap();
+        let scan = wallet.master_key().derive_path(&scan_path).unwrap();
+        let spend = wallet.master_key().derive_path(&spend_path).unwrap();
+
+        assert_eq!(keys.scan_private_key(), scan.private_key());
+        assert_eq!(keys.spend_private_key(), spend.private_key());
+    }
+
+    #[test]
+    fn test_keys_testnet_uses_coin_type_one() {
+        let wallet = test_wallet(Network::BitcoinTestnet);
+        let keys = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+
+        let scan_path = "m/352'/1'/0'/1'/0".parse().unwrap();
+        let scan = wallet.master_key().derive_path(&scan_path).unwrap();
+
+        assert_eq!(keys.scan_private_key(), scan.private_key());
+    }
+
+    #[test]
+    fn test_labeled_address_differs_per_label() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let label1 = keys.labeled_address(1).unwrap();
+        let label2 = keys.labeled_address(2).unwrap();
+
+        assert_eq!(label1.scan_key(), label2.scan_key());
+        assert_ne!(label1.spend_key(), label2.spend_key());
+        assert_ne!(label1.spend_key(), keys.address().spend_key());
+    }
+
+    // ==================== Output Derivation Tests ====================
+
+    #[test]
+    fn test_derive_outputs_no_inputs() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+
+        let result = derive_outputs(&[], &[keys.address()]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::SilentPayment("No eligible inputs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_outputs_no_recipients() {
+        let outputs = derive_outputs(&[test_input(1, 0, false)], &[]).unwrap();
+        assert!(outputs.is_empty());
+    }
+
+    #[test]
+    fn test_derive_outputs_matches_receiver() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let inputs = vec![test_input(1, 0, false), test_input(2, 1, false)];
+
+        let outputs = derive_outputs(&inputs, &[keys.address()]).unwrap();
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].recipient, keys.address());
+        assert_eq!(
+            outputs[0].output_key,
+            receiver_output(&keys, &inputs, None, 0)
+        );
+    }
+
+    #[test]
+    fn test_derive_outputs_taproot_inputs_match_receiver() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        // Powers of two can never cancel out after negation, so the sum stays non-zero
+        let inputs: Vec<_> = (0..6).map(|i| test_input(1 << i, i as u32, true)).collect();
+
+        // At least one of these keys must have odd y for the test to be meaningful
+        assert!(inputs.iter().any(|i| {
+            let (_, parity) = i.private_key.secret_key().x_only_public_key(SECP256K1);
+            parity == Parity::Odd
+        }));
+
+        let outputs = derive_outputs(&inputs, &[keys.address()]).unwrap();
+        assert_eq!(
+            outputs[0].output_key,
+            receiver_output(&keys, &inputs, None, 0)
+        );
+    }
+
+    #[test]
+    fn test_derive_outputs_same_scan_key_increments_k() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let inputs = vec![test_input(5, 0, false)];
+        let address = keys.address();
+
+        let outputs = derive_outputs(&inputs, &[address.clone(), address]).unwrap();
+
+        assert_eq!(outputs.len(), 2);
+        assert_ne!(outputs[0].output_key, outputs[1].output_key);
+        assert_eq!(
+            outputs[0].output_key,
+            receiver_output(&keys, &inputs, None, 0)
+        );
+        assert_eq!(
+            outputs[1].output_key,
+            receiver_output(&keys, &inputs, None, 1)
+        );
+    }
+
+    #[test]
+    fn test_derive_outputs_multiple_recipients() {
+        let wallet = test_wallet(Network::BitcoinMainnet);
+        let alice = SilentPaymentKeys::from_wallet(&wallet, 0).unwrap();
+        let bob = SilentPaymentKeys::from_wallet(&wallet, 1).unwrap();
+        let inputs = vec![test_input(9, 3, false)];
+
+        let outputs = derive_outputs(&inputs, &[alice.address(), bob.address()]).unwrap();
+
+        // Each recipient has its own scan key, so both start at k = 0
+        assert_eq!(
+            outputs[0].output_key,
+            receiver_output(&alice, &inputs, None, 0)
+        );
+        assert_eq!(
+            outputs[1].output_key,
+            receiver_output(&bob, &inputs, None, 0)
+        );
+    }
+
+    #[test]
+    fn test_derive_outputs_labeled_address() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let inputs = vec![test_input(4, 0, false)];
+        let labeled = keys.labeled_address(3).unwrap();
+
+        let mut msg = keys.scan_private_key().to_bytes().to_vec();
+        msg.extend_from_slice(&3u32.to_be_bytes());
+        let label = tagged_hash("BIP0352/Label", &msg);
+
+        let outputs = derive_outputs(&inputs, &[labeled]).unwrap();
+        assert_eq!(
+            outputs[0].output_key,
+            receiver_output(&keys, &inputs, Some(label), 0)
+        );
+    }
+
+    #[test]
+    fn test_derive_outputs_unlinkable_across_transactions() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let address = keys.address();
+
+        let tx1 =
+            derive_outputs(&[test_input(1, 0, false)], std::slice::from_ref(&address)).unwrap();
+        let tx2 = derive_outputs(&[test_input(2, 0, false)], &[address]).unwrap();
+
+        assert_ne!(tx1[0].output_key, tx2[0].output_key);
+    }
+
+    #[test]
+    fn test_derive_outputs_smallest_outpoint_is_order_independent() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let a = test_input(1, 0, false);
+        let b = test_input(2, 0, false);
+
+        let forward = derive_outputs(&[a.clone(), b.clone()], &[keys.address()]).unwrap();
+        let reverse = derive_outputs(&[b, a], &[keys.address()]).unwrap();
+
+        assert_eq!(forward, reverse);
+    }
+
+    #[test]
+    fn test_derive_outputs_keys_sum_to_zero() {
+        let keys =
+            SilentPaymentKeys::from_wallet(&test_wallet(Network::BitcoinMainnet), 0).unwrap();
+        let secret = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let a = SilentPaymentInput::new(OutPoint::new([1u8; 32], 0), secret.into(), false);
+        let b = SilentPaymentInput::new(OutPoint::new([2u8; 32], 0), secret.negate().into(), false);
+
+        let result = derive_outputs(&[a, b], &[keys.address()]);
+        assert!(matches!(result, Err(Error::SilentPayment(_))));
+    }
+
+    // ==================== BIP-352 Test Vectors ====================
+    //
+    // From bip-0352/send_and_receive_test_vectors.json. Txids are given in display
+    // order and reversed into serialized order here.
+
+    const VECTOR_SCAN_KEY: &str =
+        "0f694e068028a717f8af6b9411f9a133dd3565258714cc226594b34db90c1f2c";
+    const VECTOR_SPEND_KEY: &str =
+        "9d6ad855ce3417ef84e836892e5a56392bfba05fa5d97ccea30e266f540e08b3";
+    const VECTOR_ADDRESS: &str = "sp1qqgste7k9hx0qftg6qmwlkqtwuy6cycyavzmzj85c6qdfhjdpdjtdgqjuexzk6murw56suy3e0rd2cgqvycxttddwsvgxe2usfpxumr70xc9pkqwv";
+    const VECTOR_TXID_A: &str = "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16";
+    const VECTOR_TXID_B: &str = "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d";
+
+    fn vector_key(hex_key: &str) -> PrivateKey {
+        PrivateKey::from_bytes(&hex::decode(hex_key).unwrap()).unwrap()
+    }
+
+    fn vector_input(txid: &str, vout: u32, private_key: &str) -> SilentPaymentInput {
+        let mut txid: [u8; 32] = hex::decode(txid).unwrap().try_into().unwrap();
+        txid.reverse();
+        SilentPaymentInput::new(OutPoint::new(txid, vout), vector_key(private_key), false)
+    }
+
+    /// The two P2WPKH inputs shared by the multiple-output and label vectors.
+    fn vector_inputs() -> [SilentPaymentInput; 2] {
+        [
+            vector_input(
+                VECTOR_TXID_A,
+                0,
+                "eadc78165ff1f8ea94ad7cfdc54990738a4c53f6e0507b42154201b8e5dff3b1",
+            ),
+            vector_input(
+                VECTOR_TXID_B,
+                0,
+                "0378e95685b74565fa56751b84a32dfd18545d10d691641b8372e32164fad66a",
+            ),
+        ]
+    }
+
+    fn vector_keys() -> SilentPaymentKeys {
+        SilentPaymentKeys::new(
+            vector_key(VECTOR_SCAN_KEY),
+            vector_key(VECTOR_SPEND_KEY),
+            Network::BitcoinMainnet,
+        )
+    }
+
+    fn output_keys(outputs: &[SilentPaymentOutput]) -> Vec<String> {
+        outputs.iter().map(|o| hex::encode(o.output_key)).collect()
+    }
+
+    #[test]
+    fn test_vector_simple_send_two_inputs() {
+        let keys = vector_keys();
+        assert_eq!(keys.address().to_string(), VECTOR_ADDRESS);
+
+        let mut inputs = vec![
+            vector_input(
+                VECTOR_TXID_A,
+                0,
+                "eadc78165ff1f8ea94ad7cfdc54990738a4c53f6e0507b42154201b8e5dff3b1",
+            ),
+            vector_input(
+                VECTOR_TXID_B,
+                0,
+                "93f5ed907ad5b2bdbbdcb5d9116ebc0a4e1f92f910d5260237fa45a9408aad16",
+            ),
+        ];
+        let recipient: SilentPaymentAddress = VECTOR_ADDRESS.parse().unwrap();
+        let expected = ["3e9fce73d4e77a4809908e3c3a2e54ee147b9312dc5044a193d1fc85de46e3c1"];
+        assert_eq!(
+            output_keys(&derive_outputs(&inputs, std::slice::from_ref(&recipient)).unwrap()),
+            expected
+        );
+
+        // "Simple send: two inputs, order reversed"
+        inputs.reverse();
+        assert_eq!(
+            output_keys(&derive_outputs(&inputs, &[recipient]).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_vector_multiple_outputs_same_recipient() {
+        let recipient: SilentPaymentAddress = VECTOR_ADDRESS.parse().unwrap();
+        let outputs = derive_outputs(&vector_inputs(), &[recipient.clone(), recipient]).unwrap();
+        assert_eq!(
+            output_keys(&outputs),
+            [
+                "f207162b1a7abc51c42017bef055e9ec1efc3d3567cb720357e2b84325db33ac",
+                "e976a58fbd38aeb4e6093d4df02e9c1de0c4513ae0c588cef68cda5b2f8834ca",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vector_multiple_outputs_multiple_recipients() {
+        let first: SilentPaymentAddress = VECTOR_ADDRESS.parse().unwrap();
+        let second: SilentPaymentAddress = "sp1qqgrz6j0lcqnc04vxccydl0kpsj4frfje0ktmgcl2t346hkw30226xqupawdf48k8882j0strrvcmgg2kdawz53a54dd376ngdhak364hzcmynqtn"
+            .parse()
+            .unwrap();
+        let outputs = derive_outputs(
+            &vector_inputs(),
+            &[first.clone(), first, second.clone(), second],
+        )
+        .unwrap();
+        assert_eq!(
+            output_keys(&outputs),
+            [
+                "f207162b1a7abc51c42017bef055e9ec1efc3d3567cb720357e2b84325db33ac",
+                "e976a58fbd38aeb4e6093d4df02e9c1de0c4513ae0c588cef68cda5b2f8834ca",
+                "841792c33c9dc6193e76744134125d40add8f2f4a96475f28ba150be032d64e8",
+                "2e847bb01d1b491da512ddd760b8509617ee38057003d6115d00ba562451323a",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vector_labels() {
+        let keys = vector_keys();
+        assert_eq!(
+            keys.labeled_address(1).unwrap().to_string(),
+            "sp1qqgste7k9hx0qftg6qmwlkqtwuy6cycyavzmzj85c6qdfhjdpdjtdgqaxww2fnhrx05cghth75n0qcj59e3e2anscr0q9wyknjxtxycg07y3pevyj"
+        );
+
+        // "Receiving with labels: label with even parity"
+        let labeled = keys.labeled_address(2).unwrap();
+        let outputs = derive_outputs(&vector_inputs(), &[labeled]).unwrap();
+        assert_eq!(
+            output_keys(&outputs),
+            ["d014d4860f67d607d60b1af70e0ee236b99658b61bb769832acbbe87c374439a"]
+        );
+    }
+}
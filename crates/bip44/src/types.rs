@@ -210,6 +210,13 @@ impl fmt::Display for Purpose {
     }
 }
 
+#[cfg(feature = "tracing")]
+impl khodpay_tracing::LogSafe for Purpose {
+    fn log_value(&self) -> impl tracing::Value + '_ {
+        tracing::field::display(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -494,6 +501,13 @@ impl fmt::Display for Chain {
     }
 }
 
+#[cfg(feature = "tracing")]
+impl khodpay_tracing::LogSafe for Chain {
+    fn log_value(&self) -> impl tracing::Value + '_ {
+        tracing::field::display(self)
+    }
+}
+
 #[cfg(test)]
 mod chain_tests {
     use super::*;
@@ -753,6 +767,15 @@ pub enum CoinType {
     /// Path example: `m/44'/195'/0'/0/0`
     Tron,
 
+    /// XRP Ledger (XRP) - Coin type 144.
+    ///
+    /// Payment-focused ledger with a built-in decentralized exchange.
+    ///
+    /// Network: Mainnet  
+    /// Symbol: XRP  
+    /// Path example: `m/44'/144'/0'/0/0`
+    Ripple,
+
     /// Custom coin type for unlisted or future cryptocurrencies.
     ///
     /// Use this variant for coins not explicitly defined in this enum.
@@ -801,6 +824,7 @@ impl CoinType {
             CoinType::Polkadot => 354,
             CoinType::Cosmos => 118,
             CoinType::Tron => 195,
+            CoinType::Ripple => 144,
             CoinType::Custom(index) => *index,
         }
     }
@@ -832,6 +856,7 @@ impl CoinType {
             CoinType::Polkadot => "DOT",
             CoinType::Cosmos => "ATOM",
             CoinType::Tron => "TRX",
+            CoinType::Ripple => "XRP",
             CoinType::Custom(_) => "CUSTOM",
         }
     }
@@ -862,6 +887,7 @@ impl CoinType {
             CoinType::Polkadot => "Polkadot",
             CoinType::Cosmos => "Cosmos",
             CoinType::Tron => "Tron",
+            CoinType::Ripple => "XRP Ledger",
             CoinType::Custom(_) => "Custom",
         }
     }
@@ -968,6 +994,7 @@ impl TryFrom<u32> for CoinType {
             60 => CoinType::Ethereum,
             61 => CoinType::EthereumClassic,
             118 => CoinType::Cosmos,
+            144 => CoinType::Ripple,
             145 => CoinType::BitcoinCash,
             195 => CoinType::Tron,
             354 => CoinType::Polkadot,
@@ -1021,6 +1048,13 @@ impl fmt::Display for CoinType {
     }
 }
 
+#[cfg(feature = "tracing")]
+impl khodpay_tracing::LogSafe for CoinType {
+    fn log_value(&self) -> impl tracing::Value + '_ {
+        tracing::field::display(self)
+    }
+}
+
 #[cfg(test)]
 mod cointype_tests {
     use super::*;
@@ -1036,6 +1070,7 @@ mod cointype_tests {
         assert_eq!(CoinType::EthereumClassic.index(), 61);
         assert_eq!(CoinType::BitcoinCash.index(), 145);
         assert_eq!(CoinType::Cosmos.index(), 118);
+        assert_eq!(CoinType::Ripple.index(), 144);
         assert_eq!(CoinType::Tron.index(), 195);
         assert_eq!(CoinType::Polkadot.index(), 354);
         assert_eq!(CoinType::Solana.index(), 501);
@@ -1060,6 +1095,7 @@ mod cointype_tests {
         assert_eq!(CoinType::Polkadot.symbol(), "DOT");
         assert_eq!(CoinType::Cosmos.symbol(), "ATOM");
         assert_eq!(CoinType::Tron.symbol(), "TRX");
+        assert_eq!(CoinType::Ripple.symbol(), "XRP");
         assert_eq!(CoinType::Custom(123).symbol(), "CUSTOM");
     }
 
@@ -1079,6 +1115,7 @@ mod cointype_tests {
         assert_eq!(CoinType::Polkadot.name(), "Polkadot");
         assert_eq!(CoinType::Cosmos.name(), "Cosmos");
         assert_eq!(CoinType::Tron.name(), "Tron");
+        assert_eq!(CoinType::Ripple.name(), "XRP Ledger");
         assert_eq!(CoinType::Custom(456).name(), "Custom");
     }
 
@@ -1131,6 +1168,7 @@ mod cointype_tests {
         let _ada = CoinType::Cardano;
         let _dot = CoinType::Polkadot;
         let _atom = CoinType::Cosmos;
+        let _xrp = CoinType::Ripple;
     }
 
     #[test]
@@ -1155,6 +1193,7 @@ mod cointype_tests {
         assert_eq!(CoinType::try_from(60).unwrap(), CoinType::Ethereum);
         assert_eq!(CoinType::try_from(61).unwrap(), CoinType::EthereumClassic);
         assert_eq!(CoinType::try_from(118).unwrap(), CoinType::Cosmos);
+        assert_eq!(CoinType::try_from(144).unwrap(), CoinType::Ripple);
         assert_eq!(CoinType::try_from(145).unwrap(), CoinType::BitcoinCash);
         assert_eq!(CoinType::try_from(195).unwrap(), CoinType::Tron);
         assert_eq!(CoinType::try_from(354).unwrap(), CoinType::Polkadot);
@@ -168,6 +168,68 @@ impl Wallet {
         })
     }
 
+    /// Creates a new wallet from a Base58Check-encoded master extended private key.
+    ///
+    /// This allows integrations that already hold a BIP-32 root key (e.g. migrated
+    /// from another library) to use the BIP-44 layer directly. The network is taken
+    /// from the key's version bytes (`xprv` for mainnet, `tprv` for testnet).
+    ///
+    /// # Arguments
+    ///
+    /// * `xprv` - The master extended private key string
+    ///
+    /// # Returns
+    ///
+    /// A new `Wallet` instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The string is not a valid extended private key
+    /// - The key is not a master key (depth, parent fingerprint or child number is non-zero)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_bip44::Wallet;
+    /// use khodpay_bip32::Network;
+    ///
+    /// let seed = [0u8; 64];
+    /// let original = Wallet::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+    /// let xprv = original.master_key().to_string();
+    ///
+    /// let restored = Wallet::from_master_xprv(&xprv).unwrap();
+    /// assert_eq!(restored.network(), Network::BitcoinMainnet);
+    /// assert_eq!(restored.master_key(), original.master_key());
+    /// ```
+    pub fn from_master_xprv(xprv: &str) -> Result<Self> {
+        let master_key: ExtendedPrivateKey = xprv.parse()?;
+
+        if master_key.depth() != 0 {
+            return Err(Error::InvalidMasterKey(format!(
+                "expected depth 0, got {}",
+                master_key.depth()
+            )));
+        }
+        if master_key.parent_fingerprint() != &[0u8; 4] {
+            return Err(Error::InvalidMasterKey(
+                "parent fingerprint must be zero".to_string(),
+            ));
+        }
+        if master_key.child_number() != ChildNumber::Normal(0) {
+            return Err(Error::InvalidMasterKey(
+                "child number must be zero".to_string(),
+            ));
+        }
+
+        let network = master_key.network();
+        Ok(Self {
+            master_key,
+            network,
+            account_cache: HashMap::new(),
+        })
+    }
+
     /// Returns the network this wallet operates on.
     ///
     /// # Examples
@@ -290,6 +352,14 @@ impl Wallet {
         coin_type: CoinType,
         account_index: u32,
     ) -> Result<ExtendedPrivateKey> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!(
+            "derive_account",
+            purpose = purpose,
+            coin_type = coin_type,
+            account = account_index
+        );
+
         // Derive m/purpose'
         let purpose_key = self
             .master_key
@@ -394,6 +464,73 @@ mod tests {
         assert_eq!(testnet.network(), Network::BitcoinTestnet);
     }
 
+    #[test]
+    fn test_wallet_from_master_xprv_bip32_vector() {
+        // BIP-32 test vector 1: seed 000102030405060708090a0b0c0d0e0f
+        let xprv = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+
+        let from_xprv = Wallet::from_master_xprv(xprv).unwrap();
+        let from_seed = Wallet::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+
+        assert_eq!(from_xprv.network(), Network::BitcoinMainnet);
+        assert_eq!(from_xprv.master_key(), from_seed.master_key());
+        assert_eq!(from_xprv.master_key().to_string(), xprv);
+    }
+
+    #[test]
+    fn test_wallet_from_master_xprv_testnet() {
+        let seed = [7u8; 64];
+        let original = Wallet::from_seed(&seed, Network::BitcoinTestnet).unwrap();
+        let tprv = original.master_key().to_string();
+        assert!(tprv.starts_with("tprv"));
+
+        let restored = Wallet::from_master_xprv(&tprv).unwrap();
+        assert_eq!(restored.network(), Network::BitcoinTestnet);
+    }
+
+    #[test]
+    fn test_wallet_from_master_xprv_same_accounts() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mut original =
+            Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet).unwrap();
+        let mut restored = Wallet::from_master_xprv(&original.master_key().to_string()).unwrap();
+
+        let a = original
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+            .unwrap()
+            .derive_external(0)
+            .unwrap();
+        let b = restored
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+            .unwrap()
+            .derive_external(0)
+            .unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_wallet_from_master_xprv_rejects_child_key() {
+        let seed = [0u8; 64];
+        let wallet = Wallet::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+        let child = wallet
+            .master_key()
+            .derive_child(ChildNumber::Hardened(44))
+            .unwrap();
+
+        let result = Wallet::from_master_xprv(&child.to_string());
+        assert_eq!(
+            result.unwrap_err(),
+            Error::InvalidMasterKey("expected depth 0, got 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_wallet_from_master_xprv_invalid_string() {
+        let result = Wallet::from_master_xprv("not-an-xprv");
+        assert!(matches!(result, Err(Error::Bip32Error(_))));
+    }
+
     #[test]
     fn test_wallet_master_key() {
         let seed = [0u8; 64];
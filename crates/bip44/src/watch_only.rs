@@ -0,0 +1,330 @@
+//! Watch-only accounts built from extended public keys.
+//!
+//! This module provides [`WatchOnlyAccount`], an account-level extended public key
+//! (`m/purpose'/coin_type'/account'`) that derives receive and change public keys
+//! without any secret material, for "watch address/xpub" features.
+//!
+//! Accounts parse from and format to SLIP-132 strings, so the purpose travels with
+//! the key:
+//!
+//! | Prefix (mainnet / testnet) | Purpose |
+//! |---|---|
+//! | `xpub` / `tpub` | BIP-44 (or any purpose given to [`WatchOnlyAccount::new`]) |
+//! | `ypub` / `upub` | BIP-49 |
+//! | `zpub` / `vpub` | BIP-84 |
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_bip44::{Chain, Purpose, WatchOnlyAccount};
+//!
+//! // BIP-84 account 0 of "abandon abandon … about"
+//! let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+//! let account: WatchOnlyAccount = zpub.parse().unwrap();
+//!
+//! assert_eq!(account.purpose(), Purpose::BIP84);
+//! let receive = account.derive_address(Chain::External, 0).unwrap();
+//! assert_eq!(receive.depth(), 5);
+//! assert_eq!(account.to_string(), zpub);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use khodpay_bip32::{ChildNumber, ExtendedPublicKey, Network};
+
+use crate::{Account, Chain, Error, Purpose, Result};
+
+/// SLIP-132 version bytes of the public keys this module reads and writes.
+const VERSIONS: [(u32, Network, Purpose); 6] = [
+    (0x0488_B21E, Network::BitcoinMainnet, Purpose::BIP44), // xpub
+    (0x0435_87CF, Network::BitcoinTestnet, Purpose::BIP44), // tpub
+    (0x049D_7CB2, Network::BitcoinMainnet, Purpose::BIP49), // ypub
+    (0x044A_5262, Network::BitcoinTestnet, Purpose::BIP49), // upub
+    (0x04B2_4746, Network::BitcoinMainnet, Purpose::BIP84), // zpub
+    (0x045F_1CF6, Network::BitcoinTestnet, Purpose::BIP84), // vpub
+];
+
+/// An account that can derive public keys but not sign.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip44::{Chain, CoinType, Purpose, Wallet};
+/// use khodpay_bip32::Network;
+///
+/// let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet).unwrap();
+/// let account = wallet.get_account(Purpose::BIP84, CoinType::Bitcoin, 0).unwrap();
+///
+/// let watch_only = account.to_watch_only();
+/// let public = watch_only.derive_address(Chain::External, 0).unwrap();
+/// let private = account.derive_address(Chain::External, 0).unwrap();
+/// assert_eq!(public, private.to_extended_public_key());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOnlyAccount {
+    /// The BIP purpose the account's addresses use
+    purpose: Purpose,
+    /// The account-level extended public key
+    extended_key: ExtendedPublicKey,
+}
+
+impl WatchOnlyAccount {
+    /// Creates a watch-only account from an account-level extended public key.
+    ///
+    /// Use this for keys exported as plain `xpub`/`tpub` whose purpose is known
+    /// out of band, such as BIP-86 accounts.
+    pub fn new(extended_key: ExtendedPublicKey, purpose: Purpose) -> Self {
+        Self {
+            purpose,
+            extended_key,
+        }
+    }
+
+    /// Returns the purpose of this account.
+    pub const fn purpose(&self) -> Purpose {
+        self.purpose
+    }
+
+    /// Returns the account-level extended public key.
+    pub const fn extended_key(&self) -> &ExtendedPublicKey {
+        &self.extended_key
+    }
+
+    /// Returns the network of this account.
+    pub fn network(&self) -> Network {
+        self.extended_key.network()
+    }
+
+    /// Derives the public key at `chain` / `address_index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key derivation fails.
+    pub fn derive_address(&self, chain: Chain, address_index: u32) -> Result<ExtendedPublicKey> {
+        let chain_key = self
+            .extended_key
+            .derive_child(ChildNumber::Normal(chain.value()))?;
+        Ok(chain_key.derive_child(ChildNumber::Normal(address_index))?)
+    }
+
+    /// Derives `count` consecutive public keys of `chain` starting at `start_index`.
+    ///
+    /// The chain key is derived once, which makes this cheaper than repeated
+    /// [`derive_address`](Self::derive_address) calls for list views.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any key derivation fails.
+    pub fn derive_address_range(
+        &self,
+        chain: Chain,
+        start_index: u32,
+        count: u32,
+    ) -> Result<Vec<ExtendedPublicKey>> {
+        let chain_key = self
+            .extended_key
+            .derive_child(ChildNumber::Normal(chain.value()))?;
+        (0..count)
+            .map(|i| {
+                let index = start_index.saturating_add(i);
+                Ok(chain_key.derive_child(ChildNumber::Normal(index))?)
+            })
+            .collect()
+    }
+}
+
+impl Account {
+    /// Returns a watch-only copy of this account holding only its extended public key.
+    pub fn to_watch_only(&self) -> WatchOnlyAccount {
+        WatchOnlyAccount::new(self.extended_key().to_extended_public_key(), self.purpose())
+    }
+}
+
+impl fmt::Display for WatchOnlyAccount {
+    /// Formats the account key with its SLIP-132 prefix (`ypub`/`zpub` for BIP-49 and
+    /// BIP-84, `xpub`/`tpub` otherwise).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let xpub = self.extended_key.to_string();
+        let version = VERSIONS
+            .iter()
+            .find(|(_, network, purpose)| *network == self.network() && *purpose == self.purpose)
+            .map(|(version, _, _)| *version);
+        match version {
+            Some(version) => f.write_str(&with_version(&xpub, version).map_err(|_| fmt::Error)?),
+            None => f.write_str(&xpub),
+        }
+    }
+}
+
+impl FromStr for WatchOnlyAccount {
+    type Err = Error;
+
+    /// Parses an `xpub`, `ypub`, `zpub`, `tpub`, `upub` or `vpub` account key.
+    fn from_str(s: &str) -> Result<Self> {
+        let data = bs58::decode(s)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| Error::ParseError {
+                reason: format!("invalid extended public key: {}", e),
+            })?;
+        let version = data
+            .get(..4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().expect("four bytes")))
+            .ok_or_else(|| Error::ParseError {
+                reason: "extended public key too short".to_string(),
+            })?;
+        let (_, network, purpose) = VERSIONS
+            .iter()
+            .find(|(known, _, _)| *known == version)
+            .ok_or_else(|| Error::ParseError {
+                reason: format!("unknown extended public key version 0x{:08X}", version),
+            })?;
+        let extended_key: ExtendedPublicKey = with_version(s, network.xpub_version())?.parse()?;
+        if extended_key.depth() != 3 {
+            return Err(Error::ParseError {
+                reason: format!(
+                    "expected an account-level key at depth 3, got depth {}",
+                    extended_key.depth()
+                ),
+            });
+        }
+        Ok(Self::new(extended_key, *purpose))
+    }
+}
+
+/// Re-encodes a Base58Check extended key with different version bytes.
+fn with_version(s: &str, version: u32) -> Result<String> {
+    let mut data = bs58::decode(s)
+        .with_check(None)
+        .into_vec()
+        .map_err(|e| Error::ParseError {
+            reason: format!("invalid extended public key: {}", e),
+        })?;
+    if data.len() < 4 {
+        return Err(Error::ParseError {
+            reason: "extended public key too short".to_string(),
+        });
+    }
+    data[..4].copy_from_slice(&version.to_be_bytes());
+    Ok(bs58::encode(data).with_check().into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CoinType, Wallet};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn account(purpose: Purpose, network: Network) -> Account {
+        let coin = match network {
+            Network::BitcoinMainnet => CoinType::Bitcoin,
+            Network::BitcoinTestnet => CoinType::BitcoinTestnet,
+        };
+        let mut wallet = Wallet::from_english_mnemonic(MNEMONIC, "", network).unwrap();
+        wallet.get_account(purpose, coin, 0).unwrap().clone()
+    }
+
+    #[test]
+    fn test_bip84_zpub_vector() {
+        let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+        let watch_only: WatchOnlyAccount = zpub.parse().unwrap();
+
+        assert_eq!(watch_only.purpose(), Purpose::BIP84);
+        assert_eq!(watch_only.network(), Network::BitcoinMainnet);
+        assert_eq!(
+            watch_only,
+            account(Purpose::BIP84, Network::BitcoinMainnet).to_watch_only()
+        );
+        // First receive key of the BIP-84 test vector
+        assert_eq!(
+            watch_only
+                .derive_address(Chain::External, 0)
+                .unwrap()
+                .public_key()
+                .to_bytes()
+                .to_vec(),
+            vec![
+                0x03, 0x30, 0xd5, 0x4f, 0xd0, 0xdd, 0x42, 0x0a, 0x6e, 0x5f, 0x8d, 0x36, 0x24, 0xf5,
+                0xf3, 0x48, 0x2c, 0xae, 0x35, 0x0f, 0x79, 0xd5, 0xf0, 0x75, 0x3b, 0xf5, 0xbe, 0xef,
+                0x9c, 0x2d, 0x91, 0xaf, 0x3c
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slip132_round_trip() {
+        for (purpose, network, prefix) in [
+            (Purpose::BIP44, Network::BitcoinMainnet, "xpub"),
+            (Purpose::BIP49, Network::BitcoinMainnet, "ypub"),
+            (Purpose::BIP84, Network::BitcoinMainnet, "zpub"),
+            (Purpose::BIP44, Network::BitcoinTestnet, "tpub"),
+            (Purpose::BIP49, Network::BitcoinTestnet, "upub"),
+            (Purpose::BIP84, Network::BitcoinTestnet, "vpub"),
+        ] {
+            let watch_only = account(purpose, network).to_watch_only();
+            let encoded = watch_only.to_string();
+            assert!(encoded.starts_with(prefix), "{} for {:?}", encoded, purpose);
+            assert_eq!(encoded.parse::<WatchOnlyAccount>().unwrap(), watch_only);
+        }
+
+        // BIP-86 keys have no SLIP-132 prefix and read back as BIP-44 xpubs
+        let taproot = account(Purpose::BIP86, Network::BitcoinMainnet).to_watch_only();
+        let encoded = taproot.to_string();
+        assert!(encoded.starts_with("xpub"));
+        let parsed: WatchOnlyAccount = encoded.parse().unwrap();
+        assert_eq!(parsed.purpose(), Purpose::BIP44);
+        assert_eq!(
+            WatchOnlyAccount::new(parsed.extended_key().clone(), Purpose::BIP86),
+            taproot
+        );
+    }
+
+    #[test]
+    fn test_derivation_matches_private_account() {
+        let account = account(Purpose::BIP49, Network::BitcoinMainnet);
+        let watch_only = account.to_watch_only();
+        for chain in [Chain::External, Chain::Internal] {
+            let public = watch_only.derive_address_range(chain, 3, 4).unwrap();
+            let private = account.derive_address_range(chain, 3, 4).unwrap();
+            assert_eq!(public.len(), 4);
+            for (public, private) in public.iter().zip(&private) {
+                assert_eq!(public, &private.to_extended_public_key());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        let master = Wallet::from_english_mnemonic(MNEMONIC, "", Network::BitcoinMainnet)
+            .unwrap()
+            .master_key()
+            .to_extended_public_key()
+            .to_string();
+        assert!(matches!(
+            master.parse::<WatchOnlyAccount>(),
+            Err(Error::ParseError { .. })
+        ));
+
+        let zpub = account(Purpose::BIP84, Network::BitcoinMainnet)
+            .to_watch_only()
+            .to_string();
+        let mut corrupted = zpub.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'a' { b'b' } else { b'a' };
+        for invalid in [
+            String::from_utf8(corrupted).unwrap(),
+            with_version(&zpub, 0x0488_ADE4).unwrap(), // xprv version
+            "zpub".to_string(),
+            String::new(),
+        ] {
+            assert!(
+                invalid.parse::<WatchOnlyAccount>().is_err(),
+                "{} should not parse",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! The multi-coin [`Address`] type.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{
+    BitcoinAddress, Error, EvmAddress, Network, Result, SolanaAddress, TronAddress, XrpAddress,
+};
+
+/// An address on any supported network.
+///
+/// Parsing with [`FromStr`] detects the format: `0x…` hex is EVM (with its EIP-55
+/// checksum enforced when mixed case), base58 and bech32 Bitcoin addresses are
+/// Bitcoin, base58check with version `0x41` is Tron, base58check in the Ripple
+/// alphabet is XRP and 32 bytes of plain base58 is Solana. Use [`Address::parse`] in send flows to also require the network of the
+/// account paying.
+///
+/// Formatting with [`Display`](fmt::Display) gives each network's canonical form.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::{Address, BitcoinNetwork, Network};
+///
+/// let address: Address = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu".parse().unwrap();
+/// assert_eq!(address.network(), Network::Bitcoin(BitcoinNetwork::Bitcoin));
+///
+/// let address: Address = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap();
+/// assert_eq!(address.network(), Network::Tron);
+///
+/// // A Tron address pasted into an EVM send is rejected
+/// assert!(Address::parse("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", Network::Evm).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// A Bitcoin address: P2PKH, P2SH, SegWit v0 or taproot.
+    Bitcoin(BitcoinAddress),
+    /// An EVM address.
+    Evm(EvmAddress),
+    /// A Tron address.
+    Tron(TronAddress),
+    /// A Solana address.
+    Solana(SolanaAddress),
+    /// A classic XRP Ledger address.
+    Xrp(XrpAddress),
+}
+
+impl Address {
+    /// Parses an address and checks that it can receive on `network`.
+    ///
+    /// Bitcoin testnet and signet addresses share their prefixes, so either network
+    /// accepts both; the address reports the network asked for.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if `s` is not an address of any supported
+    /// format, and [`Error::WrongNetwork`] if it is one for another network.
+    pub fn parse(s: &str, network: Network) -> Result<Self> {
+        let address: Address = s.parse()?;
+        if !address.is_valid_for(network) {
+            return Err(Error::WrongNetwork {
+                address: s.to_string(),
+                network,
+            });
+        }
+        match (address, network) {
+            (Address::Bitcoin(_), Network::Bitcoin(network)) => BitcoinAddress::parse(s, network)
+                .map(Address::Bitcoin)
+                .map_err(|e| Error::InvalidAddress(e.to_string())),
+            (address, _) => Ok(address),
+        }
+    }
+
+    /// Returns `true` if `s` parses as an address that can receive on `network`.
+    pub fn is_valid(s: &str, network: Network) -> bool {
+        Self::parse(s, network).is_ok()
+    }
+
+    /// Returns the network the address belongs to.
+    ///
+    /// Parsed `tb1…` and `m…`/`n…`/`2…` Bitcoin addresses report
+    /// [`BitcoinNetwork::Testnet`](crate::BitcoinNetwork::Testnet), as signet shares
+    /// their prefixes.
+    pub fn network(&self) -> Network {
+        match self {
+            Address::Bitcoin(address) => Network::Bitcoin(address.network()),
+            Address::Evm(_) => Network::Evm,
+            Address::Tron(_) => Network::Tron,
+            Address::Solana(_) => Network::Solana,
+            Address::Xrp(_) => Network::Xrp,
+        }
+    }
+
+    /// Returns `true` if the address can receive on `network`.
+    pub fn is_valid_for(&self, network: Network) -> bool {
+        match (self, network) {
+            (Address::Bitcoin(address), Network::Bitcoin(network)) => address.is_valid_for(network),
+            (address, network) => address.network() == network,
+        }
+    }
+
+    /// Returns the Bitcoin address, if this is one.
+    pub fn as_bitcoin(&self) -> Option<&BitcoinAddress> {
+        match self {
+            Address::Bitcoin(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Returns the EVM address, if this is one.
+    pub fn as_evm(&self) -> Option<&EvmAddress> {
+        match self {
+            Address::Evm(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Returns the Tron address, if this is one.
+    pub fn as_tron(&self) -> Option<&TronAddress> {
+        match self {
+            Address::Tron(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Returns the Solana address, if this is one.
+    pub fn as_solana(&self) -> Option<&SolanaAddress> {
+        match self {
+            Address::Solana(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Returns the XRP Ledger address, if this is one.
+    pub fn as_xrp(&self) -> Option<&XrpAddress> {
+        match self {
+            Address::Xrp(address) => Some(address),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with("0x") {
+            if !EvmAddress::validate_checksum(s) {
+                return Err(Error::InvalidAddress(format!(
+                    "{} has an invalid EIP-55 checksum",
+                    s
+                )));
+            }
+            return s
+                .parse()
+                .map(Address::Evm)
+                .map_err(|e| Error::InvalidAddress(e.to_string()));
+        }
+        if let Ok(address) = s.parse() {
+            return Ok(Address::Bitcoin(address));
+        }
+        if let Ok(address) = s.parse() {
+            return Ok(Address::Tron(address));
+        }
+        if let Ok(address) = s.parse() {
+            return Ok(Address::Xrp(address));
+        }
+        if let Ok(address) = s.parse() {
+            return Ok(Address::Solana(address));
+        }
+        Err(Error::InvalidAddress(format!(
+            "{} is not a Bitcoin, EVM, Tron, Solana or XRP address",
+            s
+        )))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Bitcoin(address) => address.fmt(f),
+            Address::Evm(address) => address.fmt(f),
+            Address::Tron(address) => address.fmt(f),
+            Address::Solana(address) => address.fmt(f),
+            Address::Xrp(address) => address.fmt(f),
+        }
+    }
+}
+
+impl From<BitcoinAddress> for Address {
+    fn from(address: BitcoinAddress) -> Self {
+        Address::Bitcoin(address)
+    }
+}
+
+impl From<EvmAddress> for Address {
+    fn from(address: EvmAddress) -> Self {
+        Address::Evm(address)
+    }
+}
+
+impl From<TronAddress> for Address {
+    fn from(address: TronAddress) -> Self {
+        Address::Tron(address)
+    }
+}
+
+impl From<SolanaAddress> for Address {
+    fn from(address: SolanaAddress) -> Self {
+        Address::Solana(address)
+    }
+}
+
+impl From<XrpAddress> for Address {
+    fn from(address: XrpAddress) -> Self {
+        Address::Xrp(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BitcoinNetwork;
+
+    #[test]
+    fn test_detection() {
+        let bitcoin = Network::Bitcoin(BitcoinNetwork::Bitcoin);
+        let cases = [
+            ("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA", bitcoin),
+            ("37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf", bitcoin),
+            ("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", bitcoin),
+            (
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+                bitcoin,
+            ),
+            (
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                Network::Bitcoin(BitcoinNetwork::Testnet),
+            ),
+            ("0x9858EfFD232B4033E47d90003D41EC34EcaEda94", Network::Evm),
+            ("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", Network::Tron),
+            (
+                "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                Network::Solana,
+            ),
+            ("rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh", Network::Xrp),
+        ];
+        for (s, network) in cases {
+            let address: Address = s.parse().unwrap();
+            assert_eq!(address.network(), network, "{}", s);
+            assert_eq!(address.to_string(), s);
+        }
+
+        // Unchecksummed EVM addresses are accepted and formatted with the checksum
+        let address: Address = "0x9858effd232b4033e47d90003d41ec34ecaeda94"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            address.to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        // EIP-55 checksum broken by lowering one letter
+        assert!("0x9858efFD232B4033E47d90003D41EC34EcaEda94"
+            .parse::<Address>()
+            .is_err());
+        assert!("0x9858".parse::<Address>().is_err());
+        assert!("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyv"
+            .parse::<Address>()
+            .is_err());
+        assert!("not an address".parse::<Address>().is_err());
+        assert!("".parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn test_parse_for_network() {
+        let signet = Network::Bitcoin(BitcoinNetwork::Signet);
+        let address = Address::parse("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", signet).unwrap();
+        assert_eq!(address.network(), signet);
+
+        assert!(matches!(
+            Address::parse(
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                Network::Bitcoin(BitcoinNetwork::Testnet)
+            ),
+            Err(Error::WrongNetwork { .. })
+        ));
+        assert!(matches!(
+            Address::parse("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", Network::Evm),
+            Err(Error::WrongNetwork { .. })
+        ));
+        assert!(Address::is_valid(
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94",
+            Network::Evm
+        ));
+        assert!(!Address::is_valid(
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94",
+            Network::Tron
+        ));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let address: Address = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap();
+        let tron = *address.as_tron().unwrap();
+        assert!(address.as_evm().is_none());
+        assert_eq!(Address::from(tron), address);
+        assert_eq!(
+            Address::from(tron.to_evm()).to_string().to_lowercase(),
+            "0xa614f803b6fd780986a42c78ec9c7f77e6ded13c"
+        );
+    }
+}
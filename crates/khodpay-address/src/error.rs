@@ -0,0 +1,46 @@
+//! Error types for the address crate.
+
+use thiserror::Error;
+
+use crate::Network;
+
+/// Errors that can occur while parsing or validating addresses.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The string is not an address of any supported format.
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    /// The address is well formed but belongs to another network.
+    #[error("Address {address} is not valid on {network}")]
+    WrongNetwork {
+        /// The address as given.
+        address: String,
+        /// The network the address was expected on.
+        network: Network,
+    },
+}
+
+/// Result type alias for address operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::InvalidAddress("bad checksum".to_string()).to_string(),
+            "Invalid address: bad checksum"
+        );
+        let error = Error::WrongNetwork {
+            address: "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".to_string(),
+            network: Network::Evm,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Address TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t is not valid on EVM"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! # Khodpay Address
+//!
+//! One [`Address`] type for every network the wallet sends to, so parsing, validation
+//! and formatting work the same in every send flow instead of each crate handling
+//! address strings its own way.
+//!
+//! Bitcoin and EVM addresses wrap the types of `khodpay-btc-signing` and
+//! `khodpay-signing`, so a parsed address goes straight into a transaction builder.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`Address`] | Any supported address, with format detection |
+//! | [`Network`] | The network family an address belongs to, mapped to SLIP-44 coin types |
+//! | [`BitcoinAddress`] | P2PKH, P2SH, SegWit v0 and taproot addresses |
+//! | [`EvmAddress`] | EIP-55 checksummed EVM addresses |
+//! | [`TronAddress`] | Base58check Tron addresses and their `41…` hex form |
+//! | [`SolanaAddress`] | Base58 Solana account keys |
+//! | [`XrpAddress`] | Classic `r…` XRP Ledger addresses |
+//!
+//! ## Features
+//!
+//! - **Format Detection**: Tell Bitcoin, EVM, Tron, Solana and XRP addresses apart
+//!   from the string alone
+//! - **Network Checks**: Reject an address for another network before building a
+//!   transaction, including Bitcoin mainnet / testnet mix-ups
+//! - **Checksums**: Base58check, bech32 / bech32m and mixed-case EIP-55 checksums are
+//!   verified
+//! - **Canonical Formatting**: Checksummed EVM addresses and lowercase bech32
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_address::{Address, Network};
+//! use khodpay_bip44::CoinType;
+//!
+//! // The network of the account paying
+//! let network = Network::from_coin_type(CoinType::Ethereum).unwrap();
+//!
+//! let to = Address::parse("0x9858effd232b4033e47d90003d41ec34ecaeda94", network)?;
+//! assert_eq!(to.to_string(), "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+//! assert!(to.as_evm().is_some());
+//!
+//! // Wrong-network addresses are caught before anything is signed
+//! assert!(Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", network).is_err());
+//! # Ok::<(), khodpay_address::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod address;
+mod error;
+mod network;
+mod solana;
+mod tron;
+mod xrp;
+
+pub use address::Address;
+pub use error::{Error, Result};
+pub use network::{BitcoinNetwork, Network};
+pub use solana::SolanaAddress;
+pub use tron::{TronAddress, TRON_PREFIX};
+pub use xrp::{XrpAddress, XRP_ACCOUNT_PREFIX};
+
+/// Bitcoin address, re-exported from `khodpay-btc-signing`.
+pub use khodpay_btc_signing::Address as BitcoinAddress;
+/// EVM address, re-exported from `khodpay-signing`.
+pub use khodpay_signing::Address as EvmAddress;
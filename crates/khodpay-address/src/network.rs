@@ -0,0 +1,131 @@
+//! The networks an address can belong to.
+
+use std::fmt;
+
+use khodpay_bip44::CoinType;
+
+/// Bitcoin network, re-exported from `khodpay-btc-signing`.
+pub use khodpay_btc_signing::Network as BitcoinNetwork;
+
+/// The network family an [`Address`](crate::Address) belongs to.
+///
+/// EVM chains share one address format, so an EVM address is valid on every EVM
+/// chain; likewise Tron, Solana and XRP Ledger addresses do not encode a cluster.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::{BitcoinNetwork, Network};
+/// use khodpay_bip44::CoinType;
+///
+/// assert_eq!(Network::from_coin_type(CoinType::Ethereum), Some(Network::Evm));
+/// assert_eq!(
+///     Network::from_coin_type(CoinType::BitcoinTestnet),
+///     Some(Network::Bitcoin(BitcoinNetwork::Testnet))
+/// );
+/// assert_eq!(Network::Tron.coin_type(), CoinType::Tron);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// A Bitcoin network.
+    Bitcoin(BitcoinNetwork),
+    /// Ethereum and the other EVM chains.
+    Evm,
+    /// Tron.
+    Tron,
+    /// Solana.
+    Solana,
+    /// The XRP Ledger.
+    Xrp,
+}
+
+impl Network {
+    /// Returns the network whose addresses an account of `coin_type` receives, or
+    /// `None` for coins without a supported address format.
+    ///
+    /// Ethereum Classic and BNB share the EVM format.
+    pub fn from_coin_type(coin_type: CoinType) -> Option<Self> {
+        match coin_type {
+            CoinType::Bitcoin => Some(Network::Bitcoin(BitcoinNetwork::Bitcoin)),
+            CoinType::BitcoinTestnet => Some(Network::Bitcoin(BitcoinNetwork::Testnet)),
+            CoinType::Ethereum | CoinType::EthereumClassic | CoinType::BinanceCoin => {
+                Some(Network::Evm)
+            }
+            CoinType::Tron => Some(Network::Tron),
+            CoinType::Solana => Some(Network::Solana),
+            CoinType::Ripple => Some(Network::Xrp),
+            _ => None,
+        }
+    }
+
+    /// Returns the SLIP-44 coin type of accounts on this network.
+    ///
+    /// Bitcoin test networks share coin type 1, and EVM chains use Ethereum's 60.
+    pub fn coin_type(&self) -> CoinType {
+        match self {
+            Network::Bitcoin(BitcoinNetwork::Bitcoin) => CoinType::Bitcoin,
+            Network::Bitcoin(_) => CoinType::BitcoinTestnet,
+            Network::Evm => CoinType::Ethereum,
+            Network::Tron => CoinType::Tron,
+            Network::Solana => CoinType::Solana,
+            Network::Xrp => CoinType::Ripple,
+        }
+    }
+}
+
+impl From<BitcoinNetwork> for Network {
+    fn from(network: BitcoinNetwork) -> Self {
+        Network::Bitcoin(network)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Bitcoin(BitcoinNetwork::Bitcoin) => f.write_str("Bitcoin"),
+            Network::Bitcoin(network) => write!(f, "Bitcoin {}", network),
+            Network::Evm => f.write_str("EVM"),
+            Network::Tron => f.write_str("Tron"),
+            Network::Solana => f.write_str("Solana"),
+            Network::Xrp => f.write_str("XRP Ledger"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coin_type_round_trip() {
+        for network in [
+            Network::Bitcoin(BitcoinNetwork::Bitcoin),
+            Network::Bitcoin(BitcoinNetwork::Testnet),
+            Network::Evm,
+            Network::Tron,
+            Network::Solana,
+            Network::Xrp,
+        ] {
+            assert_eq!(Network::from_coin_type(network.coin_type()), Some(network));
+        }
+        assert_eq!(
+            Network::from_coin_type(CoinType::BinanceCoin),
+            Some(Network::Evm)
+        );
+        assert_eq!(Network::from_coin_type(CoinType::Cardano), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Network::Bitcoin(BitcoinNetwork::Bitcoin).to_string(),
+            "Bitcoin"
+        );
+        assert_eq!(
+            Network::Bitcoin(BitcoinNetwork::Signet).to_string(),
+            "Bitcoin signet"
+        );
+        assert_eq!(Network::Solana.to_string(), "Solana");
+        assert_eq!(Network::Xrp.to_string(), "XRP Ledger");
+    }
+}
@@ -0,0 +1,97 @@
+//! Solana addresses.
+//!
+//! A Solana address is a 32-byte Ed25519 public key, or a program-derived address off
+//! the curve, written in plain base58 without a checksum.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// A Solana address.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::SolanaAddress;
+///
+/// let system_program: SolanaAddress = "11111111111111111111111111111111".parse().unwrap();
+/// assert_eq!(system_program.as_bytes(), &[0u8; 32]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SolanaAddress([u8; 32]);
+
+impl SolanaAddress {
+    /// Creates an address from an Ed25519 public key or other 32-byte account key.
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the 32-byte account key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl FromStr for SolanaAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // 32 bytes take at most 44 base58 characters
+        if s.is_empty() || s.len() > 44 {
+            return Err(Error::InvalidAddress(format!(
+                "Solana address of {} characters",
+                s.len()
+            )));
+        }
+        let bytes = bs58::decode(s)
+            .into_vec()
+            .map_err(|e| Error::InvalidAddress(format!("{}: {}", s, e)))?;
+        let bytes: [u8; 32] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            Error::InvalidAddress(format!("Solana address of {} bytes", bytes.len()))
+        })?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for SolanaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+impl From<[u8; 32]> for SolanaAddress {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+        let address: SolanaAddress = usdc.parse().unwrap();
+        assert_eq!(address.as_bytes()[..4], [0xc6, 0xfa, 0x7a, 0xf3]);
+        assert_eq!(address.to_string(), usdc);
+        assert_eq!(
+            SolanaAddress::from_bytes([0; 32]).to_string(),
+            "11111111111111111111111111111111"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        // `0`, `O`, `I` and `l` are not base58
+        assert!("0PjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+            .parse::<SolanaAddress>()
+            .is_err());
+        // Base58 of the wrong length
+        assert!("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+            .parse::<SolanaAddress>()
+            .is_err());
+        assert!("".parse::<SolanaAddress>().is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Tron addresses.
+//!
+//! A Tron account is an EVM-style 20-byte hash of a secp256k1 public key, written in
+//! base58check with the version byte `0x41`, which gives every address its leading
+//! `T`. Tron's APIs also use the hex form `41…`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, EvmAddress, Result};
+
+/// Version byte of Tron addresses.
+pub const TRON_PREFIX: u8 = 0x41;
+
+/// A Tron address.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::TronAddress;
+///
+/// let usdt: TronAddress = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap();
+/// assert_eq!(usdt.to_hex(), "41a614f803b6fd780986a42c78ec9c7f77e6ded13c");
+/// assert_eq!(TronAddress::from_hex(&usdt.to_hex()).unwrap(), usdt);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TronAddress([u8; 20]);
+
+impl TronAddress {
+    /// Creates an address from its 20-byte account hash.
+    pub const fn from_bytes(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the address of an uncompressed (65-byte) or compressed (33-byte)
+    /// secp256k1 public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if the key is malformed.
+    pub fn from_public_key_bytes(public_key: &[u8]) -> Result<Self> {
+        EvmAddress::from_public_key_bytes(public_key)
+            .map(Self::from)
+            .map_err(|e| Error::InvalidAddress(e.to_string()))
+    }
+
+    /// Parses the hex form `41` followed by the 20-byte account hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if the string is not 42 hex characters
+    /// starting with `41`.
+    pub fn from_hex(s: &str) -> Result<Self> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let bytes = hex::decode(s).map_err(|e| Error::InvalidAddress(format!("{}: {}", s, e)))?;
+        Self::from_payload(&bytes)
+    }
+
+    /// Returns the 20-byte account hash.
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+
+    /// Returns the hex form, `41` followed by the account hash.
+    pub fn to_hex(&self) -> String {
+        format!("{:02x}{}", TRON_PREFIX, hex::encode(self.0))
+    }
+
+    /// Returns the EVM address with the same account hash, as used by Tron's
+    /// EVM-compatible contract calls.
+    pub fn to_evm(&self) -> EvmAddress {
+        EvmAddress::from(self.0)
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self> {
+        match payload {
+            [TRON_PREFIX, hash @ ..] if hash.len() == 20 => {
+                Ok(Self(hash.try_into().expect("length checked")))
+            }
+            [TRON_PREFIX, ..] => Err(Error::InvalidAddress(format!(
+                "Tron payload of {} bytes",
+                payload.len()
+            ))),
+            _ => Err(Error::InvalidAddress(
+                "Tron addresses start with version byte 0x41".to_string(),
+            )),
+        }
+    }
+}
+
+impl From<EvmAddress> for TronAddress {
+    fn from(address: EvmAddress) -> Self {
+        Self(address.to_bytes())
+    }
+}
+
+impl FromStr for TronAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let payload = bs58::decode(s)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| Error::InvalidAddress(format!("{}: {}", s, e)))?;
+        Self::from_payload(&payload)
+    }
+}
+
+impl fmt::Display for TronAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = Vec::with_capacity(21);
+        payload.push(TRON_PREFIX);
+        payload.extend_from_slice(&self.0);
+        f.write_str(&bs58::encode(payload).with_check().into_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let evm: EvmAddress = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+            .parse()
+            .unwrap();
+        let tron = TronAddress::from(evm);
+        assert_eq!(tron.to_string(), "TPrkFhZ8LH8Mruco8vXyA496TaeFBrbmeU");
+        assert_eq!(
+            "TPrkFhZ8LH8Mruco8vXyA496TaeFBrbmeU"
+                .parse::<TronAddress>()
+                .unwrap(),
+            tron
+        );
+        assert_eq!(tron.to_evm(), evm);
+        assert_eq!(
+            TronAddress::from_hex("0x419858effd232b4033e47d90003d41ec34ecaeda94").unwrap(),
+            tron
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        // Checksum broken in the last character
+        assert!("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6u"
+            .parse::<TronAddress>()
+            .is_err());
+        // A Bitcoin P2PKH address is base58check with another version byte
+        assert!("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+            .parse::<TronAddress>()
+            .is_err());
+        assert!(TronAddress::from_hex("409858effd232b4033e47d90003d41ec34ecaeda94").is_err());
+        assert!(TronAddress::from_hex("41zz").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! XRP Ledger addresses.
+//!
+//! An XRP Ledger account ID is the RIPEMD-160 of the SHA-256 of a public key, the
+//! same hash as a Bitcoin P2PKH address, written in base58check with the Ripple
+//! alphabet and version byte `0x00`, which gives every classic address its leading
+//! `r`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// Version byte of classic XRP Ledger addresses.
+pub const XRP_ACCOUNT_PREFIX: u8 = 0x00;
+
+/// A classic XRP Ledger address, such as `rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh`.
+///
+/// Destination tags are not part of the address; payments carry them separately.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::XrpAddress;
+///
+/// let genesis: XrpAddress = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh".parse().unwrap();
+/// let public_key =
+///     hex::decode("0330e7fc9d56bb25d6893ba3f317ae5bcf33b3291bd63db32654a313222f7fd020").unwrap();
+/// assert_eq!(XrpAddress::from_public_key_bytes(&public_key).unwrap(), genesis);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct XrpAddress([u8; 20]);
+
+impl XrpAddress {
+    /// Creates an address from its 20-byte account ID.
+    pub const fn from_bytes(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the address of a 33-byte compressed secp256k1 or Ed25519 public key
+    /// (the latter prefixed with `0xED`, as the XRP Ledger writes them).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if the key is not 33 bytes with a `02`,
+    /// `03` or `ED` prefix.
+    pub fn from_public_key_bytes(public_key: &[u8]) -> Result<Self> {
+        match public_key {
+            [0x02 | 0x03 | 0xed, ..] if public_key.len() == 33 => {
+                Ok(Self(Ripemd160::digest(Sha256::digest(public_key)).into()))
+            }
+            _ => Err(Error::InvalidAddress(format!(
+                "XRP Ledger public key of {} bytes",
+                public_key.len()
+            ))),
+        }
+    }
+
+    /// Returns the 20-byte account ID.
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+}
+
+impl From<[u8; 20]> for XrpAddress {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl FromStr for XrpAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let payload = bs58::decode(s)
+            .with_alphabet(bs58::Alphabet::RIPPLE)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| Error::InvalidAddress(format!("{}: {}", s, e)))?;
+        match payload.as_slice() {
+            [XRP_ACCOUNT_PREFIX, id @ ..] if id.len() == 20 => {
+                Ok(Self(id.try_into().expect("length checked")))
+            }
+            _ => Err(Error::InvalidAddress(format!(
+                "{} is not a classic XRP Ledger address",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for XrpAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = Vec::with_capacity(21);
+        payload.push(XRP_ACCOUNT_PREFIX);
+        payload.extend_from_slice(&self.0);
+        let encoded = bs58::encode(payload)
+            .with_alphabet(bs58::Alphabet::RIPPLE)
+            .with_check()
+            .into_string();
+        f.write_str(&encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        // ACCOUNT_ZERO and ACCOUNT_ONE, special addresses of the XRP Ledger
+        assert_eq!(
+            XrpAddress::from_bytes([0; 20]).to_string(),
+            "rrrrrrrrrrrrrrrrrrrrrhoLvTp"
+        );
+        let mut one = [0u8; 20];
+        one[19] = 1;
+        assert_eq!(
+            XrpAddress::from_bytes(one).to_string(),
+            "rrrrrrrrrrrrrrrrrrrrBZbvji"
+        );
+
+        let genesis = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh";
+        assert_eq!(genesis.parse::<XrpAddress>().unwrap().to_string(), genesis);
+    }
+
+    #[test]
+    fn test_invalid() {
+        // Checksum broken in the last character
+        assert!("rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTi"
+            .parse::<XrpAddress>()
+            .is_err());
+        // Bitcoin's base58 alphabet is a different permutation
+        assert!("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+            .parse::<XrpAddress>()
+            .is_err());
+        // X-addresses embed a tag and are not classic addresses
+        assert!("XVLhHMPHU98es4dbozjVtdWzVrDjtV18pX8yuPT7y4xaEHi"
+            .parse::<XrpAddress>()
+            .is_err());
+        assert!(XrpAddress::from_public_key_bytes(&[0x04; 33]).is_err());
+        assert!(XrpAddress::from_public_key_bytes(&[0x02; 32]).is_err());
+    }
+}
@@ -0,0 +1,673 @@
+//! Watch-only account export: `crypto-hdkey` (BCR-2020-007) and `crypto-account`
+//! (BCR-2020-015).
+//!
+//! The offline device exports account-level extended public keys with their master
+//! fingerprint and derivation path, so the online device can derive receive
+//! addresses and build PSBTs whose [`KeySource`](khodpay_btc_signing::psbt::KeySource)s
+//! the offline device recognizes. No private key material is ever encoded.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_airgap::{CryptoAccount, RegistryType};
+//! use khodpay_bip32::Network;
+//! use khodpay_bip44::{CoinType, Purpose, Wallet};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet)?;
+//! let mut export = CryptoAccount::new(wallet.master_key().fingerprint());
+//! export.add_account(wallet.get_account(Purpose::BIP84, CoinType::Bitcoin, 0)?)?;
+//! export.add_account(wallet.get_account(Purpose::BIP49, CoinType::Bitcoin, 0)?)?;
+//!
+//! // Shown as a QR code, then on the online device:
+//! let imported = CryptoAccount::from_ur(&export.to_ur())?;
+//! let xpub = imported.outputs()[0].key().to_extended_public_key()?;
+//! assert_eq!(xpub.depth(), 3);
+//! # Ok(())
+//! # }
+//! ```
+
+use khodpay_bip32::{
+    ChainCode, ChildNumber, DerivationPath, ExtendedPublicKey, Network, PublicKey,
+};
+use khodpay_bip44::{Account, CoinType};
+use khodpay_btc_signing::ScriptType;
+
+use crate::cbor::Value;
+use crate::ur::RegistryType;
+use crate::{Error, Result};
+
+const TAG_HDKEY: u64 = 303;
+const TAG_KEYPATH: u64 = 304;
+const TAG_COIN_INFO: u64 = 305;
+const TAG_SH: u64 = 400;
+const TAG_PKH: u64 = 403;
+const TAG_WPKH: u64 = 404;
+
+/// SLIP-44 coin type of Bitcoin, the `crypto-coin-info` default.
+const COIN_BITCOIN: u32 = 0;
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidCbor(message.to_string())
+}
+
+fn fingerprint_value(fingerprint: [u8; 4]) -> Value {
+    Value::Unsigned(u32::from_be_bytes(fingerprint) as u64)
+}
+
+fn fingerprint_from(value: &Value) -> Result<[u8; 4]> {
+    value
+        .as_u64()
+        .and_then(|n| u32::try_from(n).ok())
+        .map(u32::to_be_bytes)
+        .ok_or_else(|| invalid("fingerprint is not a 32-bit integer"))
+}
+
+/// A derivation path with the fingerprint of the key it starts from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPath {
+    path: DerivationPath,
+    source_fingerprint: Option<[u8; 4]>,
+    depth: Option<u8>,
+}
+
+impl KeyPath {
+    /// Creates a key path from the master key with fingerprint `master_fingerprint`.
+    pub fn new(path: DerivationPath, master_fingerprint: [u8; 4]) -> Self {
+        Self {
+            path,
+            source_fingerprint: Some(master_fingerprint),
+            depth: None,
+        }
+    }
+
+    /// Returns the path.
+    pub fn path(&self) -> &DerivationPath {
+        &self.path
+    }
+
+    /// Returns the fingerprint of the key the path starts from, usually the master
+    /// key.
+    pub fn source_fingerprint(&self) -> Option<[u8; 4]> {
+        self.source_fingerprint
+    }
+
+    /// Returns the depth of the derived key: the given depth, or the path length for
+    /// paths from the master key.
+    pub fn depth(&self) -> u8 {
+        self.depth.unwrap_or(self.path.depth())
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        let components = self
+            .path
+            .iter()
+            .flat_map(|child| {
+                [
+                    Value::Unsigned(child.value() as u64),
+                    Value::Bool(child.is_hardened()),
+                ]
+            })
+            .collect();
+        let mut entries = vec![(Value::Unsigned(1), Value::Array(components))];
+        if let Some(fingerprint) = self.source_fingerprint {
+            entries.push((Value::Unsigned(2), fingerprint_value(fingerprint)));
+        }
+        if let Some(depth) = self.depth {
+            entries.push((Value::Unsigned(3), Value::Unsigned(depth as u64)));
+        }
+        Value::Map(entries)
+    }
+
+    pub(crate) fn from_value(value: &Value) -> Result<Self> {
+        let components = value
+            .get(1)
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("key path without components"))?;
+        if components.len() % 2 != 0 {
+            return Err(invalid("key path component without hardened flag"));
+        }
+        let path = components
+            .chunks(2)
+            .map(|pair| match pair {
+                [Value::Unsigned(index), Value::Bool(hardened)]
+                    if *index <= ChildNumber::MAX_NORMAL_INDEX as u64 =>
+                {
+                    let index = *index as u32;
+                    Ok(if *hardened {
+                        ChildNumber::Hardened(index)
+                    } else {
+                        ChildNumber::Normal(index)
+                    })
+                }
+                // Wildcards and ranges describe sets of keys, not one key
+                _ => Err(Error::UnsupportedRequest(
+                    "key path wildcards and ranges".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let source_fingerprint = value.get(2).map(fingerprint_from).transpose()?;
+        let depth = value
+            .get(3)
+            .map(|depth| {
+                depth
+                    .as_u64()
+                    .and_then(|n| u8::try_from(n).ok())
+                    .ok_or_else(|| invalid("key path depth is not a byte"))
+            })
+            .transpose()?;
+        Ok(Self {
+            path: DerivationPath::new(path),
+            source_fingerprint,
+            depth,
+        })
+    }
+}
+
+/// An extended public key with its origin, as exported for a watch-only wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CryptoHdKey {
+    key_data: [u8; 33],
+    chain_code: [u8; 32],
+    coin_type: u32,
+    testnet: bool,
+    origin: KeyPath,
+    parent_fingerprint: [u8; 4],
+}
+
+impl CryptoHdKey {
+    /// Exports the public key of a BIP-44 family account, derived from the master
+    /// key with fingerprint `master_fingerprint`.
+    pub fn from_account(account: &Account, master_fingerprint: [u8; 4]) -> Self {
+        let xpub = account.extended_key().to_extended_public_key();
+        let path = DerivationPath::new(vec![
+            ChildNumber::Hardened(account.purpose().value()),
+            ChildNumber::Hardened(account.coin_type().index()),
+            ChildNumber::Hardened(account.account_index()),
+        ]);
+        let coin_type = account.coin_type();
+        Self {
+            key_data: xpub.public_key().to_bytes(),
+            chain_code: *xpub.chain_code().as_bytes(),
+            // Bitcoin testnet is Bitcoin on the test network, not its own coin
+            coin_type: match coin_type {
+                CoinType::BitcoinTestnet => COIN_BITCOIN,
+                coin_type => coin_type.index(),
+            },
+            testnet: coin_type.is_testnet(),
+            origin: KeyPath::new(path, master_fingerprint),
+            parent_fingerprint: *xpub.parent_fingerprint(),
+        }
+    }
+
+    /// Returns the compressed public key.
+    pub fn public_key(&self) -> &[u8; 33] {
+        &self.key_data
+    }
+
+    /// Returns the chain code.
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Returns the SLIP-44 coin type the key is for: `0` for Bitcoin, `60` for
+    /// Ethereum.
+    pub fn coin_type(&self) -> u32 {
+        self.coin_type
+    }
+
+    /// Returns `true` if the key is for a test network.
+    pub fn is_testnet(&self) -> bool {
+        self.testnet
+    }
+
+    /// Returns the derivation path of the key.
+    pub fn origin(&self) -> &KeyPath {
+        &self.origin
+    }
+
+    /// Returns the fingerprint of the parent key.
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// Returns the key as an extended public key, serialized as `xpub` or `tpub`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCbor`] if the origin path is empty, and
+    /// [`Error::Bip32Error`] if the key is not a valid secp256k1 point.
+    pub fn to_extended_public_key(&self) -> Result<ExtendedPublicKey> {
+        let child_number = *self
+            .origin
+            .path
+            .as_slice()
+            .last()
+            .ok_or_else(|| invalid("key without a derivation path"))?;
+        let network = if self.testnet {
+            Network::BitcoinTestnet
+        } else {
+            Network::BitcoinMainnet
+        };
+        Ok(ExtendedPublicKey::new(
+            network,
+            self.origin.depth(),
+            self.parent_fingerprint,
+            child_number,
+            ChainCode::new(self.chain_code),
+            PublicKey::from_array(self.key_data)?,
+        ))
+    }
+
+    fn to_value(&self) -> Value {
+        let mut entries = vec![
+            (Value::Unsigned(3), Value::Bytes(self.key_data.to_vec())),
+            (Value::Unsigned(4), Value::Bytes(self.chain_code.to_vec())),
+        ];
+        // Defaults are left out: Bitcoin, mainnet
+        let mut coin_info = Vec::new();
+        if self.coin_type != COIN_BITCOIN {
+            coin_info.push((Value::Unsigned(1), Value::Unsigned(self.coin_type as u64)));
+        }
+        if self.testnet {
+            coin_info.push((Value::Unsigned(2), Value::Unsigned(1)));
+        }
+        if !coin_info.is_empty() {
+            entries.push((
+                Value::Unsigned(5),
+                Value::tagged(TAG_COIN_INFO, Value::Map(coin_info)),
+            ));
+        }
+        entries.push((
+            Value::Unsigned(6),
+            Value::tagged(TAG_KEYPATH, self.origin.to_value()),
+        ));
+        entries.push((
+            Value::Unsigned(8),
+            fingerprint_value(self.parent_fingerprint),
+        ));
+        Value::Map(entries)
+    }
+
+    fn from_value(value: &Value) -> Result<Self> {
+        if matches!(value.get(1), Some(Value::Bool(true))) {
+            return Err(Error::UnsupportedRequest(
+                "master keys are not exported".to_string(),
+            ));
+        }
+        if matches!(value.get(2), Some(Value::Bool(true))) {
+            return Err(Error::UnsupportedRequest(
+                "private keys are not imported".to_string(),
+            ));
+        }
+        let bytes = |key: u64, what: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_bytes)
+                .ok_or_else(|| Error::InvalidCbor(format!("key without {}", what)))
+        };
+        let key_data = bytes(3, "key data")?
+            .try_into()
+            .map_err(|_| invalid("key data is not a compressed public key"))?;
+        let chain_code = bytes(4, "chain code")?
+            .try_into()
+            .map_err(|_| invalid("chain code is not 32 bytes"))?;
+        let (coin_type, testnet) = match value.get(5) {
+            Some(coin_info) => {
+                let coin_info = coin_info
+                    .untag(TAG_COIN_INFO)
+                    .ok_or_else(|| invalid("coin info without its tag"))?;
+                let coin_type = match coin_info.get(1) {
+                    Some(coin_type) => coin_type
+                        .as_u64()
+                        .and_then(|n| u32::try_from(n).ok())
+                        .ok_or_else(|| invalid("coin type is not a 32-bit integer"))?,
+                    None => COIN_BITCOIN,
+                };
+                let testnet = match coin_info.get(2).map(Value::as_u64) {
+                    None | Some(Some(0)) => false,
+                    Some(Some(1)) => true,
+                    Some(_) => return Err(invalid("unknown network")),
+                };
+                (coin_type, testnet)
+            }
+            None => (COIN_BITCOIN, false),
+        };
+        let origin = value
+            .get(6)
+            .and_then(|origin| origin.untag(TAG_KEYPATH))
+            .ok_or_else(|| invalid("key without an origin"))?;
+        let parent_fingerprint = value
+            .get(8)
+            .ok_or_else(|| invalid("key without a parent fingerprint"))
+            .and_then(fingerprint_from)?;
+        Ok(Self {
+            key_data,
+            chain_code,
+            coin_type,
+            testnet,
+            origin: KeyPath::from_value(origin)?,
+            parent_fingerprint,
+        })
+    }
+}
+
+impl RegistryType for CryptoHdKey {
+    const UR_TYPE: &'static str = "crypto-hdkey";
+
+    fn to_cbor(&self) -> Vec<u8> {
+        self.to_value().encode()
+    }
+
+    fn from_cbor(cbor: &[u8]) -> Result<Self> {
+        Self::from_value(&Value::decode(cbor)?)
+    }
+}
+
+/// A single-key output descriptor of an account: `pkh(…)`, `sh(wpkh(…))` or
+/// `wpkh(…)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDescriptor {
+    script_type: ScriptType,
+    key: CryptoHdKey,
+}
+
+impl OutputDescriptor {
+    /// Creates a descriptor paying to keys derived from `key`.
+    pub fn new(script_type: ScriptType, key: CryptoHdKey) -> Self {
+        Self { script_type, key }
+    }
+
+    /// Returns the script type.
+    pub fn script_type(&self) -> ScriptType {
+        self.script_type
+    }
+
+    /// Returns the account key.
+    pub fn key(&self) -> &CryptoHdKey {
+        &self.key
+    }
+
+    fn to_value(&self) -> Value {
+        let key = Value::tagged(TAG_HDKEY, self.key.to_value());
+        match self.script_type {
+            ScriptType::P2pkh => Value::tagged(TAG_PKH, key),
+            ScriptType::P2shP2wpkh => Value::tagged(TAG_SH, Value::tagged(TAG_WPKH, key)),
+            ScriptType::P2wpkh => Value::tagged(TAG_WPKH, key),
+        }
+    }
+
+    /// Decodes a descriptor, or returns `None` for script expressions this crate
+    /// cannot sign for, such as taproot and multisig.
+    fn from_value(value: &Value) -> Result<Option<Self>> {
+        let (script_type, key) = if let Some(key) = value.untag(TAG_PKH) {
+            (ScriptType::P2pkh, key)
+        } else if let Some(key) = value.untag(TAG_SH).and_then(|inner| inner.untag(TAG_WPKH)) {
+            (ScriptType::P2shP2wpkh, key)
+        } else if let Some(key) = value.untag(TAG_WPKH) {
+            (ScriptType::P2wpkh, key)
+        } else {
+            return Ok(None);
+        };
+        let Some(key) = key.untag(TAG_HDKEY) else {
+            return Ok(None);
+        };
+        Ok(Some(Self::new(script_type, CryptoHdKey::from_value(key)?)))
+    }
+}
+
+/// The Bitcoin accounts of a wallet, exported for a watch-only coordinator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CryptoAccount {
+    master_fingerprint: [u8; 4],
+    outputs: Vec<OutputDescriptor>,
+}
+
+impl CryptoAccount {
+    /// Creates an empty export for the wallet with master key fingerprint
+    /// `master_fingerprint`.
+    pub fn new(master_fingerprint: [u8; 4]) -> Self {
+        Self {
+            master_fingerprint,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Adds a BIP-44, BIP-49 or BIP-84 Bitcoin account.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedRequest`] for other coins and for taproot (BIP-86)
+    /// accounts.
+    pub fn add_account(&mut self, account: &Account) -> Result<()> {
+        if !matches!(
+            account.coin_type(),
+            CoinType::Bitcoin | CoinType::BitcoinTestnet
+        ) {
+            return Err(Error::UnsupportedRequest(format!(
+                "{} accounts are exported as crypto-hdkey",
+                account.coin_type().name()
+            )));
+        }
+        let script_type = ScriptType::from_purpose(account.purpose()).ok_or_else(|| {
+            Error::UnsupportedRequest(format!("{} accounts", account.purpose().name()))
+        })?;
+        let key = CryptoHdKey::from_account(account, self.master_fingerprint);
+        self.outputs.push(OutputDescriptor::new(script_type, key));
+        Ok(())
+    }
+
+    /// Returns the master key fingerprint.
+    pub fn master_fingerprint(&self) -> [u8; 4] {
+        self.master_fingerprint
+    }
+
+    /// Returns the account descriptors.
+    pub fn outputs(&self) -> &[OutputDescriptor] {
+        &self.outputs
+    }
+}
+
+impl RegistryType for CryptoAccount {
+    const UR_TYPE: &'static str = "crypto-account";
+
+    fn to_cbor(&self) -> Vec<u8> {
+        Value::Map(vec![
+            (
+                Value::Unsigned(1),
+                fingerprint_value(self.master_fingerprint),
+            ),
+            (
+                Value::Unsigned(2),
+                Value::Array(
+                    self.outputs
+                        .iter()
+                        .map(OutputDescriptor::to_value)
+                        .collect(),
+                ),
+            ),
+        ])
+        .encode()
+    }
+
+    /// Decodes an account export. Descriptors this crate cannot sign for, such as
+    /// taproot and multisig ones, are skipped.
+    fn from_cbor(cbor: &[u8]) -> Result<Self> {
+        let value = Value::decode(cbor)?;
+        let master_fingerprint = value
+            .get(1)
+            .ok_or_else(|| invalid("account without a master fingerprint"))
+            .and_then(fingerprint_from)?;
+        let outputs = value
+            .get(2)
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("account without output descriptors"))?
+            .iter()
+            .filter_map(|output| OutputDescriptor::from_value(output).transpose())
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            master_fingerprint,
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_bip44::{Purpose, Wallet};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn wallet(network: Network) -> Wallet {
+        Wallet::from_english_mnemonic(MNEMONIC, "", network).unwrap()
+    }
+
+    #[test]
+    fn test_hdkey_from_account() {
+        let mut wallet = wallet(Network::BitcoinMainnet);
+        let fingerprint = wallet.master_key().fingerprint();
+        let account = wallet
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+            .unwrap();
+        let key = CryptoHdKey::from_account(account, fingerprint);
+        assert_eq!(
+            key.origin().source_fingerprint(),
+            Some([0x73, 0xc5, 0xda, 0x0a])
+        );
+        assert_eq!(key.origin().path().to_string(), "m/84'/0'/0'");
+        assert_eq!(
+            key.to_extended_public_key().unwrap().to_string(),
+            "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V"
+        );
+
+        let cbor = key.to_cbor();
+        let expected = format!(
+            "a4035821{}045820{}06d90130a201861854f500f500f5021a73c5da0a081a{}",
+            ::hex::encode(key.public_key()),
+            ::hex::encode(key.chain_code()),
+            ::hex::encode(key.parent_fingerprint()),
+        );
+        assert_eq!(::hex::encode(&cbor), expected);
+        assert_eq!(CryptoHdKey::from_ur(&key.to_ur()).unwrap(), key);
+    }
+
+    #[test]
+    fn test_hdkey_coin_info() {
+        let mut wallet = wallet(Network::BitcoinTestnet);
+        let fingerprint = wallet.master_key().fingerprint();
+        let account = wallet
+            .get_account(Purpose::BIP84, CoinType::BitcoinTestnet, 0)
+            .unwrap();
+        let key = CryptoHdKey::from_account(account, fingerprint);
+        assert_eq!((key.coin_type(), key.is_testnet()), (0, true));
+        assert!(::hex::encode(key.to_cbor()).contains("05d90131a10201"));
+        assert!(key
+            .to_extended_public_key()
+            .unwrap()
+            .to_string()
+            .starts_with("tpub"));
+
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .unwrap();
+        let key = CryptoHdKey::from_account(account, fingerprint);
+        assert_eq!((key.coin_type(), key.is_testnet()), (60, false));
+        assert!(::hex::encode(key.to_cbor()).contains("05d90131a101183c"));
+        assert_eq!(CryptoHdKey::from_cbor(&key.to_cbor()).unwrap(), key);
+    }
+
+    #[test]
+    fn test_account_round_trip() {
+        let mut wallet = wallet(Network::BitcoinMainnet);
+        let mut export = CryptoAccount::new(wallet.master_key().fingerprint());
+        for purpose in [Purpose::BIP44, Purpose::BIP49, Purpose::BIP84] {
+            let account = wallet.get_account(purpose, CoinType::Bitcoin, 0).unwrap();
+            export.add_account(account).unwrap();
+        }
+        let script_types: Vec<_> = export.outputs().iter().map(|o| o.script_type()).collect();
+        assert_eq!(
+            script_types,
+            [
+                ScriptType::P2pkh,
+                ScriptType::P2shP2wpkh,
+                ScriptType::P2wpkh
+            ]
+        );
+
+        let cbor = ::hex::encode(export.to_cbor());
+        assert!(cbor.starts_with("a2011a73c5da0a0283d90193d9012fa4"));
+        assert!(cbor.contains("d90190d90194d9012fa4"));
+        assert_eq!(CryptoAccount::from_ur(&export.to_ur()).unwrap(), export);
+
+        let account = wallet
+            .get_account(Purpose::BIP86, CoinType::Bitcoin, 0)
+            .unwrap();
+        assert!(matches!(
+            export.add_account(account),
+            Err(Error::UnsupportedRequest(_))
+        ));
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .unwrap();
+        assert!(matches!(
+            export.add_account(account),
+            Err(Error::UnsupportedRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_account_skips_unsupported_descriptors() {
+        let mut wallet = wallet(Network::BitcoinMainnet);
+        let mut export = CryptoAccount::new(wallet.master_key().fingerprint());
+        let account = wallet
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+            .unwrap();
+        export.add_account(account).unwrap();
+        let key = Value::tagged(TAG_HDKEY, export.outputs()[0].key().to_value());
+        // tr(key), then wpkh(key)
+        let cbor = Value::Map(vec![
+            (
+                Value::Unsigned(1),
+                fingerprint_value(export.master_fingerprint()),
+            ),
+            (
+                Value::Unsigned(2),
+                Value::Array(vec![
+                    Value::tagged(409, key),
+                    export.outputs()[0].to_value(),
+                ]),
+            ),
+        ])
+        .encode();
+        assert_eq!(CryptoAccount::from_cbor(&cbor).unwrap(), export);
+    }
+
+    #[test]
+    fn test_invalid() {
+        // Hardened wildcard component
+        let origin = Value::Map(vec![(
+            Value::Unsigned(1),
+            Value::Array(vec![Value::Array(Vec::new()), Value::Bool(true)]),
+        )]);
+        assert!(matches!(
+            KeyPath::from_value(&origin),
+            Err(Error::UnsupportedRequest(_))
+        ));
+        assert!(matches!(
+            CryptoHdKey::from_cbor(&::hex::decode("a10241ff").unwrap()),
+            Err(Error::InvalidCbor(_))
+        ));
+        // Private key flag
+        assert!(matches!(
+            CryptoHdKey::from_cbor(&::hex::decode("a102f5").unwrap()),
+            Err(Error::UnsupportedRequest(_))
+        ));
+        let ur = crate::Ur::new("crypto-psbt", vec![0x40]).unwrap();
+        assert!(matches!(
+            CryptoAccount::from_ur(&ur),
+            Err(Error::UnexpectedType { .. })
+        ));
+    }
+}
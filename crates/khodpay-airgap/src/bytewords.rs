@@ -0,0 +1,221 @@
+//! Bytewords: bytes as four-letter English words (BCR-2020-012).
+//!
+//! Each byte maps to one of 256 words whose first and last letters are unique, so
+//! the [`Minimal`](Style::Minimal) style keeps only those two letters. URs use the
+//! minimal style; the others suit text that people read or type. A CRC-32 of the
+//! data is appended as four more words and checked when decoding.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_airgap::bytewords::{self, Style};
+//!
+//! let data = [0x00, 0x01, 0x02, 0x80, 0xff];
+//! assert_eq!(
+//!     bytewords::encode(&data, Style::Standard),
+//!     "able acid also lava zoom jade need echo taxi"
+//! );
+//! assert_eq!(bytewords::encode(&data, Style::Minimal), "aeadaolazmjendeoti");
+//! assert_eq!(bytewords::decode("aeadaolazmjendeoti", Style::Minimal).unwrap(), data);
+//! ```
+
+use crate::{Error, Result};
+
+/// How words are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// Whole words separated by spaces: `able acid also`.
+    Standard,
+    /// Whole words separated by dashes, safe in URIs: `able-acid-also`.
+    Uri,
+    /// First and last letters of each word, without separators: `aeadao`.
+    Minimal,
+}
+
+#[rustfmt::skip]
+const WORDS: [&str; 256] = [
+    "able", "acid", "also", "apex", "aqua", "arch", "atom", "aunt", "away", "axis", "back", "bald", "barn", "belt", "beta", "bias",
+    "blue", "body", "brag", "brew", "bulb", "buzz", "calm", "cash", "cats", "chef", "city", "claw", "code", "cola", "cook", "cost",
+    "crux", "curl", "cusp", "cyan", "dark", "data", "days", "deli", "dice", "diet", "door", "down", "draw", "drop", "drum", "dull",
+    "duty", "each", "easy", "echo", "edge", "epic", "even", "exam", "exit", "eyes", "fact", "fair", "fern", "figs", "film", "fish",
+    "fizz", "flap", "flew", "flux", "foxy", "free", "frog", "fuel", "fund", "gala", "game", "gear", "gems", "gift", "girl", "glow",
+    "good", "gray", "grim", "guru", "gush", "gyro", "half", "hang", "hard", "hawk", "heat", "help", "high", "hill", "holy", "hope",
+    "horn", "huts", "iced", "idea", "idle", "inch", "inky", "into", "iris", "iron", "item", "jade", "jazz", "join", "jolt", "jowl",
+    "judo", "jugs", "jump", "junk", "jury", "keep", "keno", "kept", "keys", "kick", "kiln", "king", "kite", "kiwi", "knob", "lamb",
+    "lava", "lazy", "leaf", "legs", "liar", "limp", "lion", "list", "logo", "loud", "love", "luau", "luck", "lung", "main", "many",
+    "math", "maze", "memo", "menu", "meow", "mild", "mint", "miss", "monk", "nail", "navy", "need", "news", "next", "noon", "note",
+    "numb", "obey", "oboe", "omit", "onyx", "open", "oval", "owls", "paid", "part", "peck", "play", "plus", "poem", "pool", "pose",
+    "puff", "puma", "purr", "quad", "quiz", "race", "ramp", "real", "redo", "rich", "road", "rock", "roof", "ruby", "ruin", "runs",
+    "rust", "safe", "saga", "scar", "sets", "silk", "skew", "slot", "soap", "solo", "song", "stub", "surf", "swan", "taco", "task",
+    "taxi", "tent", "tied", "time", "tiny", "toil", "tomb", "toys", "trip", "tuna", "twin", "ugly", "undo", "unit", "urge", "user",
+    "vast", "very", "veto", "vial", "vibe", "view", "visa", "void", "vows", "wall", "wand", "warm", "wasp", "wave", "waxy", "webs",
+    "what", "when", "whiz", "wolf", "work", "yank", "yawn", "yell", "yoga", "yurt", "zaps", "zero", "zest", "zinc", "zone", "zoom",
+];
+
+/// Byte of each first and last letter pair, indexed by `first * 26 + last`.
+const LOOKUP: [Option<u8>; 26 * 26] = {
+    let mut table = [None; 26 * 26];
+    let mut i = 0;
+    while i < WORDS.len() {
+        let word = WORDS[i].as_bytes();
+        table[(word[0] - b'a') as usize * 26 + (word[3] - b'a') as usize] = Some(i as u8);
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE) lookup table.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Returns the CRC-32 (IEEE) of `data`, the checksum of bytewords and fountain
+/// coded messages.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Encodes `data` and its CRC-32 as bytewords.
+pub fn encode(data: &[u8], style: Style) -> String {
+    let checksum = crc32(data).to_be_bytes();
+    let words = data
+        .iter()
+        .chain(&checksum)
+        .map(|&byte| WORDS[byte as usize]);
+    match style {
+        Style::Standard => words.collect::<Vec<_>>().join(" "),
+        Style::Uri => words.collect::<Vec<_>>().join("-"),
+        Style::Minimal => words.flat_map(|word| [&word[..1], &word[3..]]).collect(),
+    }
+}
+
+/// Decodes bytewords and checks their CRC-32. Letter case is ignored.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidBytewords`] for unknown words or a missing checksum, and
+/// [`Error::ChecksumMismatch`] if the checksum does not match.
+pub fn decode(s: &str, style: Style) -> Result<Vec<u8>> {
+    let s = s.to_ascii_lowercase();
+    let mut bytes = match style {
+        Style::Standard => s.split(' ').map(word_byte).collect::<Result<Vec<_>>>()?,
+        Style::Uri => s.split('-').map(word_byte).collect::<Result<Vec<_>>>()?,
+        Style::Minimal => {
+            if s.len() % 2 != 0 || !s.is_ascii() {
+                return Err(Error::InvalidBytewords(
+                    "minimal bytewords come in letter pairs".to_string(),
+                ));
+            }
+            s.as_bytes()
+                .chunks(2)
+                .map(|pair| letters_byte(pair[0], pair[1]))
+                .collect::<Result<Vec<_>>>()?
+        }
+    };
+    if bytes.len() < 4 {
+        return Err(Error::InvalidBytewords("missing checksum".to_string()));
+    }
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if crc32(&bytes).to_be_bytes() != checksum[..] {
+        return Err(Error::ChecksumMismatch);
+    }
+    Ok(bytes)
+}
+
+fn word_byte(word: &str) -> Result<u8> {
+    let letters = word.as_bytes();
+    let byte = match letters {
+        [first, _, _, last] => letters_byte(*first, *last)?,
+        _ => return Err(Error::InvalidBytewords(format!("unknown word {:?}", word))),
+    };
+    if WORDS[byte as usize] != word {
+        return Err(Error::InvalidBytewords(format!("unknown word {:?}", word)));
+    }
+    Ok(byte)
+}
+
+fn letters_byte(first: u8, last: u8) -> Result<u8> {
+    let unknown =
+        || Error::InvalidBytewords(format!("unknown word {}{}", first as char, last as char));
+    if !first.is_ascii_lowercase() || !last.is_ascii_lowercase() {
+        return Err(unknown());
+    }
+    LOOKUP[(first - b'a') as usize * 26 + (last - b'a') as usize].ok_or_else(unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_list() {
+        assert!(WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(LOOKUP.iter().flatten().count(), 256);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"Hello, world!"), 0xebe6_c6e6);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        for style in [Style::Standard, Style::Uri, Style::Minimal] {
+            let encoded = encode(&data, style);
+            assert_eq!(decode(&encoded, style).unwrap(), data);
+            assert_eq!(decode(&encoded.to_uppercase(), style).unwrap(), data);
+        }
+        assert_eq!(
+            encode(&[0x00, 0x01, 0x02, 0x80, 0xff], Style::Uri),
+            "able-acid-also-lava-zoom-jade-need-echo-taxi"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            decode("aeadaolazmjendeota", Style::Minimal),
+            Err(Error::ChecksumMismatch)
+        ));
+        assert!(matches!(
+            decode("aeadaolazmjendeot", Style::Minimal),
+            Err(Error::InvalidBytewords(_))
+        ));
+        assert!(matches!(
+            decode(
+                "able acid also lava zoom jade need echo tixa",
+                Style::Standard
+            ),
+            Err(Error::InvalidBytewords(_))
+        ));
+        assert!(matches!(
+            decode("aeadao", Style::Minimal),
+            Err(Error::InvalidBytewords(_))
+        ));
+        assert!(matches!(
+            decode("aéad", Style::Minimal),
+            Err(Error::InvalidBytewords(_))
+        ));
+    }
+}
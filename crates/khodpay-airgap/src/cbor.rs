@@ -0,0 +1,348 @@
+//! The subset of CBOR (RFC 8949) the UR registry types use.
+//!
+//! Values are written in the deterministic form: shortest integer heads and
+//! definite lengths, with map keys in the order given, which callers keep
+//! ascending. Reading accepts only definite lengths and no floats, which no
+//! registry type needs.
+
+use crate::{Error, Result};
+
+/// Deepest nesting [`Value::decode`] accepts.
+const MAX_DEPTH: usize = 16;
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+const FALSE: u8 = 20;
+const TRUE: u8 = 21;
+const NULL: u8 = 22;
+
+/// A CBOR data item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    /// A non-negative integer.
+    Unsigned(u64),
+    /// The integer `-1 - n`.
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    /// Returns `value` under `tag`.
+    pub(crate) fn tagged(tag: u64, value: Value) -> Self {
+        Value::Tag(tag, Box::new(value))
+    }
+
+    /// Returns the encoded item.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Unsigned(n) => write_head(out, UNSIGNED, *n),
+            Value::Negative(n) => write_head(out, NEGATIVE, *n),
+            Value::Bytes(bytes) => {
+                write_head(out, BYTES, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            Value::Text(text) => {
+                write_head(out, TEXT, text.len() as u64);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Value::Array(items) => {
+                write_head(out, ARRAY, items.len() as u64);
+                items.iter().for_each(|item| item.write(out));
+            }
+            Value::Map(entries) => {
+                write_head(out, MAP, entries.len() as u64);
+                for (key, value) in entries {
+                    key.write(out);
+                    value.write(out);
+                }
+            }
+            Value::Tag(tag, value) => {
+                write_head(out, TAG, *tag);
+                value.write(out);
+            }
+            Value::Bool(false) => out.push(SIMPLE << 5 | FALSE),
+            Value::Bool(true) => out.push(SIMPLE << 5 | TRUE),
+            Value::Null => out.push(SIMPLE << 5 | NULL),
+        }
+    }
+
+    /// Decodes exactly one item from `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCbor`] if the item is malformed, uses an unsupported
+    /// feature, or is followed by trailing bytes.
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let value = reader.value(0)?;
+        if reader.pos != bytes.len() {
+            return Err(invalid("trailing bytes"));
+        }
+        Ok(value)
+    }
+
+    /// Returns the integer, if this is an unsigned integer.
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Unsigned(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes, if this is a byte string.
+    pub(crate) fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the text, if this is a text string.
+    pub(crate) fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns the items, if this is an array.
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the value under `tag`, if this item carries that tag.
+    pub(crate) fn untag(&self, tag: u64) -> Option<&Value> {
+        match self {
+            Value::Tag(t, value) if *t == tag => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the entry under the integer `key`, if this is a map that has one.
+    pub(crate) fn get(&self, key: u64) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| *k == Value::Unsigned(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    match n {
+        0..=23 => out.push(major | n as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, n as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidCbor(message.to_string())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated item"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    /// Reads a head, returning the major type and argument.
+    fn head(&mut self) -> Result<(u8, u64)> {
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let argument = match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().expect("2 bytes")) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().expect("4 bytes")) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().expect("8 bytes")),
+            31 => return Err(invalid("indefinite lengths are not supported")),
+            _ => return Err(invalid("reserved additional information")),
+        };
+        Ok((major, argument))
+    }
+
+    /// Reads a length, which must fit in what is left of the input.
+    fn len(&mut self, argument: u64) -> Result<usize> {
+        usize::try_from(argument)
+            .ok()
+            .filter(|&len| len <= self.bytes.len() - self.pos)
+            .ok_or_else(|| invalid("length exceeds the input"))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("nested too deeply"));
+        }
+        let (major, argument) = self.head()?;
+        Ok(match major {
+            UNSIGNED => Value::Unsigned(argument),
+            NEGATIVE => Value::Negative(argument),
+            BYTES => {
+                let len = self.len(argument)?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            TEXT => {
+                let len = self.len(argument)?;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|_| invalid("text is not UTF-8"))?;
+                Value::Text(text.to_string())
+            }
+            ARRAY => {
+                // Every item takes at least a byte, so counts are bounded like lengths
+                let len = self.len(argument)?;
+                let items = (0..len)
+                    .map(|_| self.value(depth + 1))
+                    .collect::<Result<_>>()?;
+                Value::Array(items)
+            }
+            MAP => {
+                let len = self.len(argument)?;
+                let entries = (0..len)
+                    .map(|_| Ok((self.value(depth + 1)?, self.value(depth + 1)?)))
+                    .collect::<Result<_>>()?;
+                Value::Map(entries)
+            }
+            TAG => Value::tagged(argument, self.value(depth + 1)?),
+            _ => match argument {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                22 => Value::Null,
+                _ => return Err(invalid("floats and other simple values are not supported")),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: Value, hex: &str) {
+        assert_eq!(::hex::encode(value.encode()), hex);
+        assert_eq!(Value::decode(&::hex::decode(hex).unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_rfc_8949_examples() {
+        round_trip(Value::Unsigned(0), "00");
+        round_trip(Value::Unsigned(23), "17");
+        round_trip(Value::Unsigned(24), "1818");
+        round_trip(Value::Unsigned(1000), "1903e8");
+        round_trip(Value::Unsigned(1_000_000), "1a000f4240");
+        round_trip(Value::Unsigned(1_000_000_000_000), "1b000000e8d4a51000");
+        round_trip(Value::Negative(999), "3903e7");
+        round_trip(Value::Bytes(vec![1, 2, 3, 4]), "4401020304");
+        round_trip(Value::Text("IETF".to_string()), "6449455446");
+        round_trip(
+            Value::Array(vec![
+                Value::Unsigned(1),
+                Value::Array(vec![Value::Unsigned(2), Value::Unsigned(3)]),
+            ]),
+            "8201820203",
+        );
+        round_trip(
+            Value::Map(vec![
+                (Value::Unsigned(1), Value::Unsigned(2)),
+                (Value::Unsigned(3), Value::Unsigned(4)),
+            ]),
+            "a201020304",
+        );
+        round_trip(
+            Value::tagged(1, Value::Unsigned(1_363_896_240)),
+            "c11a514b67b0",
+        );
+        round_trip(Value::Bool(false), "f4");
+        round_trip(Value::Bool(true), "f5");
+        round_trip(Value::Null, "f6");
+    }
+
+    #[test]
+    fn test_accessors() {
+        let value = Value::Map(vec![
+            (Value::Unsigned(1), Value::tagged(37, Value::Unsigned(7))),
+            (Value::Unsigned(2), Value::Unsigned(9)),
+        ]);
+        assert_eq!(value.get(2).and_then(Value::as_u64), Some(9));
+        assert_eq!(
+            value.get(1).and_then(|v| v.untag(37)),
+            Some(&Value::Unsigned(7))
+        );
+        assert_eq!(value.get(1).and_then(|v| v.untag(38)), None);
+        assert_eq!(value.get(3), None);
+        assert_eq!(Value::Bytes(vec![1]).as_bytes(), Some(&[1u8][..]));
+        assert_eq!(Value::Text("a".to_string()).as_text(), Some("a"));
+        assert_eq!(Value::Array(Vec::new()).as_array(), Some(&[][..]));
+        assert_eq!(value.as_bytes(), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let invalid = |hex: &str| {
+            matches!(
+                Value::decode(&::hex::decode(hex).unwrap()),
+                Err(Error::InvalidCbor(_))
+            )
+        };
+        // Truncated, trailing, indefinite, float, reserved
+        assert!(invalid("1903"));
+        assert!(invalid("0000"));
+        assert!(invalid("5f"));
+        assert!(invalid("f93c00"));
+        assert!(invalid("1c"));
+        // Lengths beyond the input, invalid UTF-8
+        assert!(invalid("5bffffffffffffffff"));
+        assert!(invalid("9a7fffffff"));
+        assert!(invalid("62c328"));
+        // Nesting
+        assert!(invalid(&format!("{}00", "81".repeat(MAX_DEPTH + 1))));
+        assert!(!invalid(&format!("{}00", "81".repeat(MAX_DEPTH))));
+    }
+}
@@ -0,0 +1,80 @@
+//! Error types for the air-gapped signing crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while encoding, scanning or answering air-gapped requests.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Malformed UR string or fountain part, or a part from another UR.
+    #[error("Invalid UR: {0}")]
+    InvalidUr(String),
+
+    /// Unknown bytewords or a missing checksum.
+    #[error("Invalid bytewords: {0}")]
+    InvalidBytewords(String),
+
+    /// The bytewords or fountain coded message checksum does not match.
+    #[error("Checksum mismatch")]
+    ChecksumMismatch,
+
+    /// Malformed CBOR, or CBOR that is not a valid registry type.
+    #[error("Invalid CBOR: {0}")]
+    InvalidCbor(String),
+
+    /// The UR has a different type than expected.
+    #[error("Expected a {expected} UR, found {found}")]
+    UnexpectedType {
+        /// The type that was expected.
+        expected: String,
+        /// The type that was found.
+        found: String,
+    },
+
+    /// A well-formed request this crate cannot sign, such as a legacy transaction.
+    #[error("Unsupported request: {0}")]
+    UnsupportedRequest(String),
+
+    /// A response that does not answer the request, or a request that does not
+    /// match the signing key.
+    #[error("Request mismatch: {0}")]
+    RequestMismatch(String),
+
+    /// A session operation not allowed in its current phase.
+    #[error("Invalid state: {0}")]
+    InvalidState(String),
+
+    /// Error from BIP-32 operations.
+    #[error("BIP-32 error: {0}")]
+    Bip32Error(#[from] khodpay_bip32::Error),
+
+    /// Error from Bitcoin PSBT operations.
+    #[error("Bitcoin error: {0}")]
+    BitcoinError(#[from] khodpay_btc_signing::Error),
+
+    /// Error from EVM transaction or message signing.
+    #[error("Signing error: {0}")]
+    SigningError(#[from] khodpay_signing::Error),
+}
+
+/// Result type alias for air-gapped signing operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::UnexpectedType {
+                expected: "crypto-psbt".to_string(),
+                found: "bytes".to_string(),
+            }
+            .to_string(),
+            "Expected a crypto-psbt UR, found bytes"
+        );
+        assert_eq!(Error::ChecksumMismatch.to_string(), "Checksum mismatch");
+        let error = Error::from(khodpay_btc_signing::Error::PsbtError("empty".to_string()));
+        assert_eq!(error.to_string(), "Bitcoin error: Invalid PSBT: empty");
+    }
+}
@@ -0,0 +1,620 @@
+//! Ethereum signing requests: `eth-sign-request` and `eth-signature` (EIP-4527).
+//!
+//! The online wallet shows an [`EthSignRequest`] for a typed transaction, a
+//! `personal_sign` message or `eth_signTypedData_v4` JSON; the offline device
+//! answers with an [`EthSignature`] carrying the same request id. Legacy
+//! (pre-EIP-2718) transactions are rejected.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_airgap::{EthDataType, EthSignRequest, KeyPath, RegistryType};
+//! use khodpay_signing::Bip44Signer;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let signer = Bip44Signer::from_private_key(&[1u8; 32])?;
+//! let path = KeyPath::new("m/44'/60'/0'/0/0".parse()?, [0x73, 0xc5, 0xda, 0x0a]);
+//!
+//! // Online: the wallet asks for a personal_sign signature
+//! let request = EthSignRequest::new(EthDataType::PersonalMessage, b"gm".to_vec(), path)
+//!     .with_address(signer.address())
+//!     .with_origin("example.com");
+//!
+//! // Offline: scanned, reviewed and signed
+//! let request = EthSignRequest::from_ur(&request.to_ur())?;
+//! let signature = request.sign(&signer)?;
+//!
+//! // Online: the answer is checked against the request
+//! assert_eq!(request.verify(&signature)?, signer.address());
+//! # Ok(())
+//! # }
+//! ```
+
+use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+use khodpay_signing::{
+    eip191, recover_signer, Address, Bip44Signer, Eip1559Transaction, Eip2930Transaction, Signature,
+};
+
+use crate::account::KeyPath;
+use crate::cbor::Value;
+use crate::ur::RegistryType;
+use crate::{Error, Result};
+
+const TAG_UUID: u64 = 37;
+const TAG_KEYPATH: u64 = 304;
+
+/// What an [`EthSignRequest`] asks to sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EthDataType {
+    /// `eth_signTypedData_v4` JSON (EIP-712).
+    TypedData,
+    /// A `personal_sign` message (EIP-191).
+    PersonalMessage,
+    /// An unsigned EIP-2718 typed transaction: EIP-2930 or EIP-1559.
+    TypedTransaction,
+}
+
+impl EthDataType {
+    fn value(self) -> u64 {
+        match self {
+            EthDataType::TypedData => 2,
+            EthDataType::PersonalMessage => 3,
+            EthDataType::TypedTransaction => 4,
+        }
+    }
+
+    fn from_value(value: u64) -> Result<Self> {
+        match value {
+            1 => Err(Error::UnsupportedRequest(
+                "legacy transactions; use a typed transaction".to_string(),
+            )),
+            2 => Ok(EthDataType::TypedData),
+            3 => Ok(EthDataType::PersonalMessage),
+            4 => Ok(EthDataType::TypedTransaction),
+            _ => Err(Error::UnsupportedRequest(format!("data type {}", value))),
+        }
+    }
+}
+
+/// The sign data of a request, decoded.
+enum SignData {
+    Eip1559(Eip1559Transaction),
+    Eip2930(Eip2930Transaction),
+    TypedData(Box<TypedData>),
+    PersonalMessage,
+}
+
+/// A request to sign a transaction or message with the key at a derivation path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthSignRequest {
+    request_id: [u8; 16],
+    sign_data: Vec<u8>,
+    data_type: EthDataType,
+    chain_id: Option<u64>,
+    derivation_path: KeyPath,
+    address: Option<Address>,
+    origin: Option<String>,
+}
+
+impl EthSignRequest {
+    /// Creates a request with a random request id to sign `sign_data` with the key
+    /// at `derivation_path`.
+    ///
+    /// `sign_data` is the unsigned transaction envelope (`0x02 || rlp(…)`), the
+    /// message, or the typed data JSON, depending on `data_type`.
+    pub fn new(data_type: EthDataType, sign_data: Vec<u8>, derivation_path: KeyPath) -> Self {
+        let mut request_id: [u8; 16] = rand::random();
+        // UUID version 4, variant 1
+        request_id[6] = request_id[6] & 0x0f | 0x40;
+        request_id[8] = request_id[8] & 0x3f | 0x80;
+        Self {
+            request_id,
+            sign_data,
+            data_type,
+            chain_id: None,
+            derivation_path,
+            address: None,
+            origin: None,
+        }
+    }
+
+    /// Creates a request to sign an EIP-1559 transaction, for its chain.
+    pub fn transaction(tx: &Eip1559Transaction, derivation_path: KeyPath) -> Self {
+        Self::new(
+            EthDataType::TypedTransaction,
+            tx.encode_unsigned(),
+            derivation_path,
+        )
+        .with_chain_id(tx.chain_id.value())
+    }
+
+    /// Sets the chain the request is for; transactions and typed data for other
+    /// chains are refused.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Sets the address the key must have, so a wrong account or passphrase is
+    /// caught before signing.
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Sets the name of the wallet or dApp asking, shown on the offline device.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Returns the request id the [`EthSignature`] must carry.
+    pub fn request_id(&self) -> [u8; 16] {
+        self.request_id
+    }
+
+    /// Returns what is to be signed.
+    pub fn data_type(&self) -> EthDataType {
+        self.data_type
+    }
+
+    /// Returns the transaction envelope, message or typed data JSON.
+    pub fn sign_data(&self) -> &[u8] {
+        &self.sign_data
+    }
+
+    /// Returns the chain the request is for.
+    pub fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    /// Returns the derivation path of the signing key.
+    pub fn derivation_path(&self) -> &KeyPath {
+        &self.derivation_path
+    }
+
+    /// Returns the address the signing key must have.
+    pub fn address(&self) -> Option<Address> {
+        self.address
+    }
+
+    /// Returns the name of the wallet or dApp asking.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// Decodes the sign data and checks it against the request's chain.
+    fn decode_sign_data(&self) -> Result<SignData> {
+        let sign_data = match self.data_type {
+            EthDataType::TypedTransaction => match self.sign_data.first() {
+                Some(&Eip1559Transaction::TYPE) => {
+                    SignData::Eip1559(Eip1559Transaction::decode(&self.sign_data)?)
+                }
+                Some(&Eip2930Transaction::TYPE) => {
+                    SignData::Eip2930(Eip2930Transaction::decode(&self.sign_data)?)
+                }
+                _ => {
+                    return Err(Error::UnsupportedRequest(
+                        "transaction type other than EIP-2930 or EIP-1559".to_string(),
+                    ))
+                }
+            },
+            EthDataType::TypedData => {
+                let json = std::str::from_utf8(&self.sign_data)
+                    .map_err(|_| Error::InvalidCbor("typed data is not UTF-8".to_string()))?;
+                SignData::TypedData(Box::new(TypedData::from_json(json)?))
+            }
+            EthDataType::PersonalMessage => SignData::PersonalMessage,
+        };
+        let chain_id = match &sign_data {
+            SignData::Eip1559(tx) => Some(tx.chain_id.value()),
+            SignData::Eip2930(tx) => Some(tx.chain_id.value()),
+            SignData::TypedData(typed_data) => typed_data.chain_id()?,
+            SignData::PersonalMessage => None,
+        };
+        if let (Some(expected), Some(found)) = (self.chain_id, chain_id) {
+            if expected != found {
+                return Err(Error::RequestMismatch(format!(
+                    "request for chain {} signs for chain {}",
+                    expected, found
+                )));
+            }
+        }
+        Ok(sign_data)
+    }
+
+    /// Signs the request with `signer`, the key at
+    /// [`derivation_path`](Self::derivation_path).
+    ///
+    /// Transaction signatures carry the y-parity (`0` / `1`) as `v`; message and
+    /// typed data signatures carry `27` / `28`, as `personal_sign` and
+    /// `eth_signTypedData_v4` return them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RequestMismatch`] if the signer does not have the requested
+    /// address or the data is for another chain than the request,
+    /// [`Error::UnsupportedRequest`] for unsupported transaction types, and
+    /// [`Error::SigningError`] if the data is malformed or the signer's chain or
+    /// policy refuses it.
+    pub fn sign(&self, signer: &Bip44Signer) -> Result<EthSignature> {
+        if let Some(address) = self.address {
+            if address != signer.address() {
+                return Err(Error::RequestMismatch(format!(
+                    "request for {} cannot be signed by {}",
+                    address.to_checksum_string(),
+                    signer.address().to_checksum_string()
+                )));
+            }
+        }
+        let signature = match self.decode_sign_data()? {
+            SignData::Eip1559(tx) => signer.sign_transaction(&tx)?,
+            SignData::Eip2930(tx) => signer.sign_eip2930_transaction(&tx)?,
+            SignData::TypedData(typed_data) => legacy_v(sign_typed_data_json(signer, &typed_data)?),
+            SignData::PersonalMessage => legacy_v(signer.sign_message(&self.sign_data)?),
+        };
+        Ok(EthSignature {
+            request_id: self.request_id,
+            signature,
+            origin: None,
+        })
+    }
+
+    /// Checks that `signature` answers this request and returns the signer's
+    /// address.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RequestMismatch`] if the signature has another request id
+    /// or is not from the requested address, and [`Error::SigningError`] if it
+    /// does not recover.
+    pub fn verify(&self, signature: &EthSignature) -> Result<Address> {
+        if signature.request_id != self.request_id {
+            return Err(Error::RequestMismatch(
+                "signature for another request".to_string(),
+            ));
+        }
+        let hash = match self.decode_sign_data()? {
+            SignData::Eip1559(tx) => tx.signing_hash(),
+            SignData::Eip2930(tx) => tx.signing_hash(),
+            SignData::TypedData(typed_data) => typed_data.hash()?,
+            SignData::PersonalMessage => eip191::hash_message(&self.sign_data),
+        };
+        let signer = recover_signer(&hash, &signature.signature)?;
+        if let Some(address) = self.address {
+            if address != signer {
+                return Err(Error::RequestMismatch(format!(
+                    "signed by {} instead of {}",
+                    signer.to_checksum_string(),
+                    address.to_checksum_string()
+                )));
+            }
+        }
+        Ok(signer)
+    }
+}
+
+/// Adds 27 to the recovery id, the `v` of message signatures.
+fn legacy_v(mut signature: Signature) -> Signature {
+    signature.v += 27;
+    signature
+}
+
+fn uuid_value(request_id: [u8; 16]) -> Value {
+    Value::tagged(TAG_UUID, Value::Bytes(request_id.to_vec()))
+}
+
+fn uuid_from(value: Option<&Value>) -> Result<[u8; 16]> {
+    value
+        .and_then(|value| value.untag(TAG_UUID))
+        .and_then(Value::as_bytes)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::InvalidCbor("missing or malformed request id".to_string()))
+}
+
+fn origin_from(value: Option<&Value>) -> Result<Option<String>> {
+    value
+        .map(|origin| {
+            origin
+                .as_text()
+                .map(str::to_string)
+                .ok_or_else(|| Error::InvalidCbor("origin is not text".to_string()))
+        })
+        .transpose()
+}
+
+impl RegistryType for EthSignRequest {
+    const UR_TYPE: &'static str = "eth-sign-request";
+
+    fn to_cbor(&self) -> Vec<u8> {
+        let mut entries = vec![
+            (Value::Unsigned(1), uuid_value(self.request_id)),
+            (Value::Unsigned(2), Value::Bytes(self.sign_data.clone())),
+            (Value::Unsigned(3), Value::Unsigned(self.data_type.value())),
+        ];
+        if let Some(chain_id) = self.chain_id {
+            entries.push((Value::Unsigned(4), Value::Unsigned(chain_id)));
+        }
+        entries.push((
+            Value::Unsigned(5),
+            Value::tagged(TAG_KEYPATH, self.derivation_path.to_value()),
+        ));
+        if let Some(address) = self.address {
+            entries.push((
+                Value::Unsigned(6),
+                Value::Bytes(address.to_bytes().to_vec()),
+            ));
+        }
+        if let Some(origin) = &self.origin {
+            entries.push((Value::Unsigned(7), Value::Text(origin.clone())));
+        }
+        Value::Map(entries).encode()
+    }
+
+    fn from_cbor(cbor: &[u8]) -> Result<Self> {
+        let value = Value::decode(cbor)?;
+        let invalid = |message: &str| Error::InvalidCbor(message.to_string());
+        let sign_data = value
+            .get(2)
+            .and_then(Value::as_bytes)
+            .ok_or_else(|| invalid("request without sign data"))?
+            .to_vec();
+        let data_type = value
+            .get(3)
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid("request without a data type"))
+            .and_then(EthDataType::from_value)?;
+        let chain_id = value
+            .get(4)
+            .map(|chain_id| {
+                chain_id
+                    .as_u64()
+                    .ok_or_else(|| invalid("chain id is not an integer"))
+            })
+            .transpose()?;
+        let derivation_path = value
+            .get(5)
+            .and_then(|path| path.untag(TAG_KEYPATH))
+            .ok_or_else(|| invalid("request without a derivation path"))
+            .and_then(KeyPath::from_value)?;
+        let address = value
+            .get(6)
+            .map(|address| {
+                address
+                    .as_bytes()
+                    .and_then(|bytes| <[u8; 20]>::try_from(bytes).ok())
+                    .map(Address::from_bytes)
+                    .ok_or_else(|| invalid("address is not 20 bytes"))
+            })
+            .transpose()?;
+        Ok(Self {
+            request_id: uuid_from(value.get(1))?,
+            sign_data,
+            data_type,
+            chain_id,
+            derivation_path,
+            address,
+            origin: origin_from(value.get(7))?,
+        })
+    }
+}
+
+/// The signature answering an [`EthSignRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthSignature {
+    request_id: [u8; 16],
+    signature: Signature,
+    origin: Option<String>,
+}
+
+impl EthSignature {
+    /// Sets the name of the signing device, shown on the online wallet.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Returns the id of the request this answers.
+    pub fn request_id(&self) -> [u8; 16] {
+        self.request_id
+    }
+
+    /// Returns the signature, `r ‖ s ‖ v`.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Returns the name of the signing device.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+}
+
+impl RegistryType for EthSignature {
+    const UR_TYPE: &'static str = "eth-signature";
+
+    fn to_cbor(&self) -> Vec<u8> {
+        let mut entries = vec![
+            (Value::Unsigned(1), uuid_value(self.request_id)),
+            (
+                Value::Unsigned(2),
+                Value::Bytes(self.signature.to_bytes().to_vec()),
+            ),
+        ];
+        if let Some(origin) = &self.origin {
+            entries.push((Value::Unsigned(3), Value::Text(origin.clone())));
+        }
+        Value::Map(entries).encode()
+    }
+
+    fn from_cbor(cbor: &[u8]) -> Result<Self> {
+        let value = Value::decode(cbor)?;
+        let signature = value
+            .get(2)
+            .and_then(Value::as_bytes)
+            .and_then(Signature::from_bytes)
+            .ok_or_else(|| Error::InvalidCbor("signature is not 65 bytes".to_string()))?;
+        Ok(Self {
+            request_id: uuid_from(value.get(1))?,
+            signature,
+            origin: origin_from(value.get(3))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_signing::{ChainId, Wei};
+
+    fn path() -> KeyPath {
+        KeyPath::new(
+            "m/44'/60'/0'/0/0".parse().unwrap(),
+            [0x73, 0xc5, 0xda, 0x0a],
+        )
+    }
+
+    fn signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    fn tx(chain_id: ChainId) -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(3)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21000)
+            .to(Address::from_bytes([9; 20]))
+            .value(Wei::from_gwei(1_000))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        let request = EthSignRequest::new(EthDataType::PersonalMessage, b"gm".to_vec(), path())
+            .with_chain_id(1)
+            .with_address(Address::from_bytes([0xaa; 20]))
+            .with_origin("web");
+        // UUID version and variant bits
+        assert_eq!(request.request_id()[6] >> 4, 4);
+        assert_eq!(request.request_id()[8] >> 6, 2);
+        assert_ne!(
+            request.request_id(),
+            EthSignRequest::new(EthDataType::PersonalMessage, Vec::new(), path()).request_id()
+        );
+
+        let cbor = ::hex::encode(request.to_cbor());
+        let expected = format!(
+            "a701d82550{}0242676d03030401\
+             05d90130a2018a182cf5183cf500f500f400f4021a73c5da0a\
+             0654{}0763776562",
+            ::hex::encode(request.request_id()),
+            "aa".repeat(20),
+        );
+        assert_eq!(cbor, expected);
+        assert_eq!(EthSignRequest::from_ur(&request.to_ur()).unwrap(), request);
+    }
+
+    #[test]
+    fn test_sign_transaction() {
+        let signer = signer();
+        let tx = tx(ChainId::BscMainnet);
+        let request = EthSignRequest::transaction(&tx, path()).with_address(signer.address());
+        assert_eq!(request.chain_id(), Some(56));
+
+        let signature = request.sign(&signer).unwrap();
+        assert_eq!(signature.request_id(), request.request_id());
+        assert!(signature.signature().v <= 1);
+        assert_eq!(
+            signature.signature(),
+            &signer.sign_transaction(&tx).unwrap()
+        );
+        assert_eq!(request.verify(&signature).unwrap(), signer.address());
+
+        let decoded = EthSignature::from_ur(&signature.with_origin("vault").to_ur()).unwrap();
+        assert_eq!(decoded.origin(), Some("vault"));
+        assert_eq!(request.verify(&decoded).unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_sign_messages() {
+        let signer = signer();
+        let request = EthSignRequest::new(EthDataType::PersonalMessage, b"gm".to_vec(), path());
+        let signature = request.sign(&signer).unwrap();
+        assert!(signature.signature().v >= 27);
+        assert_eq!(request.verify(&signature).unwrap(), signer.address());
+
+        let json = r#"{
+            "types": { "Greeting": [{ "name": "text", "type": "string" }] },
+            "primaryType": "Greeting",
+            "domain": { "name": "Example", "chainId": 56 },
+            "message": { "text": "gm" }
+        }"#;
+        let request = EthSignRequest::new(EthDataType::TypedData, json.as_bytes().to_vec(), path());
+        let signature = request.sign(&signer).unwrap();
+        assert!(signature.signature().v >= 27);
+        assert_eq!(request.verify(&signature).unwrap(), signer.address());
+
+        let request = request.with_chain_id(1);
+        assert!(matches!(
+            request.sign(&signer),
+            Err(Error::RequestMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let signer = signer();
+        let request =
+            EthSignRequest::transaction(&tx(ChainId::BscMainnet), path()).with_chain_id(1);
+        assert!(matches!(
+            request.sign(&signer),
+            Err(Error::RequestMismatch(_))
+        ));
+
+        let request = EthSignRequest::transaction(&tx(ChainId::BscMainnet), path())
+            .with_address(Address::from_bytes([0xaa; 20]));
+        assert!(matches!(
+            request.sign(&signer),
+            Err(Error::RequestMismatch(_))
+        ));
+
+        let request = EthSignRequest::transaction(&tx(ChainId::BscMainnet), path());
+        let signature = request.sign(&signer).unwrap();
+        let other = EthSignRequest::transaction(&tx(ChainId::BscMainnet), path());
+        assert!(matches!(
+            other.verify(&signature),
+            Err(Error::RequestMismatch(_))
+        ));
+        let other = request
+            .clone()
+            .with_address(Address::from_bytes([0xaa; 20]));
+        assert!(matches!(
+            other.verify(&signature),
+            Err(Error::RequestMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let mut cbor =
+            EthSignRequest::new(EthDataType::TypedTransaction, vec![0xc0], path()).to_cbor();
+        assert!(matches!(
+            EthSignRequest::new(EthDataType::TypedTransaction, vec![0xc0], path()).sign(&signer()),
+            Err(Error::UnsupportedRequest(_))
+        ));
+        // Data type 4 → 1, a legacy transaction
+        let position = cbor.windows(2).position(|w| w == [0x03, 0x04]).unwrap();
+        cbor[position + 1] = 0x01;
+        assert!(matches!(
+            EthSignRequest::from_cbor(&cbor),
+            Err(Error::UnsupportedRequest(_))
+        ));
+        assert!(matches!(
+            EthSignature::from_cbor(&::hex::decode("a10241ff").unwrap()),
+            Err(Error::InvalidCbor(_))
+        ));
+    }
+}
@@ -0,0 +1,514 @@
+//! Fountain codes that split a message over any number of QR frames (BCR-2020-005).
+//!
+//! A message is cut into equal fragments. Parts `1..=seq_len` carry one fragment
+//! each; every later part carries the XOR of a pseudo-random set of fragments,
+//! chosen from the part's sequence number and the message checksum. A scanner that
+//! misses frames keeps reading the endless stream of mixed parts until it can solve
+//! for every fragment, whichever frames it caught.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use sha2::{Digest, Sha256};
+
+use crate::bytewords::crc32;
+use crate::cbor::Value;
+use crate::{Error, Result};
+
+/// Shortest fragment the encoder cuts, in bytes.
+pub(crate) const MIN_FRAGMENT_LEN: usize = 10;
+
+/// Longest message a decoder accepts, in bytes.
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Most fragments a decoder accepts; more frames than anyone would scan.
+const MAX_SEQ_LEN: usize = 65_536;
+
+/// The xoshiro256** generator, seeded as the reference implementation does.
+pub(crate) struct Xoshiro256 {
+    s: [u64; 4],
+}
+
+impl Xoshiro256 {
+    /// Seeds the generator with the SHA-256 of `seed`.
+    pub(crate) fn new(seed: &[u8]) -> Self {
+        let hash: [u8; 32] = Sha256::digest(seed).into();
+        let mut s = [0u64; 4];
+        for (word, chunk) in s.iter_mut().zip(hash.chunks(8)) {
+            *word = u64::from_be_bytes(chunk.try_into().expect("8 bytes"));
+        }
+        Self { s }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Returns a value in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        self.next_u64() as f64 / (u64::MAX as f64 + 1.0)
+    }
+
+    /// Returns a value in `low..=high`.
+    pub(crate) fn next_int(&mut self, low: usize, high: usize) -> usize {
+        (self.next_f64() * (high - low + 1) as f64) as usize + low
+    }
+}
+
+/// Walker's alias method over a fixed distribution.
+struct Sampler {
+    probs: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl Sampler {
+    fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        let mut p: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let (mut small, mut large) = (Vec::new(), Vec::new());
+        for j in (0..n).rev() {
+            if p[j] < 1.0 {
+                small.push(j);
+            } else {
+                large.push(j);
+            }
+        }
+
+        let mut probs = vec![0.0; n];
+        let mut aliases = vec![0; n];
+        while let (Some(&a), Some(&g)) = (small.last(), large.last()) {
+            small.pop();
+            large.pop();
+            probs[a] = p[a];
+            aliases[a] = g;
+            p[g] += p[a] - 1.0;
+            if p[g] < 1.0 {
+                small.push(g);
+            } else {
+                large.push(g);
+            }
+        }
+        for j in large.into_iter().chain(small) {
+            probs[j] = 1.0;
+        }
+        Self { probs, aliases }
+    }
+
+    fn next(&self, rng: &mut Xoshiro256) -> usize {
+        let r1 = rng.next_f64();
+        let r2 = rng.next_f64();
+        let i = (self.probs.len() as f64 * r1) as usize;
+        if r2 < self.probs[i] {
+            i
+        } else {
+            self.aliases[i]
+        }
+    }
+}
+
+/// Picks the fragments mixed into parts, which encoder and decoder must agree on.
+struct Chooser {
+    seq_len: usize,
+    checksum: u32,
+    /// Degree distribution: degree `d` is picked with weight `1 / d`.
+    degrees: Option<Sampler>,
+}
+
+impl Chooser {
+    fn new(seq_len: usize, checksum: u32) -> Self {
+        Self {
+            seq_len,
+            checksum,
+            degrees: None,
+        }
+    }
+
+    /// Returns the sorted fragment indexes XORed into part `seq_num`.
+    fn choose(&mut self, seq_num: u32) -> BTreeSet<usize> {
+        if seq_num as usize <= self.seq_len {
+            return BTreeSet::from([seq_num as usize - 1]);
+        }
+        let mut seed = [0u8; 8];
+        seed[..4].copy_from_slice(&seq_num.to_be_bytes());
+        seed[4..].copy_from_slice(&self.checksum.to_be_bytes());
+        let mut rng = Xoshiro256::new(&seed);
+
+        let seq_len = self.seq_len;
+        let degrees = self.degrees.get_or_insert_with(|| {
+            let weights: Vec<f64> = (1..=seq_len).map(|d| 1.0 / d as f64).collect();
+            Sampler::new(&weights)
+        });
+        let degree = degrees.next(&mut rng) + 1;
+        shuffled((0..seq_len).collect(), &mut rng)
+            .into_iter()
+            .take(degree)
+            .collect()
+    }
+}
+
+/// Returns `items` shuffled the way the reference implementation does.
+pub(crate) fn shuffled<T>(mut items: Vec<T>, rng: &mut Xoshiro256) -> Vec<T> {
+    let mut result = Vec::with_capacity(items.len());
+    while !items.is_empty() {
+        let index = rng.next_int(0, items.len() - 1);
+        result.push(items.remove(index));
+    }
+    result
+}
+
+/// One frame of a fountain coded message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Part {
+    pub(crate) seq_num: u32,
+    pub(crate) seq_len: usize,
+    pub(crate) message_len: usize,
+    pub(crate) checksum: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+impl Part {
+    /// Returns the CBOR array `[seq_num, seq_len, message_len, checksum, data]`.
+    pub(crate) fn to_cbor(&self) -> Vec<u8> {
+        Value::Array(vec![
+            Value::Unsigned(self.seq_num as u64),
+            Value::Unsigned(self.seq_len as u64),
+            Value::Unsigned(self.message_len as u64),
+            Value::Unsigned(self.checksum as u64),
+            Value::Bytes(self.data.clone()),
+        ])
+        .encode()
+    }
+
+    pub(crate) fn from_cbor(cbor: &[u8]) -> Result<Self> {
+        let invalid = || Error::InvalidUr("malformed fountain part".to_string());
+        let Value::Array(items) = Value::decode(cbor)? else {
+            return Err(invalid());
+        };
+        let [seq_num, seq_len, message_len, checksum, Value::Bytes(data)] = items.as_slice() else {
+            return Err(invalid());
+        };
+        let uint = |value: &Value| value.as_u64().ok_or_else(invalid);
+        Ok(Self {
+            seq_num: u32::try_from(uint(seq_num)?).map_err(|_| invalid())?,
+            seq_len: usize::try_from(uint(seq_len)?).map_err(|_| invalid())?,
+            message_len: usize::try_from(uint(message_len)?).map_err(|_| invalid())?,
+            checksum: u32::try_from(uint(checksum)?).map_err(|_| invalid())?,
+            data: data.clone(),
+        })
+    }
+}
+
+/// Cuts a message into fragments and emits parts forever.
+pub(crate) struct Encoder {
+    fragments: Vec<Vec<u8>>,
+    message_len: usize,
+    checksum: u32,
+    chooser: Chooser,
+    seq_num: u32,
+}
+
+impl Encoder {
+    /// Creates an encoder for a non-empty `message`, with fragments of at most
+    /// `max_fragment_len` bytes.
+    pub(crate) fn new(message: &[u8], max_fragment_len: usize) -> Self {
+        let fragment_len = fragment_len(message.len(), MIN_FRAGMENT_LEN, max_fragment_len);
+        let fragments: Vec<Vec<u8>> = message
+            .chunks(fragment_len)
+            .map(|chunk| {
+                let mut fragment = chunk.to_vec();
+                fragment.resize(fragment_len, 0);
+                fragment
+            })
+            .collect();
+        let checksum = crc32(message);
+        Self {
+            chooser: Chooser::new(fragments.len(), checksum),
+            fragments,
+            message_len: message.len(),
+            checksum,
+            seq_num: 0,
+        }
+    }
+
+    /// Returns the number of fragments, the parts needed at the very least.
+    pub(crate) fn seq_len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Returns the next part; the first [`seq_len`](Self::seq_len) are the fragments
+    /// themselves, in order.
+    pub(crate) fn next_part(&mut self) -> Part {
+        self.seq_num = self.seq_num.wrapping_add(1).max(1);
+        let mut data = vec![0u8; self.fragments[0].len()];
+        for index in self.chooser.choose(self.seq_num) {
+            xor_into(&mut data, &self.fragments[index]);
+        }
+        Part {
+            seq_num: self.seq_num,
+            seq_len: self.seq_len(),
+            message_len: self.message_len,
+            checksum: self.checksum,
+            data,
+        }
+    }
+}
+
+/// Returns the fragment length that splits `message_len` bytes into the fewest
+/// fragments of at most `max_len`, but not below `min_len`.
+fn fragment_len(message_len: usize, min_len: usize, max_len: usize) -> usize {
+    let max_count = (message_len / min_len).max(1);
+    (1..=max_count)
+        .map(|count| message_len.div_ceil(count))
+        .find(|&len| len <= max_len)
+        .unwrap_or_else(|| message_len.div_ceil(max_count))
+}
+
+fn xor_into(target: &mut [u8], source: &[u8]) {
+    target.iter_mut().zip(source).for_each(|(t, s)| *t ^= s);
+}
+
+/// Collects parts until every fragment is known.
+#[derive(Default)]
+pub(crate) struct Decoder {
+    /// `(seq_len, message_len, checksum, fragment_len)` of the first part.
+    expected: Option<(usize, usize, u32, usize)>,
+    chooser: Option<Chooser>,
+    fragments: BTreeMap<usize, Vec<u8>>,
+    mixed: Vec<(BTreeSet<usize>, Vec<u8>)>,
+    seen: BTreeSet<u32>,
+    message: Option<Vec<u8>>,
+}
+
+impl Decoder {
+    /// Adds a part.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidUr`] if the part is malformed or belongs to another
+    /// message, and [`Error::ChecksumMismatch`] if the fragments decode to a message
+    /// that does not match its checksum.
+    pub(crate) fn receive(&mut self, part: Part) -> Result<()> {
+        if self.message.is_some() {
+            return Ok(());
+        }
+        if part.seq_num == 0 {
+            return Err(Error::InvalidUr("part number 0".to_string()));
+        }
+        let shape = (
+            part.seq_len,
+            part.message_len,
+            part.checksum,
+            part.data.len(),
+        );
+        match self.expected {
+            Some(expected) if expected != shape => {
+                return Err(Error::InvalidUr(
+                    "part belongs to another message".to_string(),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                let (seq_len, message_len, _, fragment_len) = shape;
+                if seq_len == 0
+                    || seq_len > MAX_SEQ_LEN
+                    || message_len == 0
+                    || message_len > MAX_MESSAGE_LEN
+                    || fragment_len == 0
+                    || message_len.div_ceil(fragment_len) != seq_len
+                {
+                    return Err(Error::InvalidUr("inconsistent fountain part".to_string()));
+                }
+                self.expected = Some(shape);
+                self.chooser = Some(Chooser::new(seq_len, part.checksum));
+            }
+        }
+        if !self.seen.insert(part.seq_num) {
+            return Ok(());
+        }
+
+        let chooser = self.chooser.as_mut().expect("set with expected");
+        let mut indexes = chooser.choose(part.seq_num);
+        let mut data = part.data;
+        self.reduce(&mut indexes, &mut data);
+        match indexes.len() {
+            0 => {}
+            1 => self.add_fragment(indexes.pop_first().expect("one index"), data),
+            _ => self.mixed.push((indexes, data)),
+        }
+
+        let (seq_len, message_len, checksum, _) = shape;
+        if self.fragments.len() == seq_len {
+            let mut message: Vec<u8> = std::mem::take(&mut self.fragments)
+                .into_values()
+                .flatten()
+                .collect();
+            message.truncate(message_len);
+            if crc32(&message) != checksum {
+                *self = Self::default();
+                return Err(Error::ChecksumMismatch);
+            }
+            self.message = Some(message);
+            self.mixed.clear();
+        }
+        Ok(())
+    }
+
+    /// XORs the known fragments out of a mixed part.
+    fn reduce(&self, indexes: &mut BTreeSet<usize>, data: &mut [u8]) {
+        indexes.retain(|index| match self.fragments.get(index) {
+            Some(fragment) => {
+                xor_into(data, fragment);
+                false
+            }
+            None => true,
+        });
+    }
+
+    /// Records a fragment and solves every mixed part it completes, in turn.
+    fn add_fragment(&mut self, index: usize, data: Vec<u8>) {
+        let mut solved = vec![(index, data)];
+        while let Some((index, data)) = solved.pop() {
+            if self.fragments.contains_key(&index) {
+                continue;
+            }
+            for (indexes, mixed) in &mut self.mixed {
+                if indexes.remove(&index) {
+                    xor_into(mixed, &data);
+                }
+            }
+            self.fragments.insert(index, data);
+            let (done, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.mixed)
+                .into_iter()
+                .partition(|(indexes, _)| indexes.len() <= 1);
+            self.mixed = pending;
+            solved.extend(
+                done.into_iter()
+                    .filter_map(|(mut indexes, data)| Some((indexes.pop_first()?, data))),
+            );
+        }
+    }
+
+    /// Returns the share of fragments known, from `0.0` to `1.0`.
+    pub(crate) fn progress(&self) -> f64 {
+        match (self.expected, &self.message) {
+            (_, Some(_)) => 1.0,
+            (Some((seq_len, ..)), None) => self.fragments.len() as f64 / seq_len as f64,
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Returns the message once every fragment is known.
+    pub(crate) fn message(&self) -> Option<&[u8]> {
+        self.message.as_deref()
+    }
+}
+
+/// The reference implementation's test message: bytes from a "Wolf" generator.
+#[cfg(test)]
+pub(crate) fn make_message(len: usize) -> Vec<u8> {
+    let mut rng = Xoshiro256::new(b"Wolf");
+    (0..len).map(|_| rng.next_int(0, 255) as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xoshiro() {
+        let mut rng = Xoshiro256::new(b"Wolf");
+        let numbers: Vec<u64> = (0..12).map(|_| rng.next_u64() % 100).collect();
+        assert_eq!(numbers, [42, 81, 85, 8, 82, 84, 76, 73, 70, 88, 2, 74]);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut rng = Xoshiro256::new(b"Wolf");
+        assert_eq!(
+            shuffled((1..=10).collect(), &mut rng),
+            [6, 4, 9, 3, 10, 5, 7, 8, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_fragment_len() {
+        assert_eq!(fragment_len(12_345, 1_005, 1_955), 1_764);
+        assert_eq!(fragment_len(12_345, 1_005, 30_000), 12_345);
+        assert_eq!(fragment_len(259, 10, 30), 29);
+        assert_eq!(fragment_len(5, 10, 30), 5);
+    }
+
+    #[test]
+    fn test_choose_fragments() {
+        let mut chooser = Chooser::new(9, crc32(&make_message(256)));
+        assert_eq!(chooser.choose(3), BTreeSet::from([2]));
+        assert!((10..200).all(|seq_num| {
+            let indexes = chooser.choose(seq_num);
+            !indexes.is_empty() && indexes.iter().all(|&i| i < 9)
+        }));
+    }
+
+    #[test]
+    fn test_round_trip_with_losses() {
+        let message = make_message(1_000);
+        let mut encoder = Encoder::new(&message, 100);
+        assert_eq!(encoder.seq_len(), 10);
+
+        let mut decoder = Decoder::default();
+        let mut received = 0;
+        for seq_num in 1..1_000 {
+            let part = encoder.next_part();
+            assert_eq!(part.seq_num, seq_num);
+            // Drop every third frame, including simple ones
+            if seq_num % 3 == 0 {
+                continue;
+            }
+            let part = Part::from_cbor(&part.to_cbor()).unwrap();
+            decoder.receive(part).unwrap();
+            received += 1;
+            if decoder.message().is_some() {
+                break;
+            }
+        }
+        assert_eq!(decoder.message(), Some(message.as_slice()));
+        assert_eq!(decoder.progress(), 1.0);
+        assert!(received < 40);
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let mut encoder = Encoder::new(&make_message(100), 30);
+        let mut decoder = Decoder::default();
+        decoder.receive(encoder.next_part()).unwrap();
+        assert!(decoder.progress() > 0.0);
+
+        let other = Encoder::new(&make_message(101), 30).next_part();
+        assert!(matches!(decoder.receive(other), Err(Error::InvalidUr(_))));
+
+        let mut corrupt = encoder.next_part();
+        corrupt.data[0] ^= 1;
+        decoder.receive(corrupt).unwrap();
+        decoder.receive(encoder.next_part()).unwrap();
+        assert!(matches!(
+            decoder.receive(encoder.next_part()),
+            Err(Error::ChecksumMismatch)
+        ));
+        assert_eq!(decoder.progress(), 0.0);
+
+        let mut bogus = Encoder::new(&make_message(100), 30).next_part();
+        bogus.seq_len = 2;
+        assert!(matches!(
+            Decoder::default().receive(bogus),
+            Err(Error::InvalidUr(_))
+        ));
+    }
+}
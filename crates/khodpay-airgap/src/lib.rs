@@ -0,0 +1,93 @@
+//! # Khodpay Airgap
+//!
+//! Air-gapped signing over animated QR codes, with Blockchain Commons Uniform
+//! Resources (BC-UR).
+//!
+//! An offline device holding the keys exports its accounts once, then signs
+//! requests from an online wallet without either device ever connecting to the
+//! other: requests and responses travel as QR codes, split into fountain coded
+//! frames when they are too large for one. The formats are the ones hardware
+//! wallets such as Keystone and Passport, and wallets such as Sparrow and MetaMask,
+//! already speak.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`Ur`], [`UrEncoder`], [`UrDecoder`] | `ur:` strings and animated QR frames |
+//! | [`bytewords`] | The bytewords encoding URs are written in |
+//! | [`CryptoAccount`], [`CryptoHdKey`] | Watch-only account export (`crypto-account`, `crypto-hdkey`) |
+//! | [`Psbt`](khodpay_btc_signing::psbt::Psbt) | Bitcoin requests and responses (`crypto-psbt`) |
+//! | [`EthSignRequest`], [`EthSignature`] | Ethereum requests and responses (`eth-sign-request`, `eth-signature`) |
+//! | [`Coordinator`], [`OfflineSigner`] | The request/response exchange of each side |
+//!
+//! ## Features
+//!
+//! - **Animated QR Codes**: Fountain coded frames that can be scanned in any order,
+//!   with missed frames made up by later ones
+//! - **Watch-Only Export**: Account xpubs with master fingerprint and path, for
+//!   BIP-44, BIP-49 and BIP-84 Bitcoin accounts and any `khodpay-bip44` account
+//! - **PSBT Signing**: PSBTv2 from `khodpay-btc-signing` in both directions
+//! - **Ethereum Signing**: EIP-1559 and EIP-2930 transactions, `personal_sign` and
+//!   EIP-712 typed data, checked against the requested chain and address
+//! - **Stale QR Protection**: Responses are matched to their request before they
+//!   are accepted
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_airgap::{
+//!     Coordinator, OfflineSigner, SigningRequest, SigningResponse, DEFAULT_MAX_FRAGMENT_LEN,
+//! };
+//! use khodpay_btc_signing::psbt::Psbt;
+//! use khodpay_btc_signing::BtcSigner;
+//!
+//! # fn run(psbt: Psbt, signer: &BtcSigner, show: impl Fn(&str), scan: impl Fn() -> String)
+//! # -> khodpay_airgap::Result<()> {
+//! // Online: show the unsigned PSBT
+//! let mut online = Coordinator::new(psbt.into(), DEFAULT_MAX_FRAGMENT_LEN);
+//! show(&online.next_part()?.to_uppercase());
+//!
+//! // Offline: scan, review and sign
+//! let mut offline = OfflineSigner::new(DEFAULT_MAX_FRAGMENT_LEN);
+//! while offline.receive(&scan())?.is_none() {}
+//! if let Some(SigningRequest::Psbt(psbt)) = offline.request() {
+//!     let mut psbt = psbt.clone();
+//!     psbt.sign(signer)?;
+//!     offline.respond(psbt.into())?;
+//! }
+//! show(&offline.next_part()?.to_uppercase());
+//!
+//! // Online: scan the signed PSBT, finalize and broadcast
+//! let response = loop {
+//!     if let Some(response) = online.receive(&scan())? {
+//!         break response;
+//!     }
+//! };
+//! if let SigningResponse::Psbt(mut psbt) = response {
+//!     psbt.finalize()?;
+//!     let tx = psbt.extract_tx()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod account;
+pub mod bytewords;
+mod cbor;
+mod error;
+mod eth;
+mod fountain;
+mod psbt;
+mod session;
+mod ur;
+
+pub use account::{CryptoAccount, CryptoHdKey, KeyPath, OutputDescriptor};
+pub use error::{Error, Result};
+pub use eth::{EthDataType, EthSignRequest, EthSignature};
+pub use session::{Coordinator, OfflineSigner, Phase, SigningRequest, SigningResponse};
+pub use ur::{RegistryType, Ur, UrDecoder, UrEncoder, DEFAULT_MAX_FRAGMENT_LEN};
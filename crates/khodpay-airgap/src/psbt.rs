@@ -0,0 +1,85 @@
+//! PSBTs as `crypto-psbt` URs (BCR-2020-006).
+//!
+//! The coordinator shows the unsigned PSBT, the offline device signs it with
+//! [`Psbt::sign`] and shows it back; both directions use the same type. Only
+//! PSBTv2, the version `khodpay-btc-signing` reads, is accepted.
+
+use khodpay_btc_signing::psbt::Psbt;
+
+use crate::cbor::Value;
+use crate::ur::RegistryType;
+use crate::{Error, Result};
+
+impl RegistryType for Psbt {
+    const UR_TYPE: &'static str = "crypto-psbt";
+
+    fn to_cbor(&self) -> Vec<u8> {
+        Value::Bytes(self.serialize()).encode()
+    }
+
+    fn from_cbor(cbor: &[u8]) -> Result<Self> {
+        let value = Value::decode(cbor)?;
+        let bytes = value
+            .as_bytes()
+            .ok_or_else(|| Error::InvalidCbor("crypto-psbt is not a byte string".to_string()))?;
+        Ok(Psbt::deserialize(bytes)?)
+    }
+}
+
+/// A one-input, one-output PSBT spending from the P2WPKH address of `signer`.
+#[cfg(test)]
+pub(crate) fn unsigned_psbt(signer: &khodpay_btc_signing::BtcSigner) -> Psbt {
+    use khodpay_btc_signing::psbt::{PsbtInput, PsbtOutput};
+    use khodpay_btc_signing::{OutPoint, Script, TxOut, Txid};
+
+    let mut psbt = Psbt::new(2);
+    let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([7; 32]), 0));
+    input.witness_utxo = Some(TxOut {
+        value: 20_000,
+        script_pubkey: signer.p2wpkh_address().script_pubkey(),
+    });
+    psbt.add_input(input).unwrap();
+    psbt.add_output(PsbtOutput::new(19_000, Script::p2wpkh(&[9; 20])))
+        .unwrap();
+    psbt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{UrDecoder, UrEncoder};
+    use khodpay_btc_signing::{BtcSigner, Network};
+
+    #[test]
+    fn test_round_trip() {
+        let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+        let psbt = unsigned_psbt(&signer);
+        let cbor = psbt.to_cbor();
+        assert_eq!(
+            Value::decode(&cbor).unwrap(),
+            Value::Bytes(psbt.serialize())
+        );
+
+        let ur = psbt.to_ur();
+        assert_eq!(ur.ur_type(), "crypto-psbt");
+        let mut encoder = UrEncoder::new(&ur, 50);
+        let mut decoder = UrDecoder::new();
+        while !decoder.is_complete() {
+            decoder.receive(&encoder.next_part()).unwrap();
+        }
+        let decoded = Psbt::from_ur(decoder.ur().unwrap()).unwrap();
+        assert_eq!(decoded.serialize(), psbt.serialize());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            Psbt::from_cbor(&Value::Text("psbt".to_string()).encode()),
+            Err(Error::InvalidCbor(_))
+        ));
+        assert!(matches!(
+            Psbt::from_cbor(&Value::Bytes(b"psbt\xff\x00".to_vec()).encode()),
+            Err(Error::BitcoinError(_))
+        ));
+    }
+}
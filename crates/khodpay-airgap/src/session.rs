@@ -0,0 +1,535 @@
+//! The request/response exchange between an online coordinator and an offline
+//! signer, one animated QR code in each direction.
+//!
+//! | Step | Online: [`Coordinator`] | Offline: [`OfflineSigner`] |
+//! |---|---|---|
+//! | 1 | [`Showing`](Phase::Showing) the request | [`Scanning`](Phase::Scanning) |
+//! | 2 | | [`Reviewing`](Phase::Reviewing) the request, then signing it |
+//! | 3 | [`Scanning`](Phase::Scanning) the response | [`Showing`](Phase::Showing) the response |
+//! | 4 | [`Complete`](Phase::Complete): broadcast | |
+//!
+//! Both sides check that the response answers the request: the same unsigned
+//! transaction for PSBTs, the request id and signer for Ethereum. A stale QR code
+//! from an earlier exchange is rejected instead of being broadcast.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_airgap::{
+//!     Coordinator, EthDataType, EthSignRequest, KeyPath, OfflineSigner, SigningRequest,
+//!     SigningResponse, DEFAULT_MAX_FRAGMENT_LEN,
+//! };
+//! use khodpay_signing::Bip44Signer;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let key = Bip44Signer::from_private_key(&[1u8; 32])?;
+//! let path = KeyPath::new("m/44'/60'/0'/0/0".parse()?, [0x73, 0xc5, 0xda, 0x0a]);
+//! let request = EthSignRequest::new(EthDataType::PersonalMessage, b"gm".to_vec(), path);
+//!
+//! let mut online = Coordinator::new(request.into(), DEFAULT_MAX_FRAGMENT_LEN);
+//! let mut offline = OfflineSigner::new(DEFAULT_MAX_FRAGMENT_LEN);
+//!
+//! // The offline device scans until it has the whole request
+//! while offline.receive(&online.next_part()?)?.is_none() {}
+//! let Some(SigningRequest::Eth(request)) = offline.request() else { unreachable!() };
+//! let signature = request.sign(&key)?;
+//! offline.respond(signature.into())?;
+//!
+//! // And the online device scans the answer
+//! let response = loop {
+//!     if let Some(response) = online.receive(&offline.next_part()?)? {
+//!         break response;
+//!     }
+//! };
+//! assert!(matches!(response, SigningResponse::Eth(_)));
+//! # Ok(())
+//! # }
+//! ```
+
+use khodpay_btc_signing::psbt::Psbt;
+
+use crate::ur::RegistryType;
+use crate::{Error, EthSignRequest, EthSignature, Result, Ur, UrDecoder, UrEncoder};
+
+/// Where a [`Coordinator`] or [`OfflineSigner`] is in the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Showing an animated QR code.
+    Showing,
+    /// Scanning the other device's QR code.
+    Scanning,
+    /// A request was scanned and awaits the user's decision.
+    Reviewing,
+    /// The response was scanned and checked.
+    Complete,
+}
+
+/// A request for the offline device to sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigningRequest {
+    /// An unsigned PSBT, as `crypto-psbt`.
+    Psbt(Psbt),
+    /// An Ethereum transaction or message, as `eth-sign-request`.
+    Eth(EthSignRequest),
+}
+
+impl SigningRequest {
+    /// Returns the request as a UR.
+    pub fn to_ur(&self) -> Ur {
+        match self {
+            SigningRequest::Psbt(psbt) => psbt.to_ur(),
+            SigningRequest::Eth(request) => request.to_ur(),
+        }
+    }
+
+    /// Decodes a `crypto-psbt` or `eth-sign-request` UR.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnexpectedType`] for other types, or an error if the payload
+    /// does not decode.
+    pub fn from_ur(ur: &Ur) -> Result<Self> {
+        match ur.ur_type() {
+            Psbt::UR_TYPE => Ok(SigningRequest::Psbt(Psbt::from_ur(ur)?)),
+            EthSignRequest::UR_TYPE => Ok(SigningRequest::Eth(EthSignRequest::from_ur(ur)?)),
+            found => Err(Error::UnexpectedType {
+                expected: format!("{} or {}", Psbt::UR_TYPE, EthSignRequest::UR_TYPE),
+                found: found.to_string(),
+            }),
+        }
+    }
+
+    /// Checks that `response` answers this request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RequestMismatch`] if the response is for another request:
+    /// another unsigned transaction, another request id, or an Ethereum signature
+    /// from another address than requested.
+    pub fn check_response(&self, response: &SigningResponse) -> Result<()> {
+        match (self, response) {
+            (SigningRequest::Psbt(request), SigningResponse::Psbt(signed)) => {
+                if request.unsigned_tx()?.txid() != signed.unsigned_tx()?.txid() {
+                    return Err(Error::RequestMismatch(
+                        "PSBT for another transaction".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+            (SigningRequest::Eth(request), SigningResponse::Eth(signature)) => {
+                request.verify(signature).map(|_| ())
+            }
+            _ => Err(Error::RequestMismatch(
+                "response of another kind than the request".to_string(),
+            )),
+        }
+    }
+}
+
+impl From<Psbt> for SigningRequest {
+    fn from(psbt: Psbt) -> Self {
+        SigningRequest::Psbt(psbt)
+    }
+}
+
+impl From<EthSignRequest> for SigningRequest {
+    fn from(request: EthSignRequest) -> Self {
+        SigningRequest::Eth(request)
+    }
+}
+
+/// The offline device's answer to a [`SigningRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigningResponse {
+    /// The PSBT with the offline device's signatures, as `crypto-psbt`.
+    Psbt(Psbt),
+    /// An Ethereum signature, as `eth-signature`.
+    Eth(EthSignature),
+}
+
+impl SigningResponse {
+    /// Returns the response as a UR.
+    pub fn to_ur(&self) -> Ur {
+        match self {
+            SigningResponse::Psbt(psbt) => psbt.to_ur(),
+            SigningResponse::Eth(signature) => signature.to_ur(),
+        }
+    }
+
+    /// Decodes a `crypto-psbt` or `eth-signature` UR.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnexpectedType`] for other types, or an error if the payload
+    /// does not decode.
+    pub fn from_ur(ur: &Ur) -> Result<Self> {
+        match ur.ur_type() {
+            Psbt::UR_TYPE => Ok(SigningResponse::Psbt(Psbt::from_ur(ur)?)),
+            EthSignature::UR_TYPE => Ok(SigningResponse::Eth(EthSignature::from_ur(ur)?)),
+            found => Err(Error::UnexpectedType {
+                expected: format!("{} or {}", Psbt::UR_TYPE, EthSignature::UR_TYPE),
+                found: found.to_string(),
+            }),
+        }
+    }
+}
+
+impl From<Psbt> for SigningResponse {
+    fn from(psbt: Psbt) -> Self {
+        SigningResponse::Psbt(psbt)
+    }
+}
+
+impl From<EthSignature> for SigningResponse {
+    fn from(signature: EthSignature) -> Self {
+        SigningResponse::Eth(signature)
+    }
+}
+
+fn wrong_phase(operation: &str, phase: Phase) -> Error {
+    Error::InvalidState(format!("cannot {} while {:?}", operation, phase))
+}
+
+/// The online side: shows a request and scans the response.
+#[derive(Debug)]
+pub struct Coordinator {
+    request: SigningRequest,
+    encoder: UrEncoder,
+    decoder: UrDecoder,
+    response: Option<SigningResponse>,
+}
+
+impl Coordinator {
+    /// Starts showing `request` in frames of at most `max_fragment_len` bytes.
+    pub fn new(request: SigningRequest, max_fragment_len: usize) -> Self {
+        Self {
+            encoder: UrEncoder::new(&request.to_ur(), max_fragment_len),
+            request,
+            decoder: UrDecoder::new(),
+            response: None,
+        }
+    }
+
+    /// Returns the current phase: [`Showing`](Phase::Showing) until the first
+    /// response frame is scanned, then [`Scanning`](Phase::Scanning) and
+    /// [`Complete`](Phase::Complete).
+    pub fn phase(&self) -> Phase {
+        if self.response.is_some() {
+            Phase::Complete
+        } else if self.decoder.progress() > 0.0 {
+            Phase::Scanning
+        } else {
+            Phase::Showing
+        }
+    }
+
+    /// Returns the request.
+    pub fn request(&self) -> &SigningRequest {
+        &self.request
+    }
+
+    /// Returns the next frame of the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidState`] once the response is complete.
+    pub fn next_part(&mut self) -> Result<String> {
+        match self.phase() {
+            Phase::Complete => Err(wrong_phase("show the request", Phase::Complete)),
+            _ => Ok(self.encoder.next_part()),
+        }
+    }
+
+    /// Returns the share of the response scanned, from `0.0` to `1.0`.
+    pub fn progress(&self) -> f64 {
+        self.decoder.progress()
+    }
+
+    /// Adds a scanned response frame, returning the response once it is complete
+    /// and answers the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidState`] once complete, the [`UrDecoder`] errors for
+    /// bad frames, and [`Error::UnexpectedType`] or [`Error::RequestMismatch`] if
+    /// the scanned UR is not the response to this request. Scanning starts over
+    /// after these, so the user can scan the right QR code.
+    pub fn receive(&mut self, part: &str) -> Result<Option<SigningResponse>> {
+        if self.response.is_some() {
+            return Err(wrong_phase("scan", Phase::Complete));
+        }
+        self.decoder.receive(part)?;
+        let Some(ur) = self.decoder.ur() else {
+            return Ok(None);
+        };
+        let response = SigningResponse::from_ur(ur).and_then(|response| {
+            self.request.check_response(&response)?;
+            Ok(response)
+        });
+        match response {
+            Ok(response) => {
+                self.response = Some(response.clone());
+                Ok(Some(response))
+            }
+            Err(e) => {
+                self.decoder = UrDecoder::new();
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the response once complete.
+    pub fn response(&self) -> Option<&SigningResponse> {
+        self.response.as_ref()
+    }
+}
+
+/// The offline side: scans a request, lets the user review it, and shows the
+/// response.
+#[derive(Debug)]
+pub struct OfflineSigner {
+    max_fragment_len: usize,
+    decoder: UrDecoder,
+    request: Option<SigningRequest>,
+    encoder: Option<UrEncoder>,
+}
+
+impl OfflineSigner {
+    /// Starts scanning; responses are shown in frames of at most
+    /// `max_fragment_len` bytes.
+    pub fn new(max_fragment_len: usize) -> Self {
+        Self {
+            max_fragment_len,
+            decoder: UrDecoder::new(),
+            request: None,
+            encoder: None,
+        }
+    }
+
+    /// Returns the current phase: [`Scanning`](Phase::Scanning),
+    /// [`Reviewing`](Phase::Reviewing), then [`Showing`](Phase::Showing) until
+    /// [`reset`](Self::reset).
+    pub fn phase(&self) -> Phase {
+        match (&self.request, &self.encoder) {
+            (None, _) => Phase::Scanning,
+            (Some(_), None) => Phase::Reviewing,
+            (Some(_), Some(_)) => Phase::Showing,
+        }
+    }
+
+    /// Returns the share of the request scanned, from `0.0` to `1.0`.
+    pub fn progress(&self) -> f64 {
+        self.decoder.progress()
+    }
+
+    /// Adds a scanned request frame, returning the request once it is complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidState`] once a request was scanned, the
+    /// [`UrDecoder`] errors for bad frames, and [`Error::UnexpectedType`] or a
+    /// decoding error if the UR is not a signing request this crate supports.
+    /// Scanning starts over after the latter.
+    pub fn receive(&mut self, part: &str) -> Result<Option<&SigningRequest>> {
+        if self.request.is_some() {
+            return Err(wrong_phase("scan", self.phase()));
+        }
+        self.decoder.receive(part)?;
+        let Some(ur) = self.decoder.ur() else {
+            return Ok(None);
+        };
+        match SigningRequest::from_ur(ur) {
+            Ok(request) => {
+                self.request = Some(request);
+                Ok(self.request.as_ref())
+            }
+            Err(e) => {
+                self.decoder = UrDecoder::new();
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the scanned request.
+    pub fn request(&self) -> Option<&SigningRequest> {
+        self.request.as_ref()
+    }
+
+    /// Answers the request under review and starts showing the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidState`] unless a request is under review, and
+    /// [`Error::RequestMismatch`] if `response` does not answer it.
+    pub fn respond(&mut self, response: SigningResponse) -> Result<()> {
+        let phase = self.phase();
+        let request = match (&self.request, phase) {
+            (Some(request), Phase::Reviewing) => request,
+            _ => return Err(wrong_phase("respond", phase)),
+        };
+        request.check_response(&response)?;
+        self.encoder = Some(UrEncoder::new(&response.to_ur(), self.max_fragment_len));
+        Ok(())
+    }
+
+    /// Returns the next frame of the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidState`] unless a response is being shown.
+    pub fn next_part(&mut self) -> Result<String> {
+        let phase = self.phase();
+        self.encoder
+            .as_mut()
+            .map(UrEncoder::next_part)
+            .ok_or_else(|| wrong_phase("show a response", phase))
+    }
+
+    /// Discards the request and any response, and starts scanning again. Use it
+    /// to reject a request, or once the online device has scanned the response.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.max_fragment_len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psbt::unsigned_psbt;
+    use crate::{EthDataType, KeyPath};
+    use khodpay_btc_signing::{BtcSigner, Network};
+    use khodpay_signing::Bip44Signer;
+
+    fn eth_request() -> EthSignRequest {
+        let path = KeyPath::new("m/44'/60'/0'/0/0".parse().unwrap(), [1, 2, 3, 4]);
+        EthSignRequest::new(EthDataType::PersonalMessage, b"gm".to_vec(), path)
+    }
+
+    /// Shows every frame of `show` to `scan` until `scan` returns a value.
+    fn transfer<T>(
+        mut show: impl FnMut() -> Result<String>,
+        mut scan: impl FnMut(&str) -> Result<Option<T>>,
+    ) -> T {
+        for _ in 0..1000 {
+            if let Some(value) = scan(&show().unwrap()).unwrap() {
+                return value;
+            }
+        }
+        panic!("transfer did not complete");
+    }
+
+    #[test]
+    fn test_psbt_exchange() {
+        let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+        let psbt = unsigned_psbt(&signer);
+        let mut online = Coordinator::new(psbt.clone().into(), 40);
+        let mut offline = OfflineSigner::new(40);
+        assert_eq!(
+            (online.phase(), offline.phase()),
+            (Phase::Showing, Phase::Scanning)
+        );
+
+        let request = transfer(
+            || online.next_part(),
+            |part| Ok(offline.receive(part)?.cloned()),
+        );
+        assert_eq!(request, SigningRequest::Psbt(psbt.clone()));
+        assert_eq!(offline.phase(), Phase::Reviewing);
+        assert!(matches!(offline.next_part(), Err(Error::InvalidState(_))));
+
+        let SigningRequest::Psbt(mut signed) = request else {
+            unreachable!()
+        };
+        assert_eq!(signed.sign(&signer).unwrap(), 1);
+        offline.respond(signed.clone().into()).unwrap();
+        assert_eq!(offline.phase(), Phase::Showing);
+
+        let response = transfer(|| offline.next_part(), |part| online.receive(part));
+        assert_eq!(response, SigningResponse::Psbt(signed));
+        assert_eq!(online.phase(), Phase::Complete);
+        assert!(online.response().is_some());
+        assert!(matches!(online.next_part(), Err(Error::InvalidState(_))));
+
+        offline.reset();
+        assert_eq!(offline.phase(), Phase::Scanning);
+    }
+
+    #[test]
+    fn test_eth_exchange() {
+        let key = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let request = eth_request().with_address(key.address());
+        let mut online = Coordinator::new(request.clone().into(), 40);
+        let mut offline = OfflineSigner::new(40);
+
+        transfer(
+            || online.next_part(),
+            |part| Ok(offline.receive(part)?.cloned()),
+        );
+        let signature = request.sign(&key).unwrap();
+        offline.respond(signature.clone().into()).unwrap();
+        let response = transfer(|| offline.next_part(), |part| online.receive(part));
+        assert_eq!(response, SigningResponse::Eth(signature));
+    }
+
+    #[test]
+    fn test_mismatched_responses() {
+        let key = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let request = eth_request();
+        let mut online = Coordinator::new(request.clone().into(), 200);
+
+        // A signature for an earlier request
+        let stale = eth_request().sign(&key).unwrap();
+        let part = SigningResponse::Eth(stale.clone()).to_ur().to_string();
+        assert!(matches!(
+            online.receive(&part),
+            Err(Error::RequestMismatch(_))
+        ));
+        assert_eq!(online.phase(), Phase::Showing);
+
+        // Its own request QR code
+        let part = online.next_part().unwrap();
+        assert!(matches!(
+            online.receive(&part),
+            Err(Error::UnexpectedType { .. })
+        ));
+
+        let mut offline = OfflineSigner::new(200);
+        assert!(matches!(
+            offline.respond(stale.clone().into()),
+            Err(Error::InvalidState(_))
+        ));
+        offline.receive(&part).unwrap().unwrap();
+        assert!(matches!(
+            offline.respond(stale.into()),
+            Err(Error::RequestMismatch(_))
+        ));
+        let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+        assert!(matches!(
+            offline.respond(unsigned_psbt(&signer).into()),
+            Err(Error::RequestMismatch(_))
+        ));
+        assert!(matches!(
+            offline.receive(&part),
+            Err(Error::InvalidState(_))
+        ));
+
+        // Right response
+        offline.respond(request.sign(&key).unwrap().into()).unwrap();
+        let part = offline.next_part().unwrap();
+        assert!(online.receive(&part).unwrap().is_some());
+        assert!(matches!(online.receive(&part), Err(Error::InvalidState(_))));
+    }
+
+    #[test]
+    fn test_offline_rejects_other_types() {
+        let mut offline = OfflineSigner::new(200);
+        let key = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let signature = eth_request().sign(&key).unwrap();
+        let part = signature.to_ur().to_string();
+        assert!(matches!(
+            offline.receive(&part),
+            Err(Error::UnexpectedType { .. })
+        ));
+        assert_eq!(offline.phase(), Phase::Scanning);
+    }
+}
@@ -0,0 +1,423 @@
+//! Uniform Resources (BCR-2020-005): typed CBOR as `ur:` strings and animated QR
+//! frames.
+//!
+//! A [`Ur`] small enough for one QR code is written as `ur:<type>/<bytewords>`.
+//! Larger ones go through a [`UrEncoder`], whose `ur:<type>/<n>-<total>/…` parts
+//! are shown one after another as an animated QR code; a [`UrDecoder`] on the other
+//! device reassembles them, in any order and with frames missing.
+//!
+//! The strings are lowercase. Uppercase them for QR codes, whose alphanumeric mode
+//! stores uppercase letters more densely; decoding ignores case.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_airgap::{Ur, UrDecoder, UrEncoder};
+//!
+//! let ur = Ur::new("bytes", vec![0x44, 1, 2, 3, 4]).unwrap();
+//! assert_eq!(ur.to_string(), "ur:bytes/fyadaoaxaaztdtdpfe");
+//!
+//! // A PSBT or other large payload, shown as frames of at most 100 bytes
+//! let payload = Ur::new("bytes", [vec![0x59, 0x03, 0xe8], vec![7u8; 1000]].concat()).unwrap();
+//! let mut encoder = UrEncoder::new(&payload, 100);
+//! let mut decoder = UrDecoder::new();
+//! while !decoder.is_complete() {
+//!     let frame = encoder.next_part().to_uppercase();
+//!     decoder.receive(&frame).unwrap();
+//! }
+//! assert_eq!(decoder.ur(), Some(&payload));
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bytewords::{self, Style};
+use crate::fountain::{self, Part};
+use crate::{Error, Result};
+
+/// Fragment length [`UrEncoder`]s are usually created with: frames that fit a
+/// version 14 QR code, which phone cameras read easily.
+pub const DEFAULT_MAX_FRAGMENT_LEN: usize = 200;
+
+/// A CBOR payload with its registered type, such as `crypto-psbt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ur {
+    ur_type: String,
+    cbor: Vec<u8>,
+}
+
+impl Ur {
+    /// Creates a UR of type `ur_type` holding the encoded CBOR item `cbor`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidUr`] if the type is not lowercase letters, digits
+    /// and dashes, or the payload is empty.
+    pub fn new(ur_type: impl Into<String>, cbor: Vec<u8>) -> Result<Self> {
+        let ur_type = ur_type.into();
+        if !is_valid_type(&ur_type) {
+            return Err(Error::InvalidUr(format!("invalid type {:?}", ur_type)));
+        }
+        if cbor.is_empty() {
+            return Err(Error::InvalidUr("empty payload".to_string()));
+        }
+        Ok(Self { ur_type, cbor })
+    }
+
+    /// Returns the type, such as `crypto-psbt`.
+    pub fn ur_type(&self) -> &str {
+        &self.ur_type
+    }
+
+    /// Returns the CBOR payload.
+    pub fn cbor(&self) -> &[u8] {
+        &self.cbor
+    }
+}
+
+impl fmt::Display for Ur {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ur:{}/{}",
+            self.ur_type,
+            bytewords::encode(&self.cbor, Style::Minimal)
+        )
+    }
+}
+
+impl FromStr for Ur {
+    type Err = Error;
+
+    /// Parses a single-part UR; multi-part ones go through a [`UrDecoder`].
+    fn from_str(s: &str) -> Result<Self> {
+        match parse(s)? {
+            Parsed::Single(ur) => Ok(ur),
+            Parsed::Multi { .. } => Err(Error::InvalidUr(
+                "multi-part URs need a UrDecoder".to_string(),
+            )),
+        }
+    }
+}
+
+enum Parsed {
+    Single(Ur),
+    Multi { ur_type: String, part: Part },
+}
+
+fn is_valid_type(ur_type: &str) -> bool {
+    !ur_type.is_empty()
+        && ur_type
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+fn parse(s: &str) -> Result<Parsed> {
+    let s = s.trim().to_ascii_lowercase();
+    let rest = s
+        .strip_prefix("ur:")
+        .ok_or_else(|| Error::InvalidUr("missing ur: scheme".to_string()))?;
+    let components: Vec<&str> = rest.split('/').collect();
+    match components.as_slice() {
+        [ur_type, body] => Ok(Parsed::Single(Ur::new(
+            *ur_type,
+            bytewords::decode(body, Style::Minimal)?,
+        )?)),
+        [ur_type, sequence, body] => {
+            if !is_valid_type(ur_type) {
+                return Err(Error::InvalidUr(format!("invalid type {:?}", ur_type)));
+            }
+            let part = Part::from_cbor(&bytewords::decode(body, Style::Minimal)?)?;
+            let numbers = sequence
+                .split_once('-')
+                .and_then(|(n, total)| Some((n.parse::<u32>().ok()?, total.parse().ok()?)));
+            if numbers != Some((part.seq_num, part.seq_len)) {
+                return Err(Error::InvalidUr(format!(
+                    "sequence {} does not match the part",
+                    sequence
+                )));
+            }
+            Ok(Parsed::Multi {
+                ur_type: ur_type.to_string(),
+                part,
+            })
+        }
+        _ => Err(Error::InvalidUr("expected ur:<type>/<data>".to_string())),
+    }
+}
+
+/// Splits a [`Ur`] into the endless stream of frames of an animated QR code.
+pub struct UrEncoder {
+    ur: Ur,
+    encoder: fountain::Encoder,
+}
+
+impl UrEncoder {
+    /// Creates an encoder whose frames carry at most `max_fragment_len` bytes of
+    /// the payload each; [`DEFAULT_MAX_FRAGMENT_LEN`] suits most cameras.
+    pub fn new(ur: &Ur, max_fragment_len: usize) -> Self {
+        Self {
+            ur: ur.clone(),
+            encoder: fountain::Encoder::new(&ur.cbor, max_fragment_len),
+        }
+    }
+
+    /// Returns `true` if the UR fits in one frame, a static QR code.
+    pub fn is_single_part(&self) -> bool {
+        self.encoder.seq_len() == 1
+    }
+
+    /// Returns the number of fragments: the fewest frames a scanner needs.
+    pub fn fragment_count(&self) -> usize {
+        self.encoder.seq_len()
+    }
+
+    /// Returns the next frame. A single-part UR is the same string every time;
+    /// otherwise the frames never run out, and the scanner stops once complete.
+    pub fn next_part(&mut self) -> String {
+        if self.is_single_part() {
+            return self.ur.to_string();
+        }
+        let part = self.encoder.next_part();
+        format!(
+            "ur:{}/{}-{}/{}",
+            self.ur.ur_type,
+            part.seq_num,
+            part.seq_len,
+            bytewords::encode(&part.to_cbor(), Style::Minimal)
+        )
+    }
+}
+
+impl fmt::Debug for UrEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrEncoder")
+            .field("ur_type", &self.ur.ur_type)
+            .field("fragment_count", &self.fragment_count())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reassembles a [`Ur`] from scanned frames.
+#[derive(Default)]
+pub struct UrDecoder {
+    ur_type: Option<String>,
+    decoder: fountain::Decoder,
+    ur: Option<Ur>,
+}
+
+impl UrDecoder {
+    /// Creates an empty decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a scanned frame. Frames after completion are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidUr`] if the frame is malformed or belongs to another
+    /// UR, and [`Error::ChecksumMismatch`] if the frames decode to a corrupt
+    /// payload, in which case the decoder starts over. A scanner can report these
+    /// and keep scanning.
+    pub fn receive(&mut self, part: &str) -> Result<()> {
+        if self.ur.is_some() {
+            return Ok(());
+        }
+        let (ur_type, part) = match parse(part)? {
+            Parsed::Single(ur) => {
+                self.check_type(ur.ur_type())?;
+                self.ur = Some(ur);
+                return Ok(());
+            }
+            Parsed::Multi { ur_type, part } => (ur_type, part),
+        };
+        self.check_type(&ur_type)?;
+        if let Err(e) = self.decoder.receive(part) {
+            if matches!(e, Error::ChecksumMismatch) {
+                self.ur_type = None;
+            }
+            return Err(e);
+        }
+        self.ur_type = Some(ur_type);
+        if let Some(message) = self.decoder.message() {
+            let ur_type = self.ur_type.clone().expect("set above");
+            self.ur = Some(Ur::new(ur_type, message.to_vec())?);
+        }
+        Ok(())
+    }
+
+    fn check_type(&self, ur_type: &str) -> Result<()> {
+        match &self.ur_type {
+            Some(expected) if expected != ur_type => Err(Error::InvalidUr(format!(
+                "expected a {} frame, got {}",
+                expected, ur_type
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `true` once the UR is complete.
+    pub fn is_complete(&self) -> bool {
+        self.ur.is_some()
+    }
+
+    /// Returns the share of the payload received, from `0.0` to `1.0`, for a
+    /// progress bar.
+    pub fn progress(&self) -> f64 {
+        if self.ur.is_some() {
+            1.0
+        } else {
+            self.decoder.progress()
+        }
+    }
+
+    /// Returns the UR once complete.
+    pub fn ur(&self) -> Option<&Ur> {
+        self.ur.as_ref()
+    }
+}
+
+impl fmt::Debug for UrDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrDecoder")
+            .field("ur_type", &self.ur_type)
+            .field("progress", &self.progress())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A registered UR type, such as [`EthSignRequest`](crate::EthSignRequest) for
+/// `eth-sign-request`.
+pub trait RegistryType: Sized {
+    /// The UR type, such as `crypto-psbt`.
+    const UR_TYPE: &'static str;
+
+    /// Returns the CBOR encoding.
+    fn to_cbor(&self) -> Vec<u8>;
+
+    /// Decodes the CBOR encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CBOR is malformed or not of this type.
+    fn from_cbor(cbor: &[u8]) -> Result<Self>;
+
+    /// Returns the value as a UR.
+    fn to_ur(&self) -> Ur {
+        Ur {
+            ur_type: Self::UR_TYPE.to_string(),
+            cbor: self.to_cbor(),
+        }
+    }
+
+    /// Decodes a UR of this type.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnexpectedType`] if the UR has another type, or an error if
+    /// its CBOR does not decode.
+    fn from_ur(ur: &Ur) -> Result<Self> {
+        if ur.ur_type != Self::UR_TYPE {
+            return Err(Error::UnexpectedType {
+                expected: Self::UR_TYPE.to_string(),
+                found: ur.ur_type.clone(),
+            });
+        }
+        Self::from_cbor(&ur.cbor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fountain::make_message;
+
+    /// The reference implementation's test UR: the message as a CBOR byte string.
+    fn make_message_ur(len: usize) -> Ur {
+        Ur::new(
+            "bytes",
+            crate::cbor::Value::Bytes(make_message(len)).encode(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_single_part() {
+        let ur = make_message_ur(50);
+        let s = ur.to_string();
+        assert_eq!(
+            s,
+            "ur:bytes/hdeymejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtgwdpfnsboxgwlbaawzuefywkdplrsrjynbvygabwjldapfcsdwkbrkch"
+        );
+        assert_eq!(s.parse::<Ur>().unwrap(), ur);
+        assert_eq!(s.to_uppercase().parse::<Ur>().unwrap(), ur);
+
+        let mut encoder = UrEncoder::new(&ur, DEFAULT_MAX_FRAGMENT_LEN);
+        assert!(encoder.is_single_part());
+        assert_eq!(encoder.next_part(), s);
+        assert_eq!(encoder.next_part(), s);
+    }
+
+    #[test]
+    fn test_multi_part() {
+        let ur = make_message_ur(256);
+        let mut encoder = UrEncoder::new(&ur, 30);
+        assert_eq!(encoder.fragment_count(), 9);
+        let parts: Vec<String> = (0..40).map(|_| encoder.next_part()).collect();
+        assert_eq!(
+            parts[0],
+            "ur:bytes/1-9/lpadascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtdkgslpgh"
+        );
+        assert_eq!(
+            parts[8],
+            "ur:bytes/9-9/lpasascfadaxcywenbpljkhdcajskecpmdckihdyhphfotjojtfmlnwmadspaxrkytbztpbauotbgtgtaeaevtgavtny"
+        );
+        // Mixed parts from here on, though the 10th happens to have degree 1
+        assert_eq!(
+            parts[9],
+            "ur:bytes/10-9/lpbkascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtwdkiplzs"
+        );
+        assert_eq!(
+            parts[10],
+            "ur:bytes/11-9/lpbdascfadaxcywenbpljkhdcahelbknlkuejnbadmssfhfrdpsbiegecpasvssovlgeykssjykklronvsjkvetiiapk"
+        );
+
+        // Only mixed parts
+        let mut decoder = UrDecoder::new();
+        for part in &parts[9..] {
+            decoder.receive(part).unwrap();
+        }
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.ur(), Some(&ur));
+        assert!(matches!(parts[0].parse::<Ur>(), Err(Error::InvalidUr(_))));
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let ur = make_message_ur(256);
+        let mut encoder = UrEncoder::new(&ur, 30);
+        let mut decoder = UrDecoder::new();
+        decoder.receive(&encoder.next_part()).unwrap();
+        assert!(decoder.progress() > 0.0 && !decoder.is_complete());
+
+        let other = Ur::new("crypto-psbt", vec![0x40]).unwrap();
+        let invalid = |decoder: &mut UrDecoder, part: &str| {
+            matches!(decoder.receive(part), Err(Error::InvalidUr(_)))
+        };
+        assert!(invalid(&mut decoder, &other.to_string()));
+        let part = encoder.next_part();
+        assert!(invalid(&mut decoder, &part.replace("2-9", "3-9")));
+        assert!(invalid(&mut decoder, &part.replace("ur:", "")));
+        assert!(invalid(&mut decoder, "ur:bytes"));
+        assert!(invalid(&mut decoder, "ur:Bytes_/aeaeaeae"));
+        assert!(matches!(
+            decoder.receive(&part[..part.len() - 1]),
+            Err(Error::InvalidBytewords(_))
+        ));
+        decoder.receive(&part).unwrap();
+
+        assert!(Ur::new("crypto psbt", vec![0]).is_err());
+        assert!(Ur::new("bytes", Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Amounts that carry their coin's decimals.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use primitive_types::U256;
+
+use crate::{format_fixed, format_units, parse_units, Result};
+
+/// Decimals of bitcoin: satoshis per bitcoin is 10^8.
+pub const SATS_DECIMALS: u8 = 8;
+
+/// Decimals of an EVM chain's native coin: wei per ether is 10^18.
+pub const WEI_DECIMALS: u8 = 18;
+
+/// An amount of some coin or token: an integer in base units together with the
+/// number of decimals between a whole unit and a base unit.
+///
+/// Carrying the decimals means an amount formats itself correctly and cannot be
+/// mixed up with one of another scale: arithmetic between amounts with different
+/// decimals returns `None`, as do overflows and underflows, and such amounts do
+/// not compare. Formatting always uses `.` and no digit grouping, whatever the
+/// locale, so the output can be parsed back; precision in `{:.2}` truncates rather
+/// than rounds, so a shown balance is never more than the real one.
+///
+/// `khodpay-signing` converts [`WEI_DECIMALS`] amounts to and from its `Wei`, and
+/// `khodpay-transfer` parses what the user typed, such as `12.5 USDT`, with an
+/// asset's decimals.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_amount::CoinAmount;
+///
+/// let balance = CoinAmount::parse("0.015", 8)?;
+/// assert_eq!(balance, CoinAmount::from_sats(1_500_000));
+///
+/// let fee = CoinAmount::from_sats(2_250);
+/// let left = balance.checked_sub(fee).unwrap();
+/// assert_eq!(left.to_string(), "0.0149775");
+/// assert_eq!(format!("{:.4}", left), "0.0149");
+///
+/// // Different decimals never mix
+/// assert_eq!(balance.checked_add(CoinAmount::parse("1", 18)?), None);
+/// # Ok::<(), khodpay_amount::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CoinAmount {
+    value: U256,
+    decimals: u8,
+}
+
+impl CoinAmount {
+    /// Creates an amount of `value` base units with `decimals` decimals.
+    pub fn new(value: impl Into<U256>, decimals: u8) -> Self {
+        Self {
+            value: value.into(),
+            decimals,
+        }
+    }
+
+    /// Returns zero with `decimals` decimals.
+    pub fn zero(decimals: u8) -> Self {
+        Self::new(0, decimals)
+    }
+
+    /// Creates an amount of bitcoin from satoshis.
+    pub fn from_sats(sats: u64) -> Self {
+        Self::new(sats, SATS_DECIMALS)
+    }
+
+    /// Parses a decimal number such as `12.5` into an amount with `decimals`
+    /// decimals. Surrounding whitespace is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`](crate::Error::InvalidAmount) if `s` is not a
+    /// decimal number or has more decimal places than `decimals`.
+    pub fn parse(s: &str, decimals: u8) -> Result<Self> {
+        let value = parse_units(s.trim(), decimals)?;
+        Ok(Self::new(value, decimals))
+    }
+
+    /// Returns the amount in base units.
+    pub fn value(&self) -> U256 {
+        self.value
+    }
+
+    /// Returns the number of decimals between a whole unit and a base unit.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Returns `true` if the amount is zero.
+    pub fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+
+    /// Returns the base units as a `u64`, or `None` if they do not fit.
+    pub fn as_u64(&self) -> Option<u64> {
+        (self.value <= U256::from(u64::MAX)).then(|| self.value.as_u64())
+    }
+
+    /// Returns the base units as a `u128`, or `None` if they do not fit.
+    pub fn as_u128(&self) -> Option<u128> {
+        (self.value <= U256::from(u128::MAX)).then(|| self.value.as_u128())
+    }
+
+    /// Adds two amounts, returning `None` on overflow or if their decimals differ.
+    pub fn checked_add(self, rhs: CoinAmount) -> Option<CoinAmount> {
+        self.with_same_decimals(rhs, U256::checked_add)
+    }
+
+    /// Subtracts `rhs`, returning `None` if it is larger or their decimals differ.
+    pub fn checked_sub(self, rhs: CoinAmount) -> Option<CoinAmount> {
+        self.with_same_decimals(rhs, U256::checked_sub)
+    }
+
+    /// Multiplies by `rhs`, returning `None` on overflow.
+    pub fn checked_mul(self, rhs: u64) -> Option<CoinAmount> {
+        let value = self.value.checked_mul(U256::from(rhs))?;
+        Some(Self::new(value, self.decimals))
+    }
+
+    /// Divides by `rhs`, rounding down, returning `None` if `rhs` is zero.
+    pub fn checked_div(self, rhs: u64) -> Option<CoinAmount> {
+        let value = self.value.checked_div(U256::from(rhs))?;
+        Some(Self::new(value, self.decimals))
+    }
+
+    /// Returns the same amount with `decimals` decimals, or `None` if that would
+    /// drop digits or overflow.
+    ///
+    /// ```rust
+    /// use khodpay_amount::CoinAmount;
+    ///
+    /// let usdt = CoinAmount::parse("12.5", 6).unwrap();
+    /// assert_eq!(usdt.rescale(18).unwrap().to_string(), "12.5");
+    /// assert_eq!(CoinAmount::parse("0.5", 18).unwrap().rescale(0), None);
+    /// ```
+    pub fn rescale(self, decimals: u8) -> Option<CoinAmount> {
+        let value = match decimals.cmp(&self.decimals) {
+            Ordering::Equal => self.value,
+            Ordering::Greater => self.value.checked_mul(pow10(decimals - self.decimals)?)?,
+            Ordering::Less => {
+                let divisor = pow10(self.decimals - decimals)?;
+                if !(self.value % divisor).is_zero() {
+                    return None;
+                }
+                self.value / divisor
+            }
+        };
+        Some(Self::new(value, decimals))
+    }
+
+    /// Applies `op` to the values of two amounts with the same decimals.
+    fn with_same_decimals(
+        self,
+        rhs: CoinAmount,
+        op: fn(U256, U256) -> Option<U256>,
+    ) -> Option<CoinAmount> {
+        if self.decimals != rhs.decimals {
+            return None;
+        }
+        Some(Self::new(op(self.value, rhs.value)?, self.decimals))
+    }
+}
+
+/// Returns 10 to the power `exponent`, or `None` beyond `U256`.
+fn pow10(exponent: u8) -> Option<U256> {
+    U256::from(10u8).checked_pow(U256::from(exponent))
+}
+
+impl PartialOrd for CoinAmount {
+    /// Compares amounts with the same decimals; others are unordered.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self.decimals == other.decimals).then(|| self.value.cmp(&other.value))
+    }
+}
+
+impl fmt::Display for CoinAmount {
+    /// Formats the amount as a decimal number, trimming trailing zeros, or with
+    /// exactly the requested precision (truncated).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = match f.precision() {
+            Some(precision) => format_fixed(self.value, self.decimals, precision),
+            None => format_units(self.value, self.decimals),
+        };
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_parse_and_format() {
+        let amount = CoinAmount::parse(" 12.5 ", 6).unwrap();
+        assert_eq!(amount.value(), U256::from(12_500_000));
+        assert_eq!(amount.decimals(), 6);
+        assert_eq!(amount.to_string(), "12.5");
+        assert_eq!(format!("{:.3}", amount), "12.500");
+        assert_eq!(format!("{:.0}", amount), "12");
+        assert_eq!(format!("{:>8}", amount), "    12.5");
+        assert_eq!(CoinAmount::zero(18).to_string(), "0");
+        assert_eq!(CoinAmount::new(1, 18).to_string(), "0.000000000000000001");
+
+        for s in ["", "1,5", "1.5.0", "-1", "1e3", "0.0000001"] {
+            assert!(
+                matches!(CoinAmount::parse(s, 6), Err(Error::InvalidAmount(_))),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_constructors() {
+        assert_eq!(
+            CoinAmount::from_sats(1_500_000),
+            CoinAmount::parse("0.015", 8).unwrap()
+        );
+        let wei = CoinAmount::new(1_000_000_000u64, WEI_DECIMALS);
+        assert_eq!(wei.decimals(), 18);
+        assert_eq!(wei.to_string(), "0.000000001");
+        assert_eq!(wei.as_u64(), Some(1_000_000_000));
+        assert_eq!(CoinAmount::new(U256::MAX, 18).as_u128(), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = CoinAmount::from_sats(1_000);
+        let b = CoinAmount::from_sats(300);
+        assert_eq!(a.checked_add(b), Some(CoinAmount::from_sats(1_300)));
+        assert_eq!(a.checked_sub(b), Some(CoinAmount::from_sats(700)));
+        assert_eq!(b.checked_sub(a), None);
+        assert_eq!(a.checked_mul(3), Some(CoinAmount::from_sats(3_000)));
+        assert_eq!(a.checked_div(3), Some(CoinAmount::from_sats(333)));
+        assert_eq!(a.checked_div(0), None);
+        assert_eq!(CoinAmount::new(U256::MAX, 8).checked_add(b), None);
+        assert_eq!(CoinAmount::new(U256::MAX, 8).checked_mul(2), None);
+
+        let other = CoinAmount::new(300, 6);
+        assert_eq!(a.checked_add(other), None);
+        assert_eq!(a.checked_sub(other), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let a = CoinAmount::from_sats(1_000);
+        assert!(a > CoinAmount::from_sats(999));
+        assert_eq!(a.partial_cmp(&CoinAmount::new(1_000, 6)), None);
+        assert_ne!(a, CoinAmount::new(1_000, 6));
+    }
+
+    #[test]
+    fn test_rescale() {
+        let usdt = CoinAmount::parse("12.5", 6).unwrap();
+        assert_eq!(usdt.rescale(6), Some(usdt));
+        assert_eq!(
+            usdt.rescale(18),
+            Some(CoinAmount::parse("12.5", 18).unwrap())
+        );
+        assert_eq!(usdt.rescale(1), Some(CoinAmount::new(125, 1)));
+        assert_eq!(usdt.rescale(0), None);
+        assert_eq!(CoinAmount::new(U256::MAX, 0).rescale(1), None);
+        assert_eq!(CoinAmount::new(1, 0).rescale(u8::MAX), None);
+    }
+}
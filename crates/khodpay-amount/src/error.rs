@@ -0,0 +1,28 @@
+//! Error types for the amount crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while parsing amounts.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Error {
+    /// The string is not a decimal number, has too many decimal places, or
+    /// overflows 256 bits.
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+}
+
+/// Result type alias for amount operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::InvalidAmount("\"1.5.0\" is not a number".to_string()).to_string(),
+            "Invalid amount: \"1.5.0\" is not a number"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! # Khodpay Amount
+//!
+//! Amounts of coins and tokens that carry their decimals. A [`CoinAmount`] is an
+//! integer in base units (satoshis, wei, SUN, drops, token base units) together with
+//! the number of decimals between a whole unit and a base unit.
+//!
+//! The crate sits below every chain crate, so the Bitcoin and EVM signing crates, the
+//! chain clients and the send flow all hand amounts around as the same type instead
+//! of raw integers whose scale the caller has to remember.
+//!
+//! ## Modules
+//!
+//! | Item | Description |
+//! |---|---|
+//! | [`CoinAmount`] | An amount in base units with its coin's decimals |
+//! | [`parse_units`] | A decimal string to base units |
+//! | [`format_units`] | Base units to a decimal string, trailing zeros trimmed |
+//! | [`format_fixed`] | Base units to a decimal string with a fixed, truncated precision |
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_amount::CoinAmount;
+//!
+//! let balance = CoinAmount::parse("0.015", 8)?;
+//! assert_eq!(balance, CoinAmount::from_sats(1_500_000));
+//! assert_eq!(format!("{:.2}", balance), "0.01");
+//! # Ok::<(), khodpay_amount::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod amount;
+mod error;
+mod units;
+
+pub use amount::{CoinAmount, SATS_DECIMALS, WEI_DECIMALS};
+pub use error::{Error, Result};
+pub use primitive_types::U256;
+pub use units::{format_fixed, format_units, parse_units};
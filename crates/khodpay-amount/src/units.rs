@@ -0,0 +1,156 @@
+//! Conversions between decimal strings and integer base units.
+
+use primitive_types::U256;
+
+use crate::{Error, Result};
+
+/// Parses a decimal string such as `"1.5"` into an integer amount with `decimals`
+/// fractional digits (`"1.5"` with 6 decimals is `1_500_000`).
+///
+/// Trailing zeros beyond `decimals` are accepted; any other extra precision is
+/// rejected rather than silently truncated.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidAmount`] if the string is not a plain decimal number,
+/// has more significant fractional digits than `decimals`, or overflows `U256`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_amount::{parse_units, U256};
+///
+/// assert_eq!(parse_units("12.5", 6).unwrap(), U256::from(12_500_000u64));
+/// assert!(parse_units("0.0000001", 6).is_err());
+/// ```
+pub fn parse_units(s: &str, decimals: u8) -> Result<U256> {
+    let invalid = || Error::InvalidAmount(format!("invalid decimal amount: {:?}", s));
+    let (whole, fraction) = match s.split_once('.') {
+        Some((whole, fraction)) if !fraction.is_empty() => (whole, fraction),
+        Some(_) => return Err(invalid()),
+        None => (s, ""),
+    };
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(invalid());
+    }
+
+    let decimals = usize::from(decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals {
+        return Err(Error::InvalidAmount(format!(
+            "{:?} has more than {} decimal places",
+            s, decimals
+        )));
+    }
+
+    let overflow = || Error::InvalidAmount(format!("amount overflows U256: {:?}", s));
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+    U256::from_dec_str(digits).map_err(|_| overflow())
+}
+
+/// Formats an integer amount with `decimals` fractional digits, trimming trailing zeros.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_amount::{format_units, U256};
+///
+/// assert_eq!(format_units(U256::from(12_500_000u64), 6), "12.5");
+/// assert_eq!(format_units(U256::from(3_000_000u64), 6), "3");
+/// ```
+pub fn format_units(amount: U256, decimals: u8) -> String {
+    let (whole, fraction) = split_units(amount, decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+/// Formats an integer amount with `decimals` fractional digits as a decimal string
+/// with exactly `precision` places.
+///
+/// Digits beyond `precision` are truncated, never rounded up, so a displayed
+/// balance never exceeds the real one.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_amount::{format_fixed, U256};
+///
+/// let amount = U256::from(123_456u64);
+/// assert_eq!(format_fixed(amount, 5, 2), "1.23");
+/// assert_eq!(format_fixed(amount, 5, 0), "1");
+/// assert_eq!(format_fixed(amount, 5, 7), "1.2345600");
+/// ```
+pub fn format_fixed(amount: U256, decimals: u8, precision: usize) -> String {
+    let (whole, fraction) = split_units(amount, decimals);
+    if precision == 0 {
+        return whole;
+    }
+    let fraction: String = fraction
+        .chars()
+        .chain(std::iter::repeat('0'))
+        .take(precision)
+        .collect();
+    format!("{}.{}", whole, fraction)
+}
+
+/// Splits an amount into its whole and zero-padded fractional digit strings.
+fn split_units(amount: U256, decimals: u8) -> (String, String) {
+    let decimals = usize::from(decimals);
+    let padded = format!("{:0>width$}", amount.to_string(), width = decimals + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - decimals);
+    (whole.to_string(), fraction.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1", 0).unwrap(), U256::from(1));
+        assert_eq!(parse_units("1.50", 1).unwrap(), U256::from(15));
+        assert_eq!(parse_units("0.000", 2).unwrap(), U256::zero());
+        assert_eq!(parse_units("007", 2).unwrap(), U256::from(700));
+
+        for s in ["", ".5", "1.", "1,5", "-1", "1e3", " 1"] {
+            assert!(
+                matches!(parse_units(s, 6), Err(Error::InvalidAmount(_))),
+                "{:?}",
+                s
+            );
+        }
+        assert!(parse_units("0.001", 2).is_err());
+        assert!(parse_units(&"9".repeat(80), 0).is_err());
+    }
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::zero(), 18), "0");
+        assert_eq!(format_units(U256::from(1), 18), "0.000000000000000001");
+        assert_eq!(format_units(U256::from(1_500), 3), "1.5");
+        assert_eq!(format_units(U256::from(42), 0), "42");
+    }
+
+    #[test]
+    fn test_format_fixed() {
+        assert_eq!(format_fixed(U256::from(1_999), 3, 2), "1.99");
+        assert_eq!(format_fixed(U256::from(5), 0, 2), "5.00");
+        assert_eq!(format_fixed(U256::zero(), 18, 1), "0.0");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for s in ["0", "1", "0.1", "123.456789", "1000000"] {
+            assert_eq!(format_units(parse_units(s, 9).unwrap(), 9), s);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Hash-chained audit entries and chain verification.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::event::{write_field, AuditEvent};
+use crate::{Error, Result};
+
+/// The previous-entry hash of the first entry in a log.
+pub const GENESIS_HASH: [u8; 32] = [0; 32];
+
+/// Domain separator for entry hashes.
+const HASH_DOMAIN: &[u8] = b"khodpay-audit/v1";
+
+/// An [`AuditEvent`] as written to the log: numbered, timestamped and chained to
+/// the entry before it.
+///
+/// Each entry's `hash` covers its own fields and the previous entry's hash, so
+/// editing, removing or reordering any entry breaks every link after it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0.
+    pub sequence: u64,
+    /// Seconds since the Unix epoch when the entry was recorded.
+    pub timestamp: u64,
+    /// The signer instance that recorded the entry.
+    pub signer: String,
+    /// What happened.
+    pub event: AuditEvent,
+    /// The hash of the previous entry, or [`GENESIS_HASH`] for the first.
+    #[serde(with = "serde_hex")]
+    pub prev_hash: [u8; 32],
+    /// The hash of this entry.
+    #[serde(with = "serde_hex")]
+    pub hash: [u8; 32],
+}
+
+impl AuditEntry {
+    /// Creates an entry following `prev_hash`, computing its hash.
+    pub(crate) fn new(
+        sequence: u64,
+        timestamp: u64,
+        signer: String,
+        event: AuditEvent,
+        prev_hash: [u8; 32],
+    ) -> Self {
+        let mut entry = Self {
+            sequence,
+            timestamp,
+            signer,
+            event,
+            prev_hash,
+            hash: [0; 32],
+        };
+        entry.hash = entry.compute_hash();
+        entry
+    }
+
+    /// Computes the hash of the entry's contents, ignoring the stored `hash`.
+    pub fn compute_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(HASH_DOMAIN);
+        hasher.update(self.prev_hash);
+        hasher.update(self.sequence.to_be_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        write_field(&mut hasher, self.signer.as_bytes());
+        self.event.hash_into(&mut hasher);
+        hasher.finalize().into()
+    }
+
+    /// Returns `true` if the stored hash matches the entry's contents.
+    pub fn is_intact(&self) -> bool {
+        self.compute_hash() == self.hash
+    }
+}
+
+/// Verifies a run of consecutive entries.
+///
+/// Every entry must match its hash and link to the one before it with the next
+/// sequence number, and a run starting at sequence 0 must start from
+/// [`GENESIS_HASH`]. A run that starts later is checked from its first entry on:
+/// compare that entry's `prev_hash` against a hash kept from earlier to cover
+/// the rest.
+///
+/// Removing entries from the end cannot be seen from the entries alone; keep the
+/// latest [`AuditLog::head`](crate::AuditLog::head) somewhere the log's writer
+/// cannot change, and compare it with the last entry's hash.
+///
+/// # Errors
+///
+/// Returns [`Error::Tampered`] for the first entry that does not match its hash,
+/// and [`Error::BrokenChain`] for the first entry that does not follow on from
+/// the one before it.
+pub fn verify_chain(entries: &[AuditEntry]) -> Result<()> {
+    let mut previous: Option<&AuditEntry> = None;
+    for entry in entries {
+        if !entry.is_intact() {
+            return Err(Error::Tampered {
+                sequence: entry.sequence,
+            });
+        }
+        let broken = |reason: String| Error::BrokenChain {
+            sequence: entry.sequence,
+            reason,
+        };
+        match previous {
+            None if entry.sequence == 0 && entry.prev_hash != GENESIS_HASH => {
+                return Err(broken(
+                    "first entry does not start from genesis".to_string(),
+                ));
+            }
+            None => {}
+            Some(previous) => {
+                if entry.sequence != previous.sequence + 1 {
+                    return Err(broken(format!(
+                        "expected sequence {}",
+                        previous.sequence + 1
+                    )));
+                }
+                if entry.prev_hash != previous.hash {
+                    return Err(broken(format!(
+                        "does not link to entry {}",
+                        previous.sequence
+                    )));
+                }
+            }
+        }
+        previous = Some(entry);
+    }
+    Ok(())
+}
+
+/// Serde helpers for 32-byte hashes as hex strings.
+pub(crate) mod serde_hex {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = hex::decode(&s).map_err(D::Error::custom)?;
+        bytes
+            .try_into()
+            .map_err(|_| D::Error::custom("expected 32 bytes"))
+    }
+
+    /// The same for optional hashes.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            bytes: &Option<[u8; 32]>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) => super::serialize(bytes, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<[u8; 32]>, D::Error> {
+            #[derive(Deserialize)]
+            struct Hex(#[serde(with = "super")] [u8; 32]);
+
+            Ok(Option::<Hex>::deserialize(deserializer)?.map(|Hex(bytes)| bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(len: u64) -> Vec<AuditEntry> {
+        let mut prev_hash = GENESIS_HASH;
+        (0..len)
+            .map(|sequence| {
+                let event = AuditEvent::derivation("evm_address")
+                    .with_path(format!("m/44'/60'/0'/0/{}", sequence));
+                let entry =
+                    AuditEntry::new(sequence, 1_700_000_000, "signer-1".into(), event, prev_hash);
+                prev_hash = entry.hash;
+                entry
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let entries = chain(4);
+        verify_chain(&entries).unwrap();
+        verify_chain(&entries[2..]).unwrap();
+        verify_chain(&[]).unwrap();
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert!(entries.iter().all(AuditEntry::is_intact));
+    }
+
+    #[test]
+    fn test_edited_entry() {
+        let mut entries = chain(3);
+        entries[1].event.path = Some("m/44'/60'/0'/0/9".to_string());
+        assert!(matches!(
+            verify_chain(&entries),
+            Err(Error::Tampered { sequence: 1 })
+        ));
+
+        // Rehashing the edited entry breaks the next link instead
+        entries[1].hash = entries[1].compute_hash();
+        match verify_chain(&entries) {
+            Err(Error::BrokenChain { sequence, reason }) => {
+                assert_eq!(sequence, 2);
+                assert_eq!(reason, "does not link to entry 1");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_removed_and_reordered_entries() {
+        let mut entries = chain(4);
+        entries.remove(1);
+        assert!(matches!(
+            verify_chain(&entries),
+            Err(Error::BrokenChain { sequence: 2, .. })
+        ));
+
+        let mut entries = chain(3);
+        entries.swap(1, 2);
+        assert!(matches!(
+            verify_chain(&entries),
+            Err(Error::BrokenChain { sequence: 2, .. })
+        ));
+
+        let entries = chain(3);
+        let forged = AuditEntry::new(
+            0,
+            1_700_000_000,
+            "signer-1".into(),
+            entries[0].event.clone(),
+            [1; 32],
+        );
+        assert!(matches!(
+            verify_chain(&[forged]),
+            Err(Error::BrokenChain { sequence: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let entry = chain(1).remove(0);
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(&format!("\"prev_hash\":\"{}\"", "00".repeat(32))));
+        assert!(json.contains(&hex::encode(entry.hash)));
+        let parsed: AuditEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, entry);
+        assert!(parsed.is_intact());
+    }
+}
@@ -0,0 +1,73 @@
+//! Error types for the audit crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while writing or verifying an audit log.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A sink failed to write or flush.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A sink rejected an entry for a reason of its own.
+    #[error("Sink error: {0}")]
+    Sink(String),
+
+    /// A stored entry could not be parsed.
+    #[error("Invalid audit record on line {line}: {reason}")]
+    InvalidRecord {
+        /// The 1-based line of the record.
+        line: usize,
+        /// Why it could not be parsed.
+        reason: String,
+    },
+
+    /// An entry's contents no longer match its hash: it was edited after it was
+    /// written.
+    #[error("Audit entry {sequence} does not match its hash")]
+    Tampered {
+        /// The sequence number of the entry.
+        sequence: u64,
+    },
+
+    /// Entries are missing, reordered or do not link to the one before them.
+    #[error("Audit chain broken at entry {sequence}: {reason}")]
+    BrokenChain {
+        /// The sequence number of the first entry that does not follow on.
+        sequence: u64,
+        /// What is wrong with the link.
+        reason: String,
+    },
+}
+
+/// Result type alias for audit operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::Tampered { sequence: 7 }.to_string(),
+            "Audit entry 7 does not match its hash"
+        );
+        assert_eq!(
+            Error::BrokenChain {
+                sequence: 3,
+                reason: "expected sequence 2".to_string()
+            }
+            .to_string(),
+            "Audit chain broken at entry 3: expected sequence 2"
+        );
+        assert_eq!(
+            Error::InvalidRecord {
+                line: 2,
+                reason: "EOF".to_string()
+            }
+            .to_string(),
+            "Invalid audit record on line 2: EOF"
+        );
+    }
+}
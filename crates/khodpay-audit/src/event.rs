@@ -0,0 +1,258 @@
+//! What an audit entry records: the action, its subject and how it ended.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::entry::serde_hex;
+
+/// The kinds of key use an audit log records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// A key or address was derived.
+    Derivation,
+    /// Key material left the signer: an extended public key, a fingerprint or a
+    /// backup.
+    Export,
+    /// Something was signed.
+    Signature,
+}
+
+impl Action {
+    /// Returns the name used in logs and in the entry hash.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Derivation => "derivation",
+            Action::Export => "export",
+            Action::Signature => "signature",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether the recorded action went through.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum Outcome {
+    /// The action completed.
+    Success,
+    /// The action failed or was refused, with the reason.
+    Failure(String),
+}
+
+/// One use of a key, described for the audit log.
+///
+/// Events are built with the action and an operation name, then given as much
+/// context as the caller has: the derivation path, the digest of what was signed
+/// and free-form key/value pairs.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_audit::{Action, AuditEvent, Outcome};
+///
+/// let event = AuditEvent::signature("evm_transaction")
+///     .with_path("m/44'/60'/0'/0/0")
+///     .with_digest([0xab; 32])
+///     .with_context("chain_id", 56);
+///
+/// assert_eq!(event.action, Action::Signature);
+/// assert_eq!(event.context["chain_id"], "56");
+/// assert_eq!(event.outcome, Outcome::Success);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// The kind of key use.
+    pub action: Action,
+    /// What was done, such as `"evm_transaction"` or `"extended_public_key"`.
+    pub operation: String,
+    /// The derivation path of the key used, if there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The digest of what was signed or exported, to match the entry against the
+    /// transaction or message later.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_hex::option"
+    )]
+    pub digest: Option<[u8; 32]>,
+    /// Anything else worth keeping: chain id, address, amount, request origin.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, String>,
+    /// How the action ended.
+    pub outcome: Outcome,
+}
+
+impl AuditEvent {
+    /// Creates a successful event with no context.
+    pub fn new(action: Action, operation: impl Into<String>) -> Self {
+        Self {
+            action,
+            operation: operation.into(),
+            path: None,
+            digest: None,
+            context: BTreeMap::new(),
+            outcome: Outcome::Success,
+        }
+    }
+
+    /// Creates a [`Action::Derivation`] event.
+    pub fn derivation(operation: impl Into<String>) -> Self {
+        Self::new(Action::Derivation, operation)
+    }
+
+    /// Creates an [`Action::Export`] event.
+    pub fn export(operation: impl Into<String>) -> Self {
+        Self::new(Action::Export, operation)
+    }
+
+    /// Creates an [`Action::Signature`] event.
+    pub fn signature(operation: impl Into<String>) -> Self {
+        Self::new(Action::Signature, operation)
+    }
+
+    /// Sets the derivation path of the key used.
+    pub fn with_path(mut self, path: impl fmt::Display) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Sets the digest of what was signed or exported.
+    pub fn with_digest(mut self, digest: [u8; 32]) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+
+    /// Adds a context value, replacing any earlier value for `key`.
+    pub fn with_context(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.context.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// Marks the event as failed with `reason`.
+    pub fn failed(mut self, reason: impl fmt::Display) -> Self {
+        self.outcome = Outcome::Failure(reason.to_string());
+        self
+    }
+
+    /// Returns `true` if the action completed.
+    pub fn is_success(&self) -> bool {
+        self.outcome == Outcome::Success
+    }
+
+    /// Feeds the event into an entry hash. Every field is length-prefixed or
+    /// tagged, so no two events hash the same.
+    pub(crate) fn hash_into(&self, hasher: &mut Sha256) {
+        write_field(hasher, self.action.as_str().as_bytes());
+        write_field(hasher, self.operation.as_bytes());
+        match &self.path {
+            Some(path) => {
+                hasher.update([1]);
+                write_field(hasher, path.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+        match &self.digest {
+            Some(digest) => {
+                hasher.update([1]);
+                hasher.update(digest);
+            }
+            None => hasher.update([0]),
+        }
+        hasher.update((self.context.len() as u64).to_be_bytes());
+        for (key, value) in &self.context {
+            write_field(hasher, key.as_bytes());
+            write_field(hasher, value.as_bytes());
+        }
+        match &self.outcome {
+            Outcome::Success => hasher.update([0]),
+            Outcome::Failure(reason) => {
+                hasher.update([1]);
+                write_field(hasher, reason.as_bytes());
+            }
+        }
+    }
+}
+
+/// Writes `bytes` with a 64-bit big-endian length prefix.
+pub(crate) fn write_field(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_be_bytes());
+    hasher.update(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(event: &AuditEvent) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        event.hash_into(&mut hasher);
+        hasher.finalize().to_vec()
+    }
+
+    #[test]
+    fn test_builder() {
+        let event = AuditEvent::export("extended_public_key")
+            .with_path("m/84'/0'/0'")
+            .with_context("display", true)
+            .failed("Request rejected on the device");
+        assert_eq!(event.action, Action::Export);
+        assert_eq!(event.path.as_deref(), Some("m/84'/0'/0'"));
+        assert_eq!(event.context["display"], "true");
+        assert!(!event.is_success());
+    }
+
+    #[test]
+    fn test_json() {
+        let event = AuditEvent::signature("evm_message").with_digest([1; 32]);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "action": "signature",
+                "operation": "evm_message",
+                "digest": "01".repeat(32),
+                "outcome": { "status": "success" }
+            })
+        );
+        assert_eq!(serde_json::from_value::<AuditEvent>(json).unwrap(), event);
+
+        let failed = event.failed("denied");
+        let json = serde_json::to_value(&failed).unwrap();
+        assert_eq!(
+            json["outcome"],
+            serde_json::json!({ "status": "failure", "reason": "denied" })
+        );
+        assert_eq!(serde_json::from_value::<AuditEvent>(json).unwrap(), failed);
+    }
+
+    #[test]
+    fn test_hash_covers_every_field() {
+        let base = AuditEvent::signature("psbt");
+        let variants = [
+            AuditEvent::derivation("psbt"),
+            AuditEvent::signature("psbt2"),
+            base.clone().with_path("m/0"),
+            base.clone().with_digest([0; 32]),
+            base.clone().with_context("a", "b"),
+            base.clone().failed(""),
+        ];
+        for variant in &variants {
+            assert_ne!(hash(variant), hash(&base));
+        }
+        // Moving bytes between adjacent fields changes the hash
+        assert_ne!(
+            hash(&base.clone().with_context("ab", "c")),
+            hash(&base.with_context("a", "bc"))
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! # Khodpay Audit
+//!
+//! A tamper-evident audit log of what a signer did: every key derivation, export
+//! and signature, with its context, so an operator can reconstruct exactly what a
+//! signer instance was asked to do and what it answered.
+//!
+//! Entries are hash-chained: each one commits to the entry before it, so editing,
+//! removing or reordering any entry is caught by [`verify_chain`]. Entries go to
+//! any number of [`AuditSink`]s: memory, JSON Lines files, or a database or
+//! collector of the embedder's own.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`AuditEvent`] | What happened: action, operation, path, digest, context, outcome |
+//! | [`AuditEntry`] | An event as logged: sequence, time, signer and hash chain |
+//! | [`AuditLog`] | Records events for one signer instance and fans them out to sinks |
+//! | [`AuditSink`] | Where entries go, with [`MemorySink`] and [`JsonLinesSink`] built in |
+//! | [`AuditedSigner`] | Records every call of a `khodpay-hw-signer` [`Signer`](khodpay_hw_signer::Signer) |
+//! | [`verify_chain`] | Detects edited, missing and reordered entries |
+//!
+//! ## Features
+//!
+//! - **Hash Chain**: SHA-256 over each entry and the previous entry's hash
+//! - **Pluggable Sinks**: Write to several destinations at once; a failing sink
+//!   fails the call instead of losing entries quietly
+//! - **Resumable**: Continue a chain from the last stored entry after a restart
+//! - **Fail-Closed Signers**: [`AuditedSigner`] withholds results it could not
+//!   record
+//! - **Software Signers Too**: Record events from any code path with
+//!   [`AuditLog::record`]
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_audit::{verify_chain, AuditEvent, AuditLog, MemorySink};
+//!
+//! // In production, add a `JsonLinesSink` over an append-only file as well
+//! let sink = MemorySink::new();
+//! let mut log = AuditLog::new("hot-wallet-1").with_sink(sink.clone());
+//!
+//! log.record(
+//!     AuditEvent::signature("evm_transaction")
+//!         .with_path("m/44'/60'/0'/0/0")
+//!         .with_digest([0x5a; 32])
+//!         .with_context("chain_id", 56),
+//! )?;
+//! log.record(AuditEvent::export("extended_public_key").failed("user cancelled"))?;
+//!
+//! // Later: check nothing was changed, and that the log ends where it should
+//! let entries = sink.entries();
+//! verify_chain(&entries)?;
+//! assert_eq!(entries.last().map(|entry| entry.hash), Some(log.head()));
+//! # Ok::<(), khodpay_audit::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod entry;
+mod error;
+mod event;
+mod log;
+mod signer;
+mod sink;
+
+pub use entry::{verify_chain, AuditEntry, GENESIS_HASH};
+pub use error::{Error, Result};
+pub use event::{Action, AuditEvent, Outcome};
+pub use log::AuditLog;
+pub use signer::AuditedSigner;
+pub use sink::{read_json_lines, AuditSink, JsonLinesSink, MemorySink};
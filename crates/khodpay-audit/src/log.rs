@@ -0,0 +1,251 @@
+//! The [`AuditLog`] a signer instance records into.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{AuditEntry, AuditEvent, AuditSink, Error, Result, GENESIS_HASH};
+
+/// A hash-chained log of what one signer instance did.
+///
+/// Each recorded [`AuditEvent`] becomes an [`AuditEntry`] linked to the one
+/// before it and is handed to every sink. The log itself keeps only the chain
+/// head, so it can run for the life of the signer.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_audit::{verify_chain, AuditEvent, AuditLog, MemorySink};
+///
+/// let sink = MemorySink::new();
+/// let mut log = AuditLog::new("treasury-signer-1").with_sink(sink.clone());
+///
+/// log.record(AuditEvent::export("extended_public_key").with_path("m/84'/0'/0'"))?;
+/// log.record(AuditEvent::signature("psbt").with_digest([0x42; 32]))?;
+///
+/// let entries = sink.entries();
+/// assert_eq!(entries.len(), 2);
+/// assert_eq!(log.head(), entries[1].hash);
+/// verify_chain(&entries)?;
+/// # Ok::<(), khodpay_audit::Error>(())
+/// ```
+pub struct AuditLog {
+    signer: String,
+    sequence: u64,
+    head: [u8; 32],
+    sinks: Vec<Box<dyn AuditSink>>,
+}
+
+impl AuditLog {
+    /// Creates an empty log for the signer instance `signer`.
+    pub fn new(signer: impl Into<String>) -> Self {
+        Self {
+            signer: signer.into(),
+            sequence: 0,
+            head: GENESIS_HASH,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Continues the log that ends with `last`, such as the last line of the
+    /// signer's log file after a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Tampered`] if `last` does not match its hash.
+    pub fn resume(last: &AuditEntry) -> Result<Self> {
+        if !last.is_intact() {
+            return Err(Error::Tampered {
+                sequence: last.sequence,
+            });
+        }
+        Ok(Self {
+            signer: last.signer.clone(),
+            sequence: last.sequence + 1,
+            head: last.hash,
+            sinks: Vec::new(),
+        })
+    }
+
+    /// Adds a sink, builder style.
+    pub fn with_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.add_sink(sink);
+        self
+    }
+
+    /// Adds a sink. It receives entries recorded from now on.
+    pub fn add_sink(&mut self, sink: impl AuditSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// Returns the signer instance the log belongs to.
+    pub fn signer(&self) -> &str {
+        &self.signer
+    }
+
+    /// Returns the sequence number the next entry will get.
+    pub fn next_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the hash of the latest entry, or [`GENESIS_HASH`] if there is none.
+    ///
+    /// Publishing the head elsewhere (a database, a remote service) commits to
+    /// every entry so far, so entries cannot be dropped from the end unnoticed.
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    /// Records `event` with the current time.
+    ///
+    /// # Errors
+    ///
+    /// As [`AuditLog::record_at`].
+    pub fn record(&mut self, event: AuditEvent) -> Result<AuditEntry> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.record_at(event, timestamp)
+    }
+
+    /// Records `event` with `timestamp` (seconds since the Unix epoch) and hands
+    /// the entry to every sink.
+    ///
+    /// The entry joins the chain even if a sink fails, so the next entry follows
+    /// it and the failed sink's copy of the log shows a gap rather than a
+    /// different history.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first sink error, after every sink has been tried.
+    pub fn record_at(&mut self, event: AuditEvent, timestamp: u64) -> Result<AuditEntry> {
+        let entry = AuditEntry::new(
+            self.sequence,
+            timestamp,
+            self.signer.clone(),
+            event,
+            self.head,
+        );
+        self.sequence += 1;
+        self.head = entry.hash;
+
+        let mut result = Ok(());
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.append(&entry) {
+                result = result.and(Err(e));
+            }
+        }
+        result.map(|()| entry)
+    }
+
+    /// Flushes every sink.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first sink error, after every sink has been tried.
+    pub fn flush(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.flush() {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// Returns the sinks, to close files or connections once the signer is done.
+    pub fn into_sinks(self) -> Vec<Box<dyn AuditSink>> {
+        self.sinks
+    }
+}
+
+impl fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("signer", &self.signer)
+            .field("sequence", &self.sequence)
+            .field("head", &hex::encode(self.head))
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{verify_chain, MemorySink};
+
+    /// A sink that always fails.
+    struct Broken;
+
+    impl AuditSink for Broken {
+        fn append(&mut self, _entry: &AuditEntry) -> Result<()> {
+            Err(Error::Sink("disk full".to_string()))
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Err(Error::Sink("disk full".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let sink = MemorySink::new();
+        let mut log = AuditLog::new("signer-1").with_sink(sink.clone());
+        assert_eq!(log.head(), GENESIS_HASH);
+
+        let first = log
+            .record_at(AuditEvent::derivation("evm_address"), 100)
+            .unwrap();
+        let second = log.record_at(AuditEvent::signature("psbt"), 101).unwrap();
+        assert_eq!(first.sequence, 0);
+        assert_eq!(first.prev_hash, GENESIS_HASH);
+        assert_eq!(first.signer, "signer-1");
+        assert_eq!(second.sequence, 1);
+        assert_eq!(second.prev_hash, first.hash);
+        assert_eq!(log.head(), second.hash);
+        assert_eq!(log.next_sequence(), 2);
+        assert_eq!(sink.entries(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_resume() {
+        let sink = MemorySink::new();
+        let mut log = AuditLog::new("signer-1").with_sink(sink.clone());
+        log.record(AuditEvent::derivation("evm_address")).unwrap();
+        let last = log.record(AuditEvent::signature("psbt")).unwrap();
+
+        // Restarted signer, same chain
+        let mut log = AuditLog::resume(&last).unwrap().with_sink(sink.clone());
+        assert_eq!(log.signer(), "signer-1");
+        let next = log
+            .record(AuditEvent::export("master_fingerprint"))
+            .unwrap();
+        assert_eq!(next.sequence, 2);
+        verify_chain(&sink.entries()).unwrap();
+
+        let mut edited = last;
+        edited.timestamp += 1;
+        assert!(matches!(
+            AuditLog::resume(&edited),
+            Err(Error::Tampered { sequence: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_failing_sink() {
+        let sink = MemorySink::new();
+        let mut log = AuditLog::new("signer-1")
+            .with_sink(Broken)
+            .with_sink(sink.clone());
+        assert!(matches!(
+            log.record(AuditEvent::signature("psbt")),
+            Err(Error::Sink(_))
+        ));
+        assert!(log.flush().is_err());
+
+        // The other sinks still got the entry, and the chain moved on
+        assert_eq!(sink.len(), 1);
+        assert_eq!(log.next_sequence(), 1);
+        assert_eq!(log.head(), sink.entries()[0].hash);
+    }
+}
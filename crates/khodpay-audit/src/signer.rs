@@ -0,0 +1,353 @@
+//! [`AuditedSigner`]: a [`Signer`] that records every call.
+
+use khodpay_bip32::{DerivationPath, ExtendedPublicKey};
+use khodpay_bip44::Chain;
+use khodpay_btc_signing::psbt::Psbt;
+use khodpay_btc_signing::Address as BtcAddress;
+use khodpay_hw_signer::{BtcAccount, Error as SignerError, Result as SignerResult, Signer};
+use khodpay_signing::{eip191, Address, Eip1559Transaction, Signature};
+
+use crate::{AuditEvent, AuditLog};
+
+/// Wraps a [`Signer`] so that every derivation, export and signature it performs
+/// is recorded in an [`AuditLog`], refused requests included.
+///
+/// Logging is fail-closed: if the log cannot be written the call returns
+/// [`Error::Transport`](SignerError::Transport) and its result (an address, a
+/// signature) is withheld, so nothing leaves the signer off the record.
+///
+/// | Call | Action | Recorded |
+/// |---|---|---|
+/// | `master_fingerprint` | Export | Fingerprint |
+/// | `extended_public_key` | Export | Path, key fingerprint |
+/// | `evm_address`, `btc_address` | Derivation | Path, address, whether it was shown |
+/// | `sign_evm_transaction` | Signature | Path, signing hash, chain id, nonce |
+/// | `sign_evm_message` | Signature | Path, EIP-191 hash |
+/// | `sign_psbt` | Signature | Account path, unsigned txid, signature count |
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_audit::{AuditLog, AuditedSigner, JsonLinesSink};
+/// use khodpay_hw_signer::ledger::Ledger;
+/// use khodpay_hw_signer::{Signer, Transport};
+/// use std::fs::OpenOptions;
+///
+/// # fn open_hid() -> Box<dyn Transport> { unimplemented!() }
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let file = OpenOptions::new().create(true).append(true).open("signer.log")?;
+/// let log = AuditLog::new("ledger-desk-3").with_sink(JsonLinesSink::new(file));
+///
+/// let mut signer = AuditedSigner::new(Ledger::new(open_hid()), log);
+/// let fingerprint = signer.master_fingerprint()?; // Recorded
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AuditedSigner<S> {
+    inner: S,
+    log: AuditLog,
+}
+
+impl<S: Signer> AuditedSigner<S> {
+    /// Wraps `inner`, recording into `log`.
+    pub fn new(inner: S, log: AuditLog) -> Self {
+        Self { inner, log }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the log, to read its head or flush it.
+    pub fn log_mut(&mut self) -> &mut AuditLog {
+        &mut self.log
+    }
+
+    /// Returns the signer and the log.
+    pub fn into_parts(self) -> (S, AuditLog) {
+        (self.inner, self.log)
+    }
+
+    /// Records `event` with the outcome of `result`, then passes `result` on.
+    fn audit<T>(&mut self, event: AuditEvent, result: SignerResult<T>) -> SignerResult<T> {
+        let event = match &result {
+            Ok(_) => event,
+            Err(e) => event.failed(e),
+        };
+        self.log
+            .record(event)
+            .map_err(|e| SignerError::Transport(format!("Audit log unavailable: {}", e)))?;
+        result
+    }
+}
+
+impl<S: Signer> Signer for AuditedSigner<S> {
+    fn master_fingerprint(&mut self) -> SignerResult<[u8; 4]> {
+        let result = self.inner.master_fingerprint();
+        let mut event = AuditEvent::export("master_fingerprint");
+        if let Ok(fingerprint) = &result {
+            event = event.with_context("fingerprint", hex::encode(fingerprint));
+        }
+        self.audit(event, result)
+    }
+
+    fn extended_public_key(
+        &mut self,
+        path: &DerivationPath,
+        display: bool,
+    ) -> SignerResult<ExtendedPublicKey> {
+        let result = self.inner.extended_public_key(path, display);
+        let mut event = AuditEvent::export("extended_public_key")
+            .with_path(path)
+            .with_context("display", display);
+        if let Ok(xpub) = &result {
+            event = event.with_context("fingerprint", hex::encode(xpub.fingerprint()));
+        }
+        self.audit(event, result)
+    }
+
+    fn evm_address(&mut self, path: &DerivationPath, display: bool) -> SignerResult<Address> {
+        let result = self.inner.evm_address(path, display);
+        let mut event = AuditEvent::derivation("evm_address")
+            .with_path(path)
+            .with_context("display", display);
+        if let Ok(address) = &result {
+            event = event.with_context("address", address.to_checksum_string());
+        }
+        self.audit(event, result)
+    }
+
+    fn sign_evm_transaction(
+        &mut self,
+        path: &DerivationPath,
+        tx: &Eip1559Transaction,
+    ) -> SignerResult<Signature> {
+        let result = self.inner.sign_evm_transaction(path, tx);
+        let event = AuditEvent::signature("evm_transaction")
+            .with_path(path)
+            .with_digest(tx.signing_hash())
+            .with_context("chain_id", tx.chain_id.value())
+            .with_context("nonce", tx.nonce);
+        self.audit(event, result)
+    }
+
+    fn sign_evm_message(
+        &mut self,
+        path: &DerivationPath,
+        message: &[u8],
+    ) -> SignerResult<Signature> {
+        let result = self.inner.sign_evm_message(path, message);
+        let event = AuditEvent::signature("evm_message")
+            .with_path(path)
+            .with_digest(eip191::hash_message(message));
+        self.audit(event, result)
+    }
+
+    fn btc_address(
+        &mut self,
+        account: &BtcAccount,
+        chain: Chain,
+        index: u32,
+        display: bool,
+    ) -> SignerResult<BtcAddress> {
+        let result = self.inner.btc_address(account, chain, index, display);
+        let mut event = AuditEvent::derivation("btc_address")
+            .with_path(format!("{}/{}/{}", account.path(), chain.value(), index))
+            .with_context("display", display);
+        if let Ok(address) = &result {
+            event = event.with_context("address", address);
+        }
+        self.audit(event, result)
+    }
+
+    fn sign_psbt(&mut self, account: &BtcAccount, psbt: &mut Psbt) -> SignerResult<usize> {
+        let unsigned_tx = psbt.unsigned_tx();
+        let result = self.inner.sign_psbt(account, psbt);
+        let mut event = AuditEvent::signature("psbt").with_path(account.path());
+        if let Ok(tx) = &unsigned_tx {
+            let txid = tx.txid();
+            event = event
+                .with_digest(*txid.as_bytes())
+                .with_context("txid", txid);
+        }
+        if let Ok(signatures) = &result {
+            event = event.with_context("signatures", signatures);
+        }
+        self.audit(event, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{verify_chain, Action, AuditEntry, AuditSink, MemorySink, Outcome};
+    use khodpay_btc_signing::{Network, ScriptType};
+    use khodpay_signing::{ChainId, Wei};
+    use std::str::FromStr;
+
+    /// BIP-32 test vector 1, chain m.
+    const XPUB: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+
+    /// A device that answers everything but refuses to sign transactions.
+    struct Device;
+
+    impl Signer for Device {
+        fn master_fingerprint(&mut self) -> SignerResult<[u8; 4]> {
+            Ok([0x34, 0x42, 0x19, 0x3e])
+        }
+
+        fn extended_public_key(
+            &mut self,
+            _path: &DerivationPath,
+            _display: bool,
+        ) -> SignerResult<ExtendedPublicKey> {
+            Ok(ExtendedPublicKey::from_str(XPUB)?)
+        }
+
+        fn evm_address(&mut self, _path: &DerivationPath, _display: bool) -> SignerResult<Address> {
+            Ok(Address::from_bytes([0x11; 20]))
+        }
+
+        fn sign_evm_transaction(
+            &mut self,
+            _path: &DerivationPath,
+            _tx: &Eip1559Transaction,
+        ) -> SignerResult<Signature> {
+            Err(SignerError::UserRejected)
+        }
+
+        fn sign_evm_message(
+            &mut self,
+            _path: &DerivationPath,
+            _message: &[u8],
+        ) -> SignerResult<Signature> {
+            Ok(Signature::new([1; 32], [2; 32], 27))
+        }
+
+        fn btc_address(
+            &mut self,
+            account: &BtcAccount,
+            chain: Chain,
+            index: u32,
+            _display: bool,
+        ) -> SignerResult<BtcAddress> {
+            account.address(chain, index)
+        }
+
+        fn sign_psbt(&mut self, _account: &BtcAccount, _psbt: &mut Psbt) -> SignerResult<usize> {
+            Err(SignerError::UserRejected)
+        }
+    }
+
+    /// A sink that always fails.
+    struct Broken;
+
+    impl AuditSink for Broken {
+        fn append(&mut self, _entry: &AuditEntry) -> crate::Result<()> {
+            Err(crate::Error::Sink("collector offline".to_string()))
+        }
+    }
+
+    fn path() -> DerivationPath {
+        DerivationPath::from_str("m/44'/60'/0'/0/0").unwrap()
+    }
+
+    fn audited() -> (AuditedSigner<Device>, MemorySink) {
+        let sink = MemorySink::new();
+        let log = AuditLog::new("device-1").with_sink(sink.clone());
+        (AuditedSigner::new(Device, log), sink)
+    }
+
+    #[test]
+    fn test_records_exports_and_derivations() {
+        let (mut signer, sink) = audited();
+        let account = signer
+            .btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)
+            .unwrap();
+        let address = signer
+            .btc_address(&account, Chain::External, 3, true)
+            .unwrap();
+        signer.evm_address(&path(), false).unwrap();
+
+        let entries = sink.entries();
+        verify_chain(&entries).unwrap();
+        let operations: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.event.action, entry.event.operation.as_str()))
+            .collect();
+        assert_eq!(
+            operations,
+            [
+                (Action::Export, "master_fingerprint"),
+                (Action::Export, "extended_public_key"),
+                (Action::Derivation, "btc_address"),
+                (Action::Derivation, "evm_address"),
+            ]
+        );
+        assert_eq!(entries[0].event.context["fingerprint"], "3442193e");
+        assert_eq!(entries[1].event.path.as_deref(), Some("m/84'/0'/0'"));
+        assert_eq!(entries[2].event.path.as_deref(), Some("m/84'/0'/0'/0/3"));
+        assert_eq!(entries[2].event.context["address"], address.to_string());
+        assert_eq!(entries[2].event.context["display"], "true");
+        assert_eq!(
+            entries[3].event.context["address"],
+            Address::from_bytes([0x11; 20]).to_checksum_string()
+        );
+    }
+
+    #[test]
+    fn test_records_signatures_and_refusals() {
+        let (mut signer, sink) = audited();
+        signer.sign_evm_message(&path(), b"hello").unwrap();
+
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(7)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21000)
+            .to(Address::from_bytes([0x22; 20]))
+            .value(Wei::ZERO)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            signer.sign_evm_transaction(&path(), &tx),
+            Err(SignerError::UserRejected)
+        ));
+
+        let entries = sink.entries();
+        assert_eq!(
+            entries[0].event.digest,
+            Some(eip191::hash_message(b"hello"))
+        );
+        assert!(entries[0].event.is_success());
+        assert_eq!(entries[1].event.digest, Some(tx.signing_hash()));
+        assert_eq!(entries[1].event.context["chain_id"], "56");
+        assert_eq!(entries[1].event.context["nonce"], "7");
+        assert_eq!(
+            entries[1].event.outcome,
+            Outcome::Failure("Request rejected on the device".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fails_closed() {
+        let log = AuditLog::new("device-1").with_sink(Broken);
+        let mut signer = AuditedSigner::new(Device, log);
+        match signer.sign_evm_message(&path(), b"hello") {
+            Err(SignerError::Transport(message)) => {
+                assert_eq!(
+                    message,
+                    "Audit log unavailable: Sink error: collector offline"
+                )
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(signer.log_mut().next_sequence(), 1);
+
+        let (_, log) = signer.into_parts();
+        assert_eq!(log.signer(), "device-1");
+    }
+}
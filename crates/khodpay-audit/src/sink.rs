@@ -0,0 +1,185 @@
+//! Where audit entries go: the [`AuditSink`] trait and the sinks this crate
+//! provides.
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::{AuditEntry, Error, Result};
+
+/// A destination for audit entries.
+///
+/// Implement this to send entries to a database, a syslog daemon or a remote
+/// collector. Sinks receive every entry once, in order; [`AuditLog`](crate::AuditLog)
+/// fails the recording call if any sink returns an error.
+pub trait AuditSink: Send {
+    /// Stores `entry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry could not be stored.
+    fn append(&mut self, entry: &AuditEntry) -> Result<()>;
+
+    /// Makes every appended entry durable. The default does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if buffered entries could not be written.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Keeps entries in memory, for tests and for showing recent activity in an app.
+///
+/// Clones share the same entries, so keep one to read back what a log given the
+/// other has recorded.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    entries: Arc<Mutex<Vec<AuditEntry>>>,
+}
+
+impl MemorySink {
+    /// Creates an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the entries recorded so far.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.lock().clone()
+    }
+
+    /// Returns the number of entries recorded so far.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<AuditEntry>> {
+        // Entries are only ever pushed, so a poisoned lock still holds a valid list
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl AuditSink for MemorySink {
+    fn append(&mut self, entry: &AuditEntry) -> Result<()> {
+        self.lock().push(entry.clone());
+        Ok(())
+    }
+}
+
+/// Writes each entry as one line of JSON ([JSON Lines](https://jsonlines.org)) to
+/// a file, pipe or socket.
+///
+/// Open files in append mode so a restarted signer continues the same file, and
+/// read them back with [`read_json_lines`].
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> JsonLinesSink<W> {
+    /// Creates a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> AuditSink for JsonLinesSink<W> {
+    fn append(&mut self, entry: &AuditEntry) -> Result<()> {
+        let mut line =
+            serde_json::to_vec(entry).map_err(|e| Error::Sink(format!("JSON: {}", e)))?;
+        line.push(b'\n');
+        // Serialized in full first, so a JSON error writes nothing
+        self.writer.write_all(&line)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads entries written by a [`JsonLinesSink`], skipping blank lines.
+///
+/// Parsing does not verify the entries; pass them to
+/// [`verify_chain`](crate::verify_chain) for that.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if reading fails and [`Error::InvalidRecord`] for a line
+/// that is not an entry.
+pub fn read_json_lines<R: BufRead>(reader: R) -> Result<Vec<AuditEntry>> {
+    let mut entries = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| Error::InvalidRecord {
+            line: index + 1,
+            reason: e.to_string(),
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuditEvent, AuditLog};
+
+    #[test]
+    fn test_memory_sink_shares_entries() {
+        let sink = MemorySink::new();
+        let mut log = AuditLog::new("signer-1").with_sink(sink.clone());
+        assert!(sink.is_empty());
+        log.record(AuditEvent::export("master_fingerprint"))
+            .unwrap();
+        assert_eq!(sink.len(), 1);
+        assert_eq!(sink.entries()[0].event.operation, "master_fingerprint");
+    }
+
+    #[test]
+    fn test_json_lines_round_trip() {
+        let memory = MemorySink::new();
+        let mut log = AuditLog::new("signer-1").with_sink(memory.clone());
+        for index in 0..3 {
+            let event = AuditEvent::derivation("evm_address").with_context("index", index);
+            log.record_at(event, 1_700_000_000 + index).unwrap();
+        }
+
+        let mut sink = JsonLinesSink::new(Vec::new());
+        for entry in memory.entries() {
+            sink.append(&entry).unwrap();
+        }
+        sink.flush().unwrap();
+        let mut output = sink.into_inner();
+        assert_eq!(output.iter().filter(|&&b| b == b'\n').count(), 3);
+
+        output.extend_from_slice(b"\n");
+        let entries = read_json_lines(output.as_slice()).unwrap();
+        assert_eq!(entries, memory.entries());
+        crate::verify_chain(&entries).unwrap();
+    }
+
+    #[test]
+    fn test_read_invalid_line() {
+        let input = b"\n{\"sequence\":0}\n";
+        match read_json_lines(&input[..]) {
+            Err(Error::InvalidRecord { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,901 @@
+//! DNSSEC validation of a set of records against [`TrustAnchor`]s.
+//!
+//! The validator walks the chain of trust down from the root: the root `DNSKEY`
+//! set is signed by a key matching a trust anchor, each child zone's `DS` set is
+//! signed by its parent, and each child `DNSKEY` set by a key matching one of
+//! those `DS` records. Records answering the query are accepted only when signed
+//! by a zone validated this way.
+//!
+//! Supported algorithms are RSA/SHA-256 (8), RSA/SHA-512 (10), ECDSA P-256 (13),
+//! ECDSA P-384 (14) and Ed25519 (15), with SHA-256 and SHA-384 `DS` digests.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use ring::digest;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+
+use crate::wire::{Name, Record, CLASS_IN, TYPE_DNSKEY, TYPE_DS, TYPE_RRSIG};
+use crate::{Error, Result};
+
+/// `DNSKEY` flag: the key signs zone data.
+const FLAG_ZONE_KEY: u16 = 0x0100;
+/// `DNSKEY` flag: the key has been revoked (RFC 5011).
+const FLAG_REVOKED: u16 = 0x0080;
+/// The only `DNSKEY` protocol value.
+const PROTOCOL_DNSSEC: u8 = 3;
+
+/// `DS` digest type SHA-256.
+const DIGEST_SHA256: u8 = 2;
+/// `DS` digest type SHA-384.
+const DIGEST_SHA384: u8 = 4;
+
+/// Length of the `RRSIG` data before the signer name.
+const RRSIG_FIXED_LEN: usize = 18;
+
+/// The root zone's key-signing keys, as `DS` records.
+///
+/// KSK-2017 (key tag 20326) and KSK-2024 (key tag 38696), from
+/// <https://data.iana.org/root-anchors/root-anchors.xml>.
+const ROOT_ANCHORS: [&str; 2] = [
+    "20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+    "38696 8 2 683D2D0ACB8C9B712A1948B27F741219298D0A450D612C483AF444A4C0FB2B16",
+];
+
+/// A `DS` record for the root zone that validation starts from.
+///
+/// [`TrustAnchor::root`] returns IANA's current root key-signing keys. Other
+/// anchors are for testing against a private DNS tree.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip353::TrustAnchor;
+///
+/// let anchor: TrustAnchor =
+///     "20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D".parse()?;
+/// assert_eq!(anchor.key_tag, 20326);
+/// assert!(TrustAnchor::root().contains(&anchor));
+/// # Ok::<(), khodpay_bip353::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustAnchor {
+    /// Key tag of the root `DNSKEY`.
+    pub key_tag: u16,
+    /// Signing algorithm of the root `DNSKEY`.
+    pub algorithm: u8,
+    /// Digest type, 2 for SHA-256 or 4 for SHA-384.
+    pub digest_type: u8,
+    /// Digest of the root `DNSKEY`.
+    pub digest: Vec<u8>,
+}
+
+impl TrustAnchor {
+    /// Returns IANA's root key-signing keys.
+    pub fn root() -> Vec<TrustAnchor> {
+        ROOT_ANCHORS
+            .iter()
+            .map(|anchor| anchor.parse().expect("built-in trust anchor"))
+            .collect()
+    }
+
+    /// Returns `true` if `dnskey` (record data) hashes to this anchor.
+    fn matches(&self, dnskey: &[u8]) -> bool {
+        Ds {
+            key_tag: self.key_tag,
+            algorithm: self.algorithm,
+            digest_type: self.digest_type,
+            digest: &self.digest,
+        }
+        .matches(&Name::root(), dnskey)
+    }
+}
+
+impl FromStr for TrustAnchor {
+    type Err = Error;
+
+    /// Parses the presentation form of a `DS` record's data:
+    /// `<key tag> <algorithm> <digest type> <hex digest>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::Dnssec(format!("invalid trust anchor: {:?}", s));
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [key_tag, algorithm, digest_type, digest @ ..] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        let digest = hex::decode(digest.concat()).map_err(|_| invalid())?;
+        if digest.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            key_tag: key_tag.parse().map_err(|_| invalid())?,
+            algorithm: algorithm.parse().map_err(|_| invalid())?,
+            digest_type: digest_type.parse().map_err(|_| invalid())?,
+            digest,
+        })
+    }
+}
+
+impl fmt::Display for TrustAnchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key_tag,
+            self.algorithm,
+            self.digest_type,
+            hex::encode_upper(&self.digest)
+        )
+    }
+}
+
+/// The parsed data of a `DNSKEY` record.
+struct Dnskey<'a> {
+    flags: u16,
+    protocol: u8,
+    algorithm: u8,
+    public_key: &'a [u8],
+    rdata: &'a [u8],
+}
+
+impl<'a> Dnskey<'a> {
+    fn parse(rdata: &'a [u8]) -> Option<Self> {
+        let [f0, f1, protocol, algorithm, public_key @ ..] = rdata else {
+            return None;
+        };
+        Some(Self {
+            flags: u16::from_be_bytes([*f0, *f1]),
+            protocol: *protocol,
+            algorithm: *algorithm,
+            public_key,
+            rdata,
+        })
+    }
+
+    /// Returns `true` if the key may sign zone data.
+    fn is_zone_key(&self) -> bool {
+        self.protocol == PROTOCOL_DNSSEC
+            && self.flags & FLAG_ZONE_KEY != 0
+            && self.flags & FLAG_REVOKED == 0
+    }
+
+    /// Returns the key tag (RFC 4034, appendix B).
+    fn key_tag(&self) -> u16 {
+        key_tag(self.rdata)
+    }
+
+    /// Verifies `signature` over `data` with this key.
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        let key = self.public_key;
+        let result = match self.algorithm {
+            8 | 10 => {
+                let Some((e, n)) = rsa_components(key) else {
+                    return false;
+                };
+                let params = if self.algorithm == 8 {
+                    &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY
+                } else {
+                    &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY
+                };
+                RsaPublicKeyComponents { n, e }.verify(params, data, signature)
+            }
+            13 | 14 => {
+                let (algorithm, len) = if self.algorithm == 13 {
+                    (&signature::ECDSA_P256_SHA256_FIXED, 64)
+                } else {
+                    (&signature::ECDSA_P384_SHA384_FIXED, 96)
+                };
+                if key.len() != len {
+                    return false;
+                }
+                let mut point = Vec::with_capacity(len + 1);
+                point.push(0x04);
+                point.extend_from_slice(key);
+                UnparsedPublicKey::new(algorithm, point).verify(data, signature)
+            }
+            15 => UnparsedPublicKey::new(&signature::ED25519, key).verify(data, signature),
+            _ => return false,
+        };
+        result.is_ok()
+    }
+}
+
+/// Splits an RSA `DNSKEY` public key into exponent and modulus (RFC 3110).
+fn rsa_components(key: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (exponent_len, rest) = match key {
+        [0, hi, lo, rest @ ..] => (usize::from(u16::from_be_bytes([*hi, *lo])), rest),
+        [len, rest @ ..] => (usize::from(*len), rest),
+        [] => return None,
+    };
+    (exponent_len > 0 && rest.len() > exponent_len).then(|| rest.split_at(exponent_len))
+}
+
+/// Computes the key tag of `DNSKEY` record data (RFC 4034, appendix B).
+fn key_tag(rdata: &[u8]) -> u16 {
+    let mut acc: u32 = 0;
+    for (i, &byte) in rdata.iter().enumerate() {
+        acc += if i % 2 == 0 {
+            u32::from(byte) << 8
+        } else {
+            u32::from(byte)
+        };
+    }
+    acc += (acc >> 16) & 0xffff;
+    (acc & 0xffff) as u16
+}
+
+/// The parsed data of a `DS` record.
+struct Ds<'a> {
+    key_tag: u16,
+    algorithm: u8,
+    digest_type: u8,
+    digest: &'a [u8],
+}
+
+impl<'a> Ds<'a> {
+    fn parse(rdata: &'a [u8]) -> Option<Self> {
+        let [t0, t1, algorithm, digest_type, digest @ ..] = rdata else {
+            return None;
+        };
+        Some(Self {
+            key_tag: u16::from_be_bytes([*t0, *t1]),
+            algorithm: *algorithm,
+            digest_type: *digest_type,
+            digest,
+        })
+    }
+
+    /// Returns `true` if `dnskey` (record data) owned by `owner` hashes to this
+    /// record.
+    fn matches(&self, owner: &Name, dnskey: &[u8]) -> bool {
+        let Some(key) = Dnskey::parse(dnskey) else {
+            return false;
+        };
+        if key.algorithm != self.algorithm || key.key_tag() != self.key_tag {
+            return false;
+        }
+        let algorithm = match self.digest_type {
+            DIGEST_SHA256 => &digest::SHA256,
+            DIGEST_SHA384 => &digest::SHA384,
+            _ => return false,
+        };
+        let mut context = digest::Context::new(algorithm);
+        context.update(owner.as_bytes());
+        context.update(dnskey);
+        context.finish().as_ref() == self.digest
+    }
+}
+
+/// The parsed data of an `RRSIG` record.
+struct Rrsig<'a> {
+    type_covered: u16,
+    algorithm: u8,
+    labels: u8,
+    original_ttl: u32,
+    expiration: u32,
+    inception: u32,
+    key_tag: u16,
+    signer: Name,
+    /// The record data up to the signature, which starts the signed data.
+    header: &'a [u8],
+    signature: &'a [u8],
+}
+
+impl<'a> Rrsig<'a> {
+    fn parse(rdata: &'a [u8]) -> Option<Self> {
+        let fixed = rdata.get(..RRSIG_FIXED_LEN)?;
+        let mut pos = RRSIG_FIXED_LEN;
+        let signer = Name::parse(rdata, &mut pos).ok()?;
+        let u32_at =
+            |i: usize| u32::from_be_bytes([fixed[i], fixed[i + 1], fixed[i + 2], fixed[i + 3]]);
+        Some(Self {
+            type_covered: u16::from_be_bytes([fixed[0], fixed[1]]),
+            algorithm: fixed[2],
+            labels: fixed[3],
+            original_ttl: u32_at(4),
+            expiration: u32_at(8),
+            inception: u32_at(12),
+            key_tag: u16::from_be_bytes([fixed[16], fixed[17]]),
+            signer,
+            header: &rdata[..pos],
+            signature: &rdata[pos..],
+        })
+    }
+
+    /// Returns `true` if `now` lies between inception and expiration, in the
+    /// serial number arithmetic of RFC 4034, section 3.1.5.
+    fn is_current(&self, now: u64) -> bool {
+        let now = now as u32;
+        now.wrapping_sub(self.inception) as i32 >= 0
+            && self.expiration.wrapping_sub(now) as i32 >= 0
+    }
+
+    /// Returns the data this signature covers for the record set `rdatas` at
+    /// `owner` (RFC 4034, section 3.1.8.1).
+    fn signed_data(&self, owner: &Name, rdatas: &[&[u8]]) -> Vec<u8> {
+        let mut data = self.header.to_vec();
+        for rdata in rdatas {
+            data.extend_from_slice(owner.as_bytes());
+            data.extend_from_slice(&self.type_covered.to_be_bytes());
+            data.extend_from_slice(&CLASS_IN.to_be_bytes());
+            data.extend_from_slice(&self.original_ttl.to_be_bytes());
+            data.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            data.extend_from_slice(rdata);
+        }
+        data
+    }
+}
+
+/// Record sets proven authentic by [`validate`], keyed by owner and type.
+#[derive(Debug, Default)]
+pub(crate) struct Validated {
+    rrsets: HashMap<(Name, u16), Vec<Vec<u8>>>,
+    failures: HashMap<(Name, u16), Error>,
+}
+
+impl Validated {
+    /// Returns the data of the validated `rtype` records at `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns why validation failed if the records were present but not
+    /// authentic.
+    pub(crate) fn get(&mut self, name: &Name, rtype: u16) -> Result<Option<&[Vec<u8>]>> {
+        let key = (name.clone(), rtype);
+        if let Some(error) = self.failures.remove(&key) {
+            return Err(error);
+        }
+        Ok(self.rrsets.get(&key).map(Vec::as_slice))
+    }
+}
+
+/// Validates `records` against `anchors` at time `now` (seconds since the Unix
+/// epoch), returning every record set with a chain of trust to an anchor.
+///
+/// # Errors
+///
+/// Returns [`Error::Dnssec`] if the root keys cannot be validated against any
+/// anchor. Record sets further down that fail validation are set aside with
+/// their error rather than failing the whole proof, so a stray record does not
+/// spoil the rest.
+pub(crate) fn validate(records: &[Record], anchors: &[TrustAnchor], now: u64) -> Result<Validated> {
+    let mut rrsets: BTreeMap<(Name, u16), Vec<&[u8]>> = BTreeMap::new();
+    let mut signatures: Vec<(&Name, Rrsig<'_>)> = Vec::new();
+    for record in records.iter().filter(|r| r.class == CLASS_IN) {
+        if record.rtype == TYPE_RRSIG {
+            if let Some(rrsig) = Rrsig::parse(&record.rdata) {
+                signatures.push((&record.name, rrsig));
+            }
+        } else {
+            rrsets
+                .entry((record.name.clone(), record.rtype))
+                .or_default()
+                .push(&record.rdata);
+        }
+    }
+    for rdatas in rrsets.values_mut() {
+        rdatas.sort_unstable();
+        rdatas.dedup();
+    }
+    let checker = Checker {
+        signatures: &signatures,
+        now,
+    };
+
+    // Root keys, vouched for by an anchor
+    let root = Name::root();
+    let root_keys = rrsets
+        .get(&(root.clone(), TYPE_DNSKEY))
+        .ok_or_else(|| Error::Dnssec("missing root DNSKEY records".to_string()))?;
+    let anchored: Vec<&[u8]> = root_keys
+        .iter()
+        .copied()
+        .filter(|key| anchors.iter().any(|anchor| anchor.matches(key)))
+        .collect();
+    checker.verify(&root, TYPE_DNSKEY, root_keys, &root, &anchored)?;
+    let mut zones: HashMap<Name, Vec<&[u8]>> = HashMap::new();
+    zones.insert(root, root_keys.clone());
+
+    // Child zones, each vouched for by a DS set its parent signed
+    loop {
+        let mut progress = false;
+        for ((owner, rtype), ds_set) in &rrsets {
+            if *rtype != TYPE_DS || zones.contains_key(owner) {
+                continue;
+            }
+            if checker
+                .verify_in_zones(owner, TYPE_DS, ds_set, &zones, true)
+                .is_err()
+            {
+                continue;
+            }
+            let Some(keys) = rrsets.get(&(owner.clone(), TYPE_DNSKEY)) else {
+                continue;
+            };
+            let vouched: Vec<&[u8]> = keys
+                .iter()
+                .copied()
+                .filter(|key| {
+                    ds_set
+                        .iter()
+                        .filter_map(|ds| Ds::parse(ds))
+                        .any(|ds| ds.matches(owner, key))
+                })
+                .collect();
+            if checker
+                .verify(owner, TYPE_DNSKEY, keys, owner, &vouched)
+                .is_ok()
+            {
+                zones.insert(owner.clone(), keys.clone());
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    // Everything else, signed by a validated zone
+    let mut validated = Validated::default();
+    for ((owner, rtype), rdatas) in &rrsets {
+        if *rtype == TYPE_DNSKEY || *rtype == TYPE_DS {
+            continue;
+        }
+        let key = (owner.clone(), *rtype);
+        match checker.verify_in_zones(owner, *rtype, rdatas, &zones, false) {
+            Ok(()) => {
+                let rdatas = rdatas.iter().map(|rdata| rdata.to_vec()).collect();
+                validated.rrsets.insert(key, rdatas);
+            }
+            Err(error) => {
+                validated.failures.insert(key, error);
+            }
+        }
+    }
+    Ok(validated)
+}
+
+/// Checks record sets against the `RRSIG` records of a proof.
+struct Checker<'a> {
+    signatures: &'a [(&'a Name, Rrsig<'a>)],
+    now: u64,
+}
+
+impl Checker<'_> {
+    /// Verifies that one of `keys`, belonging to zone `signer`, signed the
+    /// `rtype` set `rdatas` at `owner`.
+    fn verify(
+        &self,
+        owner: &Name,
+        rtype: u16,
+        rdatas: &[&[u8]],
+        signer: &Name,
+        keys: &[&[u8]],
+    ) -> Result<()> {
+        let mut reason = "no signature";
+        let mut wildcard = false;
+        for (_, rrsig) in self.signatures.iter().filter(|(name, rrsig)| {
+            *name == owner && rrsig.type_covered == rtype && rrsig.signer == *signer
+        }) {
+            let labels = usize::from(rrsig.labels);
+            if labels < owner.label_count() {
+                reason = "wildcard answers are not supported";
+                wildcard = true;
+                continue;
+            }
+            if labels > owner.label_count() {
+                reason = "signature label count exceeds owner name";
+                continue;
+            }
+            if !rrsig.is_current(self.now) {
+                reason = "signature expired or not yet valid";
+                continue;
+            }
+            let data = rrsig.signed_data(owner, rdatas);
+            let verified = keys
+                .iter()
+                .filter_map(|key| Dnskey::parse(key))
+                .filter(|key| {
+                    key.is_zone_key()
+                        && key.algorithm == rrsig.algorithm
+                        && key.key_tag() == rrsig.key_tag
+                })
+                .any(|key| key.verify(&data, rrsig.signature));
+            if verified {
+                return Ok(());
+            }
+            reason = "bad signature";
+        }
+        let error = format!("{} {}: {}", owner, type_name(rtype), reason);
+        if wildcard {
+            Err(Error::Unsupported(error))
+        } else {
+            Err(Error::Dnssec(error))
+        }
+    }
+
+    /// Verifies that a validated zone among `zones` enclosing `owner` signed the
+    /// `rtype` set at `owner`. With `strict` the zone must lie strictly above
+    /// `owner`, as for the parent side of a delegation.
+    ///
+    /// On failure, returns the error from the closest enclosing zone.
+    fn verify_in_zones(
+        &self,
+        owner: &Name,
+        rtype: u16,
+        rdatas: &[&[u8]],
+        zones: &HashMap<Name, Vec<&[u8]>>,
+        strict: bool,
+    ) -> Result<()> {
+        let mut enclosing: Vec<(&Name, &Vec<&[u8]>)> = zones
+            .iter()
+            .filter(|(zone, _)| owner.is_within(zone) && !(strict && owner == *zone))
+            .collect();
+        enclosing.sort_by_key(|(zone, _)| Reverse(zone.label_count()));
+
+        let mut first_error = None;
+        for (zone, keys) in enclosing {
+            match self.verify(owner, rtype, rdatas, zone, keys) {
+                Ok(()) => return Ok(()),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| {
+            Error::Dnssec(format!(
+                "{} {}: not in a validated zone",
+                owner,
+                type_name(rtype)
+            ))
+        }))
+    }
+}
+
+/// Returns the mnemonic of a record type for error messages.
+fn type_name(rtype: u16) -> String {
+    match rtype {
+        crate::wire::TYPE_CNAME => "CNAME".to_string(),
+        crate::wire::TYPE_TXT => "TXT".to_string(),
+        TYPE_DS => "DS".to_string(),
+        TYPE_DNSKEY => "DNSKEY".to_string(),
+        other => format!("TYPE{}", other),
+    }
+}
+
+/// Test helpers for building signed zones.
+#[cfg(test)]
+pub(crate) mod testing {
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use super::*;
+    use crate::wire::TYPE_TXT;
+
+    /// Signature inception used by test zones.
+    pub const INCEPTION: u32 = 1_700_000_000;
+    /// Signature expiration used by test zones.
+    pub const EXPIRATION: u32 = 1_800_000_000;
+    /// A time at which test signatures are valid.
+    pub const NOW: u64 = 1_750_000_000;
+
+    /// An Ed25519 zone key.
+    pub struct ZoneKey {
+        pub zone: Name,
+        pair: Ed25519KeyPair,
+        flags: u16,
+    }
+
+    impl ZoneKey {
+        /// Creates a key-signing key for `zone` from a fixed seed.
+        pub fn new(zone: &str, seed: u8) -> Self {
+            Self {
+                zone: Name::from_ascii(zone).unwrap(),
+                pair: Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).unwrap(),
+                flags: 0x0101,
+            }
+        }
+
+        /// Returns the `DNSKEY` record data.
+        pub fn dnskey(&self) -> Vec<u8> {
+            let mut rdata = self.flags.to_be_bytes().to_vec();
+            rdata.extend_from_slice(&[PROTOCOL_DNSSEC, 15]);
+            rdata.extend_from_slice(self.pair.public_key().as_ref());
+            rdata
+        }
+
+        /// Returns the `DS` record data for this key.
+        pub fn ds(&self) -> Vec<u8> {
+            let dnskey = self.dnskey();
+            let mut context = digest::Context::new(&digest::SHA256);
+            context.update(self.zone.as_bytes());
+            context.update(&dnskey);
+            let mut rdata = key_tag(&dnskey).to_be_bytes().to_vec();
+            rdata.extend_from_slice(&[15, DIGEST_SHA256]);
+            rdata.extend_from_slice(context.finish().as_ref());
+            rdata
+        }
+
+        /// Returns a trust anchor for this key.
+        pub fn anchor(&self) -> TrustAnchor {
+            let ds = self.ds();
+            TrustAnchor {
+                key_tag: u16::from_be_bytes([ds[0], ds[1]]),
+                algorithm: 15,
+                digest_type: DIGEST_SHA256,
+                digest: ds[4..].to_vec(),
+            }
+        }
+
+        /// Returns the `DNSKEY` record and its signature.
+        pub fn dnskey_records(&self) -> Vec<Record> {
+            self.signed(&self.zone, TYPE_DNSKEY, vec![self.dnskey()])
+        }
+
+        /// Returns `rtype` records at `owner` with data `rdatas` and this key's
+        /// signature over them.
+        pub fn signed(&self, owner: &Name, rtype: u16, rdatas: Vec<Vec<u8>>) -> Vec<Record> {
+            let mut records: Vec<Record> = rdatas
+                .into_iter()
+                .map(|rdata| record(owner, rtype, rdata))
+                .collect();
+            records.push(self.rrsig(owner, rtype, &records));
+            records
+        }
+
+        /// Signs `records`, which share an owner and type.
+        pub fn rrsig(&self, owner: &Name, rtype: u16, records: &[Record]) -> Record {
+            let mut header = rtype.to_be_bytes().to_vec();
+            header.push(15);
+            header.push(owner.label_count() as u8);
+            header.extend_from_slice(&3600u32.to_be_bytes());
+            header.extend_from_slice(&EXPIRATION.to_be_bytes());
+            header.extend_from_slice(&INCEPTION.to_be_bytes());
+            header.extend_from_slice(&key_tag(&self.dnskey()).to_be_bytes());
+            header.extend_from_slice(self.zone.as_bytes());
+
+            let mut rdatas: Vec<&[u8]> = records.iter().map(|r| r.rdata.as_slice()).collect();
+            rdatas.sort_unstable();
+            let rrsig = Rrsig::parse(&header).unwrap();
+            let data = rrsig.signed_data(owner, &rdatas);
+            let mut rdata = header.clone();
+            rdata.extend_from_slice(self.pair.sign(&data).as_ref());
+            record(owner, TYPE_RRSIG, rdata)
+        }
+    }
+
+    /// Returns a record with a one-hour TTL.
+    pub fn record(owner: &Name, rtype: u16, rdata: Vec<u8>) -> Record {
+        Record {
+            name: owner.clone(),
+            rtype,
+            class: CLASS_IN,
+            ttl: 3600,
+            rdata,
+        }
+    }
+
+    /// Returns `TXT` record data holding `text`, split into 255-byte strings.
+    pub fn txt(text: &str) -> Vec<u8> {
+        let mut rdata = Vec::new();
+        for chunk in text.as_bytes().chunks(255) {
+            rdata.push(chunk.len() as u8);
+            rdata.extend_from_slice(chunk);
+        }
+        rdata
+    }
+
+    /// A three-level test tree: root, `com.` and `example.com.`.
+    pub struct Tree {
+        pub root: ZoneKey,
+        pub com: ZoneKey,
+        pub example: ZoneKey,
+    }
+
+    impl Tree {
+        pub fn new() -> Self {
+            Self {
+                root: ZoneKey::new(".", 1),
+                com: ZoneKey::new("com", 2),
+                example: ZoneKey::new("example.com", 3),
+            }
+        }
+
+        /// Returns the chain of trust from the root down to `example.com.`.
+        pub fn chain(&self) -> Vec<Record> {
+            let mut records = self.root.dnskey_records();
+            records.extend(
+                self.root
+                    .signed(&self.com.zone, TYPE_DS, vec![self.com.ds()]),
+            );
+            records.extend(self.com.dnskey_records());
+            records.extend(
+                self.com
+                    .signed(&self.example.zone, TYPE_DS, vec![self.example.ds()]),
+            );
+            records.extend(self.example.dnskey_records());
+            records
+        }
+
+        /// Returns the chain plus a signed `TXT` record at `owner` in `example.com.`.
+        pub fn with_txt(&self, owner: &str, text: &str) -> Vec<Record> {
+            let mut records = self.chain();
+            let owner = Name::from_ascii(owner).unwrap();
+            records.extend(self.example.signed(&owner, TYPE_TXT, vec![txt(text)]));
+            records
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+    use crate::wire::TYPE_TXT;
+
+    const OWNER: &str = "alice.user._bitcoin-payment.example.com";
+
+    fn validate_tree(records: &[Record], tree: &Tree) -> Result<Validated> {
+        validate(records, &[tree.root.anchor()], NOW)
+    }
+
+    #[test]
+    fn test_root_anchors() {
+        // KSK-2017 as published in the root zone
+        let ksk = concat!(
+            "03010001acffb409bcc939f831f7a1e5ec88f7a59255ec53040be432027390a4",
+            "ce896d6f9086f3c5e177fbfe118163aaec7af1462c47945944c4e2c026be5e98",
+            "bbcded25978272e1e3e079c5094d573f0e83c92f02b32d3513b1550b826929c8",
+            "0dd0f92cac966d17769fd5867b647c3f38029abdc48152eb8f207159ecc5d232",
+            "c7c1537c79f4b7ac28ff11682f21681bf6d6aba555032bf6f9f036beb2aaa5b3",
+            "778d6eebfba6bf9ea191be4ab0caea759e2f773a1f9029c73ecb8d5735b9321d",
+            "b085f1b8e2d8038fe2941992548cee0d67dd4547e11dd63af9c9fc1c5466fb68",
+            "4cf009d7197c2cf79e792ab501e6a8a1ca519af2cb9b5f6367e94c0d47502451",
+            "357be1b5",
+        );
+        let mut rdata = vec![0x01, 0x01, 3, 8];
+        rdata.extend(hex::decode(ksk).unwrap());
+        assert_eq!(key_tag(&rdata), 20326);
+
+        let anchors = TrustAnchor::root();
+        assert_eq!(anchors.len(), 2);
+        assert!(anchors[0].matches(&rdata));
+        assert!(!anchors[1].matches(&rdata));
+        assert_eq!(anchors[0].to_string(), ROOT_ANCHORS[0]);
+        assert!("20326 8 2".parse::<TrustAnchor>().is_err());
+        assert!("20326 8 2 zz".parse::<TrustAnchor>().is_err());
+    }
+
+    #[test]
+    fn test_rsa_components() {
+        assert_eq!(
+            rsa_components(&[3, 1, 0, 1, 0xaa, 0xbb]),
+            Some((&[1, 0, 1][..], &[0xaa, 0xbb][..]))
+        );
+        assert_eq!(
+            rsa_components(&[0, 0, 1, 3, 0xaa]),
+            Some((&[3][..], &[0xaa][..]))
+        );
+        assert_eq!(rsa_components(&[3, 1, 0, 1]), None);
+        assert_eq!(rsa_components(&[]), None);
+    }
+
+    #[test]
+    fn test_ecdsa_p256_key() {
+        use ring::rand::SystemRandom;
+        use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        // DNSKEY holds the point without its 0x04 prefix
+        let mut rdata = vec![0x01, 0x01, PROTOCOL_DNSSEC, 13];
+        rdata.extend_from_slice(&pair.public_key().as_ref()[1..]);
+        let key = Dnskey::parse(&rdata).unwrap();
+        assert!(key.is_zone_key());
+
+        let signature = pair.sign(&rng, b"signed data").unwrap();
+        assert!(key.verify(b"signed data", signature.as_ref()));
+        assert!(!key.verify(b"other data", signature.as_ref()));
+
+        let mut revoked = rdata.clone();
+        revoked[1] |= 0x80;
+        assert!(!Dnskey::parse(&revoked).unwrap().is_zone_key());
+    }
+
+    #[test]
+    fn test_validate_chain() {
+        let tree = Tree::new();
+        let records = tree.with_txt(OWNER, "bitcoin:?sp=sp1q");
+        let mut validated = validate_tree(&records, &tree).unwrap();
+        let owner = Name::from_ascii(OWNER).unwrap();
+        assert_eq!(
+            validated.get(&owner, TYPE_TXT).unwrap().unwrap(),
+            &[txt("bitcoin:?sp=sp1q")]
+        );
+        let other = Name::from_ascii("bob.user._bitcoin-payment.example.com").unwrap();
+        assert!(validated.get(&other, TYPE_TXT).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_untrusted_anchor() {
+        let tree = Tree::new();
+        let records = tree.with_txt(OWNER, "bitcoin:?sp=sp1q");
+        let other = ZoneKey::new(".", 9);
+        assert!(matches!(
+            validate(&records, &[other.anchor()], NOW),
+            Err(Error::Dnssec(_))
+        ));
+        assert!(matches!(
+            validate(&records, &TrustAnchor::root(), NOW),
+            Err(Error::Dnssec(_))
+        ));
+    }
+
+    #[test]
+    fn test_forged_records() {
+        let tree = Tree::new();
+        let owner = Name::from_ascii(OWNER).unwrap();
+
+        // Altered TXT data
+        let mut records = tree.with_txt(OWNER, "bitcoin:?sp=sp1q");
+        let txt_record = records.iter_mut().find(|r| r.rtype == TYPE_TXT).unwrap();
+        txt_record.rdata = txt("bitcoin:bc1qattacker");
+        let mut validated = validate_tree(&records, &tree).unwrap();
+        assert!(matches!(
+            validated.get(&owner, TYPE_TXT),
+            Err(Error::Dnssec(_))
+        ));
+
+        // A zone key the parent never vouched for
+        let rogue = ZoneKey::new("example.com", 7);
+        let mut records = tree.chain();
+        records.extend(rogue.dnskey_records());
+        records.extend(rogue.signed(&owner, TYPE_TXT, vec![txt("bitcoin:bc1qattacker")]));
+        let mut validated = validate_tree(&records, &tree).unwrap();
+        assert!(matches!(
+            validated.get(&owner, TYPE_TXT),
+            Err(Error::Dnssec(_))
+        ));
+
+        // Expired signatures
+        let records = tree.with_txt(OWNER, "bitcoin:?sp=sp1q");
+        assert!(validate(&records, &[tree.root.anchor()], u64::from(EXPIRATION) + 1).is_err());
+    }
+
+    #[test]
+    fn test_missing_delegation() {
+        let tree = Tree::new();
+        let owner = Name::from_ascii(OWNER).unwrap();
+        let records: Vec<Record> = tree
+            .with_txt(OWNER, "bitcoin:?sp=sp1q")
+            .into_iter()
+            .filter(|r| !(r.name == tree.example.zone && r.rtype == TYPE_DS))
+            .collect();
+        let mut validated = validate_tree(&records, &tree).unwrap();
+        assert!(matches!(
+            validated.get(&owner, TYPE_TXT),
+            Err(Error::Dnssec(_))
+        ));
+    }
+
+    #[test]
+    fn test_wildcard_rejected() {
+        let tree = Tree::new();
+        let owner = Name::from_ascii(OWNER).unwrap();
+        let mut records = tree.chain();
+        let txt_record = record(&owner, TYPE_TXT, txt("bitcoin:?sp=sp1q"));
+        let mut rrsig = tree
+            .example
+            .rrsig(&owner, TYPE_TXT, std::slice::from_ref(&txt_record));
+        rrsig.rdata[3] -= 1; // as if expanded from *.user._bitcoin-payment.example.com
+        records.extend([txt_record, rrsig]);
+
+        let mut validated = validate_tree(&records, &tree).unwrap();
+        assert!(matches!(
+            validated.get(&owner, TYPE_TXT),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+//! DNS-over-HTTPS transport (requires the `doh` feature).
+//!
+//! Sends queries as RFC 8484 `application/dns-message` POST requests, so lookups
+//! are encrypted on the way to the resolver and work wherever HTTPS does.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_bip353::doh::DohTransport;
+//! use khodpay_bip353::Resolver;
+//! use khodpay_btc_signing::Network;
+//!
+//! let resolver = Resolver::new(
+//!     DohTransport::new("https://dns.google/dns-query"),
+//!     Network::Bitcoin,
+//! );
+//! ```
+
+use async_trait::async_trait;
+
+use crate::{DnsTransport, Error, Result};
+
+/// Media type of DNS messages over HTTPS.
+const DNS_MESSAGE: &str = "application/dns-message";
+
+/// A [`DnsTransport`] over DNS-over-HTTPS.
+#[derive(Debug, Clone)]
+pub struct DohTransport {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl DohTransport {
+    /// Creates a transport for the resolver endpoint at `url`, such as
+    /// `https://cloudflare-dns.com/dns-query`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_http_client(url, reqwest::Client::new())
+    }
+
+    /// Creates a transport that reuses an existing `reqwest::Client` (for custom
+    /// timeouts, proxies or headers).
+    pub fn with_http_client(url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            url: url.into(),
+            http,
+        }
+    }
+
+    /// Returns the resolver endpoint.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl DnsTransport for DohTransport {
+    async fn exchange(&self, query: &[u8]) -> Result<Vec<u8>> {
+        let response = self
+            .http
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, DNS_MESSAGE)
+            .header(reqwest::header::ACCEPT, DNS_MESSAGE)
+            .body(query.to_vec())
+            .send()
+            .await
+            .map_err(|e| Error::Transport(format!("POST {}: {}", self.url, e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::Transport(format!(
+                "POST {}: HTTP {}",
+                self.url, status
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| Error::Transport(format!("POST {}: {}", self.url, e)))?;
+        Ok(body.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_bytes, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_exchange() {
+        let server = MockServer::start().await;
+        let query = b"\x00\x00\x01\x00query".to_vec();
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .and(header("content-type", DNS_MESSAGE))
+            .and(body_bytes(query.clone()))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"answer".to_vec()))
+            .mount(&server)
+            .await;
+
+        let transport = DohTransport::new(format!("{}/dns-query", server.uri()));
+        assert_eq!(transport.exchange(&query).await.unwrap(), b"answer");
+
+        let missing = DohTransport::new(format!("{}/other", server.uri()));
+        assert!(matches!(
+            missing.exchange(&query).await,
+            Err(Error::Transport(e)) if e.ends_with("HTTP 404 Not Found")
+        ));
+    }
+}
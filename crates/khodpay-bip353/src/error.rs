@@ -0,0 +1,69 @@
+//! Error types for the BIP-353 crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while resolving or verifying a human-readable name.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The name is not a valid `user@domain` address.
+    #[error("Invalid name: {0}")]
+    InvalidName(String),
+
+    /// A DNS message or proof is malformed.
+    #[error("Invalid DNS message: {0}")]
+    InvalidMessage(String),
+
+    /// The records do not form a DNSSEC chain of trust from a trust anchor.
+    #[error("DNSSEC validation failed: {0}")]
+    Dnssec(String),
+
+    /// The name has no `bitcoin:` record.
+    #[error("No payment instructions for {0}")]
+    NotFound(String),
+
+    /// The `bitcoin:` record is malformed or offers no payment method this crate
+    /// understands.
+    #[error("Invalid payment instructions: {0}")]
+    InvalidInstructions(String),
+
+    /// The DNS server could not be reached.
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    /// The DNS server answered with an error code, such as `SERVFAIL`.
+    #[error("DNS server error: {0}")]
+    Server(String),
+
+    /// The records use a feature this crate does not validate, such as wildcard
+    /// expansion.
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
+    /// Error from Bitcoin address or amount parsing.
+    #[error("Bitcoin error: {0}")]
+    BtcError(#[from] khodpay_btc_signing::Error),
+
+    /// Error from silent payment address parsing.
+    #[error("BIP-44 error: {0}")]
+    Bip44Error(#[from] khodpay_bip44::Error),
+}
+
+/// Result type alias for BIP-353 operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::NotFound("₿alice@example.com".to_string()).to_string(),
+            "No payment instructions for ₿alice@example.com"
+        );
+        assert_eq!(
+            Error::Dnssec("no trusted root key".to_string()).to_string(),
+            "DNSSEC validation failed: no trusted root key"
+        );
+    }
+}
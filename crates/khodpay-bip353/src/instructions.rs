@@ -0,0 +1,264 @@
+//! Payment instructions read from a `bitcoin:` URI.
+
+use std::str::FromStr;
+
+use khodpay_bip44::SilentPaymentAddress;
+use khodpay_btc_signing::bip21::{parse_btc, PaymentUri, SCHEME};
+use khodpay_btc_signing::{Address, Network};
+
+use crate::{Error, Result};
+
+/// What a BIP-353 record says to pay: a `bitcoin:` URI in the BIP-21 form
+/// extended by BIP-321, which may leave out the on-chain address.
+///
+/// Records usually carry reusable methods: a BIP-352 silent payment address
+/// (`sp`) or a BOLT 12 offer (`lno`). A plain on-chain address is allowed but is
+/// reused by every payer.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip353::PaymentInstructions;
+/// use khodpay_btc_signing::Network;
+///
+/// let instructions = PaymentInstructions::parse(
+///     "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4?amount=0.001&label=Alice",
+///     Network::Bitcoin,
+/// )?;
+/// assert_eq!(instructions.amount, Some(100_000));
+///
+/// let uri = instructions.payment_uri().unwrap();
+/// assert_eq!(uri.label.as_deref(), Some("Alice"));
+/// # Ok::<(), khodpay_bip353::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentInstructions {
+    /// On-chain address, if the URI has one.
+    pub address: Option<Address>,
+    /// BIP-352 silent payment address, from the `sp` parameter.
+    pub silent_payment: Option<SilentPaymentAddress>,
+    /// BOLT 12 offer, from the `lno` parameter.
+    pub offer: Option<String>,
+    /// BOLT 11 invoice, from the `lightning` parameter.
+    pub invoice: Option<String>,
+    /// Requested amount in satoshis, if specified.
+    pub amount: Option<u64>,
+    /// Label for the recipient, percent-decoded.
+    pub label: Option<String>,
+    /// Message describing the payment, percent-decoded.
+    pub message: Option<String>,
+    /// Other query parameters in URI order, with lowercase keys and
+    /// percent-decoded values.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl PaymentInstructions {
+    /// Parses a `bitcoin:` URI and checks that its addresses belong to `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInstructions`] if the URI is malformed, has an
+    /// unknown `req-` parameter or no payment method this crate supports, and
+    /// [`Error::BtcError`] or [`Error::Bip44Error`] if an address is malformed or
+    /// for another network.
+    pub fn parse(uri: &str, network: Network) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidInstructions(reason);
+
+        let rest = uri
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| invalid("missing bitcoin: scheme".to_string()))?;
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let mut instructions = Self {
+            address: None,
+            silent_payment: None,
+            offer: None,
+            invoice: None,
+            amount: None,
+            label: None,
+            message: None,
+            parameters: Vec::new(),
+        };
+        if !address.is_empty() {
+            instructions.address = Some(Address::parse(address, network)?);
+        }
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("parameter without value: {}", pair)))?;
+            let key = percent_decode(key)?.to_ascii_lowercase();
+            let value = percent_decode(value)?;
+            let duplicate = || invalid(format!("duplicate {}", key));
+            match key.as_str() {
+                "amount" => {
+                    if instructions.amount.replace(parse_btc(&value)?).is_some() {
+                        return Err(duplicate());
+                    }
+                }
+                "sp" => {
+                    let address = SilentPaymentAddress::from_str(&value)?;
+                    let mainnet = address.network() == khodpay_bip32::Network::BitcoinMainnet;
+                    if mainnet != (network == Network::Bitcoin) {
+                        return Err(invalid(format!(
+                            "silent payment address is not for {}",
+                            network
+                        )));
+                    }
+                    if instructions.silent_payment.replace(address).is_some() {
+                        return Err(duplicate());
+                    }
+                }
+                "label" | "message" | "lno" | "lightning" => {
+                    let slot = match key.as_str() {
+                        "label" => &mut instructions.label,
+                        "message" => &mut instructions.message,
+                        "lno" => &mut instructions.offer,
+                        _ => &mut instructions.invoice,
+                    };
+                    if slot.replace(value).is_some() {
+                        return Err(duplicate());
+                    }
+                }
+                _ if key.starts_with("req-") => {
+                    return Err(invalid(format!("unsupported required parameter: {}", key)))
+                }
+                _ => instructions.parameters.push((key, value)),
+            }
+        }
+
+        if instructions.address.is_none()
+            && instructions.silent_payment.is_none()
+            && instructions.offer.is_none()
+            && instructions.invoice.is_none()
+        {
+            return Err(invalid("no supported payment method".to_string()));
+        }
+        Ok(instructions)
+    }
+
+    /// Returns the on-chain part as a BIP-21 [`PaymentUri`], for code that pays
+    /// plain addresses, or `None` if there is no on-chain address.
+    pub fn payment_uri(&self) -> Option<PaymentUri> {
+        let address = self.address.clone()?;
+        Some(PaymentUri {
+            address,
+            amount: self.amount,
+            label: self.label.clone(),
+            message: self.message.clone(),
+            parameters: Vec::new(),
+        })
+    }
+}
+
+/// Decodes `%XX` escapes.
+fn percent_decode(s: &str) -> Result<String> {
+    let invalid = || Error::InvalidInstructions(format!("invalid percent-encoding: {}", s));
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [
+                iter.next().ok_or_else(invalid)?,
+                iter.next().ok_or_else(invalid)?,
+            ];
+            let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_bip44::{SilentPaymentKeys, Wallet};
+
+    fn silent_payment_address(network: khodpay_bip32::Network) -> String {
+        let wallet = Wallet::from_seed(&[7u8; 64], network).unwrap();
+        SilentPaymentKeys::from_wallet(&wallet, 0)
+            .unwrap()
+            .address()
+            .to_string()
+    }
+
+    #[test]
+    fn test_silent_payment_only() {
+        let sp = silent_payment_address(khodpay_bip32::Network::BitcoinMainnet);
+        let uri = format!("BITCOIN:?SP={}&lno=lno1qsgq", sp);
+        let instructions = PaymentInstructions::parse(&uri, Network::Bitcoin).unwrap();
+        assert!(instructions.address.is_none());
+        assert_eq!(
+            instructions.silent_payment.as_ref().unwrap().to_string(),
+            sp
+        );
+        assert_eq!(instructions.offer.as_deref(), Some("lno1qsgq"));
+        assert!(instructions.payment_uri().is_none());
+
+        // A testnet silent payment address on mainnet
+        let tsp = silent_payment_address(khodpay_bip32::Network::BitcoinTestnet);
+        let uri = format!("bitcoin:?sp={}", tsp);
+        assert!(matches!(
+            PaymentInstructions::parse(&uri, Network::Bitcoin),
+            Err(Error::InvalidInstructions(_))
+        ));
+        assert!(PaymentInstructions::parse(&uri, Network::Signet).is_ok());
+    }
+
+    #[test]
+    fn test_on_chain() {
+        let instructions = PaymentInstructions::parse(
+            "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4?message=Tip%20jar&pj=https://example.com/pj",
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert_eq!(instructions.message.as_deref(), Some("Tip jar"));
+        assert_eq!(
+            instructions.parameters,
+            vec![("pj".to_string(), "https://example.com/pj".to_string())]
+        );
+        let uri = instructions.payment_uri().unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4?message=Tip%20jar"
+        );
+
+        assert!(matches!(
+            PaymentInstructions::parse(
+                "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                Network::Testnet
+            ),
+            Err(Error::BtcError(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_instructions() {
+        for uri in [
+            "lightning:lnbc1",
+            "bitcoin:",
+            "bitcoin:?label=Alice",
+            "bitcoin:?lno=lno1qsgq&req-pop=1",
+            "bitcoin:?lno=a&lno=b",
+            "bitcoin:?lno=%zz",
+            "bitcoin:?lno",
+        ] {
+            assert!(
+                matches!(
+                    PaymentInstructions::parse(uri, Network::Bitcoin),
+                    Err(Error::InvalidInstructions(_))
+                ),
+                "{}",
+                uri
+            );
+        }
+        assert!(matches!(
+            PaymentInstructions::parse("bitcoin:?sp=sp1qbogus", Network::Bitcoin),
+            Err(Error::Bip44Error(_))
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+//! # Khodpay BIP-353
+//!
+//! Human-readable Bitcoin payment addresses: `₿alice@example.com` resolves to
+//! the `bitcoin:` URI in a DNSSEC-signed `TXT` record, as specified by
+//! [BIP-353](https://github.com/bitcoin/bips/blob/master/bip-0353.mediawiki).
+//!
+//! Answers are validated locally from IANA's root keys down, so the DNS resolver
+//! is only a courier: it can make a lookup fail but cannot change where a
+//! payment goes. The URI is read into [`PaymentInstructions`]: a BIP-352 silent
+//! payment address, an on-chain address as a BIP-21 [`PaymentUri`], or a
+//! Lightning offer.
+//!
+//! [`PaymentUri`]: khodpay_btc_signing::bip21::PaymentUri
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`HumanReadableName`] | A `₿user@domain` name and its DNS name |
+//! | [`Resolver`] | Looks names up over a [`DnsTransport`] and verifies the answer |
+//! | [`doh`] | DNS-over-HTTPS transport (feature `doh`) |
+//! | [`Proof`] | An RFC 9102 DNSSEC proof, verifiable offline |
+//! | [`TrustAnchor`] | The root keys validation starts from |
+//! | [`PaymentInstructions`] | What the name says to pay |
+//!
+//! ## Features
+//!
+//! - **DNSSEC Validated**: RSA, ECDSA P-256/P-384 and Ed25519 signatures checked
+//!   from the root down; wildcard answers are refused
+//! - **Offline Proofs**: Hand a [`Proof`] to a hardware wallet or air-gapped signer
+//!   and verify it there
+//! - **Silent Payments**: `sp` addresses parsed and checked against the network
+//! - **BIP-21 Compatible**: On-chain addresses come back as a
+//!   [`PaymentUri`]
+//! - **Pluggable Transport**: DNS-over-HTTPS built in, any [`DnsTransport`] works
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use khodpay_bip353::doh::DohTransport;
+//! use khodpay_bip353::{HumanReadableName, Resolver};
+//! use khodpay_btc_signing::Network;
+//!
+//! # async fn run() -> khodpay_bip353::Result<()> {
+//! let resolver = Resolver::new(
+//!     DohTransport::new("https://cloudflare-dns.com/dns-query"),
+//!     Network::Bitcoin,
+//! );
+//!
+//! let name: HumanReadableName = "₿alice@example.com".parse()?;
+//! let instructions = resolver.resolve(&name).await?;
+//! if let Some(address) = &instructions.silent_payment {
+//!     println!("silent payment to {}", address);
+//! } else if let Some(uri) = instructions.payment_uri() {
+//!     println!("on-chain payment: {}", uri);
+//! }
+//!
+//! // Or look up now and verify on another device
+//! let proof = resolver.prove(&name).await?;
+//! let bytes = proof.into_bytes();
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod dnssec;
+#[cfg(feature = "doh")]
+pub mod doh;
+mod error;
+mod instructions;
+mod name;
+mod proof;
+mod resolver;
+mod wire;
+
+pub use dnssec::TrustAnchor;
+pub use error::{Error, Result};
+pub use instructions::PaymentInstructions;
+pub use name::{HumanReadableName, BITCOIN_SIGN};
+pub use proof::Proof;
+pub use resolver::{DnsTransport, Resolver};
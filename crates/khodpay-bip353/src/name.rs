@@ -0,0 +1,163 @@
+//! Human-readable `₿user@domain` names.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::wire::Name;
+use crate::{Error, Result};
+
+/// The Bitcoin sign that may precede a human-readable name.
+pub const BITCOIN_SIGN: char = '₿';
+
+/// Label between the user and the domain in the DNS name.
+const PAYMENT_LABELS: &str = "user._bitcoin-payment";
+
+/// A BIP-353 human-readable payment address such as `₿alice@example.com`.
+///
+/// The payment instructions for `₿alice@example.com` live in a `TXT` record at
+/// `alice.user._bitcoin-payment.example.com`. Both parts are case-insensitive and
+/// stored in lowercase. Internationalized names must be given in their ASCII
+/// (`xn--`) form.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip353::HumanReadableName;
+///
+/// let name: HumanReadableName = "₿Alice@Example.com".parse()?;
+/// assert_eq!(name.user(), "alice");
+/// assert_eq!(name.domain(), "example.com");
+/// assert_eq!(name.dns_name(), "alice.user._bitcoin-payment.example.com.");
+/// assert_eq!(name.to_string(), "₿alice@example.com");
+///
+/// // The Bitcoin sign is optional
+/// assert_eq!("alice@example.com".parse::<HumanReadableName>()?, name);
+/// # Ok::<(), khodpay_bip353::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HumanReadableName {
+    user: String,
+    domain: String,
+}
+
+impl HumanReadableName {
+    /// Creates a name from its user and domain parts.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidName`] if either part is empty, holds characters
+    /// other than ASCII letters, digits, `-`, `_` and `.`, or makes a DNS name
+    /// that is too long.
+    pub fn new(user: &str, domain: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidName(format!("{}@{}: {}", user, domain, reason));
+        let domain = domain.strip_suffix('.').unwrap_or(domain);
+        for (part, what) in [(user, "user"), (domain, "domain")] {
+            if part.is_empty() {
+                return Err(invalid(&format!("empty {}", what)));
+            }
+            if let Some(c) = part
+                .chars()
+                .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            {
+                return Err(invalid(&format!("{:?} is not allowed in the {}", c, what)));
+            }
+        }
+        let name = Self {
+            user: user.to_ascii_lowercase(),
+            domain: domain.to_ascii_lowercase(),
+        };
+        name.to_dns().map_err(|e| match e {
+            Error::InvalidName(reason) => invalid(&reason),
+            other => other,
+        })?;
+        Ok(name)
+    }
+
+    /// Returns the user part, such as `alice`.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Returns the domain part, such as `example.com`.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns the DNS name holding the payment instructions, such as
+    /// `alice.user._bitcoin-payment.example.com.`.
+    pub fn dns_name(&self) -> String {
+        format!("{}.{}.{}.", self.user, PAYMENT_LABELS, self.domain)
+    }
+
+    /// Returns the DNS name in wire format.
+    pub(crate) fn to_dns(&self) -> Result<Name> {
+        Name::from_ascii(&self.dns_name())
+    }
+}
+
+impl FromStr for HumanReadableName {
+    type Err = Error;
+
+    /// Parses `₿user@domain` or `user@domain`.
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_prefix(BITCOIN_SIGN).unwrap_or(trimmed);
+        let (user, domain) = trimmed
+            .split_once('@')
+            .filter(|(_, domain)| !domain.contains('@'))
+            .ok_or_else(|| Error::InvalidName(format!("{:?}: expected user@domain", s)))?;
+        Self::new(user, domain)
+    }
+}
+
+impl fmt::Display for HumanReadableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}@{}", BITCOIN_SIGN, self.user, self.domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let name: HumanReadableName = " ₿matt@mattcorallo.com ".parse().unwrap();
+        assert_eq!(name.user(), "matt");
+        assert_eq!(
+            name.dns_name(),
+            "matt.user._bitcoin-payment.mattcorallo.com."
+        );
+        assert_eq!(
+            name.to_dns().unwrap().to_string(),
+            "matt.user._bitcoin-payment.mattcorallo.com."
+        );
+
+        let dotted = HumanReadableName::new("first.last", "example.com.").unwrap();
+        assert_eq!(dotted.to_string(), "₿first.last@example.com");
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        for name in [
+            "alice",
+            "alice@",
+            "@example.com",
+            "a@b@example.com",
+            "al ice@example.com",
+            "alicé@example.com",
+            "alice@example..com",
+        ] {
+            assert!(
+                matches!(
+                    name.parse::<HumanReadableName>(),
+                    Err(Error::InvalidName(_))
+                ),
+                "{}",
+                name
+            );
+        }
+        let long = format!("{}@example.com", "a".repeat(64));
+        assert!(long.parse::<HumanReadableName>().is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Self-contained DNSSEC proofs of payment instructions (RFC 9102).
+
+use khodpay_btc_signing::Network;
+
+use crate::dnssec::{self, TrustAnchor};
+use crate::wire::{self, Name, Record, TYPE_CNAME, TYPE_TXT};
+use crate::{Error, HumanReadableName, PaymentInstructions, Result};
+
+/// Most `CNAME` records followed from the queried name.
+pub(crate) const MAX_CNAME_HOPS: usize = 8;
+
+/// A DNSSEC proof of a name's payment instructions.
+///
+/// The proof is every record from the root keys down to the `TXT` record,
+/// uncompressed and concatenated, as in RFC 9102 and BIP-353. It can be checked
+/// without network access, so a hardware wallet or an air-gapped signer can
+/// verify instructions a phone looked up.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_bip353::{HumanReadableName, Proof};
+/// use khodpay_btc_signing::Network;
+///
+/// # fn run(bytes: Vec<u8>, now: u64) -> khodpay_bip353::Result<()> {
+/// let name: HumanReadableName = "₿alice@example.com".parse()?;
+/// let proof = Proof::from_bytes(bytes)?;
+/// let instructions = proof.verify(&name, Network::Bitcoin, now)?;
+/// println!("{:?}", instructions.silent_payment);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    bytes: Vec<u8>,
+    records: Vec<Record>,
+}
+
+impl Proof {
+    /// Reads a proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidMessage`] if the bytes are not a run of DNS
+    /// records.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Self> {
+        let bytes = bytes.into();
+        let records = wire::parse_records(&bytes)?;
+        Ok(Self { bytes, records })
+    }
+
+    /// Builds a proof from records, dropping duplicates.
+    pub(crate) fn from_records(records: Vec<Record>) -> Self {
+        let mut unique: Vec<Record> = Vec::with_capacity(records.len());
+        for record in records {
+            if !unique.contains(&record) {
+                unique.push(record);
+            }
+        }
+        let mut bytes = Vec::new();
+        for record in &unique {
+            record.write(&mut bytes);
+        }
+        Self {
+            bytes,
+            records: unique,
+        }
+    }
+
+    /// Returns the proof in wire format.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the proof in wire format, consuming it.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Verifies the proof for `name` against IANA's root keys at `now` (seconds
+    /// since the Unix epoch) and parses the instructions for `network`.
+    ///
+    /// # Errors
+    ///
+    /// As [`Proof::verify_uri`] and [`PaymentInstructions::parse`].
+    pub fn verify(
+        &self,
+        name: &HumanReadableName,
+        network: Network,
+        now: u64,
+    ) -> Result<PaymentInstructions> {
+        let uri = self.verify_uri(name, &TrustAnchor::root(), now)?;
+        PaymentInstructions::parse(&uri, network)
+    }
+
+    /// Verifies the proof for `name` against `anchors` at `now` (seconds since
+    /// the Unix epoch) and returns the `bitcoin:` URI it proves.
+    ///
+    /// `CNAME` records are followed. Records whose signatures use wildcard
+    /// expansion are refused, since proving them would also need a proof that
+    /// the exact name does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Dnssec`] if the records do not validate,
+    /// [`Error::Unsupported`] for wildcard answers, [`Error::NotFound`] if the
+    /// name has no `bitcoin:` record, and [`Error::InvalidInstructions`] if it
+    /// has more than one.
+    pub fn verify_uri(
+        &self,
+        name: &HumanReadableName,
+        anchors: &[TrustAnchor],
+        now: u64,
+    ) -> Result<String> {
+        let mut validated = dnssec::validate(&self.records, anchors, now)?;
+        let mut owner = name.to_dns()?;
+        for _ in 0..=MAX_CNAME_HOPS {
+            if let Some(txts) = validated.get(&owner, TYPE_TXT)? {
+                return bitcoin_record(name, txts);
+            }
+            match validated.get(&owner, TYPE_CNAME)? {
+                Some([target]) => owner = Name::parse(target, &mut 0)?,
+                Some(_) => {
+                    return Err(Error::InvalidMessage(format!(
+                        "{} has more than one CNAME",
+                        owner
+                    )))
+                }
+                None => return Err(Error::NotFound(name.to_string())),
+            }
+        }
+        Err(Error::InvalidMessage(format!(
+            "more than {} CNAME records from {}",
+            MAX_CNAME_HOPS,
+            name.dns_name()
+        )))
+    }
+}
+
+/// Picks the one `bitcoin:` URI among `TXT` record data.
+fn bitcoin_record(name: &HumanReadableName, txts: &[Vec<u8>]) -> Result<String> {
+    let mut uris = Vec::new();
+    for rdata in txts {
+        let text = txt_text(rdata)?;
+        let is_bitcoin = text
+            .get(..8)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case(b"bitcoin:"));
+        if is_bitcoin {
+            uris.push(text);
+        }
+    }
+    match uris.as_slice() {
+        [] => Err(Error::NotFound(name.to_string())),
+        [uri] => String::from_utf8(uri.clone())
+            .map_err(|_| Error::InvalidInstructions("record is not UTF-8".to_string())),
+        _ => Err(Error::InvalidInstructions(format!(
+            "{} has more than one bitcoin: record",
+            name
+        ))),
+    }
+}
+
+/// Joins the character strings of `TXT` record data.
+fn txt_text(rdata: &[u8]) -> Result<Vec<u8>> {
+    let mut text = Vec::with_capacity(rdata.len());
+    let mut rest = rdata;
+    while let Some((&len, tail)) = rest.split_first() {
+        let len = usize::from(len);
+        let chunk = tail
+            .get(..len)
+            .ok_or_else(|| Error::InvalidMessage("truncated TXT record".to_string()))?;
+        text.extend_from_slice(chunk);
+        rest = &tail[len..];
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnssec::testing::*;
+
+    /// A URI longer than one 255-byte TXT string.
+    fn uri() -> String {
+        format!("bitcoin:?lno=lno1{}", "qsgq".repeat(80))
+    }
+
+    fn name() -> HumanReadableName {
+        "₿alice@example.com".parse().unwrap()
+    }
+
+    fn proof(records: Vec<Record>) -> Proof {
+        let proof = Proof::from_records(records);
+        Proof::from_bytes(proof.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_verify() {
+        let tree = Tree::new();
+        let proof = proof(tree.with_txt(&name().dns_name(), &uri()));
+        let verified = proof
+            .verify_uri(&name(), &[tree.root.anchor()], NOW)
+            .unwrap();
+        assert_eq!(verified, uri());
+
+        // Not signed under IANA's root
+        assert!(matches!(
+            proof.verify(&name(), Network::Bitcoin, NOW),
+            Err(Error::Dnssec(_))
+        ));
+    }
+
+    #[test]
+    fn test_cname() {
+        let tree = Tree::new();
+        let alias = Name::from_ascii(&name().dns_name()).unwrap();
+        let target = "pay.example.com";
+        let mut records = tree.with_txt(target, &uri());
+        let target = Name::from_ascii(target).unwrap();
+        records.extend(
+            tree.example
+                .signed(&alias, TYPE_CNAME, vec![target.as_bytes().to_vec()]),
+        );
+        let verified = proof(records)
+            .verify_uri(&name(), &[tree.root.anchor()], NOW)
+            .unwrap();
+        assert_eq!(verified, uri());
+    }
+
+    #[test]
+    fn test_record_selection() {
+        let tree = Tree::new();
+        let owner = Name::from_ascii(&name().dns_name()).unwrap();
+        let anchors = [tree.root.anchor()];
+
+        // Other TXT records are ignored
+        let mut records = tree.chain();
+        records.extend(tree.example.signed(
+            &owner,
+            TYPE_TXT,
+            vec![txt("v=spf1 -all"), txt("BITCOIN:?lno=lno1qsgq")],
+        ));
+        let uri = proof(records).verify_uri(&name(), &anchors, NOW).unwrap();
+        assert_eq!(uri, "BITCOIN:?lno=lno1qsgq");
+
+        // Two bitcoin: records are ambiguous
+        let mut records = tree.chain();
+        records.extend(tree.example.signed(
+            &owner,
+            TYPE_TXT,
+            vec![txt("bitcoin:?lno=a"), txt("bitcoin:?lno=b")],
+        ));
+        assert!(matches!(
+            proof(records).verify_uri(&name(), &anchors, NOW),
+            Err(Error::InvalidInstructions(_))
+        ));
+
+        // No record at all
+        assert!(matches!(
+            proof(tree.chain()).verify_uri(&name(), &anchors, NOW),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_proof() {
+        assert!(matches!(
+            Proof::from_bytes(vec![0x07, b'e']),
+            Err(Error::InvalidMessage(_))
+        ));
+        assert!(matches!(
+            txt_text(b"\x05abc"),
+            Err(Error::InvalidMessage(_))
+        ));
+        assert_eq!(txt_text(b"\x02ab\x00\x01c").unwrap(), b"abc");
+    }
+}
@@ -0,0 +1,340 @@
+//! Resolving names over a [`DnsTransport`].
+
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use khodpay_btc_signing::Network;
+
+use crate::proof::MAX_CNAME_HOPS;
+use crate::wire::{
+    self, Name, Record, CLASS_IN, TYPE_CNAME, TYPE_DNSKEY, TYPE_DS, TYPE_RRSIG, TYPE_TXT,
+};
+use crate::{Error, HumanReadableName, PaymentInstructions, Proof, Result, TrustAnchor};
+
+/// Most zones a proof may pass through, root included.
+const MAX_ZONES: usize = 16;
+
+/// Carries DNS messages to a recursive resolver.
+///
+/// The resolver only needs to pass records through: every answer is validated
+/// locally, so a lying or compromised resolver can make resolution fail but not
+/// change its result. [`DohTransport`](crate::doh::DohTransport) implements it
+/// over DNS-over-HTTPS; implement it over UDP, TCP or a test fixture as needed.
+#[async_trait]
+pub trait DnsTransport: Send + Sync {
+    /// Sends a DNS query message and returns the response message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if the resolver cannot be reached.
+    async fn exchange(&self, query: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Resolves human-readable names into verified payment instructions.
+///
+/// Each lookup fetches the name's `TXT` record and every `DNSKEY` and `DS` set
+/// up to the root, then checks them against the trust anchors (IANA's root keys
+/// by default) before reading the instructions.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_bip353::doh::DohTransport;
+/// use khodpay_bip353::{HumanReadableName, Resolver};
+/// use khodpay_btc_signing::Network;
+///
+/// # async fn run() -> khodpay_bip353::Result<()> {
+/// let resolver = Resolver::new(
+///     DohTransport::new("https://cloudflare-dns.com/dns-query"),
+///     Network::Bitcoin,
+/// );
+/// let name: HumanReadableName = "₿matt@mattcorallo.com".parse()?;
+/// let instructions = resolver.resolve(&name).await?;
+/// if let Some(address) = &instructions.silent_payment {
+///     println!("pay {} via silent payment {}", name, address);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resolver<T> {
+    transport: T,
+    network: Network,
+    anchors: Vec<TrustAnchor>,
+}
+
+impl<T: DnsTransport> Resolver<T> {
+    /// Creates a resolver for payment instructions on `network`.
+    pub fn new(transport: T, network: Network) -> Self {
+        Self {
+            transport,
+            network,
+            anchors: TrustAnchor::root(),
+        }
+    }
+
+    /// Validates against `anchors` instead of IANA's root keys.
+    pub fn with_trust_anchors(mut self, anchors: Vec<TrustAnchor>) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    /// Returns the network instructions are checked against.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Resolves `name` and verifies the answer at the current time.
+    ///
+    /// # Errors
+    ///
+    /// As [`Resolver::resolve_at`].
+    pub async fn resolve(&self, name: &HumanReadableName) -> Result<PaymentInstructions> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.resolve_at(name, now).await
+    }
+
+    /// Resolves `name` and verifies the answer at `now` (seconds since the Unix
+    /// epoch).
+    ///
+    /// # Errors
+    ///
+    /// Returns transport and server errors from the lookups, then any error from
+    /// [`Proof::verify_uri`] and [`PaymentInstructions::parse`].
+    pub async fn resolve_at(
+        &self,
+        name: &HumanReadableName,
+        now: u64,
+    ) -> Result<PaymentInstructions> {
+        let proof = self.prove(name).await?;
+        let uri = proof.verify_uri(name, &self.anchors, now)?;
+        PaymentInstructions::parse(&uri, self.network)
+    }
+
+    /// Looks up `name` and returns a [`Proof`] of the answer without verifying
+    /// it, to hand to a device that verifies it offline.
+    ///
+    /// # Errors
+    ///
+    /// Returns transport and server errors from the lookups,
+    /// [`Error::NotFound`] if the name has no records, and
+    /// [`Error::InvalidMessage`] for malformed responses or a chain through too
+    /// many zones.
+    pub async fn prove(&self, name: &HumanReadableName) -> Result<Proof> {
+        let mut records = Vec::new();
+
+        // The TXT record, through any CNAME records
+        let mut owner = name.to_dns()?;
+        for _ in 0..=MAX_CNAME_HOPS {
+            let answers = self.query(&owner, TYPE_TXT).await?;
+            let target = follow_cnames(&answers, &owner);
+            records.extend(answers);
+            let answered = records
+                .iter()
+                .any(|r| r.name == target && r.rtype == TYPE_TXT);
+            if answered || target == owner {
+                break;
+            }
+            owner = target;
+        }
+
+        if records.is_empty() {
+            return Err(Error::NotFound(name.to_string()));
+        }
+
+        // The keys of every zone that signed something, up to the root
+        let mut visited = HashSet::new();
+        let mut pending = signers(&records);
+        while let Some(zone) = pending.pop() {
+            if !visited.insert(zone.clone()) {
+                continue;
+            }
+            if visited.len() > MAX_ZONES {
+                return Err(Error::InvalidMessage(format!(
+                    "chain of trust passes through more than {} zones",
+                    MAX_ZONES
+                )));
+            }
+            let mut answers = self.query(&zone, TYPE_DNSKEY).await?;
+            if zone != Name::root() {
+                answers.extend(self.query(&zone, TYPE_DS).await?);
+            }
+            pending.extend(signers(&answers));
+            records.extend(answers);
+        }
+        Ok(Proof::from_records(records))
+    }
+
+    /// Queries `rtype` records at `name`, keeping only Internet-class answers.
+    async fn query(&self, name: &Name, rtype: u16) -> Result<Vec<Record>> {
+        let response = self
+            .transport
+            .exchange(&wire::build_query(name, rtype))
+            .await?;
+        let answers = wire::parse_response(&response)?;
+        Ok(answers
+            .into_iter()
+            .filter(|r| r.class == CLASS_IN)
+            .collect())
+    }
+}
+
+/// Follows the `CNAME` records in `answers` from `owner`, returning the final
+/// name.
+fn follow_cnames(answers: &[Record], owner: &Name) -> Name {
+    let mut name = owner.clone();
+    for _ in 0..MAX_CNAME_HOPS {
+        let target = answers
+            .iter()
+            .find(|r| r.name == name && r.rtype == TYPE_CNAME)
+            .and_then(|r| Name::parse(&r.rdata, &mut 0).ok());
+        match target {
+            Some(target) => name = target,
+            None => break,
+        }
+    }
+    name
+}
+
+/// Returns the signer names of the `RRSIG` records in `records`.
+fn signers(records: &[Record]) -> Vec<Name> {
+    records
+        .iter()
+        .filter(|r| r.rtype == TYPE_RRSIG)
+        .filter_map(|r| Name::parse(&r.rdata, &mut 18).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::dnssec::testing::*;
+
+    /// Answers queries from a fixed set of records, as a recursive resolver
+    /// holding the test tree would.
+    struct Fixture {
+        records: Vec<Record>,
+        queries: Mutex<Vec<(Name, u16)>>,
+    }
+
+    impl Fixture {
+        fn new(records: Vec<Record>) -> Self {
+            Self {
+                records,
+                queries: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DnsTransport for Fixture {
+        async fn exchange(&self, query: &[u8]) -> Result<Vec<u8>> {
+            let mut pos = 12;
+            let name = Name::parse(query, &mut pos).unwrap();
+            let rtype = wire::read_u16(query, &mut pos).unwrap();
+            self.queries.lock().unwrap().push((name.clone(), rtype));
+
+            // Aliases answer queries of every type
+            let answers_query = |t: u16| t == rtype || t == TYPE_CNAME;
+            let covers = |r: &Record| match r.rtype {
+                TYPE_RRSIG => answers_query(u16::from_be_bytes([r.rdata[0], r.rdata[1]])),
+                other => answers_query(other),
+            };
+            let answers: Vec<&Record> = self
+                .records
+                .iter()
+                .filter(|r| r.name == name && covers(r))
+                .collect();
+
+            let mut response = vec![0, 0, 0x81, 0x80, 0, 0];
+            response.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+            response.extend_from_slice(&[0, 0, 0, 0]);
+            for record in answers {
+                record.write(&mut response);
+            }
+            Ok(response)
+        }
+    }
+
+    fn name() -> HumanReadableName {
+        "₿alice@example.com".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let tree = Tree::new();
+        let records = tree.with_txt(&name().dns_name(), "bitcoin:?lno=lno1qsgq");
+        let resolver = Resolver::new(Fixture::new(records), Network::Bitcoin)
+            .with_trust_anchors(vec![tree.root.anchor()]);
+
+        let instructions = resolver.resolve_at(&name(), NOW).await.unwrap();
+        assert_eq!(instructions.offer.as_deref(), Some("lno1qsgq"));
+
+        // TXT, then DNSKEY and DS for each zone below the root, then the root keys
+        let queries = resolver.transport().queries.lock().unwrap();
+        assert_eq!(queries.len(), 6);
+        assert_eq!(queries[0].1, TYPE_TXT);
+        assert!(queries.contains(&(Name::root(), TYPE_DNSKEY)));
+        assert!(!queries.contains(&(Name::root(), TYPE_DS)));
+    }
+
+    #[tokio::test]
+    async fn test_prove_and_verify_offline() {
+        let tree = Tree::new();
+        let records = tree.with_txt(&name().dns_name(), "bitcoin:?lno=lno1qsgq");
+        let resolver = Resolver::new(Fixture::new(records), Network::Bitcoin);
+
+        let bytes = resolver.prove(&name()).await.unwrap().into_bytes();
+        let proof = Proof::from_bytes(bytes).unwrap();
+        let uri = proof
+            .verify_uri(&name(), &[tree.root.anchor()], NOW)
+            .unwrap();
+        assert_eq!(uri, "bitcoin:?lno=lno1qsgq");
+
+        // The default anchors are IANA's, which did not sign the test tree
+        assert!(matches!(
+            resolver.resolve_at(&name(), NOW).await,
+            Err(Error::Dnssec(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cname_across_queries() {
+        let tree = Tree::new();
+        let target = Name::from_ascii("pay.example.com").unwrap();
+        let alias = Name::from_ascii(&name().dns_name()).unwrap();
+        let mut records = tree.with_txt("pay.example.com", "bitcoin:?lno=lno1qsgq");
+        records.extend(
+            tree.example
+                .signed(&alias, TYPE_CNAME, vec![target.as_bytes().to_vec()]),
+        );
+        let resolver = Resolver::new(Fixture::new(records), Network::Bitcoin)
+            .with_trust_anchors(vec![tree.root.anchor()]);
+
+        let instructions = resolver.resolve_at(&name(), NOW).await.unwrap();
+        assert_eq!(instructions.offer.as_deref(), Some("lno1qsgq"));
+        let queries = resolver.transport().queries.lock().unwrap();
+        assert!(queries.contains(&(target, TYPE_TXT)));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_name() {
+        let tree = Tree::new();
+        let resolver = Resolver::new(Fixture::new(tree.chain()), Network::Bitcoin)
+            .with_trust_anchors(vec![tree.root.anchor()]);
+        assert!(matches!(
+            resolver.resolve_at(&name(), NOW).await,
+            Err(Error::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+//! DNS wire format: names, resource records, queries and responses.
+//!
+//! Only what BIP-353 resolution needs is here: queries for one record type with
+//! DNSSEC records requested, and the answer section of responses. Names are kept
+//! in canonical form (uncompressed, lowercase) so records can be compared and
+//! hashed as DNSSEC requires.
+
+use std::fmt;
+
+use crate::{Error, Result};
+
+/// `CNAME` record type.
+pub(crate) const TYPE_CNAME: u16 = 5;
+/// `TXT` record type.
+pub(crate) const TYPE_TXT: u16 = 16;
+/// EDNS(0) `OPT` pseudo-record type.
+pub(crate) const TYPE_OPT: u16 = 41;
+/// `DS` record type.
+pub(crate) const TYPE_DS: u16 = 43;
+/// `RRSIG` record type.
+pub(crate) const TYPE_RRSIG: u16 = 46;
+/// `DNSKEY` record type.
+pub(crate) const TYPE_DNSKEY: u16 = 48;
+
+/// The Internet class.
+pub(crate) const CLASS_IN: u16 = 1;
+
+/// Maximum length of a name in wire format.
+const MAX_NAME_LEN: usize = 255;
+/// Maximum length of one label.
+const MAX_LABEL_LEN: usize = 63;
+/// Largest UDP payload advertised in queries.
+const EDNS_PAYLOAD_SIZE: u16 = 4096;
+/// The EDNS(0) "DNSSEC OK" flag.
+const EDNS_DO: u32 = 0x8000;
+
+/// Header flag: message is a response.
+const FLAG_QR: u16 = 0x8000;
+/// Header flag: message was truncated.
+const FLAG_TC: u16 = 0x0200;
+/// Header flag: recursion desired.
+const FLAG_RD: u16 = 0x0100;
+
+/// Response code for a name that does not exist.
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// A domain name in canonical wire format: uncompressed, lowercase, ending with
+/// the root label.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Name(Vec<u8>);
+
+impl Name {
+    /// The root name, `.`.
+    pub(crate) fn root() -> Self {
+        Self(vec![0])
+    }
+
+    /// Parses a dotted name such as `example.com.`; the final dot is optional.
+    pub(crate) fn from_ascii(name: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidName(format!("{:?}: {}", name, reason));
+        let trimmed = name.strip_suffix('.').unwrap_or(name);
+        let mut wire = Vec::with_capacity(trimmed.len() + 2);
+        if !trimmed.is_empty() {
+            for label in trimmed.split('.') {
+                if label.is_empty() {
+                    return Err(invalid("empty label"));
+                }
+                if label.len() > MAX_LABEL_LEN {
+                    return Err(invalid("label longer than 63 bytes"));
+                }
+                wire.push(label.len() as u8);
+                wire.extend(label.bytes().map(|b| b.to_ascii_lowercase()));
+            }
+        }
+        wire.push(0);
+        if wire.len() > MAX_NAME_LEN {
+            return Err(invalid("longer than 255 bytes"));
+        }
+        Ok(Self(wire))
+    }
+
+    /// Reads a possibly compressed name at `*pos` in `message`, moving `*pos`
+    /// past it.
+    pub(crate) fn parse(message: &[u8], pos: &mut usize) -> Result<Self> {
+        let truncated = || Error::InvalidMessage("truncated name".to_string());
+        let mut wire = Vec::new();
+        let mut cursor = *pos;
+        let mut end = None;
+        loop {
+            let len = *message.get(cursor).ok_or_else(truncated)? as usize;
+            match len {
+                0 => {
+                    wire.push(0);
+                    *pos = end.unwrap_or(cursor + 1);
+                    break;
+                }
+                1..=MAX_LABEL_LEN => {
+                    let label = message
+                        .get(cursor + 1..cursor + 1 + len)
+                        .ok_or_else(truncated)?;
+                    wire.push(len as u8);
+                    wire.extend(label.iter().map(u8::to_ascii_lowercase));
+                    cursor += 1 + len;
+                }
+                _ if len & 0xc0 == 0xc0 => {
+                    let low = *message.get(cursor + 1).ok_or_else(truncated)? as usize;
+                    let target = ((len & 0x3f) << 8) | low;
+                    // Pointers must go backwards, which also rules out loops
+                    if target >= cursor {
+                        return Err(Error::InvalidMessage(
+                            "forward compression pointer".to_string(),
+                        ));
+                    }
+                    end.get_or_insert(cursor + 2);
+                    cursor = target;
+                }
+                _ => {
+                    return Err(Error::InvalidMessage(format!(
+                        "unknown label type 0x{:02x}",
+                        len
+                    )))
+                }
+            }
+            if wire.len() >= MAX_NAME_LEN {
+                return Err(Error::InvalidMessage(
+                    "name longer than 255 bytes".to_string(),
+                ));
+            }
+        }
+        Ok(Self(wire))
+    }
+
+    /// Returns the name in wire format.
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the labels from the leftmost one, without the root label.
+    pub(crate) fn labels(&self) -> Vec<&[u8]> {
+        let mut labels = Vec::new();
+        let mut pos = 0;
+        while self.0[pos] != 0 {
+            let len = self.0[pos] as usize;
+            labels.push(&self.0[pos + 1..pos + 1 + len]);
+            pos += 1 + len;
+        }
+        labels
+    }
+
+    /// Returns the number of labels, not counting the root label.
+    pub(crate) fn label_count(&self) -> usize {
+        self.labels().len()
+    }
+
+    /// Returns `true` if the name is `zone` or below it.
+    pub(crate) fn is_within(&self, zone: &Name) -> bool {
+        let labels = self.labels();
+        let zone_labels = zone.labels();
+        labels.len() >= zone_labels.len() && labels.ends_with(&zone_labels)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels = self.labels();
+        if labels.is_empty() {
+            return f.write_str(".");
+        }
+        for label in labels {
+            for &b in label {
+                if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'*' {
+                    write!(f, "{}", b as char)?;
+                } else {
+                    write!(f, "\\{:03}", b)?;
+                }
+            }
+            f.write_str(".")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Name({})", self)
+    }
+}
+
+/// A resource record with its data in canonical form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Record {
+    /// Owner name.
+    pub name: Name,
+    /// Record type.
+    pub rtype: u16,
+    /// Record class.
+    pub class: u16,
+    /// Time to live in seconds.
+    pub ttl: u32,
+    /// Record data. Names inside `CNAME` and `RRSIG` data are decompressed and
+    /// lowercased.
+    pub rdata: Vec<u8>,
+}
+
+impl Record {
+    /// Reads a record at `*pos` in `message`, moving `*pos` past it.
+    pub(crate) fn parse(message: &[u8], pos: &mut usize) -> Result<Self> {
+        let name = Name::parse(message, pos)?;
+        let rtype = read_u16(message, pos)?;
+        let class = read_u16(message, pos)?;
+        let ttl = read_u32(message, pos)?;
+        let len = read_u16(message, pos)? as usize;
+        let end = *pos + len;
+        let raw = message
+            .get(*pos..end)
+            .ok_or_else(|| Error::InvalidMessage("truncated record data".to_string()))?;
+
+        let rdata = match rtype {
+            TYPE_CNAME => {
+                let mut inner = *pos;
+                let target = Name::parse(message, &mut inner)?;
+                if inner != end {
+                    return Err(Error::InvalidMessage("malformed CNAME".to_string()));
+                }
+                target.0
+            }
+            TYPE_RRSIG => {
+                const FIXED: usize = 18;
+                if raw.len() < FIXED {
+                    return Err(Error::InvalidMessage("malformed RRSIG".to_string()));
+                }
+                let mut inner = *pos + FIXED;
+                let signer = Name::parse(message, &mut inner)?;
+                if inner > end {
+                    return Err(Error::InvalidMessage("malformed RRSIG".to_string()));
+                }
+                let mut rdata = raw[..FIXED].to_vec();
+                rdata.extend_from_slice(signer.as_bytes());
+                rdata.extend_from_slice(&message[inner..end]);
+                rdata
+            }
+            _ => raw.to_vec(),
+        };
+        *pos = end;
+        Ok(Self {
+            name,
+            rtype,
+            class,
+            ttl,
+            rdata,
+        })
+    }
+
+    /// Appends the record in uncompressed wire format.
+    pub(crate) fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.name.as_bytes());
+        out.extend_from_slice(&self.rtype.to_be_bytes());
+        out.extend_from_slice(&self.class.to_be_bytes());
+        out.extend_from_slice(&self.ttl.to_be_bytes());
+        out.extend_from_slice(&(self.rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.rdata);
+    }
+}
+
+/// Reads a run of uncompressed records, as in an RFC 9102 proof.
+pub(crate) fn parse_records(bytes: &[u8]) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        records.push(Record::parse(bytes, &mut pos)?);
+    }
+    Ok(records)
+}
+
+/// Builds a recursive query for `rtype` records at `name`, with DNSSEC records
+/// requested.
+///
+/// The message ID is 0, as DNS-over-HTTPS recommends for cacheable queries.
+pub(crate) fn build_query(name: &Name, rtype: u16) -> Vec<u8> {
+    let mut query = Vec::with_capacity(12 + name.as_bytes().len() + 15);
+    query.extend_from_slice(&0u16.to_be_bytes()); // ID
+    query.extend_from_slice(&FLAG_RD.to_be_bytes());
+    query.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    query.extend_from_slice(&0u16.to_be_bytes()); // ANCOUNT
+    query.extend_from_slice(&0u16.to_be_bytes()); // NSCOUNT
+    query.extend_from_slice(&1u16.to_be_bytes()); // ARCOUNT
+    query.extend_from_slice(name.as_bytes());
+    query.extend_from_slice(&rtype.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    // EDNS(0) OPT record with the DO flag
+    query.push(0);
+    query.extend_from_slice(&TYPE_OPT.to_be_bytes());
+    query.extend_from_slice(&EDNS_PAYLOAD_SIZE.to_be_bytes());
+    query.extend_from_slice(&EDNS_DO.to_be_bytes());
+    query.extend_from_slice(&0u16.to_be_bytes());
+    query
+}
+
+/// Reads the answer section of a response to a query built by [`build_query`].
+///
+/// A name that does not exist gives an empty answer.
+pub(crate) fn parse_response(message: &[u8]) -> Result<Vec<Record>> {
+    let mut pos = 0;
+    let _id = read_u16(message, &mut pos)?;
+    let flags = read_u16(message, &mut pos)?;
+    let qdcount = read_u16(message, &mut pos)?;
+    let ancount = read_u16(message, &mut pos)?;
+    pos += 4; // NSCOUNT and ARCOUNT
+
+    if flags & FLAG_QR == 0 {
+        return Err(Error::InvalidMessage("not a response".to_string()));
+    }
+    if flags & FLAG_TC != 0 {
+        return Err(Error::Transport("response was truncated".to_string()));
+    }
+    match flags & 0x000f {
+        0 => {}
+        RCODE_NXDOMAIN => return Ok(Vec::new()),
+        rcode => return Err(Error::Server(rcode_name(rcode))),
+    }
+
+    for _ in 0..qdcount {
+        Name::parse(message, &mut pos)?;
+        pos += 4; // QTYPE and QCLASS
+    }
+    (0..ancount)
+        .map(|_| Record::parse(message, &mut pos))
+        .collect()
+}
+
+/// Returns the mnemonic of a response code.
+fn rcode_name(rcode: u16) -> String {
+    match rcode {
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        other => format!("RCODE {}", other),
+    }
+}
+
+/// Reads a big-endian `u16` at `*pos`, moving `*pos` past it.
+pub(crate) fn read_u16(bytes: &[u8], pos: &mut usize) -> Result<u16> {
+    let value = bytes
+        .get(*pos..*pos + 2)
+        .ok_or_else(|| Error::InvalidMessage("unexpected end of data".to_string()))?;
+    *pos += 2;
+    Ok(u16::from_be_bytes([value[0], value[1]]))
+}
+
+/// Reads a big-endian `u32` at `*pos`, moving `*pos` past it.
+pub(crate) fn read_u32(bytes: &[u8], pos: &mut usize) -> Result<u32> {
+    let value = bytes
+        .get(*pos..*pos + 4)
+        .ok_or_else(|| Error::InvalidMessage("unexpected end of data".to_string()))?;
+    *pos += 4;
+    Ok(u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        let name = Name::from_ascii("Alice.User._bitcoin-payment.Example.COM.").unwrap();
+        assert_eq!(name.to_string(), "alice.user._bitcoin-payment.example.com.");
+        assert_eq!(
+            name,
+            Name::from_ascii("alice.user._bitcoin-payment.example.com").unwrap()
+        );
+        assert_eq!(name.label_count(), 5);
+
+        let zone = Name::from_ascii("example.com").unwrap();
+        assert!(name.is_within(&zone));
+        assert!(name.is_within(&Name::root()));
+        assert!(!zone.is_within(&name));
+        assert!(!Name::from_ascii("badexample.com").unwrap().is_within(&zone));
+        assert_eq!(zone.to_string(), "example.com.");
+        assert_eq!(Name::root().to_string(), ".");
+
+        assert!(Name::from_ascii("a..b").is_err());
+        assert!(Name::from_ascii(&"a".repeat(64)).is_err());
+        assert!(Name::from_ascii(&vec!["a"; 128].join(".")).is_err());
+    }
+
+    #[test]
+    fn test_compressed_name() {
+        // "example.com" at 0, then "www" + pointer to 0
+        let message = b"\x07example\x03com\x00\x03www\xc0\x00";
+        let mut pos = 13;
+        let name = Name::parse(message, &mut pos).unwrap();
+        assert_eq!(name.to_string(), "www.example.com.");
+        assert_eq!(pos, message.len());
+
+        // A pointer to itself
+        let mut pos = 0;
+        assert!(Name::parse(b"\xc0\x00", &mut pos).is_err());
+    }
+
+    #[test]
+    fn test_query_and_response() {
+        let name = Name::from_ascii("example.com").unwrap();
+        let query = build_query(&name, TYPE_TXT);
+        assert_eq!(&query[2..4], &FLAG_RD.to_be_bytes());
+        assert_eq!(query.len(), 12 + 13 + 4 + 11);
+
+        // Turn the query into a response with one compressed TXT answer
+        let mut response = query[..12 + 13 + 4].to_vec();
+        response[2] |= 0x80;
+        response[7] = 1; // ANCOUNT
+        response[11] = 0; // ARCOUNT
+        response.extend_from_slice(b"\xc0\x0c\x00\x10\x00\x01\x00\x00\x0e\x10\x00\x06\x05hello");
+        let answers = parse_response(&response).unwrap();
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0].name, name);
+        assert_eq!(answers[0].rtype, TYPE_TXT);
+        assert_eq!(answers[0].ttl, 3600);
+        assert_eq!(answers[0].rdata, b"\x05hello");
+
+        // Uncompressed round trip
+        let mut bytes = Vec::new();
+        answers[0].write(&mut bytes);
+        assert_eq!(parse_records(&bytes).unwrap(), answers);
+
+        response[3] |= 2; // SERVFAIL
+        assert!(matches!(parse_response(&response), Err(Error::Server(e)) if e == "SERVFAIL"));
+        response[3] = (response[3] & 0xf0) | 3; // NXDOMAIN
+        assert!(parse_response(&response).unwrap().is_empty());
+    }
+}
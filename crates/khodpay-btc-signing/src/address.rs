@@ -0,0 +1,477 @@
+//! Bitcoin addresses.
+//!
+//! Parses and formats base58 (P2PKH, P2SH) and bech32 / bech32m (SegWit v0, taproot)
+//! addresses, so a transaction can pay any standard address while this crate itself
+//! spends P2WPKH outputs.
+
+use std::fmt;
+use std::str::FromStr;
+
+use bech32::{segwit, Fe32, Hrp};
+use secp256k1::{PublicKey, Scalar, SECP256K1};
+
+use crate::hash::{hash160, tagged_hash};
+use crate::{Error, Network, Result, Script};
+
+/// What an address commits to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Payload {
+    /// Legacy pay-to-public-key-hash.
+    PubkeyHash([u8; 20]),
+    /// Pay-to-script-hash, including nested SegWit.
+    ScriptHash([u8; 20]),
+    /// Native SegWit output of any witness version.
+    WitnessProgram {
+        /// Witness version (0 for P2WPKH / P2WSH, 1 for taproot).
+        version: u8,
+        /// Witness program (2–40 bytes).
+        program: Vec<u8>,
+    },
+}
+
+/// A Bitcoin address for a specific network.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::{Address, Network};
+///
+/// let address: Address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".parse().unwrap();
+/// assert_eq!(address.network(), Network::Bitcoin);
+/// assert!(address.script_pubkey().is_p2wpkh());
+/// assert_eq!(address.to_string(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Address {
+    network: Network,
+    payload: Payload,
+}
+
+impl Address {
+    /// Creates the legacy P2PKH address of a compressed public key.
+    pub fn p2pkh(public_key: &[u8; 33], network: Network) -> Self {
+        Self {
+            network,
+            payload: Payload::PubkeyHash(hash160(public_key)),
+        }
+    }
+
+    /// Creates the nested SegWit (P2SH-P2WPKH) address of a compressed public key.
+    pub fn p2sh_p2wpkh(public_key: &[u8; 33], network: Network) -> Self {
+        let redeem_script = Script::p2wpkh(&hash160(public_key));
+        Self {
+            network,
+            payload: Payload::ScriptHash(hash160(redeem_script.as_bytes())),
+        }
+    }
+
+    /// Creates the P2WPKH address of a compressed public key.
+    pub fn p2wpkh(public_key: &[u8; 33], network: Network) -> Self {
+        Self {
+            network,
+            payload: Payload::WitnessProgram {
+                version: 0,
+                program: hash160(public_key).to_vec(),
+            },
+        }
+    }
+
+    /// Creates the BIP-86 taproot address of a public key: a key-path-only output
+    /// whose key is the x-only public key tweaked with no script tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if `public_key` is not a valid point.
+    pub fn p2tr(public_key: &[u8; 33], network: Network) -> Result<Self> {
+        let internal_key = PublicKey::from_slice(public_key)
+            .map_err(|e| Error::InvalidAddress(format!("invalid public key: {}", e)))?
+            .x_only_public_key()
+            .0;
+        let tweak = Scalar::from_be_bytes(tagged_hash("TapTweak", &internal_key.serialize()))
+            .map_err(|_| Error::InvalidAddress("taproot tweak out of range".to_string()))?;
+        let (output_key, _) = internal_key
+            .add_tweak(SECP256K1, &tweak)
+            .map_err(|e| Error::InvalidAddress(format!("invalid taproot tweak: {}", e)))?;
+        Ok(Self {
+            network,
+            payload: Payload::WitnessProgram {
+                version: 1,
+                program: output_key.serialize().to_vec(),
+            },
+        })
+    }
+
+    /// Creates an address from its payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if a witness version or program length is
+    /// out of range.
+    pub fn new(payload: Payload, network: Network) -> Result<Self> {
+        if let Payload::WitnessProgram { version, program } = &payload {
+            let valid_length = match version {
+                0 => program.len() == 20 || program.len() == 32,
+                1..=16 => (2..=40).contains(&program.len()),
+                _ => false,
+            };
+            if !valid_length {
+                return Err(Error::InvalidAddress(format!(
+                    "invalid witness v{} program of {} bytes",
+                    version,
+                    program.len()
+                )));
+            }
+        }
+        Ok(Self { network, payload })
+    }
+
+    /// Returns the address an output script pays to, or `None` for scripts without
+    /// one, such as `OP_RETURN` data or bare multisig.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_btc_signing::{Address, Network, Script};
+    ///
+    /// let address = Address::from_script(&Script::p2wpkh(&[0x11; 20]), Network::Bitcoin).unwrap();
+    /// assert_eq!(address.script_pubkey(), Script::p2wpkh(&[0x11; 20]));
+    /// assert!(Address::from_script(&Script::new(vec![0x6a]), Network::Bitcoin).is_none());
+    /// ```
+    pub fn from_script(script_pubkey: &Script, network: Network) -> Option<Self> {
+        let payload = if let Some(hash) = script_pubkey.p2pkh_hash() {
+            Payload::PubkeyHash(hash)
+        } else if let Some(hash) = script_pubkey.p2sh_hash() {
+            Payload::ScriptHash(hash)
+        } else if script_pubkey.is_witness_program() {
+            let bytes = script_pubkey.as_bytes();
+            Payload::WitnessProgram {
+                // OP_0, or OP_1 (0x51) to OP_16
+                version: bytes[0].saturating_sub(0x50),
+                program: bytes[2..].to_vec(),
+            }
+        } else {
+            return None;
+        };
+        Self::new(payload, network).ok()
+    }
+
+    /// Parses an address and checks that it belongs to `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if the address is malformed or for another
+    /// network.
+    pub fn parse(s: &str, network: Network) -> Result<Self> {
+        let address: Address = s.parse()?;
+        if !network.accepts(address.network) {
+            return Err(Error::InvalidAddress(format!(
+                "{} is not a {} address",
+                s, network
+            )));
+        }
+        Ok(Self { network, ..address })
+    }
+
+    /// Returns the network the address was created or parsed for.
+    ///
+    /// Testnet and signet share prefixes, so parsed `tb1…` and `m…`/`n…`/`2…`
+    /// addresses report [`Network::Testnet`].
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns what the address commits to.
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    /// Returns `true` if the address can be used on `network`.
+    pub fn is_valid_for(&self, network: Network) -> bool {
+        network.accepts(self.network)
+    }
+
+    /// Returns the output script paying to this address.
+    pub fn script_pubkey(&self) -> Script {
+        match &self.payload {
+            Payload::PubkeyHash(hash) => Script::p2pkh(hash),
+            Payload::ScriptHash(hash) => Script::p2sh(hash),
+            Payload::WitnessProgram { version, program } => {
+                Script::witness_program(*version, program)
+            }
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.to_ascii_lowercase();
+        for network in [Network::Bitcoin, Network::Testnet, Network::Regtest] {
+            if lower.starts_with(&format!("{}1", network.hrp())) {
+                return parse_segwit(s, network);
+            }
+        }
+        parse_base58(s)
+    }
+}
+
+fn parse_segwit(s: &str, network: Network) -> Result<Address> {
+    let (_, version, program) =
+        segwit::decode(s).map_err(|e| Error::InvalidAddress(format!("{}: {}", s, e)))?;
+    Address::new(
+        Payload::WitnessProgram {
+            version: version.to_u8(),
+            program,
+        },
+        network,
+    )
+}
+
+fn parse_base58(s: &str) -> Result<Address> {
+    let data = bs58::decode(s)
+        .with_check(None)
+        .into_vec()
+        .map_err(|e| Error::InvalidAddress(format!("{}: {}", s, e)))?;
+    let (prefix, hash) = match data.as_slice() {
+        [prefix, hash @ ..] if hash.len() == 20 => (*prefix, <[u8; 20]>::try_from(hash).unwrap()),
+        _ => {
+            return Err(Error::InvalidAddress(format!(
+                "{}: unexpected payload length",
+                s
+            )))
+        }
+    };
+
+    let (network, payload) = match prefix {
+        0x00 => (Network::Bitcoin, Payload::PubkeyHash(hash)),
+        0x05 => (Network::Bitcoin, Payload::ScriptHash(hash)),
+        0x6f => (Network::Testnet, Payload::PubkeyHash(hash)),
+        0xc4 => (Network::Testnet, Payload::ScriptHash(hash)),
+        _ => {
+            return Err(Error::InvalidAddress(format!(
+                "{}: unknown version byte 0x{:02x}",
+                s, prefix
+            )))
+        }
+    };
+    Ok(Address { network, payload })
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let base58 = |prefix: u8, hash: &[u8; 20]| {
+            let mut data = vec![prefix];
+            data.extend_from_slice(hash);
+            bs58::encode(data).with_check().into_string()
+        };
+        match &self.payload {
+            Payload::PubkeyHash(hash) => f.write_str(&base58(self.network.p2pkh_prefix(), hash)),
+            Payload::ScriptHash(hash) => f.write_str(&base58(self.network.p2sh_prefix(), hash)),
+            Payload::WitnessProgram { version, program } => {
+                let hrp = Hrp::parse(self.network.hrp()).map_err(|_| fmt::Error)?;
+                let version = Fe32::try_from(*version).map_err(|_| fmt::Error)?;
+                let encoded = segwit::encode(hrp, version, program).map_err(|_| fmt::Error)?;
+                f.write_str(&encoded)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: &str) -> Address {
+        let address: Address = s.parse().unwrap();
+        assert_eq!(address.to_string(), s);
+        address
+    }
+
+    // BIP-173 / BIP-350 vectors.
+
+    #[test]
+    fn test_p2wpkh_mainnet() {
+        let address = round_trip("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(
+            address.script_pubkey().to_hex(),
+            "0014751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+    }
+
+    #[test]
+    fn test_p2wsh_testnet() {
+        let address = round_trip("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7");
+        assert_eq!(address.network(), Network::Testnet);
+        assert_eq!(
+            address.script_pubkey().to_hex(),
+            "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"
+        );
+    }
+
+    #[test]
+    fn test_taproot() {
+        let address = round_trip("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0");
+        assert_eq!(
+            address.script_pubkey().to_hex(),
+            "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+    }
+
+    #[test]
+    fn test_uppercase_bech32() {
+        let address: Address = "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            address.to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+
+    #[test]
+    fn test_regtest() {
+        let address = Address::p2wpkh(&[0x02; 33], Network::Regtest);
+        let encoded = address.to_string();
+        assert!(encoded.starts_with("bcrt1q"));
+        assert_eq!(encoded.parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    fn test_base58() {
+        let p2pkh = round_trip("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2");
+        assert!(matches!(p2pkh.payload(), Payload::PubkeyHash(_)));
+        assert_eq!(p2pkh.network(), Network::Bitcoin);
+
+        let p2sh = round_trip("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy");
+        assert!(matches!(p2sh.payload(), Payload::ScriptHash(_)));
+
+        let testnet = round_trip("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn");
+        assert_eq!(testnet.network(), Network::Testnet);
+    }
+
+    #[test]
+    fn test_p2wpkh_from_public_key() {
+        let pubkey: [u8; 33] =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(
+            Address::p2wpkh(&pubkey, Network::Bitcoin).to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+
+    #[test]
+    fn test_single_key_addresses() {
+        // BIP-44 and BIP-49 test vectors (first receive address of "abandon … about").
+        let p2pkh_key: [u8; 33] =
+            hex::decode("03aaeb52dd7494c361049de67cc680e83ebcbbbdbeb13637d92cd845f70308af5e")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(
+            Address::p2pkh(&p2pkh_key, Network::Bitcoin).to_string(),
+            "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+        );
+
+        let p2sh_key: [u8; 33] =
+            hex::decode("03a1af804ac108a8a51782198c2d034b28bf90c8803f5a53f76276fa69a4eae77f")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(
+            Address::p2sh_p2wpkh(&p2sh_key, Network::Testnet).to_string(),
+            "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2"
+        );
+
+        // BIP-86 test vector: the parity of the internal key does not matter.
+        for prefix in ["02", "03"] {
+            let p2tr_key: [u8; 33] = hex::decode(format!(
+                "{}cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+                prefix
+            ))
+            .unwrap()
+            .try_into()
+            .unwrap();
+            assert_eq!(
+                Address::p2tr(&p2tr_key, Network::Bitcoin)
+                    .unwrap()
+                    .to_string(),
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+            );
+        }
+        assert!(Address::p2tr(&[0x04; 33], Network::Bitcoin).is_err());
+    }
+
+    #[test]
+    fn test_parse_checks_network() {
+        let mainnet = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        assert!(Address::parse(mainnet, Network::Bitcoin).is_ok());
+        assert!(matches!(
+            Address::parse(mainnet, Network::Testnet),
+            Err(Error::InvalidAddress(_))
+        ));
+
+        let signet = Address::parse(
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+            Network::Signet,
+        )
+        .unwrap();
+        assert_eq!(signet.network(), Network::Signet);
+        assert!(signet.is_valid_for(Network::Testnet));
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        for invalid in [
+            // Bad checksum.
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+            // v0 program with bech32m checksum.
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+            // Bad base58 checksum.
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3",
+            "",
+        ] {
+            assert!(
+                invalid.parse::<Address>().is_err(),
+                "{} should not parse",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_new_validates_program_length() {
+        let payload = Payload::WitnessProgram {
+            version: 0,
+            program: vec![0; 21],
+        };
+        assert!(Address::new(payload, Network::Bitcoin).is_err());
+    }
+
+    #[test]
+    fn test_from_script() {
+        for encoded in [
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        ] {
+            let address: Address = encoded.parse().unwrap();
+            assert_eq!(
+                Address::from_script(&address.script_pubkey(), Network::Bitcoin),
+                Some(address)
+            );
+        }
+        // OP_RETURN, bare OP_TRUE and an empty script have no address.
+        for script in [vec![0x6a, 0x01, 0xff], vec![0x51], vec![]] {
+            assert_eq!(
+                Address::from_script(&Script::new(script), Network::Bitcoin),
+                None
+            );
+        }
+    }
+}
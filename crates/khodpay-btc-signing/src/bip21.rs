@@ -0,0 +1,410 @@
+//! BIP-21 payment request URIs.
+//!
+//! [BIP-21](https://github.com/bitcoin/bips/blob/master/bip-0021.mediawiki) URIs are
+//! what Bitcoin wallets put in payment QR codes:
+//!
+//! ```text
+//! bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4?amount=0.015&label=Coffee%20Shop
+//! ```
+//!
+//! [`PaymentUri`] parses and generates them. Amounts are decimal BTC in the URI and
+//! satoshis in the API. Unknown `req-` parameters make parsing fail, as BIP-21
+//! requires; other unknown parameters are kept.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::bip21::PaymentUri;
+//! use khodpay_btc_signing::{Address, Network};
+//!
+//! let shop = Address::parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", Network::Bitcoin).unwrap();
+//!
+//! let uri = PaymentUri::new(shop.clone())
+//!     .with_amount(1_500_000)
+//!     .with_label("Coffee Shop");
+//! let text = uri.to_string();
+//! assert_eq!(
+//!     text,
+//!     "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4?amount=0.015&label=Coffee%20Shop"
+//! );
+//!
+//! let parsed: PaymentUri = text.parse().unwrap();
+//! assert_eq!(parsed.address, shop);
+//! assert_eq!(parsed.amount, Some(1_500_000));
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use khodpay_amount::CoinAmount;
+
+use crate::{Address, Error, Network, Result, MAX_MONEY};
+
+/// URI scheme used by BIP-21.
+pub const SCHEME: &str = "bitcoin";
+
+/// Satoshis per bitcoin.
+const SATS_PER_BTC: u64 = 100_000_000;
+
+/// A BIP-21 payment request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUri {
+    /// Address to pay.
+    pub address: Address,
+    /// Requested amount in satoshis, if specified.
+    pub amount: Option<u64>,
+    /// Label for the recipient, percent-decoded.
+    pub label: Option<String>,
+    /// Message describing the payment, percent-decoded.
+    pub message: Option<String>,
+    /// Other query parameters in URI order, percent-decoded.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl PaymentUri {
+    /// Creates a payment request for `address` with no amount.
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            amount: None,
+            label: None,
+            message: None,
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Sets the requested amount in satoshis.
+    pub fn with_amount(mut self, sats: u64) -> Self {
+        self.amount = Some(sats);
+        self
+    }
+
+    /// Returns the requested amount as bitcoin with its 8 decimals, if specified.
+    pub fn coin_amount(&self) -> Option<CoinAmount> {
+        self.amount.map(CoinAmount::from_sats)
+    }
+
+    /// Sets the recipient label.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the payment message.
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Appends an extra query parameter.
+    pub fn with_parameter(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parameters.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the first extra parameter named `key`.
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses a URI and checks that its address belongs to `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPaymentUri`] if the URI is malformed, or
+    /// [`Error::InvalidAddress`] if the address is for another network.
+    pub fn parse(s: &str, network: Network) -> Result<Self> {
+        let uri: PaymentUri = s.parse()?;
+        let address = Address::parse(&uri.address.to_string(), network)?;
+        Ok(Self { address, ..uri })
+    }
+}
+
+/// Parses a decimal BTC amount such as `0.015` or `20.3` into satoshis.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidPaymentUri`] if the amount is malformed, has more than
+/// eight decimal places, or exceeds [`MAX_MONEY`].
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::bip21::parse_btc;
+///
+/// assert_eq!(parse_btc("0.015").unwrap(), 1_500_000);
+/// assert_eq!(parse_btc("21").unwrap(), 2_100_000_000);
+/// assert!(parse_btc("0.000000001").is_err());
+/// ```
+pub fn parse_btc(s: &str) -> Result<u64> {
+    let invalid = || Error::InvalidPaymentUri(format!("invalid amount: {:?}", s));
+
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || !all_digits(whole)
+        || !all_digits(fraction)
+        || fraction.len() > 8
+    {
+        return Err(invalid());
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let fraction: u64 = format!("{:0<8}", fraction).parse().map_err(|_| invalid())?;
+    whole
+        .checked_mul(SATS_PER_BTC)
+        .and_then(|sats| sats.checked_add(fraction))
+        .filter(|&sats| sats <= MAX_MONEY)
+        .ok_or_else(invalid)
+}
+
+/// Formats satoshis as a decimal BTC amount without trailing zeros.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::bip21::format_btc;
+///
+/// assert_eq!(format_btc(1_500_000), "0.015");
+/// assert_eq!(format_btc(100_000_000), "1");
+/// ```
+pub fn format_btc(sats: u64) -> String {
+    CoinAmount::from_sats(sats).to_string()
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", SCHEME, self.address)?;
+        let known = [
+            ("amount", self.amount.map(format_btc)),
+            ("label", self.label.clone()),
+            ("message", self.message.clone()),
+        ];
+        let known = known
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)));
+        for (i, (key, value)) in known.chain(self.parameters.iter().cloned()).enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(
+                f,
+                "{}{}={}",
+                separator,
+                percent_encode(&key),
+                percent_encode(&value)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PaymentUri {
+    type Err = Error;
+
+    /// Parses a `bitcoin:` payment URI.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidPaymentUri(reason);
+
+        let rest = s
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| invalid("missing bitcoin: scheme".to_string()))?;
+        let (address, query) = match rest.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (rest, None),
+        };
+        let address: Address = address
+            .parse()
+            .map_err(|_| invalid(format!("invalid address: {}", address)))?;
+
+        let mut uri = Self::new(address);
+        for pair in query.into_iter().flat_map(|q| q.split('&')) {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("parameter without value: {}", pair)))?;
+            let key = percent_decode(key)?;
+            let value = percent_decode(value)?;
+            let slot = match key.as_str() {
+                "amount" => {
+                    if uri.amount.replace(parse_btc(&value)?).is_some() {
+                        return Err(invalid("duplicate amount".to_string()));
+                    }
+                    continue;
+                }
+                "label" => &mut uri.label,
+                "message" => &mut uri.message,
+                _ if key.starts_with("req-") => {
+                    return Err(invalid(format!("unsupported required parameter: {}", key)))
+                }
+                _ => {
+                    uri.parameters.push((key, value));
+                    continue;
+                }
+            };
+            if slot.replace(value).is_some() {
+                return Err(invalid(format!("duplicate {}", key)));
+            }
+        }
+        Ok(uri)
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+    let invalid = || Error::InvalidPaymentUri(format!("invalid percent-encoding: {}", s));
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3).ok_or_else(invalid)?;
+            out.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    fn shop() -> Address {
+        Address::parse(ADDRESS, Network::Bitcoin).unwrap()
+    }
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(parse_btc("1").unwrap(), 100_000_000);
+        assert_eq!(parse_btc("0.00000001").unwrap(), 1);
+        assert_eq!(parse_btc(".5").unwrap(), 50_000_000);
+        assert_eq!(parse_btc("20.3").unwrap(), 2_030_000_000);
+        assert_eq!(parse_btc("21000000").unwrap(), MAX_MONEY);
+        for s in [
+            "",
+            ".",
+            "-1",
+            "1e3",
+            "1,5",
+            "0.123456789",
+            "21000000.00000001",
+        ] {
+            assert!(parse_btc(s).is_err(), "{:?} should be rejected", s);
+        }
+
+        assert_eq!(format_btc(0), "0");
+        assert_eq!(format_btc(1), "0.00000001");
+        assert_eq!(format_btc(2_030_000_000), "20.3");
+        for sats in [0, 1, 1_500_000, 123_456_789, MAX_MONEY] {
+            assert_eq!(parse_btc(&format_btc(sats)).unwrap(), sats);
+        }
+    }
+
+    #[test]
+    fn test_parse_bip21_examples() {
+        let uri: PaymentUri = format!("bitcoin:{}?label=Luke-Jr", ADDRESS)
+            .parse()
+            .unwrap();
+        assert_eq!(uri.address, shop());
+        assert_eq!(uri.amount, None);
+        assert_eq!(uri.label.as_deref(), Some("Luke-Jr"));
+
+        let uri: PaymentUri = format!(
+            "bitcoin:{}?amount=50&label=Luke-Jr&message=Donation%20for%20project%20xyz",
+            ADDRESS
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(uri.amount, Some(50 * SATS_PER_BTC));
+        assert_eq!(uri.coin_amount().unwrap().to_string(), "50");
+        assert_eq!(uri.message.as_deref(), Some("Donation for project xyz"));
+
+        let uri: PaymentUri = format!("bitcoin:{}?somethingyoudontunderstand=50", ADDRESS)
+            .parse()
+            .unwrap();
+        assert_eq!(uri.parameter("somethingyoudontunderstand"), Some("50"));
+    }
+
+    #[test]
+    fn test_parse_uppercase_qr() {
+        let uri: PaymentUri = format!("BITCOIN:{}?amount=0.001", ADDRESS.to_uppercase())
+            .parse()
+            .unwrap();
+        assert_eq!(uri.address, shop());
+        assert_eq!(uri.amount, Some(100_000));
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        for s in [
+            format!("ethereum:{}", ADDRESS),
+            "bitcoin:notanaddress".to_string(),
+            format!("bitcoin:{}?req-somethingyoudontunderstand=50", ADDRESS),
+            format!("bitcoin:{}?amount=1&amount=2", ADDRESS),
+            format!("bitcoin:{}?label=a&label=b", ADDRESS),
+            format!("bitcoin:{}?amount", ADDRESS),
+            format!("bitcoin:{}?amount=1e8", ADDRESS),
+            format!("bitcoin:{}?label=%ZZ", ADDRESS),
+        ] {
+            assert!(s.parse::<PaymentUri>().is_err(), "{} should be rejected", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_checks_network() {
+        let s = format!("bitcoin:{}", ADDRESS);
+        assert!(PaymentUri::parse(&s, Network::Bitcoin).is_ok());
+        assert!(matches!(
+            PaymentUri::parse(&s, Network::Testnet),
+            Err(Error::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let uri = PaymentUri::new(shop())
+            .with_amount(123_456_789)
+            .with_label("Café & Co")
+            .with_message("Order #42")
+            .with_parameter("pj", "https://example.com/pj?x=1");
+        let text = uri.to_string();
+        assert_eq!(
+            text,
+            format!(
+                "bitcoin:{}?amount=1.23456789&label=Caf%C3%A9%20%26%20Co&message=Order%20%2342\
+                 &pj=https%3A%2F%2Fexample.com%2Fpj%3Fx%3D1",
+                ADDRESS
+            )
+        );
+        assert_eq!(text.parse::<PaymentUri>().unwrap(), uri);
+        assert_eq!(
+            PaymentUri::new(shop()).to_string(),
+            format!("bitcoin:{}", ADDRESS)
+        );
+    }
+}
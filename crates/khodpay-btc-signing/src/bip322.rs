@@ -0,0 +1,587 @@
+//! BIP-322 generic signed messages.
+//!
+//! A BIP-322 signature proves control of an address by signing a virtual transaction
+//! instead of a raw message hash, so it works for any script type a wallet can spend.
+//! Two transactions are involved:
+//!
+//! - `to_spend` has one output locked to the address, spent by an input whose
+//!   `scriptSig` commits to the tagged message hash;
+//! - `to_sign` spends that output into a single `OP_RETURN`. Its witness is the
+//!   signature.
+//!
+//! The *simple* format is the base64 witness stack of `to_sign`; the *full* format is
+//! the whole serialized `to_sign` transaction.
+//!
+//! Every single-key address type is supported, each spent the way a wallet would
+//! spend it:
+//!
+//! | Address | `to_sign` input | Signature hash |
+//! |---------|-----------------|----------------|
+//! | P2PKH | `scriptSig` `<sig> <pubkey>` (full format only) | [`sighash::legacy`] |
+//! | P2SH-P2WPKH | redeem-script `scriptSig`, witness `<sig> <pubkey>` | [`sighash::segwit_v0`] |
+//! | P2WPKH | witness `<sig> <pubkey>` | [`sighash::segwit_v0`] |
+//! | P2TR | witness `<schnorr sig>`, 64 or 65 bytes (key path) | [`sighash::taproot`] |
+//!
+//! The simple format has no room for a `scriptSig`, so P2PKH signatures are full
+//! only; for P2SH-P2WPKH the `scriptSig` is implied by the key in the witness.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::bip322::verify_simple;
+//! use khodpay_btc_signing::{BtcSigner, Network};
+//!
+//! let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+//! let signature = signer.sign_message(b"I own this address").unwrap();
+//!
+//! assert!(verify_simple(&signer.p2wpkh_address(), b"I own this address", &signature).unwrap());
+//! ```
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use secp256k1::ecdsa::Signature;
+use secp256k1::{schnorr, Message, PublicKey, XOnlyPublicKey, SECP256K1};
+
+use crate::hash::{hash160, tagged_hash};
+use crate::script::{OP_0, OP_RETURN};
+use crate::sighash::{self, SIGHASH_ALL, SIGHASH_DEFAULT};
+use crate::transaction::{write_bytes, write_compact_size, Reader};
+use crate::{Address, BtcSigner, Error, OutPoint, Result, Script, Transaction, TxIn, TxOut, Txid};
+
+/// Tag of the BIP-340 tagged hash applied to messages.
+pub const MESSAGE_TAG: &str = "BIP0322-signed-message";
+
+/// Computes the BIP-322 tagged hash of `message`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::bip322::hash_message;
+///
+/// assert_eq!(
+///     hex::encode(hash_message(b"Hello World")),
+///     "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+/// );
+/// ```
+pub fn hash_message(message: &[u8]) -> [u8; 32] {
+    tagged_hash(MESSAGE_TAG, message)
+}
+
+/// Builds the virtual `to_spend` transaction for `message` and the address script.
+pub fn to_spend_tx(script_pubkey: &Script, message: &[u8]) -> Transaction {
+    let mut script_sig = vec![OP_0, 0x20];
+    script_sig.extend_from_slice(&hash_message(message));
+    Transaction {
+        version: 0,
+        inputs: vec![TxIn {
+            script_sig: Script::new(script_sig),
+            ..TxIn::new(OutPoint::new(Txid::default(), 0xffff_ffff), 0)
+        }],
+        outputs: vec![TxOut {
+            value: 0,
+            script_pubkey: script_pubkey.clone(),
+        }],
+        lock_time: 0,
+    }
+}
+
+/// Builds the unsigned virtual `to_sign` transaction spending `to_spend`.
+pub fn to_sign_tx(to_spend: &Transaction) -> Transaction {
+    Transaction {
+        version: 0,
+        inputs: vec![TxIn::new(OutPoint::new(to_spend.txid(), 0), 0)],
+        outputs: vec![TxOut {
+            value: 0,
+            script_pubkey: Script::new(vec![OP_RETURN]),
+        }],
+        lock_time: 0,
+    }
+}
+
+/// Signs `message` for `address` in the simple format (base64 witness stack).
+///
+/// # Errors
+///
+/// Returns [`Error::SigningError`] if `address` is not a P2WPKH, P2SH-P2WPKH or
+/// taproot address of `signer`. P2PKH signatures live in the `scriptSig`, which the
+/// simple format cannot carry; sign those with [`sign_full`].
+pub fn sign_simple(signer: &BtcSigner, address: &Address, message: &[u8]) -> Result<String> {
+    if address.script_pubkey().p2pkh_hash().is_some() {
+        return Err(Error::SigningError(format!(
+            "{} is a P2PKH address, which has no witness; use the full format",
+            address
+        )));
+    }
+    let to_sign = signed_to_sign_tx(signer, address, message)?;
+    Ok(BASE64.encode(encode_witness(&to_sign.inputs[0].witness)))
+}
+
+/// Signs `message` for `address` in the full format (base64 `to_sign` transaction).
+///
+/// # Errors
+///
+/// Returns [`Error::SigningError`] if `address` is not a P2PKH, P2SH-P2WPKH, P2WPKH
+/// or taproot address of `signer`.
+pub fn sign_full(signer: &BtcSigner, address: &Address, message: &[u8]) -> Result<String> {
+    let to_sign = signed_to_sign_tx(signer, address, message)?;
+    Ok(BASE64.encode(to_sign.serialize()))
+}
+
+/// Verifies a simple-format signature of `message` by `address`.
+///
+/// Returns `Ok(true)` if the witness satisfies the address, `Ok(false)` if it is
+/// well-formed but for another key or message. For P2SH-P2WPKH the `scriptSig` is
+/// rebuilt from the public key in the witness.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] if the signature is malformed, or
+/// [`Error::InvalidAddress`] for P2PKH (which needs the full format) and address
+/// types this crate cannot verify.
+pub fn verify_simple(address: &Address, message: &[u8], signature: &str) -> Result<bool> {
+    let script_pubkey = address.script_pubkey();
+    if script_pubkey.p2pkh_hash().is_some() {
+        return Err(Error::InvalidAddress(format!(
+            "{} is a P2PKH address, which has no witness; verify the full format",
+            address
+        )));
+    }
+    let bytes = decode_base64(signature)?;
+    let witness = decode_witness(&bytes)?;
+    let to_spend = to_spend_tx(&script_pubkey, message);
+    let mut to_sign = to_sign_tx(&to_spend);
+    if script_pubkey.p2sh_hash().is_some() {
+        if let [_, public_key] = witness.as_slice() {
+            to_sign.inputs[0].script_sig = nested_script_sig(public_key);
+        }
+    }
+    to_sign.inputs[0].witness = witness;
+    verify_to_sign(&script_pubkey, &to_spend, &to_sign)
+}
+
+/// Verifies a full-format signature of `message` by `address`.
+///
+/// The version, lock-time and sequence of `to_sign` are taken from the signature;
+/// it must spend the `to_spend` output into a single `OP_RETURN`. Proof-of-funds
+/// signatures with additional inputs are rejected.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] if the signature is malformed or not a
+/// `to_sign` transaction for this message, or [`Error::InvalidAddress`] for address
+/// types this crate cannot verify.
+pub fn verify_full(address: &Address, message: &[u8], signature: &str) -> Result<bool> {
+    let bytes = decode_base64(signature)?;
+    let to_sign = Transaction::deserialize(&bytes).map_err(malformed)?;
+    let script_pubkey = address.script_pubkey();
+    let to_spend = to_spend_tx(&script_pubkey, message);
+
+    let spends_to_spend = to_sign.inputs.len() == 1
+        && to_sign.inputs[0].previous_output == OutPoint::new(to_spend.txid(), 0);
+    if !spends_to_spend {
+        // A different message or address changes the to_spend txid.
+        return Ok(false);
+    }
+    if to_sign.outputs != to_sign_tx(&to_spend).outputs {
+        return Err(malformed(
+            "to_sign must have a single empty OP_RETURN output",
+        ));
+    }
+    verify_to_sign(&script_pubkey, &to_spend, &to_sign)
+}
+
+fn signed_to_sign_tx(signer: &BtcSigner, address: &Address, message: &[u8]) -> Result<Transaction> {
+    let script_pubkey = address.script_pubkey();
+    let to_spend = to_spend_tx(&script_pubkey, message);
+    let mut to_sign = to_sign_tx(&to_spend);
+
+    if let Some(script_type) = signer.script_type_of(&script_pubkey) {
+        // Legacy sighash for P2PKH, BIP-143 for the SegWit v0 types.
+        let signature = signer.sign_input(&to_sign, 0, script_type, 0, SIGHASH_ALL)?;
+        let (script_sig, witness) = script_type.satisfaction(&signer.public_key(), &signature);
+        to_sign.inputs[0].script_sig = script_sig;
+        to_sign.inputs[0].witness = witness;
+    } else if script_pubkey == signer.p2tr_address().script_pubkey() {
+        let sighash =
+            sighash::taproot(&to_sign, 0, &to_spend.outputs, SIGHASH_DEFAULT, None, None)?;
+        let signature = signer.sign_schnorr_key_path(&sighash)?;
+        to_sign.inputs[0].witness = vec![signature.to_vec()];
+    } else {
+        return Err(Error::SigningError(format!(
+            "{} is not an address of this key",
+            address
+        )));
+    }
+    Ok(to_sign)
+}
+
+fn verify_to_sign(
+    script_pubkey: &Script,
+    to_spend: &Transaction,
+    to_sign: &Transaction,
+) -> Result<bool> {
+    let input = &to_sign.inputs[0];
+    if let Some(pubkey_hash) = script_pubkey.p2wpkh_hash() {
+        let Some((signature, public_key)) = ecdsa_pair(&input.witness) else {
+            return Ok(false);
+        };
+        if !input.script_sig.is_empty() || hash160(&public_key.serialize()) != pubkey_hash {
+            return Ok(false);
+        }
+        let sighash = sighash::segwit_v0(to_sign, 0, &Script::p2pkh(&pubkey_hash), 0, SIGHASH_ALL)?;
+        Ok(verify_ecdsa(&sighash, &signature, &public_key))
+    } else if let Some(script_hash) = script_pubkey.p2sh_hash() {
+        let Some((signature, public_key)) = ecdsa_pair(&input.witness) else {
+            return Ok(false);
+        };
+        let pubkey_hash = hash160(&public_key.serialize());
+        // The scriptSig pushes the P2WPKH redeem script, whose hash the address commits to.
+        if input.script_sig != nested_script_sig(&public_key.serialize())
+            || hash160(Script::p2wpkh(&pubkey_hash).as_bytes()) != script_hash
+        {
+            return Ok(false);
+        }
+        let sighash = sighash::segwit_v0(to_sign, 0, &Script::p2pkh(&pubkey_hash), 0, SIGHASH_ALL)?;
+        Ok(verify_ecdsa(&sighash, &signature, &public_key))
+    } else if let Some(pubkey_hash) = script_pubkey.p2pkh_hash() {
+        let Some(items) = input.script_sig.pushes() else {
+            return Ok(false);
+        };
+        let Some((signature, public_key)) = ecdsa_pair(&items) else {
+            return Ok(false);
+        };
+        if !input.witness.is_empty() || hash160(&public_key.serialize()) != pubkey_hash {
+            return Ok(false);
+        }
+        let sighash = sighash::legacy(to_sign, 0, script_pubkey, SIGHASH_ALL)?;
+        Ok(verify_ecdsa(&sighash, &signature, &public_key))
+    } else if let Some(output_key) = script_pubkey.p2tr_key() {
+        // Key-path spend only: a single 64-byte signature, or 65 bytes with
+        // SIGHASH_ALL. Script paths and annexes are not accepted.
+        let ([signature], true) = (input.witness.as_slice(), input.script_sig.is_empty()) else {
+            return Ok(false);
+        };
+        let (signature, sighash_type) = match signature.len() {
+            64 => (&signature[..], SIGHASH_DEFAULT),
+            65 if u32::from(signature[64]) == SIGHASH_ALL => (&signature[..64], SIGHASH_ALL),
+            _ => return Ok(false),
+        };
+        let (Ok(signature), Ok(output_key)) = (
+            schnorr::Signature::from_slice(signature),
+            XOnlyPublicKey::from_slice(&output_key),
+        ) else {
+            return Ok(false);
+        };
+        let sighash = sighash::taproot(to_sign, 0, &to_spend.outputs, sighash_type, None, None)?;
+        Ok(SECP256K1
+            .verify_schnorr(&signature, &Message::from_digest(sighash), &output_key)
+            .is_ok())
+    } else {
+        Err(Error::InvalidAddress(format!(
+            "BIP-322 verification of script {} is not supported; only P2PKH, P2SH-P2WPKH, \
+             P2WPKH and taproot key-path addresses are",
+            script_pubkey.to_hex()
+        )))
+    }
+}
+
+/// Parses a `<signature> <public key>` stack with a `SIGHASH_ALL` DER signature and
+/// a compressed key.
+fn ecdsa_pair<T: AsRef<[u8]>>(items: &[T]) -> Option<(Signature, PublicKey)> {
+    let [signature, public_key] = items else {
+        return None;
+    };
+    let (signature, public_key) = (signature.as_ref(), public_key.as_ref());
+    let (&sighash_type, der) = signature.split_last()?;
+    if u32::from(sighash_type) != SIGHASH_ALL || public_key.len() != 33 {
+        return None;
+    }
+    Some((
+        Signature::from_der(der).ok()?,
+        PublicKey::from_slice(public_key).ok()?,
+    ))
+}
+
+fn verify_ecdsa(sighash: &[u8; 32], signature: &Signature, public_key: &PublicKey) -> bool {
+    SECP256K1
+        .verify_ecdsa(&Message::from_digest(*sighash), signature, public_key)
+        .is_ok()
+}
+
+/// The P2SH-P2WPKH `scriptSig`: a push of the P2WPKH redeem script of `public_key`.
+fn nested_script_sig(public_key: &[u8]) -> Script {
+    Script::from_pushes(&[Script::p2wpkh(&hash160(public_key)).as_bytes()])
+}
+
+fn malformed(reason: impl std::fmt::Display) -> Error {
+    Error::ValidationError(format!("malformed BIP-322 signature: {}", reason))
+}
+
+fn decode_base64(signature: &str) -> Result<Vec<u8>> {
+    BASE64.decode(signature.trim()).map_err(malformed)
+}
+
+fn encode_witness(witness: &[Vec<u8>]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_compact_size(&mut buf, witness.len() as u64);
+    for item in witness {
+        write_bytes(&mut buf, item);
+    }
+    buf
+}
+
+fn decode_witness(bytes: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(bytes);
+    let count = reader.read_compact_size().map_err(malformed)?;
+    let mut witness = Vec::new();
+    for _ in 0..count {
+        witness.push(reader.read_var_bytes().map_err(malformed)?.to_vec());
+    }
+    if !reader.is_empty() {
+        return Err(malformed("trailing bytes after the witness"));
+    }
+    Ok(witness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Network, ScriptType};
+
+    // BIP-322 test vectors.
+    const VECTOR_WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const VECTOR_ADDRESS: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const EMPTY_SIGNATURE: &str = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const HELLO_SIGNATURE: &str = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const P2TR_ADDRESS: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+    const P2TR_HELLO_SIGNATURE: &str = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+
+    // Same key, P2SH-P2WPKH and P2PKH. The BIP has no vectors for these; they were
+    // cross-checked against rust-bitcoin's BIP-143 and legacy sighashes with low-R
+    // signing.
+    const P2SH_EMPTY_SIGNATURE: &str = "AkcwRAIgWlv2eXF/W6RbkUG+IGtB6Mjpl4LFMkONYTBwwWWY/4gCIHTwq+Iw1ymi5SvIMnuGY1gZk4LKPa/NCsgAexMTr62XASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const P2SH_HELLO_SIGNATURE: &str = "AkcwRAIgRfq2Gfv9guFcXAf2vQEJSHX8FP5OuiHs1DSK1I0wk/0CIGPzqm6QNPTDJuki148OQ2DbJtXyrr71s4xYPwogQUupASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const P2SH_EMPTY_FULL: &str = "AAAAAAABAS4YugExjebpN5hg7C4MxlbmTwFw0ho5aYZ+WrCPPmFZAAAAABcWABQrBdVk5qejPAh/FuD3MNFEASN5nQAAAAABAAAAAAAAAAABagJHMEQCIFpb9nlxf1ukW5FBviBrQejI6ZeCxTJDjWEwcMFlmP+IAiB08KviMNcpouUryDJ7hmNYGZOCyj2vzQrIAHsTE6+tlwEhAsfxIAMZZEKUPYWI4BruhAQjzFT8FSFSajuFwrDL1YhyAAAAAA==";
+    const P2SH_HELLO_FULL: &str = "AAAAAAABAcbRsnNWkk3JpUbXQM8ONPeKRfpyUCEoGI9hMjBvVKIjAAAAABcWABQrBdVk5qejPAh/FuD3MNFEASN5nQAAAAABAAAAAAAAAAABagJHMEQCIEX6thn7/YLhXFwH9r0BCUh1/BT+Troh7NQ0itSNMJP9AiBj86pukDT0wybpItePDkNg2ybV8q6+9bOMWD8KIEFLqQEhAsfxIAMZZEKUPYWI4BruhAQjzFT8FSFSajuFwrDL1YhyAAAAAA==";
+    const P2PKH_EMPTY_FULL: &str = "AAAAAAHy1VRuvTW+AVeFFIlYclAtSDU4hQhUpSTdMgP3zrFYEgAAAABqRzBEAiAcu6rFV3Jb7mXhhWDbiPK4H0S/frN/QF2oBy3DcZboOwIgWue/Sjbw9mlTxZ/6K8s6RWw8vzVZFA55SttavBzBHLYBIQLH8SADGWRClD2FiOAa7oQEI8xU/BUhUmo7hcKwy9WIcgAAAAABAAAAAAAAAAABagAAAAA=";
+    const P2PKH_HELLO_FULL: &str = "AAAAAAHZIvdvR4fompS+lLTvaKJgjitVabp8CizknOvglZs2XgAAAABqRzBEAiB3hjKYQcm/KGTsalB3I4kixH3+uDyHQzt1PN5cBGJsvQIgJnRxSVWIbijmMST7VnxGpI8OOCU/tky8Pg7UH5HgSt4BIQLH8SADGWRClD2FiOAa7oQEI8xU/BUhUmo7hcKwy9WIcgAAAAABAAAAAAAAAAABagAAAAA=";
+
+    fn vector_signer() -> BtcSigner {
+        let decoded = bs58::decode(VECTOR_WIF)
+            .with_check(None)
+            .into_vec()
+            .unwrap();
+        // 0x80 || key || 0x01 (compressed)
+        let key: [u8; 32] = decoded[1..33].try_into().unwrap();
+        BtcSigner::from_private_key(&key, Network::Bitcoin).unwrap()
+    }
+
+    fn vector_address() -> Address {
+        VECTOR_ADDRESS.parse().unwrap()
+    }
+
+    #[test]
+    fn test_hash_message() {
+        assert_eq!(
+            hex::encode(hash_message(b"")),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+    }
+
+    #[test]
+    fn test_virtual_transaction_ids() {
+        let script = vector_address().script_pubkey();
+        for (message, to_spend_id, to_sign_id) in [
+            (
+                &b""[..],
+                "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7",
+                "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6",
+            ),
+            (
+                &b"Hello World"[..],
+                "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b",
+                "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf",
+            ),
+        ] {
+            let to_spend = to_spend_tx(&script, message);
+            assert_eq!(to_spend.txid().to_string(), to_spend_id);
+            assert_eq!(to_sign_tx(&to_spend).txid().to_string(), to_sign_id);
+        }
+    }
+
+    #[test]
+    fn test_sign_simple_vectors() {
+        let signer = vector_signer();
+        assert_eq!(signer.p2wpkh_address().to_string(), VECTOR_ADDRESS);
+        assert_eq!(
+            sign_simple(&signer, &vector_address(), b"").unwrap(),
+            EMPTY_SIGNATURE
+        );
+        assert_eq!(
+            sign_simple(&signer, &vector_address(), b"Hello World").unwrap(),
+            HELLO_SIGNATURE
+        );
+    }
+
+    #[test]
+    fn test_verify_simple_vectors() {
+        let address = vector_address();
+        assert!(verify_simple(&address, b"", EMPTY_SIGNATURE).unwrap());
+        assert!(verify_simple(&address, b"Hello World", HELLO_SIGNATURE).unwrap());
+        // Swapped signatures.
+        assert!(!verify_simple(&address, b"", HELLO_SIGNATURE).unwrap());
+        assert!(!verify_simple(&address, b"Hello World", EMPTY_SIGNATURE).unwrap());
+
+        let other = BtcSigner::from_private_key(&[2u8; 32], Network::Bitcoin).unwrap();
+        assert!(!verify_simple(&other.p2wpkh_address(), b"", EMPTY_SIGNATURE).unwrap());
+    }
+
+    #[test]
+    fn test_full_round_trip() {
+        let signer = vector_signer();
+        let address = vector_address();
+        let full = sign_full(&signer, &address, b"Hello World").unwrap();
+        assert!(verify_full(&address, b"Hello World", &full).unwrap());
+        assert!(!verify_full(&address, b"Hello", &full).unwrap());
+
+        // The full signature is the to_sign transaction with the simple witness.
+        let tx = Transaction::deserialize(&BASE64.decode(&full).unwrap()).unwrap();
+        assert_eq!(
+            BASE64.encode(encode_witness(&tx.inputs[0].witness)),
+            HELLO_SIGNATURE
+        );
+    }
+
+    #[test]
+    fn test_signer_sign_message() {
+        let signer = vector_signer();
+        assert_eq!(signer.sign_message(b"").unwrap(), EMPTY_SIGNATURE);
+    }
+
+    #[test]
+    fn test_sign_rejects_foreign_address() {
+        let signer = vector_signer();
+        let other = BtcSigner::from_private_key(&[2u8; 32], Network::Bitcoin).unwrap();
+        assert!(matches!(
+            sign_simple(&signer, &other.p2wpkh_address(), b"hi"),
+            Err(Error::SigningError(_))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_signatures() {
+        let address = vector_address();
+        assert!(verify_simple(&address, b"", "not base64!").is_err());
+        // Witness count of 2 with a single truncated item.
+        assert!(verify_simple(&address, b"", &BASE64.encode([0x02, 0x05, 0x00])).is_err());
+        assert!(verify_full(&address, b"", &BASE64.encode([0x00; 3])).is_err());
+        // Well-formed but empty witness.
+        assert!(!verify_simple(&address, b"", &BASE64.encode([0x00])).unwrap());
+    }
+
+    #[test]
+    fn test_nested_segwit_vectors() {
+        let signer = vector_signer();
+        let address = signer.address_for(ScriptType::P2shP2wpkh);
+        assert_eq!(address.to_string(), "37qyp7jQAzqb2rCBpMvVtLDuuzKAUCVnJb");
+
+        for (message, simple, full) in [
+            (&b""[..], P2SH_EMPTY_SIGNATURE, P2SH_EMPTY_FULL),
+            (&b"Hello World"[..], P2SH_HELLO_SIGNATURE, P2SH_HELLO_FULL),
+        ] {
+            assert_eq!(sign_simple(&signer, &address, message).unwrap(), simple);
+            assert_eq!(sign_full(&signer, &address, message).unwrap(), full);
+            assert!(verify_simple(&address, message, simple).unwrap());
+            assert!(verify_full(&address, message, full).unwrap());
+        }
+        assert!(!verify_simple(&address, b"", P2SH_HELLO_SIGNATURE).unwrap());
+        // P2WPKH and P2SH-P2WPKH witnesses are interchangeable only with the right address.
+        assert!(!verify_simple(&vector_address(), b"", P2SH_EMPTY_SIGNATURE).unwrap());
+        assert!(!verify_simple(&address, b"", EMPTY_SIGNATURE).unwrap());
+    }
+
+    #[test]
+    fn test_nested_segwit_requires_redeem_script() {
+        let address = vector_signer().address_for(ScriptType::P2shP2wpkh);
+        let mut tx = Transaction::deserialize(&BASE64.decode(P2SH_HELLO_FULL).unwrap()).unwrap();
+        tx.inputs[0].script_sig = Script::default();
+        let stripped = BASE64.encode(tx.serialize());
+        assert!(!verify_full(&address, b"Hello World", &stripped).unwrap());
+    }
+
+    #[test]
+    fn test_legacy_vectors() {
+        let signer = vector_signer();
+        let address = signer.address_for(ScriptType::P2pkh);
+        assert_eq!(address.to_string(), "14vV3aCHBeStb5bkenkNHbe2YAFinYdXgc");
+
+        for (message, full) in [
+            (&b""[..], P2PKH_EMPTY_FULL),
+            (&b"Hello World"[..], P2PKH_HELLO_FULL),
+        ] {
+            assert_eq!(sign_full(&signer, &address, message).unwrap(), full);
+            assert!(verify_full(&address, message, full).unwrap());
+        }
+        assert!(!verify_full(&address, b"", P2PKH_HELLO_FULL).unwrap());
+
+        // P2PKH signatures live in the scriptSig, which the simple format cannot carry.
+        assert!(matches!(
+            sign_simple(&signer, &address, b""),
+            Err(Error::SigningError(_))
+        ));
+    }
+
+    #[test]
+    fn test_taproot_vector() {
+        let address: Address = P2TR_ADDRESS.parse().unwrap();
+        assert_eq!(vector_signer().p2tr_address(), address);
+        assert!(verify_simple(&address, b"Hello World", P2TR_HELLO_SIGNATURE).unwrap());
+        assert!(!verify_simple(&address, b"", P2TR_HELLO_SIGNATURE).unwrap());
+
+        let other = BtcSigner::from_private_key(&[2u8; 32], Network::Bitcoin).unwrap();
+        assert!(
+            !verify_simple(&other.p2tr_address(), b"Hello World", P2TR_HELLO_SIGNATURE).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_taproot_round_trip() {
+        let signer = vector_signer();
+        let address = signer.p2tr_address();
+
+        let simple = sign_simple(&signer, &address, b"Hello World").unwrap();
+        let witness = decode_witness(&BASE64.decode(&simple).unwrap()).unwrap();
+        // SIGHASH_DEFAULT: a bare 64-byte Schnorr signature.
+        assert_eq!(witness.len(), 1);
+        assert_eq!(witness[0].len(), 64);
+        assert!(verify_simple(&address, b"Hello World", &simple).unwrap());
+        assert!(!verify_simple(&address, b"Hello", &simple).unwrap());
+
+        let full = sign_full(&signer, &address, b"Hello World").unwrap();
+        assert!(verify_full(&address, b"Hello World", &full).unwrap());
+
+        // An explicit SIGHASH_DEFAULT byte is invalid under BIP-341.
+        let mut padded = witness[0].clone();
+        padded.push(0x00);
+        let padded = BASE64.encode(encode_witness(&[padded]));
+        assert!(!verify_simple(&address, b"Hello World", &padded).unwrap());
+    }
+
+    #[test]
+    fn test_unsupported_address_type() {
+        let p2pkh: Address = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".parse().unwrap();
+        assert!(matches!(
+            verify_simple(&p2pkh, b"", EMPTY_SIGNATURE),
+            Err(Error::InvalidAddress(_))
+        ));
+        let p2wsh = Address::from_script(&Script::p2wsh(&[0x11; 32]), Network::Bitcoin).unwrap();
+        assert!(matches!(
+            verify_simple(&p2wsh, b"", EMPTY_SIGNATURE),
+            Err(Error::InvalidAddress(_))
+        ));
+        assert!(matches!(
+            sign_simple(&vector_signer(), &p2wsh, b""),
+            Err(Error::SigningError(_))
+        ));
+    }
+}
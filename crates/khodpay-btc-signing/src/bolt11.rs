@@ -0,0 +1,786 @@
+//! BOLT-11 Lightning invoices.
+//!
+//! [BOLT-11](https://github.com/lightning/bolts/blob/master/11-payment-encoding.md)
+//! invoices are what Lightning wallets put in payment QR codes:
+//!
+//! ```text
+//! lnbc2500u1pj...
+//! ```
+//!
+//! [`Invoice`] decodes and validates them so a scanned invoice can be shown and
+//! checked before the wallet can pay it: the bech32 checksum, the amount, the
+//! tagged fields and the payee's signature. It does not pay, route or create
+//! invoices.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::bolt11::Invoice;
+//! use khodpay_btc_signing::Network;
+//!
+//! let invoice = Invoice::parse(
+//!     "lightning:lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp",
+//!     Network::Bitcoin,
+//! )?;
+//! assert_eq!(invoice.amount_msat(), Some(250_000_000));
+//! assert_eq!(invoice.description(), Some("1 cup coffee"));
+//! assert_eq!(invoice.expiry(), 60);
+//! assert!(invoice.is_expired(invoice.timestamp() + 60));
+//! # Ok::<(), khodpay_btc_signing::Error>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use bech32::primitives::decode::CheckedHrpstring;
+use bech32::{Checksum, Fe32};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, SECP256K1};
+
+use crate::hash::sha256;
+use crate::{Address, Error, Network, Payload, Result, MAX_MONEY};
+
+/// URI scheme that may precede an invoice in a QR code.
+pub const SCHEME: &str = "lightning";
+
+/// Expiry in seconds of invoices without an `x` field.
+pub const DEFAULT_EXPIRY: u64 = 3600;
+
+/// `min_final_cltv_expiry_delta` of invoices without a `c` field.
+pub const DEFAULT_MIN_FINAL_CLTV_EXPIRY_DELTA: u64 = 18;
+
+/// Millisatoshis per bitcoin.
+const MSAT_PER_BTC: u64 = 100_000_000_000;
+
+/// Length of the timestamp in 5-bit groups.
+const TIMESTAMP_LEN: usize = 7;
+/// Length of the signature and recovery ID in 5-bit groups.
+const SIGNATURE_LEN: usize = 104;
+/// Length of a 256-bit hash field in 5-bit groups.
+const HASH_LEN: usize = 52;
+/// Length of a compressed public key field in 5-bit groups.
+const PUBKEY_LEN: usize = 53;
+/// Length of one route hint hop in bytes.
+const HOP_LEN: usize = 51;
+
+// Tagged field types
+const TAG_PAYMENT_HASH: u8 = 1;
+const TAG_ROUTE_HINT: u8 = 3;
+const TAG_FEATURES: u8 = 5;
+const TAG_EXPIRY: u8 = 6;
+const TAG_FALLBACK: u8 = 9;
+const TAG_DESCRIPTION: u8 = 13;
+const TAG_PAYMENT_SECRET: u8 = 16;
+const TAG_PAYEE: u8 = 19;
+const TAG_DESCRIPTION_HASH: u8 = 23;
+const TAG_MIN_FINAL_CLTV_EXPIRY_DELTA: u8 = 24;
+
+/// Bech32 with the length limit lifted, as invoices with route hints are
+/// longer than the 90 characters of addresses.
+enum Bolt11Bech32 {}
+
+impl Checksum for Bolt11Bech32 {
+    type MidstateRepr = <bech32::Bech32 as Checksum>::MidstateRepr;
+    const CODE_LENGTH: usize = 7089;
+    const CHECKSUM_LENGTH: usize = bech32::Bech32::CHECKSUM_LENGTH;
+    const GENERATOR_SH: [u32; 5] = bech32::Bech32::GENERATOR_SH;
+    const TARGET_RESIDUE: u32 = bech32::Bech32::TARGET_RESIDUE;
+}
+
+/// One hop of a private route to the payee, from an `r` field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteHop {
+    /// Node at the start of the channel.
+    pub node_id: PublicKey,
+    /// Channel to the next hop, as `block << 40 | tx << 16 | output`.
+    pub short_channel_id: u64,
+    /// Base fee charged by the node, in millisatoshis.
+    pub fee_base_msat: u32,
+    /// Proportional fee charged by the node, in millionths.
+    pub fee_proportional_millionths: u32,
+    /// CLTV expiry delta of the channel, in blocks.
+    pub cltv_expiry_delta: u16,
+}
+
+impl RouteHop {
+    /// Returns the short channel ID in the `BLOCKxTXxOUTPUT` form explorers use.
+    pub fn short_channel_id_string(&self) -> String {
+        let scid = self.short_channel_id;
+        format!(
+            "{}x{}x{}",
+            scid >> 40,
+            (scid >> 16) & 0xff_ffff,
+            scid & 0xffff
+        )
+    }
+}
+
+/// A decoded, signature-checked BOLT-11 invoice.
+///
+/// Unknown tagged fields are skipped, as BOLT-11 requires. A wallet must not pay
+/// an invoice whose [`Invoice::features`] include an even bit it does not know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invoice {
+    encoded: String,
+    network: Network,
+    amount_msat: Option<u64>,
+    timestamp: u64,
+    payment_hash: [u8; 32],
+    payment_secret: Option<[u8; 32]>,
+    description: Option<String>,
+    description_hash: Option<[u8; 32]>,
+    payee: PublicKey,
+    expiry: u64,
+    min_final_cltv_expiry_delta: u64,
+    fallbacks: Vec<Address>,
+    route_hints: Vec<Vec<RouteHop>>,
+    features: Vec<u16>,
+}
+
+impl Invoice {
+    /// Parses an invoice and checks that it is for `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInvoice`] if the invoice is malformed, its
+    /// signature is invalid, or it is for another network.
+    pub fn parse(s: &str, network: Network) -> Result<Self> {
+        let invoice: Invoice = s.parse()?;
+        if invoice.network != network {
+            return Err(Error::InvalidInvoice(format!(
+                "invoice is for {}, not {}",
+                invoice.network, network
+            )));
+        }
+        Ok(invoice)
+    }
+
+    /// Returns the network the invoice is for.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the amount in millisatoshis, or `None` if the payer chooses it.
+    pub fn amount_msat(&self) -> Option<u64> {
+        self.amount_msat
+    }
+
+    /// Returns the creation time, in seconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the seconds after [`Invoice::timestamp`] the invoice is valid for.
+    pub fn expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    /// Returns the time the invoice expires, in seconds since the Unix epoch.
+    pub fn expires_at(&self) -> u64 {
+        self.timestamp.saturating_add(self.expiry)
+    }
+
+    /// Returns `true` if the invoice has expired at `now` (seconds since the Unix
+    /// epoch).
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at()
+    }
+
+    /// Returns the SHA-256 hash of the payment preimage.
+    pub fn payment_hash(&self) -> &[u8; 32] {
+        &self.payment_hash
+    }
+
+    /// Returns the payment secret, which older invoices lack.
+    pub fn payment_secret(&self) -> Option<&[u8; 32]> {
+        self.payment_secret.as_ref()
+    }
+
+    /// Returns the description of the payment, from the `d` field.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the SHA-256 hash of a description too long for the invoice, from
+    /// the `h` field.
+    pub fn description_hash(&self) -> Option<&[u8; 32]> {
+        self.description_hash.as_ref()
+    }
+
+    /// Returns `true` if `description` is the one [`Invoice::description_hash`]
+    /// commits to.
+    pub fn matches_description(&self, description: &str) -> bool {
+        self.description_hash == Some(sha256(description.as_bytes()))
+    }
+
+    /// Returns the node that signed the invoice and will be paid.
+    pub fn payee(&self) -> &PublicKey {
+        &self.payee
+    }
+
+    /// Returns the CLTV expiry delta, in blocks, for the last hop.
+    pub fn min_final_cltv_expiry_delta(&self) -> u64 {
+        self.min_final_cltv_expiry_delta
+    }
+
+    /// Returns the on-chain addresses to pay if Lightning fails.
+    pub fn fallbacks(&self) -> &[Address] {
+        &self.fallbacks
+    }
+
+    /// Returns the private routes to the payee, one per `r` field.
+    pub fn route_hints(&self) -> &[Vec<RouteHop>] {
+        &self.route_hints
+    }
+
+    /// Returns the feature bits set, in ascending order.
+    pub fn features(&self) -> &[u16] {
+        &self.features
+    }
+}
+
+impl fmt::Display for Invoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
+
+impl FromStr for Invoice {
+    type Err = Error;
+
+    /// Parses an invoice, with or without a `lightning:` prefix, for any network.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidInvoice(reason);
+
+        let s = s.trim();
+        let s = match s.split_once(':') {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case(SCHEME) => rest,
+            _ => s,
+        };
+        let checked = CheckedHrpstring::new::<Bolt11Bech32>(s)
+            .map_err(|e| invalid(format!("invalid bech32 encoding: {}", e)))?;
+        let hrp = checked.hrp().to_lowercase();
+        let (network, amount_msat) = parse_hrp(&hrp)?;
+
+        let data: Vec<u8> = checked
+            .data_part_ascii_no_checksum()
+            .iter()
+            .map(|&c| Fe32::from_char_unchecked(c).to_u8())
+            .collect();
+        if data.len() < TIMESTAMP_LEN + SIGNATURE_LEN {
+            return Err(invalid("too short".to_string()));
+        }
+        let (signed, signature) = data.split_at(data.len() - SIGNATURE_LEN);
+
+        let mut invoice = Self {
+            encoded: s.to_ascii_lowercase(),
+            network,
+            amount_msat,
+            timestamp: to_u64(&signed[..TIMESTAMP_LEN]),
+            payment_hash: [0; 32],
+            payment_secret: None,
+            description: None,
+            description_hash: None,
+            payee: recover_payee(&hrp, signed, signature)?,
+            expiry: DEFAULT_EXPIRY,
+            min_final_cltv_expiry_delta: DEFAULT_MIN_FINAL_CLTV_EXPIRY_DELTA,
+            fallbacks: Vec::new(),
+            route_hints: Vec::new(),
+            features: Vec::new(),
+        };
+
+        let mut payment_hash = None;
+        let mut payee = None;
+        let mut fields = &signed[TIMESTAMP_LEN..];
+        while !fields.is_empty() {
+            let [tag, high, low, rest @ ..] = fields else {
+                return Err(invalid("truncated tagged field".to_string()));
+            };
+            let len = usize::from(*high) << 5 | usize::from(*low);
+            if rest.len() < len {
+                return Err(invalid("truncated tagged field".to_string()));
+            }
+            let (field, rest) = rest.split_at(len);
+            fields = rest;
+
+            // Fields of the wrong length are skipped, so readers stay compatible
+            // with future uses of the same tag
+            match (*tag, len) {
+                (TAG_PAYMENT_HASH, HASH_LEN) => {
+                    payment_hash.get_or_insert(to_array(field));
+                }
+                (TAG_PAYMENT_SECRET, HASH_LEN) => {
+                    invoice.payment_secret.get_or_insert(to_array(field));
+                }
+                (TAG_DESCRIPTION_HASH, HASH_LEN) => {
+                    invoice.description_hash.get_or_insert(to_array(field));
+                }
+                (TAG_PAYEE, PUBKEY_LEN) => {
+                    let key = PublicKey::from_slice(&to_bytes(field))
+                        .map_err(|e| invalid(format!("invalid payee: {}", e)))?;
+                    payee.get_or_insert(key);
+                }
+                (TAG_DESCRIPTION, _) => {
+                    let description = String::from_utf8(to_bytes(field))
+                        .map_err(|_| invalid("description is not UTF-8".to_string()))?;
+                    invoice.description.get_or_insert(description);
+                }
+                (TAG_EXPIRY, _) => invoice.expiry = to_u64_checked(field, "expiry")?,
+                (TAG_MIN_FINAL_CLTV_EXPIRY_DELTA, _) => {
+                    invoice.min_final_cltv_expiry_delta =
+                        to_u64_checked(field, "min_final_cltv_expiry_delta")?
+                }
+                (TAG_FALLBACK, 1..) => {
+                    if let Some(address) = parse_fallback(field, network)? {
+                        invoice.fallbacks.push(address);
+                    }
+                }
+                (TAG_ROUTE_HINT, _) => invoice.route_hints.push(parse_route_hint(field)?),
+                (TAG_FEATURES, _) => invoice.features = parse_features(field),
+                _ => {}
+            }
+        }
+
+        invoice.payment_hash =
+            payment_hash.ok_or_else(|| invalid("missing payment hash".to_string()))?;
+        if let Some(payee) = payee {
+            if payee != invoice.payee {
+                return Err(invalid("signature is not from the payee".to_string()));
+            }
+        }
+        Ok(invoice)
+    }
+}
+
+/// Reads the network and amount from the human-readable part, such as
+/// `lnbc2500u`.
+fn parse_hrp(hrp: &str) -> Result<(Network, Option<u64>)> {
+    let invalid = |reason: &str| Error::InvalidInvoice(format!("{}: {}", hrp, reason));
+
+    let currency = hrp
+        .strip_prefix("ln")
+        .ok_or_else(|| invalid("not a Lightning invoice"))?;
+    // Longer prefixes first: "bcrt" starts with "bc" and "tbs" with "tb"
+    let (network, amount) = [
+        ("bcrt", Network::Regtest),
+        ("bc", Network::Bitcoin),
+        ("tbs", Network::Signet),
+        ("tb", Network::Testnet),
+    ]
+    .into_iter()
+    .find_map(|(prefix, network)| Some((network, currency.strip_prefix(prefix)?)))
+    .ok_or_else(|| invalid("unknown currency"))?;
+    if amount.is_empty() {
+        return Ok((network, None));
+    }
+
+    let (digits, multiplier) = match amount.as_bytes()[amount.len() - 1] {
+        b'0'..=b'9' => (amount, None),
+        m => (&amount[..amount.len() - 1], Some(m)),
+    };
+    if digits.is_empty() || digits.starts_with('0') || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("invalid amount"));
+    }
+    let value: u64 = digits.parse().map_err(|_| invalid("invalid amount"))?;
+    let msat = match multiplier {
+        None => value.checked_mul(MSAT_PER_BTC),
+        Some(b'm') => value.checked_mul(MSAT_PER_BTC / 1_000),
+        Some(b'u') => value.checked_mul(MSAT_PER_BTC / 1_000_000),
+        Some(b'n') => value.checked_mul(MSAT_PER_BTC / 1_000_000_000),
+        // Picobitcoin amounts must be whole millisatoshis
+        Some(b'p') if value % 10 == 0 => Some(value / 10),
+        Some(b'p') => return Err(invalid("amount is not a whole millisatoshi")),
+        Some(_) => return Err(invalid("unknown amount multiplier")),
+    };
+    let msat = msat
+        .filter(|&msat| msat <= MAX_MONEY * 1_000)
+        .ok_or_else(|| invalid("amount exceeds the money supply"))?;
+    Ok((network, Some(msat)))
+}
+
+/// Recovers the node that signed `signed`, the data part before the signature.
+fn recover_payee(hrp: &str, signed: &[u8], signature: &[u8]) -> Result<PublicKey> {
+    let invalid = |e: secp256k1::Error| Error::InvalidInvoice(format!("invalid signature: {}", e));
+
+    let signature = to_bytes(signature);
+    let recovery_id = RecoveryId::from_i32(i32::from(signature[64])).map_err(invalid)?;
+    let signature =
+        RecoverableSignature::from_compact(&signature[..64], recovery_id).map_err(invalid)?;
+
+    // The data part is padded with zero bits to a whole number of bytes
+    let mut preimage = hrp.as_bytes().to_vec();
+    preimage.extend(to_bytes_padded(signed));
+    let message = Message::from_digest(sha256(&preimage));
+    SECP256K1
+        .recover_ecdsa(&message, &signature)
+        .map_err(invalid)
+}
+
+/// Reads an `f` field, returning `None` for unknown address versions.
+fn parse_fallback(field: &[u8], network: Network) -> Result<Option<Address>> {
+    let program = to_bytes(&field[1..]);
+    let payload = match field[0] {
+        version @ 0..=16 => Payload::WitnessProgram { version, program },
+        17 | 18 if program.len() != 20 => {
+            return Err(Error::InvalidInvoice(format!(
+                "invalid fallback hash of {} bytes",
+                program.len()
+            )))
+        }
+        17 => Payload::PubkeyHash(to_array(&field[1..])),
+        18 => Payload::ScriptHash(to_array(&field[1..])),
+        _ => return Ok(None),
+    };
+    Address::new(payload, network)
+        .map(Some)
+        .map_err(|e| Error::InvalidInvoice(format!("invalid fallback address: {}", e)))
+}
+
+/// Reads an `r` field: one or more hops of 51 bytes.
+fn parse_route_hint(field: &[u8]) -> Result<Vec<RouteHop>> {
+    let bytes = to_bytes(field);
+    if bytes.is_empty() || bytes.len() % HOP_LEN != 0 {
+        return Err(Error::InvalidInvoice(format!(
+            "route hint of {} bytes",
+            bytes.len()
+        )));
+    }
+    bytes
+        .chunks(HOP_LEN)
+        .map(|hop| {
+            let node_id = PublicKey::from_slice(&hop[..33])
+                .map_err(|e| Error::InvalidInvoice(format!("invalid route hint node: {}", e)))?;
+            Ok(RouteHop {
+                node_id,
+                short_channel_id: u64::from_be_bytes(hop[33..41].try_into().unwrap()),
+                fee_base_msat: u32::from_be_bytes(hop[41..45].try_into().unwrap()),
+                fee_proportional_millionths: u32::from_be_bytes(hop[45..49].try_into().unwrap()),
+                cltv_expiry_delta: u16::from_be_bytes([hop[49], hop[50]]),
+            })
+        })
+        .collect()
+}
+
+/// Reads a `9` field, returning the numbers of the bits set.
+fn parse_features(field: &[u8]) -> Vec<u16> {
+    // Bit 0 is the lowest bit of the last group
+    let mut bits = Vec::new();
+    for (i, group) in field.iter().rev().enumerate() {
+        for bit in 0..5 {
+            if group >> bit & 1 == 1 {
+                bits.push((i * 5 + bit) as u16);
+            }
+        }
+    }
+    bits
+}
+
+/// Reads 5-bit groups as a big-endian integer.
+fn to_u64(groups: &[u8]) -> u64 {
+    groups
+        .iter()
+        .fold(0, |value, &group| value << 5 | u64::from(group))
+}
+
+/// Reads 5-bit groups as a big-endian integer, failing if it needs more than
+/// 64 bits.
+fn to_u64_checked(groups: &[u8], what: &str) -> Result<u64> {
+    let significant = &groups[groups.iter().take_while(|&&g| g == 0).count()..];
+    // 13 groups hold 65 bits, so the first of them may use only 4
+    if significant.len() > 13 || (significant.len() == 13 && significant[0] > 15) {
+        return Err(Error::InvalidInvoice(format!("{} is too large", what)));
+    }
+    Ok(to_u64(significant))
+}
+
+/// Packs 5-bit groups into bytes, dropping trailing bits that do not fill a
+/// byte.
+fn to_bytes(groups: &[u8]) -> Vec<u8> {
+    let mut bytes = to_bytes_padded(groups);
+    bytes.truncate(groups.len() * 5 / 8);
+    bytes
+}
+
+/// Packs 5-bit groups into bytes, padding the last byte with zero bits.
+fn to_bytes_padded(groups: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(groups.len() * 5 / 8 + 1);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &group in groups {
+        acc = acc << 5 | u32::from(group);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        bytes.push((acc << (8 - bits)) as u8);
+    }
+    bytes
+}
+
+/// Packs 5-bit groups holding at least `N` bytes into an array.
+fn to_array<const N: usize>(groups: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(&to_bytes(groups)[..N]);
+    array
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bech32::{Fe32IterExt, Hrp};
+    use secp256k1::SecretKey;
+
+    /// The payee's key from the BOLT-11 examples.
+    const PAYEE_SECRET: &str = "e126f68f7eafcc8b74f54d269fe206be715000f94dac067d1c04a8ca3b2db734";
+    const PAYEE: &str = "03e7156ae33b0a208d0744199163177e909e80176e55d97a2f221ede0f934dd9ad";
+    const PAYMENT_HASH: &str = "0001020304050607080900010203040506070809000102030405060708090102";
+    const TIMESTAMP: u64 = 1_496_314_658;
+
+    fn groups(bytes: &[u8]) -> Vec<u8> {
+        let mut groups = Vec::new();
+        let (mut acc, mut bits) = (0u32, 0);
+        for &byte in bytes {
+            acc = acc << 8 | u32::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                groups.push((acc >> bits) as u8 & 31);
+            }
+        }
+        if bits > 0 {
+            groups.push((acc << (5 - bits)) as u8 & 31);
+        }
+        groups
+    }
+
+    fn int(mut value: u64, len: usize) -> Vec<u8> {
+        let mut groups = vec![0; len];
+        for group in groups.iter_mut().rev() {
+            *group = (value & 31) as u8;
+            value >>= 5;
+        }
+        groups
+    }
+
+    fn field(tag: u8, data: Vec<u8>) -> Vec<u8> {
+        let mut field = vec![tag];
+        field.extend(int(data.len() as u64, 2));
+        field.extend(data);
+        field
+    }
+
+    fn payment_hash() -> Vec<u8> {
+        field(
+            TAG_PAYMENT_HASH,
+            groups(&hex::decode(PAYMENT_HASH).unwrap()),
+        )
+    }
+
+    /// Encodes and signs an invoice with the example payee key.
+    fn encode(hrp: &str, fields: &[Vec<u8>]) -> String {
+        let mut data = int(TIMESTAMP, TIMESTAMP_LEN);
+        data.extend(fields.concat());
+
+        let mut preimage = hrp.as_bytes().to_vec();
+        preimage.extend(to_bytes_padded(&data));
+        let secret = SecretKey::from_slice(&hex::decode(PAYEE_SECRET).unwrap()).unwrap();
+        let signature =
+            SECP256K1.sign_ecdsa_recoverable(&Message::from_digest(sha256(&preimage)), &secret);
+        let (recovery_id, compact) = signature.serialize_compact();
+        let mut signature = compact.to_vec();
+        signature.push(recovery_id.to_i32() as u8);
+        data.extend(groups(&signature));
+
+        let hrp = Hrp::parse(hrp).unwrap();
+        data.into_iter()
+            .map(|g| Fe32::try_from(g).unwrap())
+            .with_checksum::<Bolt11Bech32>(&hrp)
+            .chars()
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        let description = field(TAG_DESCRIPTION, groups(b"1 cup coffee"));
+        let expiry = field(TAG_EXPIRY, int(60, 2));
+        let encoded = encode("lnbc2500u", &[payment_hash(), description, expiry]);
+        // The "cup of coffee" example from BOLT-11
+        assert_eq!(
+            encoded,
+            "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp"
+        );
+
+        let invoice = Invoice::parse(&encoded, Network::Bitcoin).unwrap();
+        assert_eq!(invoice.network(), Network::Bitcoin);
+        assert_eq!(invoice.amount_msat(), Some(250_000_000));
+        assert_eq!(invoice.timestamp(), TIMESTAMP);
+        assert_eq!(hex::encode(invoice.payment_hash()), PAYMENT_HASH);
+        assert_eq!(invoice.description(), Some("1 cup coffee"));
+        assert_eq!(invoice.payee().to_string(), PAYEE);
+        assert_eq!(invoice.expiry(), 60);
+        assert_eq!(invoice.expires_at(), TIMESTAMP + 60);
+        assert!(!invoice.is_expired(TIMESTAMP + 59));
+        assert!(invoice.is_expired(TIMESTAMP + 60));
+        assert_eq!(
+            invoice.min_final_cltv_expiry_delta(),
+            DEFAULT_MIN_FINAL_CLTV_EXPIRY_DELTA
+        );
+        assert!(invoice.payment_secret().is_none());
+        assert_eq!(invoice.to_string(), encoded);
+
+        // QR codes carry upper-case invoices behind a URI scheme
+        let scanned = format!("LIGHTNING:{}", encoded.to_uppercase());
+        assert_eq!(scanned.parse::<Invoice>().unwrap(), invoice);
+
+        assert!(matches!(
+            Invoice::parse(&encoded, Network::Testnet),
+            Err(Error::InvalidInvoice(_))
+        ));
+    }
+
+    #[test]
+    fn test_all_fields() {
+        let payee = PublicKey::from_str(PAYEE).unwrap();
+        let mut hop = payee.serialize().to_vec();
+        hop.extend_from_slice(&(700_000u64 << 40 | 1_234 << 16 | 1).to_be_bytes());
+        hop.extend_from_slice(&1_000u32.to_be_bytes());
+        hop.extend_from_slice(&100u32.to_be_bytes());
+        hop.extend_from_slice(&144u16.to_be_bytes());
+        let two_hops = [hop.clone(), hop].concat();
+
+        let mut fallback = vec![17];
+        fallback.extend(groups(&[0x11; 20]));
+        let mut witness_fallback = vec![0];
+        witness_fallback.extend(groups(&[0x22; 20]));
+        let mut unknown_fallback = vec![19];
+        unknown_fallback.extend(groups(&[0x33; 20]));
+
+        let encoded = encode(
+            "lntb20m",
+            &[
+                payment_hash(),
+                field(TAG_PAYMENT_SECRET, groups(&[0x11; 32])),
+                field(TAG_DESCRIPTION_HASH, groups(&sha256(b"a long description"))),
+                field(TAG_PAYEE, groups(&payee.serialize())),
+                field(TAG_MIN_FINAL_CLTV_EXPIRY_DELTA, int(144, 2)),
+                field(TAG_FALLBACK, fallback),
+                field(TAG_FALLBACK, witness_fallback),
+                field(TAG_FALLBACK, unknown_fallback),
+                field(TAG_ROUTE_HINT, groups(&two_hops)),
+                // Bits 8 and 14, like LDK's var_onion_optin and payment_secret
+                field(TAG_FEATURES, int(1 << 8 | 1 << 14, 3)),
+                // A payment hash of the wrong length and an unknown field
+                field(TAG_PAYMENT_HASH, groups(&[0xff; 20])),
+                field(0, groups(b"future")),
+            ],
+        );
+
+        let invoice = Invoice::parse(&encoded, Network::Testnet).unwrap();
+        assert_eq!(invoice.amount_msat(), Some(2_000_000_000));
+        assert_eq!(hex::encode(invoice.payment_hash()), PAYMENT_HASH);
+        assert_eq!(invoice.payment_secret(), Some(&[0x11; 32]));
+        assert_eq!(invoice.description(), None);
+        assert!(invoice.matches_description("a long description"));
+        assert!(!invoice.matches_description("another description"));
+        assert_eq!(invoice.expiry(), DEFAULT_EXPIRY);
+        assert_eq!(invoice.min_final_cltv_expiry_delta(), 144);
+        assert_eq!(invoice.features(), &[8, 14]);
+
+        assert_eq!(invoice.fallbacks().len(), 2);
+        assert_eq!(
+            invoice.fallbacks()[0].payload(),
+            &Payload::PubkeyHash([0x11; 20])
+        );
+        assert!(invoice.fallbacks()[1].script_pubkey().is_p2wpkh());
+        assert_eq!(invoice.fallbacks()[1].network(), Network::Testnet);
+
+        assert_eq!(invoice.route_hints().len(), 1);
+        let hops = &invoice.route_hints()[0];
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0].node_id, payee);
+        assert_eq!(hops[0].short_channel_id_string(), "700000x1234x1");
+        assert_eq!(hops[0].fee_base_msat, 1_000);
+        assert_eq!(hops[0].fee_proportional_millionths, 100);
+        assert_eq!(hops[0].cltv_expiry_delta, 144);
+    }
+
+    #[test]
+    fn test_amounts() {
+        for (hrp, network, msat) in [
+            ("lnbc", Network::Bitcoin, None),
+            ("lnbc1", Network::Bitcoin, Some(MSAT_PER_BTC)),
+            ("lnbc2500u", Network::Bitcoin, Some(250_000_000)),
+            ("lnbc10n", Network::Bitcoin, Some(1_000)),
+            ("lnbc10p", Network::Bitcoin, Some(1)),
+            ("lntbs1m", Network::Signet, Some(100_000_000)),
+            ("lnbcrt5u", Network::Regtest, Some(500_000)),
+        ] {
+            assert_eq!(parse_hrp(hrp).unwrap(), (network, msat), "{}", hrp);
+        }
+        for hrp in [
+            "lnxy1m",
+            "bc1m",
+            "lnbc1x",
+            "lnbc01m",
+            "lnbcm",
+            "lnbc15p",
+            "lnbc21000001",
+            "lnbc99999999999999999999",
+        ] {
+            assert!(parse_hrp(hrp).is_err(), "{} should be rejected", hrp);
+        }
+    }
+
+    #[test]
+    fn test_rejects() {
+        let valid = encode("lnbc", &[payment_hash()]);
+        assert!(valid.parse::<Invoice>().is_ok());
+
+        // Bad checksum
+        let mut corrupted = valid.clone().into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+        assert!(String::from_utf8(corrupted)
+            .unwrap()
+            .parse::<Invoice>()
+            .is_err());
+
+        // No payment hash
+        let no_hash = encode("lnbc", &[field(TAG_DESCRIPTION, groups(b"coffee"))]);
+        assert!(matches!(
+            no_hash.parse::<Invoice>(),
+            Err(Error::InvalidInvoice(e)) if e == "missing payment hash"
+        ));
+
+        // Signed by a key other than the stated payee
+        let other =
+            PublicKey::from_secret_key(SECP256K1, &SecretKey::from_slice(&[1; 32]).unwrap());
+        let wrong_payee = encode(
+            "lnbc",
+            &[payment_hash(), field(TAG_PAYEE, groups(&other.serialize()))],
+        );
+        assert!(matches!(
+            wrong_payee.parse::<Invoice>(),
+            Err(Error::InvalidInvoice(e)) if e == "signature is not from the payee"
+        ));
+
+        // A field running past the signature
+        let truncated = encode("lnbc", &[payment_hash(), vec![TAG_DESCRIPTION, 3, 31]]);
+        assert!(truncated.parse::<Invoice>().is_err());
+
+        // Not an invoice
+        assert!("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+            .parse::<Invoice>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_to_u64_checked() {
+        assert_eq!(to_u64_checked(&int(u64::MAX, 13), "x").unwrap(), u64::MAX);
+        assert_eq!(to_u64_checked(&int(60, 20), "x").unwrap(), 60);
+        assert!(to_u64_checked(&[16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], "x").is_err());
+        assert!(to_u64_checked(&[1; 14], "x").is_err());
+    }
+}
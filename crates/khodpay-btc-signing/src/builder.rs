@@ -0,0 +1,1083 @@
+//! Transaction builder for single-key (P2PKH, P2SH-P2WPKH and P2WPKH) wallets.
+
+use std::cmp::Ordering;
+
+use rand::rngs::OsRng;
+use rand::Rng;
+
+use crate::fee::{self, FeeRate};
+use crate::psbt::{Psbt, PsbtInput, PsbtOutput};
+use crate::sighash::SIGHASH_ALL;
+use crate::transaction::{SEQUENCE_LOCKTIME_NO_RBF, SEQUENCE_RBF};
+use crate::{
+    Address, BtcSigner, Error, Network, OutPoint, Result, Script, ScriptType, Transaction, TxIn,
+    TxOut,
+};
+
+/// Largest possible amount: 21 million BTC in satoshis.
+pub const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+
+/// An unspent output the builder can spend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utxo {
+    /// Location of the output.
+    pub outpoint: OutPoint,
+    /// Amount in satoshis.
+    pub value: u64,
+    /// The output's locking script.
+    pub script_pubkey: Script,
+}
+
+impl Utxo {
+    /// Creates a UTXO.
+    pub fn new(outpoint: OutPoint, value: u64, script_pubkey: Script) -> Self {
+        Self {
+            outpoint,
+            value,
+            script_pubkey,
+        }
+    }
+}
+
+/// How the builder orders inputs and outputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TxOrdering {
+    /// Inputs and outputs in the order they were added, change last.
+    #[default]
+    AsAdded,
+    /// BIP-69 lexicographic order: inputs by previous txid and index, outputs by
+    /// amount and script. The change output is sorted with the others.
+    Bip69,
+    /// Inputs and outputs in the order they were added, with the change output at a
+    /// random position so it cannot be told apart by being last.
+    RandomChange,
+}
+
+/// What the builder does with change below the dust limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ChangePolicy {
+    /// Leave the change output out and add its value to the fee.
+    #[default]
+    DropToFee,
+    /// Refuse to build, so no value goes to the fee unnoticed; add inputs or raise
+    /// an output instead.
+    RejectDust,
+}
+
+/// Builder for signed single-key transactions.
+///
+/// The caller picks the UTXOs, recipients and either an absolute fee or a fee rate;
+/// whatever the inputs carry beyond outputs and fee goes to the change address. Each
+/// input is signed by the [`BtcSigner`] whose P2PKH, P2SH-P2WPKH or P2WPKH script it
+/// pays.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::{
+///     Address, BtcSigner, Network, OutPoint, TransactionBuilder, Utxo,
+/// };
+///
+/// let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Testnet).unwrap();
+/// let utxo = Utxo::new(
+///     OutPoint::new(
+///         "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098".parse().unwrap(),
+///         0,
+///     ),
+///     100_000,
+///     signer.p2wpkh_address().script_pubkey(),
+/// );
+/// let recipient = Address::parse("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Testnet).unwrap();
+///
+/// let tx = TransactionBuilder::new(Network::Testnet)
+///     .add_utxo(utxo)
+///     .add_output(&recipient, 60_000)
+///     .change_address(&signer.p2wpkh_address())
+///     .fee(1_000)
+///     .sign(&[&signer])
+///     .unwrap();
+///
+/// assert_eq!(tx.outputs[1].value, 39_000);
+/// let raw_hex = tx.to_hex(); // ready for `sendrawtransaction`
+/// # let _ = raw_hex;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    network: Network,
+    version: i32,
+    lock_time: u32,
+    sequence: u32,
+    utxos: Vec<Utxo>,
+    outputs: Vec<(Option<Address>, TxOut)>,
+    change: Option<Address>,
+    fee: Option<Fee>,
+    ordering: TxOrdering,
+    dust_relay_fee: FeeRate,
+    change_policy: ChangePolicy,
+}
+
+/// How the builder's fee is set.
+#[derive(Debug, Clone, Copy)]
+enum Fee {
+    Absolute(u64),
+    Rate(FeeRate),
+}
+
+impl TransactionBuilder {
+    /// Creates a builder for `network` producing version 2 transactions.
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            version: 2,
+            lock_time: 0,
+            sequence: SEQUENCE_LOCKTIME_NO_RBF,
+            utxos: Vec::new(),
+            outputs: Vec::new(),
+            change: None,
+            fee: None,
+            ordering: TxOrdering::AsAdded,
+            dust_relay_fee: FeeRate::DUST_RELAY,
+            change_policy: ChangePolicy::DropToFee,
+        }
+    }
+
+    /// Sets the transaction version.
+    pub fn version(mut self, version: i32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets `nLockTime` (block height below 500,000,000, otherwise a Unix timestamp).
+    pub fn lock_time(mut self, lock_time: u32) -> Self {
+        self.lock_time = lock_time;
+        self
+    }
+
+    /// Signals replace-by-fee (BIP-125) on every input, so the transaction can later be
+    /// replaced with [`bump_fee`](crate::rbf::bump_fee). Off by default.
+    pub fn rbf(mut self, enabled: bool) -> Self {
+        self.sequence = if enabled {
+            SEQUENCE_RBF
+        } else {
+            SEQUENCE_LOCKTIME_NO_RBF
+        };
+        self
+    }
+
+    /// Sets how inputs and outputs are ordered, [`TxOrdering::AsAdded`] by default.
+    pub fn ordering(mut self, ordering: TxOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Sets the dust relay fee the per-script-type dust limits are derived from,
+    /// [`FeeRate::DUST_RELAY`] by default. See [`dust_threshold`](crate::dust_threshold).
+    pub fn dust_relay_fee(mut self, dust_relay_fee: FeeRate) -> Self {
+        self.dust_relay_fee = dust_relay_fee;
+        self
+    }
+
+    /// Sets what happens to change below the dust limit, [`ChangePolicy::DropToFee`]
+    /// by default.
+    pub fn change_policy(mut self, change_policy: ChangePolicy) -> Self {
+        self.change_policy = change_policy;
+        self
+    }
+
+    /// Adds an input spending `utxo`.
+    pub fn add_utxo(mut self, utxo: Utxo) -> Self {
+        self.utxos.push(utxo);
+        self
+    }
+
+    /// Adds inputs spending every UTXO in `utxos`.
+    pub fn add_utxos(mut self, utxos: impl IntoIterator<Item = Utxo>) -> Self {
+        self.utxos.extend(utxos);
+        self
+    }
+
+    /// Pays `value` satoshis to `address`.
+    pub fn add_output(mut self, address: &Address, value: u64) -> Self {
+        let output = TxOut {
+            value,
+            script_pubkey: address.script_pubkey(),
+        };
+        self.outputs.push((Some(address.clone()), output));
+        self
+    }
+
+    /// Pays `value` satoshis to a raw output script.
+    pub fn add_output_script(mut self, script_pubkey: Script, value: u64) -> Self {
+        self.outputs.push((
+            None,
+            TxOut {
+                value,
+                script_pubkey,
+            },
+        ));
+        self
+    }
+
+    /// Sends whatever remains after outputs and fee to `address`.
+    pub fn change_address(mut self, address: &Address) -> Self {
+        self.change = Some(address.clone());
+        self
+    }
+
+    /// Sets the absolute fee in satoshis, replacing any fee rate.
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = Some(Fee::Absolute(fee));
+        self
+    }
+
+    /// Pays `fee_rate` on the estimated size of the signed transaction, replacing any
+    /// absolute fee.
+    ///
+    /// See [`build_unsigned`](Self::build_unsigned) for how the change output is sized.
+    pub fn fee_rate(mut self, fee_rate: FeeRate) -> Self {
+        self.fee = Some(Fee::Rate(fee_rate));
+        self
+    }
+
+    /// Returns the sum of the added UTXOs, or `None` on overflow.
+    pub fn input_value(&self) -> Option<u64> {
+        self.utxos
+            .iter()
+            .try_fold(0u64, |total, utxo| total.checked_add(utxo.value))
+    }
+
+    /// Estimates the virtual size of the signed transaction, with a change output if a
+    /// change address is set.
+    ///
+    /// Works before a fee is chosen, so fee sliders can show totals up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ValidationError`] if an input is not P2PKH, P2SH or P2WPKH.
+    pub fn estimate_vsize(&self) -> Result<u64> {
+        self.estimate_vsize_with(self.change.as_ref())
+    }
+
+    fn estimate_vsize_with(&self, change: Option<&Address>) -> Result<u64> {
+        let mut outputs: Vec<Script> = self
+            .outputs
+            .iter()
+            .map(|(_, output)| output.script_pubkey.clone())
+            .collect();
+        outputs.extend(change.map(Address::script_pubkey));
+        Ok(fee::estimate_vsize(&input_types(&self.utxos)?, &outputs))
+    }
+
+    /// Builds the transaction without signatures.
+    ///
+    /// With a [`fee_rate`](Self::fee_rate) the fee covers the estimated size including
+    /// the change output. Change below the dust limit of the change address's script
+    /// type, or too small to pay for its own output, is handled by the
+    /// [`change_policy`](Self::change_policy): by default it goes to the fee.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ValidationError`] if inputs, outputs or the fee are missing or
+    /// out of range, an output is below its dust limit, or dust change is rejected;
+    /// [`Error::InvalidAddress`] for an address of another network, and
+    /// [`Error::InsufficientFunds`] if the inputs do not cover outputs plus fee. Without
+    /// a change address the inputs must match outputs plus fee exactly, so a forgotten
+    /// change output cannot silently become fee.
+    pub fn build_unsigned(&self) -> Result<Transaction> {
+        if self.utxos.is_empty() {
+            return Err(Error::ValidationError("no inputs".to_string()));
+        }
+        if self.outputs.is_empty() {
+            return Err(Error::ValidationError("no outputs".to_string()));
+        }
+        let fee = match self.fee {
+            Some(Fee::Absolute(fee)) => fee,
+            Some(Fee::Rate(fee_rate)) => self.fee_at_rate(fee_rate)?,
+            None => return Err(Error::ValidationError("fee is required".to_string())),
+        };
+
+        for address in self
+            .outputs
+            .iter()
+            .filter_map(|(address, _)| address.as_ref())
+            .chain(self.change.as_ref())
+        {
+            if !address.is_valid_for(self.network) {
+                return Err(Error::InvalidAddress(format!(
+                    "{} is not a {} address",
+                    address, self.network
+                )));
+            }
+        }
+
+        let available = self
+            .input_value()
+            .filter(|value| *value <= MAX_MONEY)
+            .ok_or_else(|| Error::ValidationError("input value out of range".to_string()))?;
+        let mut outputs: Vec<TxOut> = self.outputs.iter().map(|(_, out)| out.clone()).collect();
+        let required = outputs
+            .iter()
+            .try_fold(fee, |total, output| total.checked_add(output.value))
+            .filter(|value| *value <= MAX_MONEY)
+            .ok_or_else(|| Error::ValidationError("output value out of range".to_string()))?;
+
+        for (index, output) in outputs.iter().enumerate() {
+            let dust = fee::dust_threshold(&output.script_pubkey, self.dust_relay_fee);
+            if output.value < dust {
+                return Err(Error::ValidationError(format!(
+                    "output {} of {} sats is below the dust limit of {} sats",
+                    index, output.value, dust
+                )));
+            }
+        }
+
+        if available < required {
+            return Err(Error::InsufficientFunds {
+                required,
+                available,
+            });
+        }
+        let change = available - required;
+        if change > 0 {
+            let address = self.change.as_ref().ok_or_else(|| {
+                Error::ValidationError(format!(
+                    "inputs exceed outputs plus fee by {} sats but no change address is set",
+                    change
+                ))
+            })?;
+            let change = TxOut {
+                value: change,
+                script_pubkey: address.script_pubkey(),
+            };
+            let dust = fee::dust_threshold(&change.script_pubkey, self.dust_relay_fee);
+            if change.value < dust {
+                return match self.change_policy {
+                    ChangePolicy::DropToFee => self.finish(outputs),
+                    ChangePolicy::RejectDust => Err(Error::ValidationError(format!(
+                        "change of {} sats is below the dust limit of {} sats",
+                        change.value, dust
+                    ))),
+                };
+            }
+            let position = match self.ordering {
+                TxOrdering::RandomChange => OsRng.gen_range(0..=outputs.len()),
+                TxOrdering::AsAdded | TxOrdering::Bip69 => outputs.len(),
+            };
+            outputs.insert(position, change);
+        }
+        self.finish(outputs)
+    }
+
+    /// Assembles the transaction from the final outputs, applying the ordering.
+    fn finish(&self, mut outputs: Vec<TxOut>) -> Result<Transaction> {
+        if self.ordering == TxOrdering::Bip69 {
+            outputs.sort_by(bip69_output_order);
+        }
+
+        Ok(Transaction {
+            version: self.version,
+            inputs: self
+                .ordered_utxos()
+                .iter()
+                .map(|utxo| TxIn::new(utxo.outpoint, self.sequence))
+                .collect(),
+            outputs,
+            lock_time: self.lock_time,
+        })
+    }
+
+    /// Returns the UTXOs in input order.
+    fn ordered_utxos(&self) -> Vec<Utxo> {
+        let mut utxos = self.utxos.clone();
+        if self.ordering == TxOrdering::Bip69 {
+            utxos.sort_by(|a, b| bip69_input_order(&a.outpoint, &b.outpoint));
+        }
+        utxos
+    }
+
+    /// Returns the fee paying `fee_rate`, with a change output when the inputs leave
+    /// enough for one above the dust limit.
+    fn fee_at_rate(&self, fee_rate: FeeRate) -> Result<u64> {
+        let with_change = fee_rate.fee_for_vsize(self.estimate_vsize()?);
+        let Some(change) = &self.change else {
+            return Ok(with_change);
+        };
+        let without_change = fee_rate.fee_for_vsize(self.estimate_vsize_with(None)?);
+        let dust = fee::dust_threshold(&change.script_pubkey(), self.dust_relay_fee);
+        let spent = self
+            .outputs
+            .iter()
+            .try_fold(with_change, |total, (_, output)| {
+                total.checked_add(output.value)
+            });
+        match (self.input_value(), spent) {
+            // Too little for a change output above the dust limit.
+            (Some(available), Some(spent)) if available < spent.saturating_add(dust) => {
+                let outputs = spent - with_change;
+                let leftover = available
+                    .saturating_sub(outputs)
+                    .saturating_sub(without_change);
+                match self.change_policy {
+                    // The leftover becomes fee.
+                    ChangePolicy::DropToFee => Ok(without_change + leftover),
+                    ChangePolicy::RejectDust if leftover == 0 => Ok(without_change),
+                    ChangePolicy::RejectDust => Err(Error::ValidationError(format!(
+                        "{} sats left over cannot pay for change above the dust limit of {} sats",
+                        leftover, dust
+                    ))),
+                }
+            }
+            _ => Ok(with_change),
+        }
+    }
+
+    /// Builds the transaction as a PSBTv2 for external signers.
+    ///
+    /// Each input carries its witness UTXO, so hardware wallets can verify amounts and
+    /// fee. Inputs and outputs are marked non-modifiable: the change output was sized
+    /// for exactly this set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`build_unsigned`](Self::build_unsigned).
+    pub fn build_psbt(&self) -> Result<Psbt> {
+        let tx = self.build_unsigned()?;
+        let mut psbt = Psbt::new(tx.version);
+        if tx.lock_time != 0 {
+            psbt.fallback_lock_time = Some(tx.lock_time);
+        }
+        for (txin, utxo) in tx.inputs.iter().zip(&self.ordered_utxos()) {
+            let mut input = PsbtInput::new(txin.previous_output);
+            input.sequence = Some(txin.sequence);
+            input.witness_utxo = Some(TxOut {
+                value: utxo.value,
+                script_pubkey: utxo.script_pubkey.clone(),
+            });
+            psbt.add_input(input)?;
+        }
+        for output in tx.outputs {
+            psbt.add_output(PsbtOutput::new(output.value, output.script_pubkey))?;
+        }
+        psbt.set_inputs_modifiable(false);
+        psbt.set_outputs_modifiable(false);
+        Ok(psbt)
+    }
+
+    /// Builds and signs the transaction.
+    ///
+    /// Every input must pay a P2PKH, P2SH-P2WPKH or P2WPKH script of one of
+    /// `signers`; P2PKH inputs get the legacy signature hash, the others BIP-143.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`build_unsigned`](Self::build_unsigned), or
+    /// [`Error::SigningError`] if no signer owns an input.
+    pub fn sign(&self, signers: &[&BtcSigner]) -> Result<Transaction> {
+        #[cfg(feature = "tracing")]
+        let operation = khodpay_tracing::operation!(
+            "sign_transaction",
+            network = self.network,
+            inputs = _,
+            signed = _
+        );
+
+        let mut tx = self.build_unsigned()?;
+        #[cfg(feature = "tracing")]
+        operation.record("inputs", &tx.inputs.len());
+        sign_inputs(&mut tx, &self.ordered_utxos(), signers)?;
+        #[cfg(feature = "tracing")]
+        operation.record("signed", &tx.inputs.len());
+        Ok(tx)
+    }
+}
+
+/// BIP-69 input order: previous txid as displayed (reversed bytes), then index.
+fn bip69_input_order(a: &OutPoint, b: &OutPoint) -> Ordering {
+    a.txid
+        .as_bytes()
+        .iter()
+        .rev()
+        .cmp(b.txid.as_bytes().iter().rev())
+        .then(a.vout.cmp(&b.vout))
+}
+
+/// BIP-69 output order: amount, then script bytes.
+fn bip69_output_order(a: &TxOut, b: &TxOut) -> Ordering {
+    a.value
+        .cmp(&b.value)
+        .then_with(|| a.script_pubkey.as_bytes().cmp(b.script_pubkey.as_bytes()))
+}
+
+/// Returns the script types of `utxos`, for size estimation.
+pub(crate) fn input_types(utxos: &[Utxo]) -> Result<Vec<ScriptType>> {
+    utxos
+        .iter()
+        .enumerate()
+        .map(|(index, utxo)| {
+            ScriptType::of_script_pubkey(&utxo.script_pubkey).ok_or_else(|| {
+                Error::ValidationError(format!(
+                    "cannot estimate the size of input {} ({})",
+                    index, utxo.outpoint
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Signs every input of `tx`, which spends `utxos` in order, with the owning signer.
+pub(crate) fn sign_inputs(
+    tx: &mut Transaction,
+    utxos: &[Utxo],
+    signers: &[&BtcSigner],
+) -> Result<()> {
+    for (index, utxo) in utxos.iter().enumerate() {
+        let (signer, script_type) = signers
+            .iter()
+            .find_map(|signer| {
+                signer
+                    .script_type_of(&utxo.script_pubkey)
+                    .map(|script_type| (signer, script_type))
+            })
+            .ok_or_else(|| {
+                Error::SigningError(format!("no key for input {} ({})", index, utxo.outpoint))
+            })?;
+
+        let signature = signer.sign_input(tx, index, script_type, utxo.value, SIGHASH_ALL)?;
+        let (script_sig, witness) = script_type.satisfaction(&signer.public_key(), &signature);
+        tx.inputs[index].script_sig = script_sig;
+        tx.inputs[index].witness = witness;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sighash;
+    use crate::{ScriptType, Txid};
+    use secp256k1::ecdsa::Signature;
+    use secp256k1::{Message, PublicKey, SECP256K1};
+
+    fn signer(byte: u8) -> BtcSigner {
+        BtcSigner::from_private_key(&[byte; 32], Network::Bitcoin).unwrap()
+    }
+
+    fn utxo(signer: &BtcSigner, vout: u32, value: u64) -> Utxo {
+        Utxo::new(
+            OutPoint::new(Txid::from_bytes([0xaa; 32]), vout),
+            value,
+            signer.p2wpkh_address().script_pubkey(),
+        )
+    }
+
+    fn recipient() -> Address {
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+            .parse()
+            .unwrap()
+    }
+
+    fn verify_input(tx: &Transaction, index: usize, value: u64) {
+        let witness = &tx.inputs[index].witness;
+        assert_eq!(witness.len(), 2);
+        let (der, sighash_type) = witness[0].split_at(witness[0].len() - 1);
+        assert_eq!(sighash_type, &[0x01]);
+
+        let public_key = PublicKey::from_slice(&witness[1]).unwrap();
+        let pubkey_hash = crate::hash::hash160(&witness[1]);
+        let sighash =
+            sighash::segwit_v0(tx, index, &Script::p2pkh(&pubkey_hash), value, SIGHASH_ALL)
+                .unwrap();
+        let signature = Signature::from_der(der).unwrap();
+        SECP256K1
+            .verify_ecdsa(&Message::from_digest(sighash), &signature, &public_key)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sign_with_change() {
+        let alice = signer(1);
+        let tx = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 50_000))
+            .add_output(&recipient(), 30_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee(500)
+            .sign(&[&alice])
+            .unwrap();
+
+        assert_eq!(tx.version, 2);
+        assert_eq!(tx.inputs[0].sequence, SEQUENCE_LOCKTIME_NO_RBF);
+        assert!(tx.inputs[0].script_sig.is_empty());
+        assert_eq!(tx.outputs.len(), 2);
+        assert_eq!(tx.outputs[0].script_pubkey, recipient().script_pubkey());
+        assert_eq!(tx.outputs[1].value, 19_500);
+        verify_input(&tx, 0, 50_000);
+
+        // One P2WPKH input and two P2WPKH outputs with a low-R signature.
+        assert_eq!(tx.vsize(), 141);
+    }
+
+    #[test]
+    fn test_sign_multiple_keys() {
+        let alice = signer(1);
+        let bob = signer(2);
+        let tx = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxos([utxo(&alice, 0, 10_000), utxo(&bob, 1, 20_000)])
+            .add_output(&recipient(), 29_000)
+            .fee(1_000)
+            .lock_time(800_000)
+            .sign(&[&bob, &alice])
+            .unwrap();
+
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.lock_time, 800_000);
+        verify_input(&tx, 0, 10_000);
+        verify_input(&tx, 1, 20_000);
+        assert_eq!(tx.inputs[0].witness[1], alice.public_key().to_vec());
+        assert_eq!(tx.inputs[1].witness[1], bob.public_key().to_vec());
+    }
+
+    #[test]
+    fn test_txid_ignores_witness() {
+        let alice = signer(1);
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .add_output(&recipient(), 9_000)
+            .fee(1_000);
+
+        let unsigned = builder.build_unsigned().unwrap();
+        let signed = builder.sign(&[&alice]).unwrap();
+        assert_eq!(signed.txid(), unsigned.txid());
+        assert_ne!(signed.wtxid(), signed.txid());
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        let alice = signer(1);
+        let err = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .add_output(&recipient(), 9_800)
+            .fee(500)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InsufficientFunds {
+                required: 10_300,
+                available: 10_000
+            }
+        ));
+    }
+
+    #[test]
+    fn test_leftover_requires_change_address() {
+        let alice = signer(1);
+        let err = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .add_output(&recipient(), 5_000)
+            .fee(500)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err.to_string().contains("4500 sats"));
+    }
+
+    #[test]
+    fn test_missing_parts() {
+        let alice = signer(1);
+        let base = TransactionBuilder::new(Network::Bitcoin);
+        assert!(base.clone().fee(1).build_unsigned().is_err());
+        assert!(base
+            .clone()
+            .add_utxo(utxo(&alice, 0, 1_000))
+            .fee(1)
+            .build_unsigned()
+            .is_err());
+        let err = base
+            .add_utxo(utxo(&alice, 0, 1_000))
+            .add_output(&recipient(), 1_000)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err.to_string().contains("fee is required"));
+    }
+
+    #[test]
+    fn test_fee_rate() {
+        let alice = signer(1);
+        let legacy = Utxo::new(
+            OutPoint::new(Txid::from_bytes([0xbb; 32]), 0),
+            40_000,
+            alice.address_for(ScriptType::P2pkh).script_pubkey(),
+        );
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxos([utxo(&alice, 0, 50_000), legacy])
+            .add_output(&recipient(), 60_000)
+            .change_address(&alice.address_for(ScriptType::P2shP2wpkh))
+            .fee_rate(FeeRate::from_sat_per_kvb(2_500));
+
+        let vsize = builder.estimate_vsize().unwrap();
+        let tx = builder.sign(&[&alice]).unwrap();
+        assert_eq!(tx.vsize(), vsize);
+        let fee = 90_000 - tx.output_value().unwrap();
+        assert_eq!(fee, FeeRate::from_sat_per_kvb(2_500).fee_for_vsize(vsize));
+        assert_eq!(tx.outputs.len(), 2);
+
+        // An absolute fee set afterwards wins.
+        let tx = builder.fee(1_000).build_unsigned().unwrap();
+        assert_eq!(tx.outputs[1].value, 29_000);
+    }
+
+    #[test]
+    fn test_fee_rate_drops_unaffordable_change() {
+        let alice = signer(1);
+        let rate = FeeRate::from_sat_per_vb(10);
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_output(&recipient(), 40_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee_rate(rate);
+        // 110 vbytes without change, 141 with it.
+        let without_change = rate.fee_for_vsize(110);
+
+        // 200 sats over the fee without change cannot pay 310 for the change output.
+        let tx = builder
+            .clone()
+            .add_utxo(utxo(&alice, 0, 40_000 + without_change + 200))
+            .build_unsigned()
+            .unwrap();
+        assert_eq!(tx.outputs.len(), 1);
+
+        let err = builder
+            .add_utxo(utxo(&alice, 0, 40_000 + without_change - 1))
+            .build_unsigned()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InsufficientFunds { required, .. } if required == 40_000 + without_change
+        ));
+    }
+
+    #[test]
+    fn test_dust_outputs_and_change() {
+        let alice = signer(1);
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .change_address(&alice.p2wpkh_address());
+
+        // Recipients below the P2WPKH limit of 294 sats are not relayable.
+        let err = builder
+            .clone()
+            .add_output(&recipient(), 293)
+            .fee(1_000)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("output 0 of 293 sats is below the dust limit of 294 sats"));
+
+        // 200 sats of change is dust: dropped to the fee by default, or rejected.
+        let builder = builder.add_output(&recipient(), 9_000).fee(800);
+        let tx = builder.build_unsigned().unwrap();
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.output_value(), Some(9_000));
+        let err = builder
+            .clone()
+            .change_policy(ChangePolicy::RejectDust)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("change of 200 sats is below the dust limit of 294 sats"));
+
+        // A lower dust relay fee lowers the limit to 98 sats.
+        let tx = builder
+            .dust_relay_fee(FeeRate::MIN_RELAY)
+            .change_policy(ChangePolicy::RejectDust)
+            .build_unsigned()
+            .unwrap();
+        assert_eq!(tx.outputs[1].value, 200);
+    }
+
+    #[test]
+    fn test_fee_rate_drops_dust_change() {
+        let alice = signer(1);
+        let rate = FeeRate::from_sat_per_vb(10);
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_output(&recipient(), 40_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee_rate(rate);
+        let with_change = rate.fee_for_vsize(141);
+
+        // Enough for the change output, but only 293 sats of change.
+        let dusty = builder
+            .clone()
+            .add_utxo(utxo(&alice, 0, 40_000 + with_change + 293));
+        let tx = dusty.build_unsigned().unwrap();
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.output_value(), Some(40_000));
+        let err = dusty
+            .change_policy(ChangePolicy::RejectDust)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err.to_string().contains("left over"));
+
+        let tx = builder
+            .add_utxo(utxo(&alice, 0, 40_000 + with_change + 294))
+            .change_policy(ChangePolicy::RejectDust)
+            .build_unsigned()
+            .unwrap();
+        assert_eq!(tx.outputs[1].value, 294);
+    }
+
+    #[test]
+    fn test_estimate_rejects_unknown_inputs() {
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(Utxo::new(
+                OutPoint::new(Txid::from_bytes([0xbb; 32]), 0),
+                10_000,
+                Script::p2wsh(&[0x11; 32]),
+            ))
+            .add_output(&recipient(), 9_000);
+        let err = builder.estimate_vsize().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("cannot estimate the size of input 0"));
+        assert!(builder
+            .fee_rate(FeeRate::MIN_RELAY)
+            .build_unsigned()
+            .is_err());
+    }
+
+    #[test]
+    fn test_bip69_ordering() {
+        let alice = signer(1);
+        // Sorted by the displayed txid, so the last internal byte decides first.
+        let mut low = [0xff; 32];
+        low[31] = 0x00;
+        let mut high = [0x00; 32];
+        high[31] = 0x01;
+        let utxo_at = |txid: [u8; 32], vout: u32| {
+            Utxo::new(
+                OutPoint::new(Txid::from_bytes(txid), vout),
+                10_000,
+                alice.p2wpkh_address().script_pubkey(),
+            )
+        };
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxos([utxo_at(high, 0), utxo_at(low, 3), utxo_at(low, 1)])
+            .add_output_script(Script::p2wpkh(&[0x22; 20]), 5_000)
+            .add_output_script(Script::p2wpkh(&[0x11; 20]), 5_000)
+            .add_output_script(Script::p2pkh(&[0x00; 20]), 1_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee(1_000)
+            .ordering(TxOrdering::Bip69);
+
+        let tx = builder.sign(&[&alice]).unwrap();
+        let outpoints: Vec<OutPoint> = tx.inputs.iter().map(|i| i.previous_output).collect();
+        assert_eq!(
+            outpoints,
+            [
+                OutPoint::new(Txid::from_bytes(low), 1),
+                OutPoint::new(Txid::from_bytes(low), 3),
+                OutPoint::new(Txid::from_bytes(high), 0),
+            ]
+        );
+        let values: Vec<u64> = tx.outputs.iter().map(|o| o.value).collect();
+        assert_eq!(values, [1_000, 5_000, 5_000, 18_000]);
+        assert_eq!(tx.outputs[1].script_pubkey, Script::p2wpkh(&[0x11; 20]));
+        for index in 0..3 {
+            verify_input(&tx, index, 10_000);
+        }
+
+        // The PSBT carries each input's own UTXO.
+        let psbt = builder.build_psbt().unwrap();
+        for (input, txin) in psbt.inputs().iter().zip(&tx.inputs) {
+            assert_eq!(input.previous_output, txin.previous_output);
+        }
+    }
+
+    #[test]
+    fn test_random_change_position() {
+        let alice = signer(1);
+        let change = alice.p2wpkh_address().script_pubkey();
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 100_000))
+            .add_output_script(Script::p2wpkh(&[0x01; 20]), 10_000)
+            .add_output_script(Script::p2wpkh(&[0x02; 20]), 20_000)
+            .add_output_script(Script::p2wpkh(&[0x03; 20]), 30_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee(1_000)
+            .ordering(TxOrdering::RandomChange);
+
+        let mut positions = std::collections::BTreeSet::new();
+        for _ in 0..64 {
+            let tx = builder.build_unsigned().unwrap();
+            let position = tx
+                .outputs
+                .iter()
+                .position(|output| output.script_pubkey == change)
+                .unwrap();
+            positions.insert(position);
+            // The other outputs keep their order.
+            let others: Vec<u64> = tx
+                .outputs
+                .iter()
+                .filter(|output| output.script_pubkey != change)
+                .map(|output| output.value)
+                .collect();
+            assert_eq!(others, [10_000, 20_000, 30_000]);
+        }
+        assert!(positions.len() > 1);
+    }
+
+    #[test]
+    fn test_rejects_other_network_address() {
+        let alice = signer(1);
+        let testnet = Address::parse(
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+            Network::Testnet,
+        )
+        .unwrap();
+        let err = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .add_output(&testnet, 9_000)
+            .fee(1_000)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidAddress(_)));
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_values() {
+        let alice = signer(1);
+        let err = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, MAX_MONEY + 1))
+            .add_output(&recipient(), 1)
+            .fee(0)
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err.to_string().contains("input value out of range"));
+    }
+
+    #[test]
+    fn test_sign_errors() {
+        let alice = signer(1);
+        let bob = signer(2);
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .add_output(&recipient(), 9_000)
+            .fee(1_000);
+        let err = builder.sign(&[&bob]).unwrap_err();
+        assert!(err.to_string().contains("no key for input 0"));
+
+        // A P2WSH output is not a single-key script, even when it hashes the same bytes.
+        let script_hash = Utxo::new(
+            OutPoint::new(Txid::from_bytes([0xbb; 32]), 0),
+            10_000,
+            Script::p2wsh(&[0x11; 32]),
+        );
+        let err = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(script_hash)
+            .add_output(&recipient(), 9_000)
+            .fee(1_000)
+            .sign(&[&alice])
+            .unwrap_err();
+        assert!(err.to_string().contains("no key for input 0"));
+    }
+
+    #[test]
+    fn test_sign_legacy_and_nested_inputs() {
+        let alice = signer(1);
+        let legacy = Utxo::new(
+            OutPoint::new(Txid::from_bytes([0xbb; 32]), 0),
+            20_000,
+            alice.address_for(ScriptType::P2pkh).script_pubkey(),
+        );
+        let nested = Utxo::new(
+            OutPoint::new(Txid::from_bytes([0xcc; 32]), 1),
+            30_000,
+            alice.address_for(ScriptType::P2shP2wpkh).script_pubkey(),
+        );
+        let tx = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(legacy)
+            .add_utxo(nested)
+            .add_output(&recipient(), 49_000)
+            .fee(1_000)
+            .sign(&[&alice])
+            .unwrap();
+
+        let public_key = PublicKey::from_slice(&alice.public_key()).unwrap();
+        let script_code = Script::p2pkh(&alice.pubkey_hash());
+        let verify = |signature: &[u8], sighash: [u8; 32]| {
+            let (sighash_type, der) = signature.split_last().unwrap();
+            assert_eq!(*sighash_type, 0x01);
+            SECP256K1
+                .verify_ecdsa(
+                    &Message::from_digest(sighash),
+                    &Signature::from_der(der).unwrap(),
+                    &public_key,
+                )
+                .unwrap();
+        };
+
+        // P2PKH: <sig> <pubkey> in the scriptSig, no witness.
+        let (script_sig, witness) = (&tx.inputs[0].script_sig, &tx.inputs[0].witness);
+        assert!(witness.is_empty());
+        let signature_len = script_sig.as_bytes()[0] as usize;
+        let signature = &script_sig.as_bytes()[1..1 + signature_len];
+        assert_eq!(
+            &script_sig.as_bytes()[1 + signature_len..],
+            [&[33u8][..], &alice.public_key()].concat().as_slice()
+        );
+        verify(
+            signature,
+            sighash::legacy(&tx, 0, &script_code, SIGHASH_ALL).unwrap(),
+        );
+
+        // P2SH-P2WPKH: the redeem script in the scriptSig, <sig> <pubkey> as witness.
+        let redeem_script = Script::p2wpkh(&alice.pubkey_hash());
+        assert_eq!(
+            tx.inputs[1].script_sig.as_bytes(),
+            [&[22u8][..], redeem_script.as_bytes()].concat().as_slice()
+        );
+        assert_eq!(tx.inputs[1].witness[1], alice.public_key().to_vec());
+        verify(
+            &tx.inputs[1].witness[0],
+            sighash::segwit_v0(&tx, 1, &script_code, 30_000, SIGHASH_ALL).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_build_psbt_matches_sign() {
+        let alice = signer(1);
+        let builder = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 50_000))
+            .add_output(&recipient(), 30_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee(500)
+            .lock_time(800_000);
+
+        let mut psbt = builder.build_psbt().unwrap();
+        assert!(!psbt.inputs_modifiable());
+        assert!(!psbt.outputs_modifiable());
+        assert_eq!(psbt.lock_time().unwrap(), 800_000);
+        assert_eq!(
+            psbt.inputs()[0].witness_utxo.as_ref().unwrap().value,
+            50_000
+        );
+
+        assert_eq!(psbt.sign(&alice).unwrap(), 1);
+        assert_eq!(psbt.finalize().unwrap(), 1);
+        assert_eq!(psbt.extract_tx().unwrap(), builder.sign(&[&alice]).unwrap());
+    }
+
+    #[test]
+    fn test_raw_output_script() {
+        let alice = signer(1);
+        let tx = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxo(utxo(&alice, 0, 10_000))
+            .add_output_script(Script::new(vec![0x6a, 0x01, 0x42]), 0)
+            .add_output(&recipient(), 9_000)
+            .fee(1_000)
+            .sign(&[&alice])
+            .unwrap();
+        assert_eq!(tx.outputs[0].script_pubkey.to_hex(), "6a0142");
+    }
+}
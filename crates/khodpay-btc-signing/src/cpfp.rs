@@ -0,0 +1,235 @@
+//! Child-pays-for-parent (CPFP) fee bumping.
+//!
+//! An incoming transaction stuck at a low fee rate cannot be replaced by the receiver,
+//! but its outputs can be spent while it is unconfirmed. Miners evaluate the parent
+//! and such a child as a package, so a child paying enough gets both mined. [`plan`]
+//! picks the output to spend and sizes the child's fee for a target package rate;
+//! [`CpfpPlan::sign`] builds the child.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::cpfp;
+//! use khodpay_btc_signing::{
+//!     BtcSigner, FeeRate, Network, OutPoint, Transaction, TxIn, TxOut, Txid, SEQUENCE_FINAL,
+//! };
+//!
+//! let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+//! // An incoming payment, paying 150 sats of fee.
+//! let parent = Transaction {
+//!     version: 2,
+//!     inputs: vec![TxIn::new(OutPoint::new(Txid::from_bytes([7; 32]), 0), SEQUENCE_FINAL)],
+//!     outputs: vec![TxOut { value: 80_000, script_pubkey: signer.p2wpkh_address().script_pubkey() }],
+//!     lock_time: 0,
+//! };
+//!
+//! let plan = cpfp::plan(&parent, 150, &signer.p2wpkh_address(), FeeRate::from_sat_per_vb(20), &[&signer]).unwrap();
+//! assert!(plan.package_fee_rate >= FeeRate::from_sat_per_vb(20));
+//! let child = plan.sign(&[&signer]).unwrap();
+//! assert_eq!(child.inputs[0].previous_output, OutPoint::new(parent.txid(), 0));
+//! ```
+
+use crate::fee::{self, FeeRate};
+use crate::{Address, BtcSigner, Error, OutPoint, Result, Transaction, TransactionBuilder, Utxo};
+
+/// A child transaction sized to lift its parent to a target package fee rate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpfpPlan {
+    /// The parent output the child spends.
+    pub utxo: Utxo,
+    /// Where the child sends the output's value, less its fee.
+    pub destination: Address,
+    /// Fee the child pays.
+    pub child_fee: u64,
+    /// Estimated virtual size of the signed child.
+    pub child_vsize: u64,
+    /// Fee rate of parent and child together.
+    pub package_fee_rate: FeeRate,
+}
+
+impl CpfpPlan {
+    /// Returns a builder for the child, for further settings such as RBF or lock time.
+    pub fn builder(&self) -> TransactionBuilder {
+        TransactionBuilder::new(self.destination.network())
+            .add_utxo(self.utxo.clone())
+            .add_output(&self.destination, self.utxo.value - self.child_fee)
+            .fee(self.child_fee)
+    }
+
+    /// Builds and signs the child.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`TransactionBuilder::sign`].
+    pub fn sign(&self, signers: &[&BtcSigner]) -> Result<Transaction> {
+        self.builder().sign(signers)
+    }
+}
+
+/// Plans a child spending `parent` to `destination` so that parent and child together
+/// pay `target`.
+///
+/// `parent_fee` is the fee the parent pays, which cannot be derived from the
+/// transaction alone. The child spends the largest output paying one of `signers`,
+/// and pays at least `target` on its own size even if the parent already meets it.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] if no output of `parent` pays one of `signers`,
+/// and [`Error::InsufficientFunds`] if that output cannot cover the child's fee and
+/// leave at least the dust limit of `destination` at [`FeeRate::DUST_RELAY`].
+pub fn plan(
+    parent: &Transaction,
+    parent_fee: u64,
+    destination: &Address,
+    target: FeeRate,
+    signers: &[&BtcSigner],
+) -> Result<CpfpPlan> {
+    let (vout, output, script_type) = parent
+        .outputs
+        .iter()
+        .enumerate()
+        .filter_map(|(vout, output)| {
+            signers
+                .iter()
+                .find_map(|signer| signer.script_type_of(&output.script_pubkey))
+                .map(|script_type| (vout, output, script_type))
+        })
+        .max_by_key(|(_, output, _)| output.value)
+        .ok_or_else(|| {
+            Error::ValidationError("no output of the parent pays one of the signers".to_string())
+        })?;
+
+    let parent_vsize = parent.vsize();
+    let child_vsize = fee::estimate_vsize(&[script_type], &[destination.script_pubkey()]);
+    let package_vsize = parent_vsize + child_vsize;
+    let child_fee = target
+        .fee_for_vsize(package_vsize)
+        .saturating_sub(parent_fee)
+        .max(target.fee_for_vsize(child_vsize));
+    let dust = fee::dust_threshold(&destination.script_pubkey(), FeeRate::DUST_RELAY);
+    if output.value < child_fee.saturating_add(dust) {
+        return Err(Error::InsufficientFunds {
+            required: child_fee.saturating_add(dust),
+            available: output.value,
+        });
+    }
+
+    Ok(CpfpPlan {
+        utxo: Utxo::new(
+            OutPoint::new(parent.txid(), vout as u32),
+            output.value,
+            output.script_pubkey.clone(),
+        ),
+        destination: destination.clone(),
+        child_fee,
+        child_vsize,
+        package_fee_rate: FeeRate::from_fee(parent_fee.saturating_add(child_fee), package_vsize)
+            .unwrap_or(FeeRate::ZERO),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Network, ScriptType, TxIn, TxOut, Txid, SEQUENCE_FINAL};
+
+    fn signer(byte: u8) -> BtcSigner {
+        BtcSigner::from_private_key(&[byte; 32], Network::Bitcoin).unwrap()
+    }
+
+    fn parent(alice: &BtcSigner) -> Transaction {
+        let stranger = signer(9);
+        Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(
+                OutPoint::new(Txid::from_bytes([0xaa; 32]), 0),
+                SEQUENCE_FINAL,
+            )],
+            outputs: vec![
+                TxOut {
+                    value: 500_000,
+                    script_pubkey: stranger.p2wpkh_address().script_pubkey(),
+                },
+                TxOut {
+                    value: 20_000,
+                    script_pubkey: alice.address_for(ScriptType::P2pkh).script_pubkey(),
+                },
+                TxOut {
+                    value: 30_000,
+                    script_pubkey: alice.p2wpkh_address().script_pubkey(),
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_plan_reaches_package_rate() {
+        let alice = signer(1);
+        let parent = parent(&alice);
+        let target = FeeRate::from_sat_per_vb(25);
+        let plan = plan(&parent, 200, &alice.p2wpkh_address(), target, &[&alice]).unwrap();
+
+        // The largest output of ours, not the stranger's.
+        assert_eq!(plan.utxo.outpoint, OutPoint::new(parent.txid(), 2));
+        assert_eq!(plan.utxo.value, 30_000);
+        assert_eq!(plan.child_vsize, 110);
+        assert_eq!(
+            plan.child_fee,
+            target.fee_for_vsize(parent.vsize() + 110) - 200
+        );
+        assert!(plan.package_fee_rate >= target);
+
+        let child = plan.sign(&[&alice]).unwrap();
+        assert_eq!(child.vsize(), plan.child_vsize);
+        assert_eq!(child.output_value(), Some(30_000 - plan.child_fee));
+        assert_eq!(
+            child.outputs[0].script_pubkey,
+            alice.p2wpkh_address().script_pubkey()
+        );
+    }
+
+    #[test]
+    fn test_plan_when_parent_already_pays() {
+        let alice = signer(1);
+        let parent = parent(&alice);
+        let target = FeeRate::from_sat_per_vb(2);
+        let plan = plan(&parent, 100_000, &alice.p2wpkh_address(), target, &[&alice]).unwrap();
+        // The child still pays the target on its own size.
+        assert_eq!(plan.child_fee, target.fee_for_vsize(plan.child_vsize));
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        let alice = signer(1);
+        let parent = parent(&alice);
+        let destination = alice.p2wpkh_address();
+
+        let err = plan(
+            &parent,
+            200,
+            &destination,
+            FeeRate::MIN_RELAY,
+            &[&signer(2)],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no output of the parent"));
+
+        let err = plan(
+            &parent,
+            200,
+            &destination,
+            FeeRate::from_sat_per_vb(1_000),
+            &[&alice],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InsufficientFunds {
+                available: 30_000,
+                ..
+            }
+        ));
+    }
+}
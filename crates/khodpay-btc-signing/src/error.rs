@@ -0,0 +1,101 @@
+//! Error types for the Bitcoin signing crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while building or signing Bitcoin transactions.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Malformed address, or an address for a different network.
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    /// Malformed or unsupported script.
+    #[error("Invalid script: {0}")]
+    InvalidScript(String),
+
+    /// Hex decoding error.
+    #[error("Hex decode error: {0}")]
+    HexError(String),
+
+    /// Transaction validation failed.
+    #[error("Transaction validation failed: {0}")]
+    ValidationError(String),
+
+    /// The inputs do not cover the outputs plus the fee.
+    #[error("Insufficient funds: need {required} sats, have {available} sats")]
+    InsufficientFunds {
+        /// Outputs plus fee, in satoshis.
+        required: u64,
+        /// Sum of the inputs, in satoshis.
+        available: u64,
+    },
+
+    /// Malformed PSBT, or a PSBT operation not allowed in its current state.
+    #[error("Invalid PSBT: {0}")]
+    PsbtError(String),
+
+    /// Malformed BIP-21 payment URI.
+    #[error("Invalid payment URI: {0}")]
+    InvalidPaymentUri(String),
+
+    /// Malformed BOLT-11 invoice, or one with an invalid signature.
+    #[error("Invalid Lightning invoice: {0}")]
+    InvalidInvoice(String),
+
+    /// A [`UtxoSource`](crate::sweep::UtxoSource) query failed.
+    #[error("UTXO source error: {0}")]
+    UtxoSourceError(String),
+
+    /// ECDSA signing error, or no key for an input.
+    #[error("Signing error: {0}")]
+    SigningError(String),
+
+    /// Error from BIP-32 operations.
+    #[error("BIP-32 error: {0}")]
+    Bip32Error(#[from] khodpay_bip32::Error),
+
+    /// Error from BIP-44 operations.
+    #[error("BIP-44 error: {0}")]
+    Bip44Error(#[from] khodpay_bip44::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_address_error() {
+        let error = Error::InvalidAddress("bad checksum".to_string());
+        assert_eq!(error.to_string(), "Invalid address: bad checksum");
+    }
+
+    #[test]
+    fn test_insufficient_funds_error() {
+        let error = Error::InsufficientFunds {
+            required: 10_000,
+            available: 9_000,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Insufficient funds: need 10000 sats, have 9000 sats"
+        );
+    }
+
+    #[test]
+    fn test_psbt_error() {
+        let error = Error::PsbtError("missing input count".to_string());
+        assert_eq!(error.to_string(), "Invalid PSBT: missing input count");
+    }
+
+    #[test]
+    fn test_signing_error() {
+        let error = Error::SigningError("no key for input 0".to_string());
+        assert_eq!(error.to_string(), "Signing error: no key for input 0");
+    }
+
+    #[test]
+    fn test_error_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Error>();
+    }
+}
@@ -0,0 +1,227 @@
+//! Fee rates and virtual-size estimation for transactions before they are signed.
+
+use std::fmt;
+
+use crate::transaction::compact_size_len;
+use crate::{Script, ScriptType};
+
+/// Version and lock time.
+const FIXED_SIZE: u64 = 8;
+/// SegWit marker and flag, counted in witness (1 WU) bytes.
+const SEGWIT_HEADER_WEIGHT: u64 = 2;
+/// Size Bitcoin Core assumes for the input spending a legacy output: outpoint,
+/// sequence and a 107-byte `scriptSig` with its length.
+const LEGACY_SPEND_SIZE: u64 = 32 + 4 + 1 + 107 + 4;
+/// The same for a SegWit output, with the 107-byte witness discounted to 26 vbytes.
+const SEGWIT_SPEND_SIZE: u64 = 32 + 4 + 1 + 107 / 4 + 4;
+
+/// A fee rate, kept in satoshis per 1,000 virtual bytes so fractional sat/vB rates
+/// from fee estimators and sliders are exact.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::FeeRate;
+///
+/// let rate = FeeRate::from_sat_per_kvb(1_500);
+/// assert_eq!(rate.to_string(), "1.5 sat/vB");
+/// assert_eq!(rate.fee_for_vsize(141), 212); // rounded up
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FeeRate(u64);
+
+impl FeeRate {
+    /// No fee.
+    pub const ZERO: Self = Self(0);
+    /// Bitcoin Core's default minimum relay fee rate, 1 sat/vB.
+    pub const MIN_RELAY: Self = Self(1_000);
+    /// Bitcoin Core's default `-dustrelayfee`, 3 sat/vB, from which
+    /// [`dust_threshold`] derives the standard dust limits.
+    pub const DUST_RELAY: Self = Self(3_000);
+
+    /// Creates a fee rate of `sat_per_vb` satoshis per virtual byte.
+    pub fn from_sat_per_vb(sat_per_vb: u64) -> Self {
+        Self(sat_per_vb.saturating_mul(1_000))
+    }
+
+    /// Creates a fee rate of `sat_per_kvb` satoshis per 1,000 virtual bytes.
+    pub fn from_sat_per_kvb(sat_per_kvb: u64) -> Self {
+        Self(sat_per_kvb)
+    }
+
+    /// Returns the rate paid by `fee` satoshis for `vsize` virtual bytes, rounded down.
+    ///
+    /// Returns `None` if `vsize` is zero.
+    pub fn from_fee(fee: u64, vsize: u64) -> Option<Self> {
+        if vsize == 0 {
+            return None;
+        }
+        let rate = u128::from(fee) * 1_000 / u128::from(vsize);
+        Some(Self(u64::try_from(rate).unwrap_or(u64::MAX)))
+    }
+
+    /// Returns the rate in satoshis per 1,000 virtual bytes.
+    pub fn as_sat_per_kvb(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the fee for `vsize` virtual bytes, rounded up to a whole satoshi so the
+    /// rate is always met.
+    pub fn fee_for_vsize(self, vsize: u64) -> u64 {
+        let fee = (u128::from(self.0) * u128::from(vsize)).div_ceil(1_000);
+        u64::try_from(fee).unwrap_or(u64::MAX)
+    }
+
+    /// Returns the fee for `weight` weight units.
+    pub fn fee_for_weight(self, weight: u64) -> u64 {
+        self.fee_for_vsize(weight.div_ceil(4))
+    }
+}
+
+impl fmt::Display for FeeRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (whole, fraction) = (self.0 / 1_000, self.0 % 1_000);
+        if fraction == 0 {
+            write!(f, "{} sat/vB", whole)
+        } else {
+            let fraction = format!("{:03}", fraction);
+            write!(f, "{}.{} sat/vB", whole, fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+/// Returns the weight of an output paying `script_pubkey`.
+pub fn output_weight(script_pubkey: &Script) -> u64 {
+    let len = script_pubkey.len() as u64;
+    4 * (8 + compact_size_len(len) + len)
+}
+
+/// Returns the smallest value an output paying `script_pubkey` may carry to be
+/// relayed, as Bitcoin Core computes it: the cost at `dust_relay_fee` of the output
+/// plus the input that would later spend it.
+///
+/// With [`FeeRate::DUST_RELAY`] this gives the familiar limits of 546 sats for P2PKH,
+/// 540 for P2SH, 294 for P2WPKH and 330 for P2WSH and taproot. `OP_RETURN` outputs
+/// are never spent, so their limit is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::{dust_threshold, FeeRate, Script};
+///
+/// assert_eq!(dust_threshold(&Script::p2wpkh(&[0x11; 20]), FeeRate::DUST_RELAY), 294);
+/// assert_eq!(dust_threshold(&Script::p2pkh(&[0x11; 20]), FeeRate::DUST_RELAY), 546);
+/// ```
+pub fn dust_threshold(script_pubkey: &Script, dust_relay_fee: FeeRate) -> u64 {
+    if script_pubkey.is_op_return() {
+        return 0;
+    }
+    let spend_size = if script_pubkey.is_witness_program() {
+        SEGWIT_SPEND_SIZE
+    } else {
+        LEGACY_SPEND_SIZE
+    };
+    dust_relay_fee.fee_for_vsize(output_weight(script_pubkey) / 4 + spend_size)
+}
+
+/// Estimates the weight of a signed transaction spending `inputs` into outputs with
+/// the scripts `outputs`.
+///
+/// Signatures are counted at their maximum size, 71 bytes with the sighash type for
+/// the low-R signatures [`BtcSigner`](crate::BtcSigner) produces, so the estimate is
+/// exact for almost all signatures and never too low.
+pub fn estimate_weight(inputs: &[ScriptType], outputs: &[Script]) -> u64 {
+    let counts = compact_size_len(inputs.len() as u64) + compact_size_len(outputs.len() as u64);
+    let mut weight = 4 * (FIXED_SIZE + counts);
+    weight += inputs.iter().map(|input| input.input_weight()).sum::<u64>();
+    weight += outputs.iter().map(output_weight).sum::<u64>();
+    if inputs.iter().any(|input| input.is_segwit()) {
+        // Legacy inputs of a SegWit transaction still carry an empty witness.
+        let legacy_inputs = inputs.iter().filter(|input| !input.is_segwit()).count();
+        weight += SEGWIT_HEADER_WEIGHT + legacy_inputs as u64;
+    }
+    weight
+}
+
+/// Estimates the virtual size of a signed transaction, see [`estimate_weight`].
+pub fn estimate_vsize(inputs: &[ScriptType], outputs: &[Script]) -> u64 {
+    estimate_weight(inputs, outputs).div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_rate_conversions() {
+        assert_eq!(FeeRate::from_sat_per_vb(2).as_sat_per_kvb(), 2_000);
+        assert_eq!(
+            FeeRate::from_sat_per_vb(u64::MAX).as_sat_per_kvb(),
+            u64::MAX
+        );
+        assert_eq!(
+            FeeRate::from_fee(282, 141),
+            Some(FeeRate::from_sat_per_vb(2))
+        );
+        assert_eq!(FeeRate::from_fee(100, 141).unwrap().as_sat_per_kvb(), 709);
+        assert_eq!(FeeRate::from_fee(100, 0), None);
+        assert!(FeeRate::MIN_RELAY > FeeRate::ZERO);
+    }
+
+    #[test]
+    fn test_fee_rounds_up() {
+        let rate = FeeRate::from_sat_per_kvb(1_001);
+        assert_eq!(rate.fee_for_vsize(1_000), 1_001);
+        assert_eq!(rate.fee_for_vsize(1), 2);
+        assert_eq!(FeeRate::ZERO.fee_for_vsize(1_000), 0);
+        assert_eq!(FeeRate::from_sat_per_vb(10).fee_for_weight(561), 1_410);
+        assert_eq!(
+            FeeRate::from_sat_per_kvb(u64::MAX).fee_for_vsize(u64::MAX),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(FeeRate::from_sat_per_vb(12).to_string(), "12 sat/vB");
+        assert_eq!(FeeRate::from_sat_per_kvb(1_250).to_string(), "1.25 sat/vB");
+        assert_eq!(FeeRate::from_sat_per_kvb(5).to_string(), "0.005 sat/vB");
+    }
+
+    #[test]
+    fn test_estimate_vsize() {
+        let p2wpkh = Script::p2wpkh(&[0; 20]);
+        let p2pkh = Script::p2pkh(&[0; 20]);
+
+        // The well-known sizes of single-input, two-output transactions.
+        let outputs = [p2wpkh.clone(), p2wpkh.clone()];
+        assert_eq!(estimate_vsize(&[ScriptType::P2wpkh], &outputs), 141);
+        assert_eq!(estimate_vsize(&[ScriptType::P2shP2wpkh], &outputs), 164);
+        let outputs = [p2pkh.clone(), p2pkh];
+        assert_eq!(estimate_vsize(&[ScriptType::P2pkh], &outputs), 225);
+
+        // A legacy input next to a SegWit one pays for its empty witness.
+        assert_eq!(output_weight(&p2wpkh), 124);
+        assert_eq!(
+            estimate_weight(&[ScriptType::P2pkh, ScriptType::P2wpkh], &[p2wpkh]),
+            4 * (10 + 147 + 41 + 31) + 2 + 1 + 107
+        );
+    }
+
+    #[test]
+    fn test_dust_threshold() {
+        let dust = |script: &Script| dust_threshold(script, FeeRate::DUST_RELAY);
+        // Bitcoin Core's standard limits.
+        assert_eq!(dust(&Script::p2pkh(&[0; 20])), 546);
+        assert_eq!(dust(&Script::p2sh_p2wpkh(&[0; 20])), 540);
+        assert_eq!(dust(&Script::p2wpkh(&[0; 20])), 294);
+        assert_eq!(dust(&Script::p2wsh(&[0; 32])), 330);
+        assert_eq!(dust(&Script::witness_program(1, &[0; 32])), 330);
+        assert_eq!(dust(&Script::new(vec![0x6a, 0x01, 0x42])), 0);
+
+        // The limits scale with the dust relay fee.
+        let p2wpkh = Script::p2wpkh(&[0; 20]);
+        assert_eq!(dust_threshold(&p2wpkh, FeeRate::MIN_RELAY), 98);
+        assert_eq!(dust_threshold(&p2wpkh, FeeRate::ZERO), 0);
+    }
+}
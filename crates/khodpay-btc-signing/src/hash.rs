@@ -0,0 +1,64 @@
+//! Bitcoin hash functions.
+
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+/// Single SHA-256.
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Double SHA-256, used for transaction IDs and signature hashes.
+pub(crate) fn sha256d(data: &[u8]) -> [u8; 32] {
+    sha256(&sha256(data))
+}
+
+/// RIPEMD-160 of SHA-256, used for public key and script hashes.
+pub(crate) fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(sha256(data)).into()
+}
+
+/// BIP-340 tagged hash: `SHA256(SHA256(tag) || SHA256(tag) || data)`.
+pub(crate) fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256(tag.as_bytes());
+    Sha256::new()
+        .chain_update(tag_hash)
+        .chain_update(tag_hash)
+        .chain_update(data)
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256d_empty() {
+        assert_eq!(
+            hex::encode(sha256d(b"")),
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456"
+        );
+    }
+
+    #[test]
+    fn test_hash160() {
+        // Compressed public key of private key 1.
+        let pubkey =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        assert_eq!(
+            hex::encode(hash160(&pubkey)),
+            "751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+    }
+
+    #[test]
+    fn test_tagged_hash() {
+        // BIP-322 message hash of the empty message.
+        assert_eq!(
+            hex::encode(tagged_hash("BIP0322-signed-message", b"")),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! # Khodpay BTC Signing
+//!
+//! Bitcoin transaction building and signing for single-key (P2PKH, P2SH-P2WPKH and
+//! P2WPKH) wallets.
+//!
+//! This crate turns UTXOs and BIP-44 / BIP-49 / BIP-84 account keys from `khodpay-bip44` into
+//! consensus-valid, fully signed raw transactions ready for `sendrawtransaction`.
+//!
+//! ## Modules
+//!
+//! | Type | Standard | Description |
+//! |---|---|---|
+//! | [`Address`] | BIP-173 / BIP-350 | P2PKH, P2SH and bech32 / bech32m SegWit addresses |
+//! | [`Transaction`] | BIP-141 / BIP-144 | Transaction model, consensus (de)serialization, txid / wtxid and weight |
+//! | [`TransactionBuilder`] | BIP-143 | Input / output selection, change and input signing |
+//! | [`FeeRate`] | — | sat/vB fee rates, per-script-type virtual-size estimation and dust limits |
+//! | [`BtcSigner`] | BIP-44 / BIP-49 / BIP-84 | Keys derived from a `khodpay-bip44` account |
+//! | [`ScriptType`] | BIP-16 / BIP-141 | P2PKH, P2SH-P2WPKH and P2WPKH scripts of a key |
+//! | [`bip21`] | BIP-21 | `bitcoin:` payment request URIs for QR codes |
+//! | [`bip322`] | BIP-322 | Generic signed messages (simple and full) proving address ownership |
+//! | [`bolt11`] | BOLT-11 | Lightning invoice decoding and signature checks for display |
+//! | [`sighash`] | BIP-143 / BIP-341 | Legacy, SegWit v0 and taproot signature hashes |
+//! | [`rbf`] | BIP-125 | Replace-by-fee signalling and fee bumping |
+//! | [`cpfp`] | — | Child-pays-for-parent planning for stuck incoming transactions |
+//! | [`miniscript`] | BIP-379 / BIP-380 | `wsh()` miniscript descriptors for multisig and timelocked spends |
+//! | [`sweep`] | BIP-44 / BIP-84 / BIP-86 | Gap-limit scans and batched sweeps of legacy accounts into new ones |
+//! | [`psbt`] | BIP-174 / BIP-370 | PSBTv2 construction, updating, signing, finalizing, extraction and previews |
+//!
+//! ## Features
+//!
+//! - **Native SegWit**: P2WPKH inputs signed with BIP-143 signature hashes
+//! - **Any Destination**: Pay P2PKH, P2SH, P2WPKH, P2WSH and taproot addresses
+//! - **Change Handling**: Leftover value goes to a change address; without one the
+//!   builder refuses instead of silently overpaying the fee
+//! - **PSBTv2**: Hand transactions to hardware wallets and coordinators that speak BIP-370
+//! - **Message Signing**: BIP-322 proofs of address ownership for exchanges and services
+//! - **Legacy & Nested SegWit**: Sweep P2PKH (BIP-44) and P2SH-P2WPKH (BIP-49) UTXOs
+//!   with legacy and BIP-143 signature hashes
+//! - **Fee Rates**: Size the fee from a sat/vB rate using exact per-input and per-output
+//!   weights, before anything is signed
+//! - **Replace-by-Fee**: Opt-in BIP-125 signalling and re-signed fee bumps for stuck
+//!   transactions
+//! - **Child-Pays-for-Parent**: Accelerate stuck incoming payments with a child sized
+//!   for a target package fee rate
+//! - **Raw Transactions**: Parse hex from nodes and explorers, inspect and re-broadcast it
+//! - **Dust Protection**: Per-script-type dust limits; dust change is dropped to the
+//!   fee or rejected, so every transaction stays relayable
+//! - **Miniscript**: Analyze, sign and satisfy P2WSH multisig and timelocked-recovery
+//!   scripts from their descriptors
+//! - **Account Sweeps**: Move a BIP-44 or BIP-49 account into a BIP-84 or BIP-86 one in
+//!   batched, fee-rated consolidation transactions
+//! - **Shared Sighashes**: One signature-hash implementation, including taproot key and
+//!   script paths with annex, for the builder, PSBTs, messages and external signers
+//! - **Output Privacy**: Optional BIP-69 ordering or a randomized change position
+//! - **Payment URIs**: Parse and generate BIP-21 `bitcoin:` URIs from payment QR codes
+//! - **Lightning Invoices**: Decode and validate scanned BOLT-11 invoices ahead of paying them
+//! - **Network Checks**: Addresses of the wrong network are rejected before signing
+//! - **BIP-44 Integration**: Sign with keys from the same HD wallet as the EVM crates
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_bip32::Network as Bip32Network;
+//! use khodpay_bip44::{Chain, CoinType, Purpose, Wallet};
+//! use khodpay_btc_signing::{Address, BtcSigner, Network, OutPoint, TransactionBuilder, Utxo};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Bip32Network::BitcoinMainnet)?;
+//! let account = wallet.get_account(Purpose::BIP84, CoinType::Bitcoin, 0)?;
+//!
+//! let receive = BtcSigner::new(account, Chain::External, 0)?;
+//! let change = BtcSigner::new(account, Chain::Internal, 0)?;
+//!
+//! let utxo = Utxo::new(
+//!     OutPoint::new(
+//!         "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16".parse()?,
+//!         1,
+//!     ),
+//!     100_000,
+//!     receive.p2wpkh_address().script_pubkey(),
+//! );
+//! let recipient = Address::parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", Network::Bitcoin)?;
+//!
+//! let tx = TransactionBuilder::new(Network::Bitcoin)
+//!     .add_utxo(utxo)
+//!     .add_output(&recipient, 50_000)
+//!     .change_address(&change.p2wpkh_address())
+//!     .fee(1_500)
+//!     .sign(&[&receive])?;
+//!
+//! let raw_tx = tx.to_hex(); // "02000000000101..."
+//! println!("txid {} ({} vbytes): {}", tx.txid(), tx.vsize(), raw_tx);
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod address;
+pub mod bip21;
+pub mod bip322;
+pub mod bolt11;
+mod builder;
+pub mod cpfp;
+mod error;
+mod fee;
+mod hash;
+pub mod miniscript;
+mod network;
+pub mod psbt;
+pub mod rbf;
+mod script;
+pub mod sighash;
+mod signer;
+pub mod sweep;
+mod transaction;
+
+pub use address::{Address, Payload};
+pub use builder::{ChangePolicy, TransactionBuilder, TxOrdering, Utxo, MAX_MONEY};
+pub use error::Error;
+pub use fee::{dust_threshold, estimate_vsize, estimate_weight, output_weight, FeeRate};
+pub use khodpay_amount::CoinAmount;
+pub use network::Network;
+pub use script::{Script, ScriptType};
+pub use signer::BtcSigner;
+pub use transaction::{
+    OutPoint, Transaction, TxIn, TxOut, Txid, SEQUENCE_FINAL, SEQUENCE_LOCKTIME_NO_RBF,
+    SEQUENCE_RBF,
+};
+
+/// Result type alias for Bitcoin signing operations.
+pub type Result<T> = std::result::Result<T, Error>;
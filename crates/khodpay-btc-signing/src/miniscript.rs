@@ -0,0 +1,1024 @@
+//! Miniscript spending conditions in P2WSH output descriptors.
+//!
+//! [Miniscript](https://bitcoin.sipa.be/miniscript/) writes Bitcoin scripts as
+//! composable fragments, so a wallet can tell which keys and timelocks a script needs
+//! and build its witness without a hand-written template per script. This module
+//! covers the fragments of multisig and timelocked-recovery wallets:
+//!
+//! | Fragment | Script |
+//! |---|---|
+//! | `pk(K)` | `<K> CHECKSIG` |
+//! | `pkh(K)` | `DUP HASH160 <HASH160(K)> EQUALVERIFY CHECKSIG` |
+//! | `multi(k,K1,…,Kn)` | `<k> <K1> … <Kn> <n> CHECKMULTISIG` |
+//! | `older(n)` | `<n> CHECKSEQUENCEVERIFY` |
+//! | `after(n)` | `<n> CHECKLOCKTIMEVERIFY` |
+//! | `and_v(X,Y)` | `[X] [Y]` |
+//! | `or_d(X,Z)` | `[X] IFDUP NOTIF [Z] ENDIF` |
+//! | `or_i(X,Z)` | `IF [X] ELSE [Z] ENDIF` |
+//! | `v:X` | `[X] VERIFY`, merged into a final `CHECKSIG`, `CHECKMULTISIG` or `EQUAL` |
+//!
+//! Keys are hex-encoded compressed public keys; extended keys with derivation paths
+//! are not supported. Fragments are type-checked (base type, dissatisfiability and
+//! the unit property), but the satisfier picks the smallest witness without the
+//! non-malleability analysis of a full miniscript implementation.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::miniscript::Descriptor;
+//! use khodpay_btc_signing::{BtcSigner, Network, OutPoint, Transaction, TxIn, TxOut, Txid};
+//!
+//! let alice = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+//! let bob = BtcSigner::from_private_key(&[2u8; 32], Network::Bitcoin).unwrap();
+//!
+//! // Alice alone, or Bob after 144 blocks.
+//! let descriptor: Descriptor = format!(
+//!     "wsh(or_d(pk({}),and_v(v:pk({}),older(144))))",
+//!     hex::encode(alice.public_key()),
+//!     hex::encode(bob.public_key()),
+//! )
+//! .parse()
+//! .unwrap();
+//! assert_eq!(descriptor.miniscript().relative_timelocks(), [144]);
+//!
+//! let tx = Transaction {
+//!     version: 2,
+//!     inputs: vec![TxIn::new(OutPoint::new(Txid::from_bytes([7; 32]), 0), 144)],
+//!     outputs: vec![TxOut { value: 9_000, script_pubkey: descriptor.script_pubkey() }],
+//!     lock_time: 0,
+//! };
+//! let signatures = descriptor.sign(&tx, 0, 10_000, &[&bob]).unwrap();
+//! let witness = descriptor.satisfy(&signatures, &tx, 0).unwrap();
+//! assert_eq!(witness.last(), Some(&descriptor.witness_script().as_bytes().to_vec()));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use secp256k1::PublicKey;
+
+use crate::hash::{hash160, sha256};
+use crate::sighash::SIGHASH_ALL;
+use crate::transaction::SEQUENCE_FINAL;
+use crate::{Address, BtcSigner, Error, Network, Payload, Result, Script, Transaction};
+
+const OP_0: u8 = 0x00;
+const OP_1: u8 = 0x51;
+const OP_IF: u8 = 0x63;
+const OP_NOTIF: u8 = 0x64;
+const OP_ELSE: u8 = 0x67;
+const OP_ENDIF: u8 = 0x68;
+const OP_VERIFY: u8 = 0x69;
+const OP_IFDUP: u8 = 0x73;
+const OP_DUP: u8 = 0x76;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKMULTISIG: u8 = 0xae;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+
+/// Most keys `CHECKMULTISIG` accepts.
+const MAX_MULTI_KEYS: usize = 20;
+/// Largest standard P2WSH witness script.
+const MAX_WITNESS_SCRIPT_SIZE: usize = 3_600;
+
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+const SEQUENCE_DISABLE_FLAG: u32 = 1 << 31;
+const SEQUENCE_TYPE_FLAG: u32 = 1 << 22;
+const SEQUENCE_VALUE_MASK: u32 = 0x0000_ffff;
+
+/// A compressed public key.
+type Key = [u8; 33];
+
+/// A witness stack, bottom item first.
+type Witness = Vec<Vec<u8>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    Pk(Key),
+    Pkh(Key),
+    Multi(usize, Vec<Key>),
+    Older(u32),
+    After(u32),
+    Verify(Box<Node>),
+    AndV(Box<Node>, Box<Node>),
+    OrD(Box<Node>, Box<Node>),
+    OrI(Box<Node>, Box<Node>),
+}
+
+/// Miniscript type properties: `V` (verify) or `B` (base), `d` and `u`.
+#[derive(Debug, Clone, Copy)]
+struct Type {
+    verify: bool,
+    dissatisfiable: bool,
+    unit: bool,
+}
+
+/// A type-checked miniscript expression.
+///
+/// Parse one from its string form; [`Display`](fmt::Display) writes it back.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Miniscript {
+    node: Node,
+}
+
+impl Miniscript {
+    /// Encodes the expression as a script.
+    pub fn encode(&self) -> Script {
+        let mut bytes = Vec::new();
+        self.node.encode(&mut bytes);
+        Script::new(bytes)
+    }
+
+    /// Returns the public keys, in the order they appear.
+    pub fn keys(&self) -> Vec<[u8; 33]> {
+        let mut keys = Vec::new();
+        self.node.visit(&mut |node| match node {
+            Node::Pk(key) | Node::Pkh(key) => keys.push(*key),
+            Node::Multi(_, multi) => keys.extend_from_slice(multi),
+            _ => {}
+        });
+        keys
+    }
+
+    /// Returns the relative timelocks (`older`), as BIP-68 sequence values.
+    pub fn relative_timelocks(&self) -> Vec<u32> {
+        let mut timelocks = Vec::new();
+        self.node.visit(&mut |node| {
+            if let Node::Older(n) = node {
+                timelocks.push(*n);
+            }
+        });
+        timelocks
+    }
+
+    /// Returns the absolute timelocks (`after`), as lock-time values.
+    pub fn absolute_timelocks(&self) -> Vec<u32> {
+        let mut timelocks = Vec::new();
+        self.node.visit(&mut |node| {
+            if let Node::After(n) = node {
+                timelocks.push(*n);
+            }
+        });
+        timelocks
+    }
+
+    /// Returns the smallest witness satisfying the expression for input `index` of
+    /// `tx`, with `signatures` keyed by public key as in
+    /// [`PsbtInput::partial_sigs`](crate::psbt::PsbtInput::partial_sigs).
+    ///
+    /// Returns `None` if signatures are missing or a timelock is not met by the input's
+    /// sequence or the transaction's lock time.
+    pub fn satisfy(
+        &self,
+        signatures: &BTreeMap<Vec<u8>, Vec<u8>>,
+        tx: &Transaction,
+        index: usize,
+    ) -> Option<Vec<Vec<u8>>> {
+        let sequence = tx.inputs.get(index)?.sequence;
+        let context = Context {
+            signatures,
+            tx,
+            sequence,
+        };
+        self.node.satisfy(&context)
+    }
+}
+
+impl FromStr for Miniscript {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let node = Node::parse(s)?;
+        node.type_check()?;
+        let mut keys = Vec::new();
+        node.visit(&mut |node| match node {
+            Node::Pk(key) | Node::Pkh(key) => keys.push(*key),
+            Node::Multi(_, multi) => keys.extend_from_slice(multi),
+            _ => {}
+        });
+        keys.sort_unstable();
+        if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(invalid("repeated public key"));
+        }
+        Ok(Self { node })
+    }
+}
+
+impl fmt::Display for Miniscript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+/// A `wsh(…)` output descriptor with a miniscript witness script.
+///
+/// Parses with or without the BIP-380 checksum; [`Display`](fmt::Display) includes it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Descriptor {
+    miniscript: Miniscript,
+}
+
+impl Descriptor {
+    /// Creates a P2WSH descriptor for `miniscript`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidScript`] if the expression is not of base type `B` or its
+    /// script exceeds the 3,600-byte standardness limit.
+    pub fn new(miniscript: Miniscript) -> Result<Self> {
+        if miniscript.node.type_check()?.verify {
+            return Err(invalid("top-level expression must be of type B"));
+        }
+        let size = miniscript.encode().len();
+        if size > MAX_WITNESS_SCRIPT_SIZE {
+            return Err(invalid(&format!("witness script is {} bytes", size)));
+        }
+        Ok(Self { miniscript })
+    }
+
+    /// Returns the miniscript expression.
+    pub fn miniscript(&self) -> &Miniscript {
+        &self.miniscript
+    }
+
+    /// Returns the witness script.
+    pub fn witness_script(&self) -> Script {
+        self.miniscript.encode()
+    }
+
+    /// Returns the P2WSH output script.
+    pub fn script_pubkey(&self) -> Script {
+        Script::p2wsh(&sha256(self.witness_script().as_bytes()))
+    }
+
+    /// Returns the P2WSH address on `network`.
+    pub fn address(&self, network: Network) -> Address {
+        Address::new(
+            Payload::WitnessProgram {
+                version: 0,
+                program: sha256(self.witness_script().as_bytes()).to_vec(),
+            },
+            network,
+        )
+        .expect("a 32-byte v0 program is a valid address")
+    }
+
+    /// Signs input `index` of `tx`, which spends `value` satoshis from this
+    /// descriptor's output, with every key of `signers` the script uses.
+    ///
+    /// Returns the signatures (with `SIGHASH_ALL`) keyed by public key, ready for
+    /// [`satisfy`](Self::satisfy) or a PSBT's partial signatures.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] if no signer holds a key of the script or
+    /// `index` is out of range.
+    pub fn sign(
+        &self,
+        tx: &Transaction,
+        index: usize,
+        value: u64,
+        signers: &[&BtcSigner],
+    ) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+        let keys = self.miniscript.keys();
+        let witness_script = self.witness_script();
+        let mut signatures = BTreeMap::new();
+        for signer in signers {
+            if keys.contains(&signer.public_key()) {
+                let signature =
+                    signer.sign_witness_script(tx, index, &witness_script, value, SIGHASH_ALL)?;
+                signatures.insert(signer.public_key().to_vec(), signature);
+            }
+        }
+        if signatures.is_empty() {
+            return Err(Error::SigningError(
+                "none of the signers holds a key of the descriptor".to_string(),
+            ));
+        }
+        Ok(signatures)
+    }
+
+    /// Builds the complete witness for input `index` of `tx`: the smallest
+    /// satisfaction followed by the witness script.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] if `signatures` and the transaction's timelocks
+    /// do not satisfy the script.
+    pub fn satisfy(
+        &self,
+        signatures: &BTreeMap<Vec<u8>, Vec<u8>>,
+        tx: &Transaction,
+        index: usize,
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut witness = self
+            .miniscript
+            .satisfy(signatures, tx, index)
+            .ok_or_else(|| {
+                Error::SigningError(format!(
+                    "input {} cannot be satisfied with the given signatures and timelocks",
+                    index
+                ))
+            })?;
+        witness.push(self.witness_script().as_bytes().to_vec());
+        Ok(witness)
+    }
+}
+
+impl FromStr for Descriptor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let body = match s.split_once('#') {
+            Some((body, checksum)) => {
+                if checksum != descriptor_checksum(body)? {
+                    return Err(invalid("descriptor checksum mismatch"));
+                }
+                body
+            }
+            None => s,
+        };
+        let inner = body
+            .strip_prefix("wsh(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(|| invalid("only wsh() descriptors are supported"))?;
+        Self::new(inner.parse()?)
+    }
+}
+
+impl fmt::Display for Descriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let body = format!("wsh({})", self.miniscript);
+        let checksum = descriptor_checksum(&body).map_err(|_| fmt::Error)?;
+        write!(f, "{}#{}", body, checksum)
+    }
+}
+
+/// Computes the BIP-380 checksum of any output descriptor, the eight characters
+/// that follow its `#`.
+///
+/// # Errors
+///
+/// Returns an error if the descriptor has a character outside the descriptor
+/// character set.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::miniscript::descriptor_checksum;
+///
+/// assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+/// ```
+pub fn descriptor_checksum(descriptor: &str) -> Result<String> {
+    const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+    const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    const GENERATOR: [u64; 5] = [
+        0xf5dee51989,
+        0xa9fdca3312,
+        0x1bab10e32d,
+        0x3706b1677a,
+        0x644d626ffd,
+    ];
+
+    fn polymod(checksum: u64, value: u64) -> u64 {
+        let top = checksum >> 35;
+        let mut checksum = ((checksum & 0x7_ffff_ffff) << 5) ^ value;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+        checksum
+    }
+
+    let mut checksum = 1;
+    let mut groups = Vec::with_capacity(3);
+    for c in descriptor.chars() {
+        let position = INPUT_CHARSET
+            .find(c)
+            .ok_or_else(|| invalid(&format!("invalid descriptor character {:?}", c)))?
+            as u64;
+        checksum = polymod(checksum, position & 31);
+        groups.push(position >> 5);
+        if groups.len() == 3 {
+            checksum = polymod(checksum, groups[0] * 9 + groups[1] * 3 + groups[2]);
+            groups.clear();
+        }
+    }
+    match groups.as_slice() {
+        [a] => checksum = polymod(checksum, *a),
+        [a, b] => checksum = polymod(checksum, a * 3 + b),
+        _ => {}
+    }
+    for _ in 0..8 {
+        checksum = polymod(checksum, 0);
+    }
+    checksum ^= 1;
+    Ok((0..8)
+        .map(|i| CHECKSUM_CHARSET[((checksum >> (5 * (7 - i))) & 31) as usize] as char)
+        .collect())
+}
+
+/// What the satisfier can use: signatures and the spending input's timelock fields.
+struct Context<'a> {
+    signatures: &'a BTreeMap<Vec<u8>, Vec<u8>>,
+    tx: &'a Transaction,
+    sequence: u32,
+}
+
+impl Context<'_> {
+    fn signature(&self, key: &Key) -> Option<&Vec<u8>> {
+        self.signatures.get(key.as_slice())
+    }
+
+    /// BIP-68 / BIP-112: the input's sequence meets `older(n)`.
+    fn older(&self, n: u32) -> bool {
+        self.tx.version >= 2
+            && self.sequence & SEQUENCE_DISABLE_FLAG == 0
+            && self.sequence & SEQUENCE_TYPE_FLAG == n & SEQUENCE_TYPE_FLAG
+            && self.sequence & SEQUENCE_VALUE_MASK >= n & SEQUENCE_VALUE_MASK
+    }
+
+    /// BIP-65: the lock time meets `after(n)`.
+    fn after(&self, n: u32) -> bool {
+        let lock_time = self.tx.lock_time;
+        (lock_time < LOCKTIME_THRESHOLD) == (n < LOCKTIME_THRESHOLD)
+            && lock_time >= n
+            && self.sequence != SEQUENCE_FINAL
+    }
+}
+
+impl Node {
+    fn parse(s: &str) -> Result<Self> {
+        let open = s
+            .find('(')
+            .ok_or_else(|| invalid(&format!("expected a fragment, found {:?}", s)))?;
+        let (wrappers, name) = match s[..open].split_once(':') {
+            Some((wrappers, name)) => (wrappers, name),
+            None => ("", &s[..open]),
+        };
+        let args = s[open + 1..]
+            .strip_suffix(')')
+            .ok_or_else(|| invalid(&format!("unbalanced parentheses in {:?}", s)))?;
+        let args = split_args(args)?;
+
+        let node = match (name, args.as_slice()) {
+            ("pk", [key]) => Self::Pk(parse_key(key)?),
+            ("pkh", [key]) => Self::Pkh(parse_key(key)?),
+            ("multi", [k, keys @ ..]) => {
+                let k: usize = k
+                    .parse()
+                    .map_err(|_| invalid(&format!("invalid multi threshold {:?}", k)))?;
+                if k == 0 || k > keys.len() || keys.len() > MAX_MULTI_KEYS {
+                    return Err(invalid(&format!(
+                        "multi threshold {} of {} keys",
+                        k,
+                        keys.len()
+                    )));
+                }
+                let keys = keys
+                    .iter()
+                    .map(|key| parse_key(key))
+                    .collect::<Result<_>>()?;
+                Self::Multi(k, keys)
+            }
+            ("older", [n]) => Self::Older(parse_timelock(n)?),
+            ("after", [n]) => Self::After(parse_timelock(n)?),
+            ("and_v", [x, y]) => Self::AndV(Box::new(Self::parse(x)?), Box::new(Self::parse(y)?)),
+            ("or_d", [x, z]) => Self::OrD(Box::new(Self::parse(x)?), Box::new(Self::parse(z)?)),
+            ("or_i", [x, z]) => Self::OrI(Box::new(Self::parse(x)?), Box::new(Self::parse(z)?)),
+            _ => {
+                return Err(invalid(&format!(
+                    "unsupported fragment {}() with {} arguments",
+                    name,
+                    args.len()
+                )))
+            }
+        };
+        wrappers
+            .chars()
+            .rev()
+            .try_fold(node, |node, wrapper| match wrapper {
+                'v' => Ok(Self::Verify(Box::new(node))),
+                _ => Err(invalid(&format!("unsupported wrapper {}:", wrapper))),
+            })
+    }
+
+    fn type_check(&self) -> Result<Type> {
+        let base = |dissatisfiable, unit| Type {
+            verify: false,
+            dissatisfiable,
+            unit,
+        };
+        Ok(match self {
+            Self::Pk(_) | Self::Pkh(_) | Self::Multi(..) => base(true, true),
+            Self::Older(_) | Self::After(_) => base(false, false),
+            Self::Verify(x) => {
+                if x.type_check()?.verify {
+                    return Err(invalid("v: needs a B expression"));
+                }
+                Type {
+                    verify: true,
+                    dissatisfiable: false,
+                    unit: false,
+                }
+            }
+            Self::AndV(x, y) => {
+                if !x.type_check()?.verify {
+                    return Err(invalid("and_v needs a V expression first"));
+                }
+                let y = y.type_check()?;
+                Type {
+                    dissatisfiable: false,
+                    ..y
+                }
+            }
+            Self::OrD(x, z) => {
+                let x = x.type_check()?;
+                let z = z.type_check()?;
+                if x.verify || !x.dissatisfiable || !x.unit || z.verify {
+                    return Err(invalid("or_d needs a Bdu expression and a B expression"));
+                }
+                z
+            }
+            Self::OrI(x, z) => {
+                let x = x.type_check()?;
+                let z = z.type_check()?;
+                if x.verify != z.verify {
+                    return Err(invalid("or_i needs two expressions of the same type"));
+                }
+                Type {
+                    verify: x.verify,
+                    dissatisfiable: x.dissatisfiable || z.dissatisfiable,
+                    unit: x.unit && z.unit,
+                }
+            }
+        })
+    }
+
+    fn visit(&self, f: &mut impl FnMut(&Self)) {
+        f(self);
+        match self {
+            Self::Verify(x) => x.visit(f),
+            Self::AndV(x, y) | Self::OrD(x, y) | Self::OrI(x, y) => {
+                x.visit(f);
+                y.visit(f);
+            }
+            _ => {}
+        }
+    }
+
+    fn encode(&self, script: &mut Vec<u8>) {
+        match self {
+            Self::Pk(key) => {
+                push_bytes(script, key);
+                script.push(OP_CHECKSIG);
+            }
+            Self::Pkh(key) => {
+                script.extend_from_slice(&[OP_DUP, OP_HASH160]);
+                push_bytes(script, &hash160(key));
+                script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+            }
+            Self::Multi(k, keys) => {
+                push_int(script, *k as u32);
+                for key in keys {
+                    push_bytes(script, key);
+                }
+                push_int(script, keys.len() as u32);
+                script.push(OP_CHECKMULTISIG);
+            }
+            Self::Older(n) => {
+                push_int(script, *n);
+                script.push(OP_CHECKSEQUENCEVERIFY);
+            }
+            Self::After(n) => {
+                push_int(script, *n);
+                script.push(OP_CHECKLOCKTIMEVERIFY);
+            }
+            Self::Verify(x) => {
+                x.encode(script);
+                // Every fragment ends in an opcode, never in pushed data, and the
+                // VERIFY form of CHECKSIG, CHECKMULTISIG and EQUAL is the next opcode.
+                match script.last_mut() {
+                    Some(op @ (&mut OP_CHECKSIG | &mut OP_CHECKMULTISIG | &mut OP_EQUAL)) => {
+                        *op += 1
+                    }
+                    _ => script.push(OP_VERIFY),
+                }
+            }
+            Self::AndV(x, y) => {
+                x.encode(script);
+                y.encode(script);
+            }
+            Self::OrD(x, z) => {
+                x.encode(script);
+                script.extend_from_slice(&[OP_IFDUP, OP_NOTIF]);
+                z.encode(script);
+                script.push(OP_ENDIF);
+            }
+            Self::OrI(x, z) => {
+                script.push(OP_IF);
+                x.encode(script);
+                script.push(OP_ELSE);
+                z.encode(script);
+                script.push(OP_ENDIF);
+            }
+        }
+    }
+
+    fn satisfy(&self, context: &Context<'_>) -> Option<Witness> {
+        match self {
+            Self::Pk(key) => Some(vec![context.signature(key)?.clone()]),
+            Self::Pkh(key) => Some(vec![context.signature(key)?.clone(), key.to_vec()]),
+            Self::Multi(k, keys) => {
+                let signatures: Vec<Vec<u8>> = keys
+                    .iter()
+                    .filter_map(|key| context.signature(key).cloned())
+                    .take(*k)
+                    .collect();
+                // CHECKMULTISIG pops one extra item.
+                (signatures.len() == *k)
+                    .then(|| std::iter::once(Vec::new()).chain(signatures).collect())
+            }
+            Self::Older(n) => context.older(*n).then(Vec::new),
+            Self::After(n) => context.after(*n).then(Vec::new),
+            Self::Verify(x) => x.satisfy(context),
+            // Y runs after X, so its items sit below X's on the stack.
+            Self::AndV(x, y) => Some(concat(y.satisfy(context)?, x.satisfy(context)?)),
+            Self::OrD(x, z) => smallest(
+                x.satisfy(context),
+                z.satisfy(context)
+                    .zip(x.dissatisfy())
+                    .map(|(z, x)| concat(z, x)),
+            ),
+            Self::OrI(x, z) => smallest(
+                x.satisfy(context).map(|x| concat(x, vec![vec![1]])),
+                z.satisfy(context).map(|z| concat(z, vec![vec![]])),
+            ),
+        }
+    }
+
+    fn dissatisfy(&self) -> Option<Witness> {
+        match self {
+            Self::Pk(_) => Some(vec![vec![]]),
+            Self::Pkh(key) => Some(vec![vec![], key.to_vec()]),
+            Self::Multi(k, _) => Some(vec![vec![]; k + 1]),
+            Self::Older(_) | Self::After(_) | Self::Verify(_) | Self::AndV(..) => None,
+            Self::OrD(x, z) => Some(concat(z.dissatisfy()?, x.dissatisfy()?)),
+            Self::OrI(x, z) => smallest(
+                x.dissatisfy().map(|x| concat(x, vec![vec![1]])),
+                z.dissatisfy().map(|z| concat(z, vec![vec![]])),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pk(key) => write!(f, "pk({})", hex::encode(key)),
+            Self::Pkh(key) => write!(f, "pkh({})", hex::encode(key)),
+            Self::Multi(k, keys) => {
+                write!(f, "multi({}", k)?;
+                for key in keys {
+                    write!(f, ",{}", hex::encode(key))?;
+                }
+                f.write_str(")")
+            }
+            Self::Older(n) => write!(f, "older({})", n),
+            Self::After(n) => write!(f, "after({})", n),
+            Self::Verify(x) => write!(f, "v:{}", x),
+            Self::AndV(x, y) => write!(f, "and_v({},{})", x, y),
+            Self::OrD(x, z) => write!(f, "or_d({},{})", x, z),
+            Self::OrI(x, z) => write!(f, "or_i({},{})", x, z),
+        }
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidScript(format!("miniscript: {}", message))
+}
+
+/// Splits `a,b(c,d),e` at the top-level commas.
+fn split_args(s: &str) -> Result<Vec<&str>> {
+    let mut args = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| invalid("unbalanced parentheses"))?
+            }
+            ',' if depth == 0 => {
+                args.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(invalid("unbalanced parentheses"));
+    }
+    args.push(&s[start..]);
+    Ok(args)
+}
+
+fn parse_key(s: &str) -> Result<Key> {
+    let key: Key = hex::decode(s)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid(&format!("{:?} is not a compressed public key", s)))?;
+    PublicKey::from_slice(&key).map_err(|_| invalid(&format!("{} is not on the curve", s)))?;
+    Ok(key)
+}
+
+fn parse_timelock(s: &str) -> Result<u32> {
+    s.parse::<u32>()
+        .ok()
+        .filter(|n| (1..0x8000_0000).contains(n))
+        .ok_or_else(|| invalid(&format!("invalid timelock {:?}", s)))
+}
+
+fn push_bytes(script: &mut Vec<u8>, bytes: &[u8]) {
+    debug_assert!(bytes.len() <= 75);
+    script.push(bytes.len() as u8);
+    script.extend_from_slice(bytes);
+}
+
+/// Pushes a non-negative number with the minimal encoding.
+fn push_int(script: &mut Vec<u8>, n: u32) {
+    match n {
+        0 => script.push(OP_0),
+        1..=16 => script.push(OP_1 + n as u8 - 1),
+        _ => {
+            let mut bytes: Vec<u8> = n.to_le_bytes().to_vec();
+            while bytes.last() == Some(&0) {
+                bytes.pop();
+            }
+            // Script numbers are signed: keep the top bit clear.
+            if bytes.last().is_some_and(|byte| byte & 0x80 != 0) {
+                bytes.push(0);
+            }
+            push_bytes(script, &bytes);
+        }
+    }
+}
+
+fn concat(mut bottom: Witness, top: Witness) -> Witness {
+    bottom.extend(top);
+    bottom
+}
+
+fn witness_size(witness: &Witness) -> usize {
+    witness.iter().map(|item| item.len() + 1).sum()
+}
+
+fn smallest(a: Option<Witness>, b: Option<Witness>) -> Option<Witness> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if witness_size(&b) < witness_size(&a) {
+            b
+        } else {
+            a
+        }),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sighash;
+    use crate::{OutPoint, TxIn, TxOut, Txid};
+    use secp256k1::ecdsa::Signature;
+    use secp256k1::{Message, SECP256K1};
+
+    fn signer(byte: u8) -> BtcSigner {
+        BtcSigner::from_private_key(&[byte; 32], Network::Bitcoin).unwrap()
+    }
+
+    fn key(signer: &BtcSigner) -> String {
+        hex::encode(signer.public_key())
+    }
+
+    fn spending_tx(descriptor: &Descriptor, sequence: u32, lock_time: u32) -> Transaction {
+        Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(
+                OutPoint::new(Txid::from_bytes([0xaa; 32]), 0),
+                sequence,
+            )],
+            outputs: vec![TxOut {
+                value: 90_000,
+                script_pubkey: descriptor.script_pubkey(),
+            }],
+            lock_time,
+        }
+    }
+
+    #[test]
+    fn test_descriptor_checksum() {
+        // BIP-380 example.
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+    }
+
+    #[test]
+    fn test_multi_descriptor() {
+        // BIP-383 test vector.
+        let descriptor: Descriptor = "wsh(multi(2,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7,03774ae7f858a9411e5ef4246b70c65aac5649980be5c17891bbec17895da008cb,03d01115d548e7561b15c38f004d734633687cf4419620095bc5b0f47070afe85a))"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            descriptor.script_pubkey().to_hex(),
+            "0020773d709598b76c4e3b575c08aad40658963f9322affc0f8c28d1d9a68d0c944a"
+        );
+        assert_eq!(
+            descriptor.address(Network::Bitcoin).to_string(),
+            "bc1qwu7hp9vckakyuw6htsy244qxtztrlyez4l7qlrpg68v6drgvj39qn4zazc"
+        );
+
+        // Display round-trips with the checksum, which is verified on parse.
+        let text = descriptor.to_string();
+        assert_eq!(text.parse::<Descriptor>().unwrap(), descriptor);
+        let tampered = text.replacen("multi(2", "multi(1", 1);
+        let err = tampered.parse::<Descriptor>().unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn test_encoding() {
+        let (a, b) = (signer(1), signer(2));
+        let miniscript: Miniscript = format!(
+            "or_d(pk({}),and_v(v:pkh({}),older(4032)))",
+            key(&a),
+            key(&b)
+        )
+        .parse()
+        .unwrap();
+        let expected = format!(
+            "21{}ac736476a914{}88ad02c00fb268",
+            key(&a),
+            hex::encode(b.pubkey_hash())
+        );
+        assert_eq!(miniscript.encode().to_hex(), expected);
+        assert_eq!(
+            miniscript.to_string(),
+            format!(
+                "or_d(pk({}),and_v(v:pkh({}),older(4032)))",
+                key(&a),
+                key(&b)
+            )
+        );
+        assert_eq!(miniscript.keys(), [a.public_key(), b.public_key()]);
+        assert_eq!(miniscript.relative_timelocks(), [4032]);
+        assert!(miniscript.absolute_timelocks().is_empty());
+
+        // v: merges into CHECKMULTISIG; after() pushes a 3-byte number.
+        let miniscript: Miniscript = format!(
+            "or_i(and_v(v:multi(1,{},{}),after(800000)),pk({}))",
+            key(&a),
+            key(&b),
+            key(&signer(3))
+        )
+        .parse()
+        .unwrap();
+        let script = miniscript.encode().to_hex();
+        assert!(
+            script.starts_with(&format!("6351 21{} 21{} 52af", key(&a), key(&b)).replace(' ', ""))
+        );
+        assert!(script.contains("0300350cb167"));
+        assert_eq!(miniscript.absolute_timelocks(), [800_000]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let (a, b) = (key(&signer(1)), key(&signer(2)));
+        for bad in [
+            format!("pk({}", a),
+            format!("pk({},{})", a, b),
+            "pk(02)".to_string(),
+            format!("multi(3,{},{})", a, b),
+            format!("multi(0,{})", a),
+            "older(0)".to_string(),
+            "after(2147483648)".to_string(),
+            format!("and_v(pk({}),pk({}))", a, b),
+            format!("or_d(v:pk({}),pk({}))", a, b),
+            format!("or_d(older(1),pk({}))", a),
+            format!("v:v:pk({})", a),
+            format!("s:pk({})", a),
+            format!("thresh(1,pk({}))", a),
+            format!("or_d(pk({}),pk({}))", a, a),
+        ] {
+            assert!(bad.parse::<Miniscript>().is_err(), "{}", bad);
+        }
+        assert!(format!("wsh(v:pk({}))", a).parse::<Descriptor>().is_err());
+        assert!(format!("sh(pk({}))", a).parse::<Descriptor>().is_err());
+    }
+
+    #[test]
+    fn test_sign_and_satisfy_multisig() {
+        let (a, b, c) = (signer(1), signer(2), signer(3));
+        let descriptor: Descriptor = format!("wsh(multi(2,{},{},{}))", key(&a), key(&b), key(&c))
+            .parse()
+            .unwrap();
+        let tx = spending_tx(&descriptor, SEQUENCE_FINAL, 0);
+
+        let mut signatures = descriptor.sign(&tx, 0, 100_000, &[&c]).unwrap();
+        assert!(descriptor.satisfy(&signatures, &tx, 0).is_err());
+        signatures.extend(descriptor.sign(&tx, 0, 100_000, &[&a]).unwrap());
+        let witness = descriptor.satisfy(&signatures, &tx, 0).unwrap();
+
+        // <empty> <sig a> <sig c> <witness script>, signatures in key order.
+        assert_eq!(witness.len(), 4);
+        assert!(witness[0].is_empty());
+        let sighash =
+            sighash::segwit_v0(&tx, 0, &descriptor.witness_script(), 100_000, 0x01).unwrap();
+        for (item, signer) in witness[1..3].iter().zip([&a, &c]) {
+            let (_, der) = item.split_last().unwrap();
+            SECP256K1
+                .verify_ecdsa(
+                    &Message::from_digest(sighash),
+                    &Signature::from_der(der).unwrap(),
+                    &PublicKey::from_slice(&signer.public_key()).unwrap(),
+                )
+                .unwrap();
+        }
+        assert_eq!(witness[3], descriptor.witness_script().as_bytes());
+
+        let err = descriptor.sign(&tx, 0, 100_000, &[&signer(4)]).unwrap_err();
+        assert!(err.to_string().contains("none of the signers"));
+    }
+
+    #[test]
+    fn test_satisfy_timelocked_recovery() {
+        let (owner, recovery) = (signer(1), signer(2));
+        let descriptor: Descriptor = format!(
+            "wsh(or_d(pk({}),and_v(v:pk({}),older(144))))",
+            key(&owner),
+            key(&recovery)
+        )
+        .parse()
+        .unwrap();
+
+        // The owner can always spend: <sig> alone.
+        let tx = spending_tx(&descriptor, SEQUENCE_FINAL, 0);
+        let signatures = descriptor.sign(&tx, 0, 100_000, &[&owner]).unwrap();
+        let witness = descriptor.satisfy(&signatures, &tx, 0).unwrap();
+        assert_eq!(witness.len(), 2);
+
+        // The recovery key needs the input's sequence to reach 144 blocks.
+        let early = spending_tx(&descriptor, 143, 0);
+        let signatures = descriptor.sign(&early, 0, 100_000, &[&recovery]).unwrap();
+        assert!(descriptor.satisfy(&signatures, &early, 0).is_err());
+
+        let tx = spending_tx(&descriptor, 144, 0);
+        let signatures = descriptor.sign(&tx, 0, 100_000, &[&recovery]).unwrap();
+        let witness = descriptor.satisfy(&signatures, &tx, 0).unwrap();
+        // <sig recovery> <empty: dissatisfies pk(owner)> <witness script>
+        assert_eq!(witness.len(), 3);
+        assert_eq!(witness[0], signatures[&recovery.public_key().to_vec()]);
+        assert!(witness[1].is_empty());
+
+        // Time-based sequences do not satisfy a height-based older().
+        let tx = spending_tx(&descriptor, SEQUENCE_TYPE_FLAG | 144, 0);
+        let signatures = descriptor.sign(&tx, 0, 100_000, &[&recovery]).unwrap();
+        assert!(descriptor.satisfy(&signatures, &tx, 0).is_err());
+    }
+
+    #[test]
+    fn test_satisfy_absolute_timelock() {
+        let (a, b) = (signer(1), signer(2));
+        let descriptor: Descriptor = format!(
+            "wsh(or_i(and_v(v:pk({}),after(800000)),pk({})))",
+            key(&a),
+            key(&b)
+        )
+        .parse()
+        .unwrap();
+
+        let tx = spending_tx(&descriptor, 0xffff_fffe, 800_000);
+        let signatures = descriptor.sign(&tx, 0, 100_000, &[&a]).unwrap();
+        let witness = descriptor.satisfy(&signatures, &tx, 0).unwrap();
+        assert_eq!(witness[1], [1]);
+
+        for tx in [
+            spending_tx(&descriptor, 0xffff_fffe, 799_999),
+            spending_tx(&descriptor, SEQUENCE_FINAL, 800_000),
+            spending_tx(&descriptor, 0xffff_fffe, 1_700_000_000),
+        ] {
+            let signatures = descriptor.sign(&tx, 0, 100_000, &[&a]).unwrap();
+            assert!(descriptor.satisfy(&signatures, &tx, 0).is_err());
+        }
+
+        // The other branch has no timelock.
+        let tx = spending_tx(&descriptor, SEQUENCE_FINAL, 0);
+        let signatures = descriptor.sign(&tx, 0, 100_000, &[&b]).unwrap();
+        let witness = descriptor.satisfy(&signatures, &tx, 0).unwrap();
+        assert!(witness[1].is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Bitcoin networks and their address prefixes.
+
+use std::fmt;
+
+/// A Bitcoin network.
+///
+/// Determines the bech32 human-readable part and the base58 version bytes of
+/// addresses. Testnet and signet share both.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::Network;
+///
+/// assert_eq!(Network::Bitcoin.hrp(), "bc");
+/// assert_eq!(Network::Regtest.hrp(), "bcrt");
+/// assert_eq!(Network::from(khodpay_bip32::Network::BitcoinTestnet), Network::Testnet);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// Bitcoin mainnet.
+    Bitcoin,
+    /// Bitcoin testnet (testnet3 / testnet4).
+    Testnet,
+    /// Bitcoin signet.
+    Signet,
+    /// Local regression-test network.
+    Regtest,
+}
+
+impl Network {
+    /// Returns the bech32 human-readable part of SegWit addresses.
+    pub const fn hrp(&self) -> &'static str {
+        match self {
+            Network::Bitcoin => "bc",
+            Network::Testnet | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+        }
+    }
+
+    /// Returns the base58 version byte of P2PKH addresses.
+    pub const fn p2pkh_prefix(&self) -> u8 {
+        match self {
+            Network::Bitcoin => 0x00,
+            _ => 0x6f,
+        }
+    }
+
+    /// Returns the base58 version byte of P2SH addresses.
+    pub const fn p2sh_prefix(&self) -> u8 {
+        match self {
+            Network::Bitcoin => 0x05,
+            _ => 0xc4,
+        }
+    }
+
+    /// Returns `true` if addresses of `other` are valid on this network.
+    ///
+    /// Testnet and signet addresses are interchangeable, and regtest accepts their
+    /// base58 addresses too.
+    pub(crate) fn accepts(&self, other: Network) -> bool {
+        match (self, other) {
+            (Network::Bitcoin, Network::Bitcoin) => true,
+            (Network::Bitcoin, _) | (_, Network::Bitcoin) => false,
+            _ => self.hrp() == other.hrp(),
+        }
+    }
+}
+
+impl From<khodpay_bip32::Network> for Network {
+    fn from(network: khodpay_bip32::Network) -> Self {
+        match network {
+            khodpay_bip32::Network::BitcoinMainnet => Network::Bitcoin,
+            khodpay_bip32::Network::BitcoinTestnet => Network::Testnet,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Network::Bitcoin => "bitcoin",
+            Network::Testnet => "testnet",
+            Network::Signet => "signet",
+            Network::Regtest => "regtest",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl khodpay_tracing::LogSafe for Network {
+    fn log_value(&self) -> impl tracing::Value + '_ {
+        tracing::field::display(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixes() {
+        assert_eq!(Network::Bitcoin.p2pkh_prefix(), 0x00);
+        assert_eq!(Network::Bitcoin.p2sh_prefix(), 0x05);
+        assert_eq!(Network::Signet.p2pkh_prefix(), 0x6f);
+        assert_eq!(Network::Regtest.p2sh_prefix(), 0xc4);
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(Network::Bitcoin.accepts(Network::Bitcoin));
+        assert!(!Network::Bitcoin.accepts(Network::Testnet));
+        assert!(Network::Signet.accepts(Network::Testnet));
+        assert!(!Network::Regtest.accepts(Network::Testnet));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Network::Bitcoin.to_string(), "bitcoin");
+        assert_eq!(Network::Signet.to_string(), "signet");
+    }
+}
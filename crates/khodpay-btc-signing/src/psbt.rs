@@ -0,0 +1,1762 @@
+//! Partially Signed Bitcoin Transactions, version 2 (BIP-174 / BIP-370).
+//!
+//! PSBTv2 drops the global unsigned transaction of version 0. Every input and output
+//! carries its own fields (previous outpoint, sequence, amount, script), and the
+//! transaction is only assembled when it is signed or extracted. That lets a
+//! coordinator add inputs and outputs after creation, as long as the
+//! `PSBT_GLOBAL_TX_MODIFIABLE` flags allow it.
+//!
+//! The roles map onto [`Psbt`] as follows:
+//!
+//! | Role | API |
+//! |---|---|
+//! | Creator / Constructor | [`Psbt::new`], [`Psbt::add_input`], [`Psbt::add_output`], [`TransactionBuilder::build_psbt`](crate::TransactionBuilder::build_psbt) |
+//! | Updater | [`Psbt::input_mut`] / [`Psbt::output_mut`] ([`PsbtInput::witness_utxo`], [`KeySource`]s, …) |
+//! | Signer | [`Psbt::sign`] (P2PKH, P2SH-P2WPKH, P2WPKH and P2WSH inputs) |
+//! | Finalizer / Extractor | [`Psbt::finalize`], [`Psbt::finalize_miniscript`], [`Psbt::extract_tx`] |
+//! | Reviewer | [`Psbt::fee`], [`Psbt::preview`] (inputs, outputs and change for a confirmation screen) |
+//!
+//! Version 0 PSBTs are rejected when parsed. Fields this crate does not interpret,
+//! including global xpubs and proprietary keys, are kept in `unknown` maps and
+//! written back unchanged.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_btc_signing::psbt::{Psbt, PsbtInput, PsbtOutput};
+//! use khodpay_btc_signing::{BtcSigner, Network, OutPoint, Script, TxOut, Txid};
+//!
+//! let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+//!
+//! // Constructor: inputs and outputs can be added while the PSBT is modifiable.
+//! let mut psbt = Psbt::new(2);
+//! let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([7; 32]), 0));
+//! input.witness_utxo = Some(TxOut {
+//!     value: 20_000,
+//!     script_pubkey: signer.p2wpkh_address().script_pubkey(),
+//! });
+//! psbt.add_input(input).unwrap();
+//! psbt.add_output(PsbtOutput::new(19_000, Script::p2wpkh(&[9; 20]))).unwrap();
+//!
+//! // The PSBT travels as base64 between coordinator and signers.
+//! let mut psbt: Psbt = psbt.to_string().parse().unwrap();
+//!
+//! // Signer, finalizer, extractor.
+//! assert_eq!(psbt.sign(&signer).unwrap(), 1);
+//! assert!(!psbt.inputs_modifiable());
+//! psbt.finalize().unwrap();
+//! let tx = psbt.extract_tx().unwrap();
+//! assert!(tx.has_witness());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::hash::{hash160, sha256};
+use crate::miniscript::Descriptor;
+use crate::sighash::{SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_NONE, SIGHASH_SINGLE};
+use crate::transaction::{write_bytes, write_compact_size, write_txout, Reader};
+use crate::{
+    Address, BtcSigner, Error, Network, OutPoint, Result, Script, ScriptType, Transaction, TxIn,
+    TxOut, Txid, SEQUENCE_FINAL,
+};
+
+/// The PSBT version this module reads and writes.
+pub const PSBT_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 5] = b"psbt\xff";
+
+// Global key types.
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_TX_VERSION: u8 = 0x02;
+const PSBT_GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const PSBT_GLOBAL_INPUT_COUNT: u8 = 0x04;
+const PSBT_GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const PSBT_GLOBAL_TX_MODIFIABLE: u8 = 0x06;
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+// Input key types.
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_REDEEM_SCRIPT: u8 = 0x04;
+const PSBT_IN_WITNESS_SCRIPT: u8 = 0x05;
+const PSBT_IN_BIP32_DERIVATION: u8 = 0x06;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+const PSBT_IN_PREVIOUS_TXID: u8 = 0x0e;
+const PSBT_IN_OUTPUT_INDEX: u8 = 0x0f;
+const PSBT_IN_SEQUENCE: u8 = 0x10;
+const PSBT_IN_REQUIRED_TIME_LOCKTIME: u8 = 0x11;
+const PSBT_IN_REQUIRED_HEIGHT_LOCKTIME: u8 = 0x12;
+
+// Output key types.
+const PSBT_OUT_REDEEM_SCRIPT: u8 = 0x00;
+const PSBT_OUT_WITNESS_SCRIPT: u8 = 0x01;
+const PSBT_OUT_BIP32_DERIVATION: u8 = 0x02;
+const PSBT_OUT_AMOUNT: u8 = 0x03;
+const PSBT_OUT_SCRIPT: u8 = 0x04;
+
+// PSBT_GLOBAL_TX_MODIFIABLE bits.
+const INPUTS_MODIFIABLE: u8 = 0x01;
+const OUTPUTS_MODIFIABLE: u8 = 0x02;
+const HAS_SIGHASH_SINGLE: u8 = 0x04;
+
+/// Lock-time values from 500,000,000 on are Unix timestamps, below are block heights.
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Raw key-value pairs of one PSBT map, keyed by `type || keydata`.
+type RawMap = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Master key fingerprint and derivation path of a public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySource {
+    /// First four bytes of the HASH160 of the master public key.
+    pub fingerprint: [u8; 4],
+    /// Child numbers from the master key, hardened ones with bit 31 set.
+    pub path: Vec<u32>,
+}
+
+impl KeySource {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = self.fingerprint.to_vec();
+        for child in &self.path {
+            buf.extend_from_slice(&child.to_le_bytes());
+        }
+        buf
+    }
+
+    fn decode(value: &[u8]) -> Result<Self> {
+        if value.len() < 4 || value.len() % 4 != 0 {
+            return Err(psbt_error(format!(
+                "BIP-32 derivation of {} bytes",
+                value.len()
+            )));
+        }
+        let (fingerprint, path) = value.split_at(4);
+        Ok(Self {
+            fingerprint: fingerprint.try_into().expect("length checked"),
+            path: path
+                .chunks_exact(4)
+                .map(|child| u32::from_le_bytes(child.try_into().expect("chunk of 4")))
+                .collect(),
+        })
+    }
+}
+
+/// Per-input fields of a PSBTv2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtInput {
+    /// The output being spent (`PSBT_IN_PREVIOUS_TXID` / `PSBT_IN_OUTPUT_INDEX`).
+    pub previous_output: OutPoint,
+    /// `nSequence`; [`SEQUENCE_FINAL`] when absent.
+    pub sequence: Option<u32>,
+    /// Minimum Unix-time lock-time this input requires.
+    pub required_time_lock_time: Option<u32>,
+    /// Minimum block-height lock-time this input requires.
+    pub required_height_lock_time: Option<u32>,
+    /// Full serialized transaction that created the spent output.
+    pub non_witness_utxo: Option<Vec<u8>>,
+    /// The spent output, for SegWit inputs.
+    pub witness_utxo: Option<TxOut>,
+    /// Signatures collected so far, by public key.
+    pub partial_sigs: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Sighash type signers must use; `SIGHASH_ALL` when absent.
+    pub sighash_type: Option<u32>,
+    /// P2SH redeem script.
+    pub redeem_script: Option<Script>,
+    /// P2WSH witness script.
+    pub witness_script: Option<Script>,
+    /// Origins of the public keys involved, for hardware signers.
+    pub bip32_derivation: BTreeMap<Vec<u8>, KeySource>,
+    /// Finalized `scriptSig`.
+    pub final_script_sig: Option<Script>,
+    /// Finalized witness stack.
+    pub final_script_witness: Option<Vec<Vec<u8>>>,
+    /// Fields not interpreted by this crate.
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PsbtInput {
+    /// Creates an input spending `previous_output` with no other fields set.
+    pub fn new(previous_output: OutPoint) -> Self {
+        Self {
+            previous_output,
+            sequence: None,
+            required_time_lock_time: None,
+            required_height_lock_time: None,
+            non_witness_utxo: None,
+            witness_utxo: None,
+            partial_sigs: BTreeMap::new(),
+            sighash_type: None,
+            redeem_script: None,
+            witness_script: None,
+            bip32_derivation: BTreeMap::new(),
+            final_script_sig: None,
+            final_script_witness: None,
+            unknown: BTreeMap::new(),
+        }
+    }
+
+    /// Returns `true` once a finalizer has filled in the `scriptSig` or witness.
+    pub fn is_finalized(&self) -> bool {
+        self.final_script_sig.is_some() || self.final_script_witness.is_some()
+    }
+
+    /// Returns the output this input spends, taken from the non-witness UTXO when
+    /// present and from the witness UTXO otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if the non-witness UTXO does not parse, is not the
+    /// previous transaction or lacks the spent output.
+    pub fn spent_output(&self) -> Result<Option<TxOut>> {
+        let Some(raw) = &self.non_witness_utxo else {
+            return Ok(self.witness_utxo.clone());
+        };
+        let previous = Transaction::deserialize(raw)?;
+        if previous.txid() != self.previous_output.txid {
+            return Err(psbt_error(format!(
+                "non-witness UTXO {} does not match previous txid {}",
+                previous.txid(),
+                self.previous_output.txid
+            )));
+        }
+        previous
+            .outputs
+            .get(self.previous_output.vout as usize)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| {
+                psbt_error(format!(
+                    "non-witness UTXO has no output {}",
+                    self.previous_output.vout
+                ))
+            })
+    }
+
+    /// Sets the final `scriptSig` and witness, dropping the fields only signers and
+    /// finalizers need.
+    fn set_final(&mut self, script_sig: Script, witness: Vec<Vec<u8>>) {
+        self.final_script_sig = (!script_sig.is_empty()).then_some(script_sig);
+        self.final_script_witness = (!witness.is_empty()).then_some(witness);
+        self.partial_sigs.clear();
+        self.sighash_type = None;
+        self.redeem_script = None;
+        self.witness_script = None;
+        self.bip32_derivation.clear();
+    }
+
+    fn encode(&self) -> RawMap {
+        let mut map = self.unknown.clone();
+        let mut insert = |key_type: u8, value: Vec<u8>| {
+            map.insert(vec![key_type], value);
+        };
+        insert(
+            PSBT_IN_PREVIOUS_TXID,
+            self.previous_output.txid.as_bytes().to_vec(),
+        );
+        insert(
+            PSBT_IN_OUTPUT_INDEX,
+            self.previous_output.vout.to_le_bytes().to_vec(),
+        );
+        if let Some(sequence) = self.sequence {
+            insert(PSBT_IN_SEQUENCE, sequence.to_le_bytes().to_vec());
+        }
+        if let Some(time) = self.required_time_lock_time {
+            insert(PSBT_IN_REQUIRED_TIME_LOCKTIME, time.to_le_bytes().to_vec());
+        }
+        if let Some(height) = self.required_height_lock_time {
+            insert(
+                PSBT_IN_REQUIRED_HEIGHT_LOCKTIME,
+                height.to_le_bytes().to_vec(),
+            );
+        }
+        if let Some(tx) = &self.non_witness_utxo {
+            insert(PSBT_IN_NON_WITNESS_UTXO, tx.clone());
+        }
+        if let Some(utxo) = &self.witness_utxo {
+            let mut buf = Vec::new();
+            write_txout(&mut buf, utxo);
+            insert(PSBT_IN_WITNESS_UTXO, buf);
+        }
+        if let Some(sighash_type) = self.sighash_type {
+            insert(PSBT_IN_SIGHASH_TYPE, sighash_type.to_le_bytes().to_vec());
+        }
+        if let Some(script) = &self.redeem_script {
+            insert(PSBT_IN_REDEEM_SCRIPT, script.as_bytes().to_vec());
+        }
+        if let Some(script) = &self.witness_script {
+            insert(PSBT_IN_WITNESS_SCRIPT, script.as_bytes().to_vec());
+        }
+        if let Some(script) = &self.final_script_sig {
+            insert(PSBT_IN_FINAL_SCRIPTSIG, script.as_bytes().to_vec());
+        }
+        if let Some(witness) = &self.final_script_witness {
+            let mut buf = Vec::new();
+            write_compact_size(&mut buf, witness.len() as u64);
+            for item in witness {
+                write_bytes(&mut buf, item);
+            }
+            insert(PSBT_IN_FINAL_SCRIPTWITNESS, buf);
+        }
+        for (pubkey, signature) in &self.partial_sigs {
+            map.insert(keyed(PSBT_IN_PARTIAL_SIG, pubkey), signature.clone());
+        }
+        for (pubkey, source) in &self.bip32_derivation {
+            map.insert(keyed(PSBT_IN_BIP32_DERIVATION, pubkey), source.encode());
+        }
+        map
+    }
+
+    fn decode(map: RawMap) -> Result<Self> {
+        let mut txid = None;
+        let mut vout = None;
+        let mut input = Self::new(OutPoint::default());
+        for (key, value) in map {
+            let (key_type, key_data) = (key[0], &key[1..]);
+            match key_type {
+                PSBT_IN_PREVIOUS_TXID => {
+                    no_key_data(&key, "previous txid")?;
+                    txid = Some(Txid::from_bytes(fixed(&value, "previous txid")?));
+                }
+                PSBT_IN_OUTPUT_INDEX => {
+                    no_key_data(&key, "output index")?;
+                    vout = Some(u32_value(&value, "output index")?);
+                }
+                PSBT_IN_SEQUENCE => {
+                    no_key_data(&key, "sequence")?;
+                    input.sequence = Some(u32_value(&value, "sequence")?);
+                }
+                PSBT_IN_REQUIRED_TIME_LOCKTIME => {
+                    no_key_data(&key, "required time lock-time")?;
+                    let time = u32_value(&value, "required time lock-time")?;
+                    if time < LOCKTIME_THRESHOLD {
+                        return Err(psbt_error(format!(
+                            "required time lock-time {} below 500000000",
+                            time
+                        )));
+                    }
+                    input.required_time_lock_time = Some(time);
+                }
+                PSBT_IN_REQUIRED_HEIGHT_LOCKTIME => {
+                    no_key_data(&key, "required height lock-time")?;
+                    let height = u32_value(&value, "required height lock-time")?;
+                    if height == 0 || height >= LOCKTIME_THRESHOLD {
+                        return Err(psbt_error(format!(
+                            "required height lock-time {} out of range",
+                            height
+                        )));
+                    }
+                    input.required_height_lock_time = Some(height);
+                }
+                PSBT_IN_NON_WITNESS_UTXO => {
+                    no_key_data(&key, "non-witness UTXO")?;
+                    input.non_witness_utxo = Some(value);
+                }
+                PSBT_IN_WITNESS_UTXO => {
+                    no_key_data(&key, "witness UTXO")?;
+                    let mut reader = Reader::new(&value);
+                    let utxo = reader.read_txout().map_err(invalid("witness UTXO"))?;
+                    if !reader.is_empty() {
+                        return Err(psbt_error("trailing bytes in witness UTXO"));
+                    }
+                    input.witness_utxo = Some(utxo);
+                }
+                PSBT_IN_PARTIAL_SIG => {
+                    public_key_data(key_data, "partial signature")?;
+                    input.partial_sigs.insert(key_data.to_vec(), value);
+                }
+                PSBT_IN_SIGHASH_TYPE => {
+                    no_key_data(&key, "sighash type")?;
+                    input.sighash_type = Some(u32_value(&value, "sighash type")?);
+                }
+                PSBT_IN_REDEEM_SCRIPT => {
+                    no_key_data(&key, "redeem script")?;
+                    input.redeem_script = Some(Script::new(value));
+                }
+                PSBT_IN_WITNESS_SCRIPT => {
+                    no_key_data(&key, "witness script")?;
+                    input.witness_script = Some(Script::new(value));
+                }
+                PSBT_IN_BIP32_DERIVATION => {
+                    public_key_data(key_data, "BIP-32 derivation")?;
+                    input
+                        .bip32_derivation
+                        .insert(key_data.to_vec(), KeySource::decode(&value)?);
+                }
+                PSBT_IN_FINAL_SCRIPTSIG => {
+                    no_key_data(&key, "final scriptSig")?;
+                    input.final_script_sig = Some(Script::new(value));
+                }
+                PSBT_IN_FINAL_SCRIPTWITNESS => {
+                    no_key_data(&key, "final witness")?;
+                    input.final_script_witness = Some(decode_witness(&value)?);
+                }
+                _ => {
+                    input.unknown.insert(key, value);
+                }
+            }
+        }
+        match (txid, vout) {
+            (Some(txid), Some(vout)) => {
+                input.previous_output = OutPoint::new(txid, vout);
+                Ok(input)
+            }
+            _ => Err(psbt_error("input without previous txid or output index")),
+        }
+    }
+}
+
+/// Per-output fields of a PSBTv2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtOutput {
+    /// Amount in satoshis.
+    pub amount: u64,
+    /// Output script.
+    pub script_pubkey: Script,
+    /// P2SH redeem script, if the output is the signer's own.
+    pub redeem_script: Option<Script>,
+    /// P2WSH witness script, if the output is the signer's own.
+    pub witness_script: Option<Script>,
+    /// Origins of the output's keys, so hardware signers can recognise change.
+    pub bip32_derivation: BTreeMap<Vec<u8>, KeySource>,
+    /// Fields not interpreted by this crate.
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PsbtOutput {
+    /// Creates an output paying `amount` satoshis to `script_pubkey`.
+    pub fn new(amount: u64, script_pubkey: Script) -> Self {
+        Self {
+            amount,
+            script_pubkey,
+            redeem_script: None,
+            witness_script: None,
+            bip32_derivation: BTreeMap::new(),
+            unknown: BTreeMap::new(),
+        }
+    }
+
+    fn encode(&self) -> RawMap {
+        let mut map = self.unknown.clone();
+        map.insert(vec![PSBT_OUT_AMOUNT], self.amount.to_le_bytes().to_vec());
+        map.insert(
+            vec![PSBT_OUT_SCRIPT],
+            self.script_pubkey.as_bytes().to_vec(),
+        );
+        if let Some(script) = &self.redeem_script {
+            map.insert(vec![PSBT_OUT_REDEEM_SCRIPT], script.as_bytes().to_vec());
+        }
+        if let Some(script) = &self.witness_script {
+            map.insert(vec![PSBT_OUT_WITNESS_SCRIPT], script.as_bytes().to_vec());
+        }
+        for (pubkey, source) in &self.bip32_derivation {
+            map.insert(keyed(PSBT_OUT_BIP32_DERIVATION, pubkey), source.encode());
+        }
+        map
+    }
+
+    fn decode(map: RawMap) -> Result<Self> {
+        let mut amount = None;
+        let mut script_pubkey = None;
+        let mut output = Self::new(0, Script::default());
+        for (key, value) in map {
+            let (key_type, key_data) = (key[0], &key[1..]);
+            match key_type {
+                PSBT_OUT_AMOUNT => {
+                    no_key_data(&key, "amount")?;
+                    let value = i64::from_le_bytes(fixed(&value, "amount")?);
+                    amount = Some(
+                        u64::try_from(value)
+                            .map_err(|_| psbt_error(format!("negative amount {}", value)))?,
+                    );
+                }
+                PSBT_OUT_SCRIPT => {
+                    no_key_data(&key, "output script")?;
+                    script_pubkey = Some(Script::new(value));
+                }
+                PSBT_OUT_REDEEM_SCRIPT => {
+                    no_key_data(&key, "redeem script")?;
+                    output.redeem_script = Some(Script::new(value));
+                }
+                PSBT_OUT_WITNESS_SCRIPT => {
+                    no_key_data(&key, "witness script")?;
+                    output.witness_script = Some(Script::new(value));
+                }
+                PSBT_OUT_BIP32_DERIVATION => {
+                    public_key_data(key_data, "BIP-32 derivation")?;
+                    output
+                        .bip32_derivation
+                        .insert(key_data.to_vec(), KeySource::decode(&value)?);
+                }
+                _ => {
+                    output.unknown.insert(key, value);
+                }
+            }
+        }
+        match (amount, script_pubkey) {
+            (Some(amount), Some(script_pubkey)) => Ok(Self {
+                amount,
+                script_pubkey,
+                ..output
+            }),
+            _ => Err(psbt_error("output without amount or script")),
+        }
+    }
+}
+
+/// A version 2 Partially Signed Bitcoin Transaction.
+///
+/// Serializes to the binary format with [`serialize`](Self::serialize) and to base64
+/// with [`Display`](fmt::Display); parses from both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psbt {
+    /// Version of the transaction being built.
+    pub tx_version: i32,
+    /// `nLockTime` to use when no input requires one.
+    pub fallback_lock_time: Option<u32>,
+    /// Global fields not interpreted by this crate, such as xpubs.
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+    tx_modifiable: u8,
+    inputs: Vec<PsbtInput>,
+    outputs: Vec<PsbtOutput>,
+}
+
+impl Psbt {
+    /// Creates an empty PSBT (the Creator role) that accepts new inputs and outputs.
+    pub fn new(tx_version: i32) -> Self {
+        Self {
+            tx_version,
+            fallback_lock_time: None,
+            unknown: BTreeMap::new(),
+            tx_modifiable: INPUTS_MODIFIABLE | OUTPUTS_MODIFIABLE,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Returns the inputs.
+    pub fn inputs(&self) -> &[PsbtInput] {
+        &self.inputs
+    }
+
+    /// Returns the outputs.
+    pub fn outputs(&self) -> &[PsbtOutput] {
+        &self.outputs
+    }
+
+    /// Returns input `index` for an Updater to fill in, if it exists.
+    pub fn input_mut(&mut self, index: usize) -> Option<&mut PsbtInput> {
+        self.inputs.get_mut(index)
+    }
+
+    /// Returns output `index` for an Updater to fill in, if it exists.
+    pub fn output_mut(&mut self, index: usize) -> Option<&mut PsbtOutput> {
+        self.outputs.get_mut(index)
+    }
+
+    /// Returns `true` if inputs may still be added.
+    pub fn inputs_modifiable(&self) -> bool {
+        self.tx_modifiable & INPUTS_MODIFIABLE != 0
+    }
+
+    /// Returns `true` if outputs may still be added.
+    pub fn outputs_modifiable(&self) -> bool {
+        self.tx_modifiable & OUTPUTS_MODIFIABLE != 0
+    }
+
+    /// Returns `true` if some input was signed with `SIGHASH_SINGLE`, so inputs and
+    /// outputs may only be added in pairs.
+    pub fn has_sighash_single(&self) -> bool {
+        self.tx_modifiable & HAS_SIGHASH_SINGLE != 0
+    }
+
+    /// Allows or forbids adding inputs. Constructors clear this once done.
+    pub fn set_inputs_modifiable(&mut self, modifiable: bool) {
+        self.set_flag(INPUTS_MODIFIABLE, modifiable);
+    }
+
+    /// Allows or forbids adding outputs. Constructors clear this once done.
+    pub fn set_outputs_modifiable(&mut self, modifiable: bool) {
+        self.set_flag(OUTPUTS_MODIFIABLE, modifiable);
+    }
+
+    fn set_flag(&mut self, flag: u8, on: bool) {
+        if on {
+            self.tx_modifiable |= flag;
+        } else {
+            self.tx_modifiable &= !flag;
+        }
+    }
+
+    /// Adds an input (the Constructor role).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if inputs are not modifiable, the outpoint is
+    /// already spent by another input, or the input's lock-time requirement cannot be
+    /// met together with the existing ones.
+    pub fn add_input(&mut self, input: PsbtInput) -> Result<()> {
+        if !self.inputs_modifiable() {
+            return Err(psbt_error("inputs are not modifiable"));
+        }
+        if self.has_sighash_single() && self.inputs.len() >= self.outputs.len() {
+            return Err(psbt_error(
+                "a SIGHASH_SINGLE signature requires adding an output before another input",
+            ));
+        }
+        if self
+            .inputs
+            .iter()
+            .any(|existing| existing.previous_output == input.previous_output)
+        {
+            return Err(psbt_error(format!(
+                "{} is already spent by another input",
+                input.previous_output
+            )));
+        }
+        self.inputs.push(input);
+        if let Err(err) = self.lock_time() {
+            self.inputs.pop();
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Adds an output (the Constructor role).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if outputs are not modifiable.
+    pub fn add_output(&mut self, output: PsbtOutput) -> Result<()> {
+        if !self.outputs_modifiable() {
+            return Err(psbt_error("outputs are not modifiable"));
+        }
+        self.outputs.push(output);
+        Ok(())
+    }
+
+    /// Determines `nLockTime` as specified by BIP-370.
+    ///
+    /// Without input requirements this is the fallback lock-time (or 0). Otherwise it
+    /// is the largest required height if every constrained input accepts a height,
+    /// else the largest required time if every constrained input accepts a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if some inputs require a height and others a time.
+    pub fn lock_time(&self) -> Result<u32> {
+        let constrained: Vec<&PsbtInput> = self
+            .inputs
+            .iter()
+            .filter(|input| {
+                input.required_height_lock_time.is_some() || input.required_time_lock_time.is_some()
+            })
+            .collect();
+        if constrained.is_empty() {
+            return Ok(self.fallback_lock_time.unwrap_or(0));
+        }
+        let heights: Option<Vec<u32>> = constrained
+            .iter()
+            .map(|input| input.required_height_lock_time)
+            .collect();
+        if let Some(heights) = heights {
+            return Ok(heights.into_iter().max().unwrap_or(0));
+        }
+        let times: Option<Vec<u32>> = constrained
+            .iter()
+            .map(|input| input.required_time_lock_time)
+            .collect();
+        times
+            .map(|times| times.into_iter().max().unwrap_or(0))
+            .ok_or_else(|| psbt_error("inputs require both height and time lock-times"))
+    }
+
+    /// Assembles the unsigned transaction described by the PSBT.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if no lock-time satisfies all inputs.
+    pub fn unsigned_tx(&self) -> Result<Transaction> {
+        Ok(Transaction {
+            version: self.tx_version,
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| {
+                    TxIn::new(
+                        input.previous_output,
+                        input.sequence.unwrap_or(SEQUENCE_FINAL),
+                    )
+                })
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|output| TxOut {
+                    value: output.amount,
+                    script_pubkey: output.script_pubkey.clone(),
+                })
+                .collect(),
+            lock_time: self.lock_time()?,
+        })
+    }
+
+    /// Signs every unfinalized input whose spent output pays `signer` as P2PKH,
+    /// P2SH-P2WPKH or P2WPKH (the Signer role), returning how many inputs were signed.
+    ///
+    /// P2WSH inputs are signed too when their [`witness_script`](PsbtInput::witness_script)
+    /// matches the spent output and contains the signer's public key or its hash.
+    ///
+    /// Each input is signed with its requested sighash type, `SIGHASH_ALL` by default.
+    /// Afterwards the modifiable flags are narrowed as BIP-370 requires: inputs are
+    /// frozen unless `SIGHASH_ANYONECANPAY` was used, outputs unless `SIGHASH_NONE`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] for an unsupported sighash type or a P2PKH input
+    /// without its non-witness UTXO (the legacy signature hash does not commit to the
+    /// spent amount), or [`Error::PsbtError`] if the transaction cannot be assembled or
+    /// a non-witness UTXO is invalid.
+    pub fn sign(&mut self, signer: &BtcSigner) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let operation = khodpay_tracing::operation!(
+            "sign_psbt",
+            network = signer.network(),
+            inputs = self.inputs.len(),
+            signed = _
+        );
+
+        let tx = self.unsigned_tx()?;
+        let public_key = signer.public_key().to_vec();
+        let mut signed = 0;
+        for index in 0..self.inputs.len() {
+            let input = &self.inputs[index];
+            if input.is_finalized() {
+                continue;
+            }
+            let Some(utxo) = input.spent_output()? else {
+                continue;
+            };
+            let spend = match signer.script_type_of(&utxo.script_pubkey) {
+                Some(script_type) => Spend::Key(script_type),
+                None => match &input.witness_script {
+                    Some(witness_script)
+                        if utxo.script_pubkey
+                            == Script::p2wsh(&sha256(witness_script.as_bytes()))
+                            && uses_key(witness_script, signer) =>
+                    {
+                        Spend::WitnessScript(witness_script.clone())
+                    }
+                    _ => continue,
+                },
+            };
+            if matches!(spend, Spend::Key(ScriptType::P2pkh)) && input.non_witness_utxo.is_none() {
+                return Err(Error::SigningError(format!(
+                    "P2PKH input {} needs its non-witness UTXO",
+                    index
+                )));
+            }
+            let sighash_type = input.sighash_type.unwrap_or(SIGHASH_ALL);
+            let base_type = sighash_type & !SIGHASH_ANYONECANPAY;
+            if !(SIGHASH_ALL..=SIGHASH_SINGLE).contains(&base_type) {
+                return Err(Error::SigningError(format!(
+                    "unsupported sighash type 0x{:02x} on input {}",
+                    sighash_type, index
+                )));
+            }
+            if base_type == SIGHASH_SINGLE && index >= tx.outputs.len() {
+                return Err(Error::SigningError(format!(
+                    "SIGHASH_SINGLE input {} has no matching output",
+                    index
+                )));
+            }
+
+            let signature = match &spend {
+                Spend::Key(script_type) => {
+                    signer.sign_input(&tx, index, *script_type, utxo.value, sighash_type)?
+                }
+                Spend::WitnessScript(witness_script) => signer.sign_witness_script(
+                    &tx,
+                    index,
+                    witness_script,
+                    utxo.value,
+                    sighash_type,
+                )?,
+            };
+            self.inputs[index]
+                .partial_sigs
+                .insert(public_key.clone(), signature);
+            signed += 1;
+
+            if sighash_type & SIGHASH_ANYONECANPAY == 0 {
+                self.set_flag(INPUTS_MODIFIABLE, false);
+            }
+            if base_type != SIGHASH_NONE {
+                self.set_flag(OUTPUTS_MODIFIABLE, false);
+            }
+            if base_type == SIGHASH_SINGLE {
+                self.set_flag(HAS_SIGHASH_SINGLE, true);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        operation.record("signed", &signed);
+        Ok(signed)
+    }
+
+    /// Builds the final `scriptSig` and witness of every P2PKH, P2SH-P2WPKH and P2WPKH
+    /// input that has its signature (the Finalizer role), returning how many inputs
+    /// were finalized.
+    ///
+    /// Finalized inputs keep only their UTXOs, final scripts and unknown fields.
+    /// Inputs of other script types are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if a non-witness UTXO is invalid.
+    pub fn finalize(&mut self) -> Result<usize> {
+        let mut finalized = 0;
+        for input in &mut self.inputs {
+            if input.is_finalized() {
+                continue;
+            }
+            let Some(utxo) = input.spent_output()? else {
+                continue;
+            };
+            let Some((script_type, public_key, signature)) =
+                input
+                    .partial_sigs
+                    .iter()
+                    .find_map(|(public_key, signature)| {
+                        ScriptType::matching(&utxo.script_pubkey, &hash160(public_key))
+                            .map(|script_type| (script_type, public_key, signature))
+                    })
+            else {
+                continue;
+            };
+
+            let (script_sig, witness) = script_type.satisfaction(public_key, signature);
+            input.set_final(script_sig, witness);
+            finalized += 1;
+        }
+        Ok(finalized)
+    }
+
+    /// Builds the final witness of every input spending `descriptor`'s output whose
+    /// signatures and timelocks satisfy it, returning how many inputs were finalized.
+    ///
+    /// Inputs paying other scripts, and inputs still missing signatures or whose
+    /// sequence or the lock time does not meet a timelock, are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if the transaction cannot be assembled or a
+    /// non-witness UTXO is invalid.
+    pub fn finalize_miniscript(&mut self, descriptor: &Descriptor) -> Result<usize> {
+        let tx = self.unsigned_tx()?;
+        let script_pubkey = descriptor.script_pubkey();
+        let mut finalized = 0;
+        for (index, input) in self.inputs.iter_mut().enumerate() {
+            if input.is_finalized() {
+                continue;
+            }
+            match input.spent_output()? {
+                Some(utxo) if utxo.script_pubkey == script_pubkey => {}
+                _ => continue,
+            }
+            let Ok(witness) = descriptor.satisfy(&input.partial_sigs, &tx, index) else {
+                continue;
+            };
+            input.set_final(Script::default(), witness);
+            finalized += 1;
+        }
+        Ok(finalized)
+    }
+
+    /// Extracts the network-ready transaction (the Extractor role).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if an input is not finalized.
+    pub fn extract_tx(&self) -> Result<Transaction> {
+        let mut tx = self.unsigned_tx()?;
+        for (index, (txin, input)) in tx.inputs.iter_mut().zip(&self.inputs).enumerate() {
+            if !input.is_finalized() {
+                return Err(psbt_error(format!("input {} is not finalized", index)));
+            }
+            if let Some(script_sig) = &input.final_script_sig {
+                txin.script_sig = script_sig.clone();
+            }
+            if let Some(witness) = &input.final_script_witness {
+                txin.witness = witness.clone();
+            }
+        }
+        Ok(tx)
+    }
+
+    /// Returns the fee: the value of the spent outputs minus the outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if an input lacks its UTXO, a non-witness UTXO is
+    /// invalid, or the outputs exceed the inputs.
+    pub fn fee(&self) -> Result<u64> {
+        let mut input_value = 0u64;
+        for (index, input) in self.inputs.iter().enumerate() {
+            let utxo = input
+                .spent_output()?
+                .ok_or_else(|| psbt_error(format!("input {} has no UTXO", index)))?;
+            input_value = input_value
+                .checked_add(utxo.value)
+                .ok_or_else(|| psbt_error("input value overflows"))?;
+        }
+        let output_value = self
+            .outputs
+            .iter()
+            .try_fold(0u64, |sum, output| sum.checked_add(output.amount))
+            .ok_or_else(|| psbt_error("output value overflows"))?;
+        input_value.checked_sub(output_value).ok_or_else(|| {
+            psbt_error(format!(
+                "outputs of {} sats exceed inputs of {} sats",
+                output_value, input_value
+            ))
+        })
+    }
+
+    /// Summarizes the PSBT for a confirmation screen.
+    ///
+    /// Inputs and outputs paying any of `own` (as P2PKH, P2SH-P2WPKH or P2WPKH) are
+    /// marked as the wallet's; such outputs are change. Addresses are rendered for
+    /// `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if a non-witness UTXO is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_btc_signing::psbt::{Psbt, PsbtInput, PsbtOutput};
+    /// use khodpay_btc_signing::{BtcSigner, Network, OutPoint, Script, TxOut, Txid};
+    ///
+    /// let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+    /// let mine = signer.p2wpkh_address().script_pubkey();
+    ///
+    /// let mut psbt = Psbt::new(2);
+    /// let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([7; 32]), 0));
+    /// input.witness_utxo = Some(TxOut { value: 20_000, script_pubkey: mine.clone() });
+    /// psbt.add_input(input).unwrap();
+    /// psbt.add_output(PsbtOutput::new(12_000, Script::p2wpkh(&[9; 20]))).unwrap();
+    /// psbt.add_output(PsbtOutput::new(7_000, mine)).unwrap();
+    ///
+    /// let preview = psbt.preview(Network::Bitcoin, &[&signer]).unwrap();
+    /// assert_eq!(preview.fee, Some(1_000));
+    /// assert_eq!(preview.sent(), 12_000);
+    /// assert_eq!(preview.change(), 7_000);
+    /// ```
+    pub fn preview(&self, network: Network, own: &[&BtcSigner]) -> Result<PsbtPreview> {
+        let is_own = |script: &Script| {
+            own.iter()
+                .any(|signer| signer.script_type_of(script).is_some())
+        };
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let utxo = input.spent_output()?;
+                Ok(InputPreview {
+                    previous_output: input.previous_output,
+                    amount: utxo.as_ref().map(|utxo| utxo.value),
+                    address: utxo
+                        .as_ref()
+                        .and_then(|utxo| Address::from_script(&utxo.script_pubkey, network)),
+                    is_mine: utxo
+                        .as_ref()
+                        .is_some_and(|utxo| is_own(&utxo.script_pubkey)),
+                    is_signed: input.is_finalized() || !input.partial_sigs.is_empty(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|output| OutputPreview {
+                address: Address::from_script(&output.script_pubkey, network),
+                script_pubkey: output.script_pubkey.clone(),
+                amount: output.amount,
+                is_change: is_own(&output.script_pubkey),
+            })
+            .collect();
+        let fee = if inputs.iter().all(|input| input.amount.is_some()) {
+            self.fee().ok()
+        } else {
+            None
+        };
+        Ok(PsbtPreview {
+            inputs,
+            outputs,
+            fee,
+        })
+    }
+
+    /// Serializes the PSBT in the BIP-174 binary format.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut global = self.unknown.clone();
+        global.insert(
+            vec![PSBT_GLOBAL_TX_VERSION],
+            self.tx_version.to_le_bytes().to_vec(),
+        );
+        if let Some(lock_time) = self.fallback_lock_time {
+            global.insert(
+                vec![PSBT_GLOBAL_FALLBACK_LOCKTIME],
+                lock_time.to_le_bytes().to_vec(),
+            );
+        }
+        let mut count = Vec::new();
+        write_compact_size(&mut count, self.inputs.len() as u64);
+        global.insert(vec![PSBT_GLOBAL_INPUT_COUNT], count);
+        let mut count = Vec::new();
+        write_compact_size(&mut count, self.outputs.len() as u64);
+        global.insert(vec![PSBT_GLOBAL_OUTPUT_COUNT], count);
+        if self.tx_modifiable != 0 {
+            global.insert(vec![PSBT_GLOBAL_TX_MODIFIABLE], vec![self.tx_modifiable]);
+        }
+        global.insert(
+            vec![PSBT_GLOBAL_VERSION],
+            PSBT_VERSION.to_le_bytes().to_vec(),
+        );
+
+        let mut buf = MAGIC.to_vec();
+        write_map(&mut buf, &global);
+        for input in &self.inputs {
+            write_map(&mut buf, &input.encode());
+        }
+        for output in &self.outputs {
+            write_map(&mut buf, &output.encode());
+        }
+        buf
+    }
+
+    /// Parses a PSBT from the BIP-174 binary format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] if the data is malformed, has duplicate keys,
+    /// lacks a required field, or is not version 2.
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        if reader.read_bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(psbt_error("missing magic bytes"));
+        }
+
+        let mut global = read_map(&mut reader)?;
+        let version = match global.remove([PSBT_GLOBAL_VERSION].as_slice()) {
+            Some(value) => u32_value(&value, "version")?,
+            None => 0,
+        };
+        if version != PSBT_VERSION {
+            return Err(psbt_error(format!(
+                "version {} is not supported; expected {}",
+                version, PSBT_VERSION
+            )));
+        }
+        if global.contains_key([PSBT_GLOBAL_UNSIGNED_TX].as_slice()) {
+            return Err(psbt_error("version 2 PSBT with an unsigned transaction"));
+        }
+
+        let fallback_lock_time = global
+            .remove([PSBT_GLOBAL_FALLBACK_LOCKTIME].as_slice())
+            .map(|value| u32_value(&value, "fallback lock-time"))
+            .transpose()?;
+        let tx_modifiable = global
+            .remove([PSBT_GLOBAL_TX_MODIFIABLE].as_slice())
+            .map(|value| fixed::<1>(&value, "modifiable flags"))
+            .transpose()?
+            .map_or(0, |[flags]| flags);
+        let mut required = |key_type: u8, field: &str| {
+            global
+                .remove([key_type].as_slice())
+                .ok_or_else(|| psbt_error(format!("missing {}", field)))
+        };
+        let tx_version = i32::from_le_bytes(fixed(
+            &required(PSBT_GLOBAL_TX_VERSION, "tx version")?,
+            "tx version",
+        )?);
+        let input_count = compact_size_value(&required(PSBT_GLOBAL_INPUT_COUNT, "input count")?)?;
+        let output_count =
+            compact_size_value(&required(PSBT_GLOBAL_OUTPUT_COUNT, "output count")?)?;
+
+        // Each map needs at least its separator byte.
+        if input_count.saturating_add(output_count) > data.len() as u64 {
+            return Err(psbt_error("input and output counts exceed the data"));
+        }
+        let inputs = (0..input_count)
+            .map(|_| PsbtInput::decode(read_map(&mut reader)?))
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = (0..output_count)
+            .map(|_| PsbtOutput::decode(read_map(&mut reader)?))
+            .collect::<Result<Vec<_>>>()?;
+        if !reader.is_empty() {
+            return Err(psbt_error("trailing data after the last output"));
+        }
+
+        let psbt = Self {
+            tx_version,
+            fallback_lock_time,
+            unknown: global,
+            tx_modifiable,
+            inputs,
+            outputs,
+        };
+        psbt.lock_time()?;
+        Ok(psbt)
+    }
+
+    /// Parses a base64-encoded PSBT.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PsbtError`] for invalid base64 or an invalid PSBT.
+    pub fn from_base64(s: &str) -> Result<Self> {
+        let data = BASE64
+            .decode(s.trim())
+            .map_err(|e| psbt_error(format!("invalid base64: {}", e)))?;
+        Self::deserialize(&data)
+    }
+
+    /// Returns the PSBT as base64, the usual form for exchanging it.
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.serialize())
+    }
+}
+
+/// One input of a [`PsbtPreview`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputPreview {
+    /// The output being spent.
+    pub previous_output: OutPoint,
+    /// Value of the spent output, if the PSBT carries its UTXO.
+    pub amount: Option<u64>,
+    /// Address of the spent output, if known and standard.
+    pub address: Option<Address>,
+    /// `true` if the spent output pays one of the wallet's signers.
+    pub is_mine: bool,
+    /// `true` once the input has a signature or is finalized.
+    pub is_signed: bool,
+}
+
+/// One output of a [`PsbtPreview`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputPreview {
+    /// Address paid, or `None` for `OP_RETURN` and other non-standard scripts.
+    pub address: Option<Address>,
+    /// Output script.
+    pub script_pubkey: Script,
+    /// Amount in satoshis.
+    pub amount: u64,
+    /// `true` if the output pays one of the wallet's signers.
+    pub is_change: bool,
+}
+
+/// What a PSBT spends and pays, as returned by [`Psbt::preview`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtPreview {
+    /// Inputs in transaction order.
+    pub inputs: Vec<InputPreview>,
+    /// Outputs in transaction order.
+    pub outputs: Vec<OutputPreview>,
+    /// Fee in satoshis; `None` if an input's UTXO is missing.
+    pub fee: Option<u64>,
+}
+
+impl PsbtPreview {
+    /// Returns the total paid to outputs that are not change.
+    pub fn sent(&self) -> u64 {
+        self.outputs
+            .iter()
+            .filter(|output| !output.is_change)
+            .map(|output| output.amount)
+            .sum()
+    }
+
+    /// Returns the total returned to the wallet as change.
+    pub fn change(&self) -> u64 {
+        self.outputs
+            .iter()
+            .filter(|output| output.is_change)
+            .map(|output| output.amount)
+            .sum()
+    }
+}
+
+impl fmt::Display for Psbt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base64())
+    }
+}
+
+impl FromStr for Psbt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_base64(s)
+    }
+}
+
+/// How a signer spends an input.
+enum Spend {
+    /// A single-key output of this script type.
+    Key(ScriptType),
+    /// A P2WSH output committing to this witness script.
+    WitnessScript(Script),
+}
+
+/// Returns `true` if `witness_script` pushes the signer's public key or its hash.
+fn uses_key(witness_script: &Script, signer: &BtcSigner) -> bool {
+    let script = witness_script.as_bytes();
+    let key_push = [&[33u8][..], &signer.public_key()].concat();
+    let hash_push = [&[20u8][..], &signer.pubkey_hash()].concat();
+    [key_push, hash_push].iter().any(|push| {
+        script
+            .windows(push.len())
+            .any(|window| window == push.as_slice())
+    })
+}
+
+// ─── Encoding helpers ────────────────────────────────────────────────────────
+
+fn psbt_error(message: impl Into<String>) -> Error {
+    Error::PsbtError(message.into())
+}
+
+fn invalid(field: &'static str) -> impl Fn(Error) -> Error {
+    move |e| psbt_error(format!("{}: {}", field, e))
+}
+
+fn keyed(key_type: u8, key_data: &[u8]) -> Vec<u8> {
+    let mut key = vec![key_type];
+    key.extend_from_slice(key_data);
+    key
+}
+
+fn write_map(buf: &mut Vec<u8>, map: &RawMap) {
+    for (key, value) in map {
+        write_bytes(buf, key);
+        write_bytes(buf, value);
+    }
+    buf.push(0x00);
+}
+
+fn read_map(reader: &mut Reader<'_>) -> Result<RawMap> {
+    let mut map = RawMap::new();
+    loop {
+        let key = reader.read_var_bytes().map_err(invalid("key"))?;
+        if key.is_empty() {
+            return Ok(map);
+        }
+        let value = reader.read_var_bytes().map_err(invalid("value"))?;
+        if map.insert(key.to_vec(), value.to_vec()).is_some() {
+            return Err(psbt_error(format!("duplicate key {}", hex::encode(key))));
+        }
+    }
+}
+
+fn no_key_data(key: &[u8], field: &str) -> Result<()> {
+    if key.len() != 1 {
+        return Err(psbt_error(format!("unexpected key data for {}", field)));
+    }
+    Ok(())
+}
+
+fn public_key_data(key_data: &[u8], field: &str) -> Result<()> {
+    match key_data.len() {
+        33 | 65 => Ok(()),
+        len => Err(psbt_error(format!(
+            "{} keyed by a {}-byte public key",
+            field, len
+        ))),
+    }
+}
+
+fn fixed<const N: usize>(value: &[u8], field: &str) -> Result<[u8; N]> {
+    value.try_into().map_err(|_| {
+        psbt_error(format!(
+            "{} must be {} bytes, got {}",
+            field,
+            N,
+            value.len()
+        ))
+    })
+}
+
+fn u32_value(value: &[u8], field: &str) -> Result<u32> {
+    fixed(value, field).map(u32::from_le_bytes)
+}
+
+fn compact_size_value(value: &[u8]) -> Result<u64> {
+    let mut reader = Reader::new(value);
+    let n = reader.read_compact_size().map_err(invalid("count"))?;
+    if !reader.is_empty() {
+        return Err(psbt_error("trailing bytes after count"));
+    }
+    Ok(n)
+}
+
+fn decode_witness(value: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(value);
+    let count = reader
+        .read_compact_size()
+        .map_err(invalid("final witness"))?;
+    let mut witness = Vec::new();
+    for _ in 0..count {
+        let item = reader.read_var_bytes().map_err(invalid("final witness"))?;
+        witness.push(item.to_vec());
+    }
+    if !reader.is_empty() {
+        return Err(psbt_error("trailing bytes in final witness"));
+    }
+    Ok(witness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(byte: u8) -> BtcSigner {
+        BtcSigner::from_private_key(&[byte; 32], Network::Bitcoin).unwrap()
+    }
+
+    fn input_for(signer: &BtcSigner, vout: u32, value: u64) -> PsbtInput {
+        let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([0xab; 32]), vout));
+        input.witness_utxo = Some(TxOut {
+            value,
+            script_pubkey: signer.p2wpkh_address().script_pubkey(),
+        });
+        input
+    }
+
+    fn output(amount: u64) -> PsbtOutput {
+        PsbtOutput::new(amount, Script::p2wpkh(&[0x33; 20]))
+    }
+
+    #[test]
+    fn test_empty_psbt_encoding() {
+        let psbt = Psbt::new(2);
+        assert_eq!(
+            hex::encode(psbt.serialize()),
+            concat!(
+                "70736274ff",     // magic
+                "01020402000000", // PSBT_GLOBAL_TX_VERSION = 2
+                "01040100",       // PSBT_GLOBAL_INPUT_COUNT = 0
+                "01050100",       // PSBT_GLOBAL_OUTPUT_COUNT = 0
+                "01060103",       // PSBT_GLOBAL_TX_MODIFIABLE = inputs | outputs
+                "01fb0402000000", // PSBT_GLOBAL_VERSION = 2
+                "00",             // separator
+            )
+        );
+        assert_eq!(Psbt::deserialize(&psbt.serialize()).unwrap(), psbt);
+    }
+
+    #[test]
+    fn test_round_trip_all_fields() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        psbt.fallback_lock_time = Some(800_000);
+        psbt.unknown.insert(vec![0xfc, 0x01], vec![0xde, 0xad]);
+
+        let mut input = input_for(&alice, 1, 50_000);
+        input.sequence = Some(0xffff_fffd);
+        input.required_height_lock_time = Some(800_100);
+        input.non_witness_utxo = Some(vec![0x01, 0x02, 0x03]);
+        input.sighash_type = Some(SIGHASH_ALL);
+        input.redeem_script = Some(Script::new(vec![0x51]));
+        input.bip32_derivation.insert(
+            alice.public_key().to_vec(),
+            KeySource {
+                fingerprint: [0x73, 0xc5, 0xda, 0x0a],
+                path: vec![0x8000_0054, 0x8000_0000, 0x8000_0000, 0, 5],
+            },
+        );
+        input.unknown.insert(vec![0xfc, 0x02], vec![]);
+        psbt.add_input(input).unwrap();
+
+        let mut change = output(10_000);
+        change.witness_script = Some(Script::new(vec![0x52]));
+        psbt.add_output(change).unwrap();
+        psbt.add_output(output(39_000)).unwrap();
+
+        let encoded = psbt.to_string();
+        let decoded: Psbt = encoded.parse().unwrap();
+        assert_eq!(decoded, psbt);
+        assert_eq!(decoded.to_string(), encoded);
+        assert_eq!(decoded.lock_time().unwrap(), 800_100);
+    }
+
+    #[test]
+    fn test_sign_finalize_extract() {
+        let alice = signer(1);
+        let bob = signer(2);
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(input_for(&alice, 0, 30_000)).unwrap();
+        psbt.add_input(input_for(&bob, 1, 20_000)).unwrap();
+        psbt.add_output(output(49_000)).unwrap();
+
+        // Each party signs its own input on its own copy.
+        let mut for_bob = psbt.clone();
+        assert_eq!(psbt.sign(&alice).unwrap(), 1);
+        assert_eq!(for_bob.sign(&bob).unwrap(), 1);
+        assert!(psbt.extract_tx().is_err());
+
+        // Combine by copying Bob's signature over.
+        let bob_sigs = for_bob.inputs()[1].partial_sigs.clone();
+        psbt.input_mut(1).unwrap().partial_sigs.extend(bob_sigs);
+
+        assert_eq!(psbt.finalize().unwrap(), 2);
+        assert!(psbt
+            .inputs()
+            .iter()
+            .all(|input| input.partial_sigs.is_empty()));
+        let tx = psbt.extract_tx().unwrap();
+
+        // Same signatures as the builder produces for the same transaction.
+        let expected = psbt.unsigned_tx().unwrap();
+        assert_eq!(tx.txid(), expected.txid());
+        assert_eq!(
+            tx.inputs[0].witness[0],
+            alice
+                .sign_input(&expected, 0, ScriptType::P2wpkh, 30_000, SIGHASH_ALL)
+                .unwrap()
+        );
+        assert_eq!(tx.inputs[1].witness[1], bob.public_key().to_vec());
+    }
+
+    #[test]
+    fn test_sign_legacy_and_nested_inputs() {
+        let alice = signer(1);
+        let previous = Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(
+                OutPoint::new(Txid::from_bytes([0xcd; 32]), 0),
+                SEQUENCE_FINAL,
+            )],
+            outputs: vec![TxOut {
+                value: 20_000,
+                script_pubkey: alice.address_for(ScriptType::P2pkh).script_pubkey(),
+            }],
+            lock_time: 0,
+        };
+        let mut legacy = PsbtInput::new(OutPoint::new(previous.txid(), 0));
+        legacy.witness_utxo = previous.outputs.first().cloned();
+        let mut nested = PsbtInput::new(OutPoint::new(Txid::from_bytes([0xab; 32]), 1));
+        nested.witness_utxo = Some(TxOut {
+            value: 30_000,
+            script_pubkey: alice.address_for(ScriptType::P2shP2wpkh).script_pubkey(),
+        });
+
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(legacy).unwrap();
+        psbt.add_input(nested).unwrap();
+        psbt.add_output(output(49_000)).unwrap();
+
+        // The legacy sighash does not commit to the amount, so P2PKH needs the full
+        // previous transaction.
+        let err = psbt.clone().sign(&alice).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("P2PKH input 0 needs its non-witness UTXO"));
+
+        let mut wrong = previous.clone();
+        wrong.lock_time = 1;
+        psbt.input_mut(0).unwrap().non_witness_utxo = Some(wrong.serialize());
+        assert!(matches!(
+            psbt.clone().sign(&alice),
+            Err(Error::PsbtError(_))
+        ));
+
+        psbt.input_mut(0).unwrap().non_witness_utxo = Some(previous.serialize());
+        assert_eq!(psbt.sign(&alice).unwrap(), 2);
+        assert_eq!(psbt.finalize().unwrap(), 2);
+        let tx = psbt.extract_tx().unwrap();
+
+        let unsigned = psbt.unsigned_tx().unwrap();
+        let (script_sig, witness) = ScriptType::P2pkh.satisfaction(
+            &alice.public_key(),
+            &alice
+                .sign_input(&unsigned, 0, ScriptType::P2pkh, 20_000, SIGHASH_ALL)
+                .unwrap(),
+        );
+        assert_eq!(tx.inputs[0].script_sig, script_sig);
+        assert_eq!(tx.inputs[0].witness, witness);
+        assert_eq!(
+            tx.inputs[1].script_sig.as_bytes()[1..],
+            *Script::p2wpkh(&alice.pubkey_hash()).as_bytes()
+        );
+        assert_eq!(tx.inputs[1].witness[1], alice.public_key().to_vec());
+    }
+
+    #[test]
+    fn test_signing_freezes_modifiable_flags() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(input_for(&alice, 0, 30_000)).unwrap();
+        psbt.add_output(output(29_000)).unwrap();
+
+        psbt.sign(&alice).unwrap();
+        assert!(!psbt.inputs_modifiable());
+        assert!(!psbt.outputs_modifiable());
+        assert!(psbt.add_input(input_for(&alice, 1, 1)).is_err());
+        assert!(psbt.add_output(output(1)).is_err());
+    }
+
+    #[test]
+    fn test_anyonecanpay_single_keeps_inputs_open() {
+        let alice = signer(1);
+        let bob = signer(2);
+        let mut psbt = Psbt::new(2);
+        let mut input = input_for(&alice, 0, 30_000);
+        input.sighash_type = Some(SIGHASH_SINGLE | SIGHASH_ANYONECANPAY);
+        psbt.add_input(input).unwrap();
+        psbt.add_output(output(29_000)).unwrap();
+
+        psbt.sign(&alice).unwrap();
+        assert!(psbt.inputs_modifiable());
+        assert!(!psbt.outputs_modifiable());
+        assert!(psbt.has_sighash_single());
+        assert_eq!(
+            *psbt.inputs()[0]
+                .partial_sigs
+                .values()
+                .next()
+                .unwrap()
+                .last()
+                .unwrap(),
+            0x83
+        );
+
+        // Another input may only follow a matching output.
+        assert!(psbt.add_input(input_for(&bob, 1, 5_000)).is_err());
+    }
+
+    #[test]
+    fn test_sign_and_finalize_miniscript() {
+        let (alice, bob, carol) = (signer(1), signer(2), signer(3));
+        let descriptor: Descriptor = format!(
+            "wsh(multi(2,{},{},{}))",
+            hex::encode(alice.public_key()),
+            hex::encode(bob.public_key()),
+            hex::encode(carol.public_key())
+        )
+        .parse()
+        .unwrap();
+
+        let mut psbt = Psbt::new(2);
+        let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([0xab; 32]), 0));
+        input.witness_utxo = Some(TxOut {
+            value: 50_000,
+            script_pubkey: descriptor.script_pubkey(),
+        });
+        psbt.add_input(input).unwrap();
+        psbt.add_input(input_for(&alice, 1, 20_000)).unwrap();
+        psbt.add_output(output(69_000)).unwrap();
+
+        // Without the witness script the P2WSH input is not recognized.
+        assert_eq!(psbt.clone().sign(&bob).unwrap(), 0);
+        psbt.input_mut(0).unwrap().witness_script = Some(descriptor.witness_script());
+
+        assert_eq!(psbt.sign(&alice).unwrap(), 2);
+        assert_eq!(psbt.finalize_miniscript(&descriptor).unwrap(), 0);
+        assert_eq!(psbt.sign(&carol).unwrap(), 1);
+        assert_eq!(psbt.finalize_miniscript(&descriptor).unwrap(), 1);
+        assert_eq!(psbt.finalize().unwrap(), 1);
+
+        let tx = psbt.extract_tx().unwrap();
+        let unsigned = psbt.unsigned_tx().unwrap();
+        let witness_script = descriptor.witness_script();
+        assert_eq!(
+            tx.inputs[0].witness,
+            [
+                vec![],
+                alice
+                    .sign_witness_script(&unsigned, 0, &witness_script, 50_000, SIGHASH_ALL)
+                    .unwrap(),
+                carol
+                    .sign_witness_script(&unsigned, 0, &witness_script, 50_000, SIGHASH_ALL)
+                    .unwrap(),
+                witness_script.as_bytes().to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sign_skips_foreign_and_finalized_inputs() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(input_for(&signer(2), 0, 30_000)).unwrap();
+        psbt.add_input(PsbtInput::new(OutPoint::new(Txid::from_bytes([1; 32]), 0)))
+            .unwrap();
+        psbt.add_output(output(29_000)).unwrap();
+
+        assert_eq!(psbt.sign(&alice).unwrap(), 0);
+        assert!(psbt.inputs_modifiable());
+        assert_eq!(psbt.finalize().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_sign_rejects_bad_sighash_type() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        let mut input = input_for(&alice, 0, 30_000);
+        input.sighash_type = Some(0x04);
+        psbt.add_input(input).unwrap();
+        psbt.add_output(output(29_000)).unwrap();
+        assert!(matches!(psbt.sign(&alice), Err(Error::SigningError(_))));
+    }
+
+    #[test]
+    fn test_lock_time_determination() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        psbt.fallback_lock_time = Some(123);
+        assert_eq!(psbt.lock_time().unwrap(), 123);
+
+        let mut both = input_for(&alice, 0, 1);
+        both.required_height_lock_time = Some(10_000);
+        both.required_time_lock_time = Some(1_700_000_000);
+        psbt.add_input(both).unwrap();
+
+        let mut time_only = input_for(&alice, 1, 1);
+        time_only.required_time_lock_time = Some(1_800_000_000);
+        psbt.add_input(time_only).unwrap();
+        assert_eq!(psbt.lock_time().unwrap(), 1_800_000_000);
+
+        // A height-only input cannot be combined with the time-only one.
+        let mut height_only = input_for(&alice, 2, 1);
+        height_only.required_height_lock_time = Some(20_000);
+        assert!(psbt.add_input(height_only).is_err());
+        assert_eq!(psbt.inputs().len(), 2);
+    }
+
+    #[test]
+    fn test_add_input_rejects_duplicate_outpoint() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(input_for(&alice, 0, 1)).unwrap();
+        assert!(psbt.add_input(input_for(&alice, 0, 1)).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let valid = Psbt::new(2).serialize();
+
+        // Version 0 (global unsigned transaction, no version field).
+        let v0 = hex::decode("70736274ff01000a0100000000000000000000").unwrap();
+        let err = Psbt::deserialize(&v0).unwrap_err();
+        assert!(err.to_string().contains("version 0 is not supported"));
+
+        assert!(Psbt::deserialize(&valid[1..]).is_err());
+        assert!(Psbt::deserialize(&valid[..valid.len() - 1]).is_err());
+
+        let mut trailing = valid.clone();
+        trailing.push(0x00);
+        assert!(Psbt::deserialize(&trailing).is_err());
+
+        // Duplicate key: repeat PSBT_GLOBAL_TX_VERSION.
+        let mut duplicate = valid[..5].to_vec();
+        duplicate.extend_from_slice(&hex::decode("01020402000000").unwrap());
+        duplicate.extend_from_slice(&valid[5..]);
+        let err = Psbt::deserialize(&duplicate).unwrap_err();
+        assert!(err.to_string().contains("duplicate key"));
+
+        // Input count 1 with no input map.
+        let missing = hex::decode(concat!(
+            "70736274ff",
+            "01020402000000",
+            "01040101",
+            "01050100",
+            "01fb0402000000",
+            "00",
+        ))
+        .unwrap();
+        assert!(Psbt::deserialize(&missing).is_err());
+
+        // Input map without an output index.
+        let mut no_index = missing.clone();
+        no_index.extend_from_slice(&hex::decode("010e20").unwrap());
+        no_index.extend_from_slice(&[0xab; 32]);
+        no_index.push(0x00);
+        let err = Psbt::deserialize(&no_index).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("without previous txid or output index"));
+
+        assert!(Psbt::from_base64("not base64!").is_err());
+    }
+
+    #[test]
+    fn test_fee_and_preview() {
+        let alice = signer(1);
+        let bob = signer(2);
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(input_for(&alice, 0, 30_000)).unwrap();
+        psbt.add_input(input_for(&bob, 1, 20_000)).unwrap();
+        psbt.add_output(output(40_000)).unwrap();
+        psbt.add_output(PsbtOutput::new(
+            8_000,
+            alice.p2wpkh_address().script_pubkey(),
+        ))
+        .unwrap();
+        psbt.add_output(PsbtOutput::new(0, Script::new(vec![0x6a, 0x01, 0x2a])))
+            .unwrap();
+        assert_eq!(psbt.fee().unwrap(), 2_000);
+
+        psbt.sign(&alice).unwrap();
+        let preview = psbt.preview(Network::Bitcoin, &[&alice]).unwrap();
+        assert_eq!(preview.fee, Some(2_000));
+        assert_eq!(preview.sent(), 40_000);
+        assert_eq!(preview.change(), 8_000);
+
+        assert_eq!(preview.inputs[0].amount, Some(30_000));
+        assert_eq!(preview.inputs[0].address, Some(alice.p2wpkh_address()));
+        assert!(preview.inputs[0].is_mine && preview.inputs[0].is_signed);
+        assert!(!preview.inputs[1].is_mine && !preview.inputs[1].is_signed);
+
+        assert_eq!(
+            preview.outputs[0]
+                .address
+                .as_ref()
+                .map(Address::script_pubkey),
+            Some(Script::p2wpkh(&[0x33; 20]))
+        );
+        assert!(!preview.outputs[0].is_change);
+        assert!(preview.outputs[1].is_change);
+        assert_eq!(preview.outputs[2].address, None);
+    }
+
+    #[test]
+    fn test_fee_needs_utxos() {
+        let alice = signer(1);
+        let mut psbt = Psbt::new(2);
+        psbt.add_input(input_for(&alice, 0, 10_000)).unwrap();
+        psbt.add_input(PsbtInput::new(OutPoint::new(Txid::from_bytes([1; 32]), 0)))
+            .unwrap();
+        psbt.add_output(output(5_000)).unwrap();
+
+        assert!(matches!(psbt.fee(), Err(Error::PsbtError(_))));
+        let preview = psbt.preview(Network::Bitcoin, &[]).unwrap();
+        assert_eq!(preview.fee, None);
+        assert_eq!(preview.inputs[1].amount, None);
+        assert_eq!(preview.change(), 0);
+
+        let mut overspent = Psbt::new(2);
+        overspent.add_input(input_for(&alice, 0, 1_000)).unwrap();
+        overspent.add_output(output(2_000)).unwrap();
+        assert!(matches!(overspent.fee(), Err(Error::PsbtError(_))));
+        assert_eq!(overspent.preview(Network::Bitcoin, &[]).unwrap().fee, None);
+    }
+}
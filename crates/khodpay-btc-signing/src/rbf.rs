@@ -0,0 +1,259 @@
+//! Replace-by-fee (BIP-125) fee bumping.
+//!
+//! A transaction built with [`TransactionBuilder::rbf`](crate::TransactionBuilder::rbf)
+//! signals that it may be replaced while unconfirmed. [`bump_fee`] builds and signs
+//! that replacement: same inputs, same outputs, with the extra fee taken from the
+//! change output.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::rbf::bump_fee;
+//! use khodpay_btc_signing::{
+//!     Address, BtcSigner, FeeRate, Network, OutPoint, TransactionBuilder, Txid, Utxo,
+//! };
+//!
+//! let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+//! let utxo = Utxo::new(
+//!     OutPoint::new(Txid::from_bytes([7; 32]), 0),
+//!     100_000,
+//!     signer.p2wpkh_address().script_pubkey(),
+//! );
+//! let recipient: Address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".parse().unwrap();
+//!
+//! let original = TransactionBuilder::new(Network::Bitcoin)
+//!     .add_utxo(utxo.clone())
+//!     .add_output(&recipient, 50_000)
+//!     .change_address(&signer.p2wpkh_address())
+//!     .fee_rate(FeeRate::from_sat_per_vb(2))
+//!     .rbf(true)
+//!     .sign(&[&signer])
+//!     .unwrap();
+//!
+//! // Stuck in the mempool: replace it at 10 sat/vB.
+//! let replacement = bump_fee(&original, &[utxo], FeeRate::from_sat_per_vb(10), &[&signer]).unwrap();
+//! assert_eq!(replacement.outputs[0], original.outputs[0]);
+//! assert!(replacement.outputs[1].value < original.outputs[1].value);
+//! ```
+
+use crate::builder::{input_types, sign_inputs};
+use crate::fee::{self, FeeRate};
+use crate::{BtcSigner, Error, Result, Script, Transaction, Utxo};
+
+/// Minimum fee rate a replacement must add on top of the fee it replaces (BIP-125 rule
+/// 4), Bitcoin Core's default `-incrementalrelayfee`.
+pub const INCREMENTAL_RELAY_FEE: FeeRate = FeeRate::MIN_RELAY;
+
+/// Builds and signs a replacement of `original` paying `fee_rate`.
+///
+/// `utxos` are the outputs `original` spends, in input order. The replacement keeps
+/// the inputs, sequences, lock time and outputs; the output paying one of `signers`
+/// (the last one if several do) is the change and pays for the bump. Its fee is the
+/// larger of `fee_rate` on its size and the original fee plus
+/// [`INCREMENTAL_RELAY_FEE`] on its size, as BIP-125 rules 3 and 4 require.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] if `original` does not signal replace-by-fee,
+/// `utxos` do not match its inputs or none of its outputs is change, and
+/// [`Error::InsufficientFunds`] if the change cannot cover the extra fee and stay at
+/// or above its dust limit at [`FeeRate::DUST_RELAY`].
+/// Signing errors are those of [`TransactionBuilder::sign`](crate::TransactionBuilder::sign).
+pub fn bump_fee(
+    original: &Transaction,
+    utxos: &[Utxo],
+    fee_rate: FeeRate,
+    signers: &[&BtcSigner],
+) -> Result<Transaction> {
+    if !original.signals_rbf() {
+        return Err(Error::ValidationError(
+            "original transaction does not signal replace-by-fee".to_string(),
+        ));
+    }
+    if utxos.len() != original.inputs.len()
+        || utxos
+            .iter()
+            .zip(&original.inputs)
+            .any(|(utxo, input)| utxo.outpoint != input.previous_output)
+    {
+        return Err(Error::ValidationError(
+            "UTXOs do not match the original inputs".to_string(),
+        ));
+    }
+
+    let input_value = utxos
+        .iter()
+        .try_fold(0u64, |total, utxo| total.checked_add(utxo.value));
+    let original_fee = input_value
+        .zip(original.output_value())
+        .and_then(|(inputs, outputs)| inputs.checked_sub(outputs))
+        .ok_or_else(|| Error::ValidationError("original outputs exceed its inputs".to_string()))?;
+    let change = original
+        .outputs
+        .iter()
+        .rposition(|output| {
+            signers
+                .iter()
+                .any(|signer| signer.can_sign(&output.script_pubkey))
+        })
+        .ok_or_else(|| {
+            Error::ValidationError("no change output to take the fee bump from".to_string())
+        })?;
+
+    let outputs: Vec<Script> = original
+        .outputs
+        .iter()
+        .map(|output| output.script_pubkey.clone())
+        .collect();
+    let vsize = fee::estimate_vsize(&input_types(utxos)?, &outputs);
+    let fee = fee_rate
+        .fee_for_vsize(vsize)
+        .max(original_fee.saturating_add(INCREMENTAL_RELAY_FEE.fee_for_vsize(vsize)));
+    let extra = fee - original_fee;
+
+    let mut replacement = original.clone();
+    let change_output = &mut replacement.outputs[change];
+    let dust = fee::dust_threshold(&change_output.script_pubkey, FeeRate::DUST_RELAY);
+    if change_output.value < extra.saturating_add(dust) {
+        return Err(Error::InsufficientFunds {
+            required: extra.saturating_add(dust),
+            available: change_output.value,
+        });
+    }
+    change_output.value -= extra;
+    for input in &mut replacement.inputs {
+        input.script_sig = Script::default();
+        input.witness.clear();
+    }
+    sign_inputs(&mut replacement, utxos, signers)?;
+    Ok(replacement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Network, OutPoint, ScriptType, TransactionBuilder, Txid};
+
+    fn signer(byte: u8) -> BtcSigner {
+        BtcSigner::from_private_key(&[byte; 32], Network::Bitcoin).unwrap()
+    }
+
+    fn recipient() -> Address {
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+            .parse()
+            .unwrap()
+    }
+
+    fn utxos(signer: &BtcSigner) -> Vec<Utxo> {
+        vec![
+            Utxo::new(
+                OutPoint::new(Txid::from_bytes([0xaa; 32]), 0),
+                60_000,
+                signer.p2wpkh_address().script_pubkey(),
+            ),
+            Utxo::new(
+                OutPoint::new(Txid::from_bytes([0xbb; 32]), 1),
+                40_000,
+                signer.address_for(ScriptType::P2pkh).script_pubkey(),
+            ),
+        ]
+    }
+
+    fn original(signer: &BtcSigner, rbf: bool) -> Transaction {
+        TransactionBuilder::new(Network::Bitcoin)
+            .add_utxos(utxos(signer))
+            .add_output(&recipient(), 70_000)
+            .change_address(&signer.p2wpkh_address())
+            .fee_rate(FeeRate::from_sat_per_vb(2))
+            .lock_time(800_000)
+            .rbf(rbf)
+            .sign(&[signer])
+            .unwrap()
+    }
+
+    fn fee(tx: &Transaction) -> u64 {
+        100_000 - tx.output_value().unwrap()
+    }
+
+    #[test]
+    fn test_bump_fee() {
+        let alice = signer(1);
+        let original = original(&alice, true);
+        assert!(original.signals_rbf());
+
+        let rate = FeeRate::from_sat_per_vb(10);
+        let replacement = bump_fee(&original, &utxos(&alice), rate, &[&alice]).unwrap();
+        assert_ne!(replacement.txid(), original.txid());
+        assert_eq!(replacement.lock_time, original.lock_time);
+        assert_eq!(replacement.inputs.len(), 2);
+        for (new, old) in replacement.inputs.iter().zip(&original.inputs) {
+            assert_eq!(new.previous_output, old.previous_output);
+            assert_eq!(new.sequence, old.sequence);
+        }
+        assert_eq!(replacement.outputs[0], original.outputs[0]);
+        assert_eq!(fee(&replacement), rate.fee_for_vsize(replacement.vsize()));
+
+        // The re-signed replacement matches a builder-signed transaction with that fee.
+        let expected = TransactionBuilder::new(Network::Bitcoin)
+            .add_utxos(utxos(&alice))
+            .add_output(&recipient(), 70_000)
+            .change_address(&alice.p2wpkh_address())
+            .fee(fee(&replacement))
+            .lock_time(800_000)
+            .rbf(true)
+            .sign(&[&alice])
+            .unwrap();
+        assert_eq!(replacement, expected);
+    }
+
+    #[test]
+    fn test_bump_fee_adds_incremental_relay_fee() {
+        let alice = signer(1);
+        let original = original(&alice, true);
+
+        // Asking for the same (or a lower) rate still pays for the replacement's relay.
+        let replacement = bump_fee(&original, &utxos(&alice), FeeRate::ZERO, &[&alice]).unwrap();
+        assert_eq!(
+            fee(&replacement),
+            fee(&original) + INCREMENTAL_RELAY_FEE.fee_for_vsize(replacement.vsize())
+        );
+    }
+
+    #[test]
+    fn test_bump_fee_errors() {
+        let alice = signer(1);
+        let rate = FeeRate::from_sat_per_vb(10);
+
+        let final_tx = original(&alice, false);
+        assert!(!final_tx.signals_rbf());
+        let err = bump_fee(&final_tx, &utxos(&alice), rate, &[&alice]).unwrap_err();
+        assert!(err.to_string().contains("does not signal replace-by-fee"));
+
+        let original = original(&alice, true);
+        let err = bump_fee(&original, &utxos(&alice)[..1], rate, &[&alice]).unwrap_err();
+        assert!(err.to_string().contains("do not match the original inputs"));
+
+        // Only the change output can pay, and a stranger's key owns none of them.
+        let err = bump_fee(&original, &utxos(&alice), rate, &[&signer(2)]).unwrap_err();
+        assert!(err.to_string().contains("no change output"));
+
+        let err = bump_fee(
+            &original,
+            &utxos(&alice),
+            FeeRate::from_sat_per_vb(1_000),
+            &[&alice],
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InsufficientFunds { .. }));
+
+        // Paying for the bump may not leave the change as dust.
+        let change = original.outputs[1].value;
+        let rate = FeeRate::from_fee(fee(&original) + change - 100, original.vsize()).unwrap();
+        let err = bump_fee(&original, &utxos(&alice), rate, &[&alice]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InsufficientFunds { required, available }
+                if available == change && required > change - 100 + 293
+        ));
+    }
+}
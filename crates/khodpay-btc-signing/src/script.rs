@@ -0,0 +1,464 @@
+//! Bitcoin scripts.
+//!
+//! Only the standard output templates are constructed here; the crate does not
+//! interpret arbitrary scripts.
+
+use std::fmt;
+
+use khodpay_bip44::Purpose;
+
+use crate::hash::hash160;
+use crate::{Error, Result};
+
+pub(crate) const OP_0: u8 = 0x00;
+pub(crate) const OP_PUSHDATA1: u8 = 0x4c;
+pub(crate) const OP_PUSHDATA2: u8 = 0x4d;
+pub(crate) const OP_1: u8 = 0x51;
+pub(crate) const OP_DUP: u8 = 0x76;
+pub(crate) const OP_EQUAL: u8 = 0x87;
+pub(crate) const OP_EQUALVERIFY: u8 = 0x88;
+pub(crate) const OP_RETURN: u8 = 0x6a;
+pub(crate) const OP_HASH160: u8 = 0xa9;
+pub(crate) const OP_CHECKSIG: u8 = 0xac;
+
+/// A serialized Bitcoin script, such as an output's `scriptPubKey`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::Script;
+///
+/// let script = Script::p2wpkh(&[0x11; 20]);
+/// assert!(script.is_p2wpkh());
+/// assert_eq!(script.to_hex(), format!("0014{}", "11".repeat(20)));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Script(Vec<u8>);
+
+impl Script {
+    /// Wraps raw script bytes.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    /// Parses a script from hex.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::HexError`] if `hex` is not valid hex.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        hex::decode(hex)
+            .map(Self)
+            .map_err(|e| Error::HexError(e.to_string()))
+    }
+
+    /// `OP_DUP OP_HASH160 <pubkey_hash> OP_EQUALVERIFY OP_CHECKSIG`.
+    pub fn p2pkh(pubkey_hash: &[u8; 20]) -> Self {
+        let mut bytes = vec![OP_DUP, OP_HASH160, 20];
+        bytes.extend_from_slice(pubkey_hash);
+        bytes.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+        Self(bytes)
+    }
+
+    /// `OP_HASH160 <script_hash> OP_EQUAL`.
+    pub fn p2sh(script_hash: &[u8; 20]) -> Self {
+        let mut bytes = vec![OP_HASH160, 20];
+        bytes.extend_from_slice(script_hash);
+        bytes.push(OP_EQUAL);
+        Self(bytes)
+    }
+
+    /// P2SH output wrapping the P2WPKH redeem script of `pubkey_hash`.
+    pub fn p2sh_p2wpkh(pubkey_hash: &[u8; 20]) -> Self {
+        Self::p2sh(&hash160(Self::p2wpkh(pubkey_hash).as_bytes()))
+    }
+
+    /// `OP_0 <pubkey_hash>`: native SegWit v0 key hash.
+    pub fn p2wpkh(pubkey_hash: &[u8; 20]) -> Self {
+        Self::witness_program(0, pubkey_hash)
+    }
+
+    /// `OP_0 <script_hash>`: native SegWit v0 script hash.
+    pub fn p2wsh(script_hash: &[u8; 32]) -> Self {
+        Self::witness_program(0, script_hash)
+    }
+
+    /// `OP_1 <output_key>`: taproot output.
+    pub fn p2tr(output_key: &[u8; 32]) -> Self {
+        Self::witness_program(1, output_key)
+    }
+
+    /// Script pushing each of `items` with the minimal push opcode, as used for
+    /// `scriptSig`s.
+    pub(crate) fn from_pushes(items: &[&[u8]]) -> Self {
+        let mut bytes = Vec::new();
+        for item in items {
+            match item.len() {
+                len @ 0..=75 => bytes.push(len as u8),
+                len @ 76..=0xff => bytes.extend_from_slice(&[OP_PUSHDATA1, len as u8]),
+                len => {
+                    bytes.push(OP_PUSHDATA2);
+                    bytes.extend_from_slice(&(len as u16).to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(item);
+        }
+        Self(bytes)
+    }
+
+    /// `OP_n <program>` for witness version `version` (0–16).
+    pub(crate) fn witness_program(version: u8, program: &[u8]) -> Self {
+        debug_assert!(version <= 16 && (2..=40).contains(&program.len()));
+        let op = if version == 0 {
+            OP_0
+        } else {
+            OP_1 + version - 1
+        };
+        let mut bytes = vec![op, program.len() as u8];
+        bytes.extend_from_slice(program);
+        Self(bytes)
+    }
+
+    /// Returns the raw script bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the script length in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the script is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the script as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    /// Returns `true` for a native SegWit v0 key-hash output.
+    pub fn is_p2wpkh(&self) -> bool {
+        self.p2wpkh_hash().is_some()
+    }
+
+    /// Returns `true` for an `OP_RETURN` data output, which can never be spent.
+    pub fn is_op_return(&self) -> bool {
+        self.0.first() == Some(&OP_RETURN)
+    }
+
+    /// Returns `true` for a SegWit output of any version: `OP_n` and a 2 to 40 byte push.
+    pub fn is_witness_program(&self) -> bool {
+        match self.0.as_slice() {
+            [version, len, program @ ..] => {
+                (*version == OP_0 || (OP_1..=OP_1 + 15).contains(version))
+                    && (2..=40).contains(&program.len())
+                    && *len as usize == program.len()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the public key hash of a P2WPKH output.
+    pub fn p2wpkh_hash(&self) -> Option<[u8; 20]> {
+        match self.0.as_slice() {
+            [OP_0, 20, hash @ ..] => hash.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the public key hash of a P2PKH output.
+    pub fn p2pkh_hash(&self) -> Option<[u8; 20]> {
+        match self.0.as_slice() {
+            [OP_DUP, OP_HASH160, 20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] => {
+                hash.try_into().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the script hash of a P2SH output.
+    pub fn p2sh_hash(&self) -> Option<[u8; 20]> {
+        match self.0.as_slice() {
+            [OP_HASH160, 20, hash @ .., OP_EQUAL] => hash.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the x-only output key of a taproot (SegWit v1) output.
+    pub fn p2tr_key(&self) -> Option<[u8; 32]> {
+        match self.0.as_slice() {
+            [OP_1, 32, key @ ..] => key.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Splits a push-only script, such as a `scriptSig`, into its pushed items.
+    ///
+    /// Returns `None` if the script contains any other opcode or a truncated push.
+    pub(crate) fn pushes(&self) -> Option<Vec<&[u8]>> {
+        let mut items = Vec::new();
+        let mut rest = self.0.as_slice();
+        while let Some((&op, tail)) = rest.split_first() {
+            let (len, tail) = match op {
+                0..=75 => (usize::from(op), tail),
+                OP_PUSHDATA1 => (usize::from(*tail.first()?), &tail[1..]),
+                OP_PUSHDATA2 => {
+                    let len = tail.get(..2)?;
+                    (
+                        usize::from(u16::from_le_bytes([len[0], len[1]])),
+                        &tail[2..],
+                    )
+                }
+                _ => return None,
+            };
+            items.push(tail.get(..len)?);
+            rest = &tail[len..];
+        }
+        Some(items)
+    }
+}
+
+/// Single-key output types this crate can sign.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip44::Purpose;
+/// use khodpay_btc_signing::ScriptType;
+///
+/// assert_eq!(ScriptType::from_purpose(Purpose::BIP49), Some(ScriptType::P2shP2wpkh));
+/// assert_eq!(ScriptType::from_purpose(Purpose::BIP86), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptType {
+    /// Legacy pay-to-public-key-hash (BIP-44 accounts, `1…` addresses).
+    P2pkh,
+    /// P2WPKH nested in P2SH (BIP-49 accounts, `3…` addresses).
+    P2shP2wpkh,
+    /// Native SegWit v0 key hash (BIP-84 accounts, `bc1q…` addresses).
+    P2wpkh,
+}
+
+impl ScriptType {
+    /// Returns the script type of a BIP-44 family purpose, or `None` for taproot.
+    pub fn from_purpose(purpose: Purpose) -> Option<Self> {
+        match purpose {
+            Purpose::BIP44 => Some(Self::P2pkh),
+            Purpose::BIP49 => Some(Self::P2shP2wpkh),
+            Purpose::BIP84 => Some(Self::P2wpkh),
+            Purpose::BIP86 => None,
+        }
+    }
+
+    /// Returns `true` if inputs of this type are signed with BIP-143 and carry a
+    /// witness.
+    pub fn is_segwit(self) -> bool {
+        !matches!(self, Self::P2pkh)
+    }
+
+    /// Returns the weight of a signed input of this type, counting its witness but
+    /// not the SegWit marker and flag.
+    ///
+    /// Assumes a 71-byte signature (low-R, with the sighash type byte) and a compressed
+    /// public key.
+    pub fn input_weight(self) -> u64 {
+        // Outpoint, scriptSig length and sequence.
+        const OUTPOINT_AND_SEQUENCE: u64 = 36 + 1 + 4;
+        // <sig> <pubkey> with their length prefixes.
+        const SIGNATURE_AND_KEY: u64 = 1 + 71 + 1 + 33;
+        // Witnesses add their item count.
+        const WITNESS: u64 = 1 + SIGNATURE_AND_KEY;
+        match self {
+            Self::P2pkh => 4 * (OUTPOINT_AND_SEQUENCE + SIGNATURE_AND_KEY),
+            // The scriptSig pushes the 22-byte P2WPKH redeem script.
+            Self::P2shP2wpkh => 4 * (OUTPOINT_AND_SEQUENCE + 23) + WITNESS,
+            Self::P2wpkh => 4 * OUTPOINT_AND_SEQUENCE + WITNESS,
+        }
+    }
+
+    /// Returns the type of a P2PKH, P2SH or P2WPKH output script, assuming P2SH
+    /// outputs are P2SH-P2WPKH.
+    pub(crate) fn of_script_pubkey(script_pubkey: &Script) -> Option<Self> {
+        if script_pubkey.p2wpkh_hash().is_some() {
+            Some(Self::P2wpkh)
+        } else if script_pubkey.p2sh_hash().is_some() {
+            Some(Self::P2shP2wpkh)
+        } else if script_pubkey.p2pkh_hash().is_some() {
+            Some(Self::P2pkh)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the output script paying `pubkey_hash` with this type.
+    pub fn script_pubkey(self, pubkey_hash: &[u8; 20]) -> Script {
+        match self {
+            Self::P2pkh => Script::p2pkh(pubkey_hash),
+            Self::P2shP2wpkh => Script::p2sh_p2wpkh(pubkey_hash),
+            Self::P2wpkh => Script::p2wpkh(pubkey_hash),
+        }
+    }
+
+    /// Returns the type of `script_pubkey` if it pays `pubkey_hash`.
+    pub(crate) fn matching(script_pubkey: &Script, pubkey_hash: &[u8; 20]) -> Option<Self> {
+        [Self::P2wpkh, Self::P2shP2wpkh, Self::P2pkh]
+            .into_iter()
+            .find(|script_type| script_type.script_pubkey(pubkey_hash) == *script_pubkey)
+    }
+
+    /// Builds the `scriptSig` and witness spending this type with one signature.
+    pub(crate) fn satisfaction(
+        self,
+        public_key: &[u8],
+        signature: &[u8],
+    ) -> (Script, Vec<Vec<u8>>) {
+        match self {
+            Self::P2pkh => (Script::from_pushes(&[signature, public_key]), Vec::new()),
+            Self::P2shP2wpkh => {
+                let redeem_script = Script::p2wpkh(&hash160(public_key));
+                (
+                    Script::from_pushes(&[redeem_script.as_bytes()]),
+                    vec![signature.to_vec(), public_key.to_vec()],
+                )
+            }
+            Self::P2wpkh => (
+                Script::default(),
+                vec![signature.to_vec(), public_key.to_vec()],
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ScriptType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::P2pkh => "P2PKH",
+            Self::P2shP2wpkh => "P2SH-P2WPKH",
+            Self::P2wpkh => "P2WPKH",
+        })
+    }
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Script({})", self.to_hex())
+    }
+}
+
+impl From<Vec<u8>> for Script {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_p2pkh() {
+        let script = Script::p2pkh(&[0xab; 20]);
+        assert_eq!(script.to_hex(), format!("76a914{}88ac", "ab".repeat(20)));
+        assert!(!script.is_p2wpkh());
+    }
+
+    #[test]
+    fn test_p2sh() {
+        let script = Script::p2sh(&[0xab; 20]);
+        assert_eq!(script.to_hex(), format!("a914{}87", "ab".repeat(20)));
+    }
+
+    #[test]
+    fn test_witness_programs() {
+        assert_eq!(
+            Script::p2wsh(&[0xcd; 32]).to_hex(),
+            format!("0020{}", "cd".repeat(32))
+        );
+        assert_eq!(
+            Script::p2tr(&[0xef; 32]).to_hex(),
+            format!("5120{}", "ef".repeat(32))
+        );
+    }
+
+    #[test]
+    fn test_p2wpkh_hash() {
+        let script = Script::p2wpkh(&[0x11; 20]);
+        assert_eq!(script.p2wpkh_hash(), Some([0x11; 20]));
+        assert_eq!(Script::p2wsh(&[0x11; 32]).p2wpkh_hash(), None);
+        assert_eq!(Script::default().p2wpkh_hash(), None);
+    }
+
+    #[test]
+    fn test_template_hashes() {
+        assert_eq!(Script::p2pkh(&[0x22; 20]).p2pkh_hash(), Some([0x22; 20]));
+        assert_eq!(Script::p2sh(&[0x33; 20]).p2sh_hash(), Some([0x33; 20]));
+        assert_eq!(Script::p2sh(&[0x33; 20]).p2pkh_hash(), None);
+        assert_eq!(Script::p2wpkh(&[0x22; 20]).p2sh_hash(), None);
+    }
+
+    #[test]
+    fn test_p2sh_p2wpkh() {
+        // BIP-49 test vector: account 0, receive address 0.
+        let pubkey_hash = hash160(
+            &hex::decode("03a1af804ac108a8a51782198c2d034b28bf90c8803f5a53f76276fa69a4eae77f")
+                .unwrap(),
+        );
+        assert_eq!(
+            Script::p2sh_p2wpkh(&pubkey_hash).to_hex(),
+            "a914336caa13e08b96080a32b5d818d59b4ab3b3674287"
+        );
+    }
+
+    #[test]
+    fn test_from_pushes() {
+        assert_eq!(Script::from_pushes(&[&[0xaa; 3]]).to_hex(), "03aaaaaa");
+        let long = Script::from_pushes(&[&[0x01; 76]]);
+        assert_eq!(&long.as_bytes()[..2], &[OP_PUSHDATA1, 76]);
+        let longer = Script::from_pushes(&[&[0x01; 256]]);
+        assert_eq!(&longer.as_bytes()[..3], &[OP_PUSHDATA2, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_pushes() {
+        let items: [&[u8]; 3] = [&[0xaa; 3], &[0x01; 76], &[0x02; 256]];
+        let script = Script::from_pushes(&items);
+        assert_eq!(script.pushes().unwrap(), items);
+        assert_eq!(Script::default().pushes().unwrap(), Vec::<&[u8]>::new());
+        // Truncated push and a non-push opcode.
+        assert!(Script::new(vec![0x03, 0xaa]).pushes().is_none());
+        assert!(Script::new(vec![OP_DUP]).pushes().is_none());
+    }
+
+    #[test]
+    fn test_p2tr_key() {
+        assert_eq!(Script::p2tr(&[0xef; 32]).p2tr_key(), Some([0xef; 32]));
+        assert_eq!(Script::p2wsh(&[0xef; 32]).p2tr_key(), None);
+    }
+
+    #[test]
+    fn test_script_type_matching() {
+        let hash = [0x44; 20];
+        for script_type in [
+            ScriptType::P2pkh,
+            ScriptType::P2shP2wpkh,
+            ScriptType::P2wpkh,
+        ] {
+            let script = script_type.script_pubkey(&hash);
+            assert_eq!(ScriptType::matching(&script, &hash), Some(script_type));
+            assert_eq!(ScriptType::matching(&script, &[0x45; 20]), None);
+        }
+        assert_eq!(ScriptType::P2shP2wpkh.to_string(), "P2SH-P2WPKH");
+        assert!(!ScriptType::P2pkh.is_segwit());
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let script = Script::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        assert!(script.is_p2wpkh());
+        assert_eq!(script.len(), 22);
+        assert!(matches!(Script::from_hex("zz"), Err(Error::HexError(_))));
+    }
+}
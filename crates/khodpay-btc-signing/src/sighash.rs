@@ -0,0 +1,662 @@
+//! Signature hashes for every spend type: the original algorithm for legacy inputs,
+//! BIP-143 for SegWit v0 inputs and BIP-341 / BIP-342 for taproot inputs.
+//!
+//! These are the functions the [`TransactionBuilder`](crate::TransactionBuilder),
+//! [`Psbt`](crate::psbt::Psbt) signer and [`bip322`](crate::bip322) use, exposed so
+//! integrations that sign elsewhere (hardware wallets, HSMs, co-signers) hash
+//! transactions the same way.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_btc_signing::sighash::{self, SIGHASH_ALL};
+//! use khodpay_btc_signing::{BtcSigner, Network, OutPoint, Script, TransactionBuilder, Txid, Utxo};
+//!
+//! let signer = BtcSigner::from_private_key(&[1u8; 32], Network::Bitcoin).unwrap();
+//! let script_pubkey = signer.p2wpkh_address().script_pubkey();
+//! let tx = TransactionBuilder::new(Network::Bitcoin)
+//!     .add_utxo(Utxo::new(OutPoint::new(Txid::from_bytes([7; 32]), 0), 10_000, script_pubkey.clone()))
+//!     .add_output_script(script_pubkey, 9_000)
+//!     .fee(1_000)
+//!     .build_unsigned()
+//!     .unwrap();
+//!
+//! // P2WPKH signs the P2PKH script of its key hash (BIP-143).
+//! let script_code = Script::p2pkh(&signer.pubkey_hash());
+//! let hash = sighash::segwit_v0(&tx, 0, &script_code, 10_000, SIGHASH_ALL).unwrap();
+//! assert_eq!(hash.len(), 32);
+//! ```
+
+use crate::hash::{sha256, sha256d, tagged_hash};
+use crate::transaction::{write_bytes, write_outpoint, write_txout};
+use crate::{Error, Result, Script, Transaction, TxOut};
+
+/// Taproot only: sign all inputs and outputs, omitting the type byte from the signature.
+pub const SIGHASH_DEFAULT: u32 = 0x00;
+/// Sign all inputs and outputs.
+pub const SIGHASH_ALL: u32 = 0x01;
+/// Sign all inputs and no outputs.
+pub const SIGHASH_NONE: u32 = 0x02;
+/// Sign all inputs and the output with the same index.
+pub const SIGHASH_SINGLE: u32 = 0x03;
+/// Modifier: sign only this input, letting others be added.
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+/// Leaf version of BIP-342 tapscripts.
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+/// The script leaf a taproot script-path signature commits to (BIP-342).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScriptPath {
+    /// Tagged hash of the leaf script and version, see [`tap_leaf_hash`].
+    pub leaf_hash: [u8; 32],
+    /// Public key version; `0` for BIP-342 keys.
+    pub key_version: u8,
+    /// Opcode position of the last executed `OP_CODESEPARATOR`, `u32::MAX` if none.
+    pub code_separator_position: u32,
+}
+
+impl ScriptPath {
+    /// Creates a script path for `leaf_hash` with key version `0` and no
+    /// `OP_CODESEPARATOR`.
+    pub fn new(leaf_hash: [u8; 32]) -> Self {
+        Self {
+            leaf_hash,
+            key_version: 0,
+            code_separator_position: u32::MAX,
+        }
+    }
+
+    /// Creates a script path for the tapscript leaf `script`.
+    pub fn from_script(script: &Script) -> Self {
+        Self::new(tap_leaf_hash(script, TAPSCRIPT_LEAF_VERSION))
+    }
+}
+
+/// Computes the BIP-341 `TapLeaf` hash of `script` under `leaf_version`.
+pub fn tap_leaf_hash(script: &Script, leaf_version: u8) -> [u8; 32] {
+    let mut buf = vec![leaf_version];
+    write_bytes(&mut buf, script.as_bytes());
+    tagged_hash("TapLeaf", &buf)
+}
+
+/// Computes the legacy (pre-SegWit) signature hash of input `index`.
+///
+/// `script_code` is the script being satisfied: the output script for P2PKH, the
+/// redeem script for P2SH. Reproduces the consensus quirk of `SIGHASH_SINGLE` without
+/// a matching output, which signs the constant `1`.
+///
+/// # Errors
+///
+/// Returns [`Error::SigningError`] if `index` is out of range.
+pub fn legacy(
+    tx: &Transaction,
+    index: usize,
+    script_code: &Script,
+    sighash_type: u32,
+) -> Result<[u8; 32]> {
+    check_index(tx, index)?;
+    let base_type = sighash_type & 0x1f;
+    if base_type == SIGHASH_SINGLE && index >= tx.outputs.len() {
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        return Ok(one);
+    }
+
+    let mut copy = tx.clone();
+    for (i, input) in copy.inputs.iter_mut().enumerate() {
+        input.witness.clear();
+        input.script_sig = if i == index {
+            script_code.clone()
+        } else {
+            Script::default()
+        };
+        if i != index && (base_type == SIGHASH_NONE || base_type == SIGHASH_SINGLE) {
+            input.sequence = 0;
+        }
+    }
+    if base_type == SIGHASH_NONE {
+        copy.outputs.clear();
+    } else if base_type == SIGHASH_SINGLE {
+        copy.outputs.truncate(index + 1);
+        for output in &mut copy.outputs[..index] {
+            *output = TxOut {
+                value: u64::MAX,
+                script_pubkey: Script::default(),
+            };
+        }
+    }
+    if sighash_type & SIGHASH_ANYONECANPAY != 0 {
+        copy.inputs = vec![copy.inputs.swap_remove(index)];
+    }
+
+    let mut preimage = copy.serialize_without_witness();
+    preimage.extend_from_slice(&sighash_type.to_le_bytes());
+    Ok(sha256d(&preimage))
+}
+
+fn check_index(tx: &Transaction, index: usize) -> Result<()> {
+    if index >= tx.inputs.len() {
+        return Err(Error::SigningError(format!(
+            "input index {} out of range ({} inputs)",
+            index,
+            tx.inputs.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Computes the BIP-143 signature hash of input `index`.
+///
+/// `script_code` is the script being satisfied (for P2WPKH, the P2PKH script of the
+/// key hash) and `value` the amount of the output being spent.
+///
+/// # Errors
+///
+/// Returns [`Error::SigningError`] if `index` is out of range.
+pub fn segwit_v0(
+    tx: &Transaction,
+    index: usize,
+    script_code: &Script,
+    value: u64,
+    sighash_type: u32,
+) -> Result<[u8; 32]> {
+    check_index(tx, index)?;
+    let input = &tx.inputs[index];
+    let base_type = sighash_type & 0x1f;
+    let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+    let hash_prevouts = if anyone_can_pay {
+        [0u8; 32]
+    } else {
+        let mut buf = Vec::with_capacity(36 * tx.inputs.len());
+        for input in &tx.inputs {
+            write_outpoint(&mut buf, &input.previous_output);
+        }
+        sha256d(&buf)
+    };
+
+    let hash_sequence =
+        if anyone_can_pay || base_type == SIGHASH_SINGLE || base_type == SIGHASH_NONE {
+            [0u8; 32]
+        } else {
+            let buf: Vec<u8> = tx
+                .inputs
+                .iter()
+                .flat_map(|input| input.sequence.to_le_bytes())
+                .collect();
+            sha256d(&buf)
+        };
+
+    let hash_outputs = if base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE {
+        let mut buf = Vec::new();
+        for output in &tx.outputs {
+            write_txout(&mut buf, output);
+        }
+        sha256d(&buf)
+    } else if base_type == SIGHASH_SINGLE && index < tx.outputs.len() {
+        let mut buf = Vec::new();
+        write_txout(&mut buf, &tx.outputs[index]);
+        sha256d(&buf)
+    } else {
+        [0u8; 32]
+    };
+
+    let mut preimage = Vec::with_capacity(160 + script_code.len());
+    preimage.extend_from_slice(&tx.version.to_le_bytes());
+    preimage.extend_from_slice(&hash_prevouts);
+    preimage.extend_from_slice(&hash_sequence);
+    write_outpoint(&mut preimage, &input.previous_output);
+    write_bytes(&mut preimage, script_code.as_bytes());
+    preimage.extend_from_slice(&value.to_le_bytes());
+    preimage.extend_from_slice(&input.sequence.to_le_bytes());
+    preimage.extend_from_slice(&hash_outputs);
+    preimage.extend_from_slice(&tx.lock_time.to_le_bytes());
+    preimage.extend_from_slice(&sighash_type.to_le_bytes());
+    Ok(sha256d(&preimage))
+}
+
+/// Computes the BIP-341 signature hash of taproot input `index`.
+///
+/// `prevouts` are the outputs spent by all inputs, in order. `annex` is the witness
+/// annex, including its `0x50` prefix, and `script_path` the leaf of a script-path
+/// spend (`None` for a key-path spend); together they set the message's spend type
+/// and extension (BIP-342).
+///
+/// # Errors
+///
+/// Returns [`Error::SigningError`] if `index` is out of range, `prevouts` does not
+/// match the inputs, the sighash type is not one taproot allows, the annex lacks its
+/// prefix or a `SIGHASH_SINGLE` input has no matching output.
+pub fn taproot(
+    tx: &Transaction,
+    index: usize,
+    prevouts: &[TxOut],
+    sighash_type: u32,
+    annex: Option<&[u8]>,
+    script_path: Option<&ScriptPath>,
+) -> Result<[u8; 32]> {
+    check_index(tx, index)?;
+    if prevouts.len() != tx.inputs.len() {
+        return Err(Error::SigningError(format!(
+            "{} prevouts for {} inputs",
+            prevouts.len(),
+            tx.inputs.len()
+        )));
+    }
+    if !matches!(sighash_type, 0x00..=0x03 | 0x81..=0x83) {
+        return Err(Error::SigningError(format!(
+            "invalid taproot sighash type 0x{:02x}",
+            sighash_type
+        )));
+    }
+    let base_type = sighash_type & 0x03;
+    let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+    // Epoch 0, then the SigMsg of BIP-341.
+    let mut message = vec![0x00, sighash_type as u8];
+    message.extend_from_slice(&tx.version.to_le_bytes());
+    message.extend_from_slice(&tx.lock_time.to_le_bytes());
+
+    if !anyone_can_pay {
+        let (mut outpoints, mut amounts, mut script_pubkeys, mut sequences) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for (input, prevout) in tx.inputs.iter().zip(prevouts) {
+            write_outpoint(&mut outpoints, &input.previous_output);
+            amounts.extend_from_slice(&prevout.value.to_le_bytes());
+            write_bytes(&mut script_pubkeys, prevout.script_pubkey.as_bytes());
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        message.extend_from_slice(&sha256(&outpoints));
+        message.extend_from_slice(&sha256(&amounts));
+        message.extend_from_slice(&sha256(&script_pubkeys));
+        message.extend_from_slice(&sha256(&sequences));
+    }
+    if base_type != SIGHASH_NONE && base_type != SIGHASH_SINGLE {
+        let mut outputs = Vec::new();
+        for output in &tx.outputs {
+            write_txout(&mut outputs, output);
+        }
+        message.extend_from_slice(&sha256(&outputs));
+    }
+
+    let spend_type = (u8::from(script_path.is_some()) << 1) | u8::from(annex.is_some());
+    message.push(spend_type);
+    if anyone_can_pay {
+        let input = &tx.inputs[index];
+        write_outpoint(&mut message, &input.previous_output);
+        write_txout(&mut message, &prevouts[index]);
+        message.extend_from_slice(&input.sequence.to_le_bytes());
+    } else {
+        message.extend_from_slice(&(index as u32).to_le_bytes());
+    }
+    if let Some(annex) = annex {
+        if annex.first() != Some(&0x50) {
+            return Err(Error::SigningError(
+                "taproot annex must start with 0x50".to_string(),
+            ));
+        }
+        let mut buf = Vec::with_capacity(annex.len() + 9);
+        write_bytes(&mut buf, annex);
+        message.extend_from_slice(&sha256(&buf));
+    }
+    if base_type == SIGHASH_SINGLE {
+        let output = tx.outputs.get(index).ok_or_else(|| {
+            Error::SigningError(format!(
+                "SIGHASH_SINGLE input {} has no matching output",
+                index
+            ))
+        })?;
+        let mut buf = Vec::new();
+        write_txout(&mut buf, output);
+        message.extend_from_slice(&sha256(&buf));
+    }
+    if let Some(path) = script_path {
+        message.extend_from_slice(&path.leaf_hash);
+        message.push(path.key_version);
+        message.extend_from_slice(&path.code_separator_position.to_le_bytes());
+    }
+    Ok(tagged_hash("TapSighash", &message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutPoint, TxIn, TxOut, Txid};
+
+    fn txid(internal_hex: &str) -> Txid {
+        Txid::from_bytes(hex::decode(internal_hex).unwrap().try_into().unwrap())
+    }
+
+    /// The "native P2WPKH" example from BIP-143.
+    fn bip143_native_p2wpkh() -> Transaction {
+        Transaction {
+            version: 1,
+            inputs: vec![
+                TxIn::new(
+                    OutPoint::new(
+                        txid("fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f"),
+                        0,
+                    ),
+                    0xffff_ffee,
+                ),
+                TxIn::new(
+                    OutPoint::new(
+                        txid("ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a"),
+                        1,
+                    ),
+                    0xffff_ffff,
+                ),
+            ],
+            outputs: vec![
+                TxOut {
+                    value: 112_340_000,
+                    script_pubkey: Script::from_hex(
+                        "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac",
+                    )
+                    .unwrap(),
+                },
+                TxOut {
+                    value: 223_450_000,
+                    script_pubkey: Script::from_hex(
+                        "76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac",
+                    )
+                    .unwrap(),
+                },
+            ],
+            lock_time: 0x11,
+        }
+    }
+
+    #[test]
+    fn test_bip143_native_p2wpkh() {
+        let tx = bip143_native_p2wpkh();
+        let script_code =
+            Script::from_hex("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac").unwrap();
+
+        let sighash = segwit_v0(&tx, 1, &script_code, 600_000_000, SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+    }
+
+    #[test]
+    fn test_bip143_p2sh_p2wpkh() {
+        let tx = Transaction::deserialize(
+            &hex::decode(
+                "0100000001db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a5477010000\
+             0000feffffff02b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac\
+             0008af2f000000001976a914fd270b1ee6abcaea97fea7ad0402e8bd8ad6d77c88ac92040000",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let script_code =
+            Script::from_hex("76a91479091972186c449eb1ded22b78e40d009bdf008988ac").unwrap();
+
+        let sighash = segwit_v0(&tx, 0, &script_code, 1_000_000_000, SIGHASH_ALL).unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "64f3b0f4dd2bb3aa1ce8566d220cc74dda9df97d8490cc81d89d735c92e59fb6"
+        );
+    }
+
+    /// Bitcoin's first non-coinbase transaction (block 170), spending a P2PK output.
+    fn block_170() -> (Transaction, Vec<u8>, Script) {
+        let tx = Transaction::deserialize(
+            &hex::decode(
+                "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000\
+             004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220\
+             181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a\
+             3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7\
+             aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043\
+             410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84cc\
+             f9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let script_sig = tx.inputs[0].script_sig.as_bytes();
+        let signature = script_sig[1..1 + script_sig[0] as usize].to_vec();
+        // The spent output pays the same key as the change output.
+        let script_code = tx.outputs[1].script_pubkey.clone();
+        (tx, signature, script_code)
+    }
+
+    #[test]
+    fn test_legacy_sighash_verifies_block_170() {
+        use secp256k1::ecdsa::Signature;
+        use secp256k1::{Message, PublicKey, SECP256K1};
+
+        let (tx, signature, script_code) = block_170();
+        assert_eq!(
+            tx.txid().to_string(),
+            "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16"
+        );
+        let (sighash_type, der) = signature.split_last().unwrap();
+        assert_eq!(u32::from(*sighash_type), SIGHASH_ALL);
+
+        let sighash = legacy(&tx, 0, &script_code, SIGHASH_ALL).unwrap();
+        let public_key = PublicKey::from_slice(&script_code.as_bytes()[1..66]).unwrap();
+        let mut signature = Signature::from_der(der).unwrap();
+        signature.normalize_s();
+        SECP256K1
+            .verify_ecdsa(&Message::from_digest(sighash), &signature, &public_key)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_legacy_sighash_single_bug() {
+        let (tx, _, script_code) = block_170();
+        let mut tx = tx;
+        tx.inputs.push(tx.inputs[0].clone());
+        tx.inputs.push(tx.inputs[0].clone());
+        let sighash = legacy(&tx, 2, &script_code, SIGHASH_SINGLE).unwrap();
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(sighash, one);
+    }
+
+    #[test]
+    fn test_legacy_sighash_types_differ() {
+        let (tx, _, script_code) = block_170();
+        let hashes: Vec<[u8; 32]> = [
+            SIGHASH_ALL,
+            SIGHASH_NONE,
+            SIGHASH_SINGLE,
+            SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+        ]
+        .into_iter()
+        .map(|sighash_type| legacy(&tx, 0, &script_code, sighash_type).unwrap())
+        .collect();
+        for (i, a) in hashes.iter().enumerate() {
+            for b in &hashes[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    /// The `keyPathSpending` transaction of the BIP-341 wallet test vectors.
+    fn bip341_key_path() -> (Transaction, Vec<TxOut>) {
+        let tx = Transaction::deserialize(
+            &hex::decode(
+                "02000000097de20cbff686da83a54981d2b9bab3586f4ca7e48f57f5b55963115f3b334e9c01000000\
+                 0000000000d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd9900000000\
+                 00fffffffff8e1f583384333689228c5d28eac13366be082dc57441760d957275419a4184200000000\
+                 00fffffffff0689180aa63b30cb162a73c6d2a38b7eeda2a83ece74310fda0843ad604853b01000000\
+                 00feffffffaa5202bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0ba6c00000000\
+                 00feffffff956149bdc66faa968eb2be2d2faa29718acbfe3941215893a2a3446d32acd05000000000\
+                 0000000000e664b9773b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c9401000000\
+                 0000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b694bb677a632a74ef7eadfd4eabf00000000\
+                 00ffffffffa778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af101000000\
+                 00ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac80\
+                 7840cb0000000020ac9a87f5594be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b00\
+                 65cd1d",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let prevouts = [
+            (
+                420_000_000,
+                "512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+            ),
+            (
+                462_000_000,
+                "5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            ),
+            (
+                294_000_000,
+                "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+            ),
+            (
+                504_000_000,
+                "5120e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e",
+            ),
+            (
+                630_000_000,
+                "512091b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605",
+            ),
+            (378_000_000, "00147dd65592d0ab2fe0d0257d571abf032cd9db93dc"),
+            (
+                672_000_000,
+                "512075169f4001aa68f15bbed28b218df1d0a62cbbcf1188c6665110c293c907b831",
+            ),
+            (
+                546_000_000,
+                "5120712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5",
+            ),
+            (
+                588_000_000,
+                "512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220",
+            ),
+        ]
+        .into_iter()
+        .map(|(value, script)| TxOut {
+            value,
+            script_pubkey: Script::from_hex(script).unwrap(),
+        })
+        .collect();
+        (tx, prevouts)
+    }
+
+    #[test]
+    fn test_bip341_key_path() {
+        let (tx, prevouts) = bip341_key_path();
+        assert_eq!(tx.inputs.len(), 9);
+
+        for (index, sighash_type, expected) in [
+            (
+                0,
+                0x03,
+                "2514a6272f85cfa0f45eb907fcb0d121b808ed37c6ea160a5a9046ed5526d555",
+            ),
+            (
+                1,
+                0x83,
+                "325a644af47e8a5a2591cda0ab0723978537318f10e6a63d4eed783b96a71a4d",
+            ),
+            (
+                3,
+                0x01,
+                "bf013ea93474aa67815b1b6cc441d23b64fa310911d991e713cd34c7f5d46669",
+            ),
+            (
+                4,
+                0x00,
+                "4f900a0bae3f1446fd48490c2958b5a023228f01661cda3496a11da502a7f7ef",
+            ),
+            (
+                6,
+                0x02,
+                "15f25c298eb5cdc7eb1d638dd2d45c97c4c59dcaec6679cfc16ad84f30876b85",
+            ),
+            (
+                7,
+                0x82,
+                "cd292de50313804dabe4685e83f923d2969577191a3e1d2882220dca88cbeb10",
+            ),
+            (
+                8,
+                0x81,
+                "cccb739eca6c13a8a89e6e5cd317ffe55669bbda23f2fd37b0f18755e008edd2",
+            ),
+        ] {
+            let sighash = taproot(&tx, index, &prevouts, sighash_type, None, None).unwrap();
+            assert_eq!(hex::encode(sighash), expected, "input {}", index);
+        }
+    }
+
+    #[test]
+    fn test_tap_leaf_hash() {
+        // BIP-341 wallet test vector with a single script leaf.
+        let script = Script::from_hex(
+            "20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(tap_leaf_hash(&script, TAPSCRIPT_LEAF_VERSION)),
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"
+        );
+        let path = ScriptPath::from_script(&script);
+        assert_eq!(path.key_version, 0);
+        assert_eq!(path.code_separator_position, u32::MAX);
+    }
+
+    #[test]
+    fn test_taproot_annex_and_script_path() {
+        let (tx, prevouts) = bip341_key_path();
+        let path = ScriptPath::new([0x11; 32]);
+        let annex = [0x50, 0x01, 0x02];
+
+        let key_path = taproot(&tx, 3, &prevouts, SIGHASH_ALL, None, None).unwrap();
+        let with_annex = taproot(&tx, 3, &prevouts, SIGHASH_ALL, Some(&annex), None).unwrap();
+        let script_path = taproot(&tx, 3, &prevouts, SIGHASH_ALL, None, Some(&path)).unwrap();
+        let both = taproot(&tx, 3, &prevouts, SIGHASH_ALL, Some(&annex), Some(&path)).unwrap();
+        let hashes = [key_path, with_annex, script_path, both];
+        for (i, a) in hashes.iter().enumerate() {
+            for b in &hashes[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+
+        // The code separator position is committed to.
+        let separated = ScriptPath {
+            code_separator_position: 0,
+            ..path
+        };
+        assert_ne!(
+            taproot(&tx, 3, &prevouts, SIGHASH_ALL, None, Some(&separated)).unwrap(),
+            script_path
+        );
+    }
+
+    #[test]
+    fn test_taproot_errors() {
+        let (tx, prevouts) = bip341_key_path();
+        let err = |result: Result<[u8; 32]>| result.unwrap_err().to_string();
+
+        assert!(
+            err(taproot(&tx, 0, &prevouts[1..], SIGHASH_ALL, None, None))
+                .contains("8 prevouts for 9 inputs")
+        );
+        assert!(err(taproot(&tx, 0, &prevouts, 0x04, None, None))
+            .contains("invalid taproot sighash type 0x04"));
+        assert!(err(taproot(&tx, 0, &prevouts, 0x80, None, None)).contains("0x80"));
+        assert!(
+            err(taproot(&tx, 0, &prevouts, SIGHASH_ALL, Some(&[0x51]), None))
+                .contains("annex must start with 0x50")
+        );
+        assert!(err(taproot(&tx, 2, &prevouts, SIGHASH_SINGLE, None, None))
+            .contains("SIGHASH_SINGLE input 2 has no matching output"));
+        assert!(err(taproot(&tx, 9, &prevouts, SIGHASH_ALL, None, None)).contains("out of range"));
+    }
+
+    #[test]
+    fn test_input_out_of_range() {
+        let tx = bip143_native_p2wpkh();
+        assert!(segwit_v0(&tx, 2, &Script::default(), 0, SIGHASH_ALL).is_err());
+        assert!(legacy(&tx, 2, &Script::default(), SIGHASH_ALL).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+//! Bitcoin signer using BIP-44 / BIP-49 / BIP-84 derived keys.
+//!
+//! # Security
+//!
+//! The `BtcSigner` holds a private key in memory. It is kept in a
+//! `khodpay_bip32::PrivateKey`, which is wiped when the signer is dropped.
+
+use std::fmt;
+
+use khodpay_bip32::PrivateKey;
+use khodpay_bip44::{Account, Chain};
+use secp256k1::{Keypair, Message, PublicKey, Scalar, SECP256K1};
+
+use crate::hash::{hash160, tagged_hash};
+use crate::{bip322, sighash};
+use crate::{Address, Error, Network, Result, Script, ScriptType, Transaction};
+
+/// Signs Bitcoin inputs with a single secp256k1 key.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip32::Network as Bip32Network;
+/// use khodpay_bip44::{Chain, CoinType, Purpose, Wallet};
+/// use khodpay_btc_signing::BtcSigner;
+///
+/// let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Bip32Network::BitcoinMainnet).unwrap();
+/// let account = wallet.get_account(Purpose::BIP84, CoinType::Bitcoin, 0).unwrap();
+///
+/// let signer = BtcSigner::new(account, Chain::External, 0).unwrap();
+/// assert_eq!(
+///     signer.p2wpkh_address().to_string(),
+///     "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct BtcSigner {
+    /// The private key.
+    private_key: PrivateKey,
+    /// The compressed public key.
+    public_key: [u8; 33],
+    /// The network addresses are generated for.
+    network: Network,
+    /// The script type of [`address`](Self::address).
+    script_type: ScriptType,
+}
+
+impl BtcSigner {
+    /// Creates a signer for an address of a BIP-44, BIP-49 or BIP-84 account.
+    ///
+    /// The network follows the account's (mainnet or testnet) and the default
+    /// script type its purpose: P2PKH, P2SH-P2WPKH or P2WPKH respectively.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] for a BIP-86 (taproot) account, or an error if
+    /// key derivation fails.
+    pub fn new(account: &Account, chain: Chain, address_index: u32) -> Result<Self> {
+        let script_type = ScriptType::from_purpose(account.purpose()).ok_or_else(|| {
+            Error::SigningError(format!(
+                "{:?} accounts are not supported",
+                account.purpose()
+            ))
+        })?;
+        let extended_key = match chain {
+            Chain::External => account.derive_external(address_index)?,
+            Chain::Internal => account.derive_internal(address_index)?,
+        };
+        Ok(
+            Self::from_key(extended_key.private_key().clone(), account.network().into())
+                .with_script_type(script_type),
+        )
+    }
+
+    /// Creates a signer from a 32-byte private key, defaulting to P2WPKH.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] if the key is zero or not below the curve order.
+    pub fn from_private_key(private_key: &[u8; 32], network: Network) -> Result<Self> {
+        let private_key = PrivateKey::from_array(*private_key)
+            .map_err(|e| Error::SigningError(format!("Invalid private key: {}", e)))?;
+        Ok(Self::from_key(private_key, network))
+    }
+
+    fn from_key(private_key: PrivateKey, network: Network) -> Self {
+        let public_key =
+            PublicKey::from_secret_key(SECP256K1, private_key.secret_key()).serialize();
+        Self {
+            private_key,
+            public_key,
+            network,
+            script_type: ScriptType::P2wpkh,
+        }
+    }
+
+    /// Sets the script type of [`address`](Self::address).
+    ///
+    /// The signer can spend all single-key script types of its key regardless.
+    pub fn with_script_type(mut self, script_type: ScriptType) -> Self {
+        self.script_type = script_type;
+        self
+    }
+
+    /// Returns the compressed public key.
+    pub fn public_key(&self) -> [u8; 33] {
+        self.public_key
+    }
+
+    /// Returns the HASH160 of the compressed public key.
+    pub fn pubkey_hash(&self) -> [u8; 20] {
+        hash160(&self.public_key)
+    }
+
+    /// Returns the network addresses are generated for.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the default script type, from the account purpose.
+    pub fn script_type(&self) -> ScriptType {
+        self.script_type
+    }
+
+    /// Returns the address of this key for the default script type.
+    pub fn address(&self) -> Address {
+        self.address_for(self.script_type)
+    }
+
+    /// Returns the address of this key for `script_type`.
+    pub fn address_for(&self, script_type: ScriptType) -> Address {
+        match script_type {
+            ScriptType::P2pkh => Address::p2pkh(&self.public_key, self.network),
+            ScriptType::P2shP2wpkh => Address::p2sh_p2wpkh(&self.public_key, self.network),
+            ScriptType::P2wpkh => Address::p2wpkh(&self.public_key, self.network),
+        }
+    }
+
+    /// Returns the native SegWit (P2WPKH) address of this key.
+    pub fn p2wpkh_address(&self) -> Address {
+        self.address_for(ScriptType::P2wpkh)
+    }
+
+    /// Returns the BIP-86 taproot address of this key, spent through the key path.
+    pub fn p2tr_address(&self) -> Address {
+        Address::p2tr(&self.public_key, self.network)
+            .expect("a valid public key always has a taproot output key")
+    }
+
+    /// Returns the script type of `script_pubkey` if it pays this key.
+    pub fn script_type_of(&self, script_pubkey: &Script) -> Option<ScriptType> {
+        ScriptType::matching(script_pubkey, &self.pubkey_hash())
+    }
+
+    /// Returns `true` if this key can sign for `script_pubkey`.
+    pub fn can_sign(&self, script_pubkey: &Script) -> bool {
+        self.script_type_of(script_pubkey).is_some()
+    }
+
+    /// Signs `message` with BIP-322 for this key's P2WPKH address.
+    ///
+    /// Returns the signature in the simple format (base64 witness stack), as
+    /// expected by exchanges and services asking to prove address ownership.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] if signing fails.
+    pub fn sign_message(&self, message: &[u8]) -> Result<String> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!(
+            "sign_message",
+            network = self.network,
+            message_len = message.len()
+        );
+
+        bip322::sign_simple(self, &self.p2wpkh_address(), message)
+    }
+
+    /// Signs input `index` of `tx`, which spends `value` satoshis from an output of
+    /// `script_type` paying this key.
+    ///
+    /// Uses the legacy signature hash for P2PKH and BIP-143 for the SegWit types.
+    /// Returns the DER signature with the sighash type byte appended, as it goes
+    /// into the `scriptSig` or witness.
+    pub(crate) fn sign_input(
+        &self,
+        tx: &Transaction,
+        index: usize,
+        script_type: ScriptType,
+        value: u64,
+        sighash_type: u32,
+    ) -> Result<Vec<u8>> {
+        let script_code = Script::p2pkh(&self.pubkey_hash());
+        let sighash = if script_type.is_segwit() {
+            sighash::segwit_v0(tx, index, &script_code, value, sighash_type)?
+        } else {
+            sighash::legacy(tx, index, &script_code, sighash_type)?
+        };
+        let mut signature = self.sign_ecdsa(&sighash);
+        signature.push(sighash_type as u8);
+        Ok(signature)
+    }
+
+    /// Signs input `index` of `tx`, which spends `value` satoshis from a P2WSH output
+    /// committing to `witness_script`.
+    pub(crate) fn sign_witness_script(
+        &self,
+        tx: &Transaction,
+        index: usize,
+        witness_script: &Script,
+        value: u64,
+        sighash_type: u32,
+    ) -> Result<Vec<u8>> {
+        let sighash = sighash::segwit_v0(tx, index, witness_script, value, sighash_type)?;
+        let mut signature = self.sign_ecdsa(&sighash);
+        signature.push(sighash_type as u8);
+        Ok(signature)
+    }
+
+    /// Signs a 32-byte signature hash, returning a low-S DER signature.
+    ///
+    /// Like Bitcoin Core, the RFC 6979 nonce is ground until R is below 2^255, which
+    /// keeps signatures at 70 bytes and matches Core's output byte for byte.
+    pub(crate) fn sign_ecdsa(&self, sighash: &[u8; 32]) -> Vec<u8> {
+        let message = Message::from_digest(*sighash);
+        SECP256K1
+            .sign_ecdsa_low_r(&message, self.private_key.secret_key())
+            .serialize_der()
+            .to_vec()
+    }
+
+    /// Signs a BIP-341 signature hash with the key-path spending key of
+    /// [`p2tr_address`](Self::p2tr_address): this key tweaked with no script tree.
+    ///
+    /// Returns the 64-byte BIP-340 Schnorr signature, without a sighash type byte.
+    pub(crate) fn sign_schnorr_key_path(&self, sighash: &[u8; 32]) -> Result<[u8; 64]> {
+        let keypair = Keypair::from_secret_key(SECP256K1, self.private_key.secret_key());
+        let (internal_key, _) = keypair.x_only_public_key();
+        let tweak = Scalar::from_be_bytes(tagged_hash("TapTweak", &internal_key.serialize()))
+            .map_err(|_| Error::SigningError("taproot tweak out of range".to_string()))?;
+        let keypair = keypair
+            .add_xonly_tweak(SECP256K1, &tweak)
+            .map_err(|e| Error::SigningError(format!("invalid taproot tweak: {}", e)))?;
+        let message = Message::from_digest(*sighash);
+        Ok(*SECP256K1
+            .sign_schnorr_no_aux_rand(&message, &keypair)
+            .as_ref())
+    }
+}
+
+impl fmt::Debug for BtcSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BtcSigner")
+            .field("public_key", &hex::encode(self.public_key))
+            .field("network", &self.network)
+            .field("script_type", &self.script_type)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::ecdsa::Signature;
+
+    fn signer() -> BtcSigner {
+        let mut key = [0u8; 32];
+        key[31] = 1;
+        BtcSigner::from_private_key(&key, Network::Bitcoin).unwrap()
+    }
+
+    #[test]
+    fn test_public_key_and_address() {
+        let signer = signer();
+        assert_eq!(
+            hex::encode(signer.public_key()),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+        assert_eq!(
+            signer.p2wpkh_address().to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        assert!(signer.can_sign(&signer.p2wpkh_address().script_pubkey()));
+        assert!(!signer.can_sign(&Script::p2wpkh(&[0; 20])));
+    }
+
+    #[test]
+    fn test_invalid_private_key() {
+        assert!(matches!(
+            BtcSigner::from_private_key(&[0u8; 32], Network::Bitcoin),
+            Err(Error::SigningError(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_ecdsa_verifies() {
+        let signer = signer();
+        let sighash = [0x42u8; 32];
+        let der = signer.sign_ecdsa(&sighash);
+
+        let signature = Signature::from_der(&der).unwrap();
+        let public_key = PublicKey::from_slice(&signer.public_key()).unwrap();
+        assert!(SECP256K1
+            .verify_ecdsa(&Message::from_digest(sighash), &signature, &public_key)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_bip143_native_p2wpkh_signature() {
+        // Second input of the BIP-143 "native P2WPKH" example.
+        let key: [u8; 32] =
+            hex::decode("619c335025c7f4012e556c2a58b2506e30b8511b53ade95ea316fd8c3286feb9")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let signer = BtcSigner::from_private_key(&key, Network::Bitcoin).unwrap();
+        assert_eq!(
+            hex::encode(signer.pubkey_hash()),
+            "1d0f172a0ecb48aee1be1f2687d2963ae33f71a1"
+        );
+
+        let sighash: [u8; 32] =
+            hex::decode("c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(
+            hex::encode(signer.sign_ecdsa(&sighash)),
+            "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a\
+             0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee"
+        );
+    }
+
+    #[test]
+    fn test_script_types() {
+        let signer = signer();
+        assert_eq!(signer.script_type(), ScriptType::P2wpkh);
+        assert_eq!(signer.address(), signer.p2wpkh_address());
+
+        let legacy = signer.clone().with_script_type(ScriptType::P2pkh);
+        assert_eq!(
+            legacy.address().to_string(),
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"
+        );
+        for script_type in [
+            ScriptType::P2pkh,
+            ScriptType::P2shP2wpkh,
+            ScriptType::P2wpkh,
+        ] {
+            let script = signer.address_for(script_type).script_pubkey();
+            assert_eq!(signer.script_type_of(&script), Some(script_type));
+        }
+        assert_eq!(signer.script_type_of(&Script::p2pkh(&[0; 20])), None);
+    }
+
+    #[test]
+    fn test_bip49_signature() {
+        // BIP-143 "P2SH-P2WPKH" example.
+        let key: [u8; 32] =
+            hex::decode("eb696a065ef48a2192da5b28b694f87544b30fae8327c4510137a922f32c6dcf")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let signer = BtcSigner::from_private_key(&key, Network::Bitcoin).unwrap();
+        let tx = Transaction::deserialize(
+            &hex::decode(
+                "0100000001db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3ceb1a5477010000\
+                 0000feffffff02b8b4eb0b000000001976a914a457b684d7f0d539a46a45bbc043f35b59d0d96388ac\
+                 0008af2f000000001976a914fd270b1ee6abcaea97fea7ad0402e8bd8ad6d77c88ac92040000",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let signature = signer
+            .sign_input(&tx, 0, ScriptType::P2shP2wpkh, 1_000_000_000, 0x01)
+            .unwrap();
+        assert_eq!(
+            hex::encode(signature),
+            "3044022047ac8e878352d3ebbde1c94ce3a10d057c24175747116f8288e5d794d12d482f\
+             0220217f36a485cae903c713331d877c1f64677e3622ad4010726870540656fe9dcb01"
+        );
+        assert_eq!(
+            hex::encode(
+                signer
+                    .address_for(ScriptType::P2shP2wpkh)
+                    .script_pubkey()
+                    .as_bytes()
+            ),
+            format!(
+                "a914{}87",
+                hex::encode(hash160(
+                    &hex::decode("001479091972186c449eb1ded22b78e40d009bdf0089").unwrap()
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn test_debug_hides_private_key() {
+        let debug = format!("{:?}", signer());
+        assert!(debug.contains("public_key"));
+        assert!(!debug.contains("private_key"));
+    }
+}
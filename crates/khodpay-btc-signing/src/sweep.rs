@@ -0,0 +1,504 @@
+//! Sweeping an old account into a new one.
+//!
+//! Wallets migrating off a legacy scheme (BIP-44 P2PKH or BIP-49 nested SegWit) move
+//! every coin to a BIP-84 or BIP-86 account. [`Sweep::scan`] walks both chains of the
+//! old account with a gap limit, asking a [`UtxoSource`] (an Electrum server, an
+//! indexer, …) for each address's unspent outputs. [`Sweep::plan`] batches them into
+//! consolidation transactions paying one destination address, and
+//! [`SweepPlan::sign`] signs those with the old account's keys.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use khodpay_bip32::Network as Bip32Network;
+//! use khodpay_bip44::{Chain, CoinType, Purpose, Wallet};
+//! use khodpay_btc_signing::sweep::{self, Sweep, UtxoSource};
+//! use khodpay_btc_signing::{Address, BtcSigner, FeeRate, OutPoint, Txid, Utxo};
+//!
+//! struct Indexer(HashMap<String, Vec<Utxo>>);
+//!
+//! impl UtxoSource for Indexer {
+//!     fn utxos(&self, address: &Address) -> Result<Vec<Utxo>, Box<dyn std::error::Error>> {
+//!         Ok(self.0.get(&address.to_string()).cloned().unwrap_or_default())
+//!     }
+//! }
+//!
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Bip32Network::BitcoinMainnet).unwrap();
+//! let destination = sweep::receive_address(wallet.get_account(Purpose::BIP86, CoinType::Bitcoin, 0).unwrap(), 0).unwrap();
+//! let legacy = wallet.get_account(Purpose::BIP44, CoinType::Bitcoin, 0).unwrap();
+//!
+//! let old = BtcSigner::new(legacy, Chain::External, 0).unwrap().address();
+//! let utxo = Utxo::new(OutPoint::new(Txid::from_bytes([7; 32]), 0), 50_000, old.script_pubkey());
+//! let indexer = Indexer(HashMap::from([(old.to_string(), vec![utxo])]));
+//!
+//! let sweep = Sweep::new(&destination).fee_rate(FeeRate::from_sat_per_vb(5));
+//! let plan = sweep.plan(sweep.scan(legacy, &indexer).unwrap()).unwrap();
+//! let txs = plan.sign(legacy).unwrap();
+//! assert_eq!(txs[0].outputs[0].value, plan.total_value());
+//! ```
+
+use khodpay_bip44::{Account, Chain, Purpose, DEFAULT_GAP_LIMIT};
+use secp256k1::{PublicKey, SECP256K1};
+
+use crate::builder::input_types;
+use crate::fee::{self, FeeRate};
+use crate::{Address, BtcSigner, Error, Result, ScriptType, Transaction, TransactionBuilder, Utxo};
+
+/// Default most inputs per sweep transaction, which keeps even an all-P2PKH sweep
+/// well below the 400,000 weight-unit standardness limit.
+pub const DEFAULT_MAX_INPUTS: usize = 500;
+
+/// Looks up the unspent outputs of an address, for [`Sweep::scan`].
+pub trait UtxoSource {
+    /// Returns the unspent outputs paying `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    fn utxos(
+        &self,
+        address: &Address,
+    ) -> std::result::Result<Vec<Utxo>, Box<dyn std::error::Error>>;
+}
+
+/// An unspent output found on the swept account, with the key that spends it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SweptUtxo {
+    /// The unspent output.
+    pub utxo: Utxo,
+    /// The chain of the paid address.
+    pub chain: Chain,
+    /// The index of the paid address.
+    pub address_index: u32,
+}
+
+/// One consolidation transaction of a [`SweepPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SweepBatch {
+    /// The outputs the transaction spends.
+    pub utxos: Vec<SweptUtxo>,
+    /// The amount paid to the destination.
+    pub value: u64,
+    /// The fee.
+    pub fee: u64,
+    /// The estimated virtual size of the signed transaction.
+    pub vsize: u64,
+}
+
+/// The consolidation transactions of a sweep, ready to review and sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SweepPlan {
+    /// Where every batch pays.
+    pub destination: Address,
+    /// The transactions, in the order the outputs were found.
+    pub batches: Vec<SweepBatch>,
+    /// Outputs worth less than the fee to spend them, left where they are.
+    pub skipped: Vec<SweptUtxo>,
+    rbf: bool,
+}
+
+impl SweepPlan {
+    /// Returns the total paid to the destination.
+    pub fn total_value(&self) -> u64 {
+        self.batches.iter().map(|batch| batch.value).sum()
+    }
+
+    /// Returns the total fee of all batches.
+    pub fn total_fee(&self) -> u64 {
+        self.batches.iter().map(|batch| batch.fee).sum()
+    }
+
+    /// Builds and signs one transaction per batch with the keys of `account`, the
+    /// account that was scanned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key derivation fails, and the errors of
+    /// [`TransactionBuilder::sign`] (such as a key of another account).
+    pub fn sign(&self, account: &Account) -> Result<Vec<Transaction>> {
+        self.batches
+            .iter()
+            .map(|batch| {
+                let signers = batch
+                    .utxos
+                    .iter()
+                    .map(|swept| BtcSigner::new(account, swept.chain, swept.address_index))
+                    .collect::<Result<Vec<_>>>()?;
+                let signers: Vec<&BtcSigner> = signers.iter().collect();
+                TransactionBuilder::new(self.destination.network())
+                    .add_utxos(batch.utxos.iter().map(|swept| swept.utxo.clone()))
+                    .add_output(&self.destination, batch.value)
+                    .fee(batch.fee)
+                    .rbf(self.rbf)
+                    .sign(&signers)
+            })
+            .collect()
+    }
+}
+
+/// Settings of a sweep into one destination address.
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    destination: Address,
+    fee_rate: FeeRate,
+    max_inputs: usize,
+    gap_limit: u32,
+    rbf: bool,
+}
+
+impl Sweep {
+    /// Creates a sweep paying `destination` at [`FeeRate::MIN_RELAY`], with at most
+    /// [`DEFAULT_MAX_INPUTS`] inputs per transaction and the BIP-44 gap limit of 20.
+    pub fn new(destination: &Address) -> Self {
+        Self {
+            destination: destination.clone(),
+            fee_rate: FeeRate::MIN_RELAY,
+            max_inputs: DEFAULT_MAX_INPUTS,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            rbf: false,
+        }
+    }
+
+    /// Sets the fee rate every batch pays.
+    pub fn fee_rate(mut self, fee_rate: FeeRate) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Sets the most inputs per transaction (at least one); more outputs are split
+    /// into several transactions.
+    pub fn max_inputs(mut self, max_inputs: usize) -> Self {
+        self.max_inputs = max_inputs.max(1);
+        self
+    }
+
+    /// Sets how many consecutive addresses without unspent outputs end the scan of a
+    /// chain.
+    pub fn gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    /// Signals replace-by-fee on the sweep transactions, so a sweep paying too little
+    /// can be bumped. Off by default.
+    pub fn rbf(mut self, enabled: bool) -> Self {
+        self.rbf = enabled;
+        self
+    }
+
+    /// Finds the unspent outputs of `account`, external chain first.
+    ///
+    /// Each chain is scanned from index 0 until [`gap_limit`](Self::gap_limit)
+    /// consecutive addresses have no unspent outputs. Addresses are those of the
+    /// account's purpose: P2PKH for BIP-44, P2SH-P2WPKH for BIP-49 and P2WPKH for
+    /// BIP-84. Spent-from addresses count towards the gap, so raise the limit for
+    /// wallets that emptied long runs of addresses.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UtxoSourceError`] if a query fails,
+    /// [`Error::ValidationError`] if the source returns an output that does not pay
+    /// the queried address, and [`Error::SigningError`] for a BIP-86 account.
+    pub fn scan(&self, account: &Account, source: &impl UtxoSource) -> Result<Vec<SweptUtxo>> {
+        let mut found = Vec::new();
+        for chain in [Chain::External, Chain::Internal] {
+            let mut gap = 0;
+            let mut address_index = 0;
+            while gap < self.gap_limit {
+                let address = BtcSigner::new(account, chain, address_index)?.address();
+                let utxos = source
+                    .utxos(&address)
+                    .map_err(|e| Error::UtxoSourceError(format!("{}: {}", address, e)))?;
+                if utxos.is_empty() {
+                    gap += 1;
+                } else {
+                    gap = 0;
+                }
+                for utxo in utxos {
+                    if utxo.script_pubkey != address.script_pubkey() {
+                        return Err(Error::ValidationError(format!(
+                            "UTXO {} does not pay {}",
+                            utxo.outpoint, address
+                        )));
+                    }
+                    found.push(SweptUtxo {
+                        utxo,
+                        chain,
+                        address_index,
+                    });
+                }
+                address_index += 1;
+            }
+        }
+        Ok(found)
+    }
+
+    /// Batches `utxos` into consolidation transactions paying the destination.
+    ///
+    /// Outputs worth no more than the fee for their own input are skipped rather than
+    /// swept at a loss, as is a final batch whose value after fee would be dust.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ValidationError`] if an output is not P2PKH, P2SH or P2WPKH,
+    /// or nothing is worth sweeping.
+    pub fn plan(&self, utxos: Vec<SweptUtxo>) -> Result<SweepPlan> {
+        let destination = self.destination.script_pubkey();
+        let dust = fee::dust_threshold(&destination, FeeRate::DUST_RELAY);
+        let types = input_types(
+            &utxos
+                .iter()
+                .map(|swept| swept.utxo.clone())
+                .collect::<Vec<_>>(),
+        )?;
+
+        let (economical, skipped): (Vec<_>, Vec<_>) =
+            utxos
+                .into_iter()
+                .zip(types)
+                .partition(|(swept, script_type)| {
+                    swept.utxo.value > self.fee_rate.fee_for_weight(script_type.input_weight())
+                });
+        let mut skipped: Vec<SweptUtxo> = skipped.into_iter().map(|(swept, _)| swept).collect();
+
+        let mut batches = Vec::new();
+        for chunk in economical.chunks(self.max_inputs) {
+            let types: Vec<ScriptType> =
+                chunk.iter().map(|(_, script_type)| *script_type).collect();
+            let vsize = fee::estimate_vsize(&types, std::slice::from_ref(&destination));
+            let fee = self.fee_rate.fee_for_vsize(vsize);
+            let total: u64 = chunk.iter().map(|(swept, _)| swept.utxo.value).sum();
+            let utxos = chunk.iter().map(|(swept, _)| swept.clone()).collect();
+            if total < fee.saturating_add(dust) {
+                skipped.extend(utxos);
+                continue;
+            }
+            batches.push(SweepBatch {
+                utxos,
+                value: total - fee,
+                fee,
+                vsize,
+            });
+        }
+
+        if batches.is_empty() {
+            return Err(Error::ValidationError(
+                "no outputs worth sweeping at this fee rate".to_string(),
+            ));
+        }
+        Ok(SweepPlan {
+            destination: self.destination.clone(),
+            batches,
+            skipped,
+            rbf: self.rbf,
+        })
+    }
+}
+
+/// Returns the receive address at `address_index` of a BIP-84 (P2WPKH) or BIP-86
+/// (taproot) account, the usual destinations of a sweep.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] for accounts of other purposes, or an error if
+/// key derivation fails.
+pub fn receive_address(account: &Account, address_index: u32) -> Result<Address> {
+    let key = account.derive_external(address_index)?;
+    let public_key =
+        PublicKey::from_secret_key(SECP256K1, key.private_key().secret_key()).serialize();
+    let network = account.network().into();
+    match account.purpose() {
+        Purpose::BIP84 => Ok(Address::p2wpkh(&public_key, network)),
+        Purpose::BIP86 => Address::p2tr(&public_key, network),
+        purpose => Err(Error::ValidationError(format!(
+            "{:?} accounts are not sweep destinations",
+            purpose
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Network, OutPoint, Txid};
+    use khodpay_bip32::Network as Bip32Network;
+    use khodpay_bip44::{CoinType, Wallet};
+    use std::collections::HashMap;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[derive(Default)]
+    struct Source {
+        utxos: HashMap<Address, Vec<Utxo>>,
+        fail: bool,
+    }
+
+    impl Source {
+        fn add(&mut self, account: &Account, chain: Chain, index: u32, value: u64) {
+            let address = BtcSigner::new(account, chain, index).unwrap().address();
+            let utxos = self.utxos.entry(address.clone()).or_default();
+            let outpoint =
+                OutPoint::new(Txid::from_bytes([index as u8 + 1; 32]), utxos.len() as u32);
+            utxos.push(Utxo::new(outpoint, value, address.script_pubkey()));
+        }
+    }
+
+    impl UtxoSource for Source {
+        fn utxos(
+            &self,
+            address: &Address,
+        ) -> std::result::Result<Vec<Utxo>, Box<dyn std::error::Error>> {
+            if self.fail {
+                return Err("connection refused".into());
+            }
+            Ok(self.utxos.get(address).cloned().unwrap_or_default())
+        }
+    }
+
+    fn wallet() -> Wallet {
+        Wallet::from_english_mnemonic(MNEMONIC, "", Bip32Network::BitcoinMainnet).unwrap()
+    }
+
+    #[test]
+    fn test_receive_address() {
+        let mut wallet = wallet();
+        // BIP-84 and BIP-86 test vectors.
+        let bip84 = wallet
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+            .unwrap();
+        assert_eq!(
+            receive_address(bip84, 0).unwrap().to_string(),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        let bip86 = wallet
+            .get_account(Purpose::BIP86, CoinType::Bitcoin, 0)
+            .unwrap();
+        assert_eq!(
+            receive_address(bip86, 0).unwrap().to_string(),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+        let bip44 = wallet
+            .get_account(Purpose::BIP44, CoinType::Bitcoin, 0)
+            .unwrap();
+        assert!(receive_address(bip44, 0).is_err());
+    }
+
+    #[test]
+    fn test_scan_respects_gap_limit() {
+        let mut wallet = wallet();
+        let legacy = wallet
+            .get_account(Purpose::BIP44, CoinType::Bitcoin, 0)
+            .unwrap();
+        let mut source = Source::default();
+        source.add(legacy, Chain::External, 0, 10_000);
+        source.add(legacy, Chain::External, 0, 20_000);
+        source.add(legacy, Chain::External, 4, 30_000);
+        source.add(legacy, Chain::Internal, 2, 40_000);
+        // Beyond a gap of five unused addresses.
+        source.add(legacy, Chain::External, 10, 50_000);
+
+        let destination: Address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+            .parse()
+            .unwrap();
+        let found = Sweep::new(&destination)
+            .gap_limit(5)
+            .scan(legacy, &source)
+            .unwrap();
+        let positions: Vec<_> = found
+            .iter()
+            .map(|swept| (swept.chain, swept.address_index, swept.utxo.value))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (Chain::External, 0, 10_000),
+                (Chain::External, 0, 20_000),
+                (Chain::External, 4, 30_000),
+                (Chain::Internal, 2, 40_000),
+            ]
+        );
+
+        let found = Sweep::new(&destination).scan(legacy, &source).unwrap();
+        assert_eq!(found.len(), 5);
+
+        source.fail = true;
+        let err = Sweep::new(&destination).scan(legacy, &source).unwrap_err();
+        assert!(
+            matches!(err, Error::UtxoSourceError(ref message) if message.contains("connection refused"))
+        );
+    }
+
+    #[test]
+    fn test_plan_and_sign_batches() {
+        let mut wallet = wallet();
+        let destination = receive_address(
+            wallet
+                .get_account(Purpose::BIP86, CoinType::Bitcoin, 0)
+                .unwrap(),
+            0,
+        )
+        .unwrap();
+        let nested = wallet
+            .get_account(Purpose::BIP49, CoinType::Bitcoin, 0)
+            .unwrap();
+        let mut source = Source::default();
+        for index in 0..5 {
+            source.add(
+                nested,
+                Chain::External,
+                index,
+                10_000 * (u64::from(index) + 1),
+            );
+        }
+        // Costs more to spend than it is worth at 10 sat/vB.
+        source.add(nested, Chain::Internal, 0, 900);
+
+        let rate = FeeRate::from_sat_per_vb(10);
+        let sweep = Sweep::new(&destination)
+            .fee_rate(rate)
+            .max_inputs(2)
+            .rbf(true);
+        let plan = sweep.plan(sweep.scan(nested, &source).unwrap()).unwrap();
+        assert_eq!(plan.batches.len(), 3);
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].utxo.value, 900);
+        assert_eq!(plan.total_value() + plan.total_fee(), 150_000);
+
+        let txs = plan.sign(nested).unwrap();
+        for (tx, batch) in txs.iter().zip(&plan.batches) {
+            assert_eq!(tx.inputs.len(), batch.utxos.len());
+            assert_eq!(tx.outputs.len(), 1);
+            assert_eq!(tx.outputs[0].value, batch.value);
+            assert_eq!(tx.outputs[0].script_pubkey, destination.script_pubkey());
+            assert_eq!(tx.vsize(), batch.vsize);
+            assert_eq!(batch.fee, rate.fee_for_vsize(batch.vsize));
+            assert!(tx.signals_rbf());
+        }
+
+        // Signing needs the scanned account's keys.
+        let other = wallet
+            .get_account(Purpose::BIP49, CoinType::Bitcoin, 1)
+            .unwrap();
+        assert!(plan.sign(other).is_err());
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        let mut wallet = wallet();
+        let legacy = wallet
+            .get_account(Purpose::BIP44, CoinType::Bitcoin, 0)
+            .unwrap();
+        let mut source = Source::default();
+        source.add(legacy, Chain::External, 0, 1_000);
+        let destination = Address::p2wpkh(&[0x02; 33], Network::Bitcoin);
+
+        let sweep = Sweep::new(&destination).fee_rate(FeeRate::from_sat_per_vb(20));
+        let err = sweep
+            .plan(sweep.scan(legacy, &source).unwrap())
+            .unwrap_err();
+        assert!(err.to_string().contains("no outputs worth sweeping"));
+    }
+}
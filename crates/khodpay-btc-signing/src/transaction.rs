@@ -0,0 +1,628 @@
+//! Bitcoin transaction types and consensus serialization.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::hash::sha256d;
+use crate::{Error, Result, Script};
+
+/// Sequence number that disables both relative lock-time and `nLockTime`.
+pub const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+
+/// Sequence number that enables `nLockTime` without signalling replace-by-fee.
+pub const SEQUENCE_LOCKTIME_NO_RBF: u32 = 0xffff_fffe;
+
+/// Sequence number that signals replace-by-fee (BIP-125) and enables `nLockTime`.
+pub const SEQUENCE_RBF: u32 = 0xffff_fffd;
+
+/// A transaction ID.
+///
+/// Stored in internal byte order (as hashed and serialized); displayed and parsed
+/// in the reversed order used by block explorers and RPC.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::Txid;
+///
+/// let txid: Txid = "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(txid.as_bytes()[0], 0x98);
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Txid([u8; 32]);
+
+impl Txid {
+    /// Creates a transaction ID from bytes in internal order.
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the bytes in internal order.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Txid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut reversed = self.0;
+        reversed.reverse();
+        f.write_str(&hex::encode(reversed))
+    }
+}
+
+impl fmt::Debug for Txid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Txid({})", self)
+    }
+}
+
+impl FromStr for Txid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut bytes: [u8; 32] = hex::decode(s)
+            .map_err(|e| Error::HexError(e.to_string()))?
+            .try_into()
+            .map_err(|_| Error::HexError(format!("txid must be 32 bytes: {}", s)))?;
+        bytes.reverse();
+        Ok(Self(bytes))
+    }
+}
+
+/// A reference to a transaction output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OutPoint {
+    /// The transaction containing the output.
+    pub txid: Txid,
+    /// The output index.
+    pub vout: u32,
+}
+
+impl OutPoint {
+    /// Creates an outpoint.
+    pub const fn new(txid: Txid, vout: u32) -> Self {
+        Self { txid, vout }
+    }
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+/// A transaction input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxIn {
+    /// The output being spent.
+    pub previous_output: OutPoint,
+    /// Unlocking script (empty for native SegWit inputs).
+    pub script_sig: Script,
+    /// Sequence number.
+    pub sequence: u32,
+    /// Witness stack (empty for legacy inputs).
+    pub witness: Vec<Vec<u8>>,
+}
+
+impl TxIn {
+    /// Creates an unsigned input spending `previous_output`.
+    pub fn new(previous_output: OutPoint, sequence: u32) -> Self {
+        Self {
+            previous_output,
+            script_sig: Script::default(),
+            sequence,
+            witness: Vec::new(),
+        }
+    }
+}
+
+/// A transaction output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOut {
+    /// Amount in satoshis.
+    pub value: u64,
+    /// Locking script.
+    pub script_pubkey: Script,
+}
+
+/// A Bitcoin transaction.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_btc_signing::{OutPoint, Script, Transaction, TxIn, TxOut, SEQUENCE_FINAL};
+///
+/// let tx = Transaction {
+///     version: 2,
+///     inputs: vec![TxIn::new(OutPoint::default(), SEQUENCE_FINAL)],
+///     outputs: vec![TxOut { value: 1_000, script_pubkey: Script::p2wpkh(&[0; 20]) }],
+///     lock_time: 0,
+/// };
+/// assert_eq!(tx.vsize(), 82);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    /// Transaction version (2 enables BIP-68 relative lock-times).
+    pub version: i32,
+    /// Inputs.
+    pub inputs: Vec<TxIn>,
+    /// Outputs.
+    pub outputs: Vec<TxOut>,
+    /// `nLockTime`: block height or timestamp before which the transaction is invalid.
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Returns `true` if any input carries witness data.
+    pub fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|input| !input.witness.is_empty())
+    }
+
+    /// Serializes the transaction, in BIP-144 SegWit format if any input has a witness.
+    pub fn serialize(&self) -> Vec<u8> {
+        self.encode(self.has_witness())
+    }
+
+    /// Serializes the transaction without witness data, as hashed for the txid.
+    pub fn serialize_without_witness(&self) -> Vec<u8> {
+        self.encode(false)
+    }
+
+    /// Returns the serialized transaction as lowercase hex, ready for broadcast.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.serialize())
+    }
+
+    /// Returns the transaction ID.
+    pub fn txid(&self) -> Txid {
+        Txid(sha256d(&self.serialize_without_witness()))
+    }
+
+    /// Returns the witness transaction ID (equal to the txid without witnesses).
+    pub fn wtxid(&self) -> Txid {
+        Txid(sha256d(&self.serialize()))
+    }
+
+    /// Returns `true` if an input signals replace-by-fee (BIP-125).
+    pub fn signals_rbf(&self) -> bool {
+        self.inputs
+            .iter()
+            .any(|input| input.sequence < SEQUENCE_LOCKTIME_NO_RBF)
+    }
+
+    /// Returns the BIP-141 weight: base size × 3 + total size.
+    pub fn weight(&self) -> u64 {
+        let base = self.serialize_without_witness().len() as u64;
+        let total = self.serialize().len() as u64;
+        base * 3 + total
+    }
+
+    /// Returns the virtual size in vbytes (weight / 4, rounded up).
+    pub fn vsize(&self) -> u64 {
+        self.weight().div_ceil(4)
+    }
+
+    /// Returns the sum of all output values, or `None` on overflow.
+    pub fn output_value(&self) -> Option<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.value))
+    }
+
+    /// Parses a consensus-serialized transaction, in legacy or BIP-144 SegWit format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ValidationError`] for truncated or trailing data, non-canonical
+    /// CompactSize lengths, an unknown SegWit flag, or a SegWit encoding without any
+    /// witness data (which re-serializes differently, changing the wtxid).
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let version = i32::from_le_bytes(reader.read_array()?);
+
+        let mut input_count = reader.read_compact_size()?;
+        let segwit = input_count == 0;
+        if segwit {
+            if reader.read_u8()? != 0x01 {
+                return Err(Error::ValidationError("unknown SegWit flag".to_string()));
+            }
+            input_count = reader.read_compact_size()?;
+        }
+
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let txid = Txid(reader.read_array()?);
+            let vout = u32::from_le_bytes(reader.read_array()?);
+            let script_sig = Script::new(reader.read_var_bytes()?.to_vec());
+            let sequence = u32::from_le_bytes(reader.read_array()?);
+            inputs.push(TxIn {
+                script_sig,
+                ..TxIn::new(OutPoint::new(txid, vout), sequence)
+            });
+        }
+
+        let output_count = reader.read_compact_size()?;
+        let mut outputs = Vec::new();
+        for _ in 0..output_count {
+            outputs.push(reader.read_txout()?);
+        }
+
+        if segwit {
+            for input in &mut inputs {
+                let items = reader.read_compact_size()?;
+                for _ in 0..items {
+                    input.witness.push(reader.read_var_bytes()?.to_vec());
+                }
+            }
+            if inputs.iter().all(|input| input.witness.is_empty()) {
+                return Err(Error::ValidationError(
+                    "SegWit encoding without witness data".to_string(),
+                ));
+            }
+        }
+
+        let lock_time = u32::from_le_bytes(reader.read_array()?);
+        if !reader.is_empty() {
+            return Err(Error::ValidationError(
+                "trailing data after transaction".to_string(),
+            ));
+        }
+        Ok(Self {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+        })
+    }
+
+    /// Parses a hex-encoded transaction, as returned by `getrawtransaction` and block
+    /// explorer APIs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::HexError`] if `hex` is not valid hex, otherwise the errors of
+    /// [`deserialize`](Self::deserialize).
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let data = hex::decode(hex.trim()).map_err(|e| Error::HexError(e.to_string()))?;
+        Self::deserialize(&data)
+    }
+
+    fn encode(&self, witness: bool) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        if witness {
+            buf.extend_from_slice(&[0x00, 0x01]);
+        }
+
+        write_compact_size(&mut buf, self.inputs.len() as u64);
+        for input in &self.inputs {
+            write_outpoint(&mut buf, &input.previous_output);
+            write_bytes(&mut buf, input.script_sig.as_bytes());
+            buf.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+
+        write_compact_size(&mut buf, self.outputs.len() as u64);
+        for output in &self.outputs {
+            write_txout(&mut buf, output);
+        }
+
+        if witness {
+            for input in &self.inputs {
+                write_compact_size(&mut buf, input.witness.len() as u64);
+                for item in &input.witness {
+                    write_bytes(&mut buf, item);
+                }
+            }
+        }
+
+        buf.extend_from_slice(&self.lock_time.to_le_bytes());
+        buf
+    }
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl FromStr for Transaction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_hex(s)
+    }
+}
+
+// ─── Encoding helpers ────────────────────────────────────────────────────────
+
+/// Returns the encoded length of compact size `n`.
+pub(crate) fn compact_size_len(n: u64) -> u64 {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+pub(crate) fn write_compact_size(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+pub(crate) fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+pub(crate) fn write_outpoint(buf: &mut Vec<u8>, outpoint: &OutPoint) {
+    buf.extend_from_slice(outpoint.txid.as_bytes());
+    buf.extend_from_slice(&outpoint.vout.to_le_bytes());
+}
+
+pub(crate) fn write_txout(buf: &mut Vec<u8>, output: &TxOut) {
+    buf.extend_from_slice(&output.value.to_le_bytes());
+    write_bytes(buf, output.script_pubkey.as_bytes());
+}
+
+// ─── Decoding helpers ────────────────────────────────────────────────────────
+
+/// Cursor over consensus-encoded bytes.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| {
+                Error::ValidationError(format!("unexpected end of data at byte {}", self.pos))
+            })?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.read_bytes(N)?.try_into().expect("length checked"))
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a minimally encoded CompactSize integer.
+    pub(crate) fn read_compact_size(&mut self) -> Result<u64> {
+        let (value, min) = match self.read_u8()? {
+            0xfd => (u16::from_le_bytes(self.read_array()?) as u64, 0xfd),
+            0xfe => (u32::from_le_bytes(self.read_array()?) as u64, 0x1_0000),
+            0xff => (self.read_u64()?, 0x1_0000_0000),
+            n => return Ok(n as u64),
+        };
+        if value < min {
+            return Err(Error::ValidationError(format!(
+                "non-canonical CompactSize {}",
+                value
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Reads a CompactSize length followed by that many bytes.
+    pub(crate) fn read_var_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_compact_size()?;
+        let len = usize::try_from(len)
+            .map_err(|_| Error::ValidationError(format!("length {} too large", len)))?;
+        self.read_bytes(len)
+    }
+
+    pub(crate) fn read_txout(&mut self) -> Result<TxOut> {
+        let value = self.read_u64()?;
+        let script_pubkey = Script::new(self.read_var_bytes()?.to_vec());
+        Ok(TxOut {
+            value,
+            script_pubkey,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bitcoin's first non-coinbase transaction (block 170), a legacy transaction.
+    fn block_170_tx() -> Transaction {
+        let script_sig = Script::from_hex(
+            "47304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901",
+        )
+        .unwrap();
+        Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: OutPoint::new(
+                    "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9"
+                        .parse()
+                        .unwrap(),
+                    0,
+                ),
+                script_sig,
+                sequence: SEQUENCE_FINAL,
+                witness: Vec::new(),
+            }],
+            outputs: vec![
+                TxOut {
+                    value: 1_000_000_000,
+                    script_pubkey: Script::from_hex("4104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac").unwrap(),
+                },
+                TxOut {
+                    value: 4_000_000_000,
+                    script_pubkey: Script::from_hex("410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac").unwrap(),
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_legacy_txid() {
+        let tx = block_170_tx();
+        assert!(!tx.has_witness());
+        assert_eq!(
+            tx.txid().to_string(),
+            "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16"
+        );
+        assert_eq!(tx.wtxid(), tx.txid());
+        assert_eq!(tx.weight(), tx.serialize().len() as u64 * 4);
+        assert_eq!(tx.output_value(), Some(5_000_000_000));
+    }
+
+    #[test]
+    fn test_witness_serialization() {
+        let mut tx = block_170_tx();
+        tx.inputs[0].script_sig = Script::default();
+        tx.inputs[0].witness = vec![vec![0xaa; 72], vec![0xbb; 33]];
+
+        let full = tx.serialize();
+        let base = tx.serialize_without_witness();
+        assert_eq!(&full[4..6], &[0x00, 0x01]);
+        // Marker, flag, item count and two length-prefixed items.
+        assert_eq!(full.len(), base.len() + 2 + 1 + 73 + 34);
+        assert_ne!(tx.wtxid(), tx.txid());
+        assert_eq!(
+            tx.vsize(),
+            (base.len() as u64 * 3 + full.len() as u64).div_ceil(4)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_round_trip() {
+        let legacy = block_170_tx();
+        assert_eq!(
+            Transaction::deserialize(&legacy.serialize()).unwrap(),
+            legacy
+        );
+
+        let mut segwit = block_170_tx();
+        segwit.inputs[0].script_sig = Script::default();
+        segwit.inputs[0].witness = vec![vec![0xaa; 72], vec![0xbb; 33]];
+        let bytes = segwit.serialize();
+        assert_eq!(Transaction::deserialize(&bytes).unwrap(), segwit);
+
+        assert!(Transaction::deserialize(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(Transaction::deserialize(&trailing).is_err());
+
+        // The marker and flag with only empty witnesses is not a valid encoding.
+        let mut empty_witness = block_170_tx().serialize();
+        empty_witness.splice(4..4, [0x00, 0x01]);
+        let lock_time = empty_witness.len() - 4;
+        empty_witness.insert(lock_time, 0x00);
+        let err = Transaction::deserialize(&empty_witness).unwrap_err();
+        assert!(err.to_string().contains("without witness data"));
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        // BIP-143 native P2WPKH example: one legacy and one SegWit input.
+        let hex = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeeb635711000000";
+        let tx: Transaction = hex.parse().unwrap();
+        assert_eq!(tx.version, 1);
+        assert_eq!(tx.inputs.len(), 2);
+        assert!(tx.inputs[0].witness.is_empty());
+        assert_eq!(tx.inputs[1].witness.len(), 2);
+        assert_eq!(tx.outputs[0].value, 112_340_000);
+        assert_eq!(tx.lock_time, 17);
+        assert_eq!(tx.to_string(), hex);
+        assert_ne!(tx.wtxid(), tx.txid());
+
+        // The txid commits to the legacy serialization only.
+        let stripped = Transaction::from_hex(&hex::encode(tx.serialize_without_witness())).unwrap();
+        assert_eq!(stripped.txid(), tx.txid());
+        assert_eq!(stripped.wtxid(), tx.txid());
+
+        assert!(matches!(
+            Transaction::from_hex("zz"),
+            Err(Error::HexError(_))
+        ));
+    }
+
+    #[test]
+    fn test_compact_size() {
+        for (n, expected) in [
+            (0u64, "00"),
+            (0xfc, "fc"),
+            (0xfd, "fdfd00"),
+            (0xffff, "fdffff"),
+            (0x1_0000, "fe00000100"),
+            (0x1_0000_0000, "ff0000000001000000"),
+        ] {
+            let mut buf = Vec::new();
+            write_compact_size(&mut buf, n);
+            assert_eq!(hex::encode(&buf), expected, "n = {}", n);
+
+            let mut reader = Reader::new(&buf);
+            assert_eq!(reader.read_compact_size().unwrap(), n);
+            assert!(reader.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_reader_rejects_bad_input() {
+        // Non-minimal CompactSize encodings.
+        assert!(Reader::new(&[0xfd, 0xfc, 0x00])
+            .read_compact_size()
+            .is_err());
+        assert!(Reader::new(&[0xfe, 0xff, 0xff, 0x00, 0x00])
+            .read_compact_size()
+            .is_err());
+        // Length prefix beyond the data.
+        assert!(Reader::new(&[0x05, 0x01, 0x02]).read_var_bytes().is_err());
+        assert!(Reader::new(&[0x01, 0x02]).read_u64().is_err());
+    }
+
+    #[test]
+    fn test_txid_parse_errors() {
+        assert!("zz".parse::<Txid>().is_err());
+        assert!("00".parse::<Txid>().is_err());
+    }
+
+    #[test]
+    fn test_outpoint_display() {
+        let outpoint = OutPoint::new(Txid::from_bytes([0x01; 32]), 3);
+        assert_eq!(outpoint.to_string(), format!("{}:3", "01".repeat(32)));
+    }
+}
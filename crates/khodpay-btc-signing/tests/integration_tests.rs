@@ -0,0 +1,226 @@
+//! Integration tests for the khodpay-btc-signing crate.
+//!
+//! These tests verify the full workflow from mnemonic to signed raw transaction.
+
+use khodpay_bip32::Network as Bip32Network;
+use khodpay_bip44::{Chain, CoinType, Purpose, Wallet};
+use khodpay_btc_signing::{
+    Address, BtcSigner, Error, Network, OutPoint, Script, ScriptType, TransactionBuilder, Txid,
+    Utxo,
+};
+use secp256k1::ecdsa::Signature;
+use secp256k1::{Message, PublicKey, SECP256K1};
+use sha2::{Digest, Sha256};
+
+/// Standard test mnemonic (DO NOT USE IN PRODUCTION).
+const TEST_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+fn signers(network: Bip32Network, coin: CoinType) -> (BtcSigner, BtcSigner) {
+    let mut wallet = Wallet::from_english_mnemonic(TEST_MNEMONIC, "", network).unwrap();
+    let account = wallet.get_account(Purpose::BIP84, coin, 0).unwrap();
+    (
+        BtcSigner::new(account, Chain::External, 0).unwrap(),
+        BtcSigner::new(account, Chain::Internal, 0).unwrap(),
+    )
+}
+
+/// Recomputes the BIP-143 preimage from the raw transaction for a single-input,
+/// SIGHASH_ALL P2WPKH spend, independently of the crate's sighash code.
+fn single_input_sighash(raw: &[u8], value: u64, pubkey_hash: &[u8]) -> [u8; 32] {
+    // version | marker | flag | input count | outpoint | empty scriptSig | sequence
+    let version = &raw[0..4];
+    assert_eq!(&raw[4..7], &[0x00, 0x01, 0x01]);
+    let outpoint = &raw[7..43];
+    assert_eq!(raw[43], 0x00);
+    let sequence = &raw[44..48];
+
+    // Outputs run up to the witness, which starts with the item count (2).
+    let outputs_start = 48;
+    let mut cursor = outputs_start + 1;
+    for _ in 0..raw[outputs_start] {
+        cursor += 8;
+        cursor += 1 + raw[cursor] as usize;
+    }
+    let outputs = &raw[outputs_start + 1..cursor];
+    let lock_time = &raw[raw.len() - 4..];
+
+    let mut script_code = vec![0x19, 0x76, 0xa9, 0x14];
+    script_code.extend_from_slice(pubkey_hash);
+    script_code.extend_from_slice(&[0x88, 0xac]);
+
+    let mut preimage = Vec::new();
+    preimage.extend_from_slice(version);
+    preimage.extend_from_slice(&sha256d(outpoint));
+    preimage.extend_from_slice(&sha256d(sequence));
+    preimage.extend_from_slice(outpoint);
+    preimage.extend_from_slice(&script_code);
+    preimage.extend_from_slice(&value.to_le_bytes());
+    preimage.extend_from_slice(sequence);
+    preimage.extend_from_slice(&sha256d(outputs));
+    preimage.extend_from_slice(lock_time);
+    preimage.extend_from_slice(&1u32.to_le_bytes());
+    sha256d(&preimage)
+}
+
+// ==================== Full Workflow Tests ====================
+
+#[test]
+fn test_bip84_addresses() {
+    let (receive, change) = signers(Bip32Network::BitcoinMainnet, CoinType::Bitcoin);
+    // BIP-84 test vectors.
+    assert_eq!(
+        receive.p2wpkh_address().to_string(),
+        "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+    );
+    assert_eq!(
+        change.p2wpkh_address().to_string(),
+        "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"
+    );
+    assert_eq!(
+        hex::encode(receive.public_key()),
+        "0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c"
+    );
+}
+
+#[test]
+fn test_full_workflow_mnemonic_to_raw_transaction() {
+    let (receive, change) = signers(Bip32Network::BitcoinMainnet, CoinType::Bitcoin);
+    let utxo = Utxo::new(
+        OutPoint::new(Txid::from_bytes([0x11; 32]), 3),
+        250_000,
+        receive.p2wpkh_address().script_pubkey(),
+    );
+    let recipient: Address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        .parse()
+        .unwrap();
+
+    let tx = TransactionBuilder::new(Network::Bitcoin)
+        .add_utxo(utxo)
+        .add_output(&recipient, 200_000)
+        .change_address(&change.p2wpkh_address())
+        .fee(2_000)
+        .sign(&[&receive])
+        .unwrap();
+
+    let raw = tx.serialize();
+    assert_eq!(hex::encode(&raw), tx.to_hex());
+    assert!(tx.to_hex().starts_with("02000000000101"));
+    assert_eq!(tx.outputs[1].value, 48_000);
+    assert_eq!(
+        tx.outputs[1].script_pubkey,
+        change.p2wpkh_address().script_pubkey()
+    );
+
+    // The txid is the double SHA-256 of the witness-stripped serialization.
+    let mut txid = sha256d(&tx.serialize_without_witness());
+    txid.reverse();
+    assert_eq!(tx.txid().to_string(), hex::encode(txid));
+
+    // The witness signature verifies against an independently computed sighash.
+    let witness = &tx.inputs[0].witness;
+    let (der, sighash_type) = witness[0].split_at(witness[0].len() - 1);
+    assert_eq!(sighash_type, &[0x01]);
+    assert_eq!(witness[1], receive.public_key().to_vec());
+
+    let sighash = single_input_sighash(&raw, 250_000, &receive.pubkey_hash());
+    let signature = Signature::from_der(der).unwrap();
+    let public_key = PublicKey::from_slice(&witness[1]).unwrap();
+    SECP256K1
+        .verify_ecdsa(&Message::from_digest(sighash), &signature, &public_key)
+        .unwrap();
+}
+
+#[test]
+fn test_sweep_legacy_and_nested_accounts() {
+    let mut wallet =
+        Wallet::from_english_mnemonic(TEST_MNEMONIC, "", Bip32Network::BitcoinMainnet).unwrap();
+    let legacy = BtcSigner::new(
+        wallet
+            .get_account(Purpose::BIP44, CoinType::Bitcoin, 0)
+            .unwrap(),
+        Chain::External,
+        0,
+    )
+    .unwrap();
+    let nested = BtcSigner::new(
+        wallet
+            .get_account(Purpose::BIP49, CoinType::Bitcoin, 0)
+            .unwrap(),
+        Chain::External,
+        0,
+    )
+    .unwrap();
+    let (destination, _) = signers(Bip32Network::BitcoinMainnet, CoinType::Bitcoin);
+
+    // BIP-44 and BIP-49 test vectors.
+    assert_eq!(legacy.script_type(), ScriptType::P2pkh);
+    assert_eq!(
+        legacy.address().to_string(),
+        "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+    );
+    assert_eq!(nested.script_type(), ScriptType::P2shP2wpkh);
+    assert_eq!(
+        nested.address().to_string(),
+        "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf"
+    );
+
+    let tx = TransactionBuilder::new(Network::Bitcoin)
+        .add_utxo(Utxo::new(
+            OutPoint::new(Txid::from_bytes([0x33; 32]), 0),
+            40_000,
+            legacy.address().script_pubkey(),
+        ))
+        .add_utxo(Utxo::new(
+            OutPoint::new(Txid::from_bytes([0x44; 32]), 1),
+            60_000,
+            nested.address().script_pubkey(),
+        ))
+        .add_output(&destination.address(), 97_000)
+        .fee(3_000)
+        .sign(&[&legacy, &nested])
+        .unwrap();
+
+    assert!(tx.has_witness());
+    assert!(tx.inputs[0].witness.is_empty());
+    assert!(!tx.inputs[0].script_sig.is_empty());
+    assert_eq!(tx.inputs[1].witness.len(), 2);
+    assert_eq!(tx.inputs[1].witness[1], nested.public_key().to_vec());
+    assert_eq!(tx.output_value(), Some(97_000));
+}
+
+#[test]
+fn test_testnet_workflow() {
+    let (receive, change) = signers(Bip32Network::BitcoinTestnet, CoinType::BitcoinTestnet);
+    assert_eq!(receive.network(), Network::Testnet);
+    assert!(receive.p2wpkh_address().to_string().starts_with("tb1q"));
+
+    let utxo = Utxo::new(
+        OutPoint::new(Txid::from_bytes([0x22; 32]), 0),
+        10_000,
+        receive.p2wpkh_address().script_pubkey(),
+    );
+    let mainnet: Address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        .parse()
+        .unwrap();
+    let err = TransactionBuilder::new(Network::Testnet)
+        .add_utxo(utxo.clone())
+        .add_output(&mainnet, 9_000)
+        .fee(1_000)
+        .sign(&[&receive])
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidAddress(_)));
+
+    let tx = TransactionBuilder::new(Network::Testnet)
+        .add_utxo(utxo)
+        .add_output(&change.p2wpkh_address(), 9_000)
+        .fee(1_000)
+        .sign(&[&receive])
+        .unwrap();
+    assert!(tx.has_witness());
+    assert!(Script::p2wpkh(&change.pubkey_hash()) == tx.outputs[0].script_pubkey);
+}
@@ -0,0 +1,86 @@
+//! Helpers shared by the Bitcoin backends.
+
+use std::collections::BTreeMap;
+
+use khodpay_address::{Address, BitcoinNetwork};
+use khodpay_btc_signing::{FeeRate, Script};
+
+use crate::client::check_network;
+use crate::{
+    BitcoinFees, Error, Result, FAST_TARGET_BLOCKS, NORMAL_TARGET_BLOCKS, SLOW_TARGET_BLOCKS,
+};
+
+/// Returns the output script of `address`, checking it belongs to `network`.
+pub(crate) fn script_pubkey(address: &Address, network: BitcoinNetwork) -> Result<Script> {
+    check_network(address, network.into())?;
+    address
+        .as_bitcoin()
+        .map(|address| address.script_pubkey())
+        .ok_or_else(|| Error::Unsupported(format!("{} is not a Bitcoin address", address)))
+}
+
+/// Converts a rate in sat/vB, as servers report it, rounding up to whole sat/kvB and
+/// to at least the 1 sat/vB relay minimum.
+///
+/// Floating-point noise below a thousandth of a sat/kvB is ignored, so 25.1 sat/vB
+/// is 25 100 sat/kvB rather than 25 101.
+pub(crate) fn fee_rate(sat_per_vb: f64) -> FeeRate {
+    let sat_per_kvb = (sat_per_vb * 1000.0 - 0.001).ceil();
+    FeeRate::from_sat_per_kvb((sat_per_kvb as u64).max(1000))
+}
+
+/// Picks slow / normal / fast rates from estimates keyed by confirmation target.
+///
+/// A missing target takes the rate of the nearest shorter target, which pays at
+/// least as much, or failing that the shortest target known.
+pub(crate) fn bitcoin_fees(estimates: &BTreeMap<u16, f64>) -> Result<BitcoinFees> {
+    let pick = |target: u16| {
+        estimates
+            .range(..=target)
+            .next_back()
+            .or_else(|| estimates.iter().next())
+            .map(|(_, rate)| fee_rate(*rate))
+            .ok_or_else(|| Error::InvalidResponse("no fee estimates".to_string()))
+    };
+    Ok(BitcoinFees {
+        slow: pick(SLOW_TARGET_BLOCKS)?,
+        normal: pick(NORMAL_TARGET_BLOCKS)?,
+        fast: pick(FAST_TARGET_BLOCKS)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitcoin_fees() {
+        let estimates = BTreeMap::from([(2, 20.5), (5, 10.0), (25, 3.0), (144, 1.2)]);
+        let fees = bitcoin_fees(&estimates).unwrap();
+        // Target 1 falls back to the shortest target, 6 to 5
+        assert_eq!(fees.fast, FeeRate::from_sat_per_kvb(20_500));
+        assert_eq!(fees.normal, FeeRate::from_sat_per_vb(10));
+        assert_eq!(fees.slow, FeeRate::from_sat_per_kvb(1200));
+
+        assert_eq!(fee_rate(0.25), FeeRate::from_sat_per_vb(1));
+        assert_eq!(fee_rate(25.1), FeeRate::from_sat_per_kvb(25_100));
+        assert_eq!(fee_rate(12.3451), FeeRate::from_sat_per_kvb(12_346));
+        assert!(bitcoin_fees(&BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_script_pubkey() {
+        let address = Address::parse(
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            BitcoinNetwork::Bitcoin.into(),
+        )
+        .unwrap();
+        assert!(script_pubkey(&address, BitcoinNetwork::Bitcoin)
+            .unwrap()
+            .is_p2wpkh());
+        assert!(matches!(
+            script_pubkey(&address, BitcoinNetwork::Testnet),
+            Err(Error::AddressError(_))
+        ));
+    }
+}
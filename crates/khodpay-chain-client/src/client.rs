@@ -0,0 +1,129 @@
+//! The [`ChainClient`] trait.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use khodpay_address::{Address, Network};
+
+use crate::{Balance, FeeEstimate, Result, TxSummary};
+
+/// Read and broadcast access to one blockchain network.
+///
+/// Wallet code written against this trait works unchanged over any backend: Esplora
+/// or Electrum for Bitcoin, JSON-RPC for EVM chains. The trait is object safe, so a
+/// multi-coin wallet can hold one `Box<dyn ChainClient>` per network.
+///
+/// Amounts are in the chain's smallest unit: satoshis for Bitcoin, wei for EVM
+/// chains.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_address::Address;
+/// use khodpay_chain_client::{ChainClient, Result};
+///
+/// /// Prints the spendable balance of `address` on whichever network `client` serves.
+/// async fn show_balance(client: &dyn ChainClient, address: &str) -> Result<()> {
+///     let address = Address::parse(address, client.network())?;
+///     let balance = client.balance(&address).await?;
+///     println!("{} on {}: {}", address, client.network(), balance.total());
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Returns the network the client talks to.
+    fn network(&self) -> Network;
+
+    /// Returns the confirmed and pending balance of `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::AddressError`](crate::Error::AddressError) if the address
+    /// belongs to another network, or an error if the query fails.
+    async fn balance(&self, address: &Address) -> Result<Balance>;
+
+    /// Returns the transactions touching `address`, unconfirmed ones first, then
+    /// newest to oldest.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`](crate::Error::Unsupported) if the backend keeps
+    /// no per-address index, [`Error::AddressError`](crate::Error::AddressError) if
+    /// the address belongs to another network, or an error if the query fails.
+    async fn history(&self, address: &Address) -> Result<Vec<TxSummary>>;
+
+    /// Broadcasts a signed raw transaction and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Server`](crate::Error::Server) if the network rejects the
+    /// transaction, or an error if the request fails.
+    async fn broadcast(&self, raw_tx: &[u8]) -> Result<String>;
+
+    /// Returns slow / normal / fast fee suggestions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    async fn fee_estimate(&self) -> Result<FeeEstimate>;
+}
+
+#[async_trait]
+impl<T: ChainClient + ?Sized> ChainClient for Box<T> {
+    fn network(&self) -> Network {
+        (**self).network()
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        (**self).balance(address).await
+    }
+
+    async fn history(&self, address: &Address) -> Result<Vec<TxSummary>> {
+        (**self).history(address).await
+    }
+
+    async fn broadcast(&self, raw_tx: &[u8]) -> Result<String> {
+        (**self).broadcast(raw_tx).await
+    }
+
+    async fn fee_estimate(&self) -> Result<FeeEstimate> {
+        (**self).fee_estimate().await
+    }
+}
+
+#[async_trait]
+impl<T: ChainClient + ?Sized> ChainClient for Arc<T> {
+    fn network(&self) -> Network {
+        (**self).network()
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        (**self).balance(address).await
+    }
+
+    async fn history(&self, address: &Address) -> Result<Vec<TxSummary>> {
+        (**self).history(address).await
+    }
+
+    async fn broadcast(&self, raw_tx: &[u8]) -> Result<String> {
+        (**self).broadcast(raw_tx).await
+    }
+
+    async fn fee_estimate(&self) -> Result<FeeEstimate> {
+        (**self).fee_estimate().await
+    }
+}
+
+/// Checks that `address` can receive on `network`.
+#[cfg(any(feature = "esplora", feature = "electrum", feature = "evm"))]
+pub(crate) fn check_network(address: &Address, network: Network) -> Result<()> {
+    if !address.is_valid_for(network) {
+        return Err(khodpay_address::Error::WrongNetwork {
+            address: address.to_string(),
+            network,
+        }
+        .into());
+    }
+    Ok(())
+}
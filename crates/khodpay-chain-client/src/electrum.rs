@@ -0,0 +1,1079 @@
+//! Bitcoin over the Electrum protocol (requires the `electrum` feature).
+//!
+//! Speaks newline-delimited JSON-RPC to an ElectrumX, Fulcrum or electrs server,
+//! in plain TCP (`tcp://host:port`) or TLS (`ssl://host:port`). TLS certificates
+//! are checked against the Mozilla root store, so servers with self-signed
+//! certificates need plain TCP, ideally over a tunnel.
+//!
+//! [`ElectrumClient::connect_with_proxy`] connects through a SOCKS5 proxy such as
+//! Tor, which also reaches `.onion` servers.
+//!
+//! [`ElectrumMonitor`] subscribes to addresses and reports incoming payments as the
+//! server announces them.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_address::{Address, BitcoinNetwork};
+//! use khodpay_chain_client::electrum::ElectrumClient;
+//! use khodpay_chain_client::ChainClient;
+//!
+//! # async fn run() -> khodpay_chain_client::Result<()> {
+//! let client = ElectrumClient::connect("ssl://electrum.blockstream.info:50002", BitcoinNetwork::Bitcoin).await?;
+//! let address = Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", client.network())?;
+//! println!("{} BTC", client.balance(&address).await?.total());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use khodpay_address::{Address, BitcoinNetwork, Network};
+use khodpay_amount::CoinAmount;
+use khodpay_btc_signing::{Script, Transaction, Txid};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+use crate::bitcoin::{bitcoin_fees, script_pubkey};
+use crate::monitor::{Payment, PaymentAsset, PaymentEvent, PaymentMonitor};
+use crate::proxy::is_onion;
+use crate::rate_limit::throttle;
+use crate::{
+    Balance, ChainClient, Error, FeeEstimate, Proxy, RateLimiter, Result, TxSummary,
+    FAST_TARGET_BLOCKS, NORMAL_TARGET_BLOCKS, SLOW_TARGET_BLOCKS,
+};
+
+/// Electrum protocol version this client speaks.
+const PROTOCOL_VERSION: &str = "1.4";
+
+/// Offset of the timestamp in an 80-byte block header.
+const HEADER_TIME_OFFSET: usize = 68;
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Method of script hash status notifications.
+const SCRIPTHASH_SUBSCRIBE: &str = "blockchain.scripthash.subscribe";
+
+#[derive(Deserialize)]
+struct Response {
+    id: Option<u64>,
+    result: Option<Value>,
+    error: Option<Value>,
+    method: Option<String>,
+    params: Option<Value>,
+}
+
+/// A script hash whose history changed, with its new status.
+struct StatusChange {
+    script_hash: String,
+    status: Option<String>,
+}
+
+/// The server connection and what has been read from it but not yet consumed.
+struct Connection {
+    reader: BufReader<Box<dyn Stream>>,
+    /// The line being read, kept when a read is cancelled so none of it is lost.
+    line: String,
+    /// Status notifications read while waiting for responses.
+    changes: VecDeque<StatusChange>,
+}
+
+impl Connection {
+    /// Reads the next complete message.
+    async fn read(&mut self) -> Result<Response> {
+        let read = self
+            .reader
+            .read_line(&mut self.line)
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+        if read == 0 {
+            return Err(Error::Transport("connection closed by server".to_string()));
+        }
+        let line = std::mem::take(&mut self.line);
+        serde_json::from_str(&line).map_err(|e| Error::InvalidResponse(e.to_string()))
+    }
+
+    /// Queues a script hash notification; other notifications are dropped.
+    fn notify(&mut self, notification: Response) {
+        if notification.method.as_deref() != Some(SCRIPTHASH_SUBSCRIBE) {
+            return;
+        }
+        let params = notification.params.unwrap_or(Value::Null);
+        if let Some(script_hash) = params.get(0).and_then(Value::as_str) {
+            self.changes.push_back(StatusChange {
+                script_hash: script_hash.to_string(),
+                status: params.get(1).and_then(Value::as_str).map(str::to_string),
+            });
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ScriptBalance {
+    confirmed: u64,
+    unconfirmed: i64,
+}
+
+#[derive(Deserialize)]
+struct HistoryEntry {
+    tx_hash: String,
+    height: i64,
+    fee: Option<u64>,
+}
+
+/// A [`ChainClient`] for Bitcoin over one Electrum server connection.
+///
+/// Requests are serialized over the connection, so share one client between
+/// tasks rather than opening a connection per task.
+pub struct ElectrumClient {
+    network: BitcoinNetwork,
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+    limiter: Option<RateLimiter>,
+}
+
+impl fmt::Debug for ElectrumClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElectrumClient")
+            .field("network", &self.network)
+            .field("limiter", &self.limiter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ElectrumClient {
+    /// Connects to the server at `url` and negotiates the protocol version.
+    ///
+    /// `url` is `ssl://host:port` for TLS, or `tcp://host:port` (or just
+    /// `host:port`) for plain TCP.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if the URL is malformed, names a `.onion`
+    /// host (use [`connect_with_proxy`](Self::connect_with_proxy)) or the
+    /// connection fails, and [`Error::Server`] if the server refuses the protocol
+    /// version.
+    pub async fn connect(url: &str, network: BitcoinNetwork) -> Result<Self> {
+        Self::open(url, network, None).await
+    }
+
+    /// Connects to the server at `url` through `proxy`, such as [`Proxy::tor`].
+    ///
+    /// The host name is resolved by the proxy, so `.onion` servers are reachable.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`connect`](Self::connect), with proxy failures reported as
+    /// [`Error::Transport`].
+    pub async fn connect_with_proxy(
+        url: &str,
+        network: BitcoinNetwork,
+        proxy: &Proxy,
+    ) -> Result<Self> {
+        Self::open(url, network, Some(proxy)).await
+    }
+
+    async fn open(url: &str, network: BitcoinNetwork, proxy: Option<&Proxy>) -> Result<Self> {
+        let (tls, address) = match url.split_once("://") {
+            Some(("ssl", address)) => (true, address),
+            Some(("tcp", address)) => (false, address),
+            Some((scheme, _)) => {
+                return Err(Error::Transport(format!(
+                    "unsupported Electrum scheme: {}",
+                    scheme
+                )))
+            }
+            None => (false, url),
+        };
+        let Some((host, port)) = address.rsplit_once(':') else {
+            return Err(Error::Transport(format!("missing port in {}", url)));
+        };
+
+        let tcp = match proxy {
+            Some(proxy) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| Error::Transport(format!("invalid port in {}", url)))?;
+                proxy.connect(host, port).await?
+            }
+            None if is_onion(host) => {
+                return Err(Error::Transport(format!(
+                    "{} is an onion service, only reachable through a Tor proxy",
+                    host
+                )))
+            }
+            None => TcpStream::connect(address)
+                .await
+                .map_err(|e| Error::Transport(format!("{}: {}", address, e)))?,
+        };
+        let stream: Box<dyn Stream> = if tls {
+            Box::new(tls_connect(host, tcp).await?)
+        } else {
+            Box::new(tcp)
+        };
+
+        let client = Self {
+            network,
+            connection: Mutex::new(Connection {
+                reader: BufReader::new(stream),
+                line: String::new(),
+                changes: VecDeque::new(),
+            }),
+            next_id: AtomicU64::new(0),
+            limiter: None,
+        };
+        let _: Value = client
+            .call(
+                "server.version",
+                json!([
+                    concat!("khodpay-chain-client ", env!("CARGO_PKG_VERSION")),
+                    PROTOCOL_VERSION
+                ]),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    /// Paces every request from now on with `limiter`, including the
+    /// transaction and header lookups behind [`ChainClient::history`].
+    pub fn with_rate_limit(mut self, limiter: impl Into<RateLimiter>) -> Self {
+        self.limiter = Some(limiter.into());
+        self
+    }
+
+    /// Sends one request and waits for its response, skipping notifications.
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        throttle(self.limiter.as_ref()).await?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request =
+            json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+        request.push('\n');
+
+        let mut connection = self.connection.lock().await;
+        let stream = connection.reader.get_mut();
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+        stream
+            .flush()
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        loop {
+            let response = connection.read().await.map_err(|e| match e {
+                Error::InvalidResponse(message) => {
+                    Error::InvalidResponse(format!("{}: {}", method, message))
+                }
+                other => other,
+            })?;
+            // Notifications carry no id; stale ids belong to cancelled requests
+            if response.id.is_none() {
+                connection.notify(response);
+                continue;
+            }
+            if response.id != Some(id) {
+                continue;
+            }
+            if let Some(error) = response.error {
+                let message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string());
+                return Err(Error::Server(format!("{}: {}", method, message)));
+            }
+            return serde_json::from_value(response.result.unwrap_or(Value::Null))
+                .map_err(|e| Error::InvalidResponse(format!("{}: {}", method, e)));
+        }
+    }
+
+    /// Waits for the next script hash status notification.
+    ///
+    /// Holds the connection until one arrives, so requests from other tasks wait
+    /// too; [`ElectrumMonitor`] therefore owns its client.
+    async fn next_status_change(&self) -> Result<StatusChange> {
+        let mut connection = self.connection.lock().await;
+        loop {
+            if let Some(change) = connection.changes.pop_front() {
+                return Ok(change);
+            }
+            let message = connection.read().await?;
+            // Responses to cancelled requests are stale
+            if message.id.is_none() {
+                connection.notify(message);
+            }
+        }
+    }
+
+    async fn transaction(&self, txid: &str) -> Result<Transaction> {
+        let raw: String = self
+            .call("blockchain.transaction.get", json!([txid]))
+            .await?;
+        Transaction::from_hex(&raw)
+            .map_err(|e| Error::InvalidResponse(format!("transaction {}: {}", txid, e)))
+    }
+
+    async fn block_time(&self, height: u64) -> Result<u64> {
+        let header: String = self
+            .call("blockchain.block.header", json!([height]))
+            .await?;
+        let header = hex::decode(&header)
+            .map_err(|e| Error::InvalidResponse(format!("header {}: {}", height, e)))?;
+        let time = header
+            .get(HEADER_TIME_OFFSET..HEADER_TIME_OFFSET + 4)
+            .ok_or_else(|| Error::InvalidResponse(format!("short header at {}", height)))?;
+        Ok(u64::from(u32::from_le_bytes(time.try_into().unwrap())))
+    }
+}
+
+#[async_trait]
+impl ChainClient for ElectrumClient {
+    fn network(&self) -> Network {
+        Network::Bitcoin(self.network)
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        let script = script_pubkey(address, self.network)?;
+        let balance: ScriptBalance = self
+            .call(
+                "blockchain.scripthash.get_balance",
+                json!([script_hash(&script)]),
+            )
+            .await?;
+        Ok(Balance {
+            confirmed: CoinAmount::from_sats(balance.confirmed),
+            pending: i128::from(balance.unconfirmed),
+        })
+    }
+
+    async fn history(&self, address: &Address) -> Result<Vec<TxSummary>> {
+        let script = script_pubkey(address, self.network)?;
+        let mut entries: Vec<HistoryEntry> = self
+            .call(
+                "blockchain.scripthash.get_history",
+                json!([script_hash(&script)]),
+            )
+            .await?;
+        // The server lists oldest first with the mempool last
+        entries.reverse();
+
+        let mut transactions = HashMap::new();
+        for entry in &entries {
+            let txid: Txid = entry
+                .tx_hash
+                .parse()
+                .map_err(|e| Error::InvalidResponse(format!("{}: {}", entry.tx_hash, e)))?;
+            transactions.insert(txid, self.transaction(&entry.tx_hash).await?);
+        }
+
+        let mut block_times = BTreeMap::new();
+        let mut history = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let tx = &transactions[&entry.tx_hash.parse::<Txid>().unwrap()];
+            let height = u64::try_from(entry.height).ok().filter(|&h| h > 0);
+            let timestamp = match height {
+                Some(height) => match block_times.get(&height) {
+                    Some(&time) => Some(time),
+                    None => {
+                        let time = self.block_time(height).await?;
+                        block_times.insert(height, time);
+                        Some(time)
+                    }
+                },
+                None => None,
+            };
+            history.push(TxSummary {
+                net: net_amount(tx, &script, &transactions),
+                fee: entry
+                    .fee
+                    .or_else(|| implied_fee(tx, &transactions))
+                    .map(CoinAmount::from_sats),
+                txid: entry.tx_hash,
+                height,
+                timestamp,
+            });
+        }
+        Ok(history)
+    }
+
+    async fn broadcast(&self, raw_tx: &[u8]) -> Result<String> {
+        self.call(
+            "blockchain.transaction.broadcast",
+            json!([hex::encode(raw_tx)]),
+        )
+        .await
+    }
+
+    async fn fee_estimate(&self) -> Result<FeeEstimate> {
+        // Rates come in BTC/kvB, with -1 when the server has too little data
+        let mut estimates = BTreeMap::new();
+        for target in [FAST_TARGET_BLOCKS, NORMAL_TARGET_BLOCKS, SLOW_TARGET_BLOCKS] {
+            let rate: f64 = self.call("blockchain.estimatefee", json!([target])).await?;
+            if rate > 0.0 {
+                estimates.insert(target, btc_per_kvb_to_sat_per_vb(rate));
+            }
+        }
+        if estimates.is_empty() {
+            let relay_fee: f64 = self.call("blockchain.relayfee", json!([])).await?;
+            estimates.insert(FAST_TARGET_BLOCKS, btc_per_kvb_to_sat_per_vb(relay_fee));
+        }
+        bitcoin_fees(&estimates).map(FeeEstimate::Bitcoin)
+    }
+}
+
+/// A [`PaymentMonitor`] over Electrum script hash subscriptions.
+///
+/// The server notifies the monitor whenever a watched address's history changes;
+/// the monitor then reads the history and announces new incoming transactions, and
+/// again when they confirm. Only transactions that add to the address's balance are
+/// announced.
+///
+/// Waiting for a notification holds the connection, so the monitor owns its
+/// client; use another connection for everything else.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_address::{Address, BitcoinNetwork};
+/// use khodpay_chain_client::electrum::{ElectrumClient, ElectrumMonitor};
+/// use khodpay_chain_client::monitor::PaymentMonitor;
+///
+/// # async fn run() -> khodpay_chain_client::Result<()> {
+/// let client = ElectrumClient::connect("ssl://electrum.blockstream.info:50002", BitcoinNetwork::Bitcoin).await?;
+/// let mut monitor = ElectrumMonitor::new(client);
+/// let address = Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", monitor.network())?;
+/// monitor.watch(&address).await?;
+/// let event = monitor.next_event().await?;
+/// println!("{} sats", event.payment().amount);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ElectrumMonitor {
+    client: ElectrumClient,
+    scripts: HashMap<String, WatchedScript>,
+    /// Script hashes whose status changed and whose history is not yet read.
+    stale: BTreeSet<String>,
+    events: VecDeque<PaymentEvent>,
+}
+
+#[derive(Debug)]
+struct WatchedScript {
+    address: Address,
+    status: Option<String>,
+    /// Heights of the transactions seen so far, `None` while unconfirmed.
+    seen: HashMap<String, Option<u64>>,
+}
+
+impl ElectrumMonitor {
+    /// Creates a monitor that takes over `client`'s connection.
+    pub fn new(client: ElectrumClient) -> Self {
+        Self {
+            client,
+            scripts: HashMap::new(),
+            stale: BTreeSet::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Reads the history of a changed script hash and queues its events.
+    async fn refresh(&mut self, hash: &str) -> Result<()> {
+        let Some(address) = self
+            .scripts
+            .get(hash)
+            .map(|watched| watched.address.clone())
+        else {
+            return Ok(());
+        };
+        let history = self.client.history(&address).await?;
+        let watched = self.scripts.get_mut(hash).expect("watched script");
+        // History is newest first; announce in the order the payments happened
+        for tx in history.into_iter().rev() {
+            let previous = watched.seen.insert(tx.txid.clone(), tx.height);
+            let Ok(amount) = u64::try_from(tx.net) else {
+                continue;
+            };
+            if amount == 0 {
+                continue;
+            }
+            let payment = Payment {
+                address: address.clone(),
+                asset: PaymentAsset::Native,
+                amount: CoinAmount::from_sats(amount),
+                txid: Some(tx.txid),
+                height: tx.height,
+            };
+            match previous {
+                None => self.events.push_back(PaymentEvent::Received(payment)),
+                Some(None) if payment.height.is_some() => {
+                    self.events.push_back(PaymentEvent::Confirmed(payment))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PaymentMonitor for ElectrumMonitor {
+    fn network(&self) -> Network {
+        self.client.network()
+    }
+
+    async fn watch(&mut self, address: &Address) -> Result<()> {
+        let hash = script_hash(&script_pubkey(address, self.client.network)?);
+        if self.scripts.contains_key(&hash) {
+            return Ok(());
+        }
+        let status: Option<String> = self
+            .client
+            .call(SCRIPTHASH_SUBSCRIBE, json!([hash]))
+            .await?;
+        let seen = match status {
+            Some(_) => self
+                .client
+                .history(address)
+                .await?
+                .into_iter()
+                .map(|tx| (tx.txid, tx.height))
+                .collect(),
+            None => HashMap::new(),
+        };
+        self.scripts.insert(
+            hash,
+            WatchedScript {
+                address: address.clone(),
+                status,
+                seen,
+            },
+        );
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<PaymentEvent> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+            // Left in place until read, so a cancelled read is retried
+            if let Some(hash) = self.stale.iter().next().cloned() {
+                self.refresh(&hash).await?;
+                self.stale.remove(&hash);
+                continue;
+            }
+            let change = self.client.next_status_change().await?;
+            if let Some(watched) = self.scripts.get_mut(&change.script_hash) {
+                if watched.status != change.status {
+                    watched.status = change.status;
+                    self.stale.insert(change.script_hash);
+                }
+            }
+        }
+    }
+}
+
+async fn tls_connect(
+    host: &str,
+    tcp: TcpStream,
+) -> Result<tokio_rustls::client::TlsStream<TcpStream>> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| Error::Transport(e.to_string()))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|e| Error::Transport(format!("{}: {}", host, e)))?;
+    TlsConnector::from(Arc::new(config))
+        .connect(server_name, tcp)
+        .await
+        .map_err(|e| Error::Transport(format!("TLS handshake with {}: {}", host, e)))
+}
+
+/// Returns the Electrum script hash: the reversed SHA-256 of the script, in hex.
+fn script_hash(script: &Script) -> String {
+    let mut hash: [u8; 32] = Sha256::digest(script.as_bytes()).into();
+    hash.reverse();
+    hex::encode(hash)
+}
+
+fn btc_per_kvb_to_sat_per_vb(rate: f64) -> f64 {
+    rate * 100_000.0
+}
+
+/// Returns what `tx` paid to `script` minus what it spent from it.
+///
+/// Every transaction spending from the script is in its history, so
+/// `transactions` holds all the outputs that matter.
+fn net_amount(
+    tx: &Transaction,
+    script: &Script,
+    transactions: &HashMap<Txid, Transaction>,
+) -> i128 {
+    let received: u64 = tx
+        .outputs
+        .iter()
+        .filter(|output| output.script_pubkey == *script)
+        .map(|output| output.value)
+        .sum();
+    let sent: u64 = tx
+        .inputs
+        .iter()
+        .filter_map(|input| prevout(input.previous_output, transactions))
+        .filter(|prevout| prevout.script_pubkey == *script)
+        .map(|prevout| prevout.value)
+        .sum();
+    i128::from(received) - i128::from(sent)
+}
+
+/// Returns the fee of `tx` if all of its inputs spend known transactions.
+fn implied_fee(tx: &Transaction, transactions: &HashMap<Txid, Transaction>) -> Option<u64> {
+    let input_value = tx.inputs.iter().try_fold(0u64, |sum, input| {
+        sum.checked_add(prevout(input.previous_output, transactions)?.value)
+    })?;
+    input_value.checked_sub(tx.output_value()?)
+}
+
+fn prevout(
+    outpoint: khodpay_btc_signing::OutPoint,
+    transactions: &HashMap<Txid, Transaction>,
+) -> Option<&khodpay_btc_signing::TxOut> {
+    transactions
+        .get(&outpoint.txid)?
+        .outputs
+        .get(outpoint.vout as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::testing::socks_server;
+    use khodpay_btc_signing::{OutPoint, TxIn, TxOut, SEQUENCE_FINAL};
+    use std::sync::Mutex as StdMutex;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    const ADDRESS: &str = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+
+    fn address() -> Address {
+        Address::parse(ADDRESS, Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap()
+    }
+
+    /// Serves one connection, answering each request with `respond(method, params)`.
+    async fn serve<F>(respond: F) -> String
+    where
+        F: Fn(&str, &Value) -> std::result::Result<Value, Value> + Send + 'static,
+    {
+        serve_pushing(respond, mpsc::unbounded_channel().1).await
+    }
+
+    /// Like [`serve`], also sending the messages from `pushes` as they come.
+    async fn serve_pushing<F>(respond: F, mut pushes: mpsc::UnboundedReceiver<Value>) -> String
+    where
+        F: Fn(&str, &Value) -> std::result::Result<Value, Value> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            loop {
+                let line = tokio::select! {
+                    line = lines.next_line() => match line.unwrap() {
+                        Some(line) => line,
+                        None => break,
+                    },
+                    Some(push) = pushes.recv() => {
+                        let push = format!("{}\n", push);
+                        write.write_all(push.as_bytes()).await.unwrap();
+                        continue;
+                    }
+                };
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let method = request["method"].as_str().unwrap();
+                // Interleave a subscription notification with every answer
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "blockchain.headers.subscribe",
+                    "params": [{ "height": 900_000 }],
+                });
+                let response = match respond(method, &request["params"]) {
+                    Ok(result) => {
+                        json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                    }
+                    Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+                };
+                let reply = format!("{}\n{}\n", notification, response);
+                write.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        address
+    }
+
+    async fn connect<F>(respond: F) -> ElectrumClient
+    where
+        F: Fn(&str, &Value) -> std::result::Result<Value, Value> + Send + 'static,
+    {
+        let address = serve(move |method, params| match method {
+            "server.version" => {
+                assert_eq!(params[1], PROTOCOL_VERSION);
+                Ok(json!(["ElectrumX 1.16.0", PROTOCOL_VERSION]))
+            }
+            _ => respond(method, params),
+        })
+        .await;
+        ElectrumClient::connect(&format!("tcp://{}", address), BitcoinNetwork::Bitcoin)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_script_hash() {
+        // Vector from the Electrum protocol documentation
+        let script = Script::p2pkh(
+            &hex::decode("62e907b15cbf27d5425399ebf6f0fb50ebb88f18")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(
+            script_hash(&script),
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_rejects_bad_urls() {
+        assert!(matches!(
+            ElectrumClient::connect("https://example.com:50002", BitcoinNetwork::Bitcoin).await,
+            Err(Error::Transport(_))
+        ));
+        assert!(matches!(
+            ElectrumClient::connect("tcp://localhost", BitcoinNetwork::Bitcoin).await,
+            Err(Error::Transport(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_proxy() {
+        let onion = "tcp://electrumx4hhcbl2ovf3rb3cj3s6x2nopbqjkhjwk2m3aovhn3vvuxnyd.onion:50001";
+        assert!(matches!(
+            ElectrumClient::connect(onion, BitcoinNetwork::Bitcoin).await,
+            Err(Error::Transport(message)) if message.contains("Tor proxy")
+        ));
+
+        let server = serve(|method, _| match method {
+            "server.version" => Ok(json!(["ElectrumX 1.16.0", PROTOCOL_VERSION])),
+            "blockchain.scripthash.get_balance" => Ok(json!({ "confirmed": 5, "unconfirmed": 0 })),
+            _ => panic!("unexpected {}", method),
+        })
+        .await;
+        let (proxy, connections) = socks_server(server).await;
+        let proxy = Proxy::socks5(proxy).with_credentials("account-0", "isolation");
+        let client = ElectrumClient::connect_with_proxy(onion, BitcoinNetwork::Bitcoin, &proxy)
+            .await
+            .unwrap();
+        assert_eq!(
+            client.balance(&address()).await.unwrap().confirmed,
+            CoinAmount::from_sats(5)
+        );
+
+        // The onion host name reached the proxy unresolved
+        assert_eq!(
+            *connections.lock().unwrap(),
+            [(
+                onion["tcp://".len()..].to_string(),
+                Some("account-0".to_string())
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_balance_and_broadcast() {
+        let script = address().as_bitcoin().unwrap().script_pubkey();
+        let expected_hash = script_hash(&script);
+        let client = connect(move |method, params| match method {
+            "blockchain.scripthash.get_balance" => {
+                assert_eq!(params[0], expected_hash.as_str());
+                Ok(json!({ "confirmed": 100_000, "unconfirmed": -20_000 }))
+            }
+            "blockchain.transaction.broadcast" if params[0] == "0200" => Ok(json!("ab".repeat(32))),
+            "blockchain.transaction.broadcast" => Err(json!({
+                "code": 1,
+                "message": "the transaction was rejected by network rules.\n\nTX decode failed\n",
+            })),
+            _ => panic!("unexpected {}", method),
+        })
+        .await;
+
+        let balance = client.balance(&address()).await.unwrap();
+        assert_eq!(balance.confirmed, CoinAmount::from_sats(100_000));
+        assert_eq!(balance.pending, -20_000);
+
+        assert_eq!(
+            client.broadcast(&[0x02, 0x00]).await.unwrap(),
+            "ab".repeat(32)
+        );
+        let error = client.broadcast(&[0xff]).await.unwrap_err();
+        assert!(
+            matches!(error, Error::Server(ref message) if message.contains("TX decode failed"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history() {
+        let ours = address().as_bitcoin().unwrap().script_pubkey();
+        let theirs = Script::p2wpkh(&[7; 20]);
+        let funding = Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(
+                OutPoint {
+                    txid: Txid::from_bytes([1; 32]),
+                    vout: 0,
+                },
+                SEQUENCE_FINAL,
+            )],
+            outputs: vec![TxOut {
+                value: 50_000,
+                script_pubkey: ours.clone(),
+            }],
+            lock_time: 0,
+        };
+        let spend = Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(
+                OutPoint {
+                    txid: funding.txid(),
+                    vout: 0,
+                },
+                SEQUENCE_FINAL,
+            )],
+            outputs: vec![
+                TxOut {
+                    value: 30_000,
+                    script_pubkey: theirs,
+                },
+                TxOut {
+                    value: 19_000,
+                    script_pubkey: ours,
+                },
+            ],
+            lock_time: 0,
+        };
+        let funding_id = funding.txid().to_string();
+        let spend_id = spend.txid().to_string();
+
+        let (funding_hex, spend_hex) = (funding.to_hex(), spend.to_hex());
+        let (history_funding, history_spend) = (funding_id.clone(), spend_id.clone());
+        let client = connect(move |method, params| match method {
+            "blockchain.scripthash.get_history" => Ok(json!([
+                { "tx_hash": history_funding, "height": 800_000 },
+                { "tx_hash": history_spend, "height": 0, "fee": 1_000 },
+            ])),
+            "blockchain.transaction.get" if params[0] == history_funding.as_str() => {
+                Ok(json!(funding_hex))
+            }
+            "blockchain.transaction.get" => Ok(json!(spend_hex)),
+            "blockchain.block.header" => {
+                assert_eq!(params[0], 800_000);
+                let mut header = [0u8; 80];
+                header[68..72].copy_from_slice(&1_690_168_629u32.to_le_bytes());
+                Ok(json!(hex::encode(header)))
+            }
+            _ => panic!("unexpected {}", method),
+        })
+        .await;
+
+        let history = client.history(&address()).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].txid, spend_id);
+        assert_eq!(history[0].height, None);
+        assert_eq!(history[0].net, -31_000);
+        assert_eq!(history[0].fee, Some(CoinAmount::from_sats(1_000)));
+        assert_eq!(history[1].txid, funding_id);
+        assert_eq!(history[1].height, Some(800_000));
+        assert_eq!(history[1].timestamp, Some(1_690_168_629));
+        assert_eq!(history[1].net, 50_000);
+        // The funding input is not in the history, so its fee is unknown
+        assert_eq!(history[1].fee, None);
+    }
+
+    #[tokio::test]
+    async fn test_fee_estimate() {
+        let client = connect(|method, params| match method {
+            "blockchain.estimatefee" => Ok(match params[0].as_u64().unwrap() {
+                1 => json!(0.0002),
+                6 => json!(0.00008),
+                _ => json!(-1),
+            }),
+            _ => panic!("unexpected {}", method),
+        })
+        .await;
+        let fees = *client.fee_estimate().await.unwrap().as_bitcoin().unwrap();
+        assert_eq!(fees.fast.as_sat_per_kvb(), 20_000);
+        assert_eq!(fees.normal.as_sat_per_kvb(), 8_000);
+        // No estimate for 144 blocks falls back to the 6-block rate
+        assert_eq!(fees.slow, fees.normal);
+
+        let client = connect(|method, _| match method {
+            "blockchain.estimatefee" => Ok(json!(-1)),
+            "blockchain.relayfee" => Ok(json!(0.00001)),
+            _ => panic!("unexpected {}", method),
+        })
+        .await;
+        let fees = *client.fee_estimate().await.unwrap().as_bitcoin().unwrap();
+        assert_eq!(fees.slow.as_sat_per_kvb(), 1_000);
+        assert_eq!(fees.fast.as_sat_per_kvb(), 1_000);
+    }
+
+    // ─── ElectrumMonitor ───
+
+    fn payment_to(script: &Script, value: u64, seed: u8) -> Transaction {
+        Transaction {
+            version: 2,
+            inputs: vec![TxIn::new(
+                OutPoint {
+                    txid: Txid::from_bytes([seed; 32]),
+                    vout: 0,
+                },
+                SEQUENCE_FINAL,
+            )],
+            outputs: vec![TxOut {
+                value,
+                script_pubkey: script.clone(),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor() {
+        let ours = address().as_bitcoin().unwrap().script_pubkey();
+        let hash = script_hash(&ours);
+        let old = payment_to(&ours, 10_000, 1);
+        let new = payment_to(&ours, 25_000, 2);
+        let spend = Transaction {
+            inputs: vec![TxIn::new(
+                OutPoint {
+                    txid: old.txid(),
+                    vout: 0,
+                },
+                SEQUENCE_FINAL,
+            )],
+            ..payment_to(&Script::p2wpkh(&[7; 20]), 9_000, 0)
+        };
+        let transactions: HashMap<String, String> = [&old, &new, &spend]
+            .into_iter()
+            .map(|tx| (tx.txid().to_string(), tx.to_hex()))
+            .collect();
+
+        let history = Arc::new(StdMutex::new(vec![json!({
+            "tx_hash": old.txid().to_string(),
+            "height": 800_000,
+        })]));
+        let server_history = history.clone();
+        let subscribed = hash.clone();
+        let (push, pushes) = mpsc::unbounded_channel();
+        let server = serve_pushing(
+            move |method, params| match method {
+                "server.version" => Ok(json!(["ElectrumX 1.16.0", PROTOCOL_VERSION])),
+                SCRIPTHASH_SUBSCRIBE => {
+                    assert_eq!(params[0], subscribed.as_str());
+                    Ok(json!("status-1"))
+                }
+                "blockchain.scripthash.get_history" => {
+                    Ok(Value::Array(server_history.lock().unwrap().clone()))
+                }
+                "blockchain.transaction.get" => {
+                    Ok(json!(transactions[params[0].as_str().unwrap()]))
+                }
+                "blockchain.block.header" => Ok(json!(hex::encode([0u8; 80]))),
+                _ => panic!("unexpected {}", method),
+            },
+            pushes,
+        )
+        .await;
+        let client = ElectrumClient::connect(&format!("tcp://{}", server), BitcoinNetwork::Bitcoin)
+            .await
+            .unwrap();
+        let mut monitor = ElectrumMonitor::new(client);
+        monitor.watch(&address()).await.unwrap();
+        monitor.watch(&address()).await.unwrap();
+
+        let notify = |status: &str| {
+            push.send(json!({
+                "jsonrpc": "2.0",
+                "method": SCRIPTHASH_SUBSCRIBE,
+                "params": [hash, status],
+            }))
+            .unwrap()
+        };
+        // A payment and a spend reach the mempool; only the payment is announced
+        history.lock().unwrap().extend([
+            json!({ "tx_hash": spend.txid().to_string(), "height": 0 }),
+            json!({ "tx_hash": new.txid().to_string(), "height": 0 }),
+        ]);
+        notify("status-2");
+        let received = Payment {
+            address: address(),
+            asset: PaymentAsset::Native,
+            amount: CoinAmount::from_sats(25_000),
+            txid: Some(new.txid().to_string()),
+            height: None,
+        };
+        assert_eq!(
+            monitor.next_event().await.unwrap(),
+            PaymentEvent::Received(received.clone())
+        );
+
+        // A repeated status is no change
+        notify("status-2");
+        *history.lock().unwrap() = vec![
+            json!({ "tx_hash": old.txid().to_string(), "height": 800_000 }),
+            json!({ "tx_hash": spend.txid().to_string(), "height": 800_001 }),
+            json!({ "tx_hash": new.txid().to_string(), "height": 800_001 }),
+        ];
+        notify("status-3");
+        assert_eq!(
+            monitor.next_event().await.unwrap(),
+            PaymentEvent::Confirmed(Payment {
+                height: Some(800_001),
+                ..received
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_monitor_rejects_other_networks() {
+        let client = connect(|method, _| panic!("unexpected {}", method)).await;
+        let mut monitor = ElectrumMonitor::new(client);
+        let testnet = Address::parse(
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+            Network::Bitcoin(BitcoinNetwork::Testnet),
+        )
+        .unwrap();
+        assert!(matches!(
+            monitor.watch(&testnet).await,
+            Err(Error::AddressError(_))
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+//! Error types for the chain client crate.
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// Errors that can occur while querying a blockchain backend.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The backend could not be reached or the connection failed mid-request.
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    /// The backend answered with an error, such as a rejected broadcast.
+    #[error("Server error: {0}")]
+    Server(String),
+
+    /// The backend answered with data this crate cannot interpret.
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// The backend cannot answer this kind of query.
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
+    /// The request budget of a [`RateLimiter`](crate::RateLimiter) is spent for
+    /// the current window.
+    #[error("Request quota exceeded: retry after {}s", retry_after.as_secs_f64().ceil())]
+    QuotaExceeded {
+        /// Time until the window ends and the budget is restored.
+        retry_after: Duration,
+    },
+
+    /// The address is malformed or belongs to another network.
+    #[error("Address error: {0}")]
+    AddressError(#[from] khodpay_address::Error),
+}
+
+/// Result type alias for chain client operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::Server("bad-txns-inputs-missingorspent".to_string()).to_string(),
+            "Server error: bad-txns-inputs-missingorspent"
+        );
+        let error = Error::from(khodpay_address::Error::InvalidAddress("empty".to_string()));
+        assert_eq!(error.to_string(), "Address error: Invalid address: empty");
+    }
+}
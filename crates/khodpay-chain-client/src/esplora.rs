@@ -0,0 +1,456 @@
+//! Bitcoin over an Esplora REST API (requires the `esplora` feature).
+//!
+//! Works with Blockstream's and mempool.space's public instances as well as a
+//! self-hosted `electrs` in Esplora mode.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_address::{Address, BitcoinNetwork};
+//! use khodpay_chain_client::esplora::EsploraClient;
+//! use khodpay_chain_client::ChainClient;
+//!
+//! # async fn run() -> khodpay_chain_client::Result<()> {
+//! let client = EsploraClient::new("https://blockstream.info/api", BitcoinNetwork::Bitcoin);
+//! let address = Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", client.network())?;
+//! for tx in client.history(&address).await? {
+//!     println!("{} {:+} sats", tx.txid, tx.net);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use khodpay_address::{Address, BitcoinNetwork, Network};
+use khodpay_amount::CoinAmount;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::bitcoin::{bitcoin_fees, script_pubkey};
+use crate::rate_limit::throttle;
+use crate::{Balance, ChainClient, Error, FeeEstimate, Proxy, RateLimiter, Result, TxSummary};
+
+/// Confirmed transactions per page of `/address/:address/txs/chain`.
+const CHAIN_PAGE_SIZE: usize = 25;
+
+#[derive(Deserialize)]
+struct AddressInfo {
+    chain_stats: Stats,
+    mempool_stats: Stats,
+}
+
+#[derive(Deserialize)]
+struct Stats {
+    funded_txo_sum: u64,
+    spent_txo_sum: u64,
+}
+
+impl Stats {
+    fn net(&self) -> i128 {
+        i128::from(self.funded_txo_sum) - i128::from(self.spent_txo_sum)
+    }
+}
+
+#[derive(Deserialize)]
+struct Tx {
+    txid: String,
+    vin: Vec<Vin>,
+    vout: Vec<Vout>,
+    fee: Option<u64>,
+    status: Status,
+}
+
+#[derive(Deserialize)]
+struct Vin {
+    prevout: Option<Vout>,
+}
+
+#[derive(Deserialize)]
+struct Vout {
+    scriptpubkey: String,
+    value: u64,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    confirmed: bool,
+    block_height: Option<u64>,
+    block_time: Option<u64>,
+}
+
+/// A [`ChainClient`] for Bitcoin over an Esplora REST API.
+#[derive(Debug, Clone)]
+pub struct EsploraClient {
+    base_url: String,
+    network: BitcoinNetwork,
+    http: reqwest::Client,
+    limiter: Option<RateLimiter>,
+}
+
+impl EsploraClient {
+    /// Creates a client for the API at `base_url`, such as
+    /// `https://blockstream.info/api`.
+    pub fn new(base_url: impl Into<String>, network: BitcoinNetwork) -> Self {
+        Self::with_http_client(base_url, network, reqwest::Client::new())
+    }
+
+    /// Creates a client that reuses an existing `reqwest::Client` (for custom
+    /// timeouts, proxies or headers).
+    pub fn with_http_client(
+        base_url: impl Into<String>,
+        network: BitcoinNetwork,
+        http: reqwest::Client,
+    ) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        Self {
+            base_url,
+            network,
+            http,
+            limiter: None,
+        }
+    }
+
+    /// Creates a client that sends every request through `proxy`, such as
+    /// [`Proxy::tor`] for an onion service `base_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if the proxy address is malformed.
+    pub fn with_proxy(
+        base_url: impl Into<String>,
+        network: BitcoinNetwork,
+        proxy: &Proxy,
+    ) -> Result<Self> {
+        Ok(Self::with_http_client(
+            base_url,
+            network,
+            proxy.http_client()?,
+        ))
+    }
+
+    /// Paces every request, including each page of a history, with `limiter`.
+    ///
+    /// Pass a clone of one [`RateLimiter`] to every client of the same server to
+    /// limit them together.
+    pub fn with_rate_limit(mut self, limiter: impl Into<RateLimiter>) -> Self {
+        self.limiter = Some(limiter.into());
+        self
+    }
+
+    /// Returns the API base URL.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        throttle(self.limiter.as_ref()).await?;
+        let response = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .send()
+            .await
+            .map_err(|e| Error::Transport(format!("GET {}: {}", path, e)))?;
+        let body = check_status(response).await?;
+        serde_json::from_str(&body)
+            .map_err(|e| Error::InvalidResponse(format!("GET {}: {}", path, e)))
+    }
+}
+
+#[async_trait]
+impl ChainClient for EsploraClient {
+    fn network(&self) -> Network {
+        Network::Bitcoin(self.network)
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        script_pubkey(address, self.network)?;
+        let info: AddressInfo = self.get(&format!("/address/{}", address)).await?;
+        let confirmed = u64::try_from(info.chain_stats.net())
+            .map_err(|_| Error::InvalidResponse("confirmed balance out of range".to_string()))?;
+        Ok(Balance {
+            confirmed: CoinAmount::from_sats(confirmed),
+            pending: info.mempool_stats.net(),
+        })
+    }
+
+    async fn history(&self, address: &Address) -> Result<Vec<TxSummary>> {
+        let script = hex::encode(script_pubkey(address, self.network)?.as_bytes());
+        // The first page holds the mempool and the newest confirmed transactions
+        let mut txs: Vec<Tx> = self.get(&format!("/address/{}/txs", address)).await?;
+        let mut last_page = txs.iter().filter(|tx| tx.status.confirmed).count();
+        while last_page >= CHAIN_PAGE_SIZE {
+            let Some(last) = txs.last() else { break };
+            let page: Vec<Tx> = self
+                .get(&format!("/address/{}/txs/chain/{}", address, last.txid))
+                .await?;
+            last_page = page.len();
+            txs.extend(page);
+        }
+        Ok(txs.into_iter().map(|tx| summarize(tx, &script)).collect())
+    }
+
+    async fn broadcast(&self, raw_tx: &[u8]) -> Result<String> {
+        throttle(self.limiter.as_ref()).await?;
+        let response = self
+            .http
+            .post(format!("{}/tx", self.base_url))
+            .body(hex::encode(raw_tx))
+            .send()
+            .await
+            .map_err(|e| Error::Transport(format!("POST /tx: {}", e)))?;
+        Ok(check_status(response).await?.trim().to_string())
+    }
+
+    async fn fee_estimate(&self) -> Result<FeeEstimate> {
+        let raw: BTreeMap<String, f64> = self.get("/fee-estimates").await?;
+        let estimates = raw
+            .into_iter()
+            .filter_map(|(target, rate)| Some((target.parse().ok()?, rate)))
+            .collect();
+        bitcoin_fees(&estimates).map(FeeEstimate::Bitcoin)
+    }
+}
+
+/// Returns the body of a successful response, or the server's error message.
+async fn check_status(response: reqwest::Response) -> Result<String> {
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| Error::Transport(e.to_string()))?;
+    if !status.is_success() {
+        return Err(Error::Server(format!("HTTP {}: {}", status, body.trim())));
+    }
+    Ok(body)
+}
+
+/// Summarizes `tx` from the point of view of the address paid by `script`.
+fn summarize(tx: Tx, script: &str) -> TxSummary {
+    let received: u64 = tx
+        .vout
+        .iter()
+        .filter(|output| output.scriptpubkey == script)
+        .map(|output| output.value)
+        .sum();
+    let sent: u64 = tx
+        .vin
+        .iter()
+        .filter_map(|input| input.prevout.as_ref())
+        .filter(|prevout| prevout.scriptpubkey == script)
+        .map(|prevout| prevout.value)
+        .sum();
+    TxSummary {
+        txid: tx.txid,
+        height: tx.status.block_height.filter(|_| tx.status.confirmed),
+        timestamp: tx.status.block_time.filter(|_| tx.status.confirmed),
+        net: i128::from(received) - i128::from(sent),
+        fee: tx.fee.map(CoinAmount::from_sats),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::testing::socks_server;
+    use crate::RateLimit;
+    use serde_json::json;
+    use wiremock::matchers::{body_string, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ADDRESS: &str = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+    const SCRIPT: &str = "0014c0cebcd6c3d3ca8c75dc5ec62ebe55330ef910e2";
+
+    fn address() -> Address {
+        Address::parse(ADDRESS, Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap()
+    }
+
+    fn tx(txid: &str, height: Option<u64>, received: u64, spent: u64) -> serde_json::Value {
+        json!({
+            "txid": txid,
+            "vin": [
+                { "prevout": { "scriptpubkey": SCRIPT, "value": spent } },
+                { "prevout": null },
+            ],
+            "vout": [
+                { "scriptpubkey": SCRIPT, "value": received },
+                { "scriptpubkey": "6a00", "value": 0 },
+            ],
+            "fee": 141,
+            "status": {
+                "confirmed": height.is_some(),
+                "block_height": height,
+                "block_time": height.map(|h| 1_700_000_000 + h),
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_balance() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/address/{}", ADDRESS)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "address": ADDRESS,
+                "chain_stats": { "funded_txo_sum": 150_000, "spent_txo_sum": 50_000, "tx_count": 3 },
+                "mempool_stats": { "funded_txo_sum": 0, "spent_txo_sum": 20_000, "tx_count": 1 },
+            })))
+            .mount(&server)
+            .await;
+
+        let client = EsploraClient::new(format!("{}/", server.uri()), BitcoinNetwork::Bitcoin);
+        let balance = client.balance(&address()).await.unwrap();
+        assert_eq!(balance.confirmed, CoinAmount::from_sats(100_000));
+        assert_eq!(balance.pending, -20_000);
+        assert_eq!(balance.total(), CoinAmount::from_sats(80_000));
+
+        // Wrong-network addresses never reach the server
+        let testnet = EsploraClient::new(server.uri(), BitcoinNetwork::Testnet);
+        assert!(matches!(
+            testnet.balance(&address()).await,
+            Err(Error::AddressError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_with_proxy() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/fee-estimates"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "1": 20.0, "6": 8.0, "144": 1.0 })),
+            )
+            .mount(&server)
+            .await;
+
+        let (proxy, connections) = socks_server(server.address().to_string()).await;
+        let client = EsploraClient::with_proxy(
+            "http://esplora.example.onion/api",
+            BitcoinNetwork::Bitcoin,
+            &Proxy::socks5(proxy),
+        )
+        .unwrap();
+        assert!(client.fee_estimate().await.is_ok());
+
+        // The host name is resolved by the proxy, not locally
+        assert_eq!(
+            *connections.lock().unwrap(),
+            [("esplora.example.onion:80".to_string(), None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fee-estimates"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "1": 2.0 })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let limiter = RateLimiter::new(
+            RateLimit::per_second(20).with_budget(2, std::time::Duration::from_secs(60)),
+        );
+        let client = EsploraClient::new(server.uri(), BitcoinNetwork::Bitcoin)
+            .with_rate_limit(limiter.clone());
+        let started = std::time::Instant::now();
+        assert!(client.fee_estimate().await.is_ok());
+        assert!(client.fee_estimate().await.is_ok());
+        assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+
+        // The budget is spent for every client sharing the limiter
+        let other =
+            EsploraClient::new(server.uri(), BitcoinNetwork::Bitcoin).with_rate_limit(limiter);
+        for client in [client, other] {
+            assert!(matches!(
+                client.fee_estimate().await,
+                Err(Error::QuotaExceeded { retry_after }) if retry_after.as_secs() >= 59
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_pages() {
+        let server = MockServer::start().await;
+        let mut first = vec![tx("mempool", None, 0, 30_000)];
+        first.extend((0..25).map(|i| tx(&format!("c{}", i), Some(900 - i), 1_000, 0)));
+        Mock::given(method("GET"))
+            .and(path(format!("/address/{}/txs", ADDRESS)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(first))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/address/{}/txs/chain/c24", ADDRESS)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![tx(
+                "oldest",
+                Some(100),
+                5_000,
+                0,
+            )]))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = EsploraClient::new(server.uri(), BitcoinNetwork::Bitcoin);
+        let history = client.history(&address()).await.unwrap();
+        assert_eq!(history.len(), 27);
+        assert_eq!(history[0].txid, "mempool");
+        assert!(!history[0].is_confirmed());
+        assert_eq!(history[0].net, -30_000);
+        assert_eq!(history[1].height, Some(900));
+        assert_eq!(history[1].timestamp, Some(1_700_000_900));
+        assert_eq!(history[26].net, 5_000);
+        assert_eq!(history[26].fee, Some(CoinAmount::from_sats(141)));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/tx"))
+            .and(body_string("0200"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ab".repeat(32)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/tx"))
+            .and(body_string("ff"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                "sendrawtransaction RPC error: {\"code\":-22,\"message\":\"TX decode failed\"}",
+            ))
+            .mount(&server)
+            .await;
+
+        let client = EsploraClient::new(server.uri(), BitcoinNetwork::Bitcoin);
+        assert_eq!(
+            client.broadcast(&[0x02, 0x00]).await.unwrap(),
+            "ab".repeat(32)
+        );
+        let error = client.broadcast(&[0xff]).await.unwrap_err();
+        assert!(
+            matches!(error, Error::Server(ref message) if message.contains("TX decode failed"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fee_estimate() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fee-estimates"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "1": 25.1, "2": 20.0, "6": 12.0, "144": 2.0, "1008": 1.0,
+            })))
+            .mount(&server)
+            .await;
+
+        let client = EsploraClient::new(server.uri(), BitcoinNetwork::Bitcoin);
+        let estimate = client.fee_estimate().await.unwrap();
+        let fees = estimate.as_bitcoin().unwrap();
+        assert_eq!(fees.fast.as_sat_per_kvb(), 25_100);
+        assert_eq!(fees.normal.as_sat_per_kvb(), 12_000);
+        assert_eq!(fees.slow.as_sat_per_kvb(), 2_000);
+    }
+}
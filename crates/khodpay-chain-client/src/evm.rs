@@ -0,0 +1,920 @@
+//! EVM chains over Ethereum JSON-RPC (requires the `evm` feature).
+//!
+//! Wraps the [`RpcClient`] from `khodpay-signing`, so provider pools, custom HTTP
+//! clients and everything else configured there carry over.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_address::Address;
+//! use khodpay_chain_client::evm::EvmClient;
+//! use khodpay_chain_client::{ChainClient, FeeEstimate};
+//! use khodpay_signing::rpc::RpcClient;
+//!
+//! # async fn run() -> khodpay_chain_client::Result<()> {
+//! let client = EvmClient::new(RpcClient::new("https://bsc-dataseed.binance.org"));
+//! let address = Address::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", client.network())?;
+//! println!("{} BNB", client.balance(&address).await?.total());
+//!
+//! if let FeeEstimate::Evm(fees) = client.fee_estimate().await? {
+//!     println!("fast max fee: {}", fees.fast.max_fee_per_gas);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use khodpay_address::{Address, Network};
+use khodpay_signing::fee::{FeeEstimator, FeeSuggestions};
+use khodpay_signing::rpc::{BlockId, RpcClient};
+use khodpay_signing::Wei;
+
+use crate::client::check_network;
+use crate::rate_limit::throttle;
+use crate::{Balance, ChainClient, Error, FeeEstimate, Proxy, RateLimiter, Result, TxSummary};
+
+/// Blocks of `eth_feeHistory` fee estimates are computed from.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// A [`ChainClient`] for an EVM chain over JSON-RPC.
+///
+/// Plain JSON-RPC nodes keep no index of transactions by address, so
+/// [`ChainClient::history`] returns [`Error::Unsupported`]; use a block explorer
+/// API or scan logs for that.
+#[derive(Debug, Clone)]
+pub struct EvmClient {
+    rpc: RpcClient,
+    estimator: FeeEstimator,
+    limiter: Option<RateLimiter>,
+}
+
+impl EvmClient {
+    /// Creates a client over `rpc` with the default [`FeeEstimator`].
+    pub fn new(rpc: RpcClient) -> Self {
+        Self {
+            rpc,
+            estimator: FeeEstimator::default(),
+            limiter: None,
+        }
+    }
+
+    /// Creates a client for the node at `url` that sends every request through
+    /// `proxy`, such as [`Proxy::tor`].
+    ///
+    /// For a provider pool, build the [`RpcClient`] with
+    /// [`Proxy::http_client`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if the proxy address is malformed.
+    pub fn with_proxy(url: impl Into<String>, proxy: &Proxy) -> Result<Self> {
+        Ok(Self::new(RpcClient::with_http_client(
+            url,
+            proxy.http_client()?,
+        )))
+    }
+
+    /// Sets the estimator used by [`ChainClient::fee_estimate`].
+    pub fn with_fee_estimator(mut self, estimator: FeeEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Paces every JSON-RPC call made through [`ChainClient`] with `limiter`.
+    ///
+    /// Calls made directly on [`rpc`](Self::rpc) are not limited.
+    pub fn with_rate_limit(mut self, limiter: impl Into<RateLimiter>) -> Self {
+        self.limiter = Some(limiter.into());
+        self
+    }
+
+    /// Returns the underlying JSON-RPC client, for calls outside [`ChainClient`].
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+}
+
+#[async_trait]
+impl ChainClient for EvmClient {
+    fn network(&self) -> Network {
+        Network::Evm
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        check_network(address, Network::Evm)?;
+        let Some(&address) = address.as_evm() else {
+            return Err(Error::Unsupported(format!(
+                "{} is not an EVM address",
+                address
+            )));
+        };
+        throttle(self.limiter.as_ref()).await?;
+        let confirmed = self
+            .rpc
+            .get_balance(address, BlockId::Latest)
+            .await
+            .map_err(rpc_error)?;
+        throttle(self.limiter.as_ref()).await?;
+        let pending = wei_to_u128(
+            self.rpc
+                .get_balance(address, BlockId::Pending)
+                .await
+                .map_err(rpc_error)?,
+        )?;
+        let pending = i128::try_from(pending)
+            .ok()
+            .zip(i128::try_from(wei_to_u128(confirmed)?).ok())
+            .map(|(pending, confirmed)| pending - confirmed)
+            .ok_or_else(|| Error::InvalidResponse("balance exceeds i128".to_string()))?;
+        Ok(Balance {
+            confirmed: confirmed.into(),
+            pending,
+        })
+    }
+
+    async fn history(&self, _address: &Address) -> Result<Vec<TxSummary>> {
+        Err(Error::Unsupported(
+            "JSON-RPC nodes keep no per-address transaction index".to_string(),
+        ))
+    }
+
+    async fn broadcast(&self, raw_tx: &[u8]) -> Result<String> {
+        throttle(self.limiter.as_ref()).await?;
+        let hash = self
+            .rpc
+            .send_raw_transaction(raw_tx)
+            .await
+            .map_err(rpc_error)?;
+        Ok(format!("0x{}", hex::encode(hash)))
+    }
+
+    async fn fee_estimate(&self) -> Result<FeeEstimate> {
+        throttle(self.limiter.as_ref()).await?;
+        let history = self
+            .rpc
+            .fee_history(
+                FEE_HISTORY_BLOCKS,
+                BlockId::Latest,
+                &self.estimator.reward_percentiles(),
+            )
+            .await;
+        let suggestions = match history {
+            Ok(history) => self.estimator.estimate(&history).map_err(rpc_error)?,
+            // Pre-London chains and some nodes lack eth_feeHistory
+            Err(khodpay_signing::Error::JsonRpcError { .. }) => {
+                throttle(self.limiter.as_ref()).await?;
+                FeeSuggestions::from_gas_price(self.rpc.gas_price().await.map_err(rpc_error)?)
+            }
+            Err(error) => return Err(rpc_error(error)),
+        };
+        Ok(FeeEstimate::Evm(suggestions))
+    }
+}
+
+#[cfg(feature = "ws")]
+pub use ws::EvmMonitor;
+
+#[cfg(feature = "ws")]
+mod ws {
+    use std::collections::{BTreeSet, HashMap, VecDeque};
+
+    use async_trait::async_trait;
+    use khodpay_address::{Address, Network};
+    use khodpay_amount::CoinAmount;
+    use khodpay_signing::rpc::{
+        BlockHeader, Log, LogFilter, Subscription, WsClient, TRANSFER_TOPIC,
+    };
+    use khodpay_signing::{erc20, Wei, U256};
+    use serde_json::json;
+
+    use super::rpc_error;
+    use crate::client::check_network;
+    use crate::monitor::{Payment, PaymentAsset, PaymentEvent, PaymentMonitor};
+    use crate::{Error, Result};
+
+    /// Blocks for which announced transfers are remembered, to drop the duplicates
+    /// delivered while the log subscription is replaced.
+    const DEDUP_BLOCKS: u64 = 64;
+
+    /// A [`PaymentMonitor`] for an EVM chain over a WebSocket connection (requires
+    /// the `ws` feature).
+    ///
+    /// Native coin is detected by comparing each watched address's balance at every
+    /// new block with the one before, so a block's payments are announced together,
+    /// net of what the address sent, and without a transaction id. ERC-20 tokens are
+    /// detected from their `Transfer` logs, one event per transfer, in the decimals the
+    /// token contract reports; transfers of contracts without `decimals()` are dropped.
+    ///
+    /// Payments are announced once they are in a block and never as
+    /// [`PaymentEvent::Confirmed`]; count confirmations from [`Payment::height`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use khodpay_address::{Address, Network};
+    /// use khodpay_chain_client::evm::EvmMonitor;
+    /// use khodpay_chain_client::monitor::PaymentMonitor;
+    /// use khodpay_signing::rpc::WsClient;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = WsClient::connect("wss://bsc-rpc.publicnode.com").await?;
+    /// let mut monitor = EvmMonitor::new(client).await?;
+    /// let address = Address::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", Network::Evm)?;
+    /// monitor.watch(&address).await?;
+    /// let event = monitor.next_event().await?;
+    /// println!("{:?}: {}", event.payment().asset, event.payment().amount);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[derive(Debug)]
+    pub struct EvmMonitor {
+        client: WsClient,
+        heads: Subscription<BlockHeader>,
+        logs: Option<Subscription<Log>>,
+        /// Watched addresses and their native balance at the last block checked.
+        balances: HashMap<khodpay_signing::Address, (Address, Wei)>,
+        /// Newest block whose balances are not yet checked.
+        unchecked: Option<u64>,
+        /// Transfers announced in recent blocks: block, transaction hash, log index.
+        announced: BTreeSet<(u64, [u8; 32], u64)>,
+        /// Token transfers waiting for their token's decimals.
+        transfers: VecDeque<TokenTransfer>,
+        /// Decimals of the tokens seen so far, `None` for contracts without them.
+        decimals: HashMap<khodpay_signing::Address, Option<u8>>,
+        events: VecDeque<PaymentEvent>,
+    }
+
+    /// An ERC-20 transfer to a watched address, in the token's base units.
+    #[derive(Debug)]
+    struct TokenTransfer {
+        address: Address,
+        token: khodpay_signing::Address,
+        value: U256,
+        txid: String,
+        height: u64,
+    }
+
+    impl EvmMonitor {
+        /// Creates a monitor over `client` and subscribes to new blocks.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the node refuses the subscription.
+        pub async fn new(client: WsClient) -> Result<Self> {
+            let heads = client.subscribe_new_heads().await.map_err(rpc_error)?;
+            Ok(Self {
+                client,
+                heads,
+                logs: None,
+                balances: HashMap::new(),
+                unchecked: None,
+                announced: BTreeSet::new(),
+                transfers: VecDeque::new(),
+                decimals: HashMap::new(),
+                events: VecDeque::new(),
+            })
+        }
+
+        /// Returns the native balance of `address` at `block` (a number or tag).
+        async fn balance_at(&self, address: &khodpay_signing::Address, block: &str) -> Result<Wei> {
+            let balance: String = self
+                .client
+                .request(
+                    "eth_getBalance",
+                    json!([address.to_checksum_string(), block]),
+                )
+                .await
+                .map_err(rpc_error)?;
+            let digits = balance.strip_prefix("0x").unwrap_or(&balance);
+            let wei = khodpay_signing::U256::from_str_radix(digits, 16)
+                .map_err(|e| Error::InvalidResponse(format!("balance {}: {}", balance, e)))?;
+            Ok(Wei::from_wei(wei))
+        }
+
+        /// Returns the decimals of `token`, asking the contract the first time, or
+        /// `None` if it has no `decimals()`.
+        async fn token_decimals(&mut self, token: khodpay_signing::Address) -> Result<Option<u8>> {
+            if let Some(&decimals) = self.decimals.get(&token) {
+                return Ok(decimals);
+            }
+            let call = json!({
+                "to": token.to_checksum_string(),
+                "data": format!("0x{}", hex::encode(erc20::encode_decimals())),
+            });
+            let decimals = match self
+                .client
+                .request::<String>("eth_call", json!([call, "latest"]))
+                .await
+            {
+                Ok(data) => hex::decode(data.strip_prefix("0x").unwrap_or(&data))
+                    .ok()
+                    .and_then(|data| erc20::decode_decimals(&data).ok()),
+                // The call reverted
+                Err(khodpay_signing::Error::JsonRpcError { .. }) => None,
+                Err(error) => return Err(rpc_error(error)),
+            };
+            self.decimals.insert(token, decimals);
+            Ok(decimals)
+        }
+
+        /// Compares every watched balance at `block` with the last one seen.
+        async fn check_balances(&mut self, block: u64) -> Result<()> {
+            let tag = format!("0x{:x}", block);
+            let addresses: Vec<_> = self.balances.keys().copied().collect();
+            for evm in addresses {
+                let balance = self.balance_at(&evm, &tag).await?;
+                let (address, last) = self.balances.get_mut(&evm).expect("watched address");
+                if balance > *last {
+                    self.events.push_back(PaymentEvent::Received(Payment {
+                        address: address.clone(),
+                        asset: PaymentAsset::Native,
+                        amount: (balance - *last).into(),
+                        txid: None,
+                        height: Some(block),
+                    }));
+                }
+                *last = balance;
+            }
+            Ok(())
+        }
+
+        /// Queues the payment in a `Transfer` log, if it is an ERC-20 transfer to a
+        /// watched address not announced yet.
+        fn transfer(&mut self, log: Log) {
+            // ERC-721 transfers index the token id and carry no data
+            if log.removed || log.topics.len() != 3 || log.data.len() != 32 {
+                return;
+            }
+            let Some(recipient) = self.balances.keys().copied().find(|evm| {
+                log.topics[2][..12] == [0; 12] && log.topics[2][12..] == evm.as_bytes()[..]
+            }) else {
+                return;
+            };
+            let (Some(height), Some(hash), Some(index)) =
+                (log.block_number, log.transaction_hash, log.log_index)
+            else {
+                return;
+            };
+            if !self.announced.insert((height, hash, index)) {
+                return;
+            }
+            self.transfers.push_back(TokenTransfer {
+                address: self.balances[&recipient].0.clone(),
+                token: log.address,
+                value: U256::from_big_endian(&log.data),
+                txid: format!("0x{}", hex::encode(hash)),
+                height,
+            });
+        }
+    }
+
+    #[async_trait]
+    impl PaymentMonitor for EvmMonitor {
+        fn network(&self) -> Network {
+            Network::Evm
+        }
+
+        async fn watch(&mut self, address: &Address) -> Result<()> {
+            check_network(address, Network::Evm)?;
+            let Some(&evm) = address.as_evm() else {
+                return Err(Error::Unsupported(format!(
+                    "{} is not an EVM address",
+                    address
+                )));
+            };
+            if self.balances.contains_key(&evm) {
+                return Ok(());
+            }
+            let balance = self.balance_at(&evm, "latest").await?;
+
+            let filter = self
+                .balances
+                .keys()
+                .chain([&evm])
+                .fold(LogFilter::new().topic(0, TRANSFER_TOPIC), |filter, evm| {
+                    filter.topic(2, address_topic(evm))
+                });
+            let logs = self
+                .client
+                .subscribe_logs(&filter)
+                .await
+                .map_err(rpc_error)?;
+            self.balances.insert(evm, (address.clone(), balance));
+
+            // The old subscription may hold logs not read yet; the new one already
+            // covers anything arriving later
+            if let Some(mut old) = self.logs.replace(logs) {
+                loop {
+                    tokio::select! {
+                        biased;
+                        log = old.next() => match log {
+                            Some(Ok(log)) => self.transfer(log),
+                            Some(Err(_)) => {}
+                            None => break,
+                        },
+                        _ = std::future::ready(()) => break,
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        async fn next_event(&mut self) -> Result<PaymentEvent> {
+            loop {
+                if let Some(event) = self.events.pop_front() {
+                    return Ok(event);
+                }
+                // Removed only once the decimals are known, so a cancelled lookup is redone
+                if let Some(token) = self.transfers.front().map(|transfer| transfer.token) {
+                    let decimals = self.token_decimals(token).await?;
+                    let transfer = self.transfers.pop_front().expect("queued transfer");
+                    if let Some(decimals) = decimals {
+                        return Ok(PaymentEvent::Received(Payment {
+                            address: transfer.address,
+                            asset: PaymentAsset::Token(transfer.token),
+                            amount: CoinAmount::new(transfer.value, decimals),
+                            txid: Some(transfer.txid),
+                            height: Some(transfer.height),
+                        }));
+                    }
+                    continue;
+                }
+                // Cleared only once checked, so a cancelled check is redone
+                if let Some(block) = self.unchecked {
+                    self.check_balances(block).await?;
+                    self.unchecked = None;
+                    continue;
+                }
+                tokio::select! {
+                    head = self.heads.next() => {
+                        let head = head.ok_or_else(closed)?.map_err(rpc_error)?;
+                        self.announced
+                            .retain(|(height, _, _)| height + DEDUP_BLOCKS > head.number);
+                        if !self.balances.is_empty() {
+                            self.unchecked = Some(head.number);
+                        }
+                    }
+                    log = next_log(&mut self.logs) => {
+                        self.transfer(log.ok_or_else(closed)?.map_err(rpc_error)?);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Waits for the next log, forever if there is no subscription yet.
+    async fn next_log(
+        logs: &mut Option<Subscription<Log>>,
+    ) -> Option<khodpay_signing::Result<Log>> {
+        match logs {
+            Some(logs) => logs.next().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn address_topic(address: &khodpay_signing::Address) -> [u8; 32] {
+        let mut topic = [0; 32];
+        topic[12..].copy_from_slice(address.as_bytes());
+        topic
+    }
+
+    fn closed() -> Error {
+        Error::Transport("WebSocket connection closed".to_string())
+    }
+}
+
+pub(crate) fn rpc_error(error: khodpay_signing::Error) -> Error {
+    match error {
+        khodpay_signing::Error::RpcError(message) => Error::Transport(message),
+        khodpay_signing::Error::JsonRpcError { code, message } => {
+            Error::Server(format!("{} (code {})", message, code))
+        }
+        other => Error::InvalidResponse(other.to_string()),
+    }
+}
+
+fn wei_to_u128(wei: Wei) -> Result<u128> {
+    wei.as_u128()
+        .ok_or_else(|| Error::InvalidResponse(format!("balance {} exceeds u128", wei)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::testing::socks_server;
+    use crate::RateLimit;
+    use khodpay_signing::fee::FeeSpeed;
+    use serde_json::{json, Value};
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    async fn mock_result(server: &MockServer, body: Value, result: Value) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(body))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+            )
+            .mount(server)
+            .await;
+    }
+
+    async fn mock_error(server: &MockServer, rpc_method: &str, code: i64, message: &str) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": rpc_method })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": code, "message": message },
+            })))
+            .mount(server)
+            .await;
+    }
+
+    fn client(server: &MockServer) -> EvmClient {
+        EvmClient::new(RpcClient::new(server.uri()))
+    }
+
+    fn address() -> Address {
+        Address::parse(ADDRESS, Network::Evm).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_balance() {
+        let server = MockServer::start().await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_getBalance", "params": [ADDRESS, "latest"] }),
+            json!("0xde0b6b3a7640000"),
+        )
+        .await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_getBalance", "params": [ADDRESS, "pending"] }),
+            json!("0x6f05b59d3b20000"),
+        )
+        .await;
+
+        let balance = client(&server).balance(&address()).await.unwrap();
+        assert_eq!(balance.confirmed, Wei::from_ether(1).into());
+        assert_eq!(balance.pending, -500_000_000_000_000_000);
+
+        let bitcoin = Address::parse(
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            Network::Bitcoin(khodpay_address::BitcoinNetwork::Bitcoin),
+        )
+        .unwrap();
+        assert!(matches!(
+            client(&server).balance(&bitcoin).await,
+            Err(Error::AddressError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_with_proxy() {
+        let server = MockServer::start().await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_getBalance", "params": [ADDRESS, "latest"] }),
+            json!("0x1"),
+        )
+        .await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_getBalance", "params": [ADDRESS, "pending"] }),
+            json!("0x1"),
+        )
+        .await;
+
+        let (proxy, connections) = socks_server(server.address().to_string()).await;
+        let proxy = Proxy::socks5(proxy).with_credentials("evm", "isolation");
+        let client = EvmClient::with_proxy("http://rpc.example.onion:8545", &proxy).unwrap();
+        assert_eq!(
+            client.balance(&address()).await.unwrap().total(),
+            Wei::from_wei(1u64).into()
+        );
+        // Both requests may share one pooled connection
+        let connections = connections.lock().unwrap();
+        assert!(!connections.is_empty());
+        for (target, username) in connections.iter() {
+            assert_eq!(target, "rpc.example.onion:8545");
+            assert_eq!(username.as_deref(), Some("evm"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let server = MockServer::start().await;
+        mock_result(&server, json!({ "method": "eth_getBalance" }), json!("0x1")).await;
+
+        // A balance takes two requests, so a budget of three covers only one
+        let limiter = RateLimiter::new(
+            RateLimit::per_second(1000).with_budget(3, std::time::Duration::from_secs(60)),
+        );
+        let client = client(&server).with_rate_limit(limiter.clone());
+        assert!(client.balance(&address()).await.is_ok());
+        assert_eq!(limiter.remaining_budget(), Some(1));
+        assert!(matches!(
+            client.balance(&address()).await,
+            Err(Error::QuotaExceeded { .. })
+        ));
+        // The last request of the budget went out before the second one failed
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_history_unsupported() {
+        let server = MockServer::start().await;
+        assert!(matches!(
+            client(&server).history(&address()).await,
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast() {
+        let server = MockServer::start().await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_sendRawTransaction", "params": ["0x02f8"] }),
+            json!(format!("0x{}", "ab".repeat(32))),
+        )
+        .await;
+        mock_error(&server, "eth_sendRawTransaction", -32000, "nonce too low").await;
+
+        let client = client(&server);
+        assert_eq!(
+            client.broadcast(&[0x02, 0xf8]).await.unwrap(),
+            format!("0x{}", "ab".repeat(32))
+        );
+        let error = client.broadcast(&[0x02]).await.unwrap_err();
+        assert!(matches!(error, Error::Server(ref message) if message.contains("nonce too low")));
+    }
+
+    #[tokio::test]
+    async fn test_fee_estimate() {
+        let server = MockServer::start().await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_feeHistory" }),
+            json!({
+                "oldestBlock": "0x10",
+                "baseFeePerGas": ["0x3b9aca00", "0x77359400"],
+                "gasUsedRatio": [0.5],
+                "reward": [["0x1", "0x2", "0x3"]],
+            }),
+        )
+        .await;
+
+        let estimate = client(&server).fee_estimate().await.unwrap();
+        let fees = estimate.as_evm().unwrap();
+        assert_eq!(fees.base_fee, Wei::from_gwei(2));
+        assert!(
+            fees.get(FeeSpeed::Fast).max_priority_fee_per_gas
+                >= fees.get(FeeSpeed::Slow).max_priority_fee_per_gas
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fee_estimate_falls_back_to_gas_price() {
+        let server = MockServer::start().await;
+        mock_error(&server, "eth_feeHistory", -32601, "method not found").await;
+        mock_result(
+            &server,
+            json!({ "method": "eth_gasPrice" }),
+            json!("0x12a05f200"),
+        )
+        .await;
+
+        let estimate = client(&server).fee_estimate().await.unwrap();
+        assert_eq!(
+            estimate.as_evm().unwrap(),
+            &FeeSuggestions::from_gas_price(Wei::from_gwei(5))
+        );
+    }
+
+    #[cfg(feature = "ws")]
+    mod ws {
+        use super::*;
+        use crate::monitor::{Payment, PaymentAsset, PaymentEvent, PaymentMonitor};
+        use crate::CoinAmount;
+        use futures_util::{SinkExt, StreamExt};
+        use khodpay_signing::rpc::{WsClient, TRANSFER_TOPIC};
+        use std::collections::HashMap;
+        use tokio::net::TcpListener;
+        use tokio::sync::mpsc;
+        use tokio_tungstenite::tungstenite::Message;
+
+        /// Serves one WebSocket connection: subscriptions are `heads` and `logs<n>`,
+        /// balances come from `balances` by block, and `pushes` are sent as
+        /// `(subscription, result)` notifications.
+        async fn serve(
+            balances: HashMap<&'static str, &'static str>,
+            mut pushes: mpsc::UnboundedReceiver<(&'static str, Value)>,
+        ) -> String {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("ws://{}", listener.local_addr().unwrap());
+            tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let mut log_subscriptions = 0;
+                loop {
+                    let reply = tokio::select! {
+                        message = socket.next() => {
+                            let Some(Ok(Message::Text(text))) = message else {
+                                break;
+                            };
+                            let request: Value = serde_json::from_str(&text).unwrap();
+                            let params = &request["params"];
+                            let result = match request["method"].as_str().unwrap() {
+                                "eth_subscribe" if params[0] == "newHeads" => json!("heads"),
+                                "eth_subscribe" => {
+                                    assert_eq!(params[0], "logs");
+                                    assert_eq!(
+                                        params[1]["topics"][0],
+                                        format!("0x{}", hex::encode(TRANSFER_TOPIC))
+                                    );
+                                    log_subscriptions += 1;
+                                    json!(format!("logs{}", log_subscriptions))
+                                }
+                                "eth_unsubscribe" => json!(true),
+                                // Tokens have 6 decimals, except `[0x66; 20]` which has no code
+                                "eth_call" => {
+                                    assert_eq!(params[0]["data"], "0x313ce567");
+                                    if params[0]["to"] == junk().to_checksum_string() {
+                                        json!("0x")
+                                    } else {
+                                        json!(format!("0x{:064x}", 6))
+                                    }
+                                }
+                                "eth_getBalance" => {
+                                    assert_eq!(params[0], ADDRESS);
+                                    json!(balances[params[1].as_str().unwrap()])
+                                }
+                                method => panic!("unexpected {}", method),
+                            };
+                            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                        }
+                        Some((subscription, result)) = pushes.recv() => json!({
+                            "jsonrpc": "2.0",
+                            "method": "eth_subscription",
+                            "params": { "subscription": subscription, "result": result },
+                        }),
+                    };
+                    socket.send(Message::Text(reply.to_string())).await.unwrap();
+                }
+            });
+            url
+        }
+
+        fn junk() -> khodpay_signing::Address {
+            khodpay_signing::Address::from_bytes([0x66; 20])
+        }
+
+        fn head(number: u64) -> Value {
+            json!({
+                "number": format!("0x{:x}", number),
+                "hash": format!("0x{}", hex::encode([1; 32])),
+                "parentHash": format!("0x{}", hex::encode([2; 32])),
+                "timestamp": "0x64b6c1f5",
+            })
+        }
+
+        fn transfer(token: &khodpay_signing::Address, amount: u8, index: u64) -> Value {
+            let recipient = format!(
+                "0x{:0>64}",
+                hex::encode(address().as_evm().unwrap().as_bytes())
+            );
+            json!({
+                "address": token.to_checksum_string(),
+                "topics": [
+                    format!("0x{}", hex::encode(TRANSFER_TOPIC)),
+                    format!("0x{}", hex::encode([0x22; 32])),
+                    recipient,
+                ],
+                "data": format!("0x{:064x}", amount),
+                "logIndex": format!("0x{:x}", index),
+                "blockNumber": "0x10",
+                "transactionHash": format!("0x{}", hex::encode([3; 32])),
+            })
+        }
+
+        #[tokio::test]
+        async fn test_monitor() {
+            let balances =
+                HashMap::from([("latest", "0x3e8"), ("0x10", "0x5dc"), ("0x11", "0x578")]);
+            let (push, pushes) = mpsc::unbounded_channel();
+            let url = serve(balances, pushes).await;
+            let client = WsClient::connect(&url).await.unwrap();
+            let mut monitor = EvmMonitor::new(client).await.unwrap();
+            monitor.watch(&address()).await.unwrap();
+            monitor.watch(&address()).await.unwrap();
+
+            push.send(("heads", head(0x10))).unwrap();
+            assert_eq!(
+                monitor.next_event().await.unwrap(),
+                PaymentEvent::Received(Payment {
+                    address: address(),
+                    asset: PaymentAsset::Native,
+                    amount: Wei::from_wei(500u64).into(),
+                    txid: None,
+                    height: Some(0x10),
+                })
+            );
+
+            let token = khodpay_signing::Address::from_bytes([0x55; 20]);
+            push.send(("logs1", transfer(&token, 42, 1))).unwrap();
+            let received = Payment {
+                address: address(),
+                asset: PaymentAsset::Token(token),
+                amount: CoinAmount::new(42, 6),
+                txid: Some(format!("0x{}", hex::encode([3; 32]))),
+                height: Some(0x10),
+            };
+            assert_eq!(
+                monitor.next_event().await.unwrap(),
+                PaymentEvent::Received(received.clone())
+            );
+
+            // Duplicates, removed logs, NFT transfers, contracts without decimals and a
+            // falling balance are quiet
+            push.send(("logs1", transfer(&token, 42, 1))).unwrap();
+            let mut removed = transfer(&token, 9, 2);
+            removed["removed"] = json!(true);
+            push.send(("logs1", removed)).unwrap();
+            let mut nft = transfer(&token, 0, 3);
+            nft["topics"]
+                .as_array_mut()
+                .unwrap()
+                .push(json!(format!("0x{:064x}", 1)));
+            nft["data"] = json!("0x");
+            push.send(("logs1", nft)).unwrap();
+            push.send(("logs1", transfer(&junk(), 5, 5))).unwrap();
+            push.send(("heads", head(0x11))).unwrap();
+            push.send(("logs1", transfer(&token, 7, 6))).unwrap();
+            assert_eq!(
+                monitor.next_event().await.unwrap(),
+                PaymentEvent::Received(Payment {
+                    amount: CoinAmount::new(7, 6),
+                    ..received
+                })
+            );
+        }
+
+        #[tokio::test]
+        async fn test_monitor_rejects_bitcoin_addresses() {
+            let (_push, pushes) = mpsc::unbounded_channel();
+            let url = serve(HashMap::new(), pushes).await;
+            let client = WsClient::connect(&url).await.unwrap();
+            let mut monitor = EvmMonitor::new(client).await.unwrap();
+            let bitcoin = Address::parse(
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                Network::Bitcoin(khodpay_address::BitcoinNetwork::Bitcoin),
+            )
+            .unwrap();
+            assert!(matches!(
+                monitor.watch(&bitcoin).await,
+                Err(Error::AddressError(_))
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_monitor_with_proxy() {
+            let balances = HashMap::from([("latest", "0x3e8"), ("0x10", "0x5dc")]);
+            let (push, pushes) = mpsc::unbounded_channel();
+            let url = serve(balances, pushes).await;
+            let (proxy, connections) = socks_server(url.replace("ws://", "")).await;
+            let proxy = Proxy::socks5(proxy).with_credentials("ws", "isolation");
+
+            let client = proxy
+                .ws_client("ws://rpc.example.onion:8546/ws")
+                .await
+                .unwrap();
+            let mut monitor = EvmMonitor::new(client).await.unwrap();
+            monitor.watch(&address()).await.unwrap();
+            push.send(("heads", head(0x10))).unwrap();
+            assert_eq!(
+                monitor.next_event().await.unwrap().payment().amount,
+                Wei::from_wei(500u64).into()
+            );
+            assert_eq!(
+                *connections.lock().unwrap(),
+                [("rpc.example.onion:8546".to_string(), Some("ws".to_string()))]
+            );
+
+            for url in [
+                "http://rpc.example.onion",
+                "ws://",
+                "wss://node.example:port",
+            ] {
+                assert!(
+                    matches!(proxy.ws_client(url).await, Err(Error::Transport(_))),
+                    "{}",
+                    url
+                );
+            }
+        }
+    }
+}
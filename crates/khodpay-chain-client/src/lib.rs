@@ -0,0 +1,97 @@
+//! # Khodpay Chain Client
+//!
+//! One [`ChainClient`] trait for the queries every wallet screen needs (balance,
+//! history, broadcast and fee estimates), with Bitcoin backends over Esplora and
+//! Electrum and an EVM backend over JSON-RPC.
+//!
+//! Addresses come in as `khodpay-address` [`Address`](khodpay_address::Address)
+//! values and are checked against the client's network before any request is sent.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`ChainClient`] | Balance, history, broadcast and fee estimates for one network |
+//! | [`esplora`] | Bitcoin over an Esplora REST API (feature `esplora`) |
+//! | [`electrum`] | Bitcoin over the Electrum protocol, TCP or TLS (feature `electrum`) |
+//! | [`evm`] | EVM chains over Ethereum JSON-RPC (feature `evm`) |
+//! | [`monitor`] | Incoming payment events over Electrum and WebSocket subscriptions |
+//! | [`Proxy`] | A SOCKS5 proxy, such as Tor, for any backend |
+//! | [`RateLimiter`] | Request pacing and quotas per endpoint, for any backend |
+//! | [`Balance`] | Confirmed and pending balance, as a [`CoinAmount`] with the coin's decimals |
+//! | [`TxSummary`] | A transaction's net effect on an address |
+//! | [`FeeEstimate`] | Slow / normal / fast fees in the chain's own form |
+//!
+//! ## Features
+//!
+//! - **Chain Agnostic**: Hold one `Box<dyn ChainClient>` per network and query them
+//!   the same way
+//! - **Network Checks**: Addresses for another network are rejected before any
+//!   request goes out
+//! - **Net Amounts**: History entries carry what the address gained or lost, not raw
+//!   inputs and outputs
+//! - **Fee Estimates**: Bitcoin fee rates and EIP-1559 fee pairs for the same three
+//!   speeds
+//! - **Payment Monitoring**: Typed events for payments to watched addresses as the
+//!   server pushes them, instead of polling balances
+//! - **Tor Support**: Route any backend through a SOCKS5 proxy, with remote DNS and
+//!   `.onion` servers
+//! - **Rate Limits**: Pace each endpoint's requests with a token bucket and cap them
+//!   with a request budget, so public servers don't ban the wallet's IP address
+//! - **Optional Backends**: Each backend sits behind a Cargo feature, all on by default
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use khodpay_address::{Address, BitcoinNetwork};
+//! use khodpay_chain_client::esplora::EsploraClient;
+//! use khodpay_chain_client::evm::EvmClient;
+//! use khodpay_chain_client::ChainClient;
+//! use khodpay_signing::fee::FeeSpeed;
+//! use khodpay_signing::rpc::RpcClient;
+//!
+//! # async fn run() -> khodpay_chain_client::Result<()> {
+//! let clients: Vec<Box<dyn ChainClient>> = vec![
+//!     Box::new(EsploraClient::new("https://blockstream.info/api", BitcoinNetwork::Bitcoin)),
+//!     Box::new(EvmClient::new(RpcClient::new("https://bsc-dataseed.binance.org"))),
+//! ];
+//!
+//! let bitcoin = Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", clients[0].network())?;
+//! println!("{} BTC", clients[0].balance(&bitcoin).await?.total());
+//!
+//! let fees = clients[0].fee_estimate().await?;
+//! println!("fast: {}", fees.as_bitcoin().unwrap().get(FeeSpeed::Fast));
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+#[cfg(any(feature = "esplora", feature = "electrum"))]
+mod bitcoin;
+mod client;
+#[cfg(feature = "electrum")]
+pub mod electrum;
+mod error;
+#[cfg(feature = "esplora")]
+pub mod esplora;
+#[cfg(feature = "evm")]
+pub mod evm;
+pub mod monitor;
+pub mod proxy;
+#[cfg(any(feature = "esplora", feature = "electrum", feature = "evm"))]
+pub mod rate_limit;
+mod types;
+
+pub use client::ChainClient;
+pub use error::{Error, Result};
+pub use khodpay_amount::CoinAmount;
+pub use proxy::Proxy;
+#[cfg(any(feature = "esplora", feature = "electrum", feature = "evm"))]
+pub use rate_limit::{RateLimit, RateLimiter};
+pub use types::{
+    Balance, BitcoinFees, FeeEstimate, TxSummary, FAST_TARGET_BLOCKS, NORMAL_TARGET_BLOCKS,
+    SLOW_TARGET_BLOCKS,
+};
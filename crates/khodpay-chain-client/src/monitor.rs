@@ -0,0 +1,126 @@
+//! Incoming payment detection over server push.
+//!
+//! A [`PaymentMonitor`] watches the wallet's receive addresses and turns the
+//! server's notifications into typed [`PaymentEvent`]s, so a wallet can notify
+//! the user of a payment as soon as it is seen instead of polling balances:
+//!
+//! - [`ElectrumMonitor`](crate::electrum::ElectrumMonitor) subscribes to each
+//!   address's Electrum script hash and reads its history when the status changes,
+//!   announcing unconfirmed payments and again when they confirm (feature
+//!   `electrum`).
+//! - [`EvmMonitor`](crate::evm::EvmMonitor) subscribes to new blocks and ERC-20
+//!   `Transfer` logs over a WebSocket, announcing native coin received in each block
+//!   and token transfers (feature `ws`).
+//!
+//! Payments already in an address's history when it is watched are not announced.
+
+use async_trait::async_trait;
+use khodpay_address::{Address, Network};
+use khodpay_amount::CoinAmount;
+
+use crate::Result;
+
+/// What a [`Payment`] is paid in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaymentAsset {
+    /// The network's own coin: bitcoin, or ether / BNB on an EVM chain.
+    Native,
+    /// An ERC-20 token, by contract address.
+    Token(khodpay_signing::Address),
+}
+
+/// An amount received by a watched address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Payment {
+    /// The watched address that received it.
+    pub address: Address,
+    /// What it is paid in.
+    pub asset: PaymentAsset,
+    /// Amount received, with the asset's decimals.
+    pub amount: CoinAmount,
+    /// Transaction id in the chain's usual notation, when known. Native coin on EVM
+    /// chains is detected from balances, which do not name the transaction.
+    pub txid: Option<String>,
+    /// Height of the confirming block, or `None` while unconfirmed.
+    pub height: Option<u64>,
+}
+
+/// A change in a watched address's incoming payments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PaymentEvent {
+    /// A payment was seen for the first time, in the mempool or already in a block.
+    Received(Payment),
+    /// A payment first seen unconfirmed is now in a block.
+    Confirmed(Payment),
+}
+
+impl PaymentEvent {
+    /// Returns the payment the event is about.
+    pub fn payment(&self) -> &Payment {
+        match self {
+            PaymentEvent::Received(payment) | PaymentEvent::Confirmed(payment) => payment,
+        }
+    }
+}
+
+/// A stream of incoming payments to a set of watched addresses on one network.
+///
+/// The trait is object safe, so a multi-coin wallet can run one
+/// `Box<dyn PaymentMonitor>` per network.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_chain_client::monitor::{PaymentEvent, PaymentMonitor};
+/// use khodpay_chain_client::Result;
+///
+/// /// Announces every payment until the connection fails.
+/// async fn notify(monitor: &mut dyn PaymentMonitor) -> Result<()> {
+///     loop {
+///         match monitor.next_event().await? {
+///             PaymentEvent::Received(payment) => {
+///                 println!("received {} at {}", payment.amount, payment.address)
+///             }
+///             PaymentEvent::Confirmed(payment) => println!("confirmed {:?}", payment.txid),
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait PaymentMonitor: Send {
+    /// Returns the network the monitor watches.
+    fn network(&self) -> Network;
+
+    /// Starts watching `address`. Watching an address twice has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::AddressError`](crate::Error::AddressError) if the address
+    /// belongs to another network, or an error if the subscription fails.
+    async fn watch(&mut self, address: &Address) -> Result<()>;
+
+    /// Waits for the next event on any watched address.
+    ///
+    /// Cancelling the wait, e.g. in `tokio::select!`, loses no notification.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`](crate::Error::Transport) once the connection is
+    /// lost; watch the addresses again on a new connection to resume.
+    async fn next_event(&mut self) -> Result<PaymentEvent>;
+}
+
+#[async_trait]
+impl<T: PaymentMonitor + ?Sized> PaymentMonitor for Box<T> {
+    fn network(&self) -> Network {
+        (**self).network()
+    }
+
+    async fn watch(&mut self, address: &Address) -> Result<()> {
+        (**self).watch(address).await
+    }
+
+    async fn next_event(&mut self) -> Result<PaymentEvent> {
+        (**self).next_event().await
+    }
+}
@@ -0,0 +1,288 @@
+//! SOCKS5 proxies, such as a local Tor client.
+//!
+//! Every backend can route its connections through a [`Proxy`]. Host names are
+//! resolved by the proxy rather than locally, so DNS lookups do not leak the
+//! servers a wallet talks to, and Tor onion services (`*.onion`) are reachable.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_address::BitcoinNetwork;
+//! use khodpay_chain_client::esplora::EsploraClient;
+//! use khodpay_chain_client::Proxy;
+//!
+//! # fn run() -> khodpay_chain_client::Result<()> {
+//! // mempool.space's onion service, through the Tor client on 127.0.0.1:9050
+//! let client = EsploraClient::with_proxy(
+//!     "http://mempoolhqx4isw62xs7abwphsq7ldayuidyx2v2oethdhhj6mlo2r6ad.onion/api",
+//!     BitcoinNetwork::Bitcoin,
+//!     &Proxy::tor(),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+#[cfg(any(feature = "esplora", feature = "evm", feature = "electrum"))]
+use crate::{Error, Result};
+
+/// Address of the SOCKS port a Tor client listens on by default.
+pub const TOR_SOCKS_ADDRESS: &str = "127.0.0.1:9050";
+
+/// A SOCKS5 proxy that connections are routed through.
+///
+/// Tor puts connections that authenticate with different credentials on
+/// different circuits, so giving each wallet account its own
+/// [`with_credentials`](Self::with_credentials) keeps them from being linked by
+/// exit relays or servers.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Proxy {
+    address: String,
+    credentials: Option<(String, String)>,
+}
+
+impl Proxy {
+    /// Creates a proxy for the SOCKS5 server at `address`, as `host:port`.
+    pub fn socks5(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            credentials: None,
+        }
+    }
+
+    /// Creates a proxy for a Tor client on its default port,
+    /// [`TOR_SOCKS_ADDRESS`].
+    pub fn tor() -> Self {
+        Self::socks5(TOR_SOCKS_ADDRESS)
+    }
+
+    /// Authenticates to the proxy with a username and password.
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Returns the proxy address, as `host:port`.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Returns the username the proxy is authenticated to with, if any.
+    pub fn username(&self) -> Option<&str> {
+        self.credentials
+            .as_ref()
+            .map(|(username, _)| username.as_str())
+    }
+
+    /// Returns a `reqwest::Client` that sends every request through the proxy,
+    /// for HTTP backends configured by hand.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if the proxy address is malformed.
+    #[cfg(any(feature = "esplora", feature = "evm"))]
+    pub fn http_client(&self) -> Result<reqwest::Client> {
+        // socks5h: host names are resolved by the proxy
+        let mut proxy = reqwest::Proxy::all(format!("socks5h://{}", self.address))
+            .map_err(|e| Error::Transport(format!("proxy {}: {}", self.address, e)))?;
+        if let Some((username, password)) = &self.credentials {
+            proxy = proxy.basic_auth(username, password);
+        }
+        reqwest::Client::builder()
+            .proxy(proxy)
+            .build()
+            .map_err(|e| Error::Transport(format!("proxy {}: {}", self.address, e)))
+    }
+
+    /// Connects a [`WsClient`](khodpay_signing::rpc::WsClient) to the `ws://` or
+    /// `wss://` endpoint at `url` through the proxy (requires the `ws` feature), for
+    /// [`EvmMonitor`](crate::evm::EvmMonitor) and other WebSocket subscriptions.
+    ///
+    /// The host name is resolved by the proxy, so `.onion` endpoints are reachable.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if `url` is malformed, or the proxy connection or
+    /// the WebSocket handshake fails.
+    #[cfg(feature = "ws")]
+    pub async fn ws_client(&self, url: &str) -> Result<khodpay_signing::rpc::WsClient> {
+        let invalid = || Error::Transport(format!("invalid WebSocket URL: {}", url));
+        let (default_port, rest) = match url.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("ws") => (80, rest),
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("wss") => (443, rest),
+            _ => return Err(invalid()),
+        };
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid())?)
+            }
+            _ => (authority, default_port),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        let stream = self.connect(host, port).await?;
+        khodpay_signing::rpc::WsClient::connect_with_stream(url, stream)
+            .await
+            .map_err(crate::evm::rpc_error)
+    }
+
+    /// Opens a TCP connection to `host:port` through the proxy.
+    #[cfg(any(feature = "electrum", feature = "ws"))]
+    pub(crate) async fn connect(&self, host: &str, port: u16) -> Result<tokio::net::TcpStream> {
+        use tokio_socks::tcp::Socks5Stream;
+
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let stream = match &self.credentials {
+            Some((username, password)) => {
+                Socks5Stream::connect_with_password(
+                    self.address.as_str(),
+                    (host, port),
+                    username,
+                    password,
+                )
+                .await
+            }
+            None => Socks5Stream::connect(self.address.as_str(), (host, port)).await,
+        };
+        stream.map(Socks5Stream::into_inner).map_err(|e| {
+            Error::Transport(format!(
+                "{}:{} through proxy {}: {}",
+                host, port, self.address, e
+            ))
+        })
+    }
+}
+
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("address", &self.address)
+            .field("username", &self.username())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns `true` if `host` is a Tor onion service, only reachable through Tor.
+#[cfg(feature = "electrum")]
+pub(crate) fn is_onion(host: &str) -> bool {
+    host.trim_end_matches('.')
+        .rsplit_once('.')
+        .is_some_and(|(_, tld)| tld.eq_ignore_ascii_case("onion"))
+}
+
+/// A SOCKS5 server for the backends' proxy tests.
+#[cfg(all(test, any(feature = "esplora", feature = "evm", feature = "electrum")))]
+pub(crate) mod testing {
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Connections a [`socks_server`] accepted: `(target, username)`.
+    pub(crate) type Connections = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+    /// Serves a SOCKS5 proxy that records each requested target and forwards the
+    /// connection to `upstream`, whatever the target.
+    pub(crate) async fn socks_server(upstream: String) -> (String, Connections) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let connections = Connections::default();
+        let recorded = connections.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut client, _) = listener.accept().await.unwrap();
+                let (target, username) = handshake(&mut client).await;
+                recorded.lock().unwrap().push((target, username));
+                let mut server = TcpStream::connect(&upstream).await.unwrap();
+                tokio::spawn(async move {
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                });
+            }
+        });
+        (address, connections)
+    }
+
+    async fn read_string(stream: &mut TcpStream) -> String {
+        let len = stream.read_u8().await.unwrap();
+        let mut bytes = vec![0u8; len as usize];
+        stream.read_exact(&mut bytes).await.unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// Runs the server side of a SOCKS5 CONNECT, returning the target and username.
+    async fn handshake(stream: &mut TcpStream) -> (String, Option<String>) {
+        assert_eq!(stream.read_u8().await.unwrap(), 5);
+        let count = stream.read_u8().await.unwrap();
+        let mut methods = vec![0u8; count as usize];
+        stream.read_exact(&mut methods).await.unwrap();
+
+        // Prefer username / password authentication when offered
+        let username = if methods.contains(&2) {
+            stream.write_all(&[5, 2]).await.unwrap();
+            assert_eq!(stream.read_u8().await.unwrap(), 1);
+            let username = read_string(stream).await;
+            let _password = read_string(stream).await;
+            stream.write_all(&[1, 0]).await.unwrap();
+            Some(username)
+        } else {
+            stream.write_all(&[5, 0]).await.unwrap();
+            None
+        };
+
+        let mut request = [0u8; 4];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request[..3], [5, 1, 0]);
+        let host = match request[3] {
+            1 => {
+                let mut ip = [0u8; 4];
+                stream.read_exact(&mut ip).await.unwrap();
+                std::net::Ipv4Addr::from(ip).to_string()
+            }
+            3 => read_string(stream).await,
+            atyp => panic!("unexpected address type {}", atyp),
+        };
+        let port = stream.read_u16().await.unwrap();
+        stream
+            .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        (format!("{}:{}", host, port), username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy() {
+        let proxy = Proxy::tor();
+        assert_eq!(proxy.address(), "127.0.0.1:9050");
+        assert_eq!(proxy.username(), None);
+
+        let proxy = Proxy::socks5("10.0.0.2:1080").with_credentials("account-1", "secret");
+        assert_eq!(proxy.username(), Some("account-1"));
+        let debug = format!("{:?}", proxy);
+        assert!(debug.contains("account-1"));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[test]
+    #[cfg(feature = "electrum")]
+    fn test_is_onion() {
+        assert!(is_onion(
+            "mempoolhqx4isw62xs7abwphsq7ldayuidyx2v2oethdhhj6mlo2r6ad.onion"
+        ));
+        assert!(is_onion("api.example.ONION."));
+        assert!(!is_onion("blockstream.info"));
+        assert!(!is_onion("onion"));
+        assert!(!is_onion("127.0.0.1"));
+    }
+}
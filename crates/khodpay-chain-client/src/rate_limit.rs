@@ -0,0 +1,304 @@
+//! Client-side rate limits, so wallet sync stays within a provider's quotas.
+//!
+//! Public Esplora and JSON-RPC endpoints throttle or ban IP addresses that send
+//! too many requests. A [`RateLimiter`] paces a backend's requests with a token
+//! bucket: requests go out at a steady rate, a burst may go out at once after a
+//! quiet period, and requests beyond that wait for their turn. An optional
+//! request budget caps the requests per window, such as a provider's daily
+//! quota; once it is spent, requests fail with [`Error::QuotaExceeded`] instead
+//! of waiting for the window to end.
+//!
+//! Limits apply per endpoint: clones of a limiter share its bucket and budget, so
+//! clients talking to the same server can be given the same limiter.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use khodpay_address::BitcoinNetwork;
+//! use khodpay_chain_client::esplora::EsploraClient;
+//! use khodpay_chain_client::{RateLimit, RateLimiter};
+//!
+//! // 5 requests a second, up to 10 at once, and at most 20 000 an hour
+//! let limiter = RateLimiter::new(
+//!     RateLimit::per_second(5)
+//!         .with_burst(10)
+//!         .with_budget(20_000, Duration::from_secs(3600)),
+//! );
+//!
+//! let mainnet = EsploraClient::new("https://mempool.space/api", BitcoinNetwork::Bitcoin)
+//!     .with_rate_limit(limiter.clone());
+//! let signet = EsploraClient::new("https://mempool.space/signet/api", BitcoinNetwork::Signet)
+//!     .with_rate_limit(limiter);
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::{Error, Result};
+
+/// The pace and quota a [`RateLimiter`] enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    requests: u32,
+    per: Duration,
+    burst: u32,
+    budget: Option<(u32, Duration)>,
+}
+
+impl RateLimit {
+    /// Allows `requests` requests every `per`, evenly spaced, with no burst.
+    ///
+    /// A `requests` of zero is treated as one, and a zero `per` as one
+    /// millisecond.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            requests: requests.max(1),
+            per: per.max(Duration::from_millis(1)),
+            burst: 1,
+            budget: None,
+        }
+    }
+
+    /// Allows `requests` requests a second.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Allows `requests` requests a minute.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Lets up to `burst` requests go out at once after a quiet period, instead
+    /// of one at a time (at least one).
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Caps the requests sent in each `window` at `requests`; the window starts
+    /// with the first request and restarts with the first request after it ends.
+    pub fn with_budget(mut self, requests: u32, window: Duration) -> Self {
+        self.budget = Some((requests, window));
+        self
+    }
+
+    /// Returns the steady rate as `(requests, per)`.
+    pub fn rate(&self) -> (u32, Duration) {
+        (self.requests, self.per)
+    }
+
+    /// Returns the number of requests that may go out at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Returns the request budget as `(requests, window)`, if any.
+    pub fn budget(&self) -> Option<(u32, Duration)> {
+        self.budget
+    }
+
+    /// Returns the time between two requests at the steady rate.
+    fn interval(&self) -> Duration {
+        self.per / self.requests
+    }
+}
+
+/// Enforces a [`RateLimit`] on the requests of one or more clients.
+///
+/// Clones share state, so a limiter given to several clients limits their
+/// requests together.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    /// When the next request would go out at the steady rate with an empty bucket.
+    next: Instant,
+    /// Start of the current budget window, and the requests spent in it.
+    window: Option<(Instant, u32)>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter with a full bucket and an unspent budget.
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Arc::new(Mutex::new(State {
+                next: Instant::now(),
+                window: None,
+            })),
+        }
+    }
+
+    /// Returns the limit this limiter enforces.
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Returns the requests left in the current budget window, or `None` if the
+    /// limit has no budget.
+    pub fn remaining_budget(&self) -> Option<u32> {
+        let (requests, window) = self.limit.budget?;
+        let state = self.state.lock().unwrap();
+        Some(match state.window {
+            Some((start, spent)) if start.elapsed() < window => requests.saturating_sub(spent),
+            _ => requests,
+        })
+    }
+
+    /// Waits until a request may go out and takes it from the budget.
+    ///
+    /// Callers waiting together are let through in the order they called.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::QuotaExceeded`] without waiting if the budget for the
+    /// current window is spent.
+    pub async fn acquire(&self) -> Result<()> {
+        let wait = self.reserve(Instant::now())?;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Takes a request from the budget and the bucket, returning how long to wait
+    /// before sending it.
+    fn reserve(&self, now: Instant) -> Result<Duration> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some((requests, window)) = self.limit.budget {
+            let (start, spent) = match state.window {
+                Some((start, spent)) if now.duration_since(start) < window => (start, spent),
+                _ => (now, 0),
+            };
+            if spent >= requests {
+                return Err(Error::QuotaExceeded {
+                    retry_after: window - now.duration_since(start),
+                });
+            }
+            state.window = Some((start, spent + 1));
+        }
+
+        // Each request pushes `next` one interval on; a request may go out early
+        // by as many intervals as the burst has tokens left
+        let interval = self.limit.interval();
+        let next = state.next.max(now);
+        let send_at = next
+            .checked_sub(interval * (self.limit.burst - 1))
+            .map_or(now, |send_at| send_at.max(now));
+        state.next = next + interval;
+        Ok(send_at - now)
+    }
+}
+
+impl From<RateLimit> for RateLimiter {
+    fn from(limit: RateLimit) -> Self {
+        Self::new(limit)
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Waits for `limiter`, if the client has one.
+pub(crate) async fn throttle(limiter: Option<&RateLimiter>) -> Result<()> {
+    match limiter {
+        Some(limiter) => limiter.acquire().await,
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn test_rate_limit() {
+        let limit = RateLimit::per_second(4).with_burst(3);
+        assert_eq!(limit.rate(), (4, Duration::from_secs(1)));
+        assert_eq!(limit.burst(), 3);
+        assert_eq!(limit.budget(), None);
+        assert_eq!(limit.interval(), 250 * MS);
+
+        // Degenerate values are clamped instead of dividing by zero
+        let limit = RateLimit::new(0, Duration::ZERO).with_burst(0);
+        assert_eq!(limit.rate(), (1, MS));
+        assert_eq!(limit.burst(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_steady_rate() {
+        let limiter = RateLimiter::new(RateLimit::per_second(10));
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now).unwrap(), Duration::ZERO);
+        assert_eq!(limiter.reserve(now).unwrap(), 100 * MS);
+        assert_eq!(limiter.reserve(now).unwrap(), 200 * MS);
+
+        // Waiting callers are spaced out in turn
+        let started = Instant::now();
+        limiter.acquire().await.unwrap();
+        assert_eq!(started.elapsed(), 300 * MS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst() {
+        let limiter = RateLimiter::new(RateLimit::per_second(10).with_burst(3));
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(now).unwrap(), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve(now).unwrap(), 100 * MS);
+
+        // A quiet period refills the bucket, but never beyond the burst
+        let later = now + Duration::from_secs(10);
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(later).unwrap(), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve(later).unwrap(), 100 * MS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_budget() {
+        let limiter = RateLimiter::new(
+            RateLimit::per_second(1000)
+                .with_burst(10)
+                .with_budget(3, Duration::from_secs(60)),
+        );
+        assert_eq!(limiter.remaining_budget(), Some(3));
+        for _ in 0..3 {
+            limiter.acquire().await.unwrap();
+        }
+        assert_eq!(limiter.remaining_budget(), Some(0));
+
+        tokio::time::advance(Duration::from_secs(20)).await;
+        let error = limiter.acquire().await.unwrap_err();
+        assert!(matches!(
+            error,
+            Error::QuotaExceeded { retry_after } if retry_after == Duration::from_secs(40)
+        ));
+        assert_eq!(error.to_string(), "Request quota exceeded: retry after 40s");
+
+        // Clones share the budget, which comes back when the window ends
+        let clone = limiter.clone();
+        tokio::time::advance(Duration::from_secs(40)).await;
+        assert_eq!(clone.remaining_budget(), Some(3));
+        clone.acquire().await.unwrap();
+        assert_eq!(limiter.remaining_budget(), Some(2));
+    }
+}
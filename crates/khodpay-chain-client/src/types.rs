@@ -0,0 +1,123 @@
+//! Chain-agnostic query results.
+
+use khodpay_amount::CoinAmount;
+use khodpay_btc_signing::FeeRate;
+use khodpay_signing::fee::{FeeSpeed, FeeSuggestions};
+
+/// Confirmation target of [`FeeSpeed::Fast`] Bitcoin fee estimates, in blocks.
+pub const FAST_TARGET_BLOCKS: u16 = 1;
+
+/// Confirmation target of [`FeeSpeed::Normal`] Bitcoin fee estimates, in blocks.
+pub const NORMAL_TARGET_BLOCKS: u16 = 6;
+
+/// Confirmation target of [`FeeSpeed::Slow`] Bitcoin fee estimates, in blocks.
+pub const SLOW_TARGET_BLOCKS: u16 = 144;
+
+/// The balance of an address in the chain's native coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Balance {
+    /// Balance in confirmed transactions, with the coin's decimals (8 for bitcoin,
+    /// 18 for wei).
+    pub confirmed: CoinAmount,
+    /// Net effect of unconfirmed transactions in base units of `confirmed`;
+    /// negative while a spend is pending.
+    pub pending: i128,
+}
+
+impl Balance {
+    /// Returns the balance once pending transactions confirm.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_chain_client::{Balance, CoinAmount};
+    ///
+    /// let balance = Balance { confirmed: CoinAmount::from_sats(50_000), pending: -20_000 };
+    /// assert_eq!(balance.total(), CoinAmount::from_sats(30_000));
+    /// assert_eq!(balance.total().to_string(), "0.0003");
+    /// ```
+    pub fn total(&self) -> CoinAmount {
+        let pending = CoinAmount::new(self.pending.unsigned_abs(), self.confirmed.decimals());
+        let total = if self.pending < 0 {
+            self.confirmed.checked_sub(pending)
+        } else {
+            self.confirmed.checked_add(pending)
+        };
+        total.unwrap_or_else(|| CoinAmount::zero(self.confirmed.decimals()))
+    }
+}
+
+/// A transaction touching an address, as listed by [`ChainClient::history`].
+///
+/// [`ChainClient::history`]: crate::ChainClient::history
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TxSummary {
+    /// Transaction id in the chain's usual notation (reversed hex for Bitcoin).
+    pub txid: String,
+    /// Height of the confirming block, or `None` while unconfirmed.
+    pub height: Option<u64>,
+    /// Timestamp of the confirming block, in seconds since the Unix epoch.
+    pub timestamp: Option<u64>,
+    /// Amount received minus amount sent by the address, in base units of the
+    /// native coin.
+    pub net: i128,
+    /// Fee paid by the transaction in the native coin, when the backend reports or
+    /// implies it.
+    pub fee: Option<CoinAmount>,
+}
+
+impl TxSummary {
+    /// Returns `true` once the transaction is in a block.
+    pub fn is_confirmed(&self) -> bool {
+        self.height.is_some()
+    }
+}
+
+/// Slow / normal / fast Bitcoin fee rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitcoinFees {
+    /// Rate expected to confirm within [`SLOW_TARGET_BLOCKS`].
+    pub slow: FeeRate,
+    /// Rate expected to confirm within [`NORMAL_TARGET_BLOCKS`].
+    pub normal: FeeRate,
+    /// Rate expected to confirm within [`FAST_TARGET_BLOCKS`].
+    pub fast: FeeRate,
+}
+
+impl BitcoinFees {
+    /// Returns the rate for `speed`.
+    pub fn get(&self, speed: FeeSpeed) -> FeeRate {
+        match speed {
+            FeeSpeed::Slow => self.slow,
+            FeeSpeed::Normal => self.normal,
+            FeeSpeed::Fast => self.fast,
+        }
+    }
+}
+
+/// Fee suggestions in the form the chain's transactions take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeEstimate {
+    /// Fee rates for Bitcoin transactions.
+    Bitcoin(BitcoinFees),
+    /// EIP-1559 fee pairs for EVM transactions.
+    Evm(FeeSuggestions),
+}
+
+impl FeeEstimate {
+    /// Returns the Bitcoin fee rates, if this is a Bitcoin estimate.
+    pub fn as_bitcoin(&self) -> Option<&BitcoinFees> {
+        match self {
+            FeeEstimate::Bitcoin(fees) => Some(fees),
+            FeeEstimate::Evm(_) => None,
+        }
+    }
+
+    /// Returns the EVM fee suggestions, if this is an EVM estimate.
+    pub fn as_evm(&self) -> Option<&FeeSuggestions> {
+        match self {
+            FeeEstimate::Evm(suggestions) => Some(suggestions),
+            FeeEstimate::Bitcoin(_) => None,
+        }
+    }
+}
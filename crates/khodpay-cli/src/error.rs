@@ -0,0 +1,46 @@
+//! Error types for the command-line tool.
+
+use thiserror::Error;
+
+/// Errors that stop a command.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// An argument or the input read from stdin or a file is unusable.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Reading stdin or a file failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Error from parsing or generating a mnemonic.
+    #[error("Mnemonic error: {0}")]
+    Mnemonic(#[from] khodpay_bip39::Error),
+
+    /// Error from deriving keys.
+    #[error("Key derivation error: {0}")]
+    Bip32(#[from] khodpay_bip32::Error),
+
+    /// Error from deriving accounts.
+    #[error("Wallet error: {0}")]
+    Bip44(#[from] khodpay_bip44::Error),
+
+    /// Error from Bitcoin addresses, descriptors or PSBTs.
+    #[error("Bitcoin error: {0}")]
+    Bitcoin(#[from] khodpay_btc_signing::Error),
+
+    /// Error from decoding or signing an EVM transaction.
+    #[error("EVM error: {0}")]
+    Evm(#[from] khodpay_signing::Error),
+
+    /// Error from deriving a Tron address.
+    #[error("Tron error: {0}")]
+    Tron(#[from] khodpay_tron_signing::Error),
+
+    /// Error from deriving an XRP Ledger address.
+    #[error("XRP error: {0}")]
+    Xrp(#[from] khodpay_xrp_signing::Error),
+}
+
+/// Result type alias for command-line operations.
+pub type Result<T> = std::result::Result<T, Error>;
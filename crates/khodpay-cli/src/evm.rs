@@ -0,0 +1,204 @@
+//! `khodpay sign-evm`: signing EVM transactions offline.
+
+use std::io::BufRead;
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+use khodpay_bip32::Network;
+use khodpay_bip44::{CoinType, Purpose};
+use khodpay_signing::{
+    Bip44Signer, ChainId, Eip1559Transaction, Eip2930Transaction, SignedEip2930Transaction,
+    SignedTransaction,
+};
+
+use crate::{wallet, Error, Result};
+
+/// Returns the `sign-evm` command.
+pub fn command() -> Command {
+    wallet::mnemonic_args(
+        Command::new("sign-evm")
+            .about("Sign an unsigned EIP-1559 or EIP-2930 transaction")
+            .long_about(
+                "Sign an unsigned EIP-1559 (type 2) or EIP-2930 (type 1) transaction, given \
+                 as the hex of its unsigned encoding, with the key at m/44'/60'/ACCOUNT'/0/INDEX. \
+                 Prints the sender, the transaction hash and the raw signed transaction for \
+                 eth_sendRawTransaction. Nothing is sent.",
+            ),
+    )
+    .arg(
+        Arg::new("transaction")
+            .value_name("UNSIGNED_TX")
+            .required(true)
+            .help("Unsigned transaction in hex, with or without 0x"),
+    )
+    .arg(
+        Arg::new("account")
+            .long("account")
+            .value_name("INDEX")
+            .value_parser(value_parser!(u32))
+            .default_value("0")
+            .help("Account index"),
+    )
+    .arg(
+        Arg::new("index")
+            .long("index")
+            .value_name("INDEX")
+            .value_parser(value_parser!(u32))
+            .default_value("0")
+            .help("Address index"),
+    )
+    .arg(
+        Arg::new("chain-id")
+            .long("chain-id")
+            .value_name("ID")
+            .value_parser(value_parser!(u64))
+            .help("Refuse to sign unless the transaction is for this chain"),
+    )
+}
+
+/// Runs the `sign-evm` command.
+pub fn run(matches: &ArgMatches, input: &mut dyn BufRead, passphrase: &str) -> Result<String> {
+    let encoded = matches
+        .get_one::<String>("transaction")
+        .map(String::as_str)
+        .unwrap_or_default()
+        .trim();
+    let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+    let bytes = hex::decode(encoded)
+        .map_err(|e| Error::InvalidInput(format!("transaction is not hex: {}", e)))?;
+    // Decode before reading the mnemonic, so a bad transaction costs no secret input
+    let transaction = match bytes.first() {
+        Some(&Eip1559Transaction::TYPE) => {
+            Transaction::Eip1559(Eip1559Transaction::decode(&bytes)?)
+        }
+        Some(&Eip2930Transaction::TYPE) => {
+            Transaction::Eip2930(Eip2930Transaction::decode(&bytes)?)
+        }
+        _ => {
+            return Err(Error::InvalidInput(
+                "only EIP-1559 (0x02) and EIP-2930 (0x01) transactions are supported".to_string(),
+            ))
+        }
+    };
+
+    let account = matches.get_one::<u32>("account").copied().unwrap_or(0);
+    let index = matches.get_one::<u32>("index").copied().unwrap_or(0);
+    let mut wallet = wallet::open(matches, input, passphrase, Network::BitcoinMainnet)?;
+    let account = wallet.get_account(Purpose::BIP44, CoinType::Ethereum, account)?;
+    let mut signer = Bip44Signer::new(account, index)?;
+    if let Some(&chain_id) = matches.get_one::<u64>("chain-id") {
+        signer = signer.with_chain_id(ChainId::from(chain_id));
+    }
+
+    let (hash, raw) = match transaction {
+        Transaction::Eip1559(tx) => {
+            let signature = signer.sign_transaction(&tx)?;
+            let signed = SignedTransaction::new(tx, signature);
+            (signed.tx_hash_hex(), signed.to_raw_transaction())
+        }
+        Transaction::Eip2930(tx) => {
+            let signature = signer.sign_eip2930_transaction(&tx)?;
+            let signed = SignedEip2930Transaction::new(tx, signature);
+            (signed.tx_hash_hex(), signed.to_raw_transaction())
+        }
+    };
+    Ok(format!(
+        "from\t{}\nhash\t{}\nraw\t{}\n",
+        signer.address(),
+        hash,
+        raw
+    ))
+}
+
+/// An unsigned transaction of one of the supported types.
+enum Transaction {
+    Eip1559(Eip1559Transaction),
+    Eip2930(Eip2930Transaction),
+}
+
+#[cfg(test)]
+mod tests {
+    use khodpay_signing::{Address, TypedTransaction, Wei};
+
+    use super::*;
+    use crate::tests::{run_with, MNEMONIC};
+
+    fn unsigned_transfer(chain_id: ChainId) -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(7)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x11; 20]))
+            .value(Wei::from_gwei(1_000))
+            .build()
+            .unwrap()
+    }
+
+    fn field<'a>(output: &'a str, name: &str) -> &'a str {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('\t'))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sign_eip1559() {
+        let tx = unsigned_transfer(ChainId::BscMainnet);
+        let unsigned = format!("0x{}", hex::encode(tx.encode_unsigned()));
+        let output = run_with(&["sign-evm", &unsigned], MNEMONIC, "").unwrap();
+
+        let from = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+        assert_eq!(field(&output, "from"), from);
+        let raw = hex::decode(field(&output, "raw").trim_start_matches("0x")).unwrap();
+        let TypedTransaction::Eip1559(signed) = TypedTransaction::from_raw(&raw).unwrap() else {
+            panic!("expected an EIP-1559 transaction");
+        };
+        assert_eq!(signed.transaction(), &tx);
+        assert_eq!(signed.verify().unwrap().to_string(), from);
+        assert_eq!(field(&output, "hash"), signed.tx_hash_hex());
+
+        // Without the 0x prefix, pinned to the right chain
+        let output = run_with(
+            &["sign-evm", "--chain-id", "56", &unsigned[2..]],
+            MNEMONIC,
+            "",
+        )
+        .unwrap();
+        assert_eq!(field(&output, "raw"), format!("0x{}", hex::encode(&raw)));
+    }
+
+    #[test]
+    fn test_sign_eip2930() {
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::Custom(1))
+            .nonce(0)
+            .gas_price(Wei::from_gwei(20))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x22; 20]))
+            .value(Wei::from_gwei(1))
+            .build()
+            .unwrap();
+        let unsigned = hex::encode(tx.encode_unsigned());
+        let output = run_with(&["sign-evm", "--index", "1", &unsigned], MNEMONIC, "").unwrap();
+        assert!(field(&output, "raw").starts_with("0x01"));
+        assert_ne!(
+            field(&output, "from"),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+    }
+
+    #[test]
+    fn test_sign_rejects_bad_input() {
+        let unsigned = hex::encode(unsigned_transfer(ChainId::BscMainnet).encode_unsigned());
+        // Wrong chain
+        assert!(matches!(
+            run_with(&["sign-evm", "--chain-id", "1", &unsigned], MNEMONIC, ""),
+            Err(Error::Evm(khodpay_signing::Error::ChainIdMismatch { .. }))
+        ));
+        // Not hex, legacy or truncated
+        for tx in ["zz", "f86c", &unsigned[..unsigned.len() - 2]] {
+            assert!(run_with(&["sign-evm", tx], MNEMONIC, "").is_err(), "{}", tx);
+        }
+    }
+}
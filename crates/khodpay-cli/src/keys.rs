@@ -0,0 +1,336 @@
+//! `khodpay address` and `khodpay xpub`: deriving addresses and account keys.
+
+use std::io::BufRead;
+
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use khodpay_bip32::Network;
+use khodpay_bip44::{Account, Bip44Path, Chain, CoinType, Purpose};
+use khodpay_btc_signing::miniscript::descriptor_checksum;
+use khodpay_btc_signing::Address;
+use khodpay_signing::Bip44Signer;
+use khodpay_tron_signing::TronSigner;
+use khodpay_xrp_signing::XrpSigner;
+
+use crate::{wallet, Error, Result};
+
+/// Most addresses one `address` command derives.
+const MAX_COUNT: i64 = 10_000;
+
+/// A coin the tool derives keys for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coin {
+    Btc,
+    Tbtc,
+    Eth,
+    Trx,
+    Xrp,
+}
+
+impl Coin {
+    /// Coins by their command-line name.
+    const ALL: [(&'static str, Coin); 5] = [
+        ("btc", Coin::Btc),
+        ("tbtc", Coin::Tbtc),
+        ("eth", Coin::Eth),
+        ("trx", Coin::Trx),
+        ("xrp", Coin::Xrp),
+    ];
+
+    fn from_matches(matches: &ArgMatches) -> Coin {
+        let name = matches.get_one::<String>("coin").map(String::as_str);
+        Coin::ALL
+            .iter()
+            .find(|(candidate, _)| Some(*candidate) == name)
+            .map(|(_, coin)| *coin)
+            .unwrap_or(Coin::Btc)
+    }
+
+    fn coin_type(self) -> CoinType {
+        match self {
+            Coin::Btc => CoinType::Bitcoin,
+            Coin::Tbtc => CoinType::BitcoinTestnet,
+            Coin::Eth => CoinType::Ethereum,
+            Coin::Trx => CoinType::Tron,
+            Coin::Xrp => CoinType::Ripple,
+        }
+    }
+
+    fn network(self) -> Network {
+        match self {
+            Coin::Tbtc => Network::BitcoinTestnet,
+            _ => Network::BitcoinMainnet,
+        }
+    }
+
+    fn is_bitcoin(self) -> bool {
+        matches!(self, Coin::Btc | Coin::Tbtc)
+    }
+}
+
+/// Adds the arguments that select an account.
+fn account_args(command: Command) -> Command {
+    wallet::mnemonic_args(command)
+        .arg(
+            Arg::new("coin")
+                .long("coin")
+                .value_name("COIN")
+                .required(true)
+                .value_parser(PossibleValuesParser::new(Coin::ALL.map(|(name, _)| name)))
+                .help("Coin to derive for"),
+        )
+        .arg(
+            Arg::new("purpose")
+                .long("purpose")
+                .value_name("PURPOSE")
+                .value_parser(PossibleValuesParser::new(["44", "49", "84", "86"]))
+                .help("BIP-44 family purpose; Bitcoin defaults to 84 (native SegWit), other coins support only 44"),
+        )
+        .arg(
+            Arg::new("account")
+                .long("account")
+                .value_name("INDEX")
+                .value_parser(value_parser!(u32))
+                .default_value("0")
+                .help("Account index"),
+        )
+}
+
+/// Returns the `address` command.
+pub fn address_command() -> Command {
+    account_args(Command::new("address").about("Derive receive or change addresses"))
+        .arg(
+            Arg::new("change")
+                .long("change")
+                .action(ArgAction::SetTrue)
+                .help("Derive change addresses instead of receive addresses (Bitcoin only)"),
+        )
+        .arg(
+            Arg::new("start")
+                .long("start")
+                .value_name("INDEX")
+                .value_parser(value_parser!(u32))
+                .default_value("0")
+                .help("First address index"),
+        )
+        .arg(
+            Arg::new("count")
+                .long("count")
+                .value_name("N")
+                .value_parser(value_parser!(u32).range(1..=MAX_COUNT))
+                .default_value("1")
+                .help("Number of addresses"),
+        )
+}
+
+/// Returns the `xpub` command.
+pub fn xpub_command() -> Command {
+    account_args(
+        Command::new("xpub")
+            .about("Export an account's extended public key and, for Bitcoin, its descriptors"),
+    )
+}
+
+/// Reads the coin, purpose and account index, checking they go together.
+fn account_params(matches: &ArgMatches) -> Result<(Coin, Purpose, u32)> {
+    let coin = Coin::from_matches(matches);
+    let purpose = match matches.get_one::<String>("purpose") {
+        Some(purpose) => Purpose::try_from(purpose.parse::<u32>().unwrap_or_default())?,
+        None => coin.coin_type().default_purpose(),
+    };
+    if !coin.is_bitcoin() && purpose != Purpose::BIP44 {
+        return Err(Error::InvalidInput(format!(
+            "{} accounts use purpose 44 only",
+            coin.coin_type()
+        )));
+    }
+    let account = matches.get_one::<u32>("account").copied().unwrap_or(0);
+    Ok((coin, purpose, account))
+}
+
+/// Runs the `address` command: one `path<TAB>address` line per address.
+pub fn address(matches: &ArgMatches, input: &mut dyn BufRead, passphrase: &str) -> Result<String> {
+    let (coin, purpose, account_index) = account_params(matches)?;
+    let chain = if matches.get_flag("change") {
+        if !coin.is_bitcoin() {
+            return Err(Error::InvalidInput(format!(
+                "{} has no change addresses",
+                coin.coin_type()
+            )));
+        }
+        Chain::Internal
+    } else {
+        Chain::External
+    };
+    let start = matches.get_one::<u32>("start").copied().unwrap_or(0);
+    let count = matches.get_one::<u32>("count").copied().unwrap_or(1);
+    let end = start
+        .checked_add(count - 1)
+        .ok_or_else(|| Error::InvalidInput("address index out of range".to_string()))?;
+
+    let mut wallet = wallet::open(matches, input, passphrase, coin.network())?;
+    let account = wallet.get_account(purpose, coin.coin_type(), account_index)?;
+
+    let mut output = String::new();
+    for address_index in start..=end {
+        let path = Bip44Path::new(
+            purpose,
+            coin.coin_type(),
+            account_index,
+            chain,
+            address_index,
+        )?;
+        let address = derive_address(coin, account, chain, address_index)?;
+        output.push_str(&format!("{}\t{}\n", path, address));
+    }
+    Ok(output)
+}
+
+fn derive_address(coin: Coin, account: &Account, chain: Chain, index: u32) -> Result<String> {
+    Ok(match coin {
+        Coin::Btc | Coin::Tbtc => {
+            let key = account.derive_address(chain, index)?;
+            let public_key = key.to_extended_public_key().public_key().to_bytes();
+            let network = coin.network().into();
+            match account.purpose() {
+                Purpose::BIP44 => Address::p2pkh(&public_key, network),
+                Purpose::BIP49 => Address::p2sh_p2wpkh(&public_key, network),
+                Purpose::BIP84 => Address::p2wpkh(&public_key, network),
+                Purpose::BIP86 => Address::p2tr(&public_key, network)?,
+            }
+            .to_string()
+        }
+        Coin::Eth => Bip44Signer::new(account, index)?.address().to_string(),
+        Coin::Trx => TronSigner::new(account, index)?.address().to_string(),
+        Coin::Xrp => XrpSigner::new(account, index)?.address().to_string(),
+    })
+}
+
+/// Runs the `xpub` command: the account's path, master fingerprint and extended
+/// public key, and for Bitcoin its receive and change output descriptors.
+pub fn xpub(matches: &ArgMatches, input: &mut dyn BufRead, passphrase: &str) -> Result<String> {
+    let (coin, purpose, account_index) = account_params(matches)?;
+    let mut wallet = wallet::open(matches, input, passphrase, coin.network())?;
+    let fingerprint = hex::encode(wallet.master_key().fingerprint());
+    let account = wallet.get_account(purpose, coin.coin_type(), account_index)?;
+    let xpub = account.extended_key().to_extended_public_key().to_string();
+
+    let coin_index = coin.coin_type().index();
+    let mut output = format!(
+        "path\tm/{}'/{}'/{}'\nfingerprint\t{}\nxpub\t{}\n",
+        purpose.value(),
+        coin_index,
+        account_index,
+        fingerprint,
+        xpub
+    );
+    if coin.is_bitcoin() {
+        let origin = format!(
+            "[{}/{}h/{}h/{}h]{}",
+            fingerprint,
+            purpose.value(),
+            coin_index,
+            account_index,
+            xpub
+        );
+        for (name, chain) in [("receive", 0), ("change", 1)] {
+            let key = format!("{}/{}/*", origin, chain);
+            let descriptor = match purpose {
+                Purpose::BIP44 => format!("pkh({})", key),
+                Purpose::BIP49 => format!("sh(wpkh({}))", key),
+                Purpose::BIP84 => format!("wpkh({})", key),
+                Purpose::BIP86 => format!("tr({})", key),
+            };
+            let checksum = descriptor_checksum(&descriptor)?;
+            output.push_str(&format!("{}\t{}#{}\n", name, descriptor, checksum));
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{run_with, MNEMONIC};
+
+    #[test]
+    fn test_bitcoin_addresses() {
+        // BIP-84 test vectors
+        assert_eq!(
+            run_with(&["address", "--coin", "btc", "--count", "2"], MNEMONIC, "").unwrap(),
+            "m/84'/0'/0'/0/0\tbc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu\n\
+             m/84'/0'/0'/0/1\tbc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g\n"
+        );
+        assert_eq!(
+            run_with(&["address", "--coin", "btc", "--change"], MNEMONIC, "").unwrap(),
+            "m/84'/0'/0'/1/0\tbc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el\n"
+        );
+        // BIP-86 and BIP-44 test vectors
+        assert_eq!(
+            run_with(
+                &["address", "--coin", "btc", "--purpose", "86"],
+                MNEMONIC,
+                ""
+            )
+            .unwrap(),
+            "m/86'/0'/0'/0/0\tbc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr\n"
+        );
+        assert_eq!(
+            run_with(
+                &["address", "--coin", "btc", "--purpose", "44"],
+                MNEMONIC,
+                ""
+            )
+            .unwrap(),
+            "m/44'/0'/0'/0/0\t1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA\n"
+        );
+        let testnet = run_with(&["address", "--coin", "tbtc"], MNEMONIC, "").unwrap();
+        assert!(testnet.starts_with("m/84'/1'/0'/0/0\ttb1q"));
+    }
+
+    #[test]
+    fn test_account_addresses() {
+        assert_eq!(
+            run_with(&["address", "--coin", "eth"], MNEMONIC, "").unwrap(),
+            "m/44'/60'/0'/0/0\t0x9858EfFD232B4033E47d90003D41EC34EcaEda94\n"
+        );
+        let tron = run_with(&["address", "--coin", "trx", "--start", "3"], MNEMONIC, "").unwrap();
+        assert!(tron.starts_with("m/44'/195'/0'/0/3\tT"));
+        let xrp = run_with(&["address", "--coin", "xrp"], MNEMONIC, "").unwrap();
+        assert!(xrp.starts_with("m/44'/144'/0'/0/0\tr"));
+
+        assert!(run_with(&["address", "--coin", "eth", "--change"], MNEMONIC, "").is_err());
+        assert!(run_with(
+            &["address", "--coin", "eth", "--purpose", "84"],
+            MNEMONIC,
+            ""
+        )
+        .is_err());
+        assert!(run_with(&["address", "--coin", "btc", "--count", "0"], MNEMONIC, "").is_err());
+        assert!(run_with(&["address", "--coin", "doge"], MNEMONIC, "").is_err());
+    }
+
+    #[test]
+    fn test_xpub() {
+        let output = run_with(&["xpub", "--coin", "btc"], MNEMONIC, "").unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines[0], "path\tm/84'/0'/0'");
+        assert_eq!(lines[1], "fingerprint\t73c5da0a");
+        // The BIP-84 zpub with xpub version bytes
+        let xpub = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        assert_eq!(lines[2], format!("xpub\t{}", xpub));
+        assert_eq!(
+            lines[3],
+            format!("receive\twpkh([73c5da0a/84h/0h/0h]{}/0/*)#afwvtk2s", xpub)
+        );
+        assert!(lines[4].starts_with(&format!("change\twpkh([73c5da0a/84h/0h/0h]{}/1/*)#", xpub)));
+
+        let taproot =
+            run_with(&["xpub", "--coin", "tbtc", "--purpose", "86"], MNEMONIC, "").unwrap();
+        assert!(taproot.contains("\nxpub\ttpub"));
+        assert!(taproot.contains("\nreceive\ttr([73c5da0a/86h/1h/0h]tpub"));
+
+        let eth = run_with(&["xpub", "--coin", "eth", "--account", "1"], MNEMONIC, "").unwrap();
+        assert!(eth.starts_with("path\tm/44'/60'/1'\nfingerprint\t73c5da0a\nxpub\txpub"));
+        assert_eq!(eth.lines().count(), 3);
+    }
+}
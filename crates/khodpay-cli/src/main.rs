@@ -0,0 +1,154 @@
+//! # khodpay
+//!
+//! Command-line companion to the KhodPay wallet libraries, for operations teams and
+//! power users who want to script against the same audited code the app runs:
+//!
+//! - `khodpay mnemonic generate|restore`: create a BIP-39 mnemonic, or check one and
+//!   print the fingerprint of the wallet it restores
+//! - `khodpay address`: derive Bitcoin, Ethereum, Tron and XRP Ledger addresses
+//! - `khodpay xpub`: export an account's extended public key and, for Bitcoin, its
+//!   output descriptors for a watch-only wallet
+//! - `khodpay sign-evm`: sign an EIP-1559 or EIP-2930 transaction offline
+//! - `khodpay psbt inspect`: show what a PSBT spends and pays
+//!
+//! Output is one record per line with tab-separated fields. Secrets never appear on
+//! the command line: the mnemonic is read from standard input or `--mnemonic-file`
+//! and the BIP-39 passphrase from the `KHODPAY_PASSPHRASE` environment variable.
+//! Nothing touches the network.
+//!
+//! ```text
+//! $ khodpay address --coin btc --count 2 < mnemonic.txt
+//! m/84'/0'/0'/0/0    bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu
+//! m/84'/0'/0'/0/1    bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g
+//! ```
+
+mod error;
+mod evm;
+mod keys;
+mod mnemonic;
+mod psbt;
+mod wallet;
+
+use std::io::BufRead;
+use std::process::ExitCode;
+
+use clap::{ArgMatches, Command};
+use zeroize::Zeroizing;
+
+pub use error::{Error, Result};
+
+/// Returns the command-line interface.
+fn cli() -> Command {
+    Command::new("khodpay")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Offline wallet operations on the KhodPay libraries")
+        .after_help(format!(
+            "The mnemonic is read from standard input, or --mnemonic-file, and the \
+             BIP-39 passphrase from {}.",
+            wallet::PASSPHRASE_VAR
+        ))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(mnemonic::command())
+        .subcommand(keys::address_command())
+        .subcommand(keys::xpub_command())
+        .subcommand(evm::command())
+        .subcommand(psbt::command())
+}
+
+/// Runs the command in `matches`, reading any mnemonic or PSBT from `input`, and
+/// returns what to print.
+fn run(matches: &ArgMatches, input: &mut dyn BufRead, passphrase: &str) -> Result<String> {
+    match matches.subcommand() {
+        Some(("mnemonic", matches)) => mnemonic::run(matches, input, passphrase),
+        Some(("address", matches)) => keys::address(matches, input, passphrase),
+        Some(("xpub", matches)) => keys::xpub(matches, input, passphrase),
+        Some(("sign-evm", matches)) => evm::run(matches, input, passphrase),
+        Some(("psbt", matches)) => psbt::run(matches, input),
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    let passphrase = Zeroizing::new(std::env::var(wallet::PASSPHRASE_VAR).unwrap_or_default());
+    match run(&matches, &mut std::io::stdin().lock(), &passphrase) {
+        Ok(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The BIP-39 test mnemonic used throughout the libraries' test vectors.
+    pub const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Runs `khodpay <args>` with `input` on standard input.
+    pub fn run_with(args: &[&str], input: &str, passphrase: &str) -> Result<String> {
+        run_with_bytes(args, input.as_bytes(), passphrase)
+    }
+
+    /// Runs `khodpay <args>` with binary `input` on standard input.
+    pub fn run_with_bytes(args: &[&str], mut input: &[u8], passphrase: &str) -> Result<String> {
+        let matches = cli()
+            .try_get_matches_from(std::iter::once("khodpay").chain(args.iter().copied()))
+            .map_err(|e| Error::InvalidInput(e.to_string()))?;
+        run(&matches, &mut input, passphrase)
+    }
+
+    #[test]
+    fn test_cli_is_consistent() {
+        cli().debug_assert();
+    }
+
+    #[test]
+    fn test_mnemonic_file() {
+        let path = std::env::temp_dir().join(format!("khodpay-cli-{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{}\n", MNEMONIC)).unwrap();
+        let output = run_with(
+            &[
+                "address",
+                "--coin",
+                "eth",
+                "--mnemonic-file",
+                path.to_str().unwrap(),
+            ],
+            "",
+            "",
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            output.unwrap(),
+            "m/44'/60'/0'/0/0\t0x9858EfFD232B4033E47d90003D41EC34EcaEda94\n"
+        );
+        assert!(matches!(
+            run_with(
+                &[
+                    "address",
+                    "--coin",
+                    "eth",
+                    "--mnemonic-file",
+                    "/nonexistent"
+                ],
+                "",
+                ""
+            ),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_requires_subcommand() {
+        assert!(run_with(&[], "", "").is_err());
+        assert!(run_with(&["mnemonic"], "", "").is_err());
+        assert!(run_with(&["psbt"], "", "").is_err());
+    }
+}
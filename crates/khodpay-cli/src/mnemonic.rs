@@ -0,0 +1,108 @@
+//! `khodpay mnemonic`: generating and checking BIP-39 mnemonics.
+
+use std::io::BufRead;
+
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgMatches, Command};
+use khodpay_bip32::{ExtendedPrivateKey, Network};
+use khodpay_bip39::{Mnemonic, WordCount};
+use zeroize::Zeroizing;
+
+use crate::wallet::{self, PASSPHRASE_VAR};
+use crate::Result;
+
+/// Returns the `mnemonic` command.
+pub fn command() -> Command {
+    Command::new("mnemonic")
+        .about("Generate or check a BIP-39 mnemonic")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("generate")
+                .about("Generate a new mnemonic from the system's secure random source")
+                .arg(
+                    Arg::new("words")
+                        .long("words")
+                        .value_name("COUNT")
+                        .value_parser(PossibleValuesParser::new(["12", "15", "18", "21", "24"]))
+                        .default_value("24")
+                        .help("Number of words"),
+                )
+                .arg(wallet::language_arg()),
+        )
+        .subcommand(wallet::mnemonic_args(
+            Command::new("restore")
+                .about("Check a mnemonic and print the fingerprint of the wallet it restores")
+                .long_about(format!(
+                    "Check a mnemonic read from standard input or --mnemonic-file and print \
+                     the fingerprint of the wallet it restores, together with the passphrase \
+                     in {}. Compare the fingerprint with the one recorded at backup time to \
+                     catch a mistyped word or passphrase.",
+                    PASSPHRASE_VAR
+                )),
+        ))
+}
+
+/// Runs a `mnemonic` subcommand.
+pub fn run(matches: &ArgMatches, input: &mut dyn BufRead, passphrase: &str) -> Result<String> {
+    match matches.subcommand() {
+        Some(("generate", matches)) => generate(matches),
+        Some(("restore", matches)) => restore(matches, input, passphrase),
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+fn generate(matches: &ArgMatches) -> Result<String> {
+    let words = matches
+        .get_one::<String>("words")
+        .and_then(|words| words.parse().ok())
+        .unwrap_or(24);
+    let mnemonic = Mnemonic::generate(
+        WordCount::from_word_count(words)?,
+        wallet::language(matches),
+    )?;
+    Ok(format!("{}\n", mnemonic.phrase()))
+}
+
+fn restore(matches: &ArgMatches, input: &mut dyn BufRead, passphrase: &str) -> Result<String> {
+    let mnemonic = wallet::read_mnemonic(matches, input)?;
+    let seed = Zeroizing::new(mnemonic.to_seed(passphrase)?);
+    let master = ExtendedPrivateKey::from_seed(seed.as_slice(), Network::BitcoinMainnet)?;
+    Ok(format!(
+        "words\t{}\nlanguage\t{}\nfingerprint\t{}\n",
+        mnemonic.word_count().word_count(),
+        mnemonic.language().name(),
+        hex::encode(master.fingerprint())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{run_with, MNEMONIC};
+
+    #[test]
+    fn test_generate() {
+        let output = run_with(&["mnemonic", "generate", "--words", "12"], "", "").unwrap();
+        let phrase = output.trim_end();
+        assert_eq!(phrase.split(' ').count(), 12);
+
+        let restored = run_with(&["mnemonic", "restore"], phrase, "").unwrap();
+        assert!(restored.starts_with("words\t12\nlanguage\tEnglish\n"));
+        assert!(run_with(&["mnemonic", "generate", "--words", "13"], "", "").is_err());
+    }
+
+    #[test]
+    fn test_restore() {
+        // Words split across lines still parse
+        let input = MNEMONIC.replacen(' ', "\n", 3);
+        assert_eq!(
+            run_with(&["mnemonic", "restore"], &input, "").unwrap(),
+            "words\t12\nlanguage\tEnglish\nfingerprint\t73c5da0a\n"
+        );
+        let with_passphrase = run_with(&["mnemonic", "restore"], MNEMONIC, "TREZOR").unwrap();
+        assert!(!with_passphrase.ends_with("73c5da0a\n"));
+
+        let typo = MNEMONIC.replace("about", "abort");
+        assert!(run_with(&["mnemonic", "restore"], &typo, "").is_err());
+        assert!(run_with(&["mnemonic", "restore"], " \n", "").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! `khodpay psbt`: inspecting partially signed Bitcoin transactions.
+
+use std::io::BufRead;
+
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgMatches, Command};
+use khodpay_btc_signing::psbt::Psbt;
+use khodpay_btc_signing::Network;
+
+use crate::{Error, Result};
+
+/// Start of every serialized PSBT.
+const MAGIC: &[u8] = b"psbt\xff";
+
+/// Networks by their command-line name.
+const NETWORKS: [(&str, Network); 4] = [
+    ("bitcoin", Network::Bitcoin),
+    ("testnet", Network::Testnet),
+    ("signet", Network::Signet),
+    ("regtest", Network::Regtest),
+];
+
+/// Returns the `psbt` command.
+pub fn command() -> Command {
+    Command::new("psbt")
+        .about("Work with partially signed Bitcoin transactions (BIP-174/370)")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("inspect")
+                .about("Show what a PSBT spends and pays")
+                .long_about(
+                    "Show what a PSBT spends and pays, one tab-separated record per line: \
+                     the unsigned transaction id, then `input OUTPOINT AMOUNT ADDRESS STATUS` \
+                     for each input, `output AMOUNT ADDRESS` for each output (the script in \
+                     hex when it has no address) and the fee. Amounts are in satoshis; `-` \
+                     marks what the PSBT does not say. The PSBT is read from the argument or \
+                     from standard input, in base64 or binary.",
+                )
+                .arg(
+                    Arg::new("psbt")
+                        .value_name("PSBT")
+                        .help("PSBT in base64; read from standard input if omitted"),
+                )
+                .arg(
+                    Arg::new("network")
+                        .long("network")
+                        .value_name("NETWORK")
+                        .value_parser(PossibleValuesParser::new(NETWORKS.map(|(name, _)| name)))
+                        .default_value("bitcoin")
+                        .help("Network to show addresses for"),
+                ),
+        )
+}
+
+/// Runs a `psbt` subcommand.
+pub fn run(matches: &ArgMatches, input: &mut dyn BufRead) -> Result<String> {
+    match matches.subcommand() {
+        Some(("inspect", matches)) => inspect(matches, input),
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+fn inspect(matches: &ArgMatches, input: &mut dyn BufRead) -> Result<String> {
+    let psbt = match matches.get_one::<String>("psbt") {
+        Some(encoded) => Psbt::from_base64(encoded)?,
+        None => {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            if data.starts_with(MAGIC) {
+                Psbt::deserialize(&data)?
+            } else {
+                let encoded = std::str::from_utf8(&data).map_err(|_| {
+                    Error::InvalidInput("PSBT is neither base64 nor binary".to_string())
+                })?;
+                Psbt::from_base64(encoded)?
+            }
+        }
+    };
+    let name = matches.get_one::<String>("network").map(String::as_str);
+    let network = NETWORKS
+        .iter()
+        .find(|(candidate, _)| Some(*candidate) == name)
+        .map(|(_, network)| *network)
+        .unwrap_or(Network::Bitcoin);
+
+    let preview = psbt.preview(network, &[])?;
+    let mut output = format!("unsigned-txid\t{}\n", psbt.unsigned_tx()?.txid());
+    for input in &preview.inputs {
+        output.push_str(&format!(
+            "input\t{}\t{}\t{}\t{}\n",
+            input.previous_output,
+            or_dash(input.amount),
+            or_dash(input.address.as_ref()),
+            if input.is_signed {
+                "signed"
+            } else {
+                "unsigned"
+            }
+        ));
+    }
+    for paid in &preview.outputs {
+        let address = match &paid.address {
+            Some(address) => address.to_string(),
+            None => paid.script_pubkey.to_hex(),
+        };
+        output.push_str(&format!("output\t{}\t{}\n", paid.amount, address));
+    }
+    output.push_str(&format!("fee\t{}\n", or_dash(preview.fee)));
+    Ok(output)
+}
+
+/// Formats a value the PSBT may not carry.
+fn or_dash(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use khodpay_btc_signing::psbt::{PsbtInput, PsbtOutput};
+    use khodpay_btc_signing::{Address, OutPoint, Script, TxOut, Txid};
+
+    use super::*;
+    use crate::tests::{run_with, run_with_bytes};
+
+    const ADDRESS: &str = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+
+    fn sample() -> Psbt {
+        let paid = Address::parse(ADDRESS, Network::Bitcoin).unwrap();
+        let mut psbt = Psbt::new(2);
+        let mut input = PsbtInput::new(OutPoint {
+            txid: Txid::from_bytes([0x11; 32]),
+            vout: 1,
+        });
+        input.witness_utxo = Some(TxOut {
+            value: 50_000,
+            script_pubkey: paid.script_pubkey(),
+        });
+        psbt.add_input(input).unwrap();
+        psbt.add_input(PsbtInput::new(OutPoint {
+            txid: Txid::from_bytes([0x22; 32]),
+            vout: 0,
+        }))
+        .unwrap();
+        psbt.add_output(PsbtOutput::new(40_000, paid.script_pubkey()))
+            .unwrap();
+        psbt.add_output(PsbtOutput::new(0, Script::new(vec![0x6a, 0x01, 0x2a])))
+            .unwrap();
+        psbt
+    }
+
+    #[test]
+    fn test_inspect() {
+        let psbt = sample();
+        let expected = format!(
+            "unsigned-txid\t{}\n\
+             input\t{}:1\t50000\t{}\tunsigned\n\
+             input\t{}:0\t-\t-\tunsigned\n\
+             output\t40000\t{}\n\
+             output\t0\t6a012a\n\
+             fee\t-\n",
+            psbt.unsigned_tx().unwrap().txid(),
+            Txid::from_bytes([0x11; 32]),
+            ADDRESS,
+            Txid::from_bytes([0x22; 32]),
+            ADDRESS
+        );
+        let base64 = psbt.to_base64();
+        assert_eq!(
+            run_with(&["psbt", "inspect", &base64], "", "").unwrap(),
+            expected
+        );
+        // Base64 or binary on standard input
+        assert_eq!(
+            run_with(&["psbt", "inspect"], &format!("{}\n", base64), "").unwrap(),
+            expected
+        );
+        let binary = run_with_bytes(&["psbt", "inspect"], &psbt.serialize(), "");
+        assert_eq!(binary.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_inspect_network() {
+        let base64 = sample().to_base64();
+        let output = run_with(
+            &["psbt", "inspect", "--network", "testnet", &base64],
+            "",
+            "",
+        )
+        .unwrap();
+        assert!(output.contains("\noutput\t40000\ttb1q"));
+        assert!(run_with(&["psbt", "inspect", "cHNidP8="], "", "").is_err());
+        assert!(run_with(&["psbt", "inspect"], "not a psbt", "").is_err());
+    }
+}
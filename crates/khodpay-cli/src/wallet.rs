@@ -0,0 +1,101 @@
+//! Reading the wallet's secrets.
+//!
+//! The mnemonic is read from `--mnemonic-file` or, by default, from standard input
+//! until end of file, and the BIP-39 passphrase from [`PASSPHRASE_VAR`]. Neither is
+//! ever taken from the command line, where other users of the machine can read it in
+//! the process list and where it ends up in shell history.
+
+use std::io::BufRead;
+use std::path::PathBuf;
+
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use khodpay_bip32::Network;
+use khodpay_bip39::{Language, Mnemonic};
+use khodpay_bip44::Wallet;
+use zeroize::Zeroizing;
+
+use crate::{Error, Result};
+
+/// Environment variable holding the BIP-39 passphrase; unset means none.
+pub const PASSPHRASE_VAR: &str = "KHODPAY_PASSPHRASE";
+
+/// Word lists by their command-line name.
+const LANGUAGES: [(&str, Language); 9] = [
+    ("english", Language::English),
+    ("japanese", Language::Japanese),
+    ("korean", Language::Korean),
+    ("french", Language::French),
+    ("italian", Language::Italian),
+    ("spanish", Language::Spanish),
+    ("simplified-chinese", Language::SimplifiedChinese),
+    ("traditional-chinese", Language::TraditionalChinese),
+    ("czech", Language::Czech),
+];
+
+/// Returns the `--language` argument.
+pub fn language_arg() -> Arg {
+    Arg::new("language")
+        .long("language")
+        .value_name("LANGUAGE")
+        .value_parser(PossibleValuesParser::new(LANGUAGES.map(|(name, _)| name)))
+        .default_value("english")
+        .help("Word list of the mnemonic")
+}
+
+/// Adds the arguments that say where to read the mnemonic from.
+pub fn mnemonic_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("mnemonic-file")
+                .long("mnemonic-file")
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .help("Read the mnemonic from PATH instead of standard input"),
+        )
+        .arg(language_arg())
+}
+
+/// Returns the word list chosen with `--language`.
+pub fn language(matches: &ArgMatches) -> Language {
+    let name = matches
+        .get_one::<String>("language")
+        .map(String::as_str)
+        .unwrap_or("english");
+    LANGUAGES
+        .iter()
+        .find(|(candidate, _)| *candidate == name)
+        .map(|(_, language)| *language)
+        .unwrap_or(Language::English)
+}
+
+/// Reads and validates the mnemonic from `--mnemonic-file` or `input`.
+///
+/// Words may be separated by any whitespace, including line breaks.
+pub fn read_mnemonic(matches: &ArgMatches, input: &mut dyn BufRead) -> Result<Mnemonic> {
+    let text = Zeroizing::new(match matches.get_one::<PathBuf>("mnemonic-file") {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => {
+            let mut text = String::new();
+            input.read_to_string(&mut text)?;
+            text
+        }
+    });
+    let phrase = Zeroizing::new(text.split_whitespace().collect::<Vec<_>>().join(" "));
+    if phrase.is_empty() {
+        return Err(Error::InvalidInput("no mnemonic given".to_string()));
+    }
+    Ok(Mnemonic::from_phrase(&phrase, language(matches))?)
+}
+
+/// Reads the mnemonic and opens the wallet it and `passphrase` derive.
+pub fn open(
+    matches: &ArgMatches,
+    input: &mut dyn BufRead,
+    passphrase: &str,
+    network: Network,
+) -> Result<Wallet> {
+    let mnemonic = read_mnemonic(matches, input)?;
+    let seed = Zeroizing::new(mnemonic.to_seed(passphrase)?);
+    Ok(Wallet::from_seed(seed.as_slice(), network)?)
+}
@@ -0,0 +1,79 @@
+//! Checks against alloy: EVM addresses and EIP-191 signatures of keys derived by
+//! alloy's own mnemonic support.
+
+use alloy_signer::SignerSync;
+use alloy_signer_local::coins_bip39::English;
+use alloy_signer_local::{MnemonicBuilder, PrivateKeySigner};
+
+use crate::checker::{TestWallet, CHECK_MESSAGE};
+use crate::derive::{self, Value};
+use crate::report::{Check, CheckResult, CompatReport, Reference};
+
+/// Compares the first `address_count` EVM addresses, and a signature by the first
+/// key over [`CHECK_MESSAGE`].
+pub(crate) fn check(report: &mut CompatReport, wallet: &TestWallet, address_count: u32) {
+    let seed = derive::seed(&wallet.phrase, &wallet.passphrase);
+    let seed = seed.as_ref().map_err(Clone::clone);
+
+    for index in 0..address_count {
+        let signer = signer(wallet, index);
+        report.push(compare(
+            Check::EvmAddress,
+            format!("{} m/44'/60'/0'/0/{}", wallet.label, index),
+            signer.map(|signer| signer.address().to_checksum(None)),
+            seed.clone()
+                .and_then(|seed| derive::evm_address(&seed[..], index)),
+        ));
+    }
+
+    let expected = signer(wallet, 0).and_then(|signer| {
+        let signature = signer
+            .sign_message_sync(CHECK_MESSAGE)
+            .map_err(|e| e.to_string())?;
+        Ok(format!("0x{}", hex::encode(signature.as_bytes())))
+    });
+    report.push(compare(
+        Check::EvmSignature,
+        format!("{} EIP-191 m/44'/60'/0'/0/0", wallet.label),
+        expected,
+        seed.and_then(|seed| derive::evm_message_signature(&seed[..], 0, CHECK_MESSAGE)),
+    ));
+}
+
+fn compare(check: Check, case: String, expected: Value, actual: Value) -> CheckResult {
+    CheckResult::compare(check, Reference::Alloy, case, expected, actual)
+}
+
+fn signer(wallet: &TestWallet, index: u32) -> Value<PrivateKeySigner> {
+    MnemonicBuilder::<English>::default()
+        .phrase(wallet.phrase.as_str())
+        .password(wallet.passphrase.as_str())
+        .index(index)
+        .and_then(|builder| builder.build())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vectors::{ADDRESS_MNEMONIC, EVM_ADDRESS_VECTOR};
+
+    #[test]
+    fn test_reference_matches_vectors() {
+        let wallet = TestWallet::new("wallet 0", ADDRESS_MNEMONIC, "");
+        assert_eq!(
+            signer(&wallet, 0).unwrap().address().to_checksum(None),
+            EVM_ADDRESS_VECTOR
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let wallet = TestWallet::new("wallet 0", ADDRESS_MNEMONIC, "TREZOR");
+        let mut report = CompatReport::default();
+        check(&mut report, &wallet, 3);
+        assert!(report.is_compatible(), "{}", report);
+        assert_eq!(report.results.len(), 4);
+        assert_eq!(report.results[3].check, Check::EvmSignature);
+    }
+}
@@ -0,0 +1,150 @@
+//! The checker: which wallets to derive and which references to compare against.
+
+use zeroize::Zeroizing;
+
+use crate::report::CompatReport;
+use crate::vectors::{self, ADDRESS_MNEMONIC};
+
+/// The message signed by the EIP-191 signature checks.
+pub const CHECK_MESSAGE: &[u8] = b"KhodPay compatibility check";
+
+/// The number of receive addresses compared per account by default.
+pub const DEFAULT_ADDRESS_COUNT: u32 = 5;
+
+/// A mnemonic whose keys are derived by both KhodPay and the references.
+#[cfg_attr(not(any(feature = "bitcoin", feature = "alloy")), allow(dead_code))]
+pub(crate) struct TestWallet {
+    /// Names the wallet in the report instead of its mnemonic.
+    pub(crate) label: String,
+    pub(crate) phrase: Zeroizing<String>,
+    pub(crate) passphrase: Zeroizing<String>,
+}
+
+impl TestWallet {
+    pub(crate) fn new(label: &str, phrase: &str, passphrase: &str) -> Self {
+        TestWallet {
+            label: label.to_string(),
+            phrase: Zeroizing::new(phrase.to_string()),
+            passphrase: Zeroizing::new(passphrase.to_string()),
+        }
+    }
+}
+
+/// Runs KhodPay against the bundled test vectors and every reference
+/// implementation compiled in.
+///
+/// The bundled vectors always run. With the `bitcoin` feature, seeds, account keys
+/// and Bitcoin addresses are compared against rust-bitcoin; with `alloy`, EVM
+/// addresses and signatures against alloy. Reference checks derive from the
+/// `abandon … about` mnemonic, plus any added with
+/// [`with_mnemonic`](Self::with_mnemonic).
+///
+/// Reports only ever contain public values (extended public keys, addresses,
+/// signatures and seed digests), but prefer dedicated test mnemonics over a user's
+/// own.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_compat::CompatChecker;
+///
+/// let report = CompatChecker::new().run();
+/// assert!(report.is_compatible(), "{}", report);
+/// ```
+pub struct CompatChecker {
+    wallets: Vec<TestWallet>,
+    address_count: u32,
+}
+
+impl CompatChecker {
+    /// Creates a checker over the bundled test mnemonic.
+    pub fn new() -> Self {
+        CompatChecker {
+            wallets: vec![TestWallet::new("wallet 0", ADDRESS_MNEMONIC, "")],
+            address_count: DEFAULT_ADDRESS_COUNT,
+        }
+    }
+
+    /// Adds an English mnemonic and passphrase for the reference checks. It appears
+    /// in the report as `wallet <n>`, numbered from 1 in the order added.
+    pub fn with_mnemonic(mut self, phrase: &str, passphrase: &str) -> Self {
+        let label = format!("wallet {}", self.wallets.len());
+        self.wallets
+            .push(TestWallet::new(&label, phrase, passphrase));
+        self
+    }
+
+    /// Sets the number of receive addresses compared per account.
+    pub fn with_address_count(mut self, count: u32) -> Self {
+        self.address_count = count;
+        self
+    }
+
+    /// Runs every check and returns the report.
+    #[cfg_attr(
+        not(any(feature = "bitcoin", feature = "alloy")),
+        allow(unused_variables)
+    )]
+    pub fn run(&self) -> CompatReport {
+        let mut report = CompatReport::default();
+        vectors::check(&mut report);
+        let address_count = self.address_count;
+        for wallet in &self.wallets {
+            #[cfg(feature = "bitcoin")]
+            crate::rust_bitcoin::check(&mut report, wallet, address_count);
+            #[cfg(feature = "alloy")]
+            crate::alloy::check(&mut report, wallet, address_count);
+        }
+        report
+    }
+}
+
+impl Default for CompatChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{Check, Outcome, Reference};
+
+    #[test]
+    fn test_run() {
+        let report = CompatChecker::new()
+            .with_mnemonic(crate::vectors::BIP39_VECTORS[1].mnemonic, "TREZOR")
+            .with_address_count(2)
+            .run();
+        assert!(report.is_compatible(), "{}", report);
+        assert_eq!(report.references(), Reference::available());
+        assert!(report.to_string().starts_with(&format!(
+            "{} of {} checks passed against test-vectors",
+            report.results.len(),
+            report.results.len()
+        )));
+        if cfg!(feature = "bitcoin") {
+            assert!(report
+                .results
+                .iter()
+                .any(|result| result.case == "wallet 1 m/84'/0'/0'/0/1"));
+        }
+    }
+
+    #[test]
+    fn test_invalid_mnemonic_fails() {
+        let report = CompatChecker::new()
+            .with_mnemonic("abandon abandon", "")
+            .run();
+        // Without a reference, the extra mnemonic is never derived
+        assert_eq!(
+            report.is_compatible(),
+            !cfg!(any(feature = "bitcoin", feature = "alloy"))
+        );
+        for failure in report.failures() {
+            assert!(failure.case.starts_with("wallet 1"));
+            assert!(matches!(failure.outcome, Outcome::Error { .. }));
+            assert_ne!(failure.check, Check::Bip39Mnemonic);
+        }
+    }
+}
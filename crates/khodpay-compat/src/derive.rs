@@ -0,0 +1,111 @@
+//! KhodPay's side of every comparison, computed through the same public APIs
+//! embedders call. Errors are flattened to strings for the report.
+
+use std::str::FromStr;
+
+use khodpay_bip32::{DerivationPath, ExtendedPrivateKey, Network};
+use khodpay_bip39::{Language, Mnemonic};
+use khodpay_bip44::{CoinType, Purpose, Wallet};
+use khodpay_btc_signing::Address;
+use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+use khodpay_signing::{Bip44Signer, Signature};
+use zeroize::Zeroizing;
+
+/// The Bitcoin families whose addresses are compared.
+#[cfg(feature = "bitcoin")]
+pub(crate) const BITCOIN_PURPOSES: [Purpose; 4] = [
+    Purpose::BIP44,
+    Purpose::BIP49,
+    Purpose::BIP84,
+    Purpose::BIP86,
+];
+
+/// Result of one KhodPay computation.
+pub(crate) type Value<T = String> = Result<T, String>;
+
+/// Returns the English mnemonic of `entropy`.
+pub(crate) fn mnemonic(entropy: &[u8]) -> Value {
+    let mnemonic = Mnemonic::new(entropy, Language::English).map_err(|e| e.to_string())?;
+    Ok(mnemonic.phrase().to_string())
+}
+
+/// Returns the BIP-39 seed of an English mnemonic and passphrase.
+pub(crate) fn seed(phrase: &str, passphrase: &str) -> Value<Zeroizing<[u8; 64]>> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English).map_err(|e| e.to_string())?;
+    Ok(Zeroizing::new(
+        mnemonic.to_seed(passphrase).map_err(|e| e.to_string())?,
+    ))
+}
+
+/// Returns the extended private key at `path` of a seed.
+pub(crate) fn xprv(seed: &[u8], path: &str) -> Value<ExtendedPrivateKey> {
+    let path = DerivationPath::from_str(path).map_err(|e| e.to_string())?;
+    ExtendedPrivateKey::from_seed(seed, Network::BitcoinMainnet)
+        .and_then(|master| master.derive_path(&path))
+        .map_err(|e| e.to_string())
+}
+
+/// Returns the extended public key at `path` of a seed.
+#[cfg(feature = "bitcoin")]
+pub(crate) fn xpub(seed: &[u8], path: &str) -> Value {
+    Ok(xprv(seed, path)?.to_extended_public_key().to_string())
+}
+
+/// Returns the mainnet receive address at `index` of account 0 under `purpose`.
+pub(crate) fn bitcoin_address(seed: &[u8], purpose: Purpose, index: u32) -> Value {
+    let mut wallet = wallet(seed)?;
+    let account = wallet
+        .get_account(purpose, CoinType::Bitcoin, 0)
+        .map_err(|e| e.to_string())?;
+    let key = account.derive_external(index).map_err(|e| e.to_string())?;
+    let public_key = key.to_extended_public_key().public_key().to_bytes();
+    let network = khodpay_btc_signing::Network::Bitcoin;
+    let address = match purpose {
+        Purpose::BIP44 => Address::p2pkh(&public_key, network),
+        Purpose::BIP49 => Address::p2sh_p2wpkh(&public_key, network),
+        Purpose::BIP84 => Address::p2wpkh(&public_key, network),
+        Purpose::BIP86 => Address::p2tr(&public_key, network).map_err(|e| e.to_string())?,
+    };
+    Ok(address.to_string())
+}
+
+/// Returns the checksummed EVM address at `m/44'/60'/0'/0/index`.
+pub(crate) fn evm_address(seed: &[u8], index: u32) -> Value {
+    Ok(evm_signer(seed, index)?.address().to_string())
+}
+
+/// Returns the EIP-191 signature over `message` by the key at `m/44'/60'/0'/0/index`.
+#[cfg(feature = "alloy")]
+pub(crate) fn evm_message_signature(seed: &[u8], index: u32, message: &[u8]) -> Value {
+    let signature = evm_signer(seed, index)?
+        .sign_message(message)
+        .map_err(|e| e.to_string())?;
+    Ok(signature_hex(&signature))
+}
+
+/// Returns the EIP-712 signature over a JSON typed data payload by `private_key`.
+pub(crate) fn typed_data_signature(private_key: &[u8; 32], json: &str) -> Value {
+    let signer = Bip44Signer::from_private_key(private_key).map_err(|e| e.to_string())?;
+    let typed_data = TypedData::from_json(json).map_err(|e| e.to_string())?;
+    let signature = sign_typed_data_json(&signer, &typed_data).map_err(|e| e.to_string())?;
+    Ok(signature_hex(&signature))
+}
+
+/// Encodes a signature as `0x ‖ r ‖ s ‖ v`, with `v` as 27 or 28 as wallets return it.
+pub(crate) fn signature_hex(signature: &Signature) -> String {
+    let mut bytes = signature.to_bytes();
+    bytes[64] += 27;
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn wallet(seed: &[u8]) -> Value<Wallet> {
+    Wallet::from_seed(seed, Network::BitcoinMainnet).map_err(|e| e.to_string())
+}
+
+fn evm_signer(seed: &[u8], index: u32) -> Value<Bip44Signer> {
+    let mut wallet = wallet(seed)?;
+    let account = wallet
+        .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+        .map_err(|e| e.to_string())?;
+    Bip44Signer::new(account, index).map_err(|e| e.to_string())
+}
@@ -0,0 +1,697 @@
+//! Deterministic fixture sets for downstream integration tests (requires the
+//! `fixtures` feature).
+//!
+//! [`FixtureGenerator`] derives everything an app's tests usually hard-code from
+//! one test mnemonic: account extended public keys, receive and change addresses
+//! for every Bitcoin purpose on mainnet and testnet and for EVM chains, signed
+//! sample transactions and message signatures. The same mnemonic, passphrase and
+//! address count always produce the same [`FixtureSet`], byte for byte once
+//! written with [`FixtureSet::to_json`], so app teams can commit the JSON as a
+//! golden file and fail their tests when a KhodPay upgrade changes any of it.
+//!
+//! Signatures are deterministic (RFC 6979 ECDSA); taproot accounts, whose Schnorr
+//! signatures are randomized, get addresses but no sample transaction.
+//!
+//! The set of the default `abandon … about` mnemonic is bundled as
+//! [`DEFAULT_FIXTURES_JSON`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_compat::fixtures::{FixtureGenerator, FixtureSet, DEFAULT_FIXTURES_JSON};
+//!
+//! let fixtures = FixtureGenerator::new().generate().unwrap();
+//! assert_eq!(fixtures.to_json(), DEFAULT_FIXTURES_JSON);
+//!
+//! // In an app's tests: derive with the app's own code, compare with the golden file
+//! let golden = FixtureSet::from_json(DEFAULT_FIXTURES_JSON).unwrap();
+//! let address = golden.address("m/84'/0'/0'/0/0").unwrap();
+//! assert_eq!(address.address, "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+//! ```
+
+use std::fmt;
+
+use khodpay_bip32::Network;
+use khodpay_bip39::{Language, Mnemonic};
+use khodpay_bip44::{Account, Chain, CoinType, Purpose, Wallet};
+use khodpay_btc_signing::{bip322, Address, BtcSigner, OutPoint, TransactionBuilder, Txid, Utxo};
+use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, SignedTransaction, Wei};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::checker::DEFAULT_ADDRESS_COUNT;
+use crate::derive::signature_hex;
+use crate::vectors::{ADDRESS_MNEMONIC, TYPED_DATA_JSON};
+
+/// The `format` of every fixture file.
+pub const FIXTURE_FORMAT: &str = "khodpay-fixtures";
+
+/// The `version` of the fixture files this crate writes.
+pub const FIXTURE_VERSION: u32 = 1;
+
+/// The message signed by the BIP-322 and EIP-191 fixtures.
+pub const FIXTURE_MESSAGE: &str = "KhodPay fixture";
+
+/// The fixture set of [`ADDRESS_MNEMONIC`] with [`DEFAULT_ADDRESS_COUNT`] addresses
+/// per chain, as written by [`FixtureSet::to_json`].
+pub const DEFAULT_FIXTURES_JSON: &str = include_str!("../fixtures/default.json");
+
+/// The Bitcoin purposes fixtures cover.
+const BITCOIN_PURPOSES: [Purpose; 4] = [
+    Purpose::BIP44,
+    Purpose::BIP49,
+    Purpose::BIP84,
+    Purpose::BIP86,
+];
+
+/// Value of the made-up output each sample Bitcoin transaction spends.
+const FUNDING_VALUE: u64 = 100_000;
+/// Amount each sample Bitcoin transaction pays.
+const BITCOIN_AMOUNT: u64 = 40_000;
+/// Fee of each sample Bitcoin transaction.
+const BITCOIN_FEE: u64 = 1_000;
+
+/// The EVM chains sample transactions are signed for.
+const EVM_CHAINS: [ChainId; 2] = [ChainId::EthereumMainnet, ChainId::BscMainnet];
+
+/// The network a fixture belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureNetwork {
+    /// Bitcoin mainnet, coin type 0.
+    Bitcoin,
+    /// Bitcoin testnet, coin type 1.
+    BitcoinTestnet,
+    /// EVM chains, coin type 60; transactions carry their chain id.
+    Evm,
+}
+
+/// The kind of a sample transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransactionKind {
+    /// Bitcoin spend from a BIP-44 legacy address.
+    P2pkh,
+    /// Bitcoin spend from a BIP-49 nested SegWit address.
+    P2shP2wpkh,
+    /// Bitcoin spend from a BIP-84 native SegWit address.
+    P2wpkh,
+    /// EIP-1559 native coin transfer.
+    Eip1559,
+}
+
+/// The kind of a message signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// BIP-322 simple signature, base64.
+    Bip322,
+    /// EIP-191 `personal_sign`, `0x ‖ r ‖ s ‖ v` with `v` as 27 or 28.
+    Eip191,
+    /// EIP-712 typed data, `0x ‖ r ‖ s ‖ v` with `v` as 27 or 28.
+    Eip712,
+}
+
+/// An account's extended public key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountFixture {
+    /// The account's network.
+    pub network: FixtureNetwork,
+    /// The account path, such as `m/84'/0'/0'`.
+    pub path: String,
+    /// The SLIP-132 account key: `xpub`, `ypub` or `zpub` (`tpub`, `upub` or
+    /// `vpub` on testnet).
+    pub xpub: String,
+}
+
+/// An address and its public key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressFixture {
+    /// The address's network.
+    pub network: FixtureNetwork,
+    /// The key path, such as `m/84'/0'/0'/0/0`.
+    pub path: String,
+    /// The address, checksummed for EVM.
+    pub address: String,
+    /// The compressed public key in hex.
+    pub public_key: String,
+}
+
+/// A signed sample transaction.
+///
+/// Bitcoin samples spend a made-up output of 100 000 satoshis at the
+/// first receive address of the account, paying the second receive address and
+/// sending change to the first change address. EVM samples transfer from the first
+/// to the second receive address with nonce 0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionFixture {
+    /// The transaction's network.
+    pub network: FixtureNetwork,
+    /// The EVM chain id, for EVM transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// The transaction kind.
+    pub kind: TransactionKind,
+    /// Path of the signing key.
+    pub path: String,
+    /// The recipient address.
+    pub to: String,
+    /// The amount paid, in satoshis or wei, as a decimal string.
+    pub amount: String,
+    /// The signed transaction in hex, `0x`-prefixed for EVM.
+    pub raw: String,
+    /// The transaction id (Bitcoin) or hash (EVM).
+    pub txid: String,
+}
+
+/// A signed message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageFixture {
+    /// The signer's network.
+    pub network: FixtureNetwork,
+    /// The signature scheme.
+    pub kind: MessageKind,
+    /// Path of the signing key.
+    pub path: String,
+    /// The signing address.
+    pub address: String,
+    /// The message, or the typed data JSON for EIP-712.
+    pub message: String,
+    /// The signature.
+    pub signature: String,
+}
+
+/// Everything a [`FixtureGenerator`] derives from one mnemonic.
+///
+/// Fixtures are listed in a fixed order: Bitcoin mainnet, then testnet, by
+/// purpose, then EVM; addresses by chain, then index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureSet {
+    /// Always [`FIXTURE_FORMAT`].
+    pub format: String,
+    /// The version of the file layout, [`FIXTURE_VERSION`].
+    pub version: u32,
+    /// The English test mnemonic.
+    pub mnemonic: String,
+    /// The BIP-39 passphrase.
+    pub passphrase: String,
+    /// Receive and change addresses derived per account.
+    pub address_count: u32,
+    /// Account 0 of every covered purpose and network.
+    pub accounts: Vec<AccountFixture>,
+    /// The first `address_count` receive and change addresses of every account.
+    pub addresses: Vec<AddressFixture>,
+    /// Signed sample transactions.
+    pub transactions: Vec<TransactionFixture>,
+    /// Signed sample messages.
+    pub messages: Vec<MessageFixture>,
+}
+
+impl FixtureSet {
+    /// Writes the set as pretty-printed JSON with a trailing newline.
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("fixtures serialize to JSON");
+        json.push('\n');
+        json
+    }
+
+    /// Reads a set written by [`to_json`](Self::to_json).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FixtureError`] if the JSON is malformed, is not a fixture file
+    /// or has a newer version than this crate writes.
+    pub fn from_json(json: &str) -> Result<Self, FixtureError> {
+        let set: FixtureSet =
+            serde_json::from_str(json).map_err(|e| FixtureError::new("json", e))?;
+        if set.format != FIXTURE_FORMAT {
+            return Err(FixtureError::new(
+                "format",
+                format!("expected {}, got {}", FIXTURE_FORMAT, set.format),
+            ));
+        }
+        if set.version > FIXTURE_VERSION {
+            return Err(FixtureError::new(
+                "version",
+                format!("version {} is newer than {}", set.version, FIXTURE_VERSION),
+            ));
+        }
+        Ok(set)
+    }
+
+    /// Returns the address fixture at `path`, such as `m/84'/0'/0'/0/0`.
+    pub fn address(&self, path: &str) -> Option<&AddressFixture> {
+        self.addresses.iter().find(|address| address.path == path)
+    }
+
+    /// Returns the account fixture at `path`, such as `m/84'/0'/0'`.
+    pub fn account(&self, path: &str) -> Option<&AccountFixture> {
+        self.accounts.iter().find(|account| account.path == path)
+    }
+}
+
+/// A fixture that could not be generated or a file that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureError {
+    /// What was being generated or read, such as `m/49'/1'/0'` or `json`.
+    pub case: String,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl FixtureError {
+    fn new(case: impl Into<String>, message: impl fmt::Display) -> Self {
+        FixtureError {
+            case: case.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.case, self.message)
+    }
+}
+
+impl std::error::Error for FixtureError {}
+
+/// Generates a [`FixtureSet`] from a test mnemonic.
+///
+/// Use dedicated test mnemonics only: the set holds the mnemonic itself, and its
+/// addresses and signatures are meant to be committed to source control.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_compat::fixtures::FixtureGenerator;
+///
+/// let fixtures = FixtureGenerator::new()
+///     .with_mnemonic(
+///         "legal winner thank year wave sausage worth useful legal winner thank yellow",
+///         "TREZOR",
+///     )
+///     .with_address_count(2)
+///     .generate()
+///     .unwrap();
+/// std::fs::write("khodpay-fixtures.json", fixtures.to_json()).unwrap();
+/// # std::fs::remove_file("khodpay-fixtures.json").unwrap();
+/// ```
+pub struct FixtureGenerator {
+    phrase: Zeroizing<String>,
+    passphrase: Zeroizing<String>,
+    address_count: u32,
+}
+
+impl FixtureGenerator {
+    /// Creates a generator over [`ADDRESS_MNEMONIC`] with an empty passphrase and
+    /// [`DEFAULT_ADDRESS_COUNT`] addresses per chain.
+    pub fn new() -> Self {
+        FixtureGenerator {
+            phrase: Zeroizing::new(ADDRESS_MNEMONIC.to_string()),
+            passphrase: Zeroizing::new(String::new()),
+            address_count: DEFAULT_ADDRESS_COUNT,
+        }
+    }
+
+    /// Generates from another English mnemonic and passphrase.
+    pub fn with_mnemonic(mut self, phrase: &str, passphrase: &str) -> Self {
+        self.phrase = Zeroizing::new(phrase.to_string());
+        self.passphrase = Zeroizing::new(passphrase.to_string());
+        self
+    }
+
+    /// Sets the number of receive and change addresses derived per account.
+    pub fn with_address_count(mut self, count: u32) -> Self {
+        self.address_count = count;
+        self
+    }
+
+    /// Derives the fixture set.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FixtureError`] if the mnemonic is invalid or a derivation or
+    /// signature fails.
+    pub fn generate(&self) -> Result<FixtureSet, FixtureError> {
+        let mnemonic = Mnemonic::from_phrase(&self.phrase, Language::English)
+            .map_err(|e| FixtureError::new("mnemonic", e))?;
+        let seed = Zeroizing::new(
+            mnemonic
+                .to_seed(&self.passphrase)
+                .map_err(|e| FixtureError::new("seed", e))?,
+        );
+
+        let mut set = FixtureSet {
+            format: FIXTURE_FORMAT.to_string(),
+            version: FIXTURE_VERSION,
+            mnemonic: mnemonic.phrase().to_string(),
+            passphrase: self.passphrase.to_string(),
+            address_count: self.address_count,
+            accounts: Vec::new(),
+            addresses: Vec::new(),
+            transactions: Vec::new(),
+            messages: Vec::new(),
+        };
+
+        for (network, coin_type, bip32_network) in [
+            (
+                FixtureNetwork::Bitcoin,
+                CoinType::Bitcoin,
+                Network::BitcoinMainnet,
+            ),
+            (
+                FixtureNetwork::BitcoinTestnet,
+                CoinType::BitcoinTestnet,
+                Network::BitcoinTestnet,
+            ),
+        ] {
+            let mut wallet = Wallet::from_seed(&seed[..], bip32_network)
+                .map_err(|e| FixtureError::new("master key", e))?;
+            for purpose in BITCOIN_PURPOSES {
+                let account = wallet
+                    .get_account(purpose, coin_type, 0)
+                    .map_err(|e| FixtureError::new(account_path(purpose, coin_type), e))?;
+                self.add_account(&mut set, network, account)?;
+                self.add_bitcoin_samples(&mut set, network, account)?;
+            }
+        }
+
+        let mut wallet = Wallet::from_seed(&seed[..], Network::BitcoinMainnet)
+            .map_err(|e| FixtureError::new("master key", e))?;
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .map_err(|e| FixtureError::new(account_path(Purpose::BIP44, CoinType::Ethereum), e))?;
+        self.add_account(&mut set, FixtureNetwork::Evm, account)?;
+        self.add_evm_samples(&mut set, account)?;
+
+        Ok(set)
+    }
+
+    /// Adds the account key and its receive and change addresses.
+    fn add_account(
+        &self,
+        set: &mut FixtureSet,
+        network: FixtureNetwork,
+        account: &Account,
+    ) -> Result<(), FixtureError> {
+        let base = account_path(account.purpose(), account.coin_type());
+        set.accounts.push(AccountFixture {
+            network,
+            path: base.clone(),
+            xpub: account.to_watch_only().to_string(),
+        });
+        // EVM wallets only use receive addresses
+        let chains: &[Chain] = match network {
+            FixtureNetwork::Evm => &[Chain::External],
+            _ => &[Chain::External, Chain::Internal],
+        };
+        for &chain in chains {
+            for index in 0..self.address_count {
+                let path = format!("{}/{}/{}", base, chain.value(), index);
+                let public_key = account
+                    .derive_address(chain, index)
+                    .map_err(|e| FixtureError::new(&path, e))?
+                    .to_extended_public_key()
+                    .public_key()
+                    .to_bytes();
+                let address = match network {
+                    FixtureNetwork::Evm => Bip44Signer::new(account, index)
+                        .map(|signer| signer.address().to_string())
+                        .map_err(|e| FixtureError::new(&path, e))?,
+                    _ => bitcoin_address(account, &public_key)
+                        .map_err(|e| FixtureError::new(&path, e))?
+                        .to_string(),
+                };
+                set.addresses.push(AddressFixture {
+                    network,
+                    path,
+                    address,
+                    public_key: hex::encode(public_key),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a sample spend for a non-taproot account, and a BIP-322 signature for a
+    /// BIP-84 one.
+    fn add_bitcoin_samples(
+        &self,
+        set: &mut FixtureSet,
+        network: FixtureNetwork,
+        account: &Account,
+    ) -> Result<(), FixtureError> {
+        let kind = match account.purpose() {
+            Purpose::BIP44 => TransactionKind::P2pkh,
+            Purpose::BIP49 => TransactionKind::P2shP2wpkh,
+            Purpose::BIP84 => TransactionKind::P2wpkh,
+            Purpose::BIP86 => return Ok(()),
+        };
+        let base = account_path(account.purpose(), account.coin_type());
+        let path = format!("{}/0/0", base);
+        let error = |e: khodpay_btc_signing::Error| FixtureError::new(&path, e);
+
+        let signer = BtcSigner::new(account, Chain::External, 0).map_err(error)?;
+        let recipient = BtcSigner::new(account, Chain::External, 1)
+            .map_err(error)?
+            .address();
+        let change = BtcSigner::new(account, Chain::Internal, 0)
+            .map_err(error)?
+            .address();
+        // Stands in for a funding transaction; only its id is committed to
+        let funding = OutPoint::new(Txid::from_bytes([account.purpose().value() as u8; 32]), 0);
+        let tx = TransactionBuilder::new(signer.network())
+            .add_utxo(Utxo::new(
+                funding,
+                FUNDING_VALUE,
+                signer.address().script_pubkey(),
+            ))
+            .add_output(&recipient, BITCOIN_AMOUNT)
+            .change_address(&change)
+            .fee(BITCOIN_FEE)
+            .sign(&[&signer])
+            .map_err(error)?;
+        set.transactions.push(TransactionFixture {
+            network,
+            chain_id: None,
+            kind,
+            path: path.clone(),
+            to: recipient.to_string(),
+            amount: BITCOIN_AMOUNT.to_string(),
+            raw: tx.to_hex(),
+            txid: tx.txid().to_string(),
+        });
+
+        // One BIP-322 simple signature per network, from the BIP-84 account
+        if kind != TransactionKind::P2wpkh {
+            return Ok(());
+        }
+        let address = signer.address();
+        let signature =
+            bip322::sign_simple(&signer, &address, FIXTURE_MESSAGE.as_bytes()).map_err(error)?;
+        set.messages.push(MessageFixture {
+            network,
+            kind: MessageKind::Bip322,
+            path: path.clone(),
+            address: address.to_string(),
+            message: FIXTURE_MESSAGE.to_string(),
+            signature,
+        });
+        Ok(())
+    }
+
+    /// Adds a transfer per EVM chain, and EIP-191 and EIP-712 signatures.
+    fn add_evm_samples(&self, set: &mut FixtureSet, account: &Account) -> Result<(), FixtureError> {
+        let base = account_path(Purpose::BIP44, CoinType::Ethereum);
+        let path = format!("{}/0/0", base);
+        let error = |e: khodpay_signing::Error| FixtureError::new(&path, e);
+
+        let signer = Bip44Signer::new(account, 0).map_err(error)?;
+        let recipient = Bip44Signer::new(account, 1).map_err(error)?.address();
+        let amount = Wei::from_gwei(10_000_000);
+        for chain_id in EVM_CHAINS {
+            let tx = Eip1559Transaction::builder()
+                .chain_id(chain_id)
+                .nonce(0)
+                .to(recipient)
+                .value(amount)
+                .gas_limit(21_000)
+                .max_fee_per_gas(Wei::from_gwei(30))
+                .max_priority_fee_per_gas(Wei::from_gwei(1))
+                .build()
+                .map_err(error)?;
+            let signature = signer.sign_transaction(&tx).map_err(error)?;
+            let signed = SignedTransaction::new(tx, signature);
+            set.transactions.push(TransactionFixture {
+                network: FixtureNetwork::Evm,
+                chain_id: Some(chain_id.value()),
+                kind: TransactionKind::Eip1559,
+                path: path.clone(),
+                to: recipient.to_string(),
+                amount: amount.as_u256().to_string(),
+                raw: signed.to_raw_transaction(),
+                txid: signed.tx_hash_hex(),
+            });
+        }
+
+        let signature = signer
+            .sign_message(FIXTURE_MESSAGE.as_bytes())
+            .map_err(error)?;
+        set.messages.push(MessageFixture {
+            network: FixtureNetwork::Evm,
+            kind: MessageKind::Eip191,
+            path: path.clone(),
+            address: signer.address().to_string(),
+            message: FIXTURE_MESSAGE.to_string(),
+            signature: signature_hex(&signature),
+        });
+
+        let typed_data = TypedData::from_json(TYPED_DATA_JSON).map_err(error)?;
+        let signature = sign_typed_data_json(&signer, &typed_data).map_err(error)?;
+        set.messages.push(MessageFixture {
+            network: FixtureNetwork::Evm,
+            kind: MessageKind::Eip712,
+            path: path.clone(),
+            address: signer.address().to_string(),
+            message: TYPED_DATA_JSON.to_string(),
+            signature: signature_hex(&signature),
+        });
+        Ok(())
+    }
+}
+
+impl Default for FixtureGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns `m/purpose'/coin'/0'`.
+fn account_path(purpose: Purpose, coin_type: CoinType) -> String {
+    format!("m/{}'/{}'/0'", purpose.value(), coin_type.index())
+}
+
+/// Returns the address of `public_key` for the account's purpose and network.
+fn bitcoin_address(
+    account: &Account,
+    public_key: &[u8; 33],
+) -> Result<Address, khodpay_btc_signing::Error> {
+    let network = khodpay_btc_signing::Network::from(account.network());
+    Ok(match account.purpose() {
+        Purpose::BIP44 => Address::p2pkh(public_key, network),
+        Purpose::BIP49 => Address::p2sh_p2wpkh(public_key, network),
+        Purpose::BIP84 => Address::p2wpkh(public_key, network),
+        Purpose::BIP86 => Address::p2tr(public_key, network)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vectors::{BITCOIN_ADDRESS_VECTORS, EVM_ADDRESS_VECTOR};
+
+    #[test]
+    fn test_default_fixtures_match_bundled_json() {
+        let fixtures = FixtureGenerator::new().generate().unwrap();
+        assert_eq!(fixtures.to_json(), DEFAULT_FIXTURES_JSON);
+        assert_eq!(
+            FixtureSet::from_json(DEFAULT_FIXTURES_JSON).unwrap(),
+            fixtures
+        );
+    }
+
+    #[test]
+    fn test_fixtures_agree_with_vectors() {
+        let fixtures = FixtureGenerator::new()
+            .with_address_count(2)
+            .generate()
+            .unwrap();
+        assert_eq!(fixtures.accounts.len(), 9);
+        // 8 Bitcoin accounts with receive and change, EVM with receive only
+        assert_eq!(fixtures.addresses.len(), 8 * 4 + 2);
+        assert_eq!(fixtures.transactions.len(), 6 + 2);
+        assert_eq!(fixtures.messages.len(), 2 + 2);
+
+        for (purpose, expected) in BITCOIN_ADDRESS_VECTORS {
+            let path = format!("m/{}'/0'/0'/0/0", purpose.value());
+            assert_eq!(fixtures.address(&path).unwrap().address, *expected);
+        }
+        assert_eq!(
+            fixtures.address("m/44'/60'/0'/0/0").unwrap().address,
+            EVM_ADDRESS_VECTOR
+        );
+        assert!(fixtures.address("m/44'/60'/0'/1/0").is_none());
+        assert!(fixtures
+            .account("m/84'/1'/0'")
+            .unwrap()
+            .xpub
+            .starts_with("vpub"));
+        assert!(fixtures
+            .address("m/86'/1'/0'/1/1")
+            .unwrap()
+            .address
+            .starts_with("tb1p"));
+    }
+
+    #[test]
+    fn test_sample_transactions_verify() {
+        let fixtures = FixtureGenerator::new().generate().unwrap();
+        for tx in &fixtures.transactions {
+            match tx.kind {
+                TransactionKind::Eip1559 => {
+                    let raw = hex::decode(tx.raw.trim_start_matches("0x")).unwrap();
+                    let decoded = SignedTransaction::from_raw(&raw).unwrap();
+                    assert_eq!(decoded.tx_hash_hex(), tx.txid);
+                    assert_eq!(decoded.verify().unwrap().to_string(), EVM_ADDRESS_VECTOR);
+                }
+                _ => {
+                    let decoded = khodpay_btc_signing::Transaction::from_hex(&tx.raw).unwrap();
+                    assert_eq!(decoded.txid().to_string(), tx.txid);
+                    assert_eq!(decoded.outputs[0].value, BITCOIN_AMOUNT);
+                    assert_eq!(decoded.output_value(), Some(FUNDING_VALUE - BITCOIN_FEE));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let generator = FixtureGenerator::new().with_mnemonic(
+            crate::vectors::BIP39_VECTORS[1].mnemonic,
+            crate::vectors::BIP39_PASSPHRASE,
+        );
+        let first = generator.generate().unwrap();
+        assert_eq!(generator.generate().unwrap().to_json(), first.to_json());
+        assert_eq!(first.passphrase, "TREZOR");
+        assert_ne!(
+            first.address("m/84'/0'/0'/0/0"),
+            FixtureSet::from_json(DEFAULT_FIXTURES_JSON)
+                .unwrap()
+                .address("m/84'/0'/0'/0/0")
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = FixtureGenerator::new()
+            .with_mnemonic("abandon abandon", "")
+            .generate()
+            .unwrap_err();
+        assert_eq!(error.case, "mnemonic");
+
+        assert_eq!(
+            FixtureSet::from_json(r#"{"format":"other"}"#)
+                .unwrap_err()
+                .case,
+            "json"
+        );
+        let mut future: serde_json::Value = serde_json::from_str(DEFAULT_FIXTURES_JSON).unwrap();
+        future["version"] = serde_json::json!(FIXTURE_VERSION + 1);
+        assert_eq!(
+            FixtureSet::from_json(&future.to_string()).unwrap_err().case,
+            "version"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! # Khodpay Compat
+//!
+//! Cross-implementation compatibility checks: derives seeds, keys, addresses and
+//! signatures with the KhodPay crates and compares them against bundled test
+//! vectors and, optionally, against independent implementations. Embedders run
+//! it at startup or in their own CI to catch a build, platform or dependency
+//! change that silently alters what a mnemonic derives to.
+//!
+//! | Reference | Feature | Compares |
+//! |---|---|---|
+//! | Bundled [`vectors`] | always | BIP-39 mnemonics and seeds, BIP-32 keys, Bitcoin and EVM addresses, EIP-712 signatures |
+//! | rust-bitcoin | `bitcoin` | BIP-39 seeds, account extended public keys, BIP-44, 49, 84 and 86 addresses |
+//! | alloy | `alloy` | EVM addresses and EIP-191 signatures |
+//!
+//! Each comparison becomes a [`CheckResult`] in a [`CompatReport`], which is
+//! serializable with the `serde` feature.
+//!
+//! With the `fixtures` feature, `fixtures::FixtureGenerator` writes a
+//! deterministic JSON fixture set (account keys, addresses, signed sample
+//! transactions and messages) from a test mnemonic, for apps to pin as golden
+//! files in their own integration tests.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_compat::CompatChecker;
+//!
+//! let report = CompatChecker::new()
+//!     .with_address_count(3)
+//!     .run();
+//!
+//! if !report.is_compatible() {
+//!     for failure in report.failures() {
+//!         eprintln!("{}", failure);
+//!     }
+//! }
+//! println!("{}", report); // "60 of 60 checks passed against test-vectors"
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+#[cfg(feature = "alloy")]
+mod alloy;
+mod checker;
+mod derive;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+mod report;
+#[cfg(feature = "bitcoin")]
+mod rust_bitcoin;
+pub mod vectors;
+
+pub use checker::{CompatChecker, CHECK_MESSAGE, DEFAULT_ADDRESS_COUNT};
+pub use report::{Check, CheckResult, CompatReport, Outcome, Reference};
@@ -0,0 +1,320 @@
+//! The compatibility report: one result per compared value.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What a check compares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Check {
+    /// BIP-39 mnemonic from entropy.
+    Bip39Mnemonic,
+    /// BIP-39 seed from a mnemonic and passphrase.
+    Bip39Seed,
+    /// BIP-32 extended key at a derivation path.
+    Bip32Derivation,
+    /// Bitcoin address of a BIP-44, 49, 84 or 86 key.
+    BitcoinAddress,
+    /// EVM address of a BIP-44 key.
+    EvmAddress,
+    /// EVM signature over a message or typed data.
+    EvmSignature,
+}
+
+impl Check {
+    /// Returns the name used in reports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Check::Bip39Mnemonic => "bip39_mnemonic",
+            Check::Bip39Seed => "bip39_seed",
+            Check::Bip32Derivation => "bip32_derivation",
+            Check::BitcoinAddress => "bitcoin_address",
+            Check::EvmAddress => "evm_address",
+            Check::EvmSignature => "evm_signature",
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where the expected value of a check comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Reference {
+    /// The test vectors bundled with this crate.
+    TestVectors,
+    /// The `bitcoin` and `bip39` crates of the rust-bitcoin project.
+    RustBitcoin,
+    /// The alloy Ethereum libraries.
+    Alloy,
+}
+
+impl Reference {
+    /// Returns the references compiled into this build: always the test vectors,
+    /// plus rust-bitcoin and alloy with their features.
+    pub fn available() -> Vec<Reference> {
+        let mut references = vec![Reference::TestVectors];
+        if cfg!(feature = "bitcoin") {
+            references.push(Reference::RustBitcoin);
+        }
+        if cfg!(feature = "alloy") {
+            references.push(Reference::Alloy);
+        }
+        references
+    }
+
+    /// Returns the name used in reports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Reference::TestVectors => "test-vectors",
+            Reference::RustBitcoin => "rust-bitcoin",
+            Reference::Alloy => "alloy",
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a check ended.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "status", rename_all = "snake_case"))]
+pub enum Outcome {
+    /// KhodPay produced the expected value.
+    Match,
+    /// KhodPay produced a different value.
+    Mismatch {
+        /// The value of the reference.
+        expected: String,
+        /// The value KhodPay produced.
+        actual: String,
+    },
+    /// KhodPay or the reference failed to produce a value.
+    Error {
+        /// What failed.
+        message: String,
+    },
+}
+
+/// The result of comparing one value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CheckResult {
+    /// What was compared.
+    pub check: Check,
+    /// Where the expected value came from.
+    pub reference: Reference,
+    /// Which case: a vector name, derivation path or message.
+    pub case: String,
+    /// How the comparison ended.
+    pub outcome: Outcome,
+}
+
+impl CheckResult {
+    /// Compares KhodPay's value against the reference's. Errors on either side
+    /// become [`Outcome::Error`], with the reference's error first.
+    pub(crate) fn compare(
+        check: Check,
+        reference: Reference,
+        case: impl Into<String>,
+        expected: Result<String, String>,
+        actual: Result<String, String>,
+    ) -> Self {
+        let outcome = match (expected, actual) {
+            (Err(e), _) => Outcome::Error {
+                message: format!("{}: {}", reference, e),
+            },
+            (_, Err(e)) => Outcome::Error {
+                message: format!("khodpay: {}", e),
+            },
+            (Ok(expected), Ok(actual)) if expected == actual => Outcome::Match,
+            (Ok(expected), Ok(actual)) => Outcome::Mismatch { expected, actual },
+        };
+        CheckResult {
+            check,
+            reference,
+            case: case.into(),
+            outcome,
+        }
+    }
+
+    /// Returns `true` if KhodPay produced the expected value.
+    pub fn passed(&self) -> bool {
+        self.outcome == Outcome::Match
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}] {}: ", self.check, self.reference, self.case)?;
+        match &self.outcome {
+            Outcome::Match => f.write_str("ok"),
+            Outcome::Mismatch { expected, actual } => {
+                write!(f, "expected {}, got {}", expected, actual)
+            }
+            Outcome::Error { message } => write!(f, "error: {}", message),
+        }
+    }
+}
+
+/// The results of a [`CompatChecker`](crate::CompatChecker) run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompatReport {
+    /// Every comparison, in the order it ran.
+    pub results: Vec<CheckResult>,
+}
+
+impl CompatReport {
+    /// Returns `true` if every check passed.
+    pub fn is_compatible(&self) -> bool {
+        self.results.iter().all(CheckResult::passed)
+    }
+
+    /// Returns the number of checks that passed.
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|result| result.passed()).count()
+    }
+
+    /// Returns the checks that did not pass.
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+
+    /// Returns the references the checks ran against.
+    pub fn references(&self) -> Vec<Reference> {
+        let mut references = Vec::new();
+        for result in &self.results {
+            if !references.contains(&result.reference) {
+                references.push(result.reference);
+            }
+        }
+        references
+    }
+
+    pub(crate) fn push(&mut self, result: CheckResult) {
+        self.results.push(result);
+    }
+}
+
+impl fmt::Display for CompatReport {
+    /// Writes a summary line, then one line per failed check.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let references: Vec<&str> = self.references().iter().map(Reference::as_str).collect();
+        write!(
+            f,
+            "{} of {} checks passed against {}",
+            self.passed(),
+            self.results.len(),
+            references.join(", ")
+        )?;
+        for failure in self.failures() {
+            write!(f, "\n{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(expected: Result<&str, &str>, actual: Result<&str, &str>) -> CheckResult {
+        CheckResult::compare(
+            Check::BitcoinAddress,
+            Reference::RustBitcoin,
+            "m/84'/0'/0'/0/0",
+            expected.map(str::to_string).map_err(str::to_string),
+            actual.map(str::to_string).map_err(str::to_string),
+        )
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(result(Ok("a"), Ok("a")).outcome, Outcome::Match);
+        assert_eq!(
+            result(Ok("a"), Ok("b")).outcome,
+            Outcome::Mismatch {
+                expected: "a".to_string(),
+                actual: "b".to_string()
+            }
+        );
+        assert_eq!(
+            result(Err("bad key"), Err("also bad")).outcome,
+            Outcome::Error {
+                message: "rust-bitcoin: bad key".to_string()
+            }
+        );
+        assert_eq!(
+            result(Ok("a"), Err("bad key")).outcome,
+            Outcome::Error {
+                message: "khodpay: bad key".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = CompatReport::default();
+        assert!(report.is_compatible());
+        report.push(result(Ok("a"), Ok("a")));
+        report.push(result(Ok("a"), Ok("b")));
+        assert!(!report.is_compatible());
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(report.references(), vec![Reference::RustBitcoin]);
+        assert_eq!(
+            report.to_string(),
+            "1 of 2 checks passed against rust-bitcoin\n\
+             bitcoin_address [rust-bitcoin] m/84'/0'/0'/0/0: expected a, got b"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let report = CompatReport {
+            results: vec![result(Ok("a"), Ok("b"))],
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["results"][0],
+            serde_json::json!({
+                "check": "bitcoin_address",
+                "reference": "rust_bitcoin",
+                "case": "m/84'/0'/0'/0/0",
+                "outcome": { "status": "mismatch", "expected": "a", "actual": "b" }
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<CompatReport>(json).unwrap(),
+            report
+        );
+    }
+
+    #[test]
+    fn test_available_references() {
+        let references = Reference::available();
+        assert_eq!(references[0], Reference::TestVectors);
+        assert_eq!(
+            references.contains(&Reference::RustBitcoin),
+            cfg!(feature = "bitcoin")
+        );
+        assert_eq!(
+            references.contains(&Reference::Alloy),
+            cfg!(feature = "alloy")
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Checks against the rust-bitcoin project: BIP-39 seeds from the `bip39` crate,
+//! BIP-32 keys and addresses from the `bitcoin` crate.
+
+use std::str::FromStr;
+
+use bitcoin::bip32::{DerivationPath, Xpriv, Xpub};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{All, Secp256k1};
+use bitcoin::{Address, CompressedPublicKey, Network, NetworkKind};
+use khodpay_bip44::Purpose;
+use zeroize::Zeroizing;
+
+use crate::checker::TestWallet;
+use crate::derive::{self, Value, BITCOIN_PURPOSES};
+use crate::report::{Check, CheckResult, CompatReport, Reference};
+
+/// Compares seeds, account keys and the first `address_count` receive addresses of
+/// every Bitcoin purpose.
+pub(crate) fn check(report: &mut CompatReport, wallet: &TestWallet, address_count: u32) {
+    let secp = Secp256k1::new();
+    let label = &wallet.label;
+    let expected = seed(&wallet.phrase, &wallet.passphrase);
+    let actual = derive::seed(&wallet.phrase, &wallet.passphrase);
+
+    // Seeds are secret: compare their SHA-256 digests instead
+    report.push(compare(
+        Check::Bip39Seed,
+        format!("{} seed sha256", label),
+        expected
+            .as_ref()
+            .map(|seed| digest(&seed[..]))
+            .map_err(Clone::clone),
+        actual
+            .as_ref()
+            .map(|seed| digest(&seed[..]))
+            .map_err(Clone::clone),
+    ));
+    let (Ok(expected), Ok(actual)) = (expected, actual) else {
+        return;
+    };
+
+    for purpose in BITCOIN_PURPOSES {
+        let path = format!("m/{}'/0'/0'", purpose.value());
+        report.push(compare(
+            Check::Bip32Derivation,
+            format!("{} {}", label, path),
+            xpub(&secp, &expected[..], &path).map(|xpub| xpub.to_string()),
+            derive::xpub(&actual[..], &path),
+        ));
+
+        for index in 0..address_count {
+            let path = format!("{}/0/{}", path, index);
+            report.push(compare(
+                Check::BitcoinAddress,
+                format!("{} {}", label, path),
+                address(&secp, &expected[..], &path, purpose),
+                derive::bitcoin_address(&actual[..], purpose, index),
+            ));
+        }
+    }
+}
+
+fn compare(check: Check, case: String, expected: Value, actual: Value) -> CheckResult {
+    CheckResult::compare(check, Reference::RustBitcoin, case, expected, actual)
+}
+
+fn digest(seed: &[u8]) -> String {
+    sha256::Hash::hash(seed).to_string()
+}
+
+fn seed(phrase: &str, passphrase: &str) -> Value<Zeroizing<[u8; 64]>> {
+    let mnemonic =
+        bip39::Mnemonic::parse_in(bip39::Language::English, phrase).map_err(|e| e.to_string())?;
+    Ok(Zeroizing::new(mnemonic.to_seed(passphrase)))
+}
+
+fn xpub(secp: &Secp256k1<All>, seed: &[u8], path: &str) -> Value<Xpub> {
+    let path = DerivationPath::from_str(path).map_err(|e| e.to_string())?;
+    let xpriv = Xpriv::new_master(NetworkKind::Main, seed)
+        .and_then(|master| master.derive_priv(secp, &path))
+        .map_err(|e| e.to_string())?;
+    Ok(Xpub::from_priv(secp, &xpriv))
+}
+
+fn address(secp: &Secp256k1<All>, seed: &[u8], path: &str, purpose: Purpose) -> Value {
+    let public_key = xpub(secp, seed, path)?.public_key;
+    let compressed = CompressedPublicKey(public_key);
+    let address = match purpose {
+        Purpose::BIP44 => Address::p2pkh(compressed, Network::Bitcoin),
+        Purpose::BIP49 => Address::p2shwpkh(&compressed, Network::Bitcoin),
+        Purpose::BIP84 => Address::p2wpkh(&compressed, Network::Bitcoin),
+        Purpose::BIP86 => Address::p2tr(
+            secp,
+            public_key.x_only_public_key().0,
+            None,
+            Network::Bitcoin,
+        ),
+    };
+    Ok(address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Outcome;
+    use crate::vectors::{ADDRESS_MNEMONIC, BIP32_VECTORS, BITCOIN_ADDRESS_VECTORS};
+
+    #[test]
+    fn test_reference_matches_vectors() {
+        let secp = Secp256k1::new();
+        let seed = seed(ADDRESS_MNEMONIC, "").unwrap();
+        for (purpose, expected) in BITCOIN_ADDRESS_VECTORS {
+            let path = format!("m/{}'/0'/0'/0/0", purpose.value());
+            assert_eq!(
+                &address(&secp, &seed[..], &path, *purpose).unwrap(),
+                expected
+            );
+        }
+        for vector in BIP32_VECTORS {
+            let seed = hex::decode(vector.seed).unwrap();
+            for step in vector.derivations {
+                // rust-bitcoin only accepts `'` and `h` as hardened markers
+                let path = step.path.replace('H', "'");
+                assert_eq!(xpub(&secp, &seed, &path).unwrap().to_string(), step.xpub);
+            }
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let wallet = TestWallet::new("wallet 0", ADDRESS_MNEMONIC, "TREZOR");
+        let mut report = CompatReport::default();
+        check(&mut report, &wallet, 2);
+        assert!(report.is_compatible(), "{}", report);
+        // Seed, then per purpose an account key and two addresses
+        assert_eq!(report.results.len(), 1 + 4 * 3);
+        assert_eq!(report.results[3].case, "wallet 0 m/44'/0'/0'/0/1");
+    }
+
+    #[test]
+    fn test_invalid_mnemonic() {
+        let wallet = TestWallet::new("wallet 1", "abandon abandon", "");
+        let mut report = CompatReport::default();
+        check(&mut report, &wallet, 2);
+        assert_eq!(report.results.len(), 1);
+        assert!(matches!(
+            &report.results[0].outcome,
+            Outcome::Error { message } if message.starts_with("rust-bitcoin: ")
+        ));
+    }
+}
@@ -0,0 +1,390 @@
+//! Test vectors bundled with the crate, checked on every run.
+//!
+//! - BIP-39: the reference vectors of the BIP (English, passphrase `TREZOR`)
+//! - BIP-32: test vectors 1 to 4 of the BIP
+//! - Addresses: the first receive address of each BIP-44, 49, 84 and 86 account and
+//!   of the Ethereum account of the `abandon … about` mnemonic, as published in
+//!   those BIPs and reproduced by every major wallet
+//! - EIP-712: the `Mail` example of the EIP, signed by `keccak256("cow")`
+
+use khodpay_bip44::Purpose;
+
+use crate::derive;
+use crate::report::{Check, CheckResult, CompatReport, Reference};
+
+/// A BIP-39 vector: entropy, its English mnemonic, and the seed under
+/// [`BIP39_PASSPHRASE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bip39Vector {
+    /// Entropy in hex.
+    pub entropy: &'static str,
+    /// The mnemonic of the entropy.
+    pub mnemonic: &'static str,
+    /// The seed in hex.
+    pub seed: &'static str,
+}
+
+/// The passphrase of the BIP-39 reference vectors.
+pub const BIP39_PASSPHRASE: &str = "TREZOR";
+
+/// The BIP-39 reference vectors.
+pub const BIP39_VECTORS: &[Bip39Vector] = &[
+    Bip39Vector {
+        entropy: "00000000000000000000000000000000",
+        mnemonic: "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        seed: "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+    },
+    Bip39Vector {
+        entropy: "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+        mnemonic: "legal winner thank year wave sausage worth useful legal winner thank yellow",
+        seed: "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+    },
+    Bip39Vector {
+        entropy: "80808080808080808080808080808080",
+        mnemonic: "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+        seed: "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+    },
+    Bip39Vector {
+        entropy: "ffffffffffffffffffffffffffffffff",
+        mnemonic: "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+        seed: "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+    },
+    Bip39Vector {
+        entropy: "000000000000000000000000000000000000000000000000",
+        mnemonic: "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+        seed: "035895f2f481b1b0f01fcf8c289c794660b289981a78f8106447707fdd9666ca06da5a9a565181599b79f53b844d8a71dd9f439c52a3d7b3e8a79c906ac845fa",
+    },
+    Bip39Vector {
+        entropy: "808080808080808080808080808080808080808080808080",
+        mnemonic: "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter always",
+        seed: "107d7c02a5aa6f38c58083ff74f04c607c2d2c0ecc55501dadd72d025b751bc27fe913ffb796f841c49b1d33b610cf0e91d3aa239027f5e99fe4ce9e5088cd65",
+    },
+    Bip39Vector {
+        entropy: "0000000000000000000000000000000000000000000000000000000000000000",
+        mnemonic: "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+        seed: "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+    },
+    Bip39Vector {
+        entropy: "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        mnemonic: "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+        seed: "dd48c104698c30cfe2b6142103248622fb7bb0ff692eebb00089b32d22484e1613912f0a5b694407be899ffd31ed3992c456cdf60f5d4564b8ba3f05a69890ad",
+    },
+    Bip39Vector {
+        entropy: "9e885d952ad362caeb4efe34a8e91bd2",
+        mnemonic: "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+        seed: "274ddc525802f7c828d8ef7ddbcdc5304e87ac3535913611fbbfa986d0c9e5476c91689f9c8a54fd55bd38606aa6a8595ad213d4c9c9f9aca3fb217069a41028",
+    },
+    Bip39Vector {
+        entropy: "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c",
+        mnemonic: "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy gospel tennis maple dilemma loan word shrug inflict delay length",
+        seed: "64c87cde7e12ecf6704ab95bb1408bef047c22db4cc7491c4271d170a1b213d20b385bc1588d9c7b38f1b39d415665b8a9030c9ec653d75e65f847d8fc1fc440",
+    },
+];
+
+/// A BIP-32 vector: a seed and the extended keys at several paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bip32Vector {
+    /// Name of the vector in the BIP.
+    pub name: &'static str,
+    /// Seed in hex.
+    pub seed: &'static str,
+    /// Extended keys along the vector's derivation chain.
+    pub derivations: &'static [Bip32Derivation],
+}
+
+/// One step of a [`Bip32Vector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bip32Derivation {
+    /// Derivation path, with `H` marking hardened steps.
+    pub path: &'static str,
+    /// Extended public key at the path.
+    pub xpub: &'static str,
+    /// Extended private key at the path.
+    pub xprv: &'static str,
+}
+
+/// The BIP-32 test vectors.
+pub const BIP32_VECTORS: &[Bip32Vector] = &[
+    Bip32Vector {
+        name: "vector 1",
+        seed: "000102030405060708090a0b0c0d0e0f",
+        derivations: &[
+            Bip32Derivation {
+                path: "m",
+                xpub: "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+                xprv: "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+            },
+            Bip32Derivation {
+                path: "m/0H",
+                xpub: "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+                xprv: "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+            },
+            Bip32Derivation {
+                path: "m/0H/1",
+                xpub: "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+                xprv: "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+            },
+            Bip32Derivation {
+                path: "m/0H/1/2H",
+                xpub: "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+                xprv: "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM",
+            },
+            Bip32Derivation {
+                path: "m/0H/1/2H/2",
+                xpub: "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+                xprv: "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334",
+            },
+            Bip32Derivation {
+                path: "m/0H/1/2H/2/1000000000",
+                xpub: "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+                xprv: "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+            },
+        ],
+    },
+    Bip32Vector {
+        name: "vector 2",
+        seed: "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+        derivations: &[
+            Bip32Derivation {
+                path: "m",
+                xpub: "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+                xprv: "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+            },
+            Bip32Derivation {
+                path: "m/0",
+                xpub: "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+                xprv: "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt",
+            },
+            Bip32Derivation {
+                path: "m/0/2147483647H",
+                xpub: "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a",
+                xprv: "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9",
+            },
+            Bip32Derivation {
+                path: "m/0/2147483647H/1",
+                xpub: "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon",
+                xprv: "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef",
+            },
+            Bip32Derivation {
+                path: "m/0/2147483647H/1/2147483646H",
+                xpub: "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL",
+                xprv: "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc",
+            },
+            Bip32Derivation {
+                path: "m/0/2147483647H/1/2147483646H/2",
+                xpub: "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt",
+                xprv: "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j",
+            },
+        ],
+    },
+    Bip32Vector {
+        name: "vector 3",
+        seed: "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be",
+        derivations: &[
+            Bip32Derivation {
+                path: "m",
+                xpub: "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13",
+                xprv: "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6",
+            },
+            Bip32Derivation {
+                path: "m/0H",
+                xpub: "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+                xprv: "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L",
+            },
+        ],
+    },
+    Bip32Vector {
+        name: "vector 4",
+        seed: "3ddd5602285899a946114506157c7997e5444528f3003f6134712147db19b678",
+        derivations: &[
+            Bip32Derivation {
+                path: "m",
+                xpub: "xpub661MyMwAqRbcGczjuMoRm6dXaLDEhW1u34gKenbeYqAix21mdUKJyuyu5F1rzYGVxyL6tmgBUAEPrEz92mBXjByMRiJdba9wpnN37RLLAXa",
+                xprv: "xprv9s21ZrQH143K48vGoLGRPxgo2JNkJ3J3fqkirQC2zVdk5Dgd5w14S7fRDyHH4dWNHUgkvsvNDCkvAwcSHNAQwhwgNMgZhLtQC63zxwhQmRv",
+            },
+            Bip32Derivation {
+                path: "m/0H",
+                xpub: "xpub69AUMk3qDBi3uW1sXgjCmVjJ2G6WQoYSnNHyzkmdCHEhSZ4tBok37xfFEqHd2AddP56Tqp4o56AePAgCjYdvpW2PU2jbUPFKsav5ut6Ch1m",
+                xprv: "xprv9vB7xEWwNp9kh1wQRfCCQMnZUEG21LpbR9NPCNN1dwhiZkjjeGRnaALmPXCX7SgjFTiCTT6bXes17boXtjq3xLpcDjzEuGLQBM5ohqkao9G",
+            },
+            Bip32Derivation {
+                path: "m/0H/1H",
+                xpub: "xpub6BJA1jSqiukeaesWfxe6sNK9CCGaujFFSJLomWHprUL9DePQ4JDkM5d88n49sMGJxrhpjazuXYWdMf17C9T5XnxkopaeS7jGk1GyyVziaMt",
+                xprv: "xprv9xJocDuwtYCMNAo3Zw76WENQeAS6WGXQ55RCy7tDJ8oALr4FWkuVoHJeHVAcAqiZLE7Je3vZJHxspZdFHfnBEjHqU5hG1Jaj32dVoS6XLT1",
+            },
+        ],
+    },
+];
+
+/// The mnemonic of the address vectors, with an empty passphrase.
+pub const ADDRESS_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// The first receive address of account 0 of [`ADDRESS_MNEMONIC`], per purpose.
+pub const BITCOIN_ADDRESS_VECTORS: &[(Purpose, &str)] = &[
+    (Purpose::BIP44, "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"),
+    (Purpose::BIP49, "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf"),
+    (Purpose::BIP84, "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"),
+    (
+        Purpose::BIP86,
+        "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+    ),
+];
+
+/// The address at `m/44'/60'/0'/0/0` of [`ADDRESS_MNEMONIC`].
+pub const EVM_ADDRESS_VECTOR: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+
+/// The private key of the EIP-712 example, `keccak256("cow")`.
+pub const TYPED_DATA_PRIVATE_KEY: &str =
+    "c85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4";
+
+/// The `Mail` typed data of the EIP-712 example.
+pub const TYPED_DATA_JSON: &str = r#"{
+    "types": {
+        "EIP712Domain": [
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "chainId", "type": "uint256" },
+            { "name": "verifyingContract", "type": "address" }
+        ],
+        "Person": [
+            { "name": "name", "type": "string" },
+            { "name": "wallet", "type": "address" }
+        ],
+        "Mail": [
+            { "name": "from", "type": "Person" },
+            { "name": "to", "type": "Person" },
+            { "name": "contents", "type": "string" }
+        ]
+    },
+    "primaryType": "Mail",
+    "domain": {
+        "name": "Ether Mail",
+        "version": "1",
+        "chainId": 1,
+        "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+    },
+    "message": {
+        "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+        "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+        "contents": "Hello, Bob!"
+    }
+}"#;
+
+/// The signature of [`TYPED_DATA_JSON`] by [`TYPED_DATA_PRIVATE_KEY`], as
+/// `0x ‖ r ‖ s ‖ v`.
+pub const TYPED_DATA_SIGNATURE: &str = "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c";
+
+/// Checks KhodPay against every bundled vector.
+pub(crate) fn check(report: &mut CompatReport) {
+    for vector in BIP39_VECTORS {
+        let case = format!("entropy {}", vector.entropy);
+        let actual = decode(vector.entropy).and_then(|entropy| derive::mnemonic(&entropy));
+        report.push(expect(Check::Bip39Mnemonic, &case, vector.mnemonic, actual));
+
+        let actual = derive::seed(vector.mnemonic, BIP39_PASSPHRASE).map(|seed| hex::encode(*seed));
+        report.push(expect(Check::Bip39Seed, &case, vector.seed, actual));
+    }
+
+    for vector in BIP32_VECTORS {
+        let seed = decode(vector.seed);
+        for step in vector.derivations {
+            let case = format!("{} {}", vector.name, step.path);
+            let key = seed.clone().and_then(|seed| derive::xprv(&seed, step.path));
+            let actual = key
+                .as_ref()
+                .map(|key| key.to_string())
+                .map_err(Clone::clone);
+            report.push(expect(
+                Check::Bip32Derivation,
+                &format!("{} xprv", case),
+                step.xprv,
+                actual,
+            ));
+            let actual = key.map(|key| key.to_extended_public_key().to_string());
+            report.push(expect(
+                Check::Bip32Derivation,
+                &format!("{} xpub", case),
+                step.xpub,
+                actual,
+            ));
+        }
+    }
+
+    let seed = derive::seed(ADDRESS_MNEMONIC, "");
+    for (purpose, address) in BITCOIN_ADDRESS_VECTORS {
+        let case = format!("m/{}'/0'/0'/0/0", purpose.value());
+        let actual = seed
+            .as_ref()
+            .map_err(Clone::clone)
+            .and_then(|seed| derive::bitcoin_address(&seed[..], *purpose, 0));
+        report.push(expect(Check::BitcoinAddress, &case, address, actual));
+    }
+    let actual = seed
+        .as_ref()
+        .map_err(Clone::clone)
+        .and_then(|seed| derive::evm_address(&seed[..], 0));
+    report.push(expect(
+        Check::EvmAddress,
+        "m/44'/60'/0'/0/0",
+        EVM_ADDRESS_VECTOR,
+        actual,
+    ));
+
+    let actual = decode(TYPED_DATA_PRIVATE_KEY).and_then(|key| {
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| "key is not 32 bytes".to_string())?;
+        derive::typed_data_signature(&key, TYPED_DATA_JSON)
+    });
+    report.push(expect(
+        Check::EvmSignature,
+        "EIP-712 Mail",
+        TYPED_DATA_SIGNATURE,
+        actual,
+    ));
+}
+
+fn expect(check: Check, case: &str, expected: &str, actual: derive::Value) -> CheckResult {
+    CheckResult::compare(
+        check,
+        Reference::TestVectors,
+        case,
+        Ok(expected.to_string()),
+        actual,
+    )
+}
+
+fn decode(hex: &str) -> derive::Value<Vec<u8>> {
+    hex::decode(hex).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_pass() {
+        let mut report = CompatReport::default();
+        check(&mut report);
+        assert!(report.is_compatible(), "{}", report);
+        let bip32_steps: usize = BIP32_VECTORS.iter().map(|v| v.derivations.len()).sum();
+        assert_eq!(
+            report.results.len(),
+            2 * BIP39_VECTORS.len() + 2 * bip32_steps + BITCOIN_ADDRESS_VECTORS.len() + 2
+        );
+    }
+
+    #[test]
+    fn test_typed_data_key() {
+        let key: [u8; 32] = hex::decode(TYPED_DATA_PRIVATE_KEY)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let signer = khodpay_signing::Bip44Signer::from_private_key(&key).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+        );
+    }
+}
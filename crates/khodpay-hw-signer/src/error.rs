@@ -0,0 +1,84 @@
+//! Error types for the hardware signer crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while talking to a hardware wallet.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The host transport (USB HID, BLE, …) failed to deliver a command.
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    /// The user declined the request on the device.
+    #[error("Request rejected on the device")]
+    UserRejected,
+
+    /// The device is locked and must be unlocked with its PIN first.
+    #[error("Device is locked")]
+    DeviceLocked,
+
+    /// The app needed for the request is not open on the device.
+    #[error("Wrong app open on the device (status 0x{0:04X})")]
+    WrongApp(u16),
+
+    /// The device refused the request with a status word or failure code.
+    #[error("Device error 0x{status:04X}: {message}")]
+    Device {
+        /// The status word (Ledger) or failure code (Trezor) returned by the device.
+        status: u16,
+        /// A description of the failure.
+        message: String,
+    },
+
+    /// The device answered with data this crate cannot interpret.
+    #[error("Invalid device response: {0}")]
+    InvalidResponse(String),
+
+    /// The request cannot be expressed for this device.
+    #[error("Unsupported request: {0}")]
+    Unsupported(String),
+
+    /// Error from BIP-32 operations.
+    #[error("BIP-32 error: {0}")]
+    Bip32Error(#[from] khodpay_bip32::Error),
+
+    /// Error from EVM signing types.
+    #[error("EVM error: {0}")]
+    EvmError(#[from] khodpay_signing::Error),
+
+    /// Error from Bitcoin signing types.
+    #[error("Bitcoin error: {0}")]
+    BtcError(#[from] khodpay_btc_signing::Error),
+}
+
+/// Result type alias for hardware signer operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::UserRejected.to_string(),
+            "Request rejected on the device"
+        );
+        assert_eq!(
+            Error::WrongApp(0x6E00).to_string(),
+            "Wrong app open on the device (status 0x6E00)"
+        );
+        assert_eq!(
+            Error::Device {
+                status: 0x6A80,
+                message: "invalid data".to_string()
+            }
+            .to_string(),
+            "Device error 0x6A80: invalid data"
+        );
+        assert_eq!(
+            Error::Transport("device unplugged".to_string()).to_string(),
+            "Transport error: device unplugged"
+        );
+    }
+}
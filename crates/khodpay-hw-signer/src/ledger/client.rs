@@ -0,0 +1,353 @@
+//! Host side of the Ledger Bitcoin app's interactive protocol.
+//!
+//! While processing `GET_WALLET_ADDRESS` or `SIGN_PSBT` the device pauses with status
+//! `0xE000` and a client command, asking the host for a hash preimage, a Merkle leaf
+//! with its proof, or a leaf index. The host answers with `CONTINUE` and the device
+//! resumes. This module keeps the data the device may ask for and answers those
+//! commands.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::merkle::{read_varint, sha256, write_varint, MerkleTree};
+use crate::{Error, Result};
+
+/// The device hands data back to the host, such as a signature.
+pub(crate) const YIELD: u8 = 0x10;
+/// The device asks for the preimage of a SHA-256 hash.
+pub(crate) const GET_PREIMAGE: u8 = 0x40;
+/// The device asks for a Merkle leaf and its inclusion proof.
+pub(crate) const GET_MERKLE_LEAF_PROOF: u8 = 0x41;
+/// The device asks for the index of a Merkle leaf.
+pub(crate) const GET_MERKLE_LEAF_INDEX: u8 = 0x42;
+/// The device asks for queued data that did not fit in the previous answer.
+pub(crate) const GET_MORE_ELEMENTS: u8 = 0xA0;
+
+/// Largest payload of a response to a client command.
+const MAX_RESPONSE: usize = 255;
+
+/// Proof hashes sent with a Merkle leaf; the rest go to the queue.
+const PROOF_HASHES_PER_RESPONSE: usize = (MAX_RESPONSE - 32 - 1 - 1) / 32;
+
+/// A wallet policy as registered with the Bitcoin app (version 2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WalletPolicy {
+    name: String,
+    template: String,
+    keys: Vec<String>,
+}
+
+impl WalletPolicy {
+    /// Creates a policy from its descriptor template and key information strings.
+    pub(crate) fn new(name: &str, template: &str, keys: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            template: template.to_string(),
+            keys,
+        }
+    }
+
+    /// Returns the key information strings.
+    pub(crate) fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Returns the descriptor template.
+    pub(crate) fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Serializes the policy: version, name, template length and hash, key count and
+    /// the Merkle root of the keys.
+    pub(crate) fn serialize(&self) -> Vec<u8> {
+        let mut buf = vec![0x02, self.name.len() as u8];
+        buf.extend_from_slice(self.name.as_bytes());
+        write_varint(&mut buf, self.template.len() as u64);
+        buf.extend_from_slice(&sha256(self.template.as_bytes()));
+        write_varint(&mut buf, self.keys.len() as u64);
+        buf.extend_from_slice(&MerkleTree::from_elements(&self.keys).root());
+        buf
+    }
+
+    /// Returns the wallet id, the SHA-256 of the serialized policy.
+    pub(crate) fn id(&self) -> [u8; 32] {
+        sha256(&self.serialize())
+    }
+}
+
+/// Answers the client commands of one device request.
+#[derive(Debug, Default)]
+pub(crate) struct ClientCommandInterpreter {
+    preimages: HashMap<[u8; 32], Vec<u8>>,
+    trees: HashMap<[u8; 32], MerkleTree>,
+    queue: VecDeque<Vec<u8>>,
+    yielded: Vec<Vec<u8>>,
+}
+
+impl ClientCommandInterpreter {
+    /// Creates an interpreter that knows nothing yet.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the wallet policy, its template and its keys available to the device.
+    pub(crate) fn with_policy(mut self, policy: &WalletPolicy) -> Self {
+        self.add_known_preimage(policy.serialize());
+        self.add_known_preimage(policy.template().as_bytes().to_vec());
+        self.add_known_list(policy.keys());
+        self
+    }
+
+    /// Makes `preimage` available by its SHA-256.
+    pub(crate) fn add_known_preimage(&mut self, preimage: Vec<u8>) {
+        self.preimages.insert(sha256(&preimage), preimage);
+    }
+
+    /// Makes a list available as a Merkle tree, with the preimage of every leaf.
+    pub(crate) fn add_known_list<E: AsRef<[u8]>>(&mut self, elements: &[E]) {
+        for element in elements {
+            let mut preimage = vec![0x00];
+            preimage.extend_from_slice(element.as_ref());
+            self.add_known_preimage(preimage);
+        }
+        let tree = MerkleTree::from_elements(elements);
+        self.trees.insert(tree.root(), tree);
+    }
+
+    /// Makes a merkleized map available: its sorted keys and their values as two lists.
+    pub(crate) fn add_known_mapping<'a>(
+        &mut self,
+        entries: impl IntoIterator<Item = (&'a Vec<u8>, &'a Vec<u8>)>,
+    ) {
+        let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+        self.add_known_list(&keys);
+        self.add_known_list(&values);
+    }
+
+    /// Returns the data the device yielded, in order.
+    pub(crate) fn into_yielded(self) -> Vec<Vec<u8>> {
+        self.yielded
+    }
+
+    /// Answers one client command.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidResponse`] if the command is malformed, unknown or asks
+    /// for data the host does not have.
+    pub(crate) fn execute(&mut self, request: &[u8]) -> Result<Vec<u8>> {
+        let (&command, args) = request
+            .split_first()
+            .ok_or_else(|| invalid("empty client command"))?;
+        if command != GET_MORE_ELEMENTS && !self.queue.is_empty() {
+            return Err(invalid("client command received with data still queued"));
+        }
+        match command {
+            YIELD => {
+                self.yielded.push(args.to_vec());
+                Ok(Vec::new())
+            }
+            GET_PREIMAGE => self.get_preimage(args),
+            GET_MERKLE_LEAF_PROOF => self.get_merkle_leaf_proof(args),
+            GET_MERKLE_LEAF_INDEX => self.get_merkle_leaf_index(args),
+            GET_MORE_ELEMENTS => self.get_more_elements(args),
+            other => Err(invalid(format!("unknown client command 0x{:02X}", other))),
+        }
+    }
+
+    fn get_preimage(&mut self, args: &[u8]) -> Result<Vec<u8>> {
+        if args.len() != 33 || args[0] != 0 {
+            return Err(invalid("malformed GET_PREIMAGE"));
+        }
+        let hash: [u8; 32] = args[1..].try_into().expect("32 bytes");
+        let preimage = self
+            .preimages
+            .get(&hash)
+            .ok_or_else(|| invalid("device requested an unknown preimage"))?;
+
+        let mut response = Vec::new();
+        write_varint(&mut response, preimage.len() as u64);
+        let payload = preimage.len().min(MAX_RESPONSE - response.len() - 1);
+        response.push(payload as u8);
+        response.extend_from_slice(&preimage[..payload]);
+        self.queue
+            .extend(preimage[payload..].iter().map(|byte| vec![*byte]));
+        Ok(response)
+    }
+
+    fn get_merkle_leaf_proof(&mut self, args: &[u8]) -> Result<Vec<u8>> {
+        let root: [u8; 32] = args
+            .get(..32)
+            .and_then(|root| root.try_into().ok())
+            .ok_or_else(|| invalid("malformed GET_MERKLE_LEAF_PROOF"))?;
+        let (size, size_len) =
+            read_varint(&args[32..]).ok_or_else(|| invalid("malformed GET_MERKLE_LEAF_PROOF"))?;
+        let (index, index_len) = read_varint(&args[32 + size_len..])
+            .ok_or_else(|| invalid("malformed GET_MERKLE_LEAF_PROOF"))?;
+        if args.len() != 32 + size_len + index_len {
+            return Err(invalid("malformed GET_MERKLE_LEAF_PROOF"));
+        }
+        let tree = self
+            .trees
+            .get(&root)
+            .filter(|tree| tree.len() as u64 == size)
+            .ok_or_else(|| invalid("device requested an unknown Merkle tree"))?;
+        let index = usize::try_from(index).map_err(|_| invalid("leaf index out of range"))?;
+        let (leaf, proof) = tree
+            .leaf(index)
+            .zip(tree.proof(index))
+            .ok_or_else(|| invalid("leaf index out of range"))?;
+
+        let sent = proof.len().min(PROOF_HASHES_PER_RESPONSE);
+        let mut response = leaf.to_vec();
+        response.push(proof.len() as u8);
+        response.push(sent as u8);
+        for hash in &proof[..sent] {
+            response.extend_from_slice(hash);
+        }
+        self.queue
+            .extend(proof[sent..].iter().map(|hash| hash.to_vec()));
+        Ok(response)
+    }
+
+    fn get_merkle_leaf_index(&mut self, args: &[u8]) -> Result<Vec<u8>> {
+        if args.len() != 64 {
+            return Err(invalid("malformed GET_MERKLE_LEAF_INDEX"));
+        }
+        let root: [u8; 32] = args[..32].try_into().expect("32 bytes");
+        let leaf: [u8; 32] = args[32..].try_into().expect("32 bytes");
+        let tree = self
+            .trees
+            .get(&root)
+            .ok_or_else(|| invalid("device requested an unknown Merkle tree"))?;
+        let mut response = Vec::new();
+        match tree.index_of(&leaf) {
+            Some(index) => {
+                response.push(1);
+                write_varint(&mut response, index as u64);
+            }
+            None => {
+                response.push(0);
+                write_varint(&mut response, 0);
+            }
+        }
+        Ok(response)
+    }
+
+    fn get_more_elements(&mut self, args: &[u8]) -> Result<Vec<u8>> {
+        if !args.is_empty() {
+            return Err(invalid("malformed GET_MORE_ELEMENTS"));
+        }
+        let element_len = self
+            .queue
+            .front()
+            .map(Vec::len)
+            .ok_or_else(|| invalid("GET_MORE_ELEMENTS with nothing queued"))?;
+        let mut response = vec![0, element_len as u8];
+        while response.len() - 2 + element_len <= MAX_RESPONSE - 2 {
+            let Some(element) = self.queue.pop_front() else {
+                break;
+            };
+            response.extend_from_slice(&element);
+            response[0] += 1;
+        }
+        Ok(response)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidResponse(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::merkle::{element_hash, proof_root};
+
+    #[test]
+    fn test_policy_serialization() {
+        let policy = WalletPolicy::new("", "wpkh(@0/**)", vec!["[f5acc2fd/84'/1'/0']k".into()]);
+        let serialized = policy.serialize();
+        assert_eq!(serialized[0], 0x02);
+        assert_eq!(serialized[1], 0);
+        assert_eq!(serialized[2], 11);
+        assert_eq!(&serialized[3..35], &sha256(b"wpkh(@0/**)"));
+        assert_eq!(serialized[35], 1);
+        assert_eq!(&serialized[36..], &element_hash(b"[f5acc2fd/84'/1'/0']k"));
+        assert_eq!(policy.id(), sha256(&serialized));
+    }
+
+    #[test]
+    fn test_long_preimage_is_queued() {
+        let preimage: Vec<u8> = (0..=255u8).chain(0..=99).collect();
+        let mut client = ClientCommandInterpreter::new();
+        client.add_known_preimage(preimage.clone());
+
+        let mut request = vec![GET_PREIMAGE, 0];
+        request.extend_from_slice(&sha256(&preimage));
+        let response = client.execute(&request).unwrap();
+        // varint 0xfd 0x64 0x01, then 251 bytes
+        assert_eq!(&response[..3], &[0xfd, 0x64, 0x01]);
+        assert_eq!(response[3], 251);
+        let mut received = response[4..].to_vec();
+
+        // Other commands are refused until the queue is drained
+        assert!(client.execute(&request).is_err());
+        while received.len() < preimage.len() {
+            let more = client.execute(&[GET_MORE_ELEMENTS]).unwrap();
+            assert_eq!(more[1], 1);
+            assert_eq!(more.len(), 2 + more[0] as usize);
+            received.extend_from_slice(&more[2..]);
+        }
+        assert_eq!(received, preimage);
+        assert!(client.execute(&[GET_MORE_ELEMENTS]).is_err());
+    }
+
+    #[test]
+    fn test_merkle_leaf_proof_and_index() {
+        let elements: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; 3]).collect();
+        let root = MerkleTree::from_elements(&elements).root();
+        let mut client = ClientCommandInterpreter::new();
+        client.add_known_list(&elements);
+
+        let mut request = vec![GET_MERKLE_LEAF_PROOF];
+        request.extend_from_slice(&root);
+        request.extend_from_slice(&[100, 37]);
+        let response = client.execute(&request).unwrap();
+        let leaf: [u8; 32] = response[..32].try_into().unwrap();
+        assert_eq!(leaf, element_hash(&elements[37]));
+        let (total, sent) = (response[32] as usize, response[33] as usize);
+        assert_eq!((total, sent), (7, 6));
+        let mut proof: Vec<[u8; 32]> = response[34..]
+            .chunks(32)
+            .map(|c| c.try_into().unwrap())
+            .collect();
+        let more = client.execute(&[GET_MORE_ELEMENTS]).unwrap();
+        assert_eq!(&more[..2], &[1, 32]);
+        proof.push(more[2..].try_into().unwrap());
+        assert_eq!(proof_root(&leaf, &proof, 100, 37), Some(root));
+
+        let mut request = vec![GET_MERKLE_LEAF_INDEX];
+        request.extend_from_slice(&root);
+        request.extend_from_slice(&leaf);
+        assert_eq!(client.execute(&request).unwrap(), vec![1, 37]);
+        request[33..].copy_from_slice(&[0xAA; 32]);
+        assert_eq!(client.execute(&request).unwrap(), vec![0, 0]);
+
+        // Leaf preimages are known with their 0x00 prefix
+        let mut request = vec![GET_PREIMAGE, 0];
+        request.extend_from_slice(&leaf);
+        assert_eq!(client.execute(&request).unwrap(), vec![4, 4, 0, 37, 37, 37]);
+    }
+
+    #[test]
+    fn test_yield_and_unknown_commands() {
+        let mut client = ClientCommandInterpreter::new();
+        assert_eq!(client.execute(&[YIELD, 1, 2]).unwrap(), Vec::<u8>::new());
+        assert!(client.execute(&[0x77]).is_err());
+        assert!(client.execute(&[]).is_err());
+        let mut request = vec![GET_PREIMAGE, 0];
+        request.extend_from_slice(&[0u8; 32]);
+        assert!(client.execute(&request).is_err());
+        assert_eq!(client.into_yielded(), vec![vec![1, 2]]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Merkle trees and merkleized maps of the Ledger Bitcoin app.
+//!
+//! The app never holds a whole PSBT or wallet policy. The host sends Merkle roots and
+//! the device asks for single leaves with inclusion proofs. Leaves are hashed as
+//! `SHA-256(0x00 || element)` and nodes as `SHA-256(0x01 || left || right)`; the left
+//! subtree of `n` leaves holds the largest power of two strictly below `n`.
+
+use sha2::{Digest, Sha256};
+
+/// Returns `SHA-256(data)`.
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Returns the leaf hash of `element`.
+pub(crate) fn element_hash(element: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(element);
+    hasher.finalize().into()
+}
+
+/// Returns the hash of an internal node.
+pub(crate) fn combine_hashes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Appends `n` as a Bitcoin compact size.
+pub(crate) fn write_varint(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+/// Reads a compact size from the start of `data`, returning it and its length.
+pub(crate) fn read_varint(data: &[u8]) -> Option<(u64, usize)> {
+    let (&first, rest) = data.split_first()?;
+    let width = match first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        n => return Some((u64::from(n), 1)),
+    };
+    let bytes = rest.get(..width)?;
+    let mut value = [0u8; 8];
+    value[..width].copy_from_slice(bytes);
+    Some((u64::from_le_bytes(value), 1 + width))
+}
+
+/// Largest power of two strictly below `n`, for `n > 1`.
+fn left_size(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// A Merkle tree over leaf hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MerkleTree {
+    leaves: Vec<[u8; 32]>,
+}
+
+impl MerkleTree {
+    /// Builds the tree of already-hashed leaves.
+    pub(crate) fn new(leaves: Vec<[u8; 32]>) -> Self {
+        Self { leaves }
+    }
+
+    /// Builds the tree of `elements`, hashing each as a leaf.
+    pub(crate) fn from_elements<E: AsRef<[u8]>>(elements: &[E]) -> Self {
+        Self::new(elements.iter().map(|e| element_hash(e.as_ref())).collect())
+    }
+
+    /// Returns the number of leaves.
+    pub(crate) fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns the leaf hash at `index`.
+    pub(crate) fn leaf(&self, index: usize) -> Option<&[u8; 32]> {
+        self.leaves.get(index)
+    }
+
+    /// Returns the index of `leaf_hash`, if present.
+    pub(crate) fn index_of(&self, leaf_hash: &[u8; 32]) -> Option<usize> {
+        self.leaves.iter().position(|leaf| leaf == leaf_hash)
+    }
+
+    /// Returns the root; the root of an empty tree is all zeros.
+    pub(crate) fn root(&self) -> [u8; 32] {
+        if self.leaves.is_empty() {
+            return [0u8; 32];
+        }
+        root_of(&self.leaves)
+    }
+
+    /// Returns the sibling hashes from the leaf at `index` up to the root.
+    pub(crate) fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        let mut leaves = self.leaves.as_slice();
+        let mut index = index;
+        while leaves.len() > 1 {
+            let split = left_size(leaves.len());
+            let (left, right) = leaves.split_at(split);
+            if index < split {
+                proof.push(root_of(right));
+                leaves = left;
+            } else {
+                proof.push(root_of(left));
+                leaves = right;
+                index -= split;
+            }
+        }
+        proof.reverse();
+        Some(proof)
+    }
+}
+
+fn root_of(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let (left, right) = leaves.split_at(left_size(leaves.len()));
+    combine_hashes(&root_of(left), &root_of(right))
+}
+
+/// Returns the root a leaf and its bottom-up proof commit to, for a tree of `size`
+/// leaves, or `None` if the proof has the wrong length.
+#[cfg(test)]
+pub(crate) fn proof_root(
+    leaf_hash: &[u8; 32],
+    proof: &[[u8; 32]],
+    size: usize,
+    index: usize,
+) -> Option<[u8; 32]> {
+    if index >= size {
+        return None;
+    }
+    // Walk down to record which side the leaf is on at each level.
+    let mut sides = Vec::new();
+    let (mut size, mut index) = (size, index);
+    while size > 1 {
+        let split = left_size(size);
+        if index < split {
+            sides.push(true);
+            size = split;
+        } else {
+            sides.push(false);
+            size -= split;
+            index -= split;
+        }
+    }
+    if sides.len() != proof.len() {
+        return None;
+    }
+    let mut hash = *leaf_hash;
+    for (sibling, is_left) in proof.iter().zip(sides.iter().rev()) {
+        hash = if *is_left {
+            combine_hashes(&hash, sibling)
+        } else {
+            combine_hashes(sibling, &hash)
+        };
+    }
+    Some(hash)
+}
+
+/// Returns the commitment to a key-value map: `varint(n) || keys root || values root`,
+/// with the entries sorted by key.
+pub(crate) fn map_commitment<'a>(
+    entries: impl IntoIterator<Item = (&'a Vec<u8>, &'a Vec<u8>)>,
+) -> Vec<u8> {
+    let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+    let mut commitment = Vec::with_capacity(9 + 64);
+    write_varint(&mut commitment, keys.len() as u64);
+    commitment.extend_from_slice(&MerkleTree::from_elements(&keys).root());
+    commitment.extend_from_slice(&MerkleTree::from_elements(&values).root());
+    commitment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| element_hash(&[i])).collect()
+    }
+
+    #[test]
+    fn test_left_size() {
+        assert_eq!(left_size(2), 1);
+        assert_eq!(left_size(3), 2);
+        assert_eq!(left_size(4), 2);
+        assert_eq!(left_size(5), 4);
+        assert_eq!(left_size(8), 4);
+        assert_eq!(left_size(9), 8);
+    }
+
+    #[test]
+    fn test_roots() {
+        assert_eq!(MerkleTree::new(Vec::new()).root(), [0u8; 32]);
+        let l = leaves(3);
+        assert_eq!(MerkleTree::new(l[..1].to_vec()).root(), l[0]);
+        assert_eq!(
+            MerkleTree::new(l.clone()).root(),
+            combine_hashes(&combine_hashes(&l[0], &l[1]), &l[2])
+        );
+    }
+
+    #[test]
+    fn test_proofs_verify_for_every_leaf() {
+        for n in 1..=11u8 {
+            let tree = MerkleTree::new(leaves(n));
+            for i in 0..n as usize {
+                let proof = tree.proof(i).unwrap();
+                assert_eq!(
+                    proof_root(tree.leaf(i).unwrap(), &proof, n as usize, i),
+                    Some(tree.root()),
+                    "leaf {} of {}",
+                    i,
+                    n
+                );
+            }
+            assert!(tree.proof(n as usize).is_none());
+        }
+    }
+
+    #[test]
+    fn test_varint_round_trip() {
+        for n in [
+            0u64,
+            0xfc,
+            0xfd,
+            0xffff,
+            0x1_0000,
+            0xffff_ffff,
+            0x1_0000_0000,
+        ] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, n);
+            assert_eq!(read_varint(&buf), Some((n, buf.len())));
+        }
+        assert_eq!(read_varint(&[0xfd, 0x01]), None);
+    }
+}
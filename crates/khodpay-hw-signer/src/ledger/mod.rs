@@ -0,0 +1,419 @@
+//! Ledger devices, through the Ethereum app and the Bitcoin app.
+//!
+//! [`Ledger`] speaks the APDU protocols of the two apps over a host-provided
+//! [`Transport`]; the user opens the app matching the request on the device.
+//!
+//! - **Ethereum app**: addresses, EIP-1559 transactions and personal messages
+//! - **Bitcoin app** (version 2 protocol): master fingerprint, extended public keys,
+//!   wallet addresses and PSBT signing for the standard single-key policies
+//!   `pkh(@0/**)`, `sh(wpkh(@0/**))` and `wpkh(@0/**)`, which need no registration
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_bip32::DerivationPath;
+//! use khodpay_bip44::Chain;
+//! use khodpay_btc_signing::{Network, ScriptType};
+//! use khodpay_hw_signer::ledger::Ledger;
+//! use khodpay_hw_signer::{Signer, Transport};
+//! use std::str::FromStr;
+//!
+//! # fn open_hid() -> Box<dyn Transport> { unimplemented!() }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // The host app provides the USB HID or BLE connection.
+//! let mut ledger = Ledger::new(open_hid());
+//!
+//! // Bitcoin app: read the account and show a receive address on the device
+//! let account = ledger.btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)?;
+//! let address = ledger.btc_address(&account, Chain::External, 0, true)?;
+//! println!("receive to {}", address);
+//!
+//! // Ethereum app: verify an address
+//! let path = DerivationPath::from_str("m/44'/60'/0'/0/0")?;
+//! let evm = ledger.evm_address(&path, true)?;
+//! println!("EVM address {}", evm.to_checksum_string());
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod merkle;
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use khodpay_bip32::{DerivationPath, ExtendedPublicKey};
+use khodpay_bip44::Chain;
+use khodpay_btc_signing::psbt::Psbt;
+use khodpay_btc_signing::{Address as BtcAddress, ScriptType};
+use khodpay_signing::{Address, Eip1559Transaction, Signature};
+
+use self::client::{ClientCommandInterpreter, WalletPolicy};
+use self::merkle::{map_commitment, read_varint, write_varint, MerkleTree};
+use crate::signer::evm_signature;
+use crate::transport::{self, SW_DENIED, SW_LOCKED, SW_OK};
+use crate::{Apdu, BtcAccount, Error, Result, Signer, Transport};
+
+/// Instruction class of the Ethereum app.
+const ETH_CLA: u8 = 0xE0;
+const ETH_GET_ADDRESS: u8 = 0x02;
+const ETH_SIGN_TX: u8 = 0x04;
+const ETH_SIGN_PERSONAL_MESSAGE: u8 = 0x08;
+/// `P1` of the first chunk of a signing request.
+const ETH_FIRST_CHUNK: u8 = 0x00;
+/// `P1` of the following chunks.
+const ETH_MORE_CHUNKS: u8 = 0x80;
+
+/// Instruction class of the Bitcoin app.
+const BTC_CLA: u8 = 0xE1;
+const BTC_GET_EXTENDED_PUBKEY: u8 = 0x00;
+const BTC_GET_WALLET_ADDRESS: u8 = 0x03;
+const BTC_SIGN_PSBT: u8 = 0x04;
+const BTC_GET_MASTER_FINGERPRINT: u8 = 0x05;
+/// `P2` of Bitcoin app commands: protocol version 1.
+const BTC_PROTOCOL_VERSION: u8 = 0x01;
+
+/// Instruction class answering an interrupted Bitcoin app command.
+const FRAMEWORK_CLA: u8 = 0xF8;
+const CONTINUE_INTERRUPTED: u8 = 0x01;
+/// Status word of a command paused on a client command.
+const SW_INTERRUPTED: u16 = 0xE000;
+
+/// Longest derivation path the apps accept.
+const MAX_PATH_DEPTH: usize = 10;
+
+/// PSBT magic bytes: `psbt` followed by `0xff`.
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+/// A Ledger device.
+///
+/// Generic over the [`Transport`] so the host app can plug in USB HID, BLE or a
+/// Speculos emulator connection.
+#[derive(Debug)]
+pub struct Ledger<T> {
+    transport: T,
+}
+
+impl<T: Transport> Ledger<T> {
+    /// Creates a client for the device behind `transport`.
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// Returns the transport, releasing the device.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Sends a command and returns its data if the device accepted it.
+    fn exchange(&mut self, apdu: &Apdu) -> Result<Vec<u8>> {
+        let response = transport::exchange(&mut self.transport, apdu)?;
+        check_status(response.status)?;
+        Ok(response.data)
+    }
+
+    /// Sends a Bitcoin app command, answering the client commands it pauses on until
+    /// it completes.
+    fn exchange_interactive(
+        &mut self,
+        apdu: &Apdu,
+        client: &mut ClientCommandInterpreter,
+    ) -> Result<Vec<u8>> {
+        let mut response = transport::exchange(&mut self.transport, apdu)?;
+        while response.status == SW_INTERRUPTED {
+            let reply = client.execute(&response.data)?;
+            let apdu = Apdu::new(FRAMEWORK_CLA, CONTINUE_INTERRUPTED, 0, 0, reply);
+            response = transport::exchange(&mut self.transport, &apdu)?;
+        }
+        check_status(response.status)?;
+        Ok(response.data)
+    }
+
+    /// Sends `payload` to the Ethereum app in as many chunks as needed, returning the
+    /// response to the last one.
+    fn exchange_chunked(&mut self, ins: u8, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut response = Vec::new();
+        for (i, chunk) in payload.chunks(transport::MAX_APDU_DATA).enumerate() {
+            let p1 = if i == 0 {
+                ETH_FIRST_CHUNK
+            } else {
+                ETH_MORE_CHUNKS
+            };
+            response = self.exchange(&Apdu::new(ETH_CLA, ins, p1, 0x00, chunk.to_vec()))?;
+        }
+        Ok(response)
+    }
+
+    fn btc_apdu(ins: u8, data: Vec<u8>) -> Apdu {
+        Apdu::new(BTC_CLA, ins, 0x00, BTC_PROTOCOL_VERSION, data)
+    }
+}
+
+impl<T: Transport> Signer for Ledger<T> {
+    fn master_fingerprint(&mut self) -> Result<[u8; 4]> {
+        let data = self.exchange(&Self::btc_apdu(BTC_GET_MASTER_FINGERPRINT, Vec::new()))?;
+        data.as_slice()
+            .try_into()
+            .map_err(|_| invalid(format!("fingerprint of {} bytes", data.len())))
+    }
+
+    fn extended_public_key(
+        &mut self,
+        path: &DerivationPath,
+        display: bool,
+    ) -> Result<ExtendedPublicKey> {
+        let mut data = vec![u8::from(display)];
+        data.extend_from_slice(&encode_path(path)?);
+        let response = self.exchange(&Self::btc_apdu(BTC_GET_EXTENDED_PUBKEY, data))?;
+        let xpub = ExtendedPublicKey::from_str(&ascii(&response)?)
+            .map_err(|e| invalid(format!("extended public key: {}", e)))?;
+        if usize::from(xpub.depth()) != path.len() {
+            return Err(invalid(format!(
+                "extended public key of depth {} for a path of depth {}",
+                xpub.depth(),
+                path.len()
+            )));
+        }
+        Ok(xpub)
+    }
+
+    fn evm_address(&mut self, path: &DerivationPath, display: bool) -> Result<Address> {
+        let apdu = Apdu::new(
+            ETH_CLA,
+            ETH_GET_ADDRESS,
+            u8::from(display),
+            0x00,
+            encode_path(path)?,
+        );
+        let response = self.exchange(&apdu)?;
+
+        // pubkey length | uncompressed pubkey | address length | address as hex text
+        let (&key_len, rest) = response
+            .split_first()
+            .ok_or_else(|| invalid("empty address response"))?;
+        let public_key = rest
+            .get(..usize::from(key_len))
+            .filter(|key| key.len() == 65 && key[0] == 0x04)
+            .ok_or_else(|| invalid("missing uncompressed public key"))?;
+        let rest = &rest[usize::from(key_len)..];
+        let (&address_len, rest) = rest
+            .split_first()
+            .ok_or_else(|| invalid("missing address"))?;
+        let text = rest
+            .get(..usize::from(address_len))
+            .ok_or_else(|| invalid("truncated address"))?;
+        let text = ascii(text)?;
+        let reported = hex::decode(text.trim_start_matches("0x"))
+            .map_err(|_| invalid(format!("address {} is not hex", text)))?;
+
+        let address = Address::from_public_key_bytes(&public_key[1..])?;
+        if reported != address.as_bytes() {
+            return Err(invalid(format!(
+                "device address 0x{} does not match its public key",
+                hex::encode(reported)
+            )));
+        }
+        Ok(address)
+    }
+
+    fn sign_evm_transaction(
+        &mut self,
+        path: &DerivationPath,
+        tx: &Eip1559Transaction,
+    ) -> Result<Signature> {
+        let mut payload = encode_path(path)?;
+        payload.extend_from_slice(&tx.encode_unsigned());
+        let response = self.exchange_chunked(ETH_SIGN_TX, &payload)?;
+        parse_evm_signature(&response)
+    }
+
+    fn sign_evm_message(&mut self, path: &DerivationPath, message: &[u8]) -> Result<Signature> {
+        let length = u32::try_from(message.len())
+            .map_err(|_| Error::Unsupported("message longer than 4 GiB".to_string()))?;
+        let mut payload = encode_path(path)?;
+        payload.extend_from_slice(&length.to_be_bytes());
+        payload.extend_from_slice(message);
+        let response = self.exchange_chunked(ETH_SIGN_PERSONAL_MESSAGE, &payload)?;
+        parse_evm_signature(&response)
+    }
+
+    fn btc_address(
+        &mut self,
+        account: &BtcAccount,
+        chain: Chain,
+        index: u32,
+        display: bool,
+    ) -> Result<BtcAddress> {
+        let expected = account.address(chain, index)?;
+        let policy = policy(account);
+        let mut data = vec![u8::from(display)];
+        data.extend_from_slice(&policy.id());
+        data.extend_from_slice(&[0u8; 32]);
+        data.push(u8::from(chain.is_internal()));
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let mut client = ClientCommandInterpreter::new().with_policy(&policy);
+        let response =
+            self.exchange_interactive(&Self::btc_apdu(BTC_GET_WALLET_ADDRESS, data), &mut client)?;
+        let text = ascii(&response)?;
+        let address = BtcAddress::parse(&text, account.network())
+            .map_err(|e| invalid(format!("address {}: {}", text, e)))?;
+        if address != expected {
+            return Err(invalid(format!(
+                "device returned address {}, expected {}",
+                address, expected
+            )));
+        }
+        Ok(address)
+    }
+
+    fn sign_psbt(&mut self, account: &BtcAccount, psbt: &mut Psbt) -> Result<usize> {
+        let (global, inputs, outputs) = split_maps(&psbt.serialize(), psbt)?;
+        let policy = policy(account);
+        let mut client = ClientCommandInterpreter::new().with_policy(&policy);
+        client.add_known_mapping(&global);
+        for map in inputs.iter().chain(&outputs) {
+            client.add_known_mapping(map);
+        }
+        let input_commitments: Vec<_> = inputs.iter().map(map_commitment).collect();
+        let output_commitments: Vec<_> = outputs.iter().map(map_commitment).collect();
+        client.add_known_list(&input_commitments);
+        client.add_known_list(&output_commitments);
+
+        let mut data = map_commitment(&global);
+        write_varint(&mut data, inputs.len() as u64);
+        data.extend_from_slice(&MerkleTree::from_elements(&input_commitments).root());
+        write_varint(&mut data, outputs.len() as u64);
+        data.extend_from_slice(&MerkleTree::from_elements(&output_commitments).root());
+        data.extend_from_slice(&policy.id());
+        data.extend_from_slice(&[0u8; 32]);
+
+        self.exchange_interactive(&Self::btc_apdu(BTC_SIGN_PSBT, data), &mut client)?;
+
+        // Each yield: input index | pubkey length | pubkey | signature
+        let mut signed = 0;
+        for yielded in client.into_yielded() {
+            let (index, index_len) =
+                read_varint(&yielded).ok_or_else(|| invalid("malformed signature"))?;
+            let rest = &yielded[index_len..];
+            let (&key_len, rest) = rest
+                .split_first()
+                .ok_or_else(|| invalid("malformed signature"))?;
+            if key_len != 33 || rest.len() <= 33 {
+                return Err(invalid("expected an ECDSA signature for a compressed key"));
+            }
+            let (public_key, signature) = rest.split_at(33);
+            let input = usize::try_from(index)
+                .ok()
+                .and_then(|index| psbt.input_mut(index))
+                .ok_or_else(|| invalid(format!("signature for unknown input {}", index)))?;
+            input
+                .partial_sigs
+                .insert(public_key.to_vec(), signature.to_vec());
+            signed += 1;
+        }
+        Ok(signed)
+    }
+}
+
+/// Maps a status word other than success to an error.
+fn check_status(status: u16) -> Result<()> {
+    let message = match status {
+        SW_OK => return Ok(()),
+        SW_DENIED => return Err(Error::UserRejected),
+        SW_LOCKED => return Err(Error::DeviceLocked),
+        0x6D00 | 0x6E00 | 0x6E01 | 0x6511 => return Err(Error::WrongApp(status)),
+        0x6700 => "wrong data length",
+        0x6982 => "security status not satisfied",
+        0x6A80 => "invalid data",
+        0x6A82 => "not supported",
+        0x6A86 | 0x6B00 => "wrong parameters",
+        _ => "unknown status",
+    };
+    Err(Error::Device {
+        status,
+        message: message.to_string(),
+    })
+}
+
+/// Encodes a path as its depth followed by big-endian child numbers.
+fn encode_path(path: &DerivationPath) -> Result<Vec<u8>> {
+    if path.len() > MAX_PATH_DEPTH {
+        return Err(Error::Unsupported(format!(
+            "derivation path of depth {}",
+            path.len()
+        )));
+    }
+    let mut data = vec![path.len() as u8];
+    for child in path.iter() {
+        data.extend_from_slice(&child.to_index().to_be_bytes());
+    }
+    Ok(data)
+}
+
+/// Parses the `v || r || s` signature of the Ethereum app.
+fn parse_evm_signature(data: &[u8]) -> Result<Signature> {
+    if data.len() != 65 {
+        return Err(invalid(format!("signature of {} bytes", data.len())));
+    }
+    evm_signature(&data[1..33], &data[33..65], u64::from(data[0]))
+}
+
+/// Returns the standard wallet policy of a single-key account.
+fn policy(account: &BtcAccount) -> WalletPolicy {
+    let template = match account.script_type() {
+        ScriptType::P2pkh => "pkh(@0/**)",
+        ScriptType::P2shP2wpkh => "sh(wpkh(@0/**))",
+        ScriptType::P2wpkh => "wpkh(@0/**)",
+    };
+    WalletPolicy::new("", template, vec![account.key_origin()])
+}
+
+type RawMap = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Splits a serialized PSBT into its global, input and output key-value maps.
+fn split_maps(data: &[u8], psbt: &Psbt) -> Result<(RawMap, Vec<RawMap>, Vec<RawMap>)> {
+    let malformed = || Error::Unsupported("PSBT could not be split into maps".to_string());
+    let mut rest = data.strip_prefix(PSBT_MAGIC).ok_or_else(malformed)?;
+    let mut read_map = || -> Option<RawMap> {
+        let mut map = RawMap::new();
+        loop {
+            let (key_len, n) = read_varint(rest)?;
+            rest = &rest[n..];
+            if key_len == 0 {
+                return Some(map);
+            }
+            let key = rest.get(..usize::try_from(key_len).ok()?)?.to_vec();
+            rest = &rest[key.len()..];
+            let (value_len, n) = read_varint(rest)?;
+            rest = &rest[n..];
+            let value = rest.get(..usize::try_from(value_len).ok()?)?.to_vec();
+            rest = &rest[value.len()..];
+            map.insert(key, value);
+        }
+    };
+    let global = read_map().ok_or_else(malformed)?;
+    let inputs = (0..psbt.inputs().len())
+        .map(|_| read_map())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(malformed)?;
+    let outputs = (0..psbt.outputs().len())
+        .map(|_| read_map())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(malformed)?;
+    Ok((global, inputs, outputs))
+}
+
+/// Decodes a printable ASCII response.
+fn ascii(data: &[u8]) -> Result<String> {
+    if !data.iter().all(|b| b.is_ascii_graphic()) {
+        return Err(invalid("response is not printable text"));
+    }
+    Ok(String::from_utf8(data.to_vec()).expect("ASCII is UTF-8"))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidResponse(message.into())
+}
@@ -0,0 +1,541 @@
+//! [`Ledger`] against emulated Ethereum and Bitcoin apps.
+//!
+//! The emulator runs on its own thread and plays the device side of the protocols,
+//! including the Bitcoin app's client commands, with keys from the BIP-39 test
+//! mnemonic.
+
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use k256::ecdsa::SigningKey;
+use khodpay_bip32::{ChildNumber, ExtendedPrivateKey, Network as Bip32Network};
+use khodpay_bip39::{Language, Mnemonic};
+use khodpay_btc_signing::psbt::{PsbtInput, PsbtOutput};
+use khodpay_btc_signing::{BtcSigner, Network, OutPoint, TxOut, Txid};
+use khodpay_signing::{recover_signer, ChainId, Wei};
+use sha3::{Digest, Keccak256};
+
+use super::merkle::{element_hash, proof_root, sha256};
+use super::*;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn master() -> ExtendedPrivateKey {
+    let mnemonic = Mnemonic::from_phrase(MNEMONIC, Language::English).unwrap();
+    ExtendedPrivateKey::from_mnemonic(&mnemonic, None, Bip32Network::BitcoinMainnet).unwrap()
+}
+
+/// Host end of the emulator.
+struct Emulator {
+    to_device: Sender<Vec<u8>>,
+    from_device: Receiver<Vec<u8>>,
+}
+
+impl Transport for Emulator {
+    fn exchange(
+        &mut self,
+        apdu: &[u8],
+    ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.to_device.send(apdu.to_vec())?;
+        Ok(self.from_device.recv()?)
+    }
+}
+
+/// Device end of the emulator.
+struct Device {
+    from_host: Receiver<Vec<u8>>,
+    to_host: Sender<Vec<u8>>,
+    master: ExtendedPrivateKey,
+}
+
+impl Device {
+    fn reply(&self, data: &[u8], status: u16) {
+        let mut response = data.to_vec();
+        response.extend_from_slice(&status.to_be_bytes());
+        self.to_host.send(response).unwrap();
+    }
+
+    /// Sends a client command and returns the host's `CONTINUE` data.
+    fn client(&self, request: &[u8]) -> Vec<u8> {
+        self.reply(request, SW_INTERRUPTED);
+        let apdu = self.from_host.recv().unwrap();
+        assert_eq!(&apdu[..2], &[FRAMEWORK_CLA, CONTINUE_INTERRUPTED]);
+        apdu[5..].to_vec()
+    }
+
+    fn get_preimage(&self, hash: &[u8; 32]) -> Vec<u8> {
+        let mut request = vec![client::GET_PREIMAGE, 0];
+        request.extend_from_slice(hash);
+        let response = self.client(&request);
+        let (len, n) = read_varint(&response).unwrap();
+        let mut preimage = response[n + 1..n + 1 + response[n] as usize].to_vec();
+        while (preimage.len() as u64) < len {
+            let more = self.client(&[client::GET_MORE_ELEMENTS]);
+            assert_eq!(more[1], 1);
+            preimage.extend_from_slice(&more[2..]);
+        }
+        assert_eq!(sha256(&preimage), *hash);
+        preimage
+    }
+
+    /// Fetches and verifies leaf `index` of a tree, returning the element.
+    fn get_element(&self, root: &[u8; 32], size: usize, index: usize) -> Vec<u8> {
+        let mut request = vec![client::GET_MERKLE_LEAF_PROOF];
+        request.extend_from_slice(root);
+        write_varint(&mut request, size as u64);
+        write_varint(&mut request, index as u64);
+        let response = self.client(&request);
+        let leaf: [u8; 32] = response[..32].try_into().unwrap();
+        let total = response[32] as usize;
+        let mut proof: Vec<[u8; 32]> = response[34..]
+            .chunks(32)
+            .map(|c| c.try_into().unwrap())
+            .collect();
+        while proof.len() < total {
+            let more = self.client(&[client::GET_MORE_ELEMENTS]);
+            assert_eq!(more[1], 32);
+            proof.extend(
+                more[2..]
+                    .chunks(32)
+                    .map(|c| <[u8; 32]>::try_from(c).unwrap()),
+            );
+        }
+        assert_eq!(proof_root(&leaf, &proof, size, index), Some(*root));
+        let preimage = self.get_preimage(&leaf);
+        assert_eq!(preimage[0], 0x00);
+        preimage[1..].to_vec()
+    }
+
+    /// Reads a merkleized map commitment back into its entries.
+    fn get_map(&self, commitment: &[u8]) -> RawMap {
+        let (size, n) = read_varint(commitment).unwrap();
+        let keys_root: [u8; 32] = commitment[n..n + 32].try_into().unwrap();
+        let values_root: [u8; 32] = commitment[n + 32..n + 64].try_into().unwrap();
+        let size = size as usize;
+        (0..size)
+            .map(|i| {
+                let key = self.get_element(&keys_root, size, i);
+                // Look the value up by key, as the app does
+                let mut request = vec![client::GET_MERKLE_LEAF_INDEX];
+                request.extend_from_slice(&keys_root);
+                request.extend_from_slice(&element_hash(&key));
+                let response = self.client(&request);
+                assert_eq!(response[0], 1);
+                let (index, _) = read_varint(&response[1..]).unwrap();
+                assert_eq!(index as usize, i);
+                (key, self.get_element(&values_root, size, i))
+            })
+            .collect()
+    }
+
+    /// Reads the wallet policy `id`, returning the script type and the account key.
+    fn get_policy(&self, id: &[u8; 32]) -> (ScriptType, ExtendedPrivateKey) {
+        let policy = self.get_preimage(id);
+        assert_eq!(&policy[..2], &[0x02, 0x00]);
+        let (_, n) = read_varint(&policy[2..]).unwrap();
+        let template_hash: [u8; 32] = policy[2 + n..34 + n].try_into().unwrap();
+        let (keys, m) = read_varint(&policy[34 + n..]).unwrap();
+        assert_eq!(keys, 1);
+        let keys_root: [u8; 32] = policy[34 + n + m..].try_into().unwrap();
+
+        let template = String::from_utf8(self.get_preimage(&template_hash)).unwrap();
+        let script_type = match template.as_str() {
+            "pkh(@0/**)" => ScriptType::P2pkh,
+            "sh(wpkh(@0/**))" => ScriptType::P2shP2wpkh,
+            "wpkh(@0/**)" => ScriptType::P2wpkh,
+            other => panic!("unexpected template {}", other),
+        };
+        let key = String::from_utf8(self.get_element(&keys_root, 1, 0)).unwrap();
+        // Keys are trusted by their origin alone, so a host holding a stale xpub gets
+        // addresses of the device's own key
+        let (origin, _xpub) = key[1..].split_once(']').unwrap();
+        let (fingerprint, path) = origin.split_once('/').unwrap();
+        assert_eq!(fingerprint, hex::encode(self.master.fingerprint()));
+        let path = DerivationPath::from_str(&format!("m/{}", path)).unwrap();
+        (script_type, self.master.derive_path(&path).unwrap())
+    }
+
+    fn run_bitcoin(&self, apdu: &[u8]) {
+        let (ins, data) = (apdu[1], &apdu[5..]);
+        assert_eq!((apdu[0], apdu[3]), (BTC_CLA, BTC_PROTOCOL_VERSION));
+        match ins {
+            BTC_GET_MASTER_FINGERPRINT => self.reply(&self.master.fingerprint(), SW_OK),
+            BTC_GET_EXTENDED_PUBKEY => {
+                let path = decode_path(&data[1..]);
+                let xpub = self
+                    .master
+                    .derive_path(&path)
+                    .unwrap()
+                    .to_extended_public_key();
+                self.reply(xpub.to_string().as_bytes(), SW_OK);
+            }
+            BTC_GET_WALLET_ADDRESS => {
+                let id: [u8; 32] = data[1..33].try_into().unwrap();
+                assert_eq!(&data[33..65], &[0u8; 32]);
+                let (script_type, account) = self.get_policy(&id);
+                let index = u32::from_be_bytes(data[66..70].try_into().unwrap());
+                let key = account
+                    .derive_child(ChildNumber::Normal(u32::from(data[65])))
+                    .unwrap()
+                    .derive_child(ChildNumber::Normal(index))
+                    .unwrap();
+                let signer =
+                    BtcSigner::from_private_key(&key.private_key().to_bytes(), Network::Bitcoin)
+                        .unwrap();
+                self.reply(
+                    signer.address_for(script_type).to_string().as_bytes(),
+                    SW_OK,
+                );
+            }
+            BTC_SIGN_PSBT => {
+                let global = self.get_map(&data[..65]);
+                let mut offset = 65;
+                let mut lists = Vec::new();
+                for _ in 0..2 {
+                    let (size, n) = read_varint(&data[offset..]).unwrap();
+                    let root: [u8; 32] = data[offset + n..offset + n + 32].try_into().unwrap();
+                    lists.push((size as usize, root));
+                    offset += n + 32;
+                }
+                let id: [u8; 32] = data[offset..offset + 32].try_into().unwrap();
+                let (script_type, _) = self.get_policy(&id);
+
+                let mut raw = PSBT_MAGIC.to_vec();
+                let mut write_map = |map: &RawMap| {
+                    for (key, value) in map {
+                        write_varint(&mut raw, key.len() as u64);
+                        raw.extend_from_slice(key);
+                        write_varint(&mut raw, value.len() as u64);
+                        raw.extend_from_slice(value);
+                    }
+                    raw.push(0x00);
+                };
+                write_map(&global);
+                for (size, root) in lists {
+                    for i in 0..size {
+                        write_map(&self.get_map(&self.get_element(&root, size, i)));
+                    }
+                }
+                let mut psbt = Psbt::deserialize(&raw).unwrap();
+
+                // Sign with every key the inputs attribute to this device
+                let sources: Vec<_> = psbt
+                    .inputs()
+                    .iter()
+                    .flat_map(|input| input.bip32_derivation.values().cloned())
+                    .filter(|source| source.fingerprint == self.master.fingerprint())
+                    .collect();
+                for source in sources {
+                    let path = DerivationPath::new(
+                        source
+                            .path
+                            .iter()
+                            .map(|i| ChildNumber::from_index(*i))
+                            .collect(),
+                    );
+                    let key = self.master.derive_path(&path).unwrap();
+                    let signer = BtcSigner::from_private_key(
+                        &key.private_key().to_bytes(),
+                        Network::Bitcoin,
+                    )
+                    .unwrap()
+                    .with_script_type(script_type);
+                    psbt.sign(&signer).unwrap();
+                }
+                for (index, input) in psbt.inputs().iter().enumerate() {
+                    for (public_key, signature) in &input.partial_sigs {
+                        let mut request = vec![client::YIELD];
+                        write_varint(&mut request, index as u64);
+                        request.push(public_key.len() as u8);
+                        request.extend_from_slice(public_key);
+                        request.extend_from_slice(signature);
+                        assert!(self.client(&request).is_empty());
+                    }
+                }
+                self.reply(&[], SW_OK);
+            }
+            _ => self.reply(&[], 0x6D00),
+        }
+    }
+}
+
+fn decode_path(data: &[u8]) -> DerivationPath {
+    let depth = data[0] as usize;
+    DerivationPath::new(
+        data[1..1 + 4 * depth]
+            .chunks(4)
+            .map(|c| ChildNumber::from_index(u32::from_be_bytes(c.try_into().unwrap())))
+            .collect(),
+    )
+}
+
+/// Starts an emulated device running `app` for every command.
+fn emulator(app: fn(&Device, &[u8])) -> Ledger<Emulator> {
+    let (to_device, from_host) = channel();
+    let (to_host, from_device) = channel();
+    thread::spawn(move || {
+        let device = Device {
+            from_host,
+            to_host,
+            master: master(),
+        };
+        while let Ok(apdu) = device.from_host.recv() {
+            app(&device, &apdu);
+        }
+    });
+    Ledger::new(Emulator {
+        to_device,
+        from_device,
+    })
+}
+
+fn bitcoin_app(device: &Device, apdu: &[u8]) {
+    if apdu[0] == BTC_CLA {
+        device.run_bitcoin(apdu);
+    } else {
+        device.reply(&[], 0x6E00);
+    }
+}
+
+/// The Ethereum app, signing once a chunked payload is complete.
+fn ethereum_app(device: &Device, apdu: &[u8]) {
+    if apdu[0] != ETH_CLA {
+        return device.reply(&[], 0x6E00);
+    }
+    let path = decode_path(&apdu[5..]);
+    let key = device.master.derive_path(&path).unwrap();
+    let signing_key = SigningKey::from_slice(&key.private_key().to_bytes()).unwrap();
+    let mut payload = apdu[5 + 1 + 4 * path.len()..].to_vec();
+
+    match apdu[1] {
+        ETH_GET_ADDRESS => {
+            let public_key = signing_key.verifying_key().to_encoded_point(false);
+            let address = Keccak256::digest(&public_key.as_bytes()[1..]);
+            let mut response = vec![65];
+            response.extend_from_slice(public_key.as_bytes());
+            response.push(40);
+            response.extend_from_slice(hex::encode(&address[12..]).as_bytes());
+            device.reply(&response, SW_OK);
+        }
+        ETH_SIGN_TX | ETH_SIGN_PERSONAL_MESSAGE => {
+            let ins = apdu[1];
+            let expected = if ins == ETH_SIGN_TX {
+                // Type byte, then the RLP list header gives the length
+                match payload[1] {
+                    h @ 0xc0..=0xf7 => 2 + usize::from(h - 0xc0),
+                    h => {
+                        let n = usize::from(h - 0xf7);
+                        let mut len = 0usize;
+                        for b in &payload[2..2 + n] {
+                            len = len << 8 | usize::from(*b);
+                        }
+                        2 + n + len
+                    }
+                }
+            } else {
+                4 + u32::from_be_bytes(payload[..4].try_into().unwrap()) as usize
+            };
+            while payload.len() < expected {
+                device.reply(&[], SW_OK);
+                let next = device.from_host.recv().unwrap();
+                assert_eq!(&next[..4], &[ETH_CLA, ins, ETH_MORE_CHUNKS, 0x00]);
+                payload.extend_from_slice(&next[5..]);
+            }
+            let (hash, v_offset): ([u8; 32], u8) = if ins == ETH_SIGN_TX {
+                (Keccak256::digest(&payload).into(), 0)
+            } else {
+                (khodpay_signing::eip191::hash_message(&payload[4..]), 27)
+            };
+            let (signature, recovery_id) = signing_key.sign_prehash_recoverable(&hash).unwrap();
+            let mut response = vec![recovery_id.to_byte() + v_offset];
+            response.extend_from_slice(&signature.to_bytes());
+            device.reply(&response, SW_OK);
+        }
+        _ => device.reply(&[], 0x6D00),
+    }
+}
+
+fn path(s: &str) -> DerivationPath {
+    DerivationPath::from_str(s).unwrap()
+}
+
+#[test]
+fn test_evm_address() {
+    let mut ledger = emulator(ethereum_app);
+    let address = ledger.evm_address(&path("m/44'/60'/0'/0/0"), true).unwrap();
+    assert_eq!(
+        address.to_checksum_string(),
+        "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+    );
+}
+
+#[test]
+fn test_sign_evm_transaction_and_message() {
+    let mut ledger = emulator(ethereum_app);
+    let path = path("m/44'/60'/0'/0/0");
+    let from = ledger.evm_address(&path, false).unwrap();
+
+    // Calldata long enough to need several chunks
+    let tx = Eip1559Transaction::builder()
+        .chain_id(ChainId::BscMainnet)
+        .nonce(7)
+        .max_priority_fee_per_gas(Wei::from_gwei(1))
+        .max_fee_per_gas(Wei::from_gwei(5))
+        .gas_limit(200_000)
+        .to(from)
+        .value(Wei::ZERO)
+        .data(vec![0xab; 600])
+        .build()
+        .unwrap();
+    let signature = ledger.sign_evm_transaction(&path, &tx).unwrap();
+    assert!(signature.v <= 1);
+    assert_eq!(
+        recover_signer(&tx.signing_hash(), &signature).unwrap(),
+        from
+    );
+
+    let signature = ledger.sign_evm_message(&path, b"hello ledger").unwrap();
+    assert!(signature.v <= 1);
+    assert_eq!(
+        khodpay_signing::eip191::recover_message_signer(b"hello ledger", &signature).unwrap(),
+        from
+    );
+}
+
+#[test]
+fn test_btc_account_and_address() {
+    let mut ledger = emulator(bitcoin_app);
+    let account = ledger
+        .btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)
+        .unwrap();
+    assert_eq!(account.fingerprint(), [0x73, 0xc5, 0xda, 0x0a]);
+    assert_eq!(account.path().to_string(), "m/84'/0'/0'");
+    assert_eq!(
+        ledger
+            .btc_address(&account, Chain::External, 0, true)
+            .unwrap()
+            .to_string(),
+        "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+    );
+
+    let legacy = ledger
+        .btc_account(ScriptType::P2pkh, Network::Bitcoin, 0)
+        .unwrap();
+    assert_eq!(
+        ledger
+            .btc_address(&legacy, Chain::External, 0, false)
+            .unwrap()
+            .to_string(),
+        "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+    );
+
+    // A host holding a different key for the account is caught
+    let other = BtcAccount::new(
+        ScriptType::P2wpkh,
+        Network::Bitcoin,
+        account.fingerprint(),
+        account.path().clone(),
+        legacy.xpub().clone(),
+    );
+    assert!(matches!(
+        ledger.btc_address(&other, Chain::External, 0, false),
+        Err(Error::InvalidResponse(_))
+    ));
+}
+
+#[test]
+fn test_sign_psbt() {
+    let mut ledger = emulator(bitcoin_app);
+    let account = ledger
+        .btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)
+        .unwrap();
+
+    let mut psbt = Psbt::new(2);
+    for (vout, index) in [(0, 0), (1, 3)] {
+        let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([0xab; 32]), vout));
+        input.witness_utxo = Some(TxOut {
+            value: 40_000,
+            script_pubkey: account
+                .address(Chain::External, index)
+                .unwrap()
+                .script_pubkey(),
+        });
+        input.bip32_derivation.insert(
+            account.public_key(Chain::External, index).unwrap().to_vec(),
+            account.key_source(Chain::External, index),
+        );
+        psbt.add_input(input).unwrap();
+    }
+    // An input of another wallet stays unsigned
+    let stranger = BtcSigner::from_private_key(&[9u8; 32], Network::Bitcoin).unwrap();
+    let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([0xcd; 32]), 0));
+    input.witness_utxo = Some(TxOut {
+        value: 10_000,
+        script_pubkey: stranger.p2wpkh_address().script_pubkey(),
+    });
+    psbt.add_input(input).unwrap();
+    psbt.add_output(PsbtOutput::new(
+        85_000,
+        account.address(Chain::Internal, 0).unwrap().script_pubkey(),
+    ))
+    .unwrap();
+
+    assert_eq!(ledger.sign_psbt(&account, &mut psbt).unwrap(), 2);
+    assert_eq!(psbt.inputs()[0].partial_sigs.len(), 1);
+    assert_eq!(psbt.inputs()[1].partial_sigs.len(), 1);
+    assert!(psbt.inputs()[2].partial_sigs.is_empty());
+
+    // The signatures match the software signer's
+    let key = master().derive_path(&path("m/84'/0'/0'/0/3")).unwrap();
+    let mut expected = psbt.clone();
+    expected.input_mut(1).unwrap().partial_sigs.clear();
+    expected
+        .sign(
+            &BtcSigner::from_private_key(&key.private_key().to_bytes(), Network::Bitcoin).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        psbt.inputs()[1].partial_sigs,
+        expected.inputs()[1].partial_sigs
+    );
+}
+
+#[test]
+fn test_wrong_app_and_status_words() {
+    let mut ledger = emulator(ethereum_app);
+    assert!(matches!(
+        ledger.master_fingerprint(),
+        Err(Error::WrongApp(0x6E00))
+    ));
+    let mut ledger = emulator(bitcoin_app);
+    assert!(matches!(
+        ledger.evm_address(&path("m/44'/60'/0'/0/0"), false),
+        Err(Error::WrongApp(0x6E00))
+    ));
+
+    assert!(check_status(SW_OK).is_ok());
+    assert!(matches!(check_status(SW_DENIED), Err(Error::UserRejected)));
+    assert!(matches!(check_status(SW_LOCKED), Err(Error::DeviceLocked)));
+    assert!(matches!(
+        check_status(0x6A80),
+        Err(Error::Device { status: 0x6A80, .. })
+    ));
+}
+
+#[test]
+fn test_encode_path_and_signature() {
+    assert_eq!(
+        encode_path(&path("m/44'/60'/0'")).unwrap(),
+        vec![3, 0x80, 0, 0, 44, 0x80, 0, 0, 60, 0x80, 0, 0, 0]
+    );
+    assert!(encode_path(&path("m/0/1/2/3/4/5/6/7/8/9/10")).is_err());
+
+    let mut data = [0u8; 65];
+    data[0] = 28;
+    assert_eq!(parse_evm_signature(&data).unwrap().v, 1);
+    data[0] = 37;
+    assert!(parse_evm_signature(&data).is_err());
+    assert!(parse_evm_signature(&data[..64]).is_err());
+}
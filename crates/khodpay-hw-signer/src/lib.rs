@@ -0,0 +1,69 @@
+//! # Khodpay Hardware Signer
+//!
+//! Hardware wallet support: keys stay on the device while this crate builds the
+//! requests, checks the answers and merges the signatures into EVM transactions and
+//! Bitcoin PSBTs.
+//!
+//! Devices sit behind the [`Signer`] trait, so wallet code works the same with any of
+//! them, and talk through a [`Transport`] or [`MessageTransport`] the host app provides
+//! (USB HID, BLE, Trezor Bridge or an emulator socket). This crate does no I/O of its
+//! own.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`Signer`] | Device-agnostic xpub export, address verification and signing |
+//! | [`BtcAccount`] | A device-held BIP-44 / BIP-49 / BIP-84 account with host-side address derivation |
+//! | [`Transport`] | Host-provided APDU channel |
+//! | [`MessageTransport`] | Host-provided Trezor message channel |
+//! | [`ledger`] | Ledger Ethereum and Bitcoin apps |
+//! | [`trezor`] | Trezor Bitcoin and Ethereum support |
+//!
+//! ## Features
+//!
+//! - **Extended Public Keys**: Export account xpubs to watch balances without the device
+//! - **Address Verification**: Show addresses on the device screen, cross-checked
+//!   against the host's own derivation
+//! - **EVM Signing**: EIP-1559 transactions and EIP-191 personal messages
+//! - **PSBT Signing**: Standard single-key BIP-44 / BIP-49 / BIP-84 accounts, with the
+//!   signatures added to the PSBT's partial signatures
+//! - **Pluggable Transport**: Bring your own HID, BLE, Bridge or emulator connection
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use khodpay_btc_signing::psbt::Psbt;
+//! use khodpay_btc_signing::{Network, ScriptType};
+//! use khodpay_hw_signer::ledger::Ledger;
+//! use khodpay_hw_signer::{Signer, Transport};
+//!
+//! # fn open_hid() -> Box<dyn Transport> { unimplemented!() }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut device = Ledger::new(open_hid());
+//! let account = device.btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)?;
+//!
+//! // Build the PSBT on the host, tagging inputs with `account.key_source(..)`
+//! let mut psbt = Psbt::from_base64("cHNidP8B...")?;
+//! let signed = device.sign_psbt(&account, &mut psbt)?;
+//! psbt.finalize()?;
+//! println!("{} inputs signed: {}", signed, psbt.extract_tx()?.to_hex());
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod error;
+pub mod ledger;
+mod signer;
+mod transport;
+pub mod trezor;
+
+pub use error::{Error, Result};
+pub use signer::{BtcAccount, Signer};
+pub use transport::{
+    Apdu, MessageTransport, Transport, MAX_APDU_DATA, SW_DENIED, SW_LOCKED, SW_OK,
+};
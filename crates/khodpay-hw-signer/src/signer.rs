@@ -0,0 +1,322 @@
+//! The device-agnostic [`Signer`] trait and the Bitcoin accounts it signs for.
+
+use khodpay_bip32::{ChildNumber, DerivationPath, ExtendedPublicKey};
+use khodpay_bip44::Chain;
+use khodpay_btc_signing::psbt::{KeySource, Psbt};
+use khodpay_btc_signing::{Address as BtcAddress, Network, ScriptType};
+use khodpay_signing::{Address, Eip1559Transaction, Signature};
+
+use crate::{Error, Result};
+
+/// A hardware wallet that holds keys and signs on request.
+///
+/// Keys never leave the device: the host asks for public data (extended public keys,
+/// addresses) and for signatures, and the device asks its user to confirm what it
+/// signs. Every method talks to the device, so calls may block until the user
+/// answers.
+pub trait Signer {
+    /// Returns the fingerprint of the device's master key, which PSBTs use to tell
+    /// which inputs it can sign.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached or refuses the request.
+    fn master_fingerprint(&mut self) -> Result<[u8; 4]>;
+
+    /// Returns the extended public key at `path`, optionally showing it on the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached, refuses the request or
+    /// returns an invalid key.
+    fn extended_public_key(
+        &mut self,
+        path: &DerivationPath,
+        display: bool,
+    ) -> Result<ExtendedPublicKey>;
+
+    /// Returns the EVM address at `path`. With `display` set the device shows it for
+    /// the user to compare against the one the host shows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached, the user rejects the address
+    /// or the device returns an inconsistent one.
+    fn evm_address(&mut self, path: &DerivationPath, display: bool) -> Result<Address>;
+
+    /// Signs an EIP-1559 transaction with the key at `path` after the user reviews it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached or the user rejects the
+    /// transaction.
+    fn sign_evm_transaction(
+        &mut self,
+        path: &DerivationPath,
+        tx: &Eip1559Transaction,
+    ) -> Result<Signature>;
+
+    /// Signs an EIP-191 personal message with the key at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached or the user rejects the
+    /// message.
+    fn sign_evm_message(&mut self, path: &DerivationPath, message: &[u8]) -> Result<Signature>;
+
+    /// Returns the address at `chain` / `index` of `account`. With `display` set the
+    /// device shows it for the user to verify.
+    ///
+    /// The address is checked against the one derived from the account's extended
+    /// public key, so a host showing the wrong address is caught.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached, the user rejects the address
+    /// or the device returns a different address than expected.
+    fn btc_address(
+        &mut self,
+        account: &BtcAccount,
+        chain: Chain,
+        index: u32,
+        display: bool,
+    ) -> Result<BtcAddress>;
+
+    /// Signs the inputs of `psbt` that belong to `account` after the user reviews the
+    /// transaction, adding the signatures as partial signatures.
+    ///
+    /// Inputs need their previous output (`witness_utxo` or `non_witness_utxo`) and a
+    /// [`KeySource`] with the device's fingerprint, see [`BtcAccount::key_source`].
+    /// Returns the number of signatures added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached, the user rejects the
+    /// transaction or the device returns malformed signatures.
+    fn sign_psbt(&mut self, account: &BtcAccount, psbt: &mut Psbt) -> Result<usize>;
+
+    /// Reads the BIP-44 / BIP-49 / BIP-84 account `account_index` of `script_type`
+    /// from the device: `m/purpose'/coin'/account'`, with coin type `0` on mainnet and
+    /// `1` elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fingerprint or extended public key cannot be read.
+    fn btc_account(
+        &mut self,
+        script_type: ScriptType,
+        network: Network,
+        account_index: u32,
+    ) -> Result<BtcAccount> {
+        let coin_type = if network == Network::Bitcoin { 0 } else { 1 };
+        let path = DerivationPath::new(vec![
+            ChildNumber::Hardened(purpose(script_type)),
+            ChildNumber::Hardened(coin_type),
+            ChildNumber::Hardened(account_index),
+        ]);
+        let fingerprint = self.master_fingerprint()?;
+        let xpub = self.extended_public_key(&path, false)?;
+        Ok(BtcAccount::new(
+            script_type,
+            network,
+            fingerprint,
+            path,
+            xpub,
+        ))
+    }
+}
+
+/// A single-key Bitcoin account held by a hardware wallet.
+///
+/// Carries what the host needs to watch the account and to build PSBTs the device can
+/// sign: the script type, the master fingerprint, the account path and its extended
+/// public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtcAccount {
+    script_type: ScriptType,
+    network: Network,
+    fingerprint: [u8; 4],
+    path: DerivationPath,
+    xpub: ExtendedPublicKey,
+}
+
+impl BtcAccount {
+    /// Creates an account from its parts, typically read with [`Signer::btc_account`].
+    pub fn new(
+        script_type: ScriptType,
+        network: Network,
+        fingerprint: [u8; 4],
+        path: DerivationPath,
+        xpub: ExtendedPublicKey,
+    ) -> Self {
+        Self {
+            script_type,
+            network,
+            fingerprint,
+            path,
+            xpub,
+        }
+    }
+
+    /// Returns the script type of the account's addresses.
+    pub fn script_type(&self) -> ScriptType {
+        self.script_type
+    }
+
+    /// Returns the Bitcoin network.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the fingerprint of the device's master key.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        self.fingerprint
+    }
+
+    /// Returns the account path, such as `m/84'/0'/0'`.
+    pub fn path(&self) -> &DerivationPath {
+        &self.path
+    }
+
+    /// Returns the account's extended public key.
+    pub fn xpub(&self) -> &ExtendedPublicKey {
+        &self.xpub
+    }
+
+    /// Returns the key with its origin, as in output descriptors:
+    /// `[fingerprint/84'/0'/0']xpub…`.
+    pub fn key_origin(&self) -> String {
+        let path = self.path.to_string();
+        format!(
+            "[{}{}]{}",
+            hex::encode(self.fingerprint),
+            path.trim_start_matches('m'),
+            self.xpub
+        )
+    }
+
+    /// Returns the public key at `chain` / `index`, derived on the host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is hardened or derivation fails.
+    pub fn public_key(&self, chain: Chain, index: u32) -> Result<[u8; 33]> {
+        let key = self
+            .xpub
+            .derive_child(ChildNumber::Normal(chain.value()))?
+            .derive_child(ChildNumber::Normal(index))?;
+        Ok(key.public_key().to_bytes())
+    }
+
+    /// Returns the address at `chain` / `index`, derived on the host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is hardened or derivation fails.
+    pub fn address(&self, chain: Chain, index: u32) -> Result<BtcAddress> {
+        let public_key = self.public_key(chain, index)?;
+        Ok(match self.script_type {
+            ScriptType::P2pkh => BtcAddress::p2pkh(&public_key, self.network),
+            ScriptType::P2shP2wpkh => BtcAddress::p2sh_p2wpkh(&public_key, self.network),
+            ScriptType::P2wpkh => BtcAddress::p2wpkh(&public_key, self.network),
+        })
+    }
+
+    /// Returns the [`KeySource`] of the key at `chain` / `index`, to record in a PSBT
+    /// input's `bip32_derivation` so the device recognizes it.
+    pub fn key_source(&self, chain: Chain, index: u32) -> KeySource {
+        let mut path: Vec<u32> = self.path.iter().map(|child| child.to_index()).collect();
+        path.extend([chain.value(), index]);
+        KeySource {
+            fingerprint: self.fingerprint,
+            path,
+        }
+    }
+}
+
+/// Builds an EVM signature from the parts a device returns, with `v` either a
+/// recovery id or `27` / `28`, and `r` / `s` big-endian of at most 32 bytes.
+pub(crate) fn evm_signature(r: &[u8], s: &[u8], v: u64) -> Result<Signature> {
+    let v = match v {
+        0 | 1 => v as u8,
+        27 | 28 => (v - 27) as u8,
+        v => return Err(Error::InvalidResponse(format!("signature with v = {}", v))),
+    };
+    let scalar = |bytes: &[u8]| -> Result<[u8; 32]> {
+        if bytes.len() > 32 {
+            return Err(Error::InvalidResponse(format!(
+                "signature scalar of {} bytes",
+                bytes.len()
+            )));
+        }
+        let mut scalar = [0u8; 32];
+        scalar[32 - bytes.len()..].copy_from_slice(bytes);
+        Ok(scalar)
+    };
+    Ok(Signature::new(scalar(r)?, scalar(s)?, v))
+}
+
+/// Returns the BIP-44 family purpose of a script type.
+fn purpose(script_type: ScriptType) -> u32 {
+    match script_type {
+        ScriptType::P2pkh => 44,
+        ScriptType::P2shP2wpkh => 49,
+        ScriptType::P2wpkh => 84,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_bip32::{ExtendedPrivateKey, Network as Bip32Network};
+    use khodpay_bip39::{Language, Mnemonic};
+    use std::str::FromStr;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn account() -> BtcAccount {
+        let mnemonic = Mnemonic::from_phrase(MNEMONIC, Language::English).unwrap();
+        let master =
+            ExtendedPrivateKey::from_mnemonic(&mnemonic, None, Bip32Network::BitcoinMainnet)
+                .unwrap();
+        let path = DerivationPath::from_str("m/84'/0'/0'").unwrap();
+        let xpub = master.derive_path(&path).unwrap().to_extended_public_key();
+        let fingerprint = master.to_extended_public_key().fingerprint();
+        BtcAccount::new(
+            ScriptType::P2wpkh,
+            Network::Bitcoin,
+            fingerprint,
+            path,
+            xpub,
+        )
+    }
+
+    #[test]
+    fn test_account_addresses() {
+        // BIP-84 test vectors
+        let account = account();
+        assert_eq!(
+            account.address(Chain::External, 0).unwrap().to_string(),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            account.address(Chain::Internal, 0).unwrap().to_string(),
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"
+        );
+    }
+
+    #[test]
+    fn test_key_origin_and_source() {
+        let account = account();
+        assert_eq!(
+            account.key_origin(),
+            "[73c5da0a/84'/0'/0']xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V"
+        );
+        let source = account.key_source(Chain::Internal, 5);
+        assert_eq!(source.fingerprint, [0x73, 0xc5, 0xda, 0x0a]);
+        assert_eq!(
+            source.path,
+            vec![0x8000_0054, 0x8000_0000, 0x8000_0000, 1, 5]
+        );
+    }
+}
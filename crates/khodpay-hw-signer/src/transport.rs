@@ -0,0 +1,224 @@
+//! APDU framing and the host-provided transports.
+//!
+//! Ledger devices are driven with ISO 7816-4 style APDUs, Trezor devices with typed
+//! protobuf messages. This crate builds the commands and interprets the answers;
+//! moving the bytes over USB HID, BLE, Trezor Bridge or a simulator socket is left to
+//! a [`Transport`] or [`MessageTransport`] implemented by the host app.
+
+use crate::{Error, Result};
+
+/// Status word of a successful command.
+pub const SW_OK: u16 = 0x9000;
+
+/// Status word of a request the user declined on the device.
+pub const SW_DENIED: u16 = 0x6985;
+
+/// Status word of a locked device.
+pub const SW_LOCKED: u16 = 0x5515;
+
+/// Largest data field of a short APDU.
+pub const MAX_APDU_DATA: usize = 255;
+
+/// A command APDU: class, instruction, two parameters and up to 255 data bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Apdu {
+    /// Instruction class, selecting the app protocol.
+    pub cla: u8,
+    /// Instruction code.
+    pub ins: u8,
+    /// First parameter.
+    pub p1: u8,
+    /// Second parameter.
+    pub p2: u8,
+    /// Command data.
+    pub data: Vec<u8>,
+}
+
+impl Apdu {
+    /// Creates a command APDU.
+    pub fn new(cla: u8, ins: u8, p1: u8, p2: u8, data: Vec<u8>) -> Self {
+        Self {
+            cla,
+            ins,
+            p1,
+            p2,
+            data,
+        }
+    }
+
+    /// Encodes the APDU as `cla ins p1 p2 lc data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] if the data exceeds [`MAX_APDU_DATA`] bytes.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        if self.data.len() > MAX_APDU_DATA {
+            return Err(Error::Unsupported(format!(
+                "APDU data of {} bytes",
+                self.data.len()
+            )));
+        }
+        let mut buf = vec![self.cla, self.ins, self.p1, self.p2, self.data.len() as u8];
+        buf.extend_from_slice(&self.data);
+        Ok(buf)
+    }
+}
+
+/// Moves APDUs to and from a device.
+///
+/// Implementations wrap the host's USB HID, BLE or TCP (emulator) channel,
+/// including any link-layer framing such as Ledger's HID packets.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_hw_signer::Transport;
+///
+/// /// Answers every command with "OK" and no data.
+/// struct AlwaysOk;
+///
+/// impl Transport for AlwaysOk {
+///     fn exchange(&mut self, _apdu: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+///         Ok(vec![0x90, 0x00])
+///     }
+/// }
+/// ```
+pub trait Transport {
+    /// Sends one encoded command APDU and returns the response data followed by the
+    /// two status-word bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached.
+    fn exchange(&mut self, apdu: &[u8])
+        -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>>;
+}
+
+impl<T: Transport + ?Sized> Transport for &mut T {
+    fn exchange(
+        &mut self,
+        apdu: &[u8],
+    ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>> {
+        (**self).exchange(apdu)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn exchange(
+        &mut self,
+        apdu: &[u8],
+    ) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>> {
+        (**self).exchange(apdu)
+    }
+}
+
+/// Moves typed messages to and from a device speaking the Trezor wire protocol.
+///
+/// Implementations wrap the host's channel: USB HID reports (with the `?##` message
+/// framing), Trezor Bridge's `/call` endpoint or the emulator's UDP port.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_hw_signer::MessageTransport;
+///
+/// /// Answers every message with an empty `Success` (type 2).
+/// struct AlwaysSuccess;
+///
+/// impl MessageTransport for AlwaysSuccess {
+///     fn call(
+///         &mut self,
+///         _message_type: u16,
+///         _message: &[u8],
+///     ) -> Result<(u16, Vec<u8>), Box<dyn std::error::Error>> {
+///         Ok((2, Vec::new()))
+///     }
+/// }
+/// ```
+pub trait MessageTransport {
+    /// Sends one encoded message and returns the type and encoding of the device's
+    /// answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device cannot be reached.
+    fn call(
+        &mut self,
+        message_type: u16,
+        message: &[u8],
+    ) -> std::result::Result<(u16, Vec<u8>), Box<dyn std::error::Error>>;
+}
+
+impl<T: MessageTransport + ?Sized> MessageTransport for &mut T {
+    fn call(
+        &mut self,
+        message_type: u16,
+        message: &[u8],
+    ) -> std::result::Result<(u16, Vec<u8>), Box<dyn std::error::Error>> {
+        (**self).call(message_type, message)
+    }
+}
+
+impl<T: MessageTransport + ?Sized> MessageTransport for Box<T> {
+    fn call(
+        &mut self,
+        message_type: u16,
+        message: &[u8],
+    ) -> std::result::Result<(u16, Vec<u8>), Box<dyn std::error::Error>> {
+        (**self).call(message_type, message)
+    }
+}
+
+/// A response APDU split into data and status word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Response {
+    pub(crate) data: Vec<u8>,
+    pub(crate) status: u16,
+}
+
+impl Response {
+    /// Splits raw response bytes.
+    pub(crate) fn parse(mut raw: Vec<u8>) -> Result<Self> {
+        if raw.len() < 2 {
+            return Err(Error::InvalidResponse(format!(
+                "response of {} bytes has no status word",
+                raw.len()
+            )));
+        }
+        let status = u16::from_be_bytes([raw[raw.len() - 2], raw[raw.len() - 1]]);
+        raw.truncate(raw.len() - 2);
+        Ok(Self { data: raw, status })
+    }
+}
+
+/// Sends `apdu` and returns the raw response, mapping transport failures.
+pub(crate) fn exchange<T: Transport + ?Sized>(transport: &mut T, apdu: &Apdu) -> Result<Response> {
+    let raw = transport
+        .exchange(&apdu.encode()?)
+        .map_err(|e| Error::Transport(e.to_string()))?;
+    Response::parse(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apdu_encode() {
+        let apdu = Apdu::new(0xE0, 0x02, 0x01, 0x00, vec![0xAA, 0xBB]);
+        assert_eq!(
+            apdu.encode().unwrap(),
+            vec![0xE0, 0x02, 0x01, 0x00, 0x02, 0xAA, 0xBB]
+        );
+        assert!(Apdu::new(0xE0, 0x02, 0, 0, vec![0; 256]).encode().is_err());
+    }
+
+    #[test]
+    fn test_response_parse() {
+        let response = Response::parse(vec![0x01, 0x02, 0x90, 0x00]).unwrap();
+        assert_eq!(response.data, vec![0x01, 0x02]);
+        assert_eq!(response.status, SW_OK);
+        assert_eq!(Response::parse(vec![0x69, 0x85]).unwrap().status, SW_DENIED);
+        assert!(Response::parse(vec![0x90]).is_err());
+    }
+}
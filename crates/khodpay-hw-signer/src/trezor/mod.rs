@@ -0,0 +1,630 @@
+//! Trezor devices, through the Trezor wire protocol.
+//!
+//! [`Trezor`] exchanges the same protobuf messages Trezor Connect and Trezor Suite
+//! send, over a host-provided [`MessageTransport`]: USB HID, Trezor Bridge or the
+//! emulator.
+//!
+//! - **Bitcoin**: root fingerprint, extended public keys, address verification and
+//!   signing of BIP-44 / BIP-49 / BIP-84 inputs through the `SignTx` / `TxRequest` flow
+//! - **Ethereum**: addresses, EIP-1559 transactions and personal messages
+//!
+//! Button confirmations are acknowledged automatically and passphrases are entered on
+//! the device. A device waiting for a PIN matrix (Trezor One) reports
+//! [`Error::DeviceLocked`]; unlock it in the host app first.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use khodpay_bip44::Chain;
+//! use khodpay_btc_signing::{Network, ScriptType};
+//! use khodpay_hw_signer::trezor::Trezor;
+//! use khodpay_hw_signer::{MessageTransport, Signer};
+//!
+//! # fn open_bridge() -> Box<dyn MessageTransport> { unimplemented!() }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // The host app provides the USB HID or Trezor Bridge connection.
+//! let mut trezor = Trezor::new(open_bridge());
+//!
+//! let account = trezor.btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)?;
+//! let address = trezor.btc_address(&account, Chain::External, 0, true)?;
+//! println!("receive to {}", address);
+//! # Ok(())
+//! # }
+//! ```
+
+mod proto;
+#[cfg(test)]
+mod tests;
+
+use std::str::FromStr;
+
+use khodpay_bip32::{ChildNumber, DerivationPath, ExtendedPublicKey};
+use khodpay_bip44::Chain;
+use khodpay_btc_signing::psbt::{Psbt, PsbtInput, PsbtOutput};
+use khodpay_btc_signing::sighash::SIGHASH_ALL;
+use khodpay_btc_signing::{Address as BtcAddress, Network, ScriptType, Transaction};
+use khodpay_signing::{Address, Eip1559Transaction, Signature, Wei};
+
+use self::proto::{Fields, Message};
+use crate::signer::evm_signature;
+use crate::{BtcAccount, Error, MessageTransport, Result, Signer};
+
+// Message types of the wire protocol.
+const FAILURE: u16 = 3;
+const GET_PUBLIC_KEY: u16 = 11;
+const PUBLIC_KEY: u16 = 12;
+const SIGN_TX: u16 = 15;
+const PIN_MATRIX_REQUEST: u16 = 18;
+const CANCEL: u16 = 20;
+const TX_REQUEST: u16 = 21;
+const TX_ACK: u16 = 22;
+const BUTTON_REQUEST: u16 = 26;
+const BUTTON_ACK: u16 = 27;
+const GET_ADDRESS: u16 = 29;
+const ADDRESS: u16 = 30;
+const PASSPHRASE_REQUEST: u16 = 41;
+const PASSPHRASE_ACK: u16 = 42;
+const ETHEREUM_GET_ADDRESS: u16 = 56;
+const ETHEREUM_ADDRESS: u16 = 57;
+const ETHEREUM_TX_REQUEST: u16 = 59;
+const ETHEREUM_TX_ACK: u16 = 60;
+const ETHEREUM_SIGN_MESSAGE: u16 = 64;
+const ETHEREUM_MESSAGE_SIGNATURE: u16 = 66;
+const ETHEREUM_SIGN_TX_EIP1559: u16 = 452;
+
+// `FailureType` codes.
+const FAILURE_ACTION_CANCELLED: u64 = 4;
+const FAILURE_PIN_EXPECTED: u64 = 5;
+const FAILURE_PIN_CANCELLED: u64 = 6;
+const FAILURE_PIN_INVALID: u64 = 7;
+
+// `TxRequest.request_type` values.
+const TX_INPUT: u64 = 0;
+const TX_OUTPUT: u64 = 1;
+const TX_META: u64 = 2;
+const TX_FINISHED: u64 = 3;
+
+// `OutputScriptType` values.
+const PAY_TO_ADDRESS: u64 = 0;
+const PAY_TO_OP_RETURN: u64 = 3;
+
+/// Calldata sent with `EthereumSignTxEIP1559`; the device asks for the rest.
+const ETH_INITIAL_CHUNK: usize = 1024;
+
+/// A Trezor device.
+///
+/// Generic over the [`MessageTransport`] so the host app can plug in USB HID, Trezor
+/// Bridge or an emulator connection.
+#[derive(Debug)]
+pub struct Trezor<T> {
+    transport: T,
+}
+
+impl<T: MessageTransport> Trezor<T> {
+    /// Creates a client for the device behind `transport`.
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// Returns the transport, releasing the device.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    fn raw_call(&mut self, message_type: u16, message: Message) -> Result<(u16, Vec<u8>)> {
+        self.transport
+            .call(message_type, &message.into_bytes())
+            .map_err(|e| Error::Transport(e.to_string()))
+    }
+
+    /// Sends a message and returns the answer of type `expected`, acknowledging button
+    /// and passphrase requests on the way.
+    fn call(&mut self, message_type: u16, message: Message, expected: u16) -> Result<Fields> {
+        let (mut answer_type, mut answer) = self.raw_call(message_type, message)?;
+        loop {
+            match answer_type {
+                BUTTON_REQUEST => {
+                    (answer_type, answer) = self.raw_call(BUTTON_ACK, Message::new())?;
+                }
+                PASSPHRASE_REQUEST => {
+                    let ack = Message::new().bool(3, true);
+                    (answer_type, answer) = self.raw_call(PASSPHRASE_ACK, ack)?;
+                }
+                PIN_MATRIX_REQUEST => {
+                    // The answer to the cancel is a failure either way.
+                    let _ = self.raw_call(CANCEL, Message::new());
+                    return Err(Error::DeviceLocked);
+                }
+                FAILURE => return Err(failure(&Fields::parse(&answer)?)),
+                t if t == expected => return Fields::parse(&answer),
+                t => {
+                    return Err(invalid(format!(
+                        "unexpected message type {} instead of {}",
+                        t, expected
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Answers the `TxRequest`s of a Bitcoin signing session, collecting signatures.
+    fn sign_session(
+        &mut self,
+        psbt: &mut Psbt,
+        inputs: &[OwnInput],
+        outputs: &[Message],
+        mut request: Fields,
+    ) -> Result<usize> {
+        let mut signed = 0;
+        loop {
+            if let Some(serialized) = request.message(3)? {
+                if let (Some(index), Some(signature)) = (serialized.uint(1), serialized.bytes(2)) {
+                    let input = usize::try_from(index)
+                        .ok()
+                        .and_then(|index| inputs.get(index))
+                        .ok_or_else(|| invalid(format!("signature for unknown input {}", index)))?;
+                    let mut signature = signature.to_vec();
+                    signature.push(SIGHASH_ALL as u8);
+                    psbt.input_mut(input.index)
+                        .expect("input exists")
+                        .partial_sigs
+                        .insert(input.public_key.to_vec(), signature);
+                    signed += 1;
+                }
+            }
+
+            let details = request.message(2)?.unwrap_or_default();
+            let index = details.uint(1).unwrap_or(0) as usize;
+            let tx = match (request.uint(1), details.bytes(2)) {
+                (Some(TX_FINISHED), _) => return Ok(signed),
+                (Some(TX_INPUT), None) => {
+                    let input = inputs
+                        .get(index)
+                        .ok_or_else(|| out_of_range("input", index))?;
+                    Message::new().message(2, input.encode())
+                }
+                (Some(TX_OUTPUT), None) => {
+                    let output = outputs
+                        .get(index)
+                        .ok_or_else(|| out_of_range("output", index))?;
+                    Message::new().message(5, output.clone())
+                }
+                (Some(request_type), Some(hash)) => {
+                    let prev = inputs
+                        .iter()
+                        .map(|input| &input.prev_tx)
+                        .find(|tx| reversed(tx.txid().as_bytes()) == hash)
+                        .ok_or_else(|| invalid("request for an unknown previous transaction"))?;
+                    prev_tx_part(prev, request_type, index)?
+                }
+                (request_type, _) => {
+                    return Err(Error::Unsupported(format!(
+                        "transaction request type {:?}",
+                        request_type
+                    )))
+                }
+            };
+            request = self.call(TX_ACK, Message::new().message(1, tx), TX_REQUEST)?;
+        }
+    }
+}
+
+impl<T: MessageTransport> Signer for Trezor<T> {
+    fn master_fingerprint(&mut self) -> Result<[u8; 4]> {
+        let message = Message::new()
+            .uints(1, &[0x8000_002c, 0x8000_0000, 0x8000_0000])
+            .string(4, "Bitcoin");
+        let public_key = self.call(GET_PUBLIC_KEY, message, PUBLIC_KEY)?;
+        let fingerprint = public_key
+            .uint(3)
+            .and_then(|fingerprint| u32::try_from(fingerprint).ok())
+            .ok_or_else(|| {
+                Error::Unsupported("firmware does not report the root fingerprint".to_string())
+            })?;
+        Ok(fingerprint.to_be_bytes())
+    }
+
+    fn extended_public_key(
+        &mut self,
+        path: &DerivationPath,
+        display: bool,
+    ) -> Result<ExtendedPublicKey> {
+        let coin = if path.child_number_at(1) == Some(&ChildNumber::Hardened(1)) {
+            "Testnet"
+        } else {
+            "Bitcoin"
+        };
+        let message = Message::new()
+            .uints(1, &address_n(path))
+            .bool(3, display)
+            .string(4, coin);
+        let public_key = self.call(GET_PUBLIC_KEY, message, PUBLIC_KEY)?;
+        let text = public_key
+            .string(2)
+            .ok_or_else(|| invalid("missing extended public key"))?;
+        let xpub = ExtendedPublicKey::from_str(text)
+            .map_err(|e| invalid(format!("extended public key: {}", e)))?;
+        if usize::from(xpub.depth()) != path.len() {
+            return Err(invalid(format!(
+                "extended public key of depth {} for a path of depth {}",
+                xpub.depth(),
+                path.len()
+            )));
+        }
+        Ok(xpub)
+    }
+
+    fn evm_address(&mut self, path: &DerivationPath, display: bool) -> Result<Address> {
+        let message = Message::new().uints(1, &address_n(path)).bool(2, display);
+        let address = self.call(ETHEREUM_GET_ADDRESS, message, ETHEREUM_ADDRESS)?;
+        let text = address
+            .string(2)
+            .ok_or_else(|| invalid("missing address"))?;
+        Address::from_str(text).map_err(|e| invalid(format!("address {}: {}", text, e)))
+    }
+
+    fn sign_evm_transaction(
+        &mut self,
+        path: &DerivationPath,
+        tx: &Eip1559Transaction,
+    ) -> Result<Signature> {
+        let data_length = u32::try_from(tx.data.len())
+            .map_err(|_| Error::Unsupported("calldata longer than 4 GiB".to_string()))?;
+        let (initial, mut rest) = tx.data.split_at(tx.data.len().min(ETH_INITIAL_CHUNK));
+        let mut message = Message::new()
+            .uints(1, &address_n(path))
+            .bytes(2, &be_bytes(&tx.nonce.to_be_bytes()))
+            .bytes(3, &wei_bytes(tx.max_fee_per_gas))
+            .bytes(4, &wei_bytes(tx.max_priority_fee_per_gas))
+            .bytes(5, &be_bytes(&tx.gas_limit.to_be_bytes()))
+            .string(
+                6,
+                &tx.to.map(|to| to.to_checksum_string()).unwrap_or_default(),
+            )
+            .bytes(7, &wei_bytes(tx.value))
+            .bytes(8, initial)
+            .uint(9, u64::from(data_length))
+            .uint(10, u64::from(tx.chain_id));
+        for item in &tx.access_list {
+            let mut entry = Message::new().string(1, &item.address.to_checksum_string());
+            for key in &item.storage_keys {
+                entry = entry.bytes(2, key);
+            }
+            message = message.message(11, entry);
+        }
+
+        let mut request = self.call(ETHEREUM_SIGN_TX_EIP1559, message, ETHEREUM_TX_REQUEST)?;
+        while let Some(length) = request.uint(1).filter(|length| *length > 0) {
+            let length = usize::try_from(length)
+                .ok()
+                .filter(|length| *length <= rest.len())
+                .ok_or_else(|| invalid("request for more calldata than remains"))?;
+            let (chunk, remaining) = rest.split_at(length);
+            rest = remaining;
+            let ack = Message::new().bytes(1, chunk);
+            request = self.call(ETHEREUM_TX_ACK, ack, ETHEREUM_TX_REQUEST)?;
+        }
+
+        let v = request
+            .uint(2)
+            .ok_or_else(|| invalid("missing signature"))?;
+        let r = request
+            .bytes(3)
+            .ok_or_else(|| invalid("missing signature"))?;
+        let s = request
+            .bytes(4)
+            .ok_or_else(|| invalid("missing signature"))?;
+        evm_signature(r, s, v)
+    }
+
+    fn sign_evm_message(&mut self, path: &DerivationPath, message: &[u8]) -> Result<Signature> {
+        let request = Message::new().uints(1, &address_n(path)).bytes(2, message);
+        let response = self.call(ETHEREUM_SIGN_MESSAGE, request, ETHEREUM_MESSAGE_SIGNATURE)?;
+        match response.bytes(2) {
+            Some(signature) if signature.len() == 65 => evm_signature(
+                &signature[..32],
+                &signature[32..64],
+                u64::from(signature[64]),
+            ),
+            _ => Err(invalid("missing or malformed signature")),
+        }
+    }
+
+    fn btc_address(
+        &mut self,
+        account: &BtcAccount,
+        chain: Chain,
+        index: u32,
+        display: bool,
+    ) -> Result<BtcAddress> {
+        let expected = account.address(chain, index)?;
+        let mut path = address_n(account.path());
+        path.extend([chain.value(), index]);
+        let message = Message::new()
+            .uints(1, &path)
+            .string(2, coin_name(account.network()))
+            .bool(3, display)
+            .uint(5, input_script_type(account.script_type()));
+        let response = self.call(GET_ADDRESS, message, ADDRESS)?;
+        let text = response
+            .string(1)
+            .ok_or_else(|| invalid("missing address"))?;
+        let address = BtcAddress::parse(text, account.network())
+            .map_err(|e| invalid(format!("address {}: {}", text, e)))?;
+        if address != expected {
+            return Err(invalid(format!(
+                "device returned address {}, expected {}",
+                address, expected
+            )));
+        }
+        Ok(address)
+    }
+
+    /// Signs `psbt` through the `SignTx` flow.
+    ///
+    /// Trezor signs whole transactions, so every input must belong to `account` and
+    /// carry its `non_witness_utxo`, which the device checks against the outpoint to
+    /// verify the amount.
+    fn sign_psbt(&mut self, account: &BtcAccount, psbt: &mut Psbt) -> Result<usize> {
+        let inputs = psbt
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(index, input)| OwnInput::new(index, input, account))
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = psbt
+            .outputs()
+            .iter()
+            .enumerate()
+            .map(|(index, output)| encode_output(index, output, account))
+            .collect::<Result<Vec<_>>>()?;
+
+        let message = Message::new()
+            .uint(1, outputs.len() as u64)
+            .uint(2, inputs.len() as u64)
+            .string(3, coin_name(account.network()))
+            .uint(4, u64::from(psbt.tx_version as u32))
+            .uint(5, u64::from(psbt.lock_time()?));
+        let request = self.call(SIGN_TX, message, TX_REQUEST)?;
+        self.sign_session(psbt, &inputs, &outputs, request)
+    }
+}
+
+/// An input of the transaction being signed, with what the device asks about it.
+struct OwnInput {
+    index: usize,
+    public_key: [u8; 33],
+    address_n: Vec<u32>,
+    prev_hash: [u8; 32],
+    prev_index: u32,
+    sequence: u32,
+    amount: u64,
+    script_type: u64,
+    prev_tx: Transaction,
+}
+
+impl OwnInput {
+    fn new(index: usize, input: &PsbtInput, account: &BtcAccount) -> Result<Self> {
+        let account_path = address_n(account.path());
+        let (public_key, source) = input
+            .bip32_derivation
+            .iter()
+            .find(|(_, source)| {
+                source.fingerprint == account.fingerprint()
+                    && source.path.len() == account_path.len() + 2
+                    && source.path.starts_with(&account_path)
+            })
+            .ok_or_else(|| {
+                Error::Unsupported(format!("input {} does not belong to the account", index))
+            })?;
+        let public_key = public_key
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::Unsupported(format!("input {} has an uncompressed key", index)))?;
+        if input.sighash_type.is_some_and(|t| t != SIGHASH_ALL) {
+            return Err(Error::Unsupported(format!(
+                "input {} requests a sighash type other than SIGHASH_ALL",
+                index
+            )));
+        }
+        let prev_tx = input
+            .non_witness_utxo
+            .as_deref()
+            .map(Transaction::deserialize)
+            .transpose()?
+            .ok_or_else(|| {
+                Error::Unsupported(format!("input {} needs its non-witness UTXO", index))
+            })?;
+        let outpoint = &input.previous_output;
+        if prev_tx.txid() != outpoint.txid {
+            return Err(Error::Unsupported(format!(
+                "non-witness UTXO of input {} does not match its outpoint",
+                index
+            )));
+        }
+        let amount = prev_tx
+            .outputs
+            .get(outpoint.vout as usize)
+            .map(|output| output.value)
+            .ok_or_else(|| {
+                Error::Unsupported(format!("input {} spends a missing output", index))
+            })?;
+        Ok(Self {
+            index,
+            public_key,
+            address_n: source.path.clone(),
+            prev_hash: reversed(outpoint.txid.as_bytes()),
+            prev_index: outpoint.vout,
+            sequence: input.sequence.unwrap_or(u32::MAX),
+            amount,
+            script_type: input_script_type(account.script_type()),
+            prev_tx,
+        })
+    }
+
+    /// Encodes the input as a `TxInputType`.
+    fn encode(&self) -> Message {
+        Message::new()
+            .uints(1, &self.address_n)
+            .bytes(2, &self.prev_hash)
+            .uint(3, u64::from(self.prev_index))
+            .uint(5, u64::from(self.sequence))
+            .uint(6, self.script_type)
+            .uint(8, self.amount)
+    }
+}
+
+/// Encodes an output as a `TxOutputType`: by path if it is the account's change,
+/// otherwise by address or `OP_RETURN` data.
+fn encode_output(index: usize, output: &PsbtOutput, account: &BtcAccount) -> Result<Message> {
+    let account_path = address_n(account.path());
+    let change = output.bip32_derivation.values().find(|source| {
+        source.fingerprint == account.fingerprint()
+            && source.path.len() == account_path.len() + 2
+            && source.path.starts_with(&account_path)
+            && account
+                .address(
+                    if source.path[account_path.len()] == 1 {
+                        Chain::Internal
+                    } else {
+                        Chain::External
+                    },
+                    source.path[account_path.len() + 1],
+                )
+                .is_ok_and(|address| address.script_pubkey() == output.script_pubkey)
+    });
+    let message = Message::new().uint(3, output.amount);
+    if let Some(source) = change {
+        return Ok(message
+            .uints(2, &source.path)
+            .uint(4, output_script_type(account.script_type())));
+    }
+    if let Some(address) = BtcAddress::from_script(&output.script_pubkey, account.network()) {
+        return Ok(message
+            .string(1, &address.to_string())
+            .uint(4, PAY_TO_ADDRESS));
+    }
+    if let Some(data) = op_return_data(output.script_pubkey.as_bytes()) {
+        return Ok(message.uint(4, PAY_TO_OP_RETURN).bytes(6, data));
+    }
+    Err(Error::Unsupported(format!(
+        "output {} pays a script without an address",
+        index
+    )))
+}
+
+/// Answers a request about a previous transaction: its metadata, an input or an
+/// output.
+fn prev_tx_part(prev: &Transaction, request_type: u64, index: usize) -> Result<Message> {
+    match request_type {
+        TX_META => Ok(Message::new()
+            .uint(1, u64::from(prev.version as u32))
+            .uint(4, u64::from(prev.lock_time))
+            .uint(6, prev.inputs.len() as u64)
+            .uint(7, prev.outputs.len() as u64)),
+        TX_INPUT => {
+            let input = prev
+                .inputs
+                .get(index)
+                .ok_or_else(|| out_of_range("previous input", index))?;
+            let txin = Message::new()
+                .bytes(2, &reversed(input.previous_output.txid.as_bytes()))
+                .uint(3, u64::from(input.previous_output.vout))
+                .bytes(4, input.script_sig.as_bytes())
+                .uint(5, u64::from(input.sequence));
+            Ok(Message::new().message(2, txin))
+        }
+        TX_OUTPUT => {
+            let output = prev
+                .outputs
+                .get(index)
+                .ok_or_else(|| out_of_range("previous output", index))?;
+            let txout = Message::new()
+                .uint(1, output.value)
+                .bytes(2, output.script_pubkey.as_bytes());
+            Ok(Message::new().message(3, txout))
+        }
+        other => Err(Error::Unsupported(format!(
+            "previous transaction request type {}",
+            other
+        ))),
+    }
+}
+
+/// Maps a `Failure` message to an error.
+fn failure(fields: &Fields) -> Error {
+    let code = fields.uint(1).unwrap_or(0);
+    match code {
+        FAILURE_ACTION_CANCELLED | FAILURE_PIN_CANCELLED => Error::UserRejected,
+        FAILURE_PIN_EXPECTED | FAILURE_PIN_INVALID => Error::DeviceLocked,
+        _ => Error::Device {
+            status: u16::try_from(code).unwrap_or(u16::MAX),
+            message: fields.string(2).unwrap_or("failure").to_string(),
+        },
+    }
+}
+
+/// Returns the `coin_name` of a Bitcoin network.
+fn coin_name(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "Bitcoin",
+        Network::Testnet | Network::Signet => "Testnet",
+        Network::Regtest => "Regtest",
+    }
+}
+
+/// Returns the `InputScriptType` of a script type.
+fn input_script_type(script_type: ScriptType) -> u64 {
+    match script_type {
+        ScriptType::P2pkh => 0,
+        ScriptType::P2wpkh => 3,
+        ScriptType::P2shP2wpkh => 4,
+    }
+}
+
+/// Returns the `OutputScriptType` of a change output of a script type.
+fn output_script_type(script_type: ScriptType) -> u64 {
+    match script_type {
+        ScriptType::P2pkh => PAY_TO_ADDRESS,
+        ScriptType::P2wpkh => 4,
+        ScriptType::P2shP2wpkh => 5,
+    }
+}
+
+fn address_n(path: &DerivationPath) -> Vec<u32> {
+    path.iter().map(|child| child.to_index()).collect()
+}
+
+/// Returns the data of an `OP_RETURN <push>` script.
+fn op_return_data(script: &[u8]) -> Option<&[u8]> {
+    match script {
+        [0x6a] => Some(&[]),
+        [0x6a, len @ 0x01..=0x4b, data @ ..] if data.len() == usize::from(*len) => Some(data),
+        [0x6a, 0x4c, len, data @ ..] if data.len() == usize::from(*len) => Some(data),
+        _ => None,
+    }
+}
+
+/// Returns a big-endian integer without leading zero bytes.
+fn be_bytes(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+fn wei_bytes(value: Wei) -> Vec<u8> {
+    be_bytes(&value.to_be_bytes())
+}
+
+fn reversed(bytes: &[u8; 32]) -> [u8; 32] {
+    let mut reversed = *bytes;
+    reversed.reverse();
+    reversed
+}
+
+fn out_of_range(what: &str, index: usize) -> Error {
+    invalid(format!("request for {} {} out of range", what, index))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidResponse(message.into())
+}
@@ -0,0 +1,224 @@
+//! The subset of Protocol Buffers encoding the Trezor messages need.
+//!
+//! Messages are built field by field with [`Message`] and read back into [`Fields`];
+//! no schema compiler is involved. Repeated scalar fields use the unpacked proto2
+//! encoding Trezor firmware expects.
+
+use crate::{Error, Result};
+
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+/// A message under construction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Message(Vec<u8>);
+
+impl Message {
+    /// Creates an empty message.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(&mut self, field: u32, wire_type: u64) {
+        write_varint(&mut self.0, (u64::from(field) << 3) | wire_type);
+    }
+
+    /// Appends an unsigned integer or enum field.
+    pub(crate) fn uint(mut self, field: u32, value: u64) -> Self {
+        self.key(field, VARINT);
+        write_varint(&mut self.0, value);
+        self
+    }
+
+    /// Appends a boolean field.
+    pub(crate) fn bool(self, field: u32, value: bool) -> Self {
+        self.uint(field, u64::from(value))
+    }
+
+    /// Appends a bytes field.
+    pub(crate) fn bytes(mut self, field: u32, value: &[u8]) -> Self {
+        self.key(field, LENGTH_DELIMITED);
+        write_varint(&mut self.0, value.len() as u64);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    /// Appends a string field.
+    pub(crate) fn string(self, field: u32, value: &str) -> Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    /// Appends an embedded message field.
+    pub(crate) fn message(self, field: u32, value: Message) -> Self {
+        self.bytes(field, &value.0)
+    }
+
+    /// Appends one field per element of a repeated integer field.
+    pub(crate) fn uints(self, field: u32, values: &[u32]) -> Self {
+        values.iter().fold(self, |message, value| {
+            message.uint(field, u64::from(*value))
+        })
+    }
+
+    /// Returns the encoded message.
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// A decoded field value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Int(u64),
+    Bytes(Vec<u8>),
+}
+
+/// The fields of a received message, in wire order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Fields(Vec<(u32, Value)>);
+
+impl Fields {
+    /// Decodes a message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidResponse`] if the encoding is malformed.
+    pub(crate) fn parse(mut data: &[u8]) -> Result<Self> {
+        let malformed = || Error::InvalidResponse("malformed protobuf message".to_string());
+        let mut fields = Vec::new();
+        while !data.is_empty() {
+            let key = read_varint(&mut data).ok_or_else(malformed)?;
+            let field = u32::try_from(key >> 3).map_err(|_| malformed())?;
+            let value = match key & 0x07 {
+                VARINT => Value::Int(read_varint(&mut data).ok_or_else(malformed)?),
+                LENGTH_DELIMITED => {
+                    let len = read_varint(&mut data).ok_or_else(malformed)?;
+                    let len = usize::try_from(len).map_err(|_| malformed())?;
+                    let value = data.get(..len).ok_or_else(malformed)?.to_vec();
+                    data = &data[len..];
+                    Value::Bytes(value)
+                }
+                width @ (FIXED64 | FIXED32) => {
+                    let len = if width == FIXED64 { 8 } else { 4 };
+                    let bytes = data.get(..len).ok_or_else(malformed)?;
+                    let mut value = [0u8; 8];
+                    value[..len].copy_from_slice(bytes);
+                    data = &data[len..];
+                    Value::Int(u64::from_le_bytes(value))
+                }
+                _ => return Err(malformed()),
+            };
+            fields.push((field, value));
+        }
+        Ok(Self(fields))
+    }
+
+    /// Returns the last integer value of `field`.
+    pub(crate) fn uint(&self, field: u32) -> Option<u64> {
+        self.0.iter().rev().find_map(|(f, value)| match value {
+            Value::Int(n) if *f == field => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Returns every integer value of a repeated `field`.
+    #[cfg(test)]
+    pub(crate) fn uints(&self, field: u32) -> Vec<u64> {
+        self.0
+            .iter()
+            .filter_map(|(f, value)| match value {
+                Value::Int(n) if *f == field => Some(*n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the last bytes value of `field`.
+    pub(crate) fn bytes(&self, field: u32) -> Option<&[u8]> {
+        self.0.iter().rev().find_map(|(f, value)| match value {
+            Value::Bytes(bytes) if *f == field => Some(bytes.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Returns every bytes value of a repeated `field`.
+    #[cfg(test)]
+    pub(crate) fn all_bytes(&self, field: u32) -> Vec<&[u8]> {
+        self.0
+            .iter()
+            .filter_map(|(f, value)| match value {
+                Value::Bytes(bytes) if *f == field => Some(bytes.as_slice()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the last string value of `field`.
+    pub(crate) fn string(&self, field: u32) -> Option<&str> {
+        self.bytes(field)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// Decodes the last embedded message of `field`.
+    pub(crate) fn message(&self, field: u32) -> Result<Option<Fields>> {
+        self.bytes(field).map(Fields::parse).transpose()
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let inner = Message::new().string(1, "Bitcoin").uint(2, 300);
+        let encoded = Message::new()
+            .uints(1, &[0x8000_002c, 0])
+            .bool(3, true)
+            .bytes(4, &[0xde, 0xad])
+            .message(5, inner)
+            .into_bytes();
+        // address_n[0] = 44' as a five-byte varint
+        assert_eq!(&encoded[..6], &[0x08, 0xac, 0x80, 0x80, 0x80, 0x08]);
+
+        let fields = Fields::parse(&encoded).unwrap();
+        assert_eq!(fields.uints(1), vec![0x8000_002c, 0]);
+        assert_eq!(fields.uint(3), Some(1));
+        assert_eq!(fields.bytes(4), Some(&[0xde, 0xad][..]));
+        let inner = fields.message(5).unwrap().unwrap();
+        assert_eq!(inner.string(1), Some("Bitcoin"));
+        assert_eq!(inner.uint(2), Some(300));
+        assert_eq!(fields.uint(9), None);
+        assert!(fields.message(9).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(Fields::parse(&[0x0a, 0x05, 0x01]).is_err());
+        assert!(Fields::parse(&[0x08, 0x80]).is_err());
+        assert!(Fields::parse(&[0x0b]).is_err());
+        assert_eq!(Fields::parse(&[0x0d, 1, 0, 0, 0]).unwrap().uint(1), Some(1));
+    }
+}
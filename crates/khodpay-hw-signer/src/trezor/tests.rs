@@ -0,0 +1,637 @@
+//! [`Trezor`] against an emulated device.
+//!
+//! The emulator runs on its own thread and plays the firmware side of the message
+//! flows, including the `TxRequest` round trips of Bitcoin signing, with keys from
+//! the BIP-39 test mnemonic.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use k256::ecdsa::SigningKey;
+use khodpay_bip32::{ExtendedPrivateKey, Network as Bip32Network};
+use khodpay_bip39::{Language, Mnemonic};
+use khodpay_btc_signing::{BtcSigner, OutPoint, Script, TxIn, TxOut, Txid};
+use khodpay_signing::{recover_signer, AccessListItem, ChainId};
+use sha3::{Digest, Keccak256};
+
+use super::*;
+
+const SUCCESS: u16 = 2;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn master() -> ExtendedPrivateKey {
+    let mnemonic = Mnemonic::from_phrase(MNEMONIC, Language::English).unwrap();
+    ExtendedPrivateKey::from_mnemonic(&mnemonic, None, Bip32Network::BitcoinMainnet).unwrap()
+}
+
+/// Host end of the emulator.
+struct Emulator {
+    to_device: Sender<(u16, Vec<u8>)>,
+    from_device: Receiver<(u16, Vec<u8>)>,
+}
+
+impl MessageTransport for Emulator {
+    fn call(
+        &mut self,
+        message_type: u16,
+        message: &[u8],
+    ) -> std::result::Result<(u16, Vec<u8>), Box<dyn std::error::Error>> {
+        self.to_device.send((message_type, message.to_vec()))?;
+        Ok(self.from_device.recv()?)
+    }
+}
+
+/// Device end of the emulator.
+struct Device {
+    from_host: Receiver<(u16, Vec<u8>)>,
+    to_host: Sender<(u16, Vec<u8>)>,
+    master: ExtendedPrivateKey,
+}
+
+impl Device {
+    fn reply(&self, message_type: u16, message: Message) {
+        self.to_host
+            .send((message_type, message.into_bytes()))
+            .unwrap();
+    }
+
+    /// Sends a message and returns the host's answer, which must be of type `expected`.
+    fn ask(&self, message_type: u16, message: Message, expected: u16) -> Fields {
+        self.reply(message_type, message);
+        let (answer_type, answer) = self.from_host.recv().unwrap();
+        assert_eq!(answer_type, expected);
+        Fields::parse(&answer).unwrap()
+    }
+
+    /// Waits for the user to press the button.
+    fn confirm(&self) {
+        self.ask(BUTTON_REQUEST, Message::new().uint(1, 1), BUTTON_ACK);
+    }
+
+    fn key(&self, address_n: &[u64]) -> ExtendedPrivateKey {
+        let path = DerivationPath::new(
+            address_n
+                .iter()
+                .map(|i| ChildNumber::from_index(*i as u32))
+                .collect(),
+        );
+        self.master.derive_path(&path).unwrap()
+    }
+
+    /// Sends a `TxRequest` and returns the `TransactionType` of the host's `TxAck`.
+    fn tx_request(&self, request_type: u64, index: u64, hash: Option<&[u8]>) -> Fields {
+        let mut details = Message::new().uint(1, index);
+        if let Some(hash) = hash {
+            details = details.bytes(2, hash);
+        }
+        let request = Message::new().uint(1, request_type).message(2, details);
+        self.ask(TX_REQUEST, request, TX_ACK)
+            .message(1)
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Streams a previous transaction from the host and checks it hashes to `hash`.
+    fn prev_tx(&self, hash: &[u8]) -> Transaction {
+        let meta = self.tx_request(TX_META, 0, Some(hash));
+        let inputs = (0..meta.uint(6).unwrap())
+            .map(|i| {
+                let tx = self.tx_request(TX_INPUT, i, Some(hash));
+                let input = tx.message(2).unwrap().unwrap();
+                let txid = reversed(input.bytes(2).unwrap().try_into().unwrap());
+                let mut txin = TxIn::new(
+                    OutPoint::new(Txid::from_bytes(txid), input.uint(3).unwrap() as u32),
+                    input.uint(5).unwrap() as u32,
+                );
+                txin.script_sig = Script::new(input.bytes(4).unwrap().to_vec());
+                txin
+            })
+            .collect();
+        let outputs = (0..meta.uint(7).unwrap())
+            .map(|i| {
+                let tx = self.tx_request(TX_OUTPUT, i, Some(hash));
+                let output = tx.message(3).unwrap().unwrap();
+                TxOut {
+                    value: output.uint(1).unwrap(),
+                    script_pubkey: Script::new(output.bytes(2).unwrap().to_vec()),
+                }
+            })
+            .collect();
+        let tx = Transaction {
+            version: meta.uint(1).unwrap() as i32,
+            inputs,
+            outputs,
+            lock_time: meta.uint(4).unwrap() as u32,
+        };
+        assert_eq!(&reversed(tx.txid().as_bytes())[..], hash);
+        tx
+    }
+
+    fn sign_tx(&self, sign_tx: &Fields) {
+        let network = match sign_tx.string(3).unwrap() {
+            "Bitcoin" => Network::Bitcoin,
+            "Testnet" => Network::Testnet,
+            other => panic!("unexpected coin {}", other),
+        };
+        let mut psbt = Psbt::new(sign_tx.uint(4).unwrap() as i32);
+        psbt.fallback_lock_time = Some(sign_tx.uint(5).unwrap() as u32);
+
+        let mut signers = Vec::new();
+        for i in 0..sign_tx.uint(2).unwrap() {
+            let input = self
+                .tx_request(TX_INPUT, i, None)
+                .message(2)
+                .unwrap()
+                .unwrap();
+            let prev_hash = input.bytes(2).unwrap();
+            let prev = self.prev_tx(prev_hash);
+            let vout = input.uint(3).unwrap() as u32;
+            // The device checks the claimed amount against the previous transaction
+            assert_eq!(input.uint(8), Some(prev.outputs[vout as usize].value));
+
+            let mut psbt_input = PsbtInput::new(OutPoint::new(prev.txid(), vout));
+            psbt_input.sequence = input.uint(5).map(|s| s as u32);
+            psbt_input.non_witness_utxo = Some(prev.serialize());
+            psbt.add_input(psbt_input).unwrap();
+
+            let script_type = match input.uint(6).unwrap() {
+                0 => ScriptType::P2pkh,
+                3 => ScriptType::P2wpkh,
+                4 => ScriptType::P2shP2wpkh,
+                other => panic!("unexpected input script type {}", other),
+            };
+            let key = self.key(&input.uints(1));
+            signers.push(
+                BtcSigner::from_private_key(&key.private_key().to_bytes(), network)
+                    .unwrap()
+                    .with_script_type(script_type),
+            );
+        }
+
+        for i in 0..sign_tx.uint(1).unwrap() {
+            let output = self
+                .tx_request(TX_OUTPUT, i, None)
+                .message(5)
+                .unwrap()
+                .unwrap();
+            let script_pubkey = match (output.string(1), output.uint(4).unwrap()) {
+                (Some(address), _) => BtcAddress::parse(address, network).unwrap().script_pubkey(),
+                (None, PAY_TO_OP_RETURN) => {
+                    let data = output.bytes(6).unwrap();
+                    let mut script = vec![0x6a, data.len() as u8];
+                    script.extend_from_slice(data);
+                    Script::new(script)
+                }
+                (None, script_type) => {
+                    // Change is shown as such, not as an address to confirm
+                    let script_type = match script_type {
+                        0 => ScriptType::P2pkh,
+                        4 => ScriptType::P2wpkh,
+                        5 => ScriptType::P2shP2wpkh,
+                        other => panic!("unexpected output script type {}", other),
+                    };
+                    let key = self.key(&output.uints(2));
+                    BtcSigner::from_private_key(&key.private_key().to_bytes(), network)
+                        .unwrap()
+                        .address_for(script_type)
+                        .script_pubkey()
+                }
+            };
+            psbt.add_output(PsbtOutput::new(output.uint(3).unwrap(), script_pubkey))
+                .unwrap();
+        }
+        self.confirm();
+
+        for signer in &signers {
+            psbt.sign(signer).unwrap();
+        }
+        // Each signature rides on the next request
+        let count = psbt.inputs().len();
+        for (i, input) in psbt.inputs().iter().enumerate() {
+            let signature = input.partial_sigs.values().next().unwrap();
+            let serialized = Message::new()
+                .uint(1, i as u64)
+                .bytes(2, &signature[..signature.len() - 1]);
+            if i + 1 < count {
+                let request = Message::new()
+                    .uint(1, TX_INPUT)
+                    .message(2, Message::new().uint(1, i as u64 + 1))
+                    .message(3, serialized);
+                self.ask(TX_REQUEST, request, TX_ACK);
+            } else {
+                let request = Message::new().uint(1, TX_FINISHED).message(3, serialized);
+                self.reply(TX_REQUEST, request);
+            }
+        }
+    }
+
+    fn sign_eth_tx(&self, fields: &Fields) {
+        let key = self.key(&fields.uints(1));
+        let data_length = fields.uint(9).unwrap() as usize;
+        let mut data = fields.bytes(8).unwrap().to_vec();
+        while data.len() < data_length {
+            let chunk = (data_length - data.len()).min(ETH_INITIAL_CHUNK) as u64;
+            let ack = self.ask(
+                ETHEREUM_TX_REQUEST,
+                Message::new().uint(1, chunk),
+                ETHEREUM_TX_ACK,
+            );
+            data.extend_from_slice(ack.bytes(1).unwrap());
+        }
+
+        let mut nonce = [0u8; 8];
+        let raw_nonce = fields.bytes(2).unwrap();
+        nonce[8 - raw_nonce.len()..].copy_from_slice(raw_nonce);
+        let mut gas_limit = [0u8; 8];
+        let raw_gas_limit = fields.bytes(5).unwrap();
+        gas_limit[8 - raw_gas_limit.len()..].copy_from_slice(raw_gas_limit);
+        let mut builder = Eip1559Transaction::builder()
+            .chain_id(ChainId::from(fields.uint(10).unwrap()))
+            .nonce(u64::from_be_bytes(nonce))
+            .max_fee_per_gas(Wei::from_be_bytes(fields.bytes(3).unwrap()))
+            .max_priority_fee_per_gas(Wei::from_be_bytes(fields.bytes(4).unwrap()))
+            .gas_limit(u64::from_be_bytes(gas_limit))
+            .value(Wei::from_be_bytes(fields.bytes(7).unwrap()))
+            .data(data);
+        if let Some(to) = fields.string(6).filter(|to| !to.is_empty()) {
+            builder = builder.to(Address::from_str(to).unwrap());
+        }
+        for entry in fields.all_bytes(11) {
+            let entry = Fields::parse(entry).unwrap();
+            let keys = entry
+                .all_bytes(2)
+                .into_iter()
+                .map(|key| key.try_into().unwrap())
+                .collect();
+            let address = Address::from_str(entry.string(1).unwrap()).unwrap();
+            builder = builder.add_access_list_item(AccessListItem::new(address, keys));
+        }
+        let tx = builder.build().unwrap();
+        self.confirm();
+
+        let signing_key = SigningKey::from_slice(&key.private_key().to_bytes()).unwrap();
+        let (signature, recovery_id) = signing_key
+            .sign_prehash_recoverable(&tx.signing_hash())
+            .unwrap();
+        let signature = signature.to_bytes();
+        let response = Message::new()
+            .uint(2, u64::from(recovery_id.to_byte()))
+            .bytes(3, &signature[..32])
+            .bytes(4, &signature[32..]);
+        self.reply(ETHEREUM_TX_REQUEST, response);
+    }
+}
+
+fn eth_address(key: &ExtendedPrivateKey) -> String {
+    let signing_key = SigningKey::from_slice(&key.private_key().to_bytes()).unwrap();
+    let public_key = signing_key.verifying_key().to_encoded_point(false);
+    let hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// Starts an emulated device running `firmware` for every message.
+fn emulator(firmware: fn(&Device, u16, &Fields)) -> Trezor<Emulator> {
+    let (to_device, from_host) = channel();
+    let (to_host, from_device) = channel();
+    thread::spawn(move || {
+        let device = Device {
+            from_host,
+            to_host,
+            master: master(),
+        };
+        while let Ok((message_type, message)) = device.from_host.recv() {
+            firmware(&device, message_type, &Fields::parse(&message).unwrap());
+        }
+    });
+    Trezor::new(Emulator {
+        to_device,
+        from_device,
+    })
+}
+
+/// An unlocked device that asks for its passphrase on the first request.
+fn firmware(device: &Device, message_type: u16, fields: &Fields) {
+    match message_type {
+        GET_PUBLIC_KEY => {
+            device.ask(PASSPHRASE_REQUEST, Message::new(), PASSPHRASE_ACK);
+            let key = device.key(&fields.uints(1));
+            if fields.uint(3) == Some(1) {
+                device.confirm();
+            }
+            let fingerprint = u32::from_be_bytes(device.master.fingerprint());
+            let response = Message::new()
+                .string(2, &key.to_extended_public_key().to_string())
+                .uint(3, u64::from(fingerprint));
+            device.reply(PUBLIC_KEY, response);
+        }
+        GET_ADDRESS => {
+            let network = match fields.string(2).unwrap() {
+                "Bitcoin" => Network::Bitcoin,
+                _ => Network::Testnet,
+            };
+            let script_type = match fields.uint(5).unwrap_or(0) {
+                0 => ScriptType::P2pkh,
+                3 => ScriptType::P2wpkh,
+                4 => ScriptType::P2shP2wpkh,
+                other => panic!("unexpected input script type {}", other),
+            };
+            let key = device.key(&fields.uints(1));
+            let address = BtcSigner::from_private_key(&key.private_key().to_bytes(), network)
+                .unwrap()
+                .address_for(script_type);
+            if fields.uint(3) == Some(1) {
+                device.confirm();
+            }
+            device.reply(ADDRESS, Message::new().string(1, &address.to_string()));
+        }
+        ETHEREUM_GET_ADDRESS => {
+            let key = device.key(&fields.uints(1));
+            if fields.uint(2) == Some(1) {
+                device.confirm();
+            }
+            device.reply(
+                ETHEREUM_ADDRESS,
+                Message::new().string(2, &eth_address(&key)),
+            );
+        }
+        ETHEREUM_SIGN_TX_EIP1559 => device.sign_eth_tx(fields),
+        ETHEREUM_SIGN_MESSAGE => {
+            let key = device.key(&fields.uints(1));
+            device.confirm();
+            let hash = khodpay_signing::eip191::hash_message(fields.bytes(2).unwrap());
+            let signing_key = SigningKey::from_slice(&key.private_key().to_bytes()).unwrap();
+            let (signature, recovery_id) = signing_key.sign_prehash_recoverable(&hash).unwrap();
+            let mut bytes = signature.to_bytes().to_vec();
+            bytes.push(27 + recovery_id.to_byte());
+            let response = Message::new()
+                .bytes(2, &bytes)
+                .string(3, &eth_address(&key));
+            device.reply(ETHEREUM_MESSAGE_SIGNATURE, response);
+        }
+        SIGN_TX => device.sign_tx(fields),
+        _ => device.reply(
+            FAILURE,
+            Message::new().uint(1, 1).string(2, "Unexpected message"),
+        ),
+    }
+}
+
+/// A Trezor One waiting for its PIN.
+fn locked(device: &Device, message_type: u16, _fields: &Fields) {
+    if message_type == CANCEL {
+        let failure = Message::new().uint(1, FAILURE_ACTION_CANCELLED);
+        return device.reply(FAILURE, failure);
+    }
+    device.reply(PIN_MATRIX_REQUEST, Message::new().uint(1, 1));
+}
+
+/// A device whose user rejects every request.
+fn rejecting(device: &Device, _message_type: u16, _fields: &Fields) {
+    device.ask(BUTTON_REQUEST, Message::new(), BUTTON_ACK);
+    let failure = Message::new()
+        .uint(1, FAILURE_ACTION_CANCELLED)
+        .string(2, "Cancelled");
+    device.reply(FAILURE, failure);
+}
+
+fn path(s: &str) -> DerivationPath {
+    DerivationPath::from_str(s).unwrap()
+}
+
+/// Returns a transaction paying `amount` to each of `scripts`.
+fn funding_tx(scripts: &[Script], amount: u64) -> Transaction {
+    Transaction {
+        version: 2,
+        inputs: vec![TxIn::new(
+            OutPoint::new(Txid::from_bytes([0xab; 32]), 0),
+            0xffff_fffd,
+        )],
+        outputs: scripts
+            .iter()
+            .map(|script| TxOut {
+                value: amount,
+                script_pubkey: script.clone(),
+            })
+            .collect(),
+        lock_time: 0,
+    }
+}
+
+#[test]
+fn test_btc_account_and_address() {
+    let mut trezor = emulator(firmware);
+    let account = trezor
+        .btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)
+        .unwrap();
+    assert_eq!(account.fingerprint(), [0x73, 0xc5, 0xda, 0x0a]);
+    assert_eq!(account.path().to_string(), "m/84'/0'/0'");
+    assert_eq!(
+        trezor
+            .btc_address(&account, Chain::External, 0, true)
+            .unwrap()
+            .to_string(),
+        "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+    );
+
+    let legacy = trezor
+        .btc_account(ScriptType::P2pkh, Network::Bitcoin, 0)
+        .unwrap();
+    assert_eq!(
+        trezor
+            .btc_address(&legacy, Chain::External, 0, false)
+            .unwrap()
+            .to_string(),
+        "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+    );
+
+    // A host holding a different key for the account is caught
+    let other = BtcAccount::new(
+        ScriptType::P2wpkh,
+        Network::Bitcoin,
+        account.fingerprint(),
+        account.path().clone(),
+        legacy.xpub().clone(),
+    );
+    assert!(matches!(
+        trezor.btc_address(&other, Chain::External, 0, false),
+        Err(Error::InvalidResponse(_))
+    ));
+}
+
+#[test]
+fn test_evm_address_and_signing() {
+    let mut trezor = emulator(firmware);
+    let path = path("m/44'/60'/0'/0/0");
+    let from = trezor.evm_address(&path, true).unwrap();
+    assert_eq!(
+        from.to_checksum_string(),
+        "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+    );
+
+    // Calldata long enough to be streamed in chunks
+    let tx = Eip1559Transaction::builder()
+        .chain_id(ChainId::BscMainnet)
+        .nonce(7)
+        .max_priority_fee_per_gas(Wei::from_gwei(1))
+        .max_fee_per_gas(Wei::from_gwei(5))
+        .gas_limit(200_000)
+        .to(from)
+        .value(Wei::from_ether(1))
+        .data(vec![0xab; 2500])
+        .add_access_list_item(AccessListItem::new(from, vec![[0x11; 32]]))
+        .build()
+        .unwrap();
+    let signature = trezor.sign_evm_transaction(&path, &tx).unwrap();
+    assert!(signature.v <= 1);
+    assert_eq!(
+        recover_signer(&tx.signing_hash(), &signature).unwrap(),
+        from
+    );
+
+    let signature = trezor.sign_evm_message(&path, b"hello trezor").unwrap();
+    assert!(signature.v <= 1);
+    assert_eq!(
+        khodpay_signing::eip191::recover_message_signer(b"hello trezor", &signature).unwrap(),
+        from
+    );
+}
+
+#[test]
+fn test_sign_psbt() {
+    let mut trezor = emulator(firmware);
+    let account = trezor
+        .btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)
+        .unwrap();
+    let funding = funding_tx(
+        &[
+            account.address(Chain::External, 0).unwrap().script_pubkey(),
+            account.address(Chain::External, 3).unwrap().script_pubkey(),
+        ],
+        40_000,
+    );
+
+    let mut psbt = Psbt::new(2);
+    for (vout, index) in [(0, 0), (1, 3)] {
+        let mut input = PsbtInput::new(OutPoint::new(funding.txid(), vout));
+        input.non_witness_utxo = Some(funding.serialize());
+        input.bip32_derivation.insert(
+            account.public_key(Chain::External, index).unwrap().to_vec(),
+            account.key_source(Chain::External, index),
+        );
+        psbt.add_input(input).unwrap();
+    }
+    let recipient = BtcSigner::from_private_key(&[9u8; 32], Network::Bitcoin).unwrap();
+    psbt.add_output(PsbtOutput::new(
+        50_000,
+        recipient.p2wpkh_address().script_pubkey(),
+    ))
+    .unwrap();
+    let mut change = PsbtOutput::new(
+        29_000,
+        account.address(Chain::Internal, 0).unwrap().script_pubkey(),
+    );
+    change.bip32_derivation.insert(
+        account.public_key(Chain::Internal, 0).unwrap().to_vec(),
+        account.key_source(Chain::Internal, 0),
+    );
+    psbt.add_output(change).unwrap();
+    psbt.add_output(PsbtOutput::new(
+        0,
+        Script::new(vec![0x6a, 0x03, 0x61, 0x62, 0x63]),
+    ))
+    .unwrap();
+
+    assert_eq!(trezor.sign_psbt(&account, &mut psbt).unwrap(), 2);
+    assert_eq!(psbt.inputs()[0].partial_sigs.len(), 1);
+    assert_eq!(psbt.inputs()[1].partial_sigs.len(), 1);
+
+    // The signatures match the software signer's
+    let key = master().derive_path(&path("m/84'/0'/0'/0/3")).unwrap();
+    let mut expected = psbt.clone();
+    expected.input_mut(1).unwrap().partial_sigs.clear();
+    expected
+        .sign(
+            &BtcSigner::from_private_key(&key.private_key().to_bytes(), Network::Bitcoin).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        psbt.inputs()[1].partial_sigs,
+        expected.inputs()[1].partial_sigs
+    );
+    psbt.finalize().unwrap();
+    psbt.extract_tx().unwrap();
+}
+
+#[test]
+fn test_sign_psbt_requirements() {
+    let mut trezor = emulator(firmware);
+    let account = trezor
+        .btc_account(ScriptType::P2wpkh, Network::Bitcoin, 0)
+        .unwrap();
+    let script_pubkey = account.address(Chain::External, 0).unwrap().script_pubkey();
+    let funding = funding_tx(std::slice::from_ref(&script_pubkey), 40_000);
+    let mut input = PsbtInput::new(OutPoint::new(funding.txid(), 0));
+    input.witness_utxo = Some(funding.outputs[0].clone());
+    input.bip32_derivation.insert(
+        account.public_key(Chain::External, 0).unwrap().to_vec(),
+        account.key_source(Chain::External, 0),
+    );
+
+    // The device verifies amounts against the full previous transaction
+    let mut psbt = Psbt::new(2);
+    psbt.add_input(input.clone()).unwrap();
+    assert!(matches!(
+        trezor.sign_psbt(&account, &mut psbt),
+        Err(Error::Unsupported(_))
+    ));
+
+    // Inputs of other wallets cannot be skipped
+    input.non_witness_utxo = Some(funding.serialize());
+    input.bip32_derivation.clear();
+    let mut psbt = Psbt::new(2);
+    psbt.add_input(input).unwrap();
+    assert!(matches!(
+        trezor.sign_psbt(&account, &mut psbt),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_failures() {
+    let mut trezor = emulator(locked);
+    assert!(matches!(
+        trezor.master_fingerprint(),
+        Err(Error::DeviceLocked)
+    ));
+    // The cancelled session leaves the device usable
+    assert!(matches!(
+        trezor.evm_address(&path("m/44'/60'/0'/0/0"), false),
+        Err(Error::DeviceLocked)
+    ));
+
+    let mut trezor = emulator(rejecting);
+    assert!(matches!(
+        trezor.sign_evm_message(&path("m/44'/60'/0'/0/0"), b"no"),
+        Err(Error::UserRejected)
+    ));
+
+    let mut trezor = emulator(firmware);
+    let error = trezor.call(CANCEL, Message::new(), SUCCESS).unwrap_err();
+    assert!(matches!(error, Error::Device { status: 1, .. }));
+    assert!(error.to_string().contains("Unexpected message"));
+}
+
+#[test]
+fn test_encoding_helpers() {
+    assert_eq!(be_bytes(&7u64.to_be_bytes()), vec![7]);
+    assert!(be_bytes(&0u64.to_be_bytes()).is_empty());
+    assert_eq!(
+        op_return_data(&[0x6a, 0x02, 0xaa, 0xbb]),
+        Some(&[0xaa, 0xbb][..])
+    );
+    assert_eq!(op_return_data(&[0x6a]), Some(&[][..]));
+    assert_eq!(op_return_data(&[0x6a, 0x03, 0xaa]), None);
+    assert_eq!(op_return_data(&[0x00, 0x14]), None);
+}
@@ -0,0 +1,328 @@
+//! What EVM calls move, decoded from their calldata.
+
+use khodpay_signing::abi::{Function, Token};
+use khodpay_signing::disperse::DISPERSE;
+use khodpay_signing::erc20::Erc20Call;
+use khodpay_signing::{Address, U256};
+
+/// Recipients or spenders, with the amount each receives or may take.
+pub(crate) type Payouts = Vec<(Address, U256)>;
+
+/// A call made by a smart account: target, native value and calldata.
+pub(crate) type AccountCall = (Address, U256, Vec<u8>);
+
+/// The EIP-2612 permit type, as [`Eip712Type::type_string`] returns it.
+///
+/// [`Eip712Type::type_string`]: khodpay_signing::eip712::Eip712Type::type_string
+pub(crate) const PERMIT_TYPE: &str =
+    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+
+const ERC721_SAFE_TRANSFER_FROM: [&str; 2] = [
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+];
+const ERC1155_SAFE_TRANSFER_FROM: &str = "safeTransferFrom(address,address,uint256,uint256,bytes)";
+const ERC1155_SAFE_BATCH_TRANSFER_FROM: &str =
+    "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)";
+const SET_APPROVAL_FOR_ALL: &str = "setApprovalForAll(address,bool)";
+const DISPERSE_ETHER: &str = "disperseEther(address[],uint256[])";
+const DISPERSE_TOKEN: &str = "disperseToken(address,address[],uint256[])";
+
+/// `execute` of the reference ERC-4337 `SimpleAccount` and most accounts built on it.
+const EXECUTE: &str = "execute(address,uint256,bytes)";
+/// `executeBatch` of `SimpleAccount` v0.7, where an empty value array sends nothing.
+const EXECUTE_BATCH: &str = "executeBatch(address[],uint256[],bytes[])";
+/// `executeBatch` of `SimpleAccount` v0.6, without values.
+const EXECUTE_BATCH_V06: &str = "executeBatch(address[],bytes[])";
+
+/// Returns the recipients and amounts of a call to a token contract.
+///
+/// Covers ERC-20 and TRC-20 `transfer`, `transferFrom` and `approve`, ERC-721 and
+/// ERC-1155 `safeTransferFrom`, ERC-1155 `safeBatchTransferFrom` and
+/// `setApprovalForAll`. An ERC-721 transfer moves one token and an ERC-1155
+/// transfer its amount of each ID; approving an operator for all tokens counts as
+/// `u128::MAX`, revoking one as nothing. ERC-721 `transferFrom` and `approve`
+/// share their selectors with ERC-20 and read as such, the token ID as amount.
+pub(crate) fn token_spend(data: &[u8]) -> Option<Payouts> {
+    if let Ok(call) = Erc20Call::decode(data) {
+        return Some(vec![match call {
+            Erc20Call::Transfer { to, amount } | Erc20Call::TransferFrom { to, amount, .. } => {
+                (to, amount)
+            }
+            Erc20Call::Approve { spender, amount } => (spender, amount),
+        }]);
+    }
+    for signature in ERC721_SAFE_TRANSFER_FROM {
+        if let Some([_, Token::Address(to), ..]) = decode(signature, data).as_deref() {
+            return Some(vec![(*to, U256::one())]);
+        }
+    }
+    if let Some([_, Token::Address(to), _, Token::Uint(amount), _]) =
+        decode(ERC1155_SAFE_TRANSFER_FROM, data).as_deref()
+    {
+        return Some(vec![(*to, *amount)]);
+    }
+    if let Some([_, Token::Address(to), Token::Array(ids), Token::Array(amounts), _]) =
+        decode(ERC1155_SAFE_BATCH_TRANSFER_FROM, data).as_deref()
+    {
+        if ids.len() != amounts.len() {
+            return None;
+        }
+        return amounts
+            .iter()
+            .map(|amount| match amount {
+                Token::Uint(amount) => Some((*to, *amount)),
+                _ => None,
+            })
+            .collect();
+    }
+    if let Some([Token::Address(operator), Token::Bool(approved)]) =
+        decode(SET_APPROVAL_FOR_ALL, data).as_deref()
+    {
+        let amount = if *approved {
+            U256::from(u128::MAX)
+        } else {
+            U256::zero()
+        };
+        return Some(vec![(*operator, amount)]);
+    }
+    None
+}
+
+/// Returns the token paid out, `None` for the native coin, and the payouts of a
+/// call to the [`DISPERSE`] contract.
+///
+/// Calls to any other address are not read as Disperse calls, since a look-alike
+/// contract could do anything with what it is sent.
+pub(crate) fn disperse_spend(to: &Address, data: &[u8]) -> Option<(Option<Address>, Payouts)> {
+    if *to != DISPERSE {
+        return None;
+    }
+    if let Some([recipients, values]) = decode(DISPERSE_ETHER, data).as_deref() {
+        return Some((None, payouts(recipients, values)?));
+    }
+    if let Some([Token::Address(token), recipients, values]) =
+        decode(DISPERSE_TOKEN, data).as_deref()
+    {
+        return Some((Some(*token), payouts(recipients, values)?));
+    }
+    None
+}
+
+/// Returns the calls a smart account makes for ERC-4337 `call_data`: none when it
+/// is empty, one for `execute` and each of an `executeBatch`.
+pub(crate) fn account_calls(call_data: &[u8]) -> Option<Vec<AccountCall>> {
+    if call_data.is_empty() {
+        return Some(Vec::new());
+    }
+    if let Some([Token::Address(to), Token::Uint(value), Token::Bytes(data)]) =
+        decode(EXECUTE, call_data).as_deref()
+    {
+        return Some(vec![(*to, *value, data.clone())]);
+    }
+    let (targets, values, calls) = match decode(EXECUTE_BATCH, call_data).as_deref() {
+        Some([Token::Array(targets), Token::Array(values), Token::Array(calls)]) => {
+            (targets.clone(), values.clone(), calls.clone())
+        }
+        _ => match decode(EXECUTE_BATCH_V06, call_data).as_deref() {
+            Some([Token::Array(targets), Token::Array(calls)]) => {
+                (targets.clone(), Vec::new(), calls.clone())
+            }
+            _ => return None,
+        },
+    };
+    if targets.len() != calls.len() || !(values.is_empty() || values.len() == calls.len()) {
+        return None;
+    }
+    targets
+        .iter()
+        .zip(&calls)
+        .enumerate()
+        .map(|(index, call)| {
+            let value = match values.get(index) {
+                Some(Token::Uint(value)) => *value,
+                Some(_) => return None,
+                None => U256::zero(),
+            };
+            match call {
+                (Token::Address(to), Token::Bytes(data)) => Some((*to, value, data.clone())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns the spender and allowance of a [`PERMIT_TYPE`] struct from its
+/// EIP-712 `encodeData` words.
+pub(crate) fn permit(encoded: &[u8]) -> Option<(Address, U256)> {
+    let word = |index: usize| encoded.get(index * 32..(index + 1) * 32);
+    let spender = Address::from_slice(&word(1)?[12..]).ok()?;
+    Some((spender, U256::from_big_endian(word(2)?)))
+}
+
+/// Decodes `data` as a call to the function with `signature`, if its selector
+/// matches.
+fn decode(signature: &str, data: &[u8]) -> Option<Vec<Token>> {
+    Function::parse(signature).ok()?.decode_input(data).ok()
+}
+
+fn payouts(recipients: &Token, values: &Token) -> Option<Payouts> {
+    let (Token::Array(recipients), Token::Array(values)) = (recipients, values) else {
+        return None;
+    };
+    if recipients.len() != values.len() {
+        return None;
+    }
+    recipients
+        .iter()
+        .zip(values)
+        .map(|pair| match pair {
+            (Token::Address(to), Token::Uint(value)) => Some((*to, *value)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_signing::abi::encode_with_selector;
+    use khodpay_signing::{disperse, erc1155, erc20, erc721};
+
+    fn address(byte: u8) -> Address {
+        Address::from_bytes([byte; 20])
+    }
+
+    #[test]
+    fn test_token_spend() {
+        let (from, to) = (address(1), address(2));
+        let cases = [
+            (
+                erc20::encode_transfer(&to, U256::from(5u64)),
+                vec![(to, 5u64)],
+            ),
+            (erc20::encode_approve(&to, U256::from(6u64)), vec![(to, 6)]),
+            (
+                erc721::encode_safe_transfer_from(&from, &to, U256::from(77u64)),
+                vec![(to, 1)],
+            ),
+            (
+                erc721::encode_safe_transfer_from_with_data(&from, &to, U256::from(77u64), b"hi"),
+                vec![(to, 1)],
+            ),
+            (
+                erc1155::encode_safe_transfer_from(
+                    &from,
+                    &to,
+                    U256::from(3u64),
+                    U256::from(9u64),
+                    &[],
+                ),
+                vec![(to, 9)],
+            ),
+            (
+                erc1155::encode_safe_batch_transfer_from(
+                    &from,
+                    &to,
+                    &[U256::from(3u64), U256::from(4u64)],
+                    &[U256::from(9u64), U256::from(10u64)],
+                    &[],
+                )
+                .unwrap(),
+                vec![(to, 9), (to, 10)],
+            ),
+            (
+                erc721::encode_set_approval_for_all(&to, false),
+                vec![(to, 0)],
+            ),
+        ];
+        for (data, expected) in cases {
+            let expected: Payouts = expected
+                .into_iter()
+                .map(|(to, amount)| (to, U256::from(amount)))
+                .collect();
+            assert_eq!(token_spend(&data), Some(expected));
+        }
+        assert_eq!(
+            token_spend(&erc721::encode_set_approval_for_all(&to, true)),
+            Some(vec![(to, U256::from(u128::MAX))])
+        );
+        assert_eq!(token_spend(&[1, 2, 3, 4]), None);
+        assert_eq!(token_spend(&[]), None);
+    }
+
+    #[test]
+    fn test_disperse_spend() {
+        let payouts = vec![
+            (address(1), U256::from(10u64)),
+            (address(2), U256::from(20u64)),
+        ];
+        let ether = disperse::encode_disperse_ether(&payouts);
+        assert_eq!(
+            disperse_spend(&DISPERSE, &ether),
+            Some((None, payouts.clone()))
+        );
+        let token = disperse::encode_disperse_token(&address(0xAA), &payouts);
+        assert_eq!(
+            disperse_spend(&DISPERSE, &token),
+            Some((Some(address(0xAA)), payouts))
+        );
+
+        // Only the real Disperse contract
+        assert_eq!(disperse_spend(&address(3), &ether), None);
+
+        // Mismatched arrays
+        let data = encode_with_selector(
+            disperse::DISPERSE_ETHER_SELECTOR,
+            &[
+                Token::Array(vec![Token::Address(address(1))]),
+                Token::Array(vec![]),
+            ],
+        );
+        assert_eq!(disperse_spend(&DISPERSE, &data), None);
+    }
+
+    #[test]
+    fn test_account_calls() {
+        let call = erc20::encode_transfer(&address(2), U256::from(5u64));
+        let execute = Function::parse(EXECUTE)
+            .unwrap()
+            .encode_input(&[
+                Token::Address(address(1)),
+                Token::Uint(U256::from(7u64)),
+                Token::Bytes(call.clone()),
+            ])
+            .unwrap();
+        assert_eq!(
+            account_calls(&execute),
+            Some(vec![(address(1), U256::from(7u64), call.clone())])
+        );
+
+        let targets = Token::Array(vec![Token::Address(address(1)), Token::Address(address(3))]);
+        let calls = Token::Array(vec![Token::Bytes(call.clone()), Token::Bytes(vec![])]);
+        let batch = Function::parse(EXECUTE_BATCH)
+            .unwrap()
+            .encode_input(&[targets.clone(), Token::Array(vec![]), calls.clone()])
+            .unwrap();
+        let expected = vec![
+            (address(1), U256::zero(), call.clone()),
+            (address(3), U256::zero(), vec![]),
+        ];
+        assert_eq!(account_calls(&batch), Some(expected.clone()));
+        let batch = Function::parse(EXECUTE_BATCH_V06)
+            .unwrap()
+            .encode_input(&[targets.clone(), calls.clone()])
+            .unwrap();
+        assert_eq!(account_calls(&batch), Some(expected));
+
+        // One value for two calls
+        let batch = Function::parse(EXECUTE_BATCH)
+            .unwrap()
+            .encode_input(&[targets, Token::Array(vec![Token::Uint(U256::one())]), calls])
+            .unwrap();
+        assert_eq!(account_calls(&batch), None);
+
+        assert_eq!(account_calls(&[]), Some(vec![]));
+        assert_eq!(account_calls(&call), None);
+    }
+}
@@ -0,0 +1,1040 @@
+//! The [`PolicyEngine`] that enforces a [`Policy`] in front of every signer.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use khodpay_bip32::DerivationPath;
+use khodpay_btc_signing::psbt::Psbt;
+use khodpay_btc_signing::BtcSigner;
+use khodpay_hw_signer::{BtcAccount, Signer};
+use khodpay_signing::eip2771::{self, ForwardRequest};
+use khodpay_signing::eip712::{self, Eip712Domain, Eip712Type, TypedData};
+use khodpay_signing::erc4337::{self, PackedUserOperation};
+use khodpay_signing::safe::{self, SafeSignature, SafeTransaction};
+use khodpay_signing::{
+    Address, Bip44Signer, Eip1559Transaction, Eip2930Transaction, PrehashPolicy, Signature,
+};
+use khodpay_tron_signing::{SignedTransaction, Transaction as TronTransaction, TronSigner};
+use khodpay_xrp_signing::{Payment, SignedPayment, XrpSigner};
+
+use crate::request::hex;
+use crate::{Asset, Error, Network, Policy, Result, SpendRequest};
+
+/// Seconds in a UTC day.
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Asks a person or an external approval service to confirm a request.
+///
+/// Returns `true` to let the request through. Called without any lock held, so it
+/// may block while someone decides.
+pub type ConfirmationCallback = dyn Fn(&SpendRequest) -> bool + Send + Sync;
+
+/// Spending of one asset on one UTC day.
+#[derive(Debug, Clone, Copy)]
+struct DailySpend {
+    day: u64,
+    amount: u128,
+}
+
+/// Enforces a [`Policy`] in front of all the wallet's signers.
+///
+/// Create one engine when the wallet starts and route every signature through
+/// it, either with the `sign_*` methods, which describe the transaction, check
+/// it and sign it, or with [`PolicyEngine::authorize`] before calling a signer
+/// this crate does not wrap. The engine is `Sync`: share it with an `Arc` so all
+/// signers count against the same daily limits.
+///
+/// Authorized spending counts against the daily limit straight away, so
+/// concurrent requests cannot overrun it together. The `sign_*` methods give the
+/// amount back if signing fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_policy::{Asset, Error, Network, Policy, PolicyEngine, SpendRequest};
+///
+/// let bsc = Asset::native(Network::Evm(56));
+/// let policy = Policy::new()
+///     .daily_limit(bsc.clone(), 200)
+///     .confirm_above(bsc.clone(), 50);
+/// let engine = PolicyEngine::new(policy).with_confirmation(|request| request.total() <= 60);
+///
+/// let pay = |amount| SpendRequest::new(bsc.clone()).with_transfer("0x1111", amount);
+/// engine.authorize(&pay(40))?;
+/// engine.authorize(&pay(55))?; // Confirmed
+/// assert!(matches!(engine.authorize(&pay(70)), Err(Error::NotConfirmed(_))));
+/// assert!(matches!(engine.authorize(&pay(110)), Err(Error::PolicyViolation(_))));
+/// assert_eq!(engine.spent_today(&bsc), 95);
+/// # Ok::<(), Error>(())
+/// ```
+pub struct PolicyEngine {
+    policy: Policy,
+    confirmation: Option<Box<ConfirmationCallback>>,
+    spent: Mutex<HashMap<Asset, DailySpend>>,
+}
+
+impl PolicyEngine {
+    /// Creates an engine enforcing `policy`, with nothing spent yet.
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy,
+            confirmation: None,
+            spent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the callback that confirms requests above the policy's thresholds.
+    ///
+    /// Without one, such requests are refused.
+    pub fn with_confirmation(
+        mut self,
+        callback: impl Fn(&SpendRequest) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.confirmation = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the policy being enforced.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Checks `request` against the policy now and counts it against today's
+    /// limit.
+    ///
+    /// # Errors
+    ///
+    /// As [`PolicyEngine::authorize_at`].
+    pub fn authorize(&self, request: &SpendRequest) -> Result<()> {
+        self.authorize_at(request, unix_time())
+    }
+
+    /// Checks `request` against the policy at `timestamp` (seconds since the Unix
+    /// epoch) and counts it against that day's limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] if the request breaks a rule or would go
+    /// over the daily limit, and [`Error::NotConfirmed`] if it needed a
+    /// confirmation that was not given. Refused requests are not counted.
+    pub fn authorize_at(&self, request: &SpendRequest, timestamp: u64) -> Result<()> {
+        self.authorize_confirming(request, timestamp, self.policy.confirm_messages)
+    }
+
+    /// Does the work of [`PolicyEngine::authorize_at`], asking for confirmation of
+    /// the request's message when `confirm_message` is set.
+    fn authorize_confirming(
+        &self,
+        request: &SpendRequest,
+        timestamp: u64,
+        confirm_message: bool,
+    ) -> Result<()> {
+        self.policy.check(request)?;
+        let day = timestamp / SECONDS_PER_DAY;
+        let spends = self.spends(request);
+
+        {
+            let mut spent = self.lock();
+            for (asset, total) in &spends {
+                let today = spent_on_day(&spent, asset, day);
+                if let Some(limit) = self.policy.daily_limit_for(asset) {
+                    if today.saturating_add(*total) > limit {
+                        return Err(Error::PolicyViolation(format!(
+                            "spending {} would exceed the daily limit of {} for {} ({} spent today)",
+                            total, limit, asset, today
+                        )));
+                    }
+                }
+            }
+            for (asset, total) in &spends {
+                let today = spent_on_day(&spent, asset, day);
+                spent.insert(
+                    asset.clone(),
+                    DailySpend {
+                        day,
+                        amount: today.saturating_add(*total),
+                    },
+                );
+            }
+        }
+
+        let unconfirmed = spends
+            .iter()
+            .find(|(asset, total)| {
+                self.policy
+                    .confirmation_threshold_for(asset)
+                    .is_some_and(|threshold| *total > threshold)
+            })
+            .map(|(asset, total)| format!("spending {} of {}", total, asset))
+            .or_else(|| {
+                let message = request.message.as_ref()?;
+                confirm_message.then(|| format!("signing {}", message))
+            });
+        if let Some(what) = unconfirmed {
+            let confirmed = match &self.confirmation {
+                Some(callback) => callback(request),
+                None => {
+                    self.release(&spends, day);
+                    return Err(Error::NotConfirmed(format!(
+                        "{} needs confirmation and none can be asked for",
+                        what
+                    )));
+                }
+            };
+            if !confirmed {
+                self.release(&spends, day);
+                return Err(Error::NotConfirmed(format!("{} was not confirmed", what)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how much of `asset` has been authorized today.
+    pub fn spent_today(&self, asset: &Asset) -> u128 {
+        self.spent_at(asset, unix_time())
+    }
+
+    /// Returns how much of `asset` has been authorized on the UTC day containing
+    /// `timestamp`.
+    pub fn spent_at(&self, asset: &Asset, timestamp: u64) -> u128 {
+        spent_on_day(
+            &self.lock(),
+            &asset.normalized(),
+            timestamp / SECONDS_PER_DAY,
+        )
+    }
+
+    /// Counts `amount` of `asset` as spent on the day containing `timestamp`,
+    /// without checking it.
+    ///
+    /// Use it when the wallet starts to replay today's signatures from storage or
+    /// an audit log, so a restart does not reset the daily limits.
+    pub fn record_spend_at(&self, asset: &Asset, amount: u128, timestamp: u64) {
+        let asset = asset.normalized();
+        let day = timestamp / SECONDS_PER_DAY;
+        let mut spent = self.lock();
+        let today = spent_on_day(&spent, &asset, day);
+        spent.insert(
+            asset,
+            DailySpend {
+                day,
+                amount: today.saturating_add(amount),
+            },
+        );
+    }
+
+    /// Authorizes an EIP-1559 transaction and signs it with `signer`.
+    ///
+    /// The signer's own [`SigningPolicy`](khodpay_signing::SigningPolicy), if any,
+    /// applies as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_evm_transaction`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_evm_transaction(
+        &self,
+        signer: &Bip44Signer,
+        tx: &Eip1559Transaction,
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_evm_transaction(tx)?;
+        self.guarded(&request, || Ok(signer.sign_transaction(tx)?))
+    }
+
+    /// Authorizes an EIP-2930 transaction and signs it with `signer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_eip2930_transaction`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_eip2930_transaction(
+        &self,
+        signer: &Bip44Signer,
+        tx: &Eip2930Transaction,
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_eip2930_transaction(tx)?;
+        self.guarded(&request, || Ok(signer.sign_eip2930_transaction(tx)?))
+    }
+
+    /// Authorizes a Safe transaction and signs it as one of the Safe's owners.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_safe_transaction`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_safe_transaction(
+        &self,
+        signer: &Bip44Signer,
+        tx: &SafeTransaction,
+        safe: Address,
+        chain_id: u64,
+    ) -> Result<SafeSignature> {
+        let request = SpendRequest::from_safe_transaction(tx, chain_id)?;
+        self.guarded(&request, || {
+            Ok(safe::sign_safe_transaction(signer, tx, safe, chain_id)?)
+        })
+    }
+
+    /// Authorizes an ERC-4337 user operation and signs it for `entry_point`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_user_operation`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_user_operation(
+        &self,
+        signer: &Bip44Signer,
+        user_op: &PackedUserOperation,
+        entry_point: Address,
+        chain_id: u64,
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_user_operation(user_op, chain_id)?;
+        self.guarded(&request, || {
+            Ok(erc4337::sign_user_operation(
+                signer,
+                user_op,
+                entry_point,
+                chain_id,
+            )?)
+        })
+    }
+
+    /// Authorizes an ERC-2771 forward request and signs it for `domain`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_forward_request`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_forward_request(
+        &self,
+        signer: &Bip44Signer,
+        request: &ForwardRequest,
+        domain: &Eip712Domain,
+    ) -> Result<Signature> {
+        let spend = SpendRequest::from_forward_request(request, domain)?;
+        self.guarded(&spend, || {
+            Ok(eip2771::sign_forward_request(signer, request, domain)?)
+        })
+    }
+
+    /// Authorizes EIP-712 typed data of a type known at compile time and signs it.
+    ///
+    /// `chain_id` is the chain of the dapp asking, used when the domain names
+    /// none.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_eip712`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_typed_data<T: Eip712Type>(
+        &self,
+        signer: &Bip44Signer,
+        chain_id: u64,
+        domain: &Eip712Domain,
+        message: &T,
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_eip712(domain, message, chain_id)?;
+        self.guarded(&request, || {
+            Ok(eip712::sign_typed_data(signer, domain, message)?)
+        })
+    }
+
+    /// Authorizes `eth_signTypedData_v4` JSON and signs it.
+    ///
+    /// `chain_id` is the chain of the dapp asking, used when the domain names
+    /// none.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_typed_data`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_typed_data_json(
+        &self,
+        signer: &Bip44Signer,
+        chain_id: u64,
+        typed_data: &TypedData,
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_typed_data(typed_data, chain_id)?;
+        self.guarded(&request, || {
+            Ok(eip712::sign_typed_data_json(signer, typed_data)?)
+        })
+    }
+
+    /// Authorizes a `personal_sign` message from the dapp connected on `chain_id`
+    /// and signs it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_message(
+        &self,
+        signer: &Bip44Signer,
+        chain_id: u64,
+        message: &[u8],
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_message(message, chain_id);
+        self.guarded(&request, || Ok(signer.sign_message(message)?))
+    }
+
+    /// Authorizes signing a raw digest for `chain_id` and signs it under the
+    /// signer's [`PrehashPolicy`] as well.
+    ///
+    /// The policy cannot see what a digest commits to, so it must set
+    /// [`Policy::allow_prehash`] and every digest goes to the confirmation
+    /// callback, whether or not [`Policy::confirm_messages`] is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] if the policy does not allow raw
+    /// digests, the errors of [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_prehashed(
+        &self,
+        signer: &Bip44Signer,
+        chain_id: u64,
+        digest: &[u8; 32],
+        policy: &PrehashPolicy,
+    ) -> Result<Signature> {
+        let request = SpendRequest::for_message(
+            Network::Evm(chain_id),
+            format!("digest 0x{} for {}", hex(digest), policy.purpose()),
+        );
+        if !self.policy.allow_prehash {
+            return Err(Error::PolicyViolation(
+                "raw digests are not allowed".to_string(),
+            ));
+        }
+        self.guarded_confirming(
+            &request,
+            true,
+            || Ok(signer.sign_prehashed(digest, policy)?),
+        )
+    }
+
+    /// Authorizes a PSBT and signs the inputs `signer` can spend.
+    ///
+    /// Outputs paying `signer` are change and not counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_psbt`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_psbt(&self, psbt: &mut Psbt, signer: &BtcSigner) -> Result<usize> {
+        let request = SpendRequest::from_psbt(psbt, signer.network(), &[signer])?;
+        self.guarded(&request, || Ok(psbt.sign(signer)?))
+    }
+
+    /// Authorizes a Tron transaction and signs it with `signer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_tron_transaction`] and
+    /// [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_tron_transaction(
+        &self,
+        signer: &TronSigner,
+        tx: &TronTransaction,
+    ) -> Result<SignedTransaction> {
+        let request = SpendRequest::from_tron_transaction(tx)?;
+        self.guarded(&request, || Ok(signer.sign_transaction(tx)?))
+    }
+
+    /// Authorizes an XRP payment and signs it with `signer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`PolicyEngine::authorize`], or the signing error.
+    pub fn sign_xrp_payment(&self, signer: &XrpSigner, payment: &Payment) -> Result<SignedPayment> {
+        let request = SpendRequest::from_xrp_payment(payment);
+        self.guarded(&request, || Ok(signer.sign_payment(payment)?))
+    }
+
+    /// Authorizes an EIP-1559 transaction and has a hardware wallet sign it with
+    /// the key at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_evm_transaction`] and
+    /// [`PolicyEngine::authorize`], or the device error.
+    pub fn device_sign_evm_transaction(
+        &self,
+        device: &mut impl Signer,
+        path: &DerivationPath,
+        tx: &Eip1559Transaction,
+    ) -> Result<Signature> {
+        let request = SpendRequest::from_evm_transaction(tx)?;
+        self.guarded(&request, || Ok(device.sign_evm_transaction(path, tx)?))
+    }
+
+    /// Authorizes a PSBT and has a hardware wallet sign the inputs of `account`.
+    ///
+    /// Change is recognized as in [`SpendRequest::from_device_psbt`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SpendRequest::from_device_psbt`] and
+    /// [`PolicyEngine::authorize`], or the device error.
+    pub fn device_sign_psbt(
+        &self,
+        device: &mut impl Signer,
+        account: &BtcAccount,
+        psbt: &mut Psbt,
+    ) -> Result<usize> {
+        let request = SpendRequest::from_device_psbt(psbt, account)?;
+        self.guarded(&request, || Ok(device.sign_psbt(account, psbt)?))
+    }
+
+    /// Authorizes `request`, runs `sign` and gives the amount back if it fails.
+    fn guarded<T>(&self, request: &SpendRequest, sign: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.guarded_confirming(request, self.policy.confirm_messages, sign)
+    }
+
+    /// As [`PolicyEngine::guarded`], asking for confirmation of the request's
+    /// message when `confirm_message` is set.
+    fn guarded_confirming<T>(
+        &self,
+        request: &SpendRequest,
+        confirm_message: bool,
+        sign: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let timestamp = unix_time();
+        self.authorize_confirming(request, timestamp, confirm_message)?;
+        sign().inspect_err(|_| self.release(&self.spends(request), timestamp / SECONDS_PER_DAY))
+    }
+
+    /// Returns what `request` spends, with its fee unless the policy excludes
+    /// fees.
+    fn spends(&self, request: &SpendRequest) -> Vec<(Asset, u128)> {
+        request.spends(!self.policy.exclude_fees)
+    }
+
+    /// Takes the `spends` back off the spending on `day`.
+    fn release(&self, spends: &[(Asset, u128)], day: u64) {
+        let mut spent = self.lock();
+        for (asset, amount) in spends {
+            if let Some(spend) = spent.get_mut(asset) {
+                if spend.day == day {
+                    spend.amount = spend.amount.saturating_sub(*amount);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Asset, DailySpend>> {
+        // Every update leaves the map consistent, so a poisoned lock is still usable
+        self.spent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for PolicyEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolicyEngine")
+            .field("policy", &self.policy)
+            .field("confirmation", &self.confirmation.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+fn spent_on_day(spent: &HashMap<Asset, DailySpend>, asset: &Asset, day: u64) -> u128 {
+    spent
+        .get(asset)
+        .filter(|spend| spend.day == day)
+        .map_or(0, |spend| spend.amount)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_address::XrpAddress;
+    use khodpay_hw_signer::Error as DeviceError;
+    use khodpay_signing::abi::{Function, Token};
+    use khodpay_signing::erc4337::UserOperation;
+    use khodpay_signing::{erc721, ChainId, Wei, U256};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const DAY: u64 = SECONDS_PER_DAY;
+
+    fn bsc() -> Asset {
+        Asset::native(Network::Evm(56))
+    }
+
+    fn pay(amount: u128) -> SpendRequest {
+        SpendRequest::new(bsc()).with_transfer("0x1111", amount)
+    }
+
+    fn evm_tx(chain_id: ChainId, value: Wei) -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x11; 20]))
+            .value(value)
+            .build()
+            .unwrap()
+    }
+
+    /// A device that refuses everything.
+    struct Refusing;
+
+    impl Signer for Refusing {
+        fn master_fingerprint(&mut self) -> khodpay_hw_signer::Result<[u8; 4]> {
+            Err(DeviceError::UserRejected)
+        }
+
+        fn extended_public_key(
+            &mut self,
+            _path: &DerivationPath,
+            _display: bool,
+        ) -> khodpay_hw_signer::Result<khodpay_bip32::ExtendedPublicKey> {
+            Err(DeviceError::UserRejected)
+        }
+
+        fn evm_address(
+            &mut self,
+            _path: &DerivationPath,
+            _display: bool,
+        ) -> khodpay_hw_signer::Result<Address> {
+            Err(DeviceError::UserRejected)
+        }
+
+        fn sign_evm_transaction(
+            &mut self,
+            _path: &DerivationPath,
+            _tx: &Eip1559Transaction,
+        ) -> khodpay_hw_signer::Result<Signature> {
+            Err(DeviceError::UserRejected)
+        }
+
+        fn sign_evm_message(
+            &mut self,
+            _path: &DerivationPath,
+            _message: &[u8],
+        ) -> khodpay_hw_signer::Result<Signature> {
+            Err(DeviceError::UserRejected)
+        }
+
+        fn btc_address(
+            &mut self,
+            _account: &BtcAccount,
+            _chain: khodpay_bip44::Chain,
+            _index: u32,
+            _display: bool,
+        ) -> khodpay_hw_signer::Result<khodpay_btc_signing::Address> {
+            Err(DeviceError::UserRejected)
+        }
+
+        fn sign_psbt(
+            &mut self,
+            _account: &BtcAccount,
+            _psbt: &mut Psbt,
+        ) -> khodpay_hw_signer::Result<usize> {
+            Err(DeviceError::UserRejected)
+        }
+    }
+
+    #[test]
+    fn test_daily_limit_resets_each_day() {
+        let engine = PolicyEngine::new(Policy::new().daily_limit(bsc(), 100));
+        engine.authorize_at(&pay(60), DAY).unwrap();
+        engine.authorize_at(&pay(40), DAY + 10).unwrap();
+        let err = engine.authorize_at(&pay(1), 2 * DAY - 1).unwrap_err();
+        assert!(err.to_string().contains("daily limit of 100"));
+        assert_eq!(engine.spent_at(&bsc(), DAY), 100);
+
+        // Next UTC day
+        engine.authorize_at(&pay(100), 2 * DAY).unwrap();
+        assert_eq!(engine.spent_at(&bsc(), 2 * DAY), 100);
+
+        // Other assets have their own limits, or none
+        let token =
+            SpendRequest::new(Asset::token(Network::Evm(56), "0xAA")).with_transfer("0x1", 1_000);
+        engine.authorize_at(&token, 2 * DAY).unwrap();
+    }
+
+    #[test]
+    fn test_call_value_counts_against_native_limit() {
+        let token = Asset::token(Network::Evm(56), "0xAA");
+        let engine = PolicyEngine::new(
+            Policy::new()
+                .daily_limit(bsc(), 100)
+                .daily_limit(token.clone(), 1_000),
+        );
+        let call = |value| {
+            SpendRequest::new(token.clone())
+                .with_transfer("0x1", 10)
+                .with_call_value("0xaa", value)
+        };
+        engine.authorize_at(&call(60), DAY).unwrap();
+        assert_eq!(engine.spent_at(&bsc(), DAY), 60);
+        assert_eq!(engine.spent_at(&token, DAY), 10);
+
+        // Over the native limit: neither asset is counted
+        let err = engine.authorize_at(&call(50), DAY).unwrap_err();
+        assert!(err.to_string().contains("daily limit of 100"));
+        assert_eq!(engine.spent_at(&token, DAY), 10);
+    }
+
+    #[test]
+    fn test_record_spend_replays() {
+        let engine = PolicyEngine::new(Policy::new().daily_limit(bsc(), 100));
+        engine.record_spend_at(&bsc(), 90, DAY);
+        assert!(engine.authorize_at(&pay(20), DAY).is_err());
+        engine.authorize_at(&pay(10), DAY).unwrap();
+    }
+
+    #[test]
+    fn test_confirmation() {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let engine = PolicyEngine::new(Policy::new().confirm_above(bsc(), 50)).with_confirmation(
+            move |request| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                request.total() < 100
+            },
+        );
+        engine.authorize_at(&pay(50), DAY).unwrap();
+        assert_eq!(asked.load(Ordering::SeqCst), 0);
+        engine.authorize_at(&pay(60), DAY).unwrap();
+        assert!(matches!(
+            engine.authorize_at(&pay(100), DAY),
+            Err(Error::NotConfirmed(_))
+        ));
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+
+        // Declined requests are not counted
+        assert_eq!(engine.spent_at(&bsc(), DAY), 110);
+
+        // No callback: refused
+        let engine = PolicyEngine::new(Policy::new().confirm_above(bsc(), 0));
+        let err = engine.authorize_at(&pay(1), DAY).unwrap_err();
+        assert!(err.to_string().contains("none can be asked for"));
+        assert_eq!(engine.spent_at(&bsc(), DAY), 0);
+    }
+
+    #[test]
+    fn test_rules_checked_before_counting() {
+        let engine = PolicyEngine::new(Policy::new().deny_destination("0x1111"));
+        assert!(matches!(
+            engine.authorize_at(&pay(1), DAY),
+            Err(Error::PolicyViolation(_))
+        ));
+        assert_eq!(engine.spent_at(&bsc(), DAY), 0);
+    }
+
+    #[test]
+    fn test_sign_evm_transaction() {
+        // One ether plus the fee: 21,000 gas at 5 gwei
+        let fee = 105_000_000_000_000;
+        let policy = Policy::new()
+            .allow_network(Network::Evm(56))
+            .daily_limit(bsc(), Wei::from_ether(1).as_u128().unwrap() + fee);
+        let engine = PolicyEngine::new(policy);
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+
+        engine
+            .sign_evm_transaction(&signer, &evm_tx(ChainId::BscMainnet, Wei::from_ether(1)))
+            .unwrap();
+        assert!(matches!(
+            engine.sign_evm_transaction(&signer, &evm_tx(ChainId::BscMainnet, Wei::from_wei(1u64))),
+            Err(Error::PolicyViolation(_))
+        ));
+        let err = engine
+            .sign_evm_transaction(&signer, &evm_tx(ChainId::EthereumMainnet, Wei::ZERO))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Policy violation: EVM chain 1 is not allowed"
+        );
+    }
+
+    #[test]
+    fn test_failed_signature_is_not_counted() {
+        let engine = PolicyEngine::new(Policy::new().daily_limit(bsc(), 100).exclude_fees());
+        let tx = evm_tx(ChainId::BscMainnet, Wei::from_wei(60u64));
+        let path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+        assert!(matches!(
+            engine.device_sign_evm_transaction(&mut Refusing, &path, &tx),
+            Err(Error::DeviceError(DeviceError::UserRejected))
+        ));
+        assert_eq!(engine.spent_today(&bsc()), 0);
+
+        // The signer's own policy refusing counts as a failure too
+        let signer = Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_policy(khodpay_signing::SigningPolicy::new().max_value(Wei::from_wei(10u64)));
+        assert!(matches!(
+            engine.sign_evm_transaction(&signer, &tx),
+            Err(Error::EvmError(_))
+        ));
+        assert_eq!(engine.spent_today(&bsc()), 0);
+    }
+
+    #[test]
+    fn test_sign_xrp_payment() {
+        let signer = XrpSigner::from_private_key(&[1u8; 32]).unwrap();
+        let payment = |destination| {
+            Payment::builder()
+                .account(signer.address())
+                .destination(destination)
+                .amount(1_000)
+                .sequence(1)
+                .build()
+                .unwrap()
+        };
+        let trusted = XrpAddress::from_bytes([2; 20]);
+        let engine = PolicyEngine::new(Policy::new().allow_destination(trusted.to_string()));
+        engine.sign_xrp_payment(&signer, &payment(trusted)).unwrap();
+        assert!(matches!(
+            engine.sign_xrp_payment(&signer, &payment(XrpAddress::from_bytes([3; 20]))),
+            Err(Error::PolicyViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_fees_count_unless_excluded() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        // 21,000 gas at 5 gwei
+        let fee = 105_000_000_000_000;
+        let tx = evm_tx(ChainId::BscMainnet, Wei::from_wei(1_000u64));
+
+        let engine = PolicyEngine::new(Policy::new().daily_limit(bsc(), fee + 999));
+        assert!(matches!(
+            engine.sign_evm_transaction(&signer, &tx),
+            Err(Error::PolicyViolation(_))
+        ));
+        let engine = PolicyEngine::new(Policy::new().daily_limit(bsc(), fee + 1_000));
+        engine.sign_evm_transaction(&signer, &tx).unwrap();
+        assert_eq!(engine.spent_today(&bsc()), fee + 1_000);
+
+        // Confirmation thresholds count the fee too
+        let engine = PolicyEngine::new(Policy::new().confirm_above(bsc(), 1_000));
+        assert!(matches!(
+            engine.sign_evm_transaction(&signer, &tx),
+            Err(Error::NotConfirmed(_))
+        ));
+
+        let engine = PolicyEngine::new(
+            Policy::new()
+                .daily_limit(bsc(), 1_000)
+                .confirm_above(bsc(), 1_000)
+                .exclude_fees(),
+        );
+        engine.sign_evm_transaction(&signer, &tx).unwrap();
+        assert_eq!(engine.spent_today(&bsc()), 1_000);
+    }
+
+    #[test]
+    fn test_confirm_messages() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+
+        // Messages only need confirmation when the policy asks for it
+        let engine = PolicyEngine::new(Policy::new().allow_network(Network::Evm(56)));
+        engine.sign_message(&signer, 56, b"gm").unwrap();
+        let err = engine.sign_message(&signer, 1, b"gm").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Policy violation: EVM chain 1 is not allowed"
+        );
+
+        let engine = PolicyEngine::new(Policy::new().confirm_messages());
+        let err = engine.sign_message(&signer, 56, b"gm").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Confirmation required: signing message \"gm\" needs confirmation and none can be asked for"
+        );
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let engine =
+            PolicyEngine::new(Policy::new().confirm_messages()).with_confirmation(move |request| {
+                let message = request.message.clone().unwrap_or_default();
+                log.lock().unwrap().push(message.clone());
+                message.starts_with("message")
+            });
+        engine.sign_message(&signer, 56, b"gm").unwrap();
+        assert_eq!(seen.lock().unwrap().len(), 1);
+
+        // Transactions are not messages
+        engine
+            .sign_evm_transaction(&signer, &evm_tx(ChainId::BscMainnet, Wei::ZERO))
+            .unwrap();
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prehash_needs_opt_in_and_confirmation() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let prehash = PrehashPolicy::allow_any("bridge attestation").unwrap();
+
+        // A default policy refuses raw digests, even with a callback that approves
+        let engine = PolicyEngine::new(Policy::new()).with_confirmation(|_| true);
+        let err = engine
+            .sign_prehashed(&signer, 56, &[7; 32], &prehash)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Policy violation: raw digests are not allowed"
+        );
+
+        // Once allowed, each digest is confirmed without `confirm_messages`
+        let engine = PolicyEngine::new(Policy::new().allow_prehash());
+        let err = engine
+            .sign_prehashed(&signer, 56, &[7; 32], &prehash)
+            .unwrap_err();
+        assert!(err.to_string().ends_with("none can be asked for"));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let engine =
+            PolicyEngine::new(Policy::new().allow_prehash()).with_confirmation(move |request| {
+                let message = request.message.clone().unwrap_or_default();
+                log.lock().unwrap().push(message.clone());
+                message.ends_with("attestation")
+            });
+        engine
+            .sign_prehashed(&signer, 56, &[7; 32], &prehash)
+            .unwrap();
+        assert_eq!(
+            seen.lock().unwrap()[0],
+            format!("digest 0x{} for bridge attestation", "07".repeat(32))
+        );
+
+        let other = PrehashPolicy::allow_any("bridge withdrawal").unwrap();
+        let err = engine
+            .sign_prehashed(&signer, 56, &[7; 32], &other)
+            .unwrap_err();
+        assert!(err.to_string().ends_with("was not confirmed"));
+    }
+
+    #[test]
+    fn test_contract_signatures_are_checked() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let thief = Address::from_bytes([0x66; 20]);
+        let collection = Address::from_bytes([0xCC; 20]);
+        let engine = PolicyEngine::new(Policy::new().deny_destination(thief.to_string()));
+
+        // A Safe moving an NFT
+        let data = erc721::encode_safe_transfer_from(
+            &Address::from_bytes([0x01; 20]),
+            &thief,
+            U256::one(),
+        );
+        let tx = SafeTransaction::builder()
+            .to(collection)
+            .data(data.clone())
+            .nonce(0)
+            .build()
+            .unwrap();
+        let safe = Address::from_bytes([0x5A; 20]);
+        assert!(matches!(
+            engine.sign_safe_transaction(&signer, &tx, safe, 56),
+            Err(Error::PolicyViolation(_))
+        ));
+
+        // A user operation approving every NFT
+        let user_op = |call: Vec<u8>| {
+            let call_data = Function::parse("execute(address,uint256,bytes)")
+                .unwrap()
+                .encode_input(&[
+                    Token::Address(collection),
+                    Token::Uint(U256::zero()),
+                    Token::Bytes(call),
+                ])
+                .unwrap();
+            UserOperation::builder()
+                .sender(Address::from_bytes([0x01; 20]))
+                .nonce(0)
+                .call_data(call_data)
+                .call_gas_limit(100_000)
+                .verification_gas_limit(100_000)
+                .pre_verification_gas(50_000)
+                .max_fee_per_gas(5)
+                .max_priority_fee_per_gas(1)
+                .build()
+                .unwrap()
+                .pack()
+        };
+        let entry_point = Address::from_bytes([0xE7; 20]);
+        let approve_all = erc721::encode_set_approval_for_all(&thief, true);
+        assert!(matches!(
+            engine.sign_user_operation(&signer, &user_op(approve_all), entry_point, 56),
+            Err(Error::PolicyViolation(_))
+        ));
+        let friend = Address::from_bytes([0x77; 20]);
+        let approve = erc721::encode_set_approval_for_all(&friend, true);
+        engine
+            .sign_user_operation(&signer, &user_op(approve), entry_point, 56)
+            .unwrap();
+
+        // A permit to the thief
+        let permit = TypedData::from_json(&format!(
+            r#"{{
+                "types": {{
+                    "Permit": [
+                        {{"name": "owner", "type": "address"}},
+                        {{"name": "spender", "type": "address"}},
+                        {{"name": "value", "type": "uint256"}},
+                        {{"name": "nonce", "type": "uint256"}},
+                        {{"name": "deadline", "type": "uint256"}}
+                    ]
+                }},
+                "primaryType": "Permit",
+                "domain": {{"name": "Token", "chainId": 56, "verifyingContract": "{}"}},
+                "message": {{"owner": "{}", "spender": "{}", "value": "1", "nonce": 0, "deadline": 1}}
+            }}"#,
+            collection,
+            signer.address(),
+            thief
+        ))
+        .unwrap();
+        let err = engine
+            .sign_typed_data_json(&signer, 56, &permit)
+            .unwrap_err();
+        assert!(err.to_string().contains("is denied"));
+
+        // A forwarded call and an access-list transaction
+        let forward = ForwardRequest::builder()
+            .from(signer.address())
+            .to(collection)
+            .gas(100_000)
+            .nonce(0)
+            .deadline(1_700_000_000)
+            .data(data.clone())
+            .build()
+            .unwrap();
+        let domain = eip2771::forwarder_domain("Forwarder", 56, Address::from_bytes([0xF0; 20]));
+        assert!(matches!(
+            engine.sign_forward_request(&signer, &forward, &domain),
+            Err(Error::PolicyViolation(_))
+        ));
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(3))
+            .gas_limit(100_000)
+            .to(collection)
+            .data(data)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            engine.sign_eip2930_transaction(&signer, &tx),
+            Err(Error::PolicyViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_engine_is_shareable() {
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<PolicyEngine>();
+    }
+}
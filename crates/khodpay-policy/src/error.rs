@@ -0,0 +1,69 @@
+//! Error types for the policy crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while checking or signing a request under a policy.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The request breaks a rule of the policy.
+    #[error("Policy violation: {0}")]
+    PolicyViolation(String),
+
+    /// The request needed a confirmation that was declined or could not be asked
+    /// for.
+    #[error("Confirmation required: {0}")]
+    NotConfirmed(String),
+
+    /// The request cannot be described in terms the policy can check, such as a
+    /// contract deployment.
+    #[error("Unsupported request: {0}")]
+    Unsupported(String),
+
+    /// A policy file could not be read.
+    #[error("Invalid policy: {0}")]
+    InvalidPolicy(String),
+
+    /// Error from EVM signing.
+    #[error("EVM error: {0}")]
+    EvmError(#[from] khodpay_signing::Error),
+
+    /// Error from Bitcoin signing.
+    #[error("Bitcoin error: {0}")]
+    BtcError(#[from] khodpay_btc_signing::Error),
+
+    /// Error from Tron signing.
+    #[error("Tron error: {0}")]
+    TronError(#[from] khodpay_tron_signing::Error),
+
+    /// Error from XRP Ledger signing.
+    #[error("XRP error: {0}")]
+    XrpError(#[from] khodpay_xrp_signing::Error),
+
+    /// Error from a hardware signer.
+    #[error("Hardware signer error: {0}")]
+    DeviceError(#[from] khodpay_hw_signer::Error),
+}
+
+/// Result type alias for policy operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::PolicyViolation("destination bc1q… is denied".to_string()).to_string(),
+            "Policy violation: destination bc1q… is denied"
+        );
+        assert_eq!(
+            Error::NotConfirmed("no confirmation callback".to_string()).to_string(),
+            "Confirmation required: no confirmation callback"
+        );
+        assert_eq!(
+            Error::DeviceError(khodpay_hw_signer::Error::UserRejected).to_string(),
+            "Hardware signer error: Request rejected on the device"
+        );
+    }
+}
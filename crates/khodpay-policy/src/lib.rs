@@ -0,0 +1,88 @@
+//! # Khodpay Policy
+//!
+//! A declarative policy layer in front of every signer: per-day spend limits,
+//! destination allow and deny lists, confirmation callbacks, and network and coin
+//! restrictions, set once when the wallet starts.
+//!
+//! Transactions from each signing crate are described as a chain-neutral
+//! [`SpendRequest`] (asset, destinations, amounts), checked against the
+//! [`Policy`] by a [`PolicyEngine`] and only then signed. One engine covers
+//! software signers for EVM chains, Bitcoin, Tron and the XRP Ledger as well as
+//! `khodpay-hw-signer` devices, so daily limits hold across all of them.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`Policy`] | The rules, built in code or loaded from JSON |
+//! | [`PolicyEngine`] | Enforces a policy, tracks daily spending and signs what passes |
+//! | [`SpendRequest`] | What a transaction spends: [`Asset`] and [`Transfer`]s |
+//! | [`Network`] | The networks a policy tells apart |
+//!
+//! ## Features
+//!
+//! - **Daily Limits**: Per asset, native coins and tokens alike, over UTC days
+//! - **Destination Lists**: Allow-lists and deny-lists, with EVM addresses compared
+//!   case-insensitively
+//! - **Confirmations**: A callback approves requests above per-asset thresholds
+//! - **Network and Coin Restrictions**: By EVM chain ID, network or SLIP-44 coin
+//! - **Token Aware**: ERC-20 and TRC-20 transfers and approvals, ERC-721 and
+//!   ERC-1155 transfers and approvals, and Disperse batches count as token spends
+//!   to each recipient or spender
+//! - **Every Signature**: Transactions, Safe transactions, ERC-4337 user
+//!   operations, ERC-2771 forward requests, permits, typed data, messages and raw
+//!   digests, with confirmation for messages on request and raw digests only on
+//!   opt-in, always confirmed
+//! - **Fees**: Network fees count against the native coin's limits unless the
+//!   policy excludes them
+//! - **Change Aware**: Bitcoin change outputs are not counted
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_policy::{Asset, Network, Policy, PolicyEngine};
+//! use khodpay_signing::{Address, Bip44Signer, ChainId, Eip1559Transaction, Wei};
+//!
+//! // At wallet start-up
+//! let policy = Policy::from_json(r#"{
+//!     "networks": [{"evm": 56}],
+//!     "allowed_destinations": ["0x1111111111111111111111111111111111111111"],
+//!     "daily_limits": [{"asset": {"network": {"evm": 56}}, "amount": "2000000000000000000"}]
+//! }"#)?;
+//! let engine = PolicyEngine::new(policy);
+//!
+//! // Every signature goes through the engine
+//! let signer = Bip44Signer::from_private_key(&[1u8; 32])?;
+//! let tx = Eip1559Transaction::builder()
+//!     .chain_id(ChainId::BscMainnet)
+//!     .nonce(0)
+//!     .max_priority_fee_per_gas(Wei::from_gwei(1))
+//!     .max_fee_per_gas(Wei::from_gwei(5))
+//!     .gas_limit(21_000)
+//!     .to(Address::from_bytes([0x11; 20]))
+//!     .value(Wei::from_ether(1))
+//!     .build()?;
+//!
+//! // One BNB, plus 21,000 gas at 5 gwei
+//! let signature = engine.sign_evm_transaction(&signer, &tx)?;
+//! assert_eq!(
+//!     engine.spent_today(&Asset::native(Network::Evm(56))),
+//!     1_000_105_000_000_000_000
+//! );
+//! # Ok::<(), khodpay_policy::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod calldata;
+mod engine;
+mod error;
+mod policy;
+mod request;
+
+pub use engine::{ConfirmationCallback, PolicyEngine};
+pub use error::{Error, Result};
+pub use policy::{AssetAmount, Policy};
+pub use request::{Asset, Network, SpendRequest, Transfer};
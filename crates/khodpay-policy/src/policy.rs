@@ -0,0 +1,413 @@
+//! The declarative [`Policy`]: which networks, coins and destinations are
+//! allowed, and how much may be spent.
+
+use std::collections::BTreeSet;
+
+use khodpay_bip44::CoinType;
+use serde::{Deserialize, Serialize};
+
+use crate::request::normalize_address;
+use crate::{Asset, Error, Network, Result, SpendRequest};
+
+/// An amount of one asset, such as a daily limit or a confirmation threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetAmount {
+    /// The asset.
+    pub asset: Asset,
+    /// The amount in base units, written as a decimal string in policy files.
+    #[serde(with = "decimal")]
+    pub amount: u128,
+}
+
+/// The rules every signature must follow, set once when the wallet starts.
+///
+/// A policy is plain data: build it in code, or load it from a JSON file with
+/// [`Policy::from_json`]. Every rule is optional and [`Policy::new`] allows
+/// everything; a [`PolicyEngine`](crate::PolicyEngine) enforces it.
+///
+/// - **Networks and coins**: once any is listed, requests for others are refused.
+///   Coins are SLIP-44 coin types, so allowing Ethereum covers every EVM chain.
+/// - **Destinations**: a denied destination is always refused; once any is
+///   allowed, all others are refused. The contract of a token spend is checked as
+///   a destination too. EVM addresses compare case-insensitively.
+/// - **Daily limits**: per asset, over UTC calendar days.
+/// - **Confirmations**: requests above a threshold need the engine's confirmation
+///   callback to approve them, and so do messages once
+///   [`confirm_messages`](Self::confirm_messages) is set.
+/// - **Raw digests**: refused unless [`allow_prehash`](Self::allow_prehash) is
+///   set, and then always confirmed, since the policy cannot tell what they sign.
+/// - **Fees**: network fees count against the native coin's limits and thresholds
+///   unless [`exclude_fees`](Self::exclude_fees) is set.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_policy::{Asset, Network, Policy};
+///
+/// let bsc = Asset::native(Network::Evm(56));
+/// let policy = Policy::new()
+///     .allow_network(Network::Evm(56))
+///     .allow_network(Network::Bitcoin)
+///     .deny_destination("0x000000000000000000000000000000000000dead")
+///     .daily_limit(bsc.clone(), 5_000_000_000_000_000_000)
+///     .confirm_above(bsc, 1_000_000_000_000_000_000);
+///
+/// let json = r#"{
+///     "networks": [{"evm": 56}, "bitcoin"],
+///     "denied_destinations": ["0x000000000000000000000000000000000000dEaD"],
+///     "daily_limits": [{"asset": {"network": {"evm": 56}}, "amount": "5000000000000000000"}],
+///     "confirm_above": [{"asset": {"network": {"evm": 56}}, "amount": "1000000000000000000"}]
+/// }"#;
+/// assert_eq!(Policy::from_json(json)?, policy);
+/// # Ok::<(), khodpay_policy::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Networks requests may be for; empty allows all.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub networks: BTreeSet<Network>,
+    /// SLIP-44 coin types requests may be for; empty allows all.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub coins: BTreeSet<u32>,
+    /// Destinations that may be paid; empty allows all.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub allowed_destinations: BTreeSet<String>,
+    /// Destinations that may never be paid.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub denied_destinations: BTreeSet<String>,
+    /// The most that may be spent per asset per UTC day.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub daily_limits: Vec<AssetAmount>,
+    /// Requests spending more than this per asset need confirmation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub confirm_above: Vec<AssetAmount>,
+    /// Signing messages and typed data other than permits needs confirmation.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub confirm_messages: bool,
+    /// Raw digests may be signed, each one after confirmation.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub allow_prehash: bool,
+    /// Network fees are left out of daily limits and confirmation thresholds.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub exclude_fees: bool,
+}
+
+impl Policy {
+    /// Creates a policy without any restriction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a policy from JSON, in the shape shown on [`Policy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPolicy`] for malformed JSON, unknown fields and
+    /// amounts that are not decimal strings.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let policy: Self =
+            serde_json::from_str(json).map_err(|e| Error::InvalidPolicy(e.to_string()))?;
+        Ok(policy.normalized())
+    }
+
+    /// Returns the policy as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("policy serializes to JSON")
+    }
+
+    /// Adds `network` to the allowed networks.
+    pub fn allow_network(mut self, network: Network) -> Self {
+        self.networks.insert(network);
+        self
+    }
+
+    /// Adds `coin` to the allowed coin types.
+    pub fn allow_coin(mut self, coin: CoinType) -> Self {
+        self.coins.insert(coin.index());
+        self
+    }
+
+    /// Adds `destination` to the allow-list.
+    pub fn allow_destination(mut self, destination: impl AsRef<str>) -> Self {
+        self.allowed_destinations
+            .insert(normalize_address(destination.as_ref()));
+        self
+    }
+
+    /// Adds `destination` to the deny-list.
+    pub fn deny_destination(mut self, destination: impl AsRef<str>) -> Self {
+        self.denied_destinations
+            .insert(normalize_address(destination.as_ref()));
+        self
+    }
+
+    /// Sets the most of `asset` that may be spent per UTC day, replacing any
+    /// earlier limit.
+    pub fn daily_limit(mut self, asset: Asset, amount: u128) -> Self {
+        set_amount(&mut self.daily_limits, asset, amount);
+        self
+    }
+
+    /// Requires confirmation for requests spending more than `amount` of `asset`,
+    /// replacing any earlier threshold. A threshold of `0` confirms every spend.
+    pub fn confirm_above(mut self, asset: Asset, amount: u128) -> Self {
+        set_amount(&mut self.confirm_above, asset, amount);
+        self
+    }
+
+    /// Requires confirmation for every signature that is not a transaction and
+    /// moves nothing the policy can see: messages and typed data other than
+    /// permits. Raw digests are always confirmed, see
+    /// [`allow_prehash`](Self::allow_prehash).
+    pub fn confirm_messages(mut self) -> Self {
+        self.confirm_messages = true;
+        self
+    }
+
+    /// Allows signing raw digests. Each one still needs the engine's
+    /// confirmation callback to approve it, whatever
+    /// [`confirm_messages`](Self::confirm_messages) says.
+    pub fn allow_prehash(mut self) -> Self {
+        self.allow_prehash = true;
+        self
+    }
+
+    /// Leaves network fees out of daily limits and confirmation thresholds, so
+    /// they only cover what is sent.
+    pub fn exclude_fees(mut self) -> Self {
+        self.exclude_fees = true;
+        self
+    }
+
+    /// Returns the daily limit for `asset`, if there is one.
+    pub fn daily_limit_for(&self, asset: &Asset) -> Option<u128> {
+        find_amount(&self.daily_limits, asset)
+    }
+
+    /// Returns the confirmation threshold for `asset`, if there is one.
+    pub fn confirmation_threshold_for(&self, asset: &Asset) -> Option<u128> {
+        find_amount(&self.confirm_above, asset)
+    }
+
+    /// Checks the rules that do not depend on earlier requests: network, coin and
+    /// destinations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] naming the first rule the request breaks.
+    pub fn check(&self, request: &SpendRequest) -> Result<()> {
+        let network = request.asset.network;
+        if !self.networks.is_empty() && !self.networks.contains(&network) {
+            return Err(violation(format!("{} is not allowed", network)));
+        }
+        let coin = network.coin_type();
+        if !self.coins.is_empty() && !self.coins.contains(&coin.index()) {
+            return Err(violation(format!("coin type {} is not allowed", coin)));
+        }
+        let destinations = request
+            .destinations()
+            .map(|transfer| transfer.destination.as_str())
+            .chain(request.asset.token.as_deref());
+        for destination in destinations {
+            let normalized = normalize_address(destination);
+            if self.denied_destinations.contains(&normalized) {
+                return Err(violation(format!("destination {} is denied", destination)));
+            }
+            if !self.allowed_destinations.is_empty()
+                && !self.allowed_destinations.contains(&normalized)
+            {
+                return Err(violation(format!(
+                    "destination {} is not on the allow-list",
+                    destination
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the policy with every address normalized, as the builder methods
+    /// store them.
+    fn normalized(mut self) -> Self {
+        let normalize = |set: BTreeSet<String>| {
+            set.iter()
+                .map(|address| normalize_address(address))
+                .collect()
+        };
+        self.allowed_destinations = normalize(self.allowed_destinations);
+        self.denied_destinations = normalize(self.denied_destinations);
+        for entry in self
+            .daily_limits
+            .iter_mut()
+            .chain(self.confirm_above.iter_mut())
+        {
+            entry.asset = entry.asset.normalized();
+        }
+        self
+    }
+}
+
+fn violation(message: String) -> Error {
+    Error::PolicyViolation(message)
+}
+
+fn set_amount(amounts: &mut Vec<AssetAmount>, asset: Asset, amount: u128) {
+    let asset = asset.normalized();
+    amounts.retain(|entry| entry.asset != asset);
+    amounts.push(AssetAmount { asset, amount });
+}
+
+fn find_amount(amounts: &[AssetAmount], asset: &Asset) -> Option<u128> {
+    let asset = asset.normalized();
+    amounts
+        .iter()
+        .find(|entry| entry.asset.normalized() == asset)
+        .map(|entry| entry.amount)
+}
+
+/// Amounts as decimal strings, since JSON numbers lose precision above 2^53.
+mod decimal {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(amount: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&amount.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(network: Network, destination: &str) -> SpendRequest {
+        SpendRequest::new(Asset::native(network)).with_transfer(destination, 1)
+    }
+
+    #[test]
+    fn test_unrestricted() {
+        let policy = Policy::new();
+        assert!(policy.check(&request(Network::Tron, "T...")).is_ok());
+        assert_eq!(policy.daily_limit_for(&Asset::native(Network::Tron)), None);
+    }
+
+    #[test]
+    fn test_networks_and_coins() {
+        let policy = Policy::new().allow_network(Network::Evm(56));
+        assert!(policy.check(&request(Network::Evm(56), "0x1")).is_ok());
+        let err = policy.check(&request(Network::Evm(1), "0x1")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Policy violation: EVM chain 1 is not allowed"
+        );
+
+        let policy = Policy::new().allow_coin(CoinType::Ethereum);
+        assert!(policy.check(&request(Network::Evm(1), "0x1")).is_ok());
+        assert!(policy.check(&request(Network::Evm(137), "0x1")).is_ok());
+        let err = policy.check(&request(Network::Xrp, "r...")).unwrap_err();
+        assert!(err.to_string().contains("coin type"));
+    }
+
+    #[test]
+    fn test_destinations() {
+        let policy = Policy::new()
+            .allow_destination("0xAAAA")
+            .allow_destination("bc1qgood")
+            .deny_destination("bc1qgood");
+        assert!(policy.check(&request(Network::Evm(56), "0xaaaa")).is_ok());
+        let err = policy
+            .check(&request(Network::Bitcoin, "BC1QGOOD"))
+            .unwrap_err();
+        assert!(err.to_string().contains("is denied"));
+        let err = policy
+            .check(&request(Network::Evm(56), "0xbbbb"))
+            .unwrap_err();
+        assert!(err.to_string().contains("not on the allow-list"));
+
+        // Every transfer is checked
+        let mixed = request(Network::Evm(56), "0xaaaa").with_transfer("0xbbbb", 1);
+        assert!(policy.check(&mixed).is_err());
+
+        // So are the token contract and the call value's destination
+        let token = |contract| {
+            SpendRequest::new(Asset::token(Network::Evm(56), contract)).with_transfer("0xaaaa", 1)
+        };
+        let err = policy.check(&token("0xDEAD")).unwrap_err();
+        assert!(err.to_string().contains("0xdead is not on the allow-list"));
+        let policy = policy.allow_destination("0xDAC1");
+        assert!(policy.check(&token("0xdac1")).is_ok());
+        let err = policy
+            .check(&token("0xdac1").with_call_value("0xbbbb", 1))
+            .unwrap_err();
+        assert!(err.to_string().contains("0xbbbb"));
+        let policy = policy.deny_destination("0xdac1");
+        let err = policy.check(&token("0xdac1")).unwrap_err();
+        assert!(err.to_string().contains("is denied"));
+    }
+
+    #[test]
+    fn test_amounts_replace() {
+        let token = Asset::token(Network::Evm(56), "0xDAC1");
+        let policy = Policy::new()
+            .daily_limit(token.clone(), 10)
+            .daily_limit(token.clone(), 20)
+            .confirm_above(token.clone(), 5);
+        assert_eq!(policy.daily_limits.len(), 1);
+        assert_eq!(
+            policy.daily_limit_for(&Asset::token(Network::Evm(56), "0xdac1")),
+            Some(20)
+        );
+        assert_eq!(policy.confirmation_threshold_for(&token), Some(5));
+        assert_eq!(
+            policy.daily_limit_for(&Asset::native(Network::Evm(56))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let policy = Policy::new()
+            .allow_network(Network::Bitcoin)
+            .allow_coin(CoinType::Bitcoin)
+            .allow_destination("bc1qexample")
+            .daily_limit(Asset::native(Network::Bitcoin), u128::MAX);
+        let json = policy.to_json();
+        assert!(json.contains(&format!("\"{}\"", u128::MAX)));
+        assert!(!json.contains("exclude_fees"));
+        assert_eq!(Policy::from_json(&json).unwrap(), policy);
+        assert_eq!(Policy::from_json("{}").unwrap(), Policy::new());
+
+        let policy = policy.confirm_messages().allow_prehash().exclude_fees();
+        assert_eq!(Policy::from_json(&policy.to_json()).unwrap(), policy);
+        assert_eq!(
+            Policy::from_json(
+                r#"{"confirm_messages": true, "allow_prehash": true, "exclude_fees": true}"#
+            )
+            .unwrap(),
+            Policy::new()
+                .confirm_messages()
+                .allow_prehash()
+                .exclude_fees()
+        );
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        for json in [
+            r#"{"networks": ["dogecoin"]}"#,
+            r#"{"daily_limit": []}"#,
+            r#"{"daily_limits": [{"asset": {"network": "xrp"}, "amount": 5}]}"#,
+            r#"{"daily_limits": [{"asset": {"network": "xrp"}, "amount": "-5"}]}"#,
+        ] {
+            assert!(
+                matches!(Policy::from_json(json), Err(Error::InvalidPolicy(_))),
+                "{}",
+                json
+            );
+        }
+    }
+}
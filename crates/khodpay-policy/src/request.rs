@@ -0,0 +1,1267 @@
+//! Chain-neutral descriptions of what a signature would spend.
+
+use std::fmt;
+
+use khodpay_address::TronAddress;
+use khodpay_bip44::{Chain, CoinType};
+use khodpay_btc_signing::psbt::Psbt;
+use khodpay_btc_signing::{Address as BtcAddress, BtcSigner, Network as BtcNetwork};
+use khodpay_hw_signer::BtcAccount;
+use khodpay_signing::eip2771::ForwardRequest;
+use khodpay_signing::eip712::{Eip712Domain, Eip712Type, TypedData};
+use khodpay_signing::erc4337::PackedUserOperation;
+use khodpay_signing::safe::{Operation, SafeTransaction};
+use khodpay_signing::{Address, Eip1559Transaction, Eip2930Transaction, U256};
+use khodpay_tron_signing::{Contract, Transaction as TronTransaction};
+use khodpay_xrp_signing::Payment;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::calldata::{account_calls, disperse_spend, permit, token_spend, Payouts, PERMIT_TYPE};
+use crate::{Error, Result};
+
+/// The networks a policy can tell apart.
+///
+/// In policy files: `{"evm": 56}`, `"bitcoin"`, `"bitcoin_testnet"`, `"tron"` or
+/// `"xrp"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Network {
+    /// An EVM chain, by chain ID.
+    Evm(u64),
+    /// Bitcoin mainnet.
+    Bitcoin,
+    /// Bitcoin testnet, signet and regtest.
+    BitcoinTestnet,
+    /// Tron mainnet.
+    Tron,
+    /// XRP Ledger mainnet.
+    Xrp,
+}
+
+impl Network {
+    /// Returns the SLIP-44 coin type whose keys sign for this network.
+    ///
+    /// Every EVM chain signs with Ethereum (`60`) keys.
+    pub fn coin_type(&self) -> CoinType {
+        match self {
+            Network::Evm(_) => CoinType::Ethereum,
+            Network::Bitcoin => CoinType::Bitcoin,
+            Network::BitcoinTestnet => CoinType::BitcoinTestnet,
+            Network::Tron => CoinType::Tron,
+            Network::Xrp => CoinType::Ripple,
+        }
+    }
+}
+
+impl From<BtcNetwork> for Network {
+    fn from(network: BtcNetwork) -> Self {
+        match network {
+            BtcNetwork::Bitcoin => Network::Bitcoin,
+            _ => Network::BitcoinTestnet,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Evm(chain_id) => write!(f, "EVM chain {}", chain_id),
+            Network::Bitcoin => f.write_str("Bitcoin"),
+            Network::BitcoinTestnet => f.write_str("Bitcoin testnet"),
+            Network::Tron => f.write_str("Tron"),
+            Network::Xrp => f.write_str("XRP Ledger"),
+        }
+    }
+}
+
+/// A network's native coin, or a token on it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Asset {
+    /// The network the asset lives on.
+    pub network: Network,
+    /// The token contract, or `None` for the native coin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Asset {
+    /// Returns the native coin of `network`.
+    pub fn native(network: Network) -> Self {
+        Self {
+            network,
+            token: None,
+        }
+    }
+
+    /// Returns the token at `contract` on `network`.
+    pub fn token(network: Network, contract: impl fmt::Display) -> Self {
+        Self {
+            network,
+            token: Some(normalize_address(&contract.to_string())),
+        }
+    }
+
+    /// Returns the asset with its token address normalized, for comparisons.
+    pub(crate) fn normalized(&self) -> Self {
+        Self {
+            network: self.network,
+            token: self.token.as_deref().map(normalize_address),
+        }
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.token {
+            Some(token) => write!(f, "token {} on {}", token, self.network),
+            None => write!(f, "{} native coin", self.network),
+        }
+    }
+}
+
+/// One payment within a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    /// The address paid, or `script:<hex>` for Bitcoin outputs without one.
+    pub destination: String,
+    /// The amount in the asset's base units: wei, satoshis, SUN, drops or token
+    /// units.
+    pub amount: u128,
+}
+
+/// What a transaction would spend, in terms a [`Policy`](crate::Policy) can check.
+///
+/// Built from a transaction with one of the `from_*` constructors, or by hand for
+/// signers this crate does not know.
+///
+/// - ERC-20 and TRC-20 `transfer`, `transferFrom` and `approve` calls are token
+///   spends to the recipient or spender; an approval counts as spending its whole
+///   allowance. ERC-721 and ERC-1155 transfers and `setApprovalForAll` are spends
+///   of the collection, and Disperse batches pay each recipient. Native coin sent
+///   along with a token call is a [`call_values`](Self::call_values) spend to the
+///   contract.
+/// - Other contract calls spend the native value sent with them, to the contract.
+/// - Bitcoin change outputs are left out.
+/// - The most the network can charge is the [`fee`](Self::fee), in the native
+///   coin.
+/// - Signatures that move nothing the policy can see, such as `personal_sign`
+///   messages, spend nothing and carry a [`message`](Self::message).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendRequest {
+    /// The asset spent.
+    pub asset: Asset,
+    /// Where it goes.
+    pub transfers: Vec<Transfer>,
+    /// Native coin sent along with token calls, to the contracts called.
+    ///
+    /// It counts against the native coin's limits, not the token's.
+    pub call_values: Vec<Transfer>,
+    /// The most the network fee can cost, in the native coin's base units.
+    ///
+    /// It counts against the native coin's limits unless the policy excludes fees.
+    pub fee: u128,
+    /// What is signed, for signatures that are not transactions: the text of a
+    /// message, the type of typed data or a raw digest.
+    pub message: Option<String>,
+}
+
+impl SpendRequest {
+    /// Creates a request for `asset` with no transfers yet.
+    pub fn new(asset: Asset) -> Self {
+        Self {
+            asset,
+            transfers: Vec::new(),
+            call_values: Vec::new(),
+            fee: 0,
+            message: None,
+        }
+    }
+
+    /// Creates a request for signing `message` on `network`, which spends nothing.
+    pub fn for_message(network: Network, message: impl Into<String>) -> Self {
+        Self {
+            message: Some(message.into()),
+            ..Self::new(Asset::native(network))
+        }
+    }
+
+    /// Adds a transfer of `amount` base units to `destination`.
+    pub fn with_transfer(mut self, destination: impl fmt::Display, amount: u128) -> Self {
+        self.transfers.push(Transfer {
+            destination: destination.to_string(),
+            amount,
+        });
+        self
+    }
+
+    /// Adds native coin sent to `contract` along with a token call; nothing is
+    /// recorded for an `amount` of zero.
+    pub fn with_call_value(mut self, contract: impl fmt::Display, amount: u128) -> Self {
+        if amount > 0 {
+            self.call_values.push(Transfer {
+                destination: contract.to_string(),
+                amount,
+            });
+        }
+        self
+    }
+
+    /// Sets the most the network fee can cost, in the native coin.
+    pub fn with_fee(mut self, fee: u128) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Returns the sum of all transfers, saturating at `u128::MAX`.
+    ///
+    /// The [`call_values`](Self::call_values) and the [`fee`](Self::fee) are paid
+    /// in the native coin and not included.
+    pub fn total(&self) -> u128 {
+        sum(&self.transfers)
+    }
+
+    /// Returns every destination paid, the call values' included.
+    pub(crate) fn destinations(&self) -> impl Iterator<Item = &Transfer> {
+        self.transfers.iter().chain(&self.call_values)
+    }
+
+    /// Returns the normalized assets spent with their totals: the request's asset
+    /// and, with call values or a counted fee, the native coin.
+    pub(crate) fn spends(&self, count_fee: bool) -> Vec<(Asset, u128)> {
+        let asset = self.asset.normalized();
+        let mut spends = vec![(asset.clone(), self.total())];
+        let fee = if count_fee { self.fee } else { 0 };
+        let native_total = sum(&self.call_values).saturating_add(fee);
+        if native_total > 0 {
+            let native = Asset::native(asset.network);
+            match spends.iter_mut().find(|(asset, _)| *asset == native) {
+                Some((_, total)) => *total = total.saturating_add(native_total),
+                None => spends.push((native, native_total)),
+            }
+        }
+        spends
+    }
+
+    /// Adds the transfers of `payouts`.
+    fn with_payouts(
+        mut self,
+        payouts: Payouts,
+        destination: impl Fn(Address) -> String,
+    ) -> Result<Self> {
+        for (to, amount) in payouts {
+            self = self.with_transfer(destination(to), to_u128(amount)?);
+        }
+        Ok(self)
+    }
+
+    /// Adds the spends of `other`, a call made together with this one as in a
+    /// batch. Native coin sent alongside a token spend becomes call values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] if the two spend different tokens.
+    fn merge(self, other: Self) -> Result<Self> {
+        let (mut merged, other) = match (&self.asset.token, &other.asset.token) {
+            (None, Some(_)) => (other, self),
+            (Some(token), Some(other_token)) if token != other_token => {
+                return Err(Error::Unsupported(
+                    "calls spending more than one token are not covered by the policy".to_string(),
+                ))
+            }
+            _ => (self, other),
+        };
+        if other.asset.token.is_some() || merged.asset.token.is_none() {
+            merged.transfers.extend(other.transfers);
+        } else {
+            merged.call_values.extend(other.transfers);
+        }
+        merged.call_values.extend(other.call_values);
+        merged.fee = merged.fee.saturating_add(other.fee);
+        Ok(merged)
+    }
+
+    /// Describes an EIP-1559 transaction, with its gas limit at the maximum fee
+    /// per gas as fee.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] for contract deployments and amounts above
+    /// `u128::MAX`.
+    pub fn from_evm_transaction(tx: &Eip1559Transaction) -> Result<Self> {
+        let to = tx.to.ok_or_else(deployment)?;
+        let fee = tx.max_fee_per_gas.saturating_mul(tx.gas_limit);
+        Ok(
+            evm_call(tx.chain_id.value(), to, tx.value.into(), &tx.data)?
+                .with_fee(to_u128(fee.into())?),
+        )
+    }
+
+    /// Describes an EIP-2930 transaction, with its gas limit at its gas price as
+    /// fee.
+    ///
+    /// # Errors
+    ///
+    /// As [`SpendRequest::from_evm_transaction`].
+    pub fn from_eip2930_transaction(tx: &Eip2930Transaction) -> Result<Self> {
+        let to = tx.to.ok_or_else(deployment)?;
+        let fee = tx.gas_price.saturating_mul(tx.gas_limit);
+        Ok(
+            evm_call(tx.chain_id.value(), to, tx.value.into(), &tx.data)?
+                .with_fee(to_u128(fee.into())?),
+        )
+    }
+
+    /// Describes a Safe transaction on `chain_id`: the Safe's call, and the most
+    /// its gas refund can cost.
+    ///
+    /// A refund in the native coin is the fee; one in a token is a transfer of the
+    /// token to the refund receiver.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] for delegate calls, refunds without a
+    /// `safe_tx_gas` to bound them, calls spending two tokens and amounts above
+    /// `u128::MAX`.
+    pub fn from_safe_transaction(tx: &SafeTransaction, chain_id: u64) -> Result<Self> {
+        if tx.operation == Operation::DelegateCall {
+            return Err(Error::Unsupported(
+                "Safe delegate calls are not covered by the policy".to_string(),
+            ));
+        }
+        let request = evm_call(chain_id, tx.to, tx.value.into(), &tx.data)?;
+        if tx.gas_price.is_zero() {
+            return Ok(request);
+        }
+        if tx.safe_tx_gas == 0 {
+            return Err(Error::Unsupported(
+                "Safe gas refunds without a safeTxGas are not covered by the policy".to_string(),
+            ));
+        }
+        let refund = tx
+            .gas_price
+            .saturating_mul(tx.safe_tx_gas.saturating_add(tx.base_gas));
+        let refund = to_u128(refund.into())?;
+        if tx.gas_token == Address::ZERO {
+            return Ok(request.with_fee(refund));
+        }
+        request.merge(
+            Self::new(Asset::token(Network::Evm(chain_id), tx.gas_token))
+                .with_transfer(tx.refund_receiver, refund),
+        )
+    }
+
+    /// Describes an ERC-4337 user operation on `chain_id`: each call of the
+    /// account's `execute` or `executeBatch`, and its gas at the maximum fee per
+    /// gas as fee unless a paymaster pays it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] for other account calldata, batches
+    /// spending two tokens and amounts above `u128::MAX`.
+    pub fn from_user_operation(user_op: &PackedUserOperation, chain_id: u64) -> Result<Self> {
+        let calls = account_calls(&user_op.call_data).ok_or_else(|| {
+            Error::Unsupported(
+                "user operations other than execute and executeBatch are not covered by the policy"
+                    .to_string(),
+            )
+        })?;
+        let mut request = Self::new(Asset::native(Network::Evm(chain_id)));
+        for (to, value, data) in calls {
+            request = request.merge(evm_call(chain_id, to, value, &data)?)?;
+        }
+        if user_op.has_paymaster() {
+            return Ok(request);
+        }
+        let gas = user_op
+            .verification_gas_limit()
+            .saturating_add(user_op.call_gas_limit())
+            .saturating_add(user_op.pre_verification_gas);
+        Ok(request.with_fee(gas.saturating_mul(user_op.max_fee_per_gas())))
+    }
+
+    /// Describes the call an ERC-2771 forward request makes for its signer.
+    ///
+    /// The relayer pays the gas, so there is no fee.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] if `domain` has no chain ID, and for amounts
+    /// above `u128::MAX`.
+    pub fn from_forward_request(request: &ForwardRequest, domain: &Eip712Domain) -> Result<Self> {
+        let chain_id = domain.chain_id.ok_or_else(|| {
+            Error::Unsupported(
+                "forward requests without a chain ID are not covered by the policy".to_string(),
+            )
+        })?;
+        evm_call(chain_id, request.to, request.value.into(), &request.data)
+    }
+
+    /// Describes EIP-712 typed data of a type known at compile time.
+    ///
+    /// An EIP-2612 `Permit` is an approval of the domain's verifying contract to
+    /// its spender; anything else is a message. The network is the domain's
+    /// chain, or `chain_id` when the domain names none.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] for allowances above `u128::MAX`.
+    pub fn from_eip712<T: Eip712Type>(
+        domain: &Eip712Domain,
+        message: &T,
+        chain_id: u64,
+    ) -> Result<Self> {
+        let network = Network::Evm(domain.chain_id.unwrap_or(chain_id));
+        let type_string = T::type_string();
+        if type_string == PERMIT_TYPE {
+            if let (Some(token), Some((spender, amount))) =
+                (domain.verifying_contract, permit(&message.encode_data()))
+            {
+                return Ok(Self::new(Asset::token(network, token))
+                    .with_transfer(spender, to_u128(amount)?));
+            }
+        }
+        let primary_type = type_string.split('(').next().unwrap_or(type_string);
+        Ok(Self::for_message(
+            network,
+            format!("{} typed data", primary_type),
+        ))
+    }
+
+    /// Describes EIP-712 typed data in the JSON format of `eth_signTypedData_v4`.
+    ///
+    /// EIP-2612 and DAI-style `Permit`s are approvals of the domain's verifying
+    /// contract to their spender, a DAI permit of `allowed: true` counting as
+    /// `u128::MAX`; anything else is a message. The network is the domain's
+    /// chain, or `chain_id` when the domain names none.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::EvmError`] for an invalid domain chain ID and
+    /// [`Error::Unsupported`] for allowances above `u128::MAX`.
+    pub fn from_typed_data(typed_data: &TypedData, chain_id: u64) -> Result<Self> {
+        let network = Network::Evm(typed_data.chain_id()?.unwrap_or(chain_id));
+        if let Some((token, spender, amount)) = typed_permit(typed_data) {
+            return Ok(
+                Self::new(Asset::token(network, token)).with_transfer(spender, to_u128(amount)?)
+            );
+        }
+        Ok(Self::for_message(
+            network,
+            format!("{} typed data", typed_data.primary_type),
+        ))
+    }
+
+    /// Describes a `personal_sign` message for the dapp connected on `chain_id`.
+    ///
+    /// The message is shown as text when it is UTF-8, as hex otherwise.
+    pub fn from_message(message: &[u8], chain_id: u64) -> Self {
+        let text = match std::str::from_utf8(message) {
+            Ok(text) => format!("message {:?}", text),
+            Err(_) => format!("message 0x{}", hex(message)),
+        };
+        Self::for_message(Network::Evm(chain_id), text)
+    }
+
+    /// Describes a PSBT on `network`, leaving out outputs the `own` signers can
+    /// spend and outputs of zero value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an input's previous output cannot be read or the
+    /// outputs exceed the inputs.
+    pub fn from_psbt(psbt: &Psbt, network: BtcNetwork, own: &[&BtcSigner]) -> Result<Self> {
+        let preview = psbt.preview(network, own)?;
+        let transfers = preview
+            .outputs
+            .iter()
+            .filter(|output| !output.is_change && output.amount > 0)
+            .map(|output| Transfer {
+                destination: btc_destination(output.address.as_ref(), &output.script_pubkey),
+                amount: u128::from(output.amount),
+            })
+            .collect();
+        Ok(Self {
+            transfers,
+            fee: u128::from(psbt.fee()?),
+            ..Self::new(Asset::native(network.into()))
+        })
+    }
+
+    /// Describes a PSBT for a hardware wallet `account`, leaving out outputs of
+    /// zero value and change outputs.
+    ///
+    /// An output is change when its BIP-32 derivation names the account's
+    /// fingerprint and a path below the account, and the address derived from the
+    /// account's extended public key at that path is the one paid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an input's previous output cannot be read or the
+    /// outputs exceed the inputs.
+    pub fn from_device_psbt(psbt: &Psbt, account: &BtcAccount) -> Result<Self> {
+        let network = account.network();
+        let transfers = psbt
+            .outputs()
+            .iter()
+            .filter(|output| output.amount > 0)
+            .filter_map(|output| {
+                let address = BtcAddress::from_script(&output.script_pubkey, network);
+                let is_change = output.bip32_derivation.values().any(|source| {
+                    account_child(account, &source.fingerprint, &source.path)
+                        .and_then(|(chain, index)| account.address(chain, index).ok())
+                        .is_some_and(|own| Some(&own) == address.as_ref())
+                });
+                (!is_change).then(|| Transfer {
+                    destination: btc_destination(address.as_ref(), &output.script_pubkey),
+                    amount: u128::from(output.amount),
+                })
+            })
+            .collect();
+        Ok(Self {
+            transfers,
+            fee: u128::from(psbt.fee()?),
+            ..Self::new(Asset::native(network.into()))
+        })
+    }
+
+    /// Describes a Tron transaction, with its fee limit as fee.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] for token amounts above `u128::MAX`.
+    pub fn from_tron_transaction(tx: &TronTransaction) -> Result<Self> {
+        let fee = u128::from(tx.fee_limit());
+        let request = match tx.contract() {
+            Contract::Transfer { to, amount } => {
+                Self::new(Asset::native(Network::Tron)).with_transfer(to, u128::from(*amount))
+            }
+            Contract::TriggerSmartContract {
+                contract,
+                call_value,
+                data,
+            } => match token_spend(data) {
+                Some(payouts) => Self::new(Asset::token(Network::Tron, contract))
+                    .with_payouts(payouts, |to| {
+                        TronAddress::from_bytes(to.to_bytes()).to_string()
+                    })?
+                    .with_call_value(contract, u128::from(*call_value)),
+                None => Self::new(Asset::native(Network::Tron))
+                    .with_transfer(contract, u128::from(*call_value)),
+            },
+        };
+        Ok(request.with_fee(fee))
+    }
+
+    /// Describes an XRP payment, with its fee.
+    pub fn from_xrp_payment(payment: &Payment) -> Self {
+        Self::new(Asset::native(Network::Xrp))
+            .with_transfer(payment.destination(), u128::from(payment.amount()))
+            .with_fee(u128::from(payment.fee()))
+    }
+}
+
+/// Describes a call to `to` with `value` native coin on EVM chain `chain_id`.
+///
+/// Disperse payouts go to their recipients, not to the Disperse contract.
+fn evm_call(chain_id: u64, to: Address, value: U256, data: &[u8]) -> Result<SpendRequest> {
+    let network = Network::Evm(chain_id);
+    let destination = |to: Address| to.to_string();
+    match disperse_spend(&to, data) {
+        Some((None, payouts)) => {
+            // Disperse refunds whatever value the payouts leave over
+            return SpendRequest::new(Asset::native(network)).with_payouts(payouts, destination);
+        }
+        Some((Some(token), payouts)) => {
+            return Ok(SpendRequest::new(Asset::token(network, token))
+                .with_payouts(payouts, destination)?
+                .with_call_value(to, to_u128(value)?));
+        }
+        None => {}
+    }
+    match token_spend(data) {
+        Some(payouts) => Ok(SpendRequest::new(Asset::token(network, to))
+            .with_payouts(payouts, destination)?
+            .with_call_value(to, to_u128(value)?)),
+        None => Ok(SpendRequest::new(Asset::native(network)).with_transfer(to, to_u128(value)?)),
+    }
+}
+
+/// Returns the token, spender and allowance of an EIP-2612 or DAI `Permit`.
+fn typed_permit(typed_data: &TypedData) -> Option<(Address, Address, U256)> {
+    if typed_data.primary_type != "Permit" {
+        return None;
+    }
+    let address = |value: Option<&Value>| value?.as_str()?.parse::<Address>().ok();
+    let token = address(typed_data.domain.get("verifyingContract"))?;
+    let message = &typed_data.message;
+    let spender = address(message.get("spender"))?;
+    let amount = match (message.get("value"), message.get("allowed")) {
+        (Some(value), None) => json_uint(value)?,
+        (None, Some(&Value::Bool(allowed))) => U256::from(if allowed { u128::MAX } else { 0 }),
+        _ => return None,
+    };
+    Some((token, spender, amount))
+}
+
+/// Reads a JSON number, or a decimal or `0x` hex string, as an unsigned integer.
+fn json_uint(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(text) => match text.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(text).ok(),
+        },
+        _ => None,
+    }
+}
+
+fn deployment() -> Error {
+    Error::Unsupported("contract deployments are not covered by the policy".to_string())
+}
+
+fn sum(transfers: &[Transfer]) -> u128 {
+    transfers.iter().fold(0u128, |total, transfer| {
+        total.saturating_add(transfer.amount)
+    })
+}
+
+/// Lower-case hex without a prefix.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Normalizes an address for comparison: hex addresses and upper-case bech32
+/// addresses are lower-cased, base58 addresses are left alone.
+pub(crate) fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+    let is_hex = address.starts_with("0x") || address.starts_with("0X");
+    if is_hex || address == address.to_ascii_uppercase() {
+        address.to_ascii_lowercase()
+    } else {
+        address.to_string()
+    }
+}
+
+fn to_u128(amount: U256) -> Result<u128> {
+    if amount > U256::from(u128::MAX) {
+        return Err(Error::Unsupported(format!(
+            "amount {} does not fit in 128 bits",
+            amount
+        )));
+    }
+    Ok(amount.low_u128())
+}
+
+fn btc_destination(
+    address: Option<&BtcAddress>,
+    script_pubkey: &khodpay_btc_signing::Script,
+) -> String {
+    match address {
+        Some(address) => address.to_string(),
+        None => format!("script:{}", script_pubkey.to_hex()),
+    }
+}
+
+/// Returns the chain and index of `path` if it is a child of `account`.
+fn account_child(
+    account: &BtcAccount,
+    fingerprint: &[u8; 4],
+    path: &[u32],
+) -> Option<(Chain, u32)> {
+    let prefix: Vec<u32> = account
+        .path()
+        .iter()
+        .map(|child| child.to_index())
+        .collect();
+    match path.strip_prefix(prefix.as_slice())? {
+        [chain, index] if *fingerprint == account.fingerprint() => {
+            Some((Chain::try_from(*chain).ok()?, *index))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_bip32::{DerivationPath, ExtendedPublicKey};
+    use khodpay_btc_signing::psbt::{PsbtInput, PsbtOutput};
+    use khodpay_btc_signing::{OutPoint, Script, ScriptType, TxOut, Txid};
+    use khodpay_signing::abi::{Function, Token};
+    use khodpay_signing::disperse::{self, DISPERSE};
+    use khodpay_signing::erc4337::UserOperation;
+    use khodpay_signing::{eip2771, eip712, erc20, erc721, ChainId, Wei};
+    use std::str::FromStr;
+
+    fn evm_tx(to: Address, value: Wei, data: Vec<u8>) -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(100_000)
+            .to(to)
+            .value(value)
+            .data(data)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(normalize_address("0xAbC1"), "0xabc1");
+        assert_eq!(normalize_address(" BC1QXYZ "), "bc1qxyz");
+        assert_eq!(normalize_address("bc1qxyz"), "bc1qxyz");
+        assert_eq!(
+            normalize_address("TJRabPrwbZy45sbavfcjinPJC18kjpRTv8"),
+            "TJRabPrwbZy45sbavfcjinPJC18kjpRTv8"
+        );
+    }
+
+    #[test]
+    fn test_evm_native_and_token() {
+        let to = Address::from_bytes([0x11; 20]);
+        let request =
+            SpendRequest::from_evm_transaction(&evm_tx(to, Wei::from_ether(2), vec![])).unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Evm(56)));
+        assert_eq!(request.transfers[0].destination, to.to_string());
+        assert_eq!(request.total(), 2_000_000_000_000_000_000);
+
+        let token = Address::from_bytes([0xAA; 20]);
+        let recipient = Address::from_bytes([0x22; 20]);
+        for data in [
+            erc20::encode_transfer(&recipient, U256::from(500u64)),
+            erc20::encode_approve(&recipient, U256::from(500u64)),
+        ] {
+            let request =
+                SpendRequest::from_evm_transaction(&evm_tx(token, Wei::ZERO, data)).unwrap();
+            assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+            assert_eq!(request.asset.token, Some(format!("0x{}", "aa".repeat(20))));
+            assert_eq!(
+                normalize_address(&request.transfers[0].destination),
+                normalize_address(&recipient.to_string())
+            );
+            assert_eq!(request.total(), 500);
+        }
+
+        // Unknown calldata: the native value goes to the contract
+        let request = SpendRequest::from_evm_transaction(&evm_tx(
+            token,
+            Wei::from_wei(7u64),
+            vec![1, 2, 3, 4],
+        ))
+        .unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Evm(56)));
+        assert_eq!(request.total(), 7);
+    }
+
+    #[test]
+    fn test_token_call_with_value() {
+        let token = Address::from_bytes([0xAA; 20]);
+        let recipient = Address::from_bytes([0x22; 20]);
+        let data = erc20::encode_transfer(&recipient, U256::from(500u64));
+        let request =
+            SpendRequest::from_evm_transaction(&evm_tx(token, Wei::from_wei(7u64), data.clone()))
+                .unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+        assert_eq!(request.total(), 500);
+        let call_value = &request.call_values[0];
+        assert_eq!(call_value.destination, token.to_string());
+        assert_eq!(call_value.amount, 7);
+        assert_eq!(
+            request.spends(false),
+            vec![
+                (Asset::token(Network::Evm(56), token), 500),
+                (Asset::native(Network::Evm(56)), 7),
+            ]
+        );
+
+        // 100,000 gas at 5 gwei
+        assert_eq!(request.fee, 500_000_000_000_000);
+        assert_eq!(
+            request.spends(true)[1],
+            (Asset::native(Network::Evm(56)), 500_000_000_000_007)
+        );
+
+        // Without a value or a counted fee there is no native spend
+        let request = SpendRequest::from_evm_transaction(&evm_tx(token, Wei::ZERO, data)).unwrap();
+        assert!(request.call_values.is_empty());
+        assert_eq!(request.spends(false).len(), 1);
+        assert_eq!(request.spends(true).len(), 2);
+    }
+
+    #[test]
+    fn test_nft_and_disperse() {
+        let owner = Address::from_bytes([0x01; 20]);
+        let recipient = Address::from_bytes([0x22; 20]);
+        let collection = Address::from_bytes([0xCC; 20]);
+        let data = erc721::encode_safe_transfer_from(&owner, &recipient, U256::from(42u64));
+        let request =
+            SpendRequest::from_evm_transaction(&evm_tx(collection, Wei::ZERO, data)).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), collection));
+        assert_eq!(request.transfers[0].destination, recipient.to_string());
+        assert_eq!(request.total(), 1);
+
+        let data = erc721::encode_set_approval_for_all(&recipient, true);
+        let request =
+            SpendRequest::from_evm_transaction(&evm_tx(collection, Wei::ZERO, data)).unwrap();
+        assert_eq!(request.transfers[0].destination, recipient.to_string());
+        assert_eq!(request.total(), u128::MAX);
+
+        let payouts = [
+            (Address::from_bytes([0x11; 20]), U256::from(300u64)),
+            (recipient, U256::from(700u64)),
+        ];
+        let data = disperse::encode_disperse_ether(&payouts);
+        let request =
+            SpendRequest::from_evm_transaction(&evm_tx(DISPERSE, Wei::from_wei(1_000u64), data))
+                .unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Evm(56)));
+        assert_eq!(request.transfers.len(), 2);
+        assert_eq!(request.transfers[1].destination, recipient.to_string());
+        assert_eq!(request.total(), 1_000);
+
+        let token = Address::from_bytes([0xAA; 20]);
+        let data = disperse::encode_disperse_token(&token, &payouts);
+        let request =
+            SpendRequest::from_evm_transaction(&evm_tx(DISPERSE, Wei::ZERO, data)).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+        assert_eq!(request.total(), 1_000);
+        assert!(request.call_values.is_empty());
+    }
+
+    #[test]
+    fn test_eip2930_and_forward_request() {
+        let token = Address::from_bytes([0xAA; 20]);
+        let recipient = Address::from_bytes([0x22; 20]);
+        let data = erc20::encode_transfer(&recipient, U256::from(500u64));
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(3))
+            .gas_limit(60_000)
+            .to(token)
+            .data(data.clone())
+            .build()
+            .unwrap();
+        let request = SpendRequest::from_eip2930_transaction(&tx).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+        assert_eq!(request.total(), 500);
+        assert_eq!(request.fee, 180_000_000_000_000);
+
+        let forward = ForwardRequest::builder()
+            .from(Address::from_bytes([0x01; 20]))
+            .to(token)
+            .gas(60_000)
+            .nonce(0)
+            .deadline(1_700_000_000)
+            .data(data)
+            .build()
+            .unwrap();
+        let domain = eip2771::forwarder_domain("Forwarder", 56, Address::from_bytes([0xF0; 20]));
+        let request = SpendRequest::from_forward_request(&forward, &domain).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+        assert_eq!(request.total(), 500);
+        assert_eq!(request.fee, 0);
+
+        let mut domain = domain;
+        domain.chain_id = None;
+        assert!(matches!(
+            SpendRequest::from_forward_request(&forward, &domain),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_safe_transaction() {
+        let recipient = Address::from_bytes([0x22; 20]);
+        let builder = || {
+            SafeTransaction::builder()
+                .to(recipient)
+                .value(Wei::from_wei(1_000u64))
+                .nonce(3)
+        };
+        let request = SpendRequest::from_safe_transaction(&builder().build().unwrap(), 56).unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Evm(56)));
+        assert_eq!(request.total(), 1_000);
+        assert_eq!(request.fee, 0);
+
+        // A refund in the native coin is the fee
+        let tx = builder()
+            .safe_tx_gas(50_000)
+            .base_gas(10_000)
+            .gas_price(Wei::from_wei(2u64))
+            .build()
+            .unwrap();
+        assert_eq!(
+            SpendRequest::from_safe_transaction(&tx, 56).unwrap().fee,
+            120_000
+        );
+
+        // One in a token pays the refund receiver, with the native value alongside
+        let usdt = Address::from_bytes([0xAA; 20]);
+        let relayer = Address::from_bytes([0x33; 20]);
+        let tx = builder()
+            .safe_tx_gas(50_000)
+            .gas_price(Wei::from_wei(2u64))
+            .gas_token(usdt)
+            .refund_receiver(relayer)
+            .build()
+            .unwrap();
+        let request = SpendRequest::from_safe_transaction(&tx, 56).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), usdt));
+        assert_eq!(request.transfers[0].destination, relayer.to_string());
+        assert_eq!(request.total(), 100_000);
+        assert_eq!(request.call_values[0].destination, recipient.to_string());
+        assert_eq!(request.call_values[0].amount, 1_000);
+
+        for tx in [
+            builder()
+                .operation(Operation::DelegateCall)
+                .build()
+                .unwrap(),
+            builder().gas_price(Wei::from_wei(2u64)).build().unwrap(),
+        ] {
+            assert!(matches!(
+                SpendRequest::from_safe_transaction(&tx, 56),
+                Err(Error::Unsupported(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_user_operation() {
+        let token = Address::from_bytes([0xAA; 20]);
+        let router = Address::from_bytes([0x44; 20]);
+        let user_op = |calls: &[(Address, u64, Vec<u8>)], paymaster: bool| {
+            let function = Function::parse("executeBatch(address[],uint256[],bytes[])").unwrap();
+            let column = |token: fn(&(Address, u64, Vec<u8>)) -> Token| {
+                Token::Array(calls.iter().map(token).collect())
+            };
+            let call_data = function
+                .encode_input(&[
+                    column(|call| Token::Address(call.0)),
+                    column(|call| Token::Uint(U256::from(call.1))),
+                    column(|call| Token::Bytes(call.2.clone())),
+                ])
+                .unwrap();
+            let mut builder = UserOperation::builder()
+                .sender(Address::from_bytes([0x01; 20]))
+                .nonce(0)
+                .call_data(call_data)
+                .call_gas_limit(300_000)
+                .verification_gas_limit(150_000)
+                .pre_verification_gas(50_000)
+                .max_fee_per_gas(5)
+                .max_priority_fee_per_gas(1);
+            if paymaster {
+                builder =
+                    builder.paymaster(Address::from_bytes([0x55; 20]), 60_000, 40_000, vec![]);
+            }
+            builder.build().unwrap().pack()
+        };
+
+        // Approve the router, then swap with some native coin
+        let approve = erc20::encode_approve(&router, U256::from(500u64));
+        let calls = [
+            (token, 0, approve),
+            (router, 7, vec![0xde, 0xad, 0xbe, 0xef]),
+        ];
+        let request = SpendRequest::from_user_operation(&user_op(&calls, false), 56).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+        assert_eq!(request.transfers[0].destination, router.to_string());
+        assert_eq!(request.total(), 500);
+        assert_eq!(request.call_values[0].destination, router.to_string());
+        assert_eq!(request.call_values[0].amount, 7);
+        assert_eq!(request.fee, 2_500_000);
+
+        // Sponsored: no fee
+        let request = SpendRequest::from_user_operation(&user_op(&calls, true), 56).unwrap();
+        assert_eq!(request.fee, 0);
+
+        // Two tokens in one batch
+        let other = erc20::encode_transfer(&router, U256::one());
+        let calls = [
+            (token, 0, other.clone()),
+            (Address::from_bytes([0xBB; 20]), 0, other),
+        ];
+        assert!(matches!(
+            SpendRequest::from_user_operation(&user_op(&calls, false), 56),
+            Err(Error::Unsupported(_))
+        ));
+
+        // Calldata of an unknown account
+        let mut op = user_op(&[], false);
+        op.call_data = vec![1, 2, 3, 4];
+        assert!(matches!(
+            SpendRequest::from_user_operation(&op, 56),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_typed_data_and_messages() {
+        let permit = |message: &str| {
+            TypedData::from_json(&format!(
+                r#"{{
+                    "types": {{}},
+                    "primaryType": "Permit",
+                    "domain": {{"chainId": 56, "verifyingContract": "0xAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"}},
+                    "message": {}
+                }}"#,
+                message
+            ))
+            .unwrap()
+        };
+        let spender = format!("0x{}", "22".repeat(20));
+        let token = Asset::token(Network::Evm(56), format!("0x{}", "aa".repeat(20)));
+
+        let erc2612 = permit(&format!(
+            r#"{{"owner": "0x{}", "spender": "{}", "value": "1000", "nonce": 0, "deadline": 1}}"#,
+            "11".repeat(20),
+            spender
+        ));
+        let request = SpendRequest::from_typed_data(&erc2612, 1).unwrap();
+        assert_eq!(request.asset, token);
+        assert_eq!(request.transfers[0].destination, spender);
+        assert_eq!(request.total(), 1_000);
+        assert_eq!(request.message, None);
+
+        let dai = permit(&format!(
+            r#"{{"holder": "0x{}", "spender": "{}", "nonce": 0, "expiry": 1, "allowed": true}}"#,
+            "11".repeat(20),
+            spender
+        ));
+        assert_eq!(
+            SpendRequest::from_typed_data(&dai, 1).unwrap().total(),
+            u128::MAX
+        );
+
+        let mut greeting = permit("{}");
+        greeting.primary_type = "Greeting".to_string();
+        greeting.domain.remove("chainId");
+        let request = SpendRequest::from_typed_data(&greeting, 137).unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Evm(137)));
+        assert_eq!(request.message.as_deref(), Some("Greeting typed data"));
+        assert_eq!(
+            request.spends(true),
+            vec![(Asset::native(Network::Evm(137)), 0)]
+        );
+
+        let request = SpendRequest::from_message(b"gm", 56);
+        assert_eq!(request.message.as_deref(), Some("message \"gm\""));
+        let request = SpendRequest::from_message(&[0xff, 0x00], 56);
+        assert_eq!(request.message.as_deref(), Some("message 0xff00"));
+    }
+
+    #[test]
+    fn test_eip712_permit() {
+        struct Permit {
+            spender: Address,
+            value: u128,
+        }
+
+        impl Eip712Type for Permit {
+            fn type_string() -> &'static str {
+                PERMIT_TYPE
+            }
+
+            fn encode_data(&self) -> Vec<u8> {
+                let mut data = eip712::encode_address(&Address::ZERO).to_vec();
+                data.extend(eip712::encode_address(&self.spender));
+                data.extend(eip712::encode_uint256(self.value));
+                data.extend([0u8; 64]);
+                data
+            }
+        }
+
+        let token = Address::from_bytes([0xAA; 20]);
+        let spender = Address::from_bytes([0x22; 20]);
+        let domain = Eip712Domain::new("Token", "1", 56, token);
+        let permit = Permit {
+            spender,
+            value: 1_000,
+        };
+        let request = SpendRequest::from_eip712(&domain, &permit, 1).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Evm(56), token));
+        assert_eq!(request.transfers[0].destination, spender.to_string());
+        assert_eq!(request.total(), 1_000);
+
+        // Without a verifying contract there is no token to name
+        let domain = Eip712Domain::builder().name("Token").build();
+        let request = SpendRequest::from_eip712(&domain, &permit, 1).unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Evm(1)));
+        assert_eq!(request.message.as_deref(), Some("Permit typed data"));
+    }
+
+    #[test]
+    fn test_evm_unsupported() {
+        let mut tx = evm_tx(Address::ZERO, Wei::ZERO, vec![]);
+        tx.to = None;
+        assert!(matches!(
+            SpendRequest::from_evm_transaction(&tx),
+            Err(Error::Unsupported(_))
+        ));
+
+        let tx = evm_tx(
+            Address::from_bytes([0xAA; 20]),
+            Wei::ZERO,
+            erc20::encode_transfer(&Address::ZERO, U256::MAX),
+        );
+        assert!(matches!(
+            SpendRequest::from_evm_transaction(&tx),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_psbt_leaves_out_change() {
+        let signer = BtcSigner::from_private_key(&[1; 32], BtcNetwork::Bitcoin).unwrap();
+        let mine = signer.address().script_pubkey();
+        let mut psbt = Psbt::new(2);
+        let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([7; 32]), 0));
+        input.witness_utxo = Some(TxOut {
+            value: 20_000,
+            script_pubkey: mine.clone(),
+        });
+        psbt.add_input(input).unwrap();
+        psbt.add_output(PsbtOutput::new(12_000, Script::p2wpkh(&[9; 20])))
+            .unwrap();
+        psbt.add_output(PsbtOutput::new(7_000, mine)).unwrap();
+
+        let request = SpendRequest::from_psbt(&psbt, BtcNetwork::Bitcoin, &[&signer]).unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Bitcoin));
+        assert_eq!(request.transfers.len(), 1);
+        assert_eq!(request.total(), 12_000);
+        assert_eq!(request.fee, 1_000);
+        assert!(request.transfers[0].destination.starts_with("bc1q"));
+
+        // Without knowing the signer, the change is a spend too
+        let request = SpendRequest::from_psbt(&psbt, BtcNetwork::Bitcoin, &[]).unwrap();
+        assert_eq!(request.total(), 19_000);
+    }
+
+    #[test]
+    fn test_device_psbt_leaves_out_change() {
+        // BIP-32 test vector 1, chain m, standing in for an account xpub
+        let xpub = ExtendedPublicKey::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        let account = BtcAccount::new(
+            ScriptType::P2wpkh,
+            BtcNetwork::Bitcoin,
+            [1, 2, 3, 4],
+            DerivationPath::from_str("m/84'/0'/0'").unwrap(),
+            xpub,
+        );
+        let change = account.address(Chain::Internal, 5).unwrap();
+
+        let mut psbt = Psbt::new(2);
+        let mut input = PsbtInput::new(OutPoint::new(Txid::from_bytes([7; 32]), 0));
+        input.witness_utxo = Some(TxOut {
+            value: 30_000,
+            script_pubkey: change.script_pubkey(),
+        });
+        psbt.add_input(input).unwrap();
+        psbt.add_output(PsbtOutput::new(12_000, Script::p2wpkh(&[9; 20])))
+            .unwrap();
+        let mut output = PsbtOutput::new(7_000, change.script_pubkey());
+        output.bip32_derivation.insert(
+            account.public_key(Chain::Internal, 5).unwrap().to_vec(),
+            account.key_source(Chain::Internal, 5),
+        );
+        psbt.add_output(output.clone()).unwrap();
+
+        // Claims to be change at index 6, but pays the index 5 address
+        output.bip32_derivation.clear();
+        output
+            .bip32_derivation
+            .insert(vec![2; 33], account.key_source(Chain::Internal, 6));
+        psbt.add_output(output).unwrap();
+        psbt.add_output(PsbtOutput::new(0, Script::p2wpkh(&[8; 20])))
+            .unwrap();
+
+        let request = SpendRequest::from_device_psbt(&psbt, &account).unwrap();
+        assert_eq!(request.transfers.len(), 2);
+        assert_eq!(request.total(), 19_000);
+        assert_eq!(request.transfers[1].destination, change.to_string());
+        assert_eq!(request.fee, 4_000);
+    }
+
+    #[test]
+    fn test_tron_and_xrp() {
+        let owner = TronAddress::from_bytes([0x01; 20]);
+        let to = TronAddress::from_bytes([0x02; 20]);
+        let token = TronAddress::from_bytes([0x03; 20]);
+        let builder = || {
+            TronTransaction::builder()
+                .owner(owner)
+                .reference_block(1, &[0; 32])
+                .timestamp(1_700_000_000_000)
+                .fee_limit(10_000_000)
+        };
+
+        let tx = builder().transfer(to, 1_500_000).build().unwrap();
+        let request = SpendRequest::from_tron_transaction(&tx).unwrap();
+        assert_eq!(request.asset, Asset::native(Network::Tron));
+        assert_eq!(request.transfers[0].destination, to.to_string());
+        assert_eq!(request.total(), 1_500_000);
+        assert_eq!(request.fee, 10_000_000);
+
+        let tx = builder()
+            .trc20_transfer(token, to, U256::from(25u64))
+            .build()
+            .unwrap();
+        let request = SpendRequest::from_tron_transaction(&tx).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Tron, token));
+        assert_eq!(request.transfers[0].destination, to.to_string());
+        assert_eq!(request.total(), 25);
+        assert!(request.call_values.is_empty());
+
+        // TRX sent along with a token call goes to the contract
+        let tx = builder()
+            .contract(Contract::TriggerSmartContract {
+                contract: token,
+                call_value: 2_000_000,
+                data: erc20::encode_transfer(&to.to_evm(), U256::from(25u64)),
+            })
+            .build()
+            .unwrap();
+        let request = SpendRequest::from_tron_transaction(&tx).unwrap();
+        assert_eq!(request.asset, Asset::token(Network::Tron, token));
+        assert_eq!(request.total(), 25);
+        let call_value = &request.call_values[0];
+        assert_eq!(call_value.destination, token.to_string());
+        assert_eq!(call_value.amount, 2_000_000);
+
+        let payment = Payment::builder()
+            .account(khodpay_address::XrpAddress::from_bytes([1; 20]))
+            .destination(khodpay_address::XrpAddress::from_bytes([2; 20]))
+            .amount(3_000_000)
+            .fee(12)
+            .sequence(1)
+            .build()
+            .unwrap();
+        let request = SpendRequest::from_xrp_payment(&payment);
+        assert_eq!(request.asset, Asset::native(Network::Xrp));
+        assert_eq!(request.total(), 3_000_000);
+        assert_eq!(request.fee, 12);
+    }
+
+    #[test]
+    fn test_network() {
+        assert_eq!(Network::Evm(56).coin_type(), CoinType::Ethereum);
+        assert_eq!(Network::from(BtcNetwork::Signet), Network::BitcoinTestnet);
+        assert_eq!(
+            serde_json::to_string(&[Network::Evm(56), Network::Bitcoin]).unwrap(),
+            r#"[{"evm":56},"bitcoin"]"#
+        );
+        assert_eq!(
+            Asset::token(Network::Evm(1), "0xDAC1").to_string(),
+            "token 0xdac1 on EVM chain 1"
+        );
+        assert_eq!(
+            Asset::native(Network::Xrp).to_string(),
+            "XRP Ledger native coin"
+        );
+    }
+}
@@ -0,0 +1,1225 @@
+//! Solidity ABI encoding and decoding.
+//!
+//! Implements the [contract ABI specification](https://docs.soliditylang.org/en/latest/abi-spec.html)
+//! for the standard Solidity types, so arbitrary contract calls can be built and their
+//! results and event logs interpreted without an external ABI library.
+//!
+//! - [`ParamType`] describes a Solidity type and parses from its canonical name
+//!   (`"uint256"`, `"address[]"`, `"(bytes32,uint8)[2]"`).
+//! - [`Token`] is a typed value. [`encode`] and [`decode`] convert between tokens and
+//!   ABI bytes.
+//! - [`Function`] parses a human-readable signature, computes its selector and
+//!   encodes / decodes calldata and return data.
+//! - [`Event`] parses an event signature and decodes logs from topics and data.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::abi::{Function, Token};
+//! use khodpay_signing::{Address, U256};
+//!
+//! let transfer = Function::parse("transfer(address to, uint256 amount) returns (bool)").unwrap();
+//! assert_eq!(transfer.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+//!
+//! let to: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//! let calldata = transfer
+//!     .encode_input(&[Token::Address(to), Token::Uint(U256::from(1000))])
+//!     .unwrap();
+//!
+//! let args = transfer.decode_input(&calldata).unwrap();
+//! assert_eq!(args[0], Token::Address(to));
+//! ```
+
+use crate::eip712::keccak256;
+use crate::{Address, Error, Result};
+use primitive_types::U256;
+use std::fmt;
+use std::str::FromStr;
+
+/// Size of one ABI word in bytes.
+const WORD: usize = 32;
+
+// ─── Types ───────────────────────────────────────────────────────────────────
+
+/// A Solidity ABI type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParamType {
+    /// `address`
+    Address,
+    /// `uintN` with the bit size `N` (8..=256, multiple of 8).
+    Uint(usize),
+    /// `intN` with the bit size `N` (8..=256, multiple of 8).
+    Int(usize),
+    /// `bool`
+    Bool,
+    /// `bytesN` with the byte size `N` (1..=32).
+    FixedBytes(usize),
+    /// `bytes`
+    Bytes,
+    /// `string`
+    String,
+    /// `T[]`
+    Array(Box<ParamType>),
+    /// `T[k]`
+    FixedArray(Box<ParamType>, usize),
+    /// `(T1,T2,…)`
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    /// Returns `true` if the type is dynamically sized and encoded out-of-line.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Self::Bytes | Self::String | Self::Array(_) => true,
+            Self::FixedArray(inner, _) => inner.is_dynamic(),
+            Self::Tuple(items) => items.iter().any(ParamType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Returns the number of bytes this type occupies in the head of an encoding.
+    fn head_size(&self) -> usize {
+        if self.is_dynamic() {
+            return WORD;
+        }
+        match self {
+            Self::FixedArray(inner, len) => inner.head_size().saturating_mul(*len),
+            Self::Tuple(items) => items.iter().map(ParamType::head_size).sum(),
+            _ => WORD,
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address => write!(f, "address"),
+            Self::Uint(bits) => write!(f, "uint{}", bits),
+            Self::Int(bits) => write!(f, "int{}", bits),
+            Self::Bool => write!(f, "bool"),
+            Self::FixedBytes(len) => write!(f, "bytes{}", len),
+            Self::Bytes => write!(f, "bytes"),
+            Self::String => write!(f, "string"),
+            Self::Array(inner) => write!(f, "{}[]", inner),
+            Self::FixedArray(inner, len) => write!(f, "{}[{}]", inner, len),
+            Self::Tuple(items) => write!(f, "({})", join_types(items)),
+        }
+    }
+}
+
+impl FromStr for ParamType {
+    type Err = Error;
+
+    /// Parses a Solidity type name. `uint` and `int` are aliases for `uint256` and
+    /// `int256`; tuple components may carry parameter names, which are ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (base, mut rest) = if s.starts_with('(') {
+            let close = matching_paren(s)?;
+            let items = parse_params(&s[1..close])?
+                .into_iter()
+                .map(|p| p.kind)
+                .collect();
+            (ParamType::Tuple(items), &s[close + 1..])
+        } else {
+            let end = s.find('[').unwrap_or(s.len());
+            (parse_elementary(&s[..end])?, &s[end..])
+        };
+
+        let mut kind = base;
+        while !rest.is_empty() {
+            let close = rest
+                .find(']')
+                .filter(|_| rest.starts_with('['))
+                .ok_or_else(|| Error::AbiError(format!("invalid array suffix in '{}'", s)))?;
+            let size = &rest[1..close];
+            kind = if size.is_empty() {
+                ParamType::Array(Box::new(kind))
+            } else {
+                let len =
+                    size.parse().ok().filter(|&len| len > 0).ok_or_else(|| {
+                        Error::AbiError(format!("invalid array length '{}'", size))
+                    })?;
+                ParamType::FixedArray(Box::new(kind), len)
+            };
+            rest = &rest[close + 1..];
+        }
+
+        Ok(kind)
+    }
+}
+
+// ─── Values ──────────────────────────────────────────────────────────────────
+
+/// A typed ABI value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Token {
+    /// `address` value.
+    Address(Address),
+    /// `uintN` value.
+    Uint(U256),
+    /// `intN` value as a 256-bit two's complement word. See [`Token::int`].
+    Int(U256),
+    /// `bool` value.
+    Bool(bool),
+    /// `bytesN` value.
+    FixedBytes(Vec<u8>),
+    /// `bytes` value.
+    Bytes(Vec<u8>),
+    /// `string` value.
+    String(String),
+    /// `T[]` value.
+    Array(Vec<Token>),
+    /// `T[k]` value.
+    FixedArray(Vec<Token>),
+    /// `(T1,T2,…)` value.
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    /// Creates an [`Token::Int`] from a signed integer.
+    pub fn int(value: i128) -> Self {
+        let magnitude = U256::from(value.unsigned_abs());
+        Self::Int(if value < 0 {
+            twos_complement(magnitude)
+        } else {
+            magnitude
+        })
+    }
+
+    /// Returns the value as a signed integer if this is an `Int` that fits in `i128`.
+    pub fn as_i128(&self) -> Option<i128> {
+        let Self::Int(word) = self else {
+            return None;
+        };
+        if word.bit(255) {
+            let magnitude = twos_complement(*word);
+            (magnitude <= U256::from(i128::MAX as u128) + 1)
+                .then(|| (magnitude.as_u128() as i128).wrapping_neg())
+        } else {
+            (*word <= U256::from(i128::MAX as u128)).then(|| word.as_u128() as i128)
+        }
+    }
+
+    /// Returns `true` if this token is a valid value of `kind`.
+    pub fn type_check(&self, kind: &ParamType) -> bool {
+        match (self, kind) {
+            (Self::Address(_), ParamType::Address) | (Self::Bool(_), ParamType::Bool) => true,
+            (Self::Uint(v), ParamType::Uint(bits)) => v.bits() <= *bits,
+            (Self::Int(v), ParamType::Int(bits)) => fits_signed(*v, *bits),
+            (Self::FixedBytes(b), ParamType::FixedBytes(len)) => b.len() == *len,
+            (Self::Bytes(_), ParamType::Bytes) | (Self::String(_), ParamType::String) => true,
+            (Self::Array(items), ParamType::Array(inner)) => {
+                items.iter().all(|t| t.type_check(inner))
+            }
+            (Self::FixedArray(items), ParamType::FixedArray(inner, len)) => {
+                items.len() == *len && items.iter().all(|t| t.type_check(inner))
+            }
+            (Self::Tuple(items), ParamType::Tuple(kinds)) => {
+                items.len() == kinds.len() && items.iter().zip(kinds).all(|(t, k)| t.type_check(k))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Self::Bytes(_) | Self::String(_) | Self::Array(_) => true,
+            Self::FixedArray(items) | Self::Tuple(items) => items.iter().any(Token::is_dynamic),
+            _ => false,
+        }
+    }
+}
+
+// ─── Encoding ────────────────────────────────────────────────────────────────
+
+/// ABI-encodes `tokens` as a tuple (the layout used for call arguments and return data).
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let heads_len: usize = tokens.iter().map(head_len).sum();
+    let mut head = Vec::with_capacity(heads_len);
+    let mut tail = Vec::new();
+
+    for token in tokens {
+        if token.is_dynamic() {
+            head.extend_from_slice(&uint_word(U256::from(heads_len + tail.len())));
+            tail.extend_from_slice(&encode_token(token));
+        } else {
+            head.extend_from_slice(&encode_token(token));
+        }
+    }
+
+    head.extend_from_slice(&tail);
+    head
+}
+
+/// ABI-encodes `tokens` as call arguments prefixed with a 4-byte function selector.
+///
+/// Unlike [`Function::encode_input`], this performs no type checking.
+pub fn encode_with_selector(selector: [u8; 4], tokens: &[Token]) -> Vec<u8> {
+    let mut data = selector.to_vec();
+    data.extend_from_slice(&encode(tokens));
+    data
+}
+
+/// Computes the 4-byte selector of a canonical function signature.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::abi::selector;
+///
+/// assert_eq!(selector("balanceOf(address)"), [0x70, 0xa0, 0x82, 0x31]);
+/// ```
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn head_len(token: &Token) -> usize {
+    match token {
+        _ if token.is_dynamic() => WORD,
+        Token::FixedArray(items) | Token::Tuple(items) => items.iter().map(head_len).sum(),
+        _ => WORD,
+    }
+}
+
+fn encode_token(token: &Token) -> Vec<u8> {
+    match token {
+        Token::Address(address) => {
+            let mut w = [0u8; WORD];
+            w[12..].copy_from_slice(address.as_bytes());
+            w.to_vec()
+        }
+        Token::Uint(v) | Token::Int(v) => uint_word(*v).to_vec(),
+        Token::Bool(b) => uint_word(U256::from(u8::from(*b))).to_vec(),
+        Token::FixedBytes(bytes) => pad_right(bytes),
+        Token::Bytes(bytes) => encode_bytes(bytes),
+        Token::String(s) => encode_bytes(s.as_bytes()),
+        Token::Array(items) => {
+            let mut out = uint_word(U256::from(items.len())).to_vec();
+            out.extend_from_slice(&encode(items));
+            out
+        }
+        Token::FixedArray(items) | Token::Tuple(items) => encode(items),
+    }
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = uint_word(U256::from(bytes.len())).to_vec();
+    out.extend_from_slice(&pad_right(bytes));
+    out
+}
+
+fn pad_right(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    out.resize(bytes.len().div_ceil(WORD) * WORD, 0);
+    out
+}
+
+fn uint_word(value: U256) -> [u8; WORD] {
+    let mut w = [0u8; WORD];
+    value.to_big_endian(&mut w);
+    w
+}
+
+// ─── Decoding ────────────────────────────────────────────────────────────────
+
+/// Decodes ABI bytes as a tuple of `types`.
+///
+/// Decoding is strict: values must be in range for their type and padding bytes must
+/// be zero. Trailing bytes after the encoded values are ignored.
+///
+/// Offsets may point anywhere in `data`, so many heads can alias one tail and a short
+/// input could otherwise expand into millions of tokens. Every word and byte string
+/// read is charged against a budget of twice `data.len()`, which canonical encodings
+/// never exceed.
+///
+/// # Errors
+///
+/// Returns an error if `data` is truncated, an offset or length is out of bounds, a
+/// value is not valid for its type, or decoding would read more than the budget allows.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    Decoder::new(data).tuple(types, 0)
+}
+
+/// How many times over the input [`decode`] may read before giving up.
+///
+/// Canonical encodings read each byte at most once; the slack admits encoders that
+/// share the tail of identical dynamic values.
+const DECODE_BUDGET_FACTOR: usize = 2;
+
+/// Decoding state: the input and how many bytes may still be read from it.
+struct Decoder<'a> {
+    data: &'a [u8],
+    budget: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            budget: data.len().saturating_mul(DECODE_BUDGET_FACTOR),
+        }
+    }
+
+    fn tuple(&mut self, types: &[ParamType], base: usize) -> Result<Vec<Token>> {
+        let mut head = base;
+        let mut tokens = Vec::with_capacity(types.len());
+
+        for kind in types {
+            if kind.is_dynamic() {
+                let offset = self.read_usize(head)?;
+                let start = base
+                    .checked_add(offset)
+                    .filter(|&start| start <= self.data.len())
+                    .ok_or_else(|| Error::AbiError(format!("offset {} out of bounds", offset)))?;
+                tokens.push(self.token(kind, start)?);
+            } else {
+                tokens.push(self.token(kind, head)?);
+            }
+            head += kind.head_size();
+        }
+
+        Ok(tokens)
+    }
+
+    fn token(&mut self, kind: &ParamType, at: usize) -> Result<Token> {
+        match kind {
+            ParamType::Address => {
+                let w = self.read_word(at)?;
+                if w[..12].iter().any(|&b| b != 0) {
+                    return Err(Error::AbiError("address has non-zero padding".to_string()));
+                }
+                Ok(Token::Address(Address::from_slice(&w[12..])?))
+            }
+            ParamType::Uint(bits) => {
+                let v = U256::from_big_endian(self.read_word(at)?);
+                if v.bits() > *bits {
+                    return Err(Error::AbiError(format!(
+                        "value out of range for uint{}",
+                        bits
+                    )));
+                }
+                Ok(Token::Uint(v))
+            }
+            ParamType::Int(bits) => {
+                let v = U256::from_big_endian(self.read_word(at)?);
+                if !fits_signed(v, *bits) {
+                    return Err(Error::AbiError(format!(
+                        "value out of range for int{}",
+                        bits
+                    )));
+                }
+                Ok(Token::Int(v))
+            }
+            ParamType::Bool => match U256::from_big_endian(self.read_word(at)?) {
+                v if v.is_zero() => Ok(Token::Bool(false)),
+                v if v == U256::one() => Ok(Token::Bool(true)),
+                v => Err(Error::AbiError(format!("invalid bool value: {}", v))),
+            },
+            ParamType::FixedBytes(len) => {
+                let w = self.read_word(at)?;
+                if w[*len..].iter().any(|&b| b != 0) {
+                    return Err(Error::AbiError(format!(
+                        "bytes{} has non-zero padding",
+                        len
+                    )));
+                }
+                Ok(Token::FixedBytes(w[..*len].to_vec()))
+            }
+            ParamType::Bytes => Ok(Token::Bytes(self.read_bytes(at)?.to_vec())),
+            ParamType::String => String::from_utf8(self.read_bytes(at)?.to_vec())
+                .map(Token::String)
+                .map_err(|e| Error::AbiError(format!("string is not valid UTF-8: {}", e))),
+            ParamType::Array(inner) => {
+                let len = self.read_usize(at)?;
+                let start = at + WORD;
+                let types = self.element_types(inner, len, start)?;
+                self.tuple(&types, start).map(Token::Array)
+            }
+            ParamType::FixedArray(inner, len) => {
+                let types = self.element_types(inner, *len, at)?;
+                self.tuple(&types, at).map(Token::FixedArray)
+            }
+            ParamType::Tuple(types) => self.tuple(types, at).map(Token::Tuple),
+        }
+    }
+
+    /// Returns the types of `len` array elements whose heads start at `at`, after
+    /// checking the heads fit in the data.
+    ///
+    /// Elements of zero size (`T[0]`, `()`) are refused: they would let a short input
+    /// claim billions of elements.
+    fn element_types(&self, inner: &ParamType, len: usize, at: usize) -> Result<Vec<ParamType>> {
+        let size = inner.head_size();
+        if size == 0 {
+            return Err(Error::AbiError(format!(
+                "zero-sized array element type {}",
+                inner
+            )));
+        }
+        if len.saturating_mul(size) > self.data.len().saturating_sub(at) {
+            return Err(Error::AbiError(format!(
+                "array length {} out of bounds",
+                len
+            )));
+        }
+        Ok(vec![inner.clone(); len])
+    }
+
+    /// Charges `len` bytes of reading against the budget.
+    fn charge(&mut self, len: usize) -> Result<()> {
+        self.budget = self.budget.checked_sub(len).ok_or_else(|| {
+            Error::AbiError("decoding exceeds the input size budget (aliased offsets?)".to_string())
+        })?;
+        Ok(())
+    }
+
+    fn read_word(&mut self, at: usize) -> Result<&'a [u8]> {
+        let word = read_word(self.data, at)?;
+        self.charge(WORD)?;
+        Ok(word)
+    }
+
+    fn read_usize(&mut self, at: usize) -> Result<usize> {
+        let v = U256::from_big_endian(self.read_word(at)?);
+        if v > U256::from(u32::MAX) {
+            return Err(Error::AbiError(format!(
+                "offset or length too large: {}",
+                v
+            )));
+        }
+        Ok(v.as_usize())
+    }
+
+    fn read_bytes(&mut self, at: usize) -> Result<&'a [u8]> {
+        let len = self.read_usize(at)?;
+        let start = at + WORD;
+        let bytes = start
+            .checked_add(len)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| Error::AbiError(format!("bytes length {} out of bounds", len)))?;
+        self.charge(len)?;
+        Ok(bytes)
+    }
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8]> {
+    at.checked_add(WORD)
+        .and_then(|end| data.get(at..end))
+        .ok_or_else(|| Error::AbiError(format!("data too short: no word at offset {}", at)))
+}
+
+fn twos_complement(v: U256) -> U256 {
+    (!v).overflowing_add(U256::one()).0
+}
+
+fn fits_signed(v: U256, bits: usize) -> bool {
+    if bits >= 256 {
+        return true;
+    }
+    // All bits from the sign bit upwards must equal the sign bit.
+    let upper = v >> (bits - 1);
+    upper.is_zero() || upper == (U256::MAX >> (bits - 1))
+}
+
+// ─── Functions ───────────────────────────────────────────────────────────────
+
+/// A contract function parsed from a human-readable signature.
+///
+/// Accepts `name(type [name], …)` with an optional ` returns (type, …)` suffix, as
+/// well as the `function` keyword and state mutability words used by Solidity and
+/// ethers-style human-readable ABIs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// Function name.
+    pub name: String,
+    /// Input parameters.
+    pub inputs: Vec<Param>,
+    /// Output types (empty if none were declared).
+    pub outputs: Vec<ParamType>,
+}
+
+/// A named function or event parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Parameter name (empty if unnamed).
+    pub name: String,
+    /// Parameter type.
+    pub kind: ParamType,
+    /// Whether the parameter is `indexed` (events only).
+    pub indexed: bool,
+}
+
+impl Function {
+    /// Parses a function signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature or any parameter type is malformed.
+    pub fn parse(signature: &str) -> Result<Self> {
+        let s = signature.trim();
+        let s = s.strip_prefix("function ").unwrap_or(s).trim_start();
+        let (name, params, rest) = split_signature(s)?;
+
+        let outputs = match rest.find("returns") {
+            Some(i) => {
+                let returns = rest[i + "returns".len()..].trim();
+                if !returns.starts_with('(') || matching_paren(returns)? != returns.len() - 1 {
+                    return Err(Error::AbiError(format!(
+                        "invalid returns clause in '{}'",
+                        s
+                    )));
+                }
+                parse_params(&returns[1..returns.len() - 1])?
+                    .into_iter()
+                    .map(|p| p.kind)
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            inputs: parse_params(params)?,
+            outputs,
+        })
+    }
+
+    /// Returns the canonical signature, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self) -> String {
+        let kinds: Vec<ParamType> = self.inputs.iter().map(|p| p.kind.clone()).collect();
+        format!("{}({})", self.name, join_types(&kinds))
+    }
+
+    /// Returns the 4-byte function selector.
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+
+    /// Encodes calldata (`selector ‖ encode(args)`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number or types of `args` do not match the inputs.
+    pub fn encode_input(&self, args: &[Token]) -> Result<Vec<u8>> {
+        if args.len() != self.inputs.len() {
+            return Err(Error::AbiError(format!(
+                "{} expects {} arguments, got {}",
+                self.name,
+                self.inputs.len(),
+                args.len()
+            )));
+        }
+        for (i, (arg, param)) in args.iter().zip(&self.inputs).enumerate() {
+            if !arg.type_check(&param.kind) {
+                return Err(Error::AbiError(format!(
+                    "argument {} of {} is not a valid {}",
+                    i, self.name, param.kind
+                )));
+            }
+        }
+
+        Ok(encode_with_selector(self.selector(), args))
+    }
+
+    /// Decodes calldata produced for this function.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the selector does not match or the arguments are malformed.
+    pub fn decode_input(&self, calldata: &[u8]) -> Result<Vec<Token>> {
+        match calldata.split_first_chunk::<4>() {
+            Some((sel, args)) if *sel == self.selector() => {
+                let kinds: Vec<ParamType> = self.inputs.iter().map(|p| p.kind.clone()).collect();
+                decode(&kinds, args)
+            }
+            _ => Err(Error::AbiError(format!(
+                "calldata does not start with the {} selector",
+                self.signature()
+            ))),
+        }
+    }
+
+    /// Decodes return data using the declared output types.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is malformed.
+    pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>> {
+        decode(&self.outputs, data)
+    }
+}
+
+// ─── Events ──────────────────────────────────────────────────────────────────
+
+/// A contract event parsed from a human-readable signature.
+///
+/// Parameters are written as `type [indexed] [name]`, e.g.
+/// `Transfer(address indexed from, address indexed to, uint256 value)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Event name.
+    pub name: String,
+    /// Event parameters in declaration order.
+    pub inputs: Vec<Param>,
+}
+
+impl Event {
+    /// Parses an event signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature or any parameter type is malformed.
+    pub fn parse(signature: &str) -> Result<Self> {
+        let s = signature.trim();
+        let s = s.strip_prefix("event ").unwrap_or(s).trim_start();
+        let (name, params, _) = split_signature(s)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            inputs: parse_params(params)?,
+        })
+    }
+
+    /// Returns the canonical signature, e.g. `Transfer(address,address,uint256)`.
+    pub fn signature(&self) -> String {
+        let kinds: Vec<ParamType> = self.inputs.iter().map(|p| p.kind.clone()).collect();
+        format!("{}({})", self.name, join_types(&kinds))
+    }
+
+    /// Returns `topic0`, the keccak-256 hash of the canonical signature.
+    pub fn topic0(&self) -> [u8; 32] {
+        keccak256(self.signature().as_bytes())
+    }
+
+    /// Decodes a log into one token per parameter, in declaration order.
+    ///
+    /// Indexed parameters of dynamic type (`string`, `bytes`, arrays, tuples) are only
+    /// stored as their keccak-256 hash and are returned as a 32-byte
+    /// [`Token::FixedBytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `topics[0]` does not match [`Event::topic0`], the number of
+    /// topics does not match the indexed parameters, or the data is malformed.
+    pub fn decode_log(&self, topics: &[[u8; 32]], data: &[u8]) -> Result<Vec<Token>> {
+        if topics.first() != Some(&self.topic0()) {
+            return Err(Error::AbiError(format!(
+                "log topic0 does not match {}",
+                self.signature()
+            )));
+        }
+
+        let indexed = self.inputs.iter().filter(|p| p.indexed).count();
+        if topics.len() != indexed + 1 {
+            return Err(Error::AbiError(format!(
+                "{} expects {} topics, got {}",
+                self.name,
+                indexed + 1,
+                topics.len()
+            )));
+        }
+
+        let data_types: Vec<ParamType> = self
+            .inputs
+            .iter()
+            .filter(|p| !p.indexed)
+            .map(|p| p.kind.clone())
+            .collect();
+        let mut data_tokens = decode(&data_types, data)?.into_iter();
+        let mut topic_iter = topics[1..].iter();
+
+        self.inputs
+            .iter()
+            .map(|param| {
+                if !param.indexed {
+                    return Ok(data_tokens.next().expect("one token per data param"));
+                }
+                let topic = topic_iter.next().expect("topic count checked");
+                if param.kind.is_dynamic() || param.kind.head_size() != WORD {
+                    Ok(Token::FixedBytes(topic.to_vec()))
+                } else {
+                    Decoder::new(topic).token(&param.kind, 0)
+                }
+            })
+            .collect()
+    }
+}
+
+// ─── Signature parsing ───────────────────────────────────────────────────────
+
+fn split_signature(s: &str) -> Result<(&str, &str, &str)> {
+    let open = s
+        .find('(')
+        .ok_or_else(|| Error::AbiError(format!("missing '(' in '{}'", s)))?;
+    let name = s[..open].trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    {
+        return Err(Error::AbiError(format!("invalid name in '{}'", s)));
+    }
+    let close = open + matching_paren(&s[open..])?;
+    Ok((name, &s[open + 1..close], &s[close + 1..]))
+}
+
+fn matching_paren(s: &str) -> Result<usize> {
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(Error::AbiError(format!(
+        "unbalanced parentheses in '{}'",
+        s
+    )))
+}
+
+fn parse_params(s: &str) -> Result<Vec<Param>> {
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut params = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                params.push(parse_param(&s[start..i])?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(parse_param(&s[start..])?);
+    Ok(params)
+}
+
+fn parse_param(s: &str) -> Result<Param> {
+    let s = s.trim();
+    // The type ends at the first whitespace outside parentheses.
+    let mut depth = 0usize;
+    let mut split = s.len();
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => {
+                split = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let kind: ParamType = s[..split].parse()?;
+    let mut indexed = false;
+    let mut name = String::new();
+    for word in s[split..].split_whitespace() {
+        match word {
+            "indexed" => indexed = true,
+            "memory" | "calldata" | "storage" => {}
+            _ if name.is_empty() => name = word.to_string(),
+            _ => return Err(Error::AbiError(format!("invalid parameter '{}'", s))),
+        }
+    }
+
+    Ok(Param {
+        name,
+        kind,
+        indexed,
+    })
+}
+
+fn parse_elementary(s: &str) -> Result<ParamType> {
+    let invalid = || Error::AbiError(format!("unknown type '{}'", s));
+    let sized = |digits: &str| -> Result<usize> { digits.parse().map_err(|_| invalid()) };
+
+    match s {
+        "address" => Ok(ParamType::Address),
+        "bool" => Ok(ParamType::Bool),
+        "string" => Ok(ParamType::String),
+        "bytes" => Ok(ParamType::Bytes),
+        "uint" => Ok(ParamType::Uint(256)),
+        "int" => Ok(ParamType::Int(256)),
+        _ => {
+            if let Some(bits) = s.strip_prefix("uint") {
+                let bits = sized(bits)?;
+                (bits % 8 == 0 && (8..=256).contains(&bits))
+                    .then_some(ParamType::Uint(bits))
+                    .ok_or_else(invalid)
+            } else if let Some(bits) = s.strip_prefix("int") {
+                let bits = sized(bits)?;
+                (bits % 8 == 0 && (8..=256).contains(&bits))
+                    .then_some(ParamType::Int(bits))
+                    .ok_or_else(invalid)
+            } else if let Some(len) = s.strip_prefix("bytes") {
+                let len = sized(len)?;
+                (1..=32)
+                    .contains(&len)
+                    .then_some(ParamType::FixedBytes(len))
+                    .ok_or_else(invalid)
+            } else {
+                Err(invalid())
+            }
+        }
+    }
+}
+
+fn join_types(types: &[ParamType]) -> String {
+    types
+        .iter()
+        .map(ParamType::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn word_hex(data: &[u8], i: usize) -> String {
+        hex::encode(&data[i * 32..(i + 1) * 32])
+    }
+
+    // ─── ParamType ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_elementary_types() {
+        assert_eq!("address".parse::<ParamType>().unwrap(), ParamType::Address);
+        assert_eq!("uint".parse::<ParamType>().unwrap(), ParamType::Uint(256));
+        assert_eq!("int8".parse::<ParamType>().unwrap(), ParamType::Int(8));
+        assert_eq!(
+            "bytes32".parse::<ParamType>().unwrap(),
+            ParamType::FixedBytes(32)
+        );
+        assert_eq!("bytes".parse::<ParamType>().unwrap(), ParamType::Bytes);
+        assert_eq!("string".parse::<ParamType>().unwrap(), ParamType::String);
+    }
+
+    #[test]
+    fn test_parse_invalid_types() {
+        for s in [
+            "uint7",
+            "uint264",
+            "bytes0",
+            "bytes33",
+            "foo",
+            "uint256[",
+            "(uint256",
+            "uint256[0]",
+        ] {
+            assert!(s.parse::<ParamType>().is_err(), "{} should not parse", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_nested_types_roundtrip() {
+        for s in [
+            "uint256[]",
+            "address[2][]",
+            "(uint256,address)",
+            "(bytes32,(bool,string)[])[3]",
+        ] {
+            assert_eq!(s.parse::<ParamType>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_is_dynamic() {
+        let parse = |s: &str| s.parse::<ParamType>().unwrap();
+        assert!(!parse("uint256[3]").is_dynamic());
+        assert!(parse("string[3]").is_dynamic());
+        assert!(!parse("(uint256,bool)").is_dynamic());
+        assert!(parse("(uint256,bytes)").is_dynamic());
+    }
+
+    // ─── Encoding ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_encode_static() {
+        let data = encode(&[Token::Uint(U256::from(69)), Token::Bool(true)]);
+        assert_eq!(data.len(), 64);
+        assert_eq!(data[31], 69);
+        assert_eq!(data[63], 1);
+    }
+
+    #[test]
+    fn test_encode_spec_example_dynamic() {
+        // Solidity docs: sam(bytes,bool,uint256[]) with ("dave", true, [1,2,3])
+        let data = encode(&[
+            Token::Bytes(b"dave".to_vec()),
+            Token::Bool(true),
+            Token::Array(vec![
+                Token::Uint(U256::from(1)),
+                Token::Uint(U256::from(2)),
+                Token::Uint(U256::from(3)),
+            ]),
+        ]);
+
+        let expected = [
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "6461766500000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+        ];
+        assert_eq!(data.len(), expected.len() * 32);
+        for (i, w) in expected.iter().enumerate() {
+            assert_eq!(word_hex(&data, i), *w, "word {}", i);
+        }
+    }
+
+    #[test]
+    fn test_encode_negative_int() {
+        let data = encode(&[Token::int(-1)]);
+        assert_eq!(data, vec![0xffu8; 32]);
+        assert_eq!(Token::int(-1).as_i128(), Some(-1));
+        assert_eq!(Token::int(i128::MIN).as_i128(), Some(i128::MIN));
+    }
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+        assert_eq!(selector("baz(uint32,bool)"), [0xcd, 0xcd, 0x77, 0xc0]);
+    }
+
+    // ─── Decoding ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_roundtrip_nested() {
+        let types: Vec<ParamType> = ["(address,string[])", "int16", "bytes4[2]", "bytes"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let tokens = vec![
+            Token::Tuple(vec![
+                Token::Address(alice()),
+                Token::Array(vec![
+                    Token::String("hello".into()),
+                    Token::String("world!".into()),
+                ]),
+            ]),
+            Token::int(-300),
+            Token::FixedArray(vec![
+                Token::FixedBytes(vec![1, 2, 3, 4]),
+                Token::FixedBytes(vec![5, 6, 7, 8]),
+            ]),
+            Token::Bytes(vec![0xab; 40]),
+        ];
+
+        for (token, kind) in tokens.iter().zip(&types) {
+            assert!(token.type_check(kind));
+        }
+        assert_eq!(decode(&types, &encode(&tokens)).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_decode_rejects_out_of_range() {
+        let data = uint_word(U256::from(256));
+        assert!(decode(&[ParamType::Uint(8)], &data).is_err());
+        assert!(decode(&[ParamType::Bool], &data).is_err());
+        assert!(decode(&[ParamType::Int(8)], &data).is_err());
+        assert!(decode(&[ParamType::Address], &[0xffu8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_offsets() {
+        let mut data = uint_word(U256::MAX).to_vec();
+        data.extend_from_slice(&[0u8; 32]);
+        assert!(decode(&[ParamType::Bytes], &data).is_err());
+
+        // Array length claims more elements than the data holds
+        let mut data = uint_word(U256::from(32)).to_vec();
+        data.extend_from_slice(&uint_word(U256::from(1_000_000)));
+        assert!(decode(&[ParamType::Array(Box::new(ParamType::Uint(256)))], &data).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_huge_arrays() {
+        // A maximal length for elements of zero size must not allocate them all
+        let mut data = uint_word(U256::from(32)).to_vec();
+        data.extend_from_slice(&uint_word(U256::from(u32::MAX)));
+        for inner in [
+            ParamType::FixedArray(Box::new(ParamType::Uint(256)), 0),
+            ParamType::Tuple(vec![]),
+        ] {
+            let err = decode(&[ParamType::Array(Box::new(inner))], &data).unwrap_err();
+            assert!(err.to_string().contains("zero-sized"));
+        }
+
+        // So must a fixed array longer than the data
+        let huge = ParamType::FixedArray(Box::new(ParamType::Bool), usize::MAX);
+        let err = decode(&[huge], &data).unwrap_err();
+        assert!(err.to_string().contains("out of bounds"));
+    }
+
+    #[test]
+    fn test_decode_rejects_aliased_offsets() {
+        // uint256[][][] where every head of the outer and middle arrays points at the
+        // same tail: ~29 KB that would otherwise expand into n³ = 27M tokens
+        let n = 300;
+        let word = |v: usize| uint_word(U256::from(v));
+        let mut data = word(32).to_vec();
+        for _ in 0..2 {
+            data.extend_from_slice(&word(n));
+            for _ in 0..n {
+                data.extend_from_slice(&word(n * WORD));
+            }
+        }
+        data.extend_from_slice(&word(n));
+        data.resize(data.len() + n * WORD, 0);
+
+        let kind = ParamType::Array(Box::new(ParamType::Array(Box::new(ParamType::Array(
+            Box::new(ParamType::Uint(256)),
+        )))));
+        let err = decode(&[kind], &data).unwrap_err();
+        assert!(err.to_string().contains("budget"));
+    }
+
+    #[test]
+    fn test_decode_allows_shared_tail() {
+        // Two equal strings sharing one tail stay within the budget
+        let mut data = uint_word(U256::from(64)).to_vec();
+        data.extend_from_slice(&uint_word(U256::from(64)));
+        data.extend_from_slice(&uint_word(U256::from(3)));
+        data.extend_from_slice(&pad_right(b"abc"));
+        assert_eq!(
+            decode(&[ParamType::String, ParamType::String], &data).unwrap(),
+            vec![Token::String("abc".into()), Token::String("abc".into())]
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        assert!(decode(&[ParamType::Uint(256)], &[0u8; 31]).is_err());
+    }
+
+    // ─── Function ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_function_parse() {
+        let f = Function::parse(
+            "function swap(uint256 amountIn, address[] calldata path) external returns (uint256[] memory amounts)",
+        );
+        // The state mutability word sits between the parameters and `returns`
+        let f = f.unwrap();
+        assert_eq!(f.name, "swap");
+        assert_eq!(f.signature(), "swap(uint256,address[])");
+        assert_eq!(f.inputs[0].name, "amountIn");
+        assert_eq!(f.outputs, vec!["uint256[]".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_function_encode_decode() {
+        let f = Function::parse("balanceOf(address) returns (uint256)").unwrap();
+        let calldata = f.encode_input(&[Token::Address(alice())]).unwrap();
+        assert_eq!(&calldata[..4], &[0x70, 0xa0, 0x82, 0x31]);
+        assert_eq!(
+            f.decode_input(&calldata).unwrap(),
+            vec![Token::Address(alice())]
+        );
+
+        let output = f.decode_output(&uint_word(U256::from(5))).unwrap();
+        assert_eq!(output, vec![Token::Uint(U256::from(5))]);
+    }
+
+    #[test]
+    fn test_function_type_check() {
+        let f = Function::parse("approve(address,uint8)").unwrap();
+        assert!(f.encode_input(&[Token::Address(alice())]).is_err());
+        assert!(f
+            .encode_input(&[Token::Address(alice()), Token::Uint(U256::from(256))])
+            .is_err());
+        assert!(f
+            .encode_input(&[Token::Bool(true), Token::Uint(U256::from(1))])
+            .is_err());
+    }
+
+    #[test]
+    fn test_function_decode_wrong_selector() {
+        let f = Function::parse("foo()").unwrap();
+        assert!(f.decode_input(&[0, 0, 0, 0]).is_err());
+        assert!(f.decode_input(&[]).is_err());
+    }
+
+    // ─── Event ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_event_transfer_log() {
+        let event =
+            Event::parse("event Transfer(address indexed from, address indexed to, uint256 value)")
+                .unwrap();
+        assert_eq!(
+            hex::encode(event.topic0()),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+
+        let to: Address = "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap();
+        let mut from_topic = [0u8; 32];
+        from_topic[12..].copy_from_slice(alice().as_bytes());
+        let mut to_topic = [0u8; 32];
+        to_topic[12..].copy_from_slice(to.as_bytes());
+
+        let tokens = event
+            .decode_log(
+                &[event.topic0(), from_topic, to_topic],
+                &uint_word(U256::from(10)),
+            )
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Address(alice()),
+                Token::Address(to),
+                Token::Uint(U256::from(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_indexed_dynamic_is_hash() {
+        let event = Event::parse("Named(string indexed name, bytes data)").unwrap();
+        let hash = keccak256(b"alice");
+        let data = encode(&[Token::Bytes(vec![1, 2, 3])]);
+
+        let tokens = event.decode_log(&[event.topic0(), hash], &data).unwrap();
+        assert_eq!(tokens[0], Token::FixedBytes(hash.to_vec()));
+        assert_eq!(tokens[1], Token::Bytes(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_event_topic_mismatch() {
+        let event = Event::parse("Ping(uint256 indexed id)").unwrap();
+        assert!(event.decode_log(&[[0u8; 32], [0u8; 32]], &[]).is_err());
+        assert!(event.decode_log(&[event.topic0()], &[]).is_err());
+    }
+}
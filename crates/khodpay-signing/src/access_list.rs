@@ -5,6 +5,12 @@
 
 use crate::Address;
 
+/// Gas charged per address in an access list (EIP-2930).
+pub const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+
+/// Gas charged per storage key in an access list (EIP-2930).
+pub const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+
 /// An access list item specifying an address and its storage keys.
 ///
 /// Used in EIP-2930 and EIP-1559 transactions to declare which state
@@ -33,11 +39,40 @@ impl AccessListItem {
             storage_keys: Vec::new(),
         }
     }
+
+    /// Adds a storage key to this item, ignoring duplicates.
+    pub fn with_storage_key(mut self, key: [u8; 32]) -> Self {
+        if !self.storage_keys.contains(&key) {
+            self.storage_keys.push(key);
+        }
+        self
+    }
+
+    /// Returns the intrinsic gas charged for this item.
+    ///
+    /// `2400` for the address plus `1900` per storage key.
+    pub fn gas_cost(&self) -> u64 {
+        ACCESS_LIST_ADDRESS_GAS + ACCESS_LIST_STORAGE_KEY_GAS * self.storage_keys.len() as u64
+    }
 }
 
 /// A list of access list items.
 pub type AccessList = Vec<AccessListItem>;
 
+/// Returns the intrinsic gas charged for an access list.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{access_list_gas, AccessListItem, Address};
+///
+/// let item = AccessListItem::address_only(Address::ZERO).with_storage_key([1u8; 32]);
+/// assert_eq!(access_list_gas(&[item]), 2_400 + 1_900);
+/// ```
+pub fn access_list_gas(access_list: &[AccessListItem]) -> u64 {
+    access_list.iter().map(AccessListItem::gas_cost).sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,6 +100,29 @@ mod tests {
         assert!(item.storage_keys.is_empty());
     }
 
+    #[test]
+    fn test_with_storage_key_deduplicates() {
+        let item = AccessListItem::address_only(Address::ZERO)
+            .with_storage_key([1u8; 32])
+            .with_storage_key([2u8; 32])
+            .with_storage_key([1u8; 32]);
+
+        assert_eq!(item.storage_keys, vec![[1u8; 32], [2u8; 32]]);
+    }
+
+    #[test]
+    fn test_access_list_gas() {
+        let list = vec![
+            AccessListItem::address_only(Address::ZERO),
+            AccessListItem::new(Address::ZERO, vec![[1u8; 32], [2u8; 32]]),
+        ];
+
+        assert_eq!(list[0].gas_cost(), 2_400);
+        assert_eq!(list[1].gas_cost(), 2_400 + 2 * 1_900);
+        assert_eq!(access_list_gas(&list), 2 * 2_400 + 2 * 1_900);
+        assert_eq!(access_list_gas(&[]), 0);
+    }
+
     #[test]
     fn test_access_list_item_default() {
         let item = AccessListItem::default();
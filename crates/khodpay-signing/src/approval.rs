@@ -0,0 +1,430 @@
+//! Unsafe-approval detection.
+//!
+//! Approvals are the usual way drainer sites steal tokens: the victim signs an
+//! `approve(attacker, 2^256-1)` or `setApprovalForAll(attacker, true)` that looks harmless
+//! on a hex screen, and the attacker empties the wallet later. [`ApprovalChecker`] runs
+//! over outgoing calldata before signing and returns an [`ApprovalWarning`] for every
+//! unlimited allowance, collection-wide operator grant and approval to a spender the
+//! wallet does not know.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::approval::{ApprovalChecker, ApprovalRisk};
+//! use khodpay_signing::{erc20, Address, Wei, U256};
+//!
+//! let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let router: Address = "0x10ED43C718714eb63d5aA57B78B54704E256024E".parse().unwrap();
+//!
+//! let mut checker = ApprovalChecker::new();
+//! checker.trust_spender(router, "PancakeSwap Router");
+//!
+//! let data = erc20::encode_approve(&router, U256::MAX);
+//! let warnings = checker.check(Some(usdt), Wei::ZERO, &data);
+//!
+//! assert_eq!(warnings.len(), 1);
+//! assert_eq!(warnings[0].risk, ApprovalRisk::UnlimitedAllowance);
+//! assert_eq!(warnings[0].spender_label.as_deref(), Some("PancakeSwap Router"));
+//! ```
+
+use crate::abi::Token;
+use crate::decoder::{Action, TransactionDecoder};
+use crate::{Address, Eip1559Transaction, Wei};
+use primitive_types::U256;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Smallest allowance treated as unlimited by default: `2^96 - 1`.
+///
+/// Wallets and dApps commonly use `2^256 - 1`, but tokens that store balances in 96
+/// bits (UNI, COMP) cap "infinite" approvals at `type(uint96).max`. Either way the amount
+/// exceeds any real balance.
+pub const DEFAULT_UNLIMITED_THRESHOLD: U256 = U256([u64::MAX, u32::MAX as u64, 0, 0]);
+
+/// The kind of risk an approval carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApprovalRisk {
+    /// An ERC-20 allowance at or above the unlimited threshold.
+    UnlimitedAllowance,
+    /// `setApprovalForAll` granting an operator every token of a collection.
+    OperatorForAll,
+    /// An approval to a spender that is not in the trusted list.
+    UnknownSpender,
+}
+
+/// A risky approval found in outgoing calldata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalWarning {
+    /// What makes the approval risky.
+    pub risk: ApprovalRisk,
+    /// Token or NFT contract the approval is for.
+    pub contract: Address,
+    /// Spender or operator being approved.
+    pub spender: Address,
+    /// Label of the spender, if it is trusted.
+    pub spender_label: Option<String>,
+    /// Allowance or token ID, `None` for operator grants.
+    pub amount: Option<U256>,
+}
+
+impl fmt::Display for ApprovalWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spender = match &self.spender_label {
+            Some(label) => format!("{} ({})", label, self.spender),
+            None => self.spender.to_string(),
+        };
+        match self.risk {
+            ApprovalRisk::UnlimitedAllowance => write!(
+                f,
+                "{} may spend an unlimited amount of token {}",
+                spender, self.contract
+            ),
+            ApprovalRisk::OperatorForAll => write!(
+                f,
+                "{} may transfer every token of collection {}",
+                spender, self.contract
+            ),
+            ApprovalRisk::UnknownSpender => write!(
+                f,
+                "{} is not a known spender for {}",
+                spender, self.contract
+            ),
+        }
+    }
+}
+
+/// Flags unsafe approvals in outgoing calldata.
+///
+/// Recognises ERC-20 `approve` and `increaseAllowance`, ERC-721 `approve` and
+/// `setApprovalForAll`. Revocations (zero allowance, `setApprovalForAll(_, false)`) never
+/// produce warnings.
+#[derive(Debug, Clone)]
+pub struct ApprovalChecker {
+    decoder: TransactionDecoder,
+    trusted: HashMap<Address, String>,
+    unlimited_threshold: U256,
+}
+
+impl ApprovalChecker {
+    /// Creates a checker with no trusted spenders and [`DEFAULT_UNLIMITED_THRESHOLD`].
+    pub fn new() -> Self {
+        Self::with_decoder(TransactionDecoder::new())
+    }
+
+    /// Creates a checker that decodes calldata with `decoder`.
+    ///
+    /// Use a decoder with registered NFT contracts so that ERC-721 `approve` calls are
+    /// not mistaken for ERC-20 allowances (both share a selector).
+    pub fn with_decoder(mut decoder: TransactionDecoder) -> Self {
+        decoder
+            .add_function("increaseAllowance(address spender,uint256 addedValue)")
+            .expect("built-in signature is valid");
+        Self {
+            decoder,
+            trusted: HashMap::new(),
+            unlimited_threshold: DEFAULT_UNLIMITED_THRESHOLD,
+        }
+    }
+
+    /// Marks `spender` as a known contract (router, marketplace, bridge) with a label.
+    pub fn trust_spender(&mut self, spender: Address, label: impl Into<String>) -> &mut Self {
+        self.trusted.insert(spender, label.into());
+        self
+    }
+
+    /// Sets the smallest allowance reported as [`ApprovalRisk::UnlimitedAllowance`].
+    pub fn unlimited_threshold(&mut self, threshold: U256) -> &mut Self {
+        self.unlimited_threshold = threshold;
+        self
+    }
+
+    /// Returns the label of a trusted spender.
+    pub fn spender_label(&self, spender: &Address) -> Option<&str> {
+        self.trusted.get(spender).map(String::as_str)
+    }
+
+    /// Checks an EIP-1559 transaction.
+    pub fn check_transaction(&self, tx: &Eip1559Transaction) -> Vec<ApprovalWarning> {
+        self.check(tx.to, tx.value, &tx.data)
+    }
+
+    /// Checks a transaction from its recipient, value and calldata.
+    pub fn check(&self, to: Option<Address>, value: Wei, data: &[u8]) -> Vec<ApprovalWarning> {
+        self.check_action(&self.decoder.decode(to, value, data).action)
+    }
+
+    /// Checks an action already decoded by a [`TransactionDecoder`].
+    pub fn check_action(&self, action: &Action) -> Vec<ApprovalWarning> {
+        let (contract, spender, amount, risk) = match action {
+            Action::TokenApproval {
+                token,
+                spender,
+                amount,
+            } => {
+                if amount.is_zero() {
+                    return Vec::new();
+                }
+                let risk = (*amount >= self.unlimited_threshold)
+                    .then_some(ApprovalRisk::UnlimitedAllowance);
+                (*token, *spender, Some(*amount), risk)
+            }
+            Action::ContractCall {
+                to, function, args, ..
+            } if function == "increaseAllowance" => match args.as_slice() {
+                [(_, Token::Address(spender)), (_, Token::Uint(added))] if !added.is_zero() => {
+                    let risk = (*added >= self.unlimited_threshold)
+                        .then_some(ApprovalRisk::UnlimitedAllowance);
+                    (*to, *spender, Some(*added), risk)
+                }
+                _ => return Vec::new(),
+            },
+            Action::NftApproval {
+                contract,
+                approved,
+                token_id,
+            } => {
+                if *approved == Address::ZERO {
+                    return Vec::new();
+                }
+                (*contract, *approved, Some(*token_id), None)
+            }
+            Action::ApprovalForAll {
+                contract,
+                operator,
+                approved: true,
+            } => (
+                *contract,
+                *operator,
+                None,
+                Some(ApprovalRisk::OperatorForAll),
+            ),
+            _ => return Vec::new(),
+        };
+
+        let spender_label = self.trusted.get(&spender).cloned();
+        let warning = |risk| ApprovalWarning {
+            risk,
+            contract,
+            spender,
+            spender_label: spender_label.clone(),
+            amount,
+        };
+
+        let mut warnings: Vec<ApprovalWarning> = risk.map(warning).into_iter().collect();
+        if spender_label.is_none() {
+            warnings.push(warning(ApprovalRisk::UnknownSpender));
+        }
+        warnings
+    }
+}
+
+impl Default for ApprovalChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{encode_with_selector, Function};
+    use crate::decoder::TokenInfo;
+    use crate::{erc20, erc721};
+
+    fn usdt() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    fn nft() -> Address {
+        Address::from_bytes([0x99; 20])
+    }
+
+    fn router() -> Address {
+        "0x10ED43C718714eb63d5aA57B78B54704E256024E"
+            .parse()
+            .unwrap()
+    }
+
+    fn stranger() -> Address {
+        Address::from_bytes([0x66; 20])
+    }
+
+    fn checker() -> ApprovalChecker {
+        let mut decoder = TransactionDecoder::new();
+        decoder.add_token(nft(), TokenInfo::erc721("Punks"));
+        let mut checker = ApprovalChecker::with_decoder(decoder);
+        checker.trust_spender(router(), "PancakeSwap Router");
+        checker
+    }
+
+    fn risks(warnings: &[ApprovalWarning]) -> Vec<ApprovalRisk> {
+        warnings.iter().map(|w| w.risk).collect()
+    }
+
+    // ─── ERC-20 ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_bounded_approval_to_trusted_spender_is_clean() {
+        let data = erc20::encode_approve(&router(), U256::from(1_000u64));
+        assert!(checker().check(Some(usdt()), Wei::ZERO, &data).is_empty());
+    }
+
+    #[test]
+    fn test_unlimited_approval_to_trusted_spender() {
+        let data = erc20::encode_approve(&router(), U256::MAX);
+        let warnings = checker().check(Some(usdt()), Wei::ZERO, &data);
+
+        assert_eq!(
+            warnings,
+            vec![ApprovalWarning {
+                risk: ApprovalRisk::UnlimitedAllowance,
+                contract: usdt(),
+                spender: router(),
+                spender_label: Some("PancakeSwap Router".to_string()),
+                amount: Some(U256::MAX),
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            format!(
+                "PancakeSwap Router ({}) may spend an unlimited amount of token {}",
+                router(),
+                usdt()
+            )
+        );
+    }
+
+    #[test]
+    fn test_unlimited_approval_to_unknown_spender() {
+        let data = erc20::encode_approve(&stranger(), U256::MAX);
+        let warnings = checker().check(Some(usdt()), Wei::ZERO, &data);
+
+        assert_eq!(
+            risks(&warnings),
+            vec![
+                ApprovalRisk::UnlimitedAllowance,
+                ApprovalRisk::UnknownSpender
+            ]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            format!("{} is not a known spender for {}", stranger(), usdt())
+        );
+    }
+
+    #[test]
+    fn test_uint96_max_counts_as_unlimited() {
+        let data = erc20::encode_approve(&router(), DEFAULT_UNLIMITED_THRESHOLD);
+        let warnings = checker().check(Some(usdt()), Wei::ZERO, &data);
+        assert_eq!(risks(&warnings), vec![ApprovalRisk::UnlimitedAllowance]);
+
+        let data = erc20::encode_approve(&router(), DEFAULT_UNLIMITED_THRESHOLD - 1);
+        assert!(checker().check(Some(usdt()), Wei::ZERO, &data).is_empty());
+    }
+
+    #[test]
+    fn test_custom_threshold() {
+        let mut checker = checker();
+        checker.unlimited_threshold(U256::from(1_000u64));
+
+        let data = erc20::encode_approve(&router(), U256::from(1_000u64));
+        let warnings = checker.check(Some(usdt()), Wei::ZERO, &data);
+        assert_eq!(risks(&warnings), vec![ApprovalRisk::UnlimitedAllowance]);
+    }
+
+    #[test]
+    fn test_revocation_is_clean() {
+        let data = erc20::encode_approve(&stranger(), U256::zero());
+        assert!(checker().check(Some(usdt()), Wei::ZERO, &data).is_empty());
+    }
+
+    #[test]
+    fn test_increase_allowance() {
+        let function = Function::parse("increaseAllowance(address,uint256)").unwrap();
+        let data = encode_with_selector(
+            function.selector(),
+            &[Token::Address(stranger()), Token::Uint(U256::MAX)],
+        );
+        let warnings = checker().check(Some(usdt()), Wei::ZERO, &data);
+        assert_eq!(
+            risks(&warnings),
+            vec![
+                ApprovalRisk::UnlimitedAllowance,
+                ApprovalRisk::UnknownSpender
+            ]
+        );
+        assert_eq!(warnings[0].contract, usdt());
+    }
+
+    // ─── NFTs ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_nft_approval_to_unknown_spender() {
+        let data = erc721::encode_approve(&stranger(), U256::from(7u64));
+        let warnings = checker().check(Some(nft()), Wei::ZERO, &data);
+
+        assert_eq!(risks(&warnings), vec![ApprovalRisk::UnknownSpender]);
+        assert_eq!(warnings[0].amount, Some(U256::from(7u64)));
+    }
+
+    #[test]
+    fn test_nft_approval_cleared() {
+        let data = erc721::encode_approve(&Address::ZERO, U256::from(7u64));
+        assert!(checker().check(Some(nft()), Wei::ZERO, &data).is_empty());
+    }
+
+    #[test]
+    fn test_approval_for_all() {
+        let data = erc721::encode_set_approval_for_all(&router(), true);
+        let warnings = checker().check(Some(nft()), Wei::ZERO, &data);
+        assert_eq!(risks(&warnings), vec![ApprovalRisk::OperatorForAll]);
+        assert_eq!(warnings[0].amount, None);
+
+        let data = erc721::encode_set_approval_for_all(&stranger(), true);
+        let warnings = checker().check(Some(nft()), Wei::ZERO, &data);
+        assert_eq!(
+            risks(&warnings),
+            vec![ApprovalRisk::OperatorForAll, ApprovalRisk::UnknownSpender]
+        );
+
+        let data = erc721::encode_set_approval_for_all(&stranger(), false);
+        assert!(checker().check(Some(nft()), Wei::ZERO, &data).is_empty());
+    }
+
+    // ─── other calls ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_non_approvals_are_clean() {
+        let checker = checker();
+        let transfer = erc20::encode_transfer(&stranger(), U256::MAX);
+        assert!(checker.check(Some(usdt()), Wei::ZERO, &transfer).is_empty());
+        assert!(checker
+            .check(Some(stranger()), Wei::from_ether(1), &[])
+            .is_empty());
+        assert!(checker
+            .check(Some(usdt()), Wei::ZERO, &[0xde, 0xad, 0xbe, 0xef])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_check_transaction() {
+        let tx = Eip1559Transaction::builder()
+            .chain_id(crate::ChainId::BscMainnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(60_000)
+            .to(usdt())
+            .data(erc20::encode_approve(&stranger(), U256::MAX))
+            .build()
+            .unwrap();
+        assert_eq!(checker().check_transaction(&tx).len(), 2);
+        assert_eq!(
+            checker().spender_label(&router()),
+            Some("PancakeSwap Router")
+        );
+        assert_eq!(checker().spender_label(&stranger()), None);
+    }
+}
@@ -0,0 +1,307 @@
+//! Batch signing with consecutive nonces.
+//!
+//! Payout and airdrop jobs send many transactions from one account. [`Bip44Signer::sign_batch`]
+//! assigns consecutive nonces to a list of [`TxRequest`]s, signs them all and returns them
+//! in broadcast order — or returns an error without any signed output, so a job never ends
+//! up holding half a batch with a gap in its nonces.
+
+use crate::fee::FeeSuggestion;
+use crate::{
+    AccessList, Address, Bip44Signer, ChainId, Eip1559Transaction, Error, Result,
+    SignedTransaction, Wei, TRANSFER_GAS,
+};
+
+/// One transaction of a batch, without the fields the batch assigns.
+///
+/// Chain ID, nonce and fees are shared by the batch; see [`Bip44Signer::sign_batch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxRequest {
+    /// The recipient address (None for contract creation).
+    pub to: Option<Address>,
+    /// The value to transfer in wei.
+    pub value: Wei,
+    /// The transaction data (contract call data).
+    pub data: Vec<u8>,
+    /// The gas limit for the transaction.
+    pub gas_limit: u64,
+    /// The access list for gas optimization.
+    pub access_list: AccessList,
+}
+
+impl TxRequest {
+    /// Creates a native transfer of `value` to `to` with a 21,000 gas limit.
+    pub fn transfer(to: Address, value: Wei) -> Self {
+        Self {
+            to: Some(to),
+            value,
+            data: Vec::new(),
+            gas_limit: TRANSFER_GAS,
+            access_list: AccessList::new(),
+        }
+    }
+
+    /// Creates a contract call with the given calldata and gas limit.
+    pub fn call(to: Address, data: Vec<u8>, gas_limit: u64) -> Self {
+        Self {
+            to: Some(to),
+            value: Wei::ZERO,
+            data,
+            gas_limit,
+            access_list: AccessList::new(),
+        }
+    }
+
+    /// Sets the value to transfer.
+    pub fn with_value(mut self, value: Wei) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Sets the access list.
+    pub fn with_access_list(mut self, access_list: AccessList) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    fn into_transaction(
+        self,
+        chain_id: ChainId,
+        nonce: u64,
+        fees: FeeSuggestion,
+    ) -> Result<Eip1559Transaction> {
+        let mut builder = Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(nonce)
+            .fee_suggestion(fees)
+            .gas_limit(self.gas_limit)
+            .value(self.value)
+            .data(self.data)
+            .access_list(self.access_list);
+        if let Some(to) = self.to {
+            builder = builder.to(to);
+        }
+        builder.build()
+    }
+}
+
+impl Bip44Signer {
+    /// Signs a batch of transactions with consecutive nonces starting at `start_nonce`.
+    ///
+    /// Every transaction uses `chain_id` and `fees`. The result is in broadcast order:
+    /// element `i` carries nonce `start_nonce + i`.
+    ///
+    /// The batch is all-or-nothing: every request is built and validated before
+    /// anything is signed, and any failure returns an error instead of a partial batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ChainIdMismatch`] if the signer is bound to a different chain,
+    /// [`Error::InvalidNonce`] if the nonces would overflow,
+    /// [`Error::PolicyViolation`] if a request breaks the signer's
+    /// [`SigningPolicy`](crate::SigningPolicy), or an error naming the first request that
+    /// fails validation or signing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::fee::FeeSuggestion;
+    /// use khodpay_signing::{Address, Bip44Signer, ChainId, TxRequest, Wei};
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+    /// let fees = FeeSuggestion {
+    ///     max_fee_per_gas: Wei::from_gwei(5),
+    ///     max_priority_fee_per_gas: Wei::from_gwei(1),
+    /// };
+    ///
+    /// let payouts = vec![
+    ///     TxRequest::transfer(Address::from_bytes([0x11; 20]), Wei::from_gwei(1_000)),
+    ///     TxRequest::transfer(Address::from_bytes([0x22; 20]), Wei::from_gwei(2_000)),
+    /// ];
+    ///
+    /// let signed = signer.sign_batch(ChainId::BscMainnet, 7, fees, payouts).unwrap();
+    /// assert_eq!(signed[0].transaction().nonce, 7);
+    /// assert_eq!(signed[1].transaction().nonce, 8);
+    /// ```
+    pub fn sign_batch(
+        &self,
+        chain_id: ChainId,
+        start_nonce: u64,
+        fees: FeeSuggestion,
+        requests: Vec<TxRequest>,
+    ) -> Result<Vec<SignedTransaction>> {
+        self.check_chain_id(chain_id.value())?;
+
+        let transactions = requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, request)| {
+                let nonce = u64::try_from(index)
+                    .ok()
+                    .and_then(|offset| start_nonce.checked_add(offset))
+                    .ok_or_else(|| {
+                        Error::InvalidNonce(format!(
+                            "batch request {} overflows nonce {}",
+                            index, start_nonce
+                        ))
+                    })?;
+                let tx = request
+                    .into_transaction(chain_id, nonce, fees)
+                    .map_err(|e| batch_error(index, e))?;
+                if let Some(policy) = self.policy() {
+                    policy.check_transaction(&tx).map_err(|e| match e {
+                        Error::PolicyViolation(reason) => {
+                            Error::PolicyViolation(format!("batch request {}: {}", index, reason))
+                        }
+                        e => e,
+                    })?;
+                }
+                Ok(tx)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        transactions
+            .into_iter()
+            .enumerate()
+            .map(|(index, tx)| {
+                let signature = self
+                    .sign_transaction(&tx)
+                    .map_err(|e| batch_error(index, e))?;
+                Ok(SignedTransaction::new(tx, signature))
+            })
+            .collect()
+    }
+}
+
+fn batch_error(index: usize, error: Error) -> Error {
+    Error::ValidationError(format!("batch request {}: {}", index, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{erc20, U256};
+
+    fn fees() -> FeeSuggestion {
+        FeeSuggestion {
+            max_fee_per_gas: Wei::from_gwei(5),
+            max_priority_fee_per_gas: Wei::from_gwei(1),
+        }
+    }
+
+    fn signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    fn recipient(i: u8) -> Address {
+        Address::from_bytes([i; 20])
+    }
+
+    // ─── ordering ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_sign_batch_assigns_consecutive_nonces() {
+        let signer = signer();
+        let requests = (1..=3)
+            .map(|i| TxRequest::transfer(recipient(i), Wei::from_gwei(u64::from(i))))
+            .collect();
+
+        let signed = signer
+            .sign_batch(ChainId::BscMainnet, 10, fees(), requests)
+            .unwrap();
+
+        assert_eq!(signed.len(), 3);
+        for (i, tx) in signed.iter().enumerate() {
+            let unsigned = tx.transaction();
+            assert_eq!(unsigned.nonce, 10 + i as u64);
+            assert_eq!(unsigned.to, Some(recipient(i as u8 + 1)));
+            assert_eq!(unsigned.chain_id, ChainId::BscMainnet);
+            assert_eq!(unsigned.max_fee_per_gas, Wei::from_gwei(5));
+            assert_eq!(tx.verify().unwrap(), signer.address());
+        }
+    }
+
+    #[test]
+    fn test_sign_batch_mixed_requests() {
+        let token = recipient(0xaa);
+        let data = erc20::encode_transfer(&recipient(1), U256::from(100u64));
+        let requests = vec![
+            TxRequest::call(token, data.clone(), 65_000),
+            TxRequest::transfer(recipient(2), Wei::from_ether(1)),
+        ];
+
+        let signed = signer()
+            .sign_batch(ChainId::BscMainnet, 0, fees(), requests)
+            .unwrap();
+
+        assert_eq!(signed[0].transaction().data, data);
+        assert_eq!(signed[0].transaction().gas_limit, 65_000);
+        assert_eq!(signed[1].transaction().value, Wei::from_ether(1));
+    }
+
+    #[test]
+    fn test_sign_batch_empty() {
+        let signed = signer()
+            .sign_batch(ChainId::BscMainnet, 0, fees(), Vec::new())
+            .unwrap();
+        assert!(signed.is_empty());
+    }
+
+    // ─── atomicity ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_sign_batch_invalid_request_fails_whole_batch() {
+        let requests = vec![
+            TxRequest::transfer(recipient(1), Wei::ZERO),
+            TxRequest::call(recipient(2), vec![0x01], 20_000),
+        ];
+
+        let err = signer()
+            .sign_batch(ChainId::BscMainnet, 0, fees(), requests)
+            .unwrap_err();
+
+        assert!(err.to_string().contains("batch request 1"));
+    }
+
+    #[test]
+    fn test_sign_batch_nonce_overflow() {
+        let requests = vec![
+            TxRequest::transfer(recipient(1), Wei::ZERO),
+            TxRequest::transfer(recipient(2), Wei::ZERO),
+        ];
+
+        let err = signer()
+            .sign_batch(ChainId::BscMainnet, u64::MAX, fees(), requests)
+            .unwrap_err();
+
+        assert!(matches!(err, Error::InvalidNonce(_)));
+    }
+
+    #[test]
+    fn test_sign_batch_checks_policy_before_signing() {
+        let signer =
+            signer().with_policy(crate::SigningPolicy::new().max_value(Wei::from_ether(1)));
+        let requests = vec![
+            TxRequest::transfer(recipient(1), Wei::from_ether(1)),
+            TxRequest::transfer(recipient(2), Wei::from_ether(2)),
+        ];
+
+        match signer.sign_batch(ChainId::BscMainnet, 0, fees(), requests) {
+            Err(Error::PolicyViolation(reason)) => assert!(reason.starts_with("batch request 1")),
+            other => panic!("expected a policy violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sign_batch_respects_chain_binding() {
+        let signer = signer().with_chain_id(ChainId::BscMainnet);
+        let requests = vec![TxRequest::transfer(recipient(1), Wei::ZERO)];
+
+        assert!(matches!(
+            signer.sign_batch(ChainId::EthereumMainnet, 0, fees(), requests),
+            Err(Error::ChainIdMismatch {
+                expected: 56,
+                actual: 1
+            })
+        ));
+    }
+}
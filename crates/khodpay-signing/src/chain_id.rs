@@ -2,8 +2,15 @@
 //!
 //! Chain IDs are used in EIP-155 and EIP-1559 transactions for replay protection.
 //! Each EVM network has a unique chain ID that must be included in signed transactions.
+//!
+//! [`ChainMetadata`] describes a network for display (name, native currency, block
+//! explorer) and [`ChainRegistry`] collects metadata for the built-in chains plus any
+//! custom chains an application registers.
 
+use crate::Address;
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// EVM chain identifier for transaction replay protection.
 ///
@@ -28,10 +35,16 @@ use std::fmt;
 /// let bsc = ChainId::BscMainnet;
 /// assert_eq!(u64::from(bsc), 56);
 ///
-/// let custom = ChainId::Custom(137); // Polygon
-/// assert_eq!(u64::from(custom), 137);
+/// assert_eq!(ChainId::from(137), ChainId::PolygonMainnet);
+///
+/// let custom = ChainId::Custom(250); // Fantom
+/// assert_eq!(u64::from(custom), 250);
+///
+/// // `Custom` with a known value is the same chain as the named variant
+/// assert_eq!(ChainId::Custom(56), ChainId::BscMainnet);
+/// assert_eq!(ChainId::Custom(56).name(), "BSC Mainnet");
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ChainId {
     /// BSC Mainnet (chain ID 56).
@@ -40,12 +53,35 @@ pub enum ChainId {
     /// BSC Testnet (chain ID 97).
     BscTestnet,
 
+    /// Ethereum Mainnet (chain ID 1).
+    EthereumMainnet,
+
+    /// Polygon PoS Mainnet (chain ID 137).
+    PolygonMainnet,
+
+    /// Arbitrum One (chain ID 42161).
+    ArbitrumMainnet,
+
+    /// OP Mainnet (chain ID 10).
+    OptimismMainnet,
+
+    /// Base Mainnet (chain ID 8453).
+    BaseMainnet,
+
+    /// Avalanche C-Chain (chain ID 43114).
+    AvalancheMainnet,
+
     /// Custom chain ID for other EVM networks.
     ///
     /// Use this for networks not explicitly defined, such as:
-    /// - Ethereum Mainnet: `Custom(1)`
-    /// - Polygon: `Custom(137)`
-    /// - Arbitrum: `Custom(42161)`
+    /// - Fantom Opera: `Custom(250)`
+    /// - Sepolia: `Custom(11155111)`
+    ///
+    /// Register a [`ChainMetadata`] in a [`ChainRegistry`] to give it a name,
+    /// native currency and explorer.
+    ///
+    /// `Custom` holding the value of a named chain (e.g. `Custom(56)`) behaves
+    /// exactly like that named variant; see [`ChainId::normalize`].
     Custom(u64),
 }
 
@@ -56,6 +92,36 @@ impl ChainId {
     /// BSC Testnet chain ID value.
     pub const BSC_TESTNET: u64 = 97;
 
+    /// Ethereum Mainnet chain ID value.
+    pub const ETHEREUM_MAINNET: u64 = 1;
+
+    /// Polygon PoS Mainnet chain ID value.
+    pub const POLYGON_MAINNET: u64 = 137;
+
+    /// Arbitrum One chain ID value.
+    pub const ARBITRUM_MAINNET: u64 = 42161;
+
+    /// OP Mainnet chain ID value.
+    pub const OPTIMISM_MAINNET: u64 = 10;
+
+    /// Base Mainnet chain ID value.
+    pub const BASE_MAINNET: u64 = 8453;
+
+    /// Avalanche C-Chain chain ID value.
+    pub const AVALANCHE_MAINNET: u64 = 43114;
+
+    /// All named (non-custom) chains.
+    pub const KNOWN: [ChainId; 8] = [
+        ChainId::BscMainnet,
+        ChainId::BscTestnet,
+        ChainId::EthereumMainnet,
+        ChainId::PolygonMainnet,
+        ChainId::ArbitrumMainnet,
+        ChainId::OptimismMainnet,
+        ChainId::BaseMainnet,
+        ChainId::AvalancheMainnet,
+    ];
+
     /// Returns the numeric chain ID value.
     ///
     /// # Examples
@@ -65,16 +131,50 @@ impl ChainId {
     ///
     /// assert_eq!(ChainId::BscMainnet.value(), 56);
     /// assert_eq!(ChainId::BscTestnet.value(), 97);
-    /// assert_eq!(ChainId::Custom(1).value(), 1);
+    /// assert_eq!(ChainId::EthereumMainnet.value(), 1);
+    /// assert_eq!(ChainId::Custom(250).value(), 250);
     /// ```
     pub const fn value(&self) -> u64 {
         match self {
             ChainId::BscMainnet => Self::BSC_MAINNET,
             ChainId::BscTestnet => Self::BSC_TESTNET,
+            ChainId::EthereumMainnet => Self::ETHEREUM_MAINNET,
+            ChainId::PolygonMainnet => Self::POLYGON_MAINNET,
+            ChainId::ArbitrumMainnet => Self::ARBITRUM_MAINNET,
+            ChainId::OptimismMainnet => Self::OPTIMISM_MAINNET,
+            ChainId::BaseMainnet => Self::BASE_MAINNET,
+            ChainId::AvalancheMainnet => Self::AVALANCHE_MAINNET,
             ChainId::Custom(id) => *id,
         }
     }
 
+    /// Maps `Custom` with a known value to its named variant.
+    ///
+    /// Equality, hashing and every accessor go through this, so `Custom(97)` and
+    /// `BscTestnet` are indistinguishable except in their `Debug` output.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::ChainId;
+    ///
+    /// assert!(matches!(ChainId::Custom(97).normalize(), ChainId::BscTestnet));
+    /// assert!(matches!(ChainId::Custom(250).normalize(), ChainId::Custom(250)));
+    /// ```
+    pub const fn normalize(self) -> ChainId {
+        match self.value() {
+            Self::BSC_MAINNET => ChainId::BscMainnet,
+            Self::BSC_TESTNET => ChainId::BscTestnet,
+            Self::ETHEREUM_MAINNET => ChainId::EthereumMainnet,
+            Self::POLYGON_MAINNET => ChainId::PolygonMainnet,
+            Self::ARBITRUM_MAINNET => ChainId::ArbitrumMainnet,
+            Self::OPTIMISM_MAINNET => ChainId::OptimismMainnet,
+            Self::BASE_MAINNET => ChainId::BaseMainnet,
+            Self::AVALANCHE_MAINNET => ChainId::AvalancheMainnet,
+            value => ChainId::Custom(value),
+        }
+    }
+
     /// Returns the network name for this chain ID.
     ///
     /// # Examples
@@ -84,12 +184,19 @@ impl ChainId {
     ///
     /// assert_eq!(ChainId::BscMainnet.name(), "BSC Mainnet");
     /// assert_eq!(ChainId::BscTestnet.name(), "BSC Testnet");
-    /// assert_eq!(ChainId::Custom(1).name(), "Custom");
+    /// assert_eq!(ChainId::BaseMainnet.name(), "Base");
+    /// assert_eq!(ChainId::Custom(250).name(), "Custom");
     /// ```
     pub const fn name(&self) -> &'static str {
-        match self {
+        match self.normalize() {
             ChainId::BscMainnet => "BSC Mainnet",
             ChainId::BscTestnet => "BSC Testnet",
+            ChainId::EthereumMainnet => "Ethereum Mainnet",
+            ChainId::PolygonMainnet => "Polygon",
+            ChainId::ArbitrumMainnet => "Arbitrum One",
+            ChainId::OptimismMainnet => "OP Mainnet",
+            ChainId::BaseMainnet => "Base",
+            ChainId::AvalancheMainnet => "Avalanche C-Chain",
             ChainId::Custom(_) => "Custom",
         }
     }
@@ -105,7 +212,61 @@ impl ChainId {
     /// assert!(ChainId::BscTestnet.is_testnet());
     /// ```
     pub const fn is_testnet(&self) -> bool {
-        matches!(self, ChainId::BscTestnet)
+        matches!(self.normalize(), ChainId::BscTestnet)
+    }
+
+    /// Returns `true` if the value is not one of the named chains.
+    ///
+    /// `Custom(56)` is BSC Mainnet, so it is not custom.
+    pub const fn is_custom(&self) -> bool {
+        matches!(self.normalize(), ChainId::Custom(_))
+    }
+
+    /// Returns the built-in metadata for a named chain, or `None` for custom chains.
+    ///
+    /// Use a [`ChainRegistry`] to look up custom chains as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::ChainId;
+    ///
+    /// let polygon = ChainId::PolygonMainnet.metadata().unwrap();
+    /// assert_eq!(polygon.native_symbol, "POL");
+    /// assert_eq!(polygon.decimals, 18);
+    ///
+    /// assert!(ChainId::Custom(250).metadata().is_none());
+    /// ```
+    pub fn metadata(&self) -> Option<ChainMetadata> {
+        let chain_id = self.normalize();
+        let (symbol, explorer) = match chain_id {
+            ChainId::BscMainnet => ("BNB", "https://bscscan.com"),
+            ChainId::BscTestnet => ("tBNB", "https://testnet.bscscan.com"),
+            ChainId::EthereumMainnet => ("ETH", "https://etherscan.io"),
+            ChainId::PolygonMainnet => ("POL", "https://polygonscan.com"),
+            ChainId::ArbitrumMainnet => ("ETH", "https://arbiscan.io"),
+            ChainId::OptimismMainnet => ("ETH", "https://optimistic.etherscan.io"),
+            ChainId::BaseMainnet => ("ETH", "https://basescan.org"),
+            ChainId::AvalancheMainnet => ("AVAX", "https://snowtrace.io"),
+            ChainId::Custom(_) => return None,
+        };
+        Some(ChainMetadata::new(chain_id, chain_id.name(), symbol, 18).with_explorer_url(explorer))
+    }
+}
+
+/// Chains compare by [`value`](ChainId::value), so `Custom(1)` equals
+/// `EthereumMainnet`; see [`ChainId::normalize`].
+impl PartialEq for ChainId {
+    fn eq(&self, other: &Self) -> bool {
+        self.value() == other.value()
+    }
+}
+
+impl Eq for ChainId {}
+
+impl Hash for ChainId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value().hash(state);
     }
 }
 
@@ -117,22 +278,165 @@ impl From<ChainId> for u64 {
 
 impl From<u64> for ChainId {
     fn from(value: u64) -> Self {
-        match value {
-            Self::BSC_MAINNET => ChainId::BscMainnet,
-            Self::BSC_TESTNET => ChainId::BscTestnet,
-            _ => ChainId::Custom(value),
-        }
+        ChainId::Custom(value).normalize()
     }
 }
 
 impl fmt::Display for ChainId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ChainId::BscMainnet => write!(f, "BSC Mainnet (56)"),
-            ChainId::BscTestnet => write!(f, "BSC Testnet (97)"),
+        match self.normalize() {
             ChainId::Custom(id) => write!(f, "Chain {}", id),
+            _ => write!(f, "{} ({})", self.name(), self.value()),
+        }
+    }
+}
+
+/// Display metadata for an EVM network.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{ChainId, ChainMetadata};
+///
+/// let fantom = ChainMetadata::new(ChainId::Custom(250), "Fantom Opera", "FTM", 18)
+///     .with_explorer_url("https://ftmscan.com");
+///
+/// assert_eq!(
+///     fantom.tx_url(&[0xab; 32]).unwrap(),
+///     format!("https://ftmscan.com/tx/0x{}", "ab".repeat(32))
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainMetadata {
+    /// The chain identifier.
+    pub chain_id: ChainId,
+    /// Human-readable network name.
+    pub name: String,
+    /// Ticker of the native currency (e.g. `ETH`, `BNB`).
+    pub native_symbol: String,
+    /// Decimals of the native currency.
+    pub decimals: u8,
+    /// Base URL of the default block explorer, without a trailing slash.
+    pub explorer_url: Option<String>,
+}
+
+impl ChainMetadata {
+    /// Creates metadata without an explorer URL.
+    pub fn new(
+        chain_id: ChainId,
+        name: impl Into<String>,
+        native_symbol: impl Into<String>,
+        decimals: u8,
+    ) -> Self {
+        Self {
+            chain_id,
+            name: name.into(),
+            native_symbol: native_symbol.into(),
+            decimals,
+            explorer_url: None,
         }
     }
+
+    /// Sets the default block explorer URL.
+    pub fn with_explorer_url(mut self, url: impl Into<String>) -> Self {
+        self.explorer_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Returns the explorer URL for a transaction hash, if an explorer is set.
+    pub fn tx_url(&self, tx_hash: &[u8; 32]) -> Option<String> {
+        self.explorer_url
+            .as_ref()
+            .map(|base| format!("{}/tx/0x{}", base, hex::encode(tx_hash)))
+    }
+
+    /// Returns the explorer URL for an address, if an explorer is set.
+    pub fn address_url(&self, address: &Address) -> Option<String> {
+        self.explorer_url
+            .as_ref()
+            .map(|base| format!("{}/address/{}", base, address.to_checksum_string()))
+    }
+}
+
+/// A lookup table of [`ChainMetadata`] by chain ID.
+///
+/// [`ChainRegistry::new`] is pre-populated with every named [`ChainId`]; applications
+/// add their own networks with [`register`](ChainRegistry::register).
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{ChainId, ChainMetadata, ChainRegistry};
+///
+/// let mut registry = ChainRegistry::new();
+/// registry.register(ChainMetadata::new(ChainId::Custom(250), "Fantom Opera", "FTM", 18));
+///
+/// assert_eq!(registry.get(ChainId::from(250)).unwrap().native_symbol, "FTM");
+/// assert_eq!(registry.get(ChainId::BscMainnet).unwrap().native_symbol, "BNB");
+/// assert!(registry.get(ChainId::Custom(999)).is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChainRegistry {
+    chains: HashMap<u64, ChainMetadata>,
+}
+
+impl ChainRegistry {
+    /// Creates a registry containing the built-in chains.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        for chain_id in ChainId::KNOWN {
+            if let Some(metadata) = chain_id.metadata() {
+                registry.register(metadata);
+            }
+        }
+        registry
+    }
+
+    /// Creates a registry with no chains.
+    pub fn empty() -> Self {
+        Self {
+            chains: HashMap::new(),
+        }
+    }
+
+    /// Adds or replaces the metadata for a chain, returning the previous entry.
+    pub fn register(&mut self, metadata: ChainMetadata) -> Option<ChainMetadata> {
+        self.chains.insert(metadata.chain_id.value(), metadata)
+    }
+
+    /// Looks up a chain by ID.
+    ///
+    /// Named variants and `Custom` with the same numeric value resolve to the same entry.
+    pub fn get(&self, chain_id: ChainId) -> Option<&ChainMetadata> {
+        self.chains.get(&chain_id.value())
+    }
+
+    /// Returns `true` if the chain is registered.
+    pub fn contains(&self, chain_id: ChainId) -> bool {
+        self.chains.contains_key(&chain_id.value())
+    }
+
+    /// Returns the number of registered chains.
+    pub fn len(&self) -> usize {
+        self.chains.len()
+    }
+
+    /// Returns `true` if no chains are registered.
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+
+    /// Iterates over the registered chains in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &ChainMetadata> {
+        self.chains.values()
+    }
+}
+
+impl Default for ChainRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +484,16 @@ mod tests {
         assert_eq!(chain_id, ChainId::BscTestnet);
     }
 
+    #[test]
+    fn test_from_u64_named_chains() {
+        assert_eq!(ChainId::from(1u64), ChainId::EthereumMainnet);
+        assert_eq!(ChainId::from(137u64), ChainId::PolygonMainnet);
+        assert_eq!(ChainId::from(42161u64), ChainId::ArbitrumMainnet);
+        assert_eq!(ChainId::from(10u64), ChainId::OptimismMainnet);
+        assert_eq!(ChainId::from(8453u64), ChainId::BaseMainnet);
+        assert_eq!(ChainId::from(43114u64), ChainId::AvalancheMainnet);
+    }
+
     #[test]
     fn test_from_u64_custom() {
         let chain_id = ChainId::from(1u64);
@@ -189,6 +503,22 @@ mod tests {
         assert_eq!(chain_id, ChainId::Custom(137));
     }
 
+    #[test]
+    fn test_from_u64_unknown() {
+        assert!(matches!(ChainId::from(250u64), ChainId::Custom(250)));
+        assert!(matches!(
+            ChainId::from(11155111u64),
+            ChainId::Custom(11155111)
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_known() {
+        for chain_id in ChainId::KNOWN {
+            assert_eq!(ChainId::from(chain_id.value()), chain_id);
+        }
+    }
+
     // ==================== Into<u64> Tests ====================
 
     #[test]
@@ -229,13 +559,22 @@ mod tests {
         assert_eq!(original, recovered);
     }
 
+    #[test]
+    fn test_round_trip_unknown() {
+        let original = ChainId::Custom(250);
+        let value: u64 = original.into();
+        let recovered = ChainId::from(value);
+        assert_eq!(original, recovered);
+    }
+
     // ==================== Name Tests ====================
 
     #[test]
     fn test_name() {
         assert_eq!(ChainId::BscMainnet.name(), "BSC Mainnet");
         assert_eq!(ChainId::BscTestnet.name(), "BSC Testnet");
-        assert_eq!(ChainId::Custom(1).name(), "Custom");
+        assert_eq!(ChainId::EthereumMainnet.name(), "Ethereum Mainnet");
+        assert_eq!(ChainId::Custom(250).name(), "Custom");
     }
 
     // ==================== is_testnet Tests ====================
@@ -254,8 +593,40 @@ mod tests {
     fn test_display() {
         assert_eq!(ChainId::BscMainnet.to_string(), "BSC Mainnet (56)");
         assert_eq!(ChainId::BscTestnet.to_string(), "BSC Testnet (97)");
-        assert_eq!(ChainId::Custom(1).to_string(), "Chain 1");
-        assert_eq!(ChainId::Custom(137).to_string(), "Chain 137");
+        assert_eq!(ChainId::Custom(250).to_string(), "Chain 250");
+        assert_eq!(ChainId::Custom(11155111).to_string(), "Chain 11155111");
+        assert_eq!(ChainId::PolygonMainnet.to_string(), "Polygon (137)");
+        assert_eq!(
+            ChainId::AvalancheMainnet.to_string(),
+            "Avalanche C-Chain (43114)"
+        );
+    }
+
+    // ==================== Normalization Tests ====================
+
+    #[test]
+    fn test_custom_known_value_behaves_as_named() {
+        for named in ChainId::KNOWN {
+            let custom = ChainId::Custom(named.value());
+            assert_eq!(format!("{:?}", custom.normalize()), format!("{:?}", named));
+            assert_eq!(custom, named);
+            assert_eq!(custom.name(), named.name());
+            assert_eq!(custom.is_testnet(), named.is_testnet());
+            assert_eq!(custom.is_custom(), named.is_custom());
+            assert_eq!(custom.metadata(), named.metadata());
+            assert_eq!(custom.to_string(), named.to_string());
+        }
+        assert!(ChainId::Custom(97).is_testnet());
+        assert!(!ChainId::Custom(97).is_custom());
+    }
+
+    #[test]
+    fn test_custom_known_value_hash_map_lookup() {
+        use std::collections::HashMap;
+
+        let mut names = HashMap::new();
+        names.insert(ChainId::Custom(97), ChainId::Custom(97).name());
+        assert_eq!(names[&ChainId::BscTestnet], ChainId::BscTestnet.name());
     }
 
     // ==================== Debug Tests ====================
@@ -276,8 +647,12 @@ mod tests {
         assert_eq!(ChainId::Custom(1), ChainId::Custom(1));
 
         assert_ne!(ChainId::BscMainnet, ChainId::BscTestnet);
-        assert_ne!(ChainId::BscMainnet, ChainId::Custom(56)); // Different variants!
         assert_ne!(ChainId::Custom(1), ChainId::Custom(2));
+
+        // Named variants equal `Custom` with the same value
+        assert_eq!(ChainId::BscMainnet, ChainId::Custom(56));
+        assert_eq!(ChainId::Custom(1), ChainId::EthereumMainnet);
+        assert_ne!(ChainId::Custom(1), ChainId::BscMainnet);
     }
 
     // ==================== Clone/Copy Tests ====================
@@ -305,5 +680,140 @@ mod tests {
         assert!(set.contains(&ChainId::BscTestnet));
         assert!(set.contains(&ChainId::Custom(1)));
         assert!(!set.contains(&ChainId::Custom(2)));
+
+        // One key per numeric value
+        assert!(set.contains(&ChainId::Custom(56)));
+        assert!(set.contains(&ChainId::EthereumMainnet));
+        assert!(!set.insert(ChainId::Custom(97)));
+        assert_eq!(set.len(), 3);
+    }
+
+    // ==================== Metadata Tests ====================
+
+    #[test]
+    fn test_known_chain_values() {
+        let values: Vec<u64> = ChainId::KNOWN.iter().map(ChainId::value).collect();
+        assert_eq!(values, vec![56, 97, 1, 137, 42161, 10, 8453, 43114]);
+    }
+
+    #[test]
+    fn test_metadata_known_chains() {
+        for chain_id in ChainId::KNOWN {
+            let metadata = chain_id.metadata().unwrap();
+            assert_eq!(metadata.chain_id, chain_id);
+            assert_eq!(metadata.name, chain_id.name());
+            assert_eq!(metadata.decimals, 18);
+            assert!(metadata.explorer_url.unwrap().starts_with("https://"));
+        }
+
+        assert_eq!(ChainId::BscMainnet.metadata().unwrap().native_symbol, "BNB");
+        assert_eq!(
+            ChainId::BaseMainnet.metadata().unwrap().native_symbol,
+            "ETH"
+        );
+        assert_eq!(
+            ChainId::AvalancheMainnet.metadata().unwrap().native_symbol,
+            "AVAX"
+        );
+    }
+
+    #[test]
+    fn test_metadata_custom_is_none() {
+        assert!(ChainId::Custom(250).metadata().is_none());
+        assert!(ChainId::Custom(250).is_custom());
+        assert!(!ChainId::BscMainnet.is_custom());
+    }
+
+    #[test]
+    fn test_metadata_explorer_urls() {
+        let metadata = ChainId::EthereumMainnet.metadata().unwrap();
+        let address: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            metadata.tx_url(&[0x01; 32]).unwrap(),
+            format!("https://etherscan.io/tx/0x{}", "01".repeat(32))
+        );
+        assert_eq!(
+            metadata.address_url(&address).unwrap(),
+            "https://etherscan.io/address/0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+        );
+    }
+
+    #[test]
+    fn test_metadata_without_explorer() {
+        let metadata = ChainMetadata::new(ChainId::Custom(250), "Fantom Opera", "FTM", 18);
+        assert!(metadata.tx_url(&[0u8; 32]).is_none());
+    }
+
+    #[test]
+    fn test_metadata_explorer_trailing_slash() {
+        let metadata = ChainMetadata::new(ChainId::Custom(250), "Fantom Opera", "FTM", 18)
+            .with_explorer_url("https://ftmscan.com/");
+        assert_eq!(
+            metadata.explorer_url.as_deref(),
+            Some("https://ftmscan.com")
+        );
+    }
+
+    // ==================== Registry Tests ====================
+
+    #[test]
+    fn test_registry_builtins() {
+        let registry = ChainRegistry::new();
+        assert_eq!(registry.len(), ChainId::KNOWN.len());
+        for chain_id in ChainId::KNOWN {
+            assert!(registry.contains(chain_id));
+        }
+        assert!(!registry.contains(ChainId::Custom(250)));
+    }
+
+    #[test]
+    fn test_registry_register_custom() {
+        let mut registry = ChainRegistry::new();
+        let previous = registry.register(
+            ChainMetadata::new(ChainId::Custom(250), "Fantom Opera", "FTM", 18)
+                .with_explorer_url("https://ftmscan.com"),
+        );
+
+        assert!(previous.is_none());
+        assert_eq!(
+            registry.get(ChainId::Custom(250)).unwrap().name,
+            "Fantom Opera"
+        );
+    }
+
+    #[test]
+    fn test_registry_replace_returns_previous() {
+        let mut registry = ChainRegistry::new();
+        let previous = registry
+            .register(ChainMetadata::new(
+                ChainId::BscMainnet,
+                "BNB Chain",
+                "BNB",
+                18,
+            ))
+            .unwrap();
+
+        assert_eq!(previous.name, "BSC Mainnet");
+        assert_eq!(registry.get(ChainId::BscMainnet).unwrap().name, "BNB Chain");
+    }
+
+    #[test]
+    fn test_registry_lookup_by_value() {
+        let registry = ChainRegistry::new();
+        assert_eq!(
+            registry.get(ChainId::Custom(1)).unwrap().chain_id,
+            ChainId::EthereumMainnet
+        );
+    }
+
+    #[test]
+    fn test_registry_empty() {
+        let registry = ChainRegistry::empty();
+        assert!(registry.is_empty());
+        assert_eq!(registry.iter().count(), 0);
+        assert_eq!(ChainRegistry::default().len(), ChainId::KNOWN.len());
     }
 }
@@ -0,0 +1,890 @@
+//! Human-readable transaction decoding.
+//!
+//! Turns raw calldata into a structured [`Action`] and a one-line description such as
+//! `transfer 12.5 USDT to 0x742d…` for confirmation screens, so users see what they
+//! are about to sign instead of hex.
+//!
+//! [`TransactionDecoder`] knows the ERC-20, ERC-721 and ERC-1155 transfer and approval
+//! functions out of the box. Register token contracts to get symbols and decimals, and
+//! add any other function signatures your dApp uses.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::decoder::{TokenInfo, TransactionDecoder};
+//! use khodpay_signing::{erc20, Address, ChainId, Wei, U256};
+//!
+//! let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let to: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//!
+//! let mut decoder = TransactionDecoder::for_chain(ChainId::BscMainnet);
+//! decoder.add_token(usdt, TokenInfo::erc20("USDT", 18));
+//!
+//! let data = erc20::encode_transfer(&to, U256::from(12_500_000_000_000_000_000u128));
+//! let summary = decoder.decode(Some(usdt), Wei::ZERO, &data);
+//!
+//! assert_eq!(
+//!     summary.description,
+//!     "transfer 12.5 USDT to 0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+//! );
+//! ```
+
+use crate::abi::{Function, Token};
+use crate::{
+    erc1155, erc20, erc721, format_units, Address, ChainId, Eip1559Transaction, Result, Wei,
+};
+use primitive_types::U256;
+use std::collections::HashMap;
+
+/// Function signatures known to every [`TransactionDecoder`].
+pub const BUILTIN_SIGNATURES: &[&str] = &[
+    "transfer(address to,uint256 amount)",
+    "approve(address spender,uint256 amount)",
+    "transferFrom(address from,address to,uint256 amount)",
+    "safeTransferFrom(address from,address to,uint256 tokenId)",
+    "safeTransferFrom(address from,address to,uint256 tokenId,bytes data)",
+    "setApprovalForAll(address operator,bool approved)",
+    "safeTransferFrom(address from,address to,uint256 id,uint256 amount,bytes data)",
+    "safeBatchTransferFrom(address from,address to,uint256[] ids,uint256[] amounts,bytes data)",
+];
+
+/// The token standard a contract implements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStandard {
+    /// Fungible ERC-20 / BEP-20 token.
+    Erc20,
+    /// Non-fungible ERC-721 token.
+    Erc721,
+    /// Multi-token ERC-1155 contract.
+    Erc1155,
+}
+
+/// Display information for a token contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// Token symbol or collection name.
+    pub symbol: String,
+    /// Decimals used to format amounts (0 for NFTs).
+    pub decimals: u8,
+    /// Token standard.
+    pub standard: TokenStandard,
+}
+
+impl TokenInfo {
+    /// Describes an ERC-20 token.
+    pub fn erc20(symbol: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals,
+            standard: TokenStandard::Erc20,
+        }
+    }
+
+    /// Describes an ERC-721 collection.
+    pub fn erc721(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals: 0,
+            standard: TokenStandard::Erc721,
+        }
+    }
+
+    /// Describes an ERC-1155 collection.
+    pub fn erc1155(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals: 0,
+            standard: TokenStandard::Erc1155,
+        }
+    }
+}
+
+/// What a transaction does, decoded from its recipient, value and calldata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Plain transfer of the native currency.
+    NativeTransfer {
+        /// Recipient.
+        to: Address,
+        /// Amount sent.
+        value: Wei,
+    },
+    /// Contract deployment.
+    ContractCreation {
+        /// Native value sent to the constructor.
+        value: Wei,
+        /// Length of the init code in bytes.
+        code_size: usize,
+    },
+    /// ERC-20 `transfer` or `transferFrom`.
+    TokenTransfer {
+        /// Token contract.
+        token: Address,
+        /// Source account for `transferFrom`, `None` for `transfer`.
+        from: Option<Address>,
+        /// Recipient.
+        to: Address,
+        /// Raw amount in the token's smallest unit.
+        amount: U256,
+    },
+    /// ERC-20 `approve`.
+    TokenApproval {
+        /// Token contract.
+        token: Address,
+        /// Approved spender.
+        spender: Address,
+        /// Raw allowance in the token's smallest unit.
+        amount: U256,
+    },
+    /// ERC-721 / ERC-1155 transfer.
+    NftTransfer {
+        /// NFT contract.
+        contract: Address,
+        /// Current owner.
+        from: Address,
+        /// Recipient.
+        to: Address,
+        /// Token ID.
+        token_id: U256,
+        /// Number of tokens (ERC-1155 only).
+        amount: Option<U256>,
+    },
+    /// ERC-721 `approve` of a single token.
+    NftApproval {
+        /// NFT contract.
+        contract: Address,
+        /// Approved address.
+        approved: Address,
+        /// Token ID.
+        token_id: U256,
+    },
+    /// `setApprovalForAll`.
+    ApprovalForAll {
+        /// NFT contract.
+        contract: Address,
+        /// Operator being granted or revoked.
+        operator: Address,
+        /// `true` to grant, `false` to revoke.
+        approved: bool,
+    },
+    /// A call to a known function without a dedicated description.
+    ContractCall {
+        /// Called contract.
+        to: Address,
+        /// Function name.
+        function: String,
+        /// Named arguments in declaration order.
+        args: Vec<(String, Token)>,
+        /// Native value sent with the call.
+        value: Wei,
+    },
+    /// A call whose function is not in the database, or whose calldata did not decode.
+    Unknown {
+        /// Called contract.
+        to: Address,
+        /// First four bytes of calldata, if present.
+        selector: Option<[u8; 4]>,
+        /// Native value sent with the call.
+        value: Wei,
+    },
+}
+
+/// A decoded transaction: the structured action and its description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionSummary {
+    /// The decoded action.
+    pub action: Action,
+    /// One-line human-readable description of the action.
+    pub description: String,
+}
+
+/// Decodes calldata into [`TransactionSummary`] values.
+#[derive(Debug, Clone)]
+pub struct TransactionDecoder {
+    functions: HashMap<[u8; 4], Function>,
+    tokens: HashMap<Address, TokenInfo>,
+    native_symbol: String,
+    native_decimals: u8,
+}
+
+impl TransactionDecoder {
+    /// Creates a decoder with the built-in functions and `ETH` as the native currency.
+    pub fn new() -> Self {
+        let functions = BUILTIN_SIGNATURES
+            .iter()
+            .map(|sig| Function::parse(sig).expect("built-in signature is valid"))
+            .map(|f| (f.selector(), f))
+            .collect();
+
+        Self {
+            functions,
+            tokens: HashMap::new(),
+            native_symbol: "ETH".to_string(),
+            native_decimals: 18,
+        }
+    }
+
+    /// Creates a decoder using the native currency of `chain_id`.
+    ///
+    /// Falls back to `ETH` for chains without built-in metadata.
+    pub fn for_chain(chain_id: ChainId) -> Self {
+        let mut decoder = Self::new();
+        if let Some(metadata) = chain_id.metadata() {
+            decoder.native_symbol = metadata.native_symbol;
+            decoder.native_decimals = metadata.decimals;
+        }
+        decoder
+    }
+
+    /// Adds a function from its human-readable signature.
+    ///
+    /// Replaces any function with the same selector, including built-ins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature does not parse.
+    pub fn add_function(&mut self, signature: &str) -> Result<&mut Self> {
+        let function = Function::parse(signature)?;
+        self.functions.insert(function.selector(), function);
+        Ok(self)
+    }
+
+    /// Registers display information for a token contract.
+    pub fn add_token(&mut self, address: Address, info: TokenInfo) -> &mut Self {
+        self.tokens.insert(address, info);
+        self
+    }
+
+    /// Returns the function registered for `selector`, if any.
+    pub fn function(&self, selector: &[u8; 4]) -> Option<&Function> {
+        self.functions.get(selector)
+    }
+
+    /// Decodes an EIP-1559 transaction.
+    pub fn decode_transaction(&self, tx: &Eip1559Transaction) -> TransactionSummary {
+        self.decode(tx.to, tx.value, &tx.data)
+    }
+
+    /// Decodes a transaction from its recipient, value and calldata.
+    ///
+    /// Never fails: calldata that cannot be decoded yields [`Action::Unknown`].
+    pub fn decode(&self, to: Option<Address>, value: Wei, data: &[u8]) -> TransactionSummary {
+        let action = self.action(to, value, data);
+        let description = self.describe(&action);
+        TransactionSummary {
+            action,
+            description,
+        }
+    }
+
+    fn action(&self, to: Option<Address>, value: Wei, data: &[u8]) -> Action {
+        let Some(to) = to else {
+            return Action::ContractCreation {
+                value,
+                code_size: data.len(),
+            };
+        };
+        if data.is_empty() {
+            return Action::NativeTransfer { to, value };
+        }
+
+        let selector: Option<[u8; 4]> = data.get(..4).and_then(|s| s.try_into().ok());
+        let unknown = Action::Unknown {
+            to,
+            selector,
+            value,
+        };
+        let Some((function, args)) = selector
+            .and_then(|sel| self.functions.get(&sel))
+            .and_then(|f| f.decode_input(data).ok().map(|args| (f, args)))
+        else {
+            return unknown;
+        };
+
+        self.standard_action(to, value, function, &args)
+            .unwrap_or_else(|| Action::ContractCall {
+                to,
+                function: function.name.clone(),
+                args: function
+                    .inputs
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .zip(args)
+                    .collect(),
+                value,
+            })
+    }
+
+    /// Maps token standard functions to dedicated actions.
+    fn standard_action(
+        &self,
+        contract: Address,
+        value: Wei,
+        function: &Function,
+        args: &[Token],
+    ) -> Option<Action> {
+        if !value.is_zero() {
+            return None;
+        }
+        let standard = self.tokens.get(&contract).map(|t| t.standard);
+        let is_nft = matches!(
+            standard,
+            Some(TokenStandard::Erc721 | TokenStandard::Erc1155)
+        );
+
+        match (function.selector(), args) {
+            (erc20::TRANSFER_SELECTOR, [Token::Address(to), Token::Uint(amount)]) if !is_nft => {
+                Some(Action::TokenTransfer {
+                    token: contract,
+                    from: None,
+                    to: *to,
+                    amount: *amount,
+                })
+            }
+            (erc20::APPROVE_SELECTOR, [Token::Address(spender), Token::Uint(amount)]) => {
+                Some(if is_nft {
+                    Action::NftApproval {
+                        contract,
+                        approved: *spender,
+                        token_id: *amount,
+                    }
+                } else {
+                    Action::TokenApproval {
+                        token: contract,
+                        spender: *spender,
+                        amount: *amount,
+                    }
+                })
+            }
+            (
+                erc20::TRANSFER_FROM_SELECTOR,
+                [Token::Address(from), Token::Address(to), Token::Uint(amount)],
+            ) => Some(if is_nft {
+                Action::NftTransfer {
+                    contract,
+                    from: *from,
+                    to: *to,
+                    token_id: *amount,
+                    amount: None,
+                }
+            } else {
+                Action::TokenTransfer {
+                    token: contract,
+                    from: Some(*from),
+                    to: *to,
+                    amount: *amount,
+                }
+            }),
+            (
+                erc721::SAFE_TRANSFER_FROM_SELECTOR | erc721::SAFE_TRANSFER_FROM_WITH_DATA_SELECTOR,
+                [Token::Address(from), Token::Address(to), Token::Uint(token_id), ..],
+            ) => Some(Action::NftTransfer {
+                contract,
+                from: *from,
+                to: *to,
+                token_id: *token_id,
+                amount: None,
+            }),
+            (
+                erc1155::SAFE_TRANSFER_FROM_SELECTOR,
+                [Token::Address(from), Token::Address(to), Token::Uint(id), Token::Uint(amount), _],
+            ) => Some(Action::NftTransfer {
+                contract,
+                from: *from,
+                to: *to,
+                token_id: *id,
+                amount: Some(*amount),
+            }),
+            (
+                erc721::SET_APPROVAL_FOR_ALL_SELECTOR,
+                [Token::Address(operator), Token::Bool(approved)],
+            ) => Some(Action::ApprovalForAll {
+                contract,
+                operator: *operator,
+                approved: *approved,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Renders an action as a one-line description.
+    pub fn describe(&self, action: &Action) -> String {
+        match action {
+            Action::NativeTransfer { to, value } => {
+                format!("send {} to {}", self.native_amount(*value), to)
+            }
+            Action::ContractCreation { value, code_size } => {
+                let mut text = format!("deploy a contract ({} bytes of code)", code_size);
+                if !value.is_zero() {
+                    text.push_str(&format!(" with {}", self.native_amount(*value)));
+                }
+                text
+            }
+            Action::TokenTransfer {
+                token,
+                from,
+                to,
+                amount,
+            } => match from {
+                Some(from) => format!(
+                    "transfer {} from {} to {}",
+                    self.token_amount(token, *amount),
+                    from,
+                    to
+                ),
+                None => format!("transfer {} to {}", self.token_amount(token, *amount), to),
+            },
+            Action::TokenApproval {
+                token,
+                spender,
+                amount,
+            } => {
+                if amount.is_zero() {
+                    format!(
+                        "revoke {}'s allowance for {}",
+                        spender,
+                        self.token_name(token)
+                    )
+                } else if *amount == U256::MAX {
+                    format!(
+                        "approve {} to spend unlimited {}",
+                        spender,
+                        self.token_name(token)
+                    )
+                } else {
+                    format!(
+                        "approve {} to spend {}",
+                        spender,
+                        self.token_amount(token, *amount)
+                    )
+                }
+            }
+            Action::NftTransfer {
+                contract,
+                from,
+                to,
+                token_id,
+                amount,
+            } => {
+                let count = match amount {
+                    Some(amount) => format!("{} × ", amount),
+                    None => String::new(),
+                };
+                format!(
+                    "transfer {}{} #{} from {} to {}",
+                    count,
+                    self.token_name(contract),
+                    token_id,
+                    from,
+                    to
+                )
+            }
+            Action::NftApproval {
+                contract,
+                approved,
+                token_id,
+            } => format!(
+                "approve {} to transfer {} #{}",
+                approved,
+                self.token_name(contract),
+                token_id
+            ),
+            Action::ApprovalForAll {
+                contract,
+                operator,
+                approved,
+            } => {
+                if *approved {
+                    format!(
+                        "allow {} to transfer all of your {}",
+                        operator,
+                        self.token_name(contract)
+                    )
+                } else {
+                    format!(
+                        "revoke {}'s approval for all of your {}",
+                        operator,
+                        self.token_name(contract)
+                    )
+                }
+            }
+            Action::ContractCall {
+                to,
+                function,
+                args,
+                value,
+            } => {
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|(name, token)| {
+                        if name.is_empty() {
+                            format_token(token)
+                        } else {
+                            format!("{}={}", name, format_token(token))
+                        }
+                    })
+                    .collect();
+                let mut text = format!("call {}({}) on {}", function, args.join(", "), to);
+                if !value.is_zero() {
+                    text.push_str(&format!(" with {}", self.native_amount(*value)));
+                }
+                text
+            }
+            Action::Unknown {
+                to,
+                selector,
+                value,
+            } => {
+                let mut text = match selector {
+                    Some(sel) => format!("call unknown function 0x{} on {}", hex::encode(sel), to),
+                    None => format!("call {} with malformed data", to),
+                };
+                if !value.is_zero() {
+                    text.push_str(&format!(" with {}", self.native_amount(*value)));
+                }
+                text
+            }
+        }
+    }
+
+    fn native_amount(&self, value: Wei) -> String {
+        format!(
+            "{} {}",
+            format_units(value.as_u256(), self.native_decimals),
+            self.native_symbol
+        )
+    }
+
+    fn token_amount(&self, token: &Address, amount: U256) -> String {
+        match self.tokens.get(token) {
+            Some(info) => format!("{} {}", format_units(amount, info.decimals), info.symbol),
+            None => format!("{} units of token {}", amount, token),
+        }
+    }
+
+    fn token_name(&self, token: &Address) -> String {
+        match self.tokens.get(token) {
+            Some(info) => info.symbol.clone(),
+            None => format!("token {}", token),
+        }
+    }
+}
+
+impl Default for TransactionDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => address.to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Int(_) => token
+            .as_i128()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "<int256>".to_string()),
+        Token::Bool(value) => value.to_string(),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::String(s) => format!("{:?}", s),
+        Token::Array(items) | Token::FixedArray(items) => {
+            let items: Vec<String> = items.iter().map(format_token).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Token::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(format_token).collect();
+            format!("({})", items.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::encode_with_selector;
+
+    fn usdt() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    fn nft() -> Address {
+        Address::from_bytes([0x99; 20])
+    }
+
+    fn alice() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn bob() -> Address {
+        Address::from_bytes([0x11; 20])
+    }
+
+    fn decoder() -> TransactionDecoder {
+        let mut decoder = TransactionDecoder::for_chain(ChainId::BscMainnet);
+        decoder
+            .add_token(usdt(), TokenInfo::erc20("USDT", 18))
+            .add_token(nft(), TokenInfo::erc721("Punks"));
+        decoder
+    }
+
+    // ─── native ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_native_transfer() {
+        let summary = decoder().decode(Some(alice()), Wei::from_gwei(1_500_000_000), &[]);
+        assert_eq!(
+            summary.action,
+            Action::NativeTransfer {
+                to: alice(),
+                value: Wei::from_gwei(1_500_000_000)
+            }
+        );
+        assert_eq!(summary.description, format!("send 1.5 BNB to {}", alice()));
+    }
+
+    #[test]
+    fn test_default_native_symbol_is_eth() {
+        let summary = TransactionDecoder::new().decode(Some(alice()), Wei::from_ether(2), &[]);
+        assert_eq!(summary.description, format!("send 2 ETH to {}", alice()));
+    }
+
+    #[test]
+    fn test_contract_creation() {
+        let summary = decoder().decode(None, Wei::ZERO, &[0x60, 0x80, 0x60, 0x40]);
+        assert_eq!(summary.description, "deploy a contract (4 bytes of code)");
+    }
+
+    // ─── ERC-20 ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_erc20_transfer() {
+        let data = erc20::encode_transfer(&alice(), U256::from(12_500_000_000_000_000_000u128));
+        let summary = decoder().decode(Some(usdt()), Wei::ZERO, &data);
+
+        assert!(matches!(
+            summary.action,
+            Action::TokenTransfer { from: None, .. }
+        ));
+        assert_eq!(
+            summary.description,
+            format!("transfer 12.5 USDT to {}", alice())
+        );
+    }
+
+    #[test]
+    fn test_erc20_transfer_from() {
+        let data = erc20::encode_transfer_from(&bob(), &alice(), U256::exp10(18));
+        let summary = decoder().decode(Some(usdt()), Wei::ZERO, &data);
+
+        assert_eq!(
+            summary.description,
+            format!("transfer 1 USDT from {} to {}", bob(), alice())
+        );
+    }
+
+    #[test]
+    fn test_erc20_transfer_unknown_token() {
+        let token = Address::from_bytes([0x22; 20]);
+        let data = erc20::encode_transfer(&alice(), U256::from(5u64));
+        let summary = decoder().decode(Some(token), Wei::ZERO, &data);
+
+        assert_eq!(
+            summary.description,
+            format!("transfer 5 units of token {} to {}", token, alice())
+        );
+    }
+
+    #[test]
+    fn test_erc20_approvals() {
+        let d = decoder();
+
+        let unlimited = d.decode(
+            Some(usdt()),
+            Wei::ZERO,
+            &erc20::encode_approve(&bob(), U256::MAX),
+        );
+        assert_eq!(
+            unlimited.description,
+            format!("approve {} to spend unlimited USDT", bob())
+        );
+
+        let limited = d.decode(
+            Some(usdt()),
+            Wei::ZERO,
+            &erc20::encode_approve(&bob(), U256::from(25u64) * U256::exp10(17)),
+        );
+        assert_eq!(
+            limited.description,
+            format!("approve {} to spend 2.5 USDT", bob())
+        );
+
+        let revoke = d.decode(
+            Some(usdt()),
+            Wei::ZERO,
+            &erc20::encode_approve(&bob(), U256::zero()),
+        );
+        assert_eq!(
+            revoke.description,
+            format!("revoke {}'s allowance for USDT", bob())
+        );
+    }
+
+    // ─── NFTs ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_erc721_transfers() {
+        let d = decoder();
+
+        let safe = erc721::encode_safe_transfer_from(&bob(), &alice(), U256::from(1234u64));
+        assert_eq!(
+            d.decode(Some(nft()), Wei::ZERO, &safe).description,
+            format!("transfer Punks #1234 from {} to {}", bob(), alice())
+        );
+
+        // transferFrom shares its selector with ERC-20; the registered standard decides
+        let plain = erc721::encode_transfer_from(&bob(), &alice(), U256::from(7u64));
+        assert!(matches!(
+            d.decode(Some(nft()), Wei::ZERO, &plain).action,
+            Action::NftTransfer { amount: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_erc721_approve() {
+        let data = erc721::encode_approve(&bob(), U256::from(3u64));
+        assert_eq!(
+            decoder().decode(Some(nft()), Wei::ZERO, &data).description,
+            format!("approve {} to transfer Punks #3", bob())
+        );
+    }
+
+    #[test]
+    fn test_set_approval_for_all() {
+        let d = decoder();
+
+        let grant = erc721::encode_set_approval_for_all(&bob(), true);
+        assert_eq!(
+            d.decode(Some(nft()), Wei::ZERO, &grant).description,
+            format!("allow {} to transfer all of your Punks", bob())
+        );
+
+        let revoke = erc721::encode_set_approval_for_all(&bob(), false);
+        assert_eq!(
+            d.decode(Some(nft()), Wei::ZERO, &revoke).description,
+            format!("revoke {}'s approval for all of your Punks", bob())
+        );
+    }
+
+    #[test]
+    fn test_erc1155_transfer() {
+        let contract = Address::from_bytes([0x33; 20]);
+        let mut d = decoder();
+        d.add_token(contract, TokenInfo::erc1155("Items"));
+
+        let data = erc1155::encode_safe_transfer_from(
+            &bob(),
+            &alice(),
+            U256::from(5u64),
+            U256::from(3u64),
+            &[],
+        );
+        assert_eq!(
+            d.decode(Some(contract), Wei::ZERO, &data).description,
+            format!("transfer 3 × Items #5 from {} to {}", bob(), alice())
+        );
+    }
+
+    // ─── generic calls ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_custom_function() {
+        let mut d = decoder();
+        d.add_function("deposit(uint256 amount,bool stake)")
+            .unwrap();
+
+        let function = Function::parse("deposit(uint256,bool)").unwrap();
+        let data = function
+            .encode_input(&[Token::Uint(U256::from(10u64)), Token::Bool(true)])
+            .unwrap();
+        let summary = d.decode(Some(bob()), Wei::from_ether(1), &data);
+
+        assert!(matches!(summary.action, Action::ContractCall { .. }));
+        assert_eq!(
+            summary.description,
+            format!(
+                "call deposit(amount=10, stake=true) on {} with 1 BNB",
+                bob()
+            )
+        );
+    }
+
+    #[test]
+    fn test_unknown_selector() {
+        let data = encode_with_selector([0xde, 0xad, 0xbe, 0xef], &[]);
+        let summary = decoder().decode(Some(bob()), Wei::ZERO, &data);
+
+        assert_eq!(
+            summary.action,
+            Action::Unknown {
+                to: bob(),
+                selector: Some([0xde, 0xad, 0xbe, 0xef]),
+                value: Wei::ZERO
+            }
+        );
+        assert_eq!(
+            summary.description,
+            format!("call unknown function 0xdeadbeef on {}", bob())
+        );
+    }
+
+    #[test]
+    fn test_malformed_known_selector_is_unknown() {
+        let mut data = erc20::TRANSFER_SELECTOR.to_vec();
+        data.extend_from_slice(&[0u8; 10]);
+        let summary = decoder().decode(Some(usdt()), Wei::ZERO, &data);
+
+        assert!(matches!(summary.action, Action::Unknown { .. }));
+    }
+
+    #[test]
+    fn test_short_calldata() {
+        let summary = decoder().decode(Some(bob()), Wei::ZERO, &[0x01, 0x02]);
+        assert_eq!(
+            summary.description,
+            format!("call {} with malformed data", bob())
+        );
+    }
+
+    #[test]
+    fn test_decode_transaction() {
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(65_000)
+            .to(usdt())
+            .data(erc20::encode_transfer(&alice(), U256::exp10(18)))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            decoder().decode_transaction(&tx).description,
+            format!("transfer 1 USDT to {}", alice())
+        );
+    }
+
+    #[test]
+    fn test_builtin_signatures_parse() {
+        let d = TransactionDecoder::new();
+        assert!(d.function(&erc20::TRANSFER_SELECTOR).is_some());
+        assert!(d
+            .function(&erc1155::SAFE_BATCH_TRANSFER_FROM_SELECTOR)
+            .is_some());
+        assert_eq!(d.functions.len(), BUILTIN_SIGNATURES.len());
+    }
+}
@@ -0,0 +1,196 @@
+//! Batched payouts through the Disperse contract.
+//!
+//! [Disperse](https://disperse.app) is deployed at [`DISPERSE`] on Ethereum, BSC and
+//! most other EVM chains. It pays many recipients in one transaction, so a payroll
+//! or airdrop costs one nonce and one base fee instead of one per recipient:
+//!
+//! - `disperseEther` takes the total as the transaction value and forwards each
+//!   recipient's share, refunding anything left over.
+//! - `disperseToken` pulls the total from the sender with `transferFrom`, so the
+//!   sender first approves [`DISPERSE`] for it, then transfers each share.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::disperse::{self, DISPERSE};
+//! use khodpay_signing::{erc20, Address, U256};
+//!
+//! let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let payouts = [
+//!     (Address::from_bytes([0x11; 20]), U256::from(1_000u64)),
+//!     (Address::from_bytes([0x22; 20]), U256::from(2_500u64)),
+//! ];
+//!
+//! // Approve the total, then call Disperse
+//! let approve = erc20::encode_approve(&DISPERSE, disperse::total(&payouts).unwrap());
+//! let calldata = disperse::encode_disperse_token(&usdt, &payouts);
+//! assert_eq!(calldata[..4], disperse::DISPERSE_TOKEN_SELECTOR);
+//! assert_eq!(disperse::gas_limit(payouts.len()), 140_000);
+//! ```
+
+use primitive_types::U256;
+
+use crate::abi::{encode_with_selector, Token};
+use crate::Address;
+
+/// Address of the Disperse contract (`0xD152f549545093347A162Dce210e7293f1452150`),
+/// the same on every chain it is deployed to.
+pub const DISPERSE: Address = Address::from_bytes([
+    0xd1, 0x52, 0xf5, 0x49, 0x54, 0x50, 0x93, 0x34, 0x7a, 0x16, 0x2d, 0xce, 0x21, 0x0e, 0x72, 0x93,
+    0xf1, 0x45, 0x21, 0x50,
+]);
+
+/// Selector of `disperseEther(address[],uint256[])`.
+pub const DISPERSE_ETHER_SELECTOR: [u8; 4] = [0xe6, 0x3d, 0x38, 0xed];
+
+/// Selector of `disperseToken(address,address[],uint256[])`.
+pub const DISPERSE_TOKEN_SELECTOR: [u8; 4] = [0xc7, 0x3a, 0x2d, 0x60];
+
+/// Gas for a Disperse call before any recipient: the transaction, the calldata
+/// head and the token's `transferFrom`.
+pub const DISPERSE_BASE_GAS: u64 = 60_000;
+
+/// Gas for each recipient, enough for a transfer to an address that has never
+/// held the coin or token.
+pub const DISPERSE_GAS_PER_RECIPIENT: u64 = 40_000;
+
+/// Returns a gas limit for a Disperse call paying `recipients` recipients.
+///
+/// This is an upper bound; `eth_estimateGas` gives a tighter one when most
+/// recipients already hold the coin or token.
+pub fn gas_limit(recipients: usize) -> u64 {
+    let recipients = u64::try_from(recipients).unwrap_or(u64::MAX);
+    DISPERSE_BASE_GAS.saturating_add(DISPERSE_GAS_PER_RECIPIENT.saturating_mul(recipients))
+}
+
+/// Returns the sum of the `payouts`: the value of a `disperseEther` call, or the
+/// allowance a `disperseToken` call needs. `None` on overflow.
+pub fn total(payouts: &[(Address, U256)]) -> Option<U256> {
+    payouts
+        .iter()
+        .try_fold(U256::zero(), |total, (_, value)| total.checked_add(*value))
+}
+
+/// Encodes `disperseEther(recipients, values)` calldata.
+///
+/// Send it to [`DISPERSE`] with the [`total`] as the transaction value.
+pub fn encode_disperse_ether(payouts: &[(Address, U256)]) -> Vec<u8> {
+    let (recipients, values) = split(payouts);
+    encode_with_selector(DISPERSE_ETHER_SELECTOR, &[recipients, values])
+}
+
+/// Encodes `disperseToken(token, recipients, values)` calldata.
+///
+/// Send it to [`DISPERSE`] after approving it for the [`total`] on `token`.
+pub fn encode_disperse_token(token: &Address, payouts: &[(Address, U256)]) -> Vec<u8> {
+    let (recipients, values) = split(payouts);
+    encode_with_selector(
+        DISPERSE_TOKEN_SELECTOR,
+        &[Token::Address(*token), recipients, values],
+    )
+}
+
+/// Splits payouts into the `address[]` and `uint256[]` arguments.
+fn split(payouts: &[(Address, U256)]) -> (Token, Token) {
+    let recipients = payouts.iter().map(|(to, _)| Token::Address(*to)).collect();
+    let values = payouts
+        .iter()
+        .map(|(_, value)| Token::Uint(*value))
+        .collect();
+    (Token::Array(recipients), Token::Array(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{self, ParamType};
+
+    fn payouts() -> Vec<(Address, U256)> {
+        vec![
+            (Address::from_bytes([0x11; 20]), U256::from(1_000u64)),
+            (Address::from_bytes([0x22; 20]), U256::from(2_500u64)),
+        ]
+    }
+
+    #[test]
+    fn test_selectors_and_address() {
+        assert_eq!(
+            DISPERSE_ETHER_SELECTOR,
+            abi::selector("disperseEther(address[],uint256[])")
+        );
+        assert_eq!(
+            DISPERSE_TOKEN_SELECTOR,
+            abi::selector("disperseToken(address,address[],uint256[])")
+        );
+        assert_eq!(
+            DISPERSE.to_checksum_string(),
+            "0xD152f549545093347A162Dce210e7293f1452150"
+        );
+    }
+
+    #[test]
+    fn test_encode_disperse_ether() {
+        let data = encode_disperse_ether(&payouts());
+        assert_eq!(data[..4], DISPERSE_ETHER_SELECTOR);
+        let tokens = abi::decode(
+            &[
+                ParamType::Array(Box::new(ParamType::Address)),
+                ParamType::Array(Box::new(ParamType::Uint(256))),
+            ],
+            &data[4..],
+        )
+        .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Array(vec![
+                    Token::Address(payouts()[0].0),
+                    Token::Address(payouts()[1].0)
+                ]),
+                Token::Array(vec![
+                    Token::Uint(U256::from(1_000u64)),
+                    Token::Uint(U256::from(2_500u64))
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_disperse_token() {
+        let token = Address::from_bytes([0x33; 20]);
+        let data = encode_disperse_token(&token, &payouts());
+        assert_eq!(data[..4], DISPERSE_TOKEN_SELECTOR);
+        let tokens = abi::decode(
+            &[
+                ParamType::Address,
+                ParamType::Array(Box::new(ParamType::Address)),
+                ParamType::Array(Box::new(ParamType::Uint(256))),
+            ],
+            &data[4..],
+        )
+        .unwrap();
+        assert_eq!(tokens[0], Token::Address(token));
+        assert_eq!(
+            tokens[2],
+            Token::Array(vec![
+                Token::Uint(U256::from(1_000u64)),
+                Token::Uint(U256::from(2_500u64))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_total_and_gas_limit() {
+        assert_eq!(total(&payouts()), Some(U256::from(3_500u64)));
+        assert_eq!(total(&[]), Some(U256::zero()));
+        let overflow = [
+            (Address::from_bytes([0x11; 20]), U256::MAX),
+            (Address::from_bytes([0x22; 20]), U256::one()),
+        ];
+        assert_eq!(total(&overflow), None);
+
+        assert_eq!(gas_limit(0), DISPERSE_BASE_GAS);
+        assert_eq!(gas_limit(100), 4_060_000);
+        assert_eq!(gas_limit(usize::MAX), u64::MAX);
+    }
+}
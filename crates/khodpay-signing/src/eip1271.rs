@@ -0,0 +1,313 @@
+//! EIP-1271 contract signature validation.
+//!
+//! Smart-contract wallets (Safe, ERC-4337 accounts, …) cannot produce ECDSA signatures
+//! themselves. [EIP-1271](https://eips.ethereum.org/EIPS/eip-1271) lets them expose
+//! `isValidSignature(bytes32 hash, bytes signature)`, which returns the magic value
+//! `0x1626ba7e` when the signature is acceptable.
+//!
+//! This module encodes and decodes that call. With the `net` feature,
+//! `is_valid_signature` performs it through an `RpcClient` and
+//! `verify_signature` checks a signature for either kind of account.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::eip1271::{
+//!     decode_is_valid_signature, encode_is_valid_signature, encode_signature, MAGIC_VALUE,
+//! };
+//! use khodpay_signing::{Bip44Signer, PrehashPolicy};
+//!
+//! let owner = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+//! let hash = [7u8; 32];
+//! let policy = PrehashPolicy::allow_digests("EIP-1271 example", [hash]).unwrap();
+//! let signature = encode_signature(&owner.sign_prehashed(&hash, &policy).unwrap());
+//!
+//! let calldata = encode_is_valid_signature(&hash, &signature);
+//! // ... eth_call(contract, calldata) ...
+//! # let mut result = [0u8; 32];
+//! # result[..4].copy_from_slice(&MAGIC_VALUE);
+//! assert!(decode_is_valid_signature(&result));
+//! ```
+
+use crate::abi::{encode_with_selector, Token};
+use crate::Signature;
+
+/// Selector of `isValidSignature(bytes32,bytes)`.
+pub const IS_VALID_SIGNATURE_SELECTOR: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Value returned by `isValidSignature` for a valid signature.
+///
+/// Equal to [`IS_VALID_SIGNATURE_SELECTOR`] by definition.
+pub const MAGIC_VALUE: [u8; 4] = IS_VALID_SIGNATURE_SELECTOR;
+
+/// Encodes a signature in the 65-byte `r ‖ s ‖ v` form (`v` = `27` / `28`) that
+/// `ecrecover`-based 1271 implementations expect.
+pub fn encode_signature(signature: &Signature) -> Vec<u8> {
+    let mut bytes = signature.to_bytes();
+    if bytes[64] < 27 {
+        bytes[64] += 27;
+    }
+    bytes.to_vec()
+}
+
+/// Encodes `isValidSignature(hash, signature)` calldata.
+pub fn encode_is_valid_signature(hash: &[u8; 32], signature: &[u8]) -> Vec<u8> {
+    encode_with_selector(
+        IS_VALID_SIGNATURE_SELECTOR,
+        &[
+            Token::FixedBytes(hash.to_vec()),
+            Token::Bytes(signature.to_vec()),
+        ],
+    )
+}
+
+/// Returns `true` if `isValidSignature` return data carries [`MAGIC_VALUE`].
+///
+/// The return value is an ABI-encoded `bytes4`, i.e. the magic value left-aligned in
+/// a 32-byte word. Anything else, including empty data from a non-1271 contract, is
+/// treated as invalid.
+pub fn decode_is_valid_signature(data: &[u8]) -> bool {
+    data.len() >= 32 && data[..4] == MAGIC_VALUE && data[4..32].iter().all(|&b| b == 0)
+}
+
+#[cfg(feature = "net")]
+pub use net::{is_valid_signature, verify_signature};
+
+#[cfg(feature = "net")]
+mod net {
+    use super::{decode_is_valid_signature, encode_is_valid_signature};
+    use crate::rpc::{BlockId, RpcClient};
+    use crate::{recover_signer, Address, Error, Result, Signature};
+
+    /// Calls `isValidSignature(hash, signature)` on `contract`.
+    ///
+    /// A revert is reported as `Ok(false)`, since many implementations revert on bad
+    /// signatures instead of returning a non-magic value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RPC request itself fails.
+    pub async fn is_valid_signature(
+        client: &RpcClient,
+        contract: Address,
+        hash: &[u8; 32],
+        signature: &[u8],
+    ) -> Result<bool> {
+        let calldata = encode_is_valid_signature(hash, signature);
+        match client.call(contract, &calldata, BlockId::Latest).await {
+            Ok(data) => Ok(decode_is_valid_signature(&data)),
+            Err(Error::JsonRpcError { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Verifies a signature for an externally owned account or a contract account.
+    ///
+    /// If `signer` has no code, the 65-byte signature is checked with `ecrecover`;
+    /// otherwise the check is delegated to the contract via [`is_valid_signature`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an RPC request fails.
+    pub async fn verify_signature(
+        client: &RpcClient,
+        signer: Address,
+        hash: &[u8; 32],
+        signature: &[u8],
+    ) -> Result<bool> {
+        let code = client.get_code(signer, BlockId::Latest).await?;
+        if !code.is_empty() {
+            return is_valid_signature(client, signer, hash, signature).await;
+        }
+
+        Ok(Signature::from_bytes(signature)
+            .and_then(|sig| recover_signer(hash, &sig).ok())
+            .is_some_and(|recovered| recovered == signer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{self, ParamType};
+    use crate::Bip44Signer;
+
+    fn magic_word() -> Vec<u8> {
+        let mut word = vec![0u8; 32];
+        word[..4].copy_from_slice(&MAGIC_VALUE);
+        word
+    }
+
+    // ─── encoding ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(
+            IS_VALID_SIGNATURE_SELECTOR,
+            abi::selector("isValidSignature(bytes32,bytes)")
+        );
+    }
+
+    #[test]
+    fn test_encode_signature_uses_legacy_v() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let signature = signer.sign_hash(&[7u8; 32]).unwrap();
+        let encoded = encode_signature(&signature);
+
+        assert_eq!(encoded.len(), 65);
+        assert_eq!(encoded[64], signature.v + 27);
+        assert_eq!(&encoded[..32], &signature.r);
+    }
+
+    #[test]
+    fn test_encode_is_valid_signature_roundtrip() {
+        let signature = vec![0xab; 65];
+        let calldata = encode_is_valid_signature(&[0x11; 32], &signature);
+
+        assert_eq!(&calldata[..4], &IS_VALID_SIGNATURE_SELECTOR);
+        let tokens = abi::decode(
+            &[ParamType::FixedBytes(32), ParamType::Bytes],
+            &calldata[4..],
+        )
+        .unwrap();
+        assert_eq!(tokens[0], Token::FixedBytes(vec![0x11; 32]));
+        assert_eq!(tokens[1], Token::Bytes(signature));
+    }
+
+    // ─── decoding ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_decode_magic_value() {
+        assert!(decode_is_valid_signature(&magic_word()));
+    }
+
+    #[test]
+    fn test_decode_rejects_other_values() {
+        assert!(!decode_is_valid_signature(&[]));
+        assert!(!decode_is_valid_signature(&MAGIC_VALUE));
+        assert!(!decode_is_valid_signature(&[0xff; 32]));
+
+        let mut dirty = magic_word();
+        dirty[31] = 1;
+        assert!(!decode_is_valid_signature(&dirty));
+    }
+
+    // ─── RPC ─────────────────────────────────────────────────────────────────
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::rpc::RpcClient;
+        use crate::Address;
+        use serde_json::{json, Value};
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn contract() -> Address {
+            "0x55d398326f99059fF775485246999027B3197955"
+                .parse()
+                .unwrap()
+        }
+
+        async fn mock(server: &MockServer, rpc_method: &str, body: Value) {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({ "method": rpc_method })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(server)
+                .await;
+        }
+
+        async fn mock_result(server: &MockServer, rpc_method: &str, result: Value) {
+            mock(
+                server,
+                rpc_method,
+                json!({ "jsonrpc": "2.0", "id": 1, "result": result }),
+            )
+            .await;
+        }
+
+        #[tokio::test]
+        async fn test_is_valid_signature_magic() {
+            let server = MockServer::start().await;
+            mock_result(
+                &server,
+                "eth_call",
+                json!(format!("0x{}", hex::encode(magic_word()))),
+            )
+            .await;
+
+            let client = RpcClient::new(server.uri());
+            assert!(
+                is_valid_signature(&client, contract(), &[1u8; 32], &[0u8; 65])
+                    .await
+                    .unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_is_valid_signature_revert_is_false() {
+            let server = MockServer::start().await;
+            mock(
+                &server,
+                "eth_call",
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "error": { "code": 3, "message": "execution reverted" },
+                }),
+            )
+            .await;
+
+            let client = RpcClient::new(server.uri());
+            assert!(
+                !is_valid_signature(&client, contract(), &[1u8; 32], &[0u8; 65])
+                    .await
+                    .unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_verify_signature_eoa() {
+            let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+            let hash = [9u8; 32];
+            let signature = encode_signature(&signer.sign_hash(&hash).unwrap());
+
+            let server = MockServer::start().await;
+            mock_result(&server, "eth_getCode", json!("0x")).await;
+            let client = RpcClient::new(server.uri());
+
+            assert!(
+                verify_signature(&client, signer.address(), &hash, &signature)
+                    .await
+                    .unwrap()
+            );
+            assert!(!verify_signature(&client, contract(), &hash, &signature)
+                .await
+                .unwrap());
+            assert!(
+                !verify_signature(&client, signer.address(), &hash, &[0u8; 3])
+                    .await
+                    .unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_verify_signature_contract() {
+            let server = MockServer::start().await;
+            mock_result(&server, "eth_getCode", json!("0x6080")).await;
+            mock_result(
+                &server,
+                "eth_call",
+                json!(format!("0x{}", hex::encode(magic_word()))),
+            )
+            .await;
+
+            let client = RpcClient::new(server.uri());
+            assert!(
+                verify_signature(&client, contract(), &[1u8; 32], &[0u8; 65])
+                    .await
+                    .unwrap()
+            );
+        }
+    }
+}
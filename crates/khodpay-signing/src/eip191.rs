@@ -0,0 +1,186 @@
+//! EIP-191 `personal_sign` message signing.
+//!
+//! Implements version `0x45` of [EIP-191](https://eips.ethereum.org/EIPS/eip-191), the
+//! scheme behind `personal_sign` and `eth_sign` in wallets. The signed hash is:
+//!
+//! ```text
+//! keccak256("\x19Ethereum Signed Message:\n" ‖ len(message) ‖ message)
+//! ```
+//!
+//! where `len(message)` is the decimal byte length as ASCII. The prefix makes a signed
+//! message impossible to replay as a transaction, which is what wallet login flows and
+//! off-chain attestations rely on.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::eip191::verify_message;
+//! use khodpay_signing::Bip44Signer;
+//!
+//! let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+//! let signature = signer.sign_message(b"Sign in to example.com").unwrap();
+//!
+//! assert!(verify_message(b"Sign in to example.com", &signature, signer.address()).unwrap());
+//! ```
+//!
+//! # Recovery ID
+//!
+//! Signatures produced here carry `v` as a recovery ID (`0` or `1`), like every other
+//! signature in this crate. Wallets and `ecrecover` commonly use `27` / `28`; both forms
+//! are accepted by [`recover_message_signer`] and [`verify_message`].
+
+use crate::eip712::keccak256;
+use crate::{recover_signer, Address, Result, Signature};
+
+/// The EIP-191 version `0x45` prefix.
+pub const MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+
+/// Computes the EIP-191 `personal_sign` hash of `message`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::eip191::hash_message;
+///
+/// let hash = hash_message(b"Hello World");
+/// assert_eq!(
+///     hex::encode(hash),
+///     "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+/// );
+/// ```
+pub fn hash_message(message: &[u8]) -> [u8; 32] {
+    let length = message.len().to_string();
+    let mut buf = Vec::with_capacity(MESSAGE_PREFIX.len() + length.len() + message.len());
+    buf.extend_from_slice(MESSAGE_PREFIX.as_bytes());
+    buf.extend_from_slice(length.as_bytes());
+    buf.extend_from_slice(message);
+    keccak256(&buf)
+}
+
+/// Recovers the address that signed `message` with `personal_sign`.
+///
+/// Accepts `v` as either a recovery ID (`0` / `1`) or the legacy `27` / `28` form.
+///
+/// # Errors
+///
+/// Returns an error if the signature is malformed or recovery fails.
+pub fn recover_message_signer(message: &[u8], signature: &Signature) -> Result<Address> {
+    recover_signer(&hash_message(message), signature)
+}
+
+/// Verifies an EIP-191 `personal_sign` signature.
+///
+/// Recovers the signer address from the signature and compares it against
+/// `expected_signer`. Returns `Ok(true)` if they match, `Ok(false)` otherwise.
+///
+/// # Errors
+///
+/// Returns an error if signature recovery fails (e.g. invalid `v` value).
+pub fn verify_message(
+    message: &[u8],
+    signature: &Signature,
+    expected_signer: Address,
+) -> Result<bool> {
+    let recovered = recover_message_signer(message, signature)?;
+    Ok(recovered == expected_signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bip44Signer;
+
+    // web3.js `eth.accounts.sign` documentation vector
+    const VECTOR_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const VECTOR_ADDRESS: &str = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23";
+    const VECTOR_SIGNATURE: &str = "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c";
+
+    fn vector_signer() -> Bip44Signer {
+        let key: [u8; 32] = hex::decode(VECTOR_KEY).unwrap().try_into().unwrap();
+        Bip44Signer::from_private_key(&key).unwrap()
+    }
+
+    // ─── hash_message ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_hash_message_known_vector() {
+        assert_eq!(
+            hex::encode(hash_message(b"Some data")),
+            "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655"
+        );
+    }
+
+    #[test]
+    fn test_hash_message_empty() {
+        let expected = keccak256(b"\x19Ethereum Signed Message:\n0");
+        assert_eq!(hash_message(b""), expected);
+    }
+
+    #[test]
+    fn test_hash_message_length_is_decimal() {
+        let message = [0xffu8; 100];
+        let mut buf = b"\x19Ethereum Signed Message:\n100".to_vec();
+        buf.extend_from_slice(&message);
+        assert_eq!(hash_message(&message), keccak256(&buf));
+    }
+
+    // ─── sign / verify ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_sign_message_known_vector() {
+        let signer = vector_signer();
+        assert_eq!(signer.address().to_checksum_string(), VECTOR_ADDRESS);
+
+        let signature = signer.sign_message(b"Some data").unwrap();
+        let mut bytes = signature.to_bytes();
+        bytes[64] += 27;
+
+        assert_eq!(hex::encode(bytes), VECTOR_SIGNATURE);
+    }
+
+    #[test]
+    fn test_verify_message_roundtrip() {
+        let signer = Bip44Signer::from_private_key(&[7u8; 32]).unwrap();
+        let signature = signer.sign_message(b"login nonce 42").unwrap();
+
+        assert!(verify_message(b"login nonce 42", &signature, signer.address()).unwrap());
+        assert!(!verify_message(b"login nonce 43", &signature, signer.address()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_message_wrong_signer() {
+        let signer = Bip44Signer::from_private_key(&[7u8; 32]).unwrap();
+        let other = Bip44Signer::from_private_key(&[8u8; 32]).unwrap();
+        let signature = signer.sign_message(b"hello").unwrap();
+
+        assert!(!verify_message(b"hello", &signature, other.address()).unwrap());
+    }
+
+    #[test]
+    fn test_recover_accepts_legacy_v() {
+        let bytes = hex::decode(VECTOR_SIGNATURE).unwrap();
+        let signature = Signature::from_bytes(&bytes).unwrap();
+        assert_eq!(signature.v, 28);
+
+        let recovered = recover_message_signer(b"Some data", &signature).unwrap();
+        assert_eq!(recovered.to_checksum_string(), VECTOR_ADDRESS);
+    }
+
+    #[test]
+    fn test_recover_rejects_invalid_v() {
+        let signer = Bip44Signer::from_private_key(&[7u8; 32]).unwrap();
+        let mut signature = signer.sign_message(b"hello").unwrap();
+        signature.v = 35;
+
+        assert!(recover_message_signer(b"hello", &signature).is_err());
+    }
+
+    #[test]
+    fn test_message_signature_differs_from_raw_hash_signature() {
+        let signer = Bip44Signer::from_private_key(&[7u8; 32]).unwrap();
+        let raw = signer.sign_hash(&keccak256(b"hello")).unwrap();
+        let personal = signer.sign_message(b"hello").unwrap();
+
+        assert_ne!(raw, personal);
+    }
+}
@@ -0,0 +1,444 @@
+//! ERC-2771 meta-transactions through a trusted forwarder.
+//!
+//! With [ERC-2771](https://eips.ethereum.org/EIPS/eip-2771) a user signs a
+//! `ForwardRequest` off-chain and a relayer submits it to a trusted forwarder
+//! contract, paying the gas. The forwarder checks the signature and calls the
+//! target with the user's address appended to the calldata, so the target sees
+//! the user as `_msgSender()`.
+//!
+//! This module targets OpenZeppelin's `ERC2771Forwarder` (Contracts v5), whose
+//! EIP-712 domain is `(name, "1", chainId, forwarder)` and whose requests carry a
+//! `uint48` deadline.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::eip2771::{
+//!     encode_execute, forwarder_domain, sign_forward_request, ForwardRequest,
+//!     EXECUTE_SELECTOR,
+//! };
+//! use khodpay_signing::{erc20, Address, Bip44Signer, U256};
+//!
+//! let user = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+//! let forwarder: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let token: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//!
+//! let request = ForwardRequest::builder()
+//!     .from(user.address())
+//!     .to(token)
+//!     .gas(100_000)
+//!     .nonce(0)
+//!     .deadline(1_900_000_000)
+//!     .data(erc20::encode_transfer(&Address::from_bytes([0x11; 20]), U256::from(1_000u64)))
+//!     .build()
+//!     .unwrap();
+//!
+//! let domain = forwarder_domain("ERC2771Forwarder", 56, forwarder);
+//! let signature = sign_forward_request(&user, &request, &domain).unwrap();
+//!
+//! // The relayer sends this calldata to the forwarder.
+//! let calldata = encode_execute(&request, &signature);
+//! assert_eq!(&calldata[..4], &EXECUTE_SELECTOR);
+//! ```
+
+use crate::abi::{encode_with_selector, Token};
+use crate::eip712::{
+    encode_address, encode_bytes_dynamic, encode_uint64, hash_typed_data, Eip712Domain, Eip712Type,
+};
+use crate::{recover_signer, Address, Bip44Signer, Error, Result, Signature, Wei};
+
+/// EIP-712 type string of a forward request.
+pub const FORWARD_REQUEST_TYPE: &str = "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,uint48 deadline,bytes data)";
+
+/// Selector of `execute((address,address,uint256,uint256,uint48,bytes,bytes))`.
+pub const EXECUTE_SELECTOR: [u8; 4] = [0xdf, 0x90, 0x5c, 0xaf];
+
+/// Selector of `nonces(address)`.
+pub const NONCES_SELECTOR: [u8; 4] = [0x7e, 0xce, 0xbe, 0x00];
+
+/// Selector of `isTrustedForwarder(address)`, exposed by ERC-2771 recipients.
+pub const IS_TRUSTED_FORWARDER_SELECTOR: [u8; 4] = [0x57, 0x2b, 0x6c, 0x05];
+
+/// Largest deadline representable as a `uint48`.
+pub const MAX_DEADLINE: u64 = (1 << 48) - 1;
+
+/// A call to be relayed by a trusted forwarder on behalf of `from`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardRequest {
+    /// The user the call is made for; must be the signer.
+    pub from: Address,
+    /// Target contract.
+    pub to: Address,
+    /// Native value forwarded with the call (paid by the relayer).
+    pub value: Wei,
+    /// Gas the forwarder must provide to the call.
+    pub gas: u64,
+    /// The user's forwarder nonce, from `nonces(from)`.
+    pub nonce: u64,
+    /// Unix timestamp after which the request is rejected.
+    pub deadline: u64,
+    /// Calldata of the call.
+    pub data: Vec<u8>,
+}
+
+impl ForwardRequest {
+    /// Returns a builder for constructing a forward request.
+    pub fn builder() -> ForwardRequestBuilder {
+        ForwardRequestBuilder::default()
+    }
+
+    /// Computes the EIP-712 hash the user signs.
+    pub fn hash(&self, domain: &Eip712Domain) -> [u8; 32] {
+        hash_typed_data(domain, self)
+    }
+}
+
+impl Eip712Type for ForwardRequest {
+    fn type_string() -> &'static str {
+        FORWARD_REQUEST_TYPE
+    }
+
+    fn encode_data(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(32 * 7);
+        buf.extend_from_slice(&encode_address(&self.from));
+        buf.extend_from_slice(&encode_address(&self.to));
+        buf.extend_from_slice(&self.value.to_be_bytes());
+        buf.extend_from_slice(&encode_uint64(self.gas));
+        buf.extend_from_slice(&encode_uint64(self.nonce));
+        buf.extend_from_slice(&encode_uint64(self.deadline));
+        buf.extend_from_slice(&encode_bytes_dynamic(&self.data));
+        buf
+    }
+}
+
+/// Builder for [`ForwardRequest`].
+#[derive(Debug, Clone, Default)]
+pub struct ForwardRequestBuilder {
+    from: Option<Address>,
+    to: Option<Address>,
+    value: Option<Wei>,
+    gas: Option<u64>,
+    nonce: Option<u64>,
+    deadline: Option<u64>,
+    data: Vec<u8>,
+}
+
+impl ForwardRequestBuilder {
+    /// Sets the user the call is made for.
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Sets the target contract.
+    pub fn to(mut self, to: Address) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Sets the native value.
+    pub fn value(mut self, value: Wei) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Sets the gas provided to the call.
+    pub fn gas(mut self, gas: u64) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    /// Sets the forwarder nonce.
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Sets the expiry as a Unix timestamp.
+    pub fn deadline(mut self, deadline: u64) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the calldata.
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Builds the forward request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `from`, `to`, `gas`, `nonce` or `deadline` is missing, or
+    /// the deadline does not fit in a `uint48`.
+    pub fn build(self) -> Result<ForwardRequest> {
+        let required = |field: &str| Error::ValidationError(format!("{} is required", field));
+        let deadline = self.deadline.ok_or_else(|| required("deadline"))?;
+        if deadline > MAX_DEADLINE {
+            return Err(Error::ValidationError(format!(
+                "deadline {} exceeds uint48",
+                deadline
+            )));
+        }
+
+        Ok(ForwardRequest {
+            from: self.from.ok_or_else(|| required("from"))?,
+            to: self.to.ok_or_else(|| required("to"))?,
+            value: self.value.unwrap_or(Wei::ZERO),
+            gas: self.gas.ok_or_else(|| required("gas"))?,
+            nonce: self.nonce.ok_or_else(|| required("nonce"))?,
+            deadline,
+            data: self.data,
+        })
+    }
+}
+
+/// Returns the EIP-712 domain of an `ERC2771Forwarder` deployed as `name`.
+pub fn forwarder_domain(name: &str, chain_id: u64, forwarder: Address) -> Eip712Domain {
+    Eip712Domain::new(name, "1", chain_id, forwarder)
+}
+
+/// Signs a forward request as its `from` account.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] if the signer is not `request.from` (the
+/// forwarder would reject the signature), [`Error::ChainIdMismatch`] if the signer is
+/// bound to a different chain than the domain, [`Error::PolicyViolation`] if the
+/// signer's policy refuses the request, or an error if signing fails.
+pub fn sign_forward_request(
+    signer: &Bip44Signer,
+    request: &ForwardRequest,
+    domain: &Eip712Domain,
+) -> Result<Signature> {
+    if signer.address() != request.from {
+        return Err(Error::ValidationError(format!(
+            "signer {} is not the request's from address {}",
+            signer.address(),
+            request.from
+        )));
+    }
+    if let Some(chain_id) = domain.chain_id {
+        signer.check_chain_id(chain_id)?;
+    }
+    if let Some(policy) = signer.policy() {
+        policy.check_forward_request(request, domain)?;
+    }
+    signer.sign_hash(&request.hash(domain))
+}
+
+/// Checks that `signature` over `request` was produced by `request.from`.
+///
+/// # Errors
+///
+/// Returns an error if the signature is malformed or recovery fails.
+pub fn verify_forward_request(
+    request: &ForwardRequest,
+    domain: &Eip712Domain,
+    signature: &Signature,
+) -> Result<bool> {
+    Ok(recover_signer(&request.hash(domain), signature)? == request.from)
+}
+
+/// Encodes `execute(request)` calldata for the forwarder.
+///
+/// The signature is embedded as 65-byte `r ‖ s ‖ v` with `v` in `27` / `28`. The
+/// nonce is not part of the call: the forwarder reads it from its own storage.
+pub fn encode_execute(request: &ForwardRequest, signature: &Signature) -> Vec<u8> {
+    let mut sig = signature.to_bytes();
+    if sig[64] < 27 {
+        sig[64] += 27;
+    }
+
+    encode_with_selector(
+        EXECUTE_SELECTOR,
+        &[Token::Tuple(vec![
+            Token::Address(request.from),
+            Token::Address(request.to),
+            Token::Uint(request.value.as_u256()),
+            Token::Uint(request.gas.into()),
+            Token::Uint(request.deadline.into()),
+            Token::Bytes(request.data.clone()),
+            Token::Bytes(sig.to_vec()),
+        ])],
+    )
+}
+
+/// Encodes `nonces(owner)` calldata to read the next forwarder nonce.
+pub fn encode_nonces(owner: &Address) -> Vec<u8> {
+    encode_with_selector(NONCES_SELECTOR, &[Token::Address(*owner)])
+}
+
+/// Encodes `isTrustedForwarder(forwarder)` calldata for a recipient contract.
+pub fn encode_is_trusted_forwarder(forwarder: &Address) -> Vec<u8> {
+    encode_with_selector(IS_TRUSTED_FORWARDER_SELECTOR, &[Token::Address(*forwarder)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{self, ParamType};
+    use crate::ChainId;
+
+    fn user() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    fn forwarder() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    fn request() -> ForwardRequest {
+        ForwardRequest::builder()
+            .from(user().address())
+            .to(Address::from_bytes([0x22; 20]))
+            .value(Wei::from_gwei(1))
+            .gas(100_000)
+            .nonce(3)
+            .deadline(1_900_000_000)
+            .data(vec![0xde, 0xad, 0xbe, 0xef])
+            .build()
+            .unwrap()
+    }
+
+    fn domain() -> Eip712Domain {
+        forwarder_domain("ERC2771Forwarder", 56, forwarder())
+    }
+
+    // ─── constants ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(
+            EXECUTE_SELECTOR,
+            abi::selector("execute((address,address,uint256,uint256,uint48,bytes,bytes))")
+        );
+        assert_eq!(NONCES_SELECTOR, abi::selector("nonces(address)"));
+        assert_eq!(
+            IS_TRUSTED_FORWARDER_SELECTOR,
+            abi::selector("isTrustedForwarder(address)")
+        );
+    }
+
+    // ─── builder ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_builder_defaults_and_required_fields() {
+        let request = ForwardRequest::builder()
+            .from(Address::ZERO)
+            .to(Address::ZERO)
+            .gas(1)
+            .nonce(0)
+            .deadline(1)
+            .build()
+            .unwrap();
+        assert_eq!(request.value, Wei::ZERO);
+        assert!(request.data.is_empty());
+
+        assert!(ForwardRequest::builder()
+            .from(Address::ZERO)
+            .to(Address::ZERO)
+            .gas(1)
+            .deadline(1)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_deadline_over_uint48() {
+        let result = ForwardRequest::builder()
+            .from(Address::ZERO)
+            .to(Address::ZERO)
+            .gas(1)
+            .nonce(0)
+            .deadline(MAX_DEADLINE + 1)
+            .build();
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+    }
+
+    // ─── signing ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signature = sign_forward_request(&user(), &request(), &domain()).unwrap();
+        assert!(verify_forward_request(&request(), &domain(), &signature).unwrap());
+
+        let mut tampered = request();
+        tampered.gas += 1;
+        assert!(!verify_forward_request(&tampered, &domain(), &signature).unwrap());
+    }
+
+    #[test]
+    fn test_hash_depends_on_domain() {
+        let other = forwarder_domain("ERC2771Forwarder", 1, forwarder());
+        assert_ne!(request().hash(&domain()), request().hash(&other));
+    }
+
+    #[test]
+    fn test_sign_rejects_other_signer() {
+        let other = Bip44Signer::from_private_key(&[2u8; 32]).unwrap();
+        assert!(matches!(
+            sign_forward_request(&other, &request(), &domain()),
+            Err(Error::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_respects_chain_binding() {
+        let signer = user().with_chain_id(ChainId::EthereumMainnet);
+        assert!(matches!(
+            sign_forward_request(&signer, &request(), &domain()),
+            Err(Error::ChainIdMismatch { .. })
+        ));
+    }
+
+    // ─── calldata ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_encode_execute() {
+        let request = request();
+        let signature = sign_forward_request(&user(), &request, &domain()).unwrap();
+        let calldata = encode_execute(&request, &signature);
+
+        assert_eq!(&calldata[..4], &EXECUTE_SELECTOR);
+        let decoded = abi::decode(
+            &[ParamType::Tuple(vec![
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(48),
+                ParamType::Bytes,
+                ParamType::Bytes,
+            ])],
+            &calldata[4..],
+        )
+        .unwrap();
+        let Token::Tuple(fields) = &decoded[0] else {
+            panic!("expected tuple");
+        };
+        assert_eq!(fields[0], Token::Address(request.from));
+        assert_eq!(fields[4], Token::Uint(1_900_000_000u64.into()));
+        assert_eq!(fields[5], Token::Bytes(request.data.clone()));
+
+        let Token::Bytes(sig) = &fields[6] else {
+            panic!("expected bytes");
+        };
+        assert_eq!(sig.len(), 65);
+        assert!(sig[64] == 27 || sig[64] == 28);
+    }
+
+    #[test]
+    fn test_encode_nonces() {
+        let calldata = encode_nonces(&forwarder());
+        assert_eq!(&calldata[..4], &NONCES_SELECTOR);
+        assert_eq!(calldata.len(), 36);
+        assert_eq!(
+            encode_is_trusted_forwarder(&forwarder())[4..],
+            calldata[4..]
+        );
+    }
+}
@@ -0,0 +1,662 @@
+//! EIP-2930 (Type 1) access-list transactions.
+//!
+//! This module provides the transaction structure, builder, RLP encoding and signed
+//! form for EIP-2930 transactions. Type 1 transactions use a single legacy `gas_price`
+//! but carry an access list, letting callers pre-warm the addresses and storage slots
+//! a contract call will touch.
+
+use crate::rlp_encode::{append_u256, encode_access_list};
+use crate::signed_transaction::append_signature_component;
+use crate::{
+    access_list_gas, recover_signer, AccessList, AccessListItem, Address, ChainId, Error, Result,
+    Signature, Wei, TRANSFER_GAS,
+};
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+/// Additional intrinsic gas charged for contract creation.
+const CONTRACT_CREATION_GAS: u64 = 32_000;
+
+/// Intrinsic gas per zero calldata byte.
+const ZERO_BYTE_GAS: u64 = 4;
+
+/// Intrinsic gas per non-zero calldata byte.
+const NON_ZERO_BYTE_GAS: u64 = 16;
+
+/// Intrinsic gas per 32-byte word of init code (EIP-3860).
+const INIT_CODE_WORD_GAS: u64 = 2;
+
+/// EIP-2930 (Type 1) transaction.
+///
+/// # Fields
+///
+/// - `chain_id`: Network identifier (56 for BSC mainnet)
+/// - `nonce`: Transaction count from sender
+/// - `gas_price`: Price per gas unit (in wei)
+/// - `gas_limit`: Maximum gas units for execution
+/// - `to`: Recipient address (None for contract creation)
+/// - `value`: Amount to transfer (in wei)
+/// - `data`: Contract call data or empty for simple transfers
+/// - `access_list`: Addresses and storage keys to pre-warm
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{AccessListItem, ChainId, Eip2930Transaction, Wei, Address};
+///
+/// let token: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+///
+/// let tx = Eip2930Transaction::builder()
+///     .chain_id(ChainId::BscMainnet)
+///     .nonce(0)
+///     .gas_price(Wei::from_gwei(3))
+///     .gas_limit(60_000)
+///     .to(token)
+///     .add_access_list_item(AccessListItem::address_only(token).with_storage_key([0u8; 32]))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(tx.intrinsic_gas(), 21_000 + 2_400 + 1_900);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip2930Transaction {
+    /// The chain ID for replay protection.
+    pub chain_id: ChainId,
+    /// The transaction nonce (sender's transaction count).
+    pub nonce: u64,
+    /// The gas price.
+    pub gas_price: Wei,
+    /// The gas limit for the transaction.
+    pub gas_limit: u64,
+    /// The recipient address (None for contract creation).
+    pub to: Option<Address>,
+    /// The value to transfer in wei.
+    pub value: Wei,
+    /// The transaction data (contract call data).
+    pub data: Vec<u8>,
+    /// The access list.
+    pub access_list: AccessList,
+}
+
+impl Eip2930Transaction {
+    /// Transaction type identifier for EIP-2930.
+    pub const TYPE: u8 = 0x01;
+
+    /// Creates a new transaction builder.
+    pub fn builder() -> Eip2930TransactionBuilder {
+        Eip2930TransactionBuilder::new()
+    }
+
+    /// Returns the intrinsic gas of this transaction.
+    ///
+    /// This is the gas charged before any execution: the `21000` base cost, the
+    /// contract creation surcharge and init code word cost (if `to` is `None`),
+    /// calldata costs (`4` per zero byte, `16` per non-zero byte) and the access
+    /// list cost (`2400` per address, `1900` per storage key).
+    pub fn intrinsic_gas(&self) -> u64 {
+        let mut gas = TRANSFER_GAS;
+
+        if self.is_contract_creation() {
+            let words = (self.data.len() as u64).div_ceil(32);
+            gas += CONTRACT_CREATION_GAS + INIT_CODE_WORD_GAS * words;
+        }
+
+        gas += self
+            .data
+            .iter()
+            .map(|&b| {
+                if b == 0 {
+                    ZERO_BYTE_GAS
+                } else {
+                    NON_ZERO_BYTE_GAS
+                }
+            })
+            .sum::<u64>();
+
+        gas + access_list_gas(&self.access_list)
+    }
+
+    /// Validates the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `gas_limit` is below the transaction's intrinsic gas.
+    pub fn validate(&self) -> Result<()> {
+        let intrinsic = self.intrinsic_gas();
+        if self.gas_limit < intrinsic {
+            return Err(Error::InvalidGas(format!(
+                "gas_limit must be at least {} (intrinsic gas), got {}",
+                intrinsic, self.gas_limit
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if this is a contract creation transaction.
+    pub fn is_contract_creation(&self) -> bool {
+        self.to.is_none()
+    }
+
+    /// Returns `true` if this is a simple value transfer (no data).
+    pub fn is_transfer(&self) -> bool {
+        self.to.is_some() && self.data.is_empty()
+    }
+
+    /// Encodes the unsigned transaction for signing.
+    ///
+    /// Returns `0x01 || rlp([chain_id, nonce, gas_price, gas_limit, to, value, data,
+    /// access_list])`.
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(8);
+        self.append_fields(&mut stream);
+
+        let mut encoded = vec![Self::TYPE];
+        encoded.extend_from_slice(&stream.out());
+        encoded
+    }
+
+    /// Computes the signing hash for this transaction.
+    ///
+    /// The signing hash is `keccak256(0x01 || rlp(unsigned_tx))`.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.encode_unsigned()).into()
+    }
+
+    /// Returns the signing hash as a hex string with 0x prefix.
+    pub fn signing_hash_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.signing_hash()))
+    }
+
+    /// Appends the eight payload fields to an RLP stream.
+    fn append_fields(&self, stream: &mut RlpStream) {
+        stream.append(&u64::from(self.chain_id));
+        stream.append(&self.nonce);
+        append_u256(stream, self.gas_price.as_u256());
+        stream.append(&self.gas_limit);
+
+        match &self.to {
+            Some(addr) => stream.append(&addr.as_bytes().as_slice()),
+            None => stream.append_empty_data(),
+        };
+
+        append_u256(stream, self.value.as_u256());
+        stream.append(&self.data);
+        encode_access_list(stream, &self.access_list);
+    }
+}
+
+/// Builder for constructing EIP-2930 transactions.
+#[derive(Debug, Clone, Default)]
+pub struct Eip2930TransactionBuilder {
+    chain_id: Option<ChainId>,
+    nonce: Option<u64>,
+    gas_price: Option<Wei>,
+    gas_limit: Option<u64>,
+    to: Option<Address>,
+    value: Option<Wei>,
+    data: Vec<u8>,
+    access_list: AccessList,
+}
+
+impl Eip2930TransactionBuilder {
+    /// Creates a new builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the chain ID.
+    pub fn chain_id(mut self, chain_id: ChainId) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Sets the nonce.
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Sets the gas price.
+    pub fn gas_price(mut self, price: Wei) -> Self {
+        self.gas_price = Some(price);
+        self
+    }
+
+    /// Sets the gas limit.
+    pub fn gas_limit(mut self, limit: u64) -> Self {
+        self.gas_limit = Some(limit);
+        self
+    }
+
+    /// Sets the recipient address.
+    pub fn to(mut self, address: Address) -> Self {
+        self.to = Some(address);
+        self
+    }
+
+    /// Sets the value to transfer.
+    pub fn value(mut self, value: Wei) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Sets the transaction data.
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the access list.
+    pub fn access_list(mut self, access_list: AccessList) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    /// Adds an access list item.
+    pub fn add_access_list_item(mut self, item: AccessListItem) -> Self {
+        self.access_list.push(item);
+        self
+    }
+
+    /// Builds the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if required fields are missing or validation fails.
+    pub fn build(self) -> Result<Eip2930Transaction> {
+        let tx = Eip2930Transaction {
+            chain_id: self
+                .chain_id
+                .ok_or_else(|| Error::ValidationError("chain_id is required".to_string()))?,
+            nonce: self
+                .nonce
+                .ok_or_else(|| Error::ValidationError("nonce is required".to_string()))?,
+            gas_price: self
+                .gas_price
+                .ok_or_else(|| Error::ValidationError("gas_price is required".to_string()))?,
+            gas_limit: self
+                .gas_limit
+                .ok_or_else(|| Error::ValidationError("gas_limit is required".to_string()))?,
+            to: self.to,
+            value: self.value.unwrap_or(Wei::ZERO),
+            data: self.data,
+            access_list: self.access_list,
+        };
+
+        tx.validate()?;
+        Ok(tx)
+    }
+}
+
+/// A signed EIP-2930 transaction ready for broadcast.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{Bip44Signer, ChainId, Eip2930Transaction, SignedEip2930Transaction, Wei};
+///
+/// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+///
+/// let tx = Eip2930Transaction::builder()
+///     .chain_id(ChainId::BscMainnet)
+///     .nonce(0)
+///     .gas_price(Wei::from_gwei(3))
+///     .gas_limit(21_000)
+///     .to(signer.address())
+///     .value(Wei::from_ether(1))
+///     .build()
+///     .unwrap();
+///
+/// let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+/// let signed_tx = SignedEip2930Transaction::new(tx, signature);
+///
+/// assert!(signed_tx.to_raw_transaction().starts_with("0x01"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedEip2930Transaction {
+    /// The unsigned transaction.
+    transaction: Eip2930Transaction,
+    /// The ECDSA signature.
+    signature: Signature,
+}
+
+impl SignedEip2930Transaction {
+    /// Creates a new signed transaction.
+    pub fn new(transaction: Eip2930Transaction, signature: Signature) -> Self {
+        Self {
+            transaction,
+            signature,
+        }
+    }
+
+    /// Returns a reference to the unsigned transaction.
+    pub fn transaction(&self) -> &Eip2930Transaction {
+        &self.transaction
+    }
+
+    /// Returns a reference to the signature.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Encodes the signed transaction.
+    ///
+    /// Returns `0x01 || rlp([chain_id, nonce, gas_price, gas_limit, to, value, data,
+    /// access_list, y_parity, r, s])`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(11);
+        self.transaction.append_fields(&mut stream);
+
+        stream.append(&self.signature.v);
+        append_signature_component(&mut stream, &self.signature.r);
+        append_signature_component(&mut stream, &self.signature.s);
+
+        let mut encoded = vec![Eip2930Transaction::TYPE];
+        encoded.extend_from_slice(&stream.out());
+        encoded
+    }
+
+    /// Returns the raw transaction as a hex string with 0x prefix.
+    ///
+    /// This is the format expected by `eth_sendRawTransaction`.
+    pub fn to_raw_transaction(&self) -> String {
+        format!("0x{}", hex::encode(self.encode()))
+    }
+
+    /// Computes the transaction hash (`keccak256(encoded_signed_tx)`).
+    pub fn tx_hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.encode()).into()
+    }
+
+    /// Returns the transaction hash; alias for [`tx_hash`](Self::tx_hash).
+    pub fn hash(&self) -> [u8; 32] {
+        self.tx_hash()
+    }
+
+    /// Returns the transaction hash as a hex string with 0x prefix.
+    pub fn tx_hash_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.tx_hash()))
+    }
+
+    /// Returns the hash that was signed, i.e. the unsigned transaction's
+    /// [`signing_hash`](Eip2930Transaction::signing_hash).
+    pub fn signing_hash(&self) -> [u8; 32] {
+        self.transaction.signing_hash()
+    }
+
+    /// Verifies the signature and returns the sender's address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is malformed, non-canonical (high `s`) or
+    /// recovery fails.
+    pub fn verify(&self) -> Result<Address> {
+        recover_signer(&self.transaction.signing_hash(), &self.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bip44Signer;
+    use rlp::Rlp;
+
+    fn test_address() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn test_transaction() -> Eip2930Transaction {
+        Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(7)
+            .gas_price(Wei::from_gwei(3))
+            .gas_limit(100_000)
+            .to(test_address())
+            .value(Wei::from_gwei(1))
+            .data(vec![0xa9, 0x05, 0x9c, 0xbb, 0x00])
+            .add_access_list_item(AccessListItem::new(test_address(), vec![[1u8; 32]]))
+            .build()
+            .unwrap()
+    }
+
+    // ==================== Builder Tests ====================
+
+    #[test]
+    fn test_builder_minimal() {
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscTestnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(10))
+            .gas_limit(21_000)
+            .to(test_address())
+            .build()
+            .unwrap();
+
+        assert_eq!(tx.chain_id, ChainId::BscTestnet);
+        assert_eq!(tx.value, Wei::ZERO);
+        assert!(tx.access_list.is_empty());
+    }
+
+    #[test]
+    fn test_builder_missing_gas_price() {
+        let result = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_limit(21_000)
+            .build();
+
+        assert!(matches!(result, Err(Error::ValidationError(msg)) if msg.contains("gas_price")));
+    }
+
+    #[test]
+    fn test_builder_missing_chain_id() {
+        let result = Eip2930Transaction::builder()
+            .nonce(0)
+            .gas_price(Wei::from_gwei(1))
+            .gas_limit(21_000)
+            .build();
+
+        assert!(matches!(result, Err(Error::ValidationError(msg)) if msg.contains("chain_id")));
+    }
+
+    // ==================== Gas Accounting Tests ====================
+
+    #[test]
+    fn test_intrinsic_gas_transfer() {
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(1))
+            .gas_limit(21_000)
+            .to(test_address())
+            .build()
+            .unwrap();
+
+        assert_eq!(tx.intrinsic_gas(), 21_000);
+    }
+
+    #[test]
+    fn test_intrinsic_gas_calldata_and_access_list() {
+        let tx = test_transaction();
+
+        // 4 non-zero bytes, 1 zero byte, 1 address, 1 storage key
+        assert_eq!(tx.intrinsic_gas(), 21_000 + 4 * 16 + 4 + 2_400 + 1_900);
+    }
+
+    #[test]
+    fn test_intrinsic_gas_contract_creation() {
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(1))
+            .gas_limit(100_000)
+            .data(vec![1u8; 33])
+            .build()
+            .unwrap();
+
+        // base + creation + 2 init code words + 33 non-zero bytes
+        assert_eq!(tx.intrinsic_gas(), 21_000 + 32_000 + 2 * 2 + 33 * 16);
+    }
+
+    #[test]
+    fn test_validate_gas_limit_below_intrinsic() {
+        let result = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(1))
+            .gas_limit(21_000)
+            .to(test_address())
+            .add_access_list_item(AccessListItem::address_only(test_address()))
+            .build();
+
+        assert!(matches!(result, Err(Error::InvalidGas(_))));
+    }
+
+    // ==================== Encoding Tests ====================
+
+    #[test]
+    fn test_encode_unsigned_structure() {
+        let tx = test_transaction();
+        let encoded = tx.encode_unsigned();
+
+        assert_eq!(encoded[0], 0x01);
+        let rlp = Rlp::new(&encoded[1..]);
+        assert_eq!(rlp.item_count().unwrap(), 8);
+        assert_eq!(rlp.val_at::<u64>(0).unwrap(), 56);
+        assert_eq!(rlp.val_at::<u64>(1).unwrap(), 7);
+        assert_eq!(rlp.val_at::<u64>(2).unwrap(), 3_000_000_000);
+        assert_eq!(rlp.val_at::<u64>(3).unwrap(), 100_000);
+        assert_eq!(
+            rlp.val_at::<Vec<u8>>(4).unwrap(),
+            test_address().as_bytes().to_vec()
+        );
+        assert_eq!(rlp.val_at::<Vec<u8>>(6).unwrap(), tx.data);
+
+        let access_list = rlp.at(7).unwrap();
+        assert_eq!(access_list.item_count().unwrap(), 1);
+        let item = access_list.at(0).unwrap();
+        assert_eq!(item.at(1).unwrap().item_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_encode_contract_creation_empty_to() {
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(1))
+            .gas_limit(60_000)
+            .data(vec![0x60, 0x80])
+            .build()
+            .unwrap();
+
+        let encoded = tx.encode_unsigned();
+        let rlp = Rlp::new(&encoded[1..]);
+        assert!(rlp.at(4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_signing_hash_differs_from_eip1559() {
+        let tx = test_transaction();
+        let eip1559 = crate::Eip1559Transaction::builder()
+            .chain_id(tx.chain_id)
+            .nonce(tx.nonce)
+            .max_priority_fee_per_gas(tx.gas_price)
+            .max_fee_per_gas(tx.gas_price)
+            .gas_limit(tx.gas_limit)
+            .to(test_address())
+            .value(tx.value)
+            .data(tx.data.clone())
+            .access_list(tx.access_list.clone())
+            .build()
+            .unwrap();
+
+        assert_ne!(tx.signing_hash(), eip1559.signing_hash());
+    }
+
+    // ==================== Signing Tests ====================
+
+    #[test]
+    fn test_sign_and_recover() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_transaction();
+
+        let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+        let recovered = recover_signer(&tx.signing_hash(), &signature).unwrap();
+
+        assert_eq!(recovered, signer.address());
+    }
+
+    #[test]
+    fn test_signed_encode_structure() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_transaction();
+        let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+        let signed = SignedEip2930Transaction::new(tx.clone(), signature);
+
+        let encoded = signed.encode();
+        assert_eq!(encoded[0], 0x01);
+
+        let rlp = Rlp::new(&encoded[1..]);
+        assert_eq!(rlp.item_count().unwrap(), 11);
+        assert_eq!(rlp.val_at::<u8>(8).unwrap(), signature.v);
+
+        // Unsigned fields are a prefix of the signed payload
+        let unsigned = tx.encode_unsigned();
+        let unsigned_rlp = Rlp::new(&unsigned[1..]);
+        for i in 0..8 {
+            assert_eq!(
+                rlp.at(i).unwrap().as_raw(),
+                unsigned_rlp.at(i).unwrap().as_raw()
+            );
+        }
+    }
+
+    #[test]
+    fn test_signed_tx_hash() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_transaction();
+        let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+        let signed = SignedEip2930Transaction::new(tx, signature);
+
+        let expected: [u8; 32] = Keccak256::digest(signed.encode()).into();
+        assert_eq!(signed.tx_hash(), expected);
+        assert_eq!(signed.tx_hash_hex(), format!("0x{}", hex::encode(expected)));
+        assert!(signed.to_raw_transaction().starts_with("0x01"));
+    }
+
+    #[test]
+    fn test_signed_hash_preview() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_transaction();
+        let preview = tx.signing_hash_hex();
+        let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+        let signed = SignedEip2930Transaction::new(tx, signature);
+
+        assert_eq!(signed.hash(), signed.tx_hash());
+        assert_eq!(format!("0x{}", hex::encode(signed.signing_hash())), preview);
+    }
+
+    #[test]
+    fn test_signed_verify() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_transaction();
+        let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+        let signed = SignedEip2930Transaction::new(tx, signature);
+
+        assert_eq!(signed.verify().unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_signed_accessors() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_transaction();
+        let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+        let signed = SignedEip2930Transaction::new(tx.clone(), signature);
+
+        assert_eq!(signed.transaction(), &tx);
+        assert_eq!(signed.signature(), &signature);
+    }
+}
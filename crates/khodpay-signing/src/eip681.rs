@@ -0,0 +1,530 @@
+//! EIP-681 payment request URIs.
+//!
+//! [EIP-681](https://eips.ethereum.org/EIPS/eip-681) URIs are what EVM wallets put in
+//! payment QR codes:
+//!
+//! ```text
+//! ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e@56?value=1.5e18
+//! ethereum:0x55d398326f99059fF775485246999027B3197955@56/transfer?address=0x742d…&uint256=1e18
+//! ```
+//!
+//! [`PaymentUri`] parses and generates both forms — native transfers and ERC-20
+//! `transfer` calls — and turns a request into a [`TxRequest`] ready for signing.
+//! Targets must be hex addresses; ENS names are rejected.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::eip681::PaymentUri;
+//! use khodpay_signing::{Address, ChainId, U256};
+//!
+//! let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let shop: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//!
+//! let uri = PaymentUri::erc20_transfer(usdt, shop, U256::exp10(18))
+//!     .with_chain_id(ChainId::BscMainnet);
+//! let text = uri.to_string();
+//! assert_eq!(
+//!     text,
+//!     "ethereum:0x55d398326f99059fF775485246999027B3197955@56/transfer\
+//!      ?address=0x742d35Cc6634C0532925a3b844Bc454e4438f44e&uint256=1000000000000000000"
+//! );
+//!
+//! let parsed: PaymentUri = text.parse().unwrap();
+//! assert_eq!(parsed.recipient().unwrap(), shop);
+//! assert_eq!(parsed.amount().unwrap(), Some(U256::exp10(18)));
+//! ```
+
+use crate::{
+    erc20, parse_units, Address, ChainId, Error, Result, TxRequest, Wei, TOKEN_TRANSFER_GAS, U256,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// URI scheme used by EIP-681.
+pub const SCHEME: &str = "ethereum";
+
+/// Function name of an ERC-20 transfer request.
+const TRANSFER: &str = "transfer";
+
+/// An EIP-681 payment request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUri {
+    /// Payee for native transfers, or the contract being called.
+    pub target: Address,
+    /// Chain the payment is requested on, if specified.
+    pub chain_id: Option<ChainId>,
+    /// Contract function to call, e.g. `transfer`.
+    pub function: Option<String>,
+    /// Query parameters in URI order, percent-decoded.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl PaymentUri {
+    /// Creates a native currency payment request.
+    pub fn native(to: Address, value: Wei) -> Self {
+        Self {
+            target: to,
+            chain_id: None,
+            function: None,
+            parameters: vec![("value".to_string(), value.to_string())],
+        }
+    }
+
+    /// Creates an ERC-20 `transfer(to, amount)` request on `token`.
+    pub fn erc20_transfer(token: Address, to: Address, amount: U256) -> Self {
+        Self {
+            target: token,
+            chain_id: None,
+            function: Some(TRANSFER.to_string()),
+            parameters: vec![
+                ("address".to_string(), to.to_string()),
+                ("uint256".to_string(), amount.to_string()),
+            ],
+        }
+    }
+
+    /// Sets the chain ID.
+    pub fn with_chain_id(mut self, chain_id: ChainId) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Appends a query parameter, e.g. `gasLimit`.
+    pub fn with_parameter(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parameters.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the first value of parameter `key`.
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns `true` if this is an ERC-20 `transfer` request.
+    pub fn is_erc20_transfer(&self) -> bool {
+        self.function.as_deref() == Some(TRANSFER)
+    }
+
+    /// Returns the native value to send, if specified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `value` parameter is not a valid number.
+    pub fn value(&self) -> Result<Option<Wei>> {
+        self.number("value").map(|v| v.map(Wei::from_u256))
+    }
+
+    /// Returns the requested gas limit (`gasLimit`, or its alias `gas`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameter is not a valid number or exceeds `u64`.
+    pub fn gas_limit(&self) -> Result<Option<u64>> {
+        let key = if self.parameter("gasLimit").is_some() {
+            "gasLimit"
+        } else {
+            "gas"
+        };
+        self.number(key)?
+            .map(|gas| {
+                u64::try_from(gas)
+                    .map_err(|_| Error::InvalidPaymentUri(format!("{} too large: {}", key, gas)))
+            })
+            .transpose()
+    }
+
+    /// Returns who receives the funds: the `address` parameter of a token
+    /// transfer, or the target of a native payment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a token transfer lacks a valid `address` parameter.
+    pub fn recipient(&self) -> Result<Address> {
+        if !self.is_erc20_transfer() {
+            return Ok(self.target);
+        }
+        let address = self
+            .parameter("address")
+            .ok_or_else(|| Error::InvalidPaymentUri("transfer is missing address".to_string()))?;
+        address
+            .parse()
+            .map_err(|_| Error::InvalidPaymentUri(format!("invalid address: {}", address)))
+    }
+
+    /// Returns the requested amount in the smallest unit: `uint256` for token
+    /// transfers, `value` for native payments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the amount is not a valid number.
+    pub fn amount(&self) -> Result<Option<U256>> {
+        self.number(if self.is_erc20_transfer() {
+            "uint256"
+        } else {
+            "value"
+        })
+    }
+
+    /// Converts the request into a [`TxRequest`] for signing.
+    ///
+    /// Token transfers default to [`TOKEN_TRANSFER_GAS`] and native payments to
+    /// 21,000 gas unless the URI specifies a gas limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URI calls a function other than `transfer`, a token
+    /// transfer has no amount, or a parameter is malformed.
+    pub fn to_tx_request(&self) -> Result<TxRequest> {
+        let mut request = match self.function.as_deref() {
+            None => TxRequest::transfer(self.target, self.value()?.unwrap_or(Wei::ZERO)),
+            Some(TRANSFER) => {
+                let amount = self.amount()?.ok_or_else(|| {
+                    Error::InvalidPaymentUri("transfer is missing uint256".to_string())
+                })?;
+                let data = erc20::encode_transfer(&self.recipient()?, amount);
+                TxRequest::call(self.target, data, TOKEN_TRANSFER_GAS)
+                    .with_value(self.value()?.unwrap_or(Wei::ZERO))
+            }
+            Some(other) => {
+                return Err(Error::InvalidPaymentUri(format!(
+                    "unsupported function: {}",
+                    other
+                )))
+            }
+        };
+        if let Some(gas_limit) = self.gas_limit()? {
+            request.gas_limit = gas_limit;
+        }
+        Ok(request)
+    }
+
+    fn number(&self, key: &str) -> Result<Option<U256>> {
+        self.parameter(key)
+            .map(|value| {
+                parse_number(value)
+                    .map_err(|_| Error::InvalidPaymentUri(format!("invalid {}: {}", key, value)))
+            })
+            .transpose()
+    }
+}
+
+/// Parses an EIP-681 number such as `1000`, `2.014e18` or `1E6` into an integer.
+///
+/// # Errors
+///
+/// Returns an error if the number is negative, malformed, not an integer, or overflows.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::eip681::parse_number;
+/// use khodpay_signing::U256;
+///
+/// assert_eq!(parse_number("2.014e18").unwrap(), U256::from(2_014_000_000_000_000_000u64));
+/// assert!(parse_number("1.5").is_err());
+/// ```
+pub fn parse_number(s: &str) -> Result<U256> {
+    let s = s.strip_prefix('+').unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => {
+            let exponent = &s[i + 1..];
+            if exponent.is_empty() || !exponent.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(Error::InvalidValue(format!("invalid exponent: {:?}", s)));
+            }
+            let exponent = exponent
+                .parse::<u8>()
+                .map_err(|_| Error::InvalidValue(format!("exponent too large: {:?}", s)))?;
+            (&s[..i], exponent)
+        }
+        None => (s, 0),
+    };
+    parse_units(mantissa, exponent)
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", SCHEME, self.target)?;
+        if let Some(chain_id) = self.chain_id {
+            write!(f, "@{}", chain_id.value())?;
+        }
+        if let Some(function) = &self.function {
+            write!(f, "/{}", percent_encode(function))?;
+        }
+        for (i, (key, value)) in self.parameters.iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(
+                f,
+                "{}{}={}",
+                separator,
+                percent_encode(key),
+                percent_encode(value)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PaymentUri {
+    type Err = Error;
+
+    /// Parses an `ethereum:` payment URI.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidPaymentUri(reason.to_string());
+
+        let rest = s
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| invalid("missing ethereum: scheme"))?;
+        let rest = rest.strip_prefix("pay-").unwrap_or(rest);
+
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        let (target_and_chain, function) = match path.split_once('/') {
+            Some((target, function)) if !function.is_empty() => {
+                (target, Some(percent_decode(function)?))
+            }
+            Some(_) => return Err(invalid("empty function name")),
+            None => (path, None),
+        };
+        let (target, chain_id) = match target_and_chain.split_once('@') {
+            Some((target, chain)) => {
+                let id: u64 = chain.parse().map_err(|_| {
+                    Error::InvalidPaymentUri(format!("invalid chain ID: {}", chain))
+                })?;
+                (target, Some(ChainId::from(id)))
+            }
+            None => (target_and_chain, None),
+        };
+        let target: Address = target
+            .parse()
+            .map_err(|_| Error::InvalidPaymentUri(format!("invalid target address: {}", target)))?;
+
+        let parameters = query
+            .into_iter()
+            .flat_map(|q| q.split('&'))
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').ok_or_else(|| {
+                    Error::InvalidPaymentUri(format!("parameter without value: {}", pair))
+                })?;
+                Ok((percent_decode(key)?, percent_decode(value)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            target,
+            chain_id,
+            function,
+            parameters,
+        })
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'+' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+    let invalid = || Error::InvalidPaymentUri(format!("invalid percent-encoding: {}", s));
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3).ok_or_else(invalid)?;
+            out.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdt() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    fn shop() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    // ─── numbers ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1000").unwrap(), U256::from(1000u64));
+        assert_eq!(parse_number("1e6").unwrap(), U256::from(1_000_000u64));
+        assert_eq!(parse_number("1E6").unwrap(), U256::from(1_000_000u64));
+        assert_eq!(parse_number("+5").unwrap(), U256::from(5u64));
+        assert_eq!(
+            parse_number("2.014e18").unwrap(),
+            U256::from(2_014_000_000_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn test_parse_number_rejects() {
+        for s in ["", "-1", "1.5", "1e", "1e-3", "1e300", "0x10", "1.5e0"] {
+            assert!(parse_number(s).is_err(), "{:?} should be rejected", s);
+        }
+    }
+
+    // ─── parsing ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_native() {
+        let uri: PaymentUri = "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e@56?value=1.5e18"
+            .parse()
+            .unwrap();
+
+        assert_eq!(uri.target, shop());
+        assert_eq!(uri.chain_id, Some(ChainId::BscMainnet));
+        assert_eq!(uri.function, None);
+        assert_eq!(uri.value().unwrap(), Some(Wei::parse_ether("1.5").unwrap()));
+        assert_eq!(uri.recipient().unwrap(), shop());
+    }
+
+    #[test]
+    fn test_parse_erc20_transfer() {
+        let uri: PaymentUri = "ethereum:0x55d398326f99059fF775485246999027B3197955@56/transfer?address=0x742d35Cc6634C0532925a3b844Bc454e4438f44e&uint256=1e18"
+            .parse()
+            .unwrap();
+
+        assert!(uri.is_erc20_transfer());
+        assert_eq!(uri.target, usdt());
+        assert_eq!(uri.recipient().unwrap(), shop());
+        assert_eq!(uri.amount().unwrap(), Some(U256::exp10(18)));
+    }
+
+    #[test]
+    fn test_parse_pay_prefix_and_no_chain() {
+        let uri: PaymentUri = "ethereum:pay-0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap();
+        assert_eq!(uri.target, shop());
+        assert_eq!(uri.chain_id, None);
+        assert!(uri.parameters.is_empty());
+        assert_eq!(uri.value().unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_gas_parameters() {
+        let uri: PaymentUri =
+            "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e?value=1&gas=30000"
+                .parse()
+                .unwrap();
+        assert_eq!(uri.gas_limit().unwrap(), Some(30_000));
+
+        let uri = uri.with_parameter("gasLimit", "40000");
+        assert_eq!(uri.gas_limit().unwrap(), Some(40_000));
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        for s in [
+            "bitcoin:0x742d35Cc6634C0532925a3b844Bc454e4438f44e",
+            "ethereum:vitalik.eth",
+            "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e@bsc",
+            "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e/",
+            "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e?value",
+            "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e?value=%zz",
+        ] {
+            assert!(
+                matches!(s.parse::<PaymentUri>(), Err(Error::InvalidPaymentUri(_))),
+                "{:?} should be rejected",
+                s
+            );
+        }
+    }
+
+    // ─── generation ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_display_native() {
+        let uri = PaymentUri::native(shop(), Wei::from_gwei(1)).with_chain_id(ChainId::BscMainnet);
+        assert_eq!(
+            uri.to_string(),
+            "ethereum:0x742d35Cc6634C0532925a3b844Bc454e4438f44e@56?value=1000000000"
+        );
+    }
+
+    #[test]
+    fn test_display_percent_encodes() {
+        let uri = PaymentUri::native(shop(), Wei::ZERO).with_parameter("message", "Order #7");
+        assert!(uri.to_string().ends_with("&message=Order%20%237"));
+
+        let parsed: PaymentUri = uri.to_string().parse().unwrap();
+        assert_eq!(parsed.parameter("message"), Some("Order #7"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let uri = PaymentUri::erc20_transfer(usdt(), shop(), U256::from(12_500_000u64))
+            .with_chain_id(ChainId::PolygonMainnet);
+        assert_eq!(uri.to_string().parse::<PaymentUri>().unwrap(), uri);
+    }
+
+    // ─── transaction requests ────────────────────────────────────────────────
+
+    #[test]
+    fn test_to_tx_request_native() {
+        let request = PaymentUri::native(shop(), Wei::from_ether(1))
+            .to_tx_request()
+            .unwrap();
+        assert_eq!(request, TxRequest::transfer(shop(), Wei::from_ether(1)));
+    }
+
+    #[test]
+    fn test_to_tx_request_erc20() {
+        let request = PaymentUri::erc20_transfer(usdt(), shop(), U256::from(5u64))
+            .with_parameter("gasLimit", "80000")
+            .to_tx_request()
+            .unwrap();
+
+        assert_eq!(request.to, Some(usdt()));
+        assert_eq!(
+            request.data,
+            erc20::encode_transfer(&shop(), U256::from(5u64))
+        );
+        assert_eq!(request.gas_limit, 80_000);
+    }
+
+    #[test]
+    fn test_to_tx_request_rejects_unsupported() {
+        let uri = PaymentUri {
+            function: Some("approve".to_string()),
+            ..PaymentUri::native(usdt(), Wei::ZERO)
+        };
+        assert!(uri.to_tx_request().is_err());
+
+        let missing_amount = PaymentUri {
+            parameters: vec![("address".to_string(), shop().to_string())],
+            ..PaymentUri::erc20_transfer(usdt(), shop(), U256::one())
+        };
+        assert!(missing_amount.to_tx_request().is_err());
+    }
+}
@@ -67,10 +67,22 @@
 //! | [`encode_bool`] | `bool` |
 //! | [`encode_bytes32`] | `bytes32` |
 //! | [`encode_bytes_dynamic`] | `bytes` / `string` (hashed) |
+//!
+//! # JSON Typed Data
+//!
+//! With the `eip712` feature, `TypedData` hashes payloads whose types are only known
+//! at runtime, such as `eth_signTypedData_v4` requests from dApps, and
+//! `sign_typed_data_json` signs them.
 
 use crate::{Address, Result, Signature};
 use sha3::{Digest, Keccak256};
 
+#[cfg(feature = "eip712")]
+mod typed_data;
+
+#[cfg(feature = "eip712")]
+pub use typed_data::{sign_typed_data_json, TypedData, TypedDataField};
+
 // ─── Trait ───────────────────────────────────────────────────────────────────
 
 /// A type that can be hashed according to EIP-712 typed structured data rules.
@@ -302,12 +314,21 @@ pub fn hash_typed_data<T: Eip712Type>(domain: &Eip712Domain, message: &T) -> [u8
 ///
 /// # Errors
 ///
-/// Returns an error if the underlying ECDSA signing fails.
+/// Returns [`Error::ChainIdMismatch`](crate::Error::ChainIdMismatch) if the signer is
+/// bound to a different chain than `domain.chain_id`,
+/// [`Error::PolicyViolation`](crate::Error::PolicyViolation) if the signer's policy does
+/// not allow that chain, or an error if the underlying ECDSA signing fails.
 pub fn sign_typed_data<T: Eip712Type>(
     signer: &crate::Bip44Signer,
     domain: &Eip712Domain,
     message: &T,
 ) -> Result<Signature> {
+    #[cfg(feature = "tracing")]
+    let _operation = khodpay_tracing::operation!("sign_typed_data", chain_id = domain.chain_id);
+
+    if let Some(chain_id) = domain.chain_id {
+        signer.check_chain_id(chain_id)?;
+    }
     let hash = hash_typed_data(domain, message);
     signer.sign_hash(&hash)
 }
@@ -599,6 +620,25 @@ mod tests {
         assert_eq!(sig1.v, sig2.v);
     }
 
+    #[test]
+    fn test_bound_signer_checks_domain_chain() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_chain_id(crate::ChainId::BscTestnet);
+        let msg = SimpleTransfer {
+            to: test_address(),
+            amount: 1,
+        };
+
+        assert!(matches!(
+            sign_typed_data(&signer, &test_domain(), &msg),
+            Err(crate::Error::ChainIdMismatch { .. })
+        ));
+
+        let domain = Eip712Domain::new("App", "1", 97, test_address());
+        assert!(sign_typed_data(&signer, &domain, &msg).is_ok());
+    }
+
     #[test]
     fn test_cross_domain_signature_invalid() {
         let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
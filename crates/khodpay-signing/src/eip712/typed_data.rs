@@ -0,0 +1,640 @@
+//! EIP-712 typed data described at runtime, as JSON.
+//!
+//! [`Eip712Type`](super::Eip712Type) covers structs known at compile time. Requests
+//! from dApps (`eth_signTypedData_v4`) instead carry their own type definitions, so
+//! [`TypedData`] encodes any JSON payload following the EIP-712 rules.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::keccak256;
+use crate::{Address, Error, Result, Signature};
+
+/// Name of the domain type in [`TypedData::types`].
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// Domain fields in canonical order, used when a payload omits `EIP712Domain`.
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+/// One member of a struct type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypedDataField {
+    /// Member name.
+    pub name: String,
+    /// Solidity type, such as `address`, `uint256[]` or another struct's name.
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+/// An EIP-712 payload in the JSON format of `eth_signTypedData_v4`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::eip712::TypedData;
+///
+/// let typed_data = TypedData::from_json(r#"{
+///     "types": {
+///         "EIP712Domain": [{ "name": "name", "type": "string" }],
+///         "Greeting": [{ "name": "text", "type": "string" }]
+///     },
+///     "primaryType": "Greeting",
+///     "domain": { "name": "Example" },
+///     "message": { "text": "gm" }
+/// }"#)?;
+///
+/// let hash = typed_data.hash()?;
+/// assert_eq!(hash.len(), 32);
+/// # Ok::<(), khodpay_signing::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    /// Struct definitions, keyed by type name.
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    /// Type of [`message`](Self::message).
+    pub primary_type: String,
+    /// Domain values, keyed by field name.
+    #[serde(default)]
+    pub domain: Map<String, Value>,
+    /// The message to sign.
+    #[serde(default)]
+    pub message: Value,
+}
+
+impl TypedData {
+    /// Parses a JSON payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if the JSON does not have the
+    /// `eth_signTypedData_v4` shape.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::InvalidTypedData(e.to_string()))
+    }
+
+    /// Converts a JSON value, accepting both an object and a string holding one, as
+    /// wallets receive either in RPC params.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if the value does not have the
+    /// `eth_signTypedData_v4` shape.
+    pub fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::String(json) => Self::from_json(&json),
+            value => {
+                serde_json::from_value(value).map_err(|e| Error::InvalidTypedData(e.to_string()))
+            }
+        }
+    }
+
+    /// Returns the domain's `chainId`, if present.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if the chain ID is not an integer that
+    /// fits in a `u64`.
+    pub fn chain_id(&self) -> Result<Option<u64>> {
+        let Some(value) = self.domain.get("chainId") else {
+            return Ok(None);
+        };
+        let chain_id = parse_uint(value)?;
+        if chain_id > U256::from(u64::MAX) {
+            return Err(invalid(format!("chainId {} out of range", chain_id)));
+        }
+        Ok(Some(chain_id.low_u64()))
+    }
+
+    /// Returns the encoded type of `type_name`: its own signature followed by those
+    /// of every struct it references, sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if `type_name` or a referenced type is
+    /// not defined.
+    pub fn encode_type(&self, type_name: &str) -> Result<String> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut dependencies)?;
+        dependencies.remove(type_name);
+
+        let mut encoded = String::new();
+        for name in std::iter::once(type_name).chain(dependencies.iter().map(String::as_str)) {
+            let fields = self.fields(name)?;
+            let members: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{} {}", field.type_name, field.name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, members.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    /// Returns `keccak256(encodeType(type_name))`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if a type is not defined.
+    pub fn type_hash(&self, type_name: &str) -> Result<[u8; 32]> {
+        Ok(keccak256(self.encode_type(type_name)?.as_bytes()))
+    }
+
+    /// Returns `hashStruct(value)` for a struct of type `type_name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if `value` does not match the type.
+    pub fn hash_struct(&self, type_name: &str, value: &Value) -> Result<[u8; 32]> {
+        let object = value
+            .as_object()
+            .ok_or_else(|| invalid(format!("{} must be an object", type_name)))?;
+        let fields = self.fields(type_name)?;
+
+        let mut buf = Vec::with_capacity(32 * (fields.len() + 1));
+        buf.extend_from_slice(&self.type_hash(type_name)?);
+        for field in fields.iter() {
+            let member = object
+                .get(&field.name)
+                .ok_or_else(|| invalid(format!("{} is missing {}", type_name, field.name)))?;
+            buf.extend_from_slice(&self.encode_value(&field.type_name, member)?);
+        }
+        Ok(keccak256(&buf))
+    }
+
+    /// Returns the domain separator, `hashStruct(domain)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if the domain does not match its type.
+    pub fn domain_separator(&self) -> Result<[u8; 32]> {
+        self.hash_struct(DOMAIN_TYPE, &Value::Object(self.domain.clone()))
+    }
+
+    /// Returns the digest to sign:
+    /// `keccak256("\x19\x01" ‖ domainSeparator ‖ hashStruct(message))`.
+    ///
+    /// When the primary type is `EIP712Domain` the message hash is omitted, as in
+    /// `eth_signTypedData_v4`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTypedData`] if the domain or message does not match
+    /// its type.
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let mut buf = Vec::with_capacity(66);
+        buf.extend_from_slice(&[0x19, 0x01]);
+        buf.extend_from_slice(&self.domain_separator()?);
+        if self.primary_type != DOMAIN_TYPE {
+            buf.extend_from_slice(&self.hash_struct(&self.primary_type, &self.message)?);
+        }
+        Ok(keccak256(&buf))
+    }
+
+    /// Returns the fields of `type_name`, inferring `EIP712Domain` from the domain's
+    /// keys when the payload does not define it.
+    fn fields(&self, type_name: &str) -> Result<std::borrow::Cow<'_, [TypedDataField]>> {
+        if let Some(fields) = self.types.get(type_name) {
+            return Ok(fields.as_slice().into());
+        }
+        if type_name == DOMAIN_TYPE {
+            let fields = DOMAIN_FIELDS
+                .iter()
+                .filter(|(name, _)| self.domain.contains_key(*name))
+                .map(|(name, type_name)| TypedDataField {
+                    name: name.to_string(),
+                    type_name: type_name.to_string(),
+                })
+                .collect::<Vec<_>>();
+            return Ok(fields.into());
+        }
+        Err(invalid(format!("undefined type {}", type_name)))
+    }
+
+    fn collect_dependencies(&self, type_name: &str, found: &mut BTreeSet<String>) -> Result<()> {
+        if found.contains(type_name) {
+            return Ok(());
+        }
+        found.insert(type_name.to_string());
+        for field in self.fields(type_name)?.iter() {
+            let base = base_type(&field.type_name);
+            if self.types.contains_key(base) {
+                self.collect_dependencies(base, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_value(&self, type_name: &str, value: &Value) -> Result<[u8; 32]> {
+        if let Some((element, length)) = split_array(type_name) {
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid(format!("{} must be an array", type_name)))?;
+            if length.is_some_and(|length| length != items.len()) {
+                return Err(invalid(format!(
+                    "{} has {} elements",
+                    type_name,
+                    items.len()
+                )));
+            }
+            let mut buf = Vec::with_capacity(32 * items.len());
+            for item in items {
+                buf.extend_from_slice(&self.encode_value(element, item)?);
+            }
+            return Ok(keccak256(&buf));
+        }
+
+        if self.types.contains_key(type_name) {
+            return self.hash_struct(type_name, value);
+        }
+
+        let mismatch = || invalid(format!("expected {}, got {}", type_name, value));
+        match type_name {
+            "string" => Ok(keccak256(value.as_str().ok_or_else(mismatch)?.as_bytes())),
+            "bytes" => Ok(keccak256(&parse_hex(value.as_str().ok_or_else(mismatch)?)?)),
+            "bool" => {
+                let mut word = [0u8; 32];
+                word[31] = value.as_bool().ok_or_else(mismatch)? as u8;
+                Ok(word)
+            }
+            "address" => {
+                let address: Address = value
+                    .as_str()
+                    .ok_or_else(mismatch)?
+                    .parse()
+                    .map_err(|_| mismatch())?;
+                Ok(super::encode_address(&address))
+            }
+            _ => {
+                if let Some(size) = type_name.strip_prefix("bytes") {
+                    let size = parse_size(type_name, size, 1, 32)?;
+                    let bytes = parse_hex(value.as_str().ok_or_else(mismatch)?)?;
+                    if bytes.len() > size {
+                        return Err(mismatch());
+                    }
+                    let mut word = [0u8; 32];
+                    word[..bytes.len()].copy_from_slice(&bytes);
+                    Ok(word)
+                } else if let Some(bits) = type_name.strip_prefix("uint") {
+                    let bits = parse_size(type_name, bits, 8, 256)?;
+                    let number = parse_uint(value)?;
+                    if bits < 256 && number >> bits != U256::zero() {
+                        return Err(invalid(format!("{} overflows {}", number, type_name)));
+                    }
+                    Ok(number.into())
+                } else if let Some(bits) = type_name.strip_prefix("int") {
+                    parse_size(type_name, bits, 8, 256)?;
+                    Ok(parse_int(value)?.into())
+                } else {
+                    Err(invalid(format!("unsupported type {}", type_name)))
+                }
+            }
+        }
+    }
+}
+
+/// Signs a JSON [`TypedData`] payload, as requested by `eth_signTypedData_v4`.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidTypedData`] if the payload is malformed,
+/// [`Error::ChainIdMismatch`] or [`Error::PolicyViolation`] if the signer may not
+/// sign for the domain's chain, or an error if the underlying ECDSA signing fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+/// use khodpay_signing::Bip44Signer;
+///
+/// let signer = Bip44Signer::from_private_key(&[1u8; 32])?;
+/// let typed_data = TypedData::from_json(r#"{
+///     "types": { "Greeting": [{ "name": "text", "type": "string" }] },
+///     "primaryType": "Greeting",
+///     "domain": { "name": "Example", "chainId": 56 },
+///     "message": { "text": "gm" }
+/// }"#)?;
+///
+/// let signature = sign_typed_data_json(&signer, &typed_data)?;
+/// assert!(signature.v <= 1);
+/// # Ok::<(), khodpay_signing::Error>(())
+/// ```
+pub fn sign_typed_data_json(
+    signer: &crate::Bip44Signer,
+    typed_data: &TypedData,
+) -> Result<Signature> {
+    #[cfg(feature = "tracing")]
+    let operation = khodpay_tracing::operation!("sign_typed_data", chain_id = _);
+
+    if let Some(chain_id) = typed_data.chain_id()? {
+        #[cfg(feature = "tracing")]
+        operation.record("chain_id", &chain_id);
+        signer.check_chain_id(chain_id)?;
+    }
+    signer.sign_hash(&typed_data.hash()?)
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidTypedData(message)
+}
+
+/// Strips array suffixes: `Person[][2]` → `Person`.
+fn base_type(type_name: &str) -> &str {
+    type_name.split('[').next().unwrap_or(type_name)
+}
+
+/// Splits the outermost array suffix: `uint8[2][]` → (`uint8[2]`, `None`).
+fn split_array(type_name: &str) -> Option<(&str, Option<usize>)> {
+    let inner = type_name.strip_suffix(']')?;
+    let open = inner.rfind('[')?;
+    let length = &inner[open + 1..];
+    Some((
+        &inner[..open],
+        if length.is_empty() {
+            None
+        } else {
+            length.parse().ok()
+        },
+    ))
+}
+
+/// Parses the size of `bytesN`, `uintN` or `intN`: a multiple of `step` up to
+/// `max`.
+fn parse_size(type_name: &str, digits: &str, step: usize, max: usize) -> Result<usize> {
+    digits
+        .parse::<usize>()
+        .ok()
+        .filter(|size| (step..=max).contains(size) && size % step == 0)
+        .ok_or_else(|| invalid(format!("unsupported type {}", type_name)))
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    hex::decode(digits).map_err(|e| invalid(format!("invalid hex '{}': {}", s, e)))
+}
+
+/// Parses a non-negative integer given as a JSON number, decimal string or `0x`
+/// hex string.
+fn parse_uint(value: &Value) -> Result<U256> {
+    let (negative, magnitude) = parse_signed(value)?;
+    if negative && !magnitude.is_zero() {
+        return Err(invalid(format!("{} must not be negative", value)));
+    }
+    Ok(magnitude)
+}
+
+/// Parses a signed integer into its 256-bit two's complement form.
+fn parse_int(value: &Value) -> Result<U256> {
+    let (negative, magnitude) = parse_signed(value)?;
+    if negative {
+        Ok((!magnitude).overflowing_add(U256::one()).0)
+    } else {
+        Ok(magnitude)
+    }
+}
+
+fn parse_signed(value: &Value) -> Result<(bool, U256)> {
+    let not_integer = || invalid(format!("{} is not an integer", value));
+    match value {
+        Value::Number(number) => {
+            if let Some(n) = number.as_u64() {
+                Ok((false, U256::from(n)))
+            } else if let Some(n) = number.as_i64() {
+                Ok((true, U256::from(n.unsigned_abs())))
+            } else {
+                Err(not_integer())
+            }
+        }
+        Value::String(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s.as_str()),
+            };
+            let magnitude = match digits.strip_prefix("0x") {
+                Some(hex) if !hex.is_empty() => {
+                    U256::from_str_radix(hex, 16).map_err(|_| not_integer())?
+                }
+                Some(_) => return Err(not_integer()),
+                None => U256::from_dec_str(digits).map_err(|_| not_integer())?,
+            };
+            Ok((negative, magnitude))
+        }
+        _ => Err(not_integer()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eip712::{encode_address, Eip712Domain};
+    use crate::Bip44Signer;
+    use serde_json::json;
+
+    /// The `Mail` example from the EIP-712 specification.
+    fn mail() -> TypedData {
+        TypedData::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mail_vector() {
+        let typed_data = mail();
+        assert_eq!(
+            typed_data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(typed_data.domain_separator().unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(typed_data.hash_struct("Mail", &typed_data.message).unwrap()),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hex::encode(typed_data.hash().unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[test]
+    fn test_mail_signature() {
+        let key = keccak256(b"cow");
+        let signer = Bip44Signer::from_private_key(&key).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+        );
+
+        let signature = sign_typed_data_json(&signer, &mail()).unwrap();
+        assert_eq!(
+            hex::encode(signature.r),
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d"
+        );
+        assert_eq!(
+            hex::encode(signature.s),
+            "07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562"
+        );
+        assert_eq!(signature.v, 1);
+    }
+
+    #[test]
+    fn test_sign_rejects_other_chain() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_chain_id(56u64.into());
+        assert!(matches!(
+            sign_typed_data_json(&signer, &mail()),
+            Err(Error::ChainIdMismatch {
+                expected: 56,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_inferred_domain_matches_eip712_domain() {
+        let contract: Address = "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            .parse()
+            .unwrap();
+        let mut typed_data = mail();
+        typed_data.types.remove(DOMAIN_TYPE);
+        typed_data.domain.insert("chainId".into(), json!("0x38"));
+
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            Eip712Domain::new("Ether Mail", "1", 56, contract).domain_separator()
+        );
+        assert_eq!(typed_data.chain_id().unwrap(), Some(56));
+    }
+
+    #[test]
+    fn test_arrays_and_atomic_types() {
+        let typed_data = TypedData::from_value(json!({
+            "types": {
+                "Person": [{ "name": "wallet", "type": "address" }],
+                "Group": [
+                    { "name": "members", "type": "Person[]" },
+                    { "name": "flags", "type": "bool[2]" },
+                    { "name": "tag", "type": "bytes4" },
+                    { "name": "delta", "type": "int8" },
+                    { "name": "amount", "type": "uint256" },
+                    { "name": "payload", "type": "bytes" }
+                ]
+            },
+            "primaryType": "Group",
+            "domain": {},
+            "message": {
+                "members": [
+                    { "wallet": "0x0000000000000000000000000000000000000001" },
+                    { "wallet": "0x0000000000000000000000000000000000000002" }
+                ],
+                "flags": [true, false],
+                "tag": "0xdeadbeef",
+                "delta": -1,
+                "amount": "1000000000000000000000",
+                "payload": "0x0102"
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            typed_data.encode_type("Group").unwrap(),
+            "Group(Person[] members,bool[2] flags,bytes4 tag,int8 delta,uint256 amount,bytes payload)Person(address wallet)"
+        );
+
+        let person_type = keccak256(b"Person(address wallet)");
+        let mut members = Vec::new();
+        for last in [1u8, 2] {
+            let mut wallet = [0u8; 20];
+            wallet[19] = last;
+            let mut buf = person_type.to_vec();
+            buf.extend_from_slice(&encode_address(&Address::from_bytes(wallet)));
+            members.extend_from_slice(&keccak256(&buf));
+        }
+        let mut flags = [0u8; 64];
+        flags[31] = 1;
+        let mut tag = [0u8; 32];
+        tag[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let amount: [u8; 32] = U256::exp10(21).into();
+
+        let mut expected = typed_data.type_hash("Group").unwrap().to_vec();
+        expected.extend_from_slice(&keccak256(&members));
+        expected.extend_from_slice(&keccak256(&flags));
+        expected.extend_from_slice(&tag);
+        expected.extend_from_slice(&[0xff; 32]);
+        expected.extend_from_slice(&amount);
+        expected.extend_from_slice(&keccak256(&[1, 2]));
+        assert_eq!(
+            typed_data
+                .hash_struct("Group", &typed_data.message)
+                .unwrap(),
+            keccak256(&expected)
+        );
+    }
+
+    #[test]
+    fn test_invalid_payloads() {
+        let mut typed_data = mail();
+        typed_data.message["to"]["wallet"] = json!("0x1234");
+        assert!(matches!(typed_data.hash(), Err(Error::InvalidTypedData(_))));
+
+        let mut typed_data = mail();
+        typed_data.primary_type = "Letter".into();
+        assert!(matches!(typed_data.hash(), Err(Error::InvalidTypedData(_))));
+
+        let mut typed_data = mail();
+        typed_data
+            .types
+            .get_mut("Mail")
+            .unwrap()
+            .push(TypedDataField {
+                name: "count".into(),
+                type_name: "uint8".into(),
+            });
+        typed_data.message["count"] = json!(256);
+        assert!(matches!(typed_data.hash(), Err(Error::InvalidTypedData(_))));
+
+        assert!(matches!(
+            TypedData::from_json("{}"),
+            Err(Error::InvalidTypedData(_))
+        ));
+    }
+}
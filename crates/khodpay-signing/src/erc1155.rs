@@ -0,0 +1,167 @@
+//! ERC-1155 (multi-token) call encoding.
+//!
+//! Builds calldata for single and batch transfers and operator approvals so
+//! multi-token transfers can be signed through the same
+//! [`Eip1559Transaction`](crate::Eip1559Transaction) builder as any other call.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::{erc1155, Address, U256};
+//!
+//! let from: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//! let to: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//!
+//! let data = erc1155::encode_safe_transfer_from(&from, &to, U256::from(1), U256::from(10), &[]);
+//! assert_eq!(&data[..4], &erc1155::SAFE_TRANSFER_FROM_SELECTOR);
+//! ```
+
+use crate::abi::{encode_with_selector, Token};
+use crate::{Address, Error, Result};
+use primitive_types::U256;
+
+pub use crate::erc721::{encode_set_approval_for_all, SET_APPROVAL_FOR_ALL_SELECTOR};
+
+/// Selector of `safeTransferFrom(address,address,uint256,uint256,bytes)`.
+pub const SAFE_TRANSFER_FROM_SELECTOR: [u8; 4] = [0xf2, 0x42, 0x43, 0x2a];
+
+/// Selector of `safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)`.
+pub const SAFE_BATCH_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x2e, 0xb2, 0xc2, 0xd6];
+
+/// Encodes `safeTransferFrom(from, to, id, amount, data)` calldata.
+///
+/// `data` is forwarded to the recipient's `onERC1155Received` hook; pass an empty
+/// slice if none is needed.
+pub fn encode_safe_transfer_from(
+    from: &Address,
+    to: &Address,
+    id: U256,
+    amount: U256,
+    data: &[u8],
+) -> Vec<u8> {
+    encode_with_selector(
+        SAFE_TRANSFER_FROM_SELECTOR,
+        &[
+            Token::Address(*from),
+            Token::Address(*to),
+            Token::Uint(id),
+            Token::Uint(amount),
+            Token::Bytes(data.to_vec()),
+        ],
+    )
+}
+
+/// Encodes `safeBatchTransferFrom(from, to, ids, amounts, data)` calldata.
+///
+/// # Errors
+///
+/// Returns an error if `ids` and `amounts` have different lengths.
+pub fn encode_safe_batch_transfer_from(
+    from: &Address,
+    to: &Address,
+    ids: &[U256],
+    amounts: &[U256],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    if ids.len() != amounts.len() {
+        return Err(Error::ValidationError(format!(
+            "ids and amounts must have the same length ({} != {})",
+            ids.len(),
+            amounts.len()
+        )));
+    }
+
+    let uints = |values: &[U256]| Token::Array(values.iter().copied().map(Token::Uint).collect());
+    Ok(encode_with_selector(
+        SAFE_BATCH_TRANSFER_FROM_SELECTOR,
+        &[
+            Token::Address(*from),
+            Token::Address(*to),
+            uints(ids),
+            uints(amounts),
+            Token::Bytes(data.to_vec()),
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{selector, Function};
+
+    fn alice() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn bob() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        assert_eq!(
+            SAFE_TRANSFER_FROM_SELECTOR,
+            selector("safeTransferFrom(address,address,uint256,uint256,bytes)")
+        );
+        assert_eq!(
+            SAFE_BATCH_TRANSFER_FROM_SELECTOR,
+            selector("safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)")
+        );
+    }
+
+    #[test]
+    fn test_safe_transfer_from_roundtrip() {
+        let f = Function::parse("safeTransferFrom(address,address,uint256,uint256,bytes)").unwrap();
+        let data = encode_safe_transfer_from(&alice(), &bob(), U256::from(5), U256::from(100), &[]);
+
+        assert_eq!(
+            f.decode_input(&data).unwrap(),
+            vec![
+                Token::Address(alice()),
+                Token::Address(bob()),
+                Token::Uint(U256::from(5)),
+                Token::Uint(U256::from(100)),
+                Token::Bytes(vec![]),
+            ]
+        );
+        // 5 head words + empty bytes length word
+        assert_eq!(data.len(), 4 + 6 * 32);
+    }
+
+    #[test]
+    fn test_safe_batch_transfer_from_roundtrip() {
+        let f = Function::parse("safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)")
+            .unwrap();
+        let ids = [U256::from(1), U256::from(2)];
+        let amounts = [U256::from(10), U256::from(20)];
+        let data =
+            encode_safe_batch_transfer_from(&alice(), &bob(), &ids, &amounts, b"hi").unwrap();
+
+        let tokens = f.decode_input(&data).unwrap();
+        assert_eq!(
+            tokens[2],
+            Token::Array(vec![Token::Uint(ids[0]), Token::Uint(ids[1])])
+        );
+        assert_eq!(
+            tokens[3],
+            Token::Array(vec![Token::Uint(amounts[0]), Token::Uint(amounts[1])])
+        );
+        assert_eq!(tokens[4], Token::Bytes(b"hi".to_vec()));
+    }
+
+    #[test]
+    fn test_safe_batch_transfer_length_mismatch() {
+        let result = encode_safe_batch_transfer_from(&alice(), &bob(), &[U256::one()], &[], &[]);
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_set_approval_for_all_reexport() {
+        let data = encode_set_approval_for_all(&bob(), true);
+        assert_eq!(&data[..4], &SET_APPROVAL_FOR_ALL_SELECTOR);
+    }
+}
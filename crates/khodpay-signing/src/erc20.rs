@@ -0,0 +1,492 @@
+//! ERC-20 / BEP-20 token call encoding and return-data decoding.
+//!
+//! Builds calldata for the standard token functions and decodes what they return, so
+//! token workflows can be signed through [`Eip1559Transaction`](crate::Eip1559Transaction)
+//! without a general-purpose ABI dependency.
+//!
+//! | Function | Encoder | Return decoder |
+//! |---|---|---|
+//! | `transfer(address,uint256)` | [`encode_transfer`] | [`decode_bool`] |
+//! | `approve(address,uint256)` | [`encode_approve`] | [`decode_bool`] |
+//! | `transferFrom(address,address,uint256)` | [`encode_transfer_from`] | [`decode_bool`] |
+//! | `allowance(address,address)` | [`encode_allowance`] | [`decode_uint256`] |
+//! | `balanceOf(address)` | [`encode_balance_of`] | [`decode_uint256`] |
+//! | `decimals()` | [`encode_decimals`] | [`decode_decimals`] |
+//! | `symbol()` | [`encode_symbol`] | [`decode_string`] |
+//! | `name()` | [`encode_name`] | [`decode_string`] |
+//!
+//! Incoming calldata can be interpreted with [`Erc20Call::decode`].
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::erc20::{self, Erc20Call};
+//! use khodpay_signing::{Address, Eip1559Transaction, ChainId, Wei, TOKEN_TRANSFER_GAS, U256};
+//!
+//! let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let recipient: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//! let amount = U256::from(25) * U256::exp10(18);
+//!
+//! let data = erc20::encode_transfer(&recipient, amount);
+//! assert_eq!(Erc20Call::decode(&data).unwrap(), Erc20Call::Transfer { to: recipient, amount });
+//!
+//! let tx = Eip1559Transaction::builder()
+//!     .chain_id(ChainId::BscMainnet)
+//!     .nonce(0)
+//!     .max_priority_fee_per_gas(Wei::from_gwei(1))
+//!     .max_fee_per_gas(Wei::from_gwei(5))
+//!     .gas_limit(TOKEN_TRANSFER_GAS)
+//!     .to(usdt)
+//!     .data(data)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::{Address, Error, Result};
+use primitive_types::U256;
+
+// ─── Selectors ───────────────────────────────────────────────────────────────
+
+/// Selector of `transfer(address,uint256)`.
+pub const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+/// Selector of `approve(address,uint256)`.
+pub const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+/// Selector of `transferFrom(address,address,uint256)`.
+pub const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// Selector of `allowance(address,address)`.
+pub const ALLOWANCE_SELECTOR: [u8; 4] = [0xdd, 0x62, 0xed, 0x3e];
+
+/// Selector of `balanceOf(address)`.
+pub const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+
+/// Selector of `decimals()`.
+pub const DECIMALS_SELECTOR: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+
+/// Selector of `symbol()`.
+pub const SYMBOL_SELECTOR: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41];
+
+/// Selector of `name()`.
+pub const NAME_SELECTOR: [u8; 4] = [0x06, 0xfd, 0xde, 0x03];
+
+// ─── Calldata encoders ───────────────────────────────────────────────────────
+
+/// Encodes `transfer(to, amount)` calldata.
+pub fn encode_transfer(to: &Address, amount: U256) -> Vec<u8> {
+    encode_call(TRANSFER_SELECTOR, &[address_word(to), uint_word(amount)])
+}
+
+/// Encodes `approve(spender, amount)` calldata.
+///
+/// Pass [`U256::MAX`] for an unlimited approval.
+pub fn encode_approve(spender: &Address, amount: U256) -> Vec<u8> {
+    encode_call(
+        APPROVE_SELECTOR,
+        &[address_word(spender), uint_word(amount)],
+    )
+}
+
+/// Encodes `transferFrom(from, to, amount)` calldata.
+pub fn encode_transfer_from(from: &Address, to: &Address, amount: U256) -> Vec<u8> {
+    encode_call(
+        TRANSFER_FROM_SELECTOR,
+        &[address_word(from), address_word(to), uint_word(amount)],
+    )
+}
+
+/// Encodes `allowance(owner, spender)` calldata.
+pub fn encode_allowance(owner: &Address, spender: &Address) -> Vec<u8> {
+    encode_call(
+        ALLOWANCE_SELECTOR,
+        &[address_word(owner), address_word(spender)],
+    )
+}
+
+/// Encodes `balanceOf(owner)` calldata.
+pub fn encode_balance_of(owner: &Address) -> Vec<u8> {
+    encode_call(BALANCE_OF_SELECTOR, &[address_word(owner)])
+}
+
+/// Encodes `decimals()` calldata.
+pub fn encode_decimals() -> Vec<u8> {
+    DECIMALS_SELECTOR.to_vec()
+}
+
+/// Encodes `symbol()` calldata.
+pub fn encode_symbol() -> Vec<u8> {
+    SYMBOL_SELECTOR.to_vec()
+}
+
+/// Encodes `name()` calldata.
+pub fn encode_name() -> Vec<u8> {
+    NAME_SELECTOR.to_vec()
+}
+
+// ─── Return-data decoders ────────────────────────────────────────────────────
+
+/// Decodes a `uint256` return value (`balanceOf`, `allowance`).
+///
+/// # Errors
+///
+/// Returns an error if `data` is shorter than one 32-byte word.
+pub fn decode_uint256(data: &[u8]) -> Result<U256> {
+    Ok(U256::from_big_endian(word(data, 0)?))
+}
+
+/// Decodes a `bool` return value (`transfer`, `approve`, `transferFrom`).
+///
+/// Empty return data decodes as `true`: tokens such as USDT on Ethereum do not return a
+/// value and signal failure by reverting instead.
+///
+/// # Errors
+///
+/// Returns an error if the data is non-empty and not a canonical `bool` word.
+pub fn decode_bool(data: &[u8]) -> Result<bool> {
+    if data.is_empty() {
+        return Ok(true);
+    }
+
+    let value = decode_uint256(data)?;
+    if value > U256::one() {
+        return Err(Error::AbiError(format!("invalid bool value: {}", value)));
+    }
+    Ok(value == U256::one())
+}
+
+/// Decodes a `decimals()` return value.
+///
+/// # Errors
+///
+/// Returns an error if the data is too short or the value does not fit in a `u8`.
+pub fn decode_decimals(data: &[u8]) -> Result<u8> {
+    let value = decode_uint256(data)?;
+    if value > U256::from(u8::MAX) {
+        return Err(Error::AbiError(format!("decimals out of range: {}", value)));
+    }
+    Ok(value.low_u32() as u8)
+}
+
+/// Decodes a `string` return value (`symbol`, `name`).
+///
+/// Also accepts the legacy `bytes32` form returned by older tokens (e.g. MKR), trimming
+/// trailing zero bytes.
+///
+/// # Errors
+///
+/// Returns an error if the data is malformed or not valid UTF-8.
+pub fn decode_string(data: &[u8]) -> Result<String> {
+    let bytes = if data.len() == 32 {
+        let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        &data[..end]
+    } else {
+        let offset = word_as_usize(data, 0)?;
+        let len = word_as_usize(data, offset)?;
+        offset
+            .checked_add(32)
+            .and_then(|start| start.checked_add(len).map(|end| (start, end)))
+            .and_then(|(start, end)| data.get(start..end))
+            .ok_or_else(|| Error::AbiError("string data out of bounds".to_string()))?
+    };
+
+    String::from_utf8(bytes.to_vec())
+        .map_err(|e| Error::AbiError(format!("string is not valid UTF-8: {}", e)))
+}
+
+// ─── Calldata decoding ───────────────────────────────────────────────────────
+
+/// A decoded ERC-20 state-changing call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Erc20Call {
+    /// `transfer(to, amount)`.
+    Transfer {
+        /// Recipient.
+        to: Address,
+        /// Token amount in base units.
+        amount: U256,
+    },
+    /// `approve(spender, amount)`.
+    Approve {
+        /// Approved spender.
+        spender: Address,
+        /// Allowance in base units.
+        amount: U256,
+    },
+    /// `transferFrom(from, to, amount)`.
+    TransferFrom {
+        /// Token owner.
+        from: Address,
+        /// Recipient.
+        to: Address,
+        /// Token amount in base units.
+        amount: U256,
+    },
+}
+
+impl Erc20Call {
+    /// Decodes `transfer`, `approve` or `transferFrom` calldata.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the selector is not one of the three calls, the arguments
+    /// are truncated, or an address word has non-zero padding.
+    pub fn decode(calldata: &[u8]) -> Result<Self> {
+        if calldata.len() < 4 {
+            return Err(Error::AbiError(
+                "calldata shorter than selector".to_string(),
+            ));
+        }
+        let (selector, args) = calldata.split_at(4);
+
+        match <[u8; 4]>::try_from(selector).expect("split at 4") {
+            TRANSFER_SELECTOR => Ok(Self::Transfer {
+                to: decode_address_word(args, 0)?,
+                amount: U256::from_big_endian(word(args, 32)?),
+            }),
+            APPROVE_SELECTOR => Ok(Self::Approve {
+                spender: decode_address_word(args, 0)?,
+                amount: U256::from_big_endian(word(args, 32)?),
+            }),
+            TRANSFER_FROM_SELECTOR => Ok(Self::TransferFrom {
+                from: decode_address_word(args, 0)?,
+                to: decode_address_word(args, 32)?,
+                amount: U256::from_big_endian(word(args, 64)?),
+            }),
+            other => Err(Error::AbiError(format!(
+                "unknown ERC-20 selector: 0x{}",
+                hex::encode(other)
+            ))),
+        }
+    }
+
+    /// Re-encodes the call as calldata.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Transfer { to, amount } => encode_transfer(to, *amount),
+            Self::Approve { spender, amount } => encode_approve(spender, *amount),
+            Self::TransferFrom { from, to, amount } => encode_transfer_from(from, to, *amount),
+        }
+    }
+}
+
+// ─── Internal helpers ────────────────────────────────────────────────────────
+
+fn encode_call(selector: [u8; 4], words: &[[u8; 32]]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + 32 * words.len());
+    data.extend_from_slice(&selector);
+    for w in words {
+        data.extend_from_slice(w);
+    }
+    data
+}
+
+fn address_word(address: &Address) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[12..].copy_from_slice(address.as_bytes());
+    w
+}
+
+fn uint_word(value: U256) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    value.to_big_endian(&mut w);
+    w
+}
+
+fn word(data: &[u8], offset: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(32)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| Error::AbiError(format!("data too short: no word at offset {}", offset)))
+}
+
+fn word_as_usize(data: &[u8], offset: usize) -> Result<usize> {
+    let value = U256::from_big_endian(word(data, offset)?);
+    if value > U256::from(u32::MAX) {
+        return Err(Error::AbiError(format!(
+            "offset or length too large: {}",
+            value
+        )));
+    }
+    Ok(value.as_usize())
+}
+
+fn decode_address_word(data: &[u8], offset: usize) -> Result<Address> {
+    let w = word(data, offset)?;
+    if w[..12].iter().any(|&b| b != 0) {
+        return Err(Error::AbiError(
+            "address word has non-zero padding".to_string(),
+        ));
+    }
+    Address::from_slice(&w[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eip712::keccak256;
+
+    fn alice() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn bob() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    fn selector(signature: &str) -> [u8; 4] {
+        keccak256(signature.as_bytes())[..4].try_into().unwrap()
+    }
+
+    // ─── Selectors ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        assert_eq!(TRANSFER_SELECTOR, selector("transfer(address,uint256)"));
+        assert_eq!(APPROVE_SELECTOR, selector("approve(address,uint256)"));
+        assert_eq!(
+            TRANSFER_FROM_SELECTOR,
+            selector("transferFrom(address,address,uint256)")
+        );
+        assert_eq!(ALLOWANCE_SELECTOR, selector("allowance(address,address)"));
+        assert_eq!(BALANCE_OF_SELECTOR, selector("balanceOf(address)"));
+        assert_eq!(DECIMALS_SELECTOR, selector("decimals()"));
+        assert_eq!(SYMBOL_SELECTOR, selector("symbol()"));
+        assert_eq!(NAME_SELECTOR, selector("name()"));
+    }
+
+    // ─── Encoders ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_encode_transfer_layout() {
+        let data = encode_transfer(&alice(), U256::from(1000));
+
+        assert_eq!(data.len(), 4 + 64);
+        assert_eq!(&data[..4], &TRANSFER_SELECTOR);
+        assert_eq!(&data[4..16], &[0u8; 12]);
+        assert_eq!(&data[16..36], alice().as_bytes());
+        assert_eq!(U256::from_big_endian(&data[36..68]), U256::from(1000));
+    }
+
+    #[test]
+    fn test_encode_approve_unlimited() {
+        let data = encode_approve(&bob(), U256::MAX);
+        assert_eq!(&data[..4], &APPROVE_SELECTOR);
+        assert_eq!(&data[36..68], &[0xffu8; 32]);
+    }
+
+    #[test]
+    fn test_encode_transfer_from_layout() {
+        let data = encode_transfer_from(&alice(), &bob(), U256::from(5));
+
+        assert_eq!(data.len(), 4 + 96);
+        assert_eq!(&data[16..36], alice().as_bytes());
+        assert_eq!(&data[48..68], bob().as_bytes());
+        assert_eq!(data[99], 5);
+    }
+
+    #[test]
+    fn test_encode_views() {
+        assert_eq!(encode_allowance(&alice(), &bob()).len(), 4 + 64);
+        assert_eq!(encode_balance_of(&alice()).len(), 4 + 32);
+        assert_eq!(encode_decimals(), DECIMALS_SELECTOR.to_vec());
+        assert_eq!(encode_symbol(), SYMBOL_SELECTOR.to_vec());
+        assert_eq!(encode_name(), NAME_SELECTOR.to_vec());
+    }
+
+    // ─── Return-data decoders ────────────────────────────────────────────────
+
+    #[test]
+    fn test_decode_uint256() {
+        let value = U256::from(123_456_789u64) * U256::exp10(18);
+        assert_eq!(decode_uint256(&uint_word(value)).unwrap(), value);
+        assert!(decode_uint256(&[0u8; 31]).is_err());
+    }
+
+    #[test]
+    fn test_decode_bool() {
+        assert!(decode_bool(&uint_word(U256::one())).unwrap());
+        assert!(!decode_bool(&uint_word(U256::zero())).unwrap());
+        assert!(decode_bool(&[]).unwrap());
+        assert!(decode_bool(&uint_word(U256::from(2))).is_err());
+    }
+
+    #[test]
+    fn test_decode_decimals() {
+        assert_eq!(decode_decimals(&uint_word(U256::from(18))).unwrap(), 18);
+        assert!(decode_decimals(&uint_word(U256::from(256))).is_err());
+    }
+
+    #[test]
+    fn test_decode_string_abi() {
+        // offset = 0x20, length = 4, "USDT" padded
+        let mut data = uint_word(U256::from(32)).to_vec();
+        data.extend_from_slice(&uint_word(U256::from(4)));
+        let mut padded = [0u8; 32];
+        padded[..4].copy_from_slice(b"USDT");
+        data.extend_from_slice(&padded);
+
+        assert_eq!(decode_string(&data).unwrap(), "USDT");
+    }
+
+    #[test]
+    fn test_decode_string_bytes32() {
+        let mut data = [0u8; 32];
+        data[..3].copy_from_slice(b"MKR");
+        assert_eq!(decode_string(&data).unwrap(), "MKR");
+    }
+
+    #[test]
+    fn test_decode_string_out_of_bounds() {
+        let mut data = uint_word(U256::from(32)).to_vec();
+        data.extend_from_slice(&uint_word(U256::from(100)));
+        assert!(decode_string(&data).is_err());
+
+        let mut huge = uint_word(U256::MAX).to_vec();
+        huge.extend_from_slice(&[0u8; 32]);
+        assert!(decode_string(&huge).is_err());
+    }
+
+    // ─── Calldata decoding ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_call_roundtrip() {
+        let calls = [
+            Erc20Call::Transfer {
+                to: alice(),
+                amount: U256::from(1),
+            },
+            Erc20Call::Approve {
+                spender: bob(),
+                amount: U256::MAX,
+            },
+            Erc20Call::TransferFrom {
+                from: alice(),
+                to: bob(),
+                amount: U256::from(42),
+            },
+        ];
+
+        for call in calls {
+            assert_eq!(Erc20Call::decode(&call.encode()).unwrap(), call);
+        }
+    }
+
+    #[test]
+    fn test_call_decode_errors() {
+        assert!(Erc20Call::decode(&[0xa9, 0x05]).is_err());
+        assert!(Erc20Call::decode(&encode_balance_of(&alice())).is_err());
+
+        let mut truncated = encode_transfer(&alice(), U256::one());
+        truncated.pop();
+        assert!(Erc20Call::decode(&truncated).is_err());
+
+        let mut dirty = encode_transfer(&alice(), U256::one());
+        dirty[4] = 1;
+        assert!(Erc20Call::decode(&dirty).is_err());
+    }
+}
@@ -16,6 +16,10 @@
 //! v0.7 packs two gas values into each `bytes32` field to reduce calldata cost:
 //! - `accountGasLimits`: `verificationGasLimit (u128) ‖ callGasLimit (u128)`
 //! - `gasFees`: `maxPriorityFeePerGas (u128) ‖ maxFeePerGas (u128)`
+//! - `paymasterAndData`: `paymaster ‖ verificationGasLimit (u128) ‖ postOpGasLimit (u128) ‖ data`
+//!
+//! [`UserOperation`] keeps these values separate, as bundlers expect them, and
+//! [`UserOperation::pack`] converts to the packed form.
 //!
 //! ## Hash Formula
 //!
@@ -67,10 +71,25 @@
 //!
 //! The canonical ERC-4337 v0.7 EntryPoint address is available as [`ENTRY_POINT_V07`]:
 //! `0x0000000071727De22E5E9d8BAf0edAc6f37da032`
+//!
+//! # Paymasters
+//!
+//! [`Sponsorship`] applies a paymaster service's answer to a [`UserOperation`], and
+//! [`SponsorshipPolicy`] checks the sponsored operation before it is signed. With the
+//! `net` feature, `PaymasterClient` requests sponsorship over ERC-7677.
 
 use crate::eip712::keccak256;
 use crate::{Address, Error, Result, Signature};
 
+mod paymaster;
+
+#[cfg(feature = "net")]
+pub use paymaster::PaymasterClient;
+pub use paymaster::{
+    sign_verifying_paymaster, verifying_paymaster_hash, PaymasterAndData, Sponsorship,
+    SponsorshipPolicy, VerifyingPaymasterData,
+};
+
 /// The canonical ERC-4337 v0.7 EntryPoint contract address.
 pub const ENTRY_POINT_V07: &str = "0x0000000071727De22E5E9d8BAf0edAc6f37da032";
 
@@ -173,6 +192,9 @@ impl PackedUserOperation {
     }
 
     /// Returns the paymaster-specific data (bytes after the 20-byte address).
+    ///
+    /// In v0.7 this starts with the two paymaster gas limits; see
+    /// [`paymaster_verification_gas_limit`](Self::paymaster_verification_gas_limit).
     pub fn paymaster_data(&self) -> &[u8] {
         if self.paymaster_and_data.len() > 20 {
             &self.paymaster_and_data[20..]
@@ -180,6 +202,86 @@ impl PackedUserOperation {
             &[]
         }
     }
+
+    /// Unpacks `paymasterVerificationGasLimit` (bytes 20..36 of `paymaster_and_data`).
+    pub fn paymaster_verification_gas_limit(&self) -> Option<u128> {
+        self.paymaster_and_data
+            .get(20..36)
+            .map(|b| u128::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    /// Unpacks `paymasterPostOpGasLimit` (bytes 36..52 of `paymaster_and_data`).
+    pub fn paymaster_post_op_gas_limit(&self) -> Option<u128> {
+        self.paymaster_and_data
+            .get(36..52)
+            .map(|b| u128::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    /// Computes the user operation hash; see [`hash_user_operation`].
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> [u8; 32] {
+        hash_user_operation(self, entry_point, chain_id)
+    }
+
+    /// Splits the packed fields back into a [`UserOperation`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `init_code` is non-empty but shorter than a factory address,
+    /// or `paymaster_and_data` is non-empty but shorter than the 52-byte
+    /// `paymaster ‖ verificationGasLimit ‖ postOpGasLimit` prefix.
+    pub fn unpack(&self) -> Result<UserOperation> {
+        let (factory, factory_data) = match self.init_code.len() {
+            0 => (None, Vec::new()),
+            len if len < 20 => {
+                return Err(Error::ValidationError(format!(
+                    "init_code must be empty or at least 20 bytes, got {}",
+                    len
+                )))
+            }
+            _ => (
+                Some(Address::from_slice(&self.init_code[..20])?),
+                self.init_code[20..].to_vec(),
+            ),
+        };
+
+        let (paymaster, paymaster_verification_gas_limit, paymaster_post_op_gas_limit) =
+            match self.paymaster_and_data.len() {
+                0 => (None, 0, 0),
+                len if len < PAYMASTER_DATA_OFFSET => {
+                    return Err(Error::ValidationError(format!(
+                        "paymaster_and_data must be empty or at least {} bytes, got {}",
+                        PAYMASTER_DATA_OFFSET, len
+                    )))
+                }
+                _ => (
+                    self.paymaster_address(),
+                    self.paymaster_verification_gas_limit().unwrap_or_default(),
+                    self.paymaster_post_op_gas_limit().unwrap_or_default(),
+                ),
+            };
+
+        Ok(UserOperation {
+            sender: self.sender,
+            nonce: self.nonce,
+            factory,
+            factory_data,
+            call_data: self.call_data.clone(),
+            call_gas_limit: self.call_gas_limit(),
+            verification_gas_limit: self.verification_gas_limit(),
+            pre_verification_gas: self.pre_verification_gas,
+            max_fee_per_gas: self.max_fee_per_gas(),
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas(),
+            paymaster,
+            paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit,
+            paymaster_data: self
+                .paymaster_and_data
+                .get(PAYMASTER_DATA_OFFSET..)
+                .unwrap_or_default()
+                .to_vec(),
+            signature: self.signature.clone(),
+        })
+    }
 }
 
 /// Builder for [`PackedUserOperation`].
@@ -268,7 +370,9 @@ impl PackedUserOperationBuilder {
 
     /// Sets the paymaster from an address and optional paymaster-specific data.
     ///
-    /// Constructs `paymaster_and_data` as `address (20 bytes) ‖ data`.
+    /// Constructs `paymaster_and_data` as `address (20 bytes) ‖ data`. For the v0.7
+    /// EntryPoint `data` must begin with the paymaster gas limits; prefer
+    /// [`paymaster_with_gas_limits`](Self::paymaster_with_gas_limits).
     pub fn paymaster(mut self, address: Address, data: Vec<u8>) -> Self {
         let mut pad = Vec::with_capacity(20 + data.len());
         pad.extend_from_slice(address.as_bytes());
@@ -277,6 +381,22 @@ impl PackedUserOperationBuilder {
         self
     }
 
+    /// Sets a v0.7 paymaster with its gas limits.
+    ///
+    /// Constructs `paymaster_and_data` as
+    /// `address (20) ‖ verificationGasLimit (16) ‖ postOpGasLimit (16) ‖ data`.
+    pub fn paymaster_with_gas_limits(
+        mut self,
+        address: Address,
+        verification_gas_limit: u128,
+        post_op_gas_limit: u128,
+        data: Vec<u8>,
+    ) -> Self {
+        self.paymaster_and_data =
+            pack_paymaster_and_data(address, verification_gas_limit, post_op_gas_limit, &data);
+        self
+    }
+
     /// Sets the raw `paymaster_and_data` bytes directly.
     pub fn paymaster_and_data_raw(mut self, data: Vec<u8>) -> Self {
         self.paymaster_and_data = data;
@@ -313,6 +433,296 @@ impl PackedUserOperationBuilder {
     }
 }
 
+// ─── Unpacked UserOperation ──────────────────────────────────────────────────
+
+/// Offset of the paymaster-specific data in v0.7 `paymasterAndData`.
+const PAYMASTER_DATA_OFFSET: usize = 52;
+
+/// ERC-4337 v0.7 `UserOperation` in its unpacked form.
+///
+/// This is the shape bundlers accept over `eth_sendUserOperation`, with each gas
+/// value and the factory / paymaster fields kept separate. [`pack`](Self::pack)
+/// produces the on-chain [`PackedUserOperation`] used for hashing.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::erc4337::{sign_user_operation, UserOperation, ENTRY_POINT_V07};
+/// use khodpay_signing::{Address, Bip44Signer};
+///
+/// let owner = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+/// let entry_point: Address = ENTRY_POINT_V07.parse().unwrap();
+///
+/// let op = UserOperation::builder()
+///     .sender("0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap())
+///     .nonce(0)
+///     .call_data(vec![0xde, 0xad, 0xbe, 0xef])
+///     .call_gas_limit(300_000)
+///     .verification_gas_limit(150_000)
+///     .pre_verification_gas(50_000)
+///     .max_fee_per_gas(5_000_000_000)
+///     .max_priority_fee_per_gas(1_000_000_000)
+///     .paymaster(Address::from_bytes([0x11; 20]), 60_000, 40_000, vec![0x01])
+///     .build()
+///     .unwrap();
+///
+/// let signature = sign_user_operation(&owner, &op.pack(), entry_point, 56).unwrap();
+/// let signed = op.with_signature(signature.to_bytes().to_vec());
+/// assert_eq!(signed.pack().paymaster_post_op_gas_limit(), Some(40_000));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserOperation {
+    /// The smart account address.
+    pub sender: Address,
+    /// The account nonce.
+    pub nonce: u128,
+    /// Account factory, if the account is deployed by this operation.
+    pub factory: Option<Address>,
+    /// Calldata for the factory.
+    pub factory_data: Vec<u8>,
+    /// Encoded calldata for the smart account to execute.
+    pub call_data: Vec<u8>,
+    /// Gas for the execution phase.
+    pub call_gas_limit: u128,
+    /// Gas for `validateUserOp` (and account deployment).
+    pub verification_gas_limit: u128,
+    /// Pre-verification gas (bundler overhead).
+    pub pre_verification_gas: u128,
+    /// Maximum total fee per gas (in wei).
+    pub max_fee_per_gas: u128,
+    /// Maximum priority fee per gas (in wei).
+    pub max_priority_fee_per_gas: u128,
+    /// Paymaster sponsoring the operation, if any.
+    pub paymaster: Option<Address>,
+    /// Gas for the paymaster's `validatePaymasterUserOp`.
+    pub paymaster_verification_gas_limit: u128,
+    /// Gas for the paymaster's `postOp`.
+    pub paymaster_post_op_gas_limit: u128,
+    /// Paymaster-specific data.
+    pub paymaster_data: Vec<u8>,
+    /// The signature (set after signing).
+    pub signature: Vec<u8>,
+}
+
+impl UserOperation {
+    /// Returns a builder for constructing a `UserOperation`.
+    pub fn builder() -> UserOperationBuilder {
+        UserOperationBuilder::default()
+    }
+
+    /// Packs the operation into the on-chain v0.7 format.
+    pub fn pack(&self) -> PackedUserOperation {
+        let init_code = match self.factory {
+            Some(factory) => [factory.as_bytes().as_slice(), &self.factory_data].concat(),
+            None => Vec::new(),
+        };
+        let paymaster_and_data = match self.paymaster {
+            Some(paymaster) => pack_paymaster_and_data(
+                paymaster,
+                self.paymaster_verification_gas_limit,
+                self.paymaster_post_op_gas_limit,
+                &self.paymaster_data,
+            ),
+            None => Vec::new(),
+        };
+
+        PackedUserOperation {
+            sender: self.sender,
+            nonce: self.nonce,
+            init_code,
+            call_data: self.call_data.clone(),
+            account_gas_limits: PackedUserOperation::pack_gas_limits(
+                self.verification_gas_limit,
+                self.call_gas_limit,
+            ),
+            pre_verification_gas: self.pre_verification_gas,
+            gas_fees: PackedUserOperation::pack_gas_fees(
+                self.max_priority_fee_per_gas,
+                self.max_fee_per_gas,
+            ),
+            paymaster_and_data,
+            signature: self.signature.clone(),
+        }
+    }
+
+    /// Computes the user operation hash; see [`hash_user_operation`].
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> [u8; 32] {
+        hash_user_operation(&self.pack(), entry_point, chain_id)
+    }
+
+    /// Returns the operation with `signature` attached.
+    pub fn with_signature(mut self, signature: Vec<u8>) -> Self {
+        self.signature = signature;
+        self
+    }
+}
+
+impl From<UserOperation> for PackedUserOperation {
+    fn from(op: UserOperation) -> Self {
+        op.pack()
+    }
+}
+
+/// Builder for [`UserOperation`].
+#[derive(Debug, Clone, Default)]
+pub struct UserOperationBuilder {
+    sender: Option<Address>,
+    nonce: Option<u128>,
+    factory: Option<(Address, Vec<u8>)>,
+    call_data: Vec<u8>,
+    call_gas_limit: Option<u128>,
+    verification_gas_limit: Option<u128>,
+    pre_verification_gas: Option<u128>,
+    max_fee_per_gas: Option<u128>,
+    max_priority_fee_per_gas: Option<u128>,
+    paymaster: Option<(Address, u128, u128, Vec<u8>)>,
+}
+
+impl UserOperationBuilder {
+    /// Sets the smart account address (required).
+    pub fn sender(mut self, address: Address) -> Self {
+        self.sender = Some(address);
+        self
+    }
+
+    /// Sets the account nonce (required).
+    pub fn nonce(mut self, nonce: u128) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Deploys the account through `factory` with `data`.
+    pub fn factory(mut self, factory: Address, data: Vec<u8>) -> Self {
+        self.factory = Some((factory, data));
+        self
+    }
+
+    /// Sets the encoded calldata for the smart account to execute.
+    pub fn call_data(mut self, call_data: Vec<u8>) -> Self {
+        self.call_data = call_data;
+        self
+    }
+
+    /// Sets the execution gas limit (required).
+    pub fn call_gas_limit(mut self, gas: u128) -> Self {
+        self.call_gas_limit = Some(gas);
+        self
+    }
+
+    /// Sets the verification gas limit (required).
+    pub fn verification_gas_limit(mut self, gas: u128) -> Self {
+        self.verification_gas_limit = Some(gas);
+        self
+    }
+
+    /// Sets the pre-verification gas (required).
+    pub fn pre_verification_gas(mut self, gas: u128) -> Self {
+        self.pre_verification_gas = Some(gas);
+        self
+    }
+
+    /// Sets the maximum fee per gas (required).
+    pub fn max_fee_per_gas(mut self, fee: u128) -> Self {
+        self.max_fee_per_gas = Some(fee);
+        self
+    }
+
+    /// Sets the maximum priority fee per gas (required).
+    pub fn max_priority_fee_per_gas(mut self, fee: u128) -> Self {
+        self.max_priority_fee_per_gas = Some(fee);
+        self
+    }
+
+    /// Sets the paymaster, its two gas limits and paymaster-specific data.
+    pub fn paymaster(
+        mut self,
+        address: Address,
+        verification_gas_limit: u128,
+        post_op_gas_limit: u128,
+        data: Vec<u8>,
+    ) -> Self {
+        self.paymaster = Some((address, verification_gas_limit, post_op_gas_limit, data));
+        self
+    }
+
+    /// Builds the [`UserOperation`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required field is missing or
+    /// `max_priority_fee_per_gas` exceeds `max_fee_per_gas`.
+    pub fn build(self) -> Result<UserOperation> {
+        let required = |field: &str| Error::ValidationError(format!("{} is required", field));
+        let max_fee_per_gas = self
+            .max_fee_per_gas
+            .ok_or_else(|| required("max_fee_per_gas"))?;
+        let max_priority_fee_per_gas = self
+            .max_priority_fee_per_gas
+            .ok_or_else(|| required("max_priority_fee_per_gas"))?;
+        if max_priority_fee_per_gas > max_fee_per_gas {
+            return Err(Error::ValidationError(
+                "max_fee_per_gas must be >= max_priority_fee_per_gas".to_string(),
+            ));
+        }
+
+        let (factory, factory_data) = match self.factory {
+            Some((factory, data)) => (Some(factory), data),
+            None => (None, Vec::new()),
+        };
+        let (
+            paymaster,
+            paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit,
+            paymaster_data,
+        ) = match self.paymaster {
+            Some((address, verification, post_op, data)) => {
+                (Some(address), verification, post_op, data)
+            }
+            None => (None, 0, 0, Vec::new()),
+        };
+
+        Ok(UserOperation {
+            sender: self.sender.ok_or_else(|| required("sender"))?,
+            nonce: self.nonce.ok_or_else(|| required("nonce"))?,
+            factory,
+            factory_data,
+            call_data: self.call_data,
+            call_gas_limit: self
+                .call_gas_limit
+                .ok_or_else(|| required("call_gas_limit"))?,
+            verification_gas_limit: self
+                .verification_gas_limit
+                .ok_or_else(|| required("verification_gas_limit"))?,
+            pre_verification_gas: self
+                .pre_verification_gas
+                .ok_or_else(|| required("pre_verification_gas"))?,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            paymaster,
+            paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit,
+            paymaster_data,
+            signature: Vec::new(),
+        })
+    }
+}
+
+/// Builds v0.7 `paymasterAndData`:
+/// `paymaster (20) ‖ verificationGasLimit (16) ‖ postOpGasLimit (16) ‖ data`.
+fn pack_paymaster_and_data(
+    paymaster: Address,
+    verification_gas_limit: u128,
+    post_op_gas_limit: u128,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(PAYMASTER_DATA_OFFSET + data.len());
+    out.extend_from_slice(paymaster.as_bytes());
+    out.extend_from_slice(&verification_gas_limit.to_be_bytes());
+    out.extend_from_slice(&post_op_gas_limit.to_be_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
 /// Computes the ERC-4337 v0.7 user operation hash.
 ///
 /// ```text
@@ -352,13 +762,19 @@ pub fn hash_user_operation(
 ///
 /// # Errors
 ///
-/// Returns an error if the underlying ECDSA signing fails.
+/// Returns [`Error::ChainIdMismatch`] if the signer is
+/// bound to a different chain, [`Error::PolicyViolation`] if the signer's policy
+/// refuses the operation, or an error if the underlying ECDSA signing fails.
 pub fn sign_user_operation(
     signer: &crate::Bip44Signer,
     user_op: &PackedUserOperation,
     entry_point: Address,
     chain_id: u64,
 ) -> Result<Signature> {
+    signer.check_chain_id(chain_id)?;
+    if let Some(policy) = signer.policy() {
+        policy.check_user_operation(user_op, chain_id)?;
+    }
     let hash = hash_user_operation(user_op, entry_point, chain_id);
     signer.sign_hash(&hash)
 }
@@ -681,6 +1097,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bound_signer_rejects_other_chain() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_chain_id(crate::ChainId::BscMainnet);
+        let op = minimal_user_op();
+
+        assert!(sign_user_operation(&signer, &op, test_entry_point(), 56).is_ok());
+        assert!(matches!(
+            sign_user_operation(&signer, &op, test_entry_point(), 97),
+            Err(crate::Error::ChainIdMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_signature_bytes_roundtrip() {
         let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
@@ -750,4 +1180,142 @@ mod tests {
             hash_user_operation(&op_no_init, test_entry_point(), 56)
         );
     }
+
+    fn full_user_op() -> UserOperation {
+        UserOperation::builder()
+            .sender(test_sender())
+            .nonce(7)
+            .factory(Address::from_bytes([0xfa; 20]), vec![0xaa, 0xbb])
+            .call_data(vec![0xde, 0xad, 0xbe, 0xef])
+            .call_gas_limit(300_000)
+            .verification_gas_limit(150_000)
+            .pre_verification_gas(50_000)
+            .max_fee_per_gas(5_000_000_000)
+            .max_priority_fee_per_gas(1_000_000_000)
+            .paymaster(test_paymaster(), 60_000, 40_000, vec![0x01, 0x02])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_user_op_pack_layout() {
+        let packed = full_user_op().pack();
+
+        assert_eq!(&packed.init_code[..20], &[0xfa; 20]);
+        assert_eq!(&packed.init_code[20..], &[0xaa, 0xbb]);
+        assert_eq!(packed.verification_gas_limit(), 150_000);
+        assert_eq!(packed.call_gas_limit(), 300_000);
+        assert_eq!(packed.max_priority_fee_per_gas(), 1_000_000_000);
+        assert_eq!(packed.max_fee_per_gas(), 5_000_000_000);
+        assert_eq!(packed.paymaster_and_data.len(), 52 + 2);
+        assert_eq!(packed.paymaster_address(), Some(test_paymaster()));
+        assert_eq!(packed.paymaster_verification_gas_limit(), Some(60_000));
+        assert_eq!(packed.paymaster_post_op_gas_limit(), Some(40_000));
+    }
+
+    #[test]
+    fn test_user_op_matches_packed_builder() {
+        let op = UserOperation::builder()
+            .sender(test_sender())
+            .nonce(0)
+            .call_data(vec![0xde, 0xad, 0xbe, 0xef])
+            .call_gas_limit(300_000)
+            .verification_gas_limit(150_000)
+            .pre_verification_gas(50_000)
+            .max_fee_per_gas(5_000_000_000)
+            .max_priority_fee_per_gas(1_000_000_000)
+            .build()
+            .unwrap();
+
+        assert_eq!(op.pack(), minimal_user_op());
+        assert_eq!(
+            op.hash(test_entry_point(), 56),
+            hash_user_operation(&minimal_user_op(), test_entry_point(), 56)
+        );
+    }
+
+    #[test]
+    fn test_user_op_pack_unpack_round_trip() {
+        let op = full_user_op().with_signature(vec![0x55; 65]);
+        assert_eq!(op.pack().unpack().unwrap(), op);
+        assert_eq!(
+            minimal_user_op().unpack().unwrap().pack(),
+            minimal_user_op()
+        );
+    }
+
+    #[test]
+    fn test_unpack_rejects_truncated_fields() {
+        let mut op = minimal_user_op();
+        op.init_code = vec![0x01; 10];
+        assert!(op.unpack().is_err());
+
+        let mut op = minimal_user_op();
+        op.paymaster_and_data = test_paymaster().as_bytes().to_vec();
+        assert!(op.unpack().is_err());
+    }
+
+    #[test]
+    fn test_paymaster_with_gas_limits_builder() {
+        let op = PackedUserOperation::builder()
+            .sender(test_sender())
+            .nonce(0)
+            .account_gas_limits(150_000, 300_000)
+            .pre_verification_gas(50_000)
+            .gas_fees(1_000_000_000, 5_000_000_000)
+            .paymaster_with_gas_limits(test_paymaster(), 60_000, 40_000, vec![0x09])
+            .build()
+            .unwrap();
+
+        assert_eq!(op.paymaster_verification_gas_limit(), Some(60_000));
+        assert_eq!(op.paymaster_post_op_gas_limit(), Some(40_000));
+        assert_eq!(op.unpack().unwrap().paymaster_data, vec![0x09]);
+        assert_eq!(minimal_user_op().paymaster_post_op_gas_limit(), None);
+    }
+
+    #[test]
+    fn test_user_op_builder_validation() {
+        assert!(UserOperation::builder()
+            .sender(test_sender())
+            .nonce(0)
+            .call_gas_limit(1)
+            .verification_gas_limit(1)
+            .pre_verification_gas(1)
+            .max_fee_per_gas(1)
+            .build()
+            .is_err());
+
+        assert!(UserOperation::builder()
+            .sender(test_sender())
+            .nonce(0)
+            .call_gas_limit(1)
+            .verification_gas_limit(1)
+            .pre_verification_gas(1)
+            .max_fee_per_gas(1)
+            .max_priority_fee_per_gas(2)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_user_op_sign_and_verify() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let op = full_user_op();
+        let signature = sign_user_operation(&signer, &op.pack(), test_entry_point(), 56).unwrap();
+
+        assert!(verify_user_operation(
+            &op.pack(),
+            test_entry_point(),
+            56,
+            &signature,
+            signer.address()
+        )
+        .unwrap());
+        // The signature is not part of the hash
+        let signed = op.with_signature(signature.to_bytes().to_vec());
+        assert_eq!(
+            signed.hash(test_entry_point(), 56),
+            full_user_op().pack().hash(test_entry_point(), 56)
+        );
+    }
 }
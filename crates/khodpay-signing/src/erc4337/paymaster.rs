@@ -0,0 +1,1069 @@
+//! Paymaster sponsorship of user operations.
+//!
+//! A paymaster pays an operation's gas instead of the account. The operation names it
+//! in `paymasterAndData`, which the account signs over, so sponsorship happens before
+//! signing:
+//!
+//! 1. Build the [`UserOperation`] without a paymaster.
+//! 2. Ask the paymaster service for a [`Sponsorship`]; with the `net` feature,
+//!    `PaymasterClient` speaks the [ERC-7677](https://eips.ethereum.org/EIPS/eip-7677)
+//!    `pm_getPaymasterStubData` / `pm_getPaymasterData` methods.
+//! 3. Check the sponsored operation against a [`SponsorshipPolicy`]: a paymaster
+//!    service can name any paymaster, including one that charges the account in
+//!    tokens, and a verifying paymaster's signature can be expired or for another
+//!    operation, which the bundler would only report after submission.
+//! 4. Sign and submit.
+//!
+//! [`VerifyingPaymasterData`] reads and writes the data of the reference
+//! `VerifyingPaymaster`, whose off-chain signer approves each operation for a time
+//! window.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use primitive_types::U256;
+
+use super::{pack_paymaster_and_data, UserOperation, PAYMASTER_DATA_OFFSET};
+use crate::abi::{self, Token};
+use crate::eip191::hash_message;
+use crate::eip712::keccak256;
+use crate::{Address, Bip44Signer, Error, Result, Signature};
+
+/// Length of the `abi.encode(uint48 validUntil, uint48 validAfter)` prefix of
+/// verifying-paymaster data.
+const VALIDITY_LENGTH: usize = 64;
+
+/// Largest `uint48`, the type of the validity timestamps.
+const MAX_UINT48: u64 = (1 << 48) - 1;
+
+/// The v0.7 `paymasterAndData` fields of a sponsored operation.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::erc4337::PaymasterAndData;
+/// use khodpay_signing::Address;
+///
+/// let fields = PaymasterAndData::new(Address::from_bytes([0x11; 20]), 60_000, 40_000, vec![1]);
+/// let encoded = fields.encode();
+/// assert_eq!(encoded.len(), 52 + 1);
+/// assert_eq!(PaymasterAndData::decode(&encoded).unwrap(), fields);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymasterAndData {
+    /// The paymaster contract.
+    pub paymaster: Address,
+    /// Gas for the paymaster's `validatePaymasterUserOp`.
+    pub verification_gas_limit: u128,
+    /// Gas for the paymaster's `postOp`.
+    pub post_op_gas_limit: u128,
+    /// Paymaster-specific data.
+    pub data: Vec<u8>,
+}
+
+impl PaymasterAndData {
+    /// Creates the fields for `paymaster`.
+    pub fn new(
+        paymaster: Address,
+        verification_gas_limit: u128,
+        post_op_gas_limit: u128,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            paymaster,
+            verification_gas_limit,
+            post_op_gas_limit,
+            data,
+        }
+    }
+
+    /// Encodes `paymaster (20) ‖ verificationGasLimit (16) ‖ postOpGasLimit (16) ‖ data`.
+    pub fn encode(&self) -> Vec<u8> {
+        pack_paymaster_and_data(
+            self.paymaster,
+            self.verification_gas_limit,
+            self.post_op_gas_limit,
+            &self.data,
+        )
+    }
+
+    /// Decodes packed `paymasterAndData`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is shorter than the 52-byte prefix.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < PAYMASTER_DATA_OFFSET {
+            return Err(Error::PaymasterError(format!(
+                "paymasterAndData must be at least {} bytes, got {}",
+                PAYMASTER_DATA_OFFSET,
+                bytes.len()
+            )));
+        }
+        Ok(Self {
+            paymaster: Address::from_slice(&bytes[..20])?,
+            verification_gas_limit: u128::from_be_bytes(bytes[20..36].try_into().unwrap()),
+            post_op_gas_limit: u128::from_be_bytes(bytes[36..52].try_into().unwrap()),
+            data: bytes[PAYMASTER_DATA_OFFSET..].to_vec(),
+        })
+    }
+}
+
+impl UserOperation {
+    /// Returns the paymaster fields, or `None` if the operation is not sponsored.
+    pub fn paymaster_and_data(&self) -> Option<PaymasterAndData> {
+        self.paymaster.map(|paymaster| {
+            PaymasterAndData::new(
+                paymaster,
+                self.paymaster_verification_gas_limit,
+                self.paymaster_post_op_gas_limit,
+                self.paymaster_data.clone(),
+            )
+        })
+    }
+
+    /// Returns the operation sponsored through `fields`.
+    ///
+    /// The signature is cleared, as it covers `paymasterAndData`.
+    pub fn with_paymaster(mut self, fields: PaymasterAndData) -> Self {
+        self.paymaster = Some(fields.paymaster);
+        self.paymaster_verification_gas_limit = fields.verification_gas_limit;
+        self.paymaster_post_op_gas_limit = fields.post_op_gas_limit;
+        self.paymaster_data = fields.data;
+        self.signature.clear();
+        self
+    }
+}
+
+/// A paymaster service's answer to a sponsorship request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sponsorship {
+    /// The paymaster contract.
+    pub paymaster: Address,
+    /// Paymaster-specific data.
+    pub paymaster_data: Vec<u8>,
+    /// Gas for `validatePaymasterUserOp`, or `None` to keep the operation's.
+    pub verification_gas_limit: Option<u128>,
+    /// Gas for `postOp`, or `None` to keep the operation's.
+    pub post_op_gas_limit: Option<u128>,
+    /// Name of the sponsor, for display.
+    pub sponsor: Option<String>,
+    /// Whether the data is final, so no second request is needed after gas estimation.
+    pub is_final: bool,
+}
+
+impl Sponsorship {
+    /// Returns `op` sponsored by this paymaster, with its signature cleared.
+    pub fn apply(&self, op: UserOperation) -> UserOperation {
+        let fields = PaymasterAndData::new(
+            self.paymaster,
+            self.verification_gas_limit
+                .unwrap_or(op.paymaster_verification_gas_limit),
+            self.post_op_gas_limit
+                .unwrap_or(op.paymaster_post_op_gas_limit),
+            self.paymaster_data.clone(),
+        );
+        op.with_paymaster(fields)
+    }
+}
+
+/// Paymaster data of the reference v0.7 `VerifyingPaymaster`:
+/// `abi.encode(uint48 validUntil, uint48 validAfter) ‖ signature`.
+///
+/// The paymaster's off-chain signer signs [`verifying_paymaster_hash`] with
+/// `personal_sign`; the operation is valid after `valid_after` and until
+/// `valid_until`, where `0` means no expiry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingPaymasterData {
+    /// Last valid timestamp, or `0` for none.
+    pub valid_until: u64,
+    /// Timestamp the operation becomes valid after.
+    pub valid_after: u64,
+    /// Signature of the paymaster's signer.
+    pub signature: Vec<u8>,
+}
+
+impl VerifyingPaymasterData {
+    /// Encodes the data for [`PaymasterAndData::data`].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = abi::encode(&[
+            Token::Uint(U256::from(self.valid_until)),
+            Token::Uint(U256::from(self.valid_after)),
+        ]);
+        out.extend_from_slice(&self.signature);
+        out
+    }
+
+    /// Decodes paymaster-specific data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is shorter than the validity window or a
+    /// timestamp does not fit a `uint48`.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < VALIDITY_LENGTH {
+            return Err(Error::PaymasterError(format!(
+                "verifying paymaster data must be at least {} bytes, got {}",
+                VALIDITY_LENGTH,
+                data.len()
+            )));
+        }
+        let timestamp = |word: &[u8]| {
+            let value = U256::from_big_endian(word);
+            if value > U256::from(MAX_UINT48) {
+                return Err(Error::PaymasterError(format!(
+                    "timestamp {} does not fit a uint48",
+                    value
+                )));
+            }
+            Ok(value.as_u64())
+        };
+        Ok(Self {
+            valid_until: timestamp(&data[..32])?,
+            valid_after: timestamp(&data[32..64])?,
+            signature: data[VALIDITY_LENGTH..].to_vec(),
+        })
+    }
+
+    /// Returns `true` if the window includes `timestamp`, as the EntryPoint checks it.
+    pub fn is_valid_at(&self, timestamp: u64) -> bool {
+        timestamp > self.valid_after && (self.valid_until == 0 || timestamp <= self.valid_until)
+    }
+}
+
+/// Computes the hash a `VerifyingPaymaster` signer approves for `op`.
+///
+/// Matches the contract's `getHash(userOp, validUntil, validAfter)`: the operation
+/// without its signature and paymaster data, the chain ID, the paymaster address and
+/// the validity window.
+///
+/// # Errors
+///
+/// Returns an error if `op` has no paymaster.
+pub fn verifying_paymaster_hash(
+    op: &UserOperation,
+    chain_id: u64,
+    valid_until: u64,
+    valid_after: u64,
+) -> Result<[u8; 32]> {
+    let paymaster = op
+        .paymaster
+        .ok_or_else(|| Error::PaymasterError("operation has no paymaster".to_string()))?;
+    let packed = op.pack();
+    let paymaster_gas_limits = packed.paymaster_and_data[20..PAYMASTER_DATA_OFFSET].to_vec();
+
+    Ok(keccak256(&abi::encode(&[
+        Token::Address(packed.sender),
+        Token::Uint(U256::from(packed.nonce)),
+        Token::FixedBytes(keccak256(&packed.init_code).to_vec()),
+        Token::FixedBytes(keccak256(&packed.call_data).to_vec()),
+        Token::FixedBytes(packed.account_gas_limits.to_vec()),
+        Token::FixedBytes(paymaster_gas_limits),
+        Token::Uint(U256::from(packed.pre_verification_gas)),
+        Token::FixedBytes(packed.gas_fees.to_vec()),
+        Token::Uint(U256::from(chain_id)),
+        Token::Address(paymaster),
+        Token::Uint(U256::from(valid_until)),
+        Token::Uint(U256::from(valid_after)),
+    ])))
+}
+
+/// Signs `op` as the off-chain signer of a `VerifyingPaymaster`, for wallets that run
+/// their own paymaster.
+///
+/// The signature uses `v` = `27` / `28`, as the contract's `ECDSA.recover` expects.
+/// Attach the result with [`UserOperation::with_paymaster`].
+///
+/// # Errors
+///
+/// Returns [`Error::ChainIdMismatch`] if the signer is bound to another chain,
+/// [`Error::PolicyViolation`] if the signer's policy refuses the operation's gas or
+/// fees, or an error if `op` has no paymaster, a timestamp does not fit a `uint48` or signing fails.
+pub fn sign_verifying_paymaster(
+    signer: &Bip44Signer,
+    op: &UserOperation,
+    chain_id: u64,
+    valid_until: u64,
+    valid_after: u64,
+) -> Result<VerifyingPaymasterData> {
+    signer.check_chain_id(chain_id)?;
+    if let Some(policy) = signer.policy() {
+        policy.check_user_operation(&op.pack(), chain_id)?;
+    }
+    if valid_until > MAX_UINT48 || valid_after > MAX_UINT48 {
+        return Err(Error::PaymasterError(
+            "timestamps must fit a uint48".to_string(),
+        ));
+    }
+    let hash = verifying_paymaster_hash(op, chain_id, valid_until, valid_after)?;
+    let mut signature = signer.sign_message(&hash)?.to_bytes();
+    if signature[64] < 27 {
+        signature[64] += 27;
+    }
+    Ok(VerifyingPaymasterData {
+        valid_until,
+        valid_after,
+        signature: signature.to_vec(),
+    })
+}
+
+/// Which paymasters may sponsor an operation, checked before signing it.
+///
+/// [`check`](Self::check) compares the sponsored operation with the one sent for
+/// sponsorship and refuses it if:
+///
+/// - it has no paymaster, or one that is not allowed;
+/// - its paymaster verification gas limit is zero;
+/// - the sender, nonce, factory or calldata changed;
+/// - for a verifying paymaster, the signature is not from its signer, or the validity
+///   window does not cover the next [`min_validity`](Self::min_validity) seconds.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::erc4337::{
+///     sign_verifying_paymaster, PaymasterAndData, SponsorshipPolicy, UserOperation,
+/// };
+/// use khodpay_signing::{Address, Bip44Signer};
+///
+/// let paymaster = Address::from_bytes([0x11; 20]);
+/// let paymaster_signer = Bip44Signer::from_private_key(&[2u8; 32]).unwrap();
+///
+/// let requested = UserOperation::builder()
+///     .sender(Address::from_bytes([0x22; 20]))
+///     .nonce(0)
+///     .call_data(vec![0xde, 0xad, 0xbe, 0xef])
+///     .call_gas_limit(300_000)
+///     .verification_gas_limit(150_000)
+///     .pre_verification_gas(50_000)
+///     .max_fee_per_gas(5_000_000_000)
+///     .max_priority_fee_per_gas(1_000_000_000)
+///     .build()
+///     .unwrap();
+///
+/// // The paymaster service fills in its fields and signs
+/// let stub = PaymasterAndData::new(paymaster, 60_000, 0, vec![]);
+/// let op = requested.clone().with_paymaster(stub.clone());
+/// let data = sign_verifying_paymaster(&paymaster_signer, &op, 56, 1_900_000_000, 0).unwrap();
+/// let sponsored = op.with_paymaster(PaymasterAndData { data: data.encode(), ..stub });
+///
+/// let policy = SponsorshipPolicy::new()
+///     .allow_verifying_paymaster(paymaster, paymaster_signer.address())
+///     .min_validity(60);
+/// policy.check_at(&requested, &sponsored, 56, 1_800_000_000).unwrap();
+/// assert!(policy.check_at(&requested, &sponsored, 56, 1_900_000_000).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SponsorshipPolicy {
+    paymasters: Vec<(Address, Option<Address>)>,
+    min_validity: u64,
+}
+
+impl SponsorshipPolicy {
+    /// Creates a policy that allows no paymaster.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows `paymaster`, without checking its data.
+    pub fn allow_paymaster(mut self, paymaster: Address) -> Self {
+        self.paymasters.retain(|(allowed, _)| *allowed != paymaster);
+        self.paymasters.push((paymaster, None));
+        self
+    }
+
+    /// Allows the `VerifyingPaymaster` at `paymaster`, whose data must be signed by
+    /// `signer`.
+    pub fn allow_verifying_paymaster(mut self, paymaster: Address, signer: Address) -> Self {
+        self.paymasters.retain(|(allowed, _)| *allowed != paymaster);
+        self.paymasters.push((paymaster, Some(signer)));
+        self
+    }
+
+    /// Requires a verifying paymaster's approval to stay valid for `seconds` more,
+    /// leaving the bundler time to include the operation.
+    pub fn min_validity(mut self, seconds: u64) -> Self {
+        self.min_validity = seconds;
+        self
+    }
+
+    /// Returns `true` if `paymaster` is allowed.
+    pub fn allows_paymaster(&self, paymaster: Address) -> bool {
+        self.paymasters
+            .iter()
+            .any(|(allowed, _)| *allowed == paymaster)
+    }
+
+    /// Checks `sponsored`, the paymaster service's version of `requested`, at the
+    /// current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PaymasterError`] if the policy refuses the operation.
+    pub fn check(
+        &self,
+        requested: &UserOperation,
+        sponsored: &UserOperation,
+        chain_id: u64,
+    ) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::PaymasterError(format!("system clock error: {}", e)))?
+            .as_secs();
+        self.check_at(requested, sponsored, chain_id, now)
+    }
+
+    /// Checks `sponsored`, the paymaster service's version of `requested`, at Unix
+    /// time `timestamp`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PaymasterError`] if the policy refuses the operation.
+    pub fn check_at(
+        &self,
+        requested: &UserOperation,
+        sponsored: &UserOperation,
+        chain_id: u64,
+        timestamp: u64,
+    ) -> Result<()> {
+        let reject = |reason: String| Err(Error::PaymasterError(reason));
+
+        let Some(paymaster) = sponsored.paymaster else {
+            return reject("operation is not sponsored".to_string());
+        };
+        let Some((_, signer)) = self
+            .paymasters
+            .iter()
+            .find(|(allowed, _)| *allowed == paymaster)
+        else {
+            return reject(format!("paymaster {} is not allowed", paymaster));
+        };
+        if sponsored.paymaster_verification_gas_limit == 0 {
+            return reject("paymaster verification gas limit is zero".to_string());
+        }
+
+        let changed = [
+            ("sender", requested.sender != sponsored.sender),
+            ("nonce", requested.nonce != sponsored.nonce),
+            ("factory", requested.factory != sponsored.factory),
+            (
+                "factory data",
+                requested.factory_data != sponsored.factory_data,
+            ),
+            ("calldata", requested.call_data != sponsored.call_data),
+        ];
+        if let Some((field, _)) = changed.iter().find(|(_, changed)| *changed) {
+            return reject(format!("sponsored operation has a different {}", field));
+        }
+
+        let Some(signer) = signer else {
+            return Ok(());
+        };
+        let data = VerifyingPaymasterData::decode(&sponsored.paymaster_data)?;
+        let deadline = timestamp.saturating_add(self.min_validity);
+        if !data.is_valid_at(timestamp) || !data.is_valid_at(deadline) {
+            return reject(format!(
+                "sponsorship valid after {} until {} does not cover {}..={}",
+                data.valid_after, data.valid_until, timestamp, deadline
+            ));
+        }
+        let signature = Signature::from_bytes(&data.signature).ok_or_else(|| {
+            Error::PaymasterError(format!(
+                "paymaster signature must be 65 bytes, got {}",
+                data.signature.len()
+            ))
+        })?;
+        let hash =
+            verifying_paymaster_hash(sponsored, chain_id, data.valid_until, data.valid_after)?;
+        let recovered = crate::recover_signer(&hash_message(&hash), &signature)?;
+        if recovered != *signer {
+            return reject(format!(
+                "paymaster data is signed by {}, expected {}",
+                recovered, signer
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "net")]
+pub use net::PaymasterClient;
+
+#[cfg(feature = "net")]
+mod net {
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    use super::{Sponsorship, UserOperation};
+    use crate::erc4337::ENTRY_POINT_V07;
+    use crate::rpc::{parse_bytes, parse_u256, RpcClient};
+    use crate::{Address, Error, Result};
+
+    /// An [ERC-7677](https://eips.ethereum.org/EIPS/eip-7677) paymaster service.
+    ///
+    /// Sponsorship takes two requests: [`stub_data`](Self::stub_data) returns
+    /// placeholder data and the paymaster gas limits for gas estimation, then
+    /// [`paymaster_data`](Self::paymaster_data) returns the final data for the
+    /// estimated operation. [`sponsor`](Self::sponsor) does both, skipping the second
+    /// if the stub is already final.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # async fn example(op: khodpay_signing::erc4337::UserOperation) -> khodpay_signing::Result<()> {
+    /// use khodpay_signing::erc4337::{PaymasterClient, SponsorshipPolicy};
+    /// use khodpay_signing::rpc::RpcClient;
+    /// use serde_json::json;
+    ///
+    /// let paymaster: khodpay_signing::Address =
+    ///     "0x0000000000000039cd5e8aE05257CE51C473ddd1".parse().unwrap();
+    /// let client = PaymasterClient::new(RpcClient::new("https://paymaster.example/rpc"), 56)
+    ///     .with_context(json!({ "sponsorshipPolicyId": "sp_example" }));
+    ///
+    /// let sponsored = client.sponsor(&op).await?;
+    /// SponsorshipPolicy::new()
+    ///     .allow_paymaster(paymaster)
+    ///     .check(&op, &sponsored, 56)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[derive(Debug)]
+    pub struct PaymasterClient {
+        rpc: RpcClient,
+        entry_point: Address,
+        chain_id: u64,
+        context: Value,
+    }
+
+    impl PaymasterClient {
+        /// Creates a client for operations on `chain_id` through the v0.7 EntryPoint,
+        /// with an empty context.
+        pub fn new(rpc: RpcClient, chain_id: u64) -> Self {
+            Self {
+                rpc,
+                entry_point: ENTRY_POINT_V07.parse().unwrap(),
+                chain_id,
+                context: json!({}),
+            }
+        }
+
+        /// Sets the EntryPoint the operations are for.
+        pub fn with_entry_point(mut self, entry_point: Address) -> Self {
+            self.entry_point = entry_point;
+            self
+        }
+
+        /// Sets the provider-specific context sent with each request, such as a
+        /// sponsorship policy ID.
+        pub fn with_context(mut self, context: Value) -> Self {
+            self.context = context;
+            self
+        }
+
+        /// Requests stub data for gas estimation (`pm_getPaymasterStubData`).
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the request fails or the service refuses to sponsor
+        /// the operation.
+        pub async fn stub_data(&self, op: &UserOperation) -> Result<Sponsorship> {
+            self.request("pm_getPaymasterStubData", op).await
+        }
+
+        /// Requests the final paymaster data (`pm_getPaymasterData`).
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the request fails or the service refuses to sponsor
+        /// the operation.
+        pub async fn paymaster_data(&self, op: &UserOperation) -> Result<Sponsorship> {
+            self.request("pm_getPaymasterData", op).await
+        }
+
+        /// Returns `op` sponsored with final paymaster data, ready to be checked and
+        /// signed.
+        ///
+        /// The operation's gas limits are kept; to re-estimate them with the stub data
+        /// in place, call [`stub_data`](Self::stub_data) and
+        /// [`paymaster_data`](Self::paymaster_data) instead.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if a request fails or the service refuses to sponsor the
+        /// operation.
+        pub async fn sponsor(&self, op: &UserOperation) -> Result<UserOperation> {
+            let stub = self.stub_data(op).await?;
+            let op = stub.apply(op.clone());
+            if stub.is_final {
+                return Ok(op);
+            }
+            let sponsorship = self.paymaster_data(&op).await?;
+            Ok(sponsorship.apply(op))
+        }
+
+        async fn request(&self, method: &str, op: &UserOperation) -> Result<Sponsorship> {
+            let params = json!([
+                user_operation_json(op),
+                self.entry_point.to_checksum_string(),
+                format!("0x{:x}", self.chain_id),
+                self.context,
+            ]);
+            let response: SponsorshipResponse = self.rpc.request(method, params).await?;
+            response.into_sponsorship(method)
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct SponsorshipResponse {
+        paymaster: Option<String>,
+        paymaster_data: Option<String>,
+        paymaster_verification_gas_limit: Option<String>,
+        paymaster_post_op_gas_limit: Option<String>,
+        sponsor: Option<SponsorInfo>,
+        #[serde(default)]
+        is_final: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct SponsorInfo {
+        name: String,
+    }
+
+    impl SponsorshipResponse {
+        fn into_sponsorship(self, method: &str) -> Result<Sponsorship> {
+            // v0.6 services answer with `paymasterAndData` instead
+            let paymaster = self.paymaster.ok_or_else(|| {
+                Error::PaymasterError(format!("{} returned no v0.7 paymaster", method))
+            })?;
+            let gas_limit = |value: Option<String>| -> Result<Option<u128>> {
+                value
+                    .map(|value| {
+                        let gas = parse_u256(&value)?;
+                        if gas > u128::MAX.into() {
+                            return Err(Error::PaymasterError(format!(
+                                "{} returned gas limit {} above u128",
+                                method, gas
+                            )));
+                        }
+                        Ok(gas.as_u128())
+                    })
+                    .transpose()
+            };
+
+            Ok(Sponsorship {
+                paymaster: paymaster.parse()?,
+                paymaster_data: match self.paymaster_data {
+                    Some(data) => parse_bytes(&data)?,
+                    None => Vec::new(),
+                },
+                verification_gas_limit: gas_limit(self.paymaster_verification_gas_limit)?,
+                post_op_gas_limit: gas_limit(self.paymaster_post_op_gas_limit)?,
+                sponsor: self.sponsor.map(|sponsor| sponsor.name),
+                is_final: self.is_final,
+            })
+        }
+    }
+
+    /// Encodes `op` in the ERC-4337 JSON-RPC format, with hex quantities.
+    fn user_operation_json(op: &UserOperation) -> Value {
+        let quantity = |value: u128| format!("0x{:x}", value);
+        let bytes = |data: &[u8]| format!("0x{}", hex::encode(data));
+
+        let mut json = json!({
+            "sender": op.sender.to_checksum_string(),
+            "nonce": quantity(op.nonce),
+            "callData": bytes(&op.call_data),
+            "callGasLimit": quantity(op.call_gas_limit),
+            "verificationGasLimit": quantity(op.verification_gas_limit),
+            "preVerificationGas": quantity(op.pre_verification_gas),
+            "maxFeePerGas": quantity(op.max_fee_per_gas),
+            "maxPriorityFeePerGas": quantity(op.max_priority_fee_per_gas),
+            "signature": bytes(&op.signature),
+        });
+        if let Some(factory) = op.factory {
+            json["factory"] = json!(factory.to_checksum_string());
+            json["factoryData"] = json!(bytes(&op.factory_data));
+        }
+        if let Some(paymaster) = op.paymaster {
+            json["paymaster"] = json!(paymaster.to_checksum_string());
+            json["paymasterVerificationGasLimit"] =
+                json!(quantity(op.paymaster_verification_gas_limit));
+            json["paymasterPostOpGasLimit"] = json!(quantity(op.paymaster_post_op_gas_limit));
+            json["paymasterData"] = json!(bytes(&op.paymaster_data));
+        }
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN_ID: u64 = 56;
+    const NOW: u64 = 1_800_000_000;
+
+    fn paymaster() -> Address {
+        Address::from_bytes([0x11; 20])
+    }
+
+    fn paymaster_signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[2u8; 32]).unwrap()
+    }
+
+    fn requested() -> UserOperation {
+        UserOperation::builder()
+            .sender(Address::from_bytes([0x22; 20]))
+            .nonce(7)
+            .call_data(vec![0xde, 0xad, 0xbe, 0xef])
+            .call_gas_limit(300_000)
+            .verification_gas_limit(150_000)
+            .pre_verification_gas(50_000)
+            .max_fee_per_gas(5_000_000_000)
+            .max_priority_fee_per_gas(1_000_000_000)
+            .build()
+            .unwrap()
+    }
+
+    fn sponsored(valid_until: u64, valid_after: u64) -> UserOperation {
+        let stub = PaymasterAndData::new(paymaster(), 60_000, 10_000, vec![]);
+        let op = requested().with_paymaster(stub.clone());
+        let data =
+            sign_verifying_paymaster(&paymaster_signer(), &op, CHAIN_ID, valid_until, valid_after)
+                .unwrap();
+        op.with_paymaster(PaymasterAndData {
+            data: data.encode(),
+            ..stub
+        })
+    }
+
+    fn policy() -> SponsorshipPolicy {
+        SponsorshipPolicy::new()
+            .allow_verifying_paymaster(paymaster(), paymaster_signer().address())
+    }
+
+    #[test]
+    fn test_paymaster_and_data_roundtrip() {
+        let fields = PaymasterAndData::new(paymaster(), 60_000, 40_000, vec![1, 2, 3]);
+        let encoded = fields.encode();
+        assert_eq!(&encoded[..20], paymaster().as_bytes());
+        assert_eq!(PaymasterAndData::decode(&encoded).unwrap(), fields);
+        assert!(matches!(
+            PaymasterAndData::decode(&encoded[..51]),
+            Err(Error::PaymasterError(_))
+        ));
+
+        // Matches what the packed operation carries
+        let op = requested()
+            .with_signature(vec![1; 65])
+            .with_paymaster(fields.clone());
+        assert!(op.signature.is_empty());
+        assert_eq!(op.pack().paymaster_and_data, encoded);
+        assert_eq!(op.paymaster_and_data(), Some(fields));
+        assert_eq!(requested().paymaster_and_data(), None);
+    }
+
+    #[test]
+    fn test_sponsorship_apply_keeps_missing_gas_limits() {
+        let op =
+            requested().with_paymaster(PaymasterAndData::new(paymaster(), 60_000, 10_000, vec![]));
+        let sponsorship = Sponsorship {
+            paymaster: paymaster(),
+            paymaster_data: vec![9; 4],
+            verification_gas_limit: None,
+            post_op_gas_limit: Some(20_000),
+            sponsor: None,
+            is_final: true,
+        };
+        let op = sponsorship.apply(op);
+        assert_eq!(op.paymaster_verification_gas_limit, 60_000);
+        assert_eq!(op.paymaster_post_op_gas_limit, 20_000);
+        assert_eq!(op.paymaster_data, vec![9; 4]);
+    }
+
+    #[test]
+    fn test_verifying_paymaster_data_roundtrip() {
+        let data = VerifyingPaymasterData {
+            valid_until: 1_900_000_000,
+            valid_after: 5,
+            signature: vec![7; 65],
+        };
+        let encoded = data.encode();
+        assert_eq!(encoded.len(), 64 + 65);
+        assert_eq!(VerifyingPaymasterData::decode(&encoded).unwrap(), data);
+
+        assert!(VerifyingPaymasterData::decode(&encoded[..63]).is_err());
+        let mut overflow = encoded.clone();
+        overflow[25] = 1;
+        assert!(VerifyingPaymasterData::decode(&overflow).is_err());
+    }
+
+    #[test]
+    fn test_validity_window() {
+        let data = VerifyingPaymasterData {
+            valid_until: 200,
+            valid_after: 100,
+            signature: vec![],
+        };
+        assert!(!data.is_valid_at(100));
+        assert!(data.is_valid_at(101));
+        assert!(data.is_valid_at(200));
+        assert!(!data.is_valid_at(201));
+
+        let open = VerifyingPaymasterData {
+            valid_until: 0,
+            ..data
+        };
+        assert!(open.is_valid_at(u64::MAX));
+    }
+
+    #[test]
+    fn test_verifying_paymaster_hash() {
+        let op = sponsored(0, 0);
+        let hash = verifying_paymaster_hash(&op, CHAIN_ID, 0, 0).unwrap();
+
+        // Covers the chain, the window and the paymaster gas limits, but not the data
+        assert_ne!(hash, verifying_paymaster_hash(&op, 1, 0, 0).unwrap());
+        assert_ne!(hash, verifying_paymaster_hash(&op, CHAIN_ID, 1, 0).unwrap());
+        let mut other = op.clone();
+        other.paymaster_post_op_gas_limit += 1;
+        assert_ne!(
+            hash,
+            verifying_paymaster_hash(&other, CHAIN_ID, 0, 0).unwrap()
+        );
+        let mut other = op.clone();
+        other.paymaster_data.clear();
+        assert_eq!(
+            hash,
+            verifying_paymaster_hash(&other, CHAIN_ID, 0, 0).unwrap()
+        );
+
+        assert!(verifying_paymaster_hash(&requested(), CHAIN_ID, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_sign_verifying_paymaster() {
+        let op = sponsored(0, 0);
+        let data = VerifyingPaymasterData::decode(&op.paymaster_data).unwrap();
+        assert!(data.signature[64] == 27 || data.signature[64] == 28);
+
+        let signer = paymaster_signer().with_chain_id(crate::ChainId::EthereumMainnet);
+        assert!(matches!(
+            sign_verifying_paymaster(&signer, &op, CHAIN_ID, 0, 0),
+            Err(Error::ChainIdMismatch { .. })
+        ));
+        assert!(sign_verifying_paymaster(&paymaster_signer(), &op, CHAIN_ID, 1 << 48, 0).is_err());
+    }
+
+    #[test]
+    fn test_policy_accepts_sponsored_operation() {
+        let op = sponsored(NOW + 600, NOW - 600);
+        policy().check_at(&requested(), &op, CHAIN_ID, NOW).unwrap();
+        policy()
+            .min_validity(600)
+            .check_at(&requested(), &op, CHAIN_ID, NOW)
+            .unwrap();
+
+        // Plain paymasters are not checked beyond the allow-list
+        let op = requested().with_paymaster(PaymasterAndData::new(paymaster(), 60_000, 0, vec![1]));
+        SponsorshipPolicy::new()
+            .allow_paymaster(paymaster())
+            .check_at(&requested(), &op, CHAIN_ID, NOW)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_policy_rejects_unknown_paymaster() {
+        let op = sponsored(0, 0);
+        assert!(policy()
+            .check_at(&requested(), &requested(), CHAIN_ID, NOW)
+            .is_err());
+
+        let other = Address::from_bytes([0x33; 20]);
+        let error = SponsorshipPolicy::new()
+            .allow_paymaster(other)
+            .check_at(&requested(), &op, CHAIN_ID, NOW)
+            .unwrap_err();
+        assert!(error.to_string().contains("is not allowed"));
+
+        let policy = policy();
+        assert!(policy.allows_paymaster(paymaster()));
+        assert!(!policy.allows_paymaster(other));
+    }
+
+    #[test]
+    fn test_policy_rejects_changed_operation() {
+        let mut op = sponsored(0, 0);
+        op.call_data = vec![0xff];
+        let error = policy()
+            .check_at(&requested(), &op, CHAIN_ID, NOW)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Paymaster error: sponsored operation has a different calldata"
+        );
+
+        let mut op = sponsored(0, 0);
+        op.paymaster_verification_gas_limit = 0;
+        assert!(policy().check_at(&requested(), &op, CHAIN_ID, NOW).is_err());
+    }
+
+    #[test]
+    fn test_policy_rejects_bad_paymaster_signature() {
+        // Another chain, another signer, or data tampered with after signing
+        let op = sponsored(0, 0);
+        assert!(policy().check_at(&requested(), &op, 1, NOW).is_err());
+
+        let policy_for_other_signer = SponsorshipPolicy::new()
+            .allow_verifying_paymaster(paymaster(), Address::from_bytes([0x44; 20]));
+        let error = policy_for_other_signer
+            .check_at(&requested(), &op, CHAIN_ID, NOW)
+            .unwrap_err();
+        assert!(error.to_string().contains("signed by"));
+
+        let mut tampered = op.clone();
+        tampered.paymaster_data[70] ^= 1;
+        assert!(policy()
+            .check_at(&requested(), &tampered, CHAIN_ID, NOW)
+            .is_err());
+        let mut truncated = op;
+        truncated.paymaster_data.truncate(64 + 64);
+        assert!(policy()
+            .check_at(&requested(), &truncated, CHAIN_ID, NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn test_policy_rejects_expired_sponsorship() {
+        let op = sponsored(NOW + 30, 0);
+        policy().check_at(&requested(), &op, CHAIN_ID, NOW).unwrap();
+        assert!(policy()
+            .min_validity(60)
+            .check_at(&requested(), &op, CHAIN_ID, NOW)
+            .is_err());
+        assert!(policy()
+            .check_at(&requested(), &op, CHAIN_ID, NOW + 31)
+            .is_err());
+
+        let not_yet = sponsored(0, NOW + 10);
+        assert!(policy()
+            .check_at(&requested(), &not_yet, CHAIN_ID, NOW)
+            .is_err());
+    }
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::rpc::RpcClient;
+        use serde_json::json;
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn respond(server: &MockServer, rpc_method: &str, result: serde_json::Value) {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({ "method": rpc_method })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": result,
+                })))
+                .mount(server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn test_sponsor_requests_stub_then_data() {
+            let server = MockServer::start().await;
+            respond(
+                &server,
+                "pm_getPaymasterStubData",
+                json!({
+                    "paymaster": paymaster().to_checksum_string(),
+                    "paymasterData": "0x00",
+                    "paymasterVerificationGasLimit": "0xea60",
+                    "paymasterPostOpGasLimit": "0x0",
+                    "sponsor": { "name": "Example" },
+                }),
+            )
+            .await;
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({
+                    "method": "pm_getPaymasterData",
+                    "params": [
+                        {
+                            "nonce": "0x7",
+                            "callData": "0xdeadbeef",
+                            "paymasterVerificationGasLimit": "0xea60",
+                        },
+                        "0x0000000071727De22E5E9d8BAf0edAc6f37da032",
+                        "0x38",
+                        { "policy": "p1" },
+                    ],
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "result": {
+                        "paymaster": paymaster().to_checksum_string(),
+                        "paymasterData": "0x0102",
+                    },
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let client = PaymasterClient::new(RpcClient::new(server.uri()), CHAIN_ID)
+                .with_context(json!({ "policy": "p1" }));
+            let stub = client.stub_data(&requested()).await.unwrap();
+            assert_eq!(stub.sponsor.as_deref(), Some("Example"));
+            assert_eq!(stub.verification_gas_limit, Some(60_000));
+            assert!(!stub.is_final);
+
+            let op = client.sponsor(&requested()).await.unwrap();
+            assert_eq!(op.paymaster, Some(paymaster()));
+            assert_eq!(op.paymaster_verification_gas_limit, 60_000);
+            assert_eq!(op.paymaster_data, vec![1, 2]);
+            SponsorshipPolicy::new()
+                .allow_paymaster(paymaster())
+                .check_at(&requested(), &op, CHAIN_ID, NOW)
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn test_sponsor_stops_at_final_stub() {
+            let server = MockServer::start().await;
+            respond(
+                &server,
+                "pm_getPaymasterStubData",
+                json!({
+                    "paymaster": paymaster().to_checksum_string(),
+                    "paymasterData": "0x0102",
+                    "paymasterVerificationGasLimit": "0xea60",
+                    "paymasterPostOpGasLimit": "0x0",
+                    "isFinal": true,
+                }),
+            )
+            .await;
+
+            let client = PaymasterClient::new(RpcClient::new(server.uri()), CHAIN_ID);
+            let op = client.sponsor(&requested()).await.unwrap();
+            assert_eq!(op.paymaster_data, vec![1, 2]);
+        }
+
+        #[tokio::test]
+        async fn test_sponsor_rejects_v06_response() {
+            let server = MockServer::start().await;
+            respond(
+                &server,
+                "pm_getPaymasterStubData",
+                json!({ "paymasterAndData": "0x1111" }),
+            )
+            .await;
+
+            let client = PaymasterClient::new(RpcClient::new(server.uri()), CHAIN_ID);
+            assert!(matches!(
+                client.sponsor(&requested()).await,
+                Err(Error::PaymasterError(_))
+            ));
+        }
+    }
+}
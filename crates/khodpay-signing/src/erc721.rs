@@ -0,0 +1,190 @@
+//! ERC-721 (non-fungible token) call encoding.
+//!
+//! Builds calldata for NFT transfers and approvals so they can be signed through the
+//! same [`Eip1559Transaction`](crate::Eip1559Transaction) builder as any other call.
+//!
+//! Prefer [`encode_safe_transfer_from`] over [`encode_transfer_from`]: the safe variant
+//! reverts if the recipient is a contract that cannot receive NFTs.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::{erc721, Address, U256};
+//!
+//! let from: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap();
+//! let to: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//!
+//! let data = erc721::encode_safe_transfer_from(&from, &to, U256::from(1234));
+//! assert_eq!(&data[..4], &erc721::SAFE_TRANSFER_FROM_SELECTOR);
+//! ```
+
+use crate::abi::{encode_with_selector, Token};
+use crate::Address;
+use primitive_types::U256;
+
+/// Selector of `safeTransferFrom(address,address,uint256)`.
+pub const SAFE_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x42, 0x84, 0x2e, 0x0e];
+
+/// Selector of `safeTransferFrom(address,address,uint256,bytes)`.
+pub const SAFE_TRANSFER_FROM_WITH_DATA_SELECTOR: [u8; 4] = [0xb8, 0x8d, 0x4f, 0xde];
+
+/// Selector of `transferFrom(address,address,uint256)`.
+pub const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// Selector of `approve(address,uint256)`.
+pub const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+/// Selector of `setApprovalForAll(address,bool)` (shared with ERC-1155).
+pub const SET_APPROVAL_FOR_ALL_SELECTOR: [u8; 4] = [0xa2, 0x2c, 0xb4, 0x65];
+
+/// Encodes `safeTransferFrom(from, to, tokenId)` calldata.
+pub fn encode_safe_transfer_from(from: &Address, to: &Address, token_id: U256) -> Vec<u8> {
+    encode_with_selector(
+        SAFE_TRANSFER_FROM_SELECTOR,
+        &[
+            Token::Address(*from),
+            Token::Address(*to),
+            Token::Uint(token_id),
+        ],
+    )
+}
+
+/// Encodes `safeTransferFrom(from, to, tokenId, data)` calldata.
+///
+/// `data` is forwarded to the recipient's `onERC721Received` hook.
+pub fn encode_safe_transfer_from_with_data(
+    from: &Address,
+    to: &Address,
+    token_id: U256,
+    data: &[u8],
+) -> Vec<u8> {
+    encode_with_selector(
+        SAFE_TRANSFER_FROM_WITH_DATA_SELECTOR,
+        &[
+            Token::Address(*from),
+            Token::Address(*to),
+            Token::Uint(token_id),
+            Token::Bytes(data.to_vec()),
+        ],
+    )
+}
+
+/// Encodes `transferFrom(from, to, tokenId)` calldata.
+///
+/// Unlike `safeTransferFrom`, this does not check that a contract recipient can
+/// handle NFTs; tokens sent to such a contract are lost.
+pub fn encode_transfer_from(from: &Address, to: &Address, token_id: U256) -> Vec<u8> {
+    encode_with_selector(
+        TRANSFER_FROM_SELECTOR,
+        &[
+            Token::Address(*from),
+            Token::Address(*to),
+            Token::Uint(token_id),
+        ],
+    )
+}
+
+/// Encodes `approve(approved, tokenId)` calldata.
+///
+/// Approving the zero address clears the existing approval.
+pub fn encode_approve(approved: &Address, token_id: U256) -> Vec<u8> {
+    encode_with_selector(
+        APPROVE_SELECTOR,
+        &[Token::Address(*approved), Token::Uint(token_id)],
+    )
+}
+
+/// Encodes `setApprovalForAll(operator, approved)` calldata.
+pub fn encode_set_approval_for_all(operator: &Address, approved: bool) -> Vec<u8> {
+    encode_with_selector(
+        SET_APPROVAL_FOR_ALL_SELECTOR,
+        &[Token::Address(*operator), Token::Bool(approved)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{selector, Function};
+
+    fn alice() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn bob() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        assert_eq!(
+            SAFE_TRANSFER_FROM_SELECTOR,
+            selector("safeTransferFrom(address,address,uint256)")
+        );
+        assert_eq!(
+            SAFE_TRANSFER_FROM_WITH_DATA_SELECTOR,
+            selector("safeTransferFrom(address,address,uint256,bytes)")
+        );
+        assert_eq!(
+            TRANSFER_FROM_SELECTOR,
+            selector("transferFrom(address,address,uint256)")
+        );
+        assert_eq!(APPROVE_SELECTOR, selector("approve(address,uint256)"));
+        assert_eq!(
+            SET_APPROVAL_FOR_ALL_SELECTOR,
+            selector("setApprovalForAll(address,bool)")
+        );
+    }
+
+    #[test]
+    fn test_safe_transfer_from_layout() {
+        let data = encode_safe_transfer_from(&alice(), &bob(), U256::from(7));
+
+        assert_eq!(data.len(), 4 + 96);
+        assert_eq!(&data[16..36], alice().as_bytes());
+        assert_eq!(&data[48..68], bob().as_bytes());
+        assert_eq!(data[99], 7);
+    }
+
+    #[test]
+    fn test_safe_transfer_from_with_data_roundtrip() {
+        let f = Function::parse("safeTransferFrom(address,address,uint256,bytes)").unwrap();
+        let data = encode_safe_transfer_from_with_data(&alice(), &bob(), U256::MAX, b"memo");
+
+        assert_eq!(
+            f.decode_input(&data).unwrap(),
+            vec![
+                Token::Address(alice()),
+                Token::Address(bob()),
+                Token::Uint(U256::MAX),
+                Token::Bytes(b"memo".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_approvals() {
+        let data = encode_approve(&bob(), U256::from(1));
+        assert_eq!(&data[..4], &APPROVE_SELECTOR);
+        assert_eq!(data.len(), 4 + 64);
+
+        let data = encode_set_approval_for_all(&bob(), true);
+        assert_eq!(&data[..4], &SET_APPROVAL_FOR_ALL_SELECTOR);
+        assert_eq!(data[67], 1);
+
+        let revoke = encode_set_approval_for_all(&bob(), false);
+        assert_eq!(revoke[67], 0);
+    }
+
+    #[test]
+    fn test_transfer_from_matches_erc20_layout() {
+        // ERC-721 transferFrom shares its selector and layout with ERC-20
+        let nft = encode_transfer_from(&alice(), &bob(), U256::from(3));
+        let token = crate::erc20::encode_transfer_from(&alice(), &bob(), U256::from(3));
+        assert_eq!(nft, token);
+    }
+}
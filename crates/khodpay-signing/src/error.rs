@@ -9,6 +9,15 @@ pub enum Error {
     #[error("Invalid chain ID: {0}")]
     InvalidChainId(u64),
 
+    /// The transaction targets a different chain than the signer is bound to.
+    #[error("Chain ID mismatch: signer is bound to {expected}, got {actual}")]
+    ChainIdMismatch {
+        /// The chain the signer is bound to.
+        expected: u64,
+        /// The chain requested by the transaction or payload.
+        actual: u64,
+    },
+
     /// Invalid EVM address.
     #[error("Invalid address: {0}")]
     InvalidAddress(String),
@@ -37,6 +46,10 @@ pub enum Error {
     #[error("RLP encoding error: {0}")]
     RlpEncodingError(String),
 
+    /// RLP decoding error.
+    #[error("RLP decoding error: {0}")]
+    RlpDecodingError(String),
+
     /// Error from BIP-32 operations.
     #[error("BIP-32 error: {0}")]
     Bip32Error(#[from] khodpay_bip32::Error),
@@ -48,12 +61,98 @@ pub enum Error {
     /// Hex decoding error.
     #[error("Hex decode error: {0}")]
     HexError(String),
+
+    /// EIP-5564 stealth address error.
+    #[error("Stealth address error: {0}")]
+    StealthError(String),
+
+    /// ABI encoding or decoding error.
+    #[error("ABI error: {0}")]
+    AbiError(String),
+
+    /// JSON-RPC transport or response decoding error.
+    #[error("RPC error: {0}")]
+    RpcError(String),
+
+    /// Error object returned by a JSON-RPC node.
+    #[error("JSON-RPC error {code}: {message}")]
+    JsonRpcError {
+        /// The JSON-RPC error code.
+        code: i64,
+        /// The error message.
+        message: String,
+    },
+
+    /// The transaction's nonce was used by a different transaction.
+    #[error("Transaction replaced: {0}")]
+    TransactionReplaced(String),
+
+    /// The transaction disappeared from the mempool without being mined.
+    #[error("Transaction dropped: {0}")]
+    TransactionDropped(String),
+
+    /// The transaction did not reach the requested confirmations in time.
+    #[error("Confirmation timeout: {0}")]
+    ConfirmationTimeout(String),
+
+    /// Malformed or unsupported EIP-681 payment URI.
+    #[error("Invalid payment URI: {0}")]
+    InvalidPaymentUri(String),
+
+    /// Malformed or invalid EIP-4361 (Sign-In with Ethereum) message.
+    #[error("SIWE error: {0}")]
+    SiweError(String),
+
+    /// Raw digest refused by the caller's `PrehashPolicy`.
+    #[error("Prehashed signing rejected: {0}")]
+    PrehashRejected(String),
+
+    /// Signing refused by the signer's `SigningPolicy`.
+    #[error("Signing policy violation: {0}")]
+    PolicyViolation(String),
+
+    /// Reading or writing persisted wallet state failed.
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    /// Malformed or unsupported encrypted keystore.
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+
+    /// A keystore's MAC or checksum did not match: wrong password or corrupted file.
+    #[error("Wrong password or corrupted keystore")]
+    WrongPassword,
+
+    /// Malformed EIP-712 typed data, or a value that does not match its type.
+    #[error("Invalid typed data: {0}")]
+    InvalidTypedData(String),
+
+    /// Invalid token list or token metadata, or metadata that does not match the chain.
+    #[error("Token error: {0}")]
+    TokenError(String),
+
+    /// Malformed paymaster data or response, or a sponsorship refused by a
+    /// `SponsorshipPolicy`.
+    #[error("Paymaster error: {0}")]
+    PaymasterError(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chain_id_mismatch_error() {
+        let error = Error::ChainIdMismatch {
+            expected: 56,
+            actual: 1,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Chain ID mismatch: signer is bound to 56, got 1"
+        );
+    }
+
     #[test]
     fn test_invalid_chain_id_error() {
         let error = Error::InvalidChainId(999);
@@ -105,12 +204,145 @@ mod tests {
         assert_eq!(error.to_string(), "RLP encoding error: encoding failed");
     }
 
+    #[test]
+    fn test_rlp_decoding_error() {
+        let error = Error::RlpDecodingError("trailing bytes".to_string());
+        assert_eq!(error.to_string(), "RLP decoding error: trailing bytes");
+    }
+
     #[test]
     fn test_hex_error() {
         let error = Error::HexError("invalid hex character".to_string());
         assert_eq!(error.to_string(), "Hex decode error: invalid hex character");
     }
 
+    #[test]
+    fn test_stealth_error() {
+        let error = Error::StealthError("invalid meta-address".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Stealth address error: invalid meta-address"
+        );
+    }
+
+    #[test]
+    fn test_abi_error() {
+        let error = Error::AbiError("data too short".to_string());
+        assert_eq!(error.to_string(), "ABI error: data too short");
+    }
+
+    #[test]
+    fn test_rpc_error() {
+        let error = Error::RpcError("connection refused".to_string());
+        assert_eq!(error.to_string(), "RPC error: connection refused");
+    }
+
+    #[test]
+    fn test_json_rpc_error() {
+        let error = Error::JsonRpcError {
+            code: -32000,
+            message: "nonce too low".to_string(),
+        };
+        assert_eq!(error.to_string(), "JSON-RPC error -32000: nonce too low");
+    }
+
+    #[test]
+    fn test_transaction_replaced_error() {
+        let error = Error::TransactionReplaced("0xabc".to_string());
+        assert_eq!(error.to_string(), "Transaction replaced: 0xabc");
+    }
+
+    #[test]
+    fn test_transaction_dropped_error() {
+        let error = Error::TransactionDropped("0xabc".to_string());
+        assert_eq!(error.to_string(), "Transaction dropped: 0xabc");
+    }
+
+    #[test]
+    fn test_confirmation_timeout_error() {
+        let error = Error::ConfirmationTimeout("0xabc".to_string());
+        assert_eq!(error.to_string(), "Confirmation timeout: 0xabc");
+    }
+
+    #[test]
+    fn test_invalid_payment_uri_error() {
+        let error = Error::InvalidPaymentUri("missing ethereum: scheme".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Invalid payment URI: missing ethereum: scheme"
+        );
+    }
+
+    #[test]
+    fn test_siwe_error() {
+        let error = Error::SiweError("nonce mismatch".to_string());
+        assert_eq!(error.to_string(), "SIWE error: nonce mismatch");
+    }
+
+    #[test]
+    fn test_prehash_rejected_error() {
+        let error = Error::PrehashRejected("digest not in allow-list".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Prehashed signing rejected: digest not in allow-list"
+        );
+    }
+
+    #[test]
+    fn test_policy_violation_error() {
+        let error = Error::PolicyViolation("chain 1 is not allowed".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Signing policy violation: chain 1 is not allowed"
+        );
+    }
+
+    #[test]
+    fn test_invalid_typed_data_error() {
+        let error = Error::InvalidTypedData("undefined type Person".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Invalid typed data: undefined type Person"
+        );
+    }
+
+    #[test]
+    fn test_token_error() {
+        let error = Error::TokenError("empty symbol".to_string());
+        assert_eq!(error.to_string(), "Token error: empty symbol");
+    }
+
+    #[test]
+    fn test_paymaster_error() {
+        let error = Error::PaymasterError("operation is not sponsored".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Paymaster error: operation is not sponsored"
+        );
+    }
+
+    #[test]
+    fn test_storage_error() {
+        let error = Error::StorageError("queue.txt: permission denied".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Storage error: queue.txt: permission denied"
+        );
+    }
+
+    #[test]
+    fn test_keystore_errors() {
+        let error = Error::KeystoreError("unsupported cipher: aes-128-cbc".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Keystore error: unsupported cipher: aes-128-cbc"
+        );
+        assert_eq!(
+            Error::WrongPassword.to_string(),
+            "Wrong password or corrupted keystore"
+        );
+    }
+
     #[test]
     fn test_error_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -0,0 +1,467 @@
+//! EIP-1559 fee suggestions.
+//!
+//! Turns the result of `eth_feeHistory` (or a legacy `eth_gasPrice`) into slow / normal /
+//! fast `max_fee_per_gas` and `max_priority_fee_per_gas` pairs that plug straight into
+//! [`Eip1559TransactionBuilder::fee_suggestion`](crate::Eip1559TransactionBuilder::fee_suggestion).
+//!
+//! This module does no I/O: fetch the history with whatever client you use, passing
+//! [`FeeEstimator::reward_percentiles`] as the `rewardPercentiles` argument, then hand
+//! the result to [`FeeEstimator::estimate`].
+//!
+//! # Algorithm
+//!
+//! - The priority fee for each speed is the median, across the sampled blocks, of the
+//!   reward at that speed's percentile. Empty blocks (gas used ratio of zero) report
+//!   zero rewards and are skipped.
+//! - The max fee is `next_base_fee × base_fee_multiplier + priority_fee`, leaving
+//!   headroom for the base fee to rise while the transaction is pending.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use khodpay_signing::fee::{FeeEstimator, FeeHistory, FeeSpeed};
+//! use khodpay_signing::{ChainId, Eip1559Transaction, Wei};
+//!
+//! let history = FeeHistory {
+//!     oldest_block: 100,
+//!     base_fee_per_gas: vec![Wei::from_gwei(10), Wei::from_gwei(12)],
+//!     gas_used_ratio: vec![0.6],
+//!     reward: vec![vec![Wei::from_gwei(1), Wei::from_gwei(2), Wei::from_gwei(3)]],
+//! };
+//!
+//! let suggestions = FeeEstimator::default().estimate(&history).unwrap();
+//! let normal = suggestions.get(FeeSpeed::Normal);
+//! assert_eq!(normal.max_priority_fee_per_gas, Wei::from_gwei(2));
+//! assert_eq!(normal.max_fee_per_gas, Wei::from_gwei(26));
+//!
+//! let tx = Eip1559Transaction::builder()
+//!     .chain_id(ChainId::BscMainnet)
+//!     .nonce(0)
+//!     .fee_suggestion(normal)
+//!     .gas_limit(21_000)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::{Error, Result, Wei};
+use primitive_types::U256;
+
+/// Default reward percentiles for slow / normal / fast.
+pub const DEFAULT_REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Default base fee multiplier applied when computing `max_fee_per_gas`.
+pub const DEFAULT_BASE_FEE_MULTIPLIER: u64 = 2;
+
+/// Transaction inclusion speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeeSpeed {
+    /// Cheaper, may wait several blocks.
+    Slow,
+    /// Typical inclusion within a few blocks.
+    Normal,
+    /// Prioritised inclusion.
+    Fast,
+}
+
+impl FeeSpeed {
+    /// All speeds, slowest first.
+    pub const ALL: [FeeSpeed; 3] = [FeeSpeed::Slow, FeeSpeed::Normal, FeeSpeed::Fast];
+
+    const fn index(self) -> usize {
+        match self {
+            FeeSpeed::Slow => 0,
+            FeeSpeed::Normal => 1,
+            FeeSpeed::Fast => 2,
+        }
+    }
+}
+
+/// Decoded `eth_feeHistory` result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeHistory {
+    /// Number of the oldest block in the range.
+    pub oldest_block: u64,
+    /// Base fee per block, including the base fee of the next (pending) block.
+    pub base_fee_per_gas: Vec<Wei>,
+    /// Fraction of the gas limit used by each block.
+    pub gas_used_ratio: Vec<f64>,
+    /// Effective priority fee at each requested percentile, per block.
+    pub reward: Vec<Vec<Wei>>,
+}
+
+impl FeeHistory {
+    /// Returns the base fee of the next block (the last `base_fee_per_gas` entry).
+    pub fn next_base_fee(&self) -> Option<Wei> {
+        self.base_fee_per_gas.last().copied()
+    }
+}
+
+/// A fee pair for one speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    /// Suggested `max_fee_per_gas`.
+    pub max_fee_per_gas: Wei,
+    /// Suggested `max_priority_fee_per_gas`.
+    pub max_priority_fee_per_gas: Wei,
+}
+
+/// Slow / normal / fast fee suggestions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSuggestions {
+    /// Base fee of the next block the suggestions were computed from.
+    pub base_fee: Wei,
+    /// Slow suggestion.
+    pub slow: FeeSuggestion,
+    /// Normal suggestion.
+    pub normal: FeeSuggestion,
+    /// Fast suggestion.
+    pub fast: FeeSuggestion,
+}
+
+impl FeeSuggestions {
+    /// Returns the suggestion for `speed`.
+    pub fn get(&self, speed: FeeSpeed) -> FeeSuggestion {
+        match speed {
+            FeeSpeed::Slow => self.slow,
+            FeeSpeed::Normal => self.normal,
+            FeeSpeed::Fast => self.fast,
+        }
+    }
+
+    /// Builds suggestions from a legacy `eth_gasPrice` result.
+    ///
+    /// Use this for chains or nodes without `eth_feeHistory`. All three speeds pay
+    /// `gas_price` as both the max fee and the priority fee, which is exactly what a
+    /// legacy transaction would pay.
+    pub fn from_gas_price(gas_price: Wei) -> Self {
+        let suggestion = FeeSuggestion {
+            max_fee_per_gas: gas_price,
+            max_priority_fee_per_gas: gas_price,
+        };
+        Self {
+            base_fee: Wei::ZERO,
+            slow: suggestion,
+            normal: suggestion,
+            fast: suggestion,
+        }
+    }
+}
+
+/// Computes fee suggestions from fee history.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::fee::FeeEstimator;
+/// use khodpay_signing::Wei;
+///
+/// let estimator = FeeEstimator::builder()
+///     .reward_percentiles([25.0, 60.0, 95.0])
+///     .base_fee_multiplier(3)
+///     .min_priority_fee(Wei::from_gwei(1))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(estimator.reward_percentiles(), [25.0, 60.0, 95.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeEstimator {
+    reward_percentiles: [f64; 3],
+    base_fee_multiplier: u64,
+    min_priority_fee: Wei,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        Self {
+            reward_percentiles: DEFAULT_REWARD_PERCENTILES,
+            base_fee_multiplier: DEFAULT_BASE_FEE_MULTIPLIER,
+            min_priority_fee: Wei::ZERO,
+        }
+    }
+}
+
+impl FeeEstimator {
+    /// Creates a new estimator builder.
+    pub fn builder() -> FeeEstimatorBuilder {
+        FeeEstimatorBuilder::new()
+    }
+
+    /// Returns the reward percentiles (slow, normal, fast) to request from
+    /// `eth_feeHistory`.
+    pub fn reward_percentiles(&self) -> [f64; 3] {
+        self.reward_percentiles
+    }
+
+    /// Computes slow / normal / fast suggestions from `history`.
+    ///
+    /// `history` must have been requested with [`FeeEstimator::reward_percentiles`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history has no base fee or its reward rows do not have
+    /// one entry per percentile.
+    pub fn estimate(&self, history: &FeeHistory) -> Result<FeeSuggestions> {
+        let base_fee = history.next_base_fee().ok_or_else(|| {
+            Error::InvalidGas("fee history has no base_fee_per_gas entries".to_string())
+        })?;
+
+        if let Some(row) = history.reward.iter().find(|row| row.len() != 3) {
+            return Err(Error::InvalidGas(format!(
+                "fee history reward rows must have 3 entries, got {}",
+                row.len()
+            )));
+        }
+
+        let suggestion = |speed: FeeSpeed| {
+            let mut rewards: Vec<Wei> = history
+                .reward
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| history.gas_used_ratio.get(*i).copied().unwrap_or(1.0) > 0.0)
+                .map(|(_, row)| row[speed.index()])
+                .collect();
+            rewards.sort();
+
+            let priority = rewards
+                .get(rewards.len() / 2)
+                .copied()
+                .unwrap_or(Wei::ZERO)
+                .max(self.min_priority_fee);
+            let max_fee = U256::from(base_fee)
+                .saturating_mul(U256::from(self.base_fee_multiplier))
+                .saturating_add(priority.into());
+
+            FeeSuggestion {
+                max_fee_per_gas: Wei::from_u256(max_fee),
+                max_priority_fee_per_gas: priority,
+            }
+        };
+
+        Ok(FeeSuggestions {
+            base_fee,
+            slow: suggestion(FeeSpeed::Slow),
+            normal: suggestion(FeeSpeed::Normal),
+            fast: suggestion(FeeSpeed::Fast),
+        })
+    }
+}
+
+/// Builder for [`FeeEstimator`].
+#[derive(Debug, Clone, Default)]
+pub struct FeeEstimatorBuilder {
+    reward_percentiles: Option<[f64; 3]>,
+    base_fee_multiplier: Option<u64>,
+    min_priority_fee: Option<Wei>,
+}
+
+impl FeeEstimatorBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the reward percentiles for slow, normal and fast (0–100, ascending).
+    pub fn reward_percentiles(mut self, percentiles: [f64; 3]) -> Self {
+        self.reward_percentiles = Some(percentiles);
+        self
+    }
+
+    /// Sets the multiplier applied to the next base fee (default `2`).
+    pub fn base_fee_multiplier(mut self, multiplier: u64) -> Self {
+        self.base_fee_multiplier = Some(multiplier);
+        self
+    }
+
+    /// Sets a floor for the suggested priority fee (default zero).
+    ///
+    /// Useful on chains where validators ignore transactions below a minimum tip.
+    pub fn min_priority_fee(mut self, fee: Wei) -> Self {
+        self.min_priority_fee = Some(fee);
+        self
+    }
+
+    /// Builds the estimator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the percentiles are outside 0–100 or not ascending, or the
+    /// base fee multiplier is zero.
+    pub fn build(self) -> Result<FeeEstimator> {
+        let defaults = FeeEstimator::default();
+        let percentiles = self
+            .reward_percentiles
+            .unwrap_or(defaults.reward_percentiles);
+
+        if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            return Err(Error::ValidationError(
+                "reward percentiles must be between 0 and 100".to_string(),
+            ));
+        }
+        if percentiles.windows(2).any(|w| w[0] > w[1]) {
+            return Err(Error::ValidationError(
+                "reward percentiles must be ascending (slow, normal, fast)".to_string(),
+            ));
+        }
+
+        let base_fee_multiplier = self
+            .base_fee_multiplier
+            .unwrap_or(defaults.base_fee_multiplier);
+        if base_fee_multiplier == 0 {
+            return Err(Error::ValidationError(
+                "base_fee_multiplier must be at least 1".to_string(),
+            ));
+        }
+
+        Ok(FeeEstimator {
+            reward_percentiles: percentiles,
+            base_fee_multiplier,
+            min_priority_fee: self.min_priority_fee.unwrap_or(defaults.min_priority_fee),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei_row(values: [u64; 3]) -> Vec<Wei> {
+        values.iter().map(|&v| Wei::from_gwei(v)).collect()
+    }
+
+    fn sample_history() -> FeeHistory {
+        FeeHistory {
+            oldest_block: 1000,
+            base_fee_per_gas: vec![
+                Wei::from_gwei(10),
+                Wei::from_gwei(11),
+                Wei::from_gwei(12),
+                Wei::from_gwei(13),
+            ],
+            gas_used_ratio: vec![0.5, 0.9, 0.7],
+            reward: vec![
+                gwei_row([1, 2, 5]),
+                gwei_row([1, 3, 8]),
+                gwei_row([2, 2, 4]),
+            ],
+        }
+    }
+
+    // ==================== Estimation Tests ====================
+
+    #[test]
+    fn test_estimate_medians() {
+        let s = FeeEstimator::default().estimate(&sample_history()).unwrap();
+
+        assert_eq!(s.base_fee, Wei::from_gwei(13));
+        assert_eq!(s.slow.max_priority_fee_per_gas, Wei::from_gwei(1));
+        assert_eq!(s.normal.max_priority_fee_per_gas, Wei::from_gwei(2));
+        assert_eq!(s.fast.max_priority_fee_per_gas, Wei::from_gwei(5));
+        assert_eq!(s.normal.max_fee_per_gas, Wei::from_gwei(26 + 2));
+    }
+
+    #[test]
+    fn test_estimate_ordering() {
+        let s = FeeEstimator::default().estimate(&sample_history()).unwrap();
+        assert!(s.slow.max_fee_per_gas <= s.normal.max_fee_per_gas);
+        assert!(s.normal.max_fee_per_gas <= s.fast.max_fee_per_gas);
+        for speed in FeeSpeed::ALL {
+            let f = s.get(speed);
+            assert!(f.max_priority_fee_per_gas <= f.max_fee_per_gas);
+        }
+    }
+
+    #[test]
+    fn test_estimate_skips_empty_blocks() {
+        let mut history = sample_history();
+        history.gas_used_ratio = vec![0.0, 0.0, 0.7];
+
+        let s = FeeEstimator::default().estimate(&history).unwrap();
+        assert_eq!(s.fast.max_priority_fee_per_gas, Wei::from_gwei(4));
+    }
+
+    #[test]
+    fn test_estimate_min_priority_fee() {
+        let mut history = sample_history();
+        history.reward = vec![gwei_row([0, 0, 0]); 3];
+
+        let estimator = FeeEstimator::builder()
+            .min_priority_fee(Wei::from_gwei(1))
+            .build()
+            .unwrap();
+        let s = estimator.estimate(&history).unwrap();
+        assert_eq!(s.slow.max_priority_fee_per_gas, Wei::from_gwei(1));
+    }
+
+    #[test]
+    fn test_estimate_multiplier() {
+        let estimator = FeeEstimator::builder()
+            .base_fee_multiplier(1)
+            .build()
+            .unwrap();
+        let s = estimator.estimate(&sample_history()).unwrap();
+        assert_eq!(s.normal.max_fee_per_gas, Wei::from_gwei(13 + 2));
+    }
+
+    #[test]
+    fn test_estimate_no_rewards() {
+        let mut history = sample_history();
+        history.reward.clear();
+
+        let s = FeeEstimator::default().estimate(&history).unwrap();
+        assert_eq!(s.fast.max_priority_fee_per_gas, Wei::ZERO);
+        assert_eq!(s.fast.max_fee_per_gas, Wei::from_gwei(26));
+    }
+
+    #[test]
+    fn test_estimate_invalid_history() {
+        let mut history = sample_history();
+        history.base_fee_per_gas.clear();
+        assert!(FeeEstimator::default().estimate(&history).is_err());
+
+        let mut history = sample_history();
+        history.reward[1].pop();
+        assert!(FeeEstimator::default().estimate(&history).is_err());
+    }
+
+    #[test]
+    fn test_from_gas_price() {
+        let s = FeeSuggestions::from_gas_price(Wei::from_gwei(3));
+        assert_eq!(s.normal.max_fee_per_gas, Wei::from_gwei(3));
+        assert_eq!(s.fast.max_priority_fee_per_gas, Wei::from_gwei(3));
+    }
+
+    // ==================== Builder Tests ====================
+
+    #[test]
+    fn test_builder_defaults() {
+        let estimator = FeeEstimator::builder().build().unwrap();
+        assert_eq!(estimator, FeeEstimator::default());
+        assert_eq!(estimator.reward_percentiles(), DEFAULT_REWARD_PERCENTILES);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_percentiles() {
+        assert!(FeeEstimator::builder()
+            .reward_percentiles([10.0, 50.0, 101.0])
+            .build()
+            .is_err());
+        assert!(FeeEstimator::builder()
+            .reward_percentiles([50.0, 10.0, 90.0])
+            .build()
+            .is_err());
+        assert!(FeeEstimator::builder()
+            .reward_percentiles([f64::NAN, 50.0, 90.0])
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_zero_multiplier() {
+        assert!(FeeEstimator::builder()
+            .base_fee_multiplier(0)
+            .build()
+            .is_err());
+    }
+}
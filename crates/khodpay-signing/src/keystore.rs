@@ -0,0 +1,973 @@
+//! Encrypted key files: Ethereum V3 and EIP-2335 keystores.
+//!
+//! Two JSON formats are supported, so exported keys can be imported by other tools:
+//!
+//! - [`KeystoreV3`] — the [Web3 Secret Storage](https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/)
+//!   format written by geth and imported by MetaMask. The secret is encrypted with
+//!   AES-128-CTR under a scrypt or PBKDF2-HMAC-SHA256 key and authenticated with a
+//!   keccak-256 MAC.
+//! - [`Eip2335Keystore`] — the [EIP-2335](https://eips.ethereum.org/EIPS/eip-2335)
+//!   format used by consensus-layer validator tooling. Same ciphers, but a SHA-256
+//!   checksum and NFKD-normalized passwords.
+//!
+//! Both encrypt arbitrary secrets (private keys or BIP-39 seeds) and return decrypted
+//! bytes in a [`Zeroizing`] buffer. Decryption refuses KDF parameters above
+//! [`MAX_SCRYPT_LOG_N`], [`MAX_SCRYPT_R`], [`MAX_SCRYPT_P`] and [`MAX_PBKDF2_ROUNDS`],
+//! and scrypt parameters whose combined cost exceeds [`MAX_SCRYPT_COST`], so a hostile
+//! file cannot make the wallet hang or exhaust memory.
+//!
+//! Requires the `keystore` feature.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::keystore::{Kdf, KeystoreV3};
+//! use khodpay_signing::Bip44Signer;
+//!
+//! let private_key = [1u8; 32];
+//! // Light parameters keep the example fast; use `Kdf::default()` for real exports.
+//! let kdf = Kdf::Scrypt { log_n: 10, r: 8, p: 1 };
+//! let keystore = KeystoreV3::encrypt_private_key(&private_key, "correct horse", kdf).unwrap();
+//! let json = keystore.to_json();
+//!
+//! let parsed = KeystoreV3::from_json(&json).unwrap();
+//! let signer = parsed.decrypt_signer("correct horse").unwrap();
+//! assert_eq!(signer.address(), Bip44Signer::from_private_key(&private_key).unwrap().address());
+//! assert!(parsed.decrypt("wrong").is_err());
+//! ```
+
+use crate::eip712::keccak256;
+use crate::{Address, Bip44Signer, Error, Result};
+use aes::cipher::{KeyIvInit, StreamCipher};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+/// Largest accepted scrypt cost, as `log2(n)`.
+pub const MAX_SCRYPT_LOG_N: u8 = 20;
+
+/// Largest accepted scrypt block size `r`.
+pub const MAX_SCRYPT_R: u32 = 32;
+
+/// Largest accepted scrypt parallelism `p`.
+pub const MAX_SCRYPT_P: u32 = 16;
+
+/// Largest accepted scrypt cost `128·r·n·p` in bytes processed: 1 GiB, i.e.
+/// `n = 2^20` with geth's `r = 8` and `p = 1`.
+///
+/// Memory use is `128·r·n`, and `p` repeats that work sequentially, so this bounds
+/// both memory and time.
+pub const MAX_SCRYPT_COST: u64 = 1 << 30;
+
+/// Largest accepted PBKDF2 iteration count.
+pub const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+
+/// Length of the derived key; the first half encrypts, the second half authenticates.
+const DKLEN: usize = 32;
+
+/// The only cipher either format uses.
+const CIPHER: &str = "aes-128-ctr";
+
+/// PBKDF2 pseudo-random function name.
+const PRF: &str = "hmac-sha256";
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// Key derivation function protecting a keystore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// scrypt with cost `2^log_n`, block size `r` and parallelism `p`.
+    Scrypt {
+        /// Base-2 logarithm of the CPU/memory cost `n`.
+        log_n: u8,
+        /// Block size.
+        r: u32,
+        /// Parallelism.
+        p: u32,
+    },
+    /// PBKDF2-HMAC-SHA256 with `c` iterations.
+    Pbkdf2 {
+        /// Iteration count.
+        c: u32,
+    },
+}
+
+impl Kdf {
+    /// geth's standard scrypt parameters: `n = 2^18`, `r = 8`, `p = 1`.
+    pub const STANDARD_SCRYPT: Self = Self::Scrypt {
+        log_n: 18,
+        r: 8,
+        p: 1,
+    };
+
+    /// PBKDF2 with 262,144 iterations, as in the EIP-2335 test vectors.
+    pub const STANDARD_PBKDF2: Self = Self::Pbkdf2 { c: 262_144 };
+
+    /// Derives the 32-byte key for `password` and `salt`.
+    fn derive(&self, password: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; DKLEN]>> {
+        let mut key = Zeroizing::new([0u8; DKLEN]);
+        match *self {
+            Self::Scrypt { log_n, r, p } => {
+                if log_n > MAX_SCRYPT_LOG_N {
+                    return Err(Error::KeystoreError(format!(
+                        "scrypt cost 2^{} exceeds the maximum 2^{}",
+                        log_n, MAX_SCRYPT_LOG_N
+                    )));
+                }
+                if r > MAX_SCRYPT_R || p > MAX_SCRYPT_P {
+                    return Err(Error::KeystoreError(format!(
+                        "scrypt r = {}, p = {} exceed the maximums r = {}, p = {}",
+                        r, p, MAX_SCRYPT_R, MAX_SCRYPT_P
+                    )));
+                }
+                let cost = 128 * u64::from(r) * (1u64 << log_n) * u64::from(p);
+                if cost > MAX_SCRYPT_COST {
+                    return Err(Error::KeystoreError(format!(
+                        "scrypt cost 128·r·n·p = {} exceeds the maximum {}",
+                        cost, MAX_SCRYPT_COST
+                    )));
+                }
+                let params = scrypt::Params::new(log_n, r, p, DKLEN).map_err(|e| {
+                    Error::KeystoreError(format!("invalid scrypt parameters: {}", e))
+                })?;
+                scrypt::scrypt(password, salt, &params, key.as_mut()).map_err(|e| {
+                    Error::KeystoreError(format!("invalid scrypt parameters: {}", e))
+                })?;
+            }
+            Self::Pbkdf2 { c } => {
+                if c == 0 || c > MAX_PBKDF2_ROUNDS {
+                    return Err(Error::KeystoreError(format!(
+                        "PBKDF2 iteration count {} is outside 1..={}",
+                        c, MAX_PBKDF2_ROUNDS
+                    )));
+                }
+                pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, c, key.as_mut());
+            }
+        }
+        Ok(key)
+    }
+
+    /// Encodes the parameters with `salt` in the shared JSON layout.
+    fn to_params(self, salt: &[u8]) -> KdfParams {
+        let salt = hex::encode(salt);
+        match self {
+            Self::Scrypt { log_n, r, p } => KdfParams::Scrypt {
+                dklen: DKLEN as u32,
+                n: 1u64 << log_n,
+                r,
+                p,
+                salt,
+            },
+            Self::Pbkdf2 { c } => KdfParams::Pbkdf2 {
+                dklen: DKLEN as u32,
+                c,
+                prf: PRF.to_string(),
+                salt,
+            },
+        }
+    }
+}
+
+impl Default for Kdf {
+    /// Returns [`Kdf::STANDARD_SCRYPT`].
+    fn default() -> Self {
+        Self::STANDARD_SCRYPT
+    }
+}
+
+/// KDF parameters as they appear in both JSON formats.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum KdfParams {
+    Scrypt {
+        dklen: u32,
+        n: u64,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        dklen: u32,
+        c: u32,
+        prf: String,
+        salt: String,
+    },
+}
+
+impl KdfParams {
+    /// Validates the parameters against the named function and returns them with the salt.
+    fn parse(&self, function: &str) -> Result<(Kdf, Vec<u8>)> {
+        let (kdf, dklen, salt) = match self {
+            Self::Scrypt {
+                dklen,
+                n,
+                r,
+                p,
+                salt,
+            } if function == "scrypt" => {
+                if *n < 2 || !n.is_power_of_two() {
+                    return Err(Error::KeystoreError(format!(
+                        "scrypt n must be a power of two, got {}",
+                        n
+                    )));
+                }
+                let log_n = n.trailing_zeros() as u8;
+                (
+                    Kdf::Scrypt {
+                        log_n,
+                        r: *r,
+                        p: *p,
+                    },
+                    *dklen,
+                    salt,
+                )
+            }
+            Self::Pbkdf2 {
+                dklen,
+                c,
+                prf,
+                salt,
+            } if function == "pbkdf2" => {
+                if prf != PRF {
+                    return Err(Error::KeystoreError(format!(
+                        "unsupported PBKDF2 function: {}",
+                        prf
+                    )));
+                }
+                (Kdf::Pbkdf2 { c: *c }, *dklen, salt)
+            }
+            _ => {
+                return Err(Error::KeystoreError(format!(
+                    "unsupported or mismatched KDF: {}",
+                    function
+                )))
+            }
+        };
+        if dklen as usize != DKLEN {
+            return Err(Error::KeystoreError(format!(
+                "unsupported derived key length: {}",
+                dklen
+            )));
+        }
+        Ok((kdf, decode_hex(salt, "salt")?))
+    }
+}
+
+/// A freshly encrypted secret with its random salt and IV, and the derived key.
+struct Encrypted {
+    salt: [u8; 32],
+    iv: [u8; 16],
+    ciphertext: Vec<u8>,
+    key: Zeroizing<[u8; DKLEN]>,
+}
+
+impl Encrypted {
+    fn new(secret: &[u8], password: &[u8], kdf: Kdf) -> Result<Self> {
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut iv);
+        let key = kdf.derive(password, &salt)?;
+        let mut ciphertext = secret.to_vec();
+        apply_cipher(&key, &iv, &mut ciphertext);
+        Ok(Self {
+            salt,
+            iv,
+            ciphertext,
+            key,
+        })
+    }
+}
+
+/// Runs AES-128-CTR keyed with the first half of `key` over `data`.
+fn apply_cipher(key: &[u8; DKLEN], iv: &[u8; 16], data: &mut [u8]) {
+    let mut cipher = Aes128Ctr::new(key[..16].into(), iv.into());
+    cipher.apply_keystream(data);
+}
+
+/// Decodes a hex field, naming it in the error.
+fn decode_hex(value: &str, field: &str) -> Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|e| Error::KeystoreError(format!("invalid {}: {}", field, e)))
+}
+
+/// Decodes the 16-byte AES IV.
+fn decode_iv(value: &str) -> Result<[u8; 16]> {
+    decode_hex(value, "iv")?
+        .try_into()
+        .map_err(|_| Error::KeystoreError("iv must be 16 bytes".to_string()))
+}
+
+/// Returns a random version-4 UUID.
+fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Compares two MACs without an early exit.
+fn mac_matches(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Turns a private key into a signer, checking it against an expected address.
+fn signer_for(secret: &[u8], expected: Option<Address>) -> Result<Bip44Signer> {
+    let private_key: &[u8; 32] = secret
+        .try_into()
+        .map_err(|_| Error::KeystoreError("secret is not a 32-byte private key".to_string()))?;
+    let signer = Bip44Signer::from_private_key(private_key)?;
+    match expected {
+        Some(address) if address != signer.address() => Err(Error::KeystoreError(format!(
+            "decrypted key belongs to {}, keystore says {}",
+            signer.address(),
+            address
+        ))),
+        _ => Ok(signer),
+    }
+}
+
+// ==================== Web3 Secret Storage (V3) ====================
+
+/// An Ethereum V3 (Web3 Secret Storage) keystore.
+///
+/// Serializes to the JSON layout geth writes. The `address` field is present when the
+/// keystore was created from a private key and omitted for other secrets such as seeds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreV3 {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(alias = "Crypto")]
+    crypto: V3Crypto,
+    id: String,
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct V3Crypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: KdfParams,
+    mac: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+impl KeystoreV3 {
+    /// Encrypts an arbitrary secret, such as a BIP-39 seed, under `password`.
+    ///
+    /// The keystore carries no address. Use
+    /// [`encrypt_private_key`](Self::encrypt_private_key) for files meant for MetaMask
+    /// or geth.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KeystoreError`] if the KDF parameters are invalid.
+    pub fn encrypt(secret: &[u8], password: &str, kdf: Kdf) -> Result<Self> {
+        let encrypted = Encrypted::new(secret, password.as_bytes(), kdf)?;
+        let mac = v3_mac(&encrypted.key, &encrypted.ciphertext);
+        Ok(Self {
+            address: None,
+            crypto: V3Crypto {
+                cipher: CIPHER.to_string(),
+                cipherparams: CipherParams {
+                    iv: hex::encode(encrypted.iv),
+                },
+                ciphertext: hex::encode(&encrypted.ciphertext),
+                kdf: kdf_name(kdf).to_string(),
+                kdfparams: kdf.to_params(&encrypted.salt),
+                mac: hex::encode(mac),
+            },
+            id: random_uuid(),
+            version: 3,
+        })
+    }
+
+    /// Encrypts a secp256k1 private key and records its address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the private key is invalid or the KDF parameters are invalid.
+    pub fn encrypt_private_key(private_key: &[u8; 32], password: &str, kdf: Kdf) -> Result<Self> {
+        let address = Bip44Signer::from_private_key(private_key)?.address();
+        let mut keystore = Self::encrypt(private_key, password, kdf)?;
+        keystore.address = Some(hex::encode(address.as_bytes()));
+        Ok(keystore)
+    }
+
+    /// Decrypts the secret.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongPassword`] if the MAC does not match, and
+    /// [`Error::KeystoreError`] for unsupported or malformed parameters.
+    pub fn decrypt(&self, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+        let crypto = &self.crypto;
+        if self.version != 3 {
+            return Err(Error::KeystoreError(format!(
+                "unsupported keystore version: {}",
+                self.version
+            )));
+        }
+        if crypto.cipher != CIPHER {
+            return Err(Error::KeystoreError(format!(
+                "unsupported cipher: {}",
+                crypto.cipher
+            )));
+        }
+        let (kdf, salt) = crypto.kdfparams.parse(&crypto.kdf)?;
+        let iv = decode_iv(&crypto.cipherparams.iv)?;
+        let ciphertext = decode_hex(&crypto.ciphertext, "ciphertext")?;
+        let mac = decode_hex(&crypto.mac, "mac")?;
+
+        let key = kdf.derive(password.as_bytes(), &salt)?;
+        if !mac_matches(&mac, &v3_mac(&key, &ciphertext)) {
+            return Err(Error::WrongPassword);
+        }
+        let mut secret = Zeroizing::new(ciphertext);
+        apply_cipher(&key, &iv, &mut secret);
+        Ok(secret)
+    }
+
+    /// Decrypts a private key keystore into a signer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if decryption fails, the secret is not a valid private key, or
+    /// the key does not match the keystore's `address`.
+    pub fn decrypt_signer(&self, password: &str) -> Result<Bip44Signer> {
+        let secret = self.decrypt(password)?;
+        signer_for(&secret, self.address()?)
+    }
+
+    /// Returns the address recorded in the keystore, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAddress`] if the recorded address is malformed.
+    pub fn address(&self) -> Result<Option<Address>> {
+        self.address
+            .as_deref()
+            .map(|address| format!("0x{}", address.trim_start_matches("0x")).parse())
+            .transpose()
+    }
+
+    /// Returns the keystore's UUID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Parses a keystore from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KeystoreError`] if the JSON is not a V3 keystore.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::KeystoreError(e.to_string()))
+    }
+
+    /// Serializes the keystore to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("keystore serialization cannot fail")
+    }
+}
+
+/// keccak-256 over the second half of the derived key and the ciphertext.
+fn v3_mac(key: &[u8; DKLEN], ciphertext: &[u8]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(16 + ciphertext.len());
+    data.extend_from_slice(&key[16..]);
+    data.extend_from_slice(ciphertext);
+    keccak256(&data)
+}
+
+/// Name of a KDF in both JSON formats.
+fn kdf_name(kdf: Kdf) -> &'static str {
+    match kdf {
+        Kdf::Scrypt { .. } => "scrypt",
+        Kdf::Pbkdf2 { .. } => "pbkdf2",
+    }
+}
+
+// ==================== EIP-2335 ====================
+
+/// An EIP-2335 (version 4) keystore, as used by validator clients and deposit tools.
+///
+/// The public key and path are stored as given; this crate does not derive BLS keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eip2335Keystore {
+    crypto: Eip2335Crypto,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    pubkey: String,
+    path: String,
+    uuid: String,
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Eip2335Crypto {
+    kdf: Eip2335Module<KdfParams>,
+    checksum: Eip2335Module<EmptyParams>,
+    cipher: Eip2335Module<CipherParams>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Eip2335Module<P> {
+    function: String,
+    params: P,
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct EmptyParams {}
+
+impl Eip2335Keystore {
+    /// Encrypts `secret` with the BLS public key `pubkey` and EIP-2334 `path` it belongs to.
+    ///
+    /// The password is normalized as EIP-2335 requires (NFKD, control codes removed).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KeystoreError`] if the KDF parameters are invalid.
+    pub fn encrypt(
+        secret: &[u8],
+        password: &str,
+        kdf: Kdf,
+        pubkey: &[u8],
+        path: &str,
+    ) -> Result<Self> {
+        let password = normalize_password(password);
+        let encrypted = Encrypted::new(secret, password.as_bytes(), kdf)?;
+        let checksum = eip2335_checksum(&encrypted.key, &encrypted.ciphertext);
+        Ok(Self {
+            crypto: Eip2335Crypto {
+                kdf: Eip2335Module {
+                    function: kdf_name(kdf).to_string(),
+                    params: kdf.to_params(&encrypted.salt),
+                    message: String::new(),
+                },
+                checksum: Eip2335Module {
+                    function: "sha256".to_string(),
+                    params: EmptyParams {},
+                    message: hex::encode(checksum),
+                },
+                cipher: Eip2335Module {
+                    function: CIPHER.to_string(),
+                    params: CipherParams {
+                        iv: hex::encode(encrypted.iv),
+                    },
+                    message: hex::encode(&encrypted.ciphertext),
+                },
+            },
+            description: String::new(),
+            pubkey: hex::encode(pubkey),
+            path: path.to_string(),
+            uuid: random_uuid(),
+            version: 4,
+        })
+    }
+
+    /// Sets the free-form description.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Decrypts the secret.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongPassword`] if the checksum does not match, and
+    /// [`Error::KeystoreError`] for unsupported or malformed parameters.
+    pub fn decrypt(&self, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+        let crypto = &self.crypto;
+        if self.version != 4 {
+            return Err(Error::KeystoreError(format!(
+                "unsupported keystore version: {}",
+                self.version
+            )));
+        }
+        if crypto.cipher.function != CIPHER || crypto.checksum.function != "sha256" {
+            return Err(Error::KeystoreError(format!(
+                "unsupported cipher or checksum: {} / {}",
+                crypto.cipher.function, crypto.checksum.function
+            )));
+        }
+        let (kdf, salt) = crypto.kdf.params.parse(&crypto.kdf.function)?;
+        let iv = decode_iv(&crypto.cipher.params.iv)?;
+        let ciphertext = decode_hex(&crypto.cipher.message, "cipher message")?;
+        let checksum = decode_hex(&crypto.checksum.message, "checksum")?;
+
+        let password = normalize_password(password);
+        let key = kdf.derive(password.as_bytes(), &salt)?;
+        if !mac_matches(&checksum, &eip2335_checksum(&key, &ciphertext)) {
+            return Err(Error::WrongPassword);
+        }
+        let mut secret = Zeroizing::new(ciphertext);
+        apply_cipher(&key, &iv, &mut secret);
+        Ok(secret)
+    }
+
+    /// Returns the BLS public key bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KeystoreError`] if the stored public key is not hex.
+    pub fn pubkey(&self) -> Result<Vec<u8>> {
+        decode_hex(&self.pubkey, "pubkey")
+    }
+
+    /// Returns the EIP-2334 derivation path of the key.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the free-form description.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the keystore's UUID.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// Parses a keystore from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KeystoreError`] if the JSON is not an EIP-2335 keystore.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::KeystoreError(e.to_string()))
+    }
+
+    /// Serializes the keystore to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("keystore serialization cannot fail")
+    }
+}
+
+/// SHA-256 over the second half of the derived key and the ciphertext.
+fn eip2335_checksum(key: &[u8; DKLEN], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&key[16..]);
+    hasher.update(ciphertext);
+    hasher.finalize().into()
+}
+
+/// NFKD-normalizes a password and strips C0, C1 and delete control codes (EIP-2335).
+fn normalize_password(password: &str) -> Zeroizing<String> {
+    Zeroizing::new(
+        password
+            .nfkd()
+            .filter(|c| !matches!(*c as u32, 0x00..=0x1f | 0x7f..=0x9f))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIGHT: Kdf = Kdf::Scrypt {
+        log_n: 10,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn test_v3_pbkdf2_vector() {
+        // Test vector from the Web3 Secret Storage definition
+        let json = r#"{
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                "kdf": "pbkdf2",
+                "kdfparams": {
+                    "c": 262144,
+                    "dklen": 32,
+                    "prf": "hmac-sha256",
+                    "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                },
+                "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "version": 3
+        }"#;
+        let keystore = KeystoreV3::from_json(json).unwrap();
+        assert_eq!(keystore.id(), "3198bc9c-6672-5ab3-d995-4942343ae5b6");
+        assert_eq!(keystore.address().unwrap(), None);
+        assert_eq!(
+            hex::encode(keystore.decrypt("testpassword").unwrap().as_slice()),
+            "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d"
+        );
+        assert!(matches!(
+            keystore.decrypt("testpassword!"),
+            Err(Error::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_v3_private_key_round_trip() {
+        let private_key = [7u8; 32];
+        let expected = Bip44Signer::from_private_key(&private_key)
+            .unwrap()
+            .address();
+
+        for kdf in [LIGHT, Kdf::Pbkdf2 { c: 1_000 }] {
+            let keystore = KeystoreV3::encrypt_private_key(&private_key, "pässword", kdf).unwrap();
+            let json = keystore.to_json();
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value["version"], 3);
+            assert_eq!(value["crypto"]["kdf"], kdf_name(kdf));
+            assert_eq!(value["address"], hex::encode(expected.as_bytes()));
+            assert_eq!(value["id"].as_str().unwrap().len(), 36);
+
+            let parsed = KeystoreV3::from_json(&json).unwrap();
+            assert_eq!(parsed, keystore);
+            assert_eq!(parsed.address().unwrap(), Some(expected));
+            assert_eq!(
+                parsed.decrypt_signer("pässword").unwrap().address(),
+                expected
+            );
+            assert!(matches!(
+                parsed.decrypt("password"),
+                Err(Error::WrongPassword)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_v3_seed_and_geth_capitalized_crypto() {
+        let seed = [0xabu8; 64];
+        let keystore = KeystoreV3::encrypt(&seed, "pw", LIGHT).unwrap();
+        assert_eq!(keystore.address().unwrap(), None);
+        assert!(keystore.decrypt_signer("pw").is_err());
+
+        // Older geth versions wrote "Crypto"
+        let json = keystore.to_json().replace("\"crypto\"", "\"Crypto\"");
+        let parsed = KeystoreV3::from_json(&json).unwrap();
+        assert_eq!(parsed.decrypt("pw").unwrap().as_slice(), &seed[..]);
+    }
+
+    #[test]
+    fn test_v3_rejects_tampering_and_hostile_params() {
+        let keystore = KeystoreV3::encrypt_private_key(&[7u8; 32], "pw", LIGHT).unwrap();
+
+        let mut tampered = keystore.clone();
+        tampered.crypto.ciphertext.replace_range(..2, "00");
+        assert!(matches!(tampered.decrypt("pw"), Err(Error::WrongPassword)));
+
+        let mut wrong_address = keystore.clone();
+        wrong_address.address = Some("00".repeat(20));
+        assert!(matches!(
+            wrong_address.decrypt_signer("pw"),
+            Err(Error::KeystoreError(_))
+        ));
+
+        let json = keystore.to_json();
+        for (from, to) in [
+            ("\"n\":1024", "\"n\":1073741824"),
+            ("\"n\":1024", "\"n\":1000"),
+            ("\"dklen\":32", "\"dklen\":16"),
+            ("\"kdf\":\"scrypt\"", "\"kdf\":\"pbkdf2\""),
+            ("aes-128-ctr", "aes-128-cbc"),
+            ("\"version\":3", "\"version\":4"),
+        ] {
+            assert!(json.contains(from), "{}", from);
+            let hostile = KeystoreV3::from_json(&json.replace(from, to)).unwrap();
+            assert!(
+                matches!(hostile.decrypt("pw"), Err(Error::KeystoreError(_))),
+                "{} -> {}",
+                from,
+                to
+            );
+        }
+
+        assert!(KeystoreV3::from_json("{}").is_err());
+        assert!(KeystoreV3::encrypt(&[1], "pw", Kdf::Pbkdf2 { c: 0 }).is_err());
+    }
+
+    #[test]
+    fn test_scrypt_caps() {
+        let salt = [0u8; 32];
+        let too_costly = [
+            // Each parameter over its own cap
+            Kdf::Scrypt {
+                log_n: 21,
+                r: 1,
+                p: 1,
+            },
+            Kdf::Scrypt {
+                log_n: 10,
+                r: 33,
+                p: 1,
+            },
+            Kdf::Scrypt {
+                log_n: 10,
+                r: 8,
+                p: 2000,
+            },
+            // Each within its cap but too costly together
+            Kdf::Scrypt {
+                log_n: 20,
+                r: 16,
+                p: 1,
+            },
+            Kdf::Scrypt {
+                log_n: 20,
+                r: 8,
+                p: 2,
+            },
+        ];
+        for kdf in too_costly {
+            assert!(
+                matches!(kdf.derive(b"pw", &salt), Err(Error::KeystoreError(_))),
+                "{:?}",
+                kdf
+            );
+        }
+        assert!(Kdf::Scrypt {
+            log_n: 4,
+            r: 32,
+            p: 16
+        }
+        .derive(b"pw", &salt)
+        .is_ok());
+    }
+
+    #[test]
+    fn test_hostile_scrypt_r_and_p() {
+        let v3 = KeystoreV3::encrypt(&[7u8; 32], "pw", LIGHT)
+            .unwrap()
+            .to_json();
+        let eip2335 = Eip2335Keystore::encrypt(&[7u8; 32], "pw", LIGHT, &[0u8; 48], "")
+            .unwrap()
+            .to_json();
+
+        for (from, to) in [("\"r\":8", "\"r\":1024"), ("\"p\":1", "\"p\":2000")] {
+            assert!(v3.contains(from) && eip2335.contains(from), "{}", from);
+            let hostile = KeystoreV3::from_json(&v3.replace(from, to)).unwrap();
+            assert!(matches!(
+                hostile.decrypt("pw"),
+                Err(Error::KeystoreError(_))
+            ));
+            let hostile = Eip2335Keystore::from_json(&eip2335.replace(from, to)).unwrap();
+            assert!(matches!(
+                hostile.decrypt("pw"),
+                Err(Error::KeystoreError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_eip2335_pbkdf2_vector() {
+        // Test vector from EIP-2335
+        let json = r#"{
+            "crypto": {
+                "kdf": {
+                    "function": "pbkdf2",
+                    "params": {
+                        "dklen": 32,
+                        "c": 262144,
+                        "prf": "hmac-sha256",
+                        "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                    },
+                    "message": ""
+                },
+                "checksum": {
+                    "function": "sha256",
+                    "params": {},
+                    "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+                },
+                "cipher": {
+                    "function": "aes-128-ctr",
+                    "params": { "iv": "264daa3f303d7259501c93d997d84fe6" },
+                    "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+                }
+            },
+            "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+            "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+            "path": "m/12381/60/0/0",
+            "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+            "version": 4
+        }"#;
+        let keystore = Eip2335Keystore::from_json(json).unwrap();
+        assert_eq!(keystore.path(), "m/12381/60/0/0");
+        assert_eq!(keystore.pubkey().unwrap().len(), 48);
+        // NFKD folds the mathematical letters to plain ASCII
+        let secret = keystore.decrypt("𝔱𝔢𝔰𝔱𝔭𝔞𝔰𝔰𝔴𝔬𝔯𝔡🔑").unwrap();
+        assert_eq!(
+            hex::encode(secret.as_slice()),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(
+            keystore.decrypt("testpassword🔑").unwrap().as_slice(),
+            secret.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_eip2335_round_trip() {
+        let secret = [0x42u8; 32];
+        let pubkey = [0x99u8; 48];
+        let keystore = Eip2335Keystore::encrypt(
+            &secret,
+            "pass\u{7f}word",
+            LIGHT,
+            &pubkey,
+            "m/12381/3600/0/0/0",
+        )
+        .unwrap()
+        .with_description("validator 0");
+        let json = keystore.to_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 4);
+        assert_eq!(value["crypto"]["checksum"]["function"], "sha256");
+        assert_eq!(value["crypto"]["kdf"]["params"]["n"], 1024);
+
+        let parsed = Eip2335Keystore::from_json(&json).unwrap();
+        assert_eq!(parsed, keystore);
+        assert_eq!(parsed.description(), "validator 0");
+        assert_eq!(parsed.pubkey().unwrap(), pubkey);
+        // Control codes are stripped before key derivation
+        assert_eq!(parsed.decrypt("password").unwrap().as_slice(), &secret);
+        assert!(matches!(
+            parsed.decrypt("pass word"),
+            Err(Error::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_random_uuid_format() {
+        let uuid = random_uuid();
+        let parts: Vec<_> = uuid.split('-').map(str::len).collect();
+        assert_eq!(parts, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&uuid[14..15], "4");
+        assert_ne!(uuid, random_uuid());
+    }
+}
@@ -0,0 +1,631 @@
+//! L1 data fees on OP Stack rollups (Optimism, Base).
+//!
+//! A transaction on an OP Stack chain pays two fees: the usual L2 execution fee
+//! (`gas_used * effective_gas_price`) and an **L1 data fee** for posting the transaction
+//! to Ethereum. The data fee is not part of the gas limit, so a preview that only
+//! multiplies gas by the gas price understates the real cost — often by most of it.
+//!
+//! The `GasPriceOracle` predeploy at [`GAS_PRICE_ORACLE`] prices that data. This module
+//! encodes calls to it and reproduces its formulas offline from the oracle's parameters:
+//!
+//! - **Fjord** (current): the transaction is priced by its FastLZ-compressed size,
+//!   see [`L1FeeParams::l1_fee`].
+//! - **Ecotone**: the transaction is priced by its calldata gas,
+//!   see [`L1FeeParams::ecotone_l1_fee`].
+//!
+//! Like the oracle, both take the *unsigned* serialized transaction and add 68 bytes for
+//! the signature. With the `net` feature, `get_l1_fee` asks the oracle directly,
+//! `get_l1_fee_params` reads its parameters and `estimate_fee` returns an
+//! [`L2FeeEstimate`] for a transaction.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::l2_fee::L1FeeParams;
+//! use khodpay_signing::{Address, ChainId, Eip1559Transaction, Wei};
+//!
+//! let params = L1FeeParams {
+//!     l1_base_fee: Wei::from_gwei(10),
+//!     blob_base_fee: Wei::from_wei(1u64),
+//!     base_fee_scalar: 1_368,
+//!     blob_base_fee_scalar: 810_949,
+//! };
+//!
+//! let tx = Eip1559Transaction::builder()
+//!     .chain_id(ChainId::BaseMainnet)
+//!     .nonce(0)
+//!     .max_priority_fee_per_gas(Wei::from_wei(1_000_000u64))
+//!     .max_fee_per_gas(Wei::from_wei(10_000_000u64))
+//!     .gas_limit(21_000)
+//!     .to(Address::from_bytes([0x11; 20]))
+//!     .value(Wei::from_gwei(1_000))
+//!     .build()
+//!     .unwrap();
+//!
+//! let estimate = params.estimate(&tx);
+//! assert_eq!(estimate.execution_fee, Wei::from_gwei(210));
+//! assert!(estimate.l1_data_fee > Wei::ZERO);
+//! assert_eq!(estimate.total(), estimate.execution_fee + estimate.l1_data_fee);
+//! ```
+
+use crate::abi::{encode_with_selector, Token};
+use crate::{Address, ChainId, Eip1559Transaction, Wei, U256};
+
+/// Address of the `GasPriceOracle` predeploy (`0x420000000000000000000000000000000000000F`).
+pub const GAS_PRICE_ORACLE: Address = Address::from_bytes([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0f,
+]);
+
+/// Selector of `getL1Fee(bytes)`.
+pub const GET_L1_FEE_SELECTOR: [u8; 4] = [0x49, 0x94, 0x8e, 0x0e];
+
+/// Selector of `l1BaseFee()`.
+pub const L1_BASE_FEE_SELECTOR: [u8; 4] = [0x51, 0x9b, 0x4b, 0xd3];
+
+/// Selector of `blobBaseFee()`.
+pub const BLOB_BASE_FEE_SELECTOR: [u8; 4] = [0xf8, 0x20, 0x61, 0x40];
+
+/// Selector of `baseFeeScalar()`.
+pub const BASE_FEE_SCALAR_SELECTOR: [u8; 4] = [0xc5, 0x98, 0x59, 0x18];
+
+/// Selector of `blobBaseFeeScalar()`.
+pub const BLOB_BASE_FEE_SCALAR_SELECTOR: [u8; 4] = [0x68, 0xd5, 0xdc, 0xa6];
+
+/// Bytes the oracle adds to an unsigned transaction to account for its signature.
+pub const SIGNATURE_OVERHEAD: u64 = 68;
+
+/// Fjord: minimum estimated transaction size, in bytes.
+const MIN_TRANSACTION_SIZE: u64 = 100;
+
+/// Fjord: intercept of the compressed-size regression, scaled by 1e6.
+const COST_INTERCEPT: i64 = -42_585_600;
+
+/// Fjord: FastLZ-size coefficient of the compressed-size regression, scaled by 1e6.
+const FASTLZ_COEF: u64 = 836_500;
+
+/// Precision of the oracle's fixed-point scalars.
+const SCALAR_DECIMALS: u64 = 1_000_000;
+
+/// Returns `true` for the OP Stack chains known to this crate (OP Mainnet, Base and
+/// their Sepolia testnets), i.e. chains that charge an L1 data fee.
+pub fn is_op_stack(chain_id: ChainId) -> bool {
+    matches!(chain_id.value(), 10 | 8453 | 11_155_420 | 84_532)
+}
+
+/// Returns the L1 calldata gas of `data`: 4 per zero byte and 16 per non-zero byte.
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    data.iter()
+        .map(|&byte| if byte == 0 { 4 } else { 16 })
+        .sum()
+}
+
+/// Returns the length of `data` after FastLZ compression.
+///
+/// This is the size the Fjord oracle prices (`LibZip.flzCompress(data).length`); only the
+/// length is computed, not the compressed bytes.
+pub fn fastlz_compressed_len(data: &[u8]) -> u64 {
+    fn u24(data: &[u8], i: usize) -> u32 {
+        u32::from(data[i]) | (u32::from(data[i + 1]) << 8) | (u32::from(data[i + 2]) << 16)
+    }
+
+    fn hash(value: u32) -> usize {
+        ((2_654_435_769u32.wrapping_mul(value) >> 19) & 0x1fff) as usize
+    }
+
+    fn literals(len: &mut u64, run: usize) {
+        let run = run as u64;
+        *len += 0x21 * (run / 0x20);
+        let tail = run % 0x20;
+        if tail != 0 {
+            *len += tail + 1;
+        }
+    }
+
+    fn matched(len: &mut u64, match_len: usize) {
+        let rest = match_len as u64 - 1;
+        *len += 3 * (rest / 262);
+        *len += if rest % 262 >= 6 { 3 } else { 2 };
+    }
+
+    let mut len = 0;
+    let mut table = vec![0usize; 8192];
+    let mut anchor = 0;
+    let limit = data.len().saturating_sub(13);
+    let mut ip = 2;
+
+    while ip < limit {
+        let mut reference;
+        loop {
+            let sequence = u24(data, ip);
+            let slot = hash(sequence);
+            reference = table[slot];
+            table[slot] = ip;
+            let distance = ip - reference;
+            if ip >= limit {
+                break;
+            }
+            ip += 1;
+            if distance <= 0x1fff && sequence == u24(data, reference) {
+                break;
+            }
+        }
+        if ip >= limit {
+            break;
+        }
+
+        ip -= 1;
+        if ip > anchor {
+            literals(&mut len, ip - anchor);
+        }
+
+        // Mirrors the reference implementation, which counts the first mismatching
+        // byte as part of the match and drops it again in `matched`.
+        let (p, q, end) = (reference + 3, ip + 3, limit + 9);
+        let mut match_len = 0;
+        while match_len < end - q {
+            let mismatch = data[p + match_len] != data[q + match_len];
+            match_len += 1;
+            if mismatch {
+                break;
+            }
+        }
+        matched(&mut len, match_len);
+
+        ip += match_len;
+        for _ in 0..2 {
+            table[hash(u24(data, ip))] = ip;
+            ip += 1;
+        }
+        anchor = ip;
+    }
+
+    literals(&mut len, data.len() - anchor);
+    len
+}
+
+/// Encodes `getL1Fee(data)` calldata for the [`GAS_PRICE_ORACLE`].
+///
+/// `data` is the unsigned serialized transaction, e.g.
+/// [`Eip1559Transaction::encode_unsigned`].
+pub fn encode_get_l1_fee(data: &[u8]) -> Vec<u8> {
+    encode_with_selector(GET_L1_FEE_SELECTOR, &[Token::Bytes(data.to_vec())])
+}
+
+/// The `GasPriceOracle` parameters the L1 data fee is derived from.
+///
+/// Read them from the oracle with the getters behind [`L1_BASE_FEE_SELECTOR`],
+/// [`BLOB_BASE_FEE_SELECTOR`], [`BASE_FEE_SCALAR_SELECTOR`] and
+/// [`BLOB_BASE_FEE_SCALAR_SELECTOR`]. They change with every L1 block, so reuse them
+/// for previews only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L1FeeParams {
+    /// The L1 base fee.
+    pub l1_base_fee: Wei,
+    /// The L1 blob base fee.
+    pub blob_base_fee: Wei,
+    /// The base fee scalar, with 6 decimals.
+    pub base_fee_scalar: u32,
+    /// The blob base fee scalar, with 6 decimals.
+    pub blob_base_fee_scalar: u32,
+}
+
+impl L1FeeParams {
+    /// Returns the L1 data fee of `tx` under the Fjord formula.
+    pub fn l1_fee(&self, tx: &Eip1559Transaction) -> Wei {
+        self.l1_fee_for_bytes(&tx.encode_unsigned())
+    }
+
+    /// Returns the L1 data fee of an unsigned serialized transaction under the Fjord
+    /// formula, as `getL1Fee` computes it.
+    ///
+    /// The size estimate is `max(100, -42.5856 + 0.8365 * (fastlz_len + 68))` bytes,
+    /// priced at `16 * base_fee_scalar * l1_base_fee + blob_base_fee_scalar * blob_base_fee`
+    /// per byte.
+    pub fn l1_fee_for_bytes(&self, unsigned_tx: &[u8]) -> Wei {
+        let fastlz_len = fastlz_compressed_len(unsigned_tx) + SIGNATURE_OVERHEAD;
+        let scaled = (FASTLZ_COEF * fastlz_len).saturating_add_signed(COST_INTERCEPT);
+        let estimated_size = scaled.max(MIN_TRANSACTION_SIZE * SCALAR_DECIMALS);
+
+        let fee = U256::from(estimated_size).saturating_mul(self.fee_per_byte_scaled());
+        Wei::from_u256(fee / U256::from(SCALAR_DECIMALS * SCALAR_DECIMALS))
+    }
+
+    /// Returns the L1 data fee of `tx` under the pre-Fjord Ecotone formula.
+    pub fn ecotone_l1_fee(&self, tx: &Eip1559Transaction) -> Wei {
+        self.ecotone_l1_fee_for_bytes(&tx.encode_unsigned())
+    }
+
+    /// Returns the L1 data fee of an unsigned serialized transaction under the pre-Fjord
+    /// Ecotone formula: its calldata gas, plus 68 non-zero signature bytes, priced at
+    /// `base_fee_scalar * l1_base_fee + blob_base_fee_scalar * blob_base_fee / 16`.
+    pub fn ecotone_l1_fee_for_bytes(&self, unsigned_tx: &[u8]) -> Wei {
+        let gas = calldata_gas(unsigned_tx) + SIGNATURE_OVERHEAD * 16;
+        let fee = U256::from(gas).saturating_mul(self.fee_per_byte_scaled());
+        Wei::from_u256(fee / U256::from(16 * SCALAR_DECIMALS))
+    }
+
+    /// Returns the full cost preview of `tx`: the L2 execution fee at its gas limit and
+    /// `max_fee_per_gas`, and the Fjord L1 data fee.
+    pub fn estimate(&self, tx: &Eip1559Transaction) -> L2FeeEstimate {
+        L2FeeEstimate {
+            execution_fee: tx.max_fee_per_gas.saturating_mul(tx.gas_limit),
+            l1_data_fee: self.l1_fee(tx),
+        }
+    }
+
+    /// `16 * base_fee_scalar * l1_base_fee + blob_base_fee_scalar * blob_base_fee`.
+    fn fee_per_byte_scaled(&self) -> U256 {
+        let calldata = self
+            .l1_base_fee
+            .as_u256()
+            .saturating_mul(U256::from(self.base_fee_scalar))
+            .saturating_mul(U256::from(16));
+        let blob = self
+            .blob_base_fee
+            .as_u256()
+            .saturating_mul(U256::from(self.blob_base_fee_scalar));
+        calldata.saturating_add(blob)
+    }
+}
+
+/// The cost of a transaction on an OP Stack chain, split into its two components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L2FeeEstimate {
+    /// The L2 execution fee (gas × gas price).
+    pub execution_fee: Wei,
+    /// The L1 data fee charged for posting the transaction to Ethereum.
+    pub l1_data_fee: Wei,
+}
+
+impl L2FeeEstimate {
+    /// Returns the total cost, saturating at [`U256::MAX`].
+    pub fn total(&self) -> Wei {
+        self.execution_fee.saturating_add(self.l1_data_fee)
+    }
+}
+
+#[cfg(feature = "net")]
+pub use net::{estimate_fee, get_l1_fee, get_l1_fee_params};
+
+#[cfg(feature = "net")]
+mod net {
+    use super::{
+        encode_get_l1_fee, L1FeeParams, L2FeeEstimate, BASE_FEE_SCALAR_SELECTOR,
+        BLOB_BASE_FEE_SCALAR_SELECTOR, BLOB_BASE_FEE_SELECTOR, GAS_PRICE_ORACLE,
+        L1_BASE_FEE_SELECTOR,
+    };
+    use crate::rpc::{BlockId, RpcClient};
+    use crate::{Eip1559Transaction, Error, Result, Wei};
+
+    /// Asks the [`GAS_PRICE_ORACLE`] for the L1 data fee of `tx` (`getL1Fee`).
+    ///
+    /// This is exact for the current block whichever fee formula the chain runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the chain has no oracle.
+    pub async fn get_l1_fee(client: &RpcClient, tx: &Eip1559Transaction) -> Result<Wei> {
+        let calldata = encode_get_l1_fee(&tx.encode_unsigned());
+        let data = client
+            .call(GAS_PRICE_ORACLE, &calldata, BlockId::Latest)
+            .await?;
+        decode_uint(&data)
+    }
+
+    /// Reads the current [`L1FeeParams`] from the [`GAS_PRICE_ORACLE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails, the chain has no oracle or a scalar does not
+    /// fit in `u32`.
+    pub async fn get_l1_fee_params(client: &RpcClient) -> Result<L1FeeParams> {
+        Ok(L1FeeParams {
+            l1_base_fee: read(client, L1_BASE_FEE_SELECTOR).await?,
+            blob_base_fee: read(client, BLOB_BASE_FEE_SELECTOR).await?,
+            base_fee_scalar: read_scalar(client, BASE_FEE_SCALAR_SELECTOR).await?,
+            blob_base_fee_scalar: read_scalar(client, BLOB_BASE_FEE_SCALAR_SELECTOR).await?,
+        })
+    }
+
+    /// Returns the full cost preview of `tx`: the L2 execution fee at its gas limit and
+    /// `max_fee_per_gas`, and the L1 data fee from the oracle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the oracle call fails.
+    pub async fn estimate_fee(
+        client: &RpcClient,
+        tx: &Eip1559Transaction,
+    ) -> Result<L2FeeEstimate> {
+        Ok(L2FeeEstimate {
+            execution_fee: tx.max_fee_per_gas.saturating_mul(tx.gas_limit),
+            l1_data_fee: get_l1_fee(client, tx).await?,
+        })
+    }
+
+    async fn read(client: &RpcClient, selector: [u8; 4]) -> Result<Wei> {
+        let data = client
+            .call(GAS_PRICE_ORACLE, &selector, BlockId::Latest)
+            .await?;
+        decode_uint(&data)
+    }
+
+    async fn read_scalar(client: &RpcClient, selector: [u8; 4]) -> Result<u32> {
+        read(client, selector)
+            .await?
+            .as_u64()
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or_else(|| Error::RpcError("gas price oracle scalar out of range".to_string()))
+    }
+
+    fn decode_uint(data: &[u8]) -> Result<Wei> {
+        if data.len() != 32 {
+            return Err(Error::RpcError(format!(
+                "expected a uint256 from the gas price oracle, got {} bytes",
+                data.len()
+            )));
+        }
+        Ok(Wei::from_be_bytes(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{self, ParamType};
+
+    fn params() -> L1FeeParams {
+        L1FeeParams {
+            l1_base_fee: Wei::from_gwei(10),
+            blob_base_fee: Wei::from_wei(1u64),
+            base_fee_scalar: 1_368,
+            blob_base_fee_scalar: 810_949,
+        }
+    }
+
+    fn transfer() -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(ChainId::BaseMainnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_wei(1_000_000u64))
+            .max_fee_per_gas(Wei::from_wei(10_000_000u64))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x11; 20]))
+            .value(Wei::from_gwei(1_000))
+            .build()
+            .unwrap()
+    }
+
+    // ─── oracle calls ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(GET_L1_FEE_SELECTOR, abi::selector("getL1Fee(bytes)"));
+        assert_eq!(L1_BASE_FEE_SELECTOR, abi::selector("l1BaseFee()"));
+        assert_eq!(BLOB_BASE_FEE_SELECTOR, abi::selector("blobBaseFee()"));
+        assert_eq!(BASE_FEE_SCALAR_SELECTOR, abi::selector("baseFeeScalar()"));
+        assert_eq!(
+            BLOB_BASE_FEE_SCALAR_SELECTOR,
+            abi::selector("blobBaseFeeScalar()")
+        );
+    }
+
+    #[test]
+    fn test_gas_price_oracle_address() {
+        assert_eq!(
+            GAS_PRICE_ORACLE,
+            "0x420000000000000000000000000000000000000F"
+                .parse::<Address>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_encode_get_l1_fee() {
+        let unsigned = transfer().encode_unsigned();
+        let calldata = encode_get_l1_fee(&unsigned);
+
+        assert_eq!(calldata[..4], GET_L1_FEE_SELECTOR);
+        let tokens = abi::decode(&[ParamType::Bytes], &calldata[4..]).unwrap();
+        assert_eq!(tokens, vec![Token::Bytes(unsigned)]);
+    }
+
+    #[test]
+    fn test_is_op_stack() {
+        assert!(is_op_stack(ChainId::OptimismMainnet));
+        assert!(is_op_stack(ChainId::BaseMainnet));
+        assert!(is_op_stack(ChainId::Custom(84_532)));
+        assert!(!is_op_stack(ChainId::EthereumMainnet));
+        assert!(!is_op_stack(ChainId::ArbitrumMainnet));
+    }
+
+    // ─── sizes ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_calldata_gas() {
+        assert_eq!(calldata_gas(&[]), 0);
+        assert_eq!(calldata_gas(&[0, 0, 1, 0xff]), 4 + 4 + 16 + 16);
+    }
+
+    #[test]
+    fn test_fastlz_literals_only() {
+        assert_eq!(fastlz_compressed_len(&[]), 0);
+        assert_eq!(fastlz_compressed_len(&[7]), 2);
+        // 32 bytes form one full literal run with a 1-byte header.
+        let distinct: Vec<u8> = (0..32).collect();
+        assert_eq!(fastlz_compressed_len(&distinct), 33);
+        let distinct: Vec<u8> = (0..33).collect();
+        assert_eq!(fastlz_compressed_len(&distinct), 33 + 2);
+    }
+
+    #[test]
+    fn test_fastlz_compresses_repetition() {
+        let zeros = vec![0u8; 1_000];
+        let len = fastlz_compressed_len(&zeros);
+        assert!(len < 50, "1000 zero bytes compressed to {}", len);
+
+        let pattern: Vec<u8> = (0..1_000).map(|i| (i % 7) as u8).collect();
+        assert!(fastlz_compressed_len(&pattern) < 100);
+    }
+
+    #[test]
+    fn test_fastlz_incompressible_grows() {
+        // A pseudo-random sequence has no 3-byte repeats to exploit.
+        let mut state = 0x2545_f491u32;
+        let noise: Vec<u8> = (0..256)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        assert!(fastlz_compressed_len(&noise) >= 256);
+    }
+
+    // ─── fees ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_fjord_fee_uses_minimum_size() {
+        // A plain transfer is far below the 100-byte floor once compressed.
+        let params = params();
+        let per_byte = U256::from(10_000_000_000u64 * 1_368 * 16 + 810_949);
+        let expected = U256::from(100u64 * 1_000_000) * per_byte / U256::from(1_000_000_000_000u64);
+
+        assert_eq!(params.l1_fee(&transfer()), Wei::from_u256(expected));
+    }
+
+    #[test]
+    fn test_fjord_fee_grows_with_data() {
+        let params = params();
+        let noisy = Eip1559Transaction {
+            data: (0..2_000u32)
+                .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+                .collect(),
+            ..transfer()
+        };
+        assert!(params.l1_fee(&noisy) > params.l1_fee(&transfer()));
+    }
+
+    #[test]
+    fn test_ecotone_fee() {
+        let params = params();
+        let unsigned = transfer().encode_unsigned();
+        let gas = calldata_gas(&unsigned) + 68 * 16;
+        let per_byte = U256::from(10_000_000_000u64 * 1_368 * 16 + 810_949);
+        let expected = U256::from(gas) * per_byte / U256::from(16_000_000u64);
+
+        assert_eq!(params.ecotone_l1_fee(&transfer()), Wei::from_u256(expected));
+    }
+
+    #[test]
+    fn test_fee_is_zero_without_l1_prices() {
+        let params = L1FeeParams {
+            l1_base_fee: Wei::ZERO,
+            blob_base_fee: Wei::ZERO,
+            ..params()
+        };
+        assert_eq!(params.l1_fee(&transfer()), Wei::ZERO);
+        assert_eq!(params.ecotone_l1_fee(&transfer()), Wei::ZERO);
+    }
+
+    #[test]
+    fn test_estimate_total() {
+        let estimate = params().estimate(&transfer());
+        assert_eq!(estimate.execution_fee, Wei::from_gwei(210));
+        assert_eq!(estimate.l1_data_fee, params().l1_fee(&transfer()));
+        assert_eq!(
+            estimate.total(),
+            estimate.execution_fee + estimate.l1_data_fee
+        );
+    }
+
+    #[test]
+    fn test_fee_saturates() {
+        let params = L1FeeParams {
+            l1_base_fee: Wei::from_u256(U256::MAX),
+            blob_base_fee: Wei::from_u256(U256::MAX),
+            base_fee_scalar: u32::MAX,
+            blob_base_fee_scalar: u32::MAX,
+        };
+        // Must not panic on overflow.
+        let _ = params.l1_fee(&transfer());
+        let _ = params.ecotone_l1_fee(&transfer());
+    }
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::rpc::RpcClient;
+        use serde_json::{json, Value};
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn word(value: u64) -> Value {
+            json!(format!("0x{:064x}", value))
+        }
+
+        async fn mock_call(server: &MockServer, selector: [u8; 4], result: Value) {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({
+                    "method": "eth_call",
+                    "params": [{ "data": format!("0x{}", hex::encode(selector)) }],
+                })))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+                )
+                .mount(server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn test_get_l1_fee_params() {
+            let server = MockServer::start().await;
+            mock_call(&server, L1_BASE_FEE_SELECTOR, word(10_000_000_000)).await;
+            mock_call(&server, BLOB_BASE_FEE_SELECTOR, word(1)).await;
+            mock_call(&server, BASE_FEE_SCALAR_SELECTOR, word(1_368)).await;
+            mock_call(&server, BLOB_BASE_FEE_SCALAR_SELECTOR, word(810_949)).await;
+
+            let client = RpcClient::new(server.uri());
+            assert_eq!(get_l1_fee_params(&client).await.unwrap(), params());
+        }
+
+        #[tokio::test]
+        async fn test_get_l1_fee_params_scalar_out_of_range() {
+            let server = MockServer::start().await;
+            mock_call(&server, L1_BASE_FEE_SELECTOR, word(1)).await;
+            mock_call(&server, BLOB_BASE_FEE_SELECTOR, word(1)).await;
+            mock_call(&server, BASE_FEE_SCALAR_SELECTOR, word(1 << 40)).await;
+
+            let client = RpcClient::new(server.uri());
+            assert!(get_l1_fee_params(&client).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_estimate_fee() {
+            let tx = transfer();
+            let calldata = encode_get_l1_fee(&tx.encode_unsigned());
+
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({
+                    "method": "eth_call",
+                    "params": [{ "data": format!("0x{}", hex::encode(&calldata)) }],
+                })))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(
+                        json!({ "jsonrpc": "2.0", "id": 1, "result": word(123_456) }),
+                    ),
+                )
+                .mount(&server)
+                .await;
+
+            let client = RpcClient::new(server.uri());
+            let estimate = estimate_fee(&client, &tx).await.unwrap();
+            assert_eq!(estimate.l1_data_fee, Wei::from_wei(123_456u64));
+            assert_eq!(estimate.execution_fee, Wei::from_gwei(210));
+        }
+    }
+}
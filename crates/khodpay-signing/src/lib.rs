@@ -1,6 +1,6 @@
 //! # Khodpay Signing
 //!
-//! EVM transaction signing library for BSC (BNB Smart Chain) and other EVM-compatible chains.
+//! EVM transaction signing library for BSC (BNB Smart Chain), Ethereum and other EVM-compatible chains.
 //!
 //! This crate provides EIP-1559 transaction signing, EIP-712 typed data signing, and
 //! ERC-4337 (Account Abstraction) `PackedUserOperation` support — all integrating with
@@ -11,17 +11,71 @@
 //! | Module | Standard | Description |
 //! |---|---|---|
 //! | *(root)* | EIP-1559 | Type-2 transaction building and signing |
+//! | *(root)* | EIP-2930 | Type-1 access-list transactions with intrinsic gas accounting |
+//! | [`abi`] | Solidity ABI | Type parsing, encoding / decoding, selectors and event logs |
+//! | [`approval`] | ERC-20 / 721 | Unlimited-allowance and unknown-spender warnings before signing |
+//! | [`decoder`] | ERC-20 / 721 / 1155 | Human-readable calldata summaries for confirmation screens |
+//! | [`disperse`] | Disperse | Native coin and ERC-20 payouts to many recipients in one call |
+//! | [`eip1271`] | EIP-1271 | Contract-wallet `isValidSignature` encoding and RPC validation |
+//! | [`eip191`] | EIP-191 | `personal_sign` message hashing, signing and verification |
+//! | [`eip2771`] | ERC-2771 | Forwarder `ForwardRequest` signing and `execute` calldata for gasless calls |
+//! | [`eip681`] | EIP-681 | `ethereum:` payment request URIs for QR codes |
 //! | [`eip712`] | EIP-712 | Generic typed structured data signing |
-//! | [`erc4337`] | ERC-4337 v0.7 | `PackedUserOperation` build / hash / sign |
+//! | [`erc20`] | ERC-20 / BEP-20 | Token call encoding and return-data decoding |
+//! | [`erc721`] | ERC-721 | NFT `safeTransferFrom`, `transferFrom` and approvals |
+//! | [`erc1155`] | ERC-1155 | Multi-token single / batch transfers and approvals |
+//! | [`erc4337`] | ERC-4337 v0.7 | `UserOperation` / `PackedUserOperation` build, pack, hash / sign |
+//! | [`fee`] | EIP-1559 | Slow / normal / fast fee suggestions from `eth_feeHistory` |
+//! | `keystore` | V3 / EIP-2335 | Password-encrypted key files for MetaMask, geth and validator tooling (`keystore` feature) |
+//! | [`l2_fee`] | OP Stack | L1 data fee of Optimism / Base transactions via the `GasPriceOracle` |
+//! | [`multicall`] | Multicall3 | Batched read-only calls in one `eth_call` through `aggregate3` |
+//! | [`queue`] | EIP-2718 | Persistent nonce-ordered queue of signed transactions, flushed when online |
+//! | `rpc` | JSON-RPC | Async HTTP client for broadcasting, state queries and receipt polling (`net` feature); WebSocket subscriptions (`ws` feature) |
+//! | [`safe`] | Safe multisig | `SafeTx` hashing, owner signature packing and `execTransaction` calldata |
+//! | [`siwe`] | EIP-4361 | Sign-In with Ethereum message building, parsing, signing and verification |
+//! | [`stealth`] | EIP-5564 | Stealth meta-addresses and announcement scanning |
+//! | `tokens` | Token Lists | Per-chain ERC-20 registry from token lists and verified custom tokens (`tokens` feature) |
 //!
 //! ## Features
 //!
 //! - **EIP-1559 Transactions**: Modern fee market transactions for EOA wallets
+//! - **Batch Signing**: Consecutive-nonce, all-or-nothing signing for payout and airdrop jobs
+//! - **Fee Suggestions**: Percentile-based EIP-1559 fees from `eth_feeHistory`
+//! - **L2 Data Fees**: Include the L1 data fee in Optimism / Base cost previews
+//! - **EIP-2930 Transactions**: Access-list transactions for calls that touch warm storage
+//! - **Raw Transaction Decoding**: Parse signed type-1 / type-2 transactions and recover the sender
+//! - **Transaction Decoding**: Human-readable summaries ("transfer 12.5 USDT to 0x…") before signing
+//! - **Approval Warnings**: Flags unlimited allowances, operator grants and unknown spenders
+//! - **EIP-191 Messages**: `personal_sign` for wallet login flows and off-chain attestations
+//! - **Signing Policy**: Chain allow-list and value / gas / fee ceilings enforced by the signer
+//! - **Guarded Raw Digests**: `sign_prehashed` only behind an explicit `PrehashPolicy` opt-in
+//! - **Sign-In with Ethereum**: EIP-4361 login messages with domain, nonce and expiry checks
+//! - **EIP-1271 Contract Signatures**: Validate smart-wallet signatures through `isValidSignature`
+//! - **ERC-2771 Meta-Transactions**: Sign `ForwardRequest`s for relayers and trusted forwarders
+//! - **EIP-681 Payment URIs**: Parse and generate `ethereum:…` QR payment requests, native or ERC-20
 //! - **EIP-712 Typed Data**: Generic, protocol-agnostic structured data signing
+//! - **NFT Helpers**: ERC-721 and ERC-1155 transfer and approval encoding
 //! - **ERC-4337 Account Abstraction**: `PackedUserOperation` v0.7 for gasless smart wallets
+//! - **Safe Multisig**: Act as an owner of a Safe — sign `SafeTx` hashes and build `execTransaction`
+//! - **EIP-5564 Stealth Addresses**: Receive to one published address, land on unlinkable ones
+//! - **Encrypted Keystores**: Export and import V3 (scrypt / PBKDF2) and EIP-2335 key files
+//!   with the `keystore` feature
+//! - **JSON-RPC Client**: Broadcast and query over HTTP with the `net` feature
+//! - **Gas Estimation**: Builder fills in the gas limit from `eth_estimateGas` plus a safety margin
+//! - **Offline Queue**: Sign now, broadcast later; queued transactions persist and flush in nonce order
+//! - **Provider Pool**: Retries with backoff, health checks and failover across several endpoints
+//! - **WebSocket Subscriptions**: New heads, pending transactions and logs pushed by the node (`ws` feature)
+//! - **Wei Math**: Checked / saturating arithmetic and decimal parsing / formatting (`"1.5"` ↔ wei)
 //! - **BSC Support**: Native support for BNB Smart Chain (mainnet/testnet)
+//! - **Chain Registry**: Ethereum, Polygon, Arbitrum, Optimism, Base and Avalanche built in,
+//!   plus custom chains with name, native currency and explorer metadata
 //! - **BIP-44 Integration**: Seamless key derivation from HD wallets
-//! - **BEP-20 Helpers**: Token transfer encoding utilities
+//! - **ABI Encoding**: Build arbitrary contract calls and decode results and event logs
+//! - **BEP-20 Helpers**: `transfer`, `approve`, `transferFrom`, `allowance`, `balanceOf`,
+//!   `decimals`, `symbol` and `name` encoding with return-data decoding
+//! - **Multicall3 Batching**: Many contract reads in a single `eth_call`
+//! - **Token Registry**: Per-chain known tokens from token lists plus custom tokens whose
+//!   symbol and decimals are read from the chain, persisted with the `tokens` feature
 //!
 //! ## Quick Start — EIP-1559 (EOA Wallet)
 //!
@@ -100,30 +154,71 @@
 #![warn(rustdoc::broken_intra_doc_links)]
 #![deny(unsafe_code)]
 
+pub mod abi;
 mod access_list;
 mod address;
+pub mod approval;
+mod batch;
 mod chain_id;
+pub mod decoder;
+pub mod disperse;
+pub mod eip1271;
+pub mod eip191;
+pub mod eip2771;
+mod eip2930;
+pub mod eip681;
 pub mod eip712;
+pub mod erc1155;
+pub mod erc20;
 pub mod erc4337;
+pub mod erc721;
 mod error;
+pub mod fee;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+pub mod l2_fee;
+pub mod multicall;
+mod policy;
+mod prehash;
+pub mod queue;
+mod rlp_decode;
 mod rlp_encode;
+#[cfg(feature = "net")]
+pub mod rpc;
+pub mod safe;
 mod signature;
 mod signed_transaction;
 mod signer;
+pub mod siwe;
+pub mod stealth;
+#[cfg(feature = "tokens")]
+pub mod tokens;
 mod transaction;
 mod wei;
 
-pub use access_list::{AccessList, AccessListItem};
+pub use access_list::{
+    access_list_gas, AccessList, AccessListItem, ACCESS_LIST_ADDRESS_GAS,
+    ACCESS_LIST_STORAGE_KEY_GAS,
+};
 pub use address::Address;
-pub use chain_id::ChainId;
+pub use batch::TxRequest;
+pub use chain_id::{ChainId, ChainMetadata, ChainRegistry};
+pub use eip2930::{Eip2930Transaction, Eip2930TransactionBuilder, SignedEip2930Transaction};
 pub use error::Error;
+pub use khodpay_amount::CoinAmount;
+pub use policy::SigningPolicy;
+pub use prehash::PrehashPolicy;
+pub use primitive_types::U256;
+pub use rlp_decode::TypedTransaction;
 pub use signature::Signature;
 pub use signed_transaction::SignedTransaction;
 pub use signer::{recover_signer, Bip44Signer};
+#[cfg(feature = "net")]
+pub use transaction::DEFAULT_GAS_MARGIN_PERCENT;
 pub use transaction::{
     Eip1559Transaction, Eip1559TransactionBuilder, TOKEN_TRANSFER_GAS, TRANSFER_GAS,
 };
-pub use wei::{Wei, ETHER, GWEI};
+pub use wei::{format_units, parse_units, Wei, ETHER, GWEI};
 
 /// Result type alias for signing operations.
 pub type Result<T> = std::result::Result<T, Error>;
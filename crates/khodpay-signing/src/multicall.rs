@@ -0,0 +1,294 @@
+//! Batched read-only calls through Multicall3.
+//!
+//! [Multicall3](https://github.com/mds1/multicall) is deployed at [`MULTICALL3`] on
+//! Ethereum, BSC and most other EVM chains. Its `aggregate3` function runs a list of
+//! calls in one `eth_call`, so reading a token's symbol, name and decimals, or the
+//! balances of many tokens, takes one round trip instead of one per value.
+//!
+//! Each [`Call3`] says whether it may fail. A call that may not fail reverts the whole
+//! batch; one that may comes back as a [`CallResult`] with `success == false`. With the
+//! `net` feature, `aggregate3` sends a batch and decodes the results.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::multicall::{self, Call3, CallResult};
+//! use khodpay_signing::{abi, erc20, Address};
+//!
+//! let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let calls = [
+//!     Call3::new(usdt, erc20::encode_decimals()),
+//!     Call3::allow_failure(usdt, erc20::encode_symbol()),
+//! ];
+//! let calldata = multicall::encode_aggregate3(&calls);
+//! assert_eq!(calldata[..4], multicall::AGGREGATE3_SELECTOR);
+//!
+//! // `eth_call` of `calldata` to MULTICALL3 returns one result per call:
+//! let returned = abi::encode(&[abi::Token::Array(vec![
+//!     abi::Token::Tuple(vec![abi::Token::Bool(true), abi::Token::Bytes(vec![0; 32])]),
+//!     abi::Token::Tuple(vec![abi::Token::Bool(false), abi::Token::Bytes(vec![])]),
+//! ])]);
+//! let results = multicall::decode_aggregate3(&returned).unwrap();
+//! assert_eq!(results[0], CallResult { success: true, return_data: vec![0; 32] });
+//! assert!(!results[1].success);
+//! ```
+
+use crate::abi::{self, encode_with_selector, ParamType, Token};
+use crate::{Address, Error, Result};
+
+/// Address of the Multicall3 contract (`0xcA11bde05977b3631167028862bE2a173976CA11`),
+/// the same on every chain it is deployed to.
+pub const MULTICALL3: Address = Address::from_bytes([
+    0xca, 0x11, 0xbd, 0xe0, 0x59, 0x77, 0xb3, 0x63, 0x11, 0x67, 0x02, 0x88, 0x62, 0xbe, 0x2a, 0x17,
+    0x39, 0x76, 0xca, 0x11,
+]);
+
+/// Selector of `aggregate3((address,bool,bytes)[])`.
+pub const AGGREGATE3_SELECTOR: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+
+/// Selector of Multicall3's `getChainId()`.
+pub const GET_CHAIN_ID_SELECTOR: [u8; 4] = [0x34, 0x08, 0xe4, 0x70];
+
+/// One call in an `aggregate3` batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call3 {
+    /// Contract to call.
+    pub target: Address,
+    /// Whether the batch goes on if this call reverts.
+    pub allow_failure: bool,
+    /// Calldata.
+    pub call_data: Vec<u8>,
+}
+
+impl Call3 {
+    /// Creates a call whose failure reverts the whole batch.
+    pub fn new(target: Address, call_data: Vec<u8>) -> Self {
+        Self {
+            target,
+            allow_failure: false,
+            call_data,
+        }
+    }
+
+    /// Creates a call that may fail without reverting the batch.
+    pub fn allow_failure(target: Address, call_data: Vec<u8>) -> Self {
+        Self {
+            target,
+            allow_failure: true,
+            call_data,
+        }
+    }
+}
+
+/// The outcome of one [`Call3`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallResult {
+    /// Whether the call succeeded.
+    pub success: bool,
+    /// Return data, or the revert data of a failed call.
+    pub return_data: Vec<u8>,
+}
+
+/// Encodes `aggregate3(calls)` calldata for [`MULTICALL3`].
+pub fn encode_aggregate3(calls: &[Call3]) -> Vec<u8> {
+    let calls = calls
+        .iter()
+        .map(|call| {
+            Token::Tuple(vec![
+                Token::Address(call.target),
+                Token::Bool(call.allow_failure),
+                Token::Bytes(call.call_data.clone()),
+            ])
+        })
+        .collect();
+    encode_with_selector(AGGREGATE3_SELECTOR, &[Token::Array(calls)])
+}
+
+/// Decodes the `(bool,bytes)[]` returned by `aggregate3`.
+///
+/// # Errors
+///
+/// Returns an error if the data is malformed. Empty data, which is what `eth_call`
+/// returns when no contract is deployed at the address, is reported as such.
+pub fn decode_aggregate3(data: &[u8]) -> Result<Vec<CallResult>> {
+    if data.is_empty() {
+        return Err(Error::AbiError(
+            "empty aggregate3 result: is Multicall3 deployed on this chain?".to_string(),
+        ));
+    }
+    let kind = ParamType::Array(Box::new(ParamType::Tuple(vec![
+        ParamType::Bool,
+        ParamType::Bytes,
+    ])));
+    let mut tokens = abi::decode(&[kind], data)?;
+    let Some(Token::Array(results)) = tokens.pop() else {
+        return Err(Error::AbiError("malformed aggregate3 result".to_string()));
+    };
+
+    results
+        .into_iter()
+        .map(|result| match result {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(success), Token::Bytes(return_data)] => Ok(CallResult {
+                    success: *success,
+                    return_data: return_data.clone(),
+                }),
+                _ => Err(Error::AbiError("malformed aggregate3 result".to_string())),
+            },
+            _ => Err(Error::AbiError("malformed aggregate3 result".to_string())),
+        })
+        .collect()
+}
+
+#[cfg(feature = "net")]
+pub use net::aggregate3;
+
+#[cfg(feature = "net")]
+mod net {
+    use super::{decode_aggregate3, encode_aggregate3, Call3, CallResult, MULTICALL3};
+    use crate::rpc::{BlockId, RpcClient};
+    use crate::{Error, Result};
+
+    /// Runs `calls` in one `eth_call` to [`MULTICALL3`] and returns their results in
+    /// order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, a call that may not fail reverts, the
+    /// chain has no Multicall3 or the result count does not match.
+    pub async fn aggregate3(
+        client: &RpcClient,
+        calls: &[Call3],
+        block: BlockId,
+    ) -> Result<Vec<CallResult>> {
+        let data = client
+            .call(MULTICALL3, &encode_aggregate3(calls), block)
+            .await?;
+        let results = decode_aggregate3(&data)?;
+        if results.len() != calls.len() {
+            return Err(Error::RpcError(format!(
+                "aggregate3 returned {} results for {} calls",
+                results.len(),
+                calls.len()
+            )));
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(entries: &[(bool, &[u8])]) -> Vec<u8> {
+        abi::encode(&[Token::Array(
+            entries
+                .iter()
+                .map(|(success, data)| {
+                    Token::Tuple(vec![Token::Bool(*success), Token::Bytes(data.to_vec())])
+                })
+                .collect(),
+        )])
+    }
+
+    #[test]
+    fn test_selectors_and_address() {
+        assert_eq!(
+            AGGREGATE3_SELECTOR,
+            abi::selector("aggregate3((address,bool,bytes)[])")
+        );
+        assert_eq!(GET_CHAIN_ID_SELECTOR, abi::selector("getChainId()"));
+        assert_eq!(
+            MULTICALL3.to_checksum_string(),
+            "0xcA11bde05977b3631167028862bE2a173976CA11"
+        );
+    }
+
+    #[test]
+    fn test_encode_aggregate3() {
+        let target = Address::from_bytes([0x11; 20]);
+        let calls = [
+            Call3::new(target, vec![0x31, 0x3c, 0xe5, 0x67]),
+            Call3::allow_failure(target, vec![]),
+        ];
+        let function = abi::Function::parse("aggregate3((address,bool,bytes)[])").unwrap();
+        let decoded = function.decode_input(&encode_aggregate3(&calls)).unwrap();
+
+        assert_eq!(
+            decoded,
+            vec![Token::Array(vec![
+                Token::Tuple(vec![
+                    Token::Address(target),
+                    Token::Bool(false),
+                    Token::Bytes(vec![0x31, 0x3c, 0xe5, 0x67]),
+                ]),
+                Token::Tuple(vec![
+                    Token::Address(target),
+                    Token::Bool(true),
+                    Token::Bytes(vec![]),
+                ]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_decode_aggregate3() {
+        let decoded = decode_aggregate3(&results(&[(true, &[7; 32]), (false, &[])])).unwrap();
+        assert_eq!(
+            decoded,
+            vec![
+                CallResult {
+                    success: true,
+                    return_data: vec![7; 32],
+                },
+                CallResult {
+                    success: false,
+                    return_data: vec![],
+                },
+            ]
+        );
+        assert_eq!(decode_aggregate3(&results(&[])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_decode_aggregate3_rejects_empty_and_truncated() {
+        assert!(matches!(decode_aggregate3(&[]), Err(Error::AbiError(_))));
+        let data = results(&[(true, &[7; 32])]);
+        assert!(decode_aggregate3(&data[..data.len() - 32]).is_err());
+    }
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::rpc::{BlockId, RpcClient};
+        use serde_json::json;
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn test_aggregate3_checks_result_count() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({ "method": "eth_call" })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": format!("0x{}", hex::encode(results(&[(true, &[1; 32])]))),
+                })))
+                .mount(&server)
+                .await;
+
+            let client = RpcClient::new(server.uri());
+            let target = Address::from_bytes([0x11; 20]);
+            let calls = [Call3::new(target, vec![1])];
+            let decoded = aggregate3(&client, &calls, BlockId::Latest).await.unwrap();
+            assert!(decoded[0].success);
+
+            let calls = [Call3::new(target, vec![1]), Call3::new(target, vec![2])];
+            assert!(matches!(
+                aggregate3(&client, &calls, BlockId::Latest).await,
+                Err(Error::RpcError(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,522 @@
+//! Signer-level limits on what may be signed.
+//!
+//! An embedder that hands a [`Bip44Signer`](crate::Bip44Signer) to other code — a payout worker, a plugin, an
+//! RPC handler — trusts that code with the key. A [`SigningPolicy`] narrows that trust:
+//! once attached with [`Bip44Signer::with_policy`](crate::Bip44Signer::with_policy), the signer refuses chains outside an
+//! allow-list and transactions above value, gas-limit or fee ceilings, and refuses raw
+//! digests unless the policy allows them, so a compromised caller cannot get arbitrary
+//! transactions signed.
+//!
+//! The ceilings see the native value, gas and fee fields of what is signed, not its
+//! calldata: a token transfer inside a transaction, Safe transaction or user operation
+//! is not counted against [`SigningPolicy::max_value`]. Use `khodpay-policy` to
+//! decode and limit token spends.
+
+use std::collections::BTreeSet;
+
+use crate::eip2771::ForwardRequest;
+use crate::eip712::Eip712Domain;
+use crate::erc4337::PackedUserOperation;
+use crate::safe::SafeTransaction;
+use crate::{ChainId, Eip1559Transaction, Eip2930Transaction, Error, Result, Wei};
+
+/// Chain allow-list and per-transaction ceilings enforced by a [`Bip44Signer`](crate::Bip44Signer).
+///
+/// Every limit is optional; [`SigningPolicy::new`] starts unrestricted.
+///
+/// - The chain allow-list applies to everything that carries a chain ID: transactions,
+///   batches, EIP-712 data, user operations, Safe transactions, forward requests and
+///   SIWE messages.
+/// - The ceilings apply to EIP-1559 and EIP-2930 transactions, including batches, and
+///   to the fields of user operations, Safe transactions and forward requests that
+///   have an equivalent (see [`check_user_operation`](Self::check_user_operation),
+///   [`check_safe_transaction`](Self::check_safe_transaction) and
+///   [`check_forward_request`](Self::check_forward_request)).
+/// - [`Bip44Signer::sign_prehashed`](crate::Bip44Signer::sign_prehashed) is refused
+///   unless [`allow_prehash`](Self::allow_prehash) is set: a raw digest may be the
+///   hash of any transaction, on any chain.
+///
+/// EIP-191 messages carry neither a chain ID nor a value and are not checked.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{Bip44Signer, ChainId, SigningPolicy, Wei};
+///
+/// let policy = SigningPolicy::new()
+///     .allow_chain(ChainId::BscMainnet)
+///     .allow_chain(ChainId::BscTestnet)
+///     .max_value(Wei::from_ether(1))
+///     .max_gas_limit(200_000);
+///
+/// let signer = Bip44Signer::from_private_key(&[1u8; 32])
+///     .unwrap()
+///     .with_policy(policy);
+///
+/// assert!(signer.policy().unwrap().allows_chain(ChainId::BscMainnet));
+/// assert!(!signer.policy().unwrap().allows_chain(ChainId::EthereumMainnet));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SigningPolicy {
+    allowed_chains: Option<BTreeSet<u64>>,
+    max_value: Option<Wei>,
+    max_gas_limit: Option<u64>,
+    max_fee_per_gas: Option<Wei>,
+    allow_prehash: bool,
+}
+
+impl SigningPolicy {
+    /// Creates a policy without any restriction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `chain_id` to the allow-list.
+    ///
+    /// Once any chain is allowed, all others are refused. Chains are compared by numeric
+    /// value, so `Custom(56)` matches `BscMainnet`.
+    pub fn allow_chain(mut self, chain_id: ChainId) -> Self {
+        self.allowed_chains
+            .get_or_insert_with(BTreeSet::new)
+            .insert(chain_id.value());
+        self
+    }
+
+    /// Adds every chain in `chain_ids` to the allow-list.
+    pub fn allow_chains(self, chain_ids: impl IntoIterator<Item = ChainId>) -> Self {
+        chain_ids.into_iter().fold(self, Self::allow_chain)
+    }
+
+    /// Sets the highest value a single transaction may transfer.
+    pub fn max_value(mut self, value: Wei) -> Self {
+        self.max_value = Some(value);
+        self
+    }
+
+    /// Sets the highest gas limit a single transaction may carry.
+    pub fn max_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.max_gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Sets the highest `max_fee_per_gas` (EIP-1559) or `gas_price` (EIP-2930) a single
+    /// transaction may offer.
+    pub fn max_fee_per_gas(mut self, fee: Wei) -> Self {
+        self.max_fee_per_gas = Some(fee);
+        self
+    }
+
+    /// Allows [`Bip44Signer::sign_prehashed`](crate::Bip44Signer::sign_prehashed).
+    ///
+    /// A raw digest bypasses the chain allow-list and every ceiling, so only set this
+    /// when the code holding the signer must sign digests it computes itself.
+    pub fn allow_prehash(mut self) -> Self {
+        self.allow_prehash = true;
+        self
+    }
+
+    /// Returns `true` if signing raw digests is allowed.
+    pub fn allows_prehash(&self) -> bool {
+        self.allow_prehash
+    }
+
+    /// Returns `true` if signing for `chain_id` is allowed.
+    pub fn allows_chain(&self, chain_id: ChainId) -> bool {
+        self.allows_chain_id(chain_id.value())
+    }
+
+    /// Checks an EIP-1559 transaction against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] naming the first limit the transaction exceeds.
+    pub fn check_transaction(&self, tx: &Eip1559Transaction) -> Result<()> {
+        self.check_chain_id(tx.chain_id.value())?;
+        self.check_limits(tx.value, tx.gas_limit, tx.max_fee_per_gas)
+    }
+
+    /// Checks an EIP-2930 transaction against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] naming the first limit the transaction exceeds.
+    pub fn check_eip2930_transaction(&self, tx: &Eip2930Transaction) -> Result<()> {
+        self.check_chain_id(tx.chain_id.value())?;
+        self.check_limits(tx.value, tx.gas_limit, tx.gas_price)
+    }
+
+    /// Checks an ERC-4337 user operation against the policy.
+    ///
+    /// The gas ceiling applies to the sum of the verification, call and pre-verification
+    /// gas, and the fee ceiling to `maxFeePerGas`. The value ceiling does not apply: the
+    /// value an account moves is encoded in its calldata.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] naming the first limit the operation exceeds.
+    pub fn check_user_operation(&self, op: &PackedUserOperation, chain_id: u64) -> Result<()> {
+        self.check_chain_id(chain_id)?;
+        let gas = op
+            .verification_gas_limit()
+            .saturating_add(op.call_gas_limit())
+            .saturating_add(op.pre_verification_gas);
+        self.check_limits(
+            Wei::ZERO,
+            u64::try_from(gas).unwrap_or(u64::MAX),
+            Wei::from(op.max_fee_per_gas()),
+        )
+    }
+
+    /// Checks a Safe transaction against the policy.
+    ///
+    /// The value ceiling applies to the inner call's value, the gas ceiling to
+    /// `safeTxGas + baseGas` and the fee ceiling to the refund `gasPrice`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] naming the first limit the transaction exceeds.
+    pub fn check_safe_transaction(&self, tx: &SafeTransaction, chain_id: u64) -> Result<()> {
+        self.check_chain_id(chain_id)?;
+        self.check_limits(
+            tx.value,
+            tx.safe_tx_gas.saturating_add(tx.base_gas),
+            tx.gas_price,
+        )
+    }
+
+    /// Checks an ERC-2771 forward request against the policy.
+    ///
+    /// The chain allow-list applies to the domain's chain ID, if it has one. The value
+    /// and gas ceilings apply; the relayer pays the fee.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] naming the first limit the request exceeds.
+    pub fn check_forward_request(
+        &self,
+        request: &ForwardRequest,
+        domain: &Eip712Domain,
+    ) -> Result<()> {
+        if let Some(chain_id) = domain.chain_id {
+            self.check_chain_id(chain_id)?;
+        }
+        self.check_limits(request.value, request.gas, Wei::ZERO)
+    }
+
+    pub(crate) fn check_prehash(&self) -> Result<()> {
+        if self.allow_prehash {
+            Ok(())
+        } else {
+            Err(Error::PolicyViolation(
+                "signing raw digests is not allowed".to_string(),
+            ))
+        }
+    }
+
+    fn allows_chain_id(&self, chain_id: u64) -> bool {
+        match &self.allowed_chains {
+            Some(allowed) => allowed.contains(&chain_id),
+            None => true,
+        }
+    }
+
+    pub(crate) fn check_chain_id(&self, chain_id: u64) -> Result<()> {
+        if self.allows_chain_id(chain_id) {
+            Ok(())
+        } else {
+            Err(Error::PolicyViolation(format!(
+                "chain {} is not allowed",
+                chain_id
+            )))
+        }
+    }
+
+    fn check_limits(&self, value: Wei, gas_limit: u64, fee_per_gas: Wei) -> Result<()> {
+        if let Some(max) = self.max_value.filter(|max| value > *max) {
+            return Err(Error::PolicyViolation(format!(
+                "value {} wei exceeds the ceiling of {} wei",
+                value, max
+            )));
+        }
+        if let Some(max) = self.max_gas_limit.filter(|max| gas_limit > *max) {
+            return Err(Error::PolicyViolation(format!(
+                "gas limit {} exceeds the ceiling of {}",
+                gas_limit, max
+            )));
+        }
+        if let Some(max) = self.max_fee_per_gas.filter(|max| fee_per_gas > *max) {
+            return Err(Error::PolicyViolation(format!(
+                "fee per gas {} wei exceeds the ceiling of {} wei",
+                fee_per_gas, max
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eip2771::{forwarder_domain, sign_forward_request};
+    use crate::eip712::{encode_uint64, sign_typed_data, Eip712Domain, Eip712Type};
+    use crate::erc4337::sign_user_operation;
+    use crate::safe::sign_safe_transaction;
+    use crate::{Address, Bip44Signer, PrehashPolicy, Signature};
+
+    struct Ping {
+        id: u64,
+    }
+
+    impl Eip712Type for Ping {
+        fn type_string() -> &'static str {
+            "Ping(uint64 id)"
+        }
+
+        fn encode_data(&self) -> Vec<u8> {
+            encode_uint64(self.id).to_vec()
+        }
+    }
+
+    fn transaction(chain_id: ChainId, value: Wei, gas_limit: u64, fee: Wei) -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(fee)
+            .gas_limit(gas_limit)
+            .to(crate::Address::from_bytes([0x11; 20]))
+            .value(value)
+            .build()
+            .unwrap()
+    }
+
+    fn policy() -> SigningPolicy {
+        SigningPolicy::new()
+            .allow_chains([ChainId::BscMainnet, ChainId::BscTestnet])
+            .max_value(Wei::from_ether(1))
+            .max_gas_limit(100_000)
+            .max_fee_per_gas(Wei::from_gwei(10))
+    }
+
+    fn signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_policy(policy())
+    }
+
+    fn sign(tx: &Eip1559Transaction) -> Result<Signature> {
+        signer().sign_transaction(tx)
+    }
+
+    // ─── chains ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_unrestricted_policy_allows_everything() {
+        let policy = SigningPolicy::new();
+        assert!(policy.allows_chain(ChainId::Custom(999)));
+        let tx = transaction(
+            ChainId::EthereumMainnet,
+            Wei::from_ether(1_000),
+            30_000_000,
+            Wei::from_gwei(1_000),
+        );
+        assert!(policy.check_transaction(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_chain_allow_list() {
+        let policy = policy();
+        assert!(policy.allows_chain(ChainId::BscMainnet));
+        assert!(policy.allows_chain(ChainId::Custom(97)));
+        assert!(!policy.allows_chain(ChainId::EthereumMainnet));
+
+        let err = sign(&transaction(
+            ChainId::EthereumMainnet,
+            Wei::ZERO,
+            21_000,
+            Wei::from_gwei(5),
+        ))
+        .unwrap_err();
+        assert!(matches!(err, Error::PolicyViolation(_)));
+        assert!(err.to_string().contains("chain 1"));
+    }
+
+    #[test]
+    fn test_chain_allow_list_applies_to_typed_data() {
+        let domain = Eip712Domain::new("Test", "1", 1, crate::Address::ZERO);
+        let err = sign_typed_data(&signer(), &domain, &Ping { id: 1 }).unwrap_err();
+        assert!(matches!(err, Error::PolicyViolation(_)));
+    }
+
+    // ─── ceilings ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_within_ceilings_signs() {
+        let tx = transaction(
+            ChainId::BscMainnet,
+            Wei::from_ether(1),
+            100_000,
+            Wei::from_gwei(10),
+        );
+        assert!(sign(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_value_ceiling() {
+        let tx = transaction(
+            ChainId::BscMainnet,
+            Wei::from_ether(1) + Wei::from_wei(1u64),
+            21_000,
+            Wei::from_gwei(5),
+        );
+        let err = sign(&tx).unwrap_err();
+        assert!(err.to_string().contains("value"));
+    }
+
+    #[test]
+    fn test_gas_limit_ceiling() {
+        let tx = transaction(ChainId::BscMainnet, Wei::ZERO, 100_001, Wei::from_gwei(5));
+        let err = sign(&tx).unwrap_err();
+        assert!(err.to_string().contains("gas limit"));
+    }
+
+    #[test]
+    fn test_fee_ceiling() {
+        let tx = transaction(ChainId::BscMainnet, Wei::ZERO, 21_000, Wei::from_gwei(11));
+        let err = sign(&tx).unwrap_err();
+        assert!(err.to_string().contains("fee per gas"));
+    }
+
+    #[test]
+    fn test_eip2930_ceilings() {
+        let signer = signer();
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(20))
+            .gas_limit(21_000)
+            .to(signer.address())
+            .build()
+            .unwrap();
+        assert!(matches!(
+            signer.sign_eip2930_transaction(&tx),
+            Err(Error::PolicyViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_policy_and_chain_binding_combine() {
+        let signer = signer().with_chain_id(ChainId::BscTestnet);
+        let tx = transaction(ChainId::BscMainnet, Wei::ZERO, 21_000, Wei::from_gwei(5));
+        assert!(matches!(
+            signer.sign_transaction(&tx),
+            Err(Error::ChainIdMismatch { .. })
+        ));
+        assert!(signer.sign_message(b"hello").is_ok());
+        assert_eq!(signer.policy(), Some(&policy()));
+    }
+
+    // ─── other payloads ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_prehash_refused_unless_allowed() {
+        let digest = transaction(
+            ChainId::EthereumMainnet,
+            Wei::from_ether(1_000),
+            21_000,
+            Wei::from_gwei(5),
+        )
+        .signing_hash();
+        let prehash = PrehashPolicy::allow_any("custom hash scheme").unwrap();
+
+        let err = signer().sign_prehashed(&digest, &prehash).unwrap_err();
+        assert!(matches!(err, Error::PolicyViolation(_)));
+        assert!(!policy().allows_prehash());
+
+        let allowed = Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_policy(policy().allow_prehash());
+        assert!(allowed.policy().unwrap().allows_prehash());
+        assert!(allowed.sign_prehashed(&digest, &prehash).is_ok());
+    }
+
+    #[test]
+    fn test_user_operation_ceilings() {
+        let op = |call_gas: u128, max_fee: u128| {
+            PackedUserOperation::builder()
+                .sender(Address::from_bytes([0x22; 20]))
+                .nonce(0)
+                .account_gas_limits(50_000, call_gas)
+                .pre_verification_gas(20_000)
+                .gas_fees(1_000_000_000, max_fee)
+                .build()
+                .unwrap()
+        };
+        let entry_point = Address::from_bytes([0x33; 20]);
+        let gwei = 1_000_000_000;
+
+        assert!(sign_user_operation(&signer(), &op(30_000, 10 * gwei), entry_point, 56).is_ok());
+        for (op, chain_id, limit) in [
+            (op(30_000, 10 * gwei), 1, "chain 1"),
+            (op(30_001, 10 * gwei), 56, "gas limit"),
+            (op(30_000, 11 * gwei), 56, "fee per gas"),
+        ] {
+            let err = sign_user_operation(&signer(), &op, entry_point, chain_id).unwrap_err();
+            assert!(matches!(err, Error::PolicyViolation(_)));
+            assert!(err.to_string().contains(limit), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_safe_transaction_ceilings() {
+        let safe = Address::from_bytes([0x44; 20]);
+        let tx = |value: Wei, safe_tx_gas: u64| {
+            SafeTransaction::builder()
+                .to(Address::from_bytes([0x11; 20]))
+                .value(value)
+                .safe_tx_gas(safe_tx_gas)
+                .nonce(0)
+                .build()
+                .unwrap()
+        };
+
+        assert!(sign_safe_transaction(&signer(), &tx(Wei::from_ether(1), 0), safe, 56).is_ok());
+        for (tx, limit) in [
+            (tx(Wei::from_ether(2), 0), "value"),
+            (tx(Wei::ZERO, 100_001), "gas limit"),
+        ] {
+            let err = sign_safe_transaction(&signer(), &tx, safe, 56).unwrap_err();
+            assert!(matches!(err, Error::PolicyViolation(_)));
+            assert!(err.to_string().contains(limit), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_forward_request_ceilings() {
+        let signer = signer();
+        let request = |value: Wei, gas: u64| {
+            ForwardRequest::builder()
+                .from(signer.address())
+                .to(Address::from_bytes([0x11; 20]))
+                .value(value)
+                .gas(gas)
+                .nonce(0)
+                .deadline(1_900_000_000)
+                .build()
+                .unwrap()
+        };
+        let domain = forwarder_domain("ERC2771Forwarder", 56, Address::from_bytes([0x55; 20]));
+
+        assert!(
+            sign_forward_request(&signer, &request(Wei::from_ether(1), 100_000), &domain).is_ok()
+        );
+        for (request, limit) in [
+            (request(Wei::from_ether(2), 50_000), "value"),
+            (request(Wei::ZERO, 100_001), "gas limit"),
+        ] {
+            let err = sign_forward_request(&signer, &request, &domain).unwrap_err();
+            assert!(matches!(err, Error::PolicyViolation(_)));
+            assert!(err.to_string().contains(limit), "{}", err);
+        }
+    }
+}
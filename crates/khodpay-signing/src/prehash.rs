@@ -0,0 +1,188 @@
+//! Guarded signing of raw 32-byte digests.
+//!
+//! Every other signing method hashes a structure the wallet can display — a transaction,
+//! an EIP-191 message, EIP-712 typed data. A raw digest shows nothing: the same 32 bytes
+//! could be a token permit or a transaction draining the account, which is why "blind
+//! signing" is a classic phishing vector.
+//!
+//! Some protocols still need it (custom hash schemes, threshold or bridge attestations),
+//! so [`Bip44Signer::sign_prehashed`] exists, but only behind a [`PrehashPolicy`] the
+//! caller constructs on purpose. There is no default policy.
+
+use std::collections::HashSet;
+
+use crate::{Bip44Signer, Error, Result, Signature};
+
+/// Explicit opt-in to signing raw digests with [`Bip44Signer::sign_prehashed`].
+///
+/// A policy names the protocol it is for and either allows any digest or only a fixed
+/// set of digests the caller computed itself.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{Bip44Signer, PrehashPolicy};
+///
+/// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+/// let digest = [7u8; 32];
+///
+/// let policy = PrehashPolicy::allow_digests("bridge attestation", [digest]).unwrap();
+/// assert!(signer.sign_prehashed(&digest, &policy).is_ok());
+/// assert!(signer.sign_prehashed(&[8u8; 32], &policy).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrehashPolicy {
+    purpose: String,
+    allowed: Option<HashSet<[u8; 32]>>,
+}
+
+impl PrehashPolicy {
+    /// Allows signing any digest for `purpose`.
+    ///
+    /// Only use this when the digest is computed by code you control; never for digests
+    /// received from a dapp or another untrusted party.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PrehashRejected`] if `purpose` is blank.
+    pub fn allow_any(purpose: impl Into<String>) -> Result<Self> {
+        Self::new(purpose.into(), None)
+    }
+
+    /// Allows signing only the given digests for `purpose`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PrehashRejected`] if `purpose` is blank or `digests` is empty.
+    pub fn allow_digests(
+        purpose: impl Into<String>,
+        digests: impl IntoIterator<Item = [u8; 32]>,
+    ) -> Result<Self> {
+        let allowed: HashSet<[u8; 32]> = digests.into_iter().collect();
+        if allowed.is_empty() {
+            return Err(Error::PrehashRejected(
+                "digest allow-list is empty".to_string(),
+            ));
+        }
+        Self::new(purpose.into(), Some(allowed))
+    }
+
+    fn new(purpose: String, allowed: Option<HashSet<[u8; 32]>>) -> Result<Self> {
+        if purpose.trim().is_empty() {
+            return Err(Error::PrehashRejected(
+                "policy purpose must not be empty".to_string(),
+            ));
+        }
+        Ok(Self { purpose, allowed })
+    }
+
+    /// Returns the protocol this policy was created for.
+    pub fn purpose(&self) -> &str {
+        &self.purpose
+    }
+
+    /// Returns `true` if the policy allows signing `digest`.
+    pub fn permits(&self, digest: &[u8; 32]) -> bool {
+        match &self.allowed {
+            Some(allowed) => allowed.contains(digest),
+            None => true,
+        }
+    }
+}
+
+impl Bip44Signer {
+    /// Signs a raw 32-byte digest under an explicit [`PrehashPolicy`].
+    ///
+    /// The digest is signed as-is, without any domain separation, so the caller is
+    /// responsible for what it commits to. Prefer [`sign_message`](Self::sign_message)
+    /// or [`sign_typed_data`](crate::eip712::sign_typed_data) whenever the protocol
+    /// allows it. Like those, this carries no chain ID and ignores the chain binding, but
+    /// a signer with a [`SigningPolicy`](crate::SigningPolicy) refuses it unless the policy
+    /// allows raw digests: a digest can be the hash of a transaction the policy forbids.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyViolation`] if the signer has a
+    /// [`SigningPolicy`](crate::SigningPolicy) without
+    /// [`allow_prehash`](crate::SigningPolicy::allow_prehash),
+    /// [`Error::PrehashRejected`] if the policy does not permit `digest`, or an error if
+    /// signing fails.
+    pub fn sign_prehashed(&self, digest: &[u8; 32], policy: &PrehashPolicy) -> Result<Signature> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!("sign_prehashed");
+
+        if let Some(signing_policy) = self.policy() {
+            signing_policy.check_prehash()?;
+        }
+        if !policy.permits(digest) {
+            return Err(Error::PrehashRejected(format!(
+                "digest 0x{} is not allowed for {}",
+                hex::encode(digest),
+                policy.purpose
+            )));
+        }
+        self.sign_hash(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{recover_signer, ChainId};
+
+    fn signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_allow_any_signs_and_recovers() {
+        let signer = signer();
+        let digest = [9u8; 32];
+        let policy = PrehashPolicy::allow_any("custom hash scheme").unwrap();
+
+        let signature = signer.sign_prehashed(&digest, &policy).unwrap();
+        assert_eq!(
+            recover_signer(&digest, &signature).unwrap(),
+            signer.address()
+        );
+        assert_eq!(signature, signer.sign_hash(&digest).unwrap());
+    }
+
+    #[test]
+    fn test_allow_digests_rejects_others() {
+        let policy = PrehashPolicy::allow_digests("attestation", [[1u8; 32], [2u8; 32]]).unwrap();
+
+        assert!(policy.permits(&[1u8; 32]));
+        assert!(policy.permits(&[2u8; 32]));
+        assert!(!policy.permits(&[3u8; 32]));
+
+        let err = signer().sign_prehashed(&[3u8; 32], &policy).unwrap_err();
+        assert!(matches!(err, Error::PrehashRejected(_)));
+        assert!(err.to_string().contains("attestation"));
+    }
+
+    #[test]
+    fn test_policy_requires_purpose() {
+        assert!(matches!(
+            PrehashPolicy::allow_any("  "),
+            Err(Error::PrehashRejected(_))
+        ));
+        assert!(PrehashPolicy::allow_digests("", [[1u8; 32]]).is_err());
+    }
+
+    #[test]
+    fn test_policy_rejects_empty_allow_list() {
+        assert!(matches!(
+            PrehashPolicy::allow_digests("attestation", []),
+            Err(Error::PrehashRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_bound_signer_signs_prehashed() {
+        let signer = signer().with_chain_id(ChainId::BscMainnet);
+        let policy = PrehashPolicy::allow_any("custom hash scheme").unwrap();
+        assert_eq!(policy.purpose(), "custom hash scheme");
+        assert!(signer.sign_prehashed(&[1u8; 32], &policy).is_ok());
+    }
+}
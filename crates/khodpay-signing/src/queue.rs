@@ -0,0 +1,704 @@
+//! Offline queue of signed, not yet broadcast transactions.
+//!
+//! A wallet with poor connectivity can sign now and broadcast later: signed transactions
+//! go into a [`TransactionQueue`], which persists them through a [`QueueStore`] so they
+//! survive restarts, keeps them in nonce order per sender, and — with the `net`
+//! feature — flushes them to a node once one is reachable.
+//!
+//! The store only ever sees raw signed transactions. Chain, sender and nonce are decoded
+//! and recovered again when the queue is opened, so a tampered store cannot misattribute
+//! a transaction.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::queue::{MemoryStore, TransactionQueue};
+//! use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, SignedTransaction, Wei};
+//!
+//! let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+//! let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+//!
+//! let tx = Eip1559Transaction::builder()
+//!     .chain_id(ChainId::BscMainnet)
+//!     .nonce(queue.next_nonce(ChainId::BscMainnet, signer.address()).unwrap_or(0))
+//!     .max_priority_fee_per_gas(Wei::from_gwei(1))
+//!     .max_fee_per_gas(Wei::from_gwei(5))
+//!     .gas_limit(21_000)
+//!     .to(signer.address())
+//!     .build()
+//!     .unwrap();
+//! let signature = signer.sign_transaction(&tx).unwrap();
+//! queue.push(&SignedTransaction::new(tx, signature)).unwrap();
+//!
+//! assert_eq!(queue.next_nonce(ChainId::BscMainnet, signer.address()), Some(1));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{Address, ChainId, Error, Result, SignedTransaction, TypedTransaction};
+
+/// A signed transaction waiting in a [`TransactionQueue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedTransaction {
+    raw: Vec<u8>,
+    hash: [u8; 32],
+    chain_id: u64,
+    sender: Address,
+    nonce: u64,
+}
+
+impl QueuedTransaction {
+    /// Decodes a raw signed EIP-1559 or EIP-2930 transaction and recovers its sender.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes do not decode or the signature does not recover.
+    pub fn from_raw(raw: &[u8]) -> Result<Self> {
+        let tx = TypedTransaction::from_raw(raw)?;
+        Ok(Self {
+            raw: raw.to_vec(),
+            hash: tx.tx_hash(),
+            chain_id: tx.chain_id().value(),
+            sender: tx.verify()?,
+            nonce: tx.nonce(),
+        })
+    }
+
+    /// Returns the raw signed transaction, ready for `eth_sendRawTransaction`.
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Returns the transaction hash.
+    pub fn hash(&self) -> [u8; 32] {
+        self.hash
+    }
+
+    /// Returns the numeric chain ID.
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Returns the recovered sender.
+    pub fn sender(&self) -> Address {
+        self.sender
+    }
+
+    /// Returns the nonce.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn key(&self) -> QueueKey {
+        (self.chain_id, self.sender.to_bytes(), self.nonce)
+    }
+}
+
+/// Persistence backend of a [`TransactionQueue`].
+///
+/// Stores the raw signed transactions as an opaque list. The queue rewrites the whole
+/// list after every change, so implementations should replace it atomically.
+pub trait QueueStore {
+    /// Returns the persisted raw transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StorageError`] if the store cannot be read.
+    fn load(&mut self) -> Result<Vec<Vec<u8>>>;
+
+    /// Replaces the persisted raw transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StorageError`] if the store cannot be written.
+    fn save(&mut self, transactions: &[Vec<u8>]) -> Result<()>;
+}
+
+/// In-memory [`QueueStore`], for tests and short-lived sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStore {
+    transactions: Vec<Vec<u8>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the raw transactions currently persisted.
+    pub fn transactions(&self) -> &[Vec<u8>] {
+        &self.transactions
+    }
+}
+
+impl QueueStore for MemoryStore {
+    fn load(&mut self) -> Result<Vec<Vec<u8>>> {
+        Ok(self.transactions.clone())
+    }
+
+    fn save(&mut self, transactions: &[Vec<u8>]) -> Result<()> {
+        self.transactions = transactions.to_vec();
+        Ok(())
+    }
+}
+
+/// [`QueueStore`] backed by a text file with one `0x`-prefixed raw transaction per line.
+///
+/// A missing file is an empty queue. Writes go to a sibling temporary file that is then
+/// renamed over the original, so a crash never leaves a half-written queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    /// Creates a store at `path`. Nothing is read or written until the queue is opened.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl QueueStore for FileStore {
+    fn load(&mut self) -> Result<Vec<Vec<u8>>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(storage_error(&self.path, e)),
+        };
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                let digits = line.strip_prefix("0x").unwrap_or(line);
+                hex::decode(digits).map_err(|e| {
+                    Error::StorageError(format!("{}: entry {}: {}", self.path.display(), i, e))
+                })
+            })
+            .collect()
+    }
+
+    fn save(&mut self, transactions: &[Vec<u8>]) -> Result<()> {
+        let contents: String = transactions
+            .iter()
+            .map(|raw| format!("0x{}\n", hex::encode(raw)))
+            .collect();
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, contents).map_err(|e| storage_error(&tmp, e))?;
+        fs::rename(&tmp, &self.path).map_err(|e| storage_error(&self.path, e))
+    }
+}
+
+fn storage_error(path: &Path, error: std::io::Error) -> Error {
+    Error::StorageError(format!("{}: {}", path.display(), error))
+}
+
+/// Chain ID, sender bytes and nonce: the queue's sort order.
+type QueueKey = (u64, [u8; 20], u64);
+
+/// Persistent, nonce-ordered queue of signed transactions.
+///
+/// Holds at most one transaction per chain, sender and nonce: pushing a transaction
+/// with the same nonce replaces the queued one, which is how a fee bump or cancellation
+/// signed offline takes effect. Every change is written through to the store.
+#[derive(Debug)]
+pub struct TransactionQueue<S: QueueStore> {
+    store: S,
+    entries: BTreeMap<QueueKey, QueuedTransaction>,
+}
+
+impl<S: QueueStore> TransactionQueue<S> {
+    /// Opens a queue, loading and verifying everything in `store`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read or holds a transaction that does
+    /// not decode.
+    pub fn open(mut store: S) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        for raw in store.load()? {
+            let entry = QueuedTransaction::from_raw(&raw)?;
+            entries.insert(entry.key(), entry);
+        }
+        Ok(Self { store, entries })
+    }
+
+    /// Queues a signed EIP-1559 transaction and returns its hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature does not recover or the store cannot be
+    /// written.
+    pub fn push(&mut self, tx: &SignedTransaction) -> Result<[u8; 32]> {
+        self.push_raw(&tx.encode())
+    }
+
+    /// Queues a raw signed EIP-1559 or EIP-2930 transaction and returns its hash.
+    ///
+    /// Replaces any queued transaction with the same chain, sender and nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes do not decode or the store cannot be written.
+    pub fn push_raw(&mut self, raw: &[u8]) -> Result<[u8; 32]> {
+        let entry = QueuedTransaction::from_raw(raw)?;
+        let hash = entry.hash;
+        let previous = self.entries.insert(entry.key(), entry.clone());
+        if let Err(e) = self.persist() {
+            match previous {
+                Some(previous) => self.entries.insert(entry.key(), previous),
+                None => self.entries.remove(&entry.key()),
+            };
+            return Err(e);
+        }
+        Ok(hash)
+    }
+
+    /// Removes the transaction with `hash` and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    pub fn remove(&mut self, hash: &[u8; 32]) -> Result<Option<QueuedTransaction>> {
+        let Some(key) = self.find(hash) else {
+            return Ok(None);
+        };
+        let removed = self.entries.remove(&key);
+        self.persist()?;
+        Ok(removed)
+    }
+
+    /// Returns the queued transaction with `hash`.
+    pub fn get(&self, hash: &[u8; 32]) -> Option<&QueuedTransaction> {
+        self.find(hash).and_then(|key| self.entries.get(&key))
+    }
+
+    /// Iterates over queued transactions ordered by chain, sender and nonce.
+    pub fn iter(&self) -> impl Iterator<Item = &QueuedTransaction> {
+        self.entries.values()
+    }
+
+    /// Returns the number of queued transactions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the nonce following the highest one queued for `sender` on `chain_id`.
+    ///
+    /// Use it to sign further transactions while offline; `None` means nothing is
+    /// queued and the nonce has to come from the node or local bookkeeping.
+    pub fn next_nonce(&self, chain_id: ChainId, sender: Address) -> Option<u64> {
+        let chain_id = chain_id.value();
+        let sender = sender.to_bytes();
+        self.entries
+            .range((chain_id, sender, 0)..=(chain_id, sender, u64::MAX))
+            .next_back()
+            .map(|(_, entry)| entry.nonce.saturating_add(1))
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    fn find(&self, hash: &[u8; 32]) -> Option<QueueKey> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.hash == *hash)
+            .map(|(key, _)| *key)
+    }
+
+    fn persist(&mut self) -> Result<()> {
+        let raw: Vec<Vec<u8>> = self.entries.values().map(|e| e.raw.clone()).collect();
+        self.store.save(&raw)
+    }
+}
+
+#[cfg(feature = "net")]
+pub use net::FlushReport;
+
+#[cfg(feature = "net")]
+mod net {
+    use super::{QueueKey, QueueStore, TransactionQueue};
+    use crate::rpc::{BlockId, RpcClient};
+    use crate::{Address, Error, Result};
+
+    /// Outcome of [`TransactionQueue::flush`].
+    #[derive(Debug, Default)]
+    pub struct FlushReport {
+        /// Hashes of transactions the node accepted; removed from the queue.
+        pub sent: Vec<[u8; 32]>,
+        /// Hashes of transactions whose nonce was already used on chain; removed from
+        /// the queue.
+        pub stale: Vec<[u8; 32]>,
+        /// Transactions the node rejected, with the error; kept in the queue. Later
+        /// nonces of the same sender are kept too and not attempted.
+        pub failed: Vec<([u8; 32], Error)>,
+    }
+
+    impl<S: QueueStore> TransactionQueue<S> {
+        /// Broadcasts queued transactions for the client's chain.
+        ///
+        /// Each sender's transactions are sent in nonce order. Transactions whose nonce
+        /// is below the sender's mined transaction count are dropped as stale, since
+        /// they or a replacement were already included. The first rejection stops that
+        /// sender, so no nonce gap is left behind. Transactions for other chains stay
+        /// queued.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the chain ID cannot be fetched or the store cannot be
+        /// written. Per-transaction failures are reported in [`FlushReport::failed`].
+        pub async fn flush(&mut self, client: &RpcClient) -> Result<FlushReport> {
+            let chain_id = client.chain_id().await?;
+            let mut senders: Vec<Address> = self
+                .entries
+                .keys()
+                .filter(|(chain, _, _)| *chain == chain_id)
+                .map(|(_, sender, _)| Address::from_bytes(*sender))
+                .collect();
+            senders.dedup();
+
+            let mut report = FlushReport::default();
+            for sender in senders {
+                self.flush_sender(client, chain_id, sender, &mut report)
+                    .await;
+            }
+
+            if !report.sent.is_empty() || !report.stale.is_empty() {
+                self.persist()?;
+            }
+            Ok(report)
+        }
+
+        async fn flush_sender(
+            &mut self,
+            client: &RpcClient,
+            chain_id: u64,
+            sender: Address,
+            report: &mut FlushReport,
+        ) {
+            let bytes = sender.to_bytes();
+            let keys: Vec<QueueKey> = self
+                .entries
+                .range((chain_id, bytes, 0)..=(chain_id, bytes, u64::MAX))
+                .map(|(key, _)| *key)
+                .collect();
+
+            let mined = match client.get_transaction_count(sender, BlockId::Latest).await {
+                Ok(count) => count,
+                Err(e) => {
+                    if let Some(entry) = keys.first().and_then(|key| self.entries.get(key)) {
+                        report.failed.push((entry.hash, e));
+                    }
+                    return;
+                }
+            };
+
+            for key in keys {
+                let entry = &self.entries[&key];
+                if entry.nonce < mined {
+                    report.stale.push(entry.hash);
+                    self.entries.remove(&key);
+                    continue;
+                }
+                match client.send_raw_transaction(&entry.raw).await {
+                    Ok(_) => {}
+                    Err(Error::JsonRpcError { message, .. })
+                        if message.to_ascii_lowercase().contains("already known") => {}
+                    Err(e) => {
+                        report.failed.push((entry.hash, e));
+                        return;
+                    }
+                }
+                report.sent.push(entry.hash);
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bip44Signer, Eip1559Transaction, Wei};
+
+    fn signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    fn signed(signer: &Bip44Signer, chain_id: ChainId, nonce: u64, fee: u64) -> SignedTransaction {
+        let tx = Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(nonce)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(fee))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x11; 20]))
+            .value(Wei::from_gwei(1))
+            .build()
+            .unwrap();
+        let signature = signer.sign_transaction(&tx).unwrap();
+        SignedTransaction::new(tx, signature)
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("khodpay-queue-{}-{}.txt", name, std::process::id()))
+    }
+
+    // ─── queue ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_push_orders_by_nonce() {
+        let signer = signer();
+        let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+        for nonce in [2, 0, 1] {
+            queue
+                .push(&signed(&signer, ChainId::BscMainnet, nonce, 5))
+                .unwrap();
+        }
+
+        let nonces: Vec<u64> = queue.iter().map(QueuedTransaction::nonce).collect();
+        assert_eq!(nonces, vec![0, 1, 2]);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.store().transactions().len(), 3);
+        assert_eq!(
+            queue.next_nonce(ChainId::BscMainnet, signer.address()),
+            Some(3)
+        );
+        assert_eq!(
+            queue.next_nonce(ChainId::BscTestnet, signer.address()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_entry_metadata() {
+        let signer = signer();
+        let tx = signed(&signer, ChainId::BscTestnet, 4, 5);
+        let entry = QueuedTransaction::from_raw(&tx.encode()).unwrap();
+
+        assert_eq!(entry.hash(), tx.tx_hash());
+        assert_eq!(entry.chain_id(), 97);
+        assert_eq!(entry.sender(), signer.address());
+        assert_eq!(entry.nonce(), 4);
+        assert_eq!(entry.raw(), tx.encode().as_slice());
+    }
+
+    #[test]
+    fn test_same_nonce_replaces() {
+        let signer = signer();
+        let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+        let original = queue
+            .push(&signed(&signer, ChainId::BscMainnet, 0, 5))
+            .unwrap();
+        let bumped = queue
+            .push(&signed(&signer, ChainId::BscMainnet, 0, 10))
+            .unwrap();
+
+        assert_ne!(original, bumped);
+        assert_eq!(queue.len(), 1);
+        assert!(queue.get(&original).is_none());
+        assert!(queue.get(&bumped).is_some());
+    }
+
+    #[test]
+    fn test_remove() {
+        let signer = signer();
+        let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+        let hash = queue
+            .push(&signed(&signer, ChainId::BscMainnet, 0, 5))
+            .unwrap();
+
+        assert_eq!(queue.remove(&hash).unwrap().unwrap().hash(), hash);
+        assert!(queue.remove(&hash).unwrap().is_none());
+        assert!(queue.is_empty());
+        assert!(queue.store().transactions().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+        assert!(queue.push_raw(&[0x02, 0xc0]).is_err());
+        assert!(queue.is_empty());
+    }
+
+    // ─── persistence ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_reopen_from_memory_store() {
+        let signer = signer();
+        let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+        queue
+            .push(&signed(&signer, ChainId::BscMainnet, 0, 5))
+            .unwrap();
+
+        let reopened = TransactionQueue::open(queue.store().clone()).unwrap();
+        assert_eq!(
+            reopened.iter().collect::<Vec<_>>(),
+            queue.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let path = temp_path("round-trip");
+        let _ = fs::remove_file(&path);
+        let signer = signer();
+
+        let mut queue = TransactionQueue::open(FileStore::new(&path)).unwrap();
+        assert!(queue.is_empty());
+        let first = queue
+            .push(&signed(&signer, ChainId::BscMainnet, 0, 5))
+            .unwrap();
+        queue
+            .push(&signed(&signer, ChainId::BscMainnet, 1, 5))
+            .unwrap();
+        queue.remove(&first).unwrap();
+
+        let reopened = TransactionQueue::open(FileStore::new(&path)).unwrap();
+        let nonces: Vec<u64> = reopened.iter().map(QueuedTransaction::nonce).collect();
+        assert_eq!(nonces, vec![1]);
+        assert_eq!(reopened.store().path(), path.as_path());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_rejects_corrupt_entries() {
+        let path = temp_path("corrupt");
+        fs::write(&path, "0xzz\n").unwrap();
+
+        let err = TransactionQueue::open(FileStore::new(&path)).unwrap_err();
+        assert!(matches!(err, Error::StorageError(_)));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    // ─── RPC ─────────────────────────────────────────────────────────────────
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::rpc::RpcClient;
+        use serde_json::{json, Value};
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn mock(server: &MockServer, matcher: Value, body: Value) {
+            Mock::given(method("POST"))
+                .and(body_partial_json(matcher))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(server)
+                .await;
+        }
+
+        async fn mock_result(server: &MockServer, rpc_method: &str, result: Value) {
+            mock(
+                server,
+                json!({ "method": rpc_method }),
+                json!({ "jsonrpc": "2.0", "id": 1, "result": result }),
+            )
+            .await;
+        }
+
+        async fn mock_send(server: &MockServer, tx: &SignedTransaction, body: Value) {
+            mock(
+                server,
+                json!({
+                    "method": "eth_sendRawTransaction",
+                    "params": [format!("0x{}", hex::encode(tx.encode()))],
+                }),
+                body,
+            )
+            .await;
+        }
+
+        fn accepted(tx: &SignedTransaction) -> Value {
+            json!({ "jsonrpc": "2.0", "id": 1, "result": tx.tx_hash_hex() })
+        }
+
+        fn rejected(message: &str) -> Value {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32000, "message": message },
+            })
+        }
+
+        #[tokio::test]
+        async fn test_flush_sends_in_order_and_drops_stale() {
+            let server = MockServer::start().await;
+            let signer = signer();
+            let stale = signed(&signer, ChainId::BscMainnet, 2, 5);
+            let next = signed(&signer, ChainId::BscMainnet, 3, 5);
+            let known = signed(&signer, ChainId::BscMainnet, 4, 5);
+            let other_chain = signed(&signer, ChainId::BscTestnet, 0, 5);
+
+            mock_result(&server, "eth_chainId", json!("0x38")).await;
+            mock_result(&server, "eth_getTransactionCount", json!("0x3")).await;
+            mock_send(&server, &next, accepted(&next)).await;
+            mock_send(&server, &known, rejected("already known")).await;
+
+            let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+            for tx in [&known, &stale, &other_chain, &next] {
+                queue.push(tx).unwrap();
+            }
+
+            let client = RpcClient::new(server.uri());
+            let report = queue.flush(&client).await.unwrap();
+
+            assert_eq!(report.sent, vec![next.tx_hash(), known.tx_hash()]);
+            assert_eq!(report.stale, vec![stale.tx_hash()]);
+            assert!(report.failed.is_empty());
+            assert_eq!(queue.len(), 1);
+            assert!(queue.get(&other_chain.tx_hash()).is_some());
+            assert_eq!(queue.store().transactions().len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_flush_stops_sender_at_first_rejection() {
+            let server = MockServer::start().await;
+            let signer = signer();
+            let first = signed(&signer, ChainId::BscMainnet, 0, 5);
+            let second = signed(&signer, ChainId::BscMainnet, 1, 5);
+
+            mock_result(&server, "eth_chainId", json!("0x38")).await;
+            mock_result(&server, "eth_getTransactionCount", json!("0x0")).await;
+            mock_send(&server, &first, rejected("insufficient funds for gas")).await;
+            mock_send(&server, &second, accepted(&second)).await;
+
+            let mut queue = TransactionQueue::open(MemoryStore::new()).unwrap();
+            queue.push(&first).unwrap();
+            queue.push(&second).unwrap();
+
+            let client = RpcClient::new(server.uri());
+            let report = queue.flush(&client).await.unwrap();
+
+            assert!(report.sent.is_empty());
+            assert_eq!(report.failed.len(), 1);
+            assert_eq!(report.failed[0].0, first.tx_hash());
+            assert!(report.failed[0]
+                .1
+                .to_string()
+                .contains("insufficient funds"));
+            assert_eq!(queue.len(), 2);
+        }
+    }
+}
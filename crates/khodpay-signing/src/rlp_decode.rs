@@ -0,0 +1,689 @@
+//! RLP decoding for typed transactions.
+//!
+//! This module parses EIP-2718 typed transaction envelopes back into their
+//! structured form, the inverse of [`rlp_encode`](crate::rlp_encode). Decoding is
+//! strict: non-canonical integers, trailing bytes and wrong field counts are rejected,
+//! so a decoded transaction always re-encodes to the exact input bytes.
+
+use crate::{
+    AccessList, AccessListItem, Address, ChainId, Eip1559Transaction, Eip2930Transaction, Error,
+    Result, Signature, SignedEip2930Transaction, SignedTransaction, Wei,
+};
+use primitive_types::U256;
+use rlp::{DecoderError, Rlp};
+
+impl Eip1559Transaction {
+    /// Decodes an unsigned EIP-1559 transaction.
+    ///
+    /// Accepts the output of [`encode_unsigned`](Eip1559Transaction::encode_unsigned):
+    /// `0x02 || rlp([chain_id, nonce, max_priority_fee_per_gas, max_fee_per_gas,
+    /// gas_limit, to, value, data, access_list])`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RlpDecodingError`] if the type byte, field count or any field
+    /// is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Eip1559Transaction, ChainId, Wei};
+    ///
+    /// let tx = Eip1559Transaction::builder()
+    ///     .chain_id(ChainId::BscMainnet)
+    ///     .nonce(3)
+    ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///     .max_fee_per_gas(Wei::from_gwei(5))
+    ///     .gas_limit(21000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let decoded = Eip1559Transaction::decode(&tx.encode_unsigned()).unwrap();
+    /// assert_eq!(decoded, tx);
+    /// ```
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let rlp = envelope(bytes, Self::TYPE)?;
+        expect_items(&rlp, 9)?;
+        decode_eip1559_fields(&rlp)
+    }
+}
+
+impl Eip2930Transaction {
+    /// Decodes an unsigned EIP-2930 transaction.
+    ///
+    /// Accepts the output of [`encode_unsigned`](Eip2930Transaction::encode_unsigned).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RlpDecodingError`] if the type byte, field count or any field
+    /// is invalid.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let rlp = envelope(bytes, Self::TYPE)?;
+        expect_items(&rlp, 8)?;
+        decode_eip2930_fields(&rlp)
+    }
+}
+
+impl SignedTransaction {
+    /// Decodes a raw signed EIP-1559 transaction.
+    ///
+    /// Accepts the bytes sent with `eth_sendRawTransaction`, i.e. the output of
+    /// [`encode`](SignedTransaction::encode). Combine with
+    /// [`verify`](SignedTransaction::verify) to recover the sender.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RlpDecodingError`] if the input is not a well-formed signed
+    /// EIP-1559 transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, SignedTransaction, Wei};
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+    /// let tx = Eip1559Transaction::builder()
+    ///     .chain_id(ChainId::BscMainnet)
+    ///     .nonce(0)
+    ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///     .max_fee_per_gas(Wei::from_gwei(5))
+    ///     .gas_limit(21000)
+    ///     .to(signer.address())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let raw = SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap()).encode();
+    /// let decoded = SignedTransaction::from_raw(&raw).unwrap();
+    ///
+    /// assert_eq!(decoded.transaction(), &tx);
+    /// assert_eq!(decoded.verify().unwrap(), signer.address());
+    /// ```
+    pub fn from_raw(bytes: &[u8]) -> Result<Self> {
+        let rlp = envelope(bytes, Eip1559Transaction::TYPE)?;
+        expect_items(&rlp, 12)?;
+        let transaction = decode_eip1559_fields(&rlp)?;
+        let signature = decode_signature(&rlp, 9)?;
+        Ok(Self::new(transaction, signature))
+    }
+}
+
+impl SignedEip2930Transaction {
+    /// Decodes a raw signed EIP-2930 transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RlpDecodingError`] if the input is not a well-formed signed
+    /// EIP-2930 transaction.
+    pub fn from_raw(bytes: &[u8]) -> Result<Self> {
+        let rlp = envelope(bytes, Eip2930Transaction::TYPE)?;
+        expect_items(&rlp, 11)?;
+        let transaction = decode_eip2930_fields(&rlp)?;
+        let signature = decode_signature(&rlp, 8)?;
+        Ok(Self::new(transaction, signature))
+    }
+}
+
+/// A decoded signed transaction of any supported type.
+///
+/// Use [`TypedTransaction::from_raw`] when the transaction type is not known up front,
+/// e.g. for raw transactions received from a dApp or read from the mempool.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::TypedTransaction;
+///
+/// let raw = hex::decode("01c0").unwrap();
+/// assert!(TypedTransaction::from_raw(&raw).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedTransaction {
+    /// An EIP-2930 (type `0x01`) transaction.
+    Eip2930(SignedEip2930Transaction),
+    /// An EIP-1559 (type `0x02`) transaction.
+    Eip1559(SignedTransaction),
+}
+
+impl TypedTransaction {
+    /// Decodes a raw signed transaction, dispatching on its type byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RlpDecodingError`] for unsupported types (including legacy
+    /// transactions) or malformed input.
+    pub fn from_raw(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&Eip2930Transaction::TYPE) => {
+                SignedEip2930Transaction::from_raw(bytes).map(Self::Eip2930)
+            }
+            Some(&Eip1559Transaction::TYPE) => {
+                SignedTransaction::from_raw(bytes).map(Self::Eip1559)
+            }
+            Some(&ty) if ty >= 0xc0 => Err(Error::RlpDecodingError(
+                "legacy transactions are not supported".to_string(),
+            )),
+            Some(&ty) => Err(Error::RlpDecodingError(format!(
+                "unsupported transaction type 0x{ty:02x}"
+            ))),
+            None => Err(Error::RlpDecodingError("empty input".to_string())),
+        }
+    }
+
+    /// Returns the EIP-2718 transaction type byte.
+    pub fn tx_type(&self) -> u8 {
+        match self {
+            Self::Eip2930(_) => Eip2930Transaction::TYPE,
+            Self::Eip1559(_) => Eip1559Transaction::TYPE,
+        }
+    }
+
+    /// Returns the chain ID.
+    pub fn chain_id(&self) -> ChainId {
+        match self {
+            Self::Eip2930(tx) => tx.transaction().chain_id,
+            Self::Eip1559(tx) => tx.transaction().chain_id,
+        }
+    }
+
+    /// Returns the sender's nonce.
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Self::Eip2930(tx) => tx.transaction().nonce,
+            Self::Eip1559(tx) => tx.transaction().nonce,
+        }
+    }
+
+    /// Returns the recipient, or `None` for contract creation.
+    pub fn to(&self) -> Option<Address> {
+        match self {
+            Self::Eip2930(tx) => tx.transaction().to,
+            Self::Eip1559(tx) => tx.transaction().to,
+        }
+    }
+
+    /// Returns the value transferred.
+    pub fn value(&self) -> Wei {
+        match self {
+            Self::Eip2930(tx) => tx.transaction().value,
+            Self::Eip1559(tx) => tx.transaction().value,
+        }
+    }
+
+    /// Returns the call data.
+    pub fn data(&self) -> &[u8] {
+        match self {
+            Self::Eip2930(tx) => &tx.transaction().data,
+            Self::Eip1559(tx) => &tx.transaction().data,
+        }
+    }
+
+    /// Returns the signature.
+    pub fn signature(&self) -> &Signature {
+        match self {
+            Self::Eip2930(tx) => tx.signature(),
+            Self::Eip1559(tx) => tx.signature(),
+        }
+    }
+
+    /// Re-encodes the transaction as raw bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Eip2930(tx) => tx.encode(),
+            Self::Eip1559(tx) => tx.encode(),
+        }
+    }
+
+    /// Computes the transaction hash.
+    pub fn tx_hash(&self) -> [u8; 32] {
+        match self {
+            Self::Eip2930(tx) => tx.tx_hash(),
+            Self::Eip1559(tx) => tx.tx_hash(),
+        }
+    }
+
+    /// Computes the signing hash of the unsigned transaction.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        match self {
+            Self::Eip2930(tx) => tx.signing_hash(),
+            Self::Eip1559(tx) => tx.signing_hash(),
+        }
+    }
+
+    /// Verifies the signature and returns the sender's address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is malformed, non-canonical or recovery fails.
+    pub fn verify(&self) -> Result<Address> {
+        match self {
+            Self::Eip2930(tx) => tx.verify(),
+            Self::Eip1559(tx) => tx.verify(),
+        }
+    }
+}
+
+impl From<SignedTransaction> for TypedTransaction {
+    fn from(tx: SignedTransaction) -> Self {
+        Self::Eip1559(tx)
+    }
+}
+
+impl From<SignedEip2930Transaction> for TypedTransaction {
+    fn from(tx: SignedEip2930Transaction) -> Self {
+        Self::Eip2930(tx)
+    }
+}
+
+fn rlp_error(err: DecoderError) -> Error {
+    Error::RlpDecodingError(err.to_string())
+}
+
+/// Checks the type byte and returns the RLP list that follows it.
+fn envelope(bytes: &[u8], tx_type: u8) -> Result<Rlp<'_>> {
+    match bytes.split_first() {
+        Some((&ty, payload)) if ty == tx_type => {
+            let rlp = Rlp::new(payload);
+            if !rlp.is_list() {
+                return Err(Error::RlpDecodingError(
+                    "transaction payload is not a list".to_string(),
+                ));
+            }
+            let info = rlp.payload_info().map_err(rlp_error)?;
+            if info.total() != payload.len() {
+                return Err(Error::RlpDecodingError(
+                    "trailing bytes after transaction".to_string(),
+                ));
+            }
+            Ok(rlp)
+        }
+        Some((&ty, _)) => Err(Error::RlpDecodingError(format!(
+            "expected transaction type 0x{tx_type:02x}, got 0x{ty:02x}"
+        ))),
+        None => Err(Error::RlpDecodingError("empty input".to_string())),
+    }
+}
+
+fn expect_items(rlp: &Rlp<'_>, expected: usize) -> Result<()> {
+    let count = rlp.item_count().map_err(rlp_error)?;
+    if count != expected {
+        return Err(Error::RlpDecodingError(format!(
+            "expected {expected} fields, got {count}"
+        )));
+    }
+    Ok(())
+}
+
+fn decode_eip1559_fields(rlp: &Rlp<'_>) -> Result<Eip1559Transaction> {
+    Ok(Eip1559Transaction {
+        chain_id: ChainId::from(rlp.val_at::<u64>(0).map_err(rlp_error)?),
+        nonce: rlp.val_at(1).map_err(rlp_error)?,
+        max_priority_fee_per_gas: decode_wei(rlp, 2)?,
+        max_fee_per_gas: decode_wei(rlp, 3)?,
+        gas_limit: rlp.val_at(4).map_err(rlp_error)?,
+        to: decode_to(rlp, 5)?,
+        value: decode_wei(rlp, 6)?,
+        data: rlp.val_at(7).map_err(rlp_error)?,
+        access_list: decode_access_list(&rlp.at(8).map_err(rlp_error)?)?,
+    })
+}
+
+fn decode_eip2930_fields(rlp: &Rlp<'_>) -> Result<Eip2930Transaction> {
+    Ok(Eip2930Transaction {
+        chain_id: ChainId::from(rlp.val_at::<u64>(0).map_err(rlp_error)?),
+        nonce: rlp.val_at(1).map_err(rlp_error)?,
+        gas_price: decode_wei(rlp, 2)?,
+        gas_limit: rlp.val_at(3).map_err(rlp_error)?,
+        to: decode_to(rlp, 4)?,
+        value: decode_wei(rlp, 5)?,
+        data: rlp.val_at(6).map_err(rlp_error)?,
+        access_list: decode_access_list(&rlp.at(7).map_err(rlp_error)?)?,
+    })
+}
+
+fn decode_wei(rlp: &Rlp<'_>, index: usize) -> Result<Wei> {
+    rlp.val_at::<U256>(index)
+        .map(Wei::from_u256)
+        .map_err(rlp_error)
+}
+
+/// Decodes the `to` field: 20 bytes, or empty for contract creation.
+fn decode_to(rlp: &Rlp<'_>, index: usize) -> Result<Option<Address>> {
+    let item = rlp.at(index).map_err(rlp_error)?;
+    let bytes = item.data().map_err(rlp_error)?;
+    if item.is_list() {
+        return Err(Error::RlpDecodingError("`to` must be a string".to_string()));
+    }
+    match bytes.len() {
+        0 => Ok(None),
+        20 => Ok(Some(Address::from_slice(bytes)?)),
+        n => Err(Error::RlpDecodingError(format!(
+            "`to` must be 0 or 20 bytes, got {n}"
+        ))),
+    }
+}
+
+fn decode_access_list(rlp: &Rlp<'_>) -> Result<AccessList> {
+    if !rlp.is_list() {
+        return Err(Error::RlpDecodingError(
+            "access list must be a list".to_string(),
+        ));
+    }
+    rlp.iter()
+        .map(|item| {
+            expect_items(&item, 2)?;
+            let address: Vec<u8> = item.val_at(0).map_err(rlp_error)?;
+            if address.len() != 20 {
+                return Err(Error::RlpDecodingError(format!(
+                    "access list address must be 20 bytes, got {}",
+                    address.len()
+                )));
+            }
+            let keys = item.at(1).map_err(rlp_error)?;
+            if !keys.is_list() {
+                return Err(Error::RlpDecodingError(
+                    "storage keys must be a list".to_string(),
+                ));
+            }
+            let storage_keys = keys
+                .iter()
+                .map(|key| {
+                    let bytes: Vec<u8> = key.as_val().map_err(rlp_error)?;
+                    bytes.try_into().map_err(|_| {
+                        Error::RlpDecodingError("storage key must be 32 bytes".to_string())
+                    })
+                })
+                .collect::<Result<Vec<[u8; 32]>>>()?;
+            Ok(AccessListItem::new(
+                Address::from_slice(&address)?,
+                storage_keys,
+            ))
+        })
+        .collect()
+}
+
+/// Decodes `[y_parity, r, s]` starting at `index`.
+fn decode_signature(rlp: &Rlp<'_>, index: usize) -> Result<Signature> {
+    let v: u8 = rlp.val_at(index).map_err(rlp_error)?;
+    if v > 1 {
+        return Err(Error::RlpDecodingError(format!(
+            "y_parity must be 0 or 1, got {v}"
+        )));
+    }
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    rlp.val_at::<U256>(index + 1)
+        .map_err(rlp_error)?
+        .to_big_endian(&mut r);
+    rlp.val_at::<U256>(index + 2)
+        .map_err(rlp_error)?
+        .to_big_endian(&mut s);
+    Ok(Signature::new(r, s, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bip44Signer;
+
+    fn test_address() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn test_signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    fn test_access_list() -> AccessList {
+        vec![
+            AccessListItem::new(test_address(), vec![[0u8; 32], [0xffu8; 32]]),
+            AccessListItem::address_only(Address::from_bytes([0x11; 20])),
+        ]
+    }
+
+    fn eip1559_tx() -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(42)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(100_000)
+            .to(test_address())
+            .value(Wei::from_ether(1))
+            .data(vec![0xa9, 0x05, 0x9c, 0xbb])
+            .access_list(test_access_list())
+            .build()
+            .unwrap()
+    }
+
+    fn eip2930_tx() -> Eip2930Transaction {
+        Eip2930Transaction::builder()
+            .chain_id(ChainId::EthereumMainnet)
+            .nonce(7)
+            .gas_price(Wei::from_gwei(3))
+            .gas_limit(100_000)
+            .to(test_address())
+            .value(Wei::from_gwei(10))
+            .access_list(test_access_list())
+            .build()
+            .unwrap()
+    }
+
+    // ─── unsigned ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_decode_eip1559_roundtrip() {
+        let tx = eip1559_tx();
+        assert_eq!(
+            Eip1559Transaction::decode(&tx.encode_unsigned()).unwrap(),
+            tx
+        );
+    }
+
+    #[test]
+    fn test_decode_eip1559_contract_creation() {
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscTestnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::ZERO)
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(500_000)
+            .data(vec![0x60, 0x80, 0x60, 0x40])
+            .build()
+            .unwrap();
+
+        let decoded = Eip1559Transaction::decode(&tx.encode_unsigned()).unwrap();
+        assert!(decoded.is_contract_creation());
+        assert_eq!(decoded, tx);
+    }
+
+    #[test]
+    fn test_decode_eip2930_roundtrip() {
+        let tx = eip2930_tx();
+        assert_eq!(
+            Eip2930Transaction::decode(&tx.encode_unsigned()).unwrap(),
+            tx
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_wrong_type() {
+        let encoded = eip2930_tx().encode_unsigned();
+        let err = Eip1559Transaction::decode(&encoded).unwrap_err();
+        assert!(matches!(err, Error::RlpDecodingError(_)));
+    }
+
+    #[test]
+    fn test_decode_rejects_trailing_bytes() {
+        let mut encoded = eip1559_tx().encode_unsigned();
+        encoded.push(0x00);
+        assert!(Eip1559Transaction::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated() {
+        let encoded = eip1559_tx().encode_unsigned();
+        assert!(Eip1559Transaction::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(Eip1559Transaction::decode(&[]).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_signed_as_unsigned() {
+        let tx = eip1559_tx();
+        let signature = test_signer().sign_transaction(&tx).unwrap();
+        let raw = SignedTransaction::new(tx, signature).encode();
+        assert!(Eip1559Transaction::decode(&raw).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_non_canonical_integer() {
+        // nonce encoded as 0x0001 instead of 0x01
+        let mut stream = rlp::RlpStream::new_list(9);
+        stream.append(&56u64);
+        stream.append(&vec![0x00u8, 0x01]);
+        for _ in 0..4 {
+            stream.append_empty_data();
+        }
+        stream.append_empty_data();
+        stream.append_empty_data();
+        stream.begin_list(0);
+        let mut encoded = vec![0x02];
+        encoded.extend_from_slice(&stream.out());
+
+        assert!(Eip1559Transaction::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_to_length() {
+        let mut stream = rlp::RlpStream::new_list(9);
+        stream.append(&56u64);
+        stream.append(&0u64);
+        stream.append_empty_data();
+        stream.append_empty_data();
+        stream.append(&21_000u64);
+        stream.append(&vec![0x11u8; 19]);
+        stream.append_empty_data();
+        stream.append_empty_data();
+        stream.begin_list(0);
+        let mut encoded = vec![0x02];
+        encoded.extend_from_slice(&stream.out());
+
+        assert!(Eip1559Transaction::decode(&encoded).is_err());
+    }
+
+    // ─── signed ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_signed_from_raw_roundtrip() {
+        let signer = test_signer();
+        let tx = eip1559_tx();
+        let signed = SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap());
+        let raw = signed.encode();
+
+        let decoded = SignedTransaction::from_raw(&raw).unwrap();
+        assert_eq!(decoded, signed);
+        assert_eq!(decoded.encode(), raw);
+        assert_eq!(decoded.tx_hash(), signed.tx_hash());
+        assert_eq!(decoded.verify().unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_signed_eip2930_from_raw_roundtrip() {
+        let signer = test_signer();
+        let tx = eip2930_tx();
+        let signed = SignedEip2930Transaction::new(
+            tx.clone(),
+            signer.sign_eip2930_transaction(&tx).unwrap(),
+        );
+
+        let decoded = SignedEip2930Transaction::from_raw(&signed.encode()).unwrap();
+        assert_eq!(decoded, signed);
+        assert_eq!(decoded.verify().unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_signed_from_raw_rejects_unsigned() {
+        let encoded = eip1559_tx().encode_unsigned();
+        assert!(SignedTransaction::from_raw(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_signed_from_raw_rejects_invalid_y_parity() {
+        let tx = eip1559_tx();
+        let mut signature = test_signer().sign_transaction(&tx).unwrap();
+        signature.v = 27;
+        let raw = SignedTransaction::new(tx, signature).encode();
+
+        assert!(SignedTransaction::from_raw(&raw).is_err());
+    }
+
+    #[test]
+    fn test_signed_from_raw_recovers_known_sender() {
+        // Private key 0x4646…46 from the EIP-155 example
+        let signer = Bip44Signer::from_private_key(&[0x46; 32]).unwrap();
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::EthereumMainnet)
+            .nonce(9)
+            .max_priority_fee_per_gas(Wei::from_gwei(2))
+            .max_fee_per_gas(Wei::from_gwei(20))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x35; 20]))
+            .value(Wei::from_ether(1))
+            .build()
+            .unwrap();
+        let raw =
+            SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap()).encode();
+
+        let decoded = SignedTransaction::from_raw(&raw).unwrap();
+        assert_eq!(u64::from(decoded.transaction().chain_id), 1);
+        assert_eq!(
+            decoded.transaction().to,
+            Some(Address::from_bytes([0x35; 20]))
+        );
+        assert_eq!(decoded.transaction().value, Wei::from_ether(1));
+        assert_eq!(
+            decoded.verify().unwrap().to_checksum_string(),
+            "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+        );
+    }
+
+    // ─── TypedTransaction ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_typed_from_raw_dispatches() {
+        let signer = test_signer();
+
+        let tx = eip1559_tx();
+        let raw =
+            SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap()).encode();
+        let decoded = TypedTransaction::from_raw(&raw).unwrap();
+        assert_eq!(decoded.tx_type(), 0x02);
+        assert_eq!(decoded.nonce(), 42);
+        assert_eq!(decoded.to(), Some(test_address()));
+        assert_eq!(decoded.encode(), raw);
+        assert_eq!(decoded.verify().unwrap(), signer.address());
+
+        let tx = eip2930_tx();
+        let raw = SignedEip2930Transaction::new(
+            tx.clone(),
+            signer.sign_eip2930_transaction(&tx).unwrap(),
+        )
+        .encode();
+        let decoded = TypedTransaction::from_raw(&raw).unwrap();
+        assert_eq!(decoded.tx_type(), 0x01);
+        assert_eq!(decoded.chain_id(), ChainId::EthereumMainnet);
+        assert_eq!(decoded.value(), Wei::from_gwei(10));
+        assert!(decoded.data().is_empty());
+        assert_eq!(decoded.verify().unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_typed_from_raw_rejects_unsupported() {
+        assert!(TypedTransaction::from_raw(&[]).is_err());
+        assert!(TypedTransaction::from_raw(&[0x03, 0xc0]).is_err());
+
+        let err = TypedTransaction::from_raw(&[0xc0]).unwrap_err();
+        assert!(err.to_string().contains("legacy"));
+    }
+}
@@ -100,12 +100,20 @@ impl Eip1559Transaction {
         result.copy_from_slice(&hash);
         result
     }
+
+    /// Returns the signing hash as a hex string with 0x prefix.
+    ///
+    /// This is the digest a signer commits to; show it next to a hardware wallet's
+    /// confirmation screen or record it in an audit log before signing.
+    pub fn signing_hash_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.signing_hash()))
+    }
 }
 
 /// Appends a U256 value to the RLP stream.
 ///
 /// U256 values are encoded as big-endian bytes with leading zeros stripped.
-fn append_u256(stream: &mut RlpStream, value: U256) {
+pub(crate) fn append_u256(stream: &mut RlpStream, value: U256) {
     if value.is_zero() {
         stream.append_empty_data();
     } else {
@@ -118,7 +126,7 @@ fn append_u256(stream: &mut RlpStream, value: U256) {
 }
 
 /// Encodes the access list into the RLP stream.
-fn encode_access_list(stream: &mut RlpStream, access_list: &[AccessListItem]) {
+pub(crate) fn encode_access_list(stream: &mut RlpStream, access_list: &[AccessListItem]) {
     stream.begin_list(access_list.len());
     for item in access_list {
         stream.begin_list(2);
@@ -0,0 +1,177 @@
+//! Log filters for `eth_subscribe("logs")`.
+
+use crate::Address;
+use serde_json::{json, Map, Value};
+
+/// `topic0` of the ERC-20 / ERC-721 `Transfer(address,address,uint256)` event.
+pub const TRANSFER_TOPIC: [u8; 32] = [
+    0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa,
+    0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef,
+];
+
+/// Selects event logs by emitting contract and topics.
+///
+/// Empty criteria match everything. Several addresses, or several values at one topic
+/// position, match any of them.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::rpc::LogFilter;
+/// use khodpay_signing::Address;
+///
+/// let wallet = Address::from_bytes([0x11; 20]);
+/// let usdt: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+///
+/// // Incoming USDT transfers to `wallet`.
+/// let filter = LogFilter::token_transfers_to(wallet).address(usdt);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    addresses: Vec<Address>,
+    topics: [Vec<[u8; 32]>; 4],
+}
+
+impl LogFilter {
+    /// Creates a filter matching every log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches `Transfer` events of any ERC-20 or ERC-721 contract whose recipient is `to`.
+    pub fn token_transfers_to(to: Address) -> Self {
+        Self::new()
+            .topic(0, TRANSFER_TOPIC)
+            .topic(2, address_topic(&to))
+    }
+
+    /// Matches `Transfer` events of any ERC-20 or ERC-721 contract whose sender is `from`.
+    pub fn token_transfers_from(from: Address) -> Self {
+        Self::new()
+            .topic(0, TRANSFER_TOPIC)
+            .topic(1, address_topic(&from))
+    }
+
+    /// Adds a contract address to match.
+    pub fn address(mut self, address: Address) -> Self {
+        self.addresses.push(address);
+        self
+    }
+
+    /// Adds an accepted value for the topic at `position` (`0..=3`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is greater than 3.
+    pub fn topic(mut self, position: usize, topic: [u8; 32]) -> Self {
+        assert!(position < 4, "log topic position must be 0..=3");
+        self.topics[position].push(topic);
+        self
+    }
+
+    /// Returns the filter as the JSON object the node expects.
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        match self.addresses.as_slice() {
+            [] => {}
+            [address] => {
+                object.insert("address".into(), json!(address.to_checksum_string()));
+            }
+            addresses => {
+                let addresses: Vec<String> =
+                    addresses.iter().map(Address::to_checksum_string).collect();
+                object.insert("address".into(), json!(addresses));
+            }
+        }
+
+        let used = self
+            .topics
+            .iter()
+            .rposition(|topic| !topic.is_empty())
+            .map_or(0, |last| last + 1);
+        if used > 0 {
+            let topics: Vec<Value> = self.topics[..used]
+                .iter()
+                .map(|values| match values.as_slice() {
+                    [] => Value::Null,
+                    [value] => json!(hex_topic(value)),
+                    values => json!(values.iter().map(hex_topic).collect::<Vec<_>>()),
+                })
+                .collect();
+            object.insert("topics".into(), Value::Array(topics));
+        }
+        Value::Object(object)
+    }
+}
+
+/// Left-pads an address to a 32-byte indexed topic.
+fn address_topic(address: &Address) -> [u8; 32] {
+    let mut topic = [0u8; 32];
+    topic[12..].copy_from_slice(address.as_bytes());
+    topic
+}
+
+fn hex_topic(topic: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(topic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::Event;
+
+    #[test]
+    fn test_transfer_topic() {
+        let event =
+            Event::parse("Transfer(address indexed from, address indexed to, uint256 value)")
+                .unwrap();
+        assert_eq!(TRANSFER_TOPIC, event.topic0());
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        assert_eq!(LogFilter::new().to_json(), json!({}));
+    }
+
+    #[test]
+    fn test_token_transfers_to() {
+        let wallet = Address::from_bytes([0x11; 20]);
+        let filter = LogFilter::token_transfers_to(wallet);
+
+        assert_eq!(
+            filter.to_json(),
+            json!({
+                "topics": [
+                    hex_topic(&TRANSFER_TOPIC),
+                    null,
+                    format!("0x{}{}", "00".repeat(12), "11".repeat(20)),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_multiple_addresses_and_topic_values() {
+        let a = Address::from_bytes([0xaa; 20]);
+        let b = Address::from_bytes([0xbb; 20]);
+        let filter = LogFilter::new()
+            .address(a)
+            .address(b)
+            .topic(0, [1u8; 32])
+            .topic(0, [2u8; 32]);
+
+        assert_eq!(
+            filter.to_json(),
+            json!({
+                "address": [a.to_checksum_string(), b.to_checksum_string()],
+                "topics": [[hex_topic(&[1u8; 32]), hex_topic(&[2u8; 32])]],
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "0..=3")]
+    fn test_topic_position_out_of_range() {
+        let _ = LogFilter::new().topic(4, [0u8; 32]);
+    }
+}
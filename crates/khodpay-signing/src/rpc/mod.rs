@@ -0,0 +1,657 @@
+//! Minimal async JSON-RPC client (requires the `net` feature).
+//!
+//! Covers the handful of `eth_*` methods a wallet needs to broadcast signed
+//! transactions and read account state, without pulling in a full web3 framework.
+//!
+//! [`ProviderPool`] adds retries, health checks and failover across several endpoints.
+//! With the `ws` feature, [`WsClient`] speaks the same protocol over a WebSocket and
+//! adds `eth_subscribe` for new blocks, pending transactions and logs.
+//!
+//! # Quick Start
+//!
+//! ```rust,ignore
+//! use khodpay_signing::rpc::{BlockId, RpcClient};
+//!
+//! let client = RpcClient::new("https://bsc-dataseed.bnbchain.org");
+//!
+//! let nonce = client.get_transaction_count(signer.address(), BlockId::Pending).await?;
+//! let balance = client.get_balance(signer.address(), BlockId::Latest).await?;
+//!
+//! // ... build and sign `tx` with `nonce` ...
+//! let tx_hash = client.send_transaction(&SignedTransaction::new(tx, signature)).await?;
+//! ```
+
+use crate::fee::FeeHistory;
+use crate::{Address, Error, Result, SignedTransaction, TxRequest, Wei};
+use primitive_types::U256;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod filter;
+mod pool;
+mod receipt;
+#[cfg(feature = "ws")]
+mod ws;
+
+pub use filter::{LogFilter, TRANSFER_TOPIC};
+pub use pool::{
+    EndpointHealth, ProviderPool, ProviderPoolBuilder, DEFAULT_COOLDOWN, DEFAULT_FAILURE_THRESHOLD,
+    DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF, DEFAULT_MAX_BLOCK_LAG, DEFAULT_MAX_RETRIES,
+};
+pub use receipt::{Log, TransactionReceipt};
+#[cfg(feature = "ws")]
+pub use ws::{BlockHeader, Subscription, WsClient};
+
+/// Default interval between polls in [`RpcClient::await_confirmation`].
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Block selector for state queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlockId {
+    /// The most recent mined block.
+    #[default]
+    Latest,
+    /// The pending state, including mempool transactions.
+    Pending,
+    /// The genesis block.
+    Earliest,
+    /// The latest safe head block.
+    Safe,
+    /// The latest finalized block.
+    Finalized,
+    /// A specific block number.
+    Number(u64),
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockId::Latest => write!(f, "latest"),
+            BlockId::Pending => write!(f, "pending"),
+            BlockId::Earliest => write!(f, "earliest"),
+            BlockId::Safe => write!(f, "safe"),
+            BlockId::Finalized => write!(f, "finalized"),
+            BlockId::Number(n) => write!(f, "0x{:x}", n),
+        }
+    }
+}
+
+/// JSON-RPC 2.0 response envelope.
+#[derive(Deserialize)]
+struct Response {
+    result: Option<Value>,
+    error: Option<ErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// Async JSON-RPC client over HTTP(S).
+///
+/// Talks to a single endpoint, or to several through a [`ProviderPool`]. Cloning is
+/// cheap; clones share the underlying connection pool and endpoint health.
+#[derive(Debug)]
+pub struct RpcClient {
+    url: String,
+    http: reqwest::Client,
+    next_id: AtomicU64,
+    poll_interval: Duration,
+    pool: Option<Arc<ProviderPool>>,
+}
+
+impl Clone for RpcClient {
+    fn clone(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            http: self.http.clone(),
+            next_id: AtomicU64::new(self.next_id.load(Ordering::Relaxed)),
+            poll_interval: self.poll_interval,
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl RpcClient {
+    /// Creates a client for the given endpoint URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_http_client(url, reqwest::Client::new())
+    }
+
+    /// Creates a client that reuses an existing `reqwest::Client` (for custom
+    /// timeouts, proxies or headers).
+    pub fn with_http_client(url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            url: url.into(),
+            http,
+            next_id: AtomicU64::new(1),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            pool: None,
+        }
+    }
+
+    /// Creates a client that spreads requests over the endpoints of `pool`, with
+    /// retries and failover.
+    pub fn from_pool(pool: ProviderPool) -> Self {
+        Self::from_pool_with_http_client(pool, reqwest::Client::new())
+    }
+
+    /// Creates a pooled client that reuses an existing `reqwest::Client`.
+    pub fn from_pool_with_http_client(pool: ProviderPool, http: reqwest::Client) -> Self {
+        let mut client = Self::with_http_client(pool.urls()[0], http);
+        client.pool = Some(Arc::new(pool));
+        client
+    }
+
+    /// Sets the interval between polls when waiting for confirmations.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Returns the endpoint URL; for a pooled client, the preferred endpoint.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the provider pool, if the client was created with one.
+    pub fn pool(&self) -> Option<&ProviderPool> {
+        self.pool.as_deref()
+    }
+
+    /// Sends a raw JSON-RPC request and deserializes the `result` field.
+    ///
+    /// A pooled client fails over between endpoints on transport errors; see
+    /// [`ProviderPool`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RpcError`] on transport or decoding failures and
+    /// [`Error::JsonRpcError`] if the node returns an error object.
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id(),
+            "method": method,
+            "params": params,
+        });
+
+        let result = match &self.pool {
+            Some(pool) => self.send_pooled(pool, method, &body).await?,
+            None => self.send(&self.url, method, &body).await?,
+        };
+
+        serde_json::from_value(result)
+            .map_err(|e| Error::RpcError(format!("{} returned unexpected result: {}", method, e)))
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Posts `body` to `url` once and returns the `result` field.
+    async fn send(&self, url: &str, method: &str, body: &Value) -> Result<Value> {
+        let response: Response = self
+            .http
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Error::RpcError(format!("{} request failed: {}", method, e)))?
+            .error_for_status()
+            .map_err(|e| Error::RpcError(format!("{} request failed: {}", method, e)))?
+            .json()
+            .await
+            .map_err(|e| Error::RpcError(format!("{} returned invalid JSON: {}", method, e)))?;
+
+        if let Some(error) = response.error {
+            return Err(Error::JsonRpcError {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        Ok(response.result.unwrap_or(Value::Null))
+    }
+
+    /// Returns the chain ID reported by the node (`eth_chainId`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn chain_id(&self) -> Result<u64> {
+        let hex: String = self.request("eth_chainId", json!([])).await?;
+        parse_u64(&hex)
+    }
+
+    /// Returns the latest block number (`eth_blockNumber`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn block_number(&self) -> Result<u64> {
+        let hex: String = self.request("eth_blockNumber", json!([])).await?;
+        parse_u64(&hex)
+    }
+
+    /// Returns the balance of `address` (`eth_getBalance`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn get_balance(&self, address: Address, block: BlockId) -> Result<Wei> {
+        let hex: String = self
+            .request(
+                "eth_getBalance",
+                json!([address.to_checksum_string(), block.to_string()]),
+            )
+            .await?;
+        parse_u256(&hex).map(Wei::from_u256)
+    }
+
+    /// Returns the transaction count of `address` (`eth_getTransactionCount`).
+    ///
+    /// Use [`BlockId::Pending`] to get the next nonce including mempool transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn get_transaction_count(&self, address: Address, block: BlockId) -> Result<u64> {
+        let hex: String = self
+            .request(
+                "eth_getTransactionCount",
+                json!([address.to_checksum_string(), block.to_string()]),
+            )
+            .await?;
+        parse_u64(&hex)
+    }
+
+    /// Executes a read-only call (`eth_call`) and returns the raw return data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the call reverts.
+    pub async fn call(&self, to: Address, data: &[u8], block: BlockId) -> Result<Vec<u8>> {
+        let hex: String = self
+            .request(
+                "eth_call",
+                json!([
+                    {
+                        "to": to.to_checksum_string(),
+                        "data": format!("0x{}", hex::encode(data)),
+                    },
+                    block.to_string(),
+                ]),
+            )
+            .await?;
+        parse_bytes(&hex)
+    }
+
+    /// Estimates the gas `request` would use if sent by `from` (`eth_estimateGas`).
+    ///
+    /// The request's own `gas_limit` is not sent, so the node searches up to the block
+    /// gas limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the node reports that the transaction
+    /// would revert.
+    pub async fn estimate_gas(&self, from: Address, request: &TxRequest) -> Result<u64> {
+        let mut call = json!({
+            "from": from.to_checksum_string(),
+            "value": format!("0x{:x}", request.value.as_u256()),
+            "data": format!("0x{}", hex::encode(&request.data)),
+        });
+        if let Some(to) = request.to {
+            call["to"] = json!(to.to_checksum_string());
+        }
+        if !request.access_list.is_empty() {
+            call["accessList"] = request
+                .access_list
+                .iter()
+                .map(|item| {
+                    json!({
+                        "address": item.address.to_checksum_string(),
+                        "storageKeys": item
+                            .storage_keys
+                            .iter()
+                            .map(|key| format!("0x{}", hex::encode(key)))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+        }
+
+        let hex: String = self.request("eth_estimateGas", json!([call])).await?;
+        parse_u64(&hex)
+    }
+
+    /// Returns the deployed bytecode at `address` (`eth_getCode`).
+    ///
+    /// Empty for externally owned accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn get_code(&self, address: Address, block: BlockId) -> Result<Vec<u8>> {
+        let hex: String = self
+            .request(
+                "eth_getCode",
+                json!([address.to_checksum_string(), block.to_string()]),
+            )
+            .await?;
+        parse_bytes(&hex)
+    }
+
+    /// Returns the legacy gas price (`eth_gasPrice`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn gas_price(&self) -> Result<Wei> {
+        let hex: String = self.request("eth_gasPrice", json!([])).await?;
+        parse_u256(&hex).map(Wei::from_u256)
+    }
+
+    /// Fetches fee history (`eth_feeHistory`) for use with
+    /// [`FeeEstimator::estimate`](crate::fee::FeeEstimator::estimate).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is malformed.
+    pub async fn fee_history(
+        &self,
+        block_count: u64,
+        newest_block: BlockId,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RawFeeHistory {
+            oldest_block: String,
+            base_fee_per_gas: Vec<String>,
+            gas_used_ratio: Vec<f64>,
+            #[serde(default)]
+            reward: Vec<Vec<String>>,
+        }
+
+        let raw: RawFeeHistory = self
+            .request(
+                "eth_feeHistory",
+                json!([
+                    format!("0x{:x}", block_count),
+                    newest_block.to_string(),
+                    reward_percentiles,
+                ]),
+            )
+            .await?;
+
+        let wei = |s: &String| parse_u256(s).map(Wei::from_u256);
+        Ok(FeeHistory {
+            oldest_block: parse_u64(&raw.oldest_block)?,
+            base_fee_per_gas: raw
+                .base_fee_per_gas
+                .iter()
+                .map(wei)
+                .collect::<Result<_>>()?,
+            gas_used_ratio: raw.gas_used_ratio,
+            reward: raw
+                .reward
+                .iter()
+                .map(|row| row.iter().map(wei).collect::<Result<_>>())
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Broadcasts a raw signed transaction (`eth_sendRawTransaction`) and returns its
+    /// hash as reported by the node.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the node rejects the transaction.
+    pub async fn send_raw_transaction(&self, raw: &[u8]) -> Result<[u8; 32]> {
+        let hex: String = self
+            .request(
+                "eth_sendRawTransaction",
+                json!([format!("0x{}", hex::encode(raw))]),
+            )
+            .await?;
+        parse_hash(&hex)
+    }
+
+    /// Broadcasts a signed EIP-1559 transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the node rejects the transaction.
+    pub async fn send_transaction(&self, tx: &SignedTransaction) -> Result<[u8; 32]> {
+        self.send_raw_transaction(&tx.encode()).await
+    }
+}
+
+// ─── Hex quantity helpers ────────────────────────────────────────────────────
+
+fn strip_hex(s: &str) -> Result<&str> {
+    s.strip_prefix("0x")
+        .ok_or_else(|| Error::RpcError(format!("expected 0x-prefixed hex, got '{}'", s)))
+}
+
+pub(crate) fn parse_u256(s: &str) -> Result<U256> {
+    let digits = strip_hex(s)?;
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+    U256::from_str_radix(digits, 16)
+        .map_err(|e| Error::RpcError(format!("invalid hex quantity '{}': {}", s, e)))
+}
+
+pub(crate) fn parse_u64(s: &str) -> Result<u64> {
+    let value = parse_u256(s)?;
+    if value > U256::from(u64::MAX) {
+        return Err(Error::RpcError(format!("quantity '{}' overflows u64", s)));
+    }
+    Ok(value.as_u64())
+}
+
+pub(crate) fn parse_bytes(s: &str) -> Result<Vec<u8>> {
+    hex::decode(strip_hex(s)?).map_err(|e| Error::HexError(e.to_string()))
+}
+
+pub(crate) fn parse_hash(s: &str) -> Result<[u8; 32]> {
+    parse_bytes(s)?
+        .try_into()
+        .map_err(|_| Error::RpcError(format!("expected a 32-byte hash, got '{}'", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bip44Signer, ChainId, Eip1559Transaction};
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_address() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    async fn mock_result(server: &MockServer, rpc_method: &str, result: Value) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": rpc_method })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+            )
+            .mount(server)
+            .await;
+    }
+
+    // ==================== Helper Tests ====================
+
+    #[test]
+    fn test_block_id_display() {
+        assert_eq!(BlockId::Latest.to_string(), "latest");
+        assert_eq!(BlockId::Pending.to_string(), "pending");
+        assert_eq!(BlockId::Number(255).to_string(), "0xff");
+    }
+
+    #[test]
+    fn test_parse_quantities() {
+        assert_eq!(parse_u64("0x0").unwrap(), 0);
+        assert_eq!(parse_u64("0x1a").unwrap(), 26);
+        assert_eq!(parse_u256("0x").unwrap(), U256::zero());
+        assert!(parse_u64("1a").is_err());
+        assert!(parse_u64("0x10000000000000000").is_err());
+        assert!(parse_hash("0x1234").is_err());
+    }
+
+    // ==================== Client Tests ====================
+
+    #[tokio::test]
+    async fn test_get_balance_and_nonce() {
+        let server = MockServer::start().await;
+        mock_result(&server, "eth_getBalance", json!("0xde0b6b3a7640000")).await;
+        mock_result(&server, "eth_getTransactionCount", json!("0x5")).await;
+
+        let client = RpcClient::new(server.uri());
+        let balance = client
+            .get_balance(test_address(), BlockId::Latest)
+            .await
+            .unwrap();
+        let nonce = client
+            .get_transaction_count(test_address(), BlockId::Pending)
+            .await
+            .unwrap();
+
+        assert_eq!(balance, Wei::from_ether(1));
+        assert_eq!(nonce, 5);
+    }
+
+    #[tokio::test]
+    async fn test_call_returns_bytes() {
+        let server = MockServer::start().await;
+        mock_result(&server, "eth_call", json!("0x00ff")).await;
+
+        let client = RpcClient::new(server.uri());
+        let data = client
+            .call(test_address(), &[0x31, 0x3c, 0xe5, 0x67], BlockId::Latest)
+            .await
+            .unwrap();
+
+        assert_eq!(data, vec![0x00, 0xff]);
+    }
+
+    #[tokio::test]
+    async fn test_get_code() {
+        let server = MockServer::start().await;
+        mock_result(&server, "eth_getCode", json!("0x6080")).await;
+
+        let client = RpcClient::new(server.uri());
+        let code = client
+            .get_code(test_address(), BlockId::Latest)
+            .await
+            .unwrap();
+
+        assert_eq!(code, vec![0x60, 0x80]);
+    }
+
+    #[tokio::test]
+    async fn test_send_transaction() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21_000)
+            .to(test_address())
+            .build()
+            .unwrap();
+        let signed = SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap());
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "method": "eth_sendRawTransaction",
+                "params": [signed.to_raw_transaction()],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": signed.tx_hash_hex(),
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = RpcClient::new(server.uri());
+        let hash = client.send_transaction(&signed).await.unwrap();
+        assert_eq!(hash, signed.tx_hash());
+    }
+
+    #[tokio::test]
+    async fn test_fee_history() {
+        let server = MockServer::start().await;
+        mock_result(
+            &server,
+            "eth_feeHistory",
+            json!({
+                "oldestBlock": "0x10",
+                "baseFeePerGas": ["0x3b9aca00", "0x77359400"],
+                "gasUsedRatio": [0.5],
+                "reward": [["0x1", "0x2", "0x3"]],
+            }),
+        )
+        .await;
+
+        let client = RpcClient::new(server.uri());
+        let history = client
+            .fee_history(1, BlockId::Latest, &[10.0, 50.0, 90.0])
+            .await
+            .unwrap();
+
+        assert_eq!(history.oldest_block, 16);
+        assert_eq!(history.next_base_fee(), Some(Wei::from_gwei(2)));
+        assert_eq!(history.reward[0][2], Wei::from_wei(3u64));
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32000, "message": "nonce too low" },
+            })))
+            .mount(&server)
+            .await;
+
+        let client = RpcClient::new(server.uri());
+        let err = client.send_raw_transaction(&[0x02]).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::JsonRpcError { code: -32000, ref message } if message == "nonce too low"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_http_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let client = RpcClient::new(server.uri());
+        assert!(matches!(client.chain_id().await, Err(Error::RpcError(_))));
+    }
+}
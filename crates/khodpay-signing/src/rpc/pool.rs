@@ -0,0 +1,652 @@
+//! Multi-endpoint provider pool with retries, health tracking and failover.
+//!
+//! Public RPC endpoints rate-limit, stall and fall behind the chain head. A
+//! [`ProviderPool`] spreads that risk over several endpoints: an [`RpcClient`] built with
+//! [`RpcClient::from_pool`] sends each request to the first healthy endpoint, fails over
+//! to the next one on transport errors, retries whole rounds with exponential backoff and
+//! benches endpoints that keep failing for a cooldown period.
+//!
+//! Errors returned by a node itself (`execution reverted`, `nonce too low`, …) are
+//! answers, not outages, and are returned without failing over.
+//!
+//! # Quick Start
+//!
+//! ```rust,ignore
+//! use khodpay_signing::rpc::{ProviderPool, RpcClient};
+//!
+//! let pool = ProviderPool::builder()
+//!     .endpoint("https://bsc-dataseed.bnbchain.org")
+//!     .endpoint("https://bsc-rpc.publicnode.com")
+//!     .max_retries(2)
+//!     .build()?;
+//! let client = RpcClient::from_pool(pool);
+//!
+//! let balance = client.get_balance(address, BlockId::Latest).await?;
+//! for endpoint in client.check_health().await {
+//!     println!("{}: healthy={} block={:?}", endpoint.url, endpoint.healthy, endpoint.block_number);
+//! }
+//! ```
+
+use super::{parse_u64, RpcClient};
+use crate::{Error, Result};
+use serde_json::{json, Value};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Default number of retry rounds after the first one.
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// Default backoff before the first retry round; doubled for every further round.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Default upper bound for the backoff between rounds.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Default number of consecutive failures after which an endpoint is benched.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+/// Default time a benched endpoint is skipped.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Default number of blocks an endpoint may trail the best one in a health check.
+pub const DEFAULT_MAX_BLOCK_LAG: u64 = 5;
+
+/// JSON-RPC error code used by many providers for rate limiting ("limit exceeded").
+const LIMIT_EXCEEDED: i64 = -32005;
+
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    benched_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    health: Mutex<Health>,
+}
+
+impl Endpoint {
+    fn health(&self) -> MutexGuard<'_, Health> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Endpoints and failover settings shared by an [`RpcClient`] and its clones.
+///
+/// Endpoints are tried in the order they were added, so list the preferred one first.
+#[derive(Debug)]
+pub struct ProviderPool {
+    endpoints: Vec<Endpoint>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    failure_threshold: u32,
+    cooldown: Duration,
+    max_block_lag: u64,
+}
+
+impl ProviderPool {
+    /// Creates a builder with the default settings.
+    pub fn builder() -> ProviderPoolBuilder {
+        ProviderPoolBuilder::new()
+    }
+
+    /// Returns the endpoint URLs in preference order.
+    pub fn urls(&self) -> Vec<&str> {
+        self.endpoints.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    /// Returns `true` if the endpoint at `url` is currently in rotation.
+    ///
+    /// Unknown URLs are reported as unhealthy.
+    pub fn is_healthy(&self, url: &str) -> bool {
+        self.endpoints
+            .iter()
+            .find(|e| e.url == url)
+            .is_some_and(|e| is_available(e.health().benched_until, Instant::now()))
+    }
+
+    /// Returns the backoff before retry round `round` (1-based).
+    pub fn backoff(&self, round: u32) -> Duration {
+        let factor = 2u32.saturating_pow(round.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Endpoint indices to try: available ones in preference order, then benched ones
+    /// by how soon they come back.
+    fn candidates(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let (mut available, mut benched): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, endpoint)| (index, endpoint.health().benched_until))
+            .partition(|(_, until)| is_available(*until, now));
+        benched.sort_by_key(|(_, until)| *until);
+        available.append(&mut benched);
+        available.into_iter().map(|(index, _)| index).collect()
+    }
+
+    fn record_success(&self, index: usize) {
+        *self.endpoints[index].health() = Health::default();
+    }
+
+    fn record_failure(&self, index: usize) {
+        let mut health = self.endpoints[index].health();
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        if health.consecutive_failures >= self.failure_threshold {
+            health.benched_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    fn bench(&self, index: usize) {
+        let mut health = self.endpoints[index].health();
+        health.consecutive_failures = health.consecutive_failures.max(self.failure_threshold);
+        health.benched_until = Some(Instant::now() + self.cooldown);
+    }
+}
+
+fn is_available(benched_until: Option<Instant>, now: Instant) -> bool {
+    match benched_until {
+        Some(until) => until <= now,
+        None => true,
+    }
+}
+
+/// Builder for [`ProviderPool`].
+#[derive(Debug, Clone)]
+pub struct ProviderPoolBuilder {
+    urls: Vec<String>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    failure_threshold: u32,
+    cooldown: Duration,
+    max_block_lag: u64,
+}
+
+impl Default for ProviderPoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderPoolBuilder {
+    /// Creates a builder with the default settings and no endpoints.
+    pub fn new() -> Self {
+        Self {
+            urls: Vec::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+            max_block_lag: DEFAULT_MAX_BLOCK_LAG,
+        }
+    }
+
+    /// Adds an endpoint; earlier endpoints are preferred.
+    pub fn endpoint(mut self, url: impl Into<String>) -> Self {
+        self.urls.push(url.into());
+        self
+    }
+
+    /// Adds several endpoints in preference order.
+    pub fn endpoints<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.urls.extend(urls.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets how many rounds over all endpoints follow a failed first round.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Sets the backoff before the first retry round and its upper bound.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets how many consecutive failures bench an endpoint.
+    pub fn failure_threshold(mut self, failures: u32) -> Self {
+        self.failure_threshold = failures;
+        self
+    }
+
+    /// Sets how long a benched endpoint is skipped.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets how many blocks an endpoint may trail the best one in
+    /// [`RpcClient::check_health`] before it is benched.
+    pub fn max_block_lag(mut self, blocks: u64) -> Self {
+        self.max_block_lag = blocks;
+        self
+    }
+
+    /// Builds the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ValidationError`] if no endpoint was added, an endpoint appears
+    /// twice, the failure threshold is zero or the initial backoff exceeds the maximum.
+    pub fn build(self) -> Result<ProviderPool> {
+        if self.urls.is_empty() {
+            return Err(Error::ValidationError(
+                "provider pool needs at least one endpoint".to_string(),
+            ));
+        }
+        for (i, url) in self.urls.iter().enumerate() {
+            if self.urls[..i].contains(url) {
+                return Err(Error::ValidationError(format!(
+                    "duplicate provider endpoint {}",
+                    url
+                )));
+            }
+        }
+        if self.failure_threshold == 0 {
+            return Err(Error::ValidationError(
+                "failure threshold must be at least 1".to_string(),
+            ));
+        }
+        if self.initial_backoff > self.max_backoff {
+            return Err(Error::ValidationError(
+                "initial backoff exceeds the maximum backoff".to_string(),
+            ));
+        }
+
+        Ok(ProviderPool {
+            endpoints: self
+                .urls
+                .into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    health: Mutex::new(Health::default()),
+                })
+                .collect(),
+            max_retries: self.max_retries,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            failure_threshold: self.failure_threshold,
+            cooldown: self.cooldown,
+            max_block_lag: self.max_block_lag,
+        })
+    }
+}
+
+/// The result of probing one endpoint in [`RpcClient::check_health`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointHealth {
+    /// The endpoint URL.
+    pub url: String,
+    /// `true` if the endpoint answered and is within the allowed block lag.
+    pub healthy: bool,
+    /// The block number reported by the endpoint, if it answered.
+    pub block_number: Option<u64>,
+    /// Round-trip time of the probe, if it answered.
+    pub latency: Option<Duration>,
+    /// Why the probe failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Returns `true` for errors worth trying another endpoint for.
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::RpcError(_) => true,
+        Error::JsonRpcError { code, .. } => *code == LIMIT_EXCEEDED,
+        _ => false,
+    }
+}
+
+impl RpcClient {
+    /// Sends `body` through the pool, failing over and retrying as configured.
+    pub(super) async fn send_pooled(
+        &self,
+        pool: &ProviderPool,
+        method: &str,
+        body: &Value,
+    ) -> Result<Value> {
+        let mut last_error = None;
+        for round in 0..=pool.max_retries {
+            if round > 0 {
+                tokio::time::sleep(pool.backoff(round)).await;
+            }
+            for index in pool.candidates() {
+                match self.send(&pool.endpoints[index].url, method, body).await {
+                    Err(error) if is_retryable(&error) => {
+                        pool.record_failure(index);
+                        last_error = Some(error);
+                    }
+                    result => {
+                        pool.record_success(index);
+                        return result;
+                    }
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            Error::RpcError(format!("{} request failed: no endpoint available", method))
+        }))
+    }
+
+    /// Probes every endpoint with `eth_blockNumber` and updates the pool's health.
+    ///
+    /// Endpoints that fail, or trail the best reported block by more than the pool's
+    /// maximum block lag, are benched; the others are put back in rotation. Without a
+    /// pool, the single endpoint is probed and reported.
+    pub async fn check_health(&self) -> Vec<EndpointHealth> {
+        let urls: Vec<String> = match &self.pool {
+            Some(pool) => pool.endpoints.iter().map(|e| e.url.clone()).collect(),
+            None => vec![self.url.clone()],
+        };
+
+        let mut reports = Vec::with_capacity(urls.len());
+        for url in urls {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": self.next_id(),
+                "method": "eth_blockNumber",
+                "params": [],
+            });
+            let started = Instant::now();
+            let result = match self.send(&url, "eth_blockNumber", &body).await {
+                Ok(Value::String(hex)) => parse_u64(&hex),
+                Ok(other) => Err(Error::RpcError(format!(
+                    "eth_blockNumber returned unexpected result: {}",
+                    other
+                ))),
+                Err(error) => Err(error),
+            };
+            reports.push(match result {
+                Ok(block_number) => EndpointHealth {
+                    url,
+                    healthy: true,
+                    block_number: Some(block_number),
+                    latency: Some(started.elapsed()),
+                    error: None,
+                },
+                Err(error) => EndpointHealth {
+                    url,
+                    healthy: false,
+                    block_number: None,
+                    latency: None,
+                    error: Some(error.to_string()),
+                },
+            });
+        }
+
+        let max_block_lag = self.pool.as_ref().map_or(u64::MAX, |p| p.max_block_lag);
+        if let Some(best) = reports.iter().filter_map(|r| r.block_number).max() {
+            for report in &mut reports {
+                if let Some(block) = report.block_number {
+                    if best - block > max_block_lag {
+                        report.healthy = false;
+                        report.error = Some(format!("{} blocks behind", best - block));
+                    }
+                }
+            }
+        }
+
+        if let Some(pool) = &self.pool {
+            for (index, report) in reports.iter().enumerate() {
+                if report.healthy {
+                    pool.record_success(index);
+                } else {
+                    pool.bench(index);
+                }
+            }
+        }
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn pool(urls: &[String]) -> ProviderPoolBuilder {
+        ProviderPool::builder()
+            .endpoints(urls.iter().cloned())
+            .backoff(Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    async fn mock_result(server: &MockServer, rpc_method: &str, result: Value) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": rpc_method })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+            )
+            .mount(server)
+            .await;
+    }
+
+    async fn mock_status(server: &MockServer, status: u16) {
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(server)
+            .await;
+    }
+
+    async fn request_count(server: &MockServer) -> usize {
+        server.received_requests().await.unwrap().len()
+    }
+
+    // ─── builder ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_builder_validation() {
+        assert!(ProviderPool::builder().build().is_err());
+        assert!(ProviderPool::builder()
+            .endpoint("http://a")
+            .endpoint("http://a")
+            .build()
+            .is_err());
+        assert!(ProviderPool::builder()
+            .endpoint("http://a")
+            .failure_threshold(0)
+            .build()
+            .is_err());
+        assert!(ProviderPool::builder()
+            .endpoint("http://a")
+            .backoff(Duration::from_secs(2), Duration::from_secs(1))
+            .build()
+            .is_err());
+
+        let pool = ProviderPool::builder()
+            .endpoints(["http://a", "http://b"])
+            .build()
+            .unwrap();
+        assert_eq!(pool.urls(), vec!["http://a", "http://b"]);
+        assert!(pool.is_healthy("http://a"));
+        assert!(!pool.is_healthy("http://c"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let pool = ProviderPool::builder()
+            .endpoint("http://a")
+            .backoff(Duration::from_millis(100), Duration::from_millis(500))
+            .build()
+            .unwrap();
+        assert_eq!(pool.backoff(1), Duration::from_millis(100));
+        assert_eq!(pool.backoff(2), Duration::from_millis(200));
+        assert_eq!(pool.backoff(3), Duration::from_millis(400));
+        assert_eq!(pool.backoff(4), Duration::from_millis(500));
+        assert_eq!(pool.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_failures_bench_endpoint() {
+        let pool = ProviderPool::builder()
+            .endpoints(["http://a", "http://b"])
+            .failure_threshold(2)
+            .build()
+            .unwrap();
+
+        pool.record_failure(0);
+        assert!(pool.is_healthy("http://a"));
+        assert_eq!(pool.candidates(), vec![0, 1]);
+
+        pool.record_failure(0);
+        assert!(!pool.is_healthy("http://a"));
+        assert_eq!(pool.candidates(), vec![1, 0]);
+
+        pool.record_success(0);
+        assert!(pool.is_healthy("http://a"));
+    }
+
+    // ─── failover ────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_fails_over_to_next_endpoint() {
+        let down = MockServer::start().await;
+        let up = MockServer::start().await;
+        mock_status(&down, 503).await;
+        mock_result(&up, "eth_chainId", json!("0x38")).await;
+
+        let client = RpcClient::from_pool(
+            pool(&[down.uri(), up.uri()])
+                .failure_threshold(1)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(client.chain_id().await.unwrap(), 56);
+        assert!(!client.pool().unwrap().is_healthy(&down.uri()));
+
+        // The benched endpoint is skipped on the next request.
+        assert_eq!(client.chain_id().await.unwrap(), 56);
+        assert_eq!(request_count(&down).await, 1);
+        assert_eq!(request_count(&up).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_node_errors_are_not_failed_over() {
+        let first = MockServer::start().await;
+        let second = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32000, "message": "nonce too low" },
+            })))
+            .mount(&first)
+            .await;
+
+        let client = RpcClient::from_pool(pool(&[first.uri(), second.uri()]).build().unwrap());
+        assert!(matches!(
+            client.send_raw_transaction(&[0x02]).await,
+            Err(Error::JsonRpcError { code: -32000, .. })
+        ));
+        assert_eq!(request_count(&second).await, 0);
+        assert!(client.pool().unwrap().is_healthy(&first.uri()));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_fails_over() {
+        let limited = MockServer::start().await;
+        let up = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32005, "message": "limit exceeded" },
+            })))
+            .mount(&limited)
+            .await;
+        mock_result(&up, "eth_blockNumber", json!("0x10")).await;
+
+        let client = RpcClient::from_pool(pool(&[limited.uri(), up.uri()]).build().unwrap());
+        assert_eq!(client.block_number().await.unwrap(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rounds_then_fails() {
+        let a = MockServer::start().await;
+        let b = MockServer::start().await;
+        mock_status(&a, 502).await;
+        mock_status(&b, 502).await;
+
+        let client = RpcClient::from_pool(
+            pool(&[a.uri(), b.uri()])
+                .max_retries(2)
+                .failure_threshold(10)
+                .build()
+                .unwrap(),
+        );
+        assert!(matches!(client.chain_id().await, Err(Error::RpcError(_))));
+        assert_eq!(request_count(&a).await, 3);
+        assert_eq!(request_count(&b).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_all_benched_still_tried() {
+        let server = MockServer::start().await;
+        mock_result(&server, "eth_chainId", json!("0x1")).await;
+
+        let client = RpcClient::from_pool(pool(&[server.uri()]).build().unwrap());
+        client.pool().unwrap().bench(0);
+        assert_eq!(client.chain_id().await.unwrap(), 1);
+        assert!(client.pool().unwrap().is_healthy(&server.uri()));
+    }
+
+    // ─── health checks ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_check_health() {
+        let synced = MockServer::start().await;
+        let lagging = MockServer::start().await;
+        let down = MockServer::start().await;
+        mock_result(&synced, "eth_blockNumber", json!("0x64")).await;
+        mock_result(&lagging, "eth_blockNumber", json!("0x50")).await;
+        mock_status(&down, 500).await;
+
+        let client = RpcClient::from_pool(
+            pool(&[synced.uri(), lagging.uri(), down.uri()])
+                .max_block_lag(10)
+                .build()
+                .unwrap(),
+        );
+        let reports = client.check_health().await;
+
+        assert!(reports[0].healthy);
+        assert_eq!(reports[0].block_number, Some(100));
+        assert!(reports[0].latency.is_some());
+
+        assert!(!reports[1].healthy);
+        assert_eq!(reports[1].block_number, Some(80));
+        assert_eq!(reports[1].error.as_deref(), Some("20 blocks behind"));
+
+        assert!(!reports[2].healthy);
+        assert!(reports[2].error.is_some());
+
+        let pool = client.pool().unwrap();
+        assert!(pool.is_healthy(&synced.uri()));
+        assert!(!pool.is_healthy(&lagging.uri()));
+        assert!(!pool.is_healthy(&down.uri()));
+    }
+
+    #[tokio::test]
+    async fn test_check_health_single_endpoint() {
+        let server = MockServer::start().await;
+        mock_result(&server, "eth_blockNumber", json!("0x1")).await;
+
+        let reports = RpcClient::new(server.uri()).check_health().await;
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].healthy);
+    }
+}
@@ -0,0 +1,404 @@
+//! Transaction receipts and confirmation tracking.
+
+use super::{parse_bytes, parse_hash, parse_u256, parse_u64, BlockId, RpcClient};
+use crate::{Address, Error, Result, Wei};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+/// An event log emitted by a transaction.
+///
+/// Decode it with [`Event::decode_log`](crate::abi::Event::decode_log).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    /// Contract that emitted the log.
+    pub address: Address,
+    /// Indexed topics; `topics[0]` is the event signature hash for non-anonymous events.
+    pub topics: Vec<[u8; 32]>,
+    /// Non-indexed event data.
+    pub data: Vec<u8>,
+    /// Position of the log in the block.
+    pub log_index: Option<u64>,
+    /// Number of the block containing the log; `None` for pending logs.
+    pub block_number: Option<u64>,
+    /// Hash of the transaction that emitted the log; `None` for pending logs.
+    pub transaction_hash: Option<[u8; 32]>,
+    /// `true` if a reorg removed the log from the chain (subscriptions only).
+    pub removed: bool,
+}
+
+/// A mined transaction's receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionReceipt {
+    /// Transaction hash.
+    pub transaction_hash: [u8; 32],
+    /// Hash of the block containing the transaction.
+    pub block_hash: [u8; 32],
+    /// Number of the block containing the transaction.
+    pub block_number: u64,
+    /// `true` if execution succeeded, `false` if it reverted.
+    pub status: bool,
+    /// Gas used by this transaction.
+    pub gas_used: u64,
+    /// Price per gas actually paid, if reported by the node.
+    pub effective_gas_price: Option<Wei>,
+    /// Address of the created contract, for contract creation transactions.
+    pub contract_address: Option<Address>,
+    /// Logs emitted during execution.
+    pub logs: Vec<Log>,
+}
+
+impl TransactionReceipt {
+    /// Returns `true` if the transaction executed successfully.
+    pub fn is_success(&self) -> bool {
+        self.status
+    }
+
+    /// Returns the total fee paid (`gas_used × effective_gas_price`), if known.
+    pub fn fee(&self) -> Option<Wei> {
+        self.effective_gas_price.map(|price| price * self.gas_used)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct RawLog {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    log_index: Option<String>,
+    block_number: Option<String>,
+    transaction_hash: Option<String>,
+    #[serde(default)]
+    removed: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReceipt {
+    transaction_hash: String,
+    block_hash: String,
+    block_number: String,
+    status: Option<String>,
+    gas_used: String,
+    effective_gas_price: Option<String>,
+    contract_address: Option<String>,
+    logs: Vec<RawLog>,
+}
+
+#[derive(Deserialize)]
+struct RawTransaction {
+    from: String,
+    nonce: String,
+}
+
+impl RawLog {
+    pub(super) fn parse(self) -> Result<Log> {
+        Ok(Log {
+            address: self.address.parse()?,
+            topics: self
+                .topics
+                .iter()
+                .map(|t| parse_hash(t))
+                .collect::<Result<_>>()?,
+            data: parse_bytes(&self.data)?,
+            log_index: self.log_index.as_deref().map(parse_u64).transpose()?,
+            block_number: self.block_number.as_deref().map(parse_u64).transpose()?,
+            transaction_hash: self
+                .transaction_hash
+                .as_deref()
+                .map(parse_hash)
+                .transpose()?,
+            removed: self.removed,
+        })
+    }
+}
+
+impl RawReceipt {
+    fn parse(self) -> Result<TransactionReceipt> {
+        Ok(TransactionReceipt {
+            transaction_hash: parse_hash(&self.transaction_hash)?,
+            block_hash: parse_hash(&self.block_hash)?,
+            block_number: parse_u64(&self.block_number)?,
+            // Pre-Byzantium receipts carry a state root instead of a status.
+            status: self.status.as_deref().map(parse_u64).transpose()? != Some(0),
+            gas_used: parse_u64(&self.gas_used)?,
+            effective_gas_price: self
+                .effective_gas_price
+                .as_deref()
+                .map(|p| parse_u256(p).map(Wei::from_u256))
+                .transpose()?,
+            contract_address: self
+                .contract_address
+                .as_deref()
+                .map(str::parse)
+                .transpose()?,
+            logs: self
+                .logs
+                .into_iter()
+                .map(RawLog::parse)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl RpcClient {
+    /// Fetches a transaction receipt (`eth_getTransactionReceipt`).
+    ///
+    /// Returns `Ok(None)` if the transaction is not mined yet or unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the receipt is malformed.
+    pub async fn get_transaction_receipt(
+        &self,
+        tx_hash: &[u8; 32],
+    ) -> Result<Option<TransactionReceipt>> {
+        let raw: Option<RawReceipt> = self
+            .request(
+                "eth_getTransactionReceipt",
+                json!([format!("0x{}", hex::encode(tx_hash))]),
+            )
+            .await?;
+        raw.map(RawReceipt::parse).transpose()
+    }
+
+    /// Waits until `tx_hash` is mined with at least `confirmations` confirmations.
+    ///
+    /// Polls `eth_getTransactionReceipt` and `eth_blockNumber` every
+    /// [`poll interval`](RpcClient::with_poll_interval). The block containing the
+    /// transaction counts as the first confirmation. A receipt that disappears because of
+    /// a reorg is waited for again.
+    ///
+    /// A reverted transaction is still returned as `Ok`; check
+    /// [`TransactionReceipt::status`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::TransactionReplaced`] if the sender's nonce was consumed by another
+    ///   transaction.
+    /// - [`Error::TransactionDropped`] if the node stops knowing the transaction while
+    ///   its nonce is still unused.
+    /// - [`Error::ConfirmationTimeout`] if `timeout` elapses first.
+    /// - Any RPC error from the underlying requests.
+    pub async fn await_confirmation(
+        &self,
+        tx_hash: &[u8; 32],
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<TransactionReceipt> {
+        let hash_hex = format!("0x{}", hex::encode(tx_hash));
+        let deadline = Instant::now() + timeout;
+        // Sender and nonce, once the transaction has been seen.
+        let mut pending: Option<(Address, u64)> = None;
+
+        loop {
+            if let Some(receipt) = self.get_transaction_receipt(tx_hash).await? {
+                let head = self.block_number().await?;
+                let confirmed = head.saturating_sub(receipt.block_number) + 1;
+                if confirmed >= confirmations.max(1) {
+                    return Ok(receipt);
+                }
+            } else {
+                let tx: Option<RawTransaction> = self
+                    .request("eth_getTransactionByHash", json!([hash_hex]))
+                    .await?;
+
+                let seen = tx.is_some();
+                if let Some(tx) = tx {
+                    pending = Some((tx.from.parse()?, parse_u64(&tx.nonce)?));
+                }
+
+                if let Some((from, nonce)) = pending {
+                    let next = self.get_transaction_count(from, BlockId::Latest).await?;
+                    if next > nonce {
+                        // The nonce is used; recheck in case this transaction was just mined.
+                        if self.get_transaction_receipt(tx_hash).await?.is_none() {
+                            return Err(Error::TransactionReplaced(hash_hex));
+                        }
+                        continue;
+                    }
+                    if !seen {
+                        return Err(Error::TransactionDropped(hash_hex));
+                    }
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Err(Error::ConfirmationTimeout(hash_hex));
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const TX_HASH: [u8; 32] = [0xab; 32];
+    const SENDER: &str = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e";
+
+    fn receipt_json(block: u64, status: &str) -> Value {
+        json!({
+            "transactionHash": format!("0x{}", hex::encode(TX_HASH)),
+            "blockHash": format!("0x{}", "11".repeat(32)),
+            "blockNumber": format!("0x{:x}", block),
+            "status": status,
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x3b9aca00",
+            "contractAddress": null,
+            "logs": [{
+                "address": "0x55d398326f99059fF775485246999027B3197955",
+                "topics": [format!("0x{}", "dd".repeat(32))],
+                "data": "0x01",
+                "logIndex": "0x0",
+            }],
+        })
+    }
+
+    async fn mock(server: &MockServer, rpc_method: &str, result: Value, times: Option<u64>) {
+        let mut mock = Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": rpc_method })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+            );
+        if let Some(n) = times {
+            mock = mock.up_to_n_times(n).with_priority(1);
+        }
+        mock.mount(server).await;
+    }
+
+    fn client(server: &MockServer) -> RpcClient {
+        RpcClient::new(server.uri()).with_poll_interval(Duration::from_millis(10))
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_receipt() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "eth_getTransactionReceipt",
+            receipt_json(16, "0x1"),
+            None,
+        )
+        .await;
+
+        let receipt = client(&server)
+            .get_transaction_receipt(&TX_HASH)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(receipt.is_success());
+        assert_eq!(receipt.block_number, 16);
+        assert_eq!(receipt.gas_used, 21_000);
+        assert_eq!(receipt.fee(), Some(Wei::from_gwei(21_000)));
+        assert_eq!(receipt.logs.len(), 1);
+        assert_eq!(receipt.logs[0].topics[0], [0xdd; 32]);
+        assert_eq!(receipt.logs[0].data, vec![1]);
+        assert_eq!(receipt.logs[0].transaction_hash, None);
+        assert!(!receipt.logs[0].removed);
+    }
+
+    #[tokio::test]
+    async fn test_receipt_not_found() {
+        let server = MockServer::start().await;
+        mock(&server, "eth_getTransactionReceipt", Value::Null, None).await;
+
+        assert!(client(&server)
+            .get_transaction_receipt(&TX_HASH)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_await_confirmation_waits_for_depth() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "eth_getTransactionReceipt",
+            receipt_json(16, "0x0"),
+            None,
+        )
+        .await;
+        // Head is at the receipt block for the first two polls, then 2 blocks later
+        mock(&server, "eth_blockNumber", json!("0x10"), Some(2)).await;
+        mock(&server, "eth_blockNumber", json!("0x12"), None).await;
+
+        let receipt = client(&server)
+            .await_confirmation(&TX_HASH, 3, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert!(!receipt.is_success());
+        let block_polls = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| String::from_utf8_lossy(&r.body).contains("eth_blockNumber"))
+            .count();
+        assert_eq!(block_polls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_await_confirmation_replaced() {
+        let server = MockServer::start().await;
+        mock(&server, "eth_getTransactionReceipt", Value::Null, None).await;
+        mock(
+            &server,
+            "eth_getTransactionByHash",
+            json!({ "from": SENDER, "nonce": "0x1" }),
+            Some(1),
+        )
+        .await;
+        mock(&server, "eth_getTransactionByHash", Value::Null, None).await;
+        mock(&server, "eth_getTransactionCount", json!("0x2"), None).await;
+
+        let err = client(&server)
+            .await_confirmation(&TX_HASH, 1, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionReplaced(_)));
+    }
+
+    #[tokio::test]
+    async fn test_await_confirmation_dropped() {
+        let server = MockServer::start().await;
+        mock(&server, "eth_getTransactionReceipt", Value::Null, None).await;
+        mock(
+            &server,
+            "eth_getTransactionByHash",
+            json!({ "from": SENDER, "nonce": "0x1" }),
+            Some(1),
+        )
+        .await;
+        mock(&server, "eth_getTransactionByHash", Value::Null, None).await;
+        mock(&server, "eth_getTransactionCount", json!("0x1"), None).await;
+
+        let err = client(&server)
+            .await_confirmation(&TX_HASH, 1, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::TransactionDropped(_)));
+    }
+
+    #[tokio::test]
+    async fn test_await_confirmation_timeout() {
+        let server = MockServer::start().await;
+        mock(&server, "eth_getTransactionReceipt", Value::Null, None).await;
+        mock(&server, "eth_getTransactionByHash", Value::Null, None).await;
+
+        let err = client(&server)
+            .await_confirmation(&TX_HASH, 1, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ConfirmationTimeout(_)));
+    }
+}
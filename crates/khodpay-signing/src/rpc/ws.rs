@@ -0,0 +1,777 @@
+//! WebSocket JSON-RPC transport with `eth_subscribe` (requires the `ws` feature).
+//!
+//! Over HTTP a wallet has to poll for new blocks, balances and incoming payments.
+//! [`WsClient`] keeps one connection open and lets the node push them instead:
+//!
+//! - [`WsClient::subscribe_new_heads`]: one [`BlockHeader`] per new block, e.g. to refresh
+//!   balances;
+//! - [`WsClient::subscribe_pending_transactions`]: hashes of transactions entering the
+//!   mempool;
+//! - [`WsClient::subscribe_logs`]: event [`Log`]s matching a [`LogFilter`], e.g. incoming
+//!   token transfers.
+//!
+//! # Quick Start
+//!
+//! ```rust,ignore
+//! use khodpay_signing::rpc::{LogFilter, WsClient};
+//!
+//! let client = WsClient::connect("wss://bsc-rpc.publicnode.com").await?;
+//!
+//! let mut incoming = client
+//!     .subscribe_logs(&LogFilter::token_transfers_to(wallet).address(usdt))
+//!     .await?;
+//! while let Some(log) = incoming.next().await {
+//!     let log = log?;
+//!     // ... decode the Transfer event and credit the payment ...
+//! }
+//! ```
+
+use super::receipt::RawLog;
+use super::{parse_hash, parse_u256, parse_u64, ErrorObject, Log, LogFilter};
+use crate::{Error, Result, Wei};
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// The header fields of a block announced by `newHeads`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Block number.
+    pub number: u64,
+    /// Block hash.
+    pub hash: [u8; 32],
+    /// Hash of the parent block.
+    pub parent_hash: [u8; 32],
+    /// Block timestamp in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// EIP-1559 base fee, absent on pre-London chains.
+    pub base_fee_per_gas: Option<Wei>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawHeader {
+    number: String,
+    hash: String,
+    parent_hash: String,
+    timestamp: String,
+    base_fee_per_gas: Option<String>,
+}
+
+impl RawHeader {
+    fn parse(self) -> Result<BlockHeader> {
+        Ok(BlockHeader {
+            number: parse_u64(&self.number)?,
+            hash: parse_hash(&self.hash)?,
+            parent_hash: parse_hash(&self.parent_hash)?,
+            timestamp: parse_u64(&self.timestamp)?,
+            base_fee_per_gas: self
+                .base_fee_per_gas
+                .as_deref()
+                .map(|fee| parse_u256(fee).map(Wei::from_u256))
+                .transpose()?,
+        })
+    }
+}
+
+/// A request waiting for its response.
+struct PendingRequest {
+    reply: oneshot::Sender<Result<Value>>,
+    /// Set for `eth_subscribe`: receives the notifications once the ID is known.
+    notifications: Option<mpsc::UnboundedSender<Value>>,
+}
+
+/// State shared between the client, its subscriptions and the reader task.
+#[derive(Default)]
+struct Shared {
+    pending: HashMap<u64, PendingRequest>,
+    subscriptions: HashMap<String, mpsc::UnboundedSender<Value>>,
+    closed: bool,
+}
+
+/// The sending half of a connection, cloned into every [`Subscription`].
+#[derive(Clone)]
+struct Handle {
+    outgoing: mpsc::UnboundedSender<Message>,
+    shared: Arc<Mutex<Shared>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Handle {
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        lock(&self.shared)
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        params: Value,
+        notifications: Option<mpsc::UnboundedSender<Value>>,
+    ) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (reply, response) = oneshot::channel();
+        {
+            let mut shared = self.lock();
+            if shared.closed {
+                return Err(closed_error(method));
+            }
+            shared.pending.insert(
+                id,
+                PendingRequest {
+                    reply,
+                    notifications,
+                },
+            );
+        }
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        if self.outgoing.send(Message::Text(body.to_string())).is_err() {
+            self.lock().pending.remove(&id);
+            return Err(closed_error(method));
+        }
+
+        response.await.map_err(|_| closed_error(method))?
+    }
+
+    /// Sends `eth_unsubscribe` without waiting for the answer.
+    fn unsubscribe_now(&self, id: &str) {
+        self.lock().subscriptions.remove(id);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": "eth_unsubscribe",
+            "params": [id],
+        });
+        let _ = self.outgoing.send(Message::Text(body.to_string()));
+    }
+}
+
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_, Shared> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn closed_error(method: &str) -> Error {
+    Error::RpcError(format!("{} request failed: connection closed", method))
+}
+
+/// Async JSON-RPC client over a WebSocket connection.
+///
+/// One background task reads the socket and routes responses and subscription
+/// notifications, another writes outgoing requests. Dropping the client closes the
+/// connection and ends all of its subscriptions.
+pub struct WsClient {
+    url: String,
+    handle: Handle,
+    reader: JoinHandle<()>,
+    writer: JoinHandle<()>,
+}
+
+impl fmt::Debug for WsClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsClient").field("url", &self.url).finish()
+    }
+}
+
+impl Drop for WsClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+        self.writer.abort();
+    }
+}
+
+impl WsClient {
+    /// Connects to a `ws://` or `wss://` endpoint.
+    ///
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RpcError`] if the connection or handshake fails.
+    pub async fn connect(url: impl Into<String>) -> Result<Self> {
+        let url = url.into();
+        let (socket, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .map_err(|e| Error::RpcError(format!("WebSocket connection failed: {}", e)))?;
+        Ok(Self::start(url, socket))
+    }
+
+    /// Connects to a `ws://` or `wss://` endpoint over an already open `stream`,
+    /// such as a connection through a SOCKS5 proxy.
+    ///
+    /// The stream must lead to the host in `url`; `wss://` runs TLS over it, checked
+    /// against that host name. Must be called from within a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RpcError`] if the TLS or WebSocket handshake fails.
+    pub async fn connect_with_stream<S>(url: impl Into<String>, stream: S) -> Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let url = url.into();
+        let (socket, _) = tokio_tungstenite::client_async_tls(url.as_str(), stream)
+            .await
+            .map_err(|e| Error::RpcError(format!("WebSocket connection failed: {}", e)))?;
+        Ok(Self::start(url, socket))
+    }
+
+    /// Spawns the reader and writer tasks of a connected socket.
+    fn start<S>(url: String, socket: WebSocketStream<S>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (mut sink, mut stream) = socket.split();
+
+        let (outgoing, mut queue) = mpsc::unbounded_channel::<Message>();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+
+        let writer = tokio::spawn(async move {
+            while let Some(message) = queue.recv().await {
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        let reader_shared = Arc::clone(&shared);
+        let reader = tokio::spawn(async move {
+            while let Some(message) = stream.next().await {
+                match message {
+                    Ok(Message::Text(text)) => dispatch(&reader_shared, &text),
+                    Ok(Message::Close(_)) | Err(_) => break,
+                    Ok(_) => {}
+                }
+            }
+            close(&reader_shared);
+        });
+
+        Self {
+            url,
+            handle: Handle {
+                outgoing,
+                shared,
+                next_id: Arc::new(AtomicU64::new(1)),
+            },
+            reader,
+            writer,
+        }
+    }
+
+    /// Returns the endpoint URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Sends a raw JSON-RPC request and deserializes the `result` field.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RpcError`] if the connection is closed or the result cannot be
+    /// decoded, and [`Error::JsonRpcError`] if the node returns an error object.
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let result = self.handle.call(method, params, None).await?;
+        serde_json::from_value(result)
+            .map_err(|e| Error::RpcError(format!("{} returned unexpected result: {}", method, e)))
+    }
+
+    /// Subscribes to new block headers (`eth_subscribe("newHeads")`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node rejects the subscription.
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<BlockHeader>> {
+        self.subscribe(json!(["newHeads"]), |value| {
+            serde_json::from_value::<RawHeader>(value)
+                .map_err(|e| Error::RpcError(format!("invalid block header: {}", e)))?
+                .parse()
+        })
+        .await
+    }
+
+    /// Subscribes to hashes of new pending transactions
+    /// (`eth_subscribe("newPendingTransactions")`).
+    ///
+    /// Many public endpoints do not offer this subscription.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node rejects the subscription.
+    pub async fn subscribe_pending_transactions(&self) -> Result<Subscription<[u8; 32]>> {
+        self.subscribe(json!(["newPendingTransactions"]), |value| match value {
+            Value::String(hash) => parse_hash(&hash),
+            other => Err(Error::RpcError(format!(
+                "expected a transaction hash, got {}",
+                other
+            ))),
+        })
+        .await
+    }
+
+    /// Subscribes to event logs matching `filter` (`eth_subscribe("logs")`).
+    ///
+    /// Logs removed by a reorg are delivered again with [`Log::removed`] set; confirm
+    /// payments against a later block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node rejects the subscription.
+    pub async fn subscribe_logs(&self, filter: &LogFilter) -> Result<Subscription<Log>> {
+        self.subscribe(json!(["logs", filter.to_json()]), |value| {
+            serde_json::from_value::<RawLog>(value)
+                .map_err(|e| Error::RpcError(format!("invalid log: {}", e)))?
+                .parse()
+        })
+        .await
+    }
+
+    async fn subscribe<T>(
+        &self,
+        params: Value,
+        parse: fn(Value) -> Result<T>,
+    ) -> Result<Subscription<T>> {
+        let (notifications, receiver) = mpsc::unbounded_channel();
+        let id = self
+            .handle
+            .call("eth_subscribe", params, Some(notifications))
+            .await?;
+        let id = match id {
+            Value::String(id) => id,
+            other => {
+                return Err(Error::RpcError(format!(
+                    "eth_subscribe returned unexpected result: {}",
+                    other
+                )))
+            }
+        };
+
+        Ok(Subscription {
+            id,
+            handle: self.handle.clone(),
+            receiver,
+            parse,
+            active: true,
+        })
+    }
+}
+
+/// A stream of subscription notifications.
+///
+/// Dropping it unsubscribes in the background; [`unsubscribe`](Self::unsubscribe) does
+/// so and waits for the node's answer.
+pub struct Subscription<T> {
+    id: String,
+    handle: Handle,
+    receiver: mpsc::UnboundedReceiver<Value>,
+    parse: fn(Value) -> Result<T>,
+    active: bool,
+}
+
+impl<T> fmt::Debug for Subscription<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl<T> Subscription<T> {
+    /// Returns the subscription ID assigned by the node.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Waits for the next notification.
+    ///
+    /// Returns `None` once the connection is closed. A notification that cannot be
+    /// decoded is returned as an error and does not end the stream.
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        let value = self.receiver.recv().await?;
+        Some((self.parse)(value))
+    }
+
+    /// Cancels the subscription (`eth_unsubscribe`).
+    ///
+    /// Returns the node's answer: `true` if the subscription existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection is closed.
+    pub async fn unsubscribe(mut self) -> Result<bool> {
+        self.active = false;
+        self.handle.lock().subscriptions.remove(&self.id);
+        let result = self
+            .handle
+            .call("eth_unsubscribe", json!([self.id]), None)
+            .await?;
+        Ok(result.as_bool().unwrap_or(false))
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        if self.active {
+            self.handle.unsubscribe_now(&self.id);
+        }
+    }
+}
+
+/// Routes one incoming text frame to its request or subscription.
+fn dispatch(shared: &Mutex<Shared>, text: &str) {
+    let Ok(message) = serde_json::from_str::<Value>(text) else {
+        return;
+    };
+
+    if let Some(id) = message.get("id").and_then(Value::as_u64) {
+        let mut shared = lock(shared);
+        let Some(pending) = shared.pending.remove(&id) else {
+            return;
+        };
+        let result = match message.get("error") {
+            Some(error) if !error.is_null() => match ErrorObject::deserialize(error) {
+                Ok(error) => Err(Error::JsonRpcError {
+                    code: error.code,
+                    message: error.message,
+                }),
+                Err(e) => Err(Error::RpcError(format!("invalid error object: {}", e))),
+            },
+            _ => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        // Register before the next frame is read, so no notification can be missed.
+        if let (Ok(Value::String(subscription)), Some(notifications)) =
+            (&result, pending.notifications)
+        {
+            shared
+                .subscriptions
+                .insert(subscription.clone(), notifications);
+        }
+        let _ = pending.reply.send(result);
+        return;
+    }
+
+    if message.get("method").and_then(Value::as_str) == Some("eth_subscription") {
+        let params = &message["params"];
+        let Some(subscription) = params.get("subscription").and_then(Value::as_str) else {
+            return;
+        };
+        let mut shared = lock(shared);
+        let delivered = shared
+            .subscriptions
+            .get(subscription)
+            .is_some_and(|sender| sender.send(params["result"].clone()).is_ok());
+        if !delivered {
+            shared.subscriptions.remove(subscription);
+        }
+    }
+}
+
+/// Fails every pending request and ends every subscription.
+fn close(shared: &Mutex<Shared>) {
+    let mut shared = lock(shared);
+    shared.closed = true;
+    shared.subscriptions.clear();
+    for (_, pending) in shared.pending.drain() {
+        let _ = pending.reply.send(Err(Error::RpcError(
+            "connection closed before a response arrived".to_string(),
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+    use tokio::net::{TcpListener, TcpStream};
+
+    type Server = WebSocketStream<tokio::net::TcpStream>;
+
+    /// Starts a one-connection server and returns its URL and the accepted socket.
+    async fn start() -> (String, tokio::task::JoinHandle<Server>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let accept = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio_tungstenite::accept_async(stream).await.unwrap()
+        });
+        (url, accept)
+    }
+
+    async fn receive(server: &mut Server) -> Value {
+        loop {
+            if let Message::Text(text) = server.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    async fn send(server: &mut Server, value: Value) {
+        server.send(Message::Text(value.to_string())).await.unwrap();
+    }
+
+    async fn reply(server: &mut Server, request: &Value, result: Value) {
+        send(
+            server,
+            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+        )
+        .await;
+    }
+
+    async fn notify(server: &mut Server, subscription: &str, result: Value) {
+        send(
+            server,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": subscription, "result": result },
+            }),
+        )
+        .await;
+    }
+
+    fn hash(byte: u8) -> String {
+        format!("0x{}", hex::encode([byte; 32]))
+    }
+
+    #[tokio::test]
+    async fn test_request() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let call = tokio::spawn(async move {
+            let id: String = client.request("eth_chainId", json!([])).await.unwrap();
+            id
+        });
+        let request = receive(&mut server).await;
+        assert_eq!(request["method"], "eth_chainId");
+        reply(&mut server, &request, json!("0x38")).await;
+
+        assert_eq!(call.await.unwrap(), "0x38");
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_stream() {
+        let (url, accept) = start().await;
+        let stream = TcpStream::connect(url.trim_start_matches("ws://"))
+            .await
+            .unwrap();
+        let client = WsClient::connect_with_stream("ws://node.example:8546", stream)
+            .await
+            .unwrap();
+        assert_eq!(client.url(), "ws://node.example:8546");
+        let mut server = accept.await.unwrap();
+
+        let call = tokio::spawn(async move {
+            let id: String = client.request("eth_chainId", json!([])).await.unwrap();
+            id
+        });
+        let request = receive(&mut server).await;
+        reply(&mut server, &request, json!("0x38")).await;
+        assert_eq!(call.await.unwrap(), "0x38");
+    }
+
+    #[tokio::test]
+    async fn test_request_error_object() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let call = tokio::spawn(async move { client.request::<Value>("eth_foo", json!([])).await });
+        let request = receive(&mut server).await;
+        send(
+            &mut server,
+            json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32601, "message": "method not found" },
+            }),
+        )
+        .await;
+
+        assert!(matches!(
+            call.await.unwrap(),
+            Err(Error::JsonRpcError { code: -32601, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_new_heads() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let subscribe = tokio::spawn(async move {
+            let subscription = client.subscribe_new_heads().await.unwrap();
+            (client, subscription)
+        });
+        let request = receive(&mut server).await;
+        assert_eq!(request["method"], "eth_subscribe");
+        assert_eq!(request["params"], json!(["newHeads"]));
+        reply(&mut server, &request, json!("0xabc")).await;
+        // Sent right behind the response, before the caller has seen it.
+        notify(
+            &mut server,
+            "0xabc",
+            json!({
+                "number": "0x10",
+                "hash": hash(1),
+                "parentHash": hash(2),
+                "timestamp": "0x64",
+                "baseFeePerGas": "0x3b9aca00",
+            }),
+        )
+        .await;
+
+        let (_client, mut heads) = subscribe.await.unwrap();
+        assert_eq!(heads.id(), "0xabc");
+        let header = heads.next().await.unwrap().unwrap();
+        assert_eq!(header.number, 16);
+        assert_eq!(header.hash, [1u8; 32]);
+        assert_eq!(header.parent_hash, [2u8; 32]);
+        assert_eq!(header.timestamp, 100);
+        assert_eq!(header.base_fee_per_gas, Some(Wei::from_gwei(1)));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_logs_and_pending() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+        let wallet = Address::from_bytes([0x11; 20]);
+
+        let subscribe = tokio::spawn(async move {
+            let logs = client
+                .subscribe_logs(&LogFilter::token_transfers_to(wallet))
+                .await
+                .unwrap();
+            let pending = client.subscribe_pending_transactions().await.unwrap();
+            (client, logs, pending)
+        });
+
+        let request = receive(&mut server).await;
+        assert_eq!(
+            request["params"],
+            json!(["logs", LogFilter::token_transfers_to(wallet).to_json()])
+        );
+        reply(&mut server, &request, json!("0x1")).await;
+        let request = receive(&mut server).await;
+        assert_eq!(request["params"], json!(["newPendingTransactions"]));
+        reply(&mut server, &request, json!("0x2")).await;
+
+        notify(&mut server, "0x2", json!(hash(9))).await;
+        notify(
+            &mut server,
+            "0x1",
+            json!({
+                "address": "0x55d398326f99059fF775485246999027B3197955",
+                "topics": [hash(3)],
+                "data": "0x01",
+                "logIndex": "0x0",
+                "blockNumber": "0x10",
+                "transactionHash": hash(4),
+                "removed": true,
+            }),
+        )
+        .await;
+
+        let (_client, mut logs, mut pending) = subscribe.await.unwrap();
+        assert_eq!(pending.next().await.unwrap().unwrap(), [9u8; 32]);
+        let log = logs.next().await.unwrap().unwrap();
+        assert_eq!(log.topics, vec![[3u8; 32]]);
+        assert_eq!(log.data, vec![0x01]);
+        assert_eq!(log.block_number, Some(16));
+        assert_eq!(log.transaction_hash, Some([4u8; 32]));
+        assert!(log.removed);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_notification_does_not_end_stream() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let subscribe = tokio::spawn(async move {
+            let pending = client.subscribe_pending_transactions().await.unwrap();
+            (client, pending)
+        });
+        let request = receive(&mut server).await;
+        reply(&mut server, &request, json!("0x7")).await;
+        notify(&mut server, "0x7", json!(42)).await;
+        notify(&mut server, "0x7", json!(hash(5))).await;
+
+        let (_client, mut pending) = subscribe.await.unwrap();
+        assert!(pending.next().await.unwrap().is_err());
+        assert_eq!(pending.next().await.unwrap().unwrap(), [5u8; 32]);
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let task = tokio::spawn(async move {
+            let heads = client.subscribe_new_heads().await.unwrap();
+            heads.unsubscribe().await.unwrap()
+        });
+        let request = receive(&mut server).await;
+        reply(&mut server, &request, json!("0xabc")).await;
+        let request = receive(&mut server).await;
+        assert_eq!(request["method"], "eth_unsubscribe");
+        assert_eq!(request["params"], json!(["0xabc"]));
+        reply(&mut server, &request, json!(true)).await;
+
+        assert!(task.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_connection_closed() {
+        let (url, accept) = start().await;
+        let client = WsClient::connect(&url).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let subscribe = tokio::spawn(async move {
+            let pending = client.subscribe_pending_transactions().await.unwrap();
+            (client, pending)
+        });
+        let request = receive(&mut server).await;
+        reply(&mut server, &request, json!("0x1")).await;
+        let (client, mut pending) = subscribe.await.unwrap();
+
+        server.close(None).await.unwrap();
+        drop(server);
+
+        assert!(pending.next().await.is_none());
+        assert!(matches!(
+            client.request::<Value>("eth_chainId", json!([])).await,
+            Err(Error::RpcError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connect_failure() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        assert!(matches!(
+            WsClient::connect(url).await,
+            Err(Error::RpcError(_))
+        ));
+    }
+}
@@ -0,0 +1,645 @@
+//! Safe (formerly Gnosis Safe) multisig transactions.
+//!
+//! Lets a [`Bip44Signer`] act as an owner of a
+//! [Safe](https://github.com/safe-global/safe-smart-account) multisig: hash a `SafeTx`
+//! with EIP-712, sign it, pack the owners' signatures in the order the contract
+//! expects, and encode the `execTransaction` call that executes it.
+//!
+//! The domain is `EIP712Domain(uint256 chainId,address verifyingContract)`, used by
+//! Safe v1.3.0 and later.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::safe::{self, SafeTransaction};
+//! use khodpay_signing::{Address, Bip44Signer, Wei};
+//!
+//! let safe_address: Address = "0x55d398326f99059fF775485246999027B3197955".parse().unwrap();
+//! let owner_a = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+//! let owner_b = Bip44Signer::from_private_key(&[2u8; 32]).unwrap();
+//!
+//! let tx = SafeTransaction::builder()
+//!     .to("0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse().unwrap())
+//!     .value(Wei::from_ether(1))
+//!     .nonce(0)
+//!     .build()
+//!     .unwrap();
+//!
+//! let signatures = [
+//!     safe::sign_safe_transaction(&owner_a, &tx, safe_address, 1).unwrap(),
+//!     safe::sign_safe_transaction(&owner_b, &tx, safe_address, 1).unwrap(),
+//! ];
+//!
+//! let packed = safe::pack_signatures(&signatures).unwrap();
+//! let calldata = safe::encode_exec_transaction(&tx, &packed);
+//! assert_eq!(&calldata[..4], &safe::EXEC_TRANSACTION_SELECTOR);
+//! ```
+
+use crate::abi::{encode_with_selector, Token};
+use crate::eip712::{
+    encode_address, encode_bytes_dynamic, encode_uint64, hash_typed_data, Eip712Domain, Eip712Type,
+};
+use crate::{recover_signer, Address, Bip44Signer, Error, Result, Signature, Wei};
+use primitive_types::U256;
+
+/// EIP-712 type string of a Safe transaction.
+pub const SAFE_TX_TYPE: &str = "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)";
+
+/// Selector of `execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)`.
+pub const EXEC_TRANSACTION_SELECTOR: [u8; 4] = [0x6a, 0x76, 0x12, 0x02];
+
+/// Selector of `approveHash(bytes32)`.
+pub const APPROVE_HASH_SELECTOR: [u8; 4] = [0xd4, 0xd9, 0xbd, 0xcd];
+
+/// Selector of `nonce()`.
+pub const NONCE_SELECTOR: [u8; 4] = [0xaf, 0xfe, 0xd0, 0xe0];
+
+/// How the Safe executes the inner call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum Operation {
+    /// A regular `CALL`.
+    #[default]
+    Call = 0,
+    /// A `DELEGATECALL`, running the target's code in the Safe's context.
+    DelegateCall = 1,
+}
+
+// ─── SafeTx ──────────────────────────────────────────────────────────────────
+
+/// A transaction to be executed by a Safe.
+///
+/// The refund fields (`safe_tx_gas`, `base_gas`, `gas_price`, `gas_token`,
+/// `refund_receiver`) default to zero, meaning the executor pays gas without a refund.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafeTransaction {
+    /// Target of the inner call.
+    pub to: Address,
+    /// Native value sent with the inner call.
+    pub value: Wei,
+    /// Calldata of the inner call.
+    pub data: Vec<u8>,
+    /// Call or delegate call.
+    pub operation: Operation,
+    /// Gas reserved for the inner call.
+    pub safe_tx_gas: u64,
+    /// Gas overhead paid independently of the inner call (signature checks, refund).
+    pub base_gas: u64,
+    /// Gas price used for the refund.
+    pub gas_price: Wei,
+    /// Token used for the refund (`Address::ZERO` for the native currency).
+    pub gas_token: Address,
+    /// Refund recipient (`Address::ZERO` for `tx.origin`).
+    pub refund_receiver: Address,
+    /// The Safe's transaction nonce.
+    pub nonce: u64,
+}
+
+impl SafeTransaction {
+    /// Returns a builder for constructing a Safe transaction.
+    pub fn builder() -> SafeTransactionBuilder {
+        SafeTransactionBuilder::default()
+    }
+
+    /// Computes the `safeTxHash` that owners sign.
+    ///
+    /// Equal to the Safe's `getTransactionHash(...)` for the same parameters.
+    pub fn hash(&self, safe: Address, chain_id: u64) -> [u8; 32] {
+        hash_typed_data(&safe_domain(safe, chain_id), self)
+    }
+}
+
+impl Eip712Type for SafeTransaction {
+    fn type_string() -> &'static str {
+        SAFE_TX_TYPE
+    }
+
+    fn encode_data(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(32 * 10);
+        buf.extend_from_slice(&encode_address(&self.to));
+        buf.extend_from_slice(&u256_word(self.value.as_u256()));
+        buf.extend_from_slice(&encode_bytes_dynamic(&self.data));
+        buf.extend_from_slice(&encode_uint64(self.operation as u64));
+        buf.extend_from_slice(&encode_uint64(self.safe_tx_gas));
+        buf.extend_from_slice(&encode_uint64(self.base_gas));
+        buf.extend_from_slice(&u256_word(self.gas_price.as_u256()));
+        buf.extend_from_slice(&encode_address(&self.gas_token));
+        buf.extend_from_slice(&encode_address(&self.refund_receiver));
+        buf.extend_from_slice(&encode_uint64(self.nonce));
+        buf
+    }
+}
+
+/// Builder for [`SafeTransaction`].
+#[derive(Debug, Clone, Default)]
+pub struct SafeTransactionBuilder {
+    to: Option<Address>,
+    value: Option<Wei>,
+    data: Vec<u8>,
+    operation: Operation,
+    safe_tx_gas: u64,
+    base_gas: u64,
+    gas_price: Option<Wei>,
+    gas_token: Option<Address>,
+    refund_receiver: Option<Address>,
+    nonce: Option<u64>,
+}
+
+impl SafeTransactionBuilder {
+    /// Sets the target of the inner call.
+    pub fn to(mut self, to: Address) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Sets the native value.
+    pub fn value(mut self, value: Wei) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Sets the inner calldata.
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the operation type.
+    pub fn operation(mut self, operation: Operation) -> Self {
+        self.operation = operation;
+        self
+    }
+
+    /// Sets the gas reserved for the inner call.
+    pub fn safe_tx_gas(mut self, gas: u64) -> Self {
+        self.safe_tx_gas = gas;
+        self
+    }
+
+    /// Sets the base gas used for the refund calculation.
+    pub fn base_gas(mut self, gas: u64) -> Self {
+        self.base_gas = gas;
+        self
+    }
+
+    /// Sets the refund gas price.
+    pub fn gas_price(mut self, price: Wei) -> Self {
+        self.gas_price = Some(price);
+        self
+    }
+
+    /// Sets the refund token.
+    pub fn gas_token(mut self, token: Address) -> Self {
+        self.gas_token = Some(token);
+        self
+    }
+
+    /// Sets the refund receiver.
+    pub fn refund_receiver(mut self, receiver: Address) -> Self {
+        self.refund_receiver = Some(receiver);
+        self
+    }
+
+    /// Sets the Safe nonce.
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Builds the Safe transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `to` or `nonce` is missing.
+    pub fn build(self) -> Result<SafeTransaction> {
+        let to = self
+            .to
+            .ok_or_else(|| Error::ValidationError("to is required".to_string()))?;
+        let nonce = self
+            .nonce
+            .ok_or_else(|| Error::ValidationError("nonce is required".to_string()))?;
+
+        Ok(SafeTransaction {
+            to,
+            value: self.value.unwrap_or(Wei::ZERO),
+            data: self.data,
+            operation: self.operation,
+            safe_tx_gas: self.safe_tx_gas,
+            base_gas: self.base_gas,
+            gas_price: self.gas_price.unwrap_or(Wei::ZERO),
+            gas_token: self.gas_token.unwrap_or(Address::ZERO),
+            refund_receiver: self.refund_receiver.unwrap_or(Address::ZERO),
+            nonce,
+        })
+    }
+}
+
+/// Returns the EIP-712 domain of a Safe (v1.3.0+).
+pub fn safe_domain(safe: Address, chain_id: u64) -> Eip712Domain {
+    Eip712Domain::builder()
+        .chain_id(chain_id)
+        .verifying_contract(safe)
+        .build()
+}
+
+// ─── Signatures ──────────────────────────────────────────────────────────────
+
+/// One owner's confirmation of a Safe transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeSignature {
+    /// An ECDSA signature over the `safeTxHash`.
+    Ecdsa {
+        /// The signing owner.
+        owner: Address,
+        /// The signature; `v` may be a recovery ID or `27` / `28`.
+        signature: Signature,
+    },
+    /// A prior on-chain `approveHash` by the owner, or the executor being an owner.
+    ApprovedHash {
+        /// The approving owner.
+        owner: Address,
+    },
+}
+
+impl SafeSignature {
+    /// Returns the owner this signature belongs to.
+    pub fn owner(&self) -> Address {
+        match self {
+            Self::Ecdsa { owner, .. } | Self::ApprovedHash { owner } => *owner,
+        }
+    }
+
+    /// Encodes the 65-byte signature slot the Safe contract expects.
+    ///
+    /// ECDSA signatures are `r ‖ s ‖ v` with `v` in `27` / `28`. Approved hashes are
+    /// `owner (32 bytes) ‖ 0 (32 bytes) ‖ 1`.
+    pub fn to_bytes(&self) -> [u8; 65] {
+        match self {
+            Self::Ecdsa { signature, .. } => {
+                let mut bytes = signature.to_bytes();
+                if bytes[64] < 27 {
+                    bytes[64] += 27;
+                }
+                bytes
+            }
+            Self::ApprovedHash { owner } => {
+                let mut bytes = [0u8; 65];
+                bytes[..32].copy_from_slice(&encode_address(owner));
+                bytes[64] = 1;
+                bytes
+            }
+        }
+    }
+}
+
+/// Signs a Safe transaction as one of its owners.
+///
+/// # Errors
+///
+/// Returns [`Error::ChainIdMismatch`] if the signer is bound to a different chain,
+/// [`Error::PolicyViolation`] if the signer's policy refuses the transaction, or an
+/// error if signing fails.
+pub fn sign_safe_transaction(
+    signer: &Bip44Signer,
+    tx: &SafeTransaction,
+    safe: Address,
+    chain_id: u64,
+) -> Result<SafeSignature> {
+    signer.check_chain_id(chain_id)?;
+    if let Some(policy) = signer.policy() {
+        policy.check_safe_transaction(tx, chain_id)?;
+    }
+    let signature = signer.sign_hash(&tx.hash(safe, chain_id))?;
+    Ok(SafeSignature::Ecdsa {
+        owner: signer.address(),
+        signature,
+    })
+}
+
+/// Recovers the owner that produced an ECDSA signature over a `safeTxHash`.
+///
+/// # Errors
+///
+/// Returns an error if the signature is malformed or recovery fails.
+pub fn recover_safe_signer(safe_tx_hash: &[u8; 32], signature: &Signature) -> Result<Address> {
+    recover_signer(safe_tx_hash, signature)
+}
+
+/// Concatenates owner signatures for `execTransaction`.
+///
+/// The Safe requires signatures sorted by owner address in ascending order; this
+/// function sorts them.
+///
+/// # Errors
+///
+/// Returns [`Error::ValidationError`] if `signatures` is empty or contains the same
+/// owner twice.
+pub fn pack_signatures(signatures: &[SafeSignature]) -> Result<Vec<u8>> {
+    if signatures.is_empty() {
+        return Err(Error::ValidationError(
+            "at least one signature is required".to_string(),
+        ));
+    }
+
+    let mut sorted = signatures.to_vec();
+    sorted.sort_by_key(|sig| sig.owner().to_bytes());
+    if let Some(pair) = sorted.windows(2).find(|w| w[0].owner() == w[1].owner()) {
+        return Err(Error::ValidationError(format!(
+            "duplicate signature from owner {}",
+            pair[0].owner()
+        )));
+    }
+
+    Ok(sorted.iter().flat_map(SafeSignature::to_bytes).collect())
+}
+
+// ─── Calldata ────────────────────────────────────────────────────────────────
+
+/// Encodes `execTransaction(...)` calldata for a Safe transaction and packed signatures.
+pub fn encode_exec_transaction(tx: &SafeTransaction, signatures: &[u8]) -> Vec<u8> {
+    encode_with_selector(
+        EXEC_TRANSACTION_SELECTOR,
+        &[
+            Token::Address(tx.to),
+            Token::Uint(tx.value.as_u256()),
+            Token::Bytes(tx.data.clone()),
+            Token::Uint(U256::from(tx.operation as u8)),
+            Token::Uint(U256::from(tx.safe_tx_gas)),
+            Token::Uint(U256::from(tx.base_gas)),
+            Token::Uint(tx.gas_price.as_u256()),
+            Token::Address(tx.gas_token),
+            Token::Address(tx.refund_receiver),
+            Token::Bytes(signatures.to_vec()),
+        ],
+    )
+}
+
+/// Encodes `approveHash(hashToApprove)` calldata, for owners confirming on-chain.
+pub fn encode_approve_hash(safe_tx_hash: &[u8; 32]) -> Vec<u8> {
+    encode_with_selector(
+        APPROVE_HASH_SELECTOR,
+        &[Token::FixedBytes(safe_tx_hash.to_vec())],
+    )
+}
+
+/// Encodes `nonce()` calldata, to read the Safe's next transaction nonce.
+pub fn encode_nonce() -> Vec<u8> {
+    NONCE_SELECTOR.to_vec()
+}
+
+fn u256_word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{self, ParamType};
+    use crate::eip712::keccak256;
+
+    fn safe_address() -> Address {
+        "0x55d398326f99059fF775485246999027B3197955"
+            .parse()
+            .unwrap()
+    }
+
+    fn recipient() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    fn test_tx() -> SafeTransaction {
+        SafeTransaction::builder()
+            .to(recipient())
+            .value(Wei::from_ether(1))
+            .data(vec![0xde, 0xad])
+            .nonce(3)
+            .build()
+            .unwrap()
+    }
+
+    // ─── constants ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(
+            EXEC_TRANSACTION_SELECTOR,
+            abi::selector("execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)")
+        );
+        assert_eq!(APPROVE_HASH_SELECTOR, abi::selector("approveHash(bytes32)"));
+        assert_eq!(NONCE_SELECTOR, abi::selector("nonce()"));
+    }
+
+    #[test]
+    fn test_type_hashes_match_contract() {
+        // SAFE_TX_TYPEHASH and DOMAIN_SEPARATOR_TYPEHASH from Safe v1.3.0
+        assert_eq!(
+            hex::encode(SafeTransaction::type_hash()),
+            "bb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8"
+        );
+        assert_eq!(
+            hex::encode(safe_domain(safe_address(), 1).type_hash()),
+            "47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218"
+        );
+    }
+
+    // ─── builder ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_builder_defaults() {
+        let tx = SafeTransaction::builder()
+            .to(recipient())
+            .nonce(0)
+            .build()
+            .unwrap();
+
+        assert_eq!(tx.value, Wei::ZERO);
+        assert!(tx.data.is_empty());
+        assert_eq!(tx.operation, Operation::Call);
+        assert_eq!(tx.gas_token, Address::ZERO);
+        assert_eq!(tx.refund_receiver, Address::ZERO);
+    }
+
+    #[test]
+    fn test_builder_requires_to_and_nonce() {
+        assert!(SafeTransaction::builder().nonce(0).build().is_err());
+        assert!(SafeTransaction::builder().to(recipient()).build().is_err());
+    }
+
+    // ─── hashing ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_hash_matches_manual_encoding() {
+        let tx = test_tx();
+        let domain_separator = keccak256(
+            &[
+                hex::decode("47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218")
+                    .unwrap(),
+                encode_uint64(1).to_vec(),
+                encode_address(&safe_address()).to_vec(),
+            ]
+            .concat(),
+        );
+        let struct_hash = keccak256(
+            &[
+                SafeTransaction::type_hash().to_vec(),
+                encode_address(&recipient()).to_vec(),
+                u256_word(Wei::from_ether(1).as_u256()).to_vec(),
+                keccak256(&[0xde, 0xad]).to_vec(),
+                [0u8; 32 * 6].to_vec(),
+                encode_uint64(3).to_vec(),
+            ]
+            .concat(),
+        );
+        let expected = keccak256(&[&[0x19, 0x01][..], &domain_separator, &struct_hash].concat());
+
+        assert_eq!(tx.hash(safe_address(), 1), expected);
+    }
+
+    #[test]
+    fn test_hash_differs_by_chain_safe_and_nonce() {
+        let tx = test_tx();
+        let base = tx.hash(safe_address(), 1);
+
+        assert_ne!(base, tx.hash(safe_address(), 56));
+        assert_ne!(base, tx.hash(recipient(), 1));
+
+        let mut other = tx.clone();
+        other.nonce += 1;
+        assert_ne!(base, other.hash(safe_address(), 1));
+
+        let mut delegate = tx;
+        delegate.operation = Operation::DelegateCall;
+        assert_ne!(base, delegate.hash(safe_address(), 1));
+    }
+
+    // ─── signatures ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_sign_and_recover() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let tx = test_tx();
+        let sig = sign_safe_transaction(&signer, &tx, safe_address(), 1).unwrap();
+
+        let SafeSignature::Ecdsa { owner, signature } = sig else {
+            panic!("expected ECDSA signature");
+        };
+        assert_eq!(owner, signer.address());
+        assert_eq!(
+            recover_safe_signer(&tx.hash(safe_address(), 1), &signature).unwrap(),
+            owner
+        );
+    }
+
+    #[test]
+    fn test_signature_bytes_use_legacy_v() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let sig = sign_safe_transaction(&signer, &test_tx(), safe_address(), 1).unwrap();
+        let bytes = sig.to_bytes();
+
+        assert!(bytes[64] == 27 || bytes[64] == 28);
+    }
+
+    #[test]
+    fn test_approved_hash_bytes() {
+        let sig = SafeSignature::ApprovedHash { owner: recipient() };
+        let bytes = sig.to_bytes();
+
+        assert_eq!(&bytes[12..32], recipient().as_bytes());
+        assert!(bytes[..12].iter().all(|&b| b == 0));
+        assert!(bytes[32..64].iter().all(|&b| b == 0));
+        assert_eq!(bytes[64], 1);
+    }
+
+    #[test]
+    fn test_pack_signatures_sorted_by_owner() {
+        let tx = test_tx();
+        let signers: Vec<Bip44Signer> = (1..=3u8)
+            .map(|k| Bip44Signer::from_private_key(&[k; 32]).unwrap())
+            .collect();
+        let sigs: Vec<SafeSignature> = signers
+            .iter()
+            .map(|s| sign_safe_transaction(s, &tx, safe_address(), 1).unwrap())
+            .collect();
+
+        let packed = pack_signatures(&sigs).unwrap();
+        assert_eq!(packed.len(), 65 * 3);
+
+        let hash = tx.hash(safe_address(), 1);
+        let owners: Vec<Address> = packed
+            .chunks(65)
+            .map(|chunk| {
+                recover_safe_signer(&hash, &Signature::from_bytes(chunk).unwrap()).unwrap()
+            })
+            .collect();
+        let mut expected: Vec<Address> = signers.iter().map(Bip44Signer::address).collect();
+        expected.sort_by_key(|a| a.to_bytes());
+        assert_eq!(owners, expected);
+    }
+
+    #[test]
+    fn test_pack_signatures_rejects_duplicates_and_empty() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let sig = sign_safe_transaction(&signer, &test_tx(), safe_address(), 1).unwrap();
+        let approved = SafeSignature::ApprovedHash {
+            owner: signer.address(),
+        };
+
+        assert!(pack_signatures(&[]).is_err());
+        assert!(pack_signatures(&[sig, approved]).is_err());
+    }
+
+    #[test]
+    fn test_sign_respects_chain_binding() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32])
+            .unwrap()
+            .with_chain_id(crate::ChainId::BscMainnet);
+
+        assert!(sign_safe_transaction(&signer, &test_tx(), safe_address(), 1).is_err());
+        assert!(sign_safe_transaction(&signer, &test_tx(), safe_address(), 56).is_ok());
+    }
+
+    // ─── calldata ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_encode_exec_transaction_roundtrip() {
+        let tx = test_tx();
+        let signatures = vec![0xaa; 65];
+        let calldata = encode_exec_transaction(&tx, &signatures);
+
+        assert_eq!(&calldata[..4], &EXEC_TRANSACTION_SELECTOR);
+        let tokens = abi::decode(
+            &[
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Bytes,
+                ParamType::Uint(8),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Bytes,
+            ],
+            &calldata[4..],
+        )
+        .unwrap();
+
+        assert_eq!(tokens[0], Token::Address(recipient()));
+        assert_eq!(tokens[1], Token::Uint(Wei::from_ether(1).as_u256()));
+        assert_eq!(tokens[2], Token::Bytes(vec![0xde, 0xad]));
+        assert_eq!(tokens[3], Token::Uint(U256::zero()));
+        assert_eq!(tokens[9], Token::Bytes(signatures));
+    }
+
+    #[test]
+    fn test_encode_approve_hash() {
+        let calldata = encode_approve_hash(&[0x11; 32]);
+        assert_eq!(&calldata[..4], &APPROVE_HASH_SELECTOR);
+        assert_eq!(&calldata[4..], &[0x11; 32]);
+        assert_eq!(encode_nonce(), NONCE_SELECTOR.to_vec());
+    }
+}
@@ -3,8 +3,8 @@
 //! This module provides the `SignedTransaction` struct representing a fully
 //! signed EIP-1559 transaction ready for broadcast.
 
-use crate::{Eip1559Transaction, Signature};
-use primitive_types::U256;
+use crate::rlp_encode::{append_u256, encode_access_list};
+use crate::{recover_signer, Address, Eip1559Transaction, Result, Signature};
 use rlp::RlpStream;
 use sha3::{Digest, Keccak256};
 
@@ -185,26 +185,89 @@ impl SignedTransaction {
         result
     }
 
+    /// Returns the transaction hash; alias for [`tx_hash`](Self::tx_hash).
+    ///
+    /// This is the hash block explorers and `eth_getTransactionReceipt` use, and it is
+    /// only known once the transaction is signed.
+    pub fn hash(&self) -> [u8; 32] {
+        self.tx_hash()
+    }
+
     /// Returns the transaction hash as a hex string with 0x prefix.
     pub fn tx_hash_hex(&self) -> String {
         format!("0x{}", hex::encode(self.tx_hash()))
     }
-}
 
-/// Appends a U256 value to the RLP stream.
-fn append_u256(stream: &mut RlpStream, value: U256) {
-    if value.is_zero() {
-        stream.append_empty_data();
-    } else {
-        let mut bytes = [0u8; 32];
-        value.to_big_endian(&mut bytes);
-        let start = bytes.iter().position(|&b| b != 0).unwrap_or(32);
-        stream.append(&&bytes[start..]);
+    /// Returns the hash that was signed, i.e. the unsigned transaction's
+    /// [`signing_hash`](Eip1559Transaction::signing_hash).
+    ///
+    /// Compare it with the digest a hardware wallet displays to confirm both sides
+    /// signed the same transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, SignedTransaction, Wei};
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+    /// let tx = Eip1559Transaction::builder()
+    ///     .chain_id(ChainId::BscMainnet)
+    ///     .nonce(0)
+    ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///     .max_fee_per_gas(Wei::from_gwei(5))
+    ///     .gas_limit(21000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// // Preview before signing ...
+    /// let sighash = tx.signing_hash();
+    ///
+    /// // ... and check after signing
+    /// let signed_tx = SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap());
+    /// assert_eq!(signed_tx.signing_hash(), sighash);
+    /// assert_ne!(signed_tx.hash(), sighash);
+    /// ```
+    pub fn signing_hash(&self) -> [u8; 32] {
+        self.transaction.signing_hash()
+    }
+
+    /// Verifies the signature and returns the sender's address.
+    ///
+    /// Recovers the address that signed [`Eip1559Transaction::signing_hash`]. Use this
+    /// to attribute a received or replayed transaction to its sender.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is malformed, non-canonical (high `s`) or
+    /// recovery fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, SignedTransaction, Wei};
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+    /// let tx = Eip1559Transaction::builder()
+    ///     .chain_id(ChainId::BscMainnet)
+    ///     .nonce(0)
+    ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///     .max_fee_per_gas(Wei::from_gwei(5))
+    ///     .gas_limit(21000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let signature = signer.sign_transaction(&tx).unwrap();
+    /// let signed_tx = SignedTransaction::new(tx, signature);
+    ///
+    /// assert_eq!(signed_tx.verify().unwrap(), signer.address());
+    /// ```
+    pub fn verify(&self) -> Result<Address> {
+        recover_signer(&self.transaction.signing_hash(), &self.signature)
     }
 }
 
 /// Appends a signature component (r or s) to the RLP stream.
-fn append_signature_component(stream: &mut RlpStream, component: &[u8; 32]) {
+pub(crate) fn append_signature_component(stream: &mut RlpStream, component: &[u8; 32]) {
     // Strip leading zeros
     let start = component.iter().position(|&b| b != 0).unwrap_or(32);
     if start == 32 {
@@ -214,23 +277,10 @@ fn append_signature_component(stream: &mut RlpStream, component: &[u8; 32]) {
     }
 }
 
-/// Encodes the access list into the RLP stream.
-fn encode_access_list(stream: &mut RlpStream, access_list: &[crate::AccessListItem]) {
-    stream.begin_list(access_list.len());
-    for item in access_list {
-        stream.begin_list(2);
-        stream.append(&item.address.as_bytes().as_slice());
-        stream.begin_list(item.storage_keys.len());
-        for key in &item.storage_keys {
-            stream.append(&key.as_slice());
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Address, Bip44Signer, ChainId, Wei};
+    use crate::{Bip44Signer, ChainId, Wei};
 
     fn test_signer() -> Bip44Signer {
         Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
@@ -403,6 +453,42 @@ mod tests {
 
     // ==================== With Recipient Tests ====================
 
+    #[test]
+    fn test_hash_matches_tx_hash() {
+        let signed_tx = test_signed_transaction();
+        assert_eq!(signed_tx.hash(), signed_tx.tx_hash());
+    }
+
+    #[test]
+    fn test_signing_hash_matches_unsigned_preview() {
+        let tx = test_transaction();
+        let preview = tx.signing_hash();
+        let signed_tx =
+            SignedTransaction::new(tx.clone(), test_signer().sign_transaction(&tx).unwrap());
+
+        assert_eq!(signed_tx.signing_hash(), preview);
+        assert_ne!(signed_tx.hash(), preview);
+    }
+
+    #[test]
+    fn test_signing_hash_is_what_was_signed() {
+        let signer = test_signer();
+        let tx = test_transaction();
+        let signed_tx = SignedTransaction::new(tx.clone(), signer.sign_transaction(&tx).unwrap());
+
+        let raw_signature = signer.sign_hash(&signed_tx.signing_hash()).unwrap();
+        assert_eq!(&raw_signature, signed_tx.signature());
+    }
+
+    #[test]
+    fn test_signing_hash_hex() {
+        let tx = test_transaction();
+        let hex_hash = tx.signing_hash_hex();
+
+        assert_eq!(hex_hash.len(), 66);
+        assert_eq!(hex_hash, format!("0x{}", hex::encode(tx.signing_hash())));
+    }
+
     #[test]
     fn test_encode_with_recipient() {
         let signer = test_signer();
@@ -428,6 +514,34 @@ mod tests {
         assert!(raw.starts_with("0x02"));
     }
 
+    // ==================== Verification Tests ====================
+
+    #[test]
+    fn test_verify_returns_sender() {
+        let signed = test_signed_transaction();
+        assert_eq!(signed.verify().unwrap(), test_signer().address());
+    }
+
+    #[test]
+    fn test_verify_tampered_transaction() {
+        let signed = test_signed_transaction();
+        let mut tx = signed.transaction().clone();
+        tx.nonce += 1;
+        let tampered = SignedTransaction::new(tx, *signed.signature());
+
+        // A modified payload recovers to a different (unrelated) address
+        if let Ok(address) = tampered.verify() {
+            assert_ne!(address, test_signer().address());
+        }
+    }
+
+    #[test]
+    fn test_verify_invalid_signature() {
+        let signed =
+            SignedTransaction::new(test_transaction(), Signature::new([0u8; 32], [0u8; 32], 0));
+        assert!(signed.verify().is_err());
+    }
+
     // ==================== Clone/Eq Tests ====================
 
     #[test]
@@ -9,7 +9,10 @@
 //! zeroized when the signer is dropped, preventing sensitive data from lingering
 //! in memory. The underlying `k256::SigningKey` implements `Zeroize`.
 
-use crate::{Address, Eip1559Transaction, Error, Result, Signature};
+use crate::{
+    Address, ChainId, Eip1559Transaction, Eip2930Transaction, Error, Result, Signature,
+    SigningPolicy,
+};
 use k256::ecdsa::{RecoveryId, SigningKey, VerifyingKey};
 use zeroize::Zeroizing;
 
@@ -37,6 +40,10 @@ pub struct Bip44Signer {
     signing_key: SigningKey,
     /// The EVM address derived from the public key.
     address: Address,
+    /// The chain this signer is restricted to, if any.
+    chain_id: Option<ChainId>,
+    /// The signing policy enforced by this signer, if any.
+    policy: Option<SigningPolicy>,
 }
 
 impl Bip44Signer {
@@ -70,6 +77,8 @@ impl Bip44Signer {
         Ok(Self {
             signing_key,
             address,
+            chain_id: None,
+            policy: None,
         })
     }
 
@@ -101,6 +110,8 @@ impl Bip44Signer {
         Ok(Self {
             signing_key,
             address,
+            chain_id: None,
+            policy: None,
         })
     }
 
@@ -120,20 +131,113 @@ impl Bip44Signer {
         self.address
     }
 
-    /// Signs a message hash and returns the signature.
+    /// Restricts this signer to a single chain.
     ///
-    /// # Arguments
+    /// A bound signer refuses to sign transactions, user operations or EIP-712 data
+    /// whose chain ID differs, guarding against a builder configured for the wrong
+    /// network. [`sign_prehashed`](Self::sign_prehashed) and
+    /// [`sign_message`](Self::sign_message) carry no chain ID and are not checked.
     ///
-    /// * `hash` - The 32-byte message hash to sign
+    /// # Examples
     ///
-    /// # Returns
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, Error, Wei};
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32])
+    ///     .unwrap()
+    ///     .with_chain_id(ChainId::BscMainnet);
+    ///
+    /// let tx = Eip1559Transaction::builder()
+    ///     .chain_id(ChainId::EthereumMainnet)
+    ///     .nonce(0)
+    ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///     .max_fee_per_gas(Wei::from_gwei(5))
+    ///     .gas_limit(21000)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(matches!(
+    ///     signer.sign_transaction(&tx),
+    ///     Err(Error::ChainIdMismatch { expected: 56, actual: 1 })
+    /// ));
+    /// ```
+    pub fn with_chain_id(mut self, chain_id: ChainId) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Returns the chain this signer is bound to, if any.
+    pub fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    /// Attaches a [`SigningPolicy`], replacing any previous one.
+    ///
+    /// The policy's chain allow-list is checked in addition to the chain binding of
+    /// [`with_chain_id`](Self::with_chain_id); its ceilings apply to every transaction,
+    /// user operation, Safe transaction and forward request this signer signs, and
+    /// [`sign_prehashed`](Self::sign_prehashed) is refused unless the policy allows it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, Error, SigningPolicy, Wei};
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32])
+    ///     .unwrap()
+    ///     .with_policy(SigningPolicy::new().max_value(Wei::from_ether(1)));
+    ///
+    /// let tx = Eip1559Transaction::builder()
+    ///     .chain_id(ChainId::BscMainnet)
+    ///     .nonce(0)
+    ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///     .max_fee_per_gas(Wei::from_gwei(5))
+    ///     .gas_limit(21000)
+    ///     .to(signer.address())
+    ///     .value(Wei::from_ether(2))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(matches!(
+    ///     signer.sign_transaction(&tx),
+    ///     Err(Error::PolicyViolation(_))
+    /// ));
+    /// ```
+    pub fn with_policy(mut self, policy: SigningPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Returns the signing policy this signer enforces, if any.
+    pub fn policy(&self) -> Option<&SigningPolicy> {
+        self.policy.as_ref()
+    }
+
+    /// Checks `chain_id` against the bound chain and the policy's allow-list.
+    ///
+    /// Chains are compared by numeric value, so `Custom(56)` matches `BscMainnet`.
+    pub(crate) fn check_chain_id(&self, chain_id: u64) -> Result<()> {
+        match self.chain_id {
+            Some(expected) if expected.value() != chain_id => Err(Error::ChainIdMismatch {
+                expected: expected.value(),
+                actual: chain_id,
+            }),
+            _ => match &self.policy {
+                Some(policy) => policy.check_chain_id(chain_id),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Signs a message hash and returns the signature.
     ///
-    /// The ECDSA signature with recovery ID.
+    /// Crate-internal: callers outside the crate sign raw digests through
+    /// [`sign_prehashed`](Self::sign_prehashed) and a `PrehashPolicy`.
     ///
     /// # Errors
     ///
     /// Returns an error if signing fails.
-    pub fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+    pub(crate) fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
         let (signature, recovery_id) = self
             .signing_key
             .sign_prehash_recoverable(hash)
@@ -158,7 +262,9 @@ impl Bip44Signer {
     ///
     /// # Errors
     ///
-    /// Returns an error if signing fails.
+    /// Returns [`Error::ChainIdMismatch`] if the signer is bound to a different chain,
+    /// [`Error::PolicyViolation`] if the transaction breaks the signer's
+    /// [`SigningPolicy`], or an error if signing fails.
     ///
     /// # Examples
     ///
@@ -181,10 +287,97 @@ impl Bip44Signer {
     /// let signature = signer.sign_transaction(&tx).unwrap();
     /// ```
     pub fn sign_transaction(&self, tx: &Eip1559Transaction) -> Result<Signature> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!(
+            "sign_transaction",
+            chain_id = tx.chain_id.value(),
+            tx_type = "eip1559"
+        );
+
+        self.check_chain_id(tx.chain_id.value())?;
+        if let Some(policy) = &self.policy {
+            policy.check_transaction(tx)?;
+        }
+        let hash = tx.signing_hash();
+        self.sign_hash(&hash)
+    }
+
+    /// Signs an EIP-2930 (Type 1) access-list transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction to sign
+    ///
+    /// # Returns
+    ///
+    /// The ECDSA signature for the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ChainIdMismatch`] if the signer is bound to a different chain,
+    /// [`Error::PolicyViolation`] if the transaction breaks the signer's
+    /// [`SigningPolicy`], or an error if signing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, Eip2930Transaction, ChainId, Wei};
+    ///
+    /// let private_key = [1u8; 32];
+    /// let signer = Bip44Signer::from_private_key(&private_key).unwrap();
+    ///
+    /// let tx = Eip2930Transaction::builder()
+    ///     .chain_id(ChainId::BscMainnet)
+    ///     .nonce(0)
+    ///     .gas_price(Wei::from_gwei(3))
+    ///     .gas_limit(21000)
+    ///     .to(signer.address())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let signature = signer.sign_eip2930_transaction(&tx).unwrap();
+    /// ```
+    pub fn sign_eip2930_transaction(&self, tx: &Eip2930Transaction) -> Result<Signature> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!(
+            "sign_transaction",
+            chain_id = tx.chain_id.value(),
+            tx_type = "eip2930"
+        );
+
+        self.check_chain_id(tx.chain_id.value())?;
+        if let Some(policy) = &self.policy {
+            policy.check_eip2930_transaction(tx)?;
+        }
         let hash = tx.signing_hash();
         self.sign_hash(&hash)
     }
 
+    /// Signs a message using EIP-191 `personal_sign`.
+    ///
+    /// The message is prefixed with `"\x19Ethereum Signed Message:\n"` and its
+    /// decimal length before hashing; see [`eip191`](crate::eip191).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::Bip44Signer;
+    ///
+    /// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+    /// let signature = signer.sign_message(b"Sign in to example.com").unwrap();
+    /// ```
+    pub fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        #[cfg(feature = "tracing")]
+        let _operation = khodpay_tracing::operation!("sign_message", message_len = message.len());
+
+        let hash = crate::eip191::hash_message(message);
+        self.sign_hash(&hash)
+    }
+
     /// Derives an EVM address from a verifying (public) key.
     fn address_from_verifying_key(verifying_key: &VerifyingKey) -> Result<Address> {
         // Get uncompressed public key (65 bytes with 0x04 prefix)
@@ -202,6 +395,10 @@ impl Bip44Signer {
 
 /// Recovers the signer's address from a signature and message hash.
 ///
+/// This is the equivalent of the EVM `ecrecover` precompile. `v` may be given either
+/// as a recovery ID (`0` / `1`) or in the legacy `27` / `28` form. Signatures with a
+/// high `s` value are rejected, as required by EIP-2 for transactions.
+///
 /// # Arguments
 ///
 /// * `hash` - The 32-byte message hash that was signed
@@ -213,9 +410,28 @@ impl Bip44Signer {
 ///
 /// # Errors
 ///
-/// Returns an error if recovery fails.
+/// Returns an error if `v` is not a valid recovery ID, `r` or `s` is out of range,
+/// `s` is in the upper half of the curve order, or recovery fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{recover_signer, Bip44Signer, PrehashPolicy};
+///
+/// let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+/// let hash = [5u8; 32];
+/// let policy = PrehashPolicy::allow_digests("example", [hash]).unwrap();
+/// let signature = signer.sign_prehashed(&hash, &policy).unwrap();
+///
+/// assert_eq!(recover_signer(&hash, &signature).unwrap(), signer.address());
+/// ```
 pub fn recover_signer(hash: &[u8; 32], signature: &Signature) -> Result<Address> {
-    let recovery_id = RecoveryId::from_byte(signature.v)
+    let v = match signature.v {
+        27 | 28 => signature.v - 27,
+        v => v,
+    };
+    let recovery_id = RecoveryId::from_byte(v)
+        .filter(|id| !id.is_x_reduced())
         .ok_or_else(|| Error::SigningError("Invalid recovery ID".to_string()))?;
 
     let r: &k256::FieldBytes = (&signature.r).into();
@@ -224,6 +440,12 @@ pub fn recover_signer(hash: &[u8; 32], signature: &Signature) -> Result<Address>
     let ecdsa_sig = k256::ecdsa::Signature::from_scalars(*r, *s)
         .map_err(|e| Error::SigningError(format!("Invalid signature: {}", e)))?;
 
+    if ecdsa_sig.normalize_s().is_some() {
+        return Err(Error::SigningError(
+            "Invalid signature: s is not in the lower half of the curve order".to_string(),
+        ));
+    }
+
     let verifying_key = VerifyingKey::recover_from_prehash(hash, &ecdsa_sig, recovery_id)
         .map_err(|e| Error::SigningError(format!("Recovery failed: {}", e)))?;
 
@@ -354,6 +576,85 @@ mod tests {
         assert_ne!(sig1.r, sig2.r);
     }
 
+    // ==================== Chain Binding Tests ====================
+
+    fn transaction_on(chain_id: ChainId) -> Eip1559Transaction {
+        Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21000)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_unbound_signer_signs_any_chain() {
+        let signer = Bip44Signer::from_private_key(&TEST_PRIVATE_KEY).unwrap();
+        assert!(signer.chain_id().is_none());
+        assert!(signer
+            .sign_transaction(&transaction_on(ChainId::EthereumMainnet))
+            .is_ok());
+        assert!(signer
+            .sign_transaction(&transaction_on(ChainId::BscMainnet))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_bound_signer_accepts_matching_chain() {
+        let signer = Bip44Signer::from_private_key(&TEST_PRIVATE_KEY)
+            .unwrap()
+            .with_chain_id(ChainId::BscMainnet);
+
+        assert_eq!(signer.chain_id(), Some(ChainId::BscMainnet));
+        assert!(signer
+            .sign_transaction(&transaction_on(ChainId::BscMainnet))
+            .is_ok());
+        // Compared by value, not by variant
+        assert!(signer
+            .sign_transaction(&transaction_on(ChainId::Custom(56)))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_bound_signer_rejects_other_chain() {
+        let signer = Bip44Signer::from_private_key(&TEST_PRIVATE_KEY)
+            .unwrap()
+            .with_chain_id(ChainId::BscMainnet);
+
+        let err = signer
+            .sign_transaction(&transaction_on(ChainId::BaseMainnet))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ChainIdMismatch {
+                expected: 56,
+                actual: 8453
+            }
+        ));
+
+        let tx = Eip2930Transaction::builder()
+            .chain_id(ChainId::BscTestnet)
+            .nonce(0)
+            .gas_price(Wei::from_gwei(3))
+            .gas_limit(21000)
+            .to(signer.address())
+            .build()
+            .unwrap();
+        assert!(signer.sign_eip2930_transaction(&tx).is_err());
+    }
+
+    #[test]
+    fn test_bound_signer_does_not_check_raw_hashes() {
+        let signer = Bip44Signer::from_private_key(&TEST_PRIVATE_KEY)
+            .unwrap()
+            .with_chain_id(ChainId::BscMainnet);
+
+        assert!(signer.sign_hash(&[1u8; 32]).is_ok());
+        assert!(signer.sign_message(b"hello").is_ok());
+    }
+
     // ==================== Recovery Tests ====================
 
     #[test]
@@ -396,6 +697,37 @@ mod tests {
         assert!(recover_signer(&hash, &signature).is_err());
     }
 
+    #[test]
+    fn test_recover_legacy_v() {
+        let signer = Bip44Signer::from_private_key(&TEST_PRIVATE_KEY).unwrap();
+        let hash = [5u8; 32];
+
+        let mut signature = signer.sign_hash(&hash).unwrap();
+        signature.v += 27;
+
+        assert_eq!(recover_signer(&hash, &signature).unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_recover_rejects_high_s() {
+        let signer = Bip44Signer::from_private_key(&TEST_PRIVATE_KEY).unwrap();
+        let hash = [5u8; 32];
+        let signature = signer.sign_hash(&hash).unwrap();
+
+        // Flip to the malleable (n - s, v ^ 1) form
+        let sig = k256::ecdsa::Signature::from_scalars(signature.r, signature.s).unwrap();
+        let high_s: [u8; 32] = (-*sig.s()).to_bytes().into();
+        let malleable = Signature::new(signature.r, high_s, signature.v ^ 1);
+
+        assert!(recover_signer(&hash, &malleable).is_err());
+    }
+
+    #[test]
+    fn test_recover_zero_r() {
+        let signature = Signature::new([0u8; 32], [2u8; 32], 0);
+        assert!(recover_signer(&[0u8; 32], &signature).is_err());
+    }
+
     // ==================== Different Keys Tests ====================
 
     #[test]
@@ -0,0 +1,919 @@
+//! Sign-In with Ethereum (EIP-4361).
+//!
+//! [EIP-4361](https://eips.ethereum.org/EIPS/eip-4361) defines a plain-text login
+//! message that a wallet signs with `personal_sign`:
+//!
+//! ```text
+//! example.com wants you to sign in with your Ethereum account:
+//! 0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf
+//!
+//! Sign in to Example
+//!
+//! URI: https://example.com/login
+//! Version: 1
+//! Chain ID: 56
+//! Nonce: 32891756
+//! Issued At: 2024-01-01T00:00:00Z
+//! ```
+//!
+//! [`SiweMessage`] builds, prints and parses these messages. The wallet side signs
+//! with [`SiweMessage::sign`]; the server side checks the signature, domain, nonce
+//! and validity window with [`SiweMessage::verify`].
+//!
+//! Nonces are issued by the server and must be at least 8 alphanumeric characters.
+//! Timestamps are RFC 3339 strings and are kept verbatim, so a parsed message prints
+//! back to exactly the text that was signed.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::siwe::{SiweMessage, VerifyOptions};
+//! use khodpay_signing::Bip44Signer;
+//!
+//! let wallet = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+//!
+//! let message = SiweMessage::builder()
+//!     .domain("example.com")
+//!     .address(wallet.address())
+//!     .statement("Sign in to Example")
+//!     .uri("https://example.com/login")
+//!     .chain_id(56)
+//!     .nonce("32891756")
+//!     .issued_at("2024-01-01T00:00:00Z")
+//!     .build()
+//!     .unwrap();
+//!
+//! let signature = message.sign(&wallet).unwrap();
+//!
+//! // Server: parse the text the client sent back and verify it.
+//! let received: SiweMessage = message.to_string().parse().unwrap();
+//! let options = VerifyOptions::new()
+//!     .domain("example.com")
+//!     .nonce("32891756")
+//!     .time(1_704_067_200);
+//! received.verify(&signature, &options).unwrap();
+//! ```
+
+use crate::eip191::{hash_message, recover_message_signer};
+use crate::{Address, Bip44Signer, Error, Result, Signature};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header suffix that follows the domain on the first line.
+const HEADER: &str = " wants you to sign in with your Ethereum account:";
+
+/// Minimum nonce length required by EIP-4361.
+pub const MIN_NONCE_LENGTH: usize = 8;
+
+/// An EIP-4361 Sign-In with Ethereum message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiweMessage {
+    /// URI scheme of the requesting origin, e.g. `https` (optional).
+    pub scheme: Option<String>,
+    /// RFC 3986 authority requesting the sign-in, e.g. `example.com`.
+    pub domain: String,
+    /// The account signing in.
+    pub address: Address,
+    /// Human-readable assertion shown to the user (single line).
+    pub statement: Option<String>,
+    /// RFC 3986 URI of the resource being signed in to.
+    pub uri: String,
+    /// Message version; always `"1"`.
+    pub version: String,
+    /// EIP-155 chain ID the session is bound to.
+    pub chain_id: u64,
+    /// Server-issued nonce preventing replay.
+    pub nonce: String,
+    /// RFC 3339 issuance time.
+    pub issued_at: String,
+    /// RFC 3339 time after which the message is invalid.
+    pub expiration_time: Option<String>,
+    /// RFC 3339 time before which the message is invalid.
+    pub not_before: Option<String>,
+    /// System-specific request identifier.
+    pub request_id: Option<String>,
+    /// URIs the user wishes to have resolved as part of authentication.
+    pub resources: Vec<String>,
+}
+
+impl SiweMessage {
+    /// Returns a builder for constructing a message.
+    pub fn builder() -> SiweMessageBuilder {
+        SiweMessageBuilder::default()
+    }
+
+    /// Checks the message fields against EIP-4361.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SiweError`] if a required field is empty, the statement spans
+    /// several lines, the nonce is too short or not alphanumeric, the version is not
+    /// `"1"`, or a timestamp is not valid RFC 3339.
+    pub fn validate(&self) -> Result<()> {
+        if self.domain.is_empty() || self.domain.contains(char::is_whitespace) {
+            return Err(siwe_error(format!("invalid domain: {:?}", self.domain)));
+        }
+        if self.uri.is_empty() || self.uri.contains(char::is_whitespace) {
+            return Err(siwe_error(format!("invalid URI: {:?}", self.uri)));
+        }
+        if self.version != "1" {
+            return Err(siwe_error(format!("unsupported version: {}", self.version)));
+        }
+        if self.statement.as_deref().is_some_and(|s| s.contains('\n')) {
+            return Err(siwe_error("statement must be a single line"));
+        }
+        if self.nonce.len() < MIN_NONCE_LENGTH
+            || !self.nonce.bytes().all(|b| b.is_ascii_alphanumeric())
+        {
+            return Err(siwe_error(format!(
+                "nonce must be at least {} alphanumeric characters",
+                MIN_NONCE_LENGTH
+            )));
+        }
+        parse_rfc3339(&self.issued_at)?;
+        if let Some(time) = &self.expiration_time {
+            parse_rfc3339(time)?;
+        }
+        if let Some(time) = &self.not_before {
+            parse_rfc3339(time)?;
+        }
+        Ok(())
+    }
+
+    /// Computes the EIP-191 hash of the message text.
+    pub fn hash(&self) -> [u8; 32] {
+        hash_message(self.to_string().as_bytes())
+    }
+
+    /// Signs the message with `personal_sign`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SiweError`] if the signer is not the message's address,
+    /// [`Error::ChainIdMismatch`] if the signer is bound to a different chain, or an
+    /// error if signing fails.
+    pub fn sign(&self, signer: &Bip44Signer) -> Result<Signature> {
+        if signer.address() != self.address {
+            return Err(siwe_error(format!(
+                "signer {} does not match message address {}",
+                signer.address(),
+                self.address
+            )));
+        }
+        signer.check_chain_id(self.chain_id)?;
+        signer.sign_hash(&self.hash())
+    }
+
+    /// Verifies the signature and the checks in `options`.
+    ///
+    /// Always checks that the signature recovers to [`address`](Self::address) and
+    /// that the verification time lies within `not_before..expiration_time`.
+    ///
+    /// Only externally owned accounts are supported; contract wallets need an
+    /// EIP-1271 check (see [`eip1271`](crate::eip1271)).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SiweError`] describing the first failed check, or an error if
+    /// the signature is malformed.
+    pub fn verify(&self, signature: &Signature, options: &VerifyOptions) -> Result<()> {
+        self.validate()?;
+
+        if let Some(domain) = &options.domain {
+            if *domain != self.domain {
+                return Err(siwe_error(format!(
+                    "domain mismatch: expected {}, got {}",
+                    domain, self.domain
+                )));
+            }
+        }
+        if let Some(nonce) = &options.nonce {
+            if *nonce != self.nonce {
+                return Err(siwe_error("nonce mismatch"));
+            }
+        }
+
+        let now = match options.time {
+            Some(time) => time as i64,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or_default(),
+        };
+        if let Some(expiration) = &self.expiration_time {
+            if now >= parse_rfc3339(expiration)? {
+                return Err(siwe_error(format!("message expired at {}", expiration)));
+            }
+        }
+        if let Some(not_before) = &self.not_before {
+            if now < parse_rfc3339(not_before)? {
+                return Err(siwe_error(format!(
+                    "message not valid before {}",
+                    not_before
+                )));
+            }
+        }
+
+        let recovered = recover_message_signer(self.to_string().as_bytes(), signature)?;
+        if recovered != self.address {
+            return Err(siwe_error(format!(
+                "signature is from {}, not {}",
+                recovered, self.address
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{}://", scheme)?;
+        }
+        writeln!(f, "{}{}", self.domain, HEADER)?;
+        writeln!(f, "{}", self.address)?;
+        writeln!(f)?;
+        if let Some(statement) = &self.statement {
+            writeln!(f, "{}", statement)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "URI: {}", self.uri)?;
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Chain ID: {}", self.chain_id)?;
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        write!(f, "Issued At: {}", self.issued_at)?;
+        if let Some(time) = &self.expiration_time {
+            write!(f, "\nExpiration Time: {}", time)?;
+        }
+        if let Some(time) = &self.not_before {
+            write!(f, "\nNot Before: {}", time)?;
+        }
+        if let Some(id) = &self.request_id {
+            write!(f, "\nRequest ID: {}", id)?;
+        }
+        if !self.resources.is_empty() {
+            write!(f, "\nResources:")?;
+            for resource in &self.resources {
+                write!(f, "\n- {}", resource)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SiweMessage {
+    type Err = Error;
+
+    /// Parses a message in the exact EIP-4361 layout.
+    ///
+    /// Only the canonical form is accepted, so the parsed message prints back to the
+    /// same text and its signature can be checked.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.split('\n');
+
+        let header = lines.next().unwrap_or_default();
+        let origin = header
+            .strip_suffix(HEADER)
+            .ok_or_else(|| siwe_error("missing sign-in header"))?;
+        let (scheme, domain) = match origin.split_once("://") {
+            Some((scheme, domain)) => (Some(scheme.to_string()), domain.to_string()),
+            None => (None, origin.to_string()),
+        };
+
+        let address_line = lines.next().unwrap_or_default();
+        let address: Address = address_line
+            .parse()
+            .map_err(|_| siwe_error(format!("invalid address: {:?}", address_line)))?;
+        if address_line != address.to_checksum_string() {
+            return Err(siwe_error("address must be EIP-55 checksummed"));
+        }
+
+        expect_blank(lines.next())?;
+        let statement = match lines.next() {
+            Some("") => None,
+            Some(line) => {
+                expect_blank(lines.next())?;
+                Some(line.to_string())
+            }
+            None => return Err(siwe_error("message ends after address")),
+        };
+
+        parse_fields(scheme, domain, address, statement, lines)
+    }
+}
+
+fn parse_fields<'a>(
+    scheme: Option<String>,
+    domain: String,
+    address: Address,
+    statement: Option<String>,
+    lines: impl Iterator<Item = &'a str>,
+) -> Result<SiweMessage> {
+    let mut lines = lines.peekable();
+    let mut field = |name: &str, required: bool| -> Result<Option<String>> {
+        let prefix = format!("{}: ", name);
+        match lines
+            .peek()
+            .and_then(|line| line.strip_prefix(prefix.as_str()))
+        {
+            Some(value) => {
+                let value = value.to_string();
+                lines.next();
+                Ok(Some(value))
+            }
+            None if required => Err(siwe_error(format!("missing {}", name))),
+            None => Ok(None),
+        }
+    };
+
+    let uri = field("URI", true)?.unwrap_or_default();
+    let version = field("Version", true)?.unwrap_or_default();
+    let chain_id = field("Chain ID", true)?.unwrap_or_default();
+    let chain_id = chain_id
+        .parse()
+        .map_err(|_| siwe_error(format!("invalid chain ID: {}", chain_id)))?;
+    let nonce = field("Nonce", true)?.unwrap_or_default();
+    let issued_at = field("Issued At", true)?.unwrap_or_default();
+    let expiration_time = field("Expiration Time", false)?;
+    let not_before = field("Not Before", false)?;
+    let request_id = field("Request ID", false)?;
+
+    let mut resources = Vec::new();
+    match lines.next() {
+        None => {}
+        Some("Resources:") => {
+            for line in lines.by_ref() {
+                let resource = line
+                    .strip_prefix("- ")
+                    .ok_or_else(|| siwe_error(format!("invalid resource line: {:?}", line)))?;
+                resources.push(resource.to_string());
+            }
+        }
+        Some(line) => return Err(siwe_error(format!("unexpected line: {:?}", line))),
+    }
+
+    let message = SiweMessage {
+        scheme,
+        domain,
+        address,
+        statement,
+        uri,
+        version,
+        chain_id,
+        nonce,
+        issued_at,
+        expiration_time,
+        not_before,
+        request_id,
+        resources,
+    };
+    message.validate()?;
+    Ok(message)
+}
+
+fn expect_blank(line: Option<&str>) -> Result<()> {
+    match line {
+        Some("") => Ok(()),
+        _ => Err(siwe_error("expected blank line")),
+    }
+}
+
+/// Builder for [`SiweMessage`].
+#[derive(Debug, Clone, Default)]
+pub struct SiweMessageBuilder {
+    scheme: Option<String>,
+    domain: Option<String>,
+    address: Option<Address>,
+    statement: Option<String>,
+    uri: Option<String>,
+    chain_id: Option<u64>,
+    nonce: Option<String>,
+    issued_at: Option<String>,
+    expiration_time: Option<String>,
+    not_before: Option<String>,
+    request_id: Option<String>,
+    resources: Vec<String>,
+}
+
+impl SiweMessageBuilder {
+    /// Sets the origin's URI scheme, e.g. `https`.
+    pub fn scheme(mut self, scheme: &str) -> Self {
+        self.scheme = Some(scheme.to_string());
+        self
+    }
+
+    /// Sets the requesting domain (required).
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Sets the signing account (required).
+    pub fn address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Sets the statement shown to the user.
+    pub fn statement(mut self, statement: &str) -> Self {
+        self.statement = Some(statement.to_string());
+        self
+    }
+
+    /// Sets the resource URI (required).
+    pub fn uri(mut self, uri: &str) -> Self {
+        self.uri = Some(uri.to_string());
+        self
+    }
+
+    /// Sets the chain ID (required).
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Sets the server-issued nonce (required).
+    pub fn nonce(mut self, nonce: &str) -> Self {
+        self.nonce = Some(nonce.to_string());
+        self
+    }
+
+    /// Sets the RFC 3339 issuance time (required).
+    pub fn issued_at(mut self, time: &str) -> Self {
+        self.issued_at = Some(time.to_string());
+        self
+    }
+
+    /// Sets the issuance time from a Unix timestamp.
+    pub fn issued_at_unix(self, timestamp: u64) -> Self {
+        self.issued_at(&format_rfc3339(timestamp))
+    }
+
+    /// Sets the RFC 3339 expiration time.
+    pub fn expiration_time(mut self, time: &str) -> Self {
+        self.expiration_time = Some(time.to_string());
+        self
+    }
+
+    /// Sets the expiration time from a Unix timestamp.
+    pub fn expiration_time_unix(self, timestamp: u64) -> Self {
+        self.expiration_time(&format_rfc3339(timestamp))
+    }
+
+    /// Sets the RFC 3339 not-before time.
+    pub fn not_before(mut self, time: &str) -> Self {
+        self.not_before = Some(time.to_string());
+        self
+    }
+
+    /// Sets the request ID.
+    pub fn request_id(mut self, id: &str) -> Self {
+        self.request_id = Some(id.to_string());
+        self
+    }
+
+    /// Adds a resource URI.
+    pub fn resource(mut self, uri: &str) -> Self {
+        self.resources.push(uri.to_string());
+        self
+    }
+
+    /// Builds and validates the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SiweError`] if a required field is missing or
+    /// [`SiweMessage::validate`] fails.
+    pub fn build(self) -> Result<SiweMessage> {
+        let required = |field: &str| siwe_error(format!("{} is required", field));
+        let message = SiweMessage {
+            scheme: self.scheme,
+            domain: self.domain.ok_or_else(|| required("domain"))?,
+            address: self.address.ok_or_else(|| required("address"))?,
+            statement: self.statement,
+            uri: self.uri.ok_or_else(|| required("uri"))?,
+            version: "1".to_string(),
+            chain_id: self.chain_id.ok_or_else(|| required("chain_id"))?,
+            nonce: self.nonce.ok_or_else(|| required("nonce"))?,
+            issued_at: self.issued_at.ok_or_else(|| required("issued_at"))?,
+            expiration_time: self.expiration_time,
+            not_before: self.not_before,
+            request_id: self.request_id,
+            resources: self.resources,
+        };
+        message.validate()?;
+        Ok(message)
+    }
+}
+
+/// Server-side checks applied by [`SiweMessage::verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Expected domain; skipped if `None`.
+    pub domain: Option<String>,
+    /// Expected nonce; skipped if `None`.
+    pub nonce: Option<String>,
+    /// Verification time as a Unix timestamp; the system clock if `None`.
+    pub time: Option<u64>,
+}
+
+impl VerifyOptions {
+    /// Creates options that check only the signature and the validity window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires the message to be for `domain`.
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Requires the message to carry `nonce`.
+    pub fn nonce(mut self, nonce: &str) -> Self {
+        self.nonce = Some(nonce.to_string());
+        self
+    }
+
+    /// Verifies as of `timestamp` instead of the system clock.
+    pub fn time(mut self, timestamp: u64) -> Self {
+        self.time = Some(timestamp);
+        self
+    }
+}
+
+fn siwe_error(message: impl Into<String>) -> Error {
+    Error::SiweError(message.into())
+}
+
+// ─── RFC 3339 ────────────────────────────────────────────────────────────────
+
+/// Parses an RFC 3339 date-time into Unix seconds, ignoring fractional seconds.
+fn parse_rfc3339(s: &str) -> Result<i64> {
+    let invalid = || siwe_error(format!("invalid RFC 3339 timestamp: {:?}", s));
+    let b = s.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't') {
+        return Err(invalid());
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return Err(invalid());
+    }
+    let num = |range: std::ops::Range<usize>| -> Result<i64> {
+        let part = s.get(range).ok_or_else(invalid)?;
+        if !part.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        part.parse().map_err(|_| invalid())
+    };
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+
+    let mut rest = &s[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return Err(invalid());
+        }
+        rest = &fraction[digits..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return Err(invalid()),
+            };
+            let hours: i64 = rest[1..3].parse().map_err(|_| invalid())?;
+            let minutes: i64 = rest[4..6].parse().map_err(|_| invalid())?;
+            if hours > 23 || minutes > 59 {
+                return Err(invalid());
+            }
+            sign * (hours * 3600 + minutes * 60)
+        }
+        _ => return Err(invalid()),
+    };
+
+    let days = days_from_civil(year, month, day);
+    Ok(days * 86_400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+/// Formats Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`.
+fn format_rfc3339(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChainId;
+
+    const ISSUED_AT: &str = "2024-01-01T00:00:00Z";
+    const ISSUED_AT_UNIX: u64 = 1_704_067_200;
+
+    fn wallet() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    fn message() -> SiweMessage {
+        SiweMessage::builder()
+            .domain("example.com")
+            .address(wallet().address())
+            .statement("Sign in to Example")
+            .uri("https://example.com/login")
+            .chain_id(56)
+            .nonce("32891756")
+            .issued_at(ISSUED_AT)
+            .expiration_time("2024-01-01T01:00:00Z")
+            .resource("ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq")
+            .resource("https://example.com/my-web2-claim.json")
+            .build()
+            .unwrap()
+    }
+
+    fn options() -> VerifyOptions {
+        VerifyOptions::new()
+            .domain("example.com")
+            .nonce("32891756")
+            .time(ISSUED_AT_UNIX + 60)
+    }
+
+    // ─── format ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_display() {
+        let expected = format!(
+            "example.com wants you to sign in with your Ethereum account:\n\
+             {}\n\
+             \n\
+             Sign in to Example\n\
+             \n\
+             URI: https://example.com/login\n\
+             Version: 1\n\
+             Chain ID: 56\n\
+             Nonce: 32891756\n\
+             Issued At: 2024-01-01T00:00:00Z\n\
+             Expiration Time: 2024-01-01T01:00:00Z\n\
+             Resources:\n\
+             - ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq\n\
+             - https://example.com/my-web2-claim.json",
+            wallet().address()
+        );
+        assert_eq!(message().to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_without_statement() {
+        let mut message = message();
+        message.statement = None;
+        let text = message.to_string();
+        assert!(text.contains(&format!("{}\n\n\nURI: ", wallet().address())));
+    }
+
+    // ─── parsing ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_round_trip() {
+        let message = message();
+        assert_eq!(message.to_string().parse::<SiweMessage>().unwrap(), message);
+
+        let mut minimal = message.clone();
+        minimal.statement = None;
+        minimal.expiration_time = None;
+        minimal.resources.clear();
+        minimal.scheme = Some("https".to_string());
+        minimal.request_id = Some("req-1".to_string());
+        assert_eq!(minimal.to_string().parse::<SiweMessage>().unwrap(), minimal);
+    }
+
+    #[test]
+    fn test_parse_rejects_non_canonical_statement_less_form() {
+        let text = format!(
+            "example.com wants you to sign in with your Ethereum account:\n{}\n\nURI: https://example.com\nVersion: 1\nChain ID: 1\nNonce: abcdefgh\nIssued At: {}",
+            wallet().address(),
+            ISSUED_AT
+        );
+        assert!(text.parse::<SiweMessage>().is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        let good = message().to_string();
+        let lowercase = good.replace(
+            &wallet().address().to_string(),
+            &wallet().address().to_string().to_lowercase(),
+        );
+        for text in [
+            good.replace("wants you to sign in", "asks you to sign in"),
+            good.replace("Version: 1", "Version: 2"),
+            good.replace("Nonce: 32891756", "Nonce: short"),
+            good.replace("Chain ID: 56", "Chain ID: bsc"),
+            good.replace("Issued At: 2024-01-01T00:00:00Z", "Issued At: yesterday"),
+            good.replace("\nURI: https://example.com/login", ""),
+            format!("{}\nextra", good),
+            lowercase,
+        ] {
+            assert!(
+                matches!(text.parse::<SiweMessage>(), Err(Error::SiweError(_))),
+                "should reject:\n{}",
+                text
+            );
+        }
+    }
+
+    // ─── builder ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_builder_validation() {
+        let base = || {
+            SiweMessage::builder()
+                .domain("example.com")
+                .address(wallet().address())
+                .uri("https://example.com")
+                .chain_id(1)
+                .issued_at(ISSUED_AT)
+        };
+        assert!(base().nonce("abc").build().is_err());
+        assert!(base().nonce("abcd-efgh").build().is_err());
+        assert!(base().build().is_err());
+        assert!(base()
+            .nonce("abcdefgh")
+            .statement("two\nlines")
+            .build()
+            .is_err());
+        assert!(base().nonce("abcdefgh").build().is_ok());
+    }
+
+    #[test]
+    fn test_builder_unix_timestamps() {
+        let message = SiweMessage::builder()
+            .domain("example.com")
+            .address(wallet().address())
+            .uri("https://example.com")
+            .chain_id(1)
+            .nonce("abcdefgh")
+            .issued_at_unix(ISSUED_AT_UNIX)
+            .expiration_time_unix(ISSUED_AT_UNIX + 3600)
+            .build()
+            .unwrap();
+        assert_eq!(message.issued_at, ISSUED_AT);
+        assert_eq!(
+            message.expiration_time.as_deref(),
+            Some("2024-01-01T01:00:00Z")
+        );
+    }
+
+    // ─── signing and verification ────────────────────────────────────────────
+
+    #[test]
+    fn test_sign_and_verify() {
+        let message = message();
+        let signature = message.sign(&wallet()).unwrap();
+        message.verify(&signature, &options()).unwrap();
+
+        // Same as a plain personal_sign over the text
+        assert_eq!(
+            signature,
+            wallet()
+                .sign_message(message.to_string().as_bytes())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_verify_failures() {
+        let message = message();
+        let signature = message.sign(&wallet()).unwrap();
+
+        let cases = [
+            options().domain("evil.com"),
+            options().nonce("00000000"),
+            options().time(ISSUED_AT_UNIX + 3600),
+        ];
+        for case in cases {
+            assert!(matches!(
+                message.verify(&signature, &case),
+                Err(Error::SiweError(_))
+            ));
+        }
+
+        let mut tampered = message.clone();
+        tampered.chain_id = 1;
+        assert!(tampered.verify(&signature, &options()).is_err());
+    }
+
+    #[test]
+    fn test_verify_not_before() {
+        let mut message = message();
+        message.not_before = Some("2024-01-01T00:30:00Z".to_string());
+        let signature = message.sign(&wallet()).unwrap();
+
+        assert!(message.verify(&signature, &options()).is_err());
+        message
+            .verify(&signature, &options().time(ISSUED_AT_UNIX + 1800))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sign_rejects_wrong_account_or_chain() {
+        let other = Bip44Signer::from_private_key(&[2u8; 32]).unwrap();
+        assert!(matches!(message().sign(&other), Err(Error::SiweError(_))));
+
+        let bound = wallet().with_chain_id(ChainId::EthereumMainnet);
+        assert!(matches!(
+            message().sign(&bound),
+            Err(Error::ChainIdMismatch { .. })
+        ));
+    }
+
+    // ─── RFC 3339 ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(parse_rfc3339(ISSUED_AT).unwrap(), ISSUED_AT_UNIX as i64);
+        assert_eq!(
+            parse_rfc3339("2024-01-01T03:30:00.123+03:30").unwrap(),
+            ISSUED_AT_UNIX as i64
+        );
+        assert_eq!(
+            parse_rfc3339("2024-02-29T00:00:00Z").unwrap(),
+            1_709_164_800
+        );
+
+        for s in [
+            "2024-01-01",
+            "2024-01-01 00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00+0100",
+        ] {
+            assert!(parse_rfc3339(s).is_err(), "{:?} should be rejected", s);
+        }
+    }
+
+    #[test]
+    fn test_format_rfc3339_round_trip() {
+        for ts in [0, ISSUED_AT_UNIX, 1_709_164_800, 4_102_444_799] {
+            assert_eq!(parse_rfc3339(&format_rfc3339(ts)).unwrap(), ts as i64);
+        }
+        assert_eq!(format_rfc3339(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+}
@@ -0,0 +1,888 @@
+//! EIP-5564 stealth addresses.
+//!
+//! Implements [EIP-5564](https://eips.ethereum.org/EIPS/eip-5564) scheme 1 (secp256k1 with
+//! view tags) for Ethereum, BSC and other EVM chains. A recipient publishes a single
+//! *stealth meta-address*; every sender derives a fresh, unlinkable stealth address from it
+//! and publishes an `Announcement` event so the recipient can find the payment.
+//!
+//! # Overview
+//!
+//! ```text
+//! sender:    s   = p_ephemeral · P_view
+//!            s_h = keccak256(serP(s))            view tag = s_h[0]
+//!            P_stealth = P_spend + s_h · G       address  = addr(P_stealth)
+//!
+//! recipient: s   = p_view · P_ephemeral          (same shared secret)
+//!            p_stealth = p_spend + s_h
+//! ```
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::stealth::{StealthKeys, StealthMetaAddress};
+//!
+//! // Recipient publishes a meta-address
+//! let keys = StealthKeys::from_private_keys(&[1u8; 32], &[2u8; 32]).unwrap();
+//! let meta = keys.meta_address("eth");
+//! let published = meta.to_string(); // "st:eth:0x…"
+//!
+//! // Sender derives a one-time stealth address (use fresh randomness in production)
+//! let meta: StealthMetaAddress = published.parse().unwrap();
+//! let generated = meta.generate_stealth_address(&[3u8; 32]).unwrap();
+//!
+//! // Recipient recognises the payment and can sign for the stealth address
+//! let signer = keys
+//!     .stealth_signer(&generated.ephemeral_public_key, generated.stealth_address)
+//!     .unwrap()
+//!     .expect("announcement belongs to this recipient");
+//! assert_eq!(signer.address(), generated.stealth_address);
+//! ```
+
+use crate::eip712::keccak256;
+use crate::{Address, Bip44Signer, Error, Result};
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{NonZeroScalar, ProjectivePoint, PublicKey, Scalar, SecretKey, U256};
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+// ─── Constants ───────────────────────────────────────────────────────────────
+
+/// EIP-5564 scheme ID for secp256k1 with view tags.
+pub const SCHEME_ID_SECP256K1: u64 = 1;
+
+/// Canonical `ERC5564Announcer` singleton address (same on every chain).
+pub const ERC5564_ANNOUNCER: &str = "0x55649E01B5Df198D18D95b5cc5051630cfD45564";
+
+/// Canonical signature of the `Announcement` event.
+pub const ANNOUNCEMENT_EVENT_SIGNATURE: &str = "Announcement(uint256,address,address,bytes,bytes)";
+
+/// Prefix of the stealth meta-address string encoding.
+const META_ADDRESS_PREFIX: &str = "st";
+
+/// Hardened child of the account key under which [`StealthKeys::from_account`] derives.
+const STEALTH_BRANCH: u32 = 5564;
+
+/// Length of a compressed secp256k1 public key.
+const COMPRESSED_KEY_LENGTH: usize = 33;
+
+/// Returns `keccak256("Announcement(uint256,address,address,bytes,bytes)")` — `topic0` of
+/// announcement logs.
+pub fn announcement_topic() -> [u8; 32] {
+    keccak256(ANNOUNCEMENT_EVENT_SIGNATURE.as_bytes())
+}
+
+// ─── Meta-Address ────────────────────────────────────────────────────────────
+
+/// A stealth meta-address: the recipient's spending and viewing public keys.
+///
+/// Encoded as `st:<chain>:0x<spendingPubKey><viewingPubKey>` with 33-byte compressed keys,
+/// where `<chain>` is an EIP-3770 short name such as `eth` or `bnb`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::stealth::StealthKeys;
+///
+/// let keys = StealthKeys::from_private_keys(&[1u8; 32], &[2u8; 32]).unwrap();
+/// let meta = keys.meta_address("bnb");
+///
+/// assert!(meta.to_string().starts_with("st:bnb:0x"));
+/// assert_eq!(meta.chain(), "bnb");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StealthMetaAddress {
+    chain: String,
+    spending_public_key: [u8; 33],
+    viewing_public_key: [u8; 33],
+}
+
+impl StealthMetaAddress {
+    /// Creates a meta-address from compressed spending and viewing public keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StealthError`] if either key is not a valid secp256k1 point.
+    pub fn new(
+        chain: &str,
+        spending_public_key: [u8; 33],
+        viewing_public_key: [u8; 33],
+    ) -> Result<Self> {
+        parse_public_key(&spending_public_key)?;
+        parse_public_key(&viewing_public_key)?;
+        Ok(Self {
+            chain: chain.to_string(),
+            spending_public_key,
+            viewing_public_key,
+        })
+    }
+
+    /// Returns the EIP-3770 chain short name.
+    pub fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    /// Returns the compressed spending public key.
+    pub fn spending_public_key(&self) -> [u8; 33] {
+        self.spending_public_key
+    }
+
+    /// Returns the compressed viewing public key.
+    pub fn viewing_public_key(&self) -> [u8; 33] {
+        self.viewing_public_key
+    }
+
+    /// Derives a one-time stealth address for this recipient.
+    ///
+    /// `ephemeral_private_key` must be freshly generated from a secure random source for
+    /// every payment; reusing it links payments together.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StealthError`] if the ephemeral key is not a valid secp256k1 scalar.
+    pub fn generate_stealth_address(
+        &self,
+        ephemeral_private_key: &[u8; 32],
+    ) -> Result<GeneratedStealthAddress> {
+        let ephemeral = SecretKey::from_bytes(ephemeral_private_key.into())
+            .map_err(|_| Error::StealthError("Invalid ephemeral private key".to_string()))?;
+        let viewing = parse_public_key(&self.viewing_public_key)?;
+        let spending = parse_public_key(&self.spending_public_key)?;
+
+        let hashed_secret = hashed_shared_secret(&ephemeral, &viewing);
+        let stealth_point =
+            spending.to_projective() + ProjectivePoint::GENERATOR * scalar_from(&hashed_secret);
+
+        Ok(GeneratedStealthAddress {
+            stealth_address: address_from_point(&stealth_point)?,
+            ephemeral_public_key: compress(&ephemeral.public_key().to_projective()),
+            view_tag: hashed_secret[0],
+        })
+    }
+}
+
+impl fmt::Display for StealthMetaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:0x{}{}",
+            META_ADDRESS_PREFIX,
+            self.chain,
+            hex::encode(self.spending_public_key),
+            hex::encode(self.viewing_public_key)
+        )
+    }
+}
+
+impl FromStr for StealthMetaAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        let (prefix, chain, keys) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(prefix), Some(chain), Some(keys)) => (prefix, chain, keys),
+            _ => {
+                return Err(Error::StealthError(format!(
+                    "Invalid meta-address format: {}",
+                    s
+                )))
+            }
+        };
+
+        if prefix != META_ADDRESS_PREFIX {
+            return Err(Error::StealthError(format!(
+                "Invalid meta-address prefix: {}",
+                prefix
+            )));
+        }
+        if chain.is_empty() {
+            return Err(Error::StealthError("Missing chain short name".to_string()));
+        }
+
+        let hex_str = keys.strip_prefix("0x").unwrap_or(keys);
+        let bytes = hex::decode(hex_str).map_err(|e| Error::HexError(e.to_string()))?;
+        if bytes.len() != 2 * COMPRESSED_KEY_LENGTH {
+            return Err(Error::StealthError(format!(
+                "Meta-address keys must be {} bytes, got {}",
+                2 * COMPRESSED_KEY_LENGTH,
+                bytes.len()
+            )));
+        }
+
+        let mut spending = [0u8; 33];
+        let mut viewing = [0u8; 33];
+        spending.copy_from_slice(&bytes[..33]);
+        viewing.copy_from_slice(&bytes[33..]);
+
+        Self::new(chain, spending, viewing)
+    }
+}
+
+/// The result of deriving a stealth address on the sender side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratedStealthAddress {
+    /// The one-time address that receives the funds.
+    pub stealth_address: Address,
+    /// The compressed ephemeral public key to publish in the announcement.
+    pub ephemeral_public_key: [u8; 33],
+    /// The view tag to publish as the first byte of the announcement metadata.
+    pub view_tag: u8,
+}
+
+// ─── Recipient Keys ──────────────────────────────────────────────────────────
+
+/// The recipient's spending and viewing private keys.
+///
+/// The viewing key alone is enough to detect incoming payments; the spending key is
+/// additionally required to control the funds.
+pub struct StealthKeys {
+    spending_key: SecretKey,
+    viewing_key: SecretKey,
+}
+
+impl StealthKeys {
+    /// Creates stealth keys from raw 32-byte spending and viewing private keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StealthError`] if either key is not a valid secp256k1 scalar.
+    pub fn from_private_keys(spending_key: &[u8; 32], viewing_key: &[u8; 32]) -> Result<Self> {
+        let spending_key = SecretKey::from_bytes(spending_key.into())
+            .map_err(|_| Error::StealthError("Invalid spending private key".to_string()))?;
+        let viewing_key = SecretKey::from_bytes(viewing_key.into())
+            .map_err(|_| Error::StealthError("Invalid viewing private key".to_string()))?;
+        Ok(Self {
+            spending_key,
+            viewing_key,
+        })
+    }
+
+    /// Derives stealth keys from a BIP-44 account.
+    ///
+    /// Both keys come from a dedicated, fully hardened branch of the account:
+    /// the spending key is `m/44'/60'/account'/5564'/0'/index'` and the viewing key
+    /// `m/44'/60'/account'/5564'/1'/index'`. Neither is ever a receive or change key,
+    /// and because every step is hardened, a viewing key shared with a scanner reveals
+    /// nothing about its parent, its siblings or the account's exported xpubs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key derivation fails or `address_index` is not below 2³¹.
+    pub fn from_account(account: &khodpay_bip44::Account, address_index: u32) -> Result<Self> {
+        let spending = Zeroizing::new(stealth_branch_key(account, 0, address_index)?);
+        let viewing = Zeroizing::new(stealth_branch_key(account, 1, address_index)?);
+        Self::from_private_keys(&spending, &viewing)
+    }
+
+    /// Returns the stealth meta-address for these keys on the given chain.
+    pub fn meta_address(&self, chain: &str) -> StealthMetaAddress {
+        StealthMetaAddress {
+            chain: chain.to_string(),
+            spending_public_key: compress(&self.spending_key.public_key().to_projective()),
+            viewing_public_key: compress(&self.viewing_key.public_key().to_projective()),
+        }
+    }
+
+    /// Checks whether an announcement is addressed to these keys.
+    ///
+    /// Announcements with a different scheme ID, a malformed ephemeral key or a
+    /// mismatching view tag are rejected without error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if the derived stealth point is invalid.
+    pub fn check_announcement(&self, announcement: &Announcement) -> Result<bool> {
+        if announcement.scheme_id != SCHEME_ID_SECP256K1 {
+            return Ok(false);
+        }
+        let ephemeral = match parse_public_key(&announcement.ephemeral_public_key) {
+            Ok(key) => key,
+            Err(_) => return Ok(false),
+        };
+
+        let hashed_secret = hashed_shared_secret(&self.viewing_key, &ephemeral);
+        if let Some(view_tag) = announcement.view_tag() {
+            if view_tag != hashed_secret[0] {
+                return Ok(false);
+            }
+        }
+
+        let stealth_point = self.spending_key.public_key().to_projective()
+            + ProjectivePoint::GENERATOR * scalar_from(&hashed_secret);
+        Ok(address_from_point(&stealth_point)? == announcement.stealth_address)
+    }
+
+    /// Returns a signer controlling the stealth address derived from `ephemeral_public_key`.
+    ///
+    /// Returns `Ok(None)` if the derived address does not match `stealth_address`, i.e. the
+    /// payment was not addressed to these keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StealthError`] if the ephemeral key is malformed.
+    pub fn stealth_signer(
+        &self,
+        ephemeral_public_key: &[u8],
+        stealth_address: Address,
+    ) -> Result<Option<Bip44Signer>> {
+        let ephemeral = parse_public_key(ephemeral_public_key)?;
+        let hashed_secret = hashed_shared_secret(&self.viewing_key, &ephemeral);
+
+        let stealth_scalar =
+            *self.spending_key.to_nonzero_scalar().as_ref() + scalar_from(&hashed_secret);
+        let stealth_key = Option::<NonZeroScalar>::from(NonZeroScalar::new(stealth_scalar))
+            .ok_or_else(|| Error::StealthError("Derived stealth key is zero".to_string()))?;
+
+        let key_bytes: Zeroizing<[u8; 32]> =
+            Zeroizing::new(SecretKey::from(stealth_key).to_bytes().into());
+        let signer = Bip44Signer::from_private_key(&key_bytes)?;
+
+        if signer.address() == stealth_address {
+            Ok(Some(signer))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Debug for StealthKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StealthKeys")
+            .field("spending_key", &"[REDACTED]")
+            .field("viewing_key", &"[REDACTED]")
+            .finish()
+    }
+}
+
+// ─── Announcements ───────────────────────────────────────────────────────────
+
+/// A parsed `ERC5564Announcer` `Announcement` event.
+///
+/// ```text
+/// event Announcement(
+///     uint256 indexed schemeId,
+///     address indexed stealthAddress,
+///     address indexed caller,
+///     bytes ephemeralPubKey,
+///     bytes metadata
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// The stealth address scheme ID (1 for secp256k1 with view tags).
+    pub scheme_id: u64,
+    /// The stealth address that received the funds.
+    pub stealth_address: Address,
+    /// The account that emitted the announcement.
+    pub caller: Address,
+    /// The sender's ephemeral public key.
+    pub ephemeral_public_key: Vec<u8>,
+    /// Announcement metadata; the first byte is the view tag.
+    pub metadata: Vec<u8>,
+}
+
+impl Announcement {
+    /// Parses an announcement from a raw event log.
+    ///
+    /// # Arguments
+    ///
+    /// * `topics` - The log topics: event signature, `schemeId`, `stealthAddress`, `caller`
+    /// * `data` - The ABI-encoded `(bytes ephemeralPubKey, bytes metadata)` payload
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StealthError`] if the log is not a well-formed announcement.
+    pub fn from_log(topics: &[[u8; 32]], data: &[u8]) -> Result<Self> {
+        if topics.len() != 4 {
+            return Err(Error::StealthError(format!(
+                "Announcement log must have 4 topics, got {}",
+                topics.len()
+            )));
+        }
+        if topics[0] != announcement_topic() {
+            return Err(Error::StealthError(
+                "Log is not an Announcement event".to_string(),
+            ));
+        }
+
+        let scheme_id = word_to_u64(&topics[1])
+            .ok_or_else(|| Error::StealthError("Scheme ID out of range".to_string()))?;
+        let stealth_address = word_to_address(&topics[2])?;
+        let caller = word_to_address(&topics[3])?;
+
+        let ephemeral_public_key = decode_bytes_param(data, 0)?;
+        let metadata = decode_bytes_param(data, 1)?;
+
+        Ok(Self {
+            scheme_id,
+            stealth_address,
+            caller,
+            ephemeral_public_key,
+            metadata,
+        })
+    }
+
+    /// Returns the view tag (first metadata byte), if present.
+    pub fn view_tag(&self) -> Option<u8> {
+        self.metadata.first().copied()
+    }
+}
+
+// ─── Internal Helpers ────────────────────────────────────────────────────────
+
+/// Parses a SEC1-encoded secp256k1 public key.
+fn parse_public_key(bytes: &[u8]) -> Result<PublicKey> {
+    PublicKey::from_sec1_bytes(bytes)
+        .map_err(|_| Error::StealthError("Invalid secp256k1 public key".to_string()))
+}
+
+/// Computes `keccak256(serP(secret · point))` with the compressed point encoding.
+fn hashed_shared_secret(secret: &SecretKey, point: &PublicKey) -> [u8; 32] {
+    let shared = point.to_projective() * *secret.to_nonzero_scalar();
+    keccak256(&compress(&shared))
+}
+
+/// Reduces a 32-byte hash modulo the curve order.
+fn scalar_from(hash: &[u8; 32]) -> Scalar {
+    <Scalar as Reduce<U256>>::reduce_bytes(hash.into())
+}
+
+/// Derives the private key at `account'/5564'/role'/index'`.
+fn stealth_branch_key(
+    account: &khodpay_bip44::Account,
+    role: u32,
+    address_index: u32,
+) -> Result<[u8; 32]> {
+    use khodpay_bip32::ChildNumber;
+
+    let key = [STEALTH_BRANCH, role, address_index]
+        .into_iter()
+        .try_fold(account.extended_key().clone(), |key, index| {
+            key.derive_child(ChildNumber::Hardened(index))
+        })?;
+    Ok(key.private_key().to_bytes())
+}
+
+/// Serializes a point in 33-byte compressed form.
+fn compress(point: &ProjectivePoint) -> [u8; 33] {
+    let encoded = point.to_affine().to_encoded_point(true);
+    let mut out = [0u8; 33];
+    out.copy_from_slice(encoded.as_bytes());
+    out
+}
+
+/// Derives the EVM address of a curve point.
+fn address_from_point(point: &ProjectivePoint) -> Result<Address> {
+    let encoded = point.to_affine().to_encoded_point(false);
+    let bytes = encoded.as_bytes();
+    if bytes.len() != 65 {
+        return Err(Error::StealthError(
+            "Stealth point is the identity".to_string(),
+        ));
+    }
+    Address::from_public_key_bytes(&bytes[1..])
+}
+
+/// Interprets an ABI word as a `u64`, rejecting values that do not fit.
+fn word_to_u64(word: &[u8; 32]) -> Option<u64> {
+    if word[..24].iter().any(|&b| b != 0) {
+        return None;
+    }
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&word[24..]);
+    Some(u64::from_be_bytes(bytes))
+}
+
+/// Interprets an ABI word as a left-padded address.
+fn word_to_address(word: &[u8; 32]) -> Result<Address> {
+    if word[..12].iter().any(|&b| b != 0) {
+        return Err(Error::StealthError(
+            "Address topic has non-zero padding".to_string(),
+        ));
+    }
+    Address::from_slice(&word[12..])
+}
+
+/// Reads a usize from the ABI word starting at `offset`.
+fn read_usize(data: &[u8], offset: usize) -> Result<usize> {
+    let word = offset
+        .checked_add(32)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| Error::StealthError("Announcement data is truncated".to_string()))?;
+    let mut array = [0u8; 32];
+    array.copy_from_slice(word);
+    word_to_u64(&array)
+        .and_then(|v| usize::try_from(v).ok())
+        .ok_or_else(|| Error::StealthError("Announcement data offset out of range".to_string()))
+}
+
+/// Decodes the `index`-th dynamic `bytes` parameter of an ABI-encoded tuple.
+fn decode_bytes_param(data: &[u8], index: usize) -> Result<Vec<u8>> {
+    let offset = read_usize(data, index * 32)?;
+    let length = read_usize(data, offset)?;
+    let start = offset
+        .checked_add(32)
+        .ok_or_else(|| Error::StealthError("Announcement data offset out of range".to_string()))?;
+    let end = start
+        .checked_add(length)
+        .ok_or_else(|| Error::StealthError("Announcement data length out of range".to_string()))?;
+    data.get(start..end)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| Error::StealthError("Announcement data is truncated".to_string()))
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eip712::{encode_address, encode_uint64};
+
+    const SPENDING_KEY: [u8; 32] = [0x11; 32];
+    const VIEWING_KEY: [u8; 32] = [0x22; 32];
+    const EPHEMERAL_KEY: [u8; 32] = [0x33; 32];
+
+    fn test_keys() -> StealthKeys {
+        StealthKeys::from_private_keys(&SPENDING_KEY, &VIEWING_KEY).unwrap()
+    }
+
+    fn caller() -> Address {
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap()
+    }
+
+    /// ABI-encodes `(bytes, bytes)` as emitted in the announcement log data.
+    fn encode_log_data(ephemeral: &[u8], metadata: &[u8]) -> Vec<u8> {
+        fn padded(bytes: &[u8]) -> Vec<u8> {
+            let mut out = encode_uint64(bytes.len() as u64).to_vec();
+            out.extend_from_slice(bytes);
+            out.resize(32 + bytes.len().div_ceil(32) * 32, 0);
+            out
+        }
+        let first = padded(ephemeral);
+        let second = padded(metadata);
+
+        let mut data = encode_uint64(64).to_vec();
+        data.extend_from_slice(&encode_uint64(64 + first.len() as u64));
+        data.extend_from_slice(&first);
+        data.extend_from_slice(&second);
+        data
+    }
+
+    fn announcement_log(generated: &GeneratedStealthAddress) -> (Vec<[u8; 32]>, Vec<u8>) {
+        let topics = vec![
+            announcement_topic(),
+            encode_uint64(SCHEME_ID_SECP256K1),
+            encode_address(&generated.stealth_address),
+            encode_address(&caller()),
+        ];
+        let data = encode_log_data(&generated.ephemeral_public_key, &[generated.view_tag, 0xaa]);
+        (topics, data)
+    }
+
+    #[test]
+    fn test_announcement_topic() {
+        assert_eq!(
+            hex::encode(announcement_topic()),
+            hex::encode(keccak256(
+                b"Announcement(uint256,address,address,bytes,bytes)"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_meta_address_roundtrip() {
+        let meta = test_keys().meta_address("eth");
+        let encoded = meta.to_string();
+
+        assert!(encoded.starts_with("st:eth:0x"));
+        assert_eq!(encoded.len(), "st:eth:0x".len() + 132);
+
+        let parsed: StealthMetaAddress = encoded.parse().unwrap();
+        assert_eq!(parsed, meta);
+    }
+
+    #[test]
+    fn test_meta_address_key_order() {
+        let keys = test_keys();
+        let meta = keys.meta_address("eth");
+        let spending_only = StealthKeys::from_private_keys(&SPENDING_KEY, &SPENDING_KEY)
+            .unwrap()
+            .meta_address("eth");
+
+        // Spending key is encoded first
+        assert_eq!(
+            meta.spending_public_key(),
+            spending_only.spending_public_key()
+        );
+        assert_ne!(meta.viewing_public_key(), meta.spending_public_key());
+    }
+
+    #[test]
+    fn test_meta_address_parse_invalid_prefix() {
+        let encoded = test_keys().meta_address("eth").to_string();
+        let bad = encoded.replacen("st:", "sx:", 1);
+        assert!(matches!(
+            bad.parse::<StealthMetaAddress>(),
+            Err(Error::StealthError(_))
+        ));
+    }
+
+    #[test]
+    fn test_meta_address_parse_missing_parts() {
+        assert!("st:eth".parse::<StealthMetaAddress>().is_err());
+        assert!("".parse::<StealthMetaAddress>().is_err());
+    }
+
+    #[test]
+    fn test_meta_address_parse_wrong_length() {
+        let result = "st:eth:0x0202".parse::<StealthMetaAddress>();
+        assert!(matches!(result, Err(Error::StealthError(_))));
+    }
+
+    #[test]
+    fn test_meta_address_parse_invalid_point() {
+        let encoded = format!("st:eth:0x{}", "05".repeat(66));
+        assert!(matches!(
+            encoded.parse::<StealthMetaAddress>(),
+            Err(Error::StealthError(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate_is_deterministic_for_ephemeral_key() {
+        let meta = test_keys().meta_address("eth");
+        let a = meta.generate_stealth_address(&EPHEMERAL_KEY).unwrap();
+        let b = meta.generate_stealth_address(&EPHEMERAL_KEY).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_generate_unlinkable_across_ephemeral_keys() {
+        let meta = test_keys().meta_address("eth");
+        let a = meta.generate_stealth_address(&[0x33; 32]).unwrap();
+        let b = meta.generate_stealth_address(&[0x44; 32]).unwrap();
+
+        assert_ne!(a.stealth_address, b.stealth_address);
+        assert_ne!(a.ephemeral_public_key, b.ephemeral_public_key);
+    }
+
+    #[test]
+    fn test_generate_invalid_ephemeral_key() {
+        let meta = test_keys().meta_address("eth");
+        assert!(meta.generate_stealth_address(&[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_stealth_signer_controls_address() {
+        let keys = test_keys();
+        let generated = keys
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+
+        let signer = keys
+            .stealth_signer(&generated.ephemeral_public_key, generated.stealth_address)
+            .unwrap()
+            .unwrap();
+        assert_eq!(signer.address(), generated.stealth_address);
+    }
+
+    #[test]
+    fn test_stealth_signer_other_recipient() {
+        let generated = test_keys()
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let other = StealthKeys::from_private_keys(&[0x55; 32], &[0x66; 32]).unwrap();
+
+        let signer = other
+            .stealth_signer(&generated.ephemeral_public_key, generated.stealth_address)
+            .unwrap();
+        assert!(signer.is_none());
+    }
+
+    #[test]
+    fn test_announcement_from_log() {
+        let generated = test_keys()
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, data) = announcement_log(&generated);
+
+        let announcement = Announcement::from_log(&topics, &data).unwrap();
+        assert_eq!(announcement.scheme_id, SCHEME_ID_SECP256K1);
+        assert_eq!(announcement.stealth_address, generated.stealth_address);
+        assert_eq!(announcement.caller, caller());
+        assert_eq!(
+            announcement.ephemeral_public_key,
+            generated.ephemeral_public_key.to_vec()
+        );
+        assert_eq!(announcement.metadata, vec![generated.view_tag, 0xaa]);
+        assert_eq!(announcement.view_tag(), Some(generated.view_tag));
+    }
+
+    #[test]
+    fn test_announcement_wrong_topic() {
+        let generated = test_keys()
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (mut topics, data) = announcement_log(&generated);
+        topics[0] = [0u8; 32];
+
+        assert!(matches!(
+            Announcement::from_log(&topics, &data),
+            Err(Error::StealthError(_))
+        ));
+    }
+
+    #[test]
+    fn test_announcement_wrong_topic_count() {
+        let result = Announcement::from_log(&[announcement_topic()], &[]);
+        assert!(matches!(result, Err(Error::StealthError(_))));
+    }
+
+    #[test]
+    fn test_announcement_truncated_data() {
+        let generated = test_keys()
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, data) = announcement_log(&generated);
+
+        for len in [0, 31, 64, 100, data.len() - 40] {
+            assert!(
+                Announcement::from_log(&topics, &data[..len]).is_err(),
+                "truncated to {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_announcement_huge_offset() {
+        let generated = test_keys()
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, mut data) = announcement_log(&generated);
+        data[24..32].copy_from_slice(&u64::MAX.to_be_bytes());
+
+        assert!(Announcement::from_log(&topics, &data).is_err());
+    }
+
+    #[test]
+    fn test_check_announcement_match() {
+        let keys = test_keys();
+        let generated = keys
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, data) = announcement_log(&generated);
+        let announcement = Announcement::from_log(&topics, &data).unwrap();
+
+        assert!(keys.check_announcement(&announcement).unwrap());
+    }
+
+    #[test]
+    fn test_check_announcement_other_recipient() {
+        let generated = test_keys()
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, data) = announcement_log(&generated);
+        let announcement = Announcement::from_log(&topics, &data).unwrap();
+        let other = StealthKeys::from_private_keys(&[0x55; 32], &[0x66; 32]).unwrap();
+
+        assert!(!other.check_announcement(&announcement).unwrap());
+    }
+
+    #[test]
+    fn test_check_announcement_view_tag_mismatch() {
+        let keys = test_keys();
+        let generated = keys
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, data) = announcement_log(&generated);
+        let mut announcement = Announcement::from_log(&topics, &data).unwrap();
+        announcement.metadata[0] = generated.view_tag.wrapping_add(1);
+
+        assert!(!keys.check_announcement(&announcement).unwrap());
+    }
+
+    #[test]
+    fn test_check_announcement_other_scheme() {
+        let keys = test_keys();
+        let generated = keys
+            .meta_address("eth")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+        let (topics, data) = announcement_log(&generated);
+        let mut announcement = Announcement::from_log(&topics, &data).unwrap();
+        announcement.scheme_id = 2;
+
+        assert!(!keys.check_announcement(&announcement).unwrap());
+    }
+
+    #[test]
+    fn test_from_account() {
+        use khodpay_bip32::Network;
+        use khodpay_bip44::{CoinType, Purpose, Wallet};
+
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mut wallet =
+            Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet).unwrap();
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .unwrap();
+
+        let keys = StealthKeys::from_account(account, 0).unwrap();
+        let generated = keys
+            .meta_address("bnb")
+            .generate_stealth_address(&EPHEMERAL_KEY)
+            .unwrap();
+
+        // The stealth address is never the account's regular address
+        let regular = Bip44Signer::new(account, 0).unwrap().address();
+        assert_ne!(generated.stealth_address, regular);
+
+        // Neither key is a receive or change key of the account
+        let spending = keys.spending_key.to_bytes();
+        let viewing = keys.viewing_key.to_bytes();
+        for index in 0..4 {
+            for key in [
+                account.derive_external(index).unwrap(),
+                account.derive_internal(index).unwrap(),
+            ] {
+                let key = key.private_key().to_bytes();
+                assert_ne!(key[..], spending[..]);
+                assert_ne!(key[..], viewing[..]);
+            }
+        }
+
+        // Both sit on the hardened stealth branch of the account
+        for (path, key) in [("m/5564'/0'/0'", spending), ("m/5564'/1'/0'", viewing)] {
+            let expected = account
+                .extended_key()
+                .derive_path(&path.parse().unwrap())
+                .unwrap();
+            assert_eq!(expected.private_key().to_bytes()[..], key[..]);
+        }
+
+        let signer = keys
+            .stealth_signer(&generated.ephemeral_public_key, generated.stealth_address)
+            .unwrap()
+            .unwrap();
+        assert_eq!(signer.address(), generated.stealth_address);
+    }
+
+    #[test]
+    fn test_debug_redacts_keys() {
+        let debug = format!("{:?}", test_keys());
+        assert!(debug.contains("REDACTED"));
+        assert!(!debug.contains("1111"));
+    }
+}
@@ -0,0 +1,1158 @@
+//! ERC-20 token registry: token lists, custom tokens and per-chain persistence.
+//!
+//! A [`TokenRegistry`] is the set of tokens a wallet knows, per chain, for the balance
+//! and history screens to query. It is filled from two sources:
+//!
+//! - **Token lists** in the [Token Lists](https://tokenlists.org) JSON schema used by
+//!   Uniswap, PancakeSwap and CoinGecko, parsed by [`TokenList::from_json`].
+//! - **Custom tokens** the user adds by contract address. With the `net` feature,
+//!   `fetch_token` reads their symbol, name and decimals from the chain in one
+//!   Multicall3 batch, together with the chain ID, so nothing the user types is
+//!   trusted; `verify_token` checks a listed token against the chain the same way.
+//!
+//! Every change is written through to a [`TokenStore`], one set of tokens per chain.
+//!
+//! # Quick Start
+//!
+//! ```rust
+//! use khodpay_signing::tokens::{MemoryStore, TokenList, TokenRegistry, TokenSource};
+//! use khodpay_signing::ChainId;
+//!
+//! let list = TokenList::from_json(r#"{
+//!     "name": "Example List",
+//!     "timestamp": "2024-01-01T00:00:00Z",
+//!     "version": { "major": 1, "minor": 0, "patch": 0 },
+//!     "tokens": [{
+//!         "chainId": 56,
+//!         "address": "0x55d398326f99059fF775485246999027B3197955",
+//!         "symbol": "USDT",
+//!         "name": "Tether USD",
+//!         "decimals": 18
+//!     }]
+//! }"#).unwrap();
+//!
+//! let mut registry = TokenRegistry::open(MemoryStore::new()).unwrap();
+//! assert_eq!(registry.import_list(&list).unwrap(), 1);
+//!
+//! let usdt = registry.find_symbol(ChainId::BscMainnet, "usdt").next().unwrap();
+//! assert_eq!(usdt.decimals, 18);
+//! assert_eq!(usdt.source, TokenSource::List("Example List".to_string()));
+//! assert_eq!(registry.store().tokens(ChainId::BscMainnet).len(), 1);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, ChainId, CoinAmount, Error, Result, U256};
+
+/// Longest accepted token symbol, in characters.
+const MAX_SYMBOL_LEN: usize = 32;
+
+/// Longest accepted token name, in characters.
+const MAX_NAME_LEN: usize = 64;
+
+/// Where a token in the registry came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenSource {
+    /// Imported from the token list with this name.
+    List(String),
+    /// Added by the user by contract address.
+    Custom,
+}
+
+/// An ERC-20 token on one chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// Chain the contract is deployed on.
+    pub chain_id: ChainId,
+    /// Contract address.
+    pub address: Address,
+    /// Ticker symbol, e.g. `USDT`. Not unique: anyone can deploy a token of any symbol.
+    pub symbol: String,
+    /// Display name, e.g. `Tether USD`.
+    pub name: String,
+    /// Decimal places of one whole token.
+    pub decimals: u8,
+    /// Logo image URI, if the token list has one.
+    pub logo_uri: Option<String>,
+    /// Where the token came from.
+    pub source: TokenSource,
+}
+
+impl TokenInfo {
+    /// Returns `value` base units of this token as an amount with its decimals.
+    pub fn amount(&self, value: impl Into<U256>) -> CoinAmount {
+        CoinAmount::new(value, self.decimals)
+    }
+
+    fn key(&self) -> [u8; 20] {
+        self.address.to_bytes()
+    }
+
+    fn validate(&self) -> Result<()> {
+        validate_text("symbol", &self.symbol, MAX_SYMBOL_LEN)?;
+        validate_text("name", &self.name, MAX_NAME_LEN)
+    }
+}
+
+fn validate_text(field: &str, value: &str, max_len: usize) -> Result<()> {
+    if value.trim().is_empty() {
+        return Err(Error::TokenError(format!("empty {}", field)));
+    }
+    if value.chars().count() > max_len {
+        return Err(Error::TokenError(format!(
+            "{} longer than {} characters",
+            field, max_len
+        )));
+    }
+    if value.chars().any(char::is_control) {
+        return Err(Error::TokenError(format!(
+            "{} contains control characters",
+            field
+        )));
+    }
+    Ok(())
+}
+
+/// A token as it appears in a token list, and in the files of a [`DirectoryStore`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawToken {
+    chain_id: u64,
+    address: String,
+    symbol: String,
+    name: String,
+    decimals: u8,
+    #[serde(rename = "logoURI", default, skip_serializing_if = "Option::is_none")]
+    logo_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<TokenSource>,
+}
+
+impl RawToken {
+    fn into_token(self, default_source: Option<&TokenSource>) -> Result<TokenInfo> {
+        let source = self
+            .source
+            .or_else(|| default_source.cloned())
+            .ok_or_else(|| Error::TokenError("missing token source".to_string()))?;
+        let token = TokenInfo {
+            chain_id: ChainId::from(self.chain_id),
+            address: self.address.parse()?,
+            symbol: self.symbol,
+            name: self.name,
+            decimals: self.decimals,
+            logo_uri: self.logo_uri,
+            source,
+        };
+        token.validate()?;
+        Ok(token)
+    }
+}
+
+impl From<&TokenInfo> for RawToken {
+    fn from(token: &TokenInfo) -> Self {
+        Self {
+            chain_id: token.chain_id.value(),
+            address: token.address.to_checksum_string(),
+            symbol: token.symbol.clone(),
+            name: token.name.clone(),
+            decimals: token.decimals,
+            logo_uri: token.logo_uri.clone(),
+            source: Some(token.source.clone()),
+        }
+    }
+}
+
+// ─── Token lists ─────────────────────────────────────────────────────────────
+
+/// Semantic version of a [`TokenList`]. Orders like the version numbers it holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TokenListVersion {
+    /// Bumped when tokens are removed.
+    pub major: u32,
+    /// Bumped when tokens are added.
+    pub minor: u32,
+    /// Bumped when token details change.
+    pub patch: u32,
+}
+
+#[derive(Deserialize)]
+struct RawList {
+    name: String,
+    version: TokenListVersion,
+    tokens: Vec<RawToken>,
+}
+
+/// A parsed token list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenList {
+    name: String,
+    version: TokenListVersion,
+    tokens: Vec<TokenInfo>,
+}
+
+impl TokenList {
+    /// Parses a token list in the Token Lists JSON schema.
+    ///
+    /// Fields this crate does not use, such as `tags` and `extensions`, are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TokenError`] if the JSON does not follow the schema, a token has
+    /// an invalid address, an empty or overlong symbol or name, or appears twice.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let raw: RawList = serde_json::from_str(json)
+            .map_err(|e| Error::TokenError(format!("invalid token list: {}", e)))?;
+        validate_text("list name", &raw.name, MAX_NAME_LEN)?;
+
+        let source = TokenSource::List(raw.name.clone());
+        let mut seen = BTreeMap::new();
+        let mut tokens = Vec::with_capacity(raw.tokens.len());
+        for (i, mut entry) in raw.tokens.into_iter().enumerate() {
+            // A list cannot mark its own tokens as custom.
+            entry.source = None;
+            let token = entry
+                .into_token(Some(&source))
+                .map_err(|e| Error::TokenError(format!("{}: token {}: {}", raw.name, i, e)))?;
+            if seen
+                .insert((token.chain_id.value(), token.key()), i)
+                .is_some()
+            {
+                return Err(Error::TokenError(format!(
+                    "{}: token {}: {} on chain {} listed twice",
+                    raw.name, i, token.address, token.chain_id
+                )));
+            }
+            tokens.push(token);
+        }
+
+        Ok(Self {
+            name: raw.name,
+            version: raw.version,
+            tokens,
+        })
+    }
+
+    /// Returns the list name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the list version.
+    pub fn version(&self) -> TokenListVersion {
+        self.version
+    }
+
+    /// Returns the tokens on every chain.
+    pub fn tokens(&self) -> &[TokenInfo] {
+        &self.tokens
+    }
+}
+
+// ─── Persistence ─────────────────────────────────────────────────────────────
+
+/// Persistence backend of a [`TokenRegistry`].
+///
+/// Holds one set of tokens per chain. The registry rewrites a chain's whole set after
+/// every change to it, so implementations should replace it atomically.
+pub trait TokenStore {
+    /// Returns the persisted tokens of every chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StorageError`] if the store cannot be read.
+    fn load(&mut self) -> Result<Vec<TokenInfo>>;
+
+    /// Replaces the persisted tokens of `chain_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StorageError`] if the store cannot be written.
+    fn save(&mut self, chain_id: ChainId, tokens: &[TokenInfo]) -> Result<()>;
+}
+
+/// In-memory [`TokenStore`], for tests and short-lived sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStore {
+    chains: BTreeMap<u64, Vec<TokenInfo>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the tokens currently persisted for `chain_id`.
+    pub fn tokens(&self, chain_id: ChainId) -> &[TokenInfo] {
+        self.chains
+            .get(&chain_id.value())
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+impl TokenStore for MemoryStore {
+    fn load(&mut self) -> Result<Vec<TokenInfo>> {
+        Ok(self.chains.values().flatten().cloned().collect())
+    }
+
+    fn save(&mut self, chain_id: ChainId, tokens: &[TokenInfo]) -> Result<()> {
+        if tokens.is_empty() {
+            self.chains.remove(&chain_id.value());
+        } else {
+            self.chains.insert(chain_id.value(), tokens.to_vec());
+        }
+        Ok(())
+    }
+}
+
+/// [`TokenStore`] backed by a directory with one `<chain id>.json` file per chain.
+///
+/// Each file is a JSON array of token-list entries with an added `source` field. A
+/// missing directory is an empty registry, and other files in it are ignored. Writes
+/// go to a sibling temporary file that is then renamed over the original.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryStore {
+    dir: PathBuf,
+}
+
+impl DirectoryStore {
+    /// Creates a store in `dir`. Nothing is read or written until the registry is
+    /// opened; the directory is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the file holding the tokens of `chain_id`.
+    pub fn path(&self, chain_id: ChainId) -> PathBuf {
+        self.dir.join(format!("{}.json", chain_id.value()))
+    }
+
+    fn load_chain(path: &Path, chain_id: u64) -> Result<Vec<TokenInfo>> {
+        let contents = fs::read_to_string(path).map_err(|e| storage_error(path, e))?;
+        let entries: Vec<RawToken> = serde_json::from_str(&contents)
+            .map_err(|e| Error::StorageError(format!("{}: {}", path.display(), e)))?;
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                if entry.chain_id != chain_id {
+                    return Err(Error::StorageError(format!(
+                        "{}: entry {}: token of chain {} in the file of chain {}",
+                        path.display(),
+                        i,
+                        entry.chain_id,
+                        chain_id
+                    )));
+                }
+                entry.into_token(None).map_err(|e| {
+                    Error::StorageError(format!("{}: entry {}: {}", path.display(), i, e))
+                })
+            })
+            .collect()
+    }
+}
+
+impl TokenStore for DirectoryStore {
+    fn load(&mut self) -> Result<Vec<TokenInfo>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(storage_error(&self.dir, e)),
+        };
+
+        let mut files = BTreeMap::new();
+        for entry in entries {
+            let path = entry.map_err(|e| storage_error(&self.dir, e))?.path();
+            let chain_id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|stem| stem.parse::<u64>().ok());
+            if let Some(chain_id) = chain_id {
+                files.insert(chain_id, path);
+            }
+        }
+
+        let mut tokens = Vec::new();
+        for (chain_id, path) in files {
+            tokens.extend(Self::load_chain(&path, chain_id)?);
+        }
+        Ok(tokens)
+    }
+
+    fn save(&mut self, chain_id: ChainId, tokens: &[TokenInfo]) -> Result<()> {
+        let path = self.path(chain_id);
+        if tokens.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(storage_error(&path, e)),
+                _ => Ok(()),
+            };
+        }
+
+        let entries: Vec<RawToken> = tokens.iter().map(RawToken::from).collect();
+        let contents = serde_json::to_string_pretty(&entries)
+            .map_err(|e| Error::StorageError(format!("{}: {}", path.display(), e)))?;
+        fs::create_dir_all(&self.dir).map_err(|e| storage_error(&self.dir, e))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, contents).map_err(|e| storage_error(&tmp, e))?;
+        fs::rename(&tmp, &path).map_err(|e| storage_error(&path, e))
+    }
+}
+
+fn storage_error(path: &Path, error: std::io::Error) -> Error {
+    Error::StorageError(format!("{}: {}", path.display(), error))
+}
+
+// ─── Registry ────────────────────────────────────────────────────────────────
+
+/// Persistent set of known tokens, per chain and contract address.
+///
+/// Holds at most one entry per chain and address. A custom token is never replaced by
+/// a list import, and a token from one list is never replaced by another list; only
+/// re-importing the same list updates or removes its tokens. Every change is written
+/// through to the store.
+#[derive(Debug)]
+pub struct TokenRegistry<S: TokenStore> {
+    store: S,
+    chains: BTreeMap<u64, BTreeMap<[u8; 20], TokenInfo>>,
+}
+
+impl<S: TokenStore> TokenRegistry<S> {
+    /// Opens a registry, loading everything in `store`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read or holds an invalid token.
+    pub fn open(mut store: S) -> Result<Self> {
+        let mut chains: BTreeMap<u64, BTreeMap<[u8; 20], TokenInfo>> = BTreeMap::new();
+        for token in store.load()? {
+            token.validate()?;
+            chains
+                .entry(token.chain_id.value())
+                .or_default()
+                .insert(token.key(), token);
+        }
+        Ok(Self { store, chains })
+    }
+
+    /// Imports `list`, replacing the tokens previously imported from a list of the same
+    /// name, and returns how many of its tokens the registry now holds from it.
+    ///
+    /// Tokens already in the registry from another source are kept as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    pub fn import_list(&mut self, list: &TokenList) -> Result<usize> {
+        let source = TokenSource::List(list.name.clone());
+        let mut changed: Vec<u64> = Vec::new();
+
+        for (chain_id, tokens) in &mut self.chains {
+            let before = tokens.len();
+            tokens.retain(|_, token| token.source != source);
+            if tokens.len() != before {
+                changed.push(*chain_id);
+            }
+        }
+
+        let mut imported = 0;
+        for token in &list.tokens {
+            let tokens = self.chains.entry(token.chain_id.value()).or_default();
+            if tokens.contains_key(&token.key()) {
+                continue;
+            }
+            tokens.insert(token.key(), token.clone());
+            changed.push(token.chain_id.value());
+            imported += 1;
+        }
+
+        changed.sort_unstable();
+        changed.dedup();
+        for chain_id in changed {
+            self.persist(chain_id)?;
+        }
+        Ok(imported)
+    }
+
+    /// Removes the tokens imported from the list named `name` and returns how many were
+    /// removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    pub fn remove_list(&mut self, name: &str) -> Result<usize> {
+        let source = TokenSource::List(name.to_string());
+        let mut removed = 0;
+        let chain_ids: Vec<u64> = self.chains.keys().copied().collect();
+        for chain_id in chain_ids {
+            let tokens = self.chains.get_mut(&chain_id).expect("chain listed above");
+            let before = tokens.len();
+            tokens.retain(|_, token| token.source != source);
+            if tokens.len() != before {
+                removed += before - tokens.len();
+                self.persist(chain_id)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Adds a token the user chose by address, as [`TokenSource::Custom`], and returns
+    /// the entry it replaced.
+    ///
+    /// The metadata should come from the chain rather than from the user: with the
+    /// `net` feature, `fetch_token` reads it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the symbol or name is invalid, or the store cannot be
+    /// written.
+    pub fn add_custom(&mut self, mut token: TokenInfo) -> Result<Option<TokenInfo>> {
+        token.validate()?;
+        token.source = TokenSource::Custom;
+        let chain_id = token.chain_id.value();
+        let previous = self
+            .chains
+            .entry(chain_id)
+            .or_default()
+            .insert(token.key(), token);
+        self.persist(chain_id)?;
+        Ok(previous)
+    }
+
+    /// Removes a token and returns it, if it was in the registry.
+    ///
+    /// A token removed from a list comes back when the list is imported again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    pub fn remove(&mut self, chain_id: ChainId, address: &Address) -> Result<Option<TokenInfo>> {
+        let removed = self
+            .chains
+            .get_mut(&chain_id.value())
+            .and_then(|tokens| tokens.remove(&address.to_bytes()));
+        if removed.is_some() {
+            self.persist(chain_id.value())?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the token at `address` on `chain_id`.
+    pub fn get(&self, chain_id: ChainId, address: &Address) -> Option<&TokenInfo> {
+        self.chains
+            .get(&chain_id.value())
+            .and_then(|tokens| tokens.get(&address.to_bytes()))
+    }
+
+    /// Returns the tokens of `chain_id`, ordered by address.
+    pub fn tokens(&self, chain_id: ChainId) -> impl Iterator<Item = &TokenInfo> {
+        self.chains
+            .get(&chain_id.value())
+            .into_iter()
+            .flat_map(BTreeMap::values)
+    }
+
+    /// Returns the tokens of `chain_id` whose symbol matches `symbol`, ignoring ASCII
+    /// case.
+    ///
+    /// Symbols are not unique, so a payment flow should let the user pick by address
+    /// when there is more than one.
+    pub fn find_symbol<'a>(
+        &'a self,
+        chain_id: ChainId,
+        symbol: &'a str,
+    ) -> impl Iterator<Item = &'a TokenInfo> + 'a {
+        self.tokens(chain_id)
+            .filter(move |token| token.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Returns the chains that have at least one token.
+    pub fn chains(&self) -> impl Iterator<Item = ChainId> + '_ {
+        self.chains
+            .iter()
+            .filter(|(_, tokens)| !tokens.is_empty())
+            .map(|(chain_id, _)| ChainId::from(*chain_id))
+    }
+
+    /// Returns the number of tokens on every chain.
+    pub fn len(&self) -> usize {
+        self.chains.values().map(BTreeMap::len).sum()
+    }
+
+    /// Returns `true` if the registry holds no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    fn persist(&mut self, chain_id: u64) -> Result<()> {
+        let tokens: Vec<TokenInfo> = self
+            .chains
+            .get(&chain_id)
+            .map(|tokens| tokens.values().cloned().collect())
+            .unwrap_or_default();
+        self.store.save(ChainId::from(chain_id), &tokens)
+    }
+}
+
+#[cfg(feature = "net")]
+pub use net::{fetch_token, verify_token};
+
+#[cfg(feature = "net")]
+mod net {
+    use super::{TokenInfo, TokenSource};
+    use crate::multicall::{self, Call3, CallResult, MULTICALL3};
+    use crate::rpc::{BlockId, RpcClient};
+    use crate::{erc20, Address, ChainId, Error, Result, U256};
+
+    /// Reads the symbol, name and decimals of the token at `address`, and the chain ID,
+    /// in one Multicall3 batch, and returns it as a [`TokenSource::Custom`] token.
+    ///
+    /// A token without `name()` gets its symbol as name. Tokens returning `bytes32`
+    /// instead of `string`, such as MKR, are read too.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TokenError`] if `address` does not answer `symbol()` and
+    /// `decimals()` like an ERC-20 token — which includes addresses without code — or
+    /// the metadata is invalid, and an RPC error if the batch fails.
+    pub async fn fetch_token(client: &RpcClient, address: Address) -> Result<TokenInfo> {
+        let calls = [
+            Call3::new(MULTICALL3, multicall::GET_CHAIN_ID_SELECTOR.to_vec()),
+            Call3::allow_failure(address, erc20::encode_symbol()),
+            Call3::allow_failure(address, erc20::encode_name()),
+            Call3::allow_failure(address, erc20::encode_decimals()),
+        ];
+        let results = multicall::aggregate3(client, &calls, BlockId::Latest).await?;
+        let [chain_id, symbol, name, decimals] = results.as_slice() else {
+            unreachable!("aggregate3 checks the result count");
+        };
+
+        let chain_id = erc20::decode_uint256(&chain_id.return_data)?;
+        if chain_id > U256::from(u64::MAX) {
+            return Err(Error::RpcError("chain ID out of range".to_string()));
+        }
+        let chain_id = chain_id.low_u64();
+        let symbol = read(address, "symbol", symbol, erc20::decode_string)?;
+        let decimals = read(address, "decimals", decimals, erc20::decode_decimals)?;
+        let name = read(address, "name", name, erc20::decode_string).unwrap_or_default();
+        let name = if name.trim().is_empty() {
+            symbol.clone()
+        } else {
+            name
+        };
+
+        let token = TokenInfo {
+            chain_id: ChainId::from(chain_id),
+            address,
+            symbol,
+            name,
+            decimals,
+            logo_uri: None,
+            source: TokenSource::Custom,
+        };
+        token
+            .validate()
+            .map_err(|e| Error::TokenError(format!("{}: {}", address, e)))?;
+        Ok(token)
+    }
+
+    /// Checks `token` against the chain: the client's chain ID, and the contract's
+    /// symbol and decimals.
+    ///
+    /// Use it before trusting a listed token for amounts; a wrong `decimals` in a list
+    /// misstates every balance by powers of ten.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TokenError`] naming the first mismatch, or an error of
+    /// [`fetch_token`].
+    pub async fn verify_token(client: &RpcClient, token: &TokenInfo) -> Result<()> {
+        let actual = fetch_token(client, token.address).await?;
+        if actual.chain_id != token.chain_id {
+            return Err(Error::TokenError(format!(
+                "{}: registered on chain {}, node is on chain {}",
+                token.address, token.chain_id, actual.chain_id
+            )));
+        }
+        if actual.decimals != token.decimals {
+            return Err(Error::TokenError(format!(
+                "{}: registered with {} decimals, contract has {}",
+                token.address, token.decimals, actual.decimals
+            )));
+        }
+        if actual.symbol != token.symbol {
+            return Err(Error::TokenError(format!(
+                "{}: registered as {}, contract symbol is {}",
+                token.address, token.symbol, actual.symbol
+            )));
+        }
+        Ok(())
+    }
+
+    fn read<T>(
+        address: Address,
+        function: &str,
+        result: &CallResult,
+        decode: fn(&[u8]) -> Result<T>,
+    ) -> Result<T> {
+        if !result.success || result.return_data.is_empty() {
+            return Err(Error::TokenError(format!(
+                "{} does not implement {}(); not an ERC-20 token",
+                address, function
+            )));
+        }
+        decode(&result.return_data)
+            .map_err(|e| Error::TokenError(format!("{}: {}(): {}", address, function, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDT: &str = "0x55d398326f99059fF775485246999027B3197955";
+    const BUSD: &str = "0xe9e7CEA3DedcA5984780Bafc599bD69ADd087D56";
+
+    fn list_json(name: &str, tokens: &[(u64, &str, &str, u8)]) -> String {
+        let tokens: Vec<serde_json::Value> = tokens
+            .iter()
+            .map(|(chain_id, address, symbol, decimals)| {
+                serde_json::json!({
+                    "chainId": chain_id,
+                    "address": address,
+                    "symbol": symbol,
+                    "name": format!("{} Token", symbol),
+                    "decimals": decimals,
+                    "logoURI": format!("https://example.com/{}.png", symbol),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "name": name,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "version": { "major": 1, "minor": 2, "patch": 3 },
+            "keywords": ["default"],
+            "tokens": tokens,
+        })
+        .to_string()
+    }
+
+    fn list(name: &str, tokens: &[(u64, &str, &str, u8)]) -> TokenList {
+        TokenList::from_json(&list_json(name, tokens)).unwrap()
+    }
+
+    fn custom(chain_id: u64, address: &str, symbol: &str) -> TokenInfo {
+        TokenInfo {
+            chain_id: ChainId::from(chain_id),
+            address: address.parse().unwrap(),
+            symbol: symbol.to_string(),
+            name: format!("{} Token", symbol),
+            decimals: 18,
+            logo_uri: None,
+            source: TokenSource::Custom,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("khodpay-tokens-{}-{}", name, std::process::id()))
+    }
+
+    // ─── token lists ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_token_list() {
+        let list = list("Example", &[(56, USDT, "USDT", 18), (1, USDT, "USDT", 6)]);
+
+        assert_eq!(list.name(), "Example");
+        assert_eq!(
+            list.version(),
+            TokenListVersion {
+                major: 1,
+                minor: 2,
+                patch: 3
+            }
+        );
+        let token = &list.tokens()[0];
+        assert_eq!(token.chain_id, ChainId::BscMainnet);
+        assert_eq!(token.address.to_checksum_string(), USDT);
+        assert_eq!(token.name, "USDT Token");
+        assert_eq!(
+            token.logo_uri.as_deref(),
+            Some("https://example.com/USDT.png")
+        );
+        assert_eq!(token.source, TokenSource::List("Example".to_string()));
+        assert_eq!(list.tokens()[1].decimals, 6);
+        assert_eq!(list.tokens()[1].amount(12_500_000u64).to_string(), "12.5");
+
+        let json = list_json("Example", &[(56, USDT, "USDT", 18)])
+            .replace("\"decimals\":18", "\"decimals\":18,\"source\":\"custom\"");
+        let list = TokenList::from_json(&json).unwrap();
+        assert_eq!(
+            list.tokens()[0].source,
+            TokenSource::List("Example".to_string())
+        );
+    }
+
+    #[test]
+    fn test_token_list_versions_order() {
+        let version = |major, minor, patch| TokenListVersion {
+            major,
+            minor,
+            patch,
+        };
+        assert!(version(1, 10, 0) > version(1, 9, 9));
+        assert!(version(2, 0, 0) > version(1, 10, 0));
+    }
+
+    #[test]
+    fn test_token_list_rejects_invalid_tokens() {
+        for tokens in [
+            vec![(56, "0x1234", "USDT", 18)],
+            vec![(56, USDT, "", 18)],
+            vec![(56, USDT, "USDT\u{202e}\n", 18)],
+            vec![(56, USDT, "USDT", 18), (56, USDT, "USDT2", 18)],
+        ] {
+            let json = list_json("Bad", &tokens);
+            assert!(matches!(
+                TokenList::from_json(&json),
+                Err(Error::TokenError(_))
+            ));
+        }
+
+        let too_many_decimals = list_json("Bad", &[(56, USDT, "USDT", 18)])
+            .replace("\"decimals\":18", "\"decimals\":256");
+        assert!(TokenList::from_json(&too_many_decimals).is_err());
+        assert!(TokenList::from_json("{\"name\":\"No tokens\"}").is_err());
+    }
+
+    // ─── registry ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_import_list_and_lookup() {
+        let mut registry = TokenRegistry::open(MemoryStore::new()).unwrap();
+        let imported = registry
+            .import_list(&list(
+                "Example",
+                &[
+                    (56, USDT, "USDT", 18),
+                    (56, BUSD, "BUSD", 18),
+                    (1, USDT, "USDT", 6),
+                ],
+            ))
+            .unwrap();
+
+        assert_eq!(imported, 3);
+        assert_eq!(registry.len(), 3);
+        assert_eq!(
+            registry.chains().collect::<Vec<_>>(),
+            vec![ChainId::EthereumMainnet, ChainId::BscMainnet]
+        );
+        assert_eq!(registry.tokens(ChainId::BscMainnet).count(), 2);
+        assert_eq!(registry.tokens(ChainId::PolygonMainnet).count(), 0);
+
+        let usdt: Address = USDT.parse().unwrap();
+        assert_eq!(
+            registry
+                .get(ChainId::EthereumMainnet, &usdt)
+                .unwrap()
+                .decimals,
+            6
+        );
+        let found: Vec<_> = registry.find_symbol(ChainId::BscMainnet, "busd").collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].address, BUSD.parse().unwrap());
+        assert_eq!(registry.store().tokens(ChainId::BscMainnet).len(), 2);
+        assert_eq!(registry.store().tokens(ChainId::EthereumMainnet).len(), 1);
+    }
+
+    #[test]
+    fn test_reimport_replaces_list_tokens() {
+        let mut registry = TokenRegistry::open(MemoryStore::new()).unwrap();
+        registry
+            .import_list(&list(
+                "Example",
+                &[(56, USDT, "USDT", 18), (1, USDT, "USDT", 6)],
+            ))
+            .unwrap();
+        registry
+            .import_list(&list("Example", &[(56, USDT, "USDT", 6)]))
+            .unwrap();
+
+        let usdt: Address = USDT.parse().unwrap();
+        assert_eq!(
+            registry.get(ChainId::BscMainnet, &usdt).unwrap().decimals,
+            6
+        );
+        assert!(registry.get(ChainId::EthereumMainnet, &usdt).is_none());
+        assert!(registry.store().tokens(ChainId::EthereumMainnet).is_empty());
+
+        assert_eq!(registry.remove_list("Example").unwrap(), 1);
+        assert!(registry.is_empty());
+        assert!(registry.store().tokens(ChainId::BscMainnet).is_empty());
+    }
+
+    #[test]
+    fn test_list_never_replaces_other_sources() {
+        let mut registry = TokenRegistry::open(MemoryStore::new()).unwrap();
+        registry.add_custom(custom(56, USDT, "MyUSDT")).unwrap();
+        registry
+            .import_list(&list("First", &[(56, BUSD, "BUSD", 18)]))
+            .unwrap();
+
+        let imported = registry
+            .import_list(&list(
+                "Second",
+                &[(56, USDT, "USDT", 6), (56, BUSD, "FAKE", 6)],
+            ))
+            .unwrap();
+
+        assert_eq!(imported, 0);
+        let usdt: Address = USDT.parse().unwrap();
+        let busd: Address = BUSD.parse().unwrap();
+        assert_eq!(
+            registry.get(ChainId::BscMainnet, &usdt).unwrap().symbol,
+            "MyUSDT"
+        );
+        assert_eq!(
+            registry.get(ChainId::BscMainnet, &busd).unwrap().source,
+            TokenSource::List("First".to_string())
+        );
+        assert_eq!(registry.remove_list("Second").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_add_custom_and_remove() {
+        let mut registry = TokenRegistry::open(MemoryStore::new()).unwrap();
+        registry
+            .import_list(&list("Example", &[(56, USDT, "USDT", 18)]))
+            .unwrap();
+
+        let mut token = custom(56, USDT, "USDT");
+        token.source = TokenSource::List("Spoofed".to_string());
+        let previous = registry.add_custom(token).unwrap();
+        assert_eq!(
+            previous.unwrap().source,
+            TokenSource::List("Example".to_string())
+        );
+
+        let usdt: Address = USDT.parse().unwrap();
+        assert_eq!(
+            registry.get(ChainId::BscMainnet, &usdt).unwrap().source,
+            TokenSource::Custom
+        );
+        assert!(registry.add_custom(custom(56, BUSD, " ")).is_err());
+
+        assert!(registry
+            .remove(ChainId::BscMainnet, &usdt)
+            .unwrap()
+            .is_some());
+        assert!(registry
+            .remove(ChainId::BscMainnet, &usdt)
+            .unwrap()
+            .is_none());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_reopen_from_memory_store() {
+        let mut registry = TokenRegistry::open(MemoryStore::new()).unwrap();
+        registry
+            .import_list(&list("Example", &[(56, USDT, "USDT", 18)]))
+            .unwrap();
+        registry.add_custom(custom(1, BUSD, "XYZ")).unwrap();
+
+        let reopened = TokenRegistry::open(registry.store().clone()).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(
+            reopened.tokens(ChainId::BscMainnet).collect::<Vec<_>>(),
+            registry.tokens(ChainId::BscMainnet).collect::<Vec<_>>()
+        );
+    }
+
+    // ─── directory store ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_directory_store_round_trip() {
+        let dir = temp_dir("round-trip");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut registry = TokenRegistry::open(DirectoryStore::new(&dir)).unwrap();
+        assert!(registry.is_empty());
+        registry
+            .import_list(&list(
+                "Example",
+                &[(56, USDT, "USDT", 18), (1, USDT, "USDT", 6)],
+            ))
+            .unwrap();
+        registry.add_custom(custom(56, BUSD, "BUSD")).unwrap();
+        assert!(dir.join("56.json").exists());
+        assert!(dir.join("1.json").exists());
+
+        let reopened = TokenRegistry::open(DirectoryStore::new(&dir)).unwrap();
+        assert_eq!(reopened.len(), 3);
+        let busd: Address = BUSD.parse().unwrap();
+        assert_eq!(
+            reopened.get(ChainId::BscMainnet, &busd),
+            registry.get(ChainId::BscMainnet, &busd)
+        );
+
+        let mut registry = reopened;
+        let usdt: Address = USDT.parse().unwrap();
+        registry.remove(ChainId::EthereumMainnet, &usdt).unwrap();
+        assert!(!dir.join("1.json").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_store_rejects_misplaced_tokens() {
+        let dir = temp_dir("misplaced");
+        let _ = fs::remove_dir_all(&dir);
+        let mut store = DirectoryStore::new(&dir);
+        store
+            .save(ChainId::BscMainnet, &[custom(56, USDT, "USDT")])
+            .unwrap();
+        fs::rename(dir.join("56.json"), dir.join("1.json")).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        assert!(matches!(
+            TokenRegistry::open(DirectoryStore::new(&dir)),
+            Err(Error::StorageError(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::abi::{self, Token};
+        use crate::rpc::RpcClient;
+        use crate::U256;
+        use serde_json::json;
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn word(value: u64) -> Vec<u8> {
+            abi::encode(&[Token::Uint(U256::from(value))])
+        }
+
+        fn string(value: &str) -> Vec<u8> {
+            abi::encode(&[Token::String(value.to_string())])
+        }
+
+        async fn mock_aggregate3(server: &MockServer, results: &[(bool, Vec<u8>)]) {
+            let results = abi::encode(&[Token::Array(
+                results
+                    .iter()
+                    .map(|(success, data)| {
+                        Token::Tuple(vec![Token::Bool(*success), Token::Bytes(data.clone())])
+                    })
+                    .collect(),
+            )]);
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({ "method": "eth_call" })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": format!("0x{}", hex::encode(results)),
+                })))
+                .mount(server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn test_fetch_token() {
+            let server = MockServer::start().await;
+            mock_aggregate3(
+                &server,
+                &[
+                    (true, word(56)),
+                    (true, string("USDT")),
+                    (true, string("Tether USD")),
+                    (true, word(18)),
+                ],
+            )
+            .await;
+
+            let client = RpcClient::new(server.uri());
+            let usdt: Address = USDT.parse().unwrap();
+            let token = fetch_token(&client, usdt).await.unwrap();
+
+            assert_eq!(token.chain_id, ChainId::BscMainnet);
+            assert_eq!(token.symbol, "USDT");
+            assert_eq!(token.name, "Tether USD");
+            assert_eq!(token.decimals, 18);
+            assert_eq!(token.source, TokenSource::Custom);
+
+            verify_token(&client, &token).await.unwrap();
+            let mut listed = token.clone();
+            listed.decimals = 6;
+            assert!(matches!(
+                verify_token(&client, &listed).await,
+                Err(Error::TokenError(_))
+            ));
+            listed.decimals = 18;
+            listed.chain_id = ChainId::EthereumMainnet;
+            assert!(verify_token(&client, &listed).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_fetch_token_without_name_uses_symbol() {
+            let server = MockServer::start().await;
+            let mut mkr = [0u8; 32];
+            mkr[..3].copy_from_slice(b"MKR");
+            mock_aggregate3(
+                &server,
+                &[
+                    (true, word(1)),
+                    (true, mkr.to_vec()),
+                    (false, vec![]),
+                    (true, word(18)),
+                ],
+            )
+            .await;
+
+            let client = RpcClient::new(server.uri());
+            let token = fetch_token(&client, BUSD.parse().unwrap()).await.unwrap();
+            assert_eq!(token.symbol, "MKR");
+            assert_eq!(token.name, "MKR");
+        }
+
+        #[tokio::test]
+        async fn test_fetch_token_rejects_non_tokens() {
+            let server = MockServer::start().await;
+            mock_aggregate3(
+                &server,
+                &[
+                    (true, word(56)),
+                    (true, vec![]),
+                    (true, vec![]),
+                    (true, vec![]),
+                ],
+            )
+            .await;
+
+            let client = RpcClient::new(server.uri());
+            assert!(matches!(
+                fetch_token(&client, BUSD.parse().unwrap()).await,
+                Err(Error::TokenError(_))
+            ));
+        }
+    }
+}
@@ -11,6 +11,11 @@ pub const TRANSFER_GAS: u64 = 21_000;
 /// Typical gas limit for a BEP-20/ERC-20 token transfer.
 pub const TOKEN_TRANSFER_GAS: u64 = 65_000;
 
+/// Default safety margin, in percent, added on top of an `eth_estimateGas` result by
+/// [`Eip1559TransactionBuilder::build_estimated`].
+#[cfg(feature = "net")]
+pub const DEFAULT_GAS_MARGIN_PERCENT: u64 = 20;
+
 /// EIP-1559 (Type 2) transaction.
 ///
 /// This is the modern transaction format with separate base fee and priority fee,
@@ -142,6 +147,8 @@ pub struct Eip1559TransactionBuilder {
     value: Option<Wei>,
     data: Vec<u8>,
     access_list: AccessList,
+    #[cfg(feature = "net")]
+    gas_margin_percent: Option<u64>,
 }
 
 impl Eip1559TransactionBuilder {
@@ -174,12 +181,28 @@ impl Eip1559TransactionBuilder {
         self
     }
 
+    /// Sets both fee fields from a [`FeeSuggestion`](crate::fee::FeeSuggestion).
+    pub fn fee_suggestion(self, suggestion: crate::fee::FeeSuggestion) -> Self {
+        self.max_priority_fee_per_gas(suggestion.max_priority_fee_per_gas)
+            .max_fee_per_gas(suggestion.max_fee_per_gas)
+    }
+
     /// Sets the gas limit.
     pub fn gas_limit(mut self, limit: u64) -> Self {
         self.gas_limit = Some(limit);
         self
     }
 
+    /// Sets the safety margin, in percent, that
+    /// [`build_estimated`](Self::build_estimated) adds to the node's gas estimate.
+    ///
+    /// Defaults to [`DEFAULT_GAS_MARGIN_PERCENT`].
+    #[cfg(feature = "net")]
+    pub fn gas_estimate_margin(mut self, percent: u64) -> Self {
+        self.gas_margin_percent = Some(percent);
+        self
+    }
+
     /// Sets the recipient address.
     pub fn to(mut self, address: Address) -> Self {
         self.to = Some(address);
@@ -243,6 +266,79 @@ impl Eip1559TransactionBuilder {
     }
 }
 
+#[cfg(feature = "net")]
+mod net {
+    use super::{Eip1559Transaction, Eip1559TransactionBuilder, DEFAULT_GAS_MARGIN_PERCENT};
+    use crate::rpc::RpcClient;
+    use crate::{Address, Result, TxRequest, Wei};
+
+    impl Eip1559TransactionBuilder {
+        /// Builds the transaction, asking the node for the gas limit if none was set.
+        ///
+        /// When [`gas_limit`](Self::gas_limit) was omitted, the transaction is simulated
+        /// as sent by `from` with `eth_estimateGas` and the estimate is raised by the
+        /// [safety margin](Self::gas_estimate_margin). An explicit gas limit is used
+        /// as-is without contacting the node.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the estimate fails (for example because the call would
+        /// revert), or for the same reasons as [`build`](Self::build).
+        ///
+        /// # Examples
+        ///
+        /// ```rust,no_run
+        /// # async fn example() -> khodpay_signing::Result<()> {
+        /// use khodpay_signing::rpc::RpcClient;
+        /// use khodpay_signing::{Address, ChainId, Eip1559Transaction, Wei};
+        ///
+        /// let client = RpcClient::new("https://bsc-dataseed.binance.org");
+        /// let from: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".parse()?;
+        ///
+        /// let tx = Eip1559Transaction::builder()
+        ///     .chain_id(ChainId::BscMainnet)
+        ///     .nonce(0)
+        ///     .max_priority_fee_per_gas(Wei::from_gwei(1))
+        ///     .max_fee_per_gas(Wei::from_gwei(5))
+        ///     .to(from)
+        ///     .value(Wei::from_ether(1))
+        ///     .gas_estimate_margin(10)
+        ///     .build_estimated(&client, from)
+        ///     .await?;
+        /// # let _ = tx;
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn build_estimated(
+            mut self,
+            client: &RpcClient,
+            from: Address,
+        ) -> Result<Eip1559Transaction> {
+            if self.gas_limit.is_none() {
+                let request = TxRequest {
+                    to: self.to,
+                    value: self.value.unwrap_or(Wei::ZERO),
+                    data: self.data.clone(),
+                    gas_limit: 0,
+                    access_list: self.access_list.clone(),
+                };
+                let estimate = client.estimate_gas(from, &request).await?;
+                let margin = self
+                    .gas_margin_percent
+                    .unwrap_or(DEFAULT_GAS_MARGIN_PERCENT);
+                self.gas_limit = Some(with_margin(estimate, margin));
+            }
+            self.build()
+        }
+    }
+
+    /// Raises `estimate` by `percent`, rounding up and saturating at `u64::MAX`.
+    pub(super) fn with_margin(estimate: u64, percent: u64) -> u64 {
+        let raised = (u128::from(estimate) * (100 + u128::from(percent))).div_ceil(100);
+        u64::try_from(raised).unwrap_or(u64::MAX)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -599,4 +695,114 @@ mod tests {
 
         assert_eq!(u64::from(tx.chain_id), 97);
     }
+
+    // ==================== Gas Estimation Tests ====================
+
+    #[cfg(feature = "net")]
+    mod rpc {
+        use super::*;
+        use crate::rpc::RpcClient;
+        use serde_json::json;
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn builder() -> Eip1559TransactionBuilder {
+            Eip1559Transaction::builder()
+                .chain_id(ChainId::BscMainnet)
+                .nonce(0)
+                .max_priority_fee_per_gas(Wei::from_gwei(1))
+                .max_fee_per_gas(Wei::from_gwei(5))
+                .to(test_address())
+                .value(Wei::from_ether(1))
+        }
+
+        async fn mock_estimate(server: &MockServer, result: &str) {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({
+                    "method": "eth_estimateGas",
+                    "params": [{
+                        "from": test_address().to_checksum_string(),
+                        "to": test_address().to_checksum_string(),
+                        "value": "0xde0b6b3a7640000",
+                        "data": "0x",
+                    }],
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": result,
+                })))
+                .mount(server)
+                .await;
+        }
+
+        #[test]
+        fn test_with_margin() {
+            assert_eq!(net::with_margin(21_000, 20), 25_200);
+            assert_eq!(net::with_margin(21_001, 10), 23_102);
+            assert_eq!(net::with_margin(50_000, 0), 50_000);
+            assert_eq!(net::with_margin(u64::MAX, 50), u64::MAX);
+        }
+
+        #[tokio::test]
+        async fn test_build_estimated_applies_default_margin() {
+            let server = MockServer::start().await;
+            mock_estimate(&server, "0x5208").await;
+
+            let client = RpcClient::new(server.uri());
+            let tx = builder()
+                .build_estimated(&client, test_address())
+                .await
+                .unwrap();
+            assert_eq!(tx.gas_limit, 25_200);
+        }
+
+        #[tokio::test]
+        async fn test_build_estimated_custom_margin() {
+            let server = MockServer::start().await;
+            mock_estimate(&server, "0x5208").await;
+
+            let client = RpcClient::new(server.uri());
+            let tx = builder()
+                .gas_estimate_margin(50)
+                .build_estimated(&client, test_address())
+                .await
+                .unwrap();
+            assert_eq!(tx.gas_limit, 31_500);
+        }
+
+        #[tokio::test]
+        async fn test_build_estimated_keeps_explicit_gas_limit() {
+            let server = MockServer::start().await;
+            let client = RpcClient::new(server.uri());
+
+            let tx = builder()
+                .gas_limit(30_000)
+                .build_estimated(&client, test_address())
+                .await
+                .unwrap();
+            assert_eq!(tx.gas_limit, 30_000);
+            assert!(server.received_requests().await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_build_estimated_propagates_revert() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "error": { "code": 3, "message": "execution reverted" },
+                })))
+                .mount(&server)
+                .await;
+
+            let client = RpcClient::new(server.uri());
+            let err = builder()
+                .build_estimated(&client, test_address())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("execution reverted"));
+        }
+    }
 }
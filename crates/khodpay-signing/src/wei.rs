@@ -1,8 +1,10 @@
 //! Wei type for EVM transaction values.
 //!
 //! Wei is the smallest unit of Ether/BNB. This module provides a wrapper
-//! around U256 with convenient conversion methods.
+//! around U256 with convenient conversion methods, overflow-aware arithmetic,
+//! and decimal string parsing / formatting via [`parse_units`] and [`format_units`].
 
+use khodpay_amount::{CoinAmount, WEI_DECIMALS};
 use primitive_types::U256;
 use std::fmt;
 use std::ops::{Add, Mul, Sub};
@@ -16,6 +18,45 @@ pub const GWEI: u64 = 1_000_000_000;
 /// The number of wei in one ether/BNB (10^18).
 pub const ETHER: u64 = 1_000_000_000_000_000_000;
 
+/// Parses a decimal string such as `"1.5"` into an integer amount with `decimals`
+/// fractional digits (`"1.5"` with 6 decimals is `1_500_000`).
+///
+/// Trailing zeros beyond `decimals` are accepted; any other extra precision is
+/// rejected rather than silently truncated.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidValue`] if the string is not a plain decimal number,
+/// has more significant fractional digits than `decimals`, or overflows `U256`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{parse_units, U256};
+///
+/// assert_eq!(parse_units("12.5", 6).unwrap(), U256::from(12_500_000u64));
+/// assert!(parse_units("0.0000001", 6).is_err());
+/// ```
+pub fn parse_units(s: &str, decimals: u8) -> Result<U256> {
+    khodpay_amount::parse_units(s, decimals).map_err(|e| match e {
+        khodpay_amount::Error::InvalidAmount(reason) => Error::InvalidValue(reason),
+    })
+}
+
+/// Formats an integer amount with `decimals` fractional digits, trimming trailing zeros.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::{format_units, U256};
+///
+/// assert_eq!(format_units(U256::from(12_500_000u64), 6), "12.5");
+/// assert_eq!(format_units(U256::from(3_000_000u64), 6), "3");
+/// ```
+pub fn format_units(amount: U256, decimals: u8) -> String {
+    khodpay_amount::format_units(amount, decimals)
+}
+
 /// A value in wei (smallest EVM currency unit).
 ///
 /// Wei is a wrapper around U256 that provides convenient methods for
@@ -39,6 +80,12 @@ pub const ETHER: u64 = 1_000_000_000_000_000_000;
 ///
 /// // Arithmetic
 /// let total = one_gwei + one_wei;
+/// assert_eq!(Wei::ZERO.checked_sub(one_wei), None);
+///
+/// // Decimal strings
+/// let amount = Wei::parse_ether("1.5").unwrap();
+/// assert_eq!(amount.format_ether(), "1.5");
+/// assert_eq!(amount.to_fixed(18, 4), "1.5000");
 ///
 /// // Display
 /// println!("{} wei", one_ether);  // 1000000000000000000 wei
@@ -165,6 +212,122 @@ impl Wei {
         self.0.is_zero()
     }
 
+    /// Adds two values, returning `None` on overflow.
+    pub fn checked_add(self, rhs: Wei) -> Option<Wei> {
+        self.0.checked_add(rhs.0).map(Wei)
+    }
+
+    /// Subtracts `rhs`, returning `None` if the result would be negative.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::Wei;
+    ///
+    /// let balance = Wei::from_gwei(5);
+    /// assert_eq!(balance.checked_sub(Wei::from_gwei(2)), Some(Wei::from_gwei(3)));
+    /// assert_eq!(balance.checked_sub(Wei::from_gwei(6)), None);
+    /// ```
+    pub fn checked_sub(self, rhs: Wei) -> Option<Wei> {
+        self.0.checked_sub(rhs.0).map(Wei)
+    }
+
+    /// Multiplies by a scalar (e.g. a gas amount), returning `None` on overflow.
+    pub fn checked_mul(self, rhs: u64) -> Option<Wei> {
+        self.0.checked_mul(U256::from(rhs)).map(Wei)
+    }
+
+    /// Divides by a scalar, returning `None` if `rhs` is zero.
+    pub fn checked_div(self, rhs: u64) -> Option<Wei> {
+        (rhs != 0).then(|| Wei(self.0 / U256::from(rhs)))
+    }
+
+    /// Adds two values, clamping at `U256::MAX`.
+    pub fn saturating_add(self, rhs: Wei) -> Wei {
+        Wei(self.0.saturating_add(rhs.0))
+    }
+
+    /// Subtracts `rhs`, clamping at zero.
+    pub fn saturating_sub(self, rhs: Wei) -> Wei {
+        Wei(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Multiplies by a scalar, clamping at `U256::MAX`.
+    pub fn saturating_mul(self, rhs: u64) -> Wei {
+        Wei(self.0.saturating_mul(U256::from(rhs)))
+    }
+
+    /// Parses a decimal amount with `decimals` fractional digits into wei.
+    ///
+    /// See [`parse_units`] for the accepted format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidValue`] if the string is malformed, too precise or overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::Wei;
+    ///
+    /// assert_eq!(Wei::parse_units("2.5", 9).unwrap(), Wei::from_wei(2_500_000_000u64));
+    /// ```
+    pub fn parse_units(s: &str, decimals: u8) -> Result<Self> {
+        parse_units(s, decimals).map(Wei)
+    }
+
+    /// Parses a decimal ether/BNB amount such as `"1.5"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidValue`] if the string is malformed, too precise or overflows.
+    pub fn parse_ether(s: &str) -> Result<Self> {
+        Self::parse_units(s, 18)
+    }
+
+    /// Parses a decimal gwei amount such as `"0.1"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidValue`] if the string is malformed, too precise or overflows.
+    pub fn parse_gwei(s: &str) -> Result<Self> {
+        Self::parse_units(s, 9)
+    }
+
+    /// Formats the value with `decimals` fractional digits, trimming trailing zeros.
+    pub fn format_units(&self, decimals: u8) -> String {
+        format_units(self.0, decimals)
+    }
+
+    /// Formats the value in ether/BNB without rounding (`"1.5"`, `"0.000000001"`).
+    pub fn format_ether(&self) -> String {
+        self.format_units(18)
+    }
+
+    /// Formats the value in gwei without rounding.
+    pub fn format_gwei(&self) -> String {
+        self.format_units(9)
+    }
+
+    /// Formats the value with exactly `precision` fractional digits.
+    ///
+    /// Digits beyond `precision` are truncated, never rounded up, so a displayed
+    /// balance never exceeds the real one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::Wei;
+    ///
+    /// let value = Wei::parse_ether("1.23456").unwrap();
+    /// assert_eq!(value.to_fixed(18, 2), "1.23");
+    /// assert_eq!(value.to_fixed(18, 0), "1");
+    /// assert_eq!(Wei::from_ether(3).to_fixed(18, 3), "3.000");
+    /// ```
+    pub fn to_fixed(&self, decimals: u8, precision: usize) -> String {
+        khodpay_amount::format_fixed(self.0, decimals, precision)
+    }
+
     /// Returns the value as a byte array in big-endian format.
     pub fn to_be_bytes(&self) -> [u8; 32] {
         let mut bytes = [0u8; 32];
@@ -202,6 +365,31 @@ impl From<Wei> for U256 {
     }
 }
 
+impl From<Wei> for CoinAmount {
+    fn from(wei: Wei) -> Self {
+        CoinAmount::new(wei.0, WEI_DECIMALS)
+    }
+}
+
+impl TryFrom<CoinAmount> for Wei {
+    type Error = Error;
+
+    /// Converts an amount of an EVM chain's native coin back to wei.
+    ///
+    /// Fails with [`Error::InvalidValue`] unless the amount has 18 decimals, so a
+    /// token amount is never sent as ether.
+    fn try_from(amount: CoinAmount) -> Result<Self> {
+        if amount.decimals() != WEI_DECIMALS {
+            return Err(Error::InvalidValue(format!(
+                "amount has {} decimals, wei has {}",
+                amount.decimals(),
+                WEI_DECIMALS
+            )));
+        }
+        Ok(Wei(amount.value()))
+    }
+}
+
 impl Add for Wei {
     type Output = Self;
 
@@ -396,6 +584,160 @@ mod tests {
         assert_eq!(format!("{:?}", value), "Wei(1000000000)");
     }
 
+    // ==================== Checked Arithmetic Tests ====================
+
+    #[test]
+    fn test_checked_add() {
+        assert_eq!(
+            Wei::from_gwei(1).checked_add(Wei::from_gwei(2)),
+            Some(Wei::from_gwei(3))
+        );
+        assert_eq!(
+            Wei::from_u256(U256::MAX).checked_add(Wei::from_wei(1u64)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_checked_sub() {
+        assert_eq!(
+            Wei::from_gwei(5).checked_sub(Wei::from_gwei(2)),
+            Some(Wei::from_gwei(3))
+        );
+        assert_eq!(Wei::from_gwei(1).checked_sub(Wei::from_gwei(2)), None);
+    }
+
+    #[test]
+    fn test_checked_mul_div() {
+        assert_eq!(
+            Wei::from_gwei(5).checked_mul(21_000),
+            Some(Wei::from_gwei(105_000))
+        );
+        assert_eq!(Wei::from_u256(U256::MAX).checked_mul(2), None);
+        assert_eq!(Wei::from_gwei(9).checked_div(3), Some(Wei::from_gwei(3)));
+        assert_eq!(Wei::from_gwei(9).checked_div(0), None);
+    }
+
+    #[test]
+    fn test_saturating() {
+        let max = Wei::from_u256(U256::MAX);
+        assert_eq!(max.saturating_add(Wei::from_gwei(1)), max);
+        assert_eq!(max.saturating_mul(2), max);
+        assert_eq!(
+            Wei::from_gwei(1).saturating_sub(Wei::from_gwei(2)),
+            Wei::ZERO
+        );
+        assert_eq!(
+            Wei::from_gwei(3).saturating_sub(Wei::from_gwei(2)),
+            Wei::from_gwei(1)
+        );
+    }
+
+    #[test]
+    fn test_max_min() {
+        let a = Wei::from_gwei(5);
+        let b = Wei::from_gwei(10);
+        assert_eq!(a.max(b), b);
+        assert_eq!(a.min(b), a);
+    }
+
+    // ==================== Decimal Parsing Tests ====================
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(
+            parse_units("1.5", 18).unwrap(),
+            U256::from(15u64) * U256::exp10(17)
+        );
+        assert_eq!(parse_units("12.5", 6).unwrap(), U256::from(12_500_000u64));
+        assert_eq!(parse_units("42", 0).unwrap(), U256::from(42u64));
+        assert_eq!(parse_units("0.000001", 6).unwrap(), U256::one());
+        assert_eq!(parse_units("0", 18).unwrap(), U256::zero());
+        assert_eq!(parse_units("007.50", 2).unwrap(), U256::from(750u64));
+    }
+
+    #[test]
+    fn test_parse_units_trailing_zeros_beyond_decimals() {
+        assert_eq!(parse_units("1.500000", 2).unwrap(), U256::from(150u64));
+    }
+
+    #[test]
+    fn test_parse_units_too_precise() {
+        assert!(parse_units("0.0000001", 6).is_err());
+        assert!(parse_units("1.5", 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_units_invalid() {
+        for s in [
+            "", ".5", "1.", "1.2.3", "-1", "+1", "1e18", " 1", "1,5", "abc",
+        ] {
+            assert!(parse_units(s, 18).is_err(), "{:?} should be rejected", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_units_overflow() {
+        let max = U256::MAX.to_string();
+        assert_eq!(parse_units(&max, 0).unwrap(), U256::MAX);
+        assert!(parse_units(&max, 1).is_err());
+        assert!(parse_units("1", 78).is_err());
+    }
+
+    #[test]
+    fn test_parse_ether_and_gwei() {
+        assert_eq!(
+            Wei::parse_ether("1.5").unwrap(),
+            Wei::from_gwei(1_500_000_000)
+        );
+        assert_eq!(
+            Wei::parse_gwei("0.1").unwrap(),
+            Wei::from_wei(100_000_000u64)
+        );
+        assert_eq!(Wei::parse_units("3", 9).unwrap(), Wei::from_gwei(3));
+    }
+
+    // ==================== Decimal Formatting Tests ====================
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::from(12_500_000u64), 6), "12.5");
+        assert_eq!(format_units(U256::one(), 6), "0.000001");
+        assert_eq!(format_units(U256::from(3_000_000u64), 6), "3");
+        assert_eq!(format_units(U256::zero(), 18), "0");
+        assert_eq!(format_units(U256::from(42u64), 0), "42");
+    }
+
+    #[test]
+    fn test_format_ether_and_gwei() {
+        assert_eq!(Wei::from_gwei(1_500_000_000).format_ether(), "1.5");
+        assert_eq!(Wei::from_gwei(1).format_ether(), "0.000000001");
+        assert_eq!(Wei::from_wei(2_500_000_000u64).format_gwei(), "2.5");
+    }
+
+    #[test]
+    fn test_to_fixed() {
+        let value = Wei::parse_ether("1.23456").unwrap();
+        assert_eq!(value.to_fixed(18, 2), "1.23");
+        assert_eq!(value.to_fixed(18, 4), "1.2345");
+        assert_eq!(value.to_fixed(18, 0), "1");
+        assert_eq!(Wei::ZERO.to_fixed(18, 2), "0.00");
+        assert_eq!(Wei::from_wei(5u64).to_fixed(0, 2), "5.00");
+    }
+
+    #[test]
+    fn test_parse_format_round_trip() {
+        for s in [
+            "0",
+            "1",
+            "1.5",
+            "0.000000000000000001",
+            "123456789.987654321",
+        ] {
+            assert_eq!(Wei::parse_ether(s).unwrap().format_ether(), s);
+        }
+    }
+
     // ==================== Bytes Tests ====================
 
     #[test]
@@ -471,6 +813,18 @@ mod tests {
         assert_eq!(u256, U256::from(1000));
     }
 
+    #[test]
+    fn test_coin_amount_round_trip() {
+        let value = Wei::parse_ether("1.5").unwrap();
+        let amount = CoinAmount::from(value);
+        assert_eq!(amount.decimals(), 18);
+        assert_eq!(amount.to_string(), "1.5");
+        assert_eq!(Wei::try_from(amount).unwrap(), value);
+
+        let usdt = CoinAmount::parse("1.5", 6).unwrap();
+        assert!(matches!(Wei::try_from(usdt), Err(Error::InvalidValue(_))));
+    }
+
     // ==================== Hash Tests ====================
 
     #[test]
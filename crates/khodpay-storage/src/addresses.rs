@@ -0,0 +1,226 @@
+//! Wallet addresses and their derivation paths.
+
+use khodpay_address::{Address, Network};
+use khodpay_bip44::Bip44Path;
+use rusqlite::{params, OptionalExtension, Row};
+
+use crate::store::{corrupt, network_key};
+use crate::{Result, WalletStore};
+
+/// An address the wallet owns or watches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressRecord {
+    /// The address.
+    pub address: Address,
+    /// Network the address was derived for.
+    pub network: Network,
+    /// Derivation path, or `None` for imported watch-only addresses.
+    pub path: Option<Bip44Path>,
+    /// Whether any transaction has touched the address.
+    pub used: bool,
+}
+
+impl AddressRecord {
+    /// Creates an unused record without a derivation path.
+    pub fn new(address: Address, network: Network) -> Self {
+        Self {
+            address,
+            network,
+            path: None,
+            used: false,
+        }
+    }
+
+    /// Sets the derivation path.
+    pub fn with_path(mut self, path: Bip44Path) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    fn from_row(network: Network, row: &Row<'_>) -> Result<Self> {
+        let address: String = row.get("address")?;
+        let path: Option<String> = row.get("path")?;
+        Ok(Self {
+            address: Address::parse(&address, network)
+                .map_err(|e| corrupt("address", &address, e))?,
+            network,
+            path: path
+                .map(|path| path.parse().map_err(|e| corrupt("path", &path, e)))
+                .transpose()?,
+            used: row.get("used")?,
+        })
+    }
+}
+
+impl WalletStore {
+    /// Inserts or updates an address.
+    ///
+    /// A stored address stays used even if `record.used` is `false`, so re-saving
+    /// freshly derived addresses never loses sync state.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn save_address(&mut self, record: &AddressRecord) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO addresses (network, address, path, used) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (network, address) DO UPDATE
+             SET path = excluded.path, used = used OR excluded.used",
+            params![
+                network_key(record.network),
+                record.address.to_string(),
+                record.path.map(|path| path.to_string()),
+                record.used,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Marks an address as used and returns whether it is stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn mark_address_used(&mut self, network: Network, address: &Address) -> Result<bool> {
+        let updated = self.connection.execute(
+            "UPDATE addresses SET used = 1 WHERE network = ?1 AND address = ?2",
+            params![network_key(network), address.to_string()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Returns the stored record for `address`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or the record cannot be decoded.
+    pub fn address(&self, network: Network, address: &Address) -> Result<Option<AddressRecord>> {
+        self.connection
+            .query_row(
+                "SELECT address, path, used FROM addresses WHERE network = ?1 AND address = ?2",
+                params![network_key(network), address.to_string()],
+                |row| Ok(AddressRecord::from_row(network, row)),
+            )
+            .optional()?
+            .transpose()
+    }
+
+    /// Returns the addresses of `network`, ordered by account, chain and index, with
+    /// imported addresses last.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a record cannot be decoded.
+    pub fn addresses(&self, network: Network) -> Result<Vec<AddressRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT address, path, used FROM addresses WHERE network = ?1 ORDER BY address",
+        )?;
+        let mut records = statement
+            .query_and_then(params![network_key(network)], |row| {
+                AddressRecord::from_row(network, row)
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        records.sort_by_key(|record| {
+            record.path.map_or((1, 0, 0, 0), |path| {
+                (
+                    0,
+                    path.account(),
+                    path.chain().value(),
+                    path.address_index(),
+                )
+            })
+        });
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_address::BitcoinNetwork;
+    use khodpay_bip44::{Chain, CoinType, Purpose};
+
+    const BITCOIN: Network = Network::Bitcoin(BitcoinNetwork::Bitcoin);
+
+    fn address(s: &str) -> Address {
+        Address::parse(s, BITCOIN).unwrap()
+    }
+
+    fn path(chain: Chain, index: u32) -> Bip44Path {
+        Bip44Path::new(Purpose::BIP84, CoinType::Bitcoin, 0, chain, index).unwrap()
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let receive = address("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+        let record =
+            AddressRecord::new(receive.clone(), BITCOIN).with_path(path(Chain::External, 0));
+        store.save_address(&record).unwrap();
+
+        assert_eq!(store.address(BITCOIN, &receive).unwrap(), Some(record));
+        assert_eq!(
+            store
+                .address(Network::Bitcoin(BitcoinNetwork::Testnet), &receive)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_used_is_sticky() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let receive = address("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+        let record = AddressRecord::new(receive.clone(), BITCOIN);
+        store.save_address(&record).unwrap();
+
+        assert!(store.mark_address_used(BITCOIN, &receive).unwrap());
+        store.save_address(&record).unwrap();
+        assert!(store.address(BITCOIN, &receive).unwrap().unwrap().used);
+
+        let unknown = address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2");
+        assert!(!store.mark_address_used(BITCOIN, &unknown).unwrap());
+    }
+
+    #[test]
+    fn test_addresses_ordered_by_path() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let entries = [
+            (
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                Some(path(Chain::External, 10)),
+            ),
+            ("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", None),
+            (
+                "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+                Some(path(Chain::Internal, 0)),
+            ),
+            (
+                "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+                Some(path(Chain::External, 2)),
+            ),
+        ];
+        for (s, path) in entries {
+            let mut record = AddressRecord::new(address(s), BITCOIN);
+            record.path = path;
+            store.save_address(&record).unwrap();
+        }
+
+        let order: Vec<_> = store
+            .addresses(BITCOIN)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.address.to_string())
+            .collect();
+        assert_eq!(
+            order,
+            [
+                "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+                "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2",
+            ]
+        );
+        assert!(store.addresses(Network::Evm).unwrap().is_empty());
+    }
+}
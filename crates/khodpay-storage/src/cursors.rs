@@ -0,0 +1,85 @@
+//! Sync cursors: where each network's sync left off.
+
+use khodpay_address::Network;
+use rusqlite::{params, OptionalExtension};
+
+use crate::store::network_key;
+use crate::{Result, WalletStore};
+
+impl WalletStore {
+    /// Records a sync cursor, such as the last scanned block height or an
+    /// Electrum status hash.
+    ///
+    /// Values are opaque to the store; the sync code that writes a key decides its
+    /// format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn set_sync_cursor(&mut self, network: Network, key: &str, value: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO sync_cursors (network, key, value) VALUES (?1, ?2, ?3)",
+            params![network_key(network), key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Returns a sync cursor, or `None` if `network` has not synced `key` yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the query fails.
+    pub fn sync_cursor(&self, network: Network, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT value FROM sync_cursors WHERE network = ?1 AND key = ?2",
+                params![network_key(network), key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Clears every sync cursor of `network`, so the next sync starts from scratch.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn clear_sync_cursors(&mut self, network: Network) -> Result<()> {
+        self.connection.execute(
+            "DELETE FROM sync_cursors WHERE network = ?1",
+            params![network_key(network)],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_address::BitcoinNetwork;
+
+    #[test]
+    fn test_cursors() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let bitcoin = Network::Bitcoin(BitcoinNetwork::Bitcoin);
+        assert_eq!(store.sync_cursor(bitcoin, "tip").unwrap(), None);
+
+        store.set_sync_cursor(bitcoin, "tip", "800000").unwrap();
+        store.set_sync_cursor(bitcoin, "tip", "800001").unwrap();
+        store
+            .set_sync_cursor(Network::Evm, "tip", "19000000")
+            .unwrap();
+        assert_eq!(
+            store.sync_cursor(bitcoin, "tip").unwrap(),
+            Some("800001".to_string())
+        );
+
+        store.clear_sync_cursors(bitcoin).unwrap();
+        assert_eq!(store.sync_cursor(bitcoin, "tip").unwrap(), None);
+        assert_eq!(
+            store.sync_cursor(Network::Evm, "tip").unwrap(),
+            Some("19000000".to_string())
+        );
+    }
+}
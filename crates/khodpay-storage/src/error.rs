@@ -0,0 +1,48 @@
+//! Error types for the storage crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while reading or writing wallet storage.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// SQLite failed to open the database or run a statement.
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    /// The database was written by a newer version of this crate.
+    #[error("Database schema version {found} is newer than the supported version {supported}")]
+    SchemaTooNew {
+        /// Schema version found in the database.
+        found: u32,
+        /// Latest schema version this crate knows.
+        supported: u32,
+    },
+
+    /// A stored value could not be decoded.
+    #[error("Corrupt record: {0}")]
+    Corrupt(String),
+}
+
+/// Result type alias for storage operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::SchemaTooNew {
+                found: 3,
+                supported: 1
+            }
+            .to_string(),
+            "Database schema version 3 is newer than the supported version 1"
+        );
+        assert_eq!(
+            Error::Corrupt("address 'xyz'".to_string()).to_string(),
+            "Corrupt record: address 'xyz'"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! User labels on transactions, addresses and outputs.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rusqlite::{params, OptionalExtension, Row};
+
+use crate::store::corrupt;
+use crate::{Error, Result, WalletStore};
+
+/// What a label is attached to.
+///
+/// The kinds and their string forms follow BIP-329, so labels can be exported to
+/// and imported from other wallets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LabelKind {
+    /// A transaction, referenced by its id.
+    Transaction,
+    /// An address.
+    Address,
+    /// A public key, in hex.
+    PublicKey,
+    /// A transaction input, referenced as `txid:vin`.
+    Input,
+    /// A transaction output, referenced as `txid:vout`.
+    Output,
+    /// An extended public key.
+    Xpub,
+}
+
+impl LabelKind {
+    /// Returns the BIP-329 type name.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            LabelKind::Transaction => "tx",
+            LabelKind::Address => "addr",
+            LabelKind::PublicKey => "pubkey",
+            LabelKind::Input => "input",
+            LabelKind::Output => "output",
+            LabelKind::Xpub => "xpub",
+        }
+    }
+}
+
+impl fmt::Display for LabelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LabelKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "tx" => LabelKind::Transaction,
+            "addr" => LabelKind::Address,
+            "pubkey" => LabelKind::PublicKey,
+            "input" => LabelKind::Input,
+            "output" => LabelKind::Output,
+            "xpub" => LabelKind::Xpub,
+            _ => return Err(Error::Corrupt(format!("unknown label kind '{}'", s))),
+        })
+    }
+}
+
+/// A stored label.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Label {
+    /// What the label is attached to.
+    pub kind: LabelKind,
+    /// The labelled item, in the form its [`LabelKind`] describes.
+    pub reference: String,
+    /// The label text.
+    pub label: String,
+}
+
+impl Label {
+    fn from_row(row: &Row<'_>) -> Result<Self> {
+        let kind: String = row.get("kind")?;
+        Ok(Self {
+            kind: kind.parse().map_err(|e| corrupt("kind", &kind, e))?,
+            reference: row.get("reference")?,
+            label: row.get("label")?,
+        })
+    }
+}
+
+impl WalletStore {
+    /// Sets the label of an item, replacing any previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`] if the write fails.
+    pub fn set_label(&mut self, kind: LabelKind, reference: &str, label: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO labels (kind, reference, label) VALUES (?1, ?2, ?3)",
+            params![kind.as_str(), reference, label],
+        )?;
+        Ok(())
+    }
+
+    /// Removes the label of an item and returns whether it had one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`] if the write fails.
+    pub fn remove_label(&mut self, kind: LabelKind, reference: &str) -> Result<bool> {
+        let removed = self.connection.execute(
+            "DELETE FROM labels WHERE kind = ?1 AND reference = ?2",
+            params![kind.as_str(), reference],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Returns the label of an item, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`] if the query fails.
+    pub fn label(&self, kind: LabelKind, reference: &str) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT label FROM labels WHERE kind = ?1 AND reference = ?2",
+                params![kind.as_str(), reference],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Returns every label, ordered by kind and reference.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a label cannot be decoded.
+    pub fn labels(&self) -> Result<Vec<Label>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT kind, reference, label FROM labels ORDER BY kind, reference")?;
+        let labels = statement
+            .query_and_then([], Label::from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TXID: &str = "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd";
+
+    #[test]
+    fn test_kind_round_trip() {
+        for kind in [
+            LabelKind::Transaction,
+            LabelKind::Address,
+            LabelKind::PublicKey,
+            LabelKind::Input,
+            LabelKind::Output,
+            LabelKind::Xpub,
+        ] {
+            assert_eq!(kind.to_string().parse::<LabelKind>().unwrap(), kind);
+        }
+        assert!("utxo".parse::<LabelKind>().is_err());
+    }
+
+    #[test]
+    fn test_set_replace_remove() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        store
+            .set_label(LabelKind::Transaction, TXID, "Rent")
+            .unwrap();
+        store
+            .set_label(LabelKind::Transaction, TXID, "Rent, March")
+            .unwrap();
+        let output = format!("{}:1", TXID);
+        store
+            .set_label(LabelKind::Output, &output, "Change")
+            .unwrap();
+
+        assert_eq!(
+            store.label(LabelKind::Transaction, TXID).unwrap(),
+            Some("Rent, March".to_string())
+        );
+        // Labels are scoped by kind
+        assert_eq!(store.label(LabelKind::Address, TXID).unwrap(), None);
+
+        let labels = store.labels().unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0].kind, LabelKind::Output);
+        assert_eq!(labels[0].reference, output);
+
+        assert!(store.remove_label(LabelKind::Transaction, TXID).unwrap());
+        assert!(!store.remove_label(LabelKind::Transaction, TXID).unwrap());
+        assert_eq!(store.labels().unwrap().len(), 1);
+    }
+}
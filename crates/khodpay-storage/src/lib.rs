@@ -0,0 +1,75 @@
+//! # Khodpay Storage
+//!
+//! SQLite storage for wallet state: addresses, UTXOs, transaction history, labels
+//! and sync cursors. Apps keep one database that this crate owns and migrates
+//! instead of mirroring wallet state in a database of their own.
+//!
+//! Records use the types of the other KhodPay crates: `khodpay-address` addresses,
+//! `khodpay-bip44` paths, `khodpay-btc-signing` UTXOs and `khodpay-chain-client`
+//! history entries.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`WalletStore`] | One SQLite database, migrated on open |
+//! | [`AddressRecord`] | An owned or watched address with its derivation path |
+//! | [`UtxoRecord`] | An unspent Bitcoin output with its confirmation height |
+//! | [`Label`] | A BIP-329 style label on a transaction, address or output |
+//!
+//! ## Features
+//!
+//! - **Schema Migrations**: Databases from older releases are upgraded on open;
+//!   databases from newer releases are refused rather than misread
+//! - **Multi-Network**: Records are grouped by network, so one database holds every
+//!   coin of a wallet
+//! - **Atomic Batches**: History saves and UTXO resyncs commit all or nothing
+//! - **Lossless Amounts**: Wei amounts are stored as decimal text, not truncated to
+//!   64 bits
+//! - **Bundled SQLite**: The same SQLite version on every platform, with no system
+//!   library needed
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_address::{Address, BitcoinNetwork, Network};
+//! use khodpay_bip44::{Bip44Path, Chain, CoinType, Purpose};
+//! use khodpay_chain_client::TxSummary;
+//! use khodpay_storage::{AddressRecord, LabelKind, WalletStore};
+//!
+//! let network = Network::Bitcoin(BitcoinNetwork::Bitcoin);
+//! let mut store = WalletStore::open_in_memory()?; // or WalletStore::open(path)
+//!
+//! let address = Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", network).unwrap();
+//! let path = Bip44Path::new(Purpose::BIP84, CoinType::Bitcoin, 0, Chain::External, 0).unwrap();
+//! store.save_address(&AddressRecord::new(address.clone(), network).with_path(path))?;
+//!
+//! // Persist what the chain client returned
+//! let tx = TxSummary { txid: "ab".repeat(32), height: Some(800_000), timestamp: None, net: 50_000, fee: None };
+//! store.save_transactions(network, &address, &[tx.clone()])?;
+//! store.set_label(LabelKind::Transaction, &tx.txid, "Salary")?;
+//! store.set_sync_cursor(network, "tip", "800000")?;
+//!
+//! assert_eq!(store.transactions(network, &address)?, [tx]);
+//! # Ok::<(), khodpay_storage::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod addresses;
+mod cursors;
+mod error;
+mod labels;
+mod migrations;
+mod store;
+mod transactions;
+mod utxos;
+
+pub use addresses::AddressRecord;
+pub use error::{Error, Result};
+pub use labels::{Label, LabelKind};
+pub use migrations::SCHEMA_VERSION;
+pub use store::WalletStore;
+pub use utxos::UtxoRecord;
@@ -0,0 +1,118 @@
+//! Schema migrations.
+//!
+//! Each entry of [`MIGRATIONS`] upgrades the schema by one version. SQLite's
+//! `user_version` pragma records how many have run, so opening a database applies
+//! only the missing ones. Shipped migrations are never edited; schema changes go in
+//! a new entry.
+
+use rusqlite::Connection;
+
+use crate::{Error, Result};
+
+/// Schema version written by this crate.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "
+    CREATE TABLE addresses (
+        network TEXT NOT NULL,
+        address TEXT NOT NULL,
+        path TEXT,
+        used INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (network, address)
+    );
+
+    CREATE TABLE utxos (
+        network TEXT NOT NULL,
+        txid TEXT NOT NULL,
+        vout INTEGER NOT NULL,
+        value INTEGER NOT NULL,
+        script_pubkey BLOB NOT NULL,
+        height INTEGER,
+        PRIMARY KEY (network, txid, vout)
+    );
+
+    CREATE TABLE transactions (
+        network TEXT NOT NULL,
+        address TEXT NOT NULL,
+        txid TEXT NOT NULL,
+        height INTEGER,
+        timestamp INTEGER,
+        net TEXT NOT NULL,
+        fee TEXT,
+        PRIMARY KEY (network, address, txid)
+    );
+    CREATE INDEX transactions_by_txid ON transactions (network, txid);
+
+    CREATE TABLE labels (
+        kind TEXT NOT NULL,
+        reference TEXT NOT NULL,
+        label TEXT NOT NULL,
+        PRIMARY KEY (kind, reference)
+    );
+
+    CREATE TABLE sync_cursors (
+        network TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (network, key)
+    );
+    ",
+];
+
+/// Returns the schema version of the database.
+pub(crate) fn schema_version(connection: &Connection) -> Result<u32> {
+    Ok(connection.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Brings the schema up to [`SCHEMA_VERSION`] in a single transaction.
+pub(crate) fn migrate(connection: &mut Connection) -> Result<()> {
+    let found = schema_version(connection)?;
+    if found > SCHEMA_VERSION {
+        return Err(Error::SchemaTooNew {
+            found,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    let tx = connection.transaction()?;
+    for migration in &MIGRATIONS[found as usize..] {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_version_matches_migrations() {
+        assert_eq!(MIGRATIONS.len(), SCHEMA_VERSION as usize);
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&connection).unwrap(), 0);
+        migrate(&mut connection).unwrap();
+        assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION);
+        migrate(&mut connection).unwrap();
+        assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_schema() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            migrate(&mut connection),
+            Err(Error::SchemaTooNew { found, .. }) if found == SCHEMA_VERSION + 1
+        ));
+    }
+}
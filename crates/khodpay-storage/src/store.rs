@@ -0,0 +1,149 @@
+//! The [`WalletStore`] handle.
+
+use std::path::Path;
+use std::time::Duration;
+
+use khodpay_address::{BitcoinNetwork, Network};
+use rusqlite::Connection;
+
+use crate::migrations::{migrate, schema_version};
+use crate::{Error, Result};
+
+/// How long a write waits for another connection (such as the app's own) to
+/// release the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wallet state in one SQLite database.
+///
+/// Opening a store runs any pending [migrations](crate::SCHEMA_VERSION), so a
+/// database written by an older release is upgraded in place. Records are grouped
+/// by [`Network`], so one store can hold every coin of a wallet.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::{Address, Network};
+/// use khodpay_storage::{AddressRecord, WalletStore};
+///
+/// let mut store = WalletStore::open_in_memory()?;
+/// let address = Address::parse("0x9858EfFD232B4033E47d90003D41EC34EcaEda94", Network::Evm).unwrap();
+/// store.save_address(&AddressRecord::new(address.clone(), Network::Evm))?;
+///
+/// assert_eq!(store.addresses(Network::Evm)?.len(), 1);
+/// # Ok::<(), khodpay_storage::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct WalletStore {
+    pub(crate) connection: Connection,
+}
+
+impl WalletStore {
+    /// Opens or creates the database at `path` and migrates it to the current schema.
+    ///
+    /// File databases use write-ahead logging, so readers do not block the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SchemaTooNew`] if a newer release wrote the database, or
+    /// [`Error::Database`] if it cannot be opened or migrated.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        Self::from_connection(connection)
+    }
+
+    /// Opens a fresh in-memory database, for tests and watch-only sessions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`] if SQLite fails to initialize.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut connection: Connection) -> Result<Self> {
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        migrate(&mut connection)?;
+        Ok(Self { connection })
+    }
+
+    /// Returns the schema version of the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`] if the query fails.
+    pub fn schema_version(&self) -> Result<u32> {
+        schema_version(&self.connection)
+    }
+}
+
+/// Returns the key a network is stored under.
+pub(crate) fn network_key(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin(BitcoinNetwork::Bitcoin) => "bitcoin",
+        Network::Bitcoin(BitcoinNetwork::Testnet) => "testnet",
+        Network::Bitcoin(BitcoinNetwork::Signet) => "signet",
+        Network::Bitcoin(BitcoinNetwork::Regtest) => "regtest",
+        Network::Evm => "evm",
+        Network::Tron => "tron",
+        Network::Solana => "solana",
+        Network::Xrp => "xrp",
+    }
+}
+
+/// Wraps a decoding failure in a row so it surfaces as [`Error::Corrupt`].
+pub(crate) fn corrupt(column: &str, value: &str, error: impl std::fmt::Display) -> Error {
+    Error::Corrupt(format!("{} '{}': {}", column, value, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "khodpay-storage-{}-{}.sqlite",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_open_file_persists() {
+        let path = temp_path("persist");
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut store = WalletStore::open(&path).unwrap();
+            store
+                .set_sync_cursor(Network::Evm, "last_block", "19000000")
+                .unwrap();
+        }
+        let store = WalletStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), crate::SCHEMA_VERSION);
+        assert_eq!(
+            store.sync_cursor(Network::Evm, "last_block").unwrap(),
+            Some("19000000".to_string())
+        );
+        drop(store);
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = path.clone().into_os_string();
+            file.push(suffix);
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_newer_schema() {
+        let path = temp_path("newer");
+        let _ = std::fs::remove_file(&path);
+        Connection::open(&path)
+            .unwrap()
+            .pragma_update(None, "user_version", crate::SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            WalletStore::open(&path),
+            Err(Error::SchemaTooNew { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
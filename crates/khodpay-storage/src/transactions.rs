@@ -0,0 +1,194 @@
+//! Transaction history per address.
+
+use khodpay_address::{Address, Network};
+use khodpay_amount::{CoinAmount, SATS_DECIMALS, U256, WEI_DECIMALS};
+use khodpay_chain_client::TxSummary;
+use rusqlite::{params, Row};
+
+use crate::store::{corrupt, network_key};
+use crate::{Result, WalletStore};
+
+impl WalletStore {
+    /// Inserts or updates history entries of `address`, as returned by
+    /// [`ChainClient::history`](khodpay_chain_client::ChainClient::history).
+    ///
+    /// Amounts are stored as decimal text in base units, so full wei values
+    /// round-trip; fees are read back with the decimals of the network's coin.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails; no
+    /// entry is saved in that case.
+    pub fn save_transactions(
+        &mut self,
+        network: Network,
+        address: &Address,
+        transactions: &[TxSummary],
+    ) -> Result<()> {
+        let (network, address) = (network_key(network), address.to_string());
+        let tx = self.connection.transaction()?;
+        {
+            let mut statement = tx.prepare(
+                "INSERT OR REPLACE INTO transactions
+                 (network, address, txid, height, timestamp, net, fee)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for summary in transactions {
+                statement.execute(params![
+                    network,
+                    address,
+                    summary.txid,
+                    summary.height,
+                    summary.timestamp,
+                    summary.net.to_string(),
+                    summary.fee.map(|fee| fee.value().to_string()),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns the stored history of `address`, unconfirmed first, then newest to
+    /// oldest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or an entry cannot be decoded.
+    pub fn transactions(&self, network: Network, address: &Address) -> Result<Vec<TxSummary>> {
+        let mut statement = self.connection.prepare(
+            "SELECT txid, height, timestamp, net, fee FROM transactions
+             WHERE network = ?1 AND address = ?2
+             ORDER BY height IS NOT NULL, height DESC, txid",
+        )?;
+        let transactions = statement
+            .query_and_then(params![network_key(network), address.to_string()], |row| {
+                summary_from_row(row, native_decimals(network))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(transactions)
+    }
+
+    /// Removes a transaction from the history of every address, such as a mempool
+    /// transaction that was replaced or dropped, and returns whether it was stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn remove_transaction(&mut self, network: Network, txid: &str) -> Result<bool> {
+        let removed = self.connection.execute(
+            "DELETE FROM transactions WHERE network = ?1 AND txid = ?2",
+            params![network_key(network), txid],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+/// Returns the decimals of the native coin of `network`, which fees are paid in.
+fn native_decimals(network: Network) -> u8 {
+    match network {
+        Network::Bitcoin(_) => SATS_DECIMALS,
+        Network::Evm => WEI_DECIMALS,
+        Network::Tron | Network::Xrp => 6,
+        Network::Solana => 9,
+    }
+}
+
+fn summary_from_row(row: &Row<'_>, fee_decimals: u8) -> Result<TxSummary> {
+    let net: String = row.get("net")?;
+    let fee: Option<String> = row.get("fee")?;
+    Ok(TxSummary {
+        txid: row.get("txid")?,
+        height: row.get("height")?,
+        timestamp: row.get("timestamp")?,
+        net: net.parse().map_err(|e| corrupt("net", &net, e))?,
+        fee: fee
+            .map(|fee| match U256::from_dec_str(&fee) {
+                Ok(value) => Ok(CoinAmount::new(value, fee_decimals)),
+                Err(e) => Err(corrupt("fee", &fee, format!("{:?}", e))),
+            })
+            .transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> Address {
+        Address::parse("0x9858EfFD232B4033E47d90003D41EC34EcaEda94", Network::Evm).unwrap()
+    }
+
+    fn summary(txid: &str, height: Option<u64>, net: i128) -> TxSummary {
+        TxSummary {
+            txid: txid.to_string(),
+            height,
+            timestamp: height.map(|h| 1_700_000_000 + h),
+            net,
+            fee: Some(CoinAmount::new(21_000u64 * 30_000_000_000, WEI_DECIMALS)),
+        }
+    }
+
+    #[test]
+    fn test_save_and_order() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let history = [
+            summary("0xaa", Some(100), 1_000_000_000_000_000_000),
+            summary("0xbb", None, -5),
+            summary("0xcc", Some(200), -(u64::MAX as i128) * 1_000),
+        ];
+        store
+            .save_transactions(Network::Evm, &address(), &history)
+            .unwrap();
+
+        let stored = store.transactions(Network::Evm, &address()).unwrap();
+        assert_eq!(
+            stored,
+            [history[1].clone(), history[2].clone(), history[0].clone()]
+        );
+        assert!(store
+            .transactions(Network::Tron, &address())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_confirmation_updates_and_removal() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        store
+            .save_transactions(Network::Evm, &address(), &[summary("0xbb", None, 7)])
+            .unwrap();
+        store
+            .save_transactions(Network::Evm, &address(), &[summary("0xbb", Some(5), 7)])
+            .unwrap();
+        let stored = store.transactions(Network::Evm, &address()).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].is_confirmed());
+
+        assert!(store.remove_transaction(Network::Evm, "0xbb").unwrap());
+        assert!(!store.remove_transaction(Network::Evm, "0xbb").unwrap());
+        assert!(store
+            .transactions(Network::Evm, &address())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fees_use_native_decimals() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let network = Network::Bitcoin(khodpay_address::BitcoinNetwork::Bitcoin);
+        let address =
+            Address::parse("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", network).unwrap();
+        let summary = TxSummary {
+            fee: Some(CoinAmount::from_sats(141)),
+            ..summary("ab", Some(800_000), 50_000)
+        };
+        store
+            .save_transactions(network, &address, std::slice::from_ref(&summary))
+            .unwrap();
+
+        let stored = store.transactions(network, &address).unwrap();
+        assert_eq!(stored, [summary]);
+        assert_eq!(stored[0].fee.unwrap().to_string(), "0.00000141");
+    }
+}
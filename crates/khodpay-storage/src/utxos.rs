@@ -0,0 +1,199 @@
+//! Unspent Bitcoin outputs.
+
+use khodpay_address::{BitcoinNetwork, Network};
+use khodpay_btc_signing::{OutPoint, Script, Utxo};
+use rusqlite::{params, Row};
+
+use crate::store::{corrupt, network_key};
+use crate::{Result, WalletStore};
+
+/// An unspent output the wallet can spend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoRecord {
+    /// Network the output is on.
+    pub network: BitcoinNetwork,
+    /// The output, ready for a transaction builder.
+    pub utxo: Utxo,
+    /// Height of the block that created it, or `None` while unconfirmed.
+    pub height: Option<u64>,
+}
+
+impl UtxoRecord {
+    fn from_row(network: BitcoinNetwork, row: &Row<'_>) -> Result<Self> {
+        let txid: String = row.get("txid")?;
+        let outpoint = OutPoint {
+            txid: txid.parse().map_err(|e| corrupt("txid", &txid, e))?,
+            vout: row.get("vout")?,
+        };
+        Ok(Self {
+            network,
+            utxo: Utxo::new(
+                outpoint,
+                row.get("value")?,
+                Script::new(row.get("script_pubkey")?),
+            ),
+            height: row.get("height")?,
+        })
+    }
+}
+
+impl WalletStore {
+    /// Inserts or updates an unspent output.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn save_utxo(&mut self, record: &UtxoRecord) -> Result<()> {
+        insert_utxo(&self.connection, record)
+    }
+
+    /// Replaces every output of `network` with `records`, as after a full resync.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails; the
+    /// stored outputs are then left unchanged.
+    pub fn replace_utxos(&mut self, network: BitcoinNetwork, records: &[UtxoRecord]) -> Result<()> {
+        let tx = self.connection.transaction()?;
+        tx.execute(
+            "DELETE FROM utxos WHERE network = ?1",
+            params![network_key(Network::Bitcoin(network))],
+        )?;
+        for record in records {
+            insert_utxo(&tx, record)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Removes a spent output and returns whether it was stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Database`](crate::Error::Database) if the write fails.
+    pub fn remove_utxo(&mut self, network: BitcoinNetwork, outpoint: &OutPoint) -> Result<bool> {
+        let removed = self.connection.execute(
+            "DELETE FROM utxos WHERE network = ?1 AND txid = ?2 AND vout = ?3",
+            params![
+                network_key(Network::Bitcoin(network)),
+                outpoint.txid.to_string(),
+                outpoint.vout,
+            ],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Returns the unspent outputs of `network`, oldest confirmed first and
+    /// unconfirmed last.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a record cannot be decoded.
+    pub fn utxos(&self, network: BitcoinNetwork) -> Result<Vec<UtxoRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT txid, vout, value, script_pubkey, height FROM utxos WHERE network = ?1
+             ORDER BY height IS NULL, height, txid, vout",
+        )?;
+        let records = statement
+            .query_and_then(params![network_key(Network::Bitcoin(network))], |row| {
+                UtxoRecord::from_row(network, row)
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(records)
+    }
+}
+
+fn insert_utxo(connection: &rusqlite::Connection, record: &UtxoRecord) -> Result<()> {
+    let Utxo {
+        outpoint,
+        value,
+        script_pubkey,
+    } = &record.utxo;
+    connection.execute(
+        "INSERT OR REPLACE INTO utxos (network, txid, vout, value, script_pubkey, height)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            network_key(Network::Bitcoin(record.network)),
+            outpoint.txid.to_string(),
+            outpoint.vout,
+            value,
+            script_pubkey.as_bytes(),
+            record.height,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_btc_signing::Txid;
+
+    fn record(txid: u8, vout: u32, height: Option<u64>) -> UtxoRecord {
+        UtxoRecord {
+            network: BitcoinNetwork::Bitcoin,
+            utxo: Utxo::new(
+                OutPoint {
+                    txid: Txid::from_bytes([txid; 32]),
+                    vout,
+                },
+                10_000 * u64::from(txid),
+                Script::p2wpkh(&[txid; 20]),
+            ),
+            height,
+        }
+    }
+
+    #[test]
+    fn test_save_and_remove() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        let utxo = record(1, 0, None);
+        store.save_utxo(&utxo).unwrap();
+
+        // Confirmation updates the stored output
+        let confirmed = record(1, 0, Some(800_000));
+        store.save_utxo(&confirmed).unwrap();
+        assert_eq!(store.utxos(BitcoinNetwork::Bitcoin).unwrap(), [confirmed]);
+        assert!(store.utxos(BitcoinNetwork::Testnet).unwrap().is_empty());
+
+        let outpoint = utxo.utxo.outpoint;
+        assert!(store
+            .remove_utxo(BitcoinNetwork::Bitcoin, &outpoint)
+            .unwrap());
+        assert!(!store
+            .remove_utxo(BitcoinNetwork::Bitcoin, &outpoint)
+            .unwrap());
+        assert!(store.utxos(BitcoinNetwork::Bitcoin).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replace_and_order() {
+        let mut store = WalletStore::open_in_memory().unwrap();
+        store.save_utxo(&record(9, 0, Some(1))).unwrap();
+        store
+            .replace_utxos(
+                BitcoinNetwork::Bitcoin,
+                &[
+                    record(3, 0, None),
+                    record(2, 1, Some(800_100)),
+                    record(4, 0, Some(800_000)),
+                ],
+            )
+            .unwrap();
+
+        let order: Vec<_> = store
+            .utxos(BitcoinNetwork::Bitcoin)
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.utxo.value, record.height))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (40_000, Some(800_000)),
+                (20_000, Some(800_100)),
+                (30_000, None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! # Khodpay Tracing
+//!
+//! `tracing` instrumentation for the KhodPay wallet crates, built so that key
+//! material, seeds and mnemonics never reach a log field.
+//!
+//! With their `tracing` feature, `khodpay-bip32`, `khodpay-bip44`,
+//! `khodpay-signing` and `khodpay-btc-signing` open a span for every key
+//! derivation, account discovery and signature. Each span carries an operation
+//! id, the call's public parameters (derivation paths, networks, chain ids,
+//! counts) and its duration.
+//!
+//! Two mechanisms keep secrets out:
+//!
+//! | Mechanism | Guards | How |
+//! |---|---|---|
+//! | [`LogSafe`] | KhodPay's own spans | [`operation!`] only accepts field values of `LogSafe` types, which secrets are not |
+//! | [`RedactionLayer`] (`layer` feature) | Every span and event in the process | Replaces fields whose name or value looks like a secret before the wrapped layer sees them |
+//!
+//! ## Spans
+//!
+//! | Crate | Span | Fields |
+//! |---|---|---|
+//! | `khodpay-bip32` | `master_key` | `network` |
+//! | `khodpay-bip32` | `derive_path` | `path`, `network` |
+//! | `khodpay-bip32` | `derive_public_path` | `path`, `network` |
+//! | `khodpay-bip44` | `derive_account` | `purpose`, `coin_type`, `account` |
+//! | `khodpay-bip44` | `discover_accounts` | `gap_limit`, `max_accounts`, `accounts_scanned`, `addresses_checked` |
+//! | `khodpay-bip44` | `scan_chain` | `chain`, `gap_limit`, `addresses_checked` |
+//! | `khodpay-signing` | `sign_transaction` | `chain_id`, `tx_type` |
+//! | `khodpay-signing` | `sign_message` | `message_len` |
+//! | `khodpay-signing` | `sign_prehashed` | none |
+//! | `khodpay-signing` | `sign_typed_data` | `chain_id` |
+//! | `khodpay-btc-signing` | `sign_transaction`, `sign_psbt` | `network`, `inputs`, `signed` |
+//! | `khodpay-btc-signing` | `sign_message` | `network`, `message_len` |
+//!
+//! Every span also has `op_id` and `duration_us`. Spans are `INFO`; their
+//! target is the instrumented module, such as `khodpay_bip32::extended_private_key`.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_tracing::RedactionLayer;
+//! use tracing_subscriber::fmt::format::FmtSpan;
+//! use tracing_subscriber::layer::SubscriberExt;
+//!
+//! let logs = tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE);
+//! let subscriber = tracing_subscriber::registry().with(RedactionLayer::new(logs));
+//! tracing::subscriber::set_global_default(subscriber).unwrap();
+//!
+//! // INFO derive_path{path=m/44'/0'/0'/0/0 network=Bitcoin Mainnet op_id=7 duration_us=112}: close …
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod operation;
+#[cfg(feature = "layer")]
+mod redact;
+mod safe;
+#[cfg(feature = "layer")]
+mod secrets;
+
+pub use operation::{Operation, OperationId};
+#[cfg(feature = "layer")]
+pub use redact::{RedactionLayer, REDACTED};
+pub use safe::LogSafe;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use tracing;
+}
@@ -0,0 +1,178 @@
+//! Operations: one span per derivation, scan or signature, with an id and a duration.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use tracing::span::EnteredSpan;
+use tracing::{Span, Value};
+
+use crate::LogSafe;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies an operation within the process, so its span can be matched with
+/// the application's own events and errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OperationId(u64);
+
+impl OperationId {
+    /// Returns the next id: unique within the process and increasing.
+    pub fn next() -> Self {
+        OperationId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Returns the id as a number.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for OperationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl LogSafe for OperationId {
+    fn log_value(&self) -> impl Value + '_ {
+        self.0
+    }
+}
+
+/// A running operation, started by [`operation!`](crate::operation).
+///
+/// Its span stays entered until the operation is dropped, which records the
+/// span's `duration_us` field. The span closes then too, so subscribers that
+/// log span closes (`FmtSpan::CLOSE` in `tracing-subscriber`) print every field
+/// with the duration.
+///
+/// Entering a span is per thread: keep an `Operation` out of `async` code that
+/// awaits while it is alive.
+#[must_use = "the operation ends when dropped"]
+pub struct Operation {
+    id: OperationId,
+    started: Instant,
+    span: EnteredSpan,
+}
+
+impl Operation {
+    /// Starts an operation in `span`, recording a new id into its `op_id` field.
+    ///
+    /// The span must declare empty `op_id` and `duration_us` fields, as
+    /// [`operation!`](crate::operation) does.
+    pub fn start(span: Span) -> Self {
+        let id = OperationId::next();
+        span.record("op_id", id.value());
+        Operation {
+            id,
+            started: Instant::now(),
+            span: span.entered(),
+        }
+    }
+
+    /// Returns the operation's id.
+    pub fn id(&self) -> OperationId {
+        self.id
+    }
+
+    /// Records a field declared as `name = _` in [`operation!`](crate::operation),
+    /// such as a count known only once the operation is done.
+    pub fn record<T: LogSafe + ?Sized>(&self, name: &'static str, value: &T) {
+        self.span.record(name, value.log_value());
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        let duration_us = u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        self.span.record("duration_us", duration_us);
+    }
+}
+
+impl fmt::Debug for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Operation")
+            .field("id", &self.id)
+            .field("started", &self.started)
+            .finish()
+    }
+}
+
+/// Starts an [`Operation`] in a new `INFO` span named `$name`.
+///
+/// Fields are `key = value` pairs whose values are [`LogSafe`](crate::LogSafe), so
+/// secrets cannot be passed, or `key = _` for a field recorded later with
+/// [`Operation::record`]. The span also gets `op_id`, recorded now, and
+/// `duration_us`, recorded when the operation is dropped. Its target is the
+/// calling module, as for `tracing`'s own macros.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_tracing::operation;
+///
+/// fn scan(gap_limit: u32) -> usize {
+///     let operation = operation!("scan", gap_limit = gap_limit, found = _);
+///     let found = 3usize;
+///     operation.record("found", &found);
+///     found
+/// }
+/// # scan(20);
+/// ```
+///
+/// A string built at runtime is not `LogSafe`:
+///
+/// ```compile_fail
+/// let phrase = String::from("abandon abandon abandon");
+/// let _operation = khodpay_tracing::operation!("restore", phrase = phrase);
+/// ```
+#[macro_export]
+macro_rules! operation {
+    (@fields $name:literal [$($out:tt)*] $key:ident = _ $(, $($rest:tt)*)?) => {
+        $crate::operation!(
+            @fields $name
+            [$($out)* $key = $crate::__private::tracing::field::Empty,]
+            $($($rest)*)?
+        )
+    };
+    (@fields $name:literal [$($out:tt)*] $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::operation!(
+            @fields $name
+            [$($out)* $key = $crate::LogSafe::log_value(&$value),]
+            $($($rest)*)?
+        )
+    };
+    (@fields $name:literal [$($out:tt)*]) => {
+        $crate::Operation::start($crate::__private::tracing::info_span!(
+            $name,
+            $($out)*
+            op_id = $crate::__private::tracing::field::Empty,
+            duration_us = $crate::__private::tracing::field::Empty
+        ))
+    };
+    ($name:literal $(, $($fields:tt)*)?) => {
+        $crate::operation!(@fields $name [] $($($fields)*)?)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_ids_increase() {
+        let first = OperationId::next();
+        let second = OperationId::next();
+        assert!(second > first);
+        assert_eq!(second.to_string(), second.value().to_string());
+    }
+
+    #[test]
+    fn test_operation_without_subscriber() {
+        let path = "m/44'/0'/0'";
+        let operation = crate::operation!("derive", path = path, depth = 3u8, found = _,);
+        operation.record("found", &2usize);
+        assert!(operation.id().value() > 0);
+    }
+}
@@ -0,0 +1,396 @@
+//! The redaction layer: span and event fields as the wrapped layer sees them.
+
+use std::fmt;
+
+use tracing::field::{display, DisplayValue, Field, Value, ValueSet, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::secrets::{contains_secret, is_secret_bytes, is_secret_name};
+
+/// What a redacted field is recorded as.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A [`Layer`] that hides secrets from the layer it wraps.
+///
+/// Every span and event field is checked before `inner` sees it, and replaced
+/// with [`REDACTED`] if:
+///
+/// - its name contains `seed`, `mnemonic`, `phrase`, `passphrase`, `password`,
+///   `secret`, `private_key`, `privkey`, `xprv`, `entropy` or `wif`, or
+/// - its value contains an extended private key (`xprv`, `zprv`, `tprv`, ...), a
+///   WIF private key, a hex-encoded 64-byte seed, twelve or more consecutive
+///   BIP-39 words in any language, or is 64 raw bytes.
+///
+/// Fields and events without secrets pass through untouched. This guards the
+/// fields of every crate in the process, including the application's own; the
+/// KhodPay crates additionally record only [`LogSafe`](crate::LogSafe) values.
+/// A bare 32-byte key under an innocuous name looks like any hash, so it is
+/// not caught by value.
+///
+/// Wrap the layers that write logs, and add per-layer filters outside the
+/// wrapper.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_tracing::RedactionLayer;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let subscriber = tracing_subscriber::registry()
+///     .with(RedactionLayer::new(tracing_subscriber::fmt::layer()));
+///
+/// tracing::subscriber::with_default(subscriber, || {
+///     let phrase = "abandon abandon abandon abandon abandon abandon \
+///                   abandon abandon abandon abandon abandon about";
+///     // Logged as `restoring wallet phrase="[REDACTED]"`
+///     tracing::info!(phrase, "restoring wallet");
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedactionLayer<L> {
+    inner: L,
+}
+
+impl<L> RedactionLayer<L> {
+    /// Wraps `inner`, which then only sees redacted fields.
+    pub fn new(inner: L) -> Self {
+        RedactionLayer { inner }
+    }
+
+    /// Returns the wrapped layer.
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+}
+
+impl<S, L> Layer<S> for RedactionLayer<L>
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+    L: Layer<S>,
+{
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let fields = Fields::capture(|visitor| attrs.record(visitor));
+        if !fields.has_secret() {
+            return self.inner.on_new_span(attrs, id, ctx);
+        }
+        let metadata = attrs.metadata();
+        fields.redacted(metadata, |values| {
+            let redacted = if attrs.is_root() {
+                Attributes::new_root(metadata, values)
+            } else if let Some(parent) = attrs.parent() {
+                Attributes::child_of(parent.clone(), metadata, values)
+            } else {
+                Attributes::new(metadata, values)
+            };
+            self.inner.on_new_span(&redacted, id, ctx);
+        });
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let fields = Fields::capture(|visitor| values.record(visitor));
+        if !fields.has_secret() {
+            return self.inner.on_record(span, values, ctx);
+        }
+        // A span the subscriber does not know: drop the update rather than leak it
+        if let Some(metadata) = ctx.metadata(span) {
+            fields.redacted(metadata, |values| {
+                self.inner.on_record(span, &Record::new(values), ctx);
+            });
+        }
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let fields = Fields::capture(|visitor| event.record(visitor));
+        if !fields.has_secret() {
+            return self.inner.on_event(event, ctx);
+        }
+        let metadata = event.metadata();
+        fields.redacted(metadata, |values| {
+            let redacted = if event.is_root() {
+                Event::new_child_of(None, metadata, values)
+            } else if let Some(parent) = event.parent() {
+                Event::new_child_of(parent.clone(), metadata, values)
+            } else {
+                Event::new(metadata, values)
+            };
+            self.inner.on_event(&redacted, ctx);
+        });
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+}
+
+/// A field value copied out of a span or event, recorded again as it came.
+enum Captured {
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    Bytes(Box<[u8]>),
+    Debug(DisplayValue<String>),
+}
+
+impl Captured {
+    fn is_secret(&self) -> bool {
+        match self {
+            Captured::Str(text) => contains_secret(text),
+            Captured::Debug(text) => contains_secret(&text.to_string()),
+            Captured::Bytes(bytes) => is_secret_bytes(bytes),
+            _ => false,
+        }
+    }
+
+    fn as_value(&self) -> &dyn Value {
+        match self {
+            Captured::I64(value) => value,
+            Captured::U64(value) => value,
+            Captured::I128(value) => value,
+            Captured::U128(value) => value,
+            Captured::F64(value) => value,
+            Captured::Bool(value) => value,
+            Captured::Str(value) => value,
+            Captured::Bytes(value) => value,
+            Captured::Debug(value) => value,
+        }
+    }
+}
+
+/// The fields of a span, span update or event, each marked secret or not.
+#[derive(Default)]
+struct Fields {
+    fields: Vec<(Field, Captured, bool)>,
+}
+
+impl Fields {
+    fn capture(record: impl FnOnce(&mut Self)) -> Self {
+        let mut fields = Fields::default();
+        record(&mut fields);
+        fields
+    }
+
+    fn push(&mut self, field: &Field, value: Captured) {
+        let secret = is_secret_name(field.name()) || value.is_secret();
+        self.fields.push((field.clone(), value, secret));
+    }
+
+    fn has_secret(&self) -> bool {
+        self.fields.iter().any(|(_, _, secret)| *secret)
+    }
+
+    /// Calls `f` with the fields, secrets replaced by [`REDACTED`].
+    fn redacted(&self, metadata: &'static Metadata<'static>, f: impl FnOnce(&ValueSet<'_>)) {
+        let Some((first, _, _)) = self.fields.first() else {
+            return;
+        };
+        // A callsite has at most 32 fields; unused entries record nothing
+        let mut values: [(&Field, Option<&dyn Value>); 32] = [(first, None); 32];
+        for (entry, (field, value, secret)) in values.iter_mut().zip(&self.fields) {
+            let value: &dyn Value = if *secret { &REDACTED } else { value.as_value() };
+            *entry = (field, Some(value));
+        }
+        f(&metadata.fields().value_set(&values));
+    }
+}
+
+impl Visit for Fields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Captured::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, Captured::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.push(field, Captured::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.push(field, Captured::U128(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, Captured::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Captured::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, Captured::Str(value.to_string()));
+    }
+
+    fn record_bytes(&mut self, field: &Field, value: &[u8]) {
+        self.push(field, Captured::Bytes(value.into()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, Captured::Debug(display(format!("{:?}", value))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Records every field the wrapped layer sees, formatted as `name=value`.
+    #[derive(Clone, Default)]
+    struct Seen(Arc<Mutex<Vec<String>>>);
+
+    struct SeenVisitor<'a>(&'a mut Vec<String>);
+
+    impl Visit for SeenVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Seen {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            attrs.record(&mut SeenVisitor(&mut self.0.lock().unwrap()));
+        }
+
+        fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            values.record(&mut SeenVisitor(&mut self.0.lock().unwrap()));
+        }
+
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            event.record(&mut SeenVisitor(&mut self.0.lock().unwrap()));
+        }
+    }
+
+    fn seen(f: impl FnOnce()) -> Vec<String> {
+        let seen = Seen::default();
+        let subscriber = tracing_subscriber::registry().with(RedactionLayer::new(seen.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        let fields = seen.0.lock().unwrap().clone();
+        fields
+    }
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_redacts_secret_names() {
+        let fields = seen(|| {
+            tracing::info!(seed = 42, account = 3u32, "derived");
+        });
+        assert_eq!(
+            fields,
+            vec!["message=derived", "seed=\"[REDACTED]\"", "account=3"]
+        );
+    }
+
+    #[test]
+    fn test_redacts_secret_values() {
+        let fields = seen(|| {
+            tracing::warn!(input = PHRASE, "parsing failed");
+            tracing::warn!("parsing failed for {}", PHRASE);
+            tracing::info!(bytes = &[7u8; 64][..]);
+        });
+        assert_eq!(
+            fields,
+            vec![
+                "message=parsing failed",
+                "input=\"[REDACTED]\"",
+                "message=\"[REDACTED]\"",
+                "bytes=\"[REDACTED]\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_passes_public_fields() {
+        let fields = seen(|| {
+            tracing::info!(
+                path = "m/84'/0'/0'/0/0",
+                address = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                bytes = &[7u8; 32][..],
+                ok = true,
+                "signed"
+            );
+        });
+        assert_eq!(
+            fields,
+            vec![
+                "message=signed",
+                "path=\"m/84'/0'/0'/0/0\"",
+                "address=\"bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu\"",
+                format!("bytes=[{}]", ["07"; 32].join(" ")).as_str(),
+                "ok=true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redacts_span_fields() {
+        let fields = seen(|| {
+            let span = tracing::info_span!(
+                "restore",
+                words = 12u32,
+                passphrase = "hunter2",
+                xpub = tracing::field::Empty,
+                label = tracing::field::Empty
+            );
+            span.record("xpub", "xpub661MyMwAqRbc");
+            span.record("label", PHRASE);
+        });
+        assert_eq!(
+            fields,
+            vec![
+                "words=12",
+                "passphrase=\"[REDACTED]\"",
+                "xpub=\"xpub661MyMwAqRbc\"",
+                "label=\"[REDACTED]\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Values that may be recorded in trace fields.
+
+use std::time::Duration;
+
+use tracing::field::debug;
+use tracing::Value;
+
+/// A value that is safe to record in a trace field: never key material, a seed,
+/// a mnemonic or a passphrase.
+///
+/// [`operation!`](crate::operation) only accepts `LogSafe` field values, so the
+/// type checker keeps secrets out of KhodPay's spans. Numbers, booleans and
+/// string literals are `LogSafe`; strings built at runtime and byte arrays are
+/// not, since a phrase or a private key is exactly that. The wallet crates
+/// implement it for public types such as derivation paths and networks.
+///
+/// Implement it for a type of your own only if none of its values is secret.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_tracing::LogSafe;
+/// use std::fmt;
+///
+/// struct Region(u8);
+///
+/// impl fmt::Display for Region {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "region-{}", self.0)
+///     }
+/// }
+///
+/// impl LogSafe for Region {
+///     fn log_value(&self) -> impl tracing::Value + '_ {
+///         tracing::field::display(self)
+///     }
+/// }
+/// ```
+pub trait LogSafe {
+    /// Returns the value as recorded in a field.
+    fn log_value(&self) -> impl Value + '_;
+}
+
+macro_rules! impl_copy {
+    ($($ty:ty),*) => {
+        $(
+            impl LogSafe for $ty {
+                fn log_value(&self) -> impl Value + '_ {
+                    *self
+                }
+            }
+        )*
+    };
+}
+
+impl_copy!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, bool);
+
+/// Only literals and other constants: a `&str` built at runtime is not `'static`.
+impl LogSafe for &'static str {
+    fn log_value(&self) -> impl Value + '_ {
+        *self
+    }
+}
+
+impl LogSafe for Duration {
+    fn log_value(&self) -> impl Value + '_ {
+        debug(self)
+    }
+}
+
+impl<T: LogSafe> LogSafe for Option<T> {
+    fn log_value(&self) -> impl Value + '_ {
+        self.as_ref().map(LogSafe::log_value)
+    }
+}
+
+impl<T: LogSafe + ?Sized> LogSafe for &T {
+    fn log_value(&self) -> impl Value + '_ {
+        (**self).log_value()
+    }
+}
@@ -0,0 +1,193 @@
+//! What the redaction layer treats as a secret.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use khodpay_bip39::Language;
+
+/// Field names that hold secrets, matched case-insensitively anywhere in a name.
+const SECRET_NAMES: &[&str] = &[
+    "mnemonic",
+    "phrase",
+    "seed",
+    "passphrase",
+    "password",
+    "secret",
+    "private_key",
+    "privkey",
+    "xprv",
+    "entropy",
+    "wif",
+];
+
+/// Version prefixes of extended private keys: BIP-32, 49 and 84, mainnet and testnet.
+const XPRV_PREFIXES: &[&str] = &[
+    "xprv", "yprv", "zprv", "Yprv", "Zprv", "tprv", "uprv", "vprv", "Uprv", "Vprv",
+];
+
+/// The shortest run of consecutive BIP-39 words treated as a mnemonic.
+const MNEMONIC_WORDS: usize = 12;
+
+/// Returns `true` if a field called `name` holds a secret.
+pub(crate) fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_NAMES.iter().any(|secret| name.contains(secret))
+}
+
+/// Returns `true` if `text` contains an extended private key, a WIF private key,
+/// a hex-encoded 64-byte seed or a BIP-39 mnemonic.
+pub(crate) fn contains_secret(text: &str) -> bool {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .any(is_secret_token)
+        || contains_mnemonic(text)
+}
+
+/// Returns `true` for 64 raw bytes, the length of a BIP-39 seed.
+pub(crate) fn is_secret_bytes(bytes: &[u8]) -> bool {
+    bytes.len() == 64
+}
+
+fn is_secret_token(token: &str) -> bool {
+    let hex = token.strip_prefix("0x").unwrap_or(token);
+    if hex.len() == 128 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return true;
+    }
+
+    let base58 = token.chars().all(is_base58);
+    match token.len() {
+        111 => base58 && XPRV_PREFIXES.iter().any(|prefix| token.starts_with(prefix)),
+        // WIF: uncompressed keys start with 5 (mainnet) or 9 (testnet)
+        51 => base58 && token.starts_with(['5', '9']),
+        // WIF: compressed keys start with K or L (mainnet) or c (testnet)
+        52 => base58 && token.starts_with(['K', 'L', 'c']),
+        _ => false,
+    }
+}
+
+fn is_base58(c: char) -> bool {
+    c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l')
+}
+
+fn contains_mnemonic(text: &str) -> bool {
+    let mut run = 0;
+    for word in text
+        .split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+        .filter(|word| !word.is_empty())
+    {
+        if is_bip39_word(word) {
+            run += 1;
+            if run >= MNEMONIC_WORDS {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    false
+}
+
+/// Returns `true` if `word` is in the word list of any BIP-39 language.
+fn is_bip39_word(word: &str) -> bool {
+    static WORDS: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    let words = WORDS.get_or_init(|| {
+        Language::all_variants()
+            .iter()
+            .flat_map(|language| language.word_list().iter().copied())
+            .collect()
+    });
+    words.contains(word) || words.contains(word.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon abandon abandon about";
+    const XPRV: &str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+
+    #[test]
+    fn test_secret_names() {
+        for name in [
+            "seed",
+            "Mnemonic",
+            "user_passphrase",
+            "private_key_hex",
+            "xprv",
+            "wif",
+        ] {
+            assert!(is_secret_name(name), "{}", name);
+        }
+        for name in [
+            "path",
+            "chain_id",
+            "op_id",
+            "duration_us",
+            "message",
+            "address",
+        ] {
+            assert!(!is_secret_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_extended_private_keys() {
+        assert!(contains_secret(XPRV));
+        assert!(contains_secret(&format!("restored from \"{}\"", XPRV)));
+        assert!(contains_secret(&XPRV.replacen("xprv", "tprv", 1)));
+        let xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+        assert!(!contains_secret(xpub));
+    }
+
+    #[test]
+    fn test_wif() {
+        assert!(contains_secret(
+            "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn"
+        ));
+        assert!(contains_secret(
+            "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ"
+        ));
+        // Addresses are public
+        assert!(!contains_secret(
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        ));
+        assert!(!contains_secret("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"));
+    }
+
+    #[test]
+    fn test_hex_seeds() {
+        let seed = "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc1\
+                    9a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4";
+        assert!(contains_secret(seed));
+        assert!(contains_secret(&format!("0x{}", seed)));
+        // 32-byte hashes are public
+        assert!(!contains_secret(&seed[..64]));
+        assert!(!contains_secret(&format!("{}00", seed)));
+        assert!(is_secret_bytes(&[0u8; 64]));
+        assert!(!is_secret_bytes(&[0u8; 32]));
+    }
+
+    #[test]
+    fn test_mnemonics() {
+        assert!(contains_secret(PHRASE));
+        assert!(contains_secret(&format!(
+            "phrase: \"{}\"",
+            PHRASE.to_uppercase()
+        )));
+        assert!(contains_secret(
+            "あいこくしん あいこくしん あいこくしん あいこくしん あいこくしん あいこくしん \
+             あいこくしん あいこくしん あいこくしん あいこくしん あいこくしん あいさつ"
+        ));
+        // Fewer than twelve words, or broken up by other words
+        let words: Vec<&str> = PHRASE.split(' ').collect();
+        assert!(!contains_secret(&words[..11].join(" ")));
+        assert!(!contains_secret(&format!(
+            "{} 42 {}",
+            words[..6].join(" "),
+            words[6..].join(" ")
+        )));
+        assert!(!contains_secret(
+            "derived account 0 of coin 60 in 1250 microseconds after a cache miss"
+        ));
+    }
+}
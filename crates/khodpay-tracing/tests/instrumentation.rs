@@ -0,0 +1,405 @@
+//! Integration tests for the spans of the instrumented wallet crates.
+//!
+//! These tests run derivation, discovery and signing under a capturing subscriber
+//! and check the recorded spans, and that no secret reaches a field or a log line.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use khodpay_bip32::{DerivationPath, ExtendedPrivateKey, Network};
+use khodpay_bip39::{Language, Mnemonic};
+use khodpay_bip44::{AccountScanner, Chain, CoinType, GapLimitChecker, MockBlockchain, Purpose};
+use khodpay_btc_signing::{BtcSigner, OutPoint, TransactionBuilder, Txid, Utxo};
+use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+use khodpay_signing::{Bip44Signer, ChainId, Eip1559Transaction, PrehashPolicy, Wei};
+use khodpay_tracing::{RedactionLayer, REDACTED};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+
+/// Standard test mnemonic (DO NOT USE IN PRODUCTION).
+const TEST_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// A span as seen by [`Capture`]: its name and every field recorded into it.
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    name: &'static str,
+    fields: HashMap<&'static str, String>,
+}
+
+#[derive(Default)]
+struct Captured {
+    spans: Vec<SpanRecord>,
+    open: HashMap<Id, usize>,
+}
+
+/// Records every span and its fields.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Captured>>);
+
+impl Capture {
+    fn spans(&self) -> Vec<SpanRecord> {
+        self.0.lock().unwrap().spans.clone()
+    }
+
+    fn named(&self, name: &str) -> Vec<SpanRecord> {
+        self.spans()
+            .into_iter()
+            .filter(|span| span.name == name)
+            .collect()
+    }
+
+    /// Every field of every span, as one string.
+    fn all_fields(&self) -> String {
+        self.spans()
+            .iter()
+            .flat_map(|span| span.fields.values().cloned())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+struct Fields<'a>(&'a mut HashMap<&'static str, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = HashMap::new();
+        attrs.record(&mut Fields(&mut fields));
+        let mut captured = self.0.lock().unwrap();
+        captured.spans.push(SpanRecord {
+            name: attrs.metadata().name(),
+            fields,
+        });
+        let index = captured.spans.len() - 1;
+        captured.open.insert(id.clone(), index);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut captured = self.0.lock().unwrap();
+        if let Some(&index) = captured.open.get(id) {
+            values.record(&mut Fields(&mut captured.spans[index].fields));
+        }
+    }
+
+    fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {}
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        self.0.lock().unwrap().open.remove(&id);
+    }
+}
+
+/// Runs `f` with only `capture` listening, on this thread.
+fn capture<T>(f: impl FnOnce() -> T) -> (Capture, T) {
+    let capture = Capture::default();
+    let subscriber = tracing_subscriber::registry().with(capture.clone());
+    let result = tracing::subscriber::with_default(subscriber, f);
+    (capture, result)
+}
+
+/// Asserts that none of the secrets behind [`TEST_MNEMONIC`] appear in `text`.
+fn assert_no_secrets(text: &str) {
+    let mnemonic = Mnemonic::from_phrase(TEST_MNEMONIC, Language::English).unwrap();
+    let seed = mnemonic.to_seed("").unwrap();
+    let master = ExtendedPrivateKey::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+
+    assert!(!text.contains("abandon"), "mnemonic in {}", text);
+    assert!(!text.contains(&hex::encode(seed)), "seed in {}", text);
+    assert!(!text.contains(&master.to_string()), "xprv in {}", text);
+}
+
+fn assert_operation(span: &SpanRecord) {
+    let op_id: u64 = span.fields["op_id"].parse().unwrap();
+    assert!(op_id > 0);
+    assert!(span.fields["duration_us"].parse::<u64>().is_ok());
+}
+
+#[test]
+fn test_derivation_spans() {
+    let (capture, ()) = capture(|| {
+        let mnemonic = Mnemonic::from_phrase(TEST_MNEMONIC, Language::English).unwrap();
+        let seed = mnemonic.to_seed("").unwrap();
+        let master = ExtendedPrivateKey::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+
+        let path = DerivationPath::from_str("m/44'/0'/0'/0/0").unwrap();
+        master.derive_path(&path).unwrap();
+
+        let account_path = DerivationPath::from_str("m/0/1").unwrap();
+        master
+            .to_extended_public_key()
+            .derive_path(&account_path)
+            .unwrap();
+
+        let mut wallet = khodpay_bip44::Wallet::from_mnemonic(
+            TEST_MNEMONIC,
+            "",
+            Language::English,
+            Network::BitcoinMainnet,
+        )
+        .unwrap();
+        wallet
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 1)
+            .unwrap();
+        // Cached: no second derivation
+        wallet
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 1)
+            .unwrap();
+    });
+
+    let master_keys = capture.named("master_key");
+    assert_eq!(master_keys.len(), 2);
+    assert_eq!(master_keys[0].fields["network"], "Bitcoin Mainnet");
+    assert_operation(&master_keys[0]);
+
+    let derived = capture.named("derive_path");
+    assert_eq!(derived.len(), 1);
+    assert_eq!(derived[0].fields["path"], "m/44'/0'/0'/0/0");
+    assert_operation(&derived[0]);
+
+    let public = capture.named("derive_public_path");
+    assert_eq!(public.len(), 1);
+    assert_eq!(public[0].fields["path"], "m/0/1");
+
+    let accounts = capture.named("derive_account");
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].fields["purpose"], "BIP-84");
+    assert_eq!(accounts[0].fields["coin_type"], "BTC");
+    assert_eq!(accounts[0].fields["account"], "1");
+    assert_operation(&accounts[0]);
+
+    let ids: Vec<String> = capture
+        .spans()
+        .iter()
+        .map(|span| span.fields["op_id"].clone())
+        .collect();
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), ids.len());
+
+    assert_no_secrets(&capture.all_fields());
+}
+
+#[test]
+fn test_discovery_spans() {
+    let external = MockBlockchain::with_used_addresses(&[0, 2]);
+    let internal = MockBlockchain::new();
+    let scanner = AccountScanner::new(GapLimitChecker::new(20));
+
+    let (capture, report) = capture(|| scanner.discover_report(&external, &internal, 2).unwrap());
+
+    let discovery = capture.named("discover_accounts");
+    assert_eq!(discovery.len(), 1);
+    assert_eq!(discovery[0].fields["gap_limit"], "20");
+    assert_eq!(discovery[0].fields["max_accounts"], "2");
+    assert_eq!(
+        discovery[0].fields["accounts_scanned"],
+        report.accounts_scanned.to_string()
+    );
+    assert_eq!(
+        discovery[0].fields["addresses_checked"],
+        report.addresses_checked.to_string()
+    );
+    assert_operation(&discovery[0]);
+
+    let chains = capture.named("scan_chain");
+    assert_eq!(chains.len(), 2 * report.accounts_scanned as usize);
+    assert_eq!(chains[0].fields["chain"], Chain::External.to_string());
+    assert_eq!(chains[0].fields["addresses_checked"], "23");
+    assert_eq!(chains[1].fields["chain"], Chain::Internal.to_string());
+    assert_eq!(chains[1].fields["addresses_checked"], "20");
+}
+
+#[test]
+fn test_evm_signing_spans() {
+    let (capture, ()) = capture(|| {
+        let mut wallet = khodpay_bip44::Wallet::from_mnemonic(
+            TEST_MNEMONIC,
+            "",
+            Language::English,
+            Network::BitcoinMainnet,
+        )
+        .unwrap();
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .unwrap();
+        let signer = Bip44Signer::new(account, 0).unwrap();
+
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(0)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21000)
+            .to(signer.address())
+            .value(Wei::ZERO)
+            .build()
+            .unwrap();
+        signer.sign_transaction(&tx).unwrap();
+        signer.sign_message(b"Sign in to example.com").unwrap();
+        let policy = PrehashPolicy::allow_any("bridge attestation").unwrap();
+        signer.sign_prehashed(&[7; 32], &policy).unwrap();
+
+        let typed_data = TypedData::from_json(
+            r#"{
+                "types": { "Greeting": [{ "name": "text", "type": "string" }] },
+                "primaryType": "Greeting",
+                "domain": { "name": "Example", "chainId": 56 },
+                "message": { "text": "gm" }
+            }"#,
+        )
+        .unwrap();
+        sign_typed_data_json(&signer, &typed_data).unwrap();
+    });
+
+    let transactions = capture.named("sign_transaction");
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].fields["chain_id"], "56");
+    assert_eq!(transactions[0].fields["tx_type"], "eip1559");
+    assert_operation(&transactions[0]);
+
+    let messages = capture.named("sign_message");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].fields["message_len"], "22");
+
+    // A digest is always 32 bytes, so its span has nothing to add
+    let prehashed = capture.named("sign_prehashed");
+    assert_eq!(prehashed.len(), 1);
+    assert!(!prehashed[0].fields.contains_key("message_len"));
+    assert_operation(&prehashed[0]);
+
+    let typed = capture.named("sign_typed_data");
+    assert_eq!(typed.len(), 1);
+    assert_eq!(typed[0].fields["chain_id"], "56");
+
+    assert_no_secrets(&capture.all_fields());
+}
+
+#[test]
+fn test_btc_signing_spans() {
+    let (capture, ()) = capture(|| {
+        let mut wallet = khodpay_bip44::Wallet::from_mnemonic(
+            TEST_MNEMONIC,
+            "",
+            Language::English,
+            Network::BitcoinMainnet,
+        )
+        .unwrap();
+        let account = wallet
+            .get_account(Purpose::BIP84, CoinType::Bitcoin, 0)
+            .unwrap();
+        let receive = BtcSigner::new(account, Chain::External, 0).unwrap();
+        let change = BtcSigner::new(account, Chain::Internal, 0).unwrap();
+
+        let builder = TransactionBuilder::new(khodpay_btc_signing::Network::Bitcoin)
+            .add_utxo(Utxo::new(
+                OutPoint::new(Txid::from_bytes([0x11; 32]), 3),
+                250_000,
+                receive.p2wpkh_address().script_pubkey(),
+            ))
+            .add_output(&receive.p2wpkh_address(), 200_000)
+            .change_address(&change.p2wpkh_address())
+            .fee(2_000);
+        builder.sign(&[&receive]).unwrap();
+
+        let mut psbt = builder.build_psbt().unwrap();
+        assert_eq!(psbt.sign(&change).unwrap(), 0);
+
+        receive.sign_message(b"proof of reserves").unwrap();
+    });
+
+    let transactions = capture.named("sign_transaction");
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].fields["network"], "bitcoin");
+    assert_eq!(transactions[0].fields["inputs"], "1");
+    assert_eq!(transactions[0].fields["signed"], "1");
+    assert_operation(&transactions[0]);
+
+    let psbts = capture.named("sign_psbt");
+    assert_eq!(psbts.len(), 1);
+    assert_eq!(psbts[0].fields["inputs"], "1");
+    assert_eq!(psbts[0].fields["signed"], "0");
+
+    let messages = capture.named("sign_message");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].fields["network"], "bitcoin");
+    assert_eq!(messages[0].fields["message_len"], "17");
+
+    assert_no_secrets(&capture.all_fields());
+}
+
+/// A log sink shared between the subscriber and the test.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for Buffer {
+    type Writer = Buffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn test_redacted_log_output() {
+    let buffer = Buffer::default();
+    let logs = tracing_subscriber::fmt::layer()
+        .with_writer(buffer.clone())
+        .with_span_events(FmtSpan::CLOSE);
+    let subscriber = tracing_subscriber::registry().with(RedactionLayer::new(logs));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let mnemonic = Mnemonic::from_phrase(TEST_MNEMONIC, Language::English).unwrap();
+        let seed = mnemonic.to_seed("").unwrap();
+        let master = ExtendedPrivateKey::from_seed(&seed, Network::BitcoinMainnet).unwrap();
+        let path = DerivationPath::from_str("m/84'/0'/0'").unwrap();
+        master.derive_path(&path).unwrap();
+
+        // Application code logging what it should not
+        tracing::info!(phrase = TEST_MNEMONIC, "restoring wallet");
+        tracing::warn!("restored {} from {}", master, TEST_MNEMONIC);
+        tracing::debug!(?mnemonic, seed = hex::encode(seed), "keys ready");
+    });
+
+    let output = buffer.contents();
+    assert!(output.contains("master_key"));
+    assert!(output.contains("derive_path"));
+    assert!(output.contains("path=m/84'/0'/0'"));
+    assert!(output.contains("duration_us="));
+    assert!(output.contains("restoring wallet"));
+    assert!(output.contains(REDACTED));
+    assert_no_secrets(&output);
+}
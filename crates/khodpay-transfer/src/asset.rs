@@ -0,0 +1,395 @@
+//! Assets and the networks they live on.
+
+use std::fmt;
+
+use khodpay_address::{Address, BitcoinNetwork};
+use khodpay_bip44::CoinType;
+use khodpay_signing::{ChainId, ChainMetadata, U256};
+
+use crate::{CoinAmount, Error, Result};
+
+/// Decimals of BTC: amounts are in satoshis.
+const BTC_DECIMALS: u8 = 8;
+/// Decimals of TRX: amounts are in SUN.
+const TRX_DECIMALS: u8 = 6;
+/// Decimals of XRP: amounts are in drops.
+const XRP_DECIMALS: u8 = 6;
+
+/// A network the wallet can send on.
+///
+/// Unlike [`khodpay_address::Network`], EVM chains are told apart by chain ID,
+/// since a transaction is signed for one of them.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::ChainId;
+/// use khodpay_transfer::Network;
+///
+/// let bsc = Network::Evm(ChainId::BscMainnet);
+/// assert_eq!(bsc.address_network(), khodpay_address::Network::Evm);
+/// assert_eq!(bsc.to_string(), "BSC Mainnet (56)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// A Bitcoin network.
+    Bitcoin(BitcoinNetwork),
+    /// An EVM chain.
+    Evm(ChainId),
+    /// Tron.
+    Tron,
+    /// The XRP Ledger.
+    Xrp,
+}
+
+impl Network {
+    /// Returns the network family whose addresses receive on this network.
+    pub fn address_network(&self) -> khodpay_address::Network {
+        match self {
+            Network::Bitcoin(network) => khodpay_address::Network::Bitcoin(*network),
+            Network::Evm(_) => khodpay_address::Network::Evm,
+            Network::Tron => khodpay_address::Network::Tron,
+            Network::Xrp => khodpay_address::Network::Xrp,
+        }
+    }
+
+    /// Returns the SLIP-44 coin type of accounts that send on this network.
+    pub fn coin_type(&self) -> CoinType {
+        self.address_network().coin_type()
+    }
+
+    /// Returns `true` if the network has tokens: ERC-20 on EVM chains and TRC-20
+    /// on Tron.
+    pub fn has_tokens(&self) -> bool {
+        matches!(self, Network::Evm(_) | Network::Tron)
+    }
+}
+
+impl From<BitcoinNetwork> for Network {
+    fn from(network: BitcoinNetwork) -> Self {
+        Network::Bitcoin(network)
+    }
+}
+
+impl From<ChainId> for Network {
+    fn from(chain_id: ChainId) -> Self {
+        Network::Evm(chain_id)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Evm(chain_id) => chain_id.fmt(f),
+            network => network.address_network().fmt(f),
+        }
+    }
+}
+
+/// What an [`Asset`] is on its network.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    /// The network's own coin: BTC, ETH, BNB, TRX, XRP.
+    Native,
+    /// A token, by contract address: ERC-20 on EVM chains, TRC-20 on Tron.
+    Token(Address),
+}
+
+/// A coin or token the wallet can send, with the symbol and decimals to show it.
+///
+/// Amounts of an asset are [`CoinAmount`]s in its base units (satoshis, wei, SUN,
+/// drops or token units) and decimals; [`Asset::parse_amount`] and
+/// [`Asset::format_amount`] convert them from and to what the user types and reads.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::Address;
+/// use khodpay_signing::{ChainId, U256};
+/// use khodpay_transfer::{Asset, Network};
+///
+/// let bnb = Asset::native(Network::Evm(ChainId::BscMainnet))?;
+/// assert_eq!(bnb.symbol(), "BNB");
+/// assert_eq!(bnb.decimals(), 18);
+///
+/// let usdt = Asset::token(
+///     Network::Evm(ChainId::BscMainnet),
+///     "0x55d398326f99059fF775485246999027B3197955".parse::<Address>()?,
+///     "USDT",
+///     18,
+/// )?;
+/// let amount = usdt.parse_amount("12.5 USDT")?;
+/// assert_eq!(amount.value(), U256::from(12_500_000_000_000_000_000u128));
+/// assert_eq!(usdt.format_amount(amount), "12.5 USDT");
+/// # Ok::<(), khodpay_transfer::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Asset {
+    network: Network,
+    kind: AssetKind,
+    symbol: String,
+    decimals: u8,
+}
+
+impl Asset {
+    /// Returns the native coin of `network`.
+    ///
+    /// Bitcoin test networks use the symbol `tBTC`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] for a custom EVM chain, whose currency is
+    /// unknown; use [`Asset::evm_native`] with its metadata instead.
+    pub fn native(network: Network) -> Result<Self> {
+        let (symbol, decimals) = match network {
+            Network::Bitcoin(BitcoinNetwork::Bitcoin) => ("BTC".to_string(), BTC_DECIMALS),
+            Network::Bitcoin(_) => ("tBTC".to_string(), BTC_DECIMALS),
+            Network::Evm(chain_id) => {
+                let metadata = chain_id.metadata().ok_or_else(|| {
+                    Error::Unsupported(format!("native currency of {} is unknown", chain_id))
+                })?;
+                (metadata.native_symbol, metadata.decimals)
+            }
+            Network::Tron => ("TRX".to_string(), TRX_DECIMALS),
+            Network::Xrp => ("XRP".to_string(), XRP_DECIMALS),
+        };
+        Ok(Self {
+            network,
+            kind: AssetKind::Native,
+            symbol,
+            decimals,
+        })
+    }
+
+    /// Returns the native coin of the EVM chain `metadata` describes, including
+    /// custom chains registered in a [`ChainRegistry`](khodpay_signing::ChainRegistry).
+    pub fn evm_native(metadata: &ChainMetadata) -> Self {
+        Self {
+            network: Network::Evm(metadata.chain_id),
+            kind: AssetKind::Native,
+            symbol: metadata.native_symbol.clone(),
+            decimals: metadata.decimals,
+        }
+    }
+
+    /// Returns the token at `contract` on `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Unsupported`] if `network` has no tokens, and
+    /// [`Error::AddressError`] if `contract` is not an address on `network`.
+    pub fn token(
+        network: Network,
+        contract: Address,
+        symbol: impl Into<String>,
+        decimals: u8,
+    ) -> Result<Self> {
+        if !network.has_tokens() {
+            return Err(Error::Unsupported(format!("{} has no tokens", network)));
+        }
+        if !contract.is_valid_for(network.address_network()) {
+            return Err(khodpay_address::Error::WrongNetwork {
+                address: contract.to_string(),
+                network: network.address_network(),
+            }
+            .into());
+        }
+        Ok(Self {
+            network,
+            kind: AssetKind::Token(contract),
+            symbol: symbol.into(),
+            decimals,
+        })
+    }
+
+    /// Returns the network the asset lives on.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns whether the asset is the native coin or a token.
+    pub fn kind(&self) -> &AssetKind {
+        &self.kind
+    }
+
+    /// Returns the token contract, or `None` for the native coin.
+    pub fn contract(&self) -> Option<&Address> {
+        match &self.kind {
+            AssetKind::Native => None,
+            AssetKind::Token(contract) => Some(contract),
+        }
+    }
+
+    /// Returns `true` for the network's own coin.
+    pub fn is_native(&self) -> bool {
+        self.kind == AssetKind::Native
+    }
+
+    /// Returns the ticker symbol, such as `BTC` or `USDT`.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Returns the number of decimals between a whole unit and a base unit.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Returns `value` base units of the asset as an amount.
+    pub fn amount(&self, value: impl Into<U256>) -> CoinAmount {
+        CoinAmount::new(value, self.decimals)
+    }
+
+    /// Parses a decimal amount such as `12.5`, optionally followed by the
+    /// asset's symbol as in `12.5 USDT`, into an amount of the asset.
+    ///
+    /// The symbol is matched case-insensitively.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if `s` is not a decimal number, has more
+    /// decimal places than the asset or names another symbol.
+    pub fn parse_amount(&self, s: &str) -> Result<CoinAmount> {
+        let s = s.trim();
+        let number = match s.split_once(char::is_whitespace) {
+            Some((number, symbol)) => {
+                let symbol = symbol.trim_start();
+                if !symbol.eq_ignore_ascii_case(&self.symbol) {
+                    return Err(Error::InvalidAmount(format!(
+                        "{:?} is not an amount of {}",
+                        s, self.symbol
+                    )));
+                }
+                number
+            }
+            None => s,
+        };
+        Ok(CoinAmount::parse(number, self.decimals)?)
+    }
+
+    /// Formats an amount as a decimal number followed by the symbol, such as
+    /// `12.5 USDT`.
+    pub fn format_amount(&self, amount: CoinAmount) -> String {
+        format!("{} {}", amount, self.symbol)
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            AssetKind::Native => write!(f, "{} on {}", self.symbol, self.network),
+            AssetKind::Token(contract) => {
+                write!(f, "{} ({}) on {}", self.symbol, contract, self.network)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDT_TRON: &str = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+
+    #[test]
+    fn test_native() {
+        for (network, symbol, decimals) in [
+            (Network::Bitcoin(BitcoinNetwork::Bitcoin), "BTC", 8),
+            (Network::Bitcoin(BitcoinNetwork::Signet), "tBTC", 8),
+            (Network::Evm(ChainId::EthereumMainnet), "ETH", 18),
+            (Network::Evm(ChainId::BscMainnet), "BNB", 18),
+            (Network::Tron, "TRX", 6),
+            (Network::Xrp, "XRP", 6),
+        ] {
+            let asset = Asset::native(network).unwrap();
+            assert!(asset.is_native());
+            assert_eq!(asset.contract(), None);
+            assert_eq!((asset.symbol(), asset.decimals()), (symbol, decimals));
+        }
+
+        let fantom = ChainId::Custom(250);
+        assert!(matches!(
+            Asset::native(Network::Evm(fantom)),
+            Err(Error::Unsupported(_))
+        ));
+        let ftm = Asset::evm_native(&ChainMetadata::new(fantom, "Fantom Opera", "FTM", 18));
+        assert_eq!(ftm.network(), Network::Evm(fantom));
+        assert_eq!(ftm.to_string(), "FTM on Chain 250");
+    }
+
+    #[test]
+    fn test_token() {
+        let contract: Address = USDT_TRON.parse().unwrap();
+        let usdt = Asset::token(Network::Tron, contract.clone(), "USDT", 6).unwrap();
+        assert!(!usdt.is_native());
+        assert_eq!(usdt.contract(), Some(&contract));
+        assert_eq!(usdt.to_string(), format!("USDT ({}) on Tron", USDT_TRON));
+
+        // A Tron contract on an EVM chain, and tokens on Bitcoin
+        assert!(matches!(
+            Asset::token(
+                Network::Evm(ChainId::BscMainnet),
+                contract.clone(),
+                "USDT",
+                6
+            ),
+            Err(Error::AddressError(_))
+        ));
+        assert!(matches!(
+            Asset::token(
+                Network::Bitcoin(BitcoinNetwork::Bitcoin),
+                contract,
+                "USDT",
+                6
+            ),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_amounts() {
+        let btc = Asset::native(Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap();
+        let amount = CoinAmount::from_sats(1_500_000);
+        assert_eq!(btc.parse_amount(" 0.015 ").unwrap(), amount);
+        assert_eq!(btc.parse_amount("0.015 BTC").unwrap(), amount);
+        assert_eq!(btc.parse_amount("0.015  btc ").unwrap(), amount);
+        assert_eq!(btc.amount(1_500_000), amount);
+        assert_eq!(btc.format_amount(amount), "0.015 BTC");
+        for s in [
+            "",
+            "abc",
+            "1.5.0",
+            "-1",
+            "0.000000001",
+            "1 ETH",
+            "1 BTC BTC",
+            "BTC",
+        ] {
+            assert!(
+                matches!(btc.parse_amount(s), Err(Error::InvalidAmount(_))),
+                "{:?}",
+                s
+            );
+        }
+
+        let xrp = Asset::native(Network::Xrp).unwrap();
+        assert_eq!(
+            xrp.parse_amount("25").unwrap().value(),
+            U256::from(25_000_000)
+        );
+    }
+
+    #[test]
+    fn test_network() {
+        let network = Network::from(BitcoinNetwork::Testnet);
+        assert_eq!(network.coin_type(), CoinType::BitcoinTestnet);
+        assert_eq!(network.to_string(), "Bitcoin testnet");
+        assert_eq!(
+            Network::Evm(ChainId::BscMainnet).coin_type(),
+            CoinType::Ethereum
+        );
+        assert_eq!(Network::Xrp.to_string(), "XRP Ledger");
+        assert!(Network::Tron.has_tokens());
+        assert!(!Network::Xrp.has_tokens());
+    }
+}
@@ -0,0 +1,576 @@
+//! Batch transfers: one asset paid to many recipients in one transaction.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use khodpay_address::Address;
+use khodpay_btc_signing::{
+    dust_threshold, FeeRate, TransactionBuilder as BtcTransactionBuilder, MAX_MONEY,
+};
+use khodpay_signing::disperse::{self, DISPERSE};
+use khodpay_signing::{
+    erc20, Address as EvmAddress, Eip1559Transaction, Eip1559TransactionBuilder, Wei,
+    TOKEN_TRANSFER_GAS, U256,
+};
+
+use crate::{
+    Asset, AssetKind, CoinAmount, Error, Network, Result, TransferBuilder, TransferRequest,
+};
+
+/// Payouts of one [`Asset`] to many recipients, for payroll and airdrops.
+///
+/// Recipients are added one at a time, with the same checks as a
+/// [`TransferRequest`]. [`BatchTransfer::resolve`] then gives a single
+/// transaction builder for the whole batch:
+///
+/// - Bitcoin: one transaction with an output per recipient.
+/// - EVM chains: one call to the [Disperse](khodpay_signing::disperse) contract,
+///   which for tokens needs the [`approval`](Self::approval) first.
+///
+/// Payouts to the same address are merged into one output or one Disperse
+/// entry, so the batch pays for each recipient once.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::ChainId;
+/// use khodpay_transfer::{Asset, BatchBuilder, BatchTransfer, Network};
+///
+/// let bnb = Asset::native(Network::Evm(ChainId::BscMainnet))?;
+/// let mut batch = BatchTransfer::new(bnb);
+/// batch.add_parsed("0x9858EfFD232B4033E47d90003D41EC34EcaEda94", "0.25")?;
+/// batch.add_parsed("0x742d35Cc6634C0532925a3b844Bc454e4438f44e", "1.5")?;
+/// assert_eq!(batch.to_string(), "1.75 BNB to 2 recipients on BSC Mainnet (56)");
+///
+/// match batch.resolve()? {
+///     BatchBuilder::Evm(builder) => {
+///         let tx = builder
+///             .nonce(0)
+///             .max_priority_fee_per_gas(khodpay_signing::Wei::from_gwei(1))
+///             .max_fee_per_gas(khodpay_signing::Wei::from_gwei(3))
+///             .build()
+///             .unwrap();
+///         assert_eq!(tx.to, Some(khodpay_signing::disperse::DISPERSE));
+///     }
+///     BatchBuilder::Bitcoin(_) => unreachable!("BNB is on an EVM chain"),
+/// }
+/// # Ok::<(), khodpay_transfer::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchTransfer {
+    asset: Asset,
+    payouts: Vec<(Address, CoinAmount)>,
+    disperse: EvmAddress,
+}
+
+impl BatchTransfer {
+    /// Creates an empty batch of `asset`, paid on EVM chains through [`DISPERSE`].
+    pub fn new(asset: Asset) -> Self {
+        Self {
+            asset,
+            payouts: Vec::new(),
+            disperse: DISPERSE,
+        }
+    }
+
+    /// Pays through the Disperse contract at `contract`, for chains where it is
+    /// not at the usual address.
+    pub fn with_disperse_contract(mut self, contract: EvmAddress) -> Self {
+        self.disperse = contract;
+        self
+    }
+
+    /// Adds a payout of `amount` to `to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`TransferRequest::new`]: a zero amount, an amount
+    /// in other decimals than the asset, or an address of another network.
+    pub fn add(&mut self, to: Address, amount: CoinAmount) -> Result<()> {
+        let request = TransferRequest::new(self.asset.clone(), to, amount)?;
+        self.payouts.push((request.to().clone(), request.amount()));
+        Ok(())
+    }
+
+    /// Adds a payout from what was typed or imported, such as a row of a payroll
+    /// CSV: an address and a decimal amount.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`TransferRequest::parse`].
+    pub fn add_parsed(&mut self, to: &str, amount: &str) -> Result<()> {
+        let request = TransferRequest::parse(self.asset.clone(), to, amount)?;
+        self.payouts.push((request.to().clone(), request.amount()));
+        Ok(())
+    }
+
+    /// Returns the asset paid.
+    pub fn asset(&self) -> &Asset {
+        &self.asset
+    }
+
+    /// Returns the payouts in the order they were added, duplicates included.
+    pub fn payouts(&self) -> &[(Address, CoinAmount)] {
+        &self.payouts
+    }
+
+    /// Returns one payout per recipient, in the order each was first added, with
+    /// the amounts of duplicate recipients summed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if a recipient's sum overflows.
+    pub fn recipients(&self) -> Result<Vec<(Address, CoinAmount)>> {
+        let mut merged: Vec<(Address, CoinAmount)> = Vec::with_capacity(self.payouts.len());
+        let mut index: HashMap<Address, usize> = HashMap::with_capacity(self.payouts.len());
+        for (to, amount) in &self.payouts {
+            match index.get(to) {
+                Some(&i) => {
+                    merged[i].1 = merged[i]
+                        .1
+                        .checked_add(*amount)
+                        .ok_or_else(|| self.overflow())?;
+                }
+                None => {
+                    index.insert(to.clone(), merged.len());
+                    merged.push((to.clone(), *amount));
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Returns the number of payouts added.
+    pub fn len(&self) -> usize {
+        self.payouts.len()
+    }
+
+    /// Returns `true` if no payout was added.
+    pub fn is_empty(&self) -> bool {
+        self.payouts.is_empty()
+    }
+
+    /// Returns the sum of all payouts.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if the sum overflows.
+    pub fn total(&self) -> Result<CoinAmount> {
+        self.payouts
+            .iter()
+            .try_fold(
+                CoinAmount::zero(self.asset.decimals()),
+                |total, (_, amount)| total.checked_add(*amount),
+            )
+            .ok_or_else(|| self.overflow())
+    }
+
+    /// Splits the batch into batches of at most `size` payouts, in order, for
+    /// airdrops too large for one block or one transaction.
+    ///
+    /// A `size` of zero is treated as one.
+    pub fn chunks(&self, size: usize) -> Vec<BatchTransfer> {
+        self.payouts
+            .chunks(size.max(1))
+            .map(|payouts| BatchTransfer {
+                payouts: payouts.to_vec(),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// Returns the ERC-20 `approve` call that lets the Disperse contract pull
+    /// the batch total, or `None` if no approval is needed: for native coins,
+    /// Bitcoin and batches with a single recipient.
+    ///
+    /// Sign it with the nonce before the batch's.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if the batch is empty or its total
+    /// overflows.
+    pub fn approval(&self) -> Result<Option<Eip1559TransactionBuilder>> {
+        let (Network::Evm(chain_id), AssetKind::Token(contract)) =
+            (self.asset.network(), self.asset.kind())
+        else {
+            return Ok(None);
+        };
+        if self.recipients_checked()?.len() == 1 {
+            return Ok(None);
+        }
+        let contract = *contract.as_evm().ok_or_else(|| self.wrong_contract())?;
+        Ok(Some(
+            Eip1559Transaction::builder()
+                .chain_id(chain_id)
+                .to(contract)
+                .data(erc20::encode_approve(&self.disperse, self.total()?.value()))
+                .gas_limit(TOKEN_TRANSFER_GAS),
+        ))
+    }
+
+    /// Returns the builder of one transaction paying every recipient.
+    ///
+    /// - Bitcoin: a [`TransactionBuilder`](BtcTransactionBuilder) with one output
+    ///   per recipient.
+    /// - EVM chains: an [`Eip1559TransactionBuilder`] calling `disperseEther` with
+    ///   the total as value, or `disperseToken` after the
+    ///   [`approval`](Self::approval), with a [`disperse::gas_limit`]. A batch with
+    ///   a single recipient is a plain transfer instead, as
+    ///   [`TransferRequest::resolve`] builds it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if the batch is empty, an amount or the
+    /// total overflows, or a Bitcoin output is below the dust limit, and
+    /// [`Error::Unsupported`] on Tron and the XRP Ledger, which have neither
+    /// multi-output transactions nor a Disperse contract.
+    pub fn resolve(&self) -> Result<BatchBuilder> {
+        if matches!(self.asset.network(), Network::Tron | Network::Xrp) {
+            return Err(self.unsupported());
+        }
+        let recipients = self.recipients_checked()?;
+        if let [(to, amount)] = recipients.as_slice() {
+            return match TransferRequest::new(self.asset.clone(), to.clone(), *amount)?.resolve()? {
+                TransferBuilder::Bitcoin(builder) => Ok(BatchBuilder::Bitcoin(builder)),
+                TransferBuilder::Evm(builder) => Ok(BatchBuilder::Evm(builder)),
+                TransferBuilder::Tron(_) | TransferBuilder::Xrp(_) => Err(self.unsupported()),
+            };
+        }
+
+        match self.asset.network() {
+            Network::Bitcoin(network) => {
+                let total = self.total()?;
+                if total.as_u64().map_or(true, |total| total > MAX_MONEY) {
+                    return Err(self.too_large(total));
+                }
+                let mut builder = BtcTransactionBuilder::new(network);
+                for (to, amount) in &recipients {
+                    let address = to.as_bitcoin().ok_or_else(|| self.wrong_address(to))?;
+                    // No larger than the total, which fits
+                    let sats = amount.as_u64().ok_or_else(|| self.too_large(*amount))?;
+                    let dust = dust_threshold(&address.script_pubkey(), FeeRate::DUST_RELAY);
+                    if sats < dust {
+                        return Err(Error::InvalidAmount(format!(
+                            "{} to {} is below the dust limit of {} sats",
+                            self.asset.format_amount(*amount),
+                            to,
+                            dust
+                        )));
+                    }
+                    builder = builder.add_output(address, sats);
+                }
+                Ok(BatchBuilder::Bitcoin(builder))
+            }
+            Network::Evm(chain_id) => {
+                let payouts = recipients
+                    .iter()
+                    .map(|(to, amount)| {
+                        let to = *to.as_evm().ok_or_else(|| self.wrong_address(to))?;
+                        Ok((to, amount.value()))
+                    })
+                    .collect::<Result<Vec<(EvmAddress, U256)>>>()?;
+                let total = self.total()?.value();
+                let builder = Eip1559Transaction::builder()
+                    .chain_id(chain_id)
+                    .to(self.disperse)
+                    .gas_limit(disperse::gas_limit(payouts.len()));
+                Ok(BatchBuilder::Evm(match self.asset.kind() {
+                    AssetKind::Native => builder
+                        .value(Wei::from_u256(total))
+                        .data(disperse::encode_disperse_ether(&payouts)),
+                    AssetKind::Token(contract) => {
+                        let contract = contract.as_evm().ok_or_else(|| self.wrong_contract())?;
+                        builder.data(disperse::encode_disperse_token(contract, &payouts))
+                    }
+                }))
+            }
+            Network::Tron | Network::Xrp => Err(self.unsupported()),
+        }
+    }
+
+    /// Returns [`recipients`](Self::recipients), refusing an empty batch.
+    fn recipients_checked(&self) -> Result<Vec<(Address, CoinAmount)>> {
+        if self.payouts.is_empty() {
+            return Err(Error::InvalidAmount(format!(
+                "the batch of {} has no payouts",
+                self.asset.symbol()
+            )));
+        }
+        self.recipients()
+    }
+
+    fn overflow(&self) -> Error {
+        Error::InvalidAmount(format!("the batch total overflows {}", self.asset.symbol()))
+    }
+
+    fn too_large(&self, amount: CoinAmount) -> Error {
+        Error::InvalidAmount(format!(
+            "{} is more than {} can send",
+            self.asset.format_amount(amount),
+            self.asset.network()
+        ))
+    }
+
+    fn unsupported(&self) -> Error {
+        Error::Unsupported(format!("{} has no batch transfers", self.asset.network()))
+    }
+
+    // Addresses were checked against the network when they were added
+    fn wrong_address(&self, to: &Address) -> Error {
+        khodpay_address::Error::WrongNetwork {
+            address: to.to_string(),
+            network: self.asset.network().address_network(),
+        }
+        .into()
+    }
+
+    fn wrong_contract(&self) -> Error {
+        match self.asset.contract() {
+            Some(contract) => self.wrong_address(contract),
+            None => self.unsupported(),
+        }
+    }
+}
+
+impl fmt::Display for BatchTransfer {
+    /// Describes the batch for a confirmation screen, such as
+    /// `1,250 USDT to 40 recipients on BSC Mainnet (56)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let recipients = self
+            .recipients()
+            .map_or(self.payouts.len(), |recipients| recipients.len());
+        match self.total() {
+            Ok(total) => write!(f, "{}", self.asset.format_amount(total))?,
+            Err(_) => write!(f, "? {}", self.asset.symbol())?,
+        }
+        write!(
+            f,
+            " to {} recipient{} on {}",
+            recipients,
+            if recipients == 1 { "" } else { "s" },
+            self.asset.network()
+        )
+    }
+}
+
+/// The transaction builder a [`BatchTransfer`] resolves to, with every
+/// recipient paid.
+#[derive(Debug, Clone)]
+pub enum BatchBuilder {
+    /// Bitcoin: add UTXOs, a change address and a fee or fee rate, then sign.
+    Bitcoin(BtcTransactionBuilder),
+    /// EVM chains: set the nonce and fees; for tokens, sign the
+    /// [`approval`](BatchTransfer::approval) first.
+    Evm(Eip1559TransactionBuilder),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_address::BitcoinNetwork;
+    use khodpay_btc_signing::{BtcSigner, OutPoint, Utxo};
+    use khodpay_signing::ChainId;
+
+    const ALICE: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+    const BOB: &str = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e";
+    const USDT_BSC: &str = "0x55d398326f99059fF775485246999027B3197955";
+    const BTC_ALICE: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    const BTC_BOB: &str = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+
+    fn bsc() -> Network {
+        Network::Evm(ChainId::BscMainnet)
+    }
+
+    fn usdt() -> Asset {
+        Asset::token(bsc(), USDT_BSC.parse().unwrap(), "USDT", 18).unwrap()
+    }
+
+    fn build_evm(builder: Eip1559TransactionBuilder) -> Eip1559Transaction {
+        builder
+            .nonce(7)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(3))
+            .build()
+            .unwrap()
+    }
+
+    fn resolve_evm(batch: &BatchTransfer) -> Eip1559Transaction {
+        let BatchBuilder::Evm(builder) = batch.resolve().unwrap() else {
+            panic!("expected an EVM builder");
+        };
+        build_evm(builder)
+    }
+
+    fn evm(address: &str) -> EvmAddress {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn test_merges_duplicate_recipients() {
+        let mut batch = BatchTransfer::new(usdt());
+        batch.add_parsed(ALICE, "1").unwrap();
+        batch.add_parsed(BOB, "2.5").unwrap();
+        batch.add_parsed(&ALICE.to_lowercase(), "0.5").unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.total().unwrap(), usdt().parse_amount("4").unwrap());
+
+        let recipients = batch.recipients().unwrap();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0].0.to_string(), ALICE);
+        assert_eq!(recipients[0].1, usdt().parse_amount("1.5").unwrap());
+        assert_eq!(
+            batch.to_string(),
+            "4 USDT to 2 recipients on BSC Mainnet (56)"
+        );
+    }
+
+    #[test]
+    fn test_evm_native() {
+        let bnb = Asset::native(bsc()).unwrap();
+        let mut batch = BatchTransfer::new(bnb);
+        batch.add_parsed(ALICE, "0.25").unwrap();
+        batch.add_parsed(BOB, "1.5").unwrap();
+        assert!(batch.approval().unwrap().is_none());
+
+        let tx = resolve_evm(&batch);
+        assert_eq!(tx.chain_id, ChainId::BscMainnet);
+        assert_eq!(tx.to, Some(DISPERSE));
+        assert_eq!(tx.value, Wei::from_wei(1_750_000_000_000_000_000u128));
+        assert_eq!(tx.gas_limit, disperse::gas_limit(2));
+        assert_eq!(
+            tx.data,
+            disperse::encode_disperse_ether(&[
+                (evm(ALICE), U256::from(250_000_000_000_000_000u128)),
+                (evm(BOB), U256::from(1_500_000_000_000_000_000u128)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_evm_token_needs_approval() {
+        let disperse = evm("0x1111111111111111111111111111111111111111");
+        let mut batch = BatchTransfer::new(usdt()).with_disperse_contract(disperse);
+        batch.add_parsed(ALICE, "1").unwrap();
+        batch.add_parsed(BOB, "2").unwrap();
+
+        let approval = build_evm(batch.approval().unwrap().unwrap());
+        assert_eq!(approval.to, Some(evm(USDT_BSC)));
+        assert_eq!(
+            approval.data,
+            erc20::encode_approve(&disperse, U256::from(3_000_000_000_000_000_000u128))
+        );
+
+        let tx = resolve_evm(&batch);
+        assert_eq!(tx.to, Some(disperse));
+        assert!(tx.value.is_zero());
+        assert_eq!(tx.data[..4], disperse::DISPERSE_TOKEN_SELECTOR);
+    }
+
+    #[test]
+    fn test_single_recipient_is_a_plain_transfer() {
+        let mut batch = BatchTransfer::new(usdt());
+        batch.add_parsed(ALICE, "1").unwrap();
+        batch.add_parsed(ALICE, "2").unwrap();
+        assert!(batch.approval().unwrap().is_none());
+
+        let tx = resolve_evm(&batch);
+        assert_eq!(tx.to, Some(evm(USDT_BSC)));
+        assert_eq!(tx.gas_limit, TOKEN_TRANSFER_GAS);
+        assert_eq!(
+            tx.data,
+            erc20::encode_transfer(&evm(ALICE), U256::from(3_000_000_000_000_000_000u128))
+        );
+    }
+
+    #[test]
+    fn test_bitcoin() {
+        let signer = BtcSigner::from_private_key(&[1; 32], BitcoinNetwork::Bitcoin).unwrap();
+        let btc = Asset::native(Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap();
+        let mut batch = BatchTransfer::new(btc);
+        batch.add_parsed(BTC_ALICE, "0.0003").unwrap();
+        batch.add_parsed(BTC_BOB, "0.0001").unwrap();
+        batch.add_parsed(BTC_ALICE, "0.0002").unwrap();
+
+        let BatchBuilder::Bitcoin(builder) = batch.resolve().unwrap() else {
+            panic!("expected a Bitcoin builder");
+        };
+        let utxo = Utxo::new(
+            OutPoint::new(
+                "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            100_000,
+            signer.p2wpkh_address().script_pubkey(),
+        );
+        let tx = builder
+            .add_utxo(utxo)
+            .change_address(&signer.p2wpkh_address())
+            .fee(1_000)
+            .sign(&[&signer])
+            .unwrap();
+
+        // One output per recipient, plus change
+        assert_eq!(tx.outputs.len(), 3);
+        let paid = |address: &str| {
+            let script = address.parse::<Address>().unwrap();
+            let script = script.as_bitcoin().unwrap().script_pubkey();
+            tx.outputs
+                .iter()
+                .find(|output| output.script_pubkey == script)
+                .map(|output| output.value)
+        };
+        assert_eq!(paid(BTC_ALICE), Some(50_000));
+        assert_eq!(paid(BTC_BOB), Some(10_000));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let bnb = Asset::native(bsc()).unwrap();
+        let mut batch = BatchTransfer::new(bnb);
+        for amount in ["1", "2", "3", "4", "5"] {
+            batch.add_parsed(ALICE, amount).unwrap();
+        }
+        let chunks = batch.chunks(2);
+        assert_eq!(
+            chunks.iter().map(BatchTransfer::len).collect::<Vec<_>>(),
+            [2, 2, 1]
+        );
+        assert_eq!(chunks[2].payouts(), &batch.payouts()[4..]);
+        assert_eq!(batch.chunks(0).len(), 5);
+    }
+
+    #[test]
+    fn test_rejects() {
+        // Empty batches, and payouts the network cannot take
+        let bnb = Asset::native(bsc()).unwrap();
+        let mut batch = BatchTransfer::new(bnb);
+        assert!(matches!(batch.resolve(), Err(Error::InvalidAmount(_))));
+        assert!(batch.add_parsed(BTC_ALICE, "1").is_err());
+        assert!(batch.add_parsed(ALICE, "0").is_err());
+        assert!(batch.is_empty());
+
+        // Bitcoin outputs below the dust limit
+        let btc = Asset::native(Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap();
+        let mut batch = BatchTransfer::new(btc);
+        batch.add_parsed(BTC_ALICE, "0.001").unwrap();
+        batch.add_parsed(BTC_BOB, "0.000005").unwrap();
+        let error = batch.resolve().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("below the dust limit of 546 sats"));
+
+        // No batch transfers on Tron or the XRP Ledger
+        let xrp = Asset::native(Network::Xrp).unwrap();
+        let mut batch = BatchTransfer::new(xrp);
+        batch
+            .add_parsed("rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe", "1")
+            .unwrap();
+        batch
+            .add_parsed("rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh", "1")
+            .unwrap();
+        assert!(matches!(batch.resolve(), Err(Error::Unsupported(_))));
+        assert!(batch.approval().unwrap().is_none());
+    }
+}
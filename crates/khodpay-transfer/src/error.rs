@@ -0,0 +1,52 @@
+//! Error types for the transfer crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while describing or resolving a transfer.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The amount is malformed, zero, or too large for the network.
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    /// The network cannot do what was asked, such as hold tokens or take a
+    /// destination tag.
+    #[error("Unsupported transfer: {0}")]
+    Unsupported(String),
+
+    /// Error from parsing or checking an address.
+    #[error("Address error: {0}")]
+    AddressError(#[from] khodpay_address::Error),
+}
+
+impl From<khodpay_amount::Error> for Error {
+    fn from(error: khodpay_amount::Error) -> Self {
+        match error {
+            khodpay_amount::Error::InvalidAmount(reason) => Error::InvalidAmount(reason),
+        }
+    }
+}
+
+/// Result type alias for transfer operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::InvalidAmount("\"1.5.0\" is not a number".to_string()).to_string(),
+            "Invalid amount: \"1.5.0\" is not a number"
+        );
+        assert_eq!(
+            Error::Unsupported("Bitcoin has no tokens".to_string()).to_string(),
+            "Unsupported transfer: Bitcoin has no tokens"
+        );
+        let error: Error = khodpay_address::Error::InvalidAddress("0x12".to_string()).into();
+        assert_eq!(error.to_string(), "Address error: Invalid address: 0x12");
+        let error: Error = khodpay_amount::Error::InvalidAmount("\"1,5\"".to_string()).into();
+        assert_eq!(error.to_string(), "Invalid amount: \"1,5\"");
+    }
+}
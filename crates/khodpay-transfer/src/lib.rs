@@ -0,0 +1,80 @@
+//! # Khodpay Transfer
+//!
+//! A chain-agnostic send flow. An [`Asset`] is a native coin or a token with its
+//! decimals; a [`TransferRequest`] sends an amount of one to an address, and
+//! resolves into the transaction builder of the asset's network: a Bitcoin
+//! transaction, an EVM transaction or ERC-20 call, a Tron TRX or TRC-20
+//! transfer, or an XRP Ledger payment.
+//!
+//! The send screen only deals in assets, addresses and decimal amounts; the
+//! network-specific work of nonces, fees and UTXOs starts from the
+//! [`TransferBuilder`].
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`Network`] | A network the wallet sends on, with EVM chains told apart |
+//! | [`Asset`] | A native coin or token, its symbol and decimals |
+//! | [`CoinAmount`] | An amount in base units with its coin's decimals, from `khodpay-amount` |
+//! | [`TransferRequest`] | An amount of an asset to send to an address |
+//! | [`TransferBuilder`] | The per-network builder a request resolves to |
+//! | [`BatchTransfer`] | Payouts of one asset to many recipients in one transaction |
+//! | [`BatchBuilder`] | The Bitcoin or EVM builder a batch resolves to |
+//!
+//! ## Features
+//!
+//! - **Decimal Amounts**: Parse `12.5 USDT` and format amounts in each asset's
+//!   decimals, with checked arithmetic that never mixes decimals
+//! - **Checked Recipients**: Addresses must be valid on the asset's network
+//! - **Tokens**: ERC-20 on any EVM chain and TRC-20 on Tron
+//! - **Destination Tags**: For XRP Ledger exchanges and shared addresses
+//! - **Batch Transfers**: Payroll and airdrops in one multi-output Bitcoin
+//!   transaction or one Disperse contract call on EVM chains
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_address::Address;
+//! use khodpay_signing::{ChainId, Wei};
+//! use khodpay_transfer::{Asset, Network, TransferBuilder, TransferRequest};
+//!
+//! let bsc = Network::Evm(ChainId::BscMainnet);
+//! let contract: Address = "0x55d398326f99059ff775485246999027b3197955".parse()?;
+//! let usdt = Asset::token(bsc, contract, "USDT", 18)?;
+//!
+//! let request =
+//!     TransferRequest::parse(usdt, "0x9858EfFD232B4033E47d90003D41EC34EcaEda94", "12.5")?;
+//! println!("{}", request); // 12.5 USDT to 0x9858… on BSC Mainnet (56)
+//!
+//! match request.resolve()? {
+//!     TransferBuilder::Evm(builder) => {
+//!         let tx = builder
+//!             .nonce(0)
+//!             .max_priority_fee_per_gas(Wei::from_gwei(1))
+//!             .max_fee_per_gas(Wei::from_gwei(3))
+//!             .build()
+//!             .unwrap();
+//!         assert!(tx.value.is_zero());
+//!     }
+//!     TransferBuilder::Bitcoin(_) | TransferBuilder::Tron(_) | TransferBuilder::Xrp(_) => {
+//!         unreachable!("USDT on BSC is an ERC-20 token")
+//!     }
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod asset;
+mod batch;
+mod error;
+mod transfer;
+
+pub use asset::{Asset, AssetKind, Network};
+pub use batch::{BatchBuilder, BatchTransfer};
+pub use error::{Error, Result};
+pub use khodpay_amount::CoinAmount;
+pub use transfer::{TransferBuilder, TransferRequest};
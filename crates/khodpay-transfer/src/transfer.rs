@@ -0,0 +1,478 @@
+//! Transfer requests and the per-network builders they resolve to.
+
+use std::fmt;
+
+use khodpay_address::Address;
+use khodpay_btc_signing::{TransactionBuilder as BtcTransactionBuilder, MAX_MONEY};
+use khodpay_signing::{
+    erc20, Eip1559Transaction, Eip1559TransactionBuilder, Wei, TOKEN_TRANSFER_GAS, TRANSFER_GAS,
+};
+use khodpay_tron_signing::{
+    Transaction as TronTransaction, TransactionBuilder as TronTransactionBuilder,
+};
+use khodpay_xrp_signing::{Payment, PaymentBuilder};
+
+use crate::{Asset, AssetKind, CoinAmount, Error, Network, Result};
+
+/// A payment of an amount of an [`Asset`] to an address, on any network.
+///
+/// The send screen fills one in from the chosen asset and what the user typed;
+/// [`TransferRequest::resolve`] then gives the transaction builder of the asset's
+/// network with the recipient and amount set, leaving nonces, fees, inputs and
+/// other account state to the caller.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_signing::ChainId;
+/// use khodpay_transfer::{Asset, Network, TransferBuilder, TransferRequest};
+///
+/// let bnb = Asset::native(Network::Evm(ChainId::BscMainnet))?;
+/// let request =
+///     TransferRequest::parse(bnb, "0x9858EfFD232B4033E47d90003D41EC34EcaEda94", "0.25")?;
+/// assert_eq!(
+///     request.to_string(),
+///     "0.25 BNB to 0x9858EfFD232B4033E47d90003D41EC34EcaEda94 on BSC Mainnet (56)"
+/// );
+///
+/// match request.resolve()? {
+///     TransferBuilder::Evm(builder) => {
+///         let tx = builder
+///             .nonce(0)
+///             .max_priority_fee_per_gas(khodpay_signing::Wei::from_gwei(1))
+///             .max_fee_per_gas(khodpay_signing::Wei::from_gwei(3))
+///             .build()
+///             .unwrap();
+///         assert_eq!(tx.gas_limit, 21_000);
+///     }
+///     _ => unreachable!("BNB is on an EVM chain"),
+/// }
+/// # Ok::<(), khodpay_transfer::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferRequest {
+    asset: Asset,
+    to: Address,
+    amount: CoinAmount,
+    destination_tag: Option<u32>,
+}
+
+impl TransferRequest {
+    /// Creates a request to send `amount` of `asset` to `to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if `amount` is zero or has other decimals
+    /// than the asset, and [`Error::AddressError`] if `to` cannot receive on the
+    /// asset's network.
+    pub fn new(asset: Asset, to: Address, amount: CoinAmount) -> Result<Self> {
+        let network = asset.network().address_network();
+        if !to.is_valid_for(network) {
+            return Err(khodpay_address::Error::WrongNetwork {
+                address: to.to_string(),
+                network,
+            }
+            .into());
+        }
+        if amount.is_zero() {
+            return Err(Error::InvalidAmount(format!(
+                "cannot send 0 {}",
+                asset.symbol()
+            )));
+        }
+        if amount.decimals() != asset.decimals() {
+            return Err(Error::InvalidAmount(format!(
+                "{} has {} decimals, not {}",
+                asset.symbol(),
+                asset.decimals(),
+                amount.decimals()
+            )));
+        }
+        Ok(Self {
+            asset,
+            to,
+            amount,
+            destination_tag: None,
+        })
+    }
+
+    /// Creates a request from what the user typed: an address and a decimal
+    /// amount such as `12.5` or `12.5 USDT`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::AddressError`] if `to` is not an address on the asset's
+    /// network, and [`Error::InvalidAmount`] if `amount` is malformed, has too
+    /// many decimal places, names another asset or is zero.
+    pub fn parse(asset: Asset, to: &str, amount: &str) -> Result<Self> {
+        let to = Address::parse(to.trim(), asset.network().address_network())?;
+        let amount = asset.parse_amount(amount)?;
+        Self::new(asset, to, amount)
+    }
+
+    /// Sets the XRP Ledger destination tag, which exchanges and other shared
+    /// addresses need to credit the right customer.
+    ///
+    /// Only XRP transfers take one; [`TransferRequest::resolve`] refuses it on
+    /// other networks.
+    pub fn with_destination_tag(mut self, tag: u32) -> Self {
+        self.destination_tag = Some(tag);
+        self
+    }
+
+    /// Returns the asset sent.
+    pub fn asset(&self) -> &Asset {
+        &self.asset
+    }
+
+    /// Returns the recipient.
+    pub fn to(&self) -> &Address {
+        &self.to
+    }
+
+    /// Returns the amount, in the asset's decimals.
+    pub fn amount(&self) -> CoinAmount {
+        self.amount
+    }
+
+    /// Returns the XRP Ledger destination tag, if set.
+    pub fn destination_tag(&self) -> Option<u32> {
+        self.destination_tag
+    }
+
+    /// Returns the builder of the asset's network with this transfer set.
+    ///
+    /// - Bitcoin: a [`TransactionBuilder`](BtcTransactionBuilder) with one output
+    ///   to the recipient.
+    /// - EVM chains: an [`Eip1559TransactionBuilder`] with chain ID, recipient,
+    ///   value and a 21,000 gas limit, or for ERC-20 tokens a `transfer` call to
+    ///   the contract with a [`TOKEN_TRANSFER_GAS`] limit.
+    /// - Tron: a [`TransactionBuilder`](TronTransactionBuilder) with a TRX or
+    ///   TRC-20 transfer.
+    /// - XRP Ledger: a [`PaymentBuilder`] with destination, amount and any
+    ///   destination tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAmount`] if the amount is more than the network's
+    /// native amounts hold, and [`Error::Unsupported`] for a destination tag on a
+    /// network other than the XRP Ledger.
+    pub fn resolve(&self) -> Result<TransferBuilder> {
+        if self.destination_tag.is_some() && self.asset.network() != Network::Xrp {
+            return Err(Error::Unsupported(format!(
+                "destination tags are for the XRP Ledger, not {}",
+                self.asset.network()
+            )));
+        }
+        // Addresses were checked against the network when the request was made
+        let wrong_address = || {
+            Error::from(khodpay_address::Error::WrongNetwork {
+                address: self.to.to_string(),
+                network: self.asset.network().address_network(),
+            })
+        };
+
+        let builder = match (self.asset.network(), self.asset.kind()) {
+            (Network::Bitcoin(network), _) => {
+                let to = self.to.as_bitcoin().ok_or_else(wrong_address)?;
+                let sats = self.native_amount(MAX_MONEY)?;
+                TransferBuilder::Bitcoin(BtcTransactionBuilder::new(network).add_output(to, sats))
+            }
+            (Network::Evm(chain_id), kind) => {
+                let to = *self.to.as_evm().ok_or_else(wrong_address)?;
+                let builder = Eip1559Transaction::builder().chain_id(chain_id);
+                TransferBuilder::Evm(match kind {
+                    AssetKind::Native => builder
+                        .to(to)
+                        .value(Wei::from_u256(self.amount.value()))
+                        .gas_limit(TRANSFER_GAS),
+                    AssetKind::Token(contract) => builder
+                        .to(*contract.as_evm().ok_or_else(wrong_address)?)
+                        .data(erc20::encode_transfer(&to, self.amount.value()))
+                        .gas_limit(TOKEN_TRANSFER_GAS),
+                })
+            }
+            (Network::Tron, kind) => {
+                let to = *self.to.as_tron().ok_or_else(wrong_address)?;
+                let builder = TronTransaction::builder();
+                TransferBuilder::Tron(match kind {
+                    AssetKind::Native => builder.transfer(to, self.native_amount(u64::MAX)?),
+                    AssetKind::Token(contract) => builder.trc20_transfer(
+                        *contract.as_tron().ok_or_else(wrong_address)?,
+                        to,
+                        self.amount.value(),
+                    ),
+                })
+            }
+            (Network::Xrp, _) => {
+                let to = *self.to.as_xrp().ok_or_else(wrong_address)?;
+                let builder = Payment::builder()
+                    .destination(to)
+                    .amount(self.native_amount(u64::MAX)?);
+                TransferBuilder::Xrp(match self.destination_tag {
+                    Some(tag) => builder.destination_tag(tag),
+                    None => builder,
+                })
+            }
+        };
+        Ok(builder)
+    }
+
+    /// Returns the amount as a `u64` no larger than `max`.
+    fn native_amount(&self, max: u64) -> Result<u64> {
+        match self.amount.as_u64() {
+            Some(amount) if amount <= max => Ok(amount),
+            _ => Err(Error::InvalidAmount(format!(
+                "{} is more than {} can send",
+                self.asset.format_amount(self.amount),
+                self.asset.network()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TransferRequest {
+    /// Describes the transfer for a confirmation screen, such as
+    /// `12.5 USDT to T… on Tron`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} to {}",
+            self.asset.format_amount(self.amount),
+            self.to
+        )?;
+        if let Some(tag) = self.destination_tag {
+            write!(f, " (tag {})", tag)?;
+        }
+        write!(f, " on {}", self.asset.network())
+    }
+}
+
+/// The transaction builder a [`TransferRequest`] resolves to, with the
+/// recipient and amount set.
+///
+/// Each variant still needs its network's account state before it builds:
+#[derive(Debug, Clone)]
+pub enum TransferBuilder {
+    /// Bitcoin: add UTXOs, a change address and a fee or fee rate, then sign.
+    Bitcoin(BtcTransactionBuilder),
+    /// EVM chains: set the nonce and fees.
+    Evm(Eip1559TransactionBuilder),
+    /// Tron: set the owner, reference block and timestamp, plus a fee limit for
+    /// TRC-20 transfers.
+    Tron(TronTransactionBuilder),
+    /// XRP Ledger: set the sending account and sequence.
+    Xrp(PaymentBuilder),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_address::BitcoinNetwork;
+    use khodpay_btc_signing::{BtcSigner, OutPoint, Utxo};
+    use khodpay_signing::ChainId;
+    use khodpay_tron_signing::{Contract, TronSigner};
+    use khodpay_xrp_signing::XrpSigner;
+
+    const EVM_RECIPIENT: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+    const USDT_BSC: &str = "0x55d398326f99059fF775485246999027B3197955";
+    const USDT_TRON: &str = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+
+    fn bsc() -> Network {
+        Network::Evm(ChainId::BscMainnet)
+    }
+
+    fn build_evm(request: &TransferRequest) -> Eip1559Transaction {
+        let TransferBuilder::Evm(builder) = request.resolve().unwrap() else {
+            panic!("expected an EVM builder");
+        };
+        builder
+            .nonce(7)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(3))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_evm_native() {
+        let bnb = Asset::native(bsc()).unwrap();
+        let request = TransferRequest::parse(bnb, EVM_RECIPIENT, "1.5").unwrap();
+        let tx = build_evm(&request);
+        assert_eq!(tx.chain_id, ChainId::BscMainnet);
+        assert_eq!(tx.to, request.to().as_evm().copied());
+        assert_eq!(tx.value, Wei::from_wei(1_500_000_000_000_000_000u128));
+        assert_eq!(tx.gas_limit, TRANSFER_GAS);
+        assert!(tx.data.is_empty());
+    }
+
+    #[test]
+    fn test_erc20() {
+        let contract: Address = USDT_BSC.parse().unwrap();
+        let usdt = Asset::token(bsc(), contract.clone(), "USDT", 18).unwrap();
+        let request = TransferRequest::parse(usdt, EVM_RECIPIENT, "12.5").unwrap();
+        assert_eq!(
+            request.to_string(),
+            format!("12.5 USDT to {} on BSC Mainnet (56)", EVM_RECIPIENT)
+        );
+
+        let tx = build_evm(&request);
+        assert_eq!(tx.to, contract.as_evm().copied());
+        assert!(tx.value.is_zero());
+        assert_eq!(tx.gas_limit, TOKEN_TRANSFER_GAS);
+        assert_eq!(
+            tx.data,
+            erc20::encode_transfer(request.to().as_evm().unwrap(), request.amount().value())
+        );
+    }
+
+    #[test]
+    fn test_bitcoin() {
+        let signer = BtcSigner::from_private_key(&[1; 32], BitcoinNetwork::Bitcoin).unwrap();
+        let btc = Asset::native(Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap();
+        let request =
+            TransferRequest::parse(btc, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "0.0005")
+                .unwrap();
+
+        let TransferBuilder::Bitcoin(builder) = request.resolve().unwrap() else {
+            panic!("expected a Bitcoin builder");
+        };
+        let utxo = Utxo::new(
+            OutPoint::new(
+                "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            100_000,
+            signer.p2wpkh_address().script_pubkey(),
+        );
+        let tx = builder
+            .add_utxo(utxo)
+            .change_address(&signer.p2wpkh_address())
+            .fee(1_000)
+            .sign(&[&signer])
+            .unwrap();
+        assert_eq!(tx.outputs[0].value, 50_000);
+        assert_eq!(
+            tx.outputs[0].script_pubkey,
+            request.to().as_bitcoin().unwrap().script_pubkey()
+        );
+
+        // Testnet addresses are refused on mainnet
+        let btc = Asset::native(Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap();
+        assert!(matches!(
+            TransferRequest::parse(btc, "tb1qw508d6qejxtdg4y5r3zarvary0c5xdd0flq4v", "1"),
+            Err(Error::AddressError(_))
+        ));
+    }
+
+    #[test]
+    fn test_tron() {
+        let owner = TronSigner::from_private_key(&[1; 32]).unwrap().address();
+        let trx = Asset::native(Network::Tron).unwrap();
+        let request = TransferRequest::parse(trx, USDT_TRON, "2").unwrap();
+        let TransferBuilder::Tron(builder) = request.resolve().unwrap() else {
+            panic!("expected a Tron builder");
+        };
+        let tx = builder
+            .owner(owner)
+            .reference_block(1, &[0; 32])
+            .timestamp(1_700_000_000_000)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            tx.contract(),
+            Contract::Transfer {
+                amount: 2_000_000,
+                ..
+            }
+        ));
+
+        let contract: Address = USDT_TRON.parse().unwrap();
+        let usdt = Asset::token(Network::Tron, contract.clone(), "USDT", 6).unwrap();
+        let to = Address::Tron(owner);
+        let amount = usdt.amount(1_000_000);
+        let request = TransferRequest::new(usdt, to, amount).unwrap();
+        let TransferBuilder::Tron(builder) = request.resolve().unwrap() else {
+            panic!("expected a Tron builder");
+        };
+        let tx = builder
+            .owner(owner)
+            .reference_block(1, &[0; 32])
+            .timestamp(1_700_000_000_000)
+            .fee_limit(30_000_000)
+            .build()
+            .unwrap();
+        let Contract::TriggerSmartContract {
+            contract: called, ..
+        } = tx.contract()
+        else {
+            panic!("expected a contract call");
+        };
+        assert_eq!(Some(called), contract.as_tron());
+    }
+
+    #[test]
+    fn test_xrp() {
+        let account = XrpSigner::from_private_key(&[1; 32]).unwrap().address();
+        let xrp = Asset::native(Network::Xrp).unwrap();
+        let request = TransferRequest::parse(xrp, "rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe", "25")
+            .unwrap()
+            .with_destination_tag(104_729);
+        assert_eq!(
+            request.to_string(),
+            "25 XRP to rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe (tag 104729) on XRP Ledger"
+        );
+
+        let TransferBuilder::Xrp(builder) = request.resolve().unwrap() else {
+            panic!("expected an XRP builder");
+        };
+        let payment = builder.account(account).sequence(1).build().unwrap();
+        assert_eq!(payment.amount(), 25_000_000);
+        assert_eq!(payment.destination_tag(), Some(104_729));
+    }
+
+    #[test]
+    fn test_rejects() {
+        let bnb = Asset::native(bsc()).unwrap();
+        for (to, amount) in [
+            (USDT_TRON, "1"),
+            ("0x9858effd232b4033e47d90003d41ec34ecaeda9", "1"),
+            (EVM_RECIPIENT, "0"),
+            (EVM_RECIPIENT, "1.0000000000000000001"),
+            (EVM_RECIPIENT, "one"),
+        ] {
+            assert!(
+                TransferRequest::parse(bnb.clone(), to, amount).is_err(),
+                "{} {}",
+                to,
+                amount
+            );
+        }
+
+        // Amounts must be in the asset's decimals
+        let to: Address = EVM_RECIPIENT.parse().unwrap();
+        assert!(matches!(
+            TransferRequest::new(bnb.clone(), to, CoinAmount::from_sats(1)),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(TransferRequest::parse(bnb.clone(), EVM_RECIPIENT, "1 BTC").is_err());
+
+        // Destination tags are XRP-only
+        let request = TransferRequest::parse(bnb, EVM_RECIPIENT, "1")
+            .unwrap()
+            .with_destination_tag(1);
+        assert!(matches!(request.resolve(), Err(Error::Unsupported(_))));
+
+        // More satoshis than there are bitcoins
+        let btc = Asset::native(Network::Bitcoin(BitcoinNetwork::Bitcoin)).unwrap();
+        let request = TransferRequest::parse(
+            btc,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "21000001",
+        )
+        .unwrap();
+        assert!(matches!(request.resolve(), Err(Error::InvalidAmount(_))));
+    }
+}
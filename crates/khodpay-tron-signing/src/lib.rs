@@ -0,0 +1,75 @@
+//! # Khodpay Tron Signing
+//!
+//! TRX and TRC-20 transaction building and signing for Tron wallets.
+//!
+//! This crate turns a BIP-44 Tron account from `khodpay-bip44` and a recent block
+//! reference into signed protobuf transactions ready for `/wallet/broadcasthex`.
+//! Addresses are the `khodpay-address` [`TronAddress`](khodpay_address::TronAddress).
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`TransactionBuilder`] | TRX transfers, TRC-20 transfers and other contract calls |
+//! | [`Transaction`] | Protobuf `raw_data` and the `sha256` txid |
+//! | [`Contract`] | `TransferContract` and `TriggerSmartContract` |
+//! | [`TronSigner`] | Keys from a `khodpay-bip44` Tron account (`m/44'/195'/…`) |
+//! | [`SignedTransaction`] | The protobuf `Transaction` with its signature |
+//!
+//! ## Features
+//!
+//! - **TRX Transfers**: Amounts in SUN, with self-transfers and zero amounts rejected
+//! - **TRC-20 Transfers**: `transfer(address,uint256)` calls with a required fee limit
+//! - **Canonical Encoding**: Protobuf fields serialized the way java-tron hashes them,
+//!   so the txid matches the network's
+//! - **TaPoS**: Reference block bytes and hash taken from a block number and ID
+//! - **Memos**: Optional on-chain notes for exchange deposits
+//! - **BIP-44 Integration**: Sign with keys from the same HD wallet as the Bitcoin and
+//!   EVM crates
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_bip32::Network;
+//! use khodpay_bip44::{CoinType, Purpose, Wallet};
+//! use khodpay_tron_signing::{Transaction, TronSigner, SUN_PER_TRX};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet)?;
+//! let account = wallet.get_account(Purpose::BIP44, CoinType::Tron, 0)?;
+//! let signer = TronSigner::new(account, 0)?;
+//!
+//! // From the latest block of /wallet/getnowblock
+//! let block_number = 66_000_000;
+//! let block_id = [0x42; 32];
+//! let block_timestamp = 1_700_000_000_000;
+//!
+//! let tx = Transaction::builder()
+//!     .owner(signer.address())
+//!     .transfer("TPrkFhZ8LH8Mruco8vXyA496TaeFBrbmeU".parse()?, 5 * SUN_PER_TRX)
+//!     .reference_block(block_number, &block_id)
+//!     .timestamp(block_timestamp)
+//!     .build()?;
+//!
+//! let signed = signer.sign_transaction(&tx)?;
+//! println!("txid {}: {}", signed.txid_hex(), signed.to_hex());
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod error;
+mod protobuf;
+mod signer;
+mod transaction;
+
+pub use error::{Error, Result};
+pub use signer::TronSigner;
+pub use transaction::{
+    Contract, SignedTransaction, Transaction, TransactionBuilder, DEFAULT_EXPIRATION_MS,
+    SUN_PER_TRX,
+};
@@ -0,0 +1,66 @@
+//! The protobuf wire format subset Tron transactions use.
+//!
+//! Fields are written in field-number order and proto3 default values (zero and
+//! empty) are skipped, which is how java-tron serializes a transaction before
+//! hashing it; any other encoding would give a different txid.
+
+/// Wire type of varint fields.
+const VARINT: u64 = 0;
+
+/// Wire type of length-delimited fields: bytes, strings and messages.
+const LENGTH_DELIMITED: u64 = 2;
+
+/// Serializes one protobuf message.
+#[derive(Debug, Default)]
+pub(crate) struct Writer(Vec<u8>);
+
+impl Writer {
+    /// Writes an integer or enum field, unless it is zero.
+    pub(crate) fn varint(mut self, field: u64, value: u64) -> Self {
+        if value != 0 {
+            write_varint(&mut self.0, field << 3 | VARINT);
+            write_varint(&mut self.0, value);
+        }
+        self
+    }
+
+    /// Writes a bytes, string or message field, unless it is empty.
+    pub(crate) fn bytes(mut self, field: u64, value: &[u8]) -> Self {
+        if !value.is_empty() {
+            write_varint(&mut self.0, field << 3 | LENGTH_DELIMITED);
+            write_varint(&mut self.0, value.len() as u64);
+            self.0.extend_from_slice(value);
+        }
+        self
+    }
+
+    /// Returns the serialized message.
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writer() {
+        let message = Writer::default()
+            .varint(1, 150)
+            .varint(2, 0)
+            .bytes(3, b"")
+            .bytes(4, b"abc")
+            .varint(18, 10_000_000)
+            .finish();
+        assert_eq!(hex::encode(message), "0896012203616263900180ade204");
+    }
+}
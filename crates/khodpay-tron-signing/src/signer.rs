@@ -0,0 +1,175 @@
+//! Keys that sign Tron transactions.
+
+use std::fmt;
+
+use khodpay_address::TronAddress;
+use khodpay_bip44::{Account, CoinType};
+use khodpay_signing::{Bip44Signer, PrehashPolicy};
+
+use crate::{Error, Result, SignedTransaction, Transaction};
+
+/// Offset Tron adds to the recovery ID in the last signature byte, as TronWeb does.
+const RECOVERY_ID_OFFSET: u8 = 27;
+
+/// Signs Tron transactions with a key from a BIP-44 Tron account
+/// (`m/44'/195'/account'/0/index`).
+///
+/// Tron keys are secp256k1 keys like EVM ones, so this wraps a `khodpay-signing`
+/// [`Bip44Signer`]; the address is the same account hash in Tron's base58 form.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip32::Network;
+/// use khodpay_bip44::{CoinType, Purpose, Wallet};
+/// use khodpay_tron_signing::TronSigner;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet)?;
+/// let account = wallet.get_account(Purpose::BIP44, CoinType::Tron, 0)?;
+///
+/// let signer = TronSigner::new(account, 0)?;
+/// assert_eq!(signer.address().to_string(), "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH");
+/// # Ok(())
+/// # }
+/// ```
+pub struct TronSigner {
+    inner: Bip44Signer,
+    address: TronAddress,
+}
+
+impl TronSigner {
+    /// Creates a signer for the external address `address_index` of a Tron account.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAccount`] if the account is not for
+    /// [`CoinType::Tron`], or an error if key derivation fails.
+    pub fn new(account: &Account, address_index: u32) -> Result<Self> {
+        if account.coin_type() != CoinType::Tron {
+            return Err(Error::InvalidAccount(format!(
+                "expected a Tron account, got {:?}",
+                account.coin_type()
+            )));
+        }
+        Ok(Self::from_signer(Bip44Signer::new(account, address_index)?))
+    }
+
+    /// Creates a signer from a 32-byte private key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] if the key is zero or not below the curve
+    /// order.
+    pub fn from_private_key(private_key: &[u8; 32]) -> Result<Self> {
+        Ok(Self::from_signer(Bip44Signer::from_private_key(
+            private_key,
+        )?))
+    }
+
+    fn from_signer(inner: Bip44Signer) -> Self {
+        let address = TronAddress::from(inner.address());
+        Self { inner, address }
+    }
+
+    /// Returns the Tron address of the key.
+    pub fn address(&self) -> TronAddress {
+        self.address
+    }
+
+    /// Signs a transaction sent from this signer's address.
+    ///
+    /// The signature covers the txid, `sha256(raw_data)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTransaction`] if the transaction's owner is another
+    /// address, or an error if signing fails.
+    pub fn sign_transaction(&self, transaction: &Transaction) -> Result<SignedTransaction> {
+        if *transaction.owner() != self.address {
+            return Err(Error::InvalidTransaction(format!(
+                "owner {} is not the signer {}",
+                transaction.owner(),
+                self.address
+            )));
+        }
+        let txid = transaction.txid();
+        let policy = PrehashPolicy::allow_digests("Tron transaction", [txid])?;
+        let mut signature = self.inner.sign_prehashed(&txid, &policy)?.to_bytes();
+        signature[64] += RECOVERY_ID_OFFSET;
+        Ok(SignedTransaction::new(transaction.clone(), signature))
+    }
+}
+
+impl fmt::Debug for TronSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TronSigner")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_bip32::Network;
+    use khodpay_bip44::{Purpose, Wallet};
+    use khodpay_signing::{recover_signer, Signature};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn wallet() -> Wallet {
+        Wallet::from_english_mnemonic(MNEMONIC, "", Network::BitcoinMainnet).unwrap()
+    }
+
+    #[test]
+    fn test_derivation() {
+        let mut wallet = wallet();
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Tron, 0)
+            .unwrap();
+        let signer = TronSigner::new(account, 0).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH"
+        );
+
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .unwrap();
+        assert!(matches!(
+            TronSigner::new(account, 0),
+            Err(Error::InvalidAccount(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_transaction() {
+        let signer = TronSigner::from_private_key(&[1u8; 32]).unwrap();
+        let to: TronAddress = "TPrkFhZ8LH8Mruco8vXyA496TaeFBrbmeU".parse().unwrap();
+        let tx = Transaction::builder()
+            .owner(signer.address())
+            .transfer(to, 1_000_000)
+            .reference_block(66_000_000, &[0x5a; 32])
+            .timestamp(1_700_000_000_000)
+            .build()
+            .unwrap();
+
+        let signed = signer.sign_transaction(&tx).unwrap();
+        let signature = signed.signature();
+        assert!(matches!(signature[64], 27 | 28));
+        let recovered = recover_signer(&tx.txid(), &Signature::from_bytes(signature).unwrap());
+        assert_eq!(TronAddress::from(recovered.unwrap()), signer.address());
+        assert_eq!(signed.txid(), tx.txid());
+
+        // RFC 6979 nonces make signatures deterministic
+        assert_eq!(signer.sign_transaction(&tx).unwrap(), signed);
+
+        let other = TronSigner::from_private_key(&[2u8; 32]).unwrap();
+        assert!(matches!(
+            other.sign_transaction(&tx),
+            Err(Error::InvalidTransaction(_))
+        ));
+    }
+}
@@ -0,0 +1,535 @@
+//! Tron transactions: contracts, the builder and signed transactions.
+
+use khodpay_address::TronAddress;
+use khodpay_signing::{erc20, U256};
+use sha2::{Digest, Sha256};
+
+use crate::protobuf::Writer;
+use crate::{Error, Result};
+
+/// SUN per TRX; transfer amounts and fee limits are in SUN.
+pub const SUN_PER_TRX: u64 = 1_000_000;
+
+/// Time from [`timestamp`](TransactionBuilder::timestamp) to expiration when none
+/// is set, in milliseconds; the same default as java-tron and TronWeb.
+pub const DEFAULT_EXPIRATION_MS: u64 = 60_000;
+
+/// Contract type of a TRX transfer.
+const TRANSFER_CONTRACT: u64 = 1;
+
+/// Contract type of a smart contract call.
+const TRIGGER_SMART_CONTRACT: u64 = 31;
+
+const TRANSFER_CONTRACT_URL: &str = "type.googleapis.com/protocol.TransferContract";
+const TRIGGER_SMART_CONTRACT_URL: &str = "type.googleapis.com/protocol.TriggerSmartContract";
+
+/// What a transaction does; Tron calls every action a contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Contract {
+    /// Sends TRX.
+    Transfer {
+        /// The recipient.
+        to: TronAddress,
+        /// Amount in SUN.
+        amount: u64,
+    },
+    /// Calls a smart contract, such as a TRC-20 token.
+    TriggerSmartContract {
+        /// The contract.
+        contract: TronAddress,
+        /// TRX sent along with the call, in SUN.
+        call_value: u64,
+        /// ABI encoded calldata.
+        data: Vec<u8>,
+    },
+}
+
+impl Contract {
+    /// Returns the protobuf `Transaction.Contract` message, with `owner` as the
+    /// `owner_address` of the parameter.
+    fn encode(&self, owner: &TronAddress) -> Vec<u8> {
+        let owner = address_bytes(owner);
+        let (contract_type, type_url, value) = match self {
+            Contract::Transfer { to, amount } => (
+                TRANSFER_CONTRACT,
+                TRANSFER_CONTRACT_URL,
+                Writer::default()
+                    .bytes(1, &owner)
+                    .bytes(2, &address_bytes(to))
+                    .varint(3, *amount)
+                    .finish(),
+            ),
+            Contract::TriggerSmartContract {
+                contract,
+                call_value,
+                data,
+            } => (
+                TRIGGER_SMART_CONTRACT,
+                TRIGGER_SMART_CONTRACT_URL,
+                Writer::default()
+                    .bytes(1, &owner)
+                    .bytes(2, &address_bytes(contract))
+                    .varint(3, *call_value)
+                    .bytes(4, data)
+                    .finish(),
+            ),
+        };
+        // google.protobuf.Any
+        let parameter = Writer::default()
+            .bytes(1, type_url.as_bytes())
+            .bytes(2, &value)
+            .finish();
+        Writer::default()
+            .varint(1, contract_type)
+            .bytes(2, &parameter)
+            .finish()
+    }
+}
+
+/// An unsigned Tron transaction: the `raw_data` of a protobuf `Transaction`.
+///
+/// Built with [`Transaction::builder`] and signed with
+/// [`TronSigner::sign_transaction`](crate::TronSigner::sign_transaction).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    owner: TronAddress,
+    contract: Contract,
+    ref_block_bytes: [u8; 2],
+    ref_block_hash: [u8; 8],
+    expiration: u64,
+    timestamp: u64,
+    fee_limit: u64,
+    memo: Vec<u8>,
+}
+
+impl Transaction {
+    /// Returns a builder for a new transaction.
+    pub fn builder() -> TransactionBuilder {
+        TransactionBuilder::default()
+    }
+
+    /// Returns the account that sends the transaction and pays its fees.
+    pub fn owner(&self) -> &TronAddress {
+        &self.owner
+    }
+
+    /// Returns what the transaction does.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Returns the expiration, in unix milliseconds.
+    pub fn expiration(&self) -> u64 {
+        self.expiration
+    }
+
+    /// Returns the creation time, in unix milliseconds.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the most SUN a smart contract call may burn for energy; `0` for
+    /// transfers.
+    pub fn fee_limit(&self) -> u64 {
+        self.fee_limit
+    }
+
+    /// Returns the memo stored on chain with the transaction.
+    pub fn memo(&self) -> &[u8] {
+        &self.memo
+    }
+
+    /// Returns the protobuf `raw_data` the txid is computed from.
+    pub fn raw_data(&self) -> Vec<u8> {
+        Writer::default()
+            .bytes(1, &self.ref_block_bytes)
+            .bytes(4, &self.ref_block_hash)
+            .varint(8, self.expiration)
+            .bytes(10, &self.memo)
+            .bytes(11, &self.contract.encode(&self.owner))
+            .varint(14, self.timestamp)
+            .varint(18, self.fee_limit)
+            .finish()
+    }
+
+    /// Returns the `raw_data` in hex, the `raw_data_hex` of Tron's HTTP API.
+    pub fn raw_data_hex(&self) -> String {
+        hex::encode(self.raw_data())
+    }
+
+    /// Returns the transaction ID, `sha256(raw_data)`, which is also the digest
+    /// that gets signed.
+    pub fn txid(&self) -> [u8; 32] {
+        Sha256::digest(self.raw_data()).into()
+    }
+
+    /// Returns the transaction ID in hex, as shown by block explorers.
+    pub fn txid_hex(&self) -> String {
+        hex::encode(self.txid())
+    }
+}
+
+/// Builder for [`Transaction`].
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::TronAddress;
+/// use khodpay_signing::U256;
+/// use khodpay_tron_signing::{Transaction, SUN_PER_TRX};
+///
+/// let owner: TronAddress = "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH".parse().unwrap();
+/// let usdt: TronAddress = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap();
+/// let to: TronAddress = "TPrkFhZ8LH8Mruco8vXyA496TaeFBrbmeU".parse().unwrap();
+///
+/// // 10 USDT (6 decimals), burning at most 30 TRX for energy
+/// let tx = Transaction::builder()
+///     .owner(owner)
+///     .trc20_transfer(usdt, to, U256::from(10_000_000u64))
+///     .fee_limit(30 * SUN_PER_TRX)
+///     .reference_block(66_000_000, &[0xab; 32])
+///     .timestamp(1_700_000_000_000)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(tx.expiration(), 1_700_000_060_000);
+/// assert_eq!(tx.txid_hex().len(), 64);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransactionBuilder {
+    owner: Option<TronAddress>,
+    contract: Option<Contract>,
+    reference_block: Option<([u8; 2], [u8; 8])>,
+    expiration: Option<u64>,
+    timestamp: Option<u64>,
+    fee_limit: u64,
+    memo: Vec<u8>,
+}
+
+impl TransactionBuilder {
+    /// Sets the account that sends the transaction and pays its fees (required).
+    pub fn owner(mut self, owner: TronAddress) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Sends `amount` SUN of TRX to `to`.
+    pub fn transfer(self, to: TronAddress, amount: u64) -> Self {
+        self.contract(Contract::Transfer { to, amount })
+    }
+
+    /// Sends `amount` base units of the TRC-20 `token` to `to`.
+    ///
+    /// Token calls burn energy, so a [`fee_limit`](Self::fee_limit) is required.
+    pub fn trc20_transfer(self, token: TronAddress, to: TronAddress, amount: U256) -> Self {
+        self.contract(Contract::TriggerSmartContract {
+            contract: token,
+            call_value: 0,
+            data: erc20::encode_transfer(&to.to_evm(), amount),
+        })
+    }
+
+    /// Sets what the transaction does (required); [`transfer`](Self::transfer) and
+    /// [`trc20_transfer`](Self::trc20_transfer) are shortcuts for this.
+    pub fn contract(mut self, contract: Contract) -> Self {
+        self.contract = Some(contract);
+        self
+    }
+
+    /// Sets the reference block (required): a recent block, by number and 32-byte
+    /// block ID, that the transaction is only valid on top of (TaPoS).
+    ///
+    /// Use the latest solidified block from `/wallet/getnowblock` or
+    /// `/walletsolidity/getnowblock`.
+    pub fn reference_block(mut self, number: u64, block_id: &[u8; 32]) -> Self {
+        let number = number.to_be_bytes();
+        let ref_block_bytes = [number[6], number[7]];
+        let ref_block_hash = block_id[8..16].try_into().expect("8 bytes");
+        self.reference_block = Some((ref_block_bytes, ref_block_hash));
+        self
+    }
+
+    /// Sets the creation time in unix milliseconds (required).
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the expiration in unix milliseconds; defaults to
+    /// [`DEFAULT_EXPIRATION_MS`] after the timestamp. Nodes accept at most 24 hours
+    /// after the head block.
+    pub fn expiration(mut self, expiration: u64) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// Sets the most SUN a smart contract call may burn for energy.
+    pub fn fee_limit(mut self, fee_limit: u64) -> Self {
+        self.fee_limit = fee_limit;
+        self
+    }
+
+    /// Attaches a memo, stored on chain in the clear.
+    pub fn memo(mut self, memo: impl Into<Vec<u8>>) -> Self {
+        self.memo = memo.into();
+        self
+    }
+
+    /// Builds the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTransaction`] if a required field is missing, an
+    /// amount does not fit the protobuf `int64` fields, a transfer is empty or to
+    /// the owner itself, a contract call has no fee limit, or the expiration is not
+    /// after the timestamp.
+    pub fn build(self) -> Result<Transaction> {
+        let missing = |field: &str| Error::InvalidTransaction(format!("missing {}", field));
+        let owner = self.owner.ok_or_else(|| missing("owner"))?;
+        let contract = self.contract.ok_or_else(|| missing("contract"))?;
+        let (ref_block_bytes, ref_block_hash) = self
+            .reference_block
+            .ok_or_else(|| missing("reference block"))?;
+        let timestamp = self.timestamp.ok_or_else(|| missing("timestamp"))?;
+        let expiration = match self.expiration {
+            Some(expiration) => expiration,
+            None => timestamp
+                .checked_add(DEFAULT_EXPIRATION_MS)
+                .ok_or_else(|| Error::InvalidTransaction("timestamp out of range".to_string()))?,
+        };
+
+        match &contract {
+            Contract::Transfer { to, amount } => {
+                if *amount == 0 {
+                    return Err(Error::InvalidTransaction(
+                        "transfer amount must be positive".to_string(),
+                    ));
+                }
+                if *to == owner {
+                    return Err(Error::InvalidTransaction(
+                        "cannot transfer TRX to the owner itself".to_string(),
+                    ));
+                }
+                check_int64("amount", *amount)?;
+            }
+            Contract::TriggerSmartContract { call_value, .. } => {
+                if self.fee_limit == 0 {
+                    return Err(Error::InvalidTransaction(
+                        "smart contract calls need a fee limit".to_string(),
+                    ));
+                }
+                check_int64("call value", *call_value)?;
+            }
+        }
+        check_int64("fee limit", self.fee_limit)?;
+        check_int64("timestamp", timestamp)?;
+        check_int64("expiration", expiration)?;
+        if expiration <= timestamp {
+            return Err(Error::InvalidTransaction(
+                "expiration must be after the timestamp".to_string(),
+            ));
+        }
+
+        Ok(Transaction {
+            owner,
+            contract,
+            ref_block_bytes,
+            ref_block_hash,
+            expiration,
+            timestamp,
+            fee_limit: self.fee_limit,
+            memo: self.memo,
+        })
+    }
+}
+
+/// A transaction with its signature, ready to broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    transaction: Transaction,
+    signature: [u8; 65],
+}
+
+impl SignedTransaction {
+    /// Pairs a transaction with its 65-byte `r ‖ s ‖ v` signature.
+    pub fn new(transaction: Transaction, signature: [u8; 65]) -> Self {
+        Self {
+            transaction,
+            signature,
+        }
+    }
+
+    /// Returns the unsigned transaction.
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Returns the signature, `r ‖ s ‖ v` with `v` as `27` or `28`.
+    pub fn signature(&self) -> &[u8; 65] {
+        &self.signature
+    }
+
+    /// Returns the transaction ID, which signing does not change.
+    pub fn txid(&self) -> [u8; 32] {
+        self.transaction.txid()
+    }
+
+    /// Returns the transaction ID in hex.
+    pub fn txid_hex(&self) -> String {
+        self.transaction.txid_hex()
+    }
+
+    /// Returns the protobuf `Transaction`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        Writer::default()
+            .bytes(1, &self.transaction.raw_data())
+            .bytes(2, &self.signature)
+            .finish()
+    }
+
+    /// Returns the protobuf `Transaction` in hex, for `/wallet/broadcasthex`.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+}
+
+/// Returns the 21-byte `41…` form addresses take inside transactions.
+fn address_bytes(address: &TronAddress) -> [u8; 21] {
+    let mut bytes = [khodpay_address::TRON_PREFIX; 21];
+    bytes[1..].copy_from_slice(address.as_bytes());
+    bytes
+}
+
+fn check_int64(field: &str, value: u64) -> Result<()> {
+    if value > i64::MAX as u64 {
+        return Err(Error::InvalidTransaction(format!(
+            "{} {} does not fit in an int64",
+            field, value
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWNER: &str = "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH";
+    const TO: &str = "TPrkFhZ8LH8Mruco8vXyA496TaeFBrbmeU";
+    const USDT: &str = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+
+    fn address(s: &str) -> TronAddress {
+        s.parse().unwrap()
+    }
+
+    fn builder() -> TransactionBuilder {
+        let mut block_id = [0u8; 32];
+        block_id[..8].copy_from_slice(&66_000_000u64.to_be_bytes());
+        block_id[8..16].copy_from_slice(&[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]);
+        Transaction::builder()
+            .owner(address(OWNER))
+            .reference_block(66_000_000, &block_id)
+            .timestamp(1_700_000_000_000)
+    }
+
+    #[test]
+    fn test_transfer_raw_data() {
+        let tx = builder().transfer(address(TO), 1_500_000).build().unwrap();
+        let owner = address(OWNER).to_hex();
+        let to = address(TO).to_hex();
+
+        // 66_000_000 = 0x03ef1480, so the reference block bytes are 0x1480
+        let expected = [
+            "0a021480",
+            "22081122334455667788",
+            "40e0a499ffbc31",
+            "5a67",
+            "0801",
+            "1263",
+            "0a2d",
+            &hex::encode(TRANSFER_CONTRACT_URL),
+            "1232",
+            "0a15",
+            &owner,
+            "1215",
+            &to,
+            "18e0c65b",
+            "7080d095ffbc31",
+        ]
+        .concat();
+        assert_eq!(tx.raw_data_hex(), expected);
+        assert_eq!(tx.txid(), <[u8; 32]>::from(Sha256::digest(tx.raw_data())));
+        assert_eq!(tx.expiration(), 1_700_000_060_000);
+        assert_eq!(tx.fee_limit(), 0);
+    }
+
+    #[test]
+    fn test_trc20_raw_data() {
+        let tx = builder()
+            .trc20_transfer(address(USDT), address(TO), U256::from(10_000_000u64))
+            .fee_limit(10_000_000)
+            .memo("invoice 42")
+            .build()
+            .unwrap();
+        let raw = tx.raw_data_hex();
+
+        let calldata = [
+            "a9059cbb",
+            "000000000000000000000000",
+            &address(TO).to_hex()[2..],
+            &format!("{:064x}", 10_000_000),
+        ]
+        .concat();
+        let parameter = [
+            "0a15",
+            &address(OWNER).to_hex(),
+            "1215",
+            &address(USDT).to_hex(),
+            "2244",
+            &calldata,
+        ]
+        .concat();
+        assert!(raw.contains(&format!(
+            "081f12a9010a31{}1274{}",
+            hex::encode(TRIGGER_SMART_CONTRACT_URL),
+            parameter
+        )));
+        assert!(raw.contains(&format!("520a{}", hex::encode("invoice 42"))));
+        assert!(raw.ends_with("900180ade204"));
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let invalid = |builder: TransactionBuilder| {
+            matches!(builder.build(), Err(Error::InvalidTransaction(_)))
+        };
+        assert!(invalid(Transaction::builder().transfer(address(TO), 1)));
+        assert!(invalid(builder()));
+        assert!(invalid(builder().transfer(address(TO), 0)));
+        assert!(invalid(builder().transfer(address(OWNER), 1)));
+        assert!(invalid(builder().transfer(address(TO), u64::MAX)));
+        assert!(invalid(builder().trc20_transfer(
+            address(USDT),
+            address(TO),
+            U256::one()
+        )));
+        assert!(invalid(
+            builder()
+                .transfer(address(TO), 1)
+                .expiration(1_700_000_000_000)
+        ));
+    }
+
+    #[test]
+    fn test_signed_transaction_bytes() {
+        let tx = builder().transfer(address(TO), 1).build().unwrap();
+        let raw = tx.raw_data_hex();
+        assert_eq!(raw.len() / 2, 131);
+        let signed = SignedTransaction::new(tx, [0x1b; 65]);
+        assert_eq!(
+            signed.to_hex(),
+            format!("0a8301{}1241{}", raw, "1b".repeat(65))
+        );
+        assert_eq!(signed.txid(), signed.transaction().txid());
+    }
+}
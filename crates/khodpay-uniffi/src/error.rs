@@ -0,0 +1,48 @@
+//! Error types for the Kotlin and Swift bindings.
+
+use thiserror::Error;
+
+/// Errors thrown to Kotlin as `WalletException` and to Swift as `WalletError`.
+///
+/// Each case carries the Rust error message; the kind of failure is the case itself.
+#[derive(Debug, Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum WalletError {
+    /// An argument is malformed or out of range.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Error from parsing or generating a mnemonic.
+    #[error("Mnemonic error: {0}")]
+    Mnemonic(#[from] khodpay_bip39::Error),
+
+    /// Error from deriving keys.
+    #[error("Key derivation error: {0}")]
+    Bip32(#[from] khodpay_bip32::Error),
+
+    /// Error from deriving accounts.
+    #[error("Wallet error: {0}")]
+    Bip44(#[from] khodpay_bip44::Error),
+
+    /// Error from decoding or signing an EVM transaction, message or typed data.
+    #[error("EVM error: {0}")]
+    Evm(#[from] khodpay_signing::Error),
+}
+
+/// Result type alias for the bindings.
+pub type Result<T> = std::result::Result<T, WalletError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            WalletError::InvalidInput("purpose 45".to_string()).to_string(),
+            "Invalid input: purpose 45"
+        );
+        let error: WalletError = khodpay_signing::Error::InvalidAddress("0x12".to_string()).into();
+        assert!(error.to_string().starts_with("EVM error: "));
+    }
+}
@@ -0,0 +1,184 @@
+//! # KhodPay UniFFI Bindings
+//!
+//! Kotlin and Swift bindings to the KhodPay wallet core, generated with
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/), so native Android and iOS apps
+//! embed the same mnemonic, key derivation and signing code as the Flutter app.
+//!
+//! The crate builds as a `cdylib` for Android (through `cargo ndk`) and a
+//! `staticlib` for iOS; the bundled `uniffi-bindgen` binary (`cli` feature) reads
+//! the exported interface from the built library and writes the Kotlin and Swift
+//! sources:
+//!
+//! - [`generate_mnemonic`], [`validate_mnemonic`] and [`mnemonic_to_seed`]
+//! - [`Wallet`], restored from a mnemonic or seed, whose keys stay in Rust memory:
+//!   fingerprints, account and path extended public keys, EVM addresses, and EVM
+//!   transaction, EIP-191 message and EIP-712 typed data signing
+//!
+//! Errors are thrown as [`WalletError`], `WalletException` in Kotlin.
+//!
+//! ## Example
+//!
+//! ```kotlin
+//! import com.khodpay.wallet.*
+//!
+//! val phrase = generateMnemonic(12u, MnemonicLanguage.ENGLISH)
+//! Wallet.fromMnemonic(phrase, "", MnemonicLanguage.ENGLISH, Network.MAINNET).use { wallet ->
+//!     val address = wallet.evmAddress(0u, 0u)
+//!     val signed = wallet.signEvmTransaction(0u, 0u, unsignedTx)
+//!     rpc.sendRawTransaction(signed.rawTransaction)
+//! }
+//! ```
+
+pub mod error;
+mod wallet;
+
+use khodpay_bip39::{Language, Mnemonic, WordCount};
+use zeroize::Zeroizing;
+
+pub use error::{Result, WalletError};
+pub use wallet::{SignedEvmTransaction, Wallet};
+
+uniffi::setup_scaffolding!();
+
+/// Word list of a mnemonic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum MnemonicLanguage {
+    /// English.
+    English,
+    /// Japanese.
+    Japanese,
+    /// Korean.
+    Korean,
+    /// French.
+    French,
+    /// Italian.
+    Italian,
+    /// Spanish.
+    Spanish,
+    /// Simplified Chinese.
+    SimplifiedChinese,
+    /// Traditional Chinese.
+    TraditionalChinese,
+    /// Czech.
+    Czech,
+}
+
+impl From<MnemonicLanguage> for Language {
+    fn from(language: MnemonicLanguage) -> Self {
+        match language {
+            MnemonicLanguage::English => Language::English,
+            MnemonicLanguage::Japanese => Language::Japanese,
+            MnemonicLanguage::Korean => Language::Korean,
+            MnemonicLanguage::French => Language::French,
+            MnemonicLanguage::Italian => Language::Italian,
+            MnemonicLanguage::Spanish => Language::Spanish,
+            MnemonicLanguage::SimplifiedChinese => Language::SimplifiedChinese,
+            MnemonicLanguage::TraditionalChinese => Language::TraditionalChinese,
+            MnemonicLanguage::Czech => Language::Czech,
+        }
+    }
+}
+
+/// Network of a wallet's extended keys: `xpub` on mainnet, `tpub` on testnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Network {
+    /// Bitcoin mainnet version bytes.
+    Mainnet,
+    /// Bitcoin testnet version bytes.
+    Testnet,
+}
+
+impl From<Network> for khodpay_bip32::Network {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Mainnet => khodpay_bip32::Network::BitcoinMainnet,
+            Network::Testnet => khodpay_bip32::Network::BitcoinTestnet,
+        }
+    }
+}
+
+/// Generates a mnemonic of `word_count` words (12, 15, 18, 21 or 24) from the
+/// platform's secure random source.
+#[uniffi::export]
+pub fn generate_mnemonic(word_count: u8, language: MnemonicLanguage) -> Result<String> {
+    let word_count = WordCount::from_word_count(word_count.into())?;
+    let mnemonic = Mnemonic::generate(word_count, language.into())?;
+    Ok(mnemonic.phrase().to_string())
+}
+
+/// Returns `true` if `phrase` is a valid mnemonic in `language`, checksum included.
+#[uniffi::export]
+pub fn validate_mnemonic(phrase: String, language: MnemonicLanguage) -> bool {
+    khodpay_bip39::validate_phrase_in_language(&phrase, language.into()).is_ok()
+}
+
+/// Returns the 64-byte BIP-39 seed of a mnemonic and passphrase.
+#[uniffi::export]
+pub fn mnemonic_to_seed(
+    phrase: String,
+    passphrase: String,
+    language: MnemonicLanguage,
+) -> Result<Vec<u8>> {
+    let phrase = Zeroizing::new(phrase);
+    let passphrase = Zeroizing::new(passphrase);
+    let mnemonic = Mnemonic::from_phrase(&phrase, language.into())?;
+    let seed = Zeroizing::new(mnemonic.to_seed(&passphrase)?);
+    Ok(seed.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The BIP-39 test mnemonic used throughout the libraries' test vectors.
+    pub const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_generate_mnemonic() {
+        let phrase = generate_mnemonic(24, MnemonicLanguage::English).unwrap();
+        assert_eq!(phrase.split(' ').count(), 24);
+        assert!(validate_mnemonic(phrase, MnemonicLanguage::English));
+
+        let phrase = generate_mnemonic(12, MnemonicLanguage::Spanish).unwrap();
+        assert!(validate_mnemonic(phrase.clone(), MnemonicLanguage::Spanish));
+        assert!(!validate_mnemonic(phrase, MnemonicLanguage::English));
+
+        assert!(matches!(
+            generate_mnemonic(13, MnemonicLanguage::English),
+            Err(WalletError::Mnemonic(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_mnemonic() {
+        assert!(validate_mnemonic(
+            MNEMONIC.to_string(),
+            MnemonicLanguage::English
+        ));
+        assert!(!validate_mnemonic(
+            MNEMONIC.replace("about", "abort"),
+            MnemonicLanguage::English
+        ));
+    }
+
+    #[test]
+    fn test_mnemonic_to_seed() {
+        // BIP-39 test vector with passphrase "TREZOR"
+        let seed = mnemonic_to_seed(
+            MNEMONIC.to_string(),
+            "TREZOR".to_string(),
+            MnemonicLanguage::English,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(seed),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+        assert!(mnemonic_to_seed(
+            "abandon".to_string(),
+            String::new(),
+            MnemonicLanguage::English
+        )
+        .is_err());
+    }
+}
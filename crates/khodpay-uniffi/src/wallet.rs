@@ -0,0 +1,303 @@
+//! The `Wallet` object: key derivation and EVM signing over a restored wallet.
+
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use khodpay_bip32::DerivationPath;
+use khodpay_bip44::{CoinType, Purpose};
+use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+use khodpay_signing::{
+    Bip44Signer, Eip1559Transaction, Eip2930Transaction, SignedEip2930Transaction,
+    SignedTransaction,
+};
+use zeroize::Zeroizing;
+
+use crate::{MnemonicLanguage, Network, Result, WalletError};
+
+/// A signed EVM transaction.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct SignedEvmTransaction {
+    /// Checksummed address of the signer.
+    pub from: String,
+    /// Transaction hash in `0x` hex, as block explorers show it.
+    pub hash: String,
+    /// Signed transaction in `0x` hex, for `eth_sendRawTransaction`.
+    pub raw_transaction: String,
+}
+
+/// An HD wallet restored from a mnemonic or seed.
+///
+/// Private keys never leave Rust memory; Kotlin and Swift only see public keys,
+/// addresses and signatures. The keys are wiped when the object is destroyed:
+/// call `close()` (or `use { }`) in Kotlin, and drop the last reference in Swift.
+#[derive(uniffi::Object)]
+pub struct Wallet {
+    inner: Mutex<khodpay_bip44::Wallet>,
+}
+
+#[uniffi::export]
+impl Wallet {
+    /// Restores the wallet of a mnemonic and BIP-39 passphrase.
+    #[uniffi::constructor]
+    pub fn from_mnemonic(
+        phrase: String,
+        passphrase: String,
+        language: MnemonicLanguage,
+        network: Network,
+    ) -> Result<Arc<Self>> {
+        let phrase = Zeroizing::new(phrase);
+        let passphrase = Zeroizing::new(passphrase);
+        let inner = khodpay_bip44::Wallet::from_mnemonic(
+            &phrase,
+            &passphrase,
+            language.into(),
+            network.into(),
+        )?;
+        Ok(Self::new(inner))
+    }
+
+    /// Restores the wallet of a 16 to 64 byte seed.
+    #[uniffi::constructor]
+    pub fn from_seed(seed: Vec<u8>, network: Network) -> Result<Arc<Self>> {
+        let seed = Zeroizing::new(seed);
+        let inner = khodpay_bip44::Wallet::from_seed(&seed, network.into())?;
+        Ok(Self::new(inner))
+    }
+
+    /// Returns the master key fingerprint in hex, as in descriptors and PSBTs.
+    pub fn fingerprint(&self) -> String {
+        hex::encode(self.wallet().master_key().fingerprint())
+    }
+
+    /// Returns the extended public key of account `account` under BIP-44 family
+    /// `purpose` (44, 49, 84 or 86) and SLIP-44 `coin_type`.
+    pub fn account_xpub(&self, purpose: u32, coin_type: u32, account: u32) -> Result<String> {
+        let purpose = Purpose::try_from(purpose)?;
+        let coin_type = CoinType::try_from(coin_type)?;
+        let mut wallet = self.wallet();
+        let account = wallet.get_account(purpose, coin_type, account)?;
+        Ok(account.extended_key().to_extended_public_key().to_string())
+    }
+
+    /// Returns the extended public key at derivation path `path`, such as
+    /// `m/48'/0'/0'/2'`.
+    pub fn xpub_at(&self, path: String) -> Result<String> {
+        let path = DerivationPath::from_str(&path)?;
+        let key = self.wallet().master_key().derive_path(&path)?;
+        Ok(key.to_extended_public_key().to_string())
+    }
+
+    /// Returns the checksummed EVM address at `m/44'/60'/account'/0/index`.
+    pub fn evm_address(&self, account: u32, index: u32) -> Result<String> {
+        Ok(self.evm_signer(account, index)?.address().to_string())
+    }
+
+    /// Signs an unsigned EIP-1559 or EIP-2930 transaction in `0x` hex with the key
+    /// at `m/44'/60'/account'/0/index`.
+    pub fn sign_evm_transaction(
+        &self,
+        account: u32,
+        index: u32,
+        unsigned_tx: String,
+    ) -> Result<SignedEvmTransaction> {
+        let unsigned_tx = unsigned_tx.trim();
+        let bytes = hex::decode(unsigned_tx.strip_prefix("0x").unwrap_or(unsigned_tx))
+            .map_err(|e| WalletError::InvalidInput(format!("transaction is not hex: {}", e)))?;
+        let signer = self.evm_signer(account, index)?;
+        let (hash, raw_transaction) = match bytes.first() {
+            Some(&Eip1559Transaction::TYPE) => {
+                let tx = Eip1559Transaction::decode(&bytes)?;
+                let signature = signer.sign_transaction(&tx)?;
+                let signed = SignedTransaction::new(tx, signature);
+                (signed.tx_hash_hex(), signed.to_raw_transaction())
+            }
+            Some(&Eip2930Transaction::TYPE) => {
+                let tx = Eip2930Transaction::decode(&bytes)?;
+                let signature = signer.sign_eip2930_transaction(&tx)?;
+                let signed = SignedEip2930Transaction::new(tx, signature);
+                (signed.tx_hash_hex(), signed.to_raw_transaction())
+            }
+            _ => {
+                return Err(WalletError::InvalidInput(
+                    "only EIP-1559 (0x02) and EIP-2930 (0x01) transactions are supported"
+                        .to_string(),
+                ))
+            }
+        };
+        Ok(SignedEvmTransaction {
+            from: signer.address().to_string(),
+            hash,
+            raw_transaction,
+        })
+    }
+
+    /// Signs `message` as EIP-191 `personal_sign` with the key at
+    /// `m/44'/60'/account'/0/index`, returning `0x ‖ r ‖ s ‖ v` with `v` as 27 or 28.
+    pub fn sign_evm_message(&self, account: u32, index: u32, message: Vec<u8>) -> Result<String> {
+        let signature = self.evm_signer(account, index)?.sign_message(&message)?;
+        Ok(signature_hex(signature))
+    }
+
+    /// Signs an `eth_signTypedData_v4` JSON payload with the key at
+    /// `m/44'/60'/account'/0/index`, returning `0x ‖ r ‖ s ‖ v` with `v` as 27 or 28.
+    pub fn sign_evm_typed_data(
+        &self,
+        account: u32,
+        index: u32,
+        typed_data_json: String,
+    ) -> Result<String> {
+        let typed_data = TypedData::from_json(&typed_data_json)?;
+        let signature = sign_typed_data_json(&self.evm_signer(account, index)?, &typed_data)?;
+        Ok(signature_hex(signature))
+    }
+}
+
+impl Wallet {
+    fn new(inner: khodpay_bip44::Wallet) -> Arc<Self> {
+        Arc::new(Wallet {
+            inner: Mutex::new(inner),
+        })
+    }
+
+    /// Locks the wallet. The account cache only ever gains fully derived accounts,
+    /// so a lock poisoned by a panicking call is still safe to use.
+    fn wallet(&self) -> MutexGuard<'_, khodpay_bip44::Wallet> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn evm_signer(&self, account: u32, index: u32) -> Result<Bip44Signer> {
+        let mut wallet = self.wallet();
+        let account = wallet.get_account(Purpose::BIP44, CoinType::Ethereum, account)?;
+        Ok(Bip44Signer::new(account, index)?)
+    }
+}
+
+/// Encodes a signature as `0x ‖ r ‖ s ‖ v`, with `v` as 27 or 28.
+fn signature_hex(signature: khodpay_signing::Signature) -> String {
+    let mut bytes = signature.to_bytes();
+    bytes[64] += 27;
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MNEMONIC;
+    use khodpay_signing::{eip191, Address, ChainId, Signature, TypedTransaction, Wei};
+
+    const ADDRESS: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+
+    fn wallet() -> Arc<Wallet> {
+        Wallet::from_mnemonic(
+            MNEMONIC.to_string(),
+            String::new(),
+            MnemonicLanguage::English,
+            Network::Mainnet,
+        )
+        .unwrap()
+    }
+
+    fn recover(signature: &str) -> Signature {
+        let mut bytes = hex::decode(signature.trim_start_matches("0x")).unwrap();
+        assert!(bytes[64] == 27 || bytes[64] == 28);
+        bytes[64] -= 27;
+        Signature::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_keys() {
+        let wallet = wallet();
+        assert_eq!(wallet.fingerprint(), "73c5da0a");
+        assert_eq!(wallet.evm_address(0, 0).unwrap(), ADDRESS);
+        let xpub = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        assert_eq!(wallet.account_xpub(84, 0, 0).unwrap(), xpub);
+        assert_eq!(wallet.xpub_at("m/84'/0'/0'".to_string()).unwrap(), xpub);
+        assert!(matches!(
+            wallet.account_xpub(45, 0, 0),
+            Err(WalletError::Bip44(_))
+        ));
+        assert!(matches!(
+            wallet.xpub_at("84'/0'".to_string()),
+            Err(WalletError::Bip32(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let seed = crate::mnemonic_to_seed(
+            MNEMONIC.to_string(),
+            String::new(),
+            MnemonicLanguage::English,
+        )
+        .unwrap();
+        let wallet = Wallet::from_seed(seed, Network::Testnet).unwrap();
+        assert_eq!(wallet.fingerprint(), "73c5da0a");
+        assert!(wallet.account_xpub(84, 1, 0).unwrap().starts_with("tpub"));
+        assert!(Wallet::from_seed(vec![0; 8], Network::Mainnet).is_err());
+    }
+
+    #[test]
+    fn test_sign_evm_transaction() {
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(7)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x11; 20]))
+            .value(Wei::from_gwei(1_000))
+            .build()
+            .unwrap();
+        let unsigned = format!("0x{}", hex::encode(tx.encode_unsigned()));
+        let wallet = wallet();
+        let signed = wallet.sign_evm_transaction(0, 0, unsigned.clone()).unwrap();
+        assert_eq!(signed.from, ADDRESS);
+
+        let bytes = hex::decode(signed.raw_transaction.trim_start_matches("0x")).unwrap();
+        let TypedTransaction::Eip1559(decoded) = TypedTransaction::from_raw(&bytes).unwrap() else {
+            panic!("expected an EIP-1559 transaction");
+        };
+        assert_eq!(decoded.transaction(), &tx);
+        assert_eq!(decoded.tx_hash_hex(), signed.hash);
+        assert_eq!(decoded.verify().unwrap().to_string(), ADDRESS);
+
+        for bad in ["zz", "0xf86c", &unsigned[..unsigned.len() - 2]] {
+            assert!(
+                wallet.sign_evm_transaction(0, 0, bad.to_string()).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_sign_evm_message() {
+        let signature = wallet().sign_evm_message(0, 0, b"hello".to_vec()).unwrap();
+        assert_eq!(
+            eip191::recover_message_signer(b"hello", &recover(&signature))
+                .unwrap()
+                .to_string(),
+            ADDRESS
+        );
+    }
+
+    #[test]
+    fn test_sign_evm_typed_data() {
+        let json = r#"{
+            "types": { "Greeting": [{ "name": "text", "type": "string" }] },
+            "primaryType": "Greeting",
+            "domain": { "name": "Example", "chainId": 56 },
+            "message": { "text": "gm" }
+        }"#;
+        let wallet = wallet();
+        let signature = wallet.sign_evm_typed_data(0, 0, json.to_string()).unwrap();
+        let hash = TypedData::from_json(json).unwrap().hash().unwrap();
+        let signer = khodpay_signing::recover_signer(&hash, &recover(&signature)).unwrap();
+        assert_eq!(signer.to_string(), ADDRESS);
+
+        assert!(matches!(
+            wallet.sign_evm_typed_data(0, 0, "{}".to_string()),
+            Err(WalletError::Evm(_))
+        ));
+    }
+}
@@ -0,0 +1,1023 @@
+//! The wallet side of the WalletConnect v2 sign protocol.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use khodpay_signing::Address;
+use serde_json::{json, Value};
+
+use crate::crypto::{self, KeyPair};
+use crate::relay::{self, Incoming};
+use crate::session::{parse_chain, EIP155, SUPPORTED_EVENTS, SUPPORTED_METHODS};
+use crate::{
+    Error, Event, Metadata, PairingUri, RelayAuth, Result, Session, SessionProposal,
+    SessionRequest, Topic, WalletRequest, DEFAULT_RELAY_URL,
+};
+
+/// How long an approved session lasts, in seconds.
+const SESSION_TTL: u64 = 7 * 86_400;
+
+/// Error code of a proposal or request the user declined.
+const USER_REJECTED: (i64, &str) = (5000, "User rejected.");
+
+/// Error code of a request on a chain outside the session.
+const UNSUPPORTED_CHAINS: (i64, &str) = (5100, "Unsupported chains.");
+
+/// Error code of a request for a method outside the session.
+const UNSUPPORTED_METHODS: (i64, &str) = (5101, "Unsupported methods.");
+
+/// Error code of a session the wallet ended.
+const USER_DISCONNECTED: (i64, &str) = (6000, "User disconnected.");
+
+/// JSON-RPC error code of a request with malformed params.
+const INVALID_PARAMS: i64 = -32602;
+
+/// A WalletConnect v2 wallet: pairs with dApps, approves sessions and surfaces
+/// their signing requests.
+///
+/// The client does no I/O. The host app opens a websocket to
+/// [`connection_url`](Self::connection_url), feeds every text frame it receives to
+/// [`handle_frame`](Self::handle_frame) and sends every frame returned by
+/// [`take_outgoing`](Self::take_outgoing). Sessions live in memory; after a
+/// reconnect, call [`resubscribe`](Self::resubscribe).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use khodpay_signing::Bip44Signer;
+/// use khodpay_walletconnect::{Event, Metadata, WalletClient};
+///
+/// # fn receive() -> String { unimplemented!() }
+/// # fn send(_frame: String) {}
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let signer = Bip44Signer::from_private_key(&[1u8; 32])?;
+/// let mut client = WalletClient::new(Metadata::new("KhodPay", "https://khodpay.com"));
+/// // Open a websocket to `client.connection_url("<project id>")`, then:
+///
+/// client.pair(&"wc:…@2?relay-protocol=irn&symKey=…".parse()?)?;
+/// loop {
+///     for frame in client.take_outgoing() {
+///         send(frame);
+///     }
+///     match client.handle_frame(&receive())? {
+///         Some(Event::SessionProposal(proposal)) => {
+///             let chains = proposal.required_chains()?;
+///             client.approve_session(proposal.id, &chains, &[signer.address()])?;
+///         }
+///         Some(Event::SessionRequest(request)) => match request.sign(&signer) {
+///             Ok(result) => client.respond(&request, result)?,
+///             Err(_) => client.reject_request(&request)?,
+///         },
+///         _ => {}
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WalletClient {
+    metadata: Metadata,
+    auth: RelayAuth,
+    relay_url: String,
+    pairings: HashMap<Topic, [u8; 32]>,
+    sessions: HashMap<Topic, SessionState>,
+    proposals: HashMap<u64, SessionProposal>,
+    /// Pending `wc_sessionRequest`s, by ID.
+    requests: HashMap<u64, Topic>,
+    /// Our `wc_sessionSettle` requests awaiting the dApp's answer, by ID.
+    settling: HashMap<u64, Topic>,
+    /// Our `irn_subscribe` requests awaiting the relay's answer, by ID.
+    subscribing: HashMap<u64, Topic>,
+    subscriptions: HashMap<Topic, String>,
+    outgoing: Vec<String>,
+    last_id: u64,
+}
+
+#[derive(Debug)]
+struct SessionState {
+    key: [u8; 32],
+    session: Session,
+}
+
+impl WalletClient {
+    /// Creates a client with a new relay identity.
+    pub fn new(metadata: Metadata) -> Self {
+        Self::with_relay_auth(metadata, RelayAuth::generate())
+    }
+
+    /// Creates a client with a persisted relay identity.
+    pub fn with_relay_auth(metadata: Metadata, auth: RelayAuth) -> Self {
+        Self {
+            metadata,
+            auth,
+            relay_url: DEFAULT_RELAY_URL.to_string(),
+            pairings: HashMap::new(),
+            sessions: HashMap::new(),
+            proposals: HashMap::new(),
+            requests: HashMap::new(),
+            settling: HashMap::new(),
+            subscribing: HashMap::new(),
+            subscriptions: HashMap::new(),
+            outgoing: Vec::new(),
+            last_id: 0,
+        }
+    }
+
+    /// Uses a relay other than [`DEFAULT_RELAY_URL`].
+    pub fn with_relay_url(mut self, relay_url: impl Into<String>) -> Self {
+        self.relay_url = relay_url.into();
+        self
+    }
+
+    /// Returns the relay identity, to persist with [`RelayAuth::seed`].
+    pub fn relay_auth(&self) -> &RelayAuth {
+        &self.auth
+    }
+
+    /// Returns the websocket URL to connect to, with a fresh auth token.
+    pub fn connection_url(&self, project_id: &str) -> String {
+        self.auth
+            .connection_url(&self.relay_url, project_id, unix_time())
+    }
+
+    /// Returns the session on `topic`, if any.
+    pub fn session(&self, topic: &Topic) -> Option<&Session> {
+        self.sessions.get(topic).map(|state| &state.session)
+    }
+
+    /// Returns every session.
+    pub fn sessions(&self) -> impl Iterator<Item = &Session> {
+        self.sessions.values().map(|state| &state.session)
+    }
+
+    /// Takes the frames to send to the relay.
+    pub fn take_outgoing(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Pairs with a dApp from its URI and waits for its session proposal.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidUri`] if the pairing expired or does not use the
+    /// WalletConnect relay protocol.
+    pub fn pair(&mut self, uri: &PairingUri) -> Result<()> {
+        if uri.relay_protocol != "irn" {
+            return Err(Error::InvalidUri(format!(
+                "unsupported relay protocol {}",
+                uri.relay_protocol
+            )));
+        }
+        if uri.is_expired(unix_time()) {
+            return Err(Error::InvalidUri("pairing expired".to_string()));
+        }
+        self.pairings.insert(uri.topic, uri.sym_key);
+        self.subscribe(uri.topic);
+        Ok(())
+    }
+
+    /// Subscribes to every pairing and session topic again, after the websocket
+    /// reconnected.
+    pub fn resubscribe(&mut self) {
+        self.subscriptions.clear();
+        self.subscribing.clear();
+        let topics: Vec<Topic> = self
+            .pairings
+            .keys()
+            .chain(self.sessions.keys())
+            .copied()
+            .collect();
+        for topic in topics {
+            self.subscribe(topic);
+        }
+    }
+
+    /// Handles a text frame from the relay.
+    ///
+    /// Pings are answered, and requests for methods or chains outside the session
+    /// rejected, without an event.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Relay`] if the relay refused one of our requests,
+    /// [`Error::UnknownTopic`] for a message on a topic the client dropped, or
+    /// [`Error::InvalidMessage`] / [`Error::Crypto`] for malformed messages.
+    pub fn handle_frame(&mut self, frame: &str) -> Result<Option<Event>> {
+        match relay::parse(frame)? {
+            Incoming::Message { id, topic, message } => {
+                self.outgoing.push(relay::ack(id));
+                self.handle_message(topic, &message)
+            }
+            Incoming::Result { id, result } => {
+                if let Some(topic) = self.subscribing.remove(&id) {
+                    if let Some(subscription) = result.as_str() {
+                        self.subscriptions.insert(topic, subscription.to_string());
+                    }
+                }
+                Ok(None)
+            }
+            Incoming::Error { id, code, message } => {
+                self.subscribing.remove(&id);
+                Err(Error::Relay { code, message })
+            }
+        }
+    }
+
+    /// Approves a session proposal for `accounts` on the EVM `chains`, and returns
+    /// the session.
+    ///
+    /// The session is usable right away; [`Event::SessionSettled`] follows once the
+    /// dApp acknowledges it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownRequest`] if no proposal has the ID, or
+    /// [`Error::UnsupportedNamespace`] if `chains` or `accounts` is empty or the
+    /// dApp requires a namespace or chain that is not approved. The proposal stays
+    /// pending on error, so it can still be rejected.
+    pub fn approve_session(
+        &mut self,
+        proposal_id: u64,
+        chains: &[u64],
+        accounts: &[Address],
+    ) -> Result<Session> {
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .ok_or(Error::UnknownRequest(proposal_id))?;
+        if chains.is_empty() || accounts.is_empty() {
+            return Err(Error::UnsupportedNamespace(
+                "a session needs at least one chain and account".to_string(),
+            ));
+        }
+        if let Some(missing) = proposal
+            .required_chains()?
+            .into_iter()
+            .find(|chain| !chains.contains(chain))
+        {
+            return Err(Error::UnsupportedNamespace(format!(
+                "{}:{}",
+                EIP155, missing
+            )));
+        }
+        let pairing_topic = proposal.pairing_topic;
+        if !self.pairings.contains_key(&pairing_topic) {
+            return Err(Error::UnknownTopic(pairing_topic.to_string()));
+        }
+
+        let key_pair = KeyPair::generate();
+        let key = key_pair.shared_key(&proposal.proposer_public_key)?;
+        let proposal = self
+            .proposals
+            .remove(&proposal_id)
+            .expect("proposal checked above");
+        let session = Session {
+            topic: Topic::from_key(&key),
+            pairing_topic,
+            peer: proposal.proposer.clone(),
+            chains: chains.to_vec(),
+            accounts: accounts.to_vec(),
+            expiry: unix_time() + SESSION_TTL,
+        };
+
+        self.subscribe(session.topic);
+        self.send_result(
+            pairing_topic,
+            proposal_id,
+            "wc_sessionPropose",
+            json!({
+                "relay": { "protocol": "irn" },
+                "responderPublicKey": key_pair.public_key_hex(),
+            }),
+        )?;
+        self.sessions.insert(
+            session.topic,
+            SessionState {
+                key,
+                session: session.clone(),
+            },
+        );
+        let settle_id = self.send_request(
+            session.topic,
+            "wc_sessionSettle",
+            json!({
+                "relay": { "protocol": "irn" },
+                "namespaces": {
+                    EIP155: {
+                        "chains": session
+                            .chains
+                            .iter()
+                            .map(|chain| format!("{}:{}", EIP155, chain))
+                            .collect::<Vec<_>>(),
+                        "accounts": session.caip_accounts(),
+                        "methods": SUPPORTED_METHODS,
+                        "events": SUPPORTED_EVENTS,
+                    }
+                },
+                "requiredNamespaces": proposal.required_namespaces,
+                "optionalNamespaces": proposal.optional_namespaces,
+                "controller": {
+                    "publicKey": key_pair.public_key_hex(),
+                    "metadata": self.metadata,
+                },
+                "expiry": session.expiry,
+                "pairingTopic": pairing_topic.to_string(),
+            }),
+        )?;
+        self.settling.insert(settle_id, session.topic);
+        Ok(session)
+    }
+
+    /// Rejects a session proposal.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownRequest`] if no proposal has the ID.
+    pub fn reject_session(&mut self, proposal_id: u64) -> Result<()> {
+        let proposal = self
+            .proposals
+            .remove(&proposal_id)
+            .ok_or(Error::UnknownRequest(proposal_id))?;
+        self.send_error(
+            proposal.pairing_topic,
+            proposal_id,
+            "wc_sessionPropose",
+            USER_REJECTED.0,
+            USER_REJECTED.1,
+        )
+    }
+
+    /// Answers a session request, such as with the result of
+    /// [`SessionRequest::sign`] or a transaction hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownRequest`] if the request was already answered, or
+    /// [`Error::UnknownTopic`] if its session ended.
+    pub fn respond(&mut self, request: &SessionRequest, result: Value) -> Result<()> {
+        let topic = self.take_request(request)?;
+        self.send_result(topic, request.id, "wc_sessionRequest", result)
+    }
+
+    /// Rejects a session request the user declined.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownRequest`] if the request was already answered, or
+    /// [`Error::UnknownTopic`] if its session ended.
+    pub fn reject_request(&mut self, request: &SessionRequest) -> Result<()> {
+        let topic = self.take_request(request)?;
+        self.send_error(
+            topic,
+            request.id,
+            "wc_sessionRequest",
+            USER_REJECTED.0,
+            USER_REJECTED.1,
+        )
+    }
+
+    /// Emits an event to the dApp, such as `accountsChanged` or `chainChanged`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownTopic`] if no session uses `topic`.
+    pub fn emit_event(
+        &mut self,
+        topic: &Topic,
+        name: &str,
+        data: Value,
+        chain_id: u64,
+    ) -> Result<()> {
+        self.send_request(
+            *topic,
+            "wc_sessionEvent",
+            json!({
+                "event": { "name": name, "data": data },
+                "chainId": format!("{}:{}", EIP155, chain_id),
+            }),
+        )?;
+        Ok(())
+    }
+
+    /// Ends a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownTopic`] if no session uses `topic`.
+    pub fn disconnect(&mut self, topic: &Topic) -> Result<()> {
+        self.send_request(
+            *topic,
+            "wc_sessionDelete",
+            json!({ "code": USER_DISCONNECTED.0, "message": USER_DISCONNECTED.1 }),
+        )?;
+        self.remove_session(topic);
+        Ok(())
+    }
+
+    fn handle_message(&mut self, topic: Topic, message: &str) -> Result<Option<Event>> {
+        let key = self
+            .key(&topic)
+            .ok_or_else(|| Error::UnknownTopic(topic.to_string()))?;
+        let payload: Value = serde_json::from_slice(&crypto::open(&key, message)?)
+            .map_err(|e| Error::InvalidMessage(e.to_string()))?;
+        let id = payload["id"]
+            .as_u64()
+            .ok_or_else(|| Error::InvalidMessage(format!("missing id: {}", payload)))?;
+
+        match payload["method"].as_str() {
+            Some(method) => self.handle_request(topic, id, method, &payload["params"]),
+            None => Ok(self.handle_response(id, &payload)),
+        }
+    }
+
+    fn handle_request(
+        &mut self,
+        topic: Topic,
+        id: u64,
+        method: &str,
+        params: &Value,
+    ) -> Result<Option<Event>> {
+        let is_session = self.sessions.contains_key(&topic);
+        match method {
+            "wc_sessionPropose" if !is_session => {
+                let proposal = parse_proposal(id, topic, params)?;
+                self.proposals.insert(id, proposal.clone());
+                Ok(Some(Event::SessionProposal(proposal)))
+            }
+            "wc_sessionRequest" if is_session => self.handle_session_request(topic, id, params),
+            "wc_sessionPing" | "wc_pairingPing" => {
+                self.send_result(topic, id, method, json!(true))?;
+                Ok(None)
+            }
+            "wc_sessionDelete" if is_session => {
+                self.send_result(topic, id, method, json!(true))?;
+                self.remove_session(&topic);
+                Ok(Some(Event::SessionDeleted {
+                    topic,
+                    reason: params["message"].as_str().unwrap_or_default().to_string(),
+                }))
+            }
+            "wc_pairingDelete" if !is_session => {
+                self.send_result(topic, id, method, json!(true))?;
+                self.pairings.remove(&topic);
+                self.proposals
+                    .retain(|_, proposal| proposal.pairing_topic != topic);
+                self.unsubscribe(&topic);
+                Ok(None)
+            }
+            // Events and updates from a dApp need no answer from the wallet.
+            _ => Ok(None),
+        }
+    }
+
+    fn handle_session_request(
+        &mut self,
+        topic: Topic,
+        id: u64,
+        params: &Value,
+    ) -> Result<Option<Event>> {
+        let chains = &self.sessions[&topic].session.chains;
+        let Some(chain_id) = params["chainId"]
+            .as_str()
+            .and_then(parse_chain)
+            .filter(|chain| chains.contains(chain))
+        else {
+            let (code, message) = UNSUPPORTED_CHAINS;
+            self.send_error(topic, id, "wc_sessionRequest", code, message)?;
+            return Ok(None);
+        };
+
+        let method = params["request"]["method"].as_str().unwrap_or_default();
+        match WalletRequest::parse(method, &params["request"]["params"]) {
+            Ok(Some(request)) => {
+                self.requests.insert(id, topic);
+                Ok(Some(Event::SessionRequest(SessionRequest {
+                    id,
+                    topic,
+                    chain_id,
+                    method: method.to_string(),
+                    request,
+                })))
+            }
+            Ok(None) => {
+                let (code, message) = UNSUPPORTED_METHODS;
+                self.send_error(topic, id, "wc_sessionRequest", code, message)?;
+                Ok(None)
+            }
+            Err(error) => {
+                let message = error.to_string();
+                self.send_error(topic, id, "wc_sessionRequest", INVALID_PARAMS, &message)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn handle_response(&mut self, id: u64, payload: &Value) -> Option<Event> {
+        let topic = self.settling.remove(&id)?;
+        if let Some(error) = payload.get("error") {
+            self.remove_session(&topic);
+            return Some(Event::SessionDeleted {
+                topic,
+                reason: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        self.session(&topic).cloned().map(Event::SessionSettled)
+    }
+
+    fn take_request(&mut self, request: &SessionRequest) -> Result<Topic> {
+        self.requests
+            .remove(&request.id)
+            .ok_or(Error::UnknownRequest(request.id))
+    }
+
+    fn remove_session(&mut self, topic: &Topic) {
+        self.sessions.remove(topic);
+        self.requests
+            .retain(|_, request_topic| request_topic != topic);
+        self.settling
+            .retain(|_, settle_topic| settle_topic != topic);
+        self.unsubscribe(topic);
+    }
+
+    fn key(&self, topic: &Topic) -> Option<[u8; 32]> {
+        self.pairings
+            .get(topic)
+            .copied()
+            .or_else(|| self.sessions.get(topic).map(|state| state.key))
+    }
+
+    fn subscribe(&mut self, topic: Topic) {
+        let id = self.next_id();
+        self.subscribing.insert(id, topic);
+        self.outgoing.push(relay::subscribe(id, &topic));
+    }
+
+    fn unsubscribe(&mut self, topic: &Topic) {
+        if let Some(subscription) = self.subscriptions.remove(topic) {
+            let id = self.next_id();
+            self.outgoing
+                .push(relay::unsubscribe(id, topic, &subscription));
+        }
+    }
+
+    fn send_request(&mut self, topic: Topic, method: &str, params: Value) -> Result<u64> {
+        let id = self.next_id();
+        let (tag, _, ttl) = relay::method_tags(method).expect("known method");
+        self.publish(
+            topic,
+            json!({ "id": id, "jsonrpc": "2.0", "method": method, "params": params }),
+            tag,
+            ttl,
+        )?;
+        Ok(id)
+    }
+
+    fn send_result(&mut self, topic: Topic, id: u64, method: &str, result: Value) -> Result<()> {
+        let (_, tag, ttl) = relay::method_tags(method).expect("known method");
+        self.publish(
+            topic,
+            json!({ "id": id, "jsonrpc": "2.0", "result": result }),
+            tag,
+            ttl,
+        )
+    }
+
+    fn send_error(
+        &mut self,
+        topic: Topic,
+        id: u64,
+        method: &str,
+        code: i64,
+        message: &str,
+    ) -> Result<()> {
+        let (_, tag, ttl) = relay::method_tags(method).expect("known method");
+        self.publish(
+            topic,
+            json!({
+                "id": id,
+                "jsonrpc": "2.0",
+                "error": { "code": code, "message": message },
+            }),
+            tag,
+            ttl,
+        )
+    }
+
+    fn publish(&mut self, topic: Topic, payload: Value, tag: u32, ttl: u64) -> Result<()> {
+        let key = self
+            .key(&topic)
+            .ok_or_else(|| Error::UnknownTopic(topic.to_string()))?;
+        let message = crypto::seal(&key, payload.to_string().as_bytes());
+        let id = self.next_id();
+        self.outgoing
+            .push(relay::publish(id, &topic, &message, ttl, tag));
+        Ok(())
+    }
+
+    /// Returns a new JSON-RPC ID: milliseconds since the epoch times 1000, bumped
+    /// so IDs never repeat.
+    fn next_id(&mut self) -> u64 {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        self.last_id = (millis * 1000).max(self.last_id + 1);
+        self.last_id
+    }
+}
+
+fn parse_proposal(id: u64, pairing_topic: Topic, params: &Value) -> Result<SessionProposal> {
+    let invalid = |e: serde_json::Error| Error::InvalidMessage(format!("proposal: {}", e));
+    let namespaces = |name: &str| match &params[name] {
+        Value::Null => Ok(Default::default()),
+        value => serde_json::from_value(value.clone()).map_err(invalid),
+    };
+    let proposer = &params["proposer"];
+    Ok(SessionProposal {
+        id,
+        pairing_topic,
+        proposer: serde_json::from_value(proposer["metadata"].clone()).map_err(invalid)?,
+        required_namespaces: namespaces("requiredNamespaces")?,
+        optional_namespaces: namespaces("optionalNamespaces")?,
+        proposer_public_key: proposer["publicKey"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidMessage("proposal has no public key".to_string()))?
+            .to_string(),
+    })
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::random_bytes;
+    use khodpay_signing::Bip44Signer;
+
+    /// The dApp end of a pairing, driving the client through relay frames.
+    struct Dapp {
+        pairing_key: [u8; 32],
+        key_pair: KeyPair,
+        session_key: Option<[u8; 32]>,
+        next_delivery: u64,
+    }
+
+    impl Dapp {
+        fn new() -> Self {
+            Self {
+                pairing_key: random_bytes(),
+                key_pair: KeyPair::generate(),
+                session_key: None,
+                next_delivery: 1,
+            }
+        }
+
+        fn uri(&self) -> PairingUri {
+            PairingUri {
+                topic: Topic::from_key(&self.pairing_key),
+                sym_key: self.pairing_key,
+                relay_protocol: "irn".to_string(),
+                relay_data: None,
+                expiry_timestamp: Some(unix_time() + 300),
+            }
+        }
+
+        fn session_topic(&self) -> Topic {
+            Topic::from_key(&self.session_key.unwrap())
+        }
+
+        /// Wraps a payload as the relay would deliver it.
+        fn deliver(&mut self, key: &[u8; 32], payload: Value) -> String {
+            self.next_delivery += 1;
+            json!({
+                "id": self.next_delivery,
+                "jsonrpc": "2.0",
+                "method": "irn_subscription",
+                "params": {
+                    "id": "sub",
+                    "data": {
+                        "topic": Topic::from_key(key).to_string(),
+                        "message": crypto::seal(key, payload.to_string().as_bytes()),
+                        "publishedAt": 0,
+                        "tag": 0,
+                    }
+                }
+            })
+            .to_string()
+        }
+
+        fn propose(&mut self, required: Value) -> String {
+            let key = self.pairing_key;
+            let payload = json!({
+                "id": 1,
+                "jsonrpc": "2.0",
+                "method": "wc_sessionPropose",
+                "params": {
+                    "relays": [{ "protocol": "irn" }],
+                    "requiredNamespaces": required,
+                    "proposer": {
+                        "publicKey": self.key_pair.public_key_hex(),
+                        "metadata": { "name": "Swap", "description": "", "url": "https://swap.example", "icons": [] }
+                    }
+                }
+            });
+            self.deliver(&key, payload)
+        }
+
+        fn session_message(&mut self, payload: Value) -> String {
+            let key = self.session_key.unwrap();
+            self.deliver(&key, payload)
+        }
+
+        /// Decrypts the payloads the client published, keyed by topic.
+        fn read(&mut self, frames: &[String]) -> Vec<(Topic, Value, u64)> {
+            frames
+                .iter()
+                .map(|frame| serde_json::from_str::<Value>(frame).unwrap())
+                .filter(|frame| frame["method"] == "irn_publish")
+                .map(|frame| {
+                    let params = &frame["params"];
+                    let topic: Topic = params["topic"].as_str().unwrap().parse().unwrap();
+                    let key = if topic == Topic::from_key(&self.pairing_key) {
+                        self.pairing_key
+                    } else {
+                        self.session_key.unwrap()
+                    };
+                    let payload = crypto::open(&key, params["message"].as_str().unwrap()).unwrap();
+                    (
+                        topic,
+                        serde_json::from_slice(&payload).unwrap(),
+                        params["tag"].as_u64().unwrap(),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    fn methods(frames: &[String]) -> Vec<String> {
+        frames
+            .iter()
+            .map(|frame| serde_json::from_str::<Value>(frame).unwrap())
+            .map(|frame| frame["method"].as_str().unwrap_or("result").to_string())
+            .collect()
+    }
+
+    fn client() -> WalletClient {
+        WalletClient::new(Metadata::new("KhodPay", "https://khodpay.com"))
+    }
+
+    /// Pairs, approves BSC for `signer` and returns the settled session.
+    fn connect(client: &mut WalletClient, dapp: &mut Dapp, signer: &Bip44Signer) -> Session {
+        client.pair(&dapp.uri()).unwrap();
+        let subscribe: Value = serde_json::from_str(&client.take_outgoing()[0]).unwrap();
+        client
+            .handle_frame(
+                &json!({ "id": subscribe["id"], "jsonrpc": "2.0", "result": "pairing-sub" })
+                    .to_string(),
+            )
+            .unwrap();
+
+        let proposal = match client
+            .handle_frame(&dapp.propose(json!({ "eip155": { "chains": ["eip155:56"], "methods": ["personal_sign"], "events": [] } })))
+            .unwrap()
+        {
+            Some(Event::SessionProposal(proposal)) => proposal,
+            other => panic!("expected a proposal, got {:?}", other),
+        };
+        assert_eq!(proposal.proposer.name, "Swap");
+        assert_eq!(methods(&client.take_outgoing()), ["result"]);
+
+        let session = client
+            .approve_session(proposal.id, &[56], &[signer.address()])
+            .unwrap();
+        let frames = client.take_outgoing();
+        assert_eq!(
+            methods(&frames),
+            ["irn_subscribe", "irn_publish", "irn_publish"]
+        );
+        let subscribe: Value = serde_json::from_str(&frames[0]).unwrap();
+        client
+            .handle_frame(
+                &json!({ "id": subscribe["id"], "jsonrpc": "2.0", "result": "session-sub" })
+                    .to_string(),
+            )
+            .unwrap();
+
+        // The proposal response carries the wallet's key; the dApp derives the same topic.
+        let published = dapp.read(&frames[1..2]);
+        let (topic, response, tag) = &published[0];
+        assert_eq!(*topic, dapp.uri().topic);
+        assert_eq!(*tag, 1101);
+        assert_eq!(response["id"], 1);
+        dapp.session_key = Some(
+            dapp.key_pair
+                .shared_key(response["result"]["responderPublicKey"].as_str().unwrap())
+                .unwrap(),
+        );
+        assert_eq!(dapp.session_topic(), session.topic);
+
+        let published = dapp.read(&frames[2..]);
+        let (_, settle, tag) = &published[0];
+        assert_eq!(*tag, 1102);
+        assert_eq!(settle["method"], "wc_sessionSettle");
+        assert_eq!(
+            settle["params"]["namespaces"]["eip155"]["accounts"],
+            json!([format!("eip155:56:{}", signer.address())])
+        );
+        assert_eq!(
+            settle["params"]["controller"]["metadata"]["name"],
+            "KhodPay"
+        );
+
+        let ack =
+            dapp.session_message(json!({ "id": settle["id"], "jsonrpc": "2.0", "result": true }));
+        assert_eq!(
+            client.handle_frame(&ack).unwrap(),
+            Some(Event::SessionSettled(session.clone()))
+        );
+        client.take_outgoing();
+        session
+    }
+
+    #[test]
+    fn test_session_request_round_trip() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let (mut client, mut dapp) = (client(), Dapp::new());
+        let session = connect(&mut client, &mut dapp, &signer);
+        assert_eq!(client.sessions().count(), 1);
+
+        let frame = dapp.session_message(json!({
+            "id": 77,
+            "jsonrpc": "2.0",
+            "method": "wc_sessionRequest",
+            "params": {
+                "request": { "method": "personal_sign", "params": ["0x6869", signer.address().to_string()] },
+                "chainId": "eip155:56"
+            }
+        }));
+        let request = match client.handle_frame(&frame).unwrap() {
+            Some(Event::SessionRequest(request)) => request,
+            other => panic!("expected a request, got {:?}", other),
+        };
+        assert_eq!((request.id, request.chain_id), (77, 56));
+        assert_eq!(request.topic, session.topic);
+
+        let signature = request.sign(&signer).unwrap();
+        client.respond(&request, signature.clone()).unwrap();
+        let published = dapp.read(&client.take_outgoing());
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].1["result"], signature);
+        assert_eq!(published[0].2, 1109);
+        assert!(matches!(
+            client.respond(&request, Value::Null),
+            Err(Error::UnknownRequest(77))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_requests_rejected() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let (mut client, mut dapp) = (client(), Dapp::new());
+        connect(&mut client, &mut dapp, &signer);
+
+        for (chain, method, code) in [
+            ("eip155:56", "eth_sign", 5101),
+            ("eip155:1", "personal_sign", 5100),
+            ("eip155:56", "personal_sign", INVALID_PARAMS),
+        ] {
+            let frame = dapp.session_message(json!({
+                "id": 5,
+                "jsonrpc": "2.0",
+                "method": "wc_sessionRequest",
+                "params": { "request": { "method": method, "params": [] }, "chainId": chain }
+            }));
+            assert_eq!(client.handle_frame(&frame).unwrap(), None);
+            let outgoing = client.take_outgoing();
+            assert_eq!(methods(&outgoing), ["result", "irn_publish"]);
+            assert_eq!(dapp.read(&outgoing)[0].1["error"]["code"], code);
+        }
+    }
+
+    #[test]
+    fn test_ping_and_delete() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let (mut client, mut dapp) = (client(), Dapp::new());
+        let session = connect(&mut client, &mut dapp, &signer);
+
+        let ping = dapp.session_message(
+            json!({ "id": 9, "jsonrpc": "2.0", "method": "wc_sessionPing", "params": {} }),
+        );
+        assert_eq!(client.handle_frame(&ping).unwrap(), None);
+        let published = dapp.read(&client.take_outgoing());
+        assert_eq!(
+            (published[0].1["result"].clone(), published[0].2),
+            (json!(true), 1115)
+        );
+
+        let delete = dapp.session_message(json!({
+            "id": 10,
+            "jsonrpc": "2.0",
+            "method": "wc_sessionDelete",
+            "params": { "code": 6000, "message": "User disconnected." }
+        }));
+        assert_eq!(
+            client.handle_frame(&delete).unwrap(),
+            Some(Event::SessionDeleted {
+                topic: session.topic,
+                reason: "User disconnected.".to_string()
+            })
+        );
+        assert_eq!(
+            methods(&client.take_outgoing()),
+            ["result", "irn_publish", "irn_unsubscribe"]
+        );
+        assert_eq!(client.session(&session.topic), None);
+        assert!(matches!(
+            client.handle_frame(&dapp.session_message(
+                json!({ "id": 11, "jsonrpc": "2.0", "method": "wc_sessionPing", "params": {} })
+            )),
+            Err(Error::UnknownTopic(_))
+        ));
+    }
+
+    #[test]
+    fn test_disconnect_and_emit_event() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let (mut client, mut dapp) = (client(), Dapp::new());
+        let session = connect(&mut client, &mut dapp, &signer);
+
+        client
+            .emit_event(&session.topic, "chainChanged", json!(56), 56)
+            .unwrap();
+        client.disconnect(&session.topic).unwrap();
+        let outgoing = client.take_outgoing();
+        let published = dapp.read(&outgoing);
+        assert_eq!(published[0].1["params"]["event"]["name"], "chainChanged");
+        assert_eq!(published[0].2, 1110);
+        assert_eq!(published[1].1["params"]["code"], 6000);
+        assert_eq!(published[1].2, 1112);
+        assert_eq!(methods(&outgoing).last().unwrap(), "irn_unsubscribe");
+        assert!(matches!(
+            client.disconnect(&session.topic),
+            Err(Error::UnknownTopic(_))
+        ));
+    }
+
+    #[test]
+    fn test_reject_and_unsupported_proposals() {
+        let signer = Bip44Signer::from_private_key(&[1u8; 32]).unwrap();
+        let (mut client, mut dapp) = (client(), Dapp::new());
+        client.pair(&dapp.uri()).unwrap();
+
+        let frame = dapp.propose(
+            json!({ "solana": { "chains": ["solana:mainnet"], "methods": [], "events": [] } }),
+        );
+        let Some(Event::SessionProposal(proposal)) = client.handle_frame(&frame).unwrap() else {
+            panic!("expected a proposal");
+        };
+        assert!(matches!(
+            client.approve_session(proposal.id, &[56], &[signer.address()]),
+            Err(Error::UnsupportedNamespace(_))
+        ));
+        client.take_outgoing();
+
+        client.reject_session(proposal.id).unwrap();
+        let published = dapp.read(&client.take_outgoing());
+        assert_eq!(published[0].1["error"]["code"], 5000);
+        assert_eq!(published[0].2, 1101);
+        assert!(matches!(
+            client.reject_session(proposal.id),
+            Err(Error::UnknownRequest(_))
+        ));
+
+        let frame = dapp
+            .propose(json!({ "eip155": { "chains": ["eip155:1"], "methods": [], "events": [] } }));
+        client.handle_frame(&frame).unwrap();
+        assert!(matches!(
+            client.approve_session(1, &[56], &[signer.address()]),
+            Err(Error::UnsupportedNamespace(chain)) if chain == "eip155:1"
+        ));
+    }
+
+    #[test]
+    fn test_pair_and_resubscribe() {
+        let mut client = client();
+        let dapp = Dapp::new();
+        let mut uri = dapp.uri();
+        uri.expiry_timestamp = Some(1);
+        assert!(matches!(client.pair(&uri), Err(Error::InvalidUri(_))));
+
+        client.pair(&dapp.uri()).unwrap();
+        client.take_outgoing();
+        client.resubscribe();
+        assert_eq!(methods(&client.take_outgoing()), ["irn_subscribe"]);
+
+        let error = json!({ "id": 1, "jsonrpc": "2.0", "error": { "code": -32600, "message": "Invalid topic" } });
+        assert!(matches!(
+            client.handle_frame(&error.to_string()),
+            Err(Error::Relay { code: -32600, .. })
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+//! Topics, key agreement and message envelopes.
+//!
+//! Every pairing and session has a 32-byte symmetric key; its topic, the relay
+//! channel both peers subscribe to, is `sha256(key)`. Session keys come from an
+//! X25519 exchange between the wallet and the dApp, expanded with HKDF-SHA256.
+//! Payloads travel as base64 type 0 envelopes: `0x00 ‖ iv ‖ ChaCha20-Poly1305(payload)`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{Error, Result};
+
+/// Envelope type of messages sealed with a key both peers already share.
+const ENVELOPE_TYPE_0: u8 = 0;
+
+/// Length of the ChaCha20-Poly1305 nonce.
+const IV_LENGTH: usize = 12;
+
+/// A relay topic: the hex encoded channel ID a pairing or session publishes on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Topic([u8; 32]);
+
+impl Topic {
+    /// Returns the topic of a symmetric key, `sha256(key)`.
+    pub fn from_key(key: &[u8; 32]) -> Self {
+        Self(Sha256::digest(key).into())
+    }
+
+    /// Returns the raw topic bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl FromStr for Topic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(Self(parse_key(s).map_err(|_| {
+            Error::InvalidMessage(format!("invalid topic '{}'", s))
+        })?))
+    }
+}
+
+/// An X25519 key pair used to agree on a session key.
+pub(crate) struct KeyPair {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl KeyPair {
+    /// Generates a key pair from the OS random number generator.
+    pub(crate) fn generate() -> Self {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    /// Returns the public key as hex, as sent to the peer.
+    pub(crate) fn public_key_hex(&self) -> String {
+        hex::encode(self.public.as_bytes())
+    }
+
+    /// Derives the symmetric key shared with the holder of `peer_public_key`.
+    ///
+    /// Low-order peer keys are refused: they force a shared secret anyone can
+    /// compute, whatever our secret key.
+    pub(crate) fn shared_key(&self, peer_public_key: &str) -> Result<[u8; 32]> {
+        let peer = PublicKey::from(
+            parse_key(peer_public_key)
+                .map_err(|_| Error::Crypto(format!("invalid public key '{}'", peer_public_key)))?,
+        );
+        let shared = self.secret.diffie_hellman(&peer);
+        if !shared.was_contributory() {
+            return Err(Error::Crypto(format!(
+                "public key '{}' is a low-order point",
+                peer_public_key
+            )));
+        }
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, shared.as_bytes())
+            .expand(&[], &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        Ok(key)
+    }
+}
+
+/// Parses a 32-byte hex key.
+pub(crate) fn parse_key(s: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(s).map_err(|e| Error::Crypto(e.to_string()))?;
+    bytes
+        .try_into()
+        .map_err(|_| Error::Crypto("expected 32 bytes".to_string()))
+}
+
+/// Returns 32 bytes from the OS random number generator.
+pub(crate) fn random_bytes() -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Encrypts `payload` into a base64 type 0 envelope.
+pub(crate) fn seal(key: &[u8; 32], payload: &[u8]) -> String {
+    let mut iv = [0u8; IV_LENGTH];
+    OsRng.fill_bytes(&mut iv);
+    let sealed = ChaCha20Poly1305::new(key.into())
+        .encrypt(Nonce::from_slice(&iv), payload)
+        .expect("encrypting into a Vec cannot fail");
+
+    let mut envelope = Vec::with_capacity(1 + IV_LENGTH + sealed.len());
+    envelope.push(ENVELOPE_TYPE_0);
+    envelope.extend_from_slice(&iv);
+    envelope.extend_from_slice(&sealed);
+    STANDARD.encode(envelope)
+}
+
+/// Decrypts a base64 type 0 envelope.
+pub(crate) fn open(key: &[u8; 32], message: &str) -> Result<Vec<u8>> {
+    let envelope = STANDARD
+        .decode(message)
+        .map_err(|e| Error::Crypto(format!("invalid envelope: {}", e)))?;
+    match envelope.first() {
+        Some(&ENVELOPE_TYPE_0) if envelope.len() > 1 + IV_LENGTH => {}
+        Some(&ENVELOPE_TYPE_0) | None => {
+            return Err(Error::Crypto("truncated envelope".to_string()))
+        }
+        Some(other) => {
+            return Err(Error::Crypto(format!(
+                "unsupported envelope type {}",
+                other
+            )))
+        }
+    }
+    let (iv, sealed) = envelope[1..].split_at(IV_LENGTH);
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(iv), sealed)
+        .map_err(|_| Error::Crypto("message authentication failed".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_from_key() {
+        // sha256 of 32 zero bytes
+        let topic = Topic::from_key(&[0u8; 32]);
+        assert_eq!(
+            topic.to_string(),
+            "66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925"
+        );
+        assert_eq!(topic.to_string().parse::<Topic>().unwrap(), topic);
+        assert!("abcd".parse::<Topic>().is_err());
+    }
+
+    #[test]
+    fn test_key_agreement() {
+        let wallet = KeyPair::generate();
+        let dapp = KeyPair::generate();
+        assert_eq!(
+            wallet.shared_key(&dapp.public_key_hex()).unwrap(),
+            dapp.shared_key(&wallet.public_key_hex()).unwrap()
+        );
+        assert!(wallet.shared_key("00").is_err());
+        assert!(matches!(
+            wallet.shared_key(&"00".repeat(32)),
+            Err(Error::Crypto(_))
+        ));
+        // So is u = 1, a point of order 4
+        assert!(wallet
+            .shared_key(&format!("01{}", "00".repeat(31)))
+            .is_err());
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = random_bytes();
+        let envelope = seal(&key, b"{\"id\":1}");
+        assert_eq!(STANDARD.decode(&envelope).unwrap()[0], ENVELOPE_TYPE_0);
+        assert_eq!(open(&key, &envelope).unwrap(), b"{\"id\":1}");
+
+        assert!(matches!(
+            open(&random_bytes(), &envelope),
+            Err(Error::Crypto(_))
+        ));
+        assert!(matches!(open(&key, "AQ=="), Err(Error::Crypto(_))));
+        assert!(matches!(open(&key, "not base64!"), Err(Error::Crypto(_))));
+    }
+}
@@ -0,0 +1,92 @@
+//! Error types for the WalletConnect crate.
+
+use khodpay_signing::Address;
+use thiserror::Error;
+
+/// Errors that can occur while talking to dApps over WalletConnect.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The pairing URI is malformed or not a WalletConnect v2 URI.
+    #[error("Invalid pairing URI: {0}")]
+    InvalidUri(String),
+
+    /// A message could not be encrypted or decrypted.
+    #[error("Crypto error: {0}")]
+    Crypto(String),
+
+    /// The relay or the dApp sent a message this crate cannot interpret.
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+
+    /// The relay answered a request with an error.
+    #[error("Relay error {code}: {message}")]
+    Relay {
+        /// JSON-RPC error code.
+        code: i64,
+        /// Error description.
+        message: String,
+    },
+
+    /// No pairing or session uses the topic.
+    #[error("Unknown topic: {0}")]
+    UnknownTopic(String),
+
+    /// No pending proposal or request has the ID.
+    #[error("Unknown request: {0}")]
+    UnknownRequest(u64),
+
+    /// The dApp requires a namespace or chain the wallet does not approve.
+    #[error("Unsupported namespace: {0}")]
+    UnsupportedNamespace(String),
+
+    /// The dApp asked an account other than the signer's to sign.
+    #[error("Request is for account {requested}, signer is {signer}")]
+    AccountMismatch {
+        /// Account named in the request.
+        requested: Address,
+        /// Address of the signer.
+        signer: Address,
+    },
+
+    /// Typed data names a chain other than the one the request was made on.
+    #[error("Request is for chain {requested}, typed data domain is for chain {domain}")]
+    ChainMismatch {
+        /// Chain the session request targets.
+        requested: u64,
+        /// `chainId` of the typed data domain.
+        domain: u64,
+    },
+
+    /// The request cannot be answered by signing alone.
+    #[error("Unsupported request: {0}")]
+    Unsupported(String),
+
+    /// Error from EVM signing.
+    #[error("Signing error: {0}")]
+    SigningError(#[from] khodpay_signing::Error),
+}
+
+/// Result type alias for WalletConnect operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::Relay {
+                code: -32600,
+                message: "Invalid payload".to_string()
+            }
+            .to_string(),
+            "Relay error -32600: Invalid payload"
+        );
+        assert_eq!(Error::UnknownRequest(7).to_string(), "Unknown request: 7");
+        assert_eq!(
+            Error::UnsupportedNamespace("solana".to_string()).to_string(),
+            "Unsupported namespace: solana"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! # Khodpay WalletConnect
+//!
+//! The wallet side of [WalletConnect v2](https://specs.walletconnect.com/2.0): pair
+//! with a dApp from its `wc:` URI, approve a session for EVM accounts, and answer the
+//! dApp's `eth_sendTransaction`, `eth_signTypedData_v4` and `personal_sign` requests
+//! with `khodpay-signing`.
+//!
+//! The [`WalletClient`] is a state machine and does no I/O of its own: the host app
+//! owns the relay websocket, feeds it the frames it receives and sends the frames it
+//! produces, so any async runtime or platform socket works.
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`WalletClient`] | Pairings, sessions and the relay protocol |
+//! | [`PairingUri`] | `wc:` pairing URIs from QR codes and deep links |
+//! | [`Event`] | Proposals, requests and session changes to act on |
+//! | [`WalletRequest`] | Decoded dApp requests, signed with a `Bip44Signer` |
+//! | [`RelayAuth`] | The client's relay identity and connection URL |
+//!
+//! ## Features
+//!
+//! - **Pairing**: Parse and validate v2 pairing URIs
+//! - **Sessions**: X25519 key agreement and ChaCha20-Poly1305 envelopes, `eip155`
+//!   namespaces with the wallet's accounts and chains
+//! - **Signing Requests**: `personal_sign` and EIP-712 typed data signed in one call;
+//!   `eth_sendTransaction` decoded into an EIP-1559 transaction builder
+//! - **Housekeeping**: Pings answered and unsupported methods or chains rejected
+//!   automatically
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use khodpay_signing::Bip44Signer;
+//! use khodpay_walletconnect::{Event, Metadata, WalletClient};
+//!
+//! # fn receive() -> String { unimplemented!() }
+//! # fn send(_frame: String) {}
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let signer = Bip44Signer::from_private_key(&[1u8; 32])?;
+//! let mut client = WalletClient::new(Metadata::new("KhodPay", "https://khodpay.com"));
+//! // Connect a websocket to `client.connection_url("<project id>")`
+//!
+//! client.pair(&"wc:…@2?relay-protocol=irn&symKey=…".parse()?)?;
+//! loop {
+//!     for frame in client.take_outgoing() {
+//!         send(frame);
+//!     }
+//!     match client.handle_frame(&receive())? {
+//!         Some(Event::SessionProposal(proposal)) => {
+//!             client.approve_session(proposal.id, &[56], &[signer.address()])?;
+//!         }
+//!         Some(Event::SessionRequest(request)) => {
+//!             let signature = request.sign(&signer)?;
+//!             client.respond(&request, signature)?;
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod client;
+mod crypto;
+mod error;
+mod relay;
+mod request;
+mod session;
+mod uri;
+
+pub use client::WalletClient;
+pub use crypto::Topic;
+pub use error::{Error, Result};
+pub use relay::{RelayAuth, DEFAULT_RELAY_URL};
+pub use request::{SessionRequest, TransactionRequest, WalletRequest};
+pub use session::{
+    Event, Metadata, ProposalNamespace, Session, SessionProposal, EIP155, SUPPORTED_EVENTS,
+    SUPPORTED_METHODS,
+};
+pub use uri::PairingUri;
@@ -0,0 +1,315 @@
+//! The WalletConnect relay: connection auth and `irn_*` JSON-RPC frames.
+//!
+//! The relay is a publish/subscribe server reached over a websocket. Clients
+//! authenticate with a short-lived JWT signed by an Ed25519 key whose `did:key`
+//! identifies them, then exchange JSON-RPC text frames.
+
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::{json, Value};
+
+use crate::crypto::random_bytes;
+use crate::{Error, Result, Topic};
+
+/// URL of the public WalletConnect relay.
+pub const DEFAULT_RELAY_URL: &str = "wss://relay.walletconnect.org";
+
+/// Lifetime of a connection token, in seconds.
+const AUTH_TOKEN_TTL: u64 = 86_400;
+
+/// Multicodec prefix of an Ed25519 public key in a `did:key`.
+const MULTICODEC_ED25519: [u8; 2] = [0xed, 0x01];
+
+/// The Ed25519 identity a client presents to the relay.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_walletconnect::{RelayAuth, DEFAULT_RELAY_URL};
+///
+/// let auth = RelayAuth::from_seed([7u8; 32]);
+/// let url = auth.connection_url(DEFAULT_RELAY_URL, "my-project-id", 1_700_000_000);
+///
+/// assert!(url.starts_with("wss://relay.walletconnect.org/?auth=ey"));
+/// assert!(url.ends_with("&projectId=my-project-id"));
+/// ```
+#[derive(Clone)]
+pub struct RelayAuth {
+    key: SigningKey,
+}
+
+impl RelayAuth {
+    /// Generates a new identity from the OS random number generator.
+    pub fn generate() -> Self {
+        Self::from_seed(random_bytes())
+    }
+
+    /// Restores an identity from its 32-byte seed.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Returns the seed, to persist the identity across restarts.
+    pub fn seed(&self) -> [u8; 32] {
+        self.key.to_bytes()
+    }
+
+    /// Returns the `did:key` of the identity.
+    pub fn did_key(&self) -> String {
+        let mut multicodec = MULTICODEC_ED25519.to_vec();
+        multicodec.extend_from_slice(self.key.verifying_key().as_bytes());
+        format!("did:key:z{}", bs58::encode(multicodec).into_string())
+    }
+
+    /// Returns a JWT authorizing a connection to `relay_url` from unix time `now`.
+    pub fn auth_token(&self, relay_url: &str, now: u64) -> String {
+        let header = json!({ "alg": "EdDSA", "typ": "JWT" });
+        let claims = json!({
+            "iss": self.did_key(),
+            "sub": hex::encode(random_bytes()),
+            "aud": relay_url,
+            "iat": now,
+            "exp": now + AUTH_TOKEN_TTL,
+        });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = self.key.sign(signing_input.as_bytes());
+        format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    /// Returns the websocket URL to open, with a fresh token and the WalletConnect
+    /// Cloud `project_id`.
+    pub fn connection_url(&self, relay_url: &str, project_id: &str, now: u64) -> String {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("auth", &self.auth_token(relay_url, now))
+            .append_pair("projectId", project_id)
+            .finish();
+        format!("{}/?{}", relay_url.trim_end_matches('/'), query)
+    }
+}
+
+impl fmt::Debug for RelayAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RelayAuth")
+            .field("did_key", &self.did_key())
+            .finish()
+    }
+}
+
+/// Relay tags and TTL in seconds of a WalletConnect method:
+/// `(request tag, response tag, ttl)`.
+pub(crate) fn method_tags(method: &str) -> Option<(u32, u32, u64)> {
+    Some(match method {
+        "wc_pairingDelete" => (1000, 1001, 86_400),
+        "wc_pairingPing" => (1002, 1003, 30),
+        "wc_sessionPropose" => (1100, 1101, 300),
+        "wc_sessionSettle" => (1102, 1103, 300),
+        "wc_sessionUpdate" => (1104, 1105, 86_400),
+        "wc_sessionExtend" => (1106, 1107, 86_400),
+        "wc_sessionRequest" => (1108, 1109, 300),
+        "wc_sessionEvent" => (1110, 1111, 300),
+        "wc_sessionDelete" => (1112, 1113, 86_400),
+        "wc_sessionPing" => (1114, 1115, 30),
+        _ => return None,
+    })
+}
+
+/// Encodes an `irn_subscribe` request.
+pub(crate) fn subscribe(id: u64, topic: &Topic) -> String {
+    request(id, "irn_subscribe", json!({ "topic": topic.to_string() }))
+}
+
+/// Encodes an `irn_unsubscribe` request.
+pub(crate) fn unsubscribe(id: u64, topic: &Topic, subscription_id: &str) -> String {
+    request(
+        id,
+        "irn_unsubscribe",
+        json!({ "topic": topic.to_string(), "id": subscription_id }),
+    )
+}
+
+/// Encodes an `irn_publish` request.
+pub(crate) fn publish(id: u64, topic: &Topic, message: &str, ttl: u64, tag: u32) -> String {
+    request(
+        id,
+        "irn_publish",
+        json!({
+            "topic": topic.to_string(),
+            "message": message,
+            "ttl": ttl,
+            "tag": tag,
+        }),
+    )
+}
+
+/// Encodes the acknowledgement of an `irn_subscription` delivery.
+pub(crate) fn ack(id: u64) -> String {
+    json!({ "id": id, "jsonrpc": "2.0", "result": true }).to_string()
+}
+
+fn request(id: u64, method: &str, params: Value) -> String {
+    json!({ "id": id, "jsonrpc": "2.0", "method": method, "params": params }).to_string()
+}
+
+/// A frame received from the relay.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Incoming {
+    /// A message published on a subscribed topic.
+    Message {
+        /// ID to acknowledge.
+        id: u64,
+        /// Topic the message was published on.
+        topic: Topic,
+        /// The encrypted envelope.
+        message: String,
+    },
+    /// The result of one of our requests.
+    Result {
+        /// ID of the request.
+        id: u64,
+        /// The result.
+        result: Value,
+    },
+    /// One of our requests failed.
+    Error {
+        /// ID of the request.
+        id: u64,
+        /// JSON-RPC error code.
+        code: i64,
+        /// Error description.
+        message: String,
+    },
+}
+
+/// Parses a text frame from the relay.
+pub(crate) fn parse(frame: &str) -> Result<Incoming> {
+    let invalid = |message: &str| Error::InvalidMessage(format!("{}: {}", message, frame));
+    let value: Value = serde_json::from_str(frame).map_err(|_| invalid("not JSON"))?;
+    let id = value["id"].as_u64().ok_or_else(|| invalid("missing id"))?;
+
+    if let Some(method) = value["method"].as_str() {
+        if method != "irn_subscription" {
+            return Err(invalid("unexpected method"));
+        }
+        let data = &value["params"]["data"];
+        let topic = data["topic"]
+            .as_str()
+            .and_then(|topic| topic.parse().ok())
+            .ok_or_else(|| invalid("missing topic"))?;
+        let message = data["message"]
+            .as_str()
+            .ok_or_else(|| invalid("missing message"))?
+            .to_string();
+        return Ok(Incoming::Message { id, topic, message });
+    }
+
+    if let Some(error) = value.get("error") {
+        return Ok(Incoming::Error {
+            id,
+            code: error["code"].as_i64().unwrap_or_default(),
+            message: error["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    match value.get("result") {
+        Some(result) => Ok(Incoming::Result {
+            id,
+            result: result.clone(),
+        }),
+        None => Err(invalid("missing result")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    #[test]
+    fn test_did_key() {
+        // RFC 8032 test 1 key pair
+        let seed = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let auth = RelayAuth::from_seed(seed.try_into().unwrap());
+        let did = auth.did_key();
+        assert!(did.starts_with("did:key:z6Mk"));
+
+        let decoded = bs58::decode(&did["did:key:z".len()..]).into_vec().unwrap();
+        assert_eq!(decoded[..2], MULTICODEC_ED25519);
+        assert_eq!(
+            hex::encode(&decoded[2..]),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert_eq!(RelayAuth::from_seed(auth.seed()).did_key(), did);
+    }
+
+    #[test]
+    fn test_auth_token() {
+        let auth = RelayAuth::from_seed([7u8; 32]);
+        let token = auth.auth_token(DEFAULT_RELAY_URL, 1_700_000_000);
+        let parts: Vec<&str> = token.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        let claims: Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[1]).unwrap()).unwrap();
+        assert_eq!(claims["iss"], auth.did_key());
+        assert_eq!(claims["aud"], DEFAULT_RELAY_URL);
+        assert_eq!(claims["exp"], 1_700_000_000 + AUTH_TOKEN_TTL);
+
+        let key = VerifyingKey::from_bytes(auth.key.verifying_key().as_bytes()).unwrap();
+        let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(parts[2]).unwrap()).unwrap();
+        let signing_input = format!("{}.{}", parts[0], parts[1]);
+        assert!(key.verify(signing_input.as_bytes(), &signature).is_ok());
+    }
+
+    #[test]
+    fn test_frames() {
+        let topic = Topic::from_key(&[1u8; 32]);
+        let frame: Value = serde_json::from_str(&publish(9, &topic, "AA==", 300, 1101)).unwrap();
+        assert_eq!(frame["method"], "irn_publish");
+        assert_eq!(frame["params"]["topic"], topic.to_string());
+        assert_eq!(frame["params"]["tag"], 1101);
+
+        let delivery = json!({
+            "id": 42,
+            "jsonrpc": "2.0",
+            "method": "irn_subscription",
+            "params": {
+                "id": "sub",
+                "data": { "topic": topic.to_string(), "message": "AA==", "publishedAt": 1, "tag": 1100 }
+            }
+        });
+        assert_eq!(
+            parse(&delivery.to_string()).unwrap(),
+            Incoming::Message {
+                id: 42,
+                topic,
+                message: "AA==".to_string()
+            }
+        );
+        assert_eq!(
+            parse(r#"{"id":3,"jsonrpc":"2.0","result":"abc"}"#).unwrap(),
+            Incoming::Result {
+                id: 3,
+                result: json!("abc")
+            }
+        );
+        assert!(matches!(
+            parse(r#"{"id":3,"jsonrpc":"2.0","error":{"code":-32600,"message":"bad"}}"#).unwrap(),
+            Incoming::Error { code: -32600, .. }
+        ));
+        assert!(parse(r#"{"jsonrpc":"2.0","result":true}"#).is_err());
+        assert!(parse("ping").is_err());
+    }
+}
@@ -0,0 +1,465 @@
+//! Signing requests from dApps.
+
+use khodpay_signing::eip712::{sign_typed_data_json, TypedData};
+use khodpay_signing::{
+    Address, Bip44Signer, ChainId, Eip1559Transaction, Eip1559TransactionBuilder, Signature, Wei,
+    U256,
+};
+use serde_json::Value;
+
+use crate::{Error, Result, Topic};
+
+/// A `wc_sessionRequest` the wallet must respond to or reject.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRequest {
+    /// ID to respond with.
+    pub id: u64,
+    /// Topic of the session.
+    pub topic: Topic,
+    /// EVM chain the dApp targets.
+    pub chain_id: u64,
+    /// The JSON-RPC method, such as `eth_signTypedData_v4`.
+    pub method: String,
+    /// The decoded request.
+    pub request: WalletRequest,
+}
+
+impl SessionRequest {
+    /// Signs the request for the chain it targets, as [`WalletRequest::sign`].
+    ///
+    /// # Errors
+    ///
+    /// As [`WalletRequest::sign`].
+    pub fn sign(&self, signer: &Bip44Signer) -> Result<Value> {
+        self.request.sign(signer, self.chain_id)
+    }
+}
+
+/// A decoded dApp request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalletRequest {
+    /// `eth_sendTransaction`: sign and broadcast a transaction, answering with its hash.
+    SendTransaction(TransactionRequest),
+    /// `eth_signTypedData` / `eth_signTypedData_v4`: sign EIP-712 typed data.
+    SignTypedData {
+        /// Account asked to sign.
+        address: Address,
+        /// The payload.
+        typed_data: TypedData,
+    },
+    /// `personal_sign`: sign an EIP-191 message.
+    PersonalSign {
+        /// Account asked to sign.
+        address: Address,
+        /// The message bytes.
+        message: Vec<u8>,
+    },
+}
+
+impl WalletRequest {
+    /// Decodes the params of `method`, returning `None` for methods this crate does
+    /// not handle.
+    pub(crate) fn parse(method: &str, params: &Value) -> Result<Option<Self>> {
+        let param = |index: usize| {
+            params.get(index).ok_or_else(|| {
+                Error::InvalidMessage(format!("{} is missing parameter {}", method, index))
+            })
+        };
+        Ok(Some(match method {
+            "eth_sendTransaction" => Self::SendTransaction(TransactionRequest::parse(param(0)?)?),
+            "eth_signTypedData" | "eth_signTypedData_v4" => Self::SignTypedData {
+                address: parse_address(param(0)?)?,
+                typed_data: TypedData::from_value(param(1)?.clone())?,
+            },
+            "personal_sign" => {
+                // Some dApps send `[address, message]` instead of `[message, address]`.
+                let (message, address) = match parse_address(param(1)?) {
+                    Ok(address) => (param(0)?, address),
+                    Err(error) => match parse_address(param(0)?) {
+                        Ok(address) => (param(1)?, address),
+                        Err(_) => return Err(error),
+                    },
+                };
+                let message = message.as_str().ok_or_else(|| {
+                    Error::InvalidMessage("personal_sign message must be a string".to_string())
+                })?;
+                Self::PersonalSign {
+                    address,
+                    message: decode_message(message),
+                }
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Returns the account the dApp asks to sign with.
+    pub fn address(&self) -> Address {
+        match self {
+            Self::SendTransaction(tx) => tx.from,
+            Self::SignTypedData { address, .. } | Self::PersonalSign { address, .. } => *address,
+        }
+    }
+
+    /// Signs a `personal_sign` or typed data request made on `chain_id` and
+    /// returns the result to [`respond`](crate::WalletClient::respond) with: the
+    /// 65-byte signature as hex, with `v` as 27 or 28.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::AccountMismatch`] if the request names another account,
+    /// [`Error::ChainMismatch`] if typed data names a chain other than `chain_id`
+    /// in its domain, [`Error::Unsupported`] for `eth_sendTransaction`, which needs a nonce, fees
+    /// and a broadcast (see [`TransactionRequest::to_builder`]), or
+    /// [`Error::SigningError`] if signing fails.
+    pub fn sign(&self, signer: &Bip44Signer, chain_id: u64) -> Result<Value> {
+        check_account(self.address(), signer)?;
+        let signature = match self {
+            Self::SendTransaction(_) => {
+                return Err(Error::Unsupported(
+                    "eth_sendTransaction must be built, signed and broadcast".to_string(),
+                ))
+            }
+            Self::SignTypedData { typed_data, .. } => {
+                if let Some(domain) = typed_data.chain_id()? {
+                    if domain != chain_id {
+                        return Err(Error::ChainMismatch {
+                            requested: chain_id,
+                            domain,
+                        });
+                    }
+                }
+                sign_typed_data_json(signer, typed_data)?
+            }
+            Self::PersonalSign { message, .. } => signer.sign_message(message)?,
+        };
+        Ok(Value::String(signature_hex(&signature)))
+    }
+}
+
+/// The transaction of an `eth_sendTransaction` request.
+///
+/// dApps may leave out the nonce, gas and fees; the wallet fills them in before
+/// signing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRequest {
+    /// Sending account.
+    pub from: Address,
+    /// Recipient, or `None` to deploy a contract.
+    pub to: Option<Address>,
+    /// Amount to transfer.
+    pub value: Wei,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Gas limit.
+    pub gas: Option<u64>,
+    /// Nonce.
+    pub nonce: Option<u64>,
+    /// EIP-1559 fee cap.
+    pub max_fee_per_gas: Option<Wei>,
+    /// EIP-1559 priority fee.
+    pub max_priority_fee_per_gas: Option<Wei>,
+    /// Legacy gas price, used as both EIP-1559 fees when they are absent.
+    pub gas_price: Option<Wei>,
+}
+
+impl TransactionRequest {
+    fn parse(tx: &Value) -> Result<Self> {
+        let field = |name: &str| tx.get(name).filter(|value| !value.is_null());
+        let quantity = |name: &str| field(name).map(parse_quantity).transpose();
+        let small = |name: &str| {
+            quantity(name)?
+                .map(|value| {
+                    (value <= U256::from(u64::MAX))
+                        .then(|| value.low_u64())
+                        .ok_or_else(|| Error::InvalidMessage(format!("{} is too large", name)))
+                })
+                .transpose()
+        };
+        let data = match field("data").or_else(|| field("input")) {
+            Some(data) => decode_hex(data)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            from: parse_address(
+                field("from")
+                    .ok_or_else(|| Error::InvalidMessage("transaction has no from".to_string()))?,
+            )?,
+            to: field("to").map(parse_address).transpose()?,
+            value: quantity("value")?.map(Wei::from).unwrap_or(Wei::ZERO),
+            data,
+            gas: match small("gas")? {
+                Some(gas) => Some(gas),
+                None => small("gasLimit")?,
+            },
+            nonce: small("nonce")?,
+            max_fee_per_gas: quantity("maxFeePerGas")?.map(Wei::from),
+            max_priority_fee_per_gas: quantity("maxPriorityFeePerGas")?.map(Wei::from),
+            gas_price: quantity("gasPrice")?.map(Wei::from),
+        })
+    }
+
+    /// Returns a builder with every field the dApp supplied.
+    ///
+    /// Fill in the rest, for example with
+    /// [`fee_suggestion`](Eip1559TransactionBuilder::fee_suggestion), then sign the
+    /// transaction, broadcast it and respond with its hash.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use khodpay_signing::{Bip44Signer, ChainId, SignedTransaction, Wei};
+    /// use khodpay_walletconnect::{SessionRequest, WalletRequest};
+    ///
+    /// # fn pending_nonce() -> u64 { 0 }
+    /// fn sign(request: &SessionRequest, signer: &Bip44Signer) -> Option<SignedTransaction> {
+    ///     let WalletRequest::SendTransaction(tx) = &request.request else {
+    ///         return None;
+    ///     };
+    ///     let mut builder = tx.to_builder(ChainId::from(request.chain_id));
+    ///     if tx.nonce.is_none() {
+    ///         builder = builder.nonce(pending_nonce());
+    ///     }
+    ///     let tx = builder
+    ///         .max_priority_fee_per_gas(Wei::from_gwei(1))
+    ///         .max_fee_per_gas(Wei::from_gwei(5))
+    ///         .gas_limit(21_000)
+    ///         .build()
+    ///         .ok()?;
+    ///     let signature = signer.sign_transaction(&tx).ok()?;
+    ///     Some(SignedTransaction::new(tx, signature))
+    /// }
+    /// ```
+    pub fn to_builder(&self, chain_id: ChainId) -> Eip1559TransactionBuilder {
+        let mut builder = Eip1559Transaction::builder()
+            .chain_id(chain_id)
+            .value(self.value)
+            .data(self.data.clone());
+        if let Some(to) = self.to {
+            builder = builder.to(to);
+        }
+        if let Some(gas) = self.gas {
+            builder = builder.gas_limit(gas);
+        }
+        if let Some(nonce) = self.nonce {
+            builder = builder.nonce(nonce);
+        }
+        if let Some(fee) = self.max_fee_per_gas.or(self.gas_price) {
+            builder = builder.max_fee_per_gas(fee);
+        }
+        if let Some(fee) = self.max_priority_fee_per_gas.or(self.gas_price) {
+            builder = builder.max_priority_fee_per_gas(fee);
+        }
+        builder
+    }
+}
+
+/// Checks that `signer` holds the account a request names.
+pub(crate) fn check_account(requested: Address, signer: &Bip44Signer) -> Result<()> {
+    if requested == signer.address() {
+        Ok(())
+    } else {
+        Err(Error::AccountMismatch {
+            requested,
+            signer: signer.address(),
+        })
+    }
+}
+
+/// Encodes a signature as `0x ‖ r ‖ s ‖ v` with `v` as 27 or 28.
+fn signature_hex(signature: &Signature) -> String {
+    let mut bytes = signature.to_bytes();
+    bytes[64] += 27;
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn parse_address(value: &Value) -> Result<Address> {
+    value
+        .as_str()
+        .ok_or_else(|| Error::InvalidMessage(format!("expected an address, got {}", value)))?
+        .parse()
+        .map_err(Error::from)
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| Error::InvalidMessage(format!("expected hex, got {}", value)))?;
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| Error::InvalidMessage(format!("invalid hex '{}': {}", s, e)))
+}
+
+/// Parses a JSON-RPC quantity: a `0x` hex string, or a plain number.
+fn parse_quantity(value: &Value) -> Result<U256> {
+    let invalid = || Error::InvalidMessage(format!("invalid quantity {}", value));
+    match value {
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some("") => Ok(U256::zero()),
+            Some(digits) => U256::from_str_radix(digits, 16).map_err(|_| invalid()),
+            None => U256::from_dec_str(s).map_err(|_| invalid()),
+        },
+        Value::Number(n) => n.as_u64().map(U256::from).ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// Decodes a `personal_sign` message: hex bytes, or UTF-8 text for dApps that send
+/// it unencoded.
+fn decode_message(message: &str) -> Vec<u8> {
+    message
+        .strip_prefix("0x")
+        .and_then(|digits| hex::decode(digits).ok())
+        .unwrap_or_else(|| message.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_signing::recover_signer;
+    use serde_json::json;
+
+    fn signer() -> Bip44Signer {
+        Bip44Signer::from_private_key(&[1u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_send_transaction() {
+        let request = WalletRequest::parse(
+            "eth_sendTransaction",
+            &json!([{
+                "from": "0x742d35Cc6634C0532925a3b844Bc454e4438f44e",
+                "to": "0x55d398326f99059fF775485246999027B3197955",
+                "value": "0xde0b6b3a7640000",
+                "input": "0xa9059cbb",
+                "gas": "0x5208",
+                "gasPrice": "0x12a05f200",
+                "nonce": null
+            }]),
+        )
+        .unwrap()
+        .unwrap();
+        let WalletRequest::SendTransaction(tx) = request else {
+            panic!("expected a transaction");
+        };
+        assert_eq!(tx.value, Wei::from_ether(1));
+        assert_eq!(tx.data, [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(tx.gas, Some(21_000));
+        assert_eq!(tx.nonce, None);
+
+        let built = tx.to_builder(ChainId::BscMainnet).nonce(3).build().unwrap();
+        assert_eq!(built.max_fee_per_gas, Wei::from_gwei(5));
+        assert_eq!(built.max_priority_fee_per_gas, Wei::from_gwei(5));
+        assert_eq!(built.to, tx.to);
+        assert!(tx.to_builder(ChainId::BscMainnet).build().is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(WalletRequest::parse("eth_sign", &json!([])).unwrap(), None);
+        assert!(matches!(
+            WalletRequest::parse("eth_sendTransaction", &json!([])),
+            Err(Error::InvalidMessage(_))
+        ));
+        assert!(WalletRequest::parse(
+            "eth_sendTransaction",
+            &json!([{ "from": "0x742d35Cc6634C0532925a3b844Bc454e4438f44e", "gas": "0x10000000000000000" }])
+        )
+        .is_err());
+        assert!(WalletRequest::parse("personal_sign", &json!(["0x68656c6c6f", "0x1234"])).is_err());
+    }
+
+    #[test]
+    fn test_personal_sign() {
+        let signer = signer();
+        let address = signer.address().to_string();
+        for params in [
+            json!(["0x68656c6c6f", address]),
+            json!([address, "0x68656c6c6f"]),
+            json!(["hello", address]),
+        ] {
+            let request = WalletRequest::parse("personal_sign", &params)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                request,
+                WalletRequest::PersonalSign {
+                    address: signer.address(),
+                    message: b"hello".to_vec()
+                }
+            );
+
+            let result = request.sign(&signer, 56).unwrap();
+            let bytes = hex::decode(&result.as_str().unwrap()[2..]).unwrap();
+            assert!(bytes[64] == 27 || bytes[64] == 28);
+            let mut signature = Signature::from_bytes(&bytes).unwrap();
+            signature.v -= 27;
+            let hash = khodpay_signing::eip191::hash_message(b"hello");
+            assert_eq!(recover_signer(&hash, &signature).unwrap(), signer.address());
+        }
+    }
+
+    #[test]
+    fn test_sign_typed_data_checks_account() {
+        let typed_data = json!({
+            "types": { "Greeting": [{ "name": "text", "type": "string" }] },
+            "primaryType": "Greeting",
+            "domain": { "name": "Example", "chainId": 56 },
+            "message": { "text": "gm" }
+        });
+        let signer = signer();
+        let request = WalletRequest::parse(
+            "eth_signTypedData_v4",
+            &json!([signer.address().to_string(), typed_data.to_string()]),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(request.sign(&signer, 56).unwrap().as_str().unwrap().len() == 132);
+
+        let other = Bip44Signer::from_private_key(&[2u8; 32]).unwrap();
+        assert!(matches!(
+            request.sign(&other, 56),
+            Err(Error::AccountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sign_typed_data_checks_chain() {
+        let signer = signer();
+        let typed_data = |domain: Value| {
+            json!({
+                "types": { "Greeting": [{ "name": "text", "type": "string" }] },
+                "primaryType": "Greeting",
+                "domain": domain,
+                "message": { "text": "gm" }
+            })
+        };
+        let parse = |typed_data: Value| {
+            WalletRequest::parse(
+                "eth_signTypedData_v4",
+                &json!([signer.address().to_string(), typed_data.to_string()]),
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let request = SessionRequest {
+            id: 1,
+            topic: Topic::from_key(&[0; 32]),
+            chain_id: 1,
+            method: "eth_signTypedData_v4".to_string(),
+            request: parse(typed_data(json!({ "name": "Example", "chainId": 56 }))),
+        };
+        assert!(matches!(
+            request.sign(&signer),
+            Err(Error::ChainMismatch {
+                requested: 1,
+                domain: 56
+            })
+        ));
+        assert!(request.request.sign(&signer, 56).is_ok());
+
+        // A domain without a chain ID signs on any chain
+        let request = parse(typed_data(json!({ "name": "Example" })));
+        assert!(request.sign(&signer, 1).is_ok());
+    }
+}
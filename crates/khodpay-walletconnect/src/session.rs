@@ -0,0 +1,251 @@
+//! Session proposals, sessions and the events a client reports.
+
+use std::collections::BTreeMap;
+
+use khodpay_signing::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, SessionRequest, Topic};
+
+/// The only namespace this crate approves: EVM chains, per CAIP-2.
+pub const EIP155: &str = "eip155";
+
+/// Methods the wallet offers in every session.
+pub const SUPPORTED_METHODS: [&str; 4] = [
+    "eth_sendTransaction",
+    "personal_sign",
+    "eth_signTypedData",
+    "eth_signTypedData_v4",
+];
+
+/// Events the wallet may emit in every session.
+pub const SUPPORTED_EVENTS: [&str; 2] = ["chainChanged", "accountsChanged"];
+
+/// Name, description, URL and icons of an app, shown to the other peer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// App name.
+    #[serde(default)]
+    pub name: String,
+    /// Short description.
+    #[serde(default)]
+    pub description: String,
+    /// Website URL.
+    #[serde(default)]
+    pub url: String,
+    /// Icon URLs.
+    #[serde(default)]
+    pub icons: Vec<String>,
+}
+
+impl Metadata {
+    /// Creates metadata with a name and URL.
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Chains, methods and events a dApp asks for in one namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalNamespace {
+    /// CAIP-2 chain IDs, such as `eip155:56`; `None` when the namespace key itself
+    /// names the chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chains: Option<Vec<String>>,
+    /// JSON-RPC methods.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Event names.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// A dApp's request to open a session, received after pairing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionProposal {
+    /// ID to approve or reject the proposal with.
+    pub id: u64,
+    /// Topic of the pairing the proposal arrived on.
+    pub pairing_topic: Topic,
+    /// The dApp.
+    pub proposer: Metadata,
+    /// Namespaces the session must include.
+    pub required_namespaces: BTreeMap<String, ProposalNamespace>,
+    /// Namespaces the session may include.
+    pub optional_namespaces: BTreeMap<String, ProposalNamespace>,
+    pub(crate) proposer_public_key: String,
+}
+
+impl SessionProposal {
+    /// Returns the EVM chain IDs the dApp requires.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedNamespace`] if the dApp requires a namespace other
+    /// than `eip155`, such as Solana.
+    pub fn required_chains(&self) -> Result<Vec<u64>> {
+        let mut chains = Vec::new();
+        for (key, namespace) in &self.required_namespaces {
+            for chain in namespace_chains(key, namespace) {
+                chains.push(parse_chain(&chain).ok_or(Error::UnsupportedNamespace(chain.clone()))?);
+            }
+        }
+        chains.sort_unstable();
+        chains.dedup();
+        Ok(chains)
+    }
+
+    /// Returns the EVM chain IDs the dApp would also use, skipping other namespaces.
+    pub fn optional_chains(&self) -> Vec<u64> {
+        let mut chains: Vec<u64> = self
+            .optional_namespaces
+            .iter()
+            .flat_map(|(key, namespace)| namespace_chains(key, namespace))
+            .filter_map(|chain| parse_chain(&chain))
+            .collect();
+        chains.sort_unstable();
+        chains.dedup();
+        chains
+    }
+}
+
+/// An approved session with a dApp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// Topic of the session.
+    pub topic: Topic,
+    /// Topic of the pairing the session was proposed on.
+    pub pairing_topic: Topic,
+    /// The dApp.
+    pub peer: Metadata,
+    /// EVM chain IDs the session covers.
+    pub chains: Vec<u64>,
+    /// Accounts exposed to the dApp.
+    pub accounts: Vec<Address>,
+    /// Unix time the session expires at.
+    pub expiry: u64,
+}
+
+impl Session {
+    /// Returns the CAIP-10 accounts of the session, such as `eip155:56:0x…`.
+    pub fn caip_accounts(&self) -> Vec<String> {
+        self.chains
+            .iter()
+            .flat_map(|chain| {
+                self.accounts
+                    .iter()
+                    .map(move |account| format!("{}:{}:{}", EIP155, chain, account))
+            })
+            .collect()
+    }
+}
+
+/// Something a [`WalletClient`](crate::WalletClient) needs the wallet to know about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A dApp wants to open a session; approve or reject it.
+    SessionProposal(SessionProposal),
+    /// The dApp acknowledged an approved session.
+    SessionSettled(Session),
+    /// A dApp asks the wallet to sign or send something; respond or reject it.
+    SessionRequest(SessionRequest),
+    /// The dApp ended a session, or refused to settle it.
+    SessionDeleted {
+        /// Topic of the session.
+        topic: Topic,
+        /// The reason given by the dApp.
+        reason: String,
+    },
+}
+
+/// Returns the CAIP-2 chains of a namespace entry, whose key is either a
+/// namespace (`eip155`) or a chain (`eip155:1`).
+fn namespace_chains(key: &str, namespace: &ProposalNamespace) -> Vec<String> {
+    match &namespace.chains {
+        Some(chains) => chains.clone(),
+        None if key.contains(':') => vec![key.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Parses an EVM chain ID from a CAIP-2 chain such as `eip155:56`.
+pub(crate) fn parse_chain(chain: &str) -> Option<u64> {
+    chain.strip_prefix(EIP155)?.strip_prefix(':')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal(required: &[(&str, Option<&[&str]>)]) -> SessionProposal {
+        let namespaces = |entries: &[(&str, Option<&[&str]>)]| {
+            entries
+                .iter()
+                .map(|(key, chains)| {
+                    (
+                        key.to_string(),
+                        ProposalNamespace {
+                            chains: chains.map(|c| c.iter().map(|s| s.to_string()).collect()),
+                            ..ProposalNamespace::default()
+                        },
+                    )
+                })
+                .collect()
+        };
+        SessionProposal {
+            id: 1,
+            pairing_topic: Topic::from_key(&[0u8; 32]),
+            proposer: Metadata::new("dApp", "https://example.com"),
+            required_namespaces: namespaces(required),
+            optional_namespaces: namespaces(&[
+                ("eip155", Some(&["eip155:137", "eip155:56"])),
+                ("solana", Some(&["solana:mainnet"])),
+            ]),
+            proposer_public_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_required_chains() {
+        let p = proposal(&[
+            ("eip155", Some(&["eip155:56", "eip155:1"])),
+            ("eip155:1", None),
+        ]);
+        assert_eq!(p.required_chains().unwrap(), [1, 56]);
+        assert_eq!(p.optional_chains(), [56, 137]);
+
+        let p = proposal(&[("solana", Some(&["solana:mainnet"]))]);
+        assert!(matches!(
+            p.required_chains(),
+            Err(Error::UnsupportedNamespace(chain)) if chain == "solana:mainnet"
+        ));
+    }
+
+    #[test]
+    fn test_caip_accounts() {
+        let account: Address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+            .parse()
+            .unwrap();
+        let session = Session {
+            topic: Topic::from_key(&[1u8; 32]),
+            pairing_topic: Topic::from_key(&[0u8; 32]),
+            peer: Metadata::default(),
+            chains: vec![1, 56],
+            accounts: vec![account],
+            expiry: 0,
+        };
+        assert_eq!(
+            session.caip_accounts(),
+            [
+                "eip155:1:0x742d35Cc6634C0532925a3b844Bc454e4438f44e",
+                "eip155:56:0x742d35Cc6634C0532925a3b844Bc454e4438f44e",
+            ]
+        );
+        assert_eq!(parse_chain("eip155:x"), None);
+        assert_eq!(parse_chain("eip1555:1"), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! WalletConnect v2 pairing URIs.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::crypto::{parse_key, Topic};
+use crate::{Error, Result};
+
+/// A pairing URI, as shown by a dApp in a QR code or deep link:
+/// `wc:{topic}@2?relay-protocol=irn&symKey={key}&expiryTimestamp={unix}`.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_walletconnect::PairingUri;
+///
+/// let uri: PairingUri = "wc:7f6e504bfad60b485450578e05678ed3e8e8c4751d3c6160be17160d63ec90f9@2?relay-protocol=irn&symKey=587d5484ce2a2a6ee3ba1962fdd7e8588e06200c46823bd18fbd67def96ad303&expiryTimestamp=1705000000"
+///     .parse()?;
+///
+/// assert_eq!(uri.relay_protocol, "irn");
+/// assert_eq!(uri.expiry_timestamp, Some(1_705_000_000));
+/// # Ok::<(), khodpay_walletconnect::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct PairingUri {
+    /// Topic of the pairing.
+    pub topic: Topic,
+    /// Symmetric key of the pairing.
+    pub sym_key: [u8; 32],
+    /// Relay protocol, `irn` for the WalletConnect relay.
+    pub relay_protocol: String,
+    /// Optional relay-specific data.
+    pub relay_data: Option<String>,
+    /// Unix time after which the dApp stops listening on the pairing.
+    pub expiry_timestamp: Option<u64>,
+}
+
+impl PairingUri {
+    /// Returns whether the pairing has expired at unix time `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expiry_timestamp.is_some_and(|expiry| expiry <= now)
+    }
+}
+
+impl fmt::Debug for PairingUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PairingUri")
+            .field("topic", &self.topic)
+            .field("sym_key", &"<redacted>")
+            .field("relay_protocol", &self.relay_protocol)
+            .field("relay_data", &self.relay_data)
+            .field("expiry_timestamp", &self.expiry_timestamp)
+            .finish()
+    }
+}
+
+impl FromStr for PairingUri {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |message: &str| Error::InvalidUri(message.to_string());
+
+        let rest = s
+            .strip_prefix("wc:")
+            .ok_or_else(|| invalid("missing wc: scheme"))?;
+        let (path, query) = rest
+            .split_once('?')
+            .ok_or_else(|| invalid("missing parameters"))?;
+        let (topic, version) = path
+            .split_once('@')
+            .ok_or_else(|| invalid("missing version"))?;
+        if version != "2" {
+            return Err(Error::InvalidUri(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let topic = topic.parse().map_err(|_| invalid("invalid topic"))?;
+
+        let (mut sym_key, mut relay_protocol, mut relay_data, mut expiry_timestamp) =
+            (None, None, None, None);
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "symKey" => {
+                    sym_key = Some(parse_key(&value).map_err(|_| invalid("invalid symKey"))?)
+                }
+                "relay-protocol" => relay_protocol = Some(value.into_owned()),
+                "relay-data" => relay_data = Some(value.into_owned()),
+                "expiryTimestamp" => {
+                    expiry_timestamp = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid("invalid expiryTimestamp"))?,
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            topic,
+            sym_key: sym_key.ok_or_else(|| invalid("missing symKey"))?,
+            relay_protocol: relay_protocol.ok_or_else(|| invalid("missing relay-protocol"))?,
+            relay_data,
+            expiry_timestamp,
+        })
+    }
+}
+
+impl fmt::Display for PairingUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.append_pair("relay-protocol", &self.relay_protocol);
+        if let Some(relay_data) = &self.relay_data {
+            query.append_pair("relay-data", relay_data);
+        }
+        query.append_pair("symKey", &hex::encode(self.sym_key));
+        if let Some(expiry) = self.expiry_timestamp {
+            query.append_pair("expiryTimestamp", &expiry.to_string());
+        }
+        write!(f, "wc:{}@2?{}", self.topic, query.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOPIC: &str = "7f6e504bfad60b485450578e05678ed3e8e8c4751d3c6160be17160d63ec90f9";
+    const SYM_KEY: &str = "587d5484ce2a2a6ee3ba1962fdd7e8588e06200c46823bd18fbd67def96ad303";
+
+    #[test]
+    fn test_parse_and_display() {
+        let s = format!(
+            "wc:{}@2?relay-protocol=irn&symKey={}&expiryTimestamp=1705000000",
+            TOPIC, SYM_KEY
+        );
+        let uri: PairingUri = s.parse().unwrap();
+        assert_eq!(uri.topic.to_string(), TOPIC);
+        assert_eq!(hex::encode(uri.sym_key), SYM_KEY);
+        assert_eq!(uri.relay_data, None);
+        assert!(uri.is_expired(1_705_000_000));
+        assert!(!uri.is_expired(1_704_999_999));
+        assert_eq!(uri.to_string(), s);
+        assert!(!format!("{:?}", uri).contains(SYM_KEY));
+    }
+
+    #[test]
+    fn test_parse_relay_data() {
+        let uri: PairingUri = format!(
+            "wc:{}@2?symKey={}&relay-protocol=irn&relay-data=a%20b&methods=%5Bwc_sessionPropose%5D",
+            TOPIC, SYM_KEY
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(uri.relay_data.as_deref(), Some("a b"));
+        assert_eq!(uri.expiry_timestamp, None);
+        assert_eq!(uri.to_string().parse::<PairingUri>().unwrap(), uri);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in [
+            format!("https:{}@2?relay-protocol=irn&symKey={}", TOPIC, SYM_KEY),
+            format!(
+                "wc:{}@1?key=abc&bridge=https://bridge.walletconnect.org",
+                TOPIC
+            ),
+            format!("wc:{}@2?relay-protocol=irn", TOPIC),
+            format!("wc:{}@2?symKey={}", TOPIC, SYM_KEY),
+            format!("wc:{}@2?relay-protocol=irn&symKey=1234", TOPIC),
+            format!("wc:xyz@2?relay-protocol=irn&symKey={}", SYM_KEY),
+            format!("wc:{}@2", TOPIC),
+        ] {
+            assert!(
+                matches!(s.parse::<PairingUri>(), Err(Error::InvalidUri(_))),
+                "{}",
+                s
+            );
+        }
+    }
+}
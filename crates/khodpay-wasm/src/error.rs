@@ -0,0 +1,45 @@
+//! Error types for the WebAssembly bindings.
+
+use thiserror::Error;
+
+/// Errors returned to JavaScript as `Error` objects carrying the message.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// An argument is malformed or out of range.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Error from parsing or generating a mnemonic.
+    #[error("Mnemonic error: {0}")]
+    Mnemonic(#[from] khodpay_bip39::Error),
+
+    /// Error from deriving keys.
+    #[error("Key derivation error: {0}")]
+    Bip32(#[from] khodpay_bip32::Error),
+
+    /// Error from deriving accounts.
+    #[error("Wallet error: {0}")]
+    Bip44(#[from] khodpay_bip44::Error),
+
+    /// Error from decoding or signing an EVM transaction or message.
+    #[error("EVM error: {0}")]
+    Evm(#[from] khodpay_signing::Error),
+}
+
+/// Result type alias for the bindings.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::InvalidInput("purpose 45".to_string()).to_string(),
+            "Invalid input: purpose 45"
+        );
+        let error: Error = khodpay_signing::Error::InvalidAddress("0x12".to_string()).into();
+        assert!(error.to_string().starts_with("EVM error: "));
+    }
+}
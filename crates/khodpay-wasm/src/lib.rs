@@ -0,0 +1,299 @@
+//! # KhodPay WebAssembly Bindings
+//!
+//! JavaScript bindings to the KhodPay wallet core, so web front-ends run the same
+//! mnemonic, key derivation and signing code as the mobile apps. Built for
+//! `wasm32-unknown-unknown` with `wasm-pack`, the crate exports:
+//!
+//! - `generateMnemonic(words)` and `validateMnemonic(phrase)`
+//! - `Wallet.fromMnemonic(phrase, passphrase, testnet)`, a wallet whose keys stay
+//!   inside WebAssembly memory, with:
+//!   - `fingerprint()`: the master key fingerprint in hex
+//!   - `accountXpub(purpose, coinType, account)`: an account's extended public key
+//!   - `evmAddress(account, index)`: the checksummed address at `m/44'/60'/account'/0/index`
+//!   - `signEvmTransaction(account, index, unsignedTx)`: signs an unsigned EIP-1559
+//!     or EIP-2930 transaction, given and returned in `0x` hex
+//!   - `signEvmMessage(account, index, message)`: an EIP-191 `personal_sign`
+//!     signature over the message bytes
+//!   - `free()`: wipes the keys; call it when the wallet is no longer needed
+//!
+//! Errors are thrown as JavaScript `Error`s carrying the Rust error message.
+//!
+//! ## Randomness
+//!
+//! Browsers have no OS random source, so on `wasm32-unknown-unknown` this crate
+//! enables `getrandom`'s `js` backend and mnemonics draw their entropy from
+//! `crypto.getRandomValues`. Library users who build the core crates for other
+//! WebAssembly hosts can turn off their `rand` feature and bring their own entropy
+//! through `Mnemonic::new`.
+//!
+//! ## Example
+//!
+//! ```js
+//! import init, { generateMnemonic, Wallet } from "khodpay-wasm";
+//!
+//! await init();
+//! const phrase = generateMnemonic(12);
+//! const wallet = Wallet.fromMnemonic(phrase, "", false);
+//! const address = wallet.evmAddress(0, 0);
+//! const raw = wallet.signEvmTransaction(0, 0, "0x02f0...");
+//! wallet.free();
+//! ```
+
+pub mod error;
+
+use khodpay_bip32::Network;
+use khodpay_bip39::{Language, Mnemonic, WordCount};
+use khodpay_bip44::{CoinType, Purpose};
+use khodpay_signing::{
+    Bip44Signer, Eip1559Transaction, Eip2930Transaction, SignedEip2930Transaction,
+    SignedTransaction,
+};
+use wasm_bindgen::prelude::*;
+
+pub use error::{Error, Result};
+
+/// Generates an English mnemonic of `words` words (12, 15, 18, 21 or 24) from the
+/// browser's secure random source.
+#[wasm_bindgen(js_name = generateMnemonic)]
+pub fn generate_mnemonic(words: usize) -> std::result::Result<String, JsError> {
+    Ok(try_generate_mnemonic(words)?)
+}
+
+fn try_generate_mnemonic(words: usize) -> Result<String> {
+    let mnemonic = Mnemonic::generate(WordCount::from_word_count(words)?, Language::English)?;
+    Ok(mnemonic.phrase().to_string())
+}
+
+/// Returns `true` if `phrase` is a valid English mnemonic, checksum included.
+#[wasm_bindgen(js_name = validateMnemonic)]
+pub fn validate_mnemonic(phrase: &str) -> bool {
+    khodpay_bip39::validate_phrase(phrase).is_ok()
+}
+
+/// An HD wallet restored from a mnemonic.
+///
+/// Private keys never leave WebAssembly memory; JavaScript only sees public keys,
+/// addresses and signatures.
+#[wasm_bindgen]
+pub struct Wallet {
+    inner: khodpay_bip44::Wallet,
+}
+
+#[wasm_bindgen]
+impl Wallet {
+    /// Restores the wallet of an English mnemonic and BIP-39 passphrase, with
+    /// testnet (`tpub`) or mainnet (`xpub`) extended keys.
+    #[wasm_bindgen(js_name = fromMnemonic)]
+    pub fn from_mnemonic(
+        phrase: &str,
+        passphrase: &str,
+        testnet: bool,
+    ) -> std::result::Result<Wallet, JsError> {
+        Ok(Self::try_from_mnemonic(phrase, passphrase, testnet)?)
+    }
+
+    /// Returns the master key fingerprint in hex, as in descriptors and PSBTs.
+    pub fn fingerprint(&self) -> String {
+        hex::encode(self.inner.master_key().fingerprint())
+    }
+
+    /// Returns the extended public key of account `account` under BIP-44 family
+    /// `purpose` (44, 49, 84 or 86) and SLIP-44 `coin_type`.
+    #[wasm_bindgen(js_name = accountXpub)]
+    pub fn account_xpub(
+        &mut self,
+        purpose: u32,
+        coin_type: u32,
+        account: u32,
+    ) -> std::result::Result<String, JsError> {
+        Ok(self.try_account_xpub(purpose, coin_type, account)?)
+    }
+
+    /// Returns the checksummed EVM address at `m/44'/60'/account'/0/index`.
+    #[wasm_bindgen(js_name = evmAddress)]
+    pub fn evm_address(
+        &mut self,
+        account: u32,
+        index: u32,
+    ) -> std::result::Result<String, JsError> {
+        Ok(self.evm_signer(account, index)?.address().to_string())
+    }
+
+    /// Signs an unsigned EIP-1559 or EIP-2930 transaction in `0x` hex with the key
+    /// at `m/44'/60'/account'/0/index`, returning the raw signed transaction for
+    /// `eth_sendRawTransaction`.
+    #[wasm_bindgen(js_name = signEvmTransaction)]
+    pub fn sign_evm_transaction(
+        &mut self,
+        account: u32,
+        index: u32,
+        unsigned_tx: &str,
+    ) -> std::result::Result<String, JsError> {
+        Ok(self.try_sign_evm_transaction(account, index, unsigned_tx)?)
+    }
+
+    /// Signs `message` as EIP-191 `personal_sign` with the key at
+    /// `m/44'/60'/account'/0/index`, returning `0x ‖ r ‖ s ‖ v` with `v` as 27 or 28.
+    #[wasm_bindgen(js_name = signEvmMessage)]
+    pub fn sign_evm_message(
+        &mut self,
+        account: u32,
+        index: u32,
+        message: &[u8],
+    ) -> std::result::Result<String, JsError> {
+        Ok(self.try_sign_evm_message(account, index, message)?)
+    }
+}
+
+impl Wallet {
+    fn try_from_mnemonic(phrase: &str, passphrase: &str, testnet: bool) -> Result<Wallet> {
+        let network = if testnet {
+            Network::BitcoinTestnet
+        } else {
+            Network::BitcoinMainnet
+        };
+        let inner =
+            khodpay_bip44::Wallet::from_mnemonic(phrase, passphrase, Language::English, network)?;
+        Ok(Wallet { inner })
+    }
+
+    fn try_account_xpub(&mut self, purpose: u32, coin_type: u32, account: u32) -> Result<String> {
+        let purpose = Purpose::try_from(purpose)?;
+        let coin_type = CoinType::try_from(coin_type)?;
+        let account = self.inner.get_account(purpose, coin_type, account)?;
+        Ok(account.extended_key().to_extended_public_key().to_string())
+    }
+
+    fn evm_signer(&mut self, account: u32, index: u32) -> Result<Bip44Signer> {
+        let account = self
+            .inner
+            .get_account(Purpose::BIP44, CoinType::Ethereum, account)?;
+        Ok(Bip44Signer::new(account, index)?)
+    }
+
+    fn try_sign_evm_transaction(
+        &mut self,
+        account: u32,
+        index: u32,
+        unsigned_tx: &str,
+    ) -> Result<String> {
+        let unsigned_tx = unsigned_tx.trim();
+        let bytes = hex::decode(unsigned_tx.strip_prefix("0x").unwrap_or(unsigned_tx))
+            .map_err(|e| Error::InvalidInput(format!("transaction is not hex: {}", e)))?;
+        let signer = self.evm_signer(account, index)?;
+        match bytes.first() {
+            Some(&Eip1559Transaction::TYPE) => {
+                let tx = Eip1559Transaction::decode(&bytes)?;
+                let signature = signer.sign_transaction(&tx)?;
+                Ok(SignedTransaction::new(tx, signature).to_raw_transaction())
+            }
+            Some(&Eip2930Transaction::TYPE) => {
+                let tx = Eip2930Transaction::decode(&bytes)?;
+                let signature = signer.sign_eip2930_transaction(&tx)?;
+                Ok(SignedEip2930Transaction::new(tx, signature).to_raw_transaction())
+            }
+            _ => Err(Error::InvalidInput(
+                "only EIP-1559 (0x02) and EIP-2930 (0x01) transactions are supported".to_string(),
+            )),
+        }
+    }
+
+    fn try_sign_evm_message(&mut self, account: u32, index: u32, message: &[u8]) -> Result<String> {
+        let signature = self.evm_signer(account, index)?.sign_message(message)?;
+        let mut bytes = signature.to_bytes();
+        bytes[64] += 27;
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khodpay_signing::{eip191, Address, ChainId, Signature, TypedTransaction, Wei};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const ADDRESS: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+
+    fn wallet() -> Wallet {
+        Wallet::from_mnemonic(MNEMONIC, "", false).unwrap()
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        let phrase = generate_mnemonic(12).unwrap();
+        assert_eq!(phrase.split(' ').count(), 12);
+        assert!(validate_mnemonic(&phrase));
+        assert!(validate_mnemonic(MNEMONIC));
+        assert!(!validate_mnemonic(&MNEMONIC.replace("about", "abort")));
+        assert!(matches!(try_generate_mnemonic(13), Err(Error::Mnemonic(_))));
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut wallet = wallet();
+        assert_eq!(wallet.fingerprint(), "73c5da0a");
+        assert_eq!(wallet.evm_address(0, 0).unwrap(), ADDRESS);
+        // BIP-84 account 0, with xpub version bytes
+        assert_eq!(
+            wallet.account_xpub(84, 0, 0).unwrap(),
+            "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V"
+        );
+        assert!(matches!(
+            wallet.try_account_xpub(45, 0, 0),
+            Err(Error::Bip44(_))
+        ));
+
+        let mut testnet = Wallet::from_mnemonic(MNEMONIC, "", true).unwrap();
+        assert!(testnet.account_xpub(84, 1, 0).unwrap().starts_with("tpub"));
+        assert!(matches!(
+            Wallet::try_from_mnemonic("abandon", "", false),
+            Err(Error::Bip44(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_evm_transaction() {
+        let tx = Eip1559Transaction::builder()
+            .chain_id(ChainId::BscMainnet)
+            .nonce(7)
+            .max_priority_fee_per_gas(Wei::from_gwei(1))
+            .max_fee_per_gas(Wei::from_gwei(5))
+            .gas_limit(21_000)
+            .to(Address::from_bytes([0x11; 20]))
+            .value(Wei::from_gwei(1_000))
+            .build()
+            .unwrap();
+        let unsigned = format!("0x{}", hex::encode(tx.encode_unsigned()));
+        let raw = wallet().sign_evm_transaction(0, 0, &unsigned).unwrap();
+
+        let bytes = hex::decode(raw.trim_start_matches("0x")).unwrap();
+        let TypedTransaction::Eip1559(signed) = TypedTransaction::from_raw(&bytes).unwrap() else {
+            panic!("expected an EIP-1559 transaction");
+        };
+        assert_eq!(signed.transaction(), &tx);
+        assert_eq!(signed.verify().unwrap().to_string(), ADDRESS);
+
+        let mut wallet = wallet();
+        for bad in ["zz", "0xf86c", &unsigned[..unsigned.len() - 2]] {
+            assert!(
+                wallet.try_sign_evm_transaction(0, 0, bad).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_sign_evm_message() {
+        let signature = wallet().sign_evm_message(0, 0, b"hello").unwrap();
+        let bytes = hex::decode(signature.trim_start_matches("0x")).unwrap();
+        assert!(bytes[64] == 27 || bytes[64] == 28);
+        let signature = Signature::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            eip191::recover_message_signer(b"hello", &signature)
+                .unwrap()
+                .to_string(),
+            ADDRESS
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Smoke tests run inside a JavaScript engine with `wasm-pack test --node`.
+//!
+//! Native `cargo test` compiles them away; they exist to catch code that builds for
+//! wasm32-unknown-unknown but traps at runtime, such as `std::time::Instant::now`.
+
+#![cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+
+use khodpay_bip44::{AccountScanner, GapLimitChecker, MockBlockchain};
+use khodpay_wasm::{generate_mnemonic, validate_mnemonic, Wallet};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+#[wasm_bindgen_test]
+fn discovery_report() {
+    let external = MockBlockchain::with_used_addresses(&[0, 2]);
+    let internal = MockBlockchain::new();
+
+    let scanner = AccountScanner::new(GapLimitChecker::new(20));
+    let report = scanner.discover_report(&external, &internal, 1).unwrap();
+
+    assert_eq!(report.accounts_scanned, 1);
+    assert_eq!(report.accounts.len(), 1);
+    assert_eq!(report.addresses_checked, 43);
+    assert!(report.elapsed >= report.accounts[0].elapsed());
+}
+
+#[wasm_bindgen_test]
+fn mnemonic_and_wallet() {
+    let phrase = generate_mnemonic(12).unwrap();
+    assert!(validate_mnemonic(&phrase));
+
+    let mut wallet = Wallet::from_mnemonic(MNEMONIC, "", false).unwrap();
+    assert_eq!(wallet.fingerprint(), "73c5da0a");
+    assert_eq!(
+        wallet.evm_address(0, 0).unwrap(),
+        "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+    );
+}
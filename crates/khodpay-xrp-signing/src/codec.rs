@@ -0,0 +1,150 @@
+//! The parts of the XRP Ledger binary format payments need.
+//!
+//! A serialized transaction is a list of fields, each a header naming its type and
+//! field code followed by the value. Fields are sorted by type code, then field
+//! code, so that every signer produces the same bytes for the same transaction.
+
+/// Type code of 16-bit unsigned integers.
+pub(crate) const UINT16: u8 = 1;
+/// Type code of 32-bit unsigned integers.
+pub(crate) const UINT32: u8 = 2;
+/// Type code of amounts.
+pub(crate) const AMOUNT: u8 = 6;
+/// Type code of variable length blobs.
+pub(crate) const BLOB: u8 = 7;
+/// Type code of account IDs.
+pub(crate) const ACCOUNT_ID: u8 = 8;
+
+/// Bit set in native XRP amounts that are positive; the top bit marks tokens.
+const POSITIVE_XRP: u64 = 0x4000_0000_0000_0000;
+
+/// Collects fields and serializes them in canonical order.
+#[derive(Debug, Default)]
+pub(crate) struct Writer {
+    fields: Vec<(u8, u8, Vec<u8>)>,
+}
+
+impl Writer {
+    /// Adds a 16-bit unsigned integer field.
+    pub(crate) fn uint16(self, field: u8, value: u16) -> Self {
+        self.field(UINT16, field, value.to_be_bytes().to_vec())
+    }
+
+    /// Adds a 32-bit unsigned integer field.
+    pub(crate) fn uint32(self, field: u8, value: u32) -> Self {
+        self.field(UINT32, field, value.to_be_bytes().to_vec())
+    }
+
+    /// Adds a native XRP amount field, in drops.
+    pub(crate) fn xrp_amount(self, field: u8, drops: u64) -> Self {
+        self.field(AMOUNT, field, (drops | POSITIVE_XRP).to_be_bytes().to_vec())
+    }
+
+    /// Adds a variable length blob field.
+    pub(crate) fn blob(self, field: u8, value: &[u8]) -> Self {
+        let mut bytes = length_prefix(value.len());
+        bytes.extend_from_slice(value);
+        self.field(BLOB, field, bytes)
+    }
+
+    /// Adds an account ID field, which is length prefixed like a blob.
+    pub(crate) fn account(self, field: u8, account: &[u8; 20]) -> Self {
+        let mut bytes = length_prefix(account.len());
+        bytes.extend_from_slice(account);
+        self.field(ACCOUNT_ID, field, bytes)
+    }
+
+    fn field(mut self, type_code: u8, field: u8, value: Vec<u8>) -> Self {
+        self.fields.push((type_code, field, value));
+        self
+    }
+
+    /// Returns the serialized fields, sorted by type code and field code.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.fields
+            .sort_by_key(|(type_code, field, _)| (*type_code, *field));
+        let mut out = Vec::new();
+        for (type_code, field, value) in self.fields {
+            out.extend(field_header(type_code, field));
+            out.extend(value);
+        }
+        out
+    }
+}
+
+/// Returns the header of a field: type and field code share a byte when both are
+/// below 16, and each one that is not gets a byte of its own.
+fn field_header(type_code: u8, field: u8) -> Vec<u8> {
+    match (type_code < 16, field < 16) {
+        (true, true) => vec![type_code << 4 | field],
+        (true, false) => vec![type_code << 4, field],
+        (false, true) => vec![field, type_code],
+        (false, false) => vec![0, type_code, field],
+    }
+}
+
+/// Returns the length prefix of a variable length field, one to three bytes.
+///
+/// # Panics
+///
+/// Panics above 918,744 bytes, the longest length the format can express.
+fn length_prefix(len: usize) -> Vec<u8> {
+    match len {
+        0..=192 => vec![len as u8],
+        193..=12_480 => {
+            let len = len - 193;
+            vec![193 + (len >> 8) as u8, len as u8]
+        }
+        12_481..=918_744 => {
+            let len = len - 12_481;
+            vec![241 + (len >> 16) as u8, (len >> 8) as u8, len as u8]
+        }
+        _ => panic!("{} bytes is too long for an XRP Ledger field", len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_header() {
+        assert_eq!(field_header(UINT16, 2), [0x12]);
+        assert_eq!(field_header(UINT32, 27), [0x20, 0x1b]);
+        assert_eq!(field_header(16, 1), [0x01, 0x10]);
+        assert_eq!(field_header(16, 16), [0x00, 0x10, 0x10]);
+    }
+
+    #[test]
+    fn test_length_prefix() {
+        assert_eq!(length_prefix(0), [0x00]);
+        assert_eq!(length_prefix(192), [0xc0]);
+        assert_eq!(length_prefix(193), [0xc1, 0x00]);
+        assert_eq!(length_prefix(12_480), [0xf0, 0xff]);
+        assert_eq!(length_prefix(12_481), [0xf1, 0x00, 0x00]);
+        assert_eq!(length_prefix(918_744), [0xfe, 0xd4, 0x17]);
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        let bytes = Writer::default()
+            .account(1, &[0xaa; 20])
+            .xrp_amount(1, 1_000_000)
+            .uint32(27, 7)
+            .uint32(4, 1)
+            .uint16(2, 0)
+            .finish();
+        assert_eq!(
+            hex::encode(bytes),
+            [
+                "120000",
+                "2400000001",
+                "201b00000007",
+                "6140000000000f4240",
+                "8114",
+                &"aa".repeat(20),
+            ]
+            .concat()
+        );
+    }
+}
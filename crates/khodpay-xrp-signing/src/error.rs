@@ -0,0 +1,45 @@
+//! Error types for the XRP signing crate.
+
+use thiserror::Error;
+
+/// Errors that can occur while building or signing XRP Ledger transactions.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A required field is missing or a value is out of range.
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+
+    /// The BIP-44 account is not an XRP Ledger account.
+    #[error("Invalid account: {0}")]
+    InvalidAccount(String),
+
+    /// Malformed address.
+    #[error("Address error: {0}")]
+    AddressError(#[from] khodpay_address::Error),
+
+    /// ECDSA signing error.
+    #[error("Signing error: {0}")]
+    SigningError(String),
+
+    /// Error from BIP-44 operations.
+    #[error("BIP-44 error: {0}")]
+    Bip44Error(#[from] khodpay_bip44::Error),
+}
+
+/// Result type alias for XRP signing operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            Error::InvalidTransaction("missing sequence".to_string()).to_string(),
+            "Invalid transaction: missing sequence"
+        );
+        let error = Error::from(khodpay_address::Error::InvalidAddress("empty".to_string()));
+        assert_eq!(error.to_string(), "Address error: Invalid address: empty");
+    }
+}
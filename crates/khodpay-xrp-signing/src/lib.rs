@@ -0,0 +1,76 @@
+//! # Khodpay XRP Signing
+//!
+//! XRP payment building and signing for XRP Ledger wallets.
+//!
+//! This crate turns a BIP-44 XRP account from `khodpay-bip44` and the account's
+//! sequence number into signed payments in the canonical binary format, ready for
+//! the `submit` method. Addresses are the `khodpay-address`
+//! [`XrpAddress`](khodpay_address::XrpAddress).
+//!
+//! ## Modules
+//!
+//! | Type | Description |
+//! |---|---|
+//! | [`PaymentBuilder`] | XRP payments with destination tags, fee and sequence |
+//! | [`Payment`] | Canonical serialization and the SHA-512Half signing hash |
+//! | [`XrpSigner`] | Keys from a `khodpay-bip44` XRP account (`m/44'/144'/…`) |
+//! | [`SignedPayment`] | The `tx_blob` and transaction hash |
+//!
+//! ## Features
+//!
+//! - **XRP Payments**: Amounts in drops, with zero amounts and payments to the sender
+//!   rejected
+//! - **Destination Tags**: For exchanges and other shared addresses, plus source tags
+//! - **Fee and Sequence**: A 12 drop default fee, a ceiling against unit mistakes, and
+//!   an optional `LastLedgerSequence` so payments expire instead of staying pending
+//! - **Canonical Encoding**: Fields sorted the way rippled serializes them, so the
+//!   transaction hash matches the network's
+//! - **Canonical Signatures**: DER encoded low-S ECDSA signatures, as
+//!   `tfFullyCanonicalSig` requires
+//! - **BIP-44 Integration**: Sign with keys from the same HD wallet as the Bitcoin, EVM
+//!   and Tron crates
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use khodpay_bip32::Network;
+//! use khodpay_bip44::{CoinType, Purpose, Wallet};
+//! use khodpay_xrp_signing::{Payment, XrpSigner, DROPS_PER_XRP};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet)?;
+//! let account = wallet.get_account(Purpose::BIP44, CoinType::Ripple, 0)?;
+//! let signer = XrpSigner::new(account, 0)?;
+//!
+//! // From account_info and the last validated ledger
+//! let sequence = 7;
+//! let validated_ledger = 92_000_000;
+//!
+//! let payment = Payment::builder()
+//!     .account(signer.address())
+//!     .destination("rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe".parse()?)
+//!     .amount(20 * DROPS_PER_XRP)
+//!     .destination_tag(104_729)
+//!     .sequence(sequence)
+//!     .last_ledger_sequence(validated_ledger + 20)
+//!     .build()?;
+//!
+//! let signed = signer.sign_payment(&payment)?;
+//! println!("hash {}: {}", signed.hash_hex(), signed.to_hex());
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::broken_intra_doc_links)]
+#![deny(unsafe_code)]
+
+mod codec;
+mod error;
+mod payment;
+mod signer;
+
+pub use error::{Error, Result};
+pub use payment::{Payment, PaymentBuilder, SignedPayment, DEFAULT_FEE, DROPS_PER_XRP, MAX_FEE};
+pub use signer::XrpSigner;
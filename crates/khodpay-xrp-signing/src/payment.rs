@@ -0,0 +1,452 @@
+//! XRP payments: the builder, signing hashes and signed transactions.
+
+use khodpay_address::XrpAddress;
+use sha2::{Digest, Sha512};
+
+use crate::codec::Writer;
+use crate::{Error, Result};
+
+/// Drops per XRP; amounts and fees are in drops.
+pub const DROPS_PER_XRP: u64 = 1_000_000;
+
+/// Fee used when none is set, in drops: a little above the 10 drop reference fee,
+/// as most wallets pay.
+pub const DEFAULT_FEE: u64 = 12;
+
+/// Highest fee [`PaymentBuilder::build`] accepts, in drops. Fees are burned, so a
+/// larger one is more likely a unit mistake than a busy network.
+pub const MAX_FEE: u64 = 2 * DROPS_PER_XRP;
+
+/// Total XRP supply in drops, an upper bound for any amount.
+const MAX_DROPS: u64 = 100_000_000_000 * DROPS_PER_XRP;
+
+/// `tfFullyCanonicalSig`: only accept the low-S form of the signature.
+const TF_FULLY_CANONICAL_SIG: u32 = 0x8000_0000;
+
+/// `TransactionType` of payments.
+const PAYMENT: u16 = 0;
+
+/// Hash prefix of single-signed signing data, `STX\0`.
+const SIGNING_PREFIX: [u8; 4] = *b"STX\0";
+
+/// Hash prefix of transaction IDs, `TXN\0`.
+const TXID_PREFIX: [u8; 4] = *b"TXN\0";
+
+// Field codes, per type.
+const TRANSACTION_TYPE: u8 = 2;
+const FLAGS: u8 = 2;
+const SOURCE_TAG: u8 = 3;
+const SEQUENCE: u8 = 4;
+const DESTINATION_TAG: u8 = 14;
+const LAST_LEDGER_SEQUENCE: u8 = 27;
+const AMOUNT: u8 = 1;
+const FEE: u8 = 8;
+const SIGNING_PUB_KEY: u8 = 3;
+const TXN_SIGNATURE: u8 = 4;
+const ACCOUNT: u8 = 1;
+const DESTINATION: u8 = 3;
+
+/// An unsigned payment of XRP from one account to another.
+///
+/// Built with [`Payment::builder`] and signed with
+/// [`XrpSigner::sign_payment`](crate::XrpSigner::sign_payment).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+    account: XrpAddress,
+    destination: XrpAddress,
+    amount: u64,
+    fee: u64,
+    sequence: u32,
+    destination_tag: Option<u32>,
+    source_tag: Option<u32>,
+    last_ledger_sequence: Option<u32>,
+}
+
+impl Payment {
+    /// Returns a builder for a new payment.
+    pub fn builder() -> PaymentBuilder {
+        PaymentBuilder::default()
+    }
+
+    /// Returns the account that sends the payment and pays its fee.
+    pub fn account(&self) -> &XrpAddress {
+        &self.account
+    }
+
+    /// Returns the recipient.
+    pub fn destination(&self) -> &XrpAddress {
+        &self.destination
+    }
+
+    /// Returns the amount delivered, in drops.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Returns the fee burned, in drops.
+    pub fn fee(&self) -> u64 {
+        self.fee
+    }
+
+    /// Returns the sequence number of the sending account.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Returns the tag identifying the recipient at a shared address, if any.
+    pub fn destination_tag(&self) -> Option<u32> {
+        self.destination_tag
+    }
+
+    /// Returns the tag identifying the sender at a shared address, if any.
+    pub fn source_tag(&self) -> Option<u32> {
+        self.source_tag
+    }
+
+    /// Returns the last ledger the payment can be included in, if any.
+    pub fn last_ledger_sequence(&self) -> Option<u32> {
+        self.last_ledger_sequence
+    }
+
+    /// Returns the canonical binary form with the given public key and, once
+    /// signed, signature.
+    pub(crate) fn serialize(&self, public_key: &[u8], signature: Option<&[u8]>) -> Vec<u8> {
+        let mut writer = Writer::default()
+            .uint16(TRANSACTION_TYPE, PAYMENT)
+            .uint32(FLAGS, TF_FULLY_CANONICAL_SIG)
+            .uint32(SEQUENCE, self.sequence)
+            .xrp_amount(AMOUNT, self.amount)
+            .xrp_amount(FEE, self.fee)
+            .blob(SIGNING_PUB_KEY, public_key)
+            .account(ACCOUNT, self.account.as_bytes())
+            .account(DESTINATION, self.destination.as_bytes());
+        if let Some(tag) = self.source_tag {
+            writer = writer.uint32(SOURCE_TAG, tag);
+        }
+        if let Some(tag) = self.destination_tag {
+            writer = writer.uint32(DESTINATION_TAG, tag);
+        }
+        if let Some(ledger) = self.last_ledger_sequence {
+            writer = writer.uint32(LAST_LEDGER_SEQUENCE, ledger);
+        }
+        if let Some(signature) = signature {
+            writer = writer.blob(TXN_SIGNATURE, signature);
+        }
+        writer.finish()
+    }
+
+    /// Returns the digest a key with the 33-byte compressed `public_key` signs:
+    /// SHA-512Half of `STX\0` and the payment with its `SigningPubKey`.
+    pub fn signing_hash(&self, public_key: &[u8; 33]) -> [u8; 32] {
+        sha512_half(&SIGNING_PREFIX, &self.serialize(public_key, None))
+    }
+}
+
+/// Builder for [`Payment`].
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_address::XrpAddress;
+/// use khodpay_xrp_signing::{Payment, DEFAULT_FEE, DROPS_PER_XRP};
+///
+/// let account: XrpAddress = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh".parse().unwrap();
+/// let exchange: XrpAddress = "rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe".parse().unwrap();
+///
+/// // 25 XRP to an exchange, credited to the customer with tag 104_729
+/// let payment = Payment::builder()
+///     .account(account)
+///     .destination(exchange)
+///     .amount(25 * DROPS_PER_XRP)
+///     .destination_tag(104_729)
+///     .sequence(7)
+///     .last_ledger_sequence(92_000_020)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(payment.fee(), DEFAULT_FEE);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PaymentBuilder {
+    account: Option<XrpAddress>,
+    destination: Option<XrpAddress>,
+    amount: Option<u64>,
+    fee: Option<u64>,
+    sequence: Option<u32>,
+    destination_tag: Option<u32>,
+    source_tag: Option<u32>,
+    last_ledger_sequence: Option<u32>,
+}
+
+impl PaymentBuilder {
+    /// Sets the account that sends the payment and pays its fee (required).
+    pub fn account(mut self, account: XrpAddress) -> Self {
+        self.account = Some(account);
+        self
+    }
+
+    /// Sets the recipient (required).
+    pub fn destination(mut self, destination: XrpAddress) -> Self {
+        self.destination = Some(destination);
+        self
+    }
+
+    /// Sets the amount to deliver in drops (required).
+    pub fn amount(mut self, drops: u64) -> Self {
+        self.amount = Some(drops);
+        self
+    }
+
+    /// Sets the fee in drops; defaults to [`DEFAULT_FEE`]. Raise it to the
+    /// `open_ledger_fee` of the `fee` method when the network is busy.
+    pub fn fee(mut self, drops: u64) -> Self {
+        self.fee = Some(drops);
+        self
+    }
+
+    /// Sets the sequence number (required): the `Sequence` of the sending account
+    /// from `account_info`, which each transaction uses up.
+    pub fn sequence(mut self, sequence: u32) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Sets the destination tag, which exchanges and other shared addresses need to
+    /// credit the right customer.
+    pub fn destination_tag(mut self, tag: u32) -> Self {
+        self.destination_tag = Some(tag);
+        self
+    }
+
+    /// Sets the source tag, identifying the sender at a shared address.
+    pub fn source_tag(mut self, tag: u32) -> Self {
+        self.source_tag = Some(tag);
+        self
+    }
+
+    /// Sets the last ledger the payment can be included in, so that it fails for
+    /// good rather than staying pending; usually a few ledgers after the current
+    /// validated one.
+    pub fn last_ledger_sequence(mut self, ledger: u32) -> Self {
+        self.last_ledger_sequence = Some(ledger);
+        self
+    }
+
+    /// Builds the payment.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTransaction`] if a required field is missing, the
+    /// amount is zero or above the XRP supply, the fee is zero or above
+    /// [`MAX_FEE`], or the payment is to the sending account itself.
+    pub fn build(self) -> Result<Payment> {
+        let missing = |field: &str| Error::InvalidTransaction(format!("missing {}", field));
+        let account = self.account.ok_or_else(|| missing("account"))?;
+        let destination = self.destination.ok_or_else(|| missing("destination"))?;
+        let amount = self.amount.ok_or_else(|| missing("amount"))?;
+        let sequence = self.sequence.ok_or_else(|| missing("sequence"))?;
+        let fee = self.fee.unwrap_or(DEFAULT_FEE);
+
+        if amount == 0 {
+            return Err(Error::InvalidTransaction(
+                "payment amount must be positive".to_string(),
+            ));
+        }
+        if amount > MAX_DROPS {
+            return Err(Error::InvalidTransaction(format!(
+                "amount {} drops is more than the XRP supply",
+                amount
+            )));
+        }
+        if fee == 0 || fee > MAX_FEE {
+            return Err(Error::InvalidTransaction(format!(
+                "fee {} drops is not between 1 and {}",
+                fee, MAX_FEE
+            )));
+        }
+        if destination == account {
+            return Err(Error::InvalidTransaction(
+                "cannot pay XRP to the sending account itself".to_string(),
+            ));
+        }
+
+        Ok(Payment {
+            account,
+            destination,
+            amount,
+            fee,
+            sequence,
+            destination_tag: self.destination_tag,
+            source_tag: self.source_tag,
+            last_ledger_sequence: self.last_ledger_sequence,
+        })
+    }
+}
+
+/// A payment with its signature, ready to submit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedPayment {
+    payment: Payment,
+    public_key: [u8; 33],
+    signature: Vec<u8>,
+}
+
+impl SignedPayment {
+    /// Pairs a payment with the compressed public key that signed it and the DER
+    /// encoded signature over its [`signing_hash`](Payment::signing_hash).
+    pub fn new(payment: Payment, public_key: [u8; 33], signature: Vec<u8>) -> Self {
+        Self {
+            payment,
+            public_key,
+            signature,
+        }
+    }
+
+    /// Returns the unsigned payment.
+    pub fn payment(&self) -> &Payment {
+        &self.payment
+    }
+
+    /// Returns the compressed public key, the `SigningPubKey` field.
+    pub fn public_key(&self) -> &[u8; 33] {
+        &self.public_key
+    }
+
+    /// Returns the DER encoded signature, the `TxnSignature` field.
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Returns the signed transaction in the canonical binary form.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.payment
+            .serialize(&self.public_key, Some(&self.signature))
+    }
+
+    /// Returns the signed transaction in hex, the `tx_blob` of the `submit` method.
+    pub fn to_hex(&self) -> String {
+        hex::encode_upper(self.to_bytes())
+    }
+
+    /// Returns the transaction hash: SHA-512Half of `TXN\0` and the signed
+    /// transaction.
+    pub fn hash(&self) -> [u8; 32] {
+        sha512_half(&TXID_PREFIX, &self.to_bytes())
+    }
+
+    /// Returns the transaction hash in hex, as shown by ledger explorers.
+    pub fn hash_hex(&self) -> String {
+        hex::encode_upper(self.hash())
+    }
+}
+
+/// Returns the first half of the SHA-512 of `prefix ‖ data`, the hash the XRP
+/// Ledger uses throughout.
+fn sha512_half(prefix: &[u8; 4], data: &[u8]) -> [u8; 32] {
+    let digest = Sha512::new()
+        .chain_update(prefix)
+        .chain_update(data)
+        .finalize();
+    digest[..32].try_into().expect("32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh";
+    const DESTINATION: &str = "rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe";
+
+    fn address(s: &str) -> XrpAddress {
+        s.parse().unwrap()
+    }
+
+    fn builder() -> PaymentBuilder {
+        Payment::builder()
+            .account(address(ACCOUNT))
+            .destination(address(DESTINATION))
+            .amount(25 * DROPS_PER_XRP)
+            .sequence(7)
+    }
+
+    #[test]
+    fn test_serialize() {
+        let payment = builder()
+            .destination_tag(104_729)
+            .source_tag(1)
+            .last_ledger_sequence(92_000_020)
+            .build()
+            .unwrap();
+        let public_key = [0x02; 33];
+        let signature = [0x30; 70];
+
+        let expected = [
+            "120000",
+            "2280000000",
+            "2300000001",
+            "2400000007",
+            "2e00019919",
+            "201b057bcf14",
+            "6140000000017d7840",
+            "68400000000000000c",
+            "7321",
+            &"02".repeat(33),
+            "7446",
+            &"30".repeat(70),
+            "8114",
+            &hex::encode(address(ACCOUNT).as_bytes()),
+            "8314",
+            &hex::encode(address(DESTINATION).as_bytes()),
+        ]
+        .concat();
+        assert_eq!(
+            hex::encode(payment.serialize(&public_key, Some(&signature))),
+            expected
+        );
+
+        let signed = SignedPayment::new(payment.clone(), public_key, signature.to_vec());
+        assert_eq!(signed.to_hex(), expected.to_uppercase());
+        assert_eq!(
+            signed.hash(),
+            sha512_half(b"TXN\0", &hex::decode(&expected).unwrap())
+        );
+        assert_eq!(signed.hash_hex().len(), 64);
+
+        // The signing hash covers everything but the signature
+        let unsigned = expected.replace(&format!("7446{}", "30".repeat(70)), "");
+        assert_eq!(
+            payment.signing_hash(&public_key),
+            sha512_half(b"STX\0", &hex::decode(unsigned).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_optional_fields() {
+        let payment = builder().fee(5_000).build().unwrap();
+        let bytes = hex::encode(payment.serialize(&[0x03; 33], None));
+        assert!(bytes.starts_with("12000022800000002400000007614000000001"));
+        assert!(bytes.contains("6840000000000013887321"));
+        assert!(!bytes.contains("7446"));
+        assert_eq!(payment.destination_tag(), None);
+        assert_eq!(payment.last_ledger_sequence(), None);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let invalid =
+            |builder: PaymentBuilder| matches!(builder.build(), Err(Error::InvalidTransaction(_)));
+        assert!(invalid(builder().sequence(1).amount(0)));
+        assert!(invalid(Payment::builder().amount(1).sequence(1)));
+        assert!(invalid(
+            Payment::builder()
+                .account(address(ACCOUNT))
+                .destination(address(DESTINATION))
+                .amount(1)
+        ));
+        assert!(invalid(builder().amount(MAX_DROPS + 1)));
+        assert!(invalid(builder().fee(0)));
+        assert!(invalid(builder().fee(MAX_FEE + 1)));
+        assert!(invalid(builder().destination(address(ACCOUNT))));
+        assert!(builder().amount(MAX_DROPS).fee(MAX_FEE).build().is_ok());
+    }
+}
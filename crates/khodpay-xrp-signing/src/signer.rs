@@ -0,0 +1,201 @@
+//! Keys that sign XRP Ledger transactions.
+
+use std::fmt;
+
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use khodpay_address::XrpAddress;
+use khodpay_bip44::{Account, CoinType};
+use zeroize::Zeroizing;
+
+use crate::{Error, Payment, Result, SignedPayment};
+
+/// Signs XRP Ledger transactions with a secp256k1 key from a BIP-44 XRP account
+/// (`m/44'/144'/account'/0/index`), the path Ledger and most HD wallets use.
+///
+/// # Examples
+///
+/// ```rust
+/// use khodpay_bip32::Network;
+/// use khodpay_bip44::{CoinType, Purpose, Wallet};
+/// use khodpay_xrp_signing::XrpSigner;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let mut wallet = Wallet::from_english_mnemonic(mnemonic, "", Network::BitcoinMainnet)?;
+/// let account = wallet.get_account(Purpose::BIP44, CoinType::Ripple, 0)?;
+///
+/// let signer = XrpSigner::new(account, 0)?;
+/// assert_eq!(signer.address().to_string(), "rHsMGQEkVNJmpGWs8XUBoTBiAAbwxZN5v3");
+/// # Ok(())
+/// # }
+/// ```
+pub struct XrpSigner {
+    signing_key: SigningKey,
+    public_key: [u8; 33],
+    address: XrpAddress,
+}
+
+impl XrpSigner {
+    /// Creates a signer for the external address `address_index` of an XRP
+    /// account.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidAccount`] if the account is not for
+    /// [`CoinType::Ripple`], or an error if key derivation fails.
+    pub fn new(account: &Account, address_index: u32) -> Result<Self> {
+        if account.coin_type() != CoinType::Ripple {
+            return Err(Error::InvalidAccount(format!(
+                "expected an XRP account, got {:?}",
+                account.coin_type()
+            )));
+        }
+        let extended_key = account.derive_external(address_index)?;
+        let private_key: Zeroizing<[u8; 32]> =
+            Zeroizing::new(extended_key.private_key().to_bytes());
+        Self::from_private_key(&private_key)
+    }
+
+    /// Creates a signer from a 32-byte private key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SigningError`] if the key is zero or not below the curve
+    /// order.
+    pub fn from_private_key(private_key: &[u8; 32]) -> Result<Self> {
+        let signing_key = SigningKey::from_bytes(private_key.into())
+            .map_err(|e| Error::SigningError(format!("Invalid private key: {}", e)))?;
+        let public_key: [u8; 33] = signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .try_into()
+            .expect("compressed points are 33 bytes");
+        let address = XrpAddress::from_public_key_bytes(&public_key)?;
+        Ok(Self {
+            signing_key,
+            public_key,
+            address,
+        })
+    }
+
+    /// Returns the XRP Ledger address of the key.
+    pub fn address(&self) -> XrpAddress {
+        self.address
+    }
+
+    /// Returns the compressed public key, which signed transactions carry.
+    pub fn public_key(&self) -> &[u8; 33] {
+        &self.public_key
+    }
+
+    /// Signs a payment sent from this signer's address.
+    ///
+    /// The signature is the DER encoded, low-S ECDSA signature of the payment's
+    /// [`signing_hash`](Payment::signing_hash).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTransaction`] if the payment's account is another
+    /// address, or [`Error::SigningError`] if signing fails.
+    pub fn sign_payment(&self, payment: &Payment) -> Result<SignedPayment> {
+        if *payment.account() != self.address {
+            return Err(Error::InvalidTransaction(format!(
+                "account {} is not the signer {}",
+                payment.account(),
+                self.address
+            )));
+        }
+        let hash = payment.signing_hash(&self.public_key);
+        let signature: Signature = self
+            .signing_key
+            .sign_prehash(&hash)
+            .map_err(|e| Error::SigningError(e.to_string()))?;
+        // tfFullyCanonicalSig: the network rejects the high-S twin
+        let signature = signature.normalize_s().unwrap_or(signature);
+        Ok(SignedPayment::new(
+            payment.clone(),
+            self.public_key,
+            signature.to_der().as_bytes().to_vec(),
+        ))
+    }
+}
+
+impl fmt::Debug for XrpSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XrpSigner")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::signature::hazmat::PrehashVerifier;
+    use k256::ecdsa::VerifyingKey;
+    use khodpay_bip32::Network;
+    use khodpay_bip44::{Purpose, Wallet};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn wallet() -> Wallet {
+        Wallet::from_english_mnemonic(MNEMONIC, "", Network::BitcoinMainnet).unwrap()
+    }
+
+    #[test]
+    fn test_derivation() {
+        let mut wallet = wallet();
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ripple, 0)
+            .unwrap();
+        let signer = XrpSigner::new(account, 0).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "rHsMGQEkVNJmpGWs8XUBoTBiAAbwxZN5v3"
+        );
+
+        let account = wallet
+            .get_account(Purpose::BIP44, CoinType::Ethereum, 0)
+            .unwrap();
+        assert!(matches!(
+            XrpSigner::new(account, 0),
+            Err(Error::InvalidAccount(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_payment() {
+        let signer = XrpSigner::from_private_key(&[1u8; 32]).unwrap();
+        let payment = Payment::builder()
+            .account(signer.address())
+            .destination("rPT1Sjq2YGrBMTttX4GZHjKu9dyfzbpAYe".parse().unwrap())
+            .amount(1_000_000)
+            .destination_tag(12)
+            .sequence(1)
+            .build()
+            .unwrap();
+
+        let signed = signer.sign_payment(&payment).unwrap();
+        let signature = Signature::from_der(signed.signature()).unwrap();
+        assert!(signature.normalize_s().is_none());
+        let verifying_key = VerifyingKey::from_sec1_bytes(signed.public_key()).unwrap();
+        verifying_key
+            .verify_prehash(&payment.signing_hash(signer.public_key()), &signature)
+            .unwrap();
+        assert_eq!(
+            XrpAddress::from_public_key_bytes(signed.public_key()).unwrap(),
+            signer.address()
+        );
+
+        // RFC 6979 nonces make signatures deterministic
+        assert_eq!(signer.sign_payment(&payment).unwrap(), signed);
+
+        let other = XrpSigner::from_private_key(&[2u8; 32]).unwrap();
+        assert!(matches!(
+            other.sign_payment(&payment),
+            Err(Error::InvalidTransaction(_))
+        ));
+    }
+}